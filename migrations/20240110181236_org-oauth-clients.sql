@@ -0,0 +1,9 @@
+-- OAuth clients can optionally be owned by an organization instead of the user who created them.
+-- If the organization is deleted, the client falls back to being owned by its creator.
+ALTER TABLE oauth_clients ADD COLUMN organization_id bigint NULL REFERENCES organizations(id) ON DELETE SET NULL;
+
+CREATE INDEX oauth_clients_organization_id ON oauth_clients (organization_id);
+
+-- Organization owners implicitly hold every permission
+UPDATE team_members SET organization_permissions = organization_permissions | 256
+WHERE is_owner = TRUE AND organization_permissions IS NOT NULL;
@@ -0,0 +1,17 @@
+CREATE TABLE project_webhooks (
+    id bigint PRIMARY KEY,
+    project_id bigint NOT NULL REFERENCES mods(id) ON DELETE CASCADE,
+    url varchar(2048) NOT NULL,
+    secret varchar(255) NOT NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    -- Outcome of the most recent delivery, NULL until the first version is published
+    last_attempt timestamptz NULL,
+    last_error text NULL
+);
+
+CREATE INDEX project_webhooks_project_id ON project_webhooks (project_id);
+
+-- Project owners implicitly hold every permission
+UPDATE team_members SET permissions = permissions | 1024
+WHERE is_owner = TRUE;
@@ -0,0 +1,20 @@
+-- Files uploaded in several chunks through the resumable upload routes.
+-- Chunks are kept until the upload is completed, or until it expires after 24 hours.
+CREATE TABLE pending_uploads (
+    id bigint PRIMARY KEY,
+    user_id bigint NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    file_name varchar(2048) NOT NULL,
+    size bigint NOT NULL,
+    sha1 varchar(40) NOT NULL,
+    chunk_size integer NOT NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX pending_uploads_created ON pending_uploads (created);
+
+CREATE TABLE pending_upload_chunks (
+    upload_id bigint NOT NULL REFERENCES pending_uploads(id) ON DELETE CASCADE,
+    chunk_index integer NOT NULL,
+    data bytea NOT NULL,
+    PRIMARY KEY (upload_id, chunk_index)
+);
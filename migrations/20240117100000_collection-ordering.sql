@@ -0,0 +1,10 @@
+ALTER TABLE collections_mods ADD COLUMN ordering bigint NOT NULL DEFAULT 0;
+
+-- Keep the existing (id-sorted) order of projects in collections
+UPDATE collections_mods cm
+SET ordering = o.ordering
+FROM (
+    SELECT collection_id, mod_id, ROW_NUMBER() OVER (PARTITION BY collection_id ORDER BY mod_id) ordering
+    FROM collections_mods
+) o
+WHERE cm.collection_id = o.collection_id AND cm.mod_id = o.mod_id;
@@ -0,0 +1,6 @@
+CREATE TABLE notification_preferences (
+    user_id bigint REFERENCES users ON DELETE CASCADE NOT NULL,
+    notification_type varchar(64) NOT NULL,
+    in_app boolean NOT NULL,
+    PRIMARY KEY (user_id, notification_type)
+);
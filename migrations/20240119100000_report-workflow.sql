@@ -0,0 +1,6 @@
+ALTER TABLE reports ADD COLUMN status varchar(64) NOT NULL DEFAULT 'open';
+ALTER TABLE reports ADD COLUMN assigned_moderator bigint NULL REFERENCES users ON DELETE SET NULL;
+
+UPDATE reports SET status = 'resolved' WHERE closed = TRUE;
+
+CREATE INDEX reports_status ON reports (status);
@@ -0,0 +1 @@
+ALTER TABLE users ADD COLUMN payout_method jsonb NULL;
@@ -0,0 +1,3 @@
+ALTER TABLE mods ADD COLUMN icon_variants jsonb NOT NULL DEFAULT '{}'::jsonb;
+ALTER TABLE mods_gallery ADD COLUMN variants jsonb NOT NULL DEFAULT '{}'::jsonb;
+ALTER TABLE uploaded_images ADD COLUMN variants jsonb NOT NULL DEFAULT '{}'::jsonb;
@@ -0,0 +1,2 @@
+ALTER TABLE mods ADD COLUMN mature boolean NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN hide_mature_content boolean NOT NULL DEFAULT TRUE;
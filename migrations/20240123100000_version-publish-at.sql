@@ -0,0 +1,2 @@
+ALTER TABLE versions ADD COLUMN publish_at timestamptz NULL;
+ALTER TABLE versions ADD COLUMN publish_delayed boolean NOT NULL DEFAULT FALSE;
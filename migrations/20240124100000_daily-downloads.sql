@@ -0,0 +1,9 @@
+CREATE TABLE daily_downloads (
+    project_id bigint REFERENCES mods ON DELETE CASCADE NOT NULL,
+    version_id bigint REFERENCES versions ON DELETE CASCADE NOT NULL,
+    date date NOT NULL,
+    downloads integer NOT NULL,
+    PRIMARY KEY (version_id, date)
+);
+
+CREATE INDEX daily_downloads_project_date ON daily_downloads (project_id, date);
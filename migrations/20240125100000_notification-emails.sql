@@ -0,0 +1,13 @@
+-- NULL uses the notification type's default
+ALTER TABLE notification_preferences ADD COLUMN send_email boolean NULL;
+
+CREATE TABLE notification_emails (
+    id bigserial PRIMARY KEY,
+    notification_id bigint NOT NULL,
+    user_id bigint REFERENCES users ON DELETE CASCADE NOT NULL,
+    email varchar(255) NOT NULL,
+    body jsonb NOT NULL,
+    attempts integer NOT NULL DEFAULT 0,
+    last_error varchar(2048) NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
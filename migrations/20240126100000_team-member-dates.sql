@@ -0,0 +1,3 @@
+ALTER TABLE team_members ADD COLUMN created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE team_members ADD COLUMN accepted_at timestamptz NULL;
+ALTER TABLE team_members ADD COLUMN invited_by bigint NULL REFERENCES users ON DELETE SET NULL;
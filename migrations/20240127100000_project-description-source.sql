@@ -0,0 +1,2 @@
+ALTER TABLE mods ADD COLUMN description_source_url varchar(2048) NULL;
+ALTER TABLE mods ADD COLUMN description_fetched timestamptz NULL;
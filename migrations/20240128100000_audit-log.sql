@@ -0,0 +1,33 @@
+CREATE TABLE audit_log (
+    id bigint PRIMARY KEY,
+    -- Not a foreign key, so the trail survives the actor's account being deleted
+    actor_id bigint NOT NULL,
+    action varchar(64) NOT NULL,
+    target_type varchar(64) NOT NULL,
+    target_id bigint NOT NULL,
+    -- The project and organization whose logs the entry is listed in
+    project_id bigint NULL,
+    organization_id bigint NULL,
+    summary jsonb NOT NULL DEFAULT '{}'::jsonb,
+    ip varchar(255) NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX audit_log_project_id ON audit_log (project_id, created);
+CREATE INDEX audit_log_organization_id ON audit_log (organization_id, created);
+
+CREATE FUNCTION audit_log_immutable() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'Audit log entries cannot be changed';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_immutable
+BEFORE UPDATE OR DELETE ON audit_log
+FOR EACH ROW EXECUTE FUNCTION audit_log_immutable();
+
+-- Project and organization owners implicitly hold every permission
+UPDATE team_members SET permissions = permissions | 2048
+WHERE is_owner = TRUE;
+UPDATE team_members SET organization_permissions = organization_permissions | 512
+WHERE is_owner = TRUE AND organization_permissions IS NOT NULL;
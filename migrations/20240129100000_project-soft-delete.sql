@@ -0,0 +1,5 @@
+ALTER TABLE mods ADD COLUMN deleted_at timestamptz NULL;
+-- The status a deleted project is given back when it's restored
+ALTER TABLE mods ADD COLUMN status_before_deletion varchar(128) NULL;
+
+CREATE INDEX mods_deleted_at ON mods (deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Downloads made while installing a modpack which depends on the version. These are also
+-- included in `downloads`, which stays the total.
+ALTER TABLE daily_downloads ADD COLUMN pack_downloads integer NOT NULL DEFAULT 0;
@@ -0,0 +1,11 @@
+CREATE TABLE user_exports (
+    id bigint PRIMARY KEY,
+    user_id bigint REFERENCES users ON DELETE CASCADE NOT NULL,
+    status varchar(64) NOT NULL,
+    -- The path of the archive on the CDN, set once it's uploaded
+    file_name varchar(2048) NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed timestamptz NULL
+);
+
+CREATE INDEX user_exports_user_id ON user_exports (user_id, created);
@@ -0,0 +1,8 @@
+CREATE TABLE project_slug_history (
+    id bigserial PRIMARY KEY,
+    mod_id bigint REFERENCES mods ON DELETE CASCADE NOT NULL,
+    slug varchar(255) NOT NULL,
+    changed timestamptz DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX project_slug_history_slug ON project_slug_history (slug);
@@ -0,0 +1,2 @@
+ALTER TABLE pats
+    ADD COLUMN project_ids bigint[] NULL;
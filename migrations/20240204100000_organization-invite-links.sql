@@ -0,0 +1,16 @@
+CREATE TABLE organization_invite_links (
+    id bigint PRIMARY KEY,
+    organization_id bigint REFERENCES organizations ON DELETE CASCADE NOT NULL,
+    -- The part of the link users join with
+    identifier varchar(64) UNIQUE NOT NULL,
+    role varchar(255) NOT NULL,
+    organization_permissions bigint NOT NULL,
+    created_by bigint REFERENCES users NOT NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires timestamptz NOT NULL,
+    -- Unlimited if NULL
+    max_uses integer NULL,
+    uses integer NOT NULL DEFAULT 0
+);
+
+CREATE INDEX organization_invite_links_organization_id ON organization_invite_links (organization_id);
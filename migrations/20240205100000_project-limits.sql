@@ -0,0 +1,3 @@
+-- Overrides of how many projects a user or organization can have, set by admins
+ALTER TABLE users ADD COLUMN project_limit integer NULL;
+ALTER TABLE organizations ADD COLUMN project_limit integer NULL;
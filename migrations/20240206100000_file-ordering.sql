@@ -0,0 +1,10 @@
+ALTER TABLE files ADD COLUMN ordering bigint NOT NULL DEFAULT 0;
+
+-- Keep the existing order of files, with the primary file first and the rest by name
+UPDATE files f
+SET ordering = o.ordering
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY version_id ORDER BY is_primary DESC, filename) ordering
+    FROM files
+) o
+WHERE f.id = o.id;
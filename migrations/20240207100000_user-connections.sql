@@ -0,0 +1,16 @@
+CREATE TABLE user_connections (
+    id bigint PRIMARY KEY,
+    user_id bigint REFERENCES users ON DELETE CASCADE NOT NULL,
+    provider varchar(64) NOT NULL,
+    -- The ID of the account on the provider, or the URL of the page for rel="me" links
+    external_id varchar(2048) NOT NULL,
+    username varchar(2048) NOT NULL,
+    verified boolean NOT NULL DEFAULT FALSE,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    verified_at timestamptz NULL,
+    UNIQUE (user_id, provider, external_id)
+);
+
+CREATE INDEX user_connections_user_id ON user_connections (user_id);
+-- An account can be claimed by several users while unverified, but only verified for one
+CREATE UNIQUE INDEX user_connections_verified ON user_connections (provider, external_id) WHERE verified;
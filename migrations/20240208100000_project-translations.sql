@@ -0,0 +1,11 @@
+CREATE TABLE project_translations (
+    mod_id bigint REFERENCES mods ON DELETE CASCADE NOT NULL,
+    -- The BCP-47 primary language subtag, such as `de`
+    language varchar(8) NOT NULL,
+    -- Fields which aren't translated are shown in the original language
+    name varchar(255) NULL,
+    summary varchar(2048) NULL,
+    description varchar(65536) NULL,
+    updated timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (mod_id, language)
+);
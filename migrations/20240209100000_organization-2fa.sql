@@ -0,0 +1,6 @@
+ALTER TABLE organizations ADD COLUMN require_2fa boolean NOT NULL DEFAULT FALSE;
+
+-- When a member of an organization which requires 2FA was told to enable it, and whether
+-- their permissions were taken away after the grace period
+ALTER TABLE team_members ADD COLUMN two_factor_notified timestamptz NULL;
+ALTER TABLE team_members ADD COLUMN two_factor_restricted boolean NOT NULL DEFAULT FALSE;
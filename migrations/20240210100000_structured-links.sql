@@ -0,0 +1,47 @@
+UPDATE link_platforms SET name = 'buymeacoffee' WHERE name = 'bmac';
+INSERT INTO link_platforms (name, donation) VALUES ('generic', false);
+
+-- Links are named, so links which aren't on one of the known platforms can be told apart
+ALTER TABLE mods_links ADD COLUMN name varchar(32) NULL;
+
+UPDATE mods_links ml
+SET name = lp.name
+FROM link_platforms lp
+WHERE lp.id = ml.joining_platform_id;
+
+ALTER TABLE mods_links ALTER COLUMN name SET NOT NULL;
+
+-- Links on the platforms which are going away, or on the wrong site for their platform, are
+-- classified by their host, and are generic if it isn't a known one
+WITH classified AS (
+    SELECT ml.id, COALESCE(
+        CASE
+            WHEN host ~ '(^|\.)github\.com$' THEN 'github'
+            WHEN host ~ '(^|\.)(discord\.gg|discord\.com)$' THEN 'discord'
+            WHEN host ~ '(^|\.)patreon\.com$' THEN 'patreon'
+            WHEN host ~ '(^|\.)ko-fi\.com$' THEN 'ko-fi'
+            WHEN host ~ '(^|\.)(paypal\.com|paypal\.me)$' THEN 'paypal'
+            WHEN host ~ '(^|\.)buymeacoffee\.com$' THEN 'buymeacoffee'
+        END,
+        'generic'
+    ) platform
+    FROM (
+        SELECT ml.id, ml.joining_platform_id, LOWER(SUBSTRING(ml.url FROM '^[a-zA-Z]+://([^/:?#]+)')) host
+        FROM mods_links ml
+    ) ml
+    INNER JOIN link_platforms lp ON lp.id = ml.joining_platform_id
+    WHERE lp.name IN ('source', 'site', 'other')
+        OR (lp.name = 'github' AND host !~ '(^|\.)github\.com$')
+        OR (lp.name = 'discord' AND host !~ '(^|\.)(discord\.gg|discord\.com)$')
+        OR (lp.name = 'patreon' AND host !~ '(^|\.)patreon\.com$')
+        OR (lp.name = 'ko-fi' AND host !~ '(^|\.)ko-fi\.com$')
+        OR (lp.name = 'paypal' AND host !~ '(^|\.)(paypal\.com|paypal\.me)$')
+        OR (lp.name = 'buymeacoffee' AND host !~ '(^|\.)buymeacoffee\.com$')
+)
+UPDATE mods_links ml
+SET joining_platform_id = lp.id
+FROM classified c
+INNER JOIN link_platforms lp ON lp.name = c.platform
+WHERE ml.id = c.id;
+
+DELETE FROM link_platforms WHERE name IN ('source', 'site', 'other');
@@ -0,0 +1,15 @@
+-- A moderator's checklist for a moderation pass over a project. A project has at most one
+-- review in progress, which ends when a moderator approves or rejects the project.
+CREATE TABLE moderation_reviews (
+    id bigserial PRIMARY KEY,
+    mod_id bigint REFERENCES mods ON DELETE CASCADE NOT NULL,
+    moderator_id bigint REFERENCES users NOT NULL,
+    items jsonb NOT NULL DEFAULT '{}'::jsonb,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    decided timestamptz NULL,
+    decision varchar(128) NULL
+);
+
+CREATE INDEX moderation_reviews_mod_id ON moderation_reviews (mod_id, created DESC);
+CREATE UNIQUE INDEX moderation_reviews_in_progress ON moderation_reviews (mod_id) WHERE decided IS NULL;
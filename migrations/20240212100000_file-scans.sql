@@ -0,0 +1,4 @@
+-- The result of the latest virus scan of each version file. Files uploaded before scanning was
+-- added stay pending until they're rescanned.
+ALTER TABLE files ADD COLUMN scan_status varchar(32) NOT NULL DEFAULT 'pending';
+ALTER TABLE files ADD COLUMN scanned_at timestamptz NULL;
@@ -0,0 +1,15 @@
+-- Who can see a collection is now its visibility, leaving its status for moderation
+ALTER TABLE collections ADD COLUMN visibility varchar(32) NOT NULL DEFAULT 'public';
+
+UPDATE collections
+SET visibility = status, status = 'listed'
+WHERE status IN ('unlisted', 'private');
+
+-- Tokens which let anyone holding them view a private collection
+CREATE TABLE collection_share_tokens (
+    token varchar(64) PRIMARY KEY,
+    collection_id bigint REFERENCES collections ON DELETE CASCADE NOT NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX collection_share_tokens_collection_id ON collection_share_tokens (collection_id);
@@ -0,0 +1,11 @@
+-- Dependencies filled in from a file's mod metadata, rather than given by the uploader
+ALTER TABLE dependencies ADD COLUMN inferred boolean NOT NULL DEFAULT FALSE;
+
+-- The mod ids each project's files have declared in their metadata, used to resolve the mod ids
+-- other files depend on to projects
+CREATE TABLE mod_metadata_ids (
+    metadata_id varchar(255) NOT NULL,
+    mod_id bigint REFERENCES mods ON DELETE CASCADE NOT NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (metadata_id, mod_id)
+);
@@ -0,0 +1,11 @@
+-- Organization slugs share a namespace with usernames, and are stored lowercase like project slugs
+UPDATE organizations SET slug = LOWER(slug);
+
+CREATE TABLE organization_slug_history (
+    id bigserial PRIMARY KEY,
+    organization_id bigint REFERENCES organizations ON DELETE CASCADE NOT NULL,
+    slug varchar(255) NOT NULL,
+    changed timestamptz DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX organization_slug_history_slug ON organization_slug_history (slug);
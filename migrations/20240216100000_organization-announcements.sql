@@ -0,0 +1,3 @@
+-- Owners of organizations get the new permission to send announcements
+UPDATE team_members SET organization_permissions = organization_permissions | 1024
+WHERE is_owner = TRUE AND organization_permissions IS NOT NULL;
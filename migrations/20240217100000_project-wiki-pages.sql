@@ -0,0 +1,17 @@
+CREATE TABLE project_wiki_pages (
+    mod_id bigint REFERENCES mods ON DELETE CASCADE NOT NULL,
+    -- Slugs are lowercase, and unique within the project
+    slug varchar(64) NOT NULL,
+    title varchar(256) NOT NULL,
+    body varchar(65536) NOT NULL,
+    ordering int NOT NULL,
+    -- The user who last created or edited the page
+    updated_by bigint REFERENCES users NOT NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (mod_id, slug)
+);
+
+-- Project owners implicitly hold every permission
+UPDATE team_members SET permissions = permissions | 4096
+WHERE is_owner = TRUE;
@@ -0,0 +1,20 @@
+-- Versions with files which were already uploaded to a project the uploader isn't a member of
+ALTER TABLE versions ADD COLUMN flagged_reupload boolean NOT NULL DEFAULT FALSE;
+
+-- Uploaded files are looked up by their hashes across all projects
+CREATE INDEX hashes_algorithm_hash ON hashes (algorithm, hash);
+
+-- Reports can point to a second project, such as the project a file was reuploaded from
+ALTER TABLE reports ADD COLUMN related_mod_id bigint NULL REFERENCES mods ON DELETE SET NULL;
+
+INSERT INTO report_types (name) VALUES ('reupload');
+
+-- The user automatic reports are opened by
+INSERT INTO users (
+    id, username, name, email,
+    avatar_url, bio, created
+)
+VALUES (
+           127155982985830, 'AutoMod', NULL, NULL,
+           NULL, 'Reports suspicious content to the moderators', NOW()
+       );
@@ -0,0 +1,14 @@
+-- Changes to the fields moderators check when approving a project, such as its license
+CREATE TABLE project_field_history (
+    id bigserial PRIMARY KEY,
+    mod_id bigint NOT NULL REFERENCES mods ON DELETE CASCADE,
+    -- The version whose loader fields changed, for fields set on versions
+    version_id bigint NULL REFERENCES versions ON DELETE SET NULL,
+    field varchar(64) NOT NULL,
+    old_value jsonb NOT NULL,
+    new_value jsonb NOT NULL,
+    actor_id bigint NULL REFERENCES users ON DELETE SET NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX project_field_history_mod_id ON project_field_history (mod_id, created);
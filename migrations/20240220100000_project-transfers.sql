@@ -0,0 +1,19 @@
+-- Requests to hand a project's ownership to another user or an organization, which the
+-- recipient has to accept
+CREATE TABLE project_transfers (
+    id bigserial PRIMARY KEY,
+    mod_id bigint NOT NULL REFERENCES mods ON DELETE CASCADE,
+    initiated_by bigint NOT NULL REFERENCES users ON DELETE CASCADE,
+    target_user_id bigint NULL REFERENCES users ON DELETE CASCADE,
+    target_organization_id bigint NULL REFERENCES organizations ON DELETE CASCADE,
+    -- The permissions the previous owner keeps as a regular member of the project
+    previous_owner_permissions bigint NOT NULL,
+    status varchar(64) NOT NULL DEFAULT 'pending',
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires timestamptz NOT NULL,
+    resolved timestamptz NULL,
+    CHECK ((target_user_id IS NULL) <> (target_organization_id IS NULL))
+);
+
+-- A project can only have one pending transfer at a time
+CREATE UNIQUE INDEX project_transfers_pending ON project_transfers (mod_id) WHERE status = 'pending';
@@ -0,0 +1,10 @@
+-- Whether moderators have approved an uploaded image: approved, pending or quarantined.
+-- Images uploaded before moderation existed are approved.
+ALTER TABLE uploaded_images ADD COLUMN moderation_status varchar(64) NOT NULL DEFAULT 'approved';
+
+CREATE INDEX uploaded_images_pending ON uploaded_images (created) WHERE moderation_status = 'pending';
+
+-- Gallery images and icons are tracked as uploaded images so they can be moderated. Those uploaded
+-- before moderation existed have no image and are shown as approved.
+ALTER TABLE mods_gallery ADD COLUMN image_id bigint NULL REFERENCES uploaded_images ON DELETE SET NULL;
+ALTER TABLE mods ADD COLUMN icon_image_id bigint NULL REFERENCES uploaded_images ON DELETE SET NULL;
@@ -0,0 +1,5 @@
+-- When a payout reached its final status: paid, failed or cancelled. Payouts which were resolved
+-- before this was recorded have no completion time.
+ALTER TABLE payouts ADD COLUMN completed timestamptz NULL;
+
+CREATE INDEX payouts_user_created ON payouts (user_id, created);
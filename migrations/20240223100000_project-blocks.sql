@@ -0,0 +1,9 @@
+-- Users blocked from a project's threads and from joining its team
+CREATE TABLE project_blocks (
+    mod_id bigint NOT NULL REFERENCES mods ON DELETE CASCADE,
+    user_id bigint NOT NULL REFERENCES users ON DELETE CASCADE,
+    -- The team member who blocked the user
+    blocked_by bigint NULL REFERENCES users ON DELETE SET NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (mod_id, user_id)
+);
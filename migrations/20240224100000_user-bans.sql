@@ -0,0 +1,2 @@
+-- Banned users are left out of search
+ALTER TABLE users ADD COLUMN banned boolean NOT NULL DEFAULT FALSE;
@@ -0,0 +1,5 @@
+-- When an approved project is listed, if its team asked for it to go live at a set time. Projects
+-- waiting to go live are scheduled.
+ALTER TABLE mods ADD COLUMN go_live_at timestamptz NULL;
+
+CREATE INDEX mods_go_live_at ON mods (go_live_at) WHERE status = 'scheduled';
@@ -0,0 +1,17 @@
+-- Named presets of permissions an organization can give its members and its projects' members
+CREATE TABLE organization_role_templates (
+    id bigint PRIMARY KEY,
+    organization_id bigint REFERENCES organizations ON DELETE CASCADE NOT NULL,
+    name varchar(64) NOT NULL,
+    description varchar(255) NOT NULL DEFAULT '',
+    permissions bigint NOT NULL,
+    organization_permissions bigint NOT NULL,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (organization_id, name)
+);
+
+-- The template the member's permissions were last set from, if they haven't been edited since
+ALTER TABLE team_members
+    ADD COLUMN role_template_id bigint NULL REFERENCES organization_role_templates ON DELETE SET NULL;
+
+CREATE INDEX team_members_role_template_id ON team_members (role_template_id);
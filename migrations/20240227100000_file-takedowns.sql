@@ -0,0 +1,8 @@
+-- Files taken down by a moderator, such as for a DMCA complaint. The rows are kept so the
+-- takedown can be reviewed and reverted.
+ALTER TABLE files
+    ADD COLUMN withheld_at timestamptz NULL,
+    ADD COLUMN withheld_by bigint NULL REFERENCES users ON DELETE SET NULL;
+
+-- The hash of the file a report is about. File reports are also about the file's version.
+ALTER TABLE reports ADD COLUMN file_hash varchar(128) NULL;
@@ -0,0 +1,10 @@
+-- Users blocked by other users, who can't follow the blocker's projects and collections, join
+-- teams they own, post in their projects' threads, or send them notifications
+CREATE TABLE user_blocks (
+    user_id bigint NOT NULL REFERENCES users ON DELETE CASCADE,
+    blocked_user_id bigint NOT NULL REFERENCES users ON DELETE CASCADE,
+    created timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, blocked_user_id)
+);
+
+CREATE INDEX user_blocks_blocked_user_id ON user_blocks (blocked_user_id);
@@ -0,0 +1,10 @@
+-- How an organization splits the payouts of its projects between its members by default, given to
+-- the teams of projects added to it
+CREATE TABLE organization_payout_splits (
+    organization_id bigint REFERENCES organizations ON DELETE CASCADE NOT NULL,
+    user_id bigint REFERENCES users ON DELETE CASCADE NOT NULL,
+    weight numeric(96, 48) NOT NULL,
+    PRIMARY KEY (organization_id, user_id)
+);
+
+CREATE INDEX organization_payout_splits_user_id ON organization_payout_splits (user_id);
@@ -0,0 +1,2 @@
+-- Up to 3 of a project's categories can be shown on it in place of all of them
+ALTER TABLE mods_categories ADD COLUMN is_display boolean NOT NULL DEFAULT FALSE;
@@ -0,0 +1,12 @@
+-- Pending webhook deliveries, kept until they succeed or run out of attempts
+CREATE TABLE webhook_deliveries (
+    id bigserial PRIMARY KEY,
+    webhook_id bigint NOT NULL REFERENCES project_webhooks(id) ON DELETE CASCADE,
+    version_id bigint NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+    attempts int NOT NULL DEFAULT 0,
+    next_attempt timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    UNIQUE (webhook_id, version_id)
+);
+
+CREATE INDEX webhook_deliveries_next_attempt ON webhook_deliveries (next_attempt);
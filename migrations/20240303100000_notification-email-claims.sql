@@ -0,0 +1,2 @@
+-- When a sender claimed the email, NULL while it's waiting to be sent
+ALTER TABLE notification_emails ADD COLUMN sending_at timestamptz NULL;
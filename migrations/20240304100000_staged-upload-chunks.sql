@@ -0,0 +1,7 @@
+-- Chunks are staged on the file host instead of in the database. Uploads in progress are
+-- dropped, as their chunks can't be moved.
+DELETE FROM pending_uploads;
+
+ALTER TABLE pending_upload_chunks DROP COLUMN data;
+ALTER TABLE pending_upload_chunks ADD COLUMN file_id varchar(1024) NOT NULL;
+ALTER TABLE pending_upload_chunks ADD COLUMN file_name varchar(2048) NOT NULL;
@@ -0,0 +1,5 @@
+-- Payout methods are encrypted by the application, with a masked copy kept for showing to the
+-- user. Methods saved in plaintext are dropped, as they can't be encrypted here.
+ALTER TABLE users DROP COLUMN payout_method;
+ALTER TABLE users ADD COLUMN payout_method bytea NULL;
+ALTER TABLE users ADD COLUMN payout_method_masked jsonb NULL;
@@ -4,9 +4,12 @@ use crate::database::models::version_item::QueryVersion;
 use crate::database::models::Collection;
 use crate::database::redis::RedisPool;
 use crate::database::{models, Project, Version};
+use crate::models::collections::CollectionVisibility;
 use crate::models::users::User;
 use crate::routes::ApiError;
+use crate::util::signed_urls::{sign_project_images, signed_url_expiry};
 use actix_web::web;
+use chrono::Utc;
 use itertools::Itertools;
 use sqlx::PgPool;
 
@@ -62,7 +65,17 @@ pub async fn filter_visible_projects(
     .await
     .unwrap();
     projects.retain(|x| filtered_project_ids.contains(&x.inner.id));
-    Ok(projects.into_iter().map(|x| x.into()).collect())
+
+    // Hidden projects can have private images, whose URLs only work once signed
+    let expires = signed_url_expiry(Utc::now());
+    projects
+        .into_iter()
+        .map(|x| {
+            let mut project = crate::models::projects::Project::from(x);
+            sign_project_images(&mut project, expires)?;
+            Ok(project)
+        })
+        .collect()
 }
 
 // Filters projects for which we can see, meaning one of the following is true:
@@ -190,7 +203,21 @@ pub async fn filter_visible_versions(
     .await
     .unwrap();
     versions.retain(|x| filtered_version_ids.contains(&x.inner.id));
-    Ok(versions.into_iter().map(|x| x.into()).collect())
+
+    let enlisted_version_ids = filter_enlisted_version_ids(
+        versions.iter().map(|x| &x.inner).collect_vec(),
+        user_option,
+        pool,
+        redis,
+    )
+    .await?;
+    Ok(versions
+        .into_iter()
+        .map(|x| {
+            let show_scans = enlisted_version_ids.contains(&x.inner.id);
+            crate::models::projects::Version::from_query(x, show_scans)
+        })
+        .collect())
 }
 
 impl ValidateAuthorized for models::OAuthClient {
@@ -303,7 +330,8 @@ pub async fn is_visible_collection(
     collection_data: &Collection,
     user_option: &Option<User>,
 ) -> Result<bool, ApiError> {
-    let mut authorized = !collection_data.status.is_hidden();
+    let mut authorized = !collection_data.status.is_hidden()
+        && collection_data.visibility != CollectionVisibility::Private;
     if let Some(user) = &user_option {
         if !authorized && (user.role.is_mod() || user.id == collection_data.user_id.into()) {
             authorized = true;
@@ -320,7 +348,7 @@ pub async fn filter_visible_collections(
     let mut check_collections = Vec::new();
 
     for collection in collections {
-        if !collection.status.is_hidden()
+        if !collection.status.is_hidden() && collection.visibility != CollectionVisibility::Private
             || user_option
                 .as_ref()
                 .map(|x| x.role.is_mod())
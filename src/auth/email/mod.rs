@@ -4,6 +4,8 @@ use lettre::transport::smtp::authentication::Credentials;
 use lettre::{Address, Message, SmtpTransport, Transport};
 use thiserror::Error;
 
+use crate::util::sanitize::escape_attribute;
+
 #[derive(Error, Debug)]
 pub enum MailError {
     #[error("Environment Error")]
@@ -14,6 +16,8 @@ pub enum MailError {
     Address(#[from] lettre::address::AddressError),
     #[error("SMTP Error: {0}")]
     Smtp(#[from] lettre::transport::smtp::Error),
+    #[error("Blocking Error: {0}")]
+    Blocking(#[from] actix_web::error::BlockingError),
 }
 
 pub fn send_email_raw(to: String, subject: String, body: String) -> Result<(), MailError> {
@@ -46,23 +50,59 @@ pub fn send_email(
     line_two: &str,
     button_info: Option<(&str, &str)>,
 ) -> Result<(), MailError> {
+    let email = render_email(email_title, email_description, line_two, button_info);
+
+    send_email_raw(to, email_title.to_string(), email)?;
+
+    Ok(())
+}
+
+/// Fills in the HTML template used for all emails, with a button if `button_info` is given. The
+/// values are plain text, and are escaped as they can contain user input.
+pub fn render_email(
+    email_title: &str,
+    email_description: &str,
+    line_two: &str,
+    button_info: Option<(&str, &str)>,
+) -> String {
     let mut email = if button_info.is_some() {
         include_str!("button_notif.html")
     } else {
         include_str!("auth_notif.html")
     }
-    .replace("{{ email_title }}", email_title)
-    .replace("{{ email_description }}", email_description)
-    .replace("{{ line_one }}", email_description)
-    .replace("{{ line_two }}", line_two);
+    .replace("{{ email_title }}", &escape_attribute(email_title))
+    .replace(
+        "{{ email_description }}",
+        &escape_attribute(email_description),
+    )
+    .replace("{{ line_one }}", &escape_attribute(email_description))
+    .replace("{{ line_two }}", &escape_attribute(line_two));
 
     if let Some((button_title, button_link)) = button_info {
         email = email
-            .replace("{{ button_title }}", button_title)
-            .replace("{{ button_link }}", button_link);
+            .replace("{{ button_title }}", &escape_attribute(button_title))
+            .replace("{{ button_link }}", &escape_attribute(button_link));
     }
 
-    send_email_raw(to, email_title.to_string(), email)?;
+    email
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_values() {
+        let email = render_email(
+            "Title",
+            "Invited as <b>Owner</b>",
+            "Line & two",
+            Some(("View", "https://modrinth.com/\"><script>")),
+        );
+
+        assert!(email.contains("Invited as &lt;b&gt;Owner&lt;/b&gt;"));
+        assert!(email.contains("Line &amp; two"));
+        assert!(!email.contains("<b>Owner"));
+        assert!(!email.contains("<script>"));
+    }
 }
@@ -9,7 +9,7 @@ pub use checks::{
 };
 use serde::{Deserialize, Serialize};
 // pub use pat::{generate_pat, PersonalAccessToken};
-pub use validate::{check_is_moderator_from_headers, get_user_from_headers};
+pub use validate::{check_is_moderator_from_headers, get_user_from_headers, get_user_from_token};
 
 use crate::file_hosting::FileHostingError;
 use crate::models::error::ApiError;
@@ -43,6 +43,8 @@ pub enum AuthenticationError {
     InvalidClientId,
     #[error("User email/account is already registered on Modrinth")]
     DuplicateUser,
+    #[error("This account is already linked to another Modrinth user")]
+    DuplicateConnection,
     #[error("Invalid state sent, you probably need to get a new websocket")]
     SocketError,
     #[error("Invalid callback URL specified")]
@@ -65,6 +67,7 @@ impl actix_web::ResponseError for AuthenticationError {
             AuthenticationError::Url => StatusCode::BAD_REQUEST,
             AuthenticationError::FileHosting(..) => StatusCode::INTERNAL_SERVER_ERROR,
             AuthenticationError::DuplicateUser => StatusCode::BAD_REQUEST,
+            AuthenticationError::DuplicateConnection => StatusCode::CONFLICT,
             AuthenticationError::SocketError => StatusCode::BAD_REQUEST,
         }
     }
@@ -93,6 +96,7 @@ impl AuthenticationError {
             AuthenticationError::Url => "url_error",
             AuthenticationError::FileHosting(..) => "file_hosting",
             AuthenticationError::DuplicateUser => "duplicate_user",
+            AuthenticationError::DuplicateConnection => "duplicate_connection",
             AuthenticationError::SocketError => "socket",
         }
     }
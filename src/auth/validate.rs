@@ -1,14 +1,20 @@
 use super::AuthProvider;
 use crate::auth::AuthenticationError;
-use crate::database::models::user_item;
+use crate::database::models::{user_item, ProjectId, UserId as DBUserId};
 use crate::database::redis::RedisPool;
-use crate::models::pats::Scopes;
+use crate::models::connections::UserConnection;
+use crate::models::ids::base62_impl::parse_base62;
+use crate::models::pats::{PatUsage, Scopes};
 use crate::models::users::{Role, User, UserId, UserPayoutData};
 use crate::queue::session::AuthQueue;
 use crate::routes::internal::session::get_session_metadata;
+use crate::util::env::parse_var;
+use actix_web::http::Method;
 use actix_web::HttpRequest;
 use chrono::Utc;
+use log::warn;
 use reqwest::header::{HeaderValue, AUTHORIZATION};
+use std::net::{IpAddr, SocketAddr};
 
 pub async fn get_user_from_headers<'a, E>(
     req: &HttpRequest,
@@ -17,12 +23,29 @@ pub async fn get_user_from_headers<'a, E>(
     session_queue: &AuthQueue,
     required_scopes: Option<&[Scopes]>,
 ) -> Result<(Scopes, User), AuthenticationError>
+where
+    E: sqlx::Executor<'a, Database = sqlx::Postgres> + Copy,
+{
+    get_user_from_token(req, None, executor, redis, session_queue, required_scopes).await
+}
+
+/// Like [`get_user_from_headers`], but uses the given token instead of the `Authorization`
+/// header if there is one. Browsers can't set headers on WebSocket requests, so they pass the
+/// token in the query instead.
+pub async fn get_user_from_token<'a, E>(
+    req: &HttpRequest,
+    token: Option<&str>,
+    executor: E,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+    required_scopes: Option<&[Scopes]>,
+) -> Result<(Scopes, User), AuthenticationError>
 where
     E: sqlx::Executor<'a, Database = sqlx::Postgres> + Copy,
 {
     // Fetch DB user record and minos user from headers
     let (scopes, db_user) =
-        get_user_record_from_bearer_token(req, None, executor, redis, session_queue)
+        get_user_record_from_bearer_token(req, token, executor, redis, session_queue)
             .await?
             .ok_or_else(|| AuthenticationError::InvalidCredentials)?;
     let mut auth_providers = Vec::new();
@@ -62,11 +85,18 @@ where
         auth_providers: Some(auth_providers),
         has_password: Some(db_user.password.is_some()),
         has_totp: Some(db_user.totp_secret.is_some()),
+        hide_mature_content: Some(db_user.hide_mature_content),
+        connections: db_user
+            .connections
+            .into_iter()
+            .map(UserConnection::from)
+            .collect(),
         github_id: None,
         payout_data: Some(UserPayoutData {
             paypal_address: db_user.paypal_email,
             paypal_country: db_user.paypal_country,
             venmo_handle: db_user.venmo_handle,
+            method: db_user.payout_method_masked,
             balance: db_user.balance,
         }),
     };
@@ -109,9 +139,26 @@ where
                 return Err(AuthenticationError::InvalidCredentials);
             }
 
+            if let Some(project_ids) = pat.project_ids {
+                check_pat_route_project(req, &project_ids, executor).await?;
+                req.extensions_mut()
+                    .insert(PatProjectRestriction(project_ids));
+            }
+
             let user = user_item::User::get_id(pat.user_id, executor, redis).await?;
 
-            session_queue.add_pat(pat.id).await;
+            // The usage history is only informational, so failing to record it doesn't fail
+            // the request
+            let usage = get_pat_usage(req);
+            match crate::database::models::pat_item::PersonalAccessToken::record_usage(
+                pat.id, &usage, redis,
+            )
+            .await
+            {
+                Ok(true) => session_queue.add_pat(pat.id).await,
+                Ok(false) => {}
+                Err(err) => warn!("Recording the usage of PAT {} failed: {}", pat.id.0, err),
+            }
 
             user.map(|x| (pat.scopes, x))
         }
@@ -178,6 +225,254 @@ where
     Ok(possible_user)
 }
 
+/// Who made a request, as far as rate limiting is concerned
+pub struct RequestClient {
+    pub user_id: DBUserId,
+    pub role: Role,
+}
+
+/// Looks up who made a request from its token. Unlike [`get_user_from_headers`], this has no
+/// side effects: token usage isn't recorded and sessions aren't refreshed. Tokens of external
+/// providers aren't looked up, and those requests are treated as anonymous.
+pub async fn get_request_client<'a, E>(
+    req: &HttpRequest,
+    executor: E,
+    redis: &RedisPool,
+) -> Result<Option<RequestClient>, AuthenticationError>
+where
+    E: sqlx::Executor<'a, Database = sqlx::Postgres> + Copy,
+{
+    let Ok(token) = extract_authorization_header(req) else {
+        return Ok(None);
+    };
+
+    let user_id = match token.split_once('_') {
+        Some(("mrp", _)) => {
+            match crate::database::models::pat_item::PersonalAccessToken::get(
+                token, executor, redis,
+            )
+            .await?
+            {
+                Some(pat) if pat.expires >= Utc::now() => pat.user_id,
+                _ => return Ok(None),
+            }
+        }
+        Some(("mra", _)) => {
+            match crate::database::models::session_item::Session::get(token, executor, redis)
+                .await?
+            {
+                Some(session) if session.expires >= Utc::now() => session.user_id,
+                _ => return Ok(None),
+            }
+        }
+        Some(("mro", _)) => {
+            use crate::database::models::oauth_token_item::OAuthAccessToken;
+
+            match OAuthAccessToken::get(OAuthAccessToken::hash_token(token), executor).await? {
+                Some(access_token) if access_token.expires >= Utc::now() => access_token.user_id,
+                _ => return Ok(None),
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(user_item::User::get_id(user_id, executor, redis)
+        .await?
+        .map(|user| RequestClient {
+            user_id,
+            role: Role::from_string(&user.role),
+        }))
+}
+
+/// The projects the PAT a request was authenticated with is restricted to
+#[derive(Clone)]
+struct PatProjectRestriction(Vec<ProjectId>);
+
+/// Checks that the token a request was authenticated with may act on the given project. Routes
+/// which take the project from their body, a file hash or a list of ids rather than their path
+/// need to call this themselves.
+pub fn check_pat_project_restriction(
+    req: &HttpRequest,
+    project_id: ProjectId,
+) -> Result<(), AuthenticationError> {
+    match req.extensions().get::<PatProjectRestriction>() {
+        Some(restriction) if !restriction.0.contains(&project_id) => {
+            Err(AuthenticationError::InvalidCredentials)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Like [`check_pat_project_restriction`], for routes which act on several projects at once
+pub fn check_pat_project_restrictions(
+    req: &HttpRequest,
+    project_ids: impl IntoIterator<Item = ProjectId>,
+) -> Result<(), AuthenticationError> {
+    project_ids
+        .into_iter()
+        .try_for_each(|x| check_pat_project_restriction(req, x))
+}
+
+/// Refuses project restricted PATs. Used by routes which only sometimes act on a project, when
+/// they don't.
+pub fn check_pat_not_project_restricted(req: &HttpRequest) -> Result<(), AuthenticationError> {
+    match req.extensions().get::<PatProjectRestriction>() {
+        Some(_) => Err(AuthenticationError::InvalidCredentials),
+        None => Ok(()),
+    }
+}
+
+/// What a project restricted PAT may do on a route, based on its path
+enum PatRoute<'a> {
+    /// The route acts on the project with this id or slug
+    Project(&'a str),
+    /// The route acts on the project of this version
+    Version(&'a str),
+    /// The route acts on the project of this team
+    Team(&'a str),
+    /// The route takes its projects from the body, a file hash or a list of ids, so the handler
+    /// calls [`check_pat_project_restriction`] once it knows them
+    CheckedByHandler,
+    /// The route doesn't act on projects at all
+    Unrestricted,
+}
+
+fn pat_route<'a>(method: &Method, path: &'a str) -> Option<PatRoute<'a>> {
+    let segments = path
+        .split('/')
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>();
+    let segments = match segments.as_slice() {
+        ["v2" | "v3", rest @ ..] => rest,
+        rest => rest,
+    };
+
+    Some(match segments {
+        ["user" | "notification" | "notifications", ..] => PatRoute::Unrestricted,
+        ["project", id, ..] => PatRoute::Project(id),
+        ["version", id, ..] => PatRoute::Version(id),
+        ["version"] if method == Method::POST => PatRoute::CheckedByHandler,
+        ["team", id, ..] => PatRoute::Team(id),
+        ["organization", _, "projects", id] if method == Method::DELETE => PatRoute::Project(id),
+        ["organization", _, "projects"] if method == Method::POST => PatRoute::CheckedByHandler,
+        ["image"] if method == Method::POST => PatRoute::CheckedByHandler,
+        ["thread", "inbox"] => return None,
+        ["thread", _, ..] | ["projects" | "versions" | "version_file" | "version_files", ..] => {
+            PatRoute::CheckedByHandler
+        }
+        _ => return None,
+    })
+}
+
+// Project restricted PATs can only be used on routes which act on the projects they are
+// restricted to, and on routes which don't act on projects. Any other route is refused.
+async fn check_pat_route_project<'a, E>(
+    req: &HttpRequest,
+    project_ids: &[ProjectId],
+    executor: E,
+) -> Result<(), AuthenticationError>
+where
+    E: sqlx::Executor<'a, Database = sqlx::Postgres> + Copy,
+{
+    let project_id = match pat_route(req.method(), req.path()) {
+        None => return Err(AuthenticationError::InvalidCredentials),
+        Some(PatRoute::CheckedByHandler | PatRoute::Unrestricted) => return Ok(()),
+        // Ids take precedence over slugs, as a slug can look like the id of another project
+        Some(PatRoute::Project(id)) => {
+            let by_id = match parse_base62(id) {
+                Ok(parsed_id) => sqlx::query!(
+                    "
+                    SELECT id FROM mods
+                    WHERE id = $1
+                    ",
+                    parsed_id as i64
+                )
+                .fetch_optional(executor)
+                .await?
+                .map(|x| ProjectId(x.id)),
+                Err(_) => None,
+            };
+
+            match by_id {
+                Some(project_id) => Some(project_id),
+                None => sqlx::query!(
+                    "
+                    SELECT id FROM mods
+                    WHERE slug = LOWER($1)
+                    ",
+                    id
+                )
+                .fetch_optional(executor)
+                .await?
+                .map(|x| ProjectId(x.id)),
+            }
+        }
+        Some(PatRoute::Version(id)) => sqlx::query!(
+            "
+            SELECT mod_id FROM versions
+            WHERE id = $1
+            ",
+            parse_base62(id).ok().map(|x| x as i64)
+        )
+        .fetch_optional(executor)
+        .await?
+        .map(|x| ProjectId(x.mod_id)),
+        Some(PatRoute::Team(id)) => sqlx::query!(
+            "
+            SELECT id FROM mods
+            WHERE team_id = $1
+            ",
+            parse_base62(id).ok().map(|x| x as i64)
+        )
+        .fetch_optional(executor)
+        .await?
+        .map(|x| ProjectId(x.id)),
+    };
+
+    match project_id {
+        Some(project_id) if project_ids.contains(&project_id) => Ok(()),
+        _ => Err(AuthenticationError::InvalidCredentials),
+    }
+}
+
+fn get_pat_usage(req: &HttpRequest) -> PatUsage {
+    let conn_info = req.connection_info().clone();
+    let ip_addr = if parse_var("CLOUDFLARE_INTEGRATION").unwrap_or(false) {
+        if let Some(header) = req.headers().get("CF-Connecting-IP") {
+            header.to_str().ok()
+        } else {
+            conn_info.peer_addr()
+        }
+    } else {
+        conn_info.peer_addr()
+    };
+
+    PatUsage {
+        used: Utc::now(),
+        route: req.match_pattern(),
+        ip: ip_addr.and_then(truncate_ip),
+    }
+}
+
+// Only the network part of the address is kept, so usage history doesn't store full IPs
+fn truncate_ip(ip: &str) -> Option<String> {
+    let ip = ip
+        .parse::<IpAddr>()
+        .or_else(|_| ip.parse::<SocketAddr>().map(|x| x.ip()))
+        .ok()?;
+
+    Some(match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            format!("{a}.{b}.{c}.0")
+        }
+        IpAddr::V6(ip) => {
+            let [a, b, c, ..] = ip.segments();
+            format!("{a:x}:{b:x}:{c:x}::")
+        }
+    })
+}
+
 pub fn extract_authorization_header(req: &HttpRequest) -> Result<&str, AuthenticationError> {
     let headers = req.headers();
     let token_val: Option<&HeaderValue> = headers.get(AUTHORIZATION);
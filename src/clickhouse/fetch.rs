@@ -11,6 +11,14 @@ pub struct ReturnIntervals {
     pub total: u64,
 }
 
+#[derive(clickhouse::Row, Serialize, Deserialize, Clone, Debug)]
+pub struct ReturnDailyDownloads {
+    pub time: u32,
+    pub project_id: u64,
+    pub version_id: u64,
+    pub total: u64,
+}
+
 #[derive(clickhouse::Row, Serialize, Deserialize, Clone, Debug)]
 pub struct ReturnCountry {
     pub country: String,
@@ -109,6 +117,32 @@ pub async fn fetch_downloads(
     Ok(query.fetch_all().await?)
 }
 
+// Fetches the downloads of every version on each day, used to backfill the daily downloads table
+pub async fn fetch_daily_downloads(
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    client: Arc<clickhouse::Client>,
+) -> Result<Vec<ReturnDailyDownloads>, ApiError> {
+    let query = client
+        .query(
+            "
+            SELECT
+                toUnixTimestamp(toStartOfDay(recorded)) AS time,
+                project_id,
+                version_id,
+                count(1) AS total
+            FROM downloads
+            WHERE recorded BETWEEN ? AND ?
+                  AND version_id != 0
+            GROUP BY time, project_id, version_id
+            ",
+        )
+        .bind(start_date.timestamp())
+        .bind(end_date.timestamp());
+
+    Ok(query.fetch_all().await?)
+}
+
 pub async fn fetch_countries_downloads(
     projects: Vec<ProjectId>,
     start_date: DateTime<Utc>,
@@ -0,0 +1,201 @@
+use crate::database::models::project_item::PROJECTS_NAMESPACE;
+use crate::database::models::version_item::VERSIONS_NAMESPACE;
+use crate::database::models::{DatabaseError, ProjectId, VersionId};
+use crate::database::redis::RedisPool;
+use crate::database::{Project, Version};
+use crate::models::projects::{ProjectStatus, VersionStatus};
+use crate::util::env::parse_var;
+use crate::util::jobs::{JobHandle, JobType};
+use futures::{StreamExt, TryStreamExt};
+use log::info;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// How many items are fetched from the database at once
+const WARM_CHUNK_SIZE: usize = 25;
+/// How many of each project's newest versions are warmed
+const VERSIONS_PER_PROJECT: usize = 5;
+
+/// Fills the Redis caches of the most downloaded projects and their newest versions, so the
+/// first requests for them after a deploy or a flush aren't slow
+#[derive(Clone, Copy, Debug)]
+pub struct CacheWarmer {
+    /// How many of the most downloaded projects are warmed
+    pub projects: i64,
+    /// How many chunks are fetched at the same time. Each fetch holds a database connection, so
+    /// this is kept low to leave the pools to requests.
+    pub concurrency: usize,
+}
+
+/// What a run of the cache warmer did
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WarmedCaches {
+    pub projects: usize,
+    pub versions: usize,
+    /// Items which were already cached
+    pub skipped: usize,
+    pub elapsed_ms: u64,
+}
+
+impl Default for CacheWarmer {
+    fn default() -> Self {
+        CacheWarmer {
+            projects: 100,
+            concurrency: 2,
+        }
+    }
+}
+
+impl CacheWarmer {
+    /// Reads the `CACHE_WARMING_PROJECTS` and `CACHE_WARMING_CONCURRENCY` variables, falling
+    /// back to the defaults
+    pub fn from_env() -> Self {
+        let default = CacheWarmer::default();
+        CacheWarmer {
+            projects: parse_var("CACHE_WARMING_PROJECTS").unwrap_or(default.projects),
+            concurrency: parse_var("CACHE_WARMING_CONCURRENCY").unwrap_or(default.concurrency),
+        }
+    }
+
+    /// Warms the caches, reporting progress to the job registry
+    pub async fn warm(
+        &self,
+        pool: &PgPool,
+        redis: &RedisPool,
+    ) -> Result<WarmedCaches, DatabaseError> {
+        let mut job = JobHandle::start(JobType::CacheWarming, None, redis).await;
+        let result = self.warm_all(pool, redis, &mut job).await;
+        job.finish(&result).await;
+
+        if let Ok(warmed) = &result {
+            info!(
+                "Warmed {} projects and {} versions in {}ms, {} were already cached",
+                warmed.projects, warmed.versions, warmed.elapsed_ms, warmed.skipped
+            );
+        }
+
+        result
+    }
+
+    async fn warm_all(
+        &self,
+        pool: &PgPool,
+        redis: &RedisPool,
+        job: &mut JobHandle,
+    ) -> Result<WarmedCaches, DatabaseError> {
+        let start = Instant::now();
+
+        let project_ids = sqlx::query!(
+            "
+            SELECT id FROM mods
+            WHERE status = ANY($1) AND deleted_at IS NULL
+            ORDER BY downloads DESC
+            LIMIT $2
+            ",
+            &*ProjectStatus::iterator()
+                .filter(|x| x.is_searchable())
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>(),
+            self.projects.max(0),
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect::<Vec<_>>();
+
+        // Versions are fetched newest first, so the first of each project's are its newest
+        let mut project_versions = HashMap::new();
+        let version_ids = sqlx::query!(
+            "
+            SELECT id, mod_id FROM versions
+            WHERE mod_id = ANY($1) AND status = ANY($2)
+            ORDER BY date_published DESC
+            ",
+            &project_ids,
+            &*VersionStatus::iterator()
+                .filter(|x| x.is_listed())
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>(),
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .filter(|x| {
+            let count = project_versions.entry(x.mod_id).or_insert(0);
+            *count += 1;
+            *count <= VERSIONS_PER_PROJECT
+        })
+        .map(|x| x.id)
+        .collect::<Vec<_>>();
+
+        let total = project_ids.len() + version_ids.len();
+        let project_ids = uncached(PROJECTS_NAMESPACE, project_ids, redis).await?;
+        let version_ids = uncached(VERSIONS_NAMESPACE, version_ids, redis).await?;
+        let skipped = total - project_ids.len() - version_ids.len();
+
+        job.progress(
+            0,
+            (project_ids.len() + version_ids.len()) as u64,
+            "Warming projects",
+        )
+        .await;
+
+        let concurrency = self.concurrency.max(1);
+        let mut projects = futures::stream::iter(project_ids.chunks(WARM_CHUNK_SIZE))
+            .map(|chunk| async move {
+                let ids = chunk.iter().map(|x| ProjectId(*x)).collect::<Vec<_>>();
+                Project::get_many_ids(&ids, pool, redis)
+                    .await
+                    .map(|x| x.len())
+            })
+            .buffer_unordered(concurrency);
+        let mut warmed_projects = 0;
+        while let Some(count) = projects.try_next().await? {
+            warmed_projects += count;
+            job.advance(count as u64, "Warming projects").await;
+        }
+
+        let mut versions = futures::stream::iter(version_ids.chunks(WARM_CHUNK_SIZE))
+            .map(|chunk| async move {
+                let ids = chunk.iter().map(|x| VersionId(*x)).collect::<Vec<_>>();
+                Version::get_many(&ids, pool, redis).await.map(|x| x.len())
+            })
+            .buffer_unordered(concurrency);
+        let mut warmed_versions = 0;
+        while let Some(count) = versions.try_next().await? {
+            warmed_versions += count;
+            job.advance(count as u64, "Warming versions").await;
+        }
+
+        Ok(WarmedCaches {
+            projects: warmed_projects,
+            versions: warmed_versions,
+            skipped,
+            elapsed_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+// The ids which aren't in the namespace's cache
+async fn uncached(
+    namespace: &str,
+    ids: Vec<i64>,
+    redis: &RedisPool,
+) -> Result<Vec<i64>, DatabaseError> {
+    if ids.is_empty() {
+        return Ok(ids);
+    }
+
+    let mut redis = redis.connect().await?;
+    let cached = redis.multi_get::<String>(namespace, ids.iter()).await?;
+
+    Ok(ids
+        .into_iter()
+        .zip(cached)
+        .filter(|(_, cached)| cached.is_none())
+        .map(|(id, _)| id)
+        .collect())
+}
@@ -1,3 +1,4 @@
+pub mod cache_warming;
 pub mod models;
 mod postgres_database;
 pub mod redis;
@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{AuditLogEntryId, DatabaseError, OrganizationId, ProjectId, UserId};
+use crate::models::audit_log::{AuditAction, AuditTargetType};
+
+pub struct AuditLogBuilder {
+    pub actor_id: UserId,
+    pub action: AuditAction,
+    pub target_type: AuditTargetType,
+    pub target_id: i64,
+    pub project_id: Option<ProjectId>,
+    pub organization_id: Option<OrganizationId>,
+    pub summary: serde_json::Value,
+    pub ip: Option<String>,
+}
+
+/// An entry in the audit log. Entries are never edited or deleted once inserted.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct AuditLogEntry {
+    pub id: AuditLogEntryId,
+    pub actor_id: UserId,
+    pub action: AuditAction,
+    pub target_type: AuditTargetType,
+    pub target_id: i64,
+    pub project_id: Option<ProjectId>,
+    pub organization_id: Option<OrganizationId>,
+    pub summary: serde_json::Value,
+    pub ip: Option<String>,
+    pub created: DateTime<Utc>,
+}
+
+impl AuditLogBuilder {
+    pub async fn insert(
+        self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<AuditLogEntryId, DatabaseError> {
+        let id = super::generate_audit_log_entry_id(&mut *transaction).await?;
+
+        sqlx::query!(
+            "
+            INSERT INTO audit_log (
+                id, actor_id, action, target_type, target_id,
+                project_id, organization_id, summary, ip
+            )
+            VALUES (
+                $1, $2, $3, $4, $5,
+                $6, $7, $8, $9
+            )
+            ",
+            id.0,
+            self.actor_id.0,
+            self.action.as_str(),
+            self.target_type.as_str(),
+            self.target_id,
+            self.project_id.map(|x| x.0),
+            self.organization_id.map(|x| x.0),
+            self.summary,
+            self.ip,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(id)
+    }
+}
+
+impl AuditLogEntry {
+    /// Lists the entries of a project or organization newest first, optionally only those
+    /// of one action
+    pub async fn list<'a, E>(
+        project_id: Option<ProjectId>,
+        organization_id: Option<OrganizationId>,
+        action: Option<AuditAction>,
+        count: i64,
+        offset: i64,
+        exec: E,
+    ) -> Result<Vec<AuditLogEntry>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let results = sqlx::query!(
+            "
+            SELECT id, actor_id, action, target_type, target_id,
+            project_id, organization_id, summary, ip, created
+            FROM audit_log
+            WHERE ($1::bigint IS NULL OR project_id = $1)
+            AND ($2::bigint IS NULL OR organization_id = $2)
+            AND ($3::varchar IS NULL OR action = $3)
+            ORDER BY created DESC, id DESC
+            LIMIT $4 OFFSET $5
+            ",
+            project_id.map(|x| x.0),
+            organization_id.map(|x| x.0),
+            action.map(|x| x.as_str()),
+            count,
+            offset,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| AuditLogEntry {
+                id: AuditLogEntryId(r.id),
+                actor_id: UserId(r.actor_id),
+                action: AuditAction::from_string(&r.action),
+                target_type: AuditTargetType::from_string(&r.target_type),
+                target_id: r.target_id,
+                project_id: r.project_id.map(ProjectId),
+                organization_id: r.organization_id.map(OrganizationId),
+                summary: r.summary,
+                ip: r.ip,
+                created: r.created,
+            })
+            .collect())
+    }
+}
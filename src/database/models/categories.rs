@@ -86,6 +86,101 @@ impl Category {
         Ok(result.map(|r| CategoryId(r.id)))
     }
 
+    /// Gets the ids of the categories with the names which are registered for any of the
+    /// project types, by name. Categories of every type are returned if no types are given.
+    pub async fn get_ids_project_types<'a, E>(
+        names: &[String],
+        project_types: &[String],
+        exec: E,
+    ) -> Result<HashMap<String, Vec<CategoryId>>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT c.id, c.category FROM categories c
+            INNER JOIN project_types pt ON pt.id = c.project_type
+            WHERE c.category = ANY($1::text[])
+            AND (cardinality($2::text[]) = 0 OR pt.name = ANY($2::text[]))
+            ",
+            names,
+            project_types,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        let mut map: HashMap<String, Vec<CategoryId>> = HashMap::new();
+        for r in result {
+            map.entry(r.category).or_default().push(CategoryId(r.id));
+        }
+
+        Ok(map)
+    }
+
+    /// Registers a category for a project type
+    pub async fn insert(
+        name: &str,
+        project_type: ProjectTypeId,
+        icon: &str,
+        header: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<CategoryId, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            INSERT INTO categories (category, project_type, icon, header)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+            ",
+            name,
+            project_type as ProjectTypeId,
+            icon,
+            header,
+        )
+        .fetch_one(&mut **transaction)
+        .await?;
+
+        Ok(CategoryId(result.id))
+    }
+
+    /// Whether any project is in the category
+    pub async fn is_used<'a, E>(id: CategoryId, exec: E) -> Result<bool, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "SELECT EXISTS(SELECT 1 FROM mods_categories WHERE joining_category_id = $1)",
+            id as CategoryId,
+        )
+        .fetch_one(exec)
+        .await?;
+
+        Ok(result.exists.unwrap_or(false))
+    }
+
+    pub async fn remove(
+        id: CategoryId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            DELETE FROM categories
+            WHERE id = $1
+            ",
+            id as CategoryId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn clear_cache(redis: &RedisPool) -> Result<(), DatabaseError> {
+        let mut redis = redis.connect().await?;
+        redis.delete(TAGS_NAMESPACE, "category").await?;
+
+        Ok(())
+    }
+
     pub async fn list<'a, E>(exec: E, redis: &RedisPool) -> Result<Vec<Category>, DatabaseError>
     where
         E: sqlx::Executor<'a, Database = sqlx::Postgres>,
@@ -1,8 +1,8 @@
 use super::ids::*;
 use crate::database::models;
 use crate::database::models::DatabaseError;
-use crate::database::redis::RedisPool;
-use crate::models::collections::CollectionStatus;
+use crate::database::redis::{CachedEntry, RedisPool};
+use crate::models::collections::{CollectionStatus, CollectionVisibility};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -15,6 +15,7 @@ pub struct CollectionBuilder {
     pub name: String,
     pub description: Option<String>,
     pub status: CollectionStatus,
+    pub visibility: CollectionVisibility,
     pub projects: Vec<ProjectId>,
 }
 
@@ -33,7 +34,9 @@ impl CollectionBuilder {
             icon_url: None,
             color: None,
             status: self.status,
+            visibility: self.visibility,
             projects: self.projects,
+            follows: 0,
         };
         collection_struct.insert(transaction).await?;
 
@@ -51,7 +54,9 @@ pub struct Collection {
     pub icon_url: Option<String>,
     pub color: Option<u32>,
     pub status: CollectionStatus,
+    pub visibility: CollectionVisibility,
     pub projects: Vec<ProjectId>,
+    pub follows: i32,
 }
 
 impl Collection {
@@ -63,11 +68,11 @@ impl Collection {
             "
             INSERT INTO collections (
                 id, user_id, name, description, 
-                created, icon_url, status
+                created, icon_url, status, visibility
             )
             VALUES (
                 $1, $2, $3, $4, 
-                $5, $6, $7
+                $5, $6, $7, $8
             )
             ",
             self.id as CollectionId,
@@ -77,20 +82,94 @@ impl Collection {
             self.created,
             self.icon_url.as_ref(),
             self.status.to_string(),
+            self.visibility.as_str(),
         )
         .execute(&mut **transaction)
         .await?;
 
-        let (collection_ids, project_ids): (Vec<_>, Vec<_>) =
-            self.projects.iter().map(|p| (self.id.0, p.0)).unzip();
+        Self::add_projects(self.id, &self.projects, transaction).await?;
+
+        Ok(())
+    }
+
+    /// Appends projects to the end of the collection, skipping ones which are already in it
+    pub async fn add_projects(
+        id: CollectionId,
+        project_ids: &[ProjectId],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
         sqlx::query!(
             "
-                INSERT INTO collections_mods (collection_id, mod_id)
-                SELECT * FROM UNNEST($1::bigint[], $2::bigint[])
-                ON CONFLICT DO NOTHING
+            INSERT INTO collections_mods (collection_id, mod_id, ordering)
+            SELECT $1, p.mod_id, COALESCE((SELECT MAX(ordering) FROM collections_mods WHERE collection_id = $1), 0) + p.ordering
+            FROM UNNEST($2::bigint[]) WITH ORDINALITY AS p(mod_id, ordering)
+            ON CONFLICT DO NOTHING
             ",
-            &collection_ids[..],
-            &project_ids[..],
+            id as CollectionId,
+            &project_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_projects(
+        id: CollectionId,
+        project_ids: &[ProjectId],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            DELETE FROM collections_mods
+            WHERE collection_id = $1 AND mod_id = ANY($2)
+            ",
+            id as CollectionId,
+            &project_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the projects currently in the collection, locking them until the transaction ends
+    pub async fn lock_projects(
+        id: CollectionId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<ProjectId>, DatabaseError> {
+        let project_ids = sqlx::query!(
+            "
+            SELECT mod_id FROM collections_mods
+            WHERE collection_id = $1
+            FOR UPDATE
+            ",
+            id as CollectionId,
+        )
+        .fetch_all(&mut **transaction)
+        .await?
+        .into_iter()
+        .map(|x| ProjectId(x.mod_id))
+        .collect();
+
+        Ok(project_ids)
+    }
+
+    /// Sets the ordering of the projects in the collection to the order of `project_ids`
+    pub async fn reorder_projects(
+        id: CollectionId,
+        project_ids: &[ProjectId],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE collections_mods cm
+            SET ordering = p.ordering
+            FROM UNNEST($2::bigint[]) WITH ORDINALITY AS p(mod_id, ordering)
+            WHERE cm.collection_id = $1 AND cm.mod_id = p.mod_id
+            ",
+            id as CollectionId,
+            &project_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
         )
         .execute(&mut **transaction)
         .await?;
@@ -116,6 +195,26 @@ impl Collection {
             .execute(&mut **transaction)
             .await?;
 
+            sqlx::query!(
+                "
+                DELETE FROM collection_follows
+                WHERE collection_id = $1
+                ",
+                id as CollectionId,
+            )
+            .execute(&mut **transaction)
+            .await?;
+
+            sqlx::query!(
+                "
+                DELETE FROM collection_share_tokens
+                WHERE collection_id = $1
+                ",
+                id as CollectionId,
+            )
+            .execute(&mut **transaction)
+            .await?;
+
             sqlx::query!(
                 "
                 DELETE FROM collections
@@ -157,97 +256,212 @@ impl Collection {
     {
         use futures::TryStreamExt;
 
-        let mut redis = redis.connect().await?;
-
-        if collection_ids.is_empty() {
-            return Ok(Vec::new());
-        }
+        // Entries cached before collections had a visibility don't deserialize, so they're
+        // fetched again rather than being served as public
+        redis
+            .get_cached_keys(
+                COLLECTIONS_NAMESPACE,
+                None,
+                &collection_ids
+                    .iter()
+                    .map(|x| x.0.to_string())
+                    .collect::<Vec<_>>(),
+                |x| x.parse().ok(),
+                |remaining_ids| async move {
+                    let collection_ids_parsed: Vec<i64> =
+                        remaining_ids.iter().flat_map(|x| x.parse().ok()).collect();
+                    let db_collections: Vec<Collection> = sqlx::query!(
+                        "
+                        SELECT c.id id, c.name name, c.description description,
+                        c.icon_url icon_url, c.color color, c.created created, c.user_id user_id,
+                        c.updated updated, c.status status, c.visibility visibility, c.follows follows,
+                        ARRAY_AGG(cm.mod_id ORDER BY cm.ordering, cm.mod_id) filter (where cm.mod_id is not null) mods
+                        FROM collections c
+                        LEFT JOIN collections_mods cm ON cm.collection_id = c.id
+                        WHERE c.id = ANY($1)
+                        GROUP BY c.id;
+                        ",
+                        &collection_ids_parsed,
+                    )
+                    .fetch_many(exec)
+                    .try_filter_map(|e| async {
+                        Ok(e.right().map(|m| {
+                            let id = m.id;
 
-        let mut found_collections = Vec::new();
-        let mut remaining_collections: Vec<CollectionId> = collection_ids.to_vec();
-
-        if !collection_ids.is_empty() {
-            let collections = redis
-                .multi_get::<String>(
-                    COLLECTIONS_NAMESPACE,
-                    collection_ids.iter().map(|x| x.0.to_string()),
-                )
-                .await?;
-
-            for collection in collections {
-                if let Some(collection) =
-                    collection.and_then(|x| serde_json::from_str::<Collection>(&x).ok())
-                {
-                    remaining_collections.retain(|x| collection.id.0 != x.0);
-                    found_collections.push(collection);
-                    continue;
-                }
-            }
-        }
+                            Collection {
+                                id: CollectionId(id),
+                                user_id: UserId(m.user_id),
+                                name: m.name.clone(),
+                                description: m.description.clone(),
+                                icon_url: m.icon_url.clone(),
+                                color: m.color.map(|x| x as u32),
+                                created: m.created,
+                                updated: m.updated,
+                                status: CollectionStatus::from_string(&m.status),
+                                visibility: CollectionVisibility::from_string(&m.visibility),
+                                projects: m
+                                    .mods
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .map(ProjectId)
+                                    .collect(),
+                                follows: m.follows,
+                            }
+                        }))
+                    })
+                    .try_collect::<Vec<Collection>>()
+                    .await?;
 
-        if !remaining_collections.is_empty() {
-            let collection_ids_parsed: Vec<i64> =
-                remaining_collections.iter().map(|x| x.0).collect();
-            let db_collections: Vec<Collection> = sqlx::query!(
-                "
-                SELECT c.id id, c.name name, c.description description,
-                c.icon_url icon_url, c.color color, c.created created, c.user_id user_id,
-                c.updated updated, c.status status,
-                ARRAY_AGG(DISTINCT cm.mod_id) filter (where cm.mod_id is not null) mods
-                FROM collections c
-                LEFT JOIN collections_mods cm ON cm.collection_id = c.id
-                WHERE c.id = ANY($1)
-                GROUP BY c.id;
-                ",
-                &collection_ids_parsed,
+                    Ok(db_collections)
+                },
             )
-            .fetch_many(exec)
-            .try_filter_map(|e| async {
-                Ok(e.right().map(|m| {
-                    let id = m.id;
-
-                    Collection {
-                        id: CollectionId(id),
-                        user_id: UserId(m.user_id),
-                        name: m.name.clone(),
-                        description: m.description.clone(),
-                        icon_url: m.icon_url.clone(),
-                        color: m.color.map(|x| x as u32),
-                        created: m.created,
-                        updated: m.updated,
-                        status: CollectionStatus::from_string(&m.status),
-                        projects: m
-                            .mods
-                            .unwrap_or_default()
-                            .into_iter()
-                            .map(ProjectId)
-                            .collect(),
-                    }
-                }))
-            })
-            .try_collect::<Vec<Collection>>()
-            .await?;
+            .await
+    }
 
-            for collection in db_collections {
-                redis
-                    .set_serialized_to_json(
-                        COLLECTIONS_NAMESPACE,
-                        collection.id.0,
-                        &collection,
-                        None,
-                    )
-                    .await?;
-                found_collections.push(collection);
-            }
-        }
+    pub async fn is_following<'a, E>(
+        id: CollectionId,
+        user_id: UserId,
+        exec: E,
+    ) -> Result<bool, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let following = sqlx::query!(
+            "
+            SELECT EXISTS(SELECT 1 FROM collection_follows cf WHERE cf.follower_id = $1 AND cf.collection_id = $2)
+            ",
+            user_id as UserId,
+            id as CollectionId,
+        )
+        .fetch_one(exec)
+        .await?
+        .exists
+        .unwrap_or(false);
 
-        Ok(found_collections)
+        Ok(following)
+    }
+
+    pub async fn follow(
+        id: CollectionId,
+        user_id: UserId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE collections
+            SET follows = follows + 1
+            WHERE id = $1
+            ",
+            id as CollectionId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        sqlx::query!(
+            "
+            INSERT INTO collection_follows (follower_id, collection_id)
+            VALUES ($1, $2)
+            ",
+            user_id as UserId,
+            id as CollectionId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn unfollow(
+        id: CollectionId,
+        user_id: UserId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE collections
+            SET follows = follows - 1
+            WHERE id = $1
+            ",
+            id as CollectionId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        sqlx::query!(
+            "
+            DELETE FROM collection_follows
+            WHERE follower_id = $1 AND collection_id = $2
+            ",
+            user_id as UserId,
+            id as CollectionId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_followers<'a, E>(
+        id: CollectionId,
+        exec: E,
+    ) -> Result<Vec<UserId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let followers = sqlx::query!(
+            "
+            SELECT follower_id FROM collection_follows
+            WHERE collection_id = $1
+            ",
+            id as CollectionId,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| UserId(x.follower_id))
+        .collect();
+
+        Ok(followers)
+    }
+
+    /// Gets the collections a user follows, most recently followed first
+    pub async fn get_user_follows<'a, E>(
+        user_id: UserId,
+        exec: E,
+    ) -> Result<Vec<CollectionId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let collection_ids = sqlx::query!(
+            "
+            SELECT collection_id FROM collection_follows
+            WHERE follower_id = $1
+            ORDER BY created DESC
+            ",
+            user_id as UserId,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| CollectionId(x.collection_id))
+        .collect();
+
+        Ok(collection_ids)
     }
 
     pub async fn clear_cache(id: CollectionId, redis: &RedisPool) -> Result<(), DatabaseError> {
         let mut redis = redis.connect().await?;
 
         redis.delete(COLLECTIONS_NAMESPACE, id.0).await?;
+        redis
+            .delete_missing(COLLECTIONS_NAMESPACE, None, id.0, None)
+            .await?;
         Ok(())
     }
 }
+
+impl CachedEntry for Collection {
+    fn cache_id(&self) -> i64 {
+        self.id.0
+    }
+}
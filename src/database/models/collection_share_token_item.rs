@@ -0,0 +1,123 @@
+use chrono::{DateTime, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{CollectionId, DatabaseError};
+
+/// A token which lets anyone holding it view a private collection, until the owner revokes it
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct CollectionShareToken {
+    pub token: String,
+    pub collection_id: CollectionId,
+    pub created: DateTime<Utc>,
+}
+
+impl CollectionShareToken {
+    pub fn generate(collection_id: CollectionId) -> CollectionShareToken {
+        CollectionShareToken {
+            token: ChaCha20Rng::from_entropy()
+                .sample_iter(&Alphanumeric)
+                .take(32)
+                .map(char::from)
+                .collect::<String>(),
+            collection_id,
+            created: Utc::now(),
+        }
+    }
+
+    pub async fn insert(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO collection_share_tokens (token, collection_id, created)
+            VALUES ($1, $2, $3)
+            ",
+            self.token,
+            self.collection_id as CollectionId,
+            self.created,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Whether the token grants access to the collection. Tokens aren't cached, so revoking
+    /// one takes effect immediately.
+    pub async fn is_valid<'a, E>(
+        collection_id: CollectionId,
+        token: &str,
+        exec: E,
+    ) -> Result<bool, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let valid = sqlx::query!(
+            "
+            SELECT EXISTS(SELECT 1 FROM collection_share_tokens WHERE collection_id = $1 AND token = $2)
+            ",
+            collection_id as CollectionId,
+            token,
+        )
+        .fetch_one(exec)
+        .await?
+        .exists
+        .unwrap_or(false);
+
+        Ok(valid)
+    }
+
+    pub async fn get_for_collection<'a, E>(
+        collection_id: CollectionId,
+        exec: E,
+    ) -> Result<Vec<CollectionShareToken>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let tokens = sqlx::query!(
+            "
+            SELECT token, collection_id, created
+            FROM collection_share_tokens
+            WHERE collection_id = $1
+            ORDER BY created DESC
+            ",
+            collection_id as CollectionId,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| CollectionShareToken {
+            token: x.token,
+            collection_id: CollectionId(x.collection_id),
+            created: x.created,
+        })
+        .collect();
+
+        Ok(tokens)
+    }
+
+    /// Revokes the token, returning whether it existed
+    pub async fn remove(
+        collection_id: CollectionId,
+        token: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            DELETE FROM collection_share_tokens
+            WHERE collection_id = $1 AND token = $2
+            ",
+            collection_id as CollectionId,
+            token,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
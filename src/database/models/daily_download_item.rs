@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use itertools::Itertools;
+
+use super::{DatabaseError, ProjectId, VersionId};
+
+/// The number of times a version was downloaded on a single day
+#[derive(Clone, Debug)]
+pub struct DailyDownload {
+    pub project_id: ProjectId,
+    pub version_id: VersionId,
+    pub date: NaiveDate,
+    /// Every download, both organic and pack-driven
+    pub downloads: i32,
+    /// The downloads made while installing a modpack which depends on the version
+    pub pack_downloads: i32,
+}
+
+/// The downloads of a project or version on a day
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DownloadTotals {
+    pub downloads: i64,
+    pub pack_downloads: i64,
+}
+
+impl DailyDownload {
+    /// Adds the downloads onto the counts already recorded for each version and day.
+    /// Downloads of versions which no longer exist are skipped.
+    pub async fn increment_many(
+        items: &[DailyDownload],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        let (project_ids, version_ids, dates, downloads, pack_downloads) = Self::merge(items);
+
+        sqlx::query!(
+            "
+            INSERT INTO daily_downloads (project_id, version_id, date, downloads, pack_downloads)
+            SELECT u.project_id, u.version_id, u.date, u.downloads, u.pack_downloads
+            FROM UNNEST($1::bigint[], $2::bigint[], $3::date[], $4::integer[], $5::integer[])
+                AS u(project_id, version_id, date, downloads, pack_downloads)
+            INNER JOIN versions v ON v.id = u.version_id
+            ON CONFLICT (version_id, date)
+            DO UPDATE SET downloads = daily_downloads.downloads + EXCLUDED.downloads,
+                pack_downloads = daily_downloads.pack_downloads + EXCLUDED.pack_downloads
+            ",
+            &project_ids[..],
+            &version_ids[..],
+            &dates[..],
+            &downloads[..],
+            &pack_downloads[..],
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replaces the counts recorded for each version and day, used when backfilling.
+    /// The raw downloads don't record which were pack-driven, so those counts are kept as-is.
+    /// Downloads of versions which no longer exist are skipped.
+    pub async fn upsert_many(
+        items: &[DailyDownload],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        let (project_ids, version_ids, dates, downloads, _) = Self::merge(items);
+
+        sqlx::query!(
+            "
+            INSERT INTO daily_downloads (project_id, version_id, date, downloads)
+            SELECT u.project_id, u.version_id, u.date, u.downloads
+            FROM UNNEST($1::bigint[], $2::bigint[], $3::date[], $4::integer[])
+                AS u(project_id, version_id, date, downloads)
+            INNER JOIN versions v ON v.id = u.version_id
+            ON CONFLICT (version_id, date)
+            DO UPDATE SET downloads = EXCLUDED.downloads
+            ",
+            &project_ids[..],
+            &version_ids[..],
+            &dates[..],
+            &downloads[..],
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    // A single insert can't touch the same row twice, so downloads of the same version on the
+    // same day are summed first
+    #[allow(clippy::type_complexity)]
+    fn merge(items: &[DailyDownload]) -> (Vec<i64>, Vec<i64>, Vec<NaiveDate>, Vec<i32>, Vec<i32>) {
+        let mut merged: HashMap<(VersionId, NaiveDate), (ProjectId, i32, i32)> = HashMap::new();
+        for item in items {
+            let entry =
+                merged
+                    .entry((item.version_id, item.date))
+                    .or_insert((item.project_id, 0, 0));
+            entry.1 += item.downloads;
+            entry.2 += item.pack_downloads;
+        }
+
+        merged
+            .into_iter()
+            .map(
+                |((version_id, date), (project_id, downloads, pack_downloads))| {
+                    (project_id.0, version_id.0, date, downloads, pack_downloads)
+                },
+            )
+            .multiunzip()
+    }
+
+    /// Gets the total downloads of a project on each day between `start` and `end`, inclusive.
+    /// Days without any downloads are left out.
+    pub async fn get_project_totals<'a, E>(
+        project_id: ProjectId,
+        start: NaiveDate,
+        end: NaiveDate,
+        exec: E,
+    ) -> Result<HashMap<NaiveDate, DownloadTotals>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let totals = sqlx::query!(
+            "
+            SELECT date, SUM(downloads) downloads, SUM(pack_downloads) pack_downloads
+            FROM daily_downloads
+            WHERE project_id = $1 AND date BETWEEN $2 AND $3
+            GROUP BY date
+            ",
+            project_id as ProjectId,
+            start,
+            end,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| {
+            (
+                x.date,
+                DownloadTotals {
+                    downloads: x.downloads.unwrap_or(0),
+                    pack_downloads: x.pack_downloads.unwrap_or(0),
+                },
+            )
+        })
+        .collect();
+
+        Ok(totals)
+    }
+
+    /// Gets the downloads of a version on each day between `start` and `end`, inclusive.
+    /// Days without any downloads are left out.
+    pub async fn get_version_totals<'a, E>(
+        version_id: VersionId,
+        start: NaiveDate,
+        end: NaiveDate,
+        exec: E,
+    ) -> Result<HashMap<NaiveDate, DownloadTotals>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let totals = sqlx::query!(
+            "
+            SELECT date, downloads, pack_downloads
+            FROM daily_downloads
+            WHERE version_id = $1 AND date BETWEEN $2 AND $3
+            ",
+            version_id as VersionId,
+            start,
+            end,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| {
+            (
+                x.date,
+                DownloadTotals {
+                    downloads: x.downloads as i64,
+                    pack_downloads: x.pack_downloads as i64,
+                },
+            )
+        })
+        .collect();
+
+        Ok(totals)
+    }
+}
@@ -51,6 +51,15 @@ pub enum Flow {
         scopes: Scopes,
         original_redirect_uri: Option<String>, // Needed for https://datatracker.ietf.org/doc/html/rfc6749#section-4.1.3
     },
+    DataExport {
+        user_id: UserId,
+        export_id: UserExportId,
+    },
+    LinkConnection {
+        user_id: UserId,
+        url: String,
+        provider: AuthProvider,
+    },
 }
 
 impl Flow {
@@ -41,6 +41,45 @@ macro_rules! generate_ids {
     };
 }
 
+macro_rules! generate_bulk_ids {
+    ($vis:vis $function_name:ident, $return_type:ty, $id_length:expr, $select_stmnt:literal, $id_function:expr) => {
+        $vis async fn $function_name(
+            count: usize,
+            con: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        ) -> Result<Vec<$return_type>, DatabaseError> {
+            let mut rng = rand::thread_rng();
+            let length = $id_length;
+            let mut retry_count = 0;
+            let censor = Censor::Standard + Censor::Sex;
+
+            // Check if all IDs are unique
+            loop {
+                let mut ids = std::collections::HashSet::with_capacity(count);
+                while ids.len() < count {
+                    let id = random_base62_rng(&mut rng, length);
+                    if !censor.check(&*to_base62(id)) {
+                        ids.insert(id as i64);
+                    }
+                }
+                let ids = ids.into_iter().collect::<Vec<_>>();
+
+                let results = sqlx::query!($select_stmnt, &ids[..])
+                    .fetch_one(&mut **con)
+                    .await?;
+
+                if !results.exists.unwrap_or(true) {
+                    return Ok(ids.into_iter().map($id_function).collect());
+                }
+
+                retry_count += 1;
+                if retry_count > ID_RETRY_COUNT {
+                    return Err(DatabaseError::RandomId);
+                }
+            }
+        }
+    };
+}
+
 generate_ids!(
     pub generate_project_id,
     ProjectId,
@@ -120,6 +159,13 @@ generate_ids!(
     "SELECT EXISTS(SELECT 1 FROM notifications WHERE id=$1)",
     NotificationId
 );
+generate_bulk_ids!(
+    pub generate_many_notification_ids,
+    NotificationId,
+    8,
+    "SELECT EXISTS(SELECT 1 FROM notifications WHERE id = ANY($1))",
+    NotificationId
+);
 
 generate_ids!(
     pub generate_thread_id,
@@ -192,6 +238,62 @@ generate_ids!(
     PayoutId
 );
 
+generate_ids!(
+    pub generate_webhook_id,
+    WebhookId,
+    8,
+    "SELECT EXISTS(SELECT 1 FROM project_webhooks WHERE id=$1)",
+    WebhookId
+);
+
+generate_ids!(
+    pub generate_audit_log_entry_id,
+    AuditLogEntryId,
+    8,
+    "SELECT EXISTS(SELECT 1 FROM audit_log WHERE id=$1)",
+    AuditLogEntryId
+);
+
+generate_ids!(
+    pub generate_user_export_id,
+    UserExportId,
+    8,
+    "SELECT EXISTS(SELECT 1 FROM user_exports WHERE id=$1)",
+    UserExportId
+);
+
+generate_ids!(
+    pub generate_user_connection_id,
+    UserConnectionId,
+    8,
+    "SELECT EXISTS(SELECT 1 FROM user_connections WHERE id=$1)",
+    UserConnectionId
+);
+
+generate_ids!(
+    pub generate_organization_invite_link_id,
+    OrganizationInviteLinkId,
+    8,
+    "SELECT EXISTS(SELECT 1 FROM organization_invite_links WHERE id=$1)",
+    OrganizationInviteLinkId
+);
+
+generate_ids!(
+    pub generate_organization_role_template_id,
+    OrganizationRoleTemplateId,
+    8,
+    "SELECT EXISTS(SELECT 1 FROM organization_role_templates WHERE id=$1)",
+    OrganizationRoleTemplateId
+);
+
+generate_ids!(
+    pub generate_pending_upload_id,
+    PendingUploadId,
+    8,
+    "SELECT EXISTS(SELECT 1 FROM pending_uploads WHERE id=$1)",
+    PendingUploadId
+);
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Type, Hash, Serialize, Deserialize)]
 #[sqlx(transparent)]
 pub struct UserId(pub i64);
@@ -311,6 +413,34 @@ pub struct OAuthAccessTokenId(pub i64);
 #[sqlx(transparent)]
 pub struct PayoutId(pub i64);
 
+#[derive(Copy, Clone, Debug, Type, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[sqlx(transparent)]
+pub struct WebhookId(pub i64);
+
+#[derive(Copy, Clone, Debug, Type, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[sqlx(transparent)]
+pub struct PendingUploadId(pub i64);
+
+#[derive(Copy, Clone, Debug, Type, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[sqlx(transparent)]
+pub struct AuditLogEntryId(pub i64);
+
+#[derive(Copy, Clone, Debug, Type, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[sqlx(transparent)]
+pub struct UserExportId(pub i64);
+
+#[derive(Copy, Clone, Debug, Type, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[sqlx(transparent)]
+pub struct UserConnectionId(pub i64);
+
+#[derive(Copy, Clone, Debug, Type, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[sqlx(transparent)]
+pub struct OrganizationInviteLinkId(pub i64);
+
+#[derive(Copy, Clone, Debug, Type, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[sqlx(transparent)]
+pub struct OrganizationRoleTemplateId(pub i64);
+
 use crate::models::ids;
 
 impl From<ids::ProjectId> for ProjectId {
@@ -464,3 +594,74 @@ impl From<PayoutId> for ids::PayoutId {
         ids::PayoutId(id.0 as u64)
     }
 }
+
+impl From<ids::WebhookId> for WebhookId {
+    fn from(id: ids::WebhookId) -> Self {
+        WebhookId(id.0 as i64)
+    }
+}
+impl From<WebhookId> for ids::WebhookId {
+    fn from(id: WebhookId) -> Self {
+        ids::WebhookId(id.0 as u64)
+    }
+}
+impl From<ids::PendingUploadId> for PendingUploadId {
+    fn from(id: ids::PendingUploadId) -> Self {
+        PendingUploadId(id.0 as i64)
+    }
+}
+impl From<PendingUploadId> for ids::PendingUploadId {
+    fn from(id: PendingUploadId) -> Self {
+        ids::PendingUploadId(id.0 as u64)
+    }
+}
+impl From<ids::AuditLogEntryId> for AuditLogEntryId {
+    fn from(id: ids::AuditLogEntryId) -> Self {
+        AuditLogEntryId(id.0 as i64)
+    }
+}
+impl From<AuditLogEntryId> for ids::AuditLogEntryId {
+    fn from(id: AuditLogEntryId) -> Self {
+        ids::AuditLogEntryId(id.0 as u64)
+    }
+}
+impl From<ids::UserExportId> for UserExportId {
+    fn from(id: ids::UserExportId) -> Self {
+        UserExportId(id.0 as i64)
+    }
+}
+impl From<UserExportId> for ids::UserExportId {
+    fn from(id: UserExportId) -> Self {
+        ids::UserExportId(id.0 as u64)
+    }
+}
+impl From<ids::UserConnectionId> for UserConnectionId {
+    fn from(id: ids::UserConnectionId) -> Self {
+        UserConnectionId(id.0 as i64)
+    }
+}
+impl From<UserConnectionId> for ids::UserConnectionId {
+    fn from(id: UserConnectionId) -> Self {
+        ids::UserConnectionId(id.0 as u64)
+    }
+}
+impl From<ids::OrganizationInviteLinkId> for OrganizationInviteLinkId {
+    fn from(id: ids::OrganizationInviteLinkId) -> Self {
+        OrganizationInviteLinkId(id.0 as i64)
+    }
+}
+impl From<OrganizationInviteLinkId> for ids::OrganizationInviteLinkId {
+    fn from(id: OrganizationInviteLinkId) -> Self {
+        ids::OrganizationInviteLinkId(id.0 as u64)
+    }
+}
+impl From<ids::OrganizationRoleTemplateId> for OrganizationRoleTemplateId {
+    fn from(id: ids::OrganizationRoleTemplateId) -> Self {
+        OrganizationRoleTemplateId(id.0 as i64)
+    }
+}
+impl From<OrganizationRoleTemplateId> for ids::OrganizationRoleTemplateId {
+    fn from(id: OrganizationRoleTemplateId) -> Self {
+        ids::OrganizationRoleTemplateId(id.0 as u64)
+    }
+}
@@ -1,8 +1,10 @@
 use super::ids::*;
-use crate::database::redis::RedisPool;
-use crate::{database::models::DatabaseError, models::images::ImageContext};
+use crate::database::models::DatabaseError;
+use crate::database::redis::{CachedEntry, RedisPool};
+use crate::models::images::{ImageContext, ImageModerationStatus};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 const IMAGES_NAMESPACE: &str = "images";
 
@@ -10,9 +12,13 @@ const IMAGES_NAMESPACE: &str = "images";
 pub struct Image {
     pub id: ImageId,
     pub url: String,
+    #[serde(default)]
+    pub variants: HashMap<String, String>,
     pub size: u64,
     pub created: DateTime<Utc>,
     pub owner_id: UserId,
+    #[serde(default)]
+    pub moderation_status: ImageModerationStatus,
 
     // context it is associated with
     pub context: String,
@@ -31,10 +37,12 @@ impl Image {
         sqlx::query!(
             "
             INSERT INTO uploaded_images (
-                id, url, size, created, owner_id, context, mod_id, version_id, thread_message_id, report_id
+                id, url, size, created, owner_id, context, mod_id, version_id, thread_message_id, report_id, variants,
+                moderation_status
             )
             VALUES (
-                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11,
+                $12
             );
             ",
             self.id as ImageId,
@@ -47,6 +55,8 @@ impl Image {
             self.version_id.map(|x| x.0),
             self.thread_message_id.map(|x| x.0),
             self.report_id.map(|x| x.0),
+            serde_json::json!(self.variants),
+            self.moderation_status.as_str(),
         )
         .execute(&mut **transaction)
         .await?;
@@ -118,7 +128,7 @@ impl Image {
         use futures::stream::TryStreamExt;
         sqlx::query!(
             "
-            SELECT id, url, size, created, owner_id, context, mod_id, version_id, thread_message_id, report_id
+            SELECT id, url, variants, size, created, owner_id, moderation_status, context, mod_id, version_id, thread_message_id, report_id
             FROM uploaded_images
             WHERE context = $1
             AND (mod_id = $2 OR ($2 IS NULL AND mod_id IS NULL))
@@ -142,9 +152,11 @@ impl Image {
                 Image {
                     id,
                     url: row.url,
+                    variants: serde_json::from_value(row.variants).unwrap_or_default(),
                     size: row.size as u64,
                     created: row.created,
                     owner_id: UserId(row.owner_id),
+                    moderation_status: ImageModerationStatus::from_string(&row.moderation_status),
                     context: row.context,
                     project_id: row.mod_id.map(ProjectId),
                     version_id: row.version_id.map(VersionId),
@@ -157,6 +169,47 @@ impl Image {
         .await
     }
 
+    /// Gets the images attached to any of the thread messages
+    pub async fn get_many_thread_messages<'a, E>(
+        message_ids: &[ThreadMessageId],
+        exec: E,
+    ) -> Result<Vec<Image>, sqlx::Error>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        use futures::stream::TryStreamExt;
+
+        let message_ids_parsed: Vec<i64> = message_ids.iter().map(|x| x.0).collect();
+        sqlx::query!(
+            "
+            SELECT id, url, variants, size, created, owner_id, moderation_status, context, mod_id, version_id, thread_message_id, report_id
+            FROM uploaded_images
+            WHERE thread_message_id = ANY($1)
+            ORDER BY created
+            ",
+            &message_ids_parsed
+        )
+        .fetch_many(exec)
+        .try_filter_map(|e| async {
+            Ok(e.right().map(|row| Image {
+                id: ImageId(row.id),
+                url: row.url,
+                variants: serde_json::from_value(row.variants).unwrap_or_default(),
+                size: row.size as u64,
+                created: row.created,
+                owner_id: UserId(row.owner_id),
+                moderation_status: ImageModerationStatus::from_string(&row.moderation_status),
+                context: row.context,
+                project_id: row.mod_id.map(ProjectId),
+                version_id: row.version_id.map(VersionId),
+                thread_message_id: row.thread_message_id.map(ThreadMessageId),
+                report_id: row.report_id.map(ReportId),
+            }))
+        })
+        .try_collect::<Vec<Image>>()
+        .await
+    }
+
     pub async fn get<'a, 'b, E>(
         id: ImageId,
         executor: E,
@@ -180,76 +233,131 @@ impl Image {
     {
         use futures::TryStreamExt;
 
-        let mut redis = redis.connect().await?;
-        if image_ids.is_empty() {
-            return Ok(Vec::new());
-        }
+        redis
+            .get_cached_keys(
+                IMAGES_NAMESPACE,
+                None,
+                &image_ids
+                    .iter()
+                    .map(|x| x.0.to_string())
+                    .collect::<Vec<_>>(),
+                |x| x.parse().ok(),
+                |remaining_ids| async move {
+                    let db_images: Vec<Image> = sqlx::query!(
+                        "
+                        SELECT id, url, variants, size, created, owner_id, moderation_status, context, mod_id, version_id, thread_message_id, report_id
+                        FROM uploaded_images
+                        WHERE id = ANY($1)
+                        GROUP BY id;
+                        ",
+                        &remaining_ids
+                            .iter()
+                            .flat_map(|x| x.parse().ok())
+                            .collect::<Vec<i64>>(),
+                    )
+                    .fetch_many(exec)
+                    .try_filter_map(|e| async {
+                        Ok(e.right().map(|i| {
+                            let id = i.id;
 
-        let mut found_images = Vec::new();
-        let mut remaining_ids = image_ids.to_vec();
+                            Image {
+                                id: ImageId(id),
+                                url: i.url,
+                                variants: serde_json::from_value(i.variants).unwrap_or_default(),
+                                size: i.size as u64,
+                                created: i.created,
+                                owner_id: UserId(i.owner_id),
+                                moderation_status: ImageModerationStatus::from_string(
+                                    &i.moderation_status,
+                                ),
+                                context: i.context,
+                                project_id: i.mod_id.map(ProjectId),
+                                version_id: i.version_id.map(VersionId),
+                                thread_message_id: i.thread_message_id.map(ThreadMessageId),
+                                report_id: i.report_id.map(ReportId),
+                            }
+                        }))
+                    })
+                    .try_collect::<Vec<Image>>()
+                    .await?;
 
-        let image_ids = image_ids.iter().map(|x| x.0).collect::<Vec<_>>();
+                    Ok(db_images)
+                },
+            )
+            .await
+    }
 
-        if !image_ids.is_empty() {
-            let images = redis
-                .multi_get::<String>(IMAGES_NAMESPACE, image_ids.iter().map(|x| x.to_string()))
-                .await?;
-            for image in images {
-                if let Some(image) = image.and_then(|x| serde_json::from_str::<Image>(&x).ok()) {
-                    remaining_ids.retain(|x| image.id.0 != x.0);
-                    found_images.push(image);
-                    continue;
-                }
-            }
-        }
+    /// Gets the ids of the images waiting for a moderator's approval, oldest first, and how many
+    /// there are in total
+    pub async fn get_pending<'a, E>(
+        count: i64,
+        offset: i64,
+        exec: E,
+    ) -> Result<(i64, Vec<ImageId>), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        // The page is left joined onto the total so the total is returned even for empty pages
+        let rows = sqlx::query!(
+            "
+            SELECT t.total, p.id
+            FROM (SELECT COUNT(*) total FROM uploaded_images WHERE moderation_status = 'pending') t
+            LEFT JOIN LATERAL (
+                SELECT ui.id
+                FROM uploaded_images ui
+                WHERE ui.moderation_status = 'pending'
+                ORDER BY ui.created
+                LIMIT $1 OFFSET $2
+            ) p ON TRUE
+            ",
+            count,
+            offset,
+        )
+        .fetch_all(exec)
+        .await?;
 
-        if !remaining_ids.is_empty() {
-            let db_images: Vec<Image> = sqlx::query!(
-                "
-                SELECT id, url, size, created, owner_id, context, mod_id, version_id, thread_message_id, report_id
-                FROM uploaded_images
-                WHERE id = ANY($1)
-                GROUP BY id;
-                ",
-                &remaining_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
-            )
-            .fetch_many(exec)
-            .try_filter_map(|e| async {
-                Ok(e.right().map(|i| {
-                    let id = i.id;
-
-                    Image {
-                        id: ImageId(id),
-                        url: i.url,
-                        size: i.size as u64,
-                        created: i.created,
-                        owner_id: UserId(i.owner_id),
-                        context: i.context,
-                        project_id: i.mod_id.map(ProjectId),
-                        version_id: i.version_id.map(VersionId),
-                        thread_message_id: i.thread_message_id.map(ThreadMessageId),
-                        report_id: i.report_id.map(ReportId),
-                    }
-                }))
-            })
-            .try_collect::<Vec<Image>>()
-            .await?;
+        let total = rows.first().and_then(|x| x.total).unwrap_or(0);
+        let ids = rows
+            .into_iter()
+            .filter_map(|x| Some(ImageId(x.id?)))
+            .collect();
 
-            for image in db_images {
-                redis
-                    .set_serialized_to_json(IMAGES_NAMESPACE, image.id.0, &image, None)
-                    .await?;
-                found_images.push(image);
-            }
-        }
+        Ok((total, ids))
+    }
 
-        Ok(found_images)
+    pub async fn set_moderation_status(
+        id: ImageId,
+        status: ImageModerationStatus,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE uploaded_images
+            SET moderation_status = $2
+            WHERE id = $1
+            ",
+            id as ImageId,
+            status.as_str(),
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
     }
 
     pub async fn clear_cache(id: ImageId, redis: &RedisPool) -> Result<(), DatabaseError> {
         let mut redis = redis.connect().await?;
 
         redis.delete(IMAGES_NAMESPACE, id.0).await?;
+        redis
+            .delete_missing(IMAGES_NAMESPACE, None, id.0, None)
+            .await?;
         Ok(())
     }
 }
+
+impl CachedEntry for Image {
+    fn cache_id(&self) -> i64 {
+        self.id.0
+    }
+}
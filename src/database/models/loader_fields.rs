@@ -3,7 +3,7 @@ use std::hash::Hasher;
 
 use super::ids::*;
 use super::DatabaseError;
-use crate::database::redis::RedisPool;
+use crate::database::redis::{CachedEntry, RedisPool};
 use chrono::DateTime;
 use chrono::Utc;
 use futures::TryStreamExt;
@@ -190,6 +190,19 @@ pub struct LoaderField {
     pub max_val: Option<i32>,
 }
 
+// The cache entry holding the fields of a loader
+#[derive(Serialize, Deserialize)]
+struct CachedLoaderFields {
+    loader_id: LoaderId,
+    fields: Vec<LoaderField>,
+}
+
+impl CachedEntry for CachedLoaderFields {
+    fn cache_id(&self) -> i64 {
+        self.loader_id.0 as i64
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum LoaderFieldType {
     Integer,
@@ -263,6 +276,19 @@ pub struct LoaderFieldEnumValue {
     pub metadata: serde_json::Value,
 }
 
+// An enum's values are cached in one entry per enum
+#[derive(Serialize, Deserialize)]
+struct CachedEnumValues {
+    enum_id: LoaderFieldEnumId,
+    values: Vec<LoaderFieldEnumValue>,
+}
+
+impl CachedEntry for CachedEnumValues {
+    fn cache_id(&self) -> i64 {
+        self.enum_id.0 as i64
+    }
+}
+
 impl std::hash::Hash for LoaderFieldEnumValue {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
@@ -380,75 +406,61 @@ impl LoaderField {
     where
         E: sqlx::Executor<'a, Database = sqlx::Postgres>,
     {
-        type RedisLoaderFieldTuple = (LoaderId, Vec<LoaderField>);
-
-        let mut redis = redis.connect().await?;
-
-        let mut loader_ids = loader_ids.to_vec();
-        let cached_fields: Vec<RedisLoaderFieldTuple> = redis
-            .multi_get::<String>(LOADER_FIELDS_NAMESPACE, loader_ids.iter().map(|x| x.0))
-            .await?
-            .into_iter()
-            .flatten()
-            .filter_map(|x: String| serde_json::from_str::<RedisLoaderFieldTuple>(&x).ok())
-            .collect();
-
-        let mut found_loader_fields = HashMap::new();
-        if !cached_fields.is_empty() {
-            for (loader_id, fields) in cached_fields {
-                if loader_ids.contains(&loader_id) {
-                    found_loader_fields.insert(loader_id, fields);
-                    loader_ids.retain(|x| x != &loader_id);
-                }
-            }
-        }
+        let cached_fields: Vec<CachedLoaderFields> = redis
+            .get_cached_keys(
+                LOADER_FIELDS_NAMESPACE,
+                None,
+                &loader_ids.iter().map(|x| x.0.to_string()).collect::<Vec<_>>(),
+                |x| x.parse().ok(),
+                |remaining_ids| async move {
+                    let result = sqlx::query!(
+                        "
+                        SELECT DISTINCT lf.id, lf.field, lf.field_type, lf.optional, lf.min_val, lf.max_val, lf.enum_type, lfl.loader_id
+                        FROM loader_fields lf
+                        LEFT JOIN loader_fields_loaders lfl ON lfl.loader_field_id = lf.id
+                        WHERE lfl.loader_id = ANY($1)
+                        ",
+                        &remaining_ids
+                            .iter()
+                            .flat_map(|x| x.parse().ok())
+                            .collect::<Vec<i32>>()
+                    )
+                    .fetch_many(exec)
+                    .try_filter_map(|e| async {
+                        Ok(e.right().and_then(|r| {
+                            Some((LoaderId(r.loader_id) ,LoaderField {
+                                id: LoaderFieldId(r.id),
+                                field_type: LoaderFieldType::build(&r.field_type, r.enum_type)?,
+                                field: r.field,
+                                optional: r.optional,
+                                min_val: r.min_val,
+                                max_val: r.max_val,
+                            }))
+                        }))
+                    })
+                    .try_collect::<Vec<(LoaderId, LoaderField)>>()
+                    .await?;
 
-        if !loader_ids.is_empty() {
-            let result = sqlx::query!(
-                "
-                SELECT DISTINCT lf.id, lf.field, lf.field_type, lf.optional, lf.min_val, lf.max_val, lf.enum_type, lfl.loader_id
-                FROM loader_fields lf
-                LEFT JOIN loader_fields_loaders lfl ON lfl.loader_field_id = lf.id
-                WHERE lfl.loader_id = ANY($1)
-                ",
-                &loader_ids.iter().map(|x| x.0).collect::<Vec<_>>()
+                    Ok(result
+                        .into_iter()
+                        .fold(
+                            HashMap::new(),
+                            |mut acc: HashMap<LoaderId, Vec<LoaderField>>, x| {
+                                acc.entry(x.0).or_default().push(x.1);
+                                acc
+                            },
+                        )
+                        .into_iter()
+                        .map(|(loader_id, fields)| CachedLoaderFields { loader_id, fields })
+                        .collect())
+                },
             )
-            .fetch_many(exec)
-            .try_filter_map(|e| async {
-                Ok(e.right().and_then(|r| {
-                    Some((LoaderId(r.loader_id) ,LoaderField {
-                        id: LoaderFieldId(r.id),
-                        field_type: LoaderFieldType::build(&r.field_type, r.enum_type)?,
-                        field: r.field,
-                        optional: r.optional,
-                        min_val: r.min_val,
-                        max_val: r.max_val,
-                    }))
-                }))
-            })
-            .try_collect::<Vec<(LoaderId, LoaderField)>>()
             .await?;
 
-            let result: Vec<RedisLoaderFieldTuple> = result
-                .into_iter()
-                .fold(
-                    HashMap::new(),
-                    |mut acc: HashMap<LoaderId, Vec<LoaderField>>, x| {
-                        acc.entry(x.0).or_default().push(x.1);
-                        acc
-                    },
-                )
-                .into_iter()
-                .collect_vec();
-
-            for (k, v) in result.into_iter() {
-                redis
-                    .set_serialized_to_json(LOADER_FIELDS_NAMESPACE, k.0, (k, &v), None)
-                    .await?;
-                found_loader_fields.insert(k, v);
-            }
-        }
-        Ok(found_loader_fields)
+        Ok(cached_fields
+            .into_iter()
+            .map(|x| (x.loader_id, x.fields))
+            .collect())
     }
 
     // Gets all fields for a given loader(s)
@@ -500,6 +512,152 @@ impl LoaderField {
 
         Ok(result)
     }
+
+    pub async fn insert(
+        field: &str,
+        field_type: &LoaderFieldType,
+        optional: bool,
+        min_val: Option<i32>,
+        max_val: Option<i32>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<LoaderFieldId, DatabaseError> {
+        let enum_type = match field_type {
+            LoaderFieldType::Enum(id) | LoaderFieldType::ArrayEnum(id) => Some(id.0),
+            _ => None,
+        };
+
+        let id = sqlx::query!(
+            "
+            INSERT INTO loader_fields (field, field_type, enum_type, optional, min_val, max_val)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
+            ",
+            field,
+            field_type.to_str(),
+            enum_type,
+            optional,
+            min_val,
+            max_val,
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .id;
+
+        Ok(LoaderFieldId(id))
+    }
+
+    pub async fn update(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE loader_fields
+            SET optional = $2, min_val = $3, max_val = $4
+            WHERE id = $1
+            ",
+            self.id.0,
+            self.optional,
+            self.min_val,
+            self.max_val,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    // Replaces the loaders the field can be set on
+    pub async fn set_loaders(
+        id: LoaderFieldId,
+        loader_ids: &[LoaderId],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            DELETE FROM loader_fields_loaders
+            WHERE loader_field_id = $1
+            ",
+            id.0,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        sqlx::query!(
+            "
+            INSERT INTO loader_fields_loaders (loader_id, loader_field_id)
+            SELECT loader_id, $2::integer FROM UNNEST($1::integer[]) AS l(loader_id)
+            ",
+            &loader_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+            id.0,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Whether any version has a value for the field
+    pub async fn is_used<'a, E>(id: LoaderFieldId, exec: E) -> Result<bool, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let used = sqlx::query!(
+            "
+            SELECT EXISTS(SELECT 1 FROM version_fields WHERE field_id = $1)
+            ",
+            id.0,
+        )
+        .fetch_one(exec)
+        .await?
+        .exists;
+
+        Ok(used.unwrap_or(false))
+    }
+
+    pub async fn remove(
+        id: LoaderFieldId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        Self::set_loaders(id, &[], transaction).await?;
+
+        sqlx::query!(
+            "
+            DELETE FROM loader_fields
+            WHERE id = $1
+            ",
+            id.0,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Clears the cached fields of every loader, as well as the list of all fields
+    pub async fn clear_cache(
+        loader_ids: &[LoaderId],
+        redis: &RedisPool,
+    ) -> Result<(), DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        redis
+            .delete_many(
+                loader_ids
+                    .iter()
+                    .map(|x| (LOADER_FIELDS_NAMESPACE, Some(x.0.to_string())))
+                    .chain([(LOADER_FIELDS_NAMESPACE_ALL, Some(String::new()))]),
+            )
+            .await?;
+        // Loaders without fields are cached as missing
+        for loader_id in loader_ids {
+            redis
+                .delete_missing(LOADER_FIELDS_NAMESPACE, None, loader_id.0 as i64, None)
+                .await?;
+        }
+
+        Ok(())
+    }
 }
 impl LoaderFieldEnum {
     pub async fn get<'a, E>(
@@ -543,6 +701,34 @@ impl LoaderFieldEnum {
 
         Ok(result)
     }
+
+    pub async fn insert(
+        enum_name: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<LoaderFieldEnumId, DatabaseError> {
+        let id = sqlx::query!(
+            "
+            INSERT INTO loader_field_enums (enum_name)
+            VALUES ($1)
+            RETURNING id
+            ",
+            enum_name,
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .id;
+
+        Ok(LoaderFieldEnumId(id))
+    }
+
+    pub async fn clear_cache(enum_name: &str, redis: &RedisPool) -> Result<(), DatabaseError> {
+        let mut redis = redis.connect().await?;
+        redis
+            .delete(LOADER_FIELD_ENUMS_ID_NAMESPACE, enum_name)
+            .await?;
+
+        Ok(())
+    }
 }
 
 impl LoaderFieldEnumValue {
@@ -601,67 +787,59 @@ impl LoaderFieldEnumValue {
     where
         E: sqlx::Executor<'a, Database = sqlx::Postgres>,
     {
-        let mut redis = redis.connect().await?;
-        let mut found_enums = Vec::new();
-        let mut remaining_enums: Vec<LoaderFieldEnumId> = loader_field_enum_ids.to_vec();
-
-        if !remaining_enums.is_empty() {
-            let enums = redis
-                .multi_get::<String>(
-                    LOADER_FIELD_ENUM_VALUES_NAMESPACE,
-                    loader_field_enum_ids.iter().map(|x| x.0),
-                )
-                .await?;
-
-            for lfe in enums {
-                if let Some(lfe) = lfe.and_then(|x| {
-                    serde_json::from_str::<(LoaderFieldEnumId, Vec<LoaderFieldEnumValue>)>(&x).ok()
-                }) {
-                    remaining_enums.retain(|x| lfe.0 .0 != x.0);
-                    found_enums.push(lfe.1);
-                    continue;
-                }
-            }
-        }
+        let enum_sets: Vec<CachedEnumValues> = redis
+            .get_cached_keys(
+                LOADER_FIELD_ENUM_VALUES_NAMESPACE,
+                None,
+                &loader_field_enum_ids
+                    .iter()
+                    .map(|x| x.0.to_string())
+                    .collect::<Vec<_>>(),
+                |x| x.parse().ok(),
+                |remaining_ids| async move {
+                    let result = sqlx::query!(
+                        "
+                        SELECT id, enum_id, value, ordering, metadata, created FROM loader_field_enum_values
+                        WHERE enum_id = ANY($1)
+                        ORDER BY enum_id, ordering, created DESC
+                        ",
+                        &remaining_ids
+                            .iter()
+                            .flat_map(|x| x.parse().ok())
+                            .collect::<Vec<i32>>()
+                    )
+                    .fetch_many(exec)
+                    .try_filter_map(|e| async {
+                        Ok(e.right().map(|c| LoaderFieldEnumValue {
+                            id: LoaderFieldEnumValueId(c.id),
+                            enum_id: LoaderFieldEnumId(c.enum_id),
+                            value: c.value,
+                            ordering: c.ordering,
+                            created: c.created,
+                            metadata: c.metadata.unwrap_or_default(),
+                        }))
+                    })
+                    .try_collect::<Vec<LoaderFieldEnumValue>>()
+                    .await?;
 
-        let remaining_enums = remaining_enums.iter().map(|x| x.0).collect::<Vec<_>>();
-        let result = sqlx::query!(
-            "
-            SELECT id, enum_id, value, ordering, metadata, created FROM loader_field_enum_values
-            WHERE enum_id = ANY($1)
-            ORDER BY enum_id, ordering, created DESC
-            ",
-            &remaining_enums
-        )
-        .fetch_many(exec)
-        .try_filter_map(|e| async {
-            Ok(e.right().map(|c| LoaderFieldEnumValue {
-                id: LoaderFieldEnumValueId(c.id),
-                enum_id: LoaderFieldEnumId(c.enum_id),
-                value: c.value,
-                ordering: c.ordering,
-                created: c.created,
-                metadata: c.metadata.unwrap_or_default(),
-            }))
-        })
-        .try_collect::<Vec<LoaderFieldEnumValue>>()
-        .await?;
+                    // The values are sorted by enum_id, so the values of each enum are grouped together
+                    Ok(result
+                        .into_iter()
+                        .group_by(|x| x.enum_id)
+                        .into_iter()
+                        .map(|(enum_id, values)| CachedEnumValues {
+                            enum_id,
+                            values: values.collect(),
+                        })
+                        .collect())
+                },
+            )
+            .await?;
 
-        // Convert from an Vec<LoaderFieldEnumValue> to a Vec<(LoaderFieldEnumId, Vec<LoaderFieldEnumValue>)>
-        let cachable_enum_sets: Vec<(LoaderFieldEnumId, Vec<LoaderFieldEnumValue>)> = result
-            .clone()
-            .into_iter()
-            .group_by(|x| x.enum_id) // we sort by enum_id, so this will group all values of the same enum_id together
+        Ok(enum_sets
             .into_iter()
-            .map(|(k, v)| (k, v.collect::<Vec<_>>().to_vec()))
-            .collect();
-        for (k, v) in cachable_enum_sets.iter() {
-            redis
-                .set_serialized_to_json(LOADER_FIELD_ENUM_VALUES_NAMESPACE, k.0, v, None)
-                .await?;
-        }
-
-        Ok(cachable_enum_sets)
+            .map(|x| (x.enum_id, x.values))
+            .collect())
     }
 
     // Matches filter against metadata of enum values
@@ -692,6 +870,92 @@ impl LoaderFieldEnumValue {
 
         Ok(result)
     }
+
+    pub async fn insert(
+        enum_id: LoaderFieldEnumId,
+        value: &str,
+        ordering: Option<i32>,
+        metadata: Option<&serde_json::Value>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<LoaderFieldEnumValue, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            INSERT INTO loader_field_enum_values (enum_id, value, ordering, metadata)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, created
+            ",
+            enum_id.0,
+            value,
+            ordering,
+            metadata,
+        )
+        .fetch_one(&mut **transaction)
+        .await?;
+
+        Ok(LoaderFieldEnumValue {
+            id: LoaderFieldEnumValueId(result.id),
+            enum_id,
+            value: value.to_string(),
+            ordering,
+            created: result.created,
+            metadata: metadata.cloned().unwrap_or_default(),
+        })
+    }
+
+    /// Whether any version has the value set for a field
+    pub async fn is_used<'a, E>(id: LoaderFieldEnumValueId, exec: E) -> Result<bool, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let used = sqlx::query!(
+            "
+            SELECT EXISTS(SELECT 1 FROM version_fields WHERE enum_value = $1)
+            ",
+            id.0,
+        )
+        .fetch_one(exec)
+        .await?
+        .exists;
+
+        Ok(used.unwrap_or(false))
+    }
+
+    pub async fn remove(
+        id: LoaderFieldEnumValueId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            DELETE FROM loader_field_enum_values
+            WHERE id = $1
+            ",
+            id.0,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn clear_cache(
+        enum_id: LoaderFieldEnumId,
+        redis: &RedisPool,
+    ) -> Result<(), DatabaseError> {
+        let mut redis = redis.connect().await?;
+        redis
+            .delete(LOADER_FIELD_ENUM_VALUES_NAMESPACE, enum_id.0)
+            .await?;
+        redis
+            .delete_missing(
+                LOADER_FIELD_ENUM_VALUES_NAMESPACE,
+                None,
+                enum_id.0 as i64,
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
 }
 
 impl VersionField {
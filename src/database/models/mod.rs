@@ -1,26 +1,46 @@
 use thiserror::Error;
 
+pub mod audit_log_item;
 pub mod categories;
 pub mod collection_item;
+pub mod collection_share_token_item;
+pub mod daily_download_item;
 pub mod flow_item;
 pub mod ids;
 pub mod image_item;
 pub mod legacy_loader_fields;
 pub mod loader_fields;
+pub mod mod_metadata_item;
+pub mod moderation_review_item;
+pub mod notification_email_item;
 pub mod notification_item;
+pub mod notification_preference_item;
 pub mod oauth_client_authorization_item;
 pub mod oauth_client_item;
 pub mod oauth_token_item;
+pub mod organization_invite_link_item;
 pub mod organization_item;
+pub mod organization_payout_split_item;
+pub mod organization_role_template_item;
 pub mod pat_item;
 pub mod payout_item;
+pub mod pending_upload_item;
+pub mod project_block_item;
+pub mod project_field_history_item;
 pub mod project_item;
+pub mod project_transfer_item;
 pub mod report_item;
+pub mod reserved_names;
 pub mod session_item;
 pub mod team_item;
 pub mod thread_item;
+pub mod user_block_item;
+pub mod user_connection_item;
+pub mod user_export_item;
 pub mod user_item;
 pub mod version_item;
+pub mod webhook_item;
+pub mod wiki_page_item;
 
 pub use collection_item::Collection;
 pub use ids::*;
@@ -48,4 +68,6 @@ pub enum DatabaseError {
     SerdeCacheError(#[from] serde_json::Error),
     #[error("Schema error: {0}")]
     SchemaError(String),
+    #[error("Encryption error: {0}")]
+    Encryption(#[from] crate::util::encryption::EncryptionError),
 }
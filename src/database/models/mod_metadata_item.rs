@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use super::{DatabaseError, ProjectId};
+use crate::models::projects::ProjectStatus;
+
+/// The mod ids a project's files declare in their loader metadata, which let the mod ids other
+/// files depend on be resolved to projects
+pub struct ModMetadataIds;
+
+impl ModMetadataIds {
+    /// Records that the project's files declare the mod ids
+    pub async fn register(
+        project_id: ProjectId,
+        metadata_ids: &[String],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        if metadata_ids.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query!(
+            "
+            INSERT INTO mod_metadata_ids (metadata_id, mod_id)
+            SELECT metadata_id, $2 FROM UNNEST($1::varchar[]) metadata_id
+            ON CONFLICT DO NOTHING
+            ",
+            metadata_ids,
+            project_id as ProjectId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Resolves mod ids to the public projects which declare them, leaving out the project
+    /// asking. When several projects declare the same id, the most downloaded one is picked.
+    pub async fn resolve<'a, E>(
+        metadata_ids: &[String],
+        excluded_project: ProjectId,
+        exec: E,
+    ) -> Result<HashMap<String, ProjectId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        if metadata_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let statuses = ProjectStatus::iterator()
+            .filter(|x| x.is_searchable())
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+
+        let resolved = sqlx::query!(
+            "
+            SELECT DISTINCT ON (mmi.metadata_id) mmi.metadata_id, mmi.mod_id
+            FROM mod_metadata_ids mmi
+            INNER JOIN mods m ON m.id = mmi.mod_id
+            WHERE mmi.metadata_id = ANY($1) AND mmi.mod_id != $2 AND m.status = ANY($3)
+            ORDER BY mmi.metadata_id, m.downloads DESC
+            ",
+            metadata_ids,
+            excluded_project as ProjectId,
+            &statuses,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| (x.metadata_id, ProjectId(x.mod_id)))
+        .collect();
+
+        Ok(resolved)
+    }
+}
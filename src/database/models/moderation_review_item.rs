@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::{DatabaseError, ProjectId, UserId};
+use crate::models::moderation::{ChecklistEntry, ChecklistItem};
+use crate::models::projects::ProjectStatus;
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ModerationReview {
+    pub project_id: ProjectId,
+    pub moderator_id: UserId,
+    pub items: BTreeMap<ChecklistItem, ChecklistEntry>,
+    pub created: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+    pub decided: Option<DateTime<Utc>>,
+    pub decision: Option<ProjectStatus>,
+}
+
+impl ModerationReview {
+    /// Saves the items of the project's review in progress, starting a new review if there
+    /// isn't one. The moderator who last saved the review is recorded as its moderator.
+    pub async fn upsert(
+        project_id: ProjectId,
+        moderator_id: UserId,
+        items: &BTreeMap<ChecklistItem, ChecklistEntry>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO moderation_reviews (mod_id, moderator_id, items)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (mod_id) WHERE decided IS NULL DO UPDATE
+            SET moderator_id = EXCLUDED.moderator_id, items = EXCLUDED.items, updated = NOW()
+            ",
+            project_id as ProjectId,
+            moderator_id as UserId,
+            serde_json::to_value(items)?,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the project's newest review, whether it's in progress or not
+    pub async fn get_latest<'a, E>(
+        project_id: ProjectId,
+        exec: E,
+    ) -> Result<Option<ModerationReview>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT mod_id, moderator_id, items, created, updated, decided, decision
+            FROM moderation_reviews
+            WHERE mod_id = $1
+            ORDER BY created DESC
+            LIMIT 1
+            ",
+            project_id as ProjectId,
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.map(|r| ModerationReview {
+            project_id: ProjectId(r.mod_id),
+            moderator_id: UserId(r.moderator_id),
+            items: serde_json::from_value(r.items).unwrap_or_default(),
+            created: r.created,
+            updated: r.updated,
+            decided: r.decided,
+            decision: r.decision.as_deref().map(ProjectStatus::from_string),
+        }))
+    }
+
+    /// Ends the project's review in progress with the status the project was given, returning
+    /// the review if there was one
+    pub async fn decide(
+        project_id: ProjectId,
+        decision: ProjectStatus,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<ModerationReview>, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            UPDATE moderation_reviews
+            SET decided = NOW(), decision = $2
+            WHERE mod_id = $1 AND decided IS NULL
+            RETURNING mod_id, moderator_id, items, created, updated, decided, decision
+            ",
+            project_id as ProjectId,
+            decision.as_str(),
+        )
+        .fetch_optional(&mut **transaction)
+        .await?;
+
+        Ok(result.map(|r| ModerationReview {
+            project_id: ProjectId(r.mod_id),
+            moderator_id: UserId(r.moderator_id),
+            items: serde_json::from_value(r.items).unwrap_or_default(),
+            created: r.created,
+            updated: r.updated,
+            decided: r.decided,
+            decision: r.decision.as_deref().map(ProjectStatus::from_string),
+        }))
+    }
+}
@@ -0,0 +1,128 @@
+use super::ids::*;
+use crate::database::models::DatabaseError;
+use crate::models::notifications::NotificationBody;
+use itertools::Itertools;
+
+/// An email waiting to be sent for a notification. Rows are removed once the email is sent,
+/// and kept with their last error once every attempt has failed.
+pub struct NotificationEmail {
+    pub id: i64,
+    pub notification_id: NotificationId,
+    pub user_id: UserId,
+    pub email: String,
+    pub body: NotificationBody,
+    pub attempts: i32,
+}
+
+impl NotificationEmail {
+    pub async fn insert_many(
+        recipients: &[(NotificationId, UserId, String)],
+        body: &NotificationBody,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        let (notification_ids, user_ids, emails): (Vec<_>, Vec<_>, Vec<_>) = recipients
+            .iter()
+            .map(|(notification_id, user_id, email)| (notification_id.0, user_id.0, email.clone()))
+            .multiunzip();
+
+        sqlx::query!(
+            "
+            INSERT INTO notification_emails (notification_id, user_id, email, body)
+            SELECT u.notification_id, u.user_id, u.email, $4
+            FROM UNNEST($1::bigint[], $2::bigint[], $3::varchar[])
+                AS u(notification_id, user_id, email)
+            ",
+            &notification_ids[..],
+            &user_ids[..],
+            &emails[..],
+            serde_json::value::to_value(body)?,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Claims the oldest emails which haven't used up their attempts, so that concurrent
+    /// senders skip them. Claims are only held for `claim_timeout_minutes`, after which emails
+    /// are sent again in case their sender stopped before finishing them.
+    pub async fn claim_pending<'a, E>(
+        max_attempts: i32,
+        limit: i64,
+        claim_timeout_minutes: i32,
+        exec: E,
+    ) -> Result<Vec<NotificationEmail>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let emails = sqlx::query!(
+            "
+            UPDATE notification_emails
+            SET sending_at = NOW()
+            WHERE id IN (
+                SELECT id FROM notification_emails
+                WHERE attempts < $1
+                AND (sending_at IS NULL OR sending_at < NOW() - make_interval(mins => $3))
+                ORDER BY created
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, notification_id, user_id, email, body, attempts
+            ",
+            max_attempts,
+            limit,
+            claim_timeout_minutes,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| NotificationEmail {
+            id: x.id,
+            notification_id: NotificationId(x.notification_id),
+            user_id: UserId(x.user_id),
+            email: x.email,
+            body: serde_json::from_value(x.body).unwrap_or(NotificationBody::Unknown),
+            attempts: x.attempts,
+        })
+        .collect();
+
+        Ok(emails)
+    }
+
+    pub async fn remove<'a, E>(id: i64, exec: E) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        sqlx::query!(
+            "
+            DELETE FROM notification_emails
+            WHERE id = $1
+            ",
+            id,
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records a failed attempt, releasing the email's claim so it's retried on the next run
+    pub async fn record_failure<'a, E>(id: i64, error: &str, exec: E) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        sqlx::query!(
+            "
+            UPDATE notification_emails
+            SET attempts = attempts + 1, last_error = $2, sending_at = NULL
+            WHERE id = $1
+            ",
+            id,
+            error,
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,12 +1,17 @@
 use super::ids::*;
+use super::notification_email_item::NotificationEmail;
+use super::notification_preference_item::NotificationPreference;
+use super::user_block_item::UserBlock;
 use crate::database::{models::DatabaseError, redis::RedisPool};
-use crate::models::notifications::NotificationBody;
+use crate::models::notifications::{NotificationBody, NotificationType};
 use chrono::{DateTime, Utc};
 use futures::TryStreamExt;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 const USER_NOTIFICATIONS_NAMESPACE: &str = "user_notifications";
+const NOTIFICATION_INSERT_CHUNK_SIZE: usize = 1000;
 
 pub struct NotificationBuilder {
     pub body: NotificationBody,
@@ -42,24 +47,93 @@ impl NotificationBuilder {
 
     pub async fn insert_many(
         &self,
-        users: Vec<UserId>,
+        mut users: Vec<UserId>,
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         redis: &RedisPool,
     ) -> Result<(), DatabaseError> {
-        let mut notifications = Vec::new();
-        for user in users {
-            let id = generate_notification_id(&mut *transaction).await?;
-
-            notifications.push(Notification {
-                id,
-                user_id: user,
-                body: self.body.clone(),
-                read: false,
-                created: Utc::now(),
-            });
+        if let Some(originator) = self.body.originator() {
+            let blockers =
+                UserBlock::get_blockers(&users, originator.into(), &mut **transaction).await?;
+            users.retain(|x| !blockers.contains(x));
+        }
+
+        let mut in_app_users = users.clone();
+        let mut email_recipients = Vec::new();
+        if let Some(notification_type) = self.body.notification_type() {
+            let suppressed_users = NotificationPreference::get_suppressed_users(
+                &users,
+                notification_type,
+                &mut **transaction,
+            )
+            .await?;
+            in_app_users.retain(|x| !suppressed_users.contains(x));
+
+            email_recipients = NotificationPreference::get_email_recipients(
+                &users,
+                notification_type,
+                &mut **transaction,
+            )
+            .await?;
+        }
+
+        // Each user gets one id, shared by the in-app notification and the email
+        let users = users
+            .into_iter()
+            .filter(|x| in_app_users.contains(x) || email_recipients.iter().any(|(y, _)| y == x))
+            .unique()
+            .collect_vec();
+
+        if users.is_empty() {
+            return Ok(());
+        }
+
+        let ids = generate_many_notification_ids(users.len(), &mut *transaction).await?;
+        let user_ids = users.into_iter().zip(ids).collect::<HashMap<_, _>>();
+
+        let notifications = in_app_users
+            .into_iter()
+            .unique()
+            .filter_map(|user| {
+                Some(Notification {
+                    id: *user_ids.get(&user)?,
+                    user_id: user,
+                    body: self.body.clone(),
+                    read: false,
+                    created: Utc::now(),
+                })
+            })
+            .collect_vec();
+
+        if !notifications.is_empty() {
+            Notification::insert_many(&notifications, transaction, redis).await?;
+        }
+
+        let emails = email_recipients
+            .into_iter()
+            .filter_map(|(user, email)| Some((*user_ids.get(&user)?, user, email)))
+            .collect_vec();
+
+        if !emails.is_empty() {
+            NotificationEmail::insert_many(&emails, &self.body, transaction).await?;
         }
 
-        Notification::insert_many(&notifications, transaction, redis).await?;
+        Ok(())
+    }
+
+    /// Inserts the notification for a potentially large set of users, committing each chunk
+    /// in its own transaction so a single fan-out never holds one long-running transaction.
+    pub async fn insert_many_chunked(
+        &self,
+        users: Vec<UserId>,
+        pool: &sqlx::PgPool,
+        redis: &RedisPool,
+    ) -> Result<(), DatabaseError> {
+        for chunk in users.chunks(NOTIFICATION_INSERT_CHUNK_SIZE) {
+            let mut transaction = pool.begin().await?;
+            self.insert_many(chunk.to_vec(), &mut transaction, redis)
+                .await?;
+            transaction.commit().await?;
+        }
 
         Ok(())
     }
@@ -317,6 +391,64 @@ impl Notification {
         Ok(Some(()))
     }
 
+    /// Marks all of the user's notifications matching the filters as read, returning how many were changed.
+    /// The user's notification cache must be cleared after the transaction is committed.
+    pub async fn read_all_user(
+        user_id: UserId,
+        notification_type: Option<NotificationType>,
+        before: Option<DateTime<Utc>>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<u64, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            UPDATE notifications
+            SET read = TRUE
+            WHERE user_id = $1 AND read = FALSE
+            AND ($2::varchar IS NULL OR COALESCE(body->>'type', type) = $2)
+            AND ($3::timestamptz IS NULL OR created < $3)
+            ",
+            user_id as UserId,
+            notification_type.map(|x| x.as_str()),
+            before,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes all of the user's notifications matching the filters, returning how many were deleted.
+    /// The user's notification cache must be cleared after the transaction is committed.
+    pub async fn remove_all_user(
+        user_id: UserId,
+        notification_type: Option<NotificationType>,
+        before: Option<DateTime<Utc>>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<u64, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            WITH deleted AS (
+                DELETE FROM notifications
+                WHERE user_id = $1
+                AND ($2::varchar IS NULL OR COALESCE(body->>'type', type) = $2)
+                AND ($3::timestamptz IS NULL OR created < $3)
+                RETURNING id
+            ), deleted_actions AS (
+                DELETE FROM notifications_actions
+                WHERE notification_id IN (SELECT id FROM deleted)
+            )
+            SELECT COUNT(*) count FROM deleted
+            ",
+            user_id as UserId,
+            notification_type.map(|x| x.as_str()),
+            before,
+        )
+        .fetch_one(&mut **transaction)
+        .await?;
+
+        Ok(result.count.unwrap_or(0) as u64)
+    }
+
     pub async fn clear_user_notifications_cache(
         user_ids: impl IntoIterator<Item = &UserId>,
         redis: &RedisPool,
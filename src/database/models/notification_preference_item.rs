@@ -0,0 +1,143 @@
+use super::ids::*;
+use crate::database::models::DatabaseError;
+use crate::models::notifications::NotificationType;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// A user's delivery setting for a notification type. Types without a stored
+/// preference are delivered, so new kinds of notifications are never silently dropped.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NotificationPreference {
+    pub user_id: UserId,
+    pub notification_type: String,
+    pub in_app: bool,
+    /// `None` uses the type's default, see `NotificationType::sends_email`
+    pub send_email: Option<bool>,
+}
+
+impl NotificationPreference {
+    pub async fn get_user<'a, E>(
+        user_id: UserId,
+        exec: E,
+    ) -> Result<Vec<NotificationPreference>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let preferences = sqlx::query!(
+            "
+            SELECT user_id, notification_type, in_app, send_email
+            FROM notification_preferences
+            WHERE user_id = $1
+            ",
+            user_id as UserId,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| NotificationPreference {
+            user_id: UserId(x.user_id),
+            notification_type: x.notification_type,
+            in_app: x.in_app,
+            send_email: x.send_email,
+        })
+        .collect();
+
+        Ok(preferences)
+    }
+
+    pub async fn upsert_many(
+        preferences: &[NotificationPreference],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        let (user_ids, notification_types, in_apps, send_emails): (Vec<_>, Vec<_>, Vec<_>, Vec<_>) =
+            preferences
+                .iter()
+                .map(|x| {
+                    (
+                        x.user_id.0,
+                        x.notification_type.clone(),
+                        x.in_app,
+                        x.send_email,
+                    )
+                })
+                .multiunzip();
+
+        sqlx::query!(
+            "
+            INSERT INTO notification_preferences (user_id, notification_type, in_app, send_email)
+            SELECT * FROM UNNEST($1::bigint[], $2::varchar[], $3::boolean[], $4::boolean[])
+            ON CONFLICT (user_id, notification_type)
+            DO UPDATE SET in_app = EXCLUDED.in_app, send_email = EXCLUDED.send_email
+            ",
+            &user_ids[..],
+            &notification_types[..],
+            &in_apps[..],
+            &send_emails[..],
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns which of the given users have turned off in-app delivery of this notification type
+    pub async fn get_suppressed_users<'a, E>(
+        user_ids: &[UserId],
+        notification_type: NotificationType,
+        exec: E,
+    ) -> Result<Vec<UserId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let users = sqlx::query!(
+            "
+            SELECT user_id
+            FROM notification_preferences
+            WHERE user_id = ANY($1) AND notification_type = $2 AND in_app = FALSE
+            ",
+            &user_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+            notification_type.as_str(),
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| UserId(x.user_id))
+        .collect();
+
+        Ok(users)
+    }
+
+    /// Returns the verified emails of the given users who receive this notification type by email
+    pub async fn get_email_recipients<'a, E>(
+        user_ids: &[UserId],
+        notification_type: NotificationType,
+        exec: E,
+    ) -> Result<Vec<(UserId, String)>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        if !notification_type.sends_email() {
+            return Ok(Vec::new());
+        }
+
+        let recipients = sqlx::query!(
+            "
+            SELECT u.id, u.email
+            FROM users u
+            LEFT JOIN notification_preferences np
+                ON np.user_id = u.id AND np.notification_type = $2
+            WHERE u.id = ANY($1) AND u.email_verified = TRUE AND u.email IS NOT NULL
+                AND COALESCE(np.send_email, TRUE)
+            ",
+            &user_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+            notification_type.as_str(),
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .filter_map(|x| Some((UserId(x.id), x.email?)))
+        .collect();
+
+        Ok(recipients)
+    }
+}
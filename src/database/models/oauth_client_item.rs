@@ -3,7 +3,7 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
 
-use super::{DatabaseError, OAuthClientId, OAuthRedirectUriId, UserId};
+use super::{DatabaseError, OAuthClientId, OAuthRedirectUriId, OrganizationId, UserId};
 use crate::models::pats::Scopes;
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -23,6 +23,7 @@ pub struct OAuthClient {
     pub redirect_uris: Vec<OAuthRedirectUri>,
     pub created: DateTime<Utc>,
     pub created_by: UserId,
+    pub organization_id: Option<OrganizationId>,
     pub url: Option<String>,
     pub description: Option<String>,
 }
@@ -35,6 +36,7 @@ struct ClientQueryResult {
     secret_hash: String,
     created: DateTime<Utc>,
     created_by: i64,
+    organization_id: Option<i64>,
     url: Option<String>,
     description: Option<String>,
     uri_ids: Option<Vec<i64>>,
@@ -57,6 +59,7 @@ macro_rules! select_clients_with_predicate {
                 clients.secret_hash as "secret_hash!",
                 clients.created as "created!",
                 clients.created_by as "created_by!",
+                clients.organization_id as "organization_id?",
                 clients.url as "url?",
                 clients.description as "description?",
                 uris.uri_ids as "uri_ids?",
@@ -101,9 +104,25 @@ impl OAuthClient {
         exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
     ) -> Result<Vec<OAuthClient>, DatabaseError> {
         let user_id_param = user_id.0;
-        let clients = select_clients_with_predicate!("WHERE created_by = $1", user_id_param)
-            .fetch_all(exec)
-            .await?;
+        let clients = select_clients_with_predicate!(
+            "WHERE created_by = $1 AND organization_id IS NULL",
+            user_id_param
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(clients.into_iter().map(|r| r.into()).collect())
+    }
+
+    pub async fn get_all_organization_clients(
+        organization_id: OrganizationId,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<Vec<OAuthClient>, DatabaseError> {
+        let organization_id_param = organization_id.0;
+        let clients =
+            select_clients_with_predicate!("WHERE organization_id = $1", organization_id_param)
+                .fetch_all(exec)
+                .await?;
 
         Ok(clients.into_iter().map(|r| r.into()).collect())
     }
@@ -133,10 +152,10 @@ impl OAuthClient {
         sqlx::query!(
             "
             INSERT INTO oauth_clients (
-                id, name, icon_url, max_scopes, secret_hash, created_by
+                id, name, icon_url, max_scopes, secret_hash, created_by, organization_id
             )
             VALUES (
-                $1, $2, $3, $4, $5, $6
+                $1, $2, $3, $4, $5, $6, $7
             )
             ",
             self.id.0,
@@ -144,7 +163,8 @@ impl OAuthClient {
             self.icon_url,
             self.max_scopes.to_postgres(),
             self.secret_hash,
-            self.created_by.0
+            self.created_by.0,
+            self.organization_id.map(|x| x.0)
         )
         .execute(&mut **transaction)
         .await?;
@@ -248,6 +268,7 @@ impl From<ClientQueryResult> for OAuthClient {
             redirect_uris: redirects,
             created: r.created,
             created_by: UserId(r.created_by),
+            organization_id: r.organization_id.map(OrganizationId),
             url: r.url,
             description: r.description,
         }
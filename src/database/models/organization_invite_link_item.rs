@@ -0,0 +1,189 @@
+use chrono::{DateTime, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{DatabaseError, OrganizationId, OrganizationInviteLinkId, UserId};
+use crate::models::teams::OrganizationPermissions;
+
+/// A link anyone can use to join an organization, until it expires or runs out of uses
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct OrganizationInviteLink {
+    pub id: OrganizationInviteLinkId,
+    pub organization_id: OrganizationId,
+    pub identifier: String,
+    pub role: String,
+    pub organization_permissions: OrganizationPermissions,
+    pub created_by: UserId,
+    pub created: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+}
+
+impl OrganizationInviteLink {
+    pub fn generate_identifier() -> String {
+        ChaCha20Rng::from_entropy()
+            .sample_iter(&Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect::<String>()
+    }
+
+    /// Whether the link can no longer be used to join the organization
+    pub fn is_exhausted(&self) -> bool {
+        self.expires <= Utc::now() || self.max_uses.map(|x| self.uses >= x).unwrap_or(false)
+    }
+
+    pub async fn insert(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO organization_invite_links (
+                id, organization_id, identifier, role, organization_permissions,
+                created_by, created, expires, max_uses, uses
+            )
+            VALUES (
+                $1, $2, $3, $4, $5,
+                $6, $7, $8, $9, $10
+            )
+            ",
+            self.id.0,
+            self.organization_id.0,
+            self.identifier,
+            self.role,
+            self.organization_permissions.bits() as i64,
+            self.created_by.0,
+            self.created,
+            self.expires,
+            self.max_uses,
+            self.uses,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get<'a, E>(
+        organization_id: OrganizationId,
+        identifier: &str,
+        exec: E,
+    ) -> Result<Option<OrganizationInviteLink>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT id, organization_id, identifier, role, organization_permissions,
+            created_by, created, expires, max_uses, uses
+            FROM organization_invite_links
+            WHERE organization_id = $1 AND identifier = $2
+            ",
+            organization_id.0,
+            identifier,
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.map(|r| OrganizationInviteLink {
+            id: OrganizationInviteLinkId(r.id),
+            organization_id: OrganizationId(r.organization_id),
+            identifier: r.identifier,
+            role: r.role,
+            organization_permissions: OrganizationPermissions::from_bits(
+                r.organization_permissions as u64,
+            )
+            .unwrap_or_default(),
+            created_by: UserId(r.created_by),
+            created: r.created,
+            expires: r.expires,
+            max_uses: r.max_uses,
+            uses: r.uses,
+        }))
+    }
+
+    /// Lists the links of an organization, including expired and used up ones, newest first
+    pub async fn get_organization<'a, E>(
+        organization_id: OrganizationId,
+        exec: E,
+    ) -> Result<Vec<OrganizationInviteLink>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let results = sqlx::query!(
+            "
+            SELECT id, organization_id, identifier, role, organization_permissions,
+            created_by, created, expires, max_uses, uses
+            FROM organization_invite_links
+            WHERE organization_id = $1
+            ORDER BY created DESC
+            ",
+            organization_id.0,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| OrganizationInviteLink {
+                id: OrganizationInviteLinkId(r.id),
+                organization_id: OrganizationId(r.organization_id),
+                identifier: r.identifier,
+                role: r.role,
+                organization_permissions: OrganizationPermissions::from_bits(
+                    r.organization_permissions as u64,
+                )
+                .unwrap_or_default(),
+                created_by: UserId(r.created_by),
+                created: r.created,
+                expires: r.expires,
+                max_uses: r.max_uses,
+                uses: r.uses,
+            })
+            .collect())
+    }
+
+    /// Counts a use of the link, unless it has expired or run out of uses in the meantime.
+    /// Returns whether the use was counted.
+    pub async fn use_link(
+        id: OrganizationInviteLinkId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            UPDATE organization_invite_links
+            SET uses = uses + 1
+            WHERE id = $1 AND expires > NOW() AND (max_uses IS NULL OR uses < max_uses)
+            ",
+            id.0,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn remove(
+        organization_id: OrganizationId,
+        id: OrganizationInviteLinkId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            DELETE FROM organization_invite_links
+            WHERE organization_id = $1 AND id = $2
+            ",
+            organization_id.0,
+            id.0,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
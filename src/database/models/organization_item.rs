@@ -1,13 +1,18 @@
 use crate::{
-    database::redis::RedisPool,
-    models::ids::base62_impl::{parse_base62, to_base62},
+    database::redis::{CachedEntry, RedisPool},
+    models::ids::base62_impl::parse_base62,
+    models::organizations::OrganizationProjectSummary,
+    models::projects::ProjectStatus,
 };
 
+use super::project_item::SLUG_RESERVATION_DAYS;
 use super::{ids::*, TeamMember};
+use chrono::{Duration, Utc};
 use serde::{Deserialize, Serialize};
 
 const ORGANIZATIONS_NAMESPACE: &str = "organizations";
 const ORGANIZATIONS_TITLES_NAMESPACE: &str = "organizations_titles";
+const ORGANIZATIONS_PROJECT_SUMMARIES_NAMESPACE: &str = "organizations_project_summaries";
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 /// An organization of users who together control one or more projects and organizations.
@@ -30,6 +35,19 @@ pub struct Organization {
     /// The display icon for the organization
     pub icon_url: Option<String>,
     pub color: Option<u32>,
+
+    /// Whether members need two-factor authentication
+    #[serde(default)]
+    pub require_2fa: bool,
+}
+
+/// The project summaries of an organization, cached and cleared alongside the organization
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct OrganizationProjectSummaries {
+    /// Only counts the organization's listed projects
+    pub listed: OrganizationProjectSummary,
+    /// Counts all of the organization's projects, for members of the organization
+    pub all: OrganizationProjectSummary,
 }
 
 impl Organization {
@@ -58,15 +76,72 @@ impl Organization {
 
     pub async fn get<'a, E>(
         string: &str,
-        exec: E,
+        executor: E,
         redis: &RedisPool,
     ) -> Result<Option<Self>, super::DatabaseError>
+    where
+        E: sqlx::Acquire<'a, Database = sqlx::Postgres>,
+    {
+        let mut exec = executor.acquire().await?;
+
+        if let Some(organization) = Self::get_many(&[string], &mut *exec, redis)
+            .await?
+            .into_iter()
+            .next()
+        {
+            return Ok(Some(organization));
+        }
+
+        // Slugs given up recently still resolve to the organization that gave them up
+        match Self::get_id_from_old_slug(string, &mut *exec).await? {
+            Some(id) => Self::get_id(id, &mut *exec, redis).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Gets the organization which gave up the given slug, if it did so recently enough that the
+    /// slug still resolves to it
+    pub async fn get_id_from_old_slug<'a, E>(
+        slug: &str,
+        exec: E,
+    ) -> Result<Option<OrganizationId>, super::DatabaseError>
     where
         E: sqlx::Executor<'a, Database = sqlx::Postgres>,
     {
-        Self::get_many(&[string], exec, redis)
-            .await
-            .map(|x| x.into_iter().next())
+        let result = sqlx::query!(
+            "
+            SELECT organization_id FROM organization_slug_history
+            WHERE slug = LOWER($1) AND changed > $2
+            ORDER BY changed DESC
+            LIMIT 1
+            ",
+            slug,
+            Utc::now() - Duration::days(SLUG_RESERVATION_DAYS),
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.map(|r| OrganizationId(r.organization_id)))
+    }
+
+    /// Records that an organization gave up a slug, so that it keeps resolving to the organization
+    pub async fn insert_slug_history(
+        id: OrganizationId,
+        slug: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), super::DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO organization_slug_history (organization_id, slug)
+            VALUES ($1, LOWER($2))
+            ",
+            id as OrganizationId,
+            slug
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
     }
 
     pub async fn get_id<'a, 'b, E>(
@@ -107,118 +182,133 @@ impl Organization {
     {
         use futures::stream::TryStreamExt;
 
+        redis
+            .get_cached_keys(
+                ORGANIZATIONS_NAMESPACE,
+                Some(ORGANIZATIONS_TITLES_NAMESPACE),
+                &organization_strings
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>(),
+                |x| parse_base62(x).ok().map(|x| x as i64),
+                |remaining_strings| async move {
+                    let organization_ids_parsed: Vec<i64> = remaining_strings
+                        .iter()
+                        .flat_map(|x| parse_base62(&x.to_string()).ok())
+                        .map(|x| x as i64)
+                        .collect();
+
+                    let organizations: Vec<Organization> = sqlx::query!(
+                        "
+                        SELECT o.id, o.slug, o.name, o.team_id, o.description, o.icon_url, o.color, o.require_2fa
+                        FROM organizations o
+                        WHERE o.id = ANY($1) OR LOWER(o.slug) = ANY($2)
+                        GROUP BY o.id;
+                        ",
+                        &organization_ids_parsed,
+                        &remaining_strings
+                            .into_iter()
+                            .map(|x| x.to_string().to_lowercase())
+                            .collect::<Vec<_>>(),
+                    )
+                    .fetch_many(exec)
+                    .try_filter_map(|e| async {
+                        Ok(e.right().map(|m| Organization {
+                            id: OrganizationId(m.id),
+                            slug: m.slug,
+                            name: m.name,
+                            team_id: TeamId(m.team_id),
+                            description: m.description,
+                            icon_url: m.icon_url,
+                            color: m.color.map(|x| x as u32),
+                            require_2fa: m.require_2fa,
+                        }))
+                    })
+                    .try_collect::<Vec<Organization>>()
+                    .await?;
+
+                    Ok(organizations)
+                },
+            )
+            .await
+    }
+
+    pub async fn get_project_summaries<'a, E>(
+        id: OrganizationId,
+        exec: E,
+        redis: &RedisPool,
+    ) -> Result<OrganizationProjectSummaries, super::DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
         let mut redis = redis.connect().await?;
 
-        if organization_strings.is_empty() {
-            return Ok(Vec::new());
+        let summaries = redis
+            .get_deserialized_from_json::<OrganizationProjectSummaries>(
+                ORGANIZATIONS_PROJECT_SUMMARIES_NAMESPACE,
+                &id.0.to_string(),
+            )
+            .await?;
+        if let Some(summaries) = summaries {
+            return Ok(summaries);
         }
 
-        let mut found_organizations = Vec::new();
-        let mut remaining_strings = organization_strings
-            .iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<_>>();
+        let projects = sqlx::query!(
+            "
+            SELECT m.status, m.downloads, m.follows, m.updated,
+                ARRAY_AGG(DISTINCT pt.name) filter (where pt.name is not null) project_types,
+                ARRAY_AGG(DISTINCT l.loader) filter (where l.loader is not null) loaders
+            FROM mods m
+            LEFT JOIN versions v ON v.mod_id = m.id
+            LEFT JOIN loaders_versions lv ON lv.version_id = v.id
+            LEFT JOIN loaders l ON l.id = lv.loader_id
+            LEFT JOIN loaders_project_types lpt ON lpt.joining_loader_id = l.id
+            LEFT JOIN project_types pt ON pt.id = lpt.joining_project_type_id
+            WHERE m.organization_id = $1
+            GROUP BY m.id
+            ",
+            id as OrganizationId,
+        )
+        .fetch_all(exec)
+        .await?;
 
-        let mut organization_ids = organization_strings
-            .iter()
-            .flat_map(|x| parse_base62(&x.to_string()).map(|x| x as i64))
-            .collect::<Vec<_>>();
+        let mut summaries = OrganizationProjectSummaries::default();
+        for project in projects {
+            let project_types = project.project_types.unwrap_or_default();
+            let loaders = project.loaders.unwrap_or_default();
 
-        organization_ids.append(
-            &mut redis
-                .multi_get::<i64>(
-                    ORGANIZATIONS_TITLES_NAMESPACE,
-                    organization_strings
-                        .iter()
-                        .map(|x| x.to_string().to_lowercase())
-                        .collect::<Vec<_>>(),
-                )
-                .await?
-                .into_iter()
-                .flatten()
-                .collect(),
-        );
-
-        if !organization_ids.is_empty() {
-            let organizations = redis
-                .multi_get::<String>(
-                    ORGANIZATIONS_NAMESPACE,
-                    organization_ids.iter().map(|x| x.to_string()),
-                )
-                .await?;
-
-            for organization in organizations {
-                if let Some(organization) =
-                    organization.and_then(|x| serde_json::from_str::<Organization>(&x).ok())
-                {
-                    remaining_strings.retain(|x| {
-                        &to_base62(organization.id.0 as u64) != x
-                            && organization.slug.to_lowercase() != x.to_lowercase()
-                    });
-                    found_organizations.push(organization);
-                    continue;
+            let mut counted_in = vec![&mut summaries.all];
+            if ProjectStatus::from_string(&project.status).is_searchable() {
+                counted_in.push(&mut summaries.listed);
+            }
+
+            for summary in counted_in {
+                summary.projects += 1;
+                for project_type in &project_types {
+                    *summary
+                        .project_types
+                        .entry(project_type.clone())
+                        .or_default() += 1;
                 }
+                for loader in &loaders {
+                    *summary.loaders.entry(loader.clone()).or_default() += 1;
+                }
+                summary.downloads += project.downloads as u64;
+                summary.followers += project.follows as u64;
+                summary.updated = summary.updated.max(Some(project.updated));
             }
         }
 
-        if !remaining_strings.is_empty() {
-            let organization_ids_parsed: Vec<i64> = remaining_strings
-                .iter()
-                .flat_map(|x| parse_base62(&x.to_string()).ok())
-                .map(|x| x as i64)
-                .collect();
-
-            let organizations: Vec<Organization> = sqlx::query!(
-                "
-                SELECT o.id, o.slug, o.name, o.team_id, o.description, o.icon_url, o.color
-                FROM organizations o
-                WHERE o.id = ANY($1) OR LOWER(o.slug) = ANY($2)
-                GROUP BY o.id;
-                ",
-                &organization_ids_parsed,
-                &remaining_strings
-                    .into_iter()
-                    .map(|x| x.to_string().to_lowercase())
-                    .collect::<Vec<_>>(),
+        redis
+            .set_serialized_to_json(
+                ORGANIZATIONS_PROJECT_SUMMARIES_NAMESPACE,
+                id.0,
+                &summaries,
+                None,
             )
-            .fetch_many(exec)
-            .try_filter_map(|e| async {
-                Ok(e.right().map(|m| Organization {
-                    id: OrganizationId(m.id),
-                    slug: m.slug,
-                    name: m.name,
-                    team_id: TeamId(m.team_id),
-                    description: m.description,
-                    icon_url: m.icon_url,
-                    color: m.color.map(|x| x as u32),
-                }))
-            })
-            .try_collect::<Vec<Organization>>()
             .await?;
 
-            for organization in organizations {
-                redis
-                    .set_serialized_to_json(
-                        ORGANIZATIONS_NAMESPACE,
-                        organization.id.0,
-                        &organization,
-                        None,
-                    )
-                    .await?;
-                redis
-                    .set(
-                        ORGANIZATIONS_TITLES_NAMESPACE,
-                        &organization.slug.to_lowercase(),
-                        &organization.id.0.to_string(),
-                        None,
-                    )
-                    .await?;
-
-                found_organizations.push(organization);
-            }
-        }
-
-        Ok(found_organizations)
+        Ok(summaries)
     }
 
     // Gets organization associated with a project ID, if it exists and there is one
@@ -231,7 +321,7 @@ impl Organization {
     {
         let result = sqlx::query!(
             "
-            SELECT o.id, o.slug, o.name, o.team_id, o.description, o.icon_url, o.color
+            SELECT o.id, o.slug, o.name, o.team_id, o.description, o.icon_url, o.color, o.require_2fa
             FROM organizations o
             LEFT JOIN mods m ON m.organization_id = o.id
             WHERE m.id = $1
@@ -251,12 +341,54 @@ impl Organization {
                 description: result.description,
                 icon_url: result.icon_url,
                 color: result.color.map(|x| x as u32),
+                require_2fa: result.require_2fa,
             }))
         } else {
             Ok(None)
         }
     }
 
+    /// Gets the override of how many projects the organization can have, if an admin set one
+    pub async fn get_project_limit<'a, E>(
+        id: OrganizationId,
+        exec: E,
+    ) -> Result<Option<i32>, super::DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT project_limit FROM organizations
+            WHERE id = $1
+            ",
+            id as OrganizationId,
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.and_then(|x| x.project_limit))
+    }
+
+    pub async fn set_project_limit(
+        id: OrganizationId,
+        project_limit: Option<i32>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), super::DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE organizations
+            SET project_limit = $2
+            WHERE id = $1
+            ",
+            id as OrganizationId,
+            project_limit,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn remove(
         id: OrganizationId,
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
@@ -310,6 +442,15 @@ impl Organization {
     ) -> Result<(), super::DatabaseError> {
         let mut redis = redis.connect().await?;
 
+        // The organization may have been created or given the slug since it was looked up
+        redis
+            .delete_missing(
+                ORGANIZATIONS_NAMESPACE,
+                Some(ORGANIZATIONS_TITLES_NAMESPACE),
+                id.0,
+                slug.as_deref(),
+            )
+            .await?;
         redis
             .delete_many([
                 (ORGANIZATIONS_NAMESPACE, Some(id.0.to_string())),
@@ -317,8 +458,22 @@ impl Organization {
                     ORGANIZATIONS_TITLES_NAMESPACE,
                     slug.map(|x| x.to_lowercase()),
                 ),
+                (
+                    ORGANIZATIONS_PROJECT_SUMMARIES_NAMESPACE,
+                    Some(id.0.to_string()),
+                ),
             ])
             .await?;
         Ok(())
     }
 }
+
+impl CachedEntry for Organization {
+    fn cache_id(&self) -> i64 {
+        self.id.0
+    }
+
+    fn cache_slug(&self) -> Option<String> {
+        Some(self.slug.clone())
+    }
+}
@@ -0,0 +1,181 @@
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::team_item::TeamMember;
+use super::{generate_team_member_id, DatabaseError, OrganizationId, TeamId, UserId};
+use crate::models::teams::ProjectPermissions;
+
+/// A member's share of the payouts of an organization's projects, out of 100
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct OrganizationPayoutSplit {
+    pub user_id: UserId,
+    pub weight: Decimal,
+}
+
+impl OrganizationPayoutSplit {
+    /// Gets the default payout splits of an organization, largest first
+    pub async fn get_organization<'a, E>(
+        organization_id: OrganizationId,
+        exec: E,
+    ) -> Result<Vec<OrganizationPayoutSplit>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let results = sqlx::query!(
+            "
+            SELECT user_id, weight
+            FROM organization_payout_splits
+            WHERE organization_id = $1
+            ORDER BY weight DESC, user_id
+            ",
+            organization_id.0,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| OrganizationPayoutSplit {
+                user_id: UserId(r.user_id),
+                weight: r.weight,
+            })
+            .collect())
+    }
+
+    /// Replaces the default payout splits of an organization
+    pub async fn set_organization(
+        organization_id: OrganizationId,
+        splits: &[OrganizationPayoutSplit],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            DELETE FROM organization_payout_splits
+            WHERE organization_id = $1
+            ",
+            organization_id.0,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        let (user_ids, weights): (Vec<_>, Vec<_>) =
+            splits.iter().map(|x| (x.user_id.0, x.weight)).unzip();
+        sqlx::query!(
+            "
+            INSERT INTO organization_payout_splits (organization_id, user_id, weight)
+            SELECT $1, user_id, weight
+            FROM UNNEST($2::bigint[], $3::numeric[]) AS s(user_id, weight)
+            ",
+            organization_id.0,
+            &user_ids,
+            &weights,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Splits the payouts of a project in the organization by the organization's defaults. Users
+    /// with a split who aren't on the project team are added to it, with the project permissions
+    /// they have through the organization, and the other members' splits are zeroed. Returns the
+    /// members who aren't accepted members of the organization. Teams are left as they are if
+    /// the organization has no defaults.
+    pub async fn apply_to_team(
+        organization_id: OrganizationId,
+        team_id: TeamId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<UserId>, DatabaseError> {
+        let splits = Self::get_organization(organization_id, &mut **transaction).await?;
+        if splits.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let organization_members = sqlx::query!(
+            "
+            SELECT tm.user_id, tm.role, tm.permissions, tm.is_owner
+            FROM organizations o
+            INNER JOIN team_members tm ON tm.team_id = o.team_id AND tm.accepted = TRUE
+            WHERE o.id = $1
+            ",
+            organization_id.0,
+        )
+        .fetch_all(&mut **transaction)
+        .await?;
+
+        let team_members = sqlx::query!(
+            "
+            SELECT user_id
+            FROM team_members
+            WHERE team_id = $1
+            ",
+            team_id.0,
+        )
+        .fetch_all(&mut **transaction)
+        .await?
+        .into_iter()
+        .map(|x| UserId(x.user_id))
+        .collect::<Vec<_>>();
+
+        let weight = |user_id: UserId| {
+            splits
+                .iter()
+                .find(|x| x.user_id == user_id)
+                .map_or(Decimal::ZERO, |x| x.weight)
+        };
+        let (user_ids, payouts_splits): (Vec<_>, Vec<_>) =
+            team_members.iter().map(|x| (x.0, weight(*x))).unzip();
+        sqlx::query!(
+            "
+            UPDATE team_members tm
+            SET payouts_split = s.payouts_split
+            FROM UNNEST($2::bigint[], $3::numeric[]) AS s(user_id, payouts_split)
+            WHERE tm.team_id = $1 AND tm.user_id = s.user_id
+            ",
+            team_id.0,
+            &user_ids,
+            &payouts_splits,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        for split in splits.iter().filter(|x| !team_members.contains(&x.user_id)) {
+            let Some(member) = organization_members
+                .iter()
+                .find(|x| x.user_id == split.user_id.0)
+            else {
+                continue;
+            };
+
+            TeamMember {
+                id: generate_team_member_id(transaction).await?,
+                team_id,
+                user_id: split.user_id,
+                role: member.role.clone(),
+                is_owner: false,
+                permissions: if member.is_owner {
+                    ProjectPermissions::all()
+                } else {
+                    ProjectPermissions::from_bits(member.permissions as u64).unwrap_or_default()
+                },
+                organization_permissions: None,
+                accepted: true,
+                payouts_split: split.weight,
+                ordering: 0,
+                created: Utc::now(),
+                accepted_at: Some(Utc::now()),
+                invited_by: None,
+                two_factor_restricted: false,
+                role_template_id: None,
+            }
+            .insert(transaction)
+            .await?;
+        }
+
+        Ok(team_members
+            .into_iter()
+            .filter(|x| !organization_members.iter().any(|m| m.user_id == x.0))
+            .collect())
+    }
+}
@@ -0,0 +1,206 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{DatabaseError, OrganizationId, OrganizationRoleTemplateId, TeamId};
+use crate::models::teams::{OrganizationPermissions, ProjectPermissions};
+
+/// A named preset of permissions for the members of an organization and of its projects
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct OrganizationRoleTemplate {
+    pub id: OrganizationRoleTemplateId,
+    pub organization_id: OrganizationId,
+    pub name: String,
+    pub description: String,
+    pub permissions: ProjectPermissions,
+    pub organization_permissions: OrganizationPermissions,
+    pub created: DateTime<Utc>,
+}
+
+impl OrganizationRoleTemplate {
+    /// Inserts the template, unless the organization already has `max_templates` templates.
+    /// Returns whether it was inserted.
+    pub async fn insert(
+        &self,
+        max_templates: i64,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            INSERT INTO organization_role_templates (
+                id, organization_id, name, description, permissions, organization_permissions, created
+            )
+            SELECT $1, $2, $3, $4, $5, $6, $7
+            WHERE (SELECT COUNT(*) FROM organization_role_templates WHERE organization_id = $2) < $8
+            ",
+            self.id.0,
+            self.organization_id.0,
+            self.name,
+            self.description,
+            self.permissions.bits() as i64,
+            self.organization_permissions.bits() as i64,
+            self.created,
+            max_templates,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get<'a, E>(
+        organization_id: OrganizationId,
+        id: OrganizationRoleTemplateId,
+        exec: E,
+    ) -> Result<Option<OrganizationRoleTemplate>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT id, organization_id, name, description, permissions, organization_permissions, created
+            FROM organization_role_templates
+            WHERE organization_id = $1 AND id = $2
+            ",
+            organization_id.0,
+            id.0,
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.map(|r| OrganizationRoleTemplate {
+            id: OrganizationRoleTemplateId(r.id),
+            organization_id: OrganizationId(r.organization_id),
+            name: r.name,
+            description: r.description,
+            permissions: ProjectPermissions::from_bits(r.permissions as u64).unwrap_or_default(),
+            organization_permissions: OrganizationPermissions::from_bits(
+                r.organization_permissions as u64,
+            )
+            .unwrap_or_default(),
+            created: r.created,
+        }))
+    }
+
+    /// Lists the templates of an organization by name
+    pub async fn get_organization<'a, E>(
+        organization_id: OrganizationId,
+        exec: E,
+    ) -> Result<Vec<OrganizationRoleTemplate>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let results = sqlx::query!(
+            "
+            SELECT id, organization_id, name, description, permissions, organization_permissions, created
+            FROM organization_role_templates
+            WHERE organization_id = $1
+            ORDER BY name
+            ",
+            organization_id.0,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| OrganizationRoleTemplate {
+                id: OrganizationRoleTemplateId(r.id),
+                organization_id: OrganizationId(r.organization_id),
+                name: r.name,
+                description: r.description,
+                permissions: ProjectPermissions::from_bits(r.permissions as u64)
+                    .unwrap_or_default(),
+                organization_permissions: OrganizationPermissions::from_bits(
+                    r.organization_permissions as u64,
+                )
+                .unwrap_or_default(),
+                created: r.created,
+            })
+            .collect())
+    }
+
+    pub async fn update(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE organization_role_templates
+            SET name = $1, description = $2, permissions = $3, organization_permissions = $4
+            WHERE id = $5
+            ",
+            self.name,
+            self.description,
+            self.permissions.bits() as i64,
+            self.organization_permissions.bits() as i64,
+            self.id.0,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gives the template's current permissions to the members who were last given it, other
+    /// than owners. Members of organization teams also get its organization permissions. Only
+    /// members of the template's organization or of its projects are updated. Returns the teams
+    /// of the members which were updated.
+    pub async fn apply_to_members(
+        id: OrganizationRoleTemplateId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<TeamId>, DatabaseError> {
+        let team_ids = sqlx::query!(
+            "
+            UPDATE team_members tm
+            SET
+                permissions = rt.permissions,
+                organization_permissions = CASE
+                    WHEN EXISTS (SELECT 1 FROM organizations o WHERE o.team_id = tm.team_id)
+                    THEN rt.organization_permissions
+                    ELSE NULL
+                END
+            FROM organization_role_templates rt
+            WHERE rt.id = $1 AND tm.role_template_id = rt.id AND NOT tm.is_owner
+            AND (
+                EXISTS (
+                    SELECT 1 FROM organizations o
+                    WHERE o.team_id = tm.team_id AND o.id = rt.organization_id
+                )
+                OR EXISTS (
+                    SELECT 1 FROM mods m
+                    WHERE m.team_id = tm.team_id AND m.organization_id = rt.organization_id
+                )
+            )
+            RETURNING tm.team_id
+            ",
+            id.0,
+        )
+        .fetch_all(&mut **transaction)
+        .await?
+        .into_iter()
+        .map(|x| TeamId(x.team_id))
+        .collect();
+
+        Ok(team_ids)
+    }
+
+    /// Removes the template. Members who were given it keep their permissions.
+    pub async fn remove(
+        organization_id: OrganizationId,
+        id: OrganizationRoleTemplateId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            DELETE FROM organization_role_templates
+            WHERE organization_id = $1 AND id = $2
+            ",
+            organization_id.0,
+            id.0,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,14 +1,21 @@
 use super::ids::*;
 use crate::database::models::DatabaseError;
-use crate::database::redis::RedisPool;
-use crate::models::ids::base62_impl::{parse_base62, to_base62};
-use crate::models::pats::Scopes;
+use crate::database::redis::{CachedEntry, RedisPool};
+use crate::models::ids::base62_impl::parse_base62;
+use crate::models::pats::{PatUsage, Scopes};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 const PATS_NAMESPACE: &str = "pats";
 const PATS_TOKENS_NAMESPACE: &str = "pats_tokens";
 const PATS_USERS_NAMESPACE: &str = "pats_users";
+const PATS_LAST_USED_NAMESPACE: &str = "pats_last_used";
+const PATS_USAGE_NAMESPACE: &str = "pats_usage";
+
+// `last_used` is written to the database at most once per token in this interval
+const PAT_LAST_USED_INTERVAL: i64 = 60 * 15;
+const PAT_USAGE_HISTORY_LENGTH: usize = 50;
+const PAT_USAGE_EXPIRY: i64 = 60 * 60 * 24 * 7; // 7 days
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct PersonalAccessToken {
@@ -20,6 +27,9 @@ pub struct PersonalAccessToken {
     pub created: DateTime<Utc>,
     pub expires: DateTime<Utc>,
     pub last_used: Option<DateTime<Utc>>,
+    /// The projects the token may act on, if it is restricted to any
+    #[serde(default)]
+    pub project_ids: Option<Vec<ProjectId>>,
 }
 
 impl PersonalAccessToken {
@@ -31,11 +41,11 @@ impl PersonalAccessToken {
             "
             INSERT INTO pats (
                 id, name, access_token, scopes, user_id,
-                expires
+                expires, project_ids
             )
             VALUES (
                 $1, $2, $3, $4, $5,
-                $6
+                $6, $7
             )
             ",
             self.id as PatId,
@@ -43,7 +53,10 @@ impl PersonalAccessToken {
             self.access_token,
             self.scopes.bits() as i64,
             self.user_id as UserId,
-            self.expires
+            self.expires,
+            self.project_ids
+                .as_ref()
+                .map(|x| x.iter().map(|x| x.0).collect::<Vec<_>>()),
         )
         .execute(&mut **transaction)
         .await?;
@@ -51,6 +64,47 @@ impl PersonalAccessToken {
         Ok(())
     }
 
+    /// Records a use of the token in its usage history. Returns whether `last_used` should be
+    /// written to the database, which only happens once per token every 15 minutes.
+    pub async fn record_usage(
+        id: PatId,
+        usage: &PatUsage,
+        redis: &RedisPool,
+    ) -> Result<bool, DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        redis
+            .push_capped(
+                PATS_USAGE_NAMESPACE,
+                &id.0.to_string(),
+                &serde_json::to_string(usage)?,
+                PAT_USAGE_HISTORY_LENGTH,
+                Some(PAT_USAGE_EXPIRY),
+            )
+            .await?;
+
+        redis
+            .set_if_absent(
+                PATS_LAST_USED_NAMESPACE,
+                &id.0.to_string(),
+                &usage.used.timestamp().to_string(),
+                Some(PAT_LAST_USED_INTERVAL),
+            )
+            .await
+    }
+
+    /// Gets the most recent uses of the token, newest first
+    pub async fn get_usage(id: PatId, redis: &RedisPool) -> Result<Vec<PatUsage>, DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        Ok(redis
+            .get_list(PATS_USAGE_NAMESPACE, &id.0.to_string())
+            .await?
+            .into_iter()
+            .filter_map(|x| serde_json::from_str(&x).ok())
+            .collect())
+    }
+
     pub async fn get<'a, E, T: ToString>(
         id: T,
         exec: E,
@@ -89,103 +143,54 @@ impl PersonalAccessToken {
     {
         use futures::TryStreamExt;
 
-        let mut redis = redis.connect().await?;
-
-        if pat_strings.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        let mut found_pats = Vec::new();
-        let mut remaining_strings = pat_strings
-            .iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<_>>();
-
-        let mut pat_ids = pat_strings
-            .iter()
-            .flat_map(|x| parse_base62(&x.to_string()).map(|x| x as i64))
-            .collect::<Vec<_>>();
-
-        pat_ids.append(
-            &mut redis
-                .multi_get::<i64>(
-                    PATS_TOKENS_NAMESPACE,
-                    pat_strings.iter().map(|x| x.to_string()),
-                )
-                .await?
-                .into_iter()
-                .flatten()
-                .collect(),
-        );
-
-        if !pat_ids.is_empty() {
-            let pats = redis
-                .multi_get::<String>(PATS_NAMESPACE, pat_ids.iter().map(|x| x.to_string()))
-                .await?;
-            for pat in pats {
-                if let Some(pat) =
-                    pat.and_then(|x| serde_json::from_str::<PersonalAccessToken>(&x).ok())
-                {
-                    remaining_strings
-                        .retain(|x| &to_base62(pat.id.0 as u64) != x && &pat.access_token != x);
-                    found_pats.push(pat);
-                    continue;
-                }
-            }
-        }
-
-        if !remaining_strings.is_empty() {
-            let pat_ids_parsed: Vec<i64> = remaining_strings
-                .iter()
-                .flat_map(|x| parse_base62(&x.to_string()).ok())
-                .map(|x| x as i64)
-                .collect();
-            let db_pats: Vec<PersonalAccessToken> = sqlx::query!(
-                "
-                SELECT id, name, access_token, scopes, user_id, created, expires, last_used
-                FROM pats
-                WHERE id = ANY($1) OR access_token = ANY($2)
-                ORDER BY created DESC
-                ",
-                &pat_ids_parsed,
-                &remaining_strings
-                    .into_iter()
+        redis
+            .get_cached_keys(
+                PATS_NAMESPACE,
+                Some(PATS_TOKENS_NAMESPACE),
+                &pat_strings
+                    .iter()
                     .map(|x| x.to_string())
                     .collect::<Vec<_>>(),
-            )
-            .fetch_many(exec)
-            .try_filter_map(|e| async {
-                Ok(e.right().map(|x| PersonalAccessToken {
-                    id: PatId(x.id),
-                    name: x.name,
-                    access_token: x.access_token,
-                    scopes: Scopes::from_bits(x.scopes as u64).unwrap_or(Scopes::NONE),
-                    user_id: UserId(x.user_id),
-                    created: x.created,
-                    expires: x.expires,
-                    last_used: x.last_used,
-                }))
-            })
-            .try_collect::<Vec<PersonalAccessToken>>()
-            .await?;
-
-            for pat in db_pats {
-                redis
-                    .set_serialized_to_json(PATS_NAMESPACE, pat.id.0, &pat, None)
-                    .await?;
-                redis
-                    .set(
-                        PATS_TOKENS_NAMESPACE,
-                        &pat.access_token,
-                        &pat.id.0.to_string(),
-                        None,
+                |x| parse_base62(x).ok().map(|x| x as i64),
+                |remaining_strings| async move {
+                    let pat_ids_parsed: Vec<i64> = remaining_strings
+                        .iter()
+                        .flat_map(|x| parse_base62(&x.to_string()).ok())
+                        .map(|x| x as i64)
+                        .collect();
+                    let db_pats: Vec<PersonalAccessToken> = sqlx::query!(
+                        "
+                        SELECT id, name, access_token, scopes, user_id, created, expires, last_used, project_ids
+                        FROM pats
+                        WHERE id = ANY($1) OR access_token = ANY($2)
+                        ORDER BY created DESC
+                        ",
+                        &pat_ids_parsed,
+                        &remaining_strings,
                     )
+                    .fetch_many(exec)
+                    .try_filter_map(|e| async {
+                        Ok(e.right().map(|x| PersonalAccessToken {
+                            id: PatId(x.id),
+                            name: x.name,
+                            access_token: x.access_token,
+                            scopes: Scopes::from_bits(x.scopes as u64).unwrap_or(Scopes::NONE),
+                            user_id: UserId(x.user_id),
+                            created: x.created,
+                            expires: x.expires,
+                            last_used: x.last_used,
+                            project_ids: x
+                                .project_ids
+                                .map(|x| x.into_iter().map(ProjectId).collect()),
+                        }))
+                    })
+                    .try_collect::<Vec<PersonalAccessToken>>()
                     .await?;
-                found_pats.push(pat);
-            }
-        }
 
-        Ok(found_pats)
+                    Ok(db_pats)
+                },
+            )
+            .await
     }
 
     pub async fn get_user_pats<'a, E>(
@@ -271,3 +276,17 @@ impl PersonalAccessToken {
         Ok(Some(()))
     }
 }
+
+impl CachedEntry for PersonalAccessToken {
+    fn cache_id(&self) -> i64 {
+        self.id.0
+    }
+
+    fn cache_slug(&self) -> Option<String> {
+        Some(self.access_token.clone())
+    }
+
+    fn slug_key(slug: &str) -> String {
+        slug.to_string()
+    }
+}
@@ -17,6 +17,8 @@ pub struct Payout {
     pub method: Option<PayoutMethodType>,
     pub method_address: Option<String>,
     pub platform_id: Option<String>,
+    /// When the payout was paid, failed or was cancelled
+    pub completed: Option<DateTime<Utc>>,
 }
 
 impl Payout {
@@ -68,7 +70,8 @@ impl Payout {
 
         let results = sqlx::query!(
             "
-            SELECT id, user_id, created, amount, status, method, method_address, platform_id, fee
+            SELECT id, user_id, created, amount, status, method, method_address, platform_id, fee,
+            completed
             FROM payouts
             WHERE id = ANY($1)
             ",
@@ -86,6 +89,7 @@ impl Payout {
                 method_address: r.method_address,
                 platform_id: r.platform_id,
                 fee: r.fee,
+                completed: r.completed,
             }))
         })
         .try_collect::<Vec<Payout>>()
@@ -114,4 +118,107 @@ impl Payout {
             .map(|r| PayoutId(r.id))
             .collect::<Vec<_>>())
     }
+
+    /// Gets a page of the user's payouts created in the range, newest first, along with the
+    /// number of payouts in the range. Either end of the range may be left open.
+    pub async fn get_user_page(
+        user_id: UserId,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        count: i64,
+        offset: i64,
+        exec: impl sqlx::Executor<'_, Database = sqlx::Postgres>,
+    ) -> Result<(i64, Vec<PayoutId>), DatabaseError> {
+        // The page is left joined onto the total so the total is returned even for empty pages
+        let rows = sqlx::query!(
+            "
+            SELECT t.total, p.id
+            FROM (
+                SELECT COUNT(*) total FROM payouts
+                WHERE user_id = $1
+                AND ($2::timestamptz IS NULL OR created >= $2)
+                AND ($3::timestamptz IS NULL OR created < $3)
+            ) t
+            LEFT JOIN LATERAL (
+                SELECT id FROM payouts
+                WHERE user_id = $1
+                AND ($2::timestamptz IS NULL OR created >= $2)
+                AND ($3::timestamptz IS NULL OR created < $3)
+                ORDER BY created DESC, id DESC
+                LIMIT $4 OFFSET $5
+            ) p ON TRUE
+            ",
+            user_id.0,
+            start,
+            end,
+            count,
+            offset,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        let total = rows.first().and_then(|x| x.total).unwrap_or(0);
+        let payout_ids = rows
+            .into_iter()
+            .filter_map(|x| Some(PayoutId(x.id?)))
+            .collect();
+
+        Ok((total, payout_ids))
+    }
+
+    /// Declares `cursor` over the user's payouts created in the range, oldest first. Each month
+    /// with payouts is followed by a row totalling the month's paid payouts, whose `kind` is
+    /// `month_total`. The rows are `PayoutStatementRow`s.
+    pub async fn declare_statement_export(
+        cursor: &str,
+        user_id: UserId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query(&format!(
+            "
+            DECLARE {cursor} NO SCROLL CURSOR FOR
+            SELECT kind, month, id, created, completed, status, method, amount, fee
+            FROM (
+                SELECT 'payout' kind, to_char(p.created AT TIME ZONE 'UTC', 'YYYY-MM') month,
+                    p.id, p.created, p.completed, p.status, p.method, p.amount, p.fee
+                FROM payouts p
+                WHERE p.user_id = $1 AND p.created >= $2 AND p.created < $3
+                UNION ALL
+                SELECT 'month_total', to_char(p.created AT TIME ZONE 'UTC', 'YYYY-MM'),
+                    NULL, NULL, NULL, NULL, NULL,
+                    COALESCE(SUM(p.amount) FILTER (WHERE p.status = $4), 0),
+                    COALESCE(SUM(p.fee) FILTER (WHERE p.status = $4), 0)
+                FROM payouts p
+                WHERE p.user_id = $1 AND p.created >= $2 AND p.created < $3
+                GROUP BY 2
+            ) r
+            ORDER BY month, kind = 'month_total', created, id
+            "
+        ))
+        .bind(user_id.0)
+        .bind(start)
+        .bind(end)
+        .bind(PayoutStatus::Success.as_str())
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct PayoutStatementRow {
+    /// `payout`, or `month_total` for the totals of a month
+    pub kind: String,
+    /// The month the payout was created in, as `YYYY-MM`
+    pub month: String,
+    pub id: Option<PayoutId>,
+    pub created: Option<DateTime<Utc>>,
+    pub completed: Option<DateTime<Utc>>,
+    pub status: Option<String>,
+    pub method: Option<String>,
+    pub amount: Decimal,
+    pub fee: Option<Decimal>,
 }
@@ -0,0 +1,207 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{DatabaseError, PendingUploadId, UserId};
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PendingUpload {
+    pub id: PendingUploadId,
+    pub user_id: UserId,
+    pub file_name: String,
+    pub size: i64,
+    pub sha1: String,
+    pub chunk_size: i32,
+    pub created: DateTime<Utc>,
+}
+
+/// A chunk of an upload, staged on the file host until the upload is completed
+#[derive(Clone, Debug)]
+pub struct PendingUploadChunk {
+    pub chunk_index: i32,
+    pub file_id: String,
+    pub file_name: String,
+}
+
+impl PendingUpload {
+    pub fn chunk_count(&self) -> i32 {
+        ((self.size + self.chunk_size as i64 - 1) / self.chunk_size as i64) as i32
+    }
+
+    /// The number of bytes the chunk at `chunk_index` is expected to contain.
+    /// Every chunk is `chunk_size` bytes long, except for the final one.
+    pub fn expected_chunk_len(&self, chunk_index: i32) -> usize {
+        let start = chunk_index as i64 * self.chunk_size as i64;
+        (self.size - start).min(self.chunk_size as i64) as usize
+    }
+
+    pub async fn insert(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO pending_uploads (
+                id, user_id, file_name, size, sha1, chunk_size
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6
+            )
+            ",
+            self.id.0,
+            self.user_id.0,
+            self.file_name,
+            self.size,
+            self.sha1,
+            self.chunk_size,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get<'a, E>(
+        id: PendingUploadId,
+        exec: E,
+    ) -> Result<Option<PendingUpload>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT id, user_id, file_name, size, sha1, chunk_size, created
+            FROM pending_uploads
+            WHERE id = $1
+            ",
+            id.0
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.map(|r| PendingUpload {
+            id: PendingUploadId(r.id),
+            user_id: UserId(r.user_id),
+            file_name: r.file_name,
+            size: r.size,
+            sha1: r.sha1,
+            chunk_size: r.chunk_size,
+            created: r.created,
+        }))
+    }
+
+    /// Records a chunk of the upload staged on the file host, replacing the chunk if it was
+    /// already sent before
+    pub async fn upsert_chunk<'a, E>(
+        id: PendingUploadId,
+        chunk: &PendingUploadChunk,
+        exec: E,
+    ) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        sqlx::query!(
+            "
+            INSERT INTO pending_upload_chunks (upload_id, chunk_index, file_id, file_name)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (upload_id, chunk_index)
+            DO UPDATE SET file_id = EXCLUDED.file_id, file_name = EXCLUDED.file_name
+            ",
+            id.0,
+            chunk.chunk_index,
+            chunk.file_id,
+            chunk.file_name,
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the chunks received so far, in order
+    pub async fn get_chunks<'a, E>(
+        id: PendingUploadId,
+        exec: E,
+    ) -> Result<Vec<PendingUploadChunk>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let chunks = sqlx::query!(
+            "
+            SELECT chunk_index, file_id, file_name
+            FROM pending_upload_chunks
+            WHERE upload_id = $1
+            ORDER BY chunk_index ASC
+            ",
+            id.0
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(chunks
+            .into_iter()
+            .map(|x| PendingUploadChunk {
+                chunk_index: x.chunk_index,
+                file_id: x.file_id,
+                file_name: x.file_name,
+            })
+            .collect())
+    }
+
+    /// Gets how many uploads the user has in progress, and how many bytes they add up to
+    pub async fn get_user_usage<'a, E>(
+        user_id: UserId,
+        exec: E,
+    ) -> Result<(i64, i64), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT COUNT(*) count, COALESCE(SUM(size), 0)::bigint size
+            FROM pending_uploads
+            WHERE user_id = $1
+            ",
+            user_id.0
+        )
+        .fetch_one(exec)
+        .await?;
+
+        Ok((result.count.unwrap_or(0), result.size.unwrap_or(0)))
+    }
+
+    pub async fn remove(
+        id: PendingUploadId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        // The records of the chunks are removed by the cascading foreign key, but the chunks
+        // themselves have to be removed from the file host
+        sqlx::query!(
+            "
+            DELETE FROM pending_uploads
+            WHERE id = $1
+            ",
+            id.0,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the uploads which were started more than 24 hours ago and never completed
+    pub async fn get_expired<'a, E>(exec: E) -> Result<Vec<PendingUploadId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT id FROM pending_uploads
+            WHERE created < NOW() - INTERVAL '24 hours'
+            "
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(result.into_iter().map(|x| PendingUploadId(x.id)).collect())
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{DatabaseError, ProjectId, UserId};
+
+/// A user blocked from posting in a project's threads and from joining its team
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ProjectBlock {
+    pub project_id: ProjectId,
+    pub user_id: UserId,
+    pub blocked_by: Option<UserId>,
+    pub created: DateTime<Utc>,
+}
+
+impl ProjectBlock {
+    /// Blocks the user, unless the project already has `max_blocks` blocks. Returns whether the
+    /// user was blocked, which they aren't if the project is at the limit. Blocking a user who
+    /// is already blocked keeps the original block.
+    pub async fn insert(
+        project_id: ProjectId,
+        user_id: UserId,
+        blocked_by: UserId,
+        max_blocks: i64,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            INSERT INTO project_blocks (mod_id, user_id, blocked_by)
+            SELECT $1, $2, $3
+            WHERE (SELECT COUNT(*) FROM project_blocks WHERE mod_id = $1) < $4
+            ON CONFLICT (mod_id, user_id) DO NOTHING
+            ",
+            project_id as ProjectId,
+            user_id as UserId,
+            blocked_by as UserId,
+            max_blocks,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            return Ok(true);
+        }
+
+        Self::is_blocked(project_id, user_id, &mut **transaction).await
+    }
+
+    /// Unblocks the user. Returns whether they were blocked.
+    pub async fn remove(
+        project_id: ProjectId,
+        user_id: UserId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            DELETE FROM project_blocks
+            WHERE mod_id = $1 AND user_id = $2
+            ",
+            project_id as ProjectId,
+            user_id as UserId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn is_blocked<'a, E>(
+        project_id: ProjectId,
+        user_id: UserId,
+        exec: E,
+    ) -> Result<bool, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT EXISTS(SELECT 1 FROM project_blocks WHERE mod_id = $1 AND user_id = $2)
+            ",
+            project_id as ProjectId,
+            user_id as UserId,
+        )
+        .fetch_one(exec)
+        .await?;
+
+        Ok(result.exists.unwrap_or(false))
+    }
+
+    /// Gets the users the project blocked, most recently blocked first
+    pub async fn get_for_project<'a, E>(
+        project_id: ProjectId,
+        exec: E,
+    ) -> Result<Vec<ProjectBlock>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let blocks = sqlx::query!(
+            "
+            SELECT mod_id, user_id, blocked_by, created
+            FROM project_blocks
+            WHERE mod_id = $1
+            ORDER BY created DESC
+            ",
+            project_id as ProjectId,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| ProjectBlock {
+            project_id: ProjectId(x.mod_id),
+            user_id: UserId(x.user_id),
+            blocked_by: x.blocked_by.map(UserId),
+            created: x.created,
+        })
+        .collect();
+
+        Ok(blocks)
+    }
+}
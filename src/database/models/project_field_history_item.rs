@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{DatabaseError, ProjectId, UserId, VersionId};
+
+/// A change to one of the fields moderators check when approving a project
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ProjectFieldChange {
+    pub project_id: ProjectId,
+    /// The version whose loader field changed, for fields set on versions
+    pub version_id: Option<VersionId>,
+    pub field: String,
+    pub old_value: serde_json::Value,
+    pub new_value: serde_json::Value,
+    pub actor_id: Option<UserId>,
+    pub created: DateTime<Utc>,
+}
+
+impl ProjectFieldChange {
+    /// Records the change, which is dated when it's inserted
+    pub async fn insert(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO project_field_history (
+                mod_id, version_id, field, old_value, new_value, actor_id
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6
+            )
+            ",
+            self.project_id as ProjectId,
+            self.version_id.map(|x| x.0),
+            self.field,
+            self.old_value,
+            self.new_value,
+            self.actor_id.map(|x| x.0),
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the changes to the fields of the project, oldest first
+    pub async fn get_for_project<'a, E>(
+        project_id: ProjectId,
+        fields: &[String],
+        exec: E,
+    ) -> Result<Vec<ProjectFieldChange>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let changes = sqlx::query!(
+            "
+            SELECT mod_id, version_id, field, old_value, new_value, actor_id, created
+            FROM project_field_history
+            WHERE mod_id = $1 AND field = ANY($2)
+            ORDER BY created, id
+            ",
+            project_id as ProjectId,
+            fields,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| ProjectFieldChange {
+            project_id: ProjectId(x.mod_id),
+            version_id: x.version_id.map(VersionId),
+            field: x.field,
+            old_value: x.old_value,
+            new_value: x.new_value,
+            actor_id: x.actor_id.map(UserId),
+            created: x.created,
+        })
+        .collect();
+
+        Ok(changes)
+    }
+}
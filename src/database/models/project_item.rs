@@ -4,21 +4,32 @@ use super::loader_fields::{
 use super::{ids::*, User};
 use crate::database::models;
 use crate::database::models::DatabaseError;
-use crate::database::redis::RedisPool;
-use crate::models::ids::base62_impl::{parse_base62, to_base62};
+use crate::database::redis::{CachedEntry, RedisPool};
+use crate::models::ids::base62_impl::parse_base62;
+use crate::models::images::ImageModerationStatus;
 use crate::models::projects::{MonetizationStatus, ProjectStatus};
-use chrono::{DateTime, Utc};
+use crate::models::reports::ReportStatus;
+use crate::util::etag::item_etag;
+use chrono::{DateTime, Duration, Utc};
 use dashmap::{DashMap, DashSet};
 use futures::TryStreamExt;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 pub const PROJECTS_NAMESPACE: &str = "projects";
 pub const PROJECTS_SLUGS_NAMESPACE: &str = "projects_slugs";
+const PROJECTS_SLUG_HISTORY_NAMESPACE: &str = "projects_slug_history";
+
+/// How many days a slug a project gave up stays reserved before another project can claim it
+pub const SLUG_RESERVATION_DAYS: i64 = 30;
 const PROJECTS_DEPENDENCIES_NAMESPACE: &str = "projects_dependencies";
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LinkUrl {
+    /// The name the link is listed under, which is the platform's name for links on known
+    /// platforms
+    pub name: String,
     pub platform_id: LinkPlatformId,
     pub platform_name: String,
     pub url: String,
@@ -31,18 +42,19 @@ impl LinkUrl {
         project_id: ProjectId,
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), sqlx::error::Error> {
-        let (project_ids, platform_ids, urls): (Vec<_>, Vec<_>, Vec<_>) = links
+        let (project_ids, names, platform_ids, urls): (Vec<_>, Vec<_>, Vec<_>, Vec<_>) = links
             .into_iter()
-            .map(|url| (project_id.0, url.platform_id.0, url.url))
+            .map(|url| (project_id.0, url.name, url.platform_id.0, url.url))
             .multiunzip();
         sqlx::query!(
             "
             INSERT INTO mods_links (
-                joining_mod_id, joining_platform_id, url
+                joining_mod_id, name, joining_platform_id, url
             )
-            SELECT * FROM UNNEST($1::bigint[], $2::int[], $3::varchar[])
+            SELECT * FROM UNNEST($1::bigint[], $2::varchar[], $3::int[], $4::varchar[])
             ",
             &project_ids[..],
+            &names[..],
             &platform_ids[..],
             &urls[..],
         )
@@ -61,6 +73,14 @@ pub struct GalleryItem {
     pub description: Option<String>,
     pub created: DateTime<Utc>,
     pub ordering: i64,
+    #[serde(default)]
+    pub variants: HashMap<String, String>,
+    /// The uploaded image tracking the item's moderation, unset for items from before images
+    /// were moderated
+    #[serde(default)]
+    pub image_id: Option<ImageId>,
+    #[serde(default)]
+    pub moderation_status: ImageModerationStatus,
 }
 
 impl GalleryItem {
@@ -69,7 +89,18 @@ impl GalleryItem {
         project_id: ProjectId,
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), sqlx::error::Error> {
-        let (project_ids, image_urls, featureds, names, descriptions, orderings): (
+        let (
+            project_ids,
+            image_urls,
+            featureds,
+            names,
+            descriptions,
+            orderings,
+            variants,
+            image_ids,
+        ): (
+            Vec<_>,
+            Vec<_>,
             Vec<_>,
             Vec<_>,
             Vec<_>,
@@ -86,28 +117,111 @@ impl GalleryItem {
                     gi.name,
                     gi.description,
                     gi.ordering,
+                    serde_json::json!(gi.variants),
+                    gi.image_id.map(|x| x.0),
                 )
             })
             .multiunzip();
         sqlx::query!(
             "
             INSERT INTO mods_gallery (
-                mod_id, image_url, featured, name, description, ordering
+                mod_id, image_url, featured, name, description, ordering, variants, image_id
             )
-            SELECT * FROM UNNEST ($1::bigint[], $2::varchar[], $3::bool[], $4::varchar[], $5::varchar[], $6::bigint[])
+            SELECT * FROM UNNEST ($1::bigint[], $2::varchar[], $3::bool[], $4::varchar[], $5::varchar[], $6::bigint[], $7::jsonb[], $8::bigint[])
             ",
             &project_ids[..],
             &image_urls[..],
             &featureds[..],
             &names[..] as &[Option<String>],
             &descriptions[..] as &[Option<String>],
-            &orderings[..]
+            &orderings[..],
+            &variants[..],
+            &image_ids[..] as &[Option<i64>]
         )
         .execute(&mut **transaction)
         .await?;
 
         Ok(())
     }
+
+    /// Sets the ordering of a project's gallery items to their position in `image_urls`
+    pub async fn reorder(
+        project_id: ProjectId,
+        image_urls: &[String],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), sqlx::error::Error> {
+        sqlx::query!(
+            "
+            UPDATE mods_gallery mg
+            SET ordering = o.ordering
+            FROM UNNEST($2::varchar[]) WITH ORDINALITY AS o(image_url, ordering)
+            WHERE mg.mod_id = $1 AND mg.image_url = o.image_url
+            ",
+            project_id as ProjectId,
+            image_urls,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// A project's name, summary and description in another language
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProjectTranslation {
+    pub language: String,
+    pub name: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+}
+
+impl ProjectTranslation {
+    /// Adds the translation, replacing the project's existing one in the same language
+    pub async fn upsert(
+        &self,
+        project_id: ProjectId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO project_translations (mod_id, language, name, summary, description)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (mod_id, language) DO UPDATE
+            SET name = EXCLUDED.name, summary = EXCLUDED.summary,
+                description = EXCLUDED.description, updated = NOW()
+            ",
+            project_id as ProjectId,
+            self.language,
+            self.name,
+            self.summary,
+            self.description,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Removes the project's translation in the language, returning whether there was one
+    pub async fn remove(
+        project_id: ProjectId,
+        language: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            DELETE FROM project_translations
+            WHERE mod_id = $1 AND language = $2
+            ",
+            project_id as ProjectId,
+            language,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
 
 #[derive(derive_new::new)]
@@ -140,6 +254,28 @@ impl ModCategory {
 
         Ok(())
     }
+
+    /// Shows the project's categories with the names in place of all of its categories
+    pub async fn set_display(
+        project_id: ProjectId,
+        names: &[String],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE mods_categories mc
+            SET is_display = c.category = ANY($2::text[])
+            FROM categories c
+            WHERE mc.joining_category_id = c.id AND mc.joining_mod_id = $1
+            ",
+            project_id as ProjectId,
+            names,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
@@ -151,6 +287,7 @@ pub struct ProjectBuilder {
     pub summary: String,
     pub description: String,
     pub icon_url: Option<String>,
+    pub icon_variants: HashMap<String, String>,
     pub license_url: Option<String>,
     pub categories: Vec<CategoryId>,
     pub additional_categories: Vec<CategoryId>,
@@ -163,6 +300,8 @@ pub struct ProjectBuilder {
     pub gallery_items: Vec<GalleryItem>,
     pub color: Option<u32>,
     pub monetization_status: MonetizationStatus,
+    pub description_source_url: Option<String>,
+    pub description_fetched: Option<DateTime<Utc>>,
 }
 
 impl ProjectBuilder {
@@ -190,6 +329,7 @@ impl ProjectBuilder {
             downloads: 0,
             follows: 0,
             icon_url: self.icon_url,
+            icon_variants: self.icon_variants,
             license_url: self.license_url,
             license: self.license,
             slug: self.slug,
@@ -199,6 +339,12 @@ impl ProjectBuilder {
             color: self.color,
             monetization_status: self.monetization_status,
             loaders: vec![],
+            mature: false,
+            description_source_url: self.description_source_url,
+            description_fetched: self.description_fetched,
+            icon_image_id: None,
+            icon_moderation_status: ImageModerationStatus::Approved,
+            go_live_at: None,
         };
         project_struct.insert(&mut *transaction).await?;
 
@@ -251,6 +397,8 @@ pub struct Project {
     pub downloads: i32,
     pub follows: i32,
     pub icon_url: Option<String>,
+    #[serde(default)]
+    pub icon_variants: HashMap<String, String>,
     pub license_url: Option<String>,
     pub license: String,
     pub slug: Option<String>,
@@ -260,6 +408,21 @@ pub struct Project {
     pub color: Option<u32>,
     pub monetization_status: MonetizationStatus,
     pub loaders: Vec<String>,
+    #[serde(default)]
+    pub mature: bool,
+    #[serde(default)]
+    pub description_source_url: Option<String>,
+    #[serde(default)]
+    pub description_fetched: Option<DateTime<Utc>>,
+    /// The uploaded image tracking the icon's moderation, unset for icons from before images
+    /// were moderated
+    #[serde(default)]
+    pub icon_image_id: Option<ImageId>,
+    #[serde(default)]
+    pub icon_moderation_status: ImageModerationStatus,
+    /// When the project is listed once approved, if the team scheduled it
+    #[serde(default)]
+    pub go_live_at: Option<DateTime<Utc>>,
 }
 
 impl Project {
@@ -273,13 +436,15 @@ impl Project {
                 id, team_id, name, summary, description,
                 published, downloads, icon_url, status, requested_status,
                 license_url, license,
-                slug, color, monetization_status, organization_id
+                slug, color, monetization_status, organization_id, icon_variants,
+                description_source_url, description_fetched
             )
             VALUES (
                 $1, $2, $3, $4, $5, $6, 
                 $7, $8, $9, $10, 
                 $11, $12, 
-                LOWER($13), $14, $15, $16
+                LOWER($13), $14, $15, $16, $17,
+                $18, $19
             )
             ",
             self.id as ProjectId,
@@ -298,6 +463,9 @@ impl Project {
             self.color.map(|x| x as i32),
             self.monetization_status.as_str(),
             self.organization_id.map(|x| x.0 as i64),
+            serde_json::json!(self.icon_variants),
+            self.description_source_url.as_ref(),
+            self.description_fetched,
         )
         .execute(&mut **transaction)
         .await?;
@@ -310,10 +478,23 @@ impl Project {
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         redis: &RedisPool,
     ) -> Result<Option<()>, DatabaseError> {
-        let project = Self::get_id(id, &mut **transaction, redis).await?;
+        // Deleted projects can't be fetched normally, but are still removed once their restore
+        // window has passed
+        let project = sqlx::query!(
+            "
+            SELECT m.slug, m.team_id, t.id thread_id,
+            ARRAY(SELECT v.id FROM versions v WHERE v.mod_id = m.id) versions
+            FROM mods m
+            INNER JOIN threads t ON t.mod_id = m.id
+            WHERE m.id = $1
+            ",
+            id as ProjectId,
+        )
+        .fetch_optional(&mut **transaction)
+        .await?;
 
         if let Some(project) = project {
-            Project::clear_cache(id, project.inner.slug, Some(true), redis).await?;
+            Project::clear_cache(id, project.slug, Some(true), redis).await?;
 
             sqlx::query!(
                 "
@@ -365,7 +546,7 @@ impl Project {
                 models::Thread::remove_full(thread_id, transaction).await?;
             }
 
-            models::Thread::remove_full(project.thread_id, transaction).await?;
+            models::Thread::remove_full(ThreadId(project.thread_id), transaction).await?;
 
             sqlx::query!(
                 "
@@ -397,8 +578,8 @@ impl Project {
             .execute(&mut **transaction)
             .await?;
 
-            for version in project.versions {
-                super::Version::remove_full(version, redis, transaction).await?;
+            for version in project.versions.unwrap_or_default() {
+                super::Version::remove_full(VersionId(version), redis, transaction).await?;
             }
 
             sqlx::query!(
@@ -431,7 +612,7 @@ impl Project {
             .execute(&mut **transaction)
             .await?;
 
-            models::TeamMember::clear_cache(project.inner.team_id, redis).await?;
+            models::TeamMember::clear_cache(TeamId(project.team_id), redis).await?;
 
             let affected_user_ids = sqlx::query!(
                 "
@@ -439,7 +620,7 @@ impl Project {
                 WHERE team_id = $1
                 RETURNING user_id
                 ",
-                project.inner.team_id as TeamId,
+                project.team_id,
             )
             .fetch_many(&mut **transaction)
             .try_filter_map(|e| async { Ok(e.right().map(|x| UserId(x.user_id))) })
@@ -453,17 +634,126 @@ impl Project {
                 DELETE FROM teams
                 WHERE id = $1
                 ",
-                project.inner.team_id as TeamId,
+                project.team_id,
+            )
+            .execute(&mut **transaction)
+            .await?;
+
+            Ok(Some(()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Hides the project as if it were removed, keeping everything about it so it can be
+    /// restored until it's removed for good
+    pub async fn soft_delete(
+        id: ProjectId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        redis: &RedisPool,
+    ) -> Result<Option<()>, DatabaseError> {
+        let project = Self::get_id(id, &mut **transaction, redis).await?;
+
+        if let Some(project) = project {
+            sqlx::query!(
+                "
+                UPDATE mods
+                SET status_before_deletion = status, status = $1, deleted_at = NOW()
+                WHERE id = $2
+                ",
+                ProjectStatus::Deleted.as_str(),
+                id as ProjectId,
             )
             .execute(&mut **transaction)
             .await?;
 
+            Project::clear_cache(id, project.inner.slug, Some(true), redis).await?;
+
             Ok(Some(()))
         } else {
             Ok(None)
         }
     }
 
+    /// Gets a deleted project by its ID or slug
+    pub async fn get_deleted<'a, E>(
+        string: &str,
+        exec: E,
+    ) -> Result<Option<DeletedProject>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let id = parse_base62(string).ok().map(|x| x as i64);
+
+        let result = sqlx::query!(
+            "
+            SELECT id, slug, team_id, organization_id, deleted_at
+            FROM mods
+            WHERE (id = $1 OR LOWER(slug) = $2) AND deleted_at IS NOT NULL
+            ",
+            id,
+            string.to_lowercase(),
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.and_then(|r| {
+            Some(DeletedProject {
+                id: ProjectId(r.id),
+                slug: r.slug,
+                team_id: TeamId(r.team_id),
+                organization_id: r.organization_id.map(OrganizationId),
+                deleted_at: r.deleted_at?,
+            })
+        }))
+    }
+
+    /// Gets the projects which were deleted before the given time
+    pub async fn get_deleted_before<'a, E>(
+        before: DateTime<Utc>,
+        exec: E,
+    ) -> Result<Vec<ProjectId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let results = sqlx::query!(
+            "
+            SELECT id
+            FROM mods
+            WHERE deleted_at < $1
+            ",
+            before,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(results.into_iter().map(|x| ProjectId(x.id)).collect())
+    }
+
+    /// Undoes a soft deletion, giving the project back the status it had before
+    pub async fn restore(
+        project: &DeletedProject,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        redis: &RedisPool,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE mods
+            SET status = COALESCE(status_before_deletion, $1), status_before_deletion = NULL,
+            deleted_at = NULL
+            WHERE id = $2
+            ",
+            ProjectStatus::Draft.as_str(),
+            project.id as ProjectId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Project::clear_cache(project.id, project.slug.clone(), Some(true), redis).await?;
+
+        Ok(())
+    }
+
     pub async fn get<'a, 'b, E>(
         string: &str,
         executor: E,
@@ -472,9 +762,112 @@ impl Project {
     where
         E: sqlx::Acquire<'a, Database = sqlx::Postgres>,
     {
-        Project::get_many(&[string], executor, redis)
-            .await
-            .map(|x| x.into_iter().next())
+        let mut exec = executor.acquire().await?;
+
+        if let Some(project) = Project::get_many(&[string], &mut *exec, redis)
+            .await?
+            .into_iter()
+            .next()
+        {
+            return Ok(Some(project));
+        }
+
+        // Slugs no project currently claims still resolve to the project that last used them
+        match Project::get_id_from_old_slug(string, &mut *exec, redis).await? {
+            Some(id) => Project::get_id(id, &mut *exec, redis).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Gets the project which most recently gave up the given slug
+    pub async fn get_id_from_old_slug<'a, E>(
+        slug: &str,
+        exec: E,
+        redis: &RedisPool,
+    ) -> Result<Option<ProjectId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let slug = slug.to_lowercase();
+        let mut redis = redis.connect().await?;
+
+        if let Some(id) = redis
+            .get_deserialized_from_json::<i64>(PROJECTS_SLUG_HISTORY_NAMESPACE, &slug)
+            .await?
+        {
+            return Ok(Some(ProjectId(id)));
+        }
+
+        let result = sqlx::query!(
+            "
+            SELECT mod_id FROM project_slug_history
+            WHERE slug = $1
+            ORDER BY changed DESC
+            LIMIT 1
+            ",
+            slug
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        if let Some(row) = &result {
+            redis
+                .set(
+                    PROJECTS_SLUG_HISTORY_NAMESPACE,
+                    &slug,
+                    &row.mod_id.to_string(),
+                    None,
+                )
+                .await?;
+        }
+
+        Ok(result.map(|r| ProjectId(r.mod_id)))
+    }
+
+    /// Records that a project gave up a slug, so that it keeps redirecting to the project
+    pub async fn insert_slug_history(
+        id: ProjectId,
+        slug: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO project_slug_history (mod_id, slug)
+            VALUES ($1, LOWER($2))
+            ",
+            id as ProjectId,
+            slug
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Whether a slug was given up by another project recently enough that it can't be claimed yet
+    pub async fn is_slug_reserved<'a, E>(
+        slug: &str,
+        except: Option<ProjectId>,
+        exec: E,
+    ) -> Result<bool, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT EXISTS(
+                SELECT 1 FROM project_slug_history
+                WHERE slug = LOWER($1) AND changed > $2 AND mod_id IS DISTINCT FROM $3
+            )
+            ",
+            slug,
+            Utc::now() - Duration::days(SLUG_RESERVATION_DAYS),
+            except.map(|x| x.0),
+        )
+        .fetch_one(exec)
+        .await?;
+
+        Ok(result.exists.unwrap_or(false))
     }
 
     pub async fn get_id<'a, 'b, E>(
@@ -523,352 +916,565 @@ impl Project {
             return Ok(Vec::new());
         }
 
-        let mut redis = redis.connect().await?;
-        let mut exec = exec.acquire().await?;
+        redis
+            .get_cached_keys(
+                PROJECTS_NAMESPACE,
+                Some(PROJECTS_SLUGS_NAMESPACE),
+                &project_strings,
+                |x| parse_base62(x).ok().map(|x| x as i64),
+                |remaining_strings| async move {
+                    let mut exec = exec.acquire().await?;
+                    let project_ids_parsed: Vec<i64> = remaining_strings
+                        .iter()
+                        .flat_map(|x| parse_base62(&x.to_string()).ok())
+                        .map(|x| x as i64)
+                        .collect();
+                    let slugs = remaining_strings
+                        .into_iter()
+                        .map(|x| x.to_lowercase())
+                        .collect::<Vec<_>>();
+
+                    let all_version_ids = DashSet::new();
+                    let versions: DashMap<ProjectId, Vec<(VersionId, DateTime<Utc>)>> = sqlx::query!(
+                        "
+                        SELECT DISTINCT mod_id, v.id as id, date_published
+                        FROM mods m
+                        INNER JOIN versions v ON m.id = v.mod_id AND v.status = ANY($3)
+                        WHERE m.id = ANY($1) OR m.slug = ANY($2)
+                        ",
+                        &project_ids_parsed,
+                        &slugs,
+                        &*crate::models::projects::VersionStatus::iterator()
+                            .filter(|x| x.is_listed())
+                            .map(|x| x.to_string())
+                            .collect::<Vec<String>>()
+                    )
+                    .fetch(&mut *exec)
+                    .try_fold(
+                        DashMap::new(),
+                        |acc: DashMap<ProjectId, Vec<(VersionId, DateTime<Utc>)>>, m| {
+                            let version_id = VersionId(m.id);
+                            let date_published = m.date_published;
+                            all_version_ids.insert(version_id);
+                            acc.entry(ProjectId(m.mod_id))
+                                .or_default()
+                                .push((version_id, date_published));
+                            async move { Ok(acc) }
+                        },
+                    )
+                    .await?;
 
-        let mut found_projects = Vec::new();
-        let mut remaining_strings = project_strings.clone();
+                    let loader_field_ids = DashSet::new();
+                    let loader_field_enum_value_ids = DashSet::new();
+                    let version_fields: DashMap<ProjectId, Vec<QueryVersionField>> = sqlx::query!(
+                        "
+                        SELECT DISTINCT mod_id, version_id, field_id, int_value, enum_value, string_value
+                        FROM versions v
+                        INNER JOIN version_fields vf ON v.id = vf.version_id
+                        WHERE v.id = ANY($1)
+                        ",
+                        &all_version_ids.iter().map(|x| x.0).collect::<Vec<_>>()
+                    )
+                    .fetch(&mut *exec)
+                    .try_fold(
+                        DashMap::new(),
+                        |acc: DashMap<ProjectId, Vec<QueryVersionField>>, m| {
+                            let qvf = QueryVersionField {
+                                version_id: VersionId(m.version_id),
+                                field_id: LoaderFieldId(m.field_id),
+                                int_value: m.int_value,
+                                enum_value: m.enum_value.map(LoaderFieldEnumValueId),
+                                string_value: m.string_value,
+                            };
+
+                            loader_field_ids.insert(LoaderFieldId(m.field_id));
+                            if let Some(enum_value) = m.enum_value {
+                                loader_field_enum_value_ids.insert(LoaderFieldEnumValueId(enum_value));
+                            }
+
+                            acc.entry(ProjectId(m.mod_id)).or_default().push(qvf);
+                            async move { Ok(acc) }
+                        },
+                    )
+                    .await?;
 
-        let mut project_ids = project_strings
-            .iter()
-            .flat_map(|x| parse_base62(&x.to_string()).map(|x| x as i64))
-            .collect::<Vec<_>>();
+                    let loader_fields: Vec<QueryLoaderField> = sqlx::query!(
+                        "
+                        SELECT DISTINCT id, field, field_type, enum_type, min_val, max_val, optional
+                        FROM loader_fields lf
+                        WHERE id = ANY($1)  
+                        ",
+                        &loader_field_ids.iter().map(|x| x.0).collect::<Vec<_>>()
+                    )
+                    .fetch(&mut *exec)
+                    .map_ok(|m| QueryLoaderField {
+                        id: LoaderFieldId(m.id),
+                        field: m.field,
+                        field_type: m.field_type,
+                        enum_type: m.enum_type.map(LoaderFieldEnumId),
+                        min_val: m.min_val,
+                        max_val: m.max_val,
+                        optional: m.optional,
+                    })
+                    .try_collect()
+                    .await?;
 
-        project_ids.append(
-            &mut redis
-                .multi_get::<i64>(
-                    PROJECTS_SLUGS_NAMESPACE,
-                    project_strings.iter().map(|x| x.to_string().to_lowercase()),
-                )
-                .await?
-                .into_iter()
-                .flatten()
-                .collect(),
-        );
-        if !project_ids.is_empty() {
-            let projects = redis
-                .multi_get::<String>(
-                    PROJECTS_NAMESPACE,
-                    project_ids.iter().map(|x| x.to_string()),
-                )
-                .await?;
-            for project in projects {
-                if let Some(project) =
-                    project.and_then(|x| serde_json::from_str::<QueryProject>(&x).ok())
-                {
-                    remaining_strings.retain(|x| {
-                        &to_base62(project.inner.id.0 as u64) != x
-                            && project.inner.slug.as_ref().map(|x| x.to_lowercase())
-                                != Some(x.to_lowercase())
-                    });
-                    found_projects.push(project);
-                    continue;
-                }
-            }
-        }
-        if !remaining_strings.is_empty() {
-            let project_ids_parsed: Vec<i64> = remaining_strings
-                .iter()
-                .flat_map(|x| parse_base62(&x.to_string()).ok())
-                .map(|x| x as i64)
-                .collect();
-            let slugs = remaining_strings
-                .into_iter()
-                .map(|x| x.to_lowercase())
-                .collect::<Vec<_>>();
-
-            let all_version_ids = DashSet::new();
-            let versions: DashMap<ProjectId, Vec<(VersionId, DateTime<Utc>)>> = sqlx::query!(
-                "
-                SELECT DISTINCT mod_id, v.id as id, date_published
-                FROM mods m
-                INNER JOIN versions v ON m.id = v.mod_id AND v.status = ANY($3)
-                WHERE m.id = ANY($1) OR m.slug = ANY($2)
-                ",
-                &project_ids_parsed,
-                &slugs,
-                &*crate::models::projects::VersionStatus::iterator()
-                    .filter(|x| x.is_listed())
-                    .map(|x| x.to_string())
-                    .collect::<Vec<String>>()
-            )
-            .fetch(&mut *exec)
-            .try_fold(
-                DashMap::new(),
-                |acc: DashMap<ProjectId, Vec<(VersionId, DateTime<Utc>)>>, m| {
-                    let version_id = VersionId(m.id);
-                    let date_published = m.date_published;
-                    all_version_ids.insert(version_id);
-                    acc.entry(ProjectId(m.mod_id))
-                        .or_default()
-                        .push((version_id, date_published));
-                    async move { Ok(acc) }
-                },
-            )
-            .await?;
+                    let loader_field_enum_values: Vec<QueryLoaderFieldEnumValue> = sqlx::query!(
+                        "
+                        SELECT DISTINCT id, enum_id, value, ordering, created, metadata
+                        FROM loader_field_enum_values lfev
+                        WHERE id = ANY($1)  
+                        ORDER BY enum_id, ordering, created DESC
+                        ",
+                        &loader_field_enum_value_ids
+                            .iter()
+                            .map(|x| x.0)
+                            .collect::<Vec<_>>()
+                    )
+                    .fetch(&mut *exec)
+                    .map_ok(|m| QueryLoaderFieldEnumValue {
+                        id: LoaderFieldEnumValueId(m.id),
+                        enum_id: LoaderFieldEnumId(m.enum_id),
+                        value: m.value,
+                        ordering: m.ordering,
+                        created: m.created,
+                        metadata: m.metadata,
+                    })
+                    .try_collect()
+                    .await?;
 
-            let loader_field_ids = DashSet::new();
-            let loader_field_enum_value_ids = DashSet::new();
-            let version_fields: DashMap<ProjectId, Vec<QueryVersionField>> = sqlx::query!(
-                "
-                SELECT DISTINCT mod_id, version_id, field_id, int_value, enum_value, string_value
-                FROM versions v
-                INNER JOIN version_fields vf ON v.id = vf.version_id
-                WHERE v.id = ANY($1)
-                ",
-                &all_version_ids.iter().map(|x| x.0).collect::<Vec<_>>()
-            )
-            .fetch(&mut *exec)
-            .try_fold(
-                DashMap::new(),
-                |acc: DashMap<ProjectId, Vec<QueryVersionField>>, m| {
-                    let qvf = QueryVersionField {
-                        version_id: VersionId(m.version_id),
-                        field_id: LoaderFieldId(m.field_id),
-                        int_value: m.int_value,
-                        enum_value: m.enum_value.map(LoaderFieldEnumValueId),
-                        string_value: m.string_value,
-                    };
-
-                    loader_field_ids.insert(LoaderFieldId(m.field_id));
-                    if let Some(enum_value) = m.enum_value {
-                        loader_field_enum_value_ids.insert(LoaderFieldEnumValueId(enum_value));
+                    let mods_gallery: DashMap<ProjectId, Vec<GalleryItem>> = sqlx::query!(
+                        "
+                        SELECT DISTINCT mod_id, mg.image_url, mg.featured, mg.name, mg.description, mg.created, mg.ordering, mg.variants,
+                        mg.image_id, ui.moderation_status
+                        FROM mods_gallery mg
+                        INNER JOIN mods m ON mg.mod_id = m.id
+                        LEFT JOIN uploaded_images ui ON ui.id = mg.image_id
+                        WHERE m.id = ANY($1) OR m.slug = ANY($2)
+                        ORDER BY mg.ordering, mg.created
+                        ",
+                        &project_ids_parsed,
+                        &slugs
+                    ).fetch(&mut *exec)
+                    .try_fold(DashMap::new(), |acc : DashMap<ProjectId, Vec<GalleryItem>>, m| {
+                            acc.entry(ProjectId(m.mod_id))
+                            .or_default()
+                            .push(GalleryItem {
+                                image_url: m.image_url,
+                                featured: m.featured.unwrap_or(false),
+                                name: m.name,
+                                description: m.description,
+                                created: m.created,
+                                ordering: m.ordering,
+                                variants: serde_json::from_value(m.variants).unwrap_or_default(),
+                                image_id: m.image_id.map(ImageId),
+                                moderation_status: m.moderation_status.map(|x| ImageModerationStatus::from_string(&x)).unwrap_or_default(),
+                            });
+                            async move { Ok(acc) }
+                        }
+                    ).await?;
+
+                    let links: DashMap<ProjectId, Vec<LinkUrl>> = sqlx::query!(
+                        "
+                        SELECT DISTINCT joining_mod_id as mod_id, ml.name, joining_platform_id as platform_id, lp.name as platform_name, url, lp.donation as donation
+                        FROM mods_links ml
+                        INNER JOIN mods m ON ml.joining_mod_id = m.id 
+                        INNER JOIN link_platforms lp ON ml.joining_platform_id = lp.id
+                        WHERE m.id = ANY($1) OR m.slug = ANY($2)
+                        ",
+                        &project_ids_parsed,
+                        &slugs
+                    ).fetch(&mut *exec)
+                    .try_fold(DashMap::new(), |acc : DashMap<ProjectId, Vec<LinkUrl>>, m| {
+                            acc.entry(ProjectId(m.mod_id))
+                            .or_default()
+                            .push(LinkUrl {
+                                name: m.name,
+                                platform_id: LinkPlatformId(m.platform_id),
+                                platform_name: m.platform_name,
+                                url: m.url,
+                                donation: m.donation,
+                            });
+                            async move { Ok(acc) }
+                        }
+                    ).await?;
+
+                    let translations: DashMap<ProjectId, Vec<ProjectTranslation>> = sqlx::query!(
+                        "
+                        SELECT pt.mod_id, pt.language, pt.name, pt.summary, pt.description
+                        FROM project_translations pt
+                        INNER JOIN mods m ON pt.mod_id = m.id
+                        WHERE m.id = ANY($1) OR m.slug = ANY($2)
+                        ORDER BY pt.language
+                        ",
+                        &project_ids_parsed,
+                        &slugs
+                    )
+                    .fetch(&mut *exec)
+                    .try_fold(
+                        DashMap::new(),
+                        |acc: DashMap<ProjectId, Vec<ProjectTranslation>>, m| {
+                            acc.entry(ProjectId(m.mod_id))
+                                .or_default()
+                                .push(ProjectTranslation {
+                                    language: m.language,
+                                    name: m.name,
+                                    summary: m.summary,
+                                    description: m.description,
+                                });
+                            async move { Ok(acc) }
+                        },
+                    )
+                    .await?;
+
+                    // Versions of any status count, since a takedown of a primary file withholds its version
+                    let withheld_file_projects: DashSet<ProjectId> = sqlx::query!(
+                        "
+                        SELECT DISTINCT v.mod_id
+                        FROM mods m
+                        INNER JOIN versions v ON v.mod_id = m.id
+                        INNER JOIN files f ON f.version_id = v.id
+                        WHERE (m.id = ANY($1) OR m.slug = ANY($2)) AND f.withheld_at IS NOT NULL
+                        ",
+                        &project_ids_parsed,
+                        &slugs
+                    )
+                    .fetch(&mut *exec)
+                    .map_ok(|m| ProjectId(m.mod_id))
+                    .try_collect()
+                    .await?;
+
+                    type StringTriple = (Vec<String>, Vec<String>, Vec<String>);
+                    let loaders_ptypes_games: DashMap<ProjectId, StringTriple> = sqlx::query!(
+                        "
+                        SELECT DISTINCT mod_id,
+                            ARRAY_AGG(DISTINCT l.loader) filter (where l.loader is not null) loaders,
+                            ARRAY_AGG(DISTINCT pt.name) filter (where pt.name is not null) project_types,
+                            ARRAY_AGG(DISTINCT g.slug) filter (where g.slug is not null) games
+                        FROM versions v
+                        INNER JOIN loaders_versions lv ON v.id = lv.version_id
+                        INNER JOIN loaders l ON lv.loader_id = l.id
+                        INNER JOIN loaders_project_types lpt ON lpt.joining_loader_id = l.id
+                        INNER JOIN project_types pt ON pt.id = lpt.joining_project_type_id
+                        INNER JOIN loaders_project_types_games lptg ON lptg.loader_id = l.id AND lptg.project_type_id = pt.id
+                        INNER JOIN games g ON lptg.game_id = g.id
+                        WHERE v.id = ANY($1)
+                        GROUP BY mod_id
+                        ",
+                        &all_version_ids.iter().map(|x| x.0).collect::<Vec<_>>()
+                    ).fetch(&mut *exec)
+                    .map_ok(|m| {
+                        let project_id = ProjectId(m.mod_id);
+                        let loaders = m.loaders.unwrap_or_default();
+                            let project_types = m.project_types.unwrap_or_default();
+                            let games = m.games.unwrap_or_default();
+
+                            (project_id, (loaders, project_types, games))
+
+                        }
+                    ).try_collect().await?;
+
+                    let db_projects: Vec<QueryProject> = sqlx::query!(
+                        "
+                        SELECT m.id id, m.name name, m.summary summary, m.downloads downloads, m.follows follows,
+                        m.icon_url icon_url, m.icon_variants icon_variants, m.description description, m.published published,
+                        m.updated updated, m.approved approved, m.queued, m.status status, m.requested_status requested_status,
+                        m.license_url license_url,
+                        m.team_id team_id, m.organization_id organization_id, m.license license, m.slug slug, m.moderation_message moderation_message, m.moderation_message_body moderation_message_body,
+                        m.webhook_sent, m.color, m.mature, m.description_source_url, m.description_fetched,
+                        t.id thread_id, m.monetization_status monetization_status,
+                        m.icon_image_id, ii.moderation_status icon_moderation_status, m.go_live_at,
+                        ARRAY_AGG(DISTINCT c.category) filter (where c.category is not null and mc.is_additional is false) categories,
+                        ARRAY_AGG(DISTINCT c.category) filter (where c.category is not null and mc.is_additional is true) additional_categories,
+                        ARRAY_AGG(DISTINCT c.category) filter (where c.category is not null and mc.is_display is true) display_categories
+                        FROM mods m                
+                        INNER JOIN threads t ON t.mod_id = m.id
+                        LEFT JOIN mods_categories mc ON mc.joining_mod_id = m.id
+                        LEFT JOIN categories c ON mc.joining_category_id = c.id
+                        LEFT JOIN uploaded_images ii ON ii.id = m.icon_image_id
+                        WHERE (m.id = ANY($1) OR m.slug = ANY($2)) AND m.deleted_at IS NULL
+                        GROUP BY t.id, m.id, ii.id;
+                        ",
+                        &project_ids_parsed,
+                        &slugs,
+                    )
+                        .fetch_many(&mut *exec)
+                        .try_filter_map(|e| async {
+                            Ok(e.right().map(|m| {
+                                let id = m.id;
+                                let project_id = ProjectId(id);
+                                let (loaders, project_types, games) = loaders_ptypes_games.remove(&project_id).map(|x| x.1).unwrap_or_default();
+                                let mut versions = versions.remove(&project_id).map(|x| x.1).unwrap_or_default();
+                                let mut gallery = mods_gallery.remove(&project_id).map(|x| x.1).unwrap_or_default();
+                                let urls = links.remove(&project_id).map(|x| x.1).unwrap_or_default();
+                                let translations = translations.remove(&project_id).map(|x| x.1).unwrap_or_default();
+                                let version_fields = version_fields.remove(&project_id).map(|x| x.1).unwrap_or_default();
+                            QueryProject {
+                                inner: Project {
+                                    id: ProjectId(id),
+                                    team_id: TeamId(m.team_id),
+                                    organization_id: m.organization_id.map(OrganizationId),
+                                    name: m.name.clone(),
+                                    summary: m.summary.clone(),
+                                    downloads: m.downloads,
+                                    icon_url: m.icon_url.clone(),
+                                    icon_variants: serde_json::from_value(m.icon_variants.clone()).unwrap_or_default(),
+                                    published: m.published,
+                                    updated: m.updated,
+                                    license_url: m.license_url.clone(),
+                                    status: ProjectStatus::from_string(
+                                        &m.status,
+                                    ),
+                                    requested_status: m.requested_status.map(|x| ProjectStatus::from_string(
+                                        &x,
+                                    )),
+                                    license: m.license.clone(),
+                                    slug: m.slug.clone(),
+                                    description: m.description.clone(),
+                                    follows: m.follows,
+                                    moderation_message: m.moderation_message,
+                                    moderation_message_body: m.moderation_message_body,
+                                    approved: m.approved,
+                                    webhook_sent: m.webhook_sent,
+                                    color: m.color.map(|x| x as u32),
+                                    queued: m.queued,
+                                    monetization_status: MonetizationStatus::from_string(
+                                        &m.monetization_status,
+                                    ),
+                                    loaders,
+                                    mature: m.mature,
+                                    description_source_url: m.description_source_url,
+                                    description_fetched: m.description_fetched,
+                                    icon_image_id: m.icon_image_id.map(ImageId),
+                                    icon_moderation_status: m.icon_moderation_status.map(|x| ImageModerationStatus::from_string(&x)).unwrap_or_default(),
+                                    go_live_at: m.go_live_at,
+                                },
+                                categories: m.categories.unwrap_or_default(),
+                                additional_categories: m.additional_categories.unwrap_or_default(),
+                                display_categories: m.display_categories.unwrap_or_default(),
+                                project_types,
+                                games,
+                                versions: {
+                                        // Each version is a tuple of (VersionId, DateTime<Utc>)
+                                        versions.sort_by(|a, b| a.1.cmp(&b.1));
+                                        versions.into_iter().map(|x| x.0).collect()
+                                    },
+                                    gallery_items: {
+                                        gallery.sort_by(|a, b| a.ordering.cmp(&b.ordering));
+                                        gallery
+                                    },
+                                    urls,
+                                    translations,
+                                has_withheld_files: withheld_file_projects.contains(&project_id),
+                                aggregate_version_fields: VersionField::from_query_json(version_fields, &loader_fields, &loader_field_enum_values, true),
+                                thread_id: ThreadId(m.thread_id),
+                                etag: String::new(),
+                            }}))
+                        })
+                        .try_collect::<Vec<QueryProject>>()
+                        .await?;
+
+                    let mut projects = Vec::new();
+                    for mut project in db_projects {
+                        // Translations are part of the body when the project is shown in their language,
+                        // and images are only part of it once they're approved
+                        project.etag = item_etag(
+                            project.inner.updated,
+                            &(
+                                crate::models::projects::Project::from(project.clone()),
+                                &project.translations,
+                                project.has_withheld_files,
+                                project.inner.icon_moderation_status,
+                                project
+                                    .gallery_items
+                                    .iter()
+                                    .map(|x| x.moderation_status)
+                                    .collect::<Vec<_>>(),
+                            ),
+                        );
+
+                        projects.push(project);
                     }
 
-                    acc.entry(ProjectId(m.mod_id)).or_default().push(qvf);
-                    async move { Ok(acc) }
+                    Ok::<_, DatabaseError>(projects)
                 },
             )
-            .await?;
+            .await
+    }
 
-            let loader_fields: Vec<QueryLoaderField> = sqlx::query!(
-                "
-                SELECT DISTINCT id, field, field_type, enum_type, min_val, max_val, optional
-                FROM loader_fields lf
-                WHERE id = ANY($1)  
-                ",
-                &loader_field_ids.iter().map(|x| x.0).collect::<Vec<_>>()
-            )
-            .fetch(&mut *exec)
-            .map_ok(|m| QueryLoaderField {
-                id: LoaderFieldId(m.id),
-                field: m.field,
-                field_type: m.field_type,
-                enum_type: m.enum_type.map(LoaderFieldEnumId),
-                min_val: m.min_val,
-                max_val: m.max_val,
-                optional: m.optional,
-            })
-            .try_collect()
-            .await?;
+    /// Counts the projects a user owns outside of organizations. Deleted and rejected projects
+    /// aren't counted.
+    pub async fn count_user_projects<'a, E>(user_id: UserId, exec: E) -> Result<i64, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let uncounted_statuses =
+            [ProjectStatus::Deleted, ProjectStatus::Rejected].map(|x| x.as_str().to_string());
 
-            let loader_field_enum_values: Vec<QueryLoaderFieldEnumValue> = sqlx::query!(
-                "
-                SELECT DISTINCT id, enum_id, value, ordering, created, metadata
-                FROM loader_field_enum_values lfev
-                WHERE id = ANY($1)  
-                ORDER BY enum_id, ordering, created DESC
-                ",
-                &loader_field_enum_value_ids
-                    .iter()
-                    .map(|x| x.0)
-                    .collect::<Vec<_>>()
-            )
-            .fetch(&mut *exec)
-            .map_ok(|m| QueryLoaderFieldEnumValue {
-                id: LoaderFieldEnumValueId(m.id),
-                enum_id: LoaderFieldEnumId(m.enum_id),
-                value: m.value,
-                ordering: m.ordering,
-                created: m.created,
-                metadata: m.metadata,
-            })
-            .try_collect()
-            .await?;
+        let count = sqlx::query!(
+            "
+            SELECT COUNT(m.id) count FROM mods m
+            INNER JOIN team_members tm ON tm.team_id = m.team_id
+            WHERE tm.user_id = $1 AND tm.is_owner AND tm.accepted
+            AND m.organization_id IS NULL AND m.deleted_at IS NULL AND NOT m.status = ANY($2)
+            ",
+            user_id as UserId,
+            &uncounted_statuses[..],
+        )
+        .fetch_one(exec)
+        .await?
+        .count;
 
-            let mods_gallery: DashMap<ProjectId, Vec<GalleryItem>> = sqlx::query!(
-                "
-                SELECT DISTINCT mod_id, mg.image_url, mg.featured, mg.name, mg.description, mg.created, mg.ordering
-                FROM mods_gallery mg
-                INNER JOIN mods m ON mg.mod_id = m.id
-                WHERE m.id = ANY($1) OR m.slug = ANY($2)
-                ",
-                &project_ids_parsed,
-                &slugs
-            ).fetch(&mut *exec)
-            .try_fold(DashMap::new(), |acc : DashMap<ProjectId, Vec<GalleryItem>>, m| {
-                    acc.entry(ProjectId(m.mod_id))
-                    .or_default()
-                    .push(GalleryItem {
-                        image_url: m.image_url,
-                        featured: m.featured.unwrap_or(false),
-                        name: m.name,
-                        description: m.description,
-                        created: m.created,
-                        ordering: m.ordering,
-                    });
-                    async move { Ok(acc) }
-                }
-            ).await?;
+        Ok(count.unwrap_or(0))
+    }
 
-            let links: DashMap<ProjectId, Vec<LinkUrl>> = sqlx::query!(
-                "
-                SELECT DISTINCT joining_mod_id as mod_id, joining_platform_id as platform_id, lp.name as platform_name, url, lp.donation as donation
-                FROM mods_links ml
-                INNER JOIN mods m ON ml.joining_mod_id = m.id 
-                INNER JOIN link_platforms lp ON ml.joining_platform_id = lp.id
-                WHERE m.id = ANY($1) OR m.slug = ANY($2)
-                ",
-                &project_ids_parsed,
-                &slugs
-            ).fetch(&mut *exec)
-            .try_fold(DashMap::new(), |acc : DashMap<ProjectId, Vec<LinkUrl>>, m| {
-                    acc.entry(ProjectId(m.mod_id))
-                    .or_default()
-                    .push(LinkUrl {
-                        platform_id: LinkPlatformId(m.platform_id),
-                        platform_name: m.platform_name,
-                        url: m.url,
-                        donation: m.donation,
-                    });
-                    async move { Ok(acc) }
-                }
-            ).await?;
-
-            type StringTriple = (Vec<String>, Vec<String>, Vec<String>);
-            let loaders_ptypes_games: DashMap<ProjectId, StringTriple> = sqlx::query!(
-                "
-                SELECT DISTINCT mod_id,
-                    ARRAY_AGG(DISTINCT l.loader) filter (where l.loader is not null) loaders,
-                    ARRAY_AGG(DISTINCT pt.name) filter (where pt.name is not null) project_types,
-                    ARRAY_AGG(DISTINCT g.slug) filter (where g.slug is not null) games
-                FROM versions v
-                INNER JOIN loaders_versions lv ON v.id = lv.version_id
-                INNER JOIN loaders l ON lv.loader_id = l.id
-                INNER JOIN loaders_project_types lpt ON lpt.joining_loader_id = l.id
-                INNER JOIN project_types pt ON pt.id = lpt.joining_project_type_id
-                INNER JOIN loaders_project_types_games lptg ON lptg.loader_id = l.id AND lptg.project_type_id = pt.id
-                INNER JOIN games g ON lptg.game_id = g.id
-                WHERE v.id = ANY($1)
-                GROUP BY mod_id
-                ",
-                &all_version_ids.iter().map(|x| x.0).collect::<Vec<_>>()
-            ).fetch(&mut *exec)
-            .map_ok(|m| {
-                let project_id = ProjectId(m.mod_id);
-                let loaders = m.loaders.unwrap_or_default();
-                    let project_types = m.project_types.unwrap_or_default();
-                    let games = m.games.unwrap_or_default();
+    /// Counts the projects of an organization. Deleted and rejected projects aren't counted.
+    pub async fn count_organization_projects<'a, E>(
+        organization_id: OrganizationId,
+        exec: E,
+    ) -> Result<i64, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let uncounted_statuses =
+            [ProjectStatus::Deleted, ProjectStatus::Rejected].map(|x| x.as_str().to_string());
 
-                    (project_id, (loaders, project_types, games))
+        let count = sqlx::query!(
+            "
+            SELECT COUNT(m.id) count FROM mods m
+            WHERE m.organization_id = $1 AND m.deleted_at IS NULL AND NOT m.status = ANY($2)
+            ",
+            organization_id as OrganizationId,
+            &uncounted_statuses[..],
+        )
+        .fetch_one(exec)
+        .await?
+        .count;
 
-                }
-            ).try_collect().await?;
+        Ok(count.unwrap_or(0))
+    }
 
-            let db_projects: Vec<QueryProject> = sqlx::query!(
-                "
-                SELECT m.id id, m.name name, m.summary summary, m.downloads downloads, m.follows follows,
-                m.icon_url icon_url, m.description description, m.published published,
-                m.updated updated, m.approved approved, m.queued, m.status status, m.requested_status requested_status,
-                m.license_url license_url,
-                m.team_id team_id, m.organization_id organization_id, m.license license, m.slug slug, m.moderation_message moderation_message, m.moderation_message_body moderation_message_body,
-                m.webhook_sent, m.color,
-                t.id thread_id, m.monetization_status monetization_status,
-                ARRAY_AGG(DISTINCT c.category) filter (where c.category is not null and mc.is_additional is false) categories,
-                ARRAY_AGG(DISTINCT c.category) filter (where c.category is not null and mc.is_additional is true) additional_categories
-                FROM mods m                
-                INNER JOIN threads t ON t.mod_id = m.id
-                LEFT JOIN mods_categories mc ON mc.joining_mod_id = m.id
-                LEFT JOIN categories c ON mc.joining_category_id = c.id
-                WHERE m.id = ANY($1) OR m.slug = ANY($2)
-                GROUP BY t.id, m.id;
-                ",
-                &project_ids_parsed,
-                &slugs,
+    /// Gets a page of the projects awaiting review, along with the total number of them
+    pub async fn get_moderation_queue<'a, E>(
+        project_type: Option<&str>,
+        sort: ModerationQueueSort,
+        count: i64,
+        offset: i64,
+        exec: E,
+    ) -> Result<(i64, Vec<ModerationQueueEntry>), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let open_statuses =
+            [ReportStatus::Open, ReportStatus::UnderReview].map(|x| x.as_str().to_string());
+
+        // The page is left joined onto the total so the total is returned even for empty pages
+        let rows = sqlx::query!(
+            "
+            WITH queue AS (
+                SELECT m.id, m.team_id, m.downloads, COALESCE(m.queued, m.published) queued
+                FROM mods m
+                WHERE m.status = $1 AND ($2::text IS NULL OR EXISTS (
+                    SELECT 1 FROM versions v
+                    INNER JOIN loaders_versions lv ON lv.version_id = v.id
+                    INNER JOIN loaders_project_types lpt ON lpt.joining_loader_id = lv.loader_id
+                    INNER JOIN project_types pt ON pt.id = lpt.joining_project_type_id
+                    WHERE v.mod_id = m.id AND pt.name = $2
+                ))
             )
-                .fetch_many(&mut *exec)
-                .try_filter_map(|e| async {
-                    Ok(e.right().map(|m| {
-                        let id = m.id;
-                        let project_id = ProjectId(id);
-                        let (loaders, project_types, games) = loaders_ptypes_games.remove(&project_id).map(|x| x.1).unwrap_or_default();
-                        let mut versions = versions.remove(&project_id).map(|x| x.1).unwrap_or_default();
-                        let mut gallery = mods_gallery.remove(&project_id).map(|x| x.1).unwrap_or_default();
-                        let urls = links.remove(&project_id).map(|x| x.1).unwrap_or_default();
-                        let version_fields = version_fields.remove(&project_id).map(|x| x.1).unwrap_or_default();
-                    QueryProject {
-                        inner: Project {
-                            id: ProjectId(id),
-                            team_id: TeamId(m.team_id),
-                            organization_id: m.organization_id.map(OrganizationId),
-                            name: m.name.clone(),
-                            summary: m.summary.clone(),
-                            downloads: m.downloads,
-                            icon_url: m.icon_url.clone(),
-                            published: m.published,
-                            updated: m.updated,
-                            license_url: m.license_url.clone(),
-                            status: ProjectStatus::from_string(
-                                &m.status,
-                            ),
-                            requested_status: m.requested_status.map(|x| ProjectStatus::from_string(
-                                &x,
-                            )),
-                            license: m.license.clone(),
-                            slug: m.slug.clone(),
-                            description: m.description.clone(),
-                            follows: m.follows,
-                            moderation_message: m.moderation_message,
-                            moderation_message_body: m.moderation_message_body,
-                            approved: m.approved,
-                            webhook_sent: m.webhook_sent,
-                            color: m.color.map(|x| x as u32),
-                            queued: m.queued,
-                            monetization_status: MonetizationStatus::from_string(
-                                &m.monetization_status,
-                            ),
-                            loaders,
-                        },
-                        categories: m.categories.unwrap_or_default(),
-                        additional_categories: m.additional_categories.unwrap_or_default(),
-                        project_types,
-                        games,
-                        versions: {
-                                // Each version is a tuple of (VersionId, DateTime<Utc>)
-                                versions.sort_by(|a, b| a.1.cmp(&b.1));
-                                versions.into_iter().map(|x| x.0).collect()
-                            },
-                            gallery_items: {
-                                gallery.sort_by(|a, b| a.ordering.cmp(&b.ordering));
-                                gallery
-                            },
-                            urls,
-                        aggregate_version_fields: VersionField::from_query_json(version_fields, &loader_fields, &loader_field_enum_values, true),
-                        thread_id: ThreadId(m.thread_id),
-                    }}))
+            SELECT t.total, p.id, p.queued, p.open_reports, p.flagged_reupload
+            FROM (SELECT COUNT(*) total FROM queue) t
+            LEFT JOIN LATERAL (
+                SELECT q.id, q.queued, (
+                    SELECT COUNT(DISTINCT r.id) FROM reports r
+                    INNER JOIN team_members tm ON tm.user_id = r.user_id
+                    WHERE tm.team_id = q.team_id AND r.status = ANY($4)
+                ) open_reports, EXISTS (
+                    SELECT 1 FROM versions v
+                    WHERE v.mod_id = q.id AND v.flagged_reupload = TRUE
+                ) flagged_reupload
+                FROM queue q
+                ORDER BY
+                    CASE WHEN $3 = 'downloads' THEN q.downloads END DESC,
+                    CASE WHEN $3 = 'newest' THEN q.queued END DESC,
+                    q.queued ASC
+                LIMIT $5 OFFSET $6
+            ) p ON TRUE
+            ",
+            ProjectStatus::Processing.as_str(),
+            project_type,
+            sort.as_str(),
+            &open_statuses[..],
+            count,
+            offset,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        let total = rows.first().and_then(|x| x.total).unwrap_or(0);
+        let entries = rows
+            .into_iter()
+            .filter_map(|x| {
+                Some(ModerationQueueEntry {
+                    id: ProjectId(x.id?),
+                    queued: x.queued?,
+                    open_reports: x.open_reports.unwrap_or(0),
+                    flagged_reupload: x.flagged_reupload.unwrap_or(false),
                 })
-                .try_collect::<Vec<QueryProject>>()
-                .await?;
+            })
+            .collect();
 
-            for project in db_projects {
-                redis
-                    .set_serialized_to_json(PROJECTS_NAMESPACE, project.inner.id.0, &project, None)
-                    .await?;
-                if let Some(slug) = &project.inner.slug {
-                    redis
-                        .set(
-                            PROJECTS_SLUGS_NAMESPACE,
-                            &slug.to_lowercase(),
-                            &project.inner.id.0.to_string(),
-                            None,
-                        )
-                        .await?;
-                }
-                found_projects.push(project);
-            }
-        }
+        Ok((total, entries))
+    }
+
+    /// Declares `cursor` over the whole moderation queue, with the same filters and order as
+    /// `get_moderation_queue`. The rows are `ModerationQueueExportRow`s.
+    pub async fn declare_moderation_queue_export(
+        cursor: &str,
+        project_type: Option<&str>,
+        sort: ModerationQueueSort,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        let open_statuses =
+            [ReportStatus::Open, ReportStatus::UnderReview].map(|x| x.as_str().to_string());
+
+        sqlx::query(&format!(
+            "
+            DECLARE {cursor} NO SCROLL CURSOR FOR
+            SELECT m.id, m.slug, m.name, COALESCE(m.queued, m.published) queued, m.downloads,
+                u.username owner, o.name organization, (
+                    SELECT COUNT(DISTINCT r.id) FROM reports r
+                    INNER JOIN team_members rtm ON rtm.user_id = r.user_id
+                    WHERE rtm.team_id = m.team_id AND r.status = ANY($3)
+                ) open_reports, EXISTS (
+                    SELECT 1 FROM versions fv
+                    WHERE fv.mod_id = m.id AND fv.flagged_reupload = TRUE
+                ) flagged_reupload
+            FROM mods m
+            LEFT JOIN team_members tm ON tm.team_id = m.team_id AND tm.is_owner = TRUE AND tm.accepted = TRUE
+            LEFT JOIN users u ON u.id = tm.user_id
+            LEFT JOIN organizations o ON o.id = m.organization_id
+            WHERE m.status = $1 AND ($2::text IS NULL OR EXISTS (
+                SELECT 1 FROM versions v
+                INNER JOIN loaders_versions lv ON lv.version_id = v.id
+                INNER JOIN loaders_project_types lpt ON lpt.joining_loader_id = lv.loader_id
+                INNER JOIN project_types pt ON pt.id = lpt.joining_project_type_id
+                WHERE v.mod_id = m.id AND pt.name = $2
+            ))
+            ORDER BY
+                CASE WHEN $4 = 'downloads' THEN m.downloads END DESC,
+                CASE WHEN $4 = 'newest' THEN COALESCE(m.queued, m.published) END DESC,
+                COALESCE(m.queued, m.published) ASC, m.id ASC
+            "
+        ))
+        .bind(ProjectStatus::Processing.as_str())
+        .bind(project_type)
+        .bind(&open_statuses[..])
+        .bind(sort.as_str())
+        .execute(&mut **transaction)
+        .await?;
 
-        Ok(found_projects)
+        Ok(())
     }
 
     pub async fn get_dependencies<'a, E>(
@@ -934,10 +1540,26 @@ impl Project {
     ) -> Result<(), DatabaseError> {
         let mut redis = redis.connect().await?;
 
+        // The project may have been created or given the slug since it was looked up
+        redis
+            .delete_missing(
+                PROJECTS_NAMESPACE,
+                Some(PROJECTS_SLUGS_NAMESPACE),
+                id.0,
+                slug.as_deref(),
+            )
+            .await?;
         redis
             .delete_many([
                 (PROJECTS_NAMESPACE, Some(id.0.to_string())),
-                (PROJECTS_SLUGS_NAMESPACE, slug.map(|x| x.to_lowercase())),
+                (
+                    PROJECTS_SLUGS_NAMESPACE,
+                    slug.as_ref().map(|x| x.to_lowercase()),
+                ),
+                (
+                    PROJECTS_SLUG_HISTORY_NAMESPACE,
+                    slug.map(|x| x.to_lowercase()),
+                ),
                 (
                     PROJECTS_DEPENDENCIES_NAMESPACE,
                     if clear_dependencies.unwrap_or(false) {
@@ -952,16 +1574,106 @@ impl Project {
     }
 }
 
+/// A project which was soft deleted and can still be restored
+#[derive(Clone, Debug)]
+pub struct DeletedProject {
+    pub id: ProjectId,
+    pub slug: Option<String>,
+    pub team_id: TeamId,
+    pub organization_id: Option<OrganizationId>,
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationQueueSort {
+    /// Projects which have been waiting the longest first
+    #[default]
+    Oldest,
+    Newest,
+    /// Projects with the most downloads first
+    Downloads,
+}
+
+impl ModerationQueueSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationQueueSort::Oldest => "oldest",
+            ModerationQueueSort::Newest => "newest",
+            ModerationQueueSort::Downloads => "downloads",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ModerationQueueEntry {
+    pub id: ProjectId,
+    /// When the project was submitted for review
+    pub queued: DateTime<Utc>,
+    /// Open reports against members of the project's team
+    pub open_reports: i64,
+    /// Whether any of the project's versions are flagged as reuploads
+    pub flagged_reupload: bool,
+}
+
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct ModerationQueueExportRow {
+    pub id: ProjectId,
+    pub slug: Option<String>,
+    pub name: String,
+    pub queued: DateTime<Utc>,
+    pub downloads: i32,
+    /// The username of the owner, unless the project is in an organization
+    pub owner: Option<String>,
+    pub organization: Option<String>,
+    pub open_reports: i64,
+    pub flagged_reupload: bool,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct QueryProject {
     pub inner: Project,
     pub categories: Vec<String>,
     pub additional_categories: Vec<String>,
+    /// The categories shown on the project in place of all of them, if it has any
+    #[serde(default)]
+    pub display_categories: Vec<String>,
     pub versions: Vec<VersionId>,
     pub project_types: Vec<String>,
     pub games: Vec<String>,
     pub urls: Vec<LinkUrl>,
     pub gallery_items: Vec<GalleryItem>,
+    #[serde(default)]
+    pub translations: Vec<ProjectTranslation>,
+    /// Whether any of the project's files were taken down by a moderator
+    #[serde(default)]
+    pub has_withheld_files: bool,
     pub thread_id: ThreadId,
     pub aggregate_version_fields: Vec<VersionField>,
+    /// Computed when the project is cached so conditional requests don't need to serialize it
+    pub etag: String,
+}
+
+impl CachedEntry for QueryProject {
+    fn cache_id(&self) -> i64 {
+        self.inner.id.0
+    }
+
+    fn cache_slug(&self) -> Option<String> {
+        self.inner.slug.clone()
+    }
+}
+
+impl QueryProject {
+    /// Removes the gallery images and icon the user can't see. `elevated` users, the project's
+    /// team and moderators, see images waiting for approval.
+    pub fn hide_unmoderated_images(&mut self, elevated: bool) {
+        self.gallery_items
+            .retain(|x| x.moderation_status.is_visible(elevated));
+
+        if !self.inner.icon_moderation_status.is_visible(elevated) {
+            self.inner.icon_url = None;
+            self.inner.icon_variants = HashMap::new();
+        }
+    }
 }
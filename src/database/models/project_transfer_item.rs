@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{DatabaseError, OrganizationId, ProjectId, UserId};
+use crate::models::project_transfers::ProjectTransferStatus;
+use crate::models::teams::ProjectPermissions;
+
+/// A request to hand the ownership of a project to a user or an organization. A project has at
+/// most one pending transfer, so transfers are looked up by their project.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ProjectTransfer {
+    pub project_id: ProjectId,
+    pub initiated_by: UserId,
+    pub target_user_id: Option<UserId>,
+    pub target_organization_id: Option<OrganizationId>,
+    pub previous_owner_permissions: ProjectPermissions,
+    pub status: ProjectTransferStatus,
+    pub created: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+}
+
+impl ProjectTransfer {
+    pub async fn insert(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO project_transfers (
+                mod_id, initiated_by, target_user_id, target_organization_id,
+                previous_owner_permissions, status, created, expires
+            )
+            VALUES (
+                $1, $2, $3, $4,
+                $5, $6, $7, $8
+            )
+            ",
+            self.project_id as ProjectId,
+            self.initiated_by as UserId,
+            self.target_user_id.map(|x| x.0),
+            self.target_organization_id.map(|x| x.0),
+            self.previous_owner_permissions.bits() as i64,
+            self.status.as_str(),
+            self.created,
+            self.expires,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the project's pending transfer, unless it has expired
+    pub async fn get_pending<'a, E>(
+        project_id: ProjectId,
+        exec: E,
+    ) -> Result<Option<ProjectTransfer>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT mod_id, initiated_by, target_user_id, target_organization_id,
+            previous_owner_permissions, status, created, expires
+            FROM project_transfers
+            WHERE mod_id = $1 AND status = 'pending' AND expires > NOW()
+            ",
+            project_id as ProjectId,
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.map(|r| ProjectTransfer {
+            project_id: ProjectId(r.mod_id),
+            initiated_by: UserId(r.initiated_by),
+            target_user_id: r.target_user_id.map(UserId),
+            target_organization_id: r.target_organization_id.map(OrganizationId),
+            previous_owner_permissions: ProjectPermissions::from_bits(
+                r.previous_owner_permissions as u64,
+            )
+            .unwrap_or_default(),
+            status: ProjectTransferStatus::from_string(&r.status),
+            created: r.created,
+            expires: r.expires,
+        }))
+    }
+
+    /// Marks the project's pending transfer as expired if its recipient didn't answer in time,
+    /// so that a new one can be started
+    pub async fn expire_stale(
+        project_id: ProjectId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE project_transfers
+            SET status = 'expired', resolved = expires
+            WHERE mod_id = $1 AND status = 'pending' AND expires <= NOW()
+            ",
+            project_id as ProjectId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Resolves the project's pending transfer with the status. Returns whether there still was
+    /// a pending transfer to resolve, as it may have been resolved concurrently.
+    pub async fn resolve(
+        project_id: ProjectId,
+        status: ProjectTransferStatus,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            UPDATE project_transfers
+            SET status = $2, resolved = NOW()
+            WHERE mod_id = $1 AND status = 'pending' AND expires > NOW()
+            ",
+            project_id as ProjectId,
+            status.as_str(),
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
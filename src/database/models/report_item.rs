@@ -1,4 +1,5 @@
 use super::ids::*;
+use crate::models::reports::ReportStatus;
 use chrono::{DateTime, Utc};
 
 pub struct Report {
@@ -6,7 +7,11 @@ pub struct Report {
     pub report_type_id: ReportTypeId,
     pub project_id: Option<ProjectId>,
     pub version_id: Option<VersionId>,
+    /// The hash of the file the report is about, if it's about one of the version's files
+    pub file_hash: Option<String>,
     pub user_id: Option<UserId>,
+    /// Another project the report is about, such as the project a file was reuploaded from
+    pub related_project_id: Option<ProjectId>,
     pub body: String,
     pub reporter: UserId,
     pub created: DateTime<Utc>,
@@ -18,11 +23,16 @@ pub struct QueryReport {
     pub report_type: String,
     pub project_id: Option<ProjectId>,
     pub version_id: Option<VersionId>,
+    /// The hash of the file the report is about, if it's about one of the version's files
+    pub file_hash: Option<String>,
     pub user_id: Option<UserId>,
+    pub related_project_id: Option<ProjectId>,
     pub body: String,
     pub reporter: UserId,
     pub created: DateTime<Utc>,
     pub closed: bool,
+    pub status: ReportStatus,
+    pub assigned_moderator: Option<UserId>,
     pub thread_id: ThreadId,
 }
 
@@ -35,11 +45,11 @@ impl Report {
             "
             INSERT INTO reports (
                 id, report_type_id, mod_id, version_id, user_id,
-                body, reporter
+                body, reporter, related_mod_id, file_hash
             )
             VALUES (
                 $1, $2, $3, $4, $5,
-                $6, $7
+                $6, $7, $8, $9
             )
             ",
             self.id as ReportId,
@@ -48,7 +58,9 @@ impl Report {
             self.version_id.map(|x| x.0 as i64),
             self.user_id.map(|x| x.0 as i64),
             self.body,
-            self.reporter as UserId
+            self.reporter as UserId,
+            self.related_project_id.map(|x| x.0 as i64),
+            self.file_hash,
         )
         .execute(&mut **transaction)
         .await?;
@@ -77,7 +89,7 @@ impl Report {
         let report_ids_parsed: Vec<i64> = report_ids.iter().map(|x| x.0).collect();
         let reports = sqlx::query!(
             "
-            SELECT r.id, rt.name, r.mod_id, r.version_id, r.user_id, r.body, r.reporter, r.created, t.id thread_id, r.closed
+            SELECT r.id, rt.name, r.mod_id, r.version_id, r.user_id, r.related_mod_id, r.body, r.reporter, r.created, t.id thread_id, r.closed, r.status, r.assigned_moderator, r.file_hash
             FROM reports r
             INNER JOIN report_types rt ON rt.id = r.report_type_id
             INNER JOIN threads t ON t.report_id = r.id
@@ -93,11 +105,15 @@ impl Report {
                 report_type: x.name,
                 project_id: x.mod_id.map(ProjectId),
                 version_id: x.version_id.map(VersionId),
+                file_hash: x.file_hash,
                 user_id: x.user_id.map(UserId),
+                related_project_id: x.related_mod_id.map(ProjectId),
                 body: x.body,
                 reporter: UserId(x.reporter),
                 created: x.created,
                 closed: x.closed,
+                status: ReportStatus::from_string(&x.status),
+                assigned_moderator: x.assigned_moderator.map(UserId),
                 thread_id: ThreadId(x.thread_id)
             }))
         })
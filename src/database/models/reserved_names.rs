@@ -0,0 +1,66 @@
+use chrono::{Duration, Utc};
+
+use super::project_item::SLUG_RESERVATION_DAYS;
+use super::{DatabaseError, OrganizationId, UserId};
+
+/// What holds a name in the namespace usernames and organization slugs share, as both are used
+/// for `/@{name}` pages
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameHolder {
+    User,
+    Organization,
+}
+
+impl NameHolder {
+    pub fn conflict_message(&self, name: &str) -> String {
+        match self {
+            NameHolder::User => format!("{name} is already the username of a user!"),
+            NameHolder::Organization => {
+                format!("{name} is already the slug of another organization!")
+            }
+        }
+    }
+}
+
+/// Gets what already holds the name, ignoring the user or organization it's being given to.
+/// Slugs an organization gave up recently are still held by it, as they keep resolving to it.
+pub async fn get_name_holder<'a, E>(
+    name: &str,
+    user_id: Option<UserId>,
+    organization_id: Option<OrganizationId>,
+    exec: E,
+) -> Result<Option<NameHolder>, DatabaseError>
+where
+    E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    let result = sqlx::query!(
+        "
+        SELECT
+            EXISTS(
+                SELECT 1 FROM users
+                WHERE LOWER(username) = LOWER($1) AND id IS DISTINCT FROM $2
+            ) user_exists,
+            EXISTS(
+                SELECT 1 FROM organizations
+                WHERE LOWER(slug) = LOWER($1) AND id IS DISTINCT FROM $3
+                UNION ALL
+                SELECT 1 FROM organization_slug_history
+                WHERE slug = LOWER($1) AND changed > $4 AND organization_id IS DISTINCT FROM $3
+            ) organization_exists
+        ",
+        name,
+        user_id.map(|x| x.0),
+        organization_id.map(|x| x.0),
+        Utc::now() - Duration::days(SLUG_RESERVATION_DAYS),
+    )
+    .fetch_one(exec)
+    .await?;
+
+    Ok(if result.user_exists.unwrap_or(false) {
+        Some(NameHolder::User)
+    } else if result.organization_exists.unwrap_or(false) {
+        Some(NameHolder::Organization)
+    } else {
+        None
+    })
+}
@@ -1,7 +1,7 @@
 use super::ids::*;
 use crate::database::models::DatabaseError;
-use crate::database::redis::RedisPool;
-use crate::models::ids::base62_impl::{parse_base62, to_base62};
+use crate::database::redis::{CachedEntry, RedisPool};
+use crate::models::ids::base62_impl::parse_base62;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -130,109 +130,57 @@ impl Session {
     {
         use futures::TryStreamExt;
 
-        let mut redis = redis.connect().await?;
-
-        if session_strings.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        let mut found_sessions = Vec::new();
-        let mut remaining_strings = session_strings
-            .iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<_>>();
-
-        let mut session_ids = session_strings
-            .iter()
-            .flat_map(|x| parse_base62(&x.to_string()).map(|x| x as i64))
-            .collect::<Vec<_>>();
-
-        session_ids.append(
-            &mut redis
-                .multi_get::<i64>(
-                    SESSIONS_IDS_NAMESPACE,
-                    session_strings.iter().map(|x| x.to_string()),
-                )
-                .await?
-                .into_iter()
-                .flatten()
-                .collect(),
-        );
-
-        if !session_ids.is_empty() {
-            let sessions = redis
-                .multi_get::<String>(
-                    SESSIONS_NAMESPACE,
-                    session_ids.iter().map(|x| x.to_string()),
-                )
-                .await?;
-            for session in sessions {
-                if let Some(session) =
-                    session.and_then(|x| serde_json::from_str::<Session>(&x).ok())
-                {
-                    remaining_strings
-                        .retain(|x| &to_base62(session.id.0 as u64) != x && &session.session != x);
-                    found_sessions.push(session);
-                    continue;
-                }
-            }
-        }
-
-        if !remaining_strings.is_empty() {
-            let session_ids_parsed: Vec<i64> = remaining_strings
-                .iter()
-                .flat_map(|x| parse_base62(&x.to_string()).ok())
-                .map(|x| x as i64)
-                .collect();
-            let db_sessions: Vec<Session> = sqlx::query!(
-                "
-                SELECT id, user_id, session, created, last_login, expires, refresh_expires, os, platform,
-                city, country, ip, user_agent
-                FROM sessions
-                WHERE id = ANY($1) OR session = ANY($2)
-                ORDER BY created DESC
-                ",
-                &session_ids_parsed,
-                &remaining_strings.into_iter().map(|x| x.to_string()).collect::<Vec<_>>(),
-            )
-                .fetch_many(exec)
-                .try_filter_map(|e| async {
-                    Ok(e.right().map(|x| Session {
-                        id: SessionId(x.id),
-                        session: x.session,
-                        user_id: UserId(x.user_id),
-                        created: x.created,
-                        last_login: x.last_login,
-                        expires: x.expires,
-                        refresh_expires: x.refresh_expires,
-                        os: x.os,
-                        platform: x.platform,
-                        city: x.city,
-                        country: x.country,
-                        ip: x.ip,
-                        user_agent: x.user_agent,
-                    }))
-                })
-                .try_collect::<Vec<Session>>()
-                .await?;
-
-            for session in db_sessions {
-                redis
-                    .set_serialized_to_json(SESSIONS_NAMESPACE, session.id.0, &session, None)
-                    .await?;
-                redis
-                    .set(
-                        SESSIONS_IDS_NAMESPACE,
-                        &session.session,
-                        &session.id.0.to_string(),
-                        None,
+        redis
+            .get_cached_keys(
+                SESSIONS_NAMESPACE,
+                Some(SESSIONS_IDS_NAMESPACE),
+                &session_strings
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>(),
+                |x| parse_base62(x).ok().map(|x| x as i64),
+                |remaining_strings| async move {
+                    let session_ids_parsed: Vec<i64> = remaining_strings
+                        .iter()
+                        .flat_map(|x| parse_base62(&x.to_string()).ok())
+                        .map(|x| x as i64)
+                        .collect();
+                    let db_sessions: Vec<Session> = sqlx::query!(
+                        "
+                        SELECT id, user_id, session, created, last_login, expires, refresh_expires, os, platform,
+                        city, country, ip, user_agent
+                        FROM sessions
+                        WHERE id = ANY($1) OR session = ANY($2)
+                        ORDER BY created DESC
+                        ",
+                        &session_ids_parsed,
+                        &remaining_strings,
                     )
+                    .fetch_many(exec)
+                    .try_filter_map(|e| async {
+                        Ok(e.right().map(|x| Session {
+                            id: SessionId(x.id),
+                            session: x.session,
+                            user_id: UserId(x.user_id),
+                            created: x.created,
+                            last_login: x.last_login,
+                            expires: x.expires,
+                            refresh_expires: x.refresh_expires,
+                            os: x.os,
+                            platform: x.platform,
+                            city: x.city,
+                            country: x.country,
+                            ip: x.ip,
+                            user_agent: x.user_agent,
+                        }))
+                    })
+                    .try_collect::<Vec<Session>>()
                     .await?;
-                found_sessions.push(session);
-            }
-        }
 
-        Ok(found_sessions)
+                    Ok(db_sessions)
+                },
+            )
+            .await
     }
 
     pub async fn get_user_sessions<'a, E>(
@@ -320,3 +268,17 @@ impl Session {
         Ok(Some(()))
     }
 }
+
+impl CachedEntry for Session {
+    fn cache_id(&self) -> i64 {
+        self.id.0
+    }
+
+    fn cache_slug(&self) -> Option<String> {
+        Some(self.session.clone())
+    }
+
+    fn slug_key(slug: &str) -> String {
+        slug.to_string()
+    }
+}
@@ -1,8 +1,9 @@
 use super::{ids::*, Organization, Project};
 use crate::{
-    database::redis::RedisPool,
+    database::redis::{CachedEntry, RedisPool},
     models::teams::{OrganizationPermissions, ProjectPermissions},
 };
+use chrono::{DateTime, Utc};
 use itertools::Itertools;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -85,8 +86,10 @@ impl TeamBuilder {
             .multiunzip();
         sqlx::query!(
             "
-            INSERT INTO team_members (id, team_id, user_id, role, is_owner, permissions, organization_permissions, accepted, payouts_split, ordering)
-            SELECT * FROM UNNEST ($1::int8[], $2::int8[], $3::int8[], $4::varchar[], $5::bool[], $6::int8[], $7::int8[], $8::bool[], $9::numeric[], $10::int8[])
+            INSERT INTO team_members (id, team_id, user_id, role, is_owner, permissions, organization_permissions, accepted, payouts_split, ordering, accepted_at)
+            SELECT *, CASE WHEN u.accepted THEN NOW() END
+            FROM UNNEST ($1::int8[], $2::int8[], $3::int8[], $4::varchar[], $5::bool[], $6::int8[], $7::int8[], $8::bool[], $9::numeric[], $10::int8[])
+                AS u(id, team_id, user_id, role, is_owner, permissions, organization_permissions, accepted, payouts_split, ordering)
             ",
             &team_member_ids[..],
             &team_ids[..],
@@ -130,7 +133,7 @@ impl Team {
             "
             SELECT m.id AS pid, NULL AS oid
             FROM mods m
-            WHERE m.team_id = $1
+            WHERE m.team_id = $1 AND m.deleted_at IS NULL
             
             UNION ALL
              
@@ -180,6 +183,34 @@ pub struct TeamMember {
     pub accepted: bool,
     pub payouts_split: Decimal,
     pub ordering: i64,
+
+    /// When the user was invited or added to the team
+    pub created: DateTime<Utc>,
+    /// When the user accepted their invite, unset for pending members
+    pub accepted_at: Option<DateTime<Utc>>,
+    /// The user who invited this member, if they were invited
+    pub invited_by: Option<UserId>,
+    /// Whether the member's permissions were taken away because they didn't enable 2FA, which
+    /// their organization requires
+    #[serde(default)]
+    pub two_factor_restricted: bool,
+    /// The organization role template the member's permissions were last set from, unless
+    /// they've been edited by hand since
+    #[serde(default)]
+    pub role_template_id: Option<OrganizationRoleTemplateId>,
+}
+
+// The members of a team are cached in a single entry
+#[derive(Deserialize, Serialize)]
+struct CachedTeamMembers {
+    id: TeamId,
+    members: Vec<TeamMember>,
+}
+
+impl CachedEntry for CachedTeamMembers {
+    fn cache_id(&self) -> i64 {
+        self.id.0
+    }
 }
 
 impl TeamMember {
@@ -205,86 +236,94 @@ impl TeamMember {
     {
         use futures::stream::TryStreamExt;
 
-        if team_ids.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        let mut redis = redis.connect().await?;
-
-        let mut team_ids_parsed: Vec<i64> = team_ids.iter().map(|x| x.0).collect();
-
-        let mut found_teams = Vec::new();
-
-        let teams = redis
-            .multi_get::<String>(
+        let teams: Vec<CachedTeamMembers> = redis
+            .get_cached_keys(
                 TEAMS_NAMESPACE,
-                team_ids_parsed.iter().map(|x| x.to_string()),
-            )
-            .await?;
-
-        for team_raw in teams {
-            if let Some(mut team) = team_raw
-                .clone()
-                .and_then(|x| serde_json::from_str::<Vec<TeamMember>>(&x).ok())
-            {
-                if let Some(team_id) = team.first().map(|x| x.team_id) {
-                    team_ids_parsed.retain(|x| &team_id.0 != x);
-                }
-
-                found_teams.append(&mut team);
-                continue;
-            }
-        }
+                None,
+                &team_ids.iter().map(|x| x.0.to_string()).collect::<Vec<_>>(),
+                |x| x.parse().ok(),
+                |remaining_ids| async move {
+                    let team_ids_parsed: Vec<i64> =
+                        remaining_ids.iter().flat_map(|x| x.parse().ok()).collect();
+                    let teams: Vec<TeamMember> = sqlx::query!(
+                        "
+                        SELECT id, team_id, role AS member_role, is_owner, permissions, organization_permissions,
+                        accepted, payouts_split,
+                        ordering, user_id, created, accepted_at, invited_by, two_factor_restricted, role_template_id
+                        FROM team_members
+                        WHERE team_id = ANY($1)
+                        ORDER BY team_id, ordering;
+                        ",
+                        &team_ids_parsed
+                    )
+                    .fetch_many(exec)
+                    .try_filter_map(|e| async {
+                        Ok(e.right().map(|m| TeamMember {
+                            id: TeamMemberId(m.id),
+                            team_id: TeamId(m.team_id),
+                            role: m.member_role,
+                            is_owner: m.is_owner,
+                            permissions: ProjectPermissions::from_bits(m.permissions as u64)
+                                .unwrap_or_default(),
+                            organization_permissions: m.organization_permissions.map(|p| {
+                                OrganizationPermissions::from_bits(p as u64).unwrap_or_default()
+                            }),
+                            accepted: m.accepted,
+                            user_id: UserId(m.user_id),
+                            payouts_split: m.payouts_split,
+                            ordering: m.ordering,
+                            created: m.created,
+                            accepted_at: m.accepted_at,
+                            invited_by: m.invited_by.map(UserId),
+                            two_factor_restricted: m.two_factor_restricted,
+                            role_template_id: m.role_template_id.map(OrganizationRoleTemplateId),
+                        }))
+                    })
+                    .try_collect::<Vec<TeamMember>>()
+                    .await?;
 
-        if !team_ids_parsed.is_empty() {
-            let teams: Vec<TeamMember> = sqlx::query!(
-                "
-                SELECT id, team_id, role AS member_role, is_owner, permissions, organization_permissions,
-                accepted, payouts_split, 
-                ordering, user_id
-                FROM team_members
-                WHERE team_id = ANY($1)
-                ORDER BY team_id, ordering;
-                ",
-                &team_ids_parsed
+                    Ok(teams
+                        .into_iter()
+                        .group_by(|x| x.team_id)
+                        .into_iter()
+                        .map(|(id, members)| CachedTeamMembers {
+                            id,
+                            members: members.collect(),
+                        })
+                        .collect())
+                },
             )
-            .fetch_many(exec)
-            .try_filter_map(|e| async {
-                Ok(e.right().map(|m| TeamMember {
-                    id: TeamMemberId(m.id),
-                    team_id: TeamId(m.team_id),
-                    role: m.member_role,
-                    is_owner: m.is_owner,
-                    permissions: ProjectPermissions::from_bits(m.permissions as u64)
-                        .unwrap_or_default(),
-                    organization_permissions: m
-                        .organization_permissions
-                        .map(|p| OrganizationPermissions::from_bits(p as u64).unwrap_or_default()),
-                    accepted: m.accepted,
-                    user_id: UserId(m.user_id),
-                    payouts_split: m.payouts_split,
-                    ordering: m.ordering,
-                }))
-            })
-            .try_collect::<Vec<TeamMember>>()
             .await?;
 
-            for (id, members) in &teams.into_iter().group_by(|x| x.team_id) {
-                let mut members = members.collect::<Vec<_>>();
+        Ok(teams.into_iter().flat_map(|x| x.members).collect())
+    }
 
-                redis
-                    .set_serialized_to_json(TEAMS_NAMESPACE, id.0, &members, None)
-                    .await?;
-                found_teams.append(&mut members);
-            }
-        }
+    /// Counts the members of a team, including those who haven't accepted their invites
+    pub async fn count<'a, E>(id: TeamId, executor: E) -> Result<i64, super::DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let count = sqlx::query!(
+            "
+            SELECT COUNT(*) FROM team_members
+            WHERE team_id = $1
+            ",
+            id as TeamId
+        )
+        .fetch_one(executor)
+        .await?
+        .count;
 
-        Ok(found_teams)
+        Ok(count.unwrap_or(0))
     }
 
     pub async fn clear_cache(id: TeamId, redis: &RedisPool) -> Result<(), super::DatabaseError> {
         let mut redis = redis.connect().await?;
         redis.delete(TEAMS_NAMESPACE, id.0).await?;
+        // Teams without members are cached as missing
+        redis
+            .delete_missing(TEAMS_NAMESPACE, None, id.0, None)
+            .await?;
         Ok(())
     }
 
@@ -319,7 +358,7 @@ impl TeamMember {
             "
             SELECT id, team_id, role AS member_role, is_owner, permissions, organization_permissions,
             accepted, payouts_split, role,
-            ordering, user_id
+            ordering, user_id, created, accepted_at, invited_by, two_factor_restricted, role_template_id
             FROM team_members
             WHERE (team_id = ANY($1) AND user_id = $2 AND accepted = TRUE)
             ORDER BY ordering
@@ -344,6 +383,11 @@ impl TeamMember {
                     accepted: m.accepted,
                     payouts_split: m.payouts_split,
                     ordering: m.ordering,
+                    created: m.created,
+                    accepted_at: m.accepted_at,
+                    invited_by: m.invited_by.map(UserId),
+                    two_factor_restricted: m.two_factor_restricted,
+                    role_template_id: m.role_template_id.map(OrganizationRoleTemplateId),
                 })))
             } else {
                 Ok(None)
@@ -372,8 +416,7 @@ impl TeamMember {
             "
             SELECT id, team_id, role AS member_role, is_owner, permissions, organization_permissions,
                 accepted, payouts_split, role,
-                ordering, user_id
-                
+                ordering, user_id, created, accepted_at, invited_by, two_factor_restricted, role_template_id
             FROM team_members
             WHERE (team_id = $1 AND user_id = $2)
             ORDER BY ordering
@@ -399,6 +442,11 @@ impl TeamMember {
                 accepted: m.accepted,
                 payouts_split: m.payouts_split,
                 ordering: m.ordering,
+                created: m.created,
+                accepted_at: m.accepted_at,
+                invited_by: m.invited_by.map(UserId),
+                two_factor_restricted: m.two_factor_restricted,
+                role_template_id: m.role_template_id.map(OrganizationRoleTemplateId),
             }))
         } else {
             Ok(None)
@@ -412,10 +460,11 @@ impl TeamMember {
         sqlx::query!(
             "
             INSERT INTO team_members (
-                id, team_id, user_id, role, permissions, organization_permissions, is_owner, accepted, payouts_split
+                id, team_id, user_id, role, permissions, organization_permissions, is_owner, accepted, payouts_split,
+                created, accepted_at, invited_by, role_template_id
             )
             VALUES (
-                $1, $2, $3, $4, $5, $6, $7, $8, $9
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13
             )
             ",
             self.id as TeamMemberId,
@@ -426,7 +475,11 @@ impl TeamMember {
             self.organization_permissions.map(|p| p.bits() as i64),
             self.is_owner,
             self.accepted,
-            self.payouts_split
+            self.payouts_split,
+            self.created,
+            self.accepted_at,
+            self.invited_by.map(|x| x.0),
+            self.role_template_id.map(|x| x.0),
         )
         .execute(&mut **transaction)
         .await?;
@@ -434,6 +487,61 @@ impl TeamMember {
         Ok(())
     }
 
+    /// Copies every member of an organization's team who isn't already a member of the project
+    /// team onto it, with the project permissions they currently have through the organization.
+    /// Organization owners are copied as regular members, so they never replace the project owner.
+    /// Returns the users which were added to the project team.
+    pub async fn copy_organization_members(
+        organization_team_id: TeamId,
+        project_team_id: TeamId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<UserId>, super::DatabaseError> {
+        let members = sqlx::query!(
+            "
+            SELECT om.user_id, om.role, om.permissions, om.is_owner, om.accepted
+            FROM team_members om
+            WHERE om.team_id = $1 AND NOT EXISTS (
+                SELECT 1 FROM team_members pm
+                WHERE pm.team_id = $2 AND pm.user_id = om.user_id
+            )
+            ORDER BY om.ordering, om.id
+            ",
+            organization_team_id as TeamId,
+            project_team_id as TeamId,
+        )
+        .fetch_all(&mut **transaction)
+        .await?;
+
+        let mut user_ids = Vec::new();
+        for member in members {
+            let member = TeamMember {
+                id: generate_team_member_id(transaction).await?,
+                team_id: project_team_id,
+                user_id: UserId(member.user_id),
+                role: member.role,
+                is_owner: false,
+                permissions: if member.is_owner {
+                    ProjectPermissions::all()
+                } else {
+                    ProjectPermissions::from_bits(member.permissions as u64).unwrap_or_default()
+                },
+                organization_permissions: None,
+                accepted: member.accepted,
+                payouts_split: Decimal::ZERO,
+                ordering: 0,
+                created: Utc::now(),
+                accepted_at: member.accepted.then(Utc::now),
+                invited_by: None,
+                two_factor_restricted: false,
+                role_template_id: None,
+            };
+            member.insert(transaction).await?;
+            user_ids.push(member.user_id);
+        }
+
+        Ok(user_ids)
+    }
+
     pub async fn delete<'a, 'b>(
         id: TeamId,
         user_id: UserId,
@@ -453,6 +561,27 @@ impl TeamMember {
         Ok(())
     }
 
+    /// Gives a user who enabled 2FA their permissions back in the organizations which require
+    /// it. Returns the teams which changed.
+    pub async fn clear_two_factor_restrictions(
+        user_id: UserId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<TeamId>, super::DatabaseError> {
+        let teams = sqlx::query!(
+            "
+            UPDATE team_members
+            SET two_factor_notified = NULL, two_factor_restricted = FALSE
+            WHERE user_id = $1 AND two_factor_notified IS NOT NULL
+            RETURNING team_id
+            ",
+            user_id as UserId,
+        )
+        .fetch_all(&mut **transaction)
+        .await?;
+
+        Ok(teams.into_iter().map(|x| TeamId(x.team_id)).collect())
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub async fn edit_team_member(
         id: TeamId,
@@ -516,7 +645,7 @@ impl TeamMember {
                 sqlx::query!(
                     "
                     UPDATE team_members
-                    SET accepted = TRUE
+                    SET accepted = TRUE, accepted_at = NOW()
                     WHERE (team_id = $1 AND user_id = $2)
                     ",
                     id as TeamId,
@@ -575,6 +704,50 @@ impl TeamMember {
         Ok(())
     }
 
+    /// Records the role template the member's permissions were set from, or that they were set
+    /// by hand if `None`
+    pub async fn set_role_template(
+        id: TeamId,
+        user_id: UserId,
+        role_template_id: Option<OrganizationRoleTemplateId>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), super::DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE team_members
+            SET role_template_id = $1
+            WHERE (team_id = $2 AND user_id = $3)
+            ",
+            role_template_id.map(|x| x.0),
+            id as TeamId,
+            user_id as UserId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Forgets the role templates the team's members were given, for when the team's project
+    /// leaves the organization the templates belong to. The members keep their permissions.
+    pub async fn clear_role_templates(
+        id: TeamId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), super::DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE team_members
+            SET role_template_id = NULL
+            WHERE team_id = $1 AND role_template_id IS NOT NULL
+            ",
+            id as TeamId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn get_from_user_id_project<'a, 'b, E>(
         id: ProjectId,
         user_id: UserId,
@@ -592,7 +765,7 @@ impl TeamMember {
 
         let result = sqlx::query!(
             "
-            SELECT tm.id, tm.team_id, tm.user_id, tm.role, tm.is_owner, tm.permissions, tm.organization_permissions, tm.accepted, tm.payouts_split, tm.ordering
+            SELECT tm.id, tm.team_id, tm.user_id, tm.role, tm.is_owner, tm.permissions, tm.organization_permissions, tm.accepted, tm.payouts_split, tm.ordering, tm.created, tm.accepted_at, tm.invited_by, tm.two_factor_restricted, tm.role_template_id
             FROM mods m
             INNER JOIN team_members tm ON tm.team_id = m.team_id AND user_id = $2 AND accepted = ANY($3)
             WHERE m.id = $1
@@ -619,6 +792,11 @@ impl TeamMember {
                 accepted: m.accepted,
                 payouts_split: m.payouts_split,
                 ordering: m.ordering,
+                created: m.created,
+                accepted_at: m.accepted_at,
+                invited_by: m.invited_by.map(UserId),
+                two_factor_restricted: m.two_factor_restricted,
+                role_template_id: m.role_template_id.map(OrganizationRoleTemplateId),
             }))
         } else {
             Ok(None)
@@ -641,7 +819,7 @@ impl TeamMember {
         };
         let result = sqlx::query!(
             "
-            SELECT tm.id, tm.team_id, tm.user_id, tm.role, tm.is_owner, tm.permissions, tm.organization_permissions, tm.accepted, tm.payouts_split, tm.ordering
+            SELECT tm.id, tm.team_id, tm.user_id, tm.role, tm.is_owner, tm.permissions, tm.organization_permissions, tm.accepted, tm.payouts_split, tm.ordering, tm.created, tm.accepted_at, tm.invited_by, tm.two_factor_restricted, tm.role_template_id
             FROM organizations o
             INNER JOIN team_members tm ON tm.team_id = o.team_id AND user_id = $2 AND accepted = ANY($3)
             WHERE o.id = $1
@@ -668,6 +846,11 @@ impl TeamMember {
                 accepted: m.accepted,
                 payouts_split: m.payouts_split,
                 ordering: m.ordering,
+                created: m.created,
+                accepted_at: m.accepted_at,
+                invited_by: m.invited_by.map(UserId),
+                two_factor_restricted: m.two_factor_restricted,
+                role_template_id: m.role_template_id.map(OrganizationRoleTemplateId),
             }))
         } else {
             Ok(None)
@@ -684,7 +867,7 @@ impl TeamMember {
     {
         let result = sqlx::query!(
             "
-            SELECT tm.id, tm.team_id, tm.user_id, tm.role, tm.is_owner, tm.permissions, tm.organization_permissions, tm.accepted, tm.payouts_split, tm.ordering, v.mod_id 
+            SELECT tm.id, tm.team_id, tm.user_id, tm.role, tm.is_owner, tm.permissions, tm.organization_permissions, tm.accepted, tm.payouts_split, tm.ordering, tm.created, tm.accepted_at, tm.invited_by, tm.two_factor_restricted, tm.role_template_id, v.mod_id 
             FROM versions v
             INNER JOIN mods m ON m.id = v.mod_id
             INNER JOIN team_members tm ON tm.team_id = m.team_id AND tm.user_id = $2 AND tm.accepted = TRUE
@@ -711,6 +894,11 @@ impl TeamMember {
                 accepted: m.accepted,
                 payouts_split: m.payouts_split,
                 ordering: m.ordering,
+                created: m.created,
+                accepted_at: m.accepted_at,
+                invited_by: m.invited_by.map(UserId),
+                two_factor_restricted: m.two_factor_restricted,
+                role_template_id: m.role_template_id.map(OrganizationRoleTemplateId),
             }))
         } else {
             Ok(None)
@@ -1,9 +1,13 @@
 use super::ids::*;
 use crate::database::models::DatabaseError;
+use crate::database::redis::RedisPool;
 use crate::models::threads::{MessageBody, ThreadType};
 use chrono::{DateTime, Utc};
+use log::warn;
 use serde::{Deserialize, Serialize};
 
+pub const THREAD_UPDATES_NAMESPACE: &str = "thread_updates";
+
 pub struct ThreadBuilder {
     pub type_: ThreadType,
     pub members: Vec<UserId>,
@@ -39,6 +43,45 @@ pub struct ThreadMessage {
     pub created: DateTime<Utc>,
 }
 
+/// A change to a thread, published to the thread's subscribers once it's been committed
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ThreadUpdate {
+    Message {
+        message: ThreadMessage,
+        /// Moderators are anonymous to other users
+        author_is_mod: bool,
+    },
+    MessageDeleted {
+        id: ThreadMessageId,
+        private: bool,
+    },
+}
+
+impl ThreadUpdate {
+    /// Publishes the update to the thread's subscribers. Subscribers can still fetch the
+    /// thread, so failing to publish is only logged.
+    pub async fn publish(&self, thread_id: ThreadId, redis: &RedisPool) {
+        let result = async {
+            let mut redis = redis.connect().await?;
+            redis
+                .publish(
+                    THREAD_UPDATES_NAMESPACE,
+                    &thread_id.0.to_string(),
+                    &serde_json::to_string(self)?,
+                )
+                .await
+        };
+
+        if let Err(err) = result.await {
+            warn!(
+                "Failed to publish an update to thread {}: {}",
+                thread_id.0, err
+            );
+        }
+    }
+}
+
 impl ThreadMessageBuilder {
     pub async fn insert(
         &self,
@@ -0,0 +1,198 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{DatabaseError, ProjectId, TeamId, UserId};
+
+/// A user blocked by another user. Blocks are one-directional, and the blocked user isn't told
+/// about them.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct UserBlock {
+    pub user_id: UserId,
+    pub blocked_user_id: UserId,
+    pub created: DateTime<Utc>,
+}
+
+impl UserBlock {
+    /// Blocks the user, unless the blocker already has `max_blocks` blocks. Returns whether the
+    /// user was blocked, which they aren't if the blocker is at the limit. Blocking a user who
+    /// is already blocked keeps the original block.
+    pub async fn insert(
+        user_id: UserId,
+        blocked_user_id: UserId,
+        max_blocks: i64,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            INSERT INTO user_blocks (user_id, blocked_user_id)
+            SELECT $1, $2
+            WHERE (SELECT COUNT(*) FROM user_blocks WHERE user_id = $1) < $3
+            ON CONFLICT (user_id, blocked_user_id) DO NOTHING
+            ",
+            user_id as UserId,
+            blocked_user_id as UserId,
+            max_blocks,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            return Ok(true);
+        }
+
+        Self::is_blocked(user_id, blocked_user_id, &mut **transaction).await
+    }
+
+    /// Unblocks the user. Returns whether they were blocked.
+    pub async fn remove(
+        user_id: UserId,
+        blocked_user_id: UserId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            DELETE FROM user_blocks
+            WHERE user_id = $1 AND blocked_user_id = $2
+            ",
+            user_id as UserId,
+            blocked_user_id as UserId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Whether `user_id` blocked `blocked_user_id`
+    pub async fn is_blocked<'a, E>(
+        user_id: UserId,
+        blocked_user_id: UserId,
+        exec: E,
+    ) -> Result<bool, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT EXISTS(SELECT 1 FROM user_blocks WHERE user_id = $1 AND blocked_user_id = $2)
+            ",
+            user_id as UserId,
+            blocked_user_id as UserId,
+        )
+        .fetch_one(exec)
+        .await?;
+
+        Ok(result.exists.unwrap_or(false))
+    }
+
+    /// Whether an owner of the team blocked the user
+    pub async fn is_blocked_by_team_owner<'a, E>(
+        team_id: TeamId,
+        blocked_user_id: UserId,
+        exec: E,
+    ) -> Result<bool, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT EXISTS(
+                SELECT 1 FROM team_members tm
+                INNER JOIN user_blocks ub ON ub.user_id = tm.user_id AND ub.blocked_user_id = $2
+                WHERE tm.team_id = $1 AND tm.is_owner = TRUE
+            )
+            ",
+            team_id as TeamId,
+            blocked_user_id as UserId,
+        )
+        .fetch_one(exec)
+        .await?;
+
+        Ok(result.exists.unwrap_or(false))
+    }
+
+    /// Whether the owner of the project, or of the organization which owns it, blocked the user
+    pub async fn is_blocked_by_project_owner<'a, E>(
+        project_id: ProjectId,
+        blocked_user_id: UserId,
+        exec: E,
+    ) -> Result<bool, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT EXISTS(
+                SELECT 1 FROM mods m
+                LEFT JOIN organizations o ON o.id = m.organization_id
+                INNER JOIN team_members tm ON (tm.team_id = m.team_id OR tm.team_id = o.team_id)
+                    AND tm.is_owner = TRUE
+                INNER JOIN user_blocks ub ON ub.user_id = tm.user_id AND ub.blocked_user_id = $2
+                WHERE m.id = $1
+            )
+            ",
+            project_id as ProjectId,
+            blocked_user_id as UserId,
+        )
+        .fetch_one(exec)
+        .await?;
+
+        Ok(result.exists.unwrap_or(false))
+    }
+
+    /// Gets which of the users blocked `blocked_user_id`
+    pub async fn get_blockers<'a, E>(
+        user_ids: &[UserId],
+        blocked_user_id: UserId,
+        exec: E,
+    ) -> Result<Vec<UserId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let blockers = sqlx::query!(
+            "
+            SELECT user_id FROM user_blocks
+            WHERE user_id = ANY($1) AND blocked_user_id = $2
+            ",
+            &user_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+            blocked_user_id as UserId,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| UserId(x.user_id))
+        .collect();
+
+        Ok(blockers)
+    }
+
+    /// Gets the users the user blocked, most recently blocked first
+    pub async fn get_for_user<'a, E>(
+        user_id: UserId,
+        exec: E,
+    ) -> Result<Vec<UserBlock>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let blocks = sqlx::query!(
+            "
+            SELECT user_id, blocked_user_id, created
+            FROM user_blocks
+            WHERE user_id = $1
+            ORDER BY created DESC
+            ",
+            user_id as UserId,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| UserBlock {
+            user_id: UserId(x.user_id),
+            blocked_user_id: UserId(x.blocked_user_id),
+            created: x.created,
+        })
+        .collect();
+
+        Ok(blocks)
+    }
+}
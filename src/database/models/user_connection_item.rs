@@ -0,0 +1,139 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{DatabaseError, UserConnectionId, UserId};
+use crate::models::connections::ConnectionProvider;
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct UserConnection {
+    pub id: UserConnectionId,
+    pub user_id: UserId,
+    pub provider: ConnectionProvider,
+    pub external_id: String,
+    pub username: String,
+    pub verified: bool,
+    pub created: DateTime<Utc>,
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+impl UserConnection {
+    /// Inserts the connection. Adding an account the user already has linked updates its
+    /// username, and verifies it if the new connection is verified.
+    pub async fn insert(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<UserConnectionId, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            INSERT INTO user_connections (id, user_id, provider, external_id, username, verified, created, verified_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (user_id, provider, external_id) DO UPDATE
+            SET username = EXCLUDED.username,
+                verified = user_connections.verified OR EXCLUDED.verified,
+                verified_at = COALESCE(user_connections.verified_at, EXCLUDED.verified_at)
+            RETURNING id
+            ",
+            self.id as UserConnectionId,
+            self.user_id as UserId,
+            self.provider.as_str(),
+            self.external_id,
+            self.username,
+            self.verified,
+            self.created,
+            self.verified_at,
+        )
+        .fetch_one(&mut **transaction)
+        .await?;
+
+        Ok(UserConnectionId(result.id))
+    }
+
+    pub async fn get<'a, E>(
+        id: UserConnectionId,
+        exec: E,
+    ) -> Result<Option<UserConnection>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT id, user_id, provider, external_id, username, verified, created, verified_at
+            FROM user_connections
+            WHERE id = $1
+            ",
+            id as UserConnectionId,
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.map(|r| UserConnection {
+            id: UserConnectionId(r.id),
+            user_id: UserId(r.user_id),
+            provider: ConnectionProvider::from_string(&r.provider),
+            external_id: r.external_id,
+            username: r.username,
+            verified: r.verified,
+            created: r.created,
+            verified_at: r.verified_at,
+        }))
+    }
+
+    /// Gets the user an account is verified for, if any
+    pub async fn get_verified_user<'a, E>(
+        provider: ConnectionProvider,
+        external_id: &str,
+        exec: E,
+    ) -> Result<Option<UserId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT user_id
+            FROM user_connections
+            WHERE provider = $1 AND external_id = $2 AND verified
+            ",
+            provider.as_str(),
+            external_id,
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.map(|r| UserId(r.user_id)))
+    }
+
+    pub async fn verify(
+        id: UserConnectionId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE user_connections
+            SET verified = TRUE, verified_at = NOW()
+            WHERE id = $1
+            ",
+            id as UserConnectionId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(
+        id: UserConnectionId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            DELETE FROM user_connections
+            WHERE id = $1
+            ",
+            id as UserConnectionId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+}
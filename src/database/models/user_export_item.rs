@@ -0,0 +1,214 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{DatabaseError, UserExportId, UserId};
+use crate::models::exports::UserExportStatus;
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct UserExport {
+    pub id: UserExportId,
+    pub user_id: UserId,
+    pub status: UserExportStatus,
+    pub file_name: Option<String>,
+    pub created: DateTime<Utc>,
+    pub completed: Option<DateTime<Utc>>,
+}
+
+impl UserExport {
+    pub async fn insert(
+        id: UserExportId,
+        user_id: UserId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO user_exports (id, user_id, status)
+            VALUES ($1, $2, $3)
+            ",
+            id.0,
+            user_id.0,
+            UserExportStatus::Queued.as_str(),
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Locks the user's row until the transaction ends, so that concurrent requests for an
+    /// export are checked against the limit one at a time
+    pub async fn lock_user(
+        user_id: UserId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            SELECT id FROM users
+            WHERE id = $1
+            FOR UPDATE
+            ",
+            user_id.0
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the most recently requested export of a user
+    pub async fn get_latest<'a, E>(
+        user_id: UserId,
+        exec: E,
+    ) -> Result<Option<UserExport>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT id, user_id, status, file_name, created, completed
+            FROM user_exports
+            WHERE user_id = $1
+            ORDER BY created DESC
+            LIMIT 1
+            ",
+            user_id.0
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.map(|r| UserExport {
+            id: UserExportId(r.id),
+            user_id: UserId(r.user_id),
+            status: UserExportStatus::from_string(&r.status),
+            file_name: r.file_name,
+            created: r.created,
+            completed: r.completed,
+        }))
+    }
+
+    /// Gets the most recently requested export of a user which didn't fail. Failed exports
+    /// don't count towards how often a user can request one.
+    pub async fn get_latest_not_failed<'a, E>(
+        user_id: UserId,
+        exec: E,
+    ) -> Result<Option<UserExport>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT id, user_id, status, file_name, created, completed
+            FROM user_exports
+            WHERE user_id = $1 AND status != $2
+            ORDER BY created DESC
+            LIMIT 1
+            ",
+            user_id.0,
+            UserExportStatus::Failed.as_str(),
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.map(|r| UserExport {
+            id: UserExportId(r.id),
+            user_id: UserId(r.user_id),
+            status: UserExportStatus::from_string(&r.status),
+            file_name: r.file_name,
+            created: r.created,
+            completed: r.completed,
+        }))
+    }
+
+    pub async fn get<'a, E>(id: UserExportId, exec: E) -> Result<Option<UserExport>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT id, user_id, status, file_name, created, completed
+            FROM user_exports
+            WHERE id = $1
+            ",
+            id.0
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.map(|r| UserExport {
+            id: UserExportId(r.id),
+            user_id: UserId(r.user_id),
+            status: UserExportStatus::from_string(&r.status),
+            file_name: r.file_name,
+            created: r.created,
+            completed: r.completed,
+        }))
+    }
+
+    /// Sets the status of an export. The completion time is set when the export finishes,
+    /// whether it succeeded or not.
+    pub async fn set_status<'a, E>(
+        id: UserExportId,
+        status: UserExportStatus,
+        file_name: Option<&str>,
+        exec: E,
+    ) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let completed = matches!(
+            status,
+            UserExportStatus::Complete | UserExportStatus::Failed
+        );
+
+        sqlx::query!(
+            "
+            UPDATE user_exports
+            SET status = $2,
+                file_name = COALESCE($3, file_name),
+                completed = CASE WHEN $4 THEN NOW() ELSE completed END
+            WHERE id = $1
+            ",
+            id.0,
+            status.as_str(),
+            file_name,
+            completed,
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the completed exports whose archives were uploaded before `before`
+    pub async fn get_completed_before<'a, E>(
+        before: DateTime<Utc>,
+        exec: E,
+    ) -> Result<Vec<UserExport>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let results = sqlx::query!(
+            "
+            SELECT id, user_id, status, file_name, created, completed
+            FROM user_exports
+            WHERE status = $1 AND completed < $2
+            ",
+            UserExportStatus::Complete.as_str(),
+            before,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| UserExport {
+                id: UserExportId(r.id),
+                user_id: UserId(r.user_id),
+                status: UserExportStatus::from_string(&r.status),
+                file_name: r.file_name,
+                created: r.created,
+                completed: r.completed,
+            })
+            .collect())
+    }
+}
@@ -1,10 +1,15 @@
 use super::ids::{ProjectId, UserId};
+use super::user_connection_item::UserConnection;
 use super::CollectionId;
 use crate::database::models::{DatabaseError, OrganizationId};
-use crate::database::redis::RedisPool;
+use crate::database::redis::{CachedEntry, RedisPool};
 use crate::models::ids::base62_impl::{parse_base62, to_base62};
-use crate::models::users::Badges;
+use crate::models::payouts::UserPayoutMethod;
+use crate::models::users::{Badges, Role};
+use crate::util::encryption;
 use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +17,15 @@ const USERS_NAMESPACE: &str = "users";
 const USER_USERNAMES_NAMESPACE: &str = "users_usernames";
 const USERS_PROJECTS_NAMESPACE: &str = "users_projects";
 
+lazy_static! {
+    static ref RE_VENMO_HANDLE: Regex = Regex::new(r"^@?[a-zA-Z0-9_-]{5,30}$").unwrap();
+    static ref RE_BIC: Regex = Regex::new(r"^[A-Z]{6}[A-Z0-9]{2}([A-Z0-9]{3})?$").unwrap();
+}
+
+fn default_hide_mature_content() -> bool {
+    true
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct User {
     pub id: UserId,
@@ -28,6 +42,12 @@ pub struct User {
     pub paypal_country: Option<String>,
     pub paypal_email: Option<String>,
     pub venmo_handle: Option<String>,
+    /// The payout method with its sensitive fields masked. The full method is only kept
+    /// encrypted, and is loaded with `get_payout_method`.
+    #[serde(default)]
+    pub payout_method_masked: Option<UserPayoutMethod>,
+    #[serde(default = "default_hide_mature_content")]
+    pub hide_mature_content: bool,
 
     pub totp_secret: Option<String>,
 
@@ -42,6 +62,20 @@ pub struct User {
     pub badges: Badges,
 
     pub balance: Decimal,
+
+    /// Every connection of the user, including unverified ones
+    #[serde(default)]
+    pub connections: Vec<UserConnection>,
+}
+
+impl CachedEntry for User {
+    fn cache_id(&self) -> i64 {
+        self.id.0
+    }
+
+    fn cache_slug(&self) -> Option<String> {
+        Some(self.username.clone())
+    }
 }
 
 impl User {
@@ -142,123 +176,89 @@ impl User {
     {
         use futures::TryStreamExt;
 
-        let mut redis = redis.connect().await?;
-
-        if users_strings.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        let mut found_users = Vec::new();
-        let mut remaining_strings = users_strings
+        let users_strings = users_strings
             .iter()
             .map(|x| x.to_string())
             .collect::<Vec<_>>();
 
-        let mut user_ids = users_strings
-            .iter()
-            .flat_map(|x| parse_base62(&x.to_string()).map(|x| x as i64))
-            .collect::<Vec<_>>();
-
-        user_ids.append(
-            &mut redis
-                .multi_get::<i64>(
-                    USER_USERNAMES_NAMESPACE,
-                    users_strings.iter().map(|x| x.to_string().to_lowercase()),
-                )
-                .await?
-                .into_iter()
-                .flatten()
-                .collect(),
-        );
-
-        if !user_ids.is_empty() {
-            let users = redis
-                .multi_get::<String>(USERS_NAMESPACE, user_ids.iter().map(|x| x.to_string()))
-                .await?;
-            for user in users {
-                if let Some(user) = user.and_then(|x| serde_json::from_str::<User>(&x).ok()) {
-                    remaining_strings.retain(|x| {
-                        &to_base62(user.id.0 as u64) != x
-                            && user.username.to_lowercase() != x.to_lowercase()
-                    });
-                    found_users.push(user);
-                    continue;
-                }
-            }
-        }
-
-        if !remaining_strings.is_empty() {
-            let user_ids_parsed: Vec<i64> = remaining_strings
-                .iter()
-                .flat_map(|x| parse_base62(&x.to_string()).ok())
-                .map(|x| x as i64)
-                .collect();
-            let db_users: Vec<User> = sqlx::query!(
-                "
-                SELECT id, name, email,
-                    avatar_url, username, bio,
-                    created, role, badges,
-                    balance,
-                    github_id, discord_id, gitlab_id, google_id, steam_id, microsoft_id,
-                    email_verified, password, totp_secret, paypal_id, paypal_country, paypal_email,
-                    venmo_handle
-                FROM users
-                WHERE id = ANY($1) OR LOWER(username) = ANY($2)
-                ",
-                &user_ids_parsed,
-                &remaining_strings
-                    .into_iter()
-                    .map(|x| x.to_string().to_lowercase())
-                    .collect::<Vec<_>>(),
-            )
-            .fetch_many(exec)
-            .try_filter_map(|e| async {
-                Ok(e.right().map(|u| User {
-                    id: UserId(u.id),
-                    github_id: u.github_id,
-                    discord_id: u.discord_id,
-                    gitlab_id: u.gitlab_id,
-                    google_id: u.google_id,
-                    steam_id: u.steam_id,
-                    microsoft_id: u.microsoft_id,
-                    name: u.name,
-                    email: u.email,
-                    email_verified: u.email_verified,
-                    avatar_url: u.avatar_url,
-                    username: u.username,
-                    bio: u.bio,
-                    created: u.created,
-                    role: u.role,
-                    badges: Badges::from_bits(u.badges as u64).unwrap_or_default(),
-                    balance: u.balance,
-                    password: u.password,
-                    paypal_id: u.paypal_id,
-                    paypal_country: u.paypal_country,
-                    paypal_email: u.paypal_email,
-                    venmo_handle: u.venmo_handle,
-                    totp_secret: u.totp_secret,
-                }))
-            })
-            .try_collect::<Vec<User>>()
-            .await?;
-
-            for user in db_users {
-                redis
-                    .set_serialized_to_json(USERS_NAMESPACE, user.id.0, &user, None)
-                    .await?;
-                redis
-                    .set(
-                        USER_USERNAMES_NAMESPACE,
-                        &user.username.to_lowercase(),
-                        &user.id.0.to_string(),
-                        None,
+        redis
+            .get_cached_keys(
+                USERS_NAMESPACE,
+                Some(USER_USERNAMES_NAMESPACE),
+                &users_strings,
+                |x| parse_base62(x).ok().map(|x| x as i64),
+                |remaining_strings| async move {
+                    let user_ids_parsed: Vec<i64> = remaining_strings
+                        .iter()
+                        .flat_map(|x| parse_base62(&x.to_string()).ok())
+                        .map(|x| x as i64)
+                        .collect();
+                    let db_users: Vec<User> = sqlx::query!(
+                        "
+                        SELECT id, name, email,
+                            avatar_url, username, bio,
+                            created, role, badges,
+                            balance,
+                            github_id, discord_id, gitlab_id, google_id, steam_id, microsoft_id,
+                            email_verified, password, totp_secret, paypal_id, paypal_country, paypal_email,
+                            venmo_handle, payout_method_masked, hide_mature_content,
+                            (
+                                SELECT JSONB_AGG(jsonb_build_object('id', uc.id, 'user_id', uc.user_id, 'provider', uc.provider, 'external_id', uc.external_id, 'username', uc.username, 'verified', uc.verified, 'created', uc.created, 'verified_at', uc.verified_at) ORDER BY uc.created)
+                                FROM user_connections uc
+                                WHERE uc.user_id = users.id
+                            ) connections
+                        FROM users
+                        WHERE id = ANY($1) OR LOWER(username) = ANY($2)
+                        ",
+                        &user_ids_parsed,
+                        &remaining_strings
+                            .into_iter()
+                            .map(|x| x.to_string().to_lowercase())
+                            .collect::<Vec<_>>(),
                     )
+                    .fetch_many(exec)
+                    .try_filter_map(|e| async {
+                        Ok(e.right().map(|u| User {
+                            id: UserId(u.id),
+                            github_id: u.github_id,
+                            discord_id: u.discord_id,
+                            gitlab_id: u.gitlab_id,
+                            google_id: u.google_id,
+                            steam_id: u.steam_id,
+                            microsoft_id: u.microsoft_id,
+                            name: u.name,
+                            email: u.email,
+                            email_verified: u.email_verified,
+                            avatar_url: u.avatar_url,
+                            username: u.username,
+                            bio: u.bio,
+                            created: u.created,
+                            role: u.role,
+                            badges: Badges::from_bits(u.badges as u64).unwrap_or_default(),
+                            balance: u.balance,
+                            password: u.password,
+                            paypal_id: u.paypal_id,
+                            paypal_country: u.paypal_country,
+                            paypal_email: u.paypal_email,
+                            venmo_handle: u.venmo_handle,
+                            payout_method_masked: u
+                                .payout_method_masked
+                                .and_then(|x| serde_json::from_value(x).ok()),
+                            hide_mature_content: u.hide_mature_content,
+                            totp_secret: u.totp_secret,
+                            connections: u
+                                .connections
+                                .and_then(|x| serde_json::from_value(x).ok())
+                                .unwrap_or_default(),
+                        }))
+                    })
+                    .try_collect::<Vec<User>>()
                     .await?;
-                found_users.push(user);
-            }
-        }
 
-        Ok(found_users)
+                    Ok::<_, DatabaseError>(db_users)
+                },
+            )
+            .await
     }
 
     pub async fn get_email<'a, E>(email: &str, exec: E) -> Result<Option<UserId>, sqlx::Error>
@@ -278,6 +278,27 @@ impl User {
         Ok(user_pass.map(|x| UserId(x.id)))
     }
 
+    /// Gets the moderators and admins
+    pub async fn get_moderator_ids<'a, E>(exec: E) -> Result<Vec<UserId>, sqlx::Error>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let users = sqlx::query!(
+            "
+            SELECT id FROM users
+            WHERE role = ANY($1)
+            ",
+            &[
+                Role::Moderator.as_str().to_string(),
+                Role::Admin.as_str().to_string(),
+            ],
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(users.into_iter().map(|x| UserId(x.id)).collect())
+    }
+
     pub async fn get_projects<'a, E>(
         user_id: UserId,
         exec: E,
@@ -322,6 +343,76 @@ impl User {
         Ok(db_projects)
     }
 
+    pub async fn get_followed_projects<'a, E>(
+        user_id: UserId,
+        exec: E,
+    ) -> Result<Vec<ProjectId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let projects = sqlx::query!(
+            "
+            SELECT mod_id FROM mod_follows
+            WHERE follower_id = $1
+            ",
+            user_id as UserId,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(projects.into_iter().map(|x| ProjectId(x.mod_id)).collect())
+    }
+
+    /// Gets the categories and loaders most common among the projects a user follows, most
+    /// common first
+    pub async fn get_followed_tags<'a, E>(
+        user_id: UserId,
+        limit: usize,
+        exec: E,
+    ) -> Result<(Vec<String>, Vec<String>), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let tags = sqlx::query!(
+            "
+            SELECT tag, loader, COUNT(*) count FROM (
+                SELECT c.category tag, FALSE loader FROM mod_follows mf
+                INNER JOIN mods_categories mc ON mc.joining_mod_id = mf.mod_id
+                INNER JOIN categories c ON c.id = mc.joining_category_id
+                WHERE mf.follower_id = $1
+                UNION ALL
+                SELECT l.loader tag, TRUE loader FROM mod_follows mf
+                INNER JOIN versions v ON v.mod_id = mf.mod_id
+                INNER JOIN loaders_versions lv ON lv.version_id = v.id
+                INNER JOIN loaders l ON l.id = lv.loader_id
+                WHERE mf.follower_id = $1
+                GROUP BY mf.mod_id, l.loader
+            ) tags
+            GROUP BY tag, loader
+            ORDER BY count DESC, tag
+            ",
+            user_id as UserId,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        let (loaders, categories): (Vec<_>, Vec<_>) =
+            tags.into_iter().partition(|x| x.loader.unwrap_or(false));
+
+        Ok((
+            categories
+                .into_iter()
+                .filter_map(|x| x.tag)
+                .take(limit)
+                .collect(),
+            loaders
+                .into_iter()
+                .filter_map(|x| x.tag)
+                .take(limit)
+                .collect(),
+        ))
+    }
+
     pub async fn get_organizations<'a, E>(
         user_id: UserId,
         exec: E,
@@ -395,6 +486,111 @@ impl User {
         Ok(codes)
     }
 
+    /// Sets the user's payout method. Venmo handles are also kept in their own column, as
+    /// payouts are sent using it. PayPal emails aren't: `paypal_email` is only set by linking a
+    /// PayPal account, which verifies it, so a chosen PayPal email is only kept in the method.
+    /// The method is stored encrypted, along with a masked copy.
+    pub async fn set_payout_method(
+        id: UserId,
+        method: &UserPayoutMethod,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE users
+            SET payout_method = $2, payout_method_masked = $3
+            WHERE id = $1
+            ",
+            id as UserId,
+            encryption::encrypt(&serde_json::to_vec(method)?)?,
+            serde_json::to_value(method.masked())?,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        match method {
+            UserPayoutMethod::Venmo { handle } => {
+                sqlx::query!(
+                    "
+                    UPDATE users
+                    SET venmo_handle = $2
+                    WHERE id = $1
+                    ",
+                    id as UserId,
+                    handle,
+                )
+                .execute(&mut **transaction)
+                .await?;
+            }
+            UserPayoutMethod::Paypal { .. } | UserPayoutMethod::BankTransfer { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    /// Gets the user's payout method with its sensitive fields, for sending payouts. Everywhere
+    /// else, the masked method on the user is used instead.
+    pub async fn get_payout_method<'a, E>(
+        id: UserId,
+        exec: E,
+    ) -> Result<Option<UserPayoutMethod>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT payout_method FROM users
+            WHERE id = $1
+            ",
+            id as UserId,
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        match result.and_then(|x| x.payout_method) {
+            Some(data) => Ok(Some(serde_json::from_slice(&encryption::decrypt(&data)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets the override of how many projects the user can own, if an admin set one
+    pub async fn get_project_limit<'a, E>(id: UserId, exec: E) -> Result<Option<i32>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT project_limit FROM users
+            WHERE id = $1
+            ",
+            id as UserId,
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.and_then(|x| x.project_limit))
+    }
+
+    pub async fn set_project_limit(
+        id: UserId,
+        project_limit: Option<i32>,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE users
+            SET project_limit = $2
+            WHERE id = $1
+            ",
+            id as UserId,
+            project_limit,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn clear_caches(
         user_ids: &[(UserId, Option<String>)],
         redis: &RedisPool,
@@ -412,6 +608,17 @@ impl User {
                 ]
             }))
             .await?;
+        // The users may have been created or renamed since they were looked up
+        for (id, username) in user_ids {
+            redis
+                .delete_missing(
+                    USERS_NAMESPACE,
+                    Some(USER_USERNAMES_NAMESPACE),
+                    id.0,
+                    username.as_deref(),
+                )
+                .await?;
+        }
         Ok(())
     }
 
@@ -468,6 +675,30 @@ impl User {
             .execute(&mut **transaction)
             .await?;
 
+            sqlx::query!(
+                "
+                UPDATE moderation_reviews
+                SET moderator_id = $1
+                WHERE moderator_id = $2
+                ",
+                deleted_user as UserId,
+                id as UserId,
+            )
+            .execute(&mut **transaction)
+            .await?;
+
+            sqlx::query!(
+                "
+                UPDATE project_wiki_pages
+                SET updated_by = $1
+                WHERE updated_by = $2
+                ",
+                deleted_user as UserId,
+                id as UserId,
+            )
+            .execute(&mut **transaction)
+            .await?;
+
             use futures::TryStreamExt;
             let notifications: Vec<i64> = sqlx::query!(
                 "
@@ -521,6 +752,27 @@ impl User {
             .execute(&mut **transaction)
             .await?;
 
+            sqlx::query!(
+                "
+                UPDATE collections
+                SET follows = follows - 1
+                WHERE id IN (SELECT collection_id FROM collection_follows WHERE follower_id = $1)
+                ",
+                id as UserId,
+            )
+            .execute(&mut **transaction)
+            .await?;
+
+            sqlx::query!(
+                "
+                DELETE FROM collection_follows
+                WHERE follower_id = $1
+                ",
+                id as UserId,
+            )
+            .execute(&mut **transaction)
+            .await?;
+
             sqlx::query!(
                 "
                 DELETE FROM team_members
@@ -619,3 +871,87 @@ impl User {
         }
     }
 }
+
+/// Normalizes the payout method's fields and validates them for its type. Returns the invalid
+/// fields along with the reason they failed validation.
+pub fn validate_payout_method(
+    method: &mut UserPayoutMethod,
+) -> Result<(), Vec<(&'static str, &'static str)>> {
+    let mut errors = Vec::new();
+
+    match method {
+        UserPayoutMethod::Paypal { email } => {
+            *email = email.trim().to_string();
+            if !validator::validate_email(&**email) {
+                errors.push(("email", "invalid_email"));
+            }
+        }
+        UserPayoutMethod::Venmo { handle } => {
+            *handle = handle.trim().to_string();
+            if !RE_VENMO_HANDLE.is_match(handle) {
+                errors.push(("handle", "invalid_handle"));
+            }
+        }
+        UserPayoutMethod::BankTransfer {
+            account_holder,
+            iban,
+            bic,
+        } => {
+            *account_holder = account_holder.trim().to_string();
+            if account_holder.is_empty() || account_holder.len() > 255 {
+                errors.push(("account_holder", "length"));
+            }
+
+            *iban = iban
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+                .to_uppercase();
+            if !is_valid_iban(iban) {
+                errors.push(("iban", "invalid_checksum"));
+            }
+
+            if let Some(bic) = bic {
+                *bic = bic.trim().to_uppercase();
+                if !RE_BIC.is_match(bic) {
+                    errors.push(("bic", "invalid_bic"));
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+// Checks the length, country code and mod-97 checksum of a normalized IBAN
+fn is_valid_iban(iban: &str) -> bool {
+    if !(15..=34).contains(&iban.len()) || !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+
+    let (country, rest) = iban.split_at(2);
+    if !country.chars().all(|c| c.is_ascii_uppercase())
+        || !rest[..2].chars().all(|c| c.is_ascii_digit())
+    {
+        return false;
+    }
+
+    // The first four characters are moved to the end, and letters are replaced with 10-35
+    let remainder = iban[4..]
+        .chars()
+        .chain(iban[..4].chars())
+        .fold(0u32, |acc, c| {
+            let value = c.to_digit(36).unwrap_or(0);
+            if value >= 10 {
+                (acc * 100 + value) % 97
+            } else {
+                (acc * 10 + value) % 97
+            }
+        });
+
+    remainder == 1
+}
@@ -1,17 +1,23 @@
 use super::ids::*;
-use super::loader_fields::VersionField;
+use super::legacy_loader_fields::MinecraftGameVersion;
+use super::loader_fields::{VersionField, VersionFieldValue};
 use super::DatabaseError;
 use crate::database::models::loader_fields::{
     QueryLoaderField, QueryLoaderFieldEnumValue, QueryVersionField,
 };
-use crate::database::redis::RedisPool;
-use crate::models::projects::{FileType, VersionStatus};
+use crate::database::redis::{CachedEntry, RedisPool};
+use crate::models::projects::{
+    DependencyType, FileScanStatus, FileType, ProjectStatus, VersionStatus,
+};
+use crate::util::etag::item_etag;
+use crate::validate::metadata::ModMetadata;
+use crate::validate::registry::ValidationIssue;
 use chrono::{DateTime, Utc};
 use dashmap::{DashMap, DashSet};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::iter;
 
 pub const VERSIONS_NAMESPACE: &str = "versions";
@@ -34,6 +40,7 @@ pub struct VersionBuilder {
     pub status: VersionStatus,
     pub requested_status: Option<VersionStatus>,
     pub ordering: Option<i32>,
+    pub publish_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone)]
@@ -42,6 +49,8 @@ pub struct DependencyBuilder {
     pub version_id: Option<VersionId>,
     pub file_name: Option<String>,
     pub dependency_type: String,
+    /// Whether the dependency was filled in from the file's mod metadata
+    pub inferred: bool,
 }
 
 impl DependencyBuilder {
@@ -60,7 +69,8 @@ impl DependencyBuilder {
             );
         }
 
-        let (version_ids, dependency_types, dependency_ids, filenames): (
+        let (version_ids, dependency_types, dependency_ids, filenames, inferred): (
+            Vec<_>,
             Vec<_>,
             Vec<_>,
             Vec<_>,
@@ -73,19 +83,21 @@ impl DependencyBuilder {
                     d.dependency_type,
                     d.version_id.map(|v| v.0),
                     d.file_name,
+                    d.inferred,
                 )
             })
             .multiunzip();
         sqlx::query!(
             "
-            INSERT INTO dependencies (dependent_id, dependency_type, dependency_id, mod_dependency_id, dependency_file_name)
-            SELECT * FROM UNNEST ($1::bigint[], $2::varchar[], $3::bigint[], $4::bigint[], $5::varchar[])
+            INSERT INTO dependencies (dependent_id, dependency_type, dependency_id, mod_dependency_id, dependency_file_name, inferred)
+            SELECT * FROM UNNEST ($1::bigint[], $2::varchar[], $3::bigint[], $4::bigint[], $5::varchar[], $6::bool[])
             ",
             &version_ids[..],
             &dependency_types[..],
             &dependency_ids[..] as &[Option<i64>],
             &project_ids[..] as &[Option<i64>],
             &filenames[..] as &[Option<String>],
+            &inferred[..],
         )
         .execute(&mut **transaction)
         .await?;
@@ -93,7 +105,7 @@ impl DependencyBuilder {
         Ok(())
     }
 
-    async fn try_get_project_id(
+    pub async fn try_get_project_id(
         &self,
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<Option<ProjectId>, DatabaseError> {
@@ -123,6 +135,12 @@ pub struct VersionFileBuilder {
     pub primary: bool,
     pub size: u32,
     pub file_type: Option<FileType>,
+    /// What the file declares in its loader metadata, if it's a mod. Only used while the
+    /// version is being created, so isn't stored.
+    pub mod_metadata: Option<ModMetadata>,
+    /// The problems the file validators found which didn't stop the file being uploaded. Only
+    /// returned when the version is created, so isn't stored.
+    pub validation_warnings: Vec<ValidationIssue>,
 }
 
 impl VersionFileBuilder {
@@ -135,8 +153,10 @@ impl VersionFileBuilder {
 
         sqlx::query!(
             "
-            INSERT INTO files (id, version_id, url, filename, is_primary, size, file_type)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            INSERT INTO files (id, version_id, url, filename, is_primary, size, file_type, ordering)
+            SELECT $1, $2, $3, $4, $5, $6, $7, COALESCE(MAX(ordering) + 1, 0)
+            FROM files
+            WHERE version_id = $2
             ",
             file_id as FileId,
             version_id as VersionId,
@@ -174,6 +194,14 @@ pub struct HashBuilder {
 }
 
 impl VersionBuilder {
+    /// What the version's primary file declares in its mod metadata, if it's a mod
+    pub fn primary_mod_metadata(&self) -> Option<&ModMetadata> {
+        self.files
+            .iter()
+            .find(|x| x.primary)
+            .and_then(|x| x.mod_metadata.as_ref())
+    }
+
     pub async fn insert(
         self,
         transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
@@ -192,6 +220,8 @@ impl VersionBuilder {
             status: self.status,
             requested_status: self.requested_status,
             ordering: self.ordering,
+            publish_at: self.publish_at,
+            flagged_reupload: false,
         };
 
         version.insert(transaction).await?;
@@ -210,11 +240,13 @@ impl VersionBuilder {
         let VersionBuilder {
             dependencies,
             loaders,
-            files,
+            mut files,
             version_id,
             ..
         } = self;
 
+        // The primary file is listed first until the files are reordered
+        files.sort_by_key(|x| !x.primary);
         for file in files {
             file.insert(version_id, transaction).await?;
         }
@@ -278,6 +310,9 @@ pub struct Version {
     pub status: VersionStatus,
     pub requested_status: Option<VersionStatus>,
     pub ordering: Option<i32>,
+    pub publish_at: Option<DateTime<Utc>>,
+    /// Whether any of the files were already uploaded to a project the uploader isn't a member of
+    pub flagged_reupload: bool,
 }
 
 impl Version {
@@ -290,12 +325,14 @@ impl Version {
             INSERT INTO versions (
                 id, mod_id, author_id, name, version_number,
                 changelog, date_published, downloads,
-                version_type, featured, status, ordering
+                version_type, featured, status, ordering,
+                publish_at
             )
             VALUES (
                 $1, $2, $3, $4, $5,
                 $6, $7, $8,
-                $9, $10, $11, $12
+                $9, $10, $11, $12,
+                $13
             )
             ",
             self.id as VersionId,
@@ -309,7 +346,33 @@ impl Version {
             &self.version_type,
             self.featured,
             self.status.as_str(),
-            self.ordering
+            self.ordering,
+            self.publish_at
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Orders the files of a version as given in `file_ids`, and makes `primary` the only
+    /// primary file. Every file of the version should be included.
+    pub async fn update_files(
+        id: VersionId,
+        file_ids: &[FileId],
+        primary: FileId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE files f
+            SET ordering = p.ordering - 1, is_primary = (f.id = $3)
+            FROM UNNEST($2::bigint[]) WITH ORDINALITY AS p(id, ordering)
+            WHERE f.version_id = $1 AND f.id = p.id
+            ",
+            id as VersionId,
+            &file_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+            primary as FileId,
         )
         .execute(&mut **transaction)
         .await?;
@@ -317,6 +380,75 @@ impl Version {
         Ok(())
     }
 
+    /// Records the result of scanning one of a version's files
+    pub async fn update_file_scan_status<'a, E>(
+        file_id: FileId,
+        status: FileScanStatus,
+        exec: E,
+    ) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        sqlx::query!(
+            "
+            UPDATE files
+            SET scan_status = $2, scanned_at = NOW()
+            WHERE id = $1
+            ",
+            file_id as FileId,
+            status.as_str(),
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Takes down one of a version's files, or reinstates it when `withheld_by` is `None`.
+    /// The file is kept so the takedown can be audited.
+    pub async fn set_file_withheld<'a, E>(
+        file_id: FileId,
+        withheld_by: Option<UserId>,
+        exec: E,
+    ) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        sqlx::query!(
+            "
+            UPDATE files
+            SET withheld_at = CASE WHEN $2::bigint IS NULL THEN NULL ELSE NOW() END, withheld_by = $2
+            WHERE id = $1
+            ",
+            file_id as FileId,
+            withheld_by.map(|x| x.0),
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Marks all of a version's files as waiting to be scanned again
+    pub async fn reset_file_scans<'a, E>(id: VersionId, exec: E) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        sqlx::query!(
+            "
+            UPDATE files
+            SET scan_status = $2
+            WHERE version_id = $1
+            ",
+            id as VersionId,
+            FileScanStatus::Pending.as_str(),
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn remove_full(
         id: VersionId,
         redis: &RedisPool,
@@ -461,354 +593,742 @@ impl Version {
             .map(|x| x.into_iter().next())
     }
 
-    pub async fn get_many<'a, E>(
-        version_ids: &[VersionId],
+    /// Gets every version of the given projects which supports the loader and at least one of the
+    /// game versions. Candidates are fetched with a single query, and game versions are matched
+    /// against each version's loader fields.
+    pub async fn get_many_for_loader<'a, E>(
+        project_ids: &[ProjectId],
+        loader: &str,
+        game_versions: &[String],
         exec: E,
         redis: &RedisPool,
     ) -> Result<Vec<QueryVersion>, DatabaseError>
     where
-        E: sqlx::Acquire<'a, Database = sqlx::Postgres>,
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>
+            + sqlx::Acquire<'a, Database = sqlx::Postgres>
+            + Copy,
     {
-        let version_ids = version_ids
-            .iter()
-            .unique()
-            .copied()
-            .collect::<Vec<VersionId>>();
-
-        use futures::stream::TryStreamExt;
-
-        if version_ids.is_empty() {
+        if project_ids.is_empty() {
             return Ok(Vec::new());
         }
 
-        let mut exec = exec.acquire().await?;
-        let mut redis = redis.connect().await?;
-
-        let mut version_ids_parsed: Vec<i64> = version_ids.iter().map(|x| x.0).collect();
+        let version_ids = sqlx::query!(
+            "
+            SELECT DISTINCT v.id
+            FROM versions v
+            INNER JOIN loaders_versions lv ON v.id = lv.version_id
+            INNER JOIN loaders l ON lv.loader_id = l.id
+            WHERE v.mod_id = ANY($1) AND l.loader = $2
+            ",
+            &project_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+            loader,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| VersionId(x.id))
+        .collect::<Vec<_>>();
 
-        let mut found_versions = Vec::new();
+        let game_versions = game_versions
+            .iter()
+            .map(|x| serde_json::Value::String(x.clone()))
+            .collect::<Vec<_>>();
 
-        let versions = redis
-            .multi_get::<String>(
-                VERSIONS_NAMESPACE,
-                version_ids_parsed
-                    .clone()
+        Ok(Self::get_many(&version_ids, exec, redis)
+            .await?
+            .into_iter()
+            .filter(|x| {
+                x.version_fields
                     .iter()
-                    .map(|x| x.to_string())
-                    .collect::<Vec<_>>(),
-            )
-            .await?;
+                    .find(|y| y.field_name == "game_versions")
+                    .map(|y| game_versions.iter().any(|v| y.value.contains_json_value(v)))
+                    .unwrap_or(false)
+            })
+            .collect())
+    }
 
-        for version in versions {
-            if let Some(version) =
-                version.and_then(|x| serde_json::from_str::<QueryVersion>(&x).ok())
-            {
-                version_ids_parsed.retain(|x| &version.inner.id.0 != x);
-                found_versions.push(version);
-                continue;
-            }
+    /// Gets the newest publicly visible version of each project which supports one of the
+    /// loaders and, unless none are given, one of the game versions
+    pub async fn get_newest_compatible<'a, E>(
+        project_ids: &[ProjectId],
+        loaders: &[String],
+        game_versions: &[String],
+        exec: E,
+    ) -> Result<HashMap<ProjectId, VersionId>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        if project_ids.is_empty() {
+            return Ok(HashMap::new());
         }
 
-        if !version_ids_parsed.is_empty() {
-            let loader_field_ids = DashSet::new();
-            let loader_field_enum_value_ids = DashSet::new();
-            let version_fields: DashMap<VersionId, Vec<QueryVersionField>> = sqlx::query!(
-                "
-                SELECT version_id, field_id, int_value, enum_value, string_value
-                FROM version_fields
-                WHERE version_id = ANY($1)
-                ",
-                &version_ids_parsed
-            )
-            .fetch(&mut *exec)
-            .try_fold(
-                DashMap::new(),
-                |acc: DashMap<VersionId, Vec<QueryVersionField>>, m| {
-                    let qvf = QueryVersionField {
-                        version_id: VersionId(m.version_id),
-                        field_id: LoaderFieldId(m.field_id),
-                        int_value: m.int_value,
-                        enum_value: m.enum_value.map(LoaderFieldEnumValueId),
-                        string_value: m.string_value,
-                    };
-
-                    loader_field_ids.insert(LoaderFieldId(m.field_id));
-                    if let Some(enum_value) = m.enum_value {
-                        loader_field_enum_value_ids.insert(LoaderFieldEnumValueId(enum_value));
-                    }
+        let newest = sqlx::query!(
+            "
+            SELECT DISTINCT ON (v.mod_id) v.mod_id, v.id
+            FROM versions v
+            INNER JOIN mods m ON m.id = v.mod_id
+            WHERE v.mod_id = ANY($1)
+                AND v.status = ANY($3) AND NOT (m.status = ANY($4))
+                AND EXISTS (
+                    SELECT 1 FROM loaders_versions lv
+                    INNER JOIN loaders l ON lv.loader_id = l.id
+                    WHERE lv.version_id = v.id AND l.loader = ANY($2)
+                )
+                AND (cardinality($5::text[]) = 0 OR EXISTS (
+                    SELECT 1 FROM version_fields vf
+                    INNER JOIN loader_fields lf ON lf.id = vf.field_id
+                    INNER JOIN loader_field_enum_values lfev ON lfev.id = vf.enum_value
+                    WHERE vf.version_id = v.id AND lf.field = 'game_versions'
+                        AND lfev.value = ANY($5)
+                ))
+            ORDER BY v.mod_id, v.date_published DESC, v.id DESC
+            ",
+            &project_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+            loaders,
+            &*VersionStatus::iterator()
+                .filter(|x| x.is_listed())
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>(),
+            &*ProjectStatus::iterator()
+                .filter(|x| x.is_hidden())
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>(),
+            game_versions,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| (ProjectId(x.mod_id), VersionId(x.id)))
+        .collect();
 
-                    acc.entry(VersionId(m.version_id)).or_default().push(qvf);
-                    async move { Ok(acc) }
-                },
-            )
-            .await?;
+        Ok(newest)
+    }
 
-            let loader_fields: Vec<QueryLoaderField> = sqlx::query!(
-                "
-                SELECT DISTINCT id, field, field_type, enum_type, min_val, max_val, optional
-                FROM loader_fields lf
-                WHERE id = ANY($1)  
-                ",
-                &loader_field_ids.iter().map(|x| x.0).collect::<Vec<_>>()
-            )
-            .fetch(&mut *exec)
-            .map_ok(|m| QueryLoaderField {
-                id: LoaderFieldId(m.id),
-                field: m.field,
-                field_type: m.field_type,
-                enum_type: m.enum_type.map(LoaderFieldEnumId),
-                min_val: m.min_val,
-                max_val: m.max_val,
-                optional: m.optional,
-            })
-            .try_collect()
-            .await?;
+    /// Walks the dependency graph of a version breadth-first, up to `max_depth` levels deep,
+    /// fetching each level in bulk. Dependencies on a project rather than a specific version are
+    /// resolved to the project's newest publicly visible version compatible with the root
+    /// version's loaders and game versions, which is then walked like any other. Incompatible
+    /// dependencies are skipped unless `include_incompatible` is set, in which case their
+    /// versions are included but not walked, and dependencies on incompatible projects aren't
+    /// resolved.
+    pub async fn get_dependency_graph<'a, E>(
+        version_id: VersionId,
+        max_depth: usize,
+        include_incompatible: bool,
+        exec: E,
+        redis: &RedisPool,
+    ) -> Result<DependencyGraph, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>
+            + sqlx::Acquire<'a, Database = sqlx::Postgres>
+            + Copy,
+    {
+        let mut graph = DependencyGraph::default();
+        let mut visited = HashSet::from([version_id]);
+        let mut leaves = HashSet::new();
+        let mut frontier = vec![version_id];
+        // The loaders and game versions of the root version, which resolved versions must support
+        let mut root_loaders = Vec::new();
+        let mut root_game_versions = Vec::new();
+
+        for depth in 0..=max_depth {
+            if frontier.is_empty() {
+                break;
+            }
 
-            let loader_field_enum_values: Vec<QueryLoaderFieldEnumValue> = sqlx::query!(
-                "
-                SELECT DISTINCT id, enum_id, value, ordering, created, metadata
-                FROM loader_field_enum_values lfev
-                WHERE id = ANY($1)  
-                ORDER BY enum_id, ordering, created ASC
-                ",
-                &loader_field_enum_value_ids
-                    .iter()
-                    .map(|x| x.0)
-                    .collect::<Vec<_>>()
-            )
-            .fetch(&mut *exec)
-            .map_ok(|m| QueryLoaderFieldEnumValue {
-                id: LoaderFieldEnumValueId(m.id),
-                enum_id: LoaderFieldEnumId(m.enum_id),
-                value: m.value,
-                ordering: m.ordering,
-                created: m.created,
-                metadata: m.metadata,
-            })
-            .try_collect()
-            .await?;
+            let versions = Self::get_many(&frontier, exec, redis).await?;
+            frontier = Vec::new();
 
-            type StringTriple = (Vec<String>, Vec<String>, Vec<String>);
-            let loaders_ptypes_games: DashMap<VersionId, StringTriple> = sqlx::query!(
-                "
-                SELECT DISTINCT version_id,
-                    ARRAY_AGG(DISTINCT l.loader) filter (where l.loader is not null) loaders,
-                    ARRAY_AGG(DISTINCT pt.name) filter (where pt.name is not null) project_types,
-                    ARRAY_AGG(DISTINCT g.slug) filter (where g.slug is not null) games
-                FROM versions v
-                INNER JOIN loaders_versions lv ON v.id = lv.version_id
-                INNER JOIN loaders l ON lv.loader_id = l.id
-                INNER JOIN loaders_project_types lpt ON lpt.joining_loader_id = l.id
-                INNER JOIN project_types pt ON pt.id = lpt.joining_project_type_id
-                INNER JOIN loaders_project_types_games lptg ON lptg.loader_id = l.id AND lptg.project_type_id = pt.id
-                INNER JOIN games g ON lptg.game_id = g.id
-                WHERE v.id = ANY($1)
-                GROUP BY version_id
-                ",
-                &version_ids_parsed
-            ).fetch(&mut *exec)
-            .map_ok(|m| {
-                let version_id = VersionId(m.version_id);
-                let loaders = m.loaders.unwrap_or_default();
-                    let project_types = m.project_types.unwrap_or_default();
-                    let games = m.games.unwrap_or_default();
+            if depth == 0 {
+                if let Some(root) = versions.first() {
+                    root_loaders = root.loaders.clone();
+                    root_game_versions = root
+                        .version_fields
+                        .iter()
+                        .find(|x| x.field_name == "game_versions")
+                        .map(|x| x.value.as_strings())
+                        .unwrap_or_default();
+                }
+            }
 
-                    (version_id, (loaders, project_types, games))
+            // The edges of this level which point at a project, to be resolved in bulk
+            let mut unresolved = Vec::new();
 
+            for version in &versions {
+                if depth == max_depth || leaves.contains(&version.inner.id) {
+                    continue;
                 }
-            ).try_collect().await?;
 
-            #[derive(Deserialize)]
-            struct Hash {
-                pub file_id: FileId,
-                pub algorithm: String,
-                pub hash: String,
-            }
+                for dependency in &version.dependencies {
+                    let incompatible =
+                        dependency.dependency_type == DependencyType::Incompatible.as_str();
+                    if incompatible && !include_incompatible {
+                        continue;
+                    }
 
-            #[derive(Deserialize)]
-            struct File {
-                pub id: FileId,
-                pub url: String,
-                pub filename: String,
-                pub primary: bool,
-                pub size: u32,
-                pub file_type: Option<FileType>,
+                    graph.edges.push(DependencyEdge {
+                        version_id: version.inner.id,
+                        dependency: dependency.clone(),
+                        resolved_version_id: None,
+                    });
+
+                    match (dependency.version_id, dependency.project_id) {
+                        (Some(dependency_version_id), _) => {
+                            if visited.insert(dependency_version_id) {
+                                if incompatible {
+                                    leaves.insert(dependency_version_id);
+                                }
+                                frontier.push(dependency_version_id);
+                            }
+                        }
+                        (None, Some(_)) if !incompatible => {
+                            unresolved.push(graph.edges.len() - 1);
+                        }
+                        _ => {}
+                    }
+                }
             }
 
-            let file_ids = DashSet::new();
-            let reverse_file_map = DashMap::new();
-            let files : DashMap<VersionId, Vec<File>> = sqlx::query!(
-                "
-                SELECT DISTINCT version_id, f.id, f.url, f.filename, f.is_primary, f.size, f.file_type
-                FROM files f
-                WHERE f.version_id = ANY($1)
-                ",
-                &version_ids_parsed
-            ).fetch(&mut *exec)
-            .try_fold(DashMap::new(), |acc : DashMap<VersionId, Vec<File>>, m| {
-                    let file = File {
-                        id: FileId(m.id),
-                        url: m.url,
-                        filename: m.filename,
-                        primary: m.is_primary,
-                        size: m.size as u32,
-                        file_type: m.file_type.map(|x| FileType::from_string(&x)),
-                    };
-
-                    file_ids.insert(FileId(m.id));
-                    reverse_file_map.insert(FileId(m.id), VersionId(m.version_id));
-
-                    acc.entry(VersionId(m.version_id))
-                    .or_default()
-                    .push(file);
-                    async move { Ok(acc) }
+            let project_ids = unresolved
+                .iter()
+                .filter_map(|x| graph.edges[*x].dependency.project_id)
+                .unique()
+                .collect::<Vec<_>>();
+            let resolved =
+                Self::get_newest_compatible(&project_ids, &root_loaders, &root_game_versions, exec)
+                    .await?;
+            for index in unresolved {
+                let edge = &mut graph.edges[index];
+                let Some(resolved_version_id) = edge
+                    .dependency
+                    .project_id
+                    .and_then(|x| resolved.get(&x).copied())
+                else {
+                    continue;
+                };
+
+                edge.resolved_version_id = Some(resolved_version_id);
+                if visited.insert(resolved_version_id) {
+                    frontier.push(resolved_version_id);
                 }
-            ).await?;
+            }
 
-            let hashes: DashMap<VersionId, Vec<Hash>> = sqlx::query!(
-                "
-                SELECT DISTINCT file_id, algorithm, encode(hash, 'escape') hash
-                FROM hashes
-                WHERE file_id = ANY($1)
-                ",
-                &file_ids.iter().map(|x| x.0).collect::<Vec<_>>()
-            )
-            .fetch(&mut *exec)
-            .try_fold(DashMap::new(), |acc: DashMap<VersionId, Vec<Hash>>, m| {
-                if let Some(found_hash) = m.hash {
-                    let hash = Hash {
-                        file_id: FileId(m.file_id),
-                        algorithm: m.algorithm,
-                        hash: found_hash,
-                    };
-
-                    if let Some(version_id) = reverse_file_map.get(&FileId(m.file_id)) {
-                        acc.entry(*version_id).or_default().push(hash);
+            graph.versions.extend(versions);
+        }
+
+        Ok(graph)
+    }
+
+    /// Gets the versions of a project published strictly after `from` and up to and including
+    /// `to`, oldest first, with the hashes of their files. Hidden and unlisted versions are
+    /// included, so callers should filter them by the requester's access.
+    pub async fn get_changelog_range<'a, E>(
+        project_id: ProjectId,
+        from: VersionId,
+        to: VersionId,
+        exec: E,
+    ) -> Result<Vec<ChangelogEntry>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        #[derive(Deserialize)]
+        struct Hash {
+            pub file_id: i64,
+            pub filename: String,
+            pub algorithm: String,
+            pub hash: String,
+        }
+
+        let entries = sqlx::query!(
+            "
+            SELECT v.id, v.version_number, v.version_type, v.changelog, v.status, v.date_published,
+            JSONB_AGG(DISTINCT jsonb_build_object('file_id', f.id, 'filename', f.filename, 'algorithm', h.algorithm, 'hash', encode(h.hash, 'escape'))) filter (where h.hash is not null) hashes
+            FROM versions v
+            LEFT JOIN files f ON f.version_id = v.id
+            LEFT JOIN hashes h ON h.file_id = f.id
+            WHERE v.mod_id = $1
+                AND v.date_published > (SELECT date_published FROM versions WHERE id = $2)
+                AND v.date_published <= (SELECT date_published FROM versions WHERE id = $3)
+            GROUP BY v.id
+            ORDER BY v.date_published
+            ",
+            project_id as ProjectId,
+            from as VersionId,
+            to as VersionId,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|v| {
+            let hashes = serde_json::from_value::<Vec<Hash>>(v.hashes.unwrap_or_default())
+                .unwrap_or_default();
+
+            let mut files: Vec<(i64, ChangelogFile)> = Vec::new();
+            for hash in hashes {
+                match files.iter_mut().find(|(id, _)| *id == hash.file_id) {
+                    Some((_, file)) => {
+                        file.hashes.insert(hash.algorithm, hash.hash);
                     }
+                    None => files.push((
+                        hash.file_id,
+                        ChangelogFile {
+                            filename: hash.filename,
+                            hashes: HashMap::from([(hash.algorithm, hash.hash)]),
+                        },
+                    )),
                 }
-                async move { Ok(acc) }
-            })
-            .await?;
+            }
 
-            let dependencies : DashMap<VersionId, Vec<QueryDependency>> = sqlx::query!(
-                "
-                SELECT DISTINCT dependent_id as version_id, d.mod_dependency_id as dependency_project_id, d.dependency_id as dependency_version_id, d.dependency_file_name as file_name, d.dependency_type as dependency_type
-                FROM dependencies d
-                WHERE dependent_id = ANY($1)
-                ",
-                &version_ids_parsed
-            ).fetch(&mut *exec)
-            .try_fold(DashMap::new(), |acc : DashMap<_,Vec<QueryDependency>>, m| {
-                    let dependency = QueryDependency {
-                        project_id: m.dependency_project_id.map(ProjectId),
-                        version_id: m.dependency_version_id.map(VersionId),
-                        file_name: m.file_name,
-                        dependency_type: m.dependency_type,
-                    };
-
-                    acc.entry(VersionId(m.version_id))
-                    .or_default()
-                    .push(dependency);
-                    async move { Ok(acc) }
-                }
-            ).await?;
+            ChangelogEntry {
+                id: VersionId(v.id),
+                version_number: v.version_number,
+                version_type: v.version_type,
+                changelog: v.changelog,
+                status: VersionStatus::from_string(&v.status),
+                date_published: v.date_published,
+                files: files.into_iter().map(|(_, file)| file).collect(),
+            }
+        })
+        .collect();
 
-            let db_versions: Vec<QueryVersion> = sqlx::query!(
-                "
-                SELECT v.id id, v.mod_id mod_id, v.author_id author_id, v.name version_name, v.version_number version_number,
-                v.changelog changelog, v.date_published date_published, v.downloads downloads,
-                v.version_type version_type, v.featured featured, v.status status, v.requested_status requested_status, v.ordering ordering
+        Ok(entries)
+    }
+
+    /// Gets a page of the project's versions matching the filters, newest first, starting after
+    /// the `before` version. Only the ids are fetched, so the page can be loaded with `get_many`.
+    pub async fn get_page<'a, E>(
+        project_id: ProjectId,
+        filters: &VersionPageFilters,
+        before: Option<VersionId>,
+        count: i64,
+        exec: E,
+    ) -> Result<VersionPage, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let (field_names, field_values): (Vec<_>, Vec<_>) =
+            filters.loader_fields.iter().cloned().unzip();
+
+        // A version matches a loader field if it doesn't have the field or has one of the values.
+        // One more version than the page holds is fetched to know whether there is a next page,
+        // and the page is left joined onto the total so the total is returned even for empty pages.
+        let rows = sqlx::query!(
+            "
+            WITH matching AS (
+                SELECT v.id, v.date_published
                 FROM versions v
-                WHERE v.id = ANY($1)
-                ORDER BY v.ordering ASC NULLS LAST, v.date_published ASC;
-                ",
-                &version_ids_parsed
+                WHERE v.mod_id = $1 AND v.status = ANY($2)
+                AND ($3::text[] IS NULL OR EXISTS (
+                    SELECT 1 FROM loaders_versions lv
+                    INNER JOIN loaders l ON l.id = lv.loader_id
+                    WHERE lv.version_id = v.id AND l.loader = ANY($3)
+                ))
+                AND ($4::bool IS NULL OR v.featured = $4)
+                AND ($5::text IS NULL OR v.version_type = $5)
+                AND NOT EXISTS (
+                    SELECT 1
+                    FROM version_fields vf
+                    INNER JOIN loader_fields lf ON lf.id = vf.field_id
+                    LEFT JOIN loader_field_enum_values lfev ON lfev.id = vf.enum_value
+                    LEFT JOIN UNNEST($6::text[], $7::text[]) f(field, value)
+                        ON f.field = lf.field AND f.value IN (lfev.value, vf.string_value, vf.int_value::text)
+                    WHERE vf.version_id = v.id AND lf.field = ANY($6)
+                    GROUP BY lf.field
+                    HAVING COUNT(f.value) = 0
+                )
             )
-                .fetch_many(&mut *exec)
-                .try_filter_map(|e| async {
-                    Ok(e.right().map(|v|
-                        {
-                        let version_id = VersionId(v.id);
-                        let (loaders, project_types, games) = loaders_ptypes_games.remove(&version_id).map(|x|x.1).unwrap_or_default();
-                        let files = files.remove(&version_id).map(|x|x.1).unwrap_or_default();
-                        let hashes = hashes.remove(&version_id).map(|x|x.1).unwrap_or_default();
-                        let version_fields = version_fields.remove(&version_id).map(|x|x.1).unwrap_or_default();
-                        let dependencies = dependencies.remove(&version_id).map(|x|x.1).unwrap_or_default();
-
-                        QueryVersion {
-                            inner: Version {
-                                id: VersionId(v.id),
-                                project_id: ProjectId(v.mod_id),
-                                author_id: UserId(v.author_id),
-                                name: v.version_name,
-                                version_number: v.version_number,
-                                changelog: v.changelog,
-                                date_published: v.date_published,
-                                downloads: v.downloads,
-                                version_type: v.version_type,
-                                featured: v.featured,
-                                status: VersionStatus::from_string(&v.status),
-                                requested_status: v.requested_status
-                                    .map(|x| VersionStatus::from_string(&x)),
-                                ordering: v.ordering,
-                            },
-                            files: {
-                                let mut files = files.into_iter().map(|x| {
-                                    let mut file_hashes = HashMap::new();
-
-                                    for hash in hashes.iter() {
-                                        if hash.file_id == x.id {
-                                            file_hashes.insert(
-                                                hash.algorithm.clone(),
-                                                hash.hash.clone(),
-                                            );
-                                        }
-                                    }
-
-                                    QueryFile {
-                                        id: x.id,
-                                        url: x.url.clone(),
-                                        filename: x.filename.clone(),
-                                        hashes: file_hashes,
-                                        primary: x.primary,
-                                        size: x.size,
-                                        file_type: x.file_type,
-                                    }
-                                }).collect::<Vec<_>>();
-
-                                files.sort_by(|a, b| {
-                                    if a.primary {
-                                        Ordering::Less
-                                    } else if b.primary {
-                                        Ordering::Greater
-                                    } else {
-                                        a.filename.cmp(&b.filename)
-                                    }
-                                });
-
-                                files
-                            },
-                            version_fields: VersionField::from_query_json(version_fields, &loader_fields, &loader_field_enum_values, false),
-                            loaders,
-                            project_types,
-                            games,
-                            dependencies,
+            SELECT t.total, p.id
+            FROM (SELECT COUNT(*) total FROM matching) t
+            LEFT JOIN LATERAL (
+                SELECT m.id
+                FROM matching m
+                WHERE $8::bigint IS NULL OR (m.date_published, m.id) < (
+                    SELECT bv.date_published, bv.id FROM versions bv WHERE bv.id = $8
+                )
+                ORDER BY m.date_published DESC, m.id DESC
+                LIMIT $9
+            ) p ON TRUE
+            ",
+            project_id as ProjectId,
+            &filters.statuses[..],
+            filters.loaders.as_deref(),
+            filters.featured,
+            filters.version_type.as_deref(),
+            &field_names[..],
+            &field_values[..],
+            before.map(|x| x.0),
+            count + 1,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        let total = rows.first().and_then(|x| x.total).unwrap_or(0);
+        let mut version_ids = rows
+            .into_iter()
+            .filter_map(|x| Some(VersionId(x.id?)))
+            .collect::<Vec<_>>();
+
+        let next_cursor = if version_ids.len() as i64 > count {
+            version_ids.truncate(count as usize);
+            version_ids.last().copied()
+        } else {
+            None
+        };
+
+        Ok(VersionPage {
+            total,
+            version_ids,
+            next_cursor,
+        })
+    }
+
+    pub async fn get_many<'a, E>(
+        version_ids: &[VersionId],
+        exec: E,
+        redis: &RedisPool,
+    ) -> Result<Vec<QueryVersion>, DatabaseError>
+    where
+        E: sqlx::Acquire<'a, Database = sqlx::Postgres>,
+    {
+        let version_ids = version_ids
+            .iter()
+            .unique()
+            .copied()
+            .collect::<Vec<VersionId>>();
+
+        use futures::stream::TryStreamExt;
+
+        if version_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        redis
+            .get_cached_keys(
+                VERSIONS_NAMESPACE,
+                None,
+                &version_ids
+                    .iter()
+                    .map(|x| x.0.to_string())
+                    .collect::<Vec<_>>(),
+                |x| x.parse().ok(),
+                |remaining_ids| async move {
+                    let mut exec = exec.acquire().await?;
+                    let version_ids_parsed = remaining_ids
+                        .iter()
+                        .flat_map(|x| x.parse().ok())
+                        .collect::<Vec<i64>>();
+
+                    let loader_field_ids = DashSet::new();
+                    let loader_field_enum_value_ids = DashSet::new();
+                    let version_fields: DashMap<VersionId, Vec<QueryVersionField>> = sqlx::query!(
+                        "
+                        SELECT version_id, field_id, int_value, enum_value, string_value
+                        FROM version_fields
+                        WHERE version_id = ANY($1)
+                        ",
+                        &version_ids_parsed
+                    )
+                    .fetch(&mut *exec)
+                    .try_fold(
+                        DashMap::new(),
+                        |acc: DashMap<VersionId, Vec<QueryVersionField>>, m| {
+                            let qvf = QueryVersionField {
+                                version_id: VersionId(m.version_id),
+                                field_id: LoaderFieldId(m.field_id),
+                                int_value: m.int_value,
+                                enum_value: m.enum_value.map(LoaderFieldEnumValueId),
+                                string_value: m.string_value,
+                            };
+
+                            loader_field_ids.insert(LoaderFieldId(m.field_id));
+                            if let Some(enum_value) = m.enum_value {
+                                loader_field_enum_value_ids.insert(LoaderFieldEnumValueId(enum_value));
+                            }
+
+                            acc.entry(VersionId(m.version_id)).or_default().push(qvf);
+                            async move { Ok(acc) }
+                        },
+                    )
+                    .await?;
+
+                    let loader_fields: Vec<QueryLoaderField> = sqlx::query!(
+                        "
+                        SELECT DISTINCT id, field, field_type, enum_type, min_val, max_val, optional
+                        FROM loader_fields lf
+                        WHERE id = ANY($1)  
+                        ",
+                        &loader_field_ids.iter().map(|x| x.0).collect::<Vec<_>>()
+                    )
+                    .fetch(&mut *exec)
+                    .map_ok(|m| QueryLoaderField {
+                        id: LoaderFieldId(m.id),
+                        field: m.field,
+                        field_type: m.field_type,
+                        enum_type: m.enum_type.map(LoaderFieldEnumId),
+                        min_val: m.min_val,
+                        max_val: m.max_val,
+                        optional: m.optional,
+                    })
+                    .try_collect()
+                    .await?;
+
+                    let loader_field_enum_values: Vec<QueryLoaderFieldEnumValue> = sqlx::query!(
+                        "
+                        SELECT DISTINCT id, enum_id, value, ordering, created, metadata
+                        FROM loader_field_enum_values lfev
+                        WHERE id = ANY($1)  
+                        ORDER BY enum_id, ordering, created ASC
+                        ",
+                        &loader_field_enum_value_ids
+                            .iter()
+                            .map(|x| x.0)
+                            .collect::<Vec<_>>()
+                    )
+                    .fetch(&mut *exec)
+                    .map_ok(|m| QueryLoaderFieldEnumValue {
+                        id: LoaderFieldEnumValueId(m.id),
+                        enum_id: LoaderFieldEnumId(m.enum_id),
+                        value: m.value,
+                        ordering: m.ordering,
+                        created: m.created,
+                        metadata: m.metadata,
+                    })
+                    .try_collect()
+                    .await?;
+
+                    type StringTriple = (Vec<String>, Vec<String>, Vec<String>);
+                    let loaders_ptypes_games: DashMap<VersionId, StringTriple> = sqlx::query!(
+                        "
+                        SELECT DISTINCT version_id,
+                            ARRAY_AGG(DISTINCT l.loader) filter (where l.loader is not null) loaders,
+                            ARRAY_AGG(DISTINCT pt.name) filter (where pt.name is not null) project_types,
+                            ARRAY_AGG(DISTINCT g.slug) filter (where g.slug is not null) games
+                        FROM versions v
+                        INNER JOIN loaders_versions lv ON v.id = lv.version_id
+                        INNER JOIN loaders l ON lv.loader_id = l.id
+                        INNER JOIN loaders_project_types lpt ON lpt.joining_loader_id = l.id
+                        INNER JOIN project_types pt ON pt.id = lpt.joining_project_type_id
+                        INNER JOIN loaders_project_types_games lptg ON lptg.loader_id = l.id AND lptg.project_type_id = pt.id
+                        INNER JOIN games g ON lptg.game_id = g.id
+                        WHERE v.id = ANY($1)
+                        GROUP BY version_id
+                        ",
+                        &version_ids_parsed
+                    ).fetch(&mut *exec)
+                    .map_ok(|m| {
+                        let version_id = VersionId(m.version_id);
+                        let loaders = m.loaders.unwrap_or_default();
+                            let project_types = m.project_types.unwrap_or_default();
+                            let games = m.games.unwrap_or_default();
+
+                            (version_id, (loaders, project_types, games))
+
                         }
-                }))
-                })
-                .try_collect::<Vec<QueryVersion>>()
-                .await?;
+                    ).try_collect().await?;
 
-            for version in db_versions {
-                redis
-                    .set_serialized_to_json(VERSIONS_NAMESPACE, version.inner.id.0, &version, None)
+                    #[derive(Deserialize)]
+                    struct Hash {
+                        pub file_id: FileId,
+                        pub algorithm: String,
+                        pub hash: String,
+                    }
+
+                    #[derive(Deserialize)]
+                    struct File {
+                        pub id: FileId,
+                        pub url: String,
+                        pub filename: String,
+                        pub primary: bool,
+                        pub size: u32,
+                        pub file_type: Option<FileType>,
+                        pub ordering: i64,
+                        pub scan_status: FileScanStatus,
+                        pub scanned_at: Option<DateTime<Utc>>,
+                        pub withheld_at: Option<DateTime<Utc>>,
+                    }
+
+                    let file_ids = DashSet::new();
+                    let reverse_file_map = DashMap::new();
+                    let files : DashMap<VersionId, Vec<File>> = sqlx::query!(
+                        "
+                        SELECT DISTINCT version_id, f.id, f.url, f.filename, f.is_primary, f.size, f.file_type, f.ordering, f.scan_status, f.scanned_at, f.withheld_at
+                        FROM files f
+                        WHERE f.version_id = ANY($1)
+                        ",
+                        &version_ids_parsed
+                    ).fetch(&mut *exec)
+                    .try_fold(DashMap::new(), |acc : DashMap<VersionId, Vec<File>>, m| {
+                            let file = File {
+                                id: FileId(m.id),
+                                url: m.url,
+                                filename: m.filename,
+                                primary: m.is_primary,
+                                size: m.size as u32,
+                                file_type: m.file_type.map(|x| FileType::from_string(&x)),
+                                ordering: m.ordering,
+                                scan_status: FileScanStatus::from_string(&m.scan_status),
+                                scanned_at: m.scanned_at,
+                                withheld_at: m.withheld_at,
+                            };
+
+                            file_ids.insert(FileId(m.id));
+                            reverse_file_map.insert(FileId(m.id), VersionId(m.version_id));
+
+                            acc.entry(VersionId(m.version_id))
+                            .or_default()
+                            .push(file);
+                            async move { Ok(acc) }
+                        }
+                    ).await?;
+
+                    let hashes: DashMap<VersionId, Vec<Hash>> = sqlx::query!(
+                        "
+                        SELECT DISTINCT file_id, algorithm, encode(hash, 'escape') hash
+                        FROM hashes
+                        WHERE file_id = ANY($1)
+                        ",
+                        &file_ids.iter().map(|x| x.0).collect::<Vec<_>>()
+                    )
+                    .fetch(&mut *exec)
+                    .try_fold(DashMap::new(), |acc: DashMap<VersionId, Vec<Hash>>, m| {
+                        if let Some(found_hash) = m.hash {
+                            let hash = Hash {
+                                file_id: FileId(m.file_id),
+                                algorithm: m.algorithm,
+                                hash: found_hash,
+                            };
+
+                            if let Some(version_id) = reverse_file_map.get(&FileId(m.file_id)) {
+                                acc.entry(*version_id).or_default().push(hash);
+                            }
+                        }
+                        async move { Ok(acc) }
+                    })
                     .await?;
 
-                found_versions.push(version);
-            }
-        }
+                    let dependencies : DashMap<VersionId, Vec<QueryDependency>> = sqlx::query!(
+                        "
+                        SELECT DISTINCT dependent_id as version_id, d.mod_dependency_id as dependency_project_id, d.dependency_id as dependency_version_id, d.dependency_file_name as file_name, d.dependency_type as dependency_type, d.inferred as inferred
+                        FROM dependencies d
+                        WHERE dependent_id = ANY($1)
+                        ",
+                        &version_ids_parsed
+                    ).fetch(&mut *exec)
+                    .try_fold(DashMap::new(), |acc : DashMap<_,Vec<QueryDependency>>, m| {
+                            let dependency = QueryDependency {
+                                project_id: m.dependency_project_id.map(ProjectId),
+                                version_id: m.dependency_version_id.map(VersionId),
+                                file_name: m.file_name,
+                                dependency_type: m.dependency_type,
+                                inferred: m.inferred,
+                            };
+
+                            acc.entry(VersionId(m.version_id))
+                            .or_default()
+                            .push(dependency);
+                            async move { Ok(acc) }
+                        }
+                    ).await?;
+
+                    let db_versions: Vec<QueryVersion> = sqlx::query!(
+                        "
+                        SELECT v.id id, v.mod_id mod_id, v.author_id author_id, v.name version_name, v.version_number version_number,
+                        v.changelog changelog, v.date_published date_published, v.downloads downloads,
+                        v.version_type version_type, v.featured featured, v.status status, v.requested_status requested_status, v.ordering ordering,
+                        v.publish_at publish_at, v.flagged_reupload flagged_reupload
+                        FROM versions v
+                        WHERE v.id = ANY($1)
+                        ORDER BY v.ordering ASC NULLS LAST, v.date_published ASC;
+                        ",
+                        &version_ids_parsed
+                    )
+                        .fetch_many(&mut *exec)
+                        .try_filter_map(|e| async {
+                            Ok(e.right().map(|v|
+                                {
+                                let version_id = VersionId(v.id);
+                                let (loaders, project_types, games) = loaders_ptypes_games.remove(&version_id).map(|x|x.1).unwrap_or_default();
+                                let mut files = files.remove(&version_id).map(|x|x.1).unwrap_or_default();
+                                let hashes = hashes.remove(&version_id).map(|x|x.1).unwrap_or_default();
+                                let version_fields = version_fields.remove(&version_id).map(|x|x.1).unwrap_or_default();
+                                let dependencies = dependencies.remove(&version_id).map(|x|x.1).unwrap_or_default();
+
+                                QueryVersion {
+                                    inner: Version {
+                                        id: VersionId(v.id),
+                                        project_id: ProjectId(v.mod_id),
+                                        author_id: UserId(v.author_id),
+                                        name: v.version_name,
+                                        version_number: v.version_number,
+                                        changelog: v.changelog,
+                                        date_published: v.date_published,
+                                        downloads: v.downloads,
+                                        version_type: v.version_type,
+                                        featured: v.featured,
+                                        status: VersionStatus::from_string(&v.status),
+                                        requested_status: v.requested_status
+                                            .map(|x| VersionStatus::from_string(&x)),
+                                        ordering: v.ordering,
+                                        publish_at: v.publish_at,
+                                        flagged_reupload: v.flagged_reupload,
+                                    },
+                                    files: {
+                                        files.sort_by(|a, b| {
+                                            a.ordering.cmp(&b.ordering).then_with(|| a.filename.cmp(&b.filename))
+                                        });
+
+                                        files.into_iter().map(|x| {
+                                            let mut file_hashes = HashMap::new();
+
+                                            for hash in hashes.iter() {
+                                                if hash.file_id == x.id {
+                                                    file_hashes.insert(
+                                                        hash.algorithm.clone(),
+                                                        hash.hash.clone(),
+                                                    );
+                                                }
+                                            }
+
+                                            QueryFile {
+                                                id: x.id,
+                                                url: x.url.clone(),
+                                                filename: x.filename.clone(),
+                                                hashes: file_hashes,
+                                                primary: x.primary,
+                                                size: x.size,
+                                                file_type: x.file_type,
+                                                scan_status: x.scan_status,
+                                                scanned_at: x.scanned_at,
+                                                withheld_at: x.withheld_at,
+                                            }
+                                        }).collect::<Vec<_>>()
+                                    },
+                                    version_fields: VersionField::from_query_json(version_fields, &loader_fields, &loader_field_enum_values, false),
+                                    loaders,
+                                    project_types,
+                                    games,
+                                    dependencies,
+                                    etag: String::new(),
+                                }
+                        }))
+                        })
+                        .try_collect::<Vec<QueryVersion>>()
+                        .await?;
+
+                    let mut versions = Vec::new();
+                    for mut version in db_versions {
+                        version.etag = item_etag(
+                            version.inner.date_published,
+                            &crate::models::projects::Version::from(version.clone()),
+                        );
 
-        Ok(found_versions)
+                        versions.push(version);
+                    }
+
+                    Ok::<_, DatabaseError>(versions)
+                },
+            )
+            .await
     }
 
     pub async fn get_file_from_hash<'a, 'b, E>(
@@ -876,7 +1396,7 @@ impl Version {
         if !file_ids_parsed.is_empty() {
             let db_files: Vec<SingleFile> = sqlx::query!(
                 "
-                SELECT f.id, f.version_id, v.mod_id, f.url, f.filename, f.is_primary, f.size, f.file_type,
+                SELECT f.id, f.version_id, v.mod_id, f.url, f.filename, f.is_primary, f.size, f.file_type, f.withheld_at,
                 JSONB_AGG(DISTINCT jsonb_build_object('algorithm', h.algorithm, 'hash', encode(h.hash, 'escape'))) filter (where h.hash is not null) hashes
                 FROM files f
                 INNER JOIN versions v on v.id = f.version_id
@@ -911,6 +1431,7 @@ impl Version {
                             primary: f.is_primary,
                             size: f.size as u32,
                             file_type: f.file_type.map(|x| FileType::from_string(&x)),
+                            withheld_at: f.withheld_at,
                         }
                     }
                     ))
@@ -950,6 +1471,10 @@ impl Version {
     ) -> Result<(), DatabaseError> {
         let mut redis = redis.connect().await?;
 
+        // The version may have been created since it was looked up
+        redis
+            .delete_missing(VERSIONS_NAMESPACE, None, version.inner.id.0, None)
+            .await?;
         redis
             .delete_many(
                 iter::once((VERSIONS_NAMESPACE, Some(version.inner.id.0.to_string()))).chain(
@@ -975,6 +1500,135 @@ pub struct QueryVersion {
     pub project_types: Vec<String>,
     pub games: Vec<String>,
     pub dependencies: Vec<QueryDependency>,
+    /// Computed when the version is cached so conditional requests don't need to serialize it
+    pub etag: String,
+}
+
+impl CachedEntry for QueryVersion {
+    fn cache_id(&self) -> i64 {
+        self.inner.id.0
+    }
+}
+
+/// Filters for a page of a project's versions
+#[derive(Clone, Debug, Default)]
+pub struct VersionPageFilters {
+    /// The statuses of the versions to include
+    pub statuses: Vec<String>,
+    pub loaders: Option<Vec<String>>,
+    /// Pairs of loader field names and values, of which versions have to match any value given
+    /// for each field. Values are compared as text, with booleans as `0` or `1`.
+    pub loader_fields: Vec<(String, String)>,
+    pub featured: Option<bool>,
+    pub version_type: Option<String>,
+}
+
+pub struct VersionPage {
+    /// The number of versions matching the filters, across all pages
+    pub total: i64,
+    pub version_ids: Vec<VersionId>,
+    /// The last version of the page, if there are more versions after it
+    pub next_cursor: Option<VersionId>,
+}
+
+/// The newest version of a project for a game version and loader
+#[derive(Clone, Debug)]
+pub struct VersionMatrixCell {
+    pub version_id: VersionId,
+    pub version_type: String,
+}
+
+/// The newest version of a project for each game version and loader its versions support
+#[derive(Clone, Debug, Default)]
+pub struct VersionMatrix {
+    /// Newest first, in the order of the game version loader field's values
+    pub game_versions: Vec<String>,
+    pub loaders: Vec<String>,
+    /// A row for each game version with a cell for each loader, empty where no version
+    /// supports both
+    pub cells: Vec<Vec<Option<VersionMatrixCell>>>,
+}
+
+impl VersionMatrix {
+    /// Builds the matrix from the versions of a project, only counting versions of the given
+    /// types if any are given. Versions are only visited once, so this scales with the number of
+    /// versions rather than the size of the matrix.
+    pub fn build(versions: &[QueryVersion], version_types: Option<&[String]>) -> Self {
+        let mut game_versions = HashMap::new();
+        let mut loaders = HashSet::new();
+        let mut newest: HashMap<(&str, &str), &QueryVersion> = HashMap::new();
+
+        for version in versions.iter().filter(|x| {
+            version_types
+                .map(|types| types.contains(&x.inner.version_type))
+                .unwrap_or(true)
+        }) {
+            let Some(VersionFieldValue::ArrayEnum(_, values)) = version
+                .version_fields
+                .iter()
+                .find(|x| x.field_name == MinecraftGameVersion::FIELD_NAME)
+                .map(|x| &x.value)
+            else {
+                continue;
+            };
+
+            for value in values {
+                game_versions
+                    .entry(value.value.as_str())
+                    .or_insert((value.ordering, value.created));
+
+                for loader in &version.loaders {
+                    loaders.insert(loader.as_str());
+                    newest
+                        .entry((value.value.as_str(), loader.as_str()))
+                        .and_modify(|x| {
+                            if version.inner.date_published > x.inner.date_published {
+                                *x = version;
+                            }
+                        })
+                        .or_insert(version);
+                }
+            }
+        }
+
+        // Game versions are ordered like the values of their loader field are listed
+        let game_versions = game_versions
+            .into_iter()
+            .sorted_by(
+                |(a, (a_ordering, a_created)), (b, (b_ordering, b_created))| {
+                    a_ordering
+                        .cmp(b_ordering)
+                        .then(b_created.cmp(a_created))
+                        .then(a.cmp(b))
+                },
+            )
+            .map(|(x, _)| x)
+            .collect::<Vec<_>>();
+        let loaders = loaders.into_iter().sorted().collect::<Vec<_>>();
+
+        let cells = game_versions
+            .iter()
+            .map(|game_version| {
+                loaders
+                    .iter()
+                    .map(|loader| {
+                        newest
+                            .get(&(*game_version, *loader))
+                            .map(|x| VersionMatrixCell {
+                                version_id: x.inner.id,
+                                version_type: x.inner.version_type.clone(),
+                            })
+                    })
+                    .collect()
+            })
+            .collect();
+
+        VersionMatrix {
+            game_versions: game_versions.into_iter().map(String::from).collect(),
+            loaders: loaders.into_iter().map(String::from).collect(),
+            cells,
+        }
+    }
 }
 
 #[derive(Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -983,6 +1637,93 @@ pub struct QueryDependency {
     pub version_id: Option<VersionId>,
     pub file_name: Option<String>,
     pub dependency_type: String,
+    pub inferred: bool,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ChangelogEntry {
+    pub id: VersionId,
+    pub version_number: String,
+    pub version_type: String,
+    pub changelog: String,
+    pub status: VersionStatus,
+    pub date_published: DateTime<Utc>,
+    pub files: Vec<ChangelogFile>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ChangelogFile {
+    pub filename: String,
+    pub hashes: HashMap<String, String>,
+}
+
+#[derive(Clone)]
+pub struct DependencyEdge {
+    /// The version which has the dependency
+    pub version_id: VersionId,
+    pub dependency: QueryDependency,
+    /// The version a dependency on a project was resolved to
+    pub resolved_version_id: Option<VersionId>,
+}
+
+impl DependencyEdge {
+    /// The version the dependency points at, either directly or through its project
+    pub fn target_version_id(&self) -> Option<VersionId> {
+        self.dependency.version_id.or(self.resolved_version_id)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct DependencyGraph {
+    /// Every version reached from the root version, including itself
+    pub versions: Vec<QueryVersion>,
+    pub edges: Vec<DependencyEdge>,
+}
+
+/// Finds the circular dependencies reachable from the root version, returning the versions of
+/// each cycle in dependency order. Incompatible dependencies never form part of a cycle.
+// Depth-first search from the root, where any edge back to a version on the current path closes a cycle
+pub fn find_dependency_cycles(root: VersionId, edges: &[DependencyEdge]) -> Vec<Vec<VersionId>> {
+    fn visit(
+        version_id: VersionId,
+        adjacency: &HashMap<VersionId, Vec<VersionId>>,
+        path: &mut Vec<VersionId>,
+        done: &mut HashSet<VersionId>,
+        cycles: &mut Vec<Vec<VersionId>>,
+    ) {
+        path.push(version_id);
+        for dependency in adjacency.get(&version_id).into_iter().flatten() {
+            if let Some(start) = path.iter().position(|x| x == dependency) {
+                cycles.push(path[start..].to_vec());
+            } else if !done.contains(dependency) {
+                visit(*dependency, adjacency, path, done, cycles);
+            }
+        }
+        path.pop();
+        done.insert(version_id);
+    }
+
+    let mut adjacency: HashMap<VersionId, Vec<VersionId>> = HashMap::new();
+    for edge in edges {
+        if let Some(dependency_version_id) = edge.target_version_id() {
+            if edge.dependency.dependency_type != DependencyType::Incompatible.as_str() {
+                adjacency
+                    .entry(edge.version_id)
+                    .or_default()
+                    .push(dependency_version_id);
+            }
+        }
+    }
+
+    let mut cycles = Vec::new();
+    visit(
+        root,
+        &adjacency,
+        &mut Vec::new(),
+        &mut HashSet::new(),
+        &mut cycles,
+    );
+    cycles
 }
 
 #[derive(Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -994,6 +1735,10 @@ pub struct QueryFile {
     pub primary: bool,
     pub size: u32,
     pub file_type: Option<FileType>,
+    pub scan_status: FileScanStatus,
+    pub scanned_at: Option<DateTime<Utc>>,
+    /// When the file was taken down by a moderator. Withheld files can't be downloaded.
+    pub withheld_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -1007,6 +1752,7 @@ pub struct SingleFile {
     pub primary: bool,
     pub size: u32,
     pub file_type: Option<FileType>,
+    pub withheld_at: Option<DateTime<Utc>>,
 }
 
 impl std::cmp::Ord for QueryVersion {
@@ -1085,6 +1831,8 @@ mod tests {
             featured: Default::default(),
             status: VersionStatus::Listed,
             requested_status: Default::default(),
+            publish_at: Default::default(),
+            flagged_reupload: Default::default(),
         }
     }
 }
@@ -0,0 +1,266 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{DatabaseError, ProjectId, VersionId, WebhookId};
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ProjectWebhook {
+    pub id: WebhookId,
+    pub project_id: ProjectId,
+    pub url: String,
+    pub secret: String,
+    pub created: DateTime<Utc>,
+
+    pub last_attempt: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl ProjectWebhook {
+    pub async fn insert(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO project_webhooks (
+                id, project_id, url, secret
+            )
+            VALUES (
+                $1, $2, $3, $4
+            )
+            ",
+            self.id.0,
+            self.project_id.0,
+            self.url,
+            self.secret,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get<'a, E>(id: WebhookId, exec: E) -> Result<Option<ProjectWebhook>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let result = sqlx::query!(
+            "
+            SELECT id, project_id, url, secret, created, last_attempt, last_error
+            FROM project_webhooks
+            WHERE id = $1
+            ",
+            id.0
+        )
+        .fetch_optional(exec)
+        .await?;
+
+        Ok(result.map(|r| ProjectWebhook {
+            id: WebhookId(r.id),
+            project_id: ProjectId(r.project_id),
+            url: r.url,
+            secret: r.secret,
+            created: r.created,
+            last_attempt: r.last_attempt,
+            last_error: r.last_error,
+        }))
+    }
+
+    pub async fn get_all_project<'a, E>(
+        project_id: ProjectId,
+        exec: E,
+    ) -> Result<Vec<ProjectWebhook>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let results = sqlx::query!(
+            "
+            SELECT id, project_id, url, secret, created, last_attempt, last_error
+            FROM project_webhooks
+            WHERE project_id = $1
+            ORDER BY created ASC
+            ",
+            project_id.0
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| ProjectWebhook {
+                id: WebhookId(r.id),
+                project_id: ProjectId(r.project_id),
+                url: r.url,
+                secret: r.secret,
+                created: r.created,
+                last_attempt: r.last_attempt,
+                last_error: r.last_error,
+            })
+            .collect())
+    }
+
+    /// Records the outcome of the latest delivery attempt. A `None` error clears any
+    /// previously recorded failure.
+    pub async fn update_attempt<'a, E>(
+        id: WebhookId,
+        last_error: Option<String>,
+        exec: E,
+    ) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        sqlx::query!(
+            "
+            UPDATE project_webhooks
+            SET last_attempt = NOW(), last_error = $2
+            WHERE id = $1
+            ",
+            id.0,
+            last_error,
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(
+        id: WebhookId,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<()>, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            DELETE FROM project_webhooks
+            WHERE id = $1
+            ",
+            id.0,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(()))
+    }
+}
+
+/// A delivery of a version to a webhook which hasn't succeeded yet
+#[derive(Clone, Debug)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: WebhookId,
+    pub version_id: VersionId,
+    /// How many times delivering it has failed
+    pub attempts: i32,
+}
+
+impl WebhookDelivery {
+    /// Queues deliveries of each of the versions to each of their project's webhooks
+    pub async fn insert_versions<'a, E>(
+        version_ids: &[VersionId],
+        exec: E,
+    ) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        sqlx::query!(
+            "
+            INSERT INTO webhook_deliveries (webhook_id, version_id)
+            SELECT pw.id, v.id
+            FROM versions v
+            INNER JOIN project_webhooks pw ON pw.project_id = v.mod_id
+            WHERE v.id = ANY($1)
+            ON CONFLICT (webhook_id, version_id) DO NOTHING
+            ",
+            &version_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Claims up to `count` deliveries which are due, oldest first. They aren't due again until
+    /// `lease_until`, so other instances don't send them too, and they're retried then if this
+    /// one stops before finishing them.
+    pub async fn claim_due<'a, E>(
+        count: i64,
+        lease_until: DateTime<Utc>,
+        exec: E,
+    ) -> Result<Vec<WebhookDelivery>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let deliveries = sqlx::query!(
+            "
+            UPDATE webhook_deliveries
+            SET next_attempt = $2
+            WHERE id IN (
+                SELECT id FROM webhook_deliveries
+                WHERE next_attempt <= NOW()
+                ORDER BY next_attempt
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, webhook_id, version_id, attempts
+            ",
+            count,
+            lease_until,
+        )
+        .fetch_all(exec)
+        .await?;
+
+        Ok(deliveries
+            .into_iter()
+            .map(|x| WebhookDelivery {
+                id: x.id,
+                webhook_id: WebhookId(x.webhook_id),
+                version_id: VersionId(x.version_id),
+                attempts: x.attempts,
+            })
+            .collect())
+    }
+
+    /// Records a failed attempt, to be retried at `next_attempt`
+    pub async fn retry_at<'a, E>(
+        id: i64,
+        next_attempt: DateTime<Utc>,
+        exec: E,
+    ) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        sqlx::query!(
+            "
+            UPDATE webhook_deliveries
+            SET attempts = attempts + 1, next_attempt = $2
+            WHERE id = $1
+            ",
+            id,
+            next_attempt,
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove<'a, E>(id: i64, exec: E) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        sqlx::query!(
+            "
+            DELETE FROM webhook_deliveries
+            WHERE id = $1
+            ",
+            id,
+        )
+        .execute(exec)
+        .await?;
+
+        Ok(())
+    }
+}
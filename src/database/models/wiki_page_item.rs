@@ -0,0 +1,203 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{DatabaseError, ProjectId, UserId};
+
+/// A page of a project's documentation, beyond its description
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct WikiPage {
+    pub project_id: ProjectId,
+    pub slug: String,
+    pub title: String,
+    pub body: String,
+    pub ordering: i32,
+    pub updated_by: UserId,
+    pub created: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+}
+
+impl WikiPage {
+    /// Adds the page after the project's other pages
+    pub async fn insert(
+        &self,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            INSERT INTO project_wiki_pages (mod_id, slug, title, body, ordering, updated_by)
+            SELECT $1, $2, $3, $4, COALESCE(MAX(ordering) + 1, 0), $5
+            FROM project_wiki_pages
+            WHERE mod_id = $1
+            ",
+            self.project_id as ProjectId,
+            self.slug,
+            self.title,
+            self.body,
+            self.updated_by as UserId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get<'a, E>(
+        project_id: ProjectId,
+        slug: &str,
+        exec: E,
+    ) -> Result<Option<WikiPage>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let page = sqlx::query!(
+            "
+            SELECT mod_id, slug, title, body, ordering, updated_by, created, updated
+            FROM project_wiki_pages
+            WHERE mod_id = $1 AND slug = $2
+            ",
+            project_id as ProjectId,
+            slug,
+        )
+        .fetch_optional(exec)
+        .await?
+        .map(|x| WikiPage {
+            project_id: ProjectId(x.mod_id),
+            slug: x.slug,
+            title: x.title,
+            body: x.body,
+            ordering: x.ordering,
+            updated_by: UserId(x.updated_by),
+            created: x.created,
+            updated: x.updated,
+        });
+
+        Ok(page)
+    }
+
+    /// Gets all of the project's pages, in order
+    pub async fn get_for_project<'a, E>(
+        project_id: ProjectId,
+        exec: E,
+    ) -> Result<Vec<WikiPage>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let pages = sqlx::query!(
+            "
+            SELECT mod_id, slug, title, body, ordering, updated_by, created, updated
+            FROM project_wiki_pages
+            WHERE mod_id = $1
+            ORDER BY ordering, slug
+            ",
+            project_id as ProjectId,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| WikiPage {
+            project_id: ProjectId(x.mod_id),
+            slug: x.slug,
+            title: x.title,
+            body: x.body,
+            ordering: x.ordering,
+            updated_by: UserId(x.updated_by),
+            created: x.created,
+            updated: x.updated,
+        })
+        .collect();
+
+        Ok(pages)
+    }
+
+    /// Gets the slugs of all of the project's pages, which is cheaper than getting the pages
+    pub async fn get_slugs<'a, E>(
+        project_id: ProjectId,
+        exec: E,
+    ) -> Result<Vec<String>, DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        let slugs = sqlx::query!(
+            "
+            SELECT slug
+            FROM project_wiki_pages
+            WHERE mod_id = $1
+            ",
+            project_id as ProjectId,
+        )
+        .fetch_all(exec)
+        .await?
+        .into_iter()
+        .map(|x| x.slug)
+        .collect();
+
+        Ok(slugs)
+    }
+
+    /// Saves the page's slug, title and body over the page which had the slug `old_slug`
+    pub async fn update(
+        &self,
+        old_slug: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE project_wiki_pages
+            SET slug = $3, title = $4, body = $5, updated_by = $6, updated = NOW()
+            WHERE mod_id = $1 AND slug = $2
+            ",
+            self.project_id as ProjectId,
+            old_slug,
+            self.slug,
+            self.title,
+            self.body,
+            self.updated_by as UserId,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Removes the page, returning whether it existed
+    pub async fn remove(
+        project_id: ProjectId,
+        slug: &str,
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, DatabaseError> {
+        let result = sqlx::query!(
+            "
+            DELETE FROM project_wiki_pages
+            WHERE mod_id = $1 AND slug = $2
+            ",
+            project_id as ProjectId,
+            slug,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Sets the ordering of a project's pages to their position in `slugs`
+    pub async fn reorder(
+        project_id: ProjectId,
+        slugs: &[String],
+        transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), DatabaseError> {
+        sqlx::query!(
+            "
+            UPDATE project_wiki_pages p
+            SET ordering = o.ordering - 1
+            FROM UNNEST($2::varchar[]) WITH ORDINALITY AS o(slug, ordering)
+            WHERE p.mod_id = $1 AND p.slug = o.slug
+            ",
+            project_id as ProjectId,
+            slugs,
+        )
+        .execute(&mut **transaction)
+        .await?;
+
+        Ok(())
+    }
+}
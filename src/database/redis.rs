@@ -3,12 +3,49 @@ use deadpool_redis::{Config, Runtime};
 use itertools::Itertools;
 use redis::{cmd, Cmd, FromRedisValue};
 use std::fmt::Display;
+use std::future::Future;
+use std::time::{Duration, Instant};
 
 const DEFAULT_EXPIRY: i64 = 1800; // 30 minutes
 
+// How long keys which weren't found are cached as missing, in seconds
+const MISSING_EXPIRY: i64 = 60;
+// How long a caller can hold the lock on fetching a key, in seconds
+const FETCH_LOCK_EXPIRY: i64 = 5;
+// How long callers wait for another caller to fetch a key before fetching it themselves
+const FETCH_LOCK_WAIT: Duration = Duration::from_secs(3);
+const FETCH_LOCK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// An item which is cached by `RedisPool::get_cached_keys`
+pub trait CachedEntry: serde::Serialize + serde::de::DeserializeOwned {
+    /// The id the item is cached by
+    fn cache_id(&self) -> i64;
+
+    /// The slug the item can also be looked up by, if it has one
+    fn cache_slug(&self) -> Option<String> {
+        None
+    }
+
+    /// The key a slug is cached by. Slugs are case insensitive unless this is overridden, as it
+    /// is for tokens.
+    fn slug_key(slug: &str) -> String {
+        slug.to_lowercase()
+    }
+}
+
+/// The namespace the keys of `namespace` which weren't found are cached as missing in
+pub fn missing_namespace(namespace: &str) -> String {
+    format!("{}_missing", namespace)
+}
+
+fn lock_namespace(namespace: &str) -> String {
+    format!("{}_lock", namespace)
+}
+
 #[derive(Clone)]
 pub struct RedisPool {
     pub pool: deadpool_redis::Pool,
+    url: String,
     meta_namespace: String,
 }
 
@@ -22,7 +59,8 @@ impl RedisPool {
     // testing pool uses a hashmap to mimic redis behaviour for very small data sizes (ie: tests)
     // PANICS: production pool will panic if redis url is not set
     pub fn new(meta_namespace: Option<String>) -> Self {
-        let redis_pool = Config::from_url(dotenvy::var("REDIS_URL").expect("Redis URL not set"))
+        let url = dotenvy::var("REDIS_URL").expect("Redis URL not set");
+        let redis_pool = Config::from_url(url.clone())
             .builder()
             .expect("Error building Redis pool")
             .max_size(
@@ -37,6 +75,7 @@ impl RedisPool {
 
         RedisPool {
             pool: redis_pool,
+            url,
             meta_namespace: meta_namespace.unwrap_or("".to_string()),
         }
     }
@@ -47,9 +86,265 @@ impl RedisPool {
             meta_namespace: self.meta_namespace.clone(),
         })
     }
+
+    // Subscribes to a pub/sub channel. Subscribed connections can't run other commands, so
+    // each subscription gets its own connection rather than one from the pool.
+    pub async fn subscribe(
+        &self,
+        namespace: &str,
+        id: &str,
+    ) -> Result<redis::aio::PubSub, DatabaseError> {
+        let client = redis::Client::open(self.url.as_str())?;
+        let mut pubsub = client.get_async_connection().await?.into_pubsub();
+        pubsub
+            .subscribe(format!("{}_{}:{}", self.meta_namespace, namespace, id))
+            .await?;
+        Ok(pubsub)
+    }
+
+    /// Gets the items with the ids or slugs in `keys`, calling `fetch` with the keys which
+    /// aren't cached. Keys which aren't found are cached as missing for a short while, until an
+    /// item with the key is created. Only one caller fetches a key at a time, while the others
+    /// wait for it to be cached.
+    ///
+    /// `parse_id` gets the id a key refers to, and `slug_namespace` is where the ids of the
+    /// items are cached by their lowercase slugs, for items which have them.
+    pub async fn get_cached_keys<T, F, Fut>(
+        &self,
+        namespace: &str,
+        slug_namespace: Option<&str>,
+        keys: &[String],
+        parse_id: fn(&str) -> Option<i64>,
+        fetch: F,
+    ) -> Result<Vec<T>, DatabaseError>
+    where
+        T: CachedEntry,
+        F: FnOnce(Vec<String>) -> Fut,
+        Fut: Future<Output = Result<Vec<T>, DatabaseError>>,
+    {
+        let keys = keys.iter().unique().cloned().collect_vec();
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut redis = self.connect().await?;
+        let lock_namespace = lock_namespace(namespace);
+
+        let (mut found, remaining) = redis
+            .get_cached(namespace, slug_namespace, keys, parse_id)
+            .await?;
+
+        let acquired = redis
+            .set_many_if_absent(
+                &lock_namespace,
+                remaining.iter().map(|x| T::slug_key(x)),
+                "",
+                Some(FETCH_LOCK_EXPIRY),
+            )
+            .await?;
+        let mut locked = Vec::new();
+        let mut waiting = Vec::new();
+        for (key, acquired) in remaining.into_iter().zip(acquired) {
+            if acquired {
+                locked.push(key);
+            } else {
+                waiting.push(key);
+            }
+        }
+
+        // Keys another caller is fetching are waited for, unless it gives up on them
+        let mut fetching = locked.clone();
+        let started = Instant::now();
+        while !waiting.is_empty() {
+            if started.elapsed() >= FETCH_LOCK_WAIT {
+                fetching.append(&mut waiting);
+                break;
+            }
+            tokio::time::sleep(FETCH_LOCK_POLL_INTERVAL).await;
+
+            // The locks are checked first, as they're released after the keys are cached
+            let locks = redis
+                .multi_get::<String>(&lock_namespace, waiting.iter().map(|x| T::slug_key(x)))
+                .await?;
+            let released = waiting
+                .iter()
+                .zip(locks)
+                .filter(|(_, lock)| lock.is_none())
+                .map(|(key, _)| key.clone())
+                .collect_vec();
+
+            let (mut cached, remaining) = redis
+                .get_cached(namespace, slug_namespace, waiting, parse_id)
+                .await?;
+            found.append(&mut cached);
+
+            let (still_waiting, abandoned): (Vec<_>, Vec<_>) =
+                remaining.into_iter().partition(|x| !released.contains(x));
+            waiting = still_waiting;
+            fetching.extend(abandoned);
+        }
+
+        if !fetching.is_empty() {
+            let fetched = fetch(fetching.clone()).await;
+
+            let missing_id_namespace = missing_namespace(namespace);
+            let missing_slug_namespace = slug_namespace.map(missing_namespace);
+            let cached = async {
+                // The items and the keys which weren't found are cached in a single round trip
+                let mut entries = Vec::new();
+                for item in fetched? {
+                    fetching.retain(|x| !matches_key(&item, x, slug_namespace, parse_id));
+
+                    let id = item.cache_id().to_string();
+                    entries.push((namespace, id.clone(), serde_json::to_string(&item)?, None));
+                    if let (Some(slug_namespace), Some(slug)) = (slug_namespace, item.cache_slug())
+                    {
+                        entries.push((slug_namespace, T::slug_key(&slug), id, None));
+                    }
+                    found.push(item);
+                }
+
+                for key in &fetching {
+                    if let Some(id) = parse_id(key) {
+                        entries.push((
+                            missing_id_namespace.as_str(),
+                            id.to_string(),
+                            String::new(),
+                            Some(MISSING_EXPIRY),
+                        ));
+                    }
+                    if let Some(missing_slug_namespace) = &missing_slug_namespace {
+                        entries.push((
+                            missing_slug_namespace.as_str(),
+                            T::slug_key(key),
+                            String::new(),
+                            Some(MISSING_EXPIRY),
+                        ));
+                    }
+                }
+
+                redis.set_many(entries).await
+            }
+            .await;
+
+            // The locks are released even if the fetch failed, so the waiting callers retry
+            redis
+                .delete_many(
+                    locked
+                        .iter()
+                        .map(|x| (lock_namespace.as_str(), Some(T::slug_key(x)))),
+                )
+                .await?;
+            cached?;
+        }
+
+        Ok(found.into_iter().unique_by(|x| x.cache_id()).collect())
+    }
+}
+
+fn matches_key<T: CachedEntry>(
+    item: &T,
+    key: &str,
+    slug_namespace: Option<&str>,
+    parse_id: fn(&str) -> Option<i64>,
+) -> bool {
+    parse_id(key) == Some(item.cache_id())
+        || (slug_namespace.is_some()
+            && item.cache_slug().map(|x| T::slug_key(&x)) == Some(T::slug_key(key)))
 }
 
 impl RedisConnection {
+    // Gets the cached items with the keys, along with the keys which aren't cached and weren't
+    // recently found to be missing
+    async fn get_cached<T: CachedEntry>(
+        &mut self,
+        namespace: &str,
+        slug_namespace: Option<&str>,
+        keys: Vec<String>,
+        parse_id: fn(&str) -> Option<i64>,
+    ) -> Result<(Vec<T>, Vec<String>), DatabaseError> {
+        let mut ids = keys.iter().flat_map(|x| parse_id(x)).collect_vec();
+        if let Some(slug_namespace) = slug_namespace {
+            ids.extend(
+                self.multi_get::<i64>(slug_namespace, keys.iter().map(|x| T::slug_key(x)))
+                    .await?
+                    .into_iter()
+                    .flatten(),
+            );
+        }
+
+        let mut found = Vec::new();
+        let mut remaining = keys;
+        if !ids.is_empty() {
+            let items = self
+                .multi_get::<String>(namespace, ids.iter().unique())
+                .await?;
+            for item in items
+                .into_iter()
+                .flatten()
+                .filter_map(|x| serde_json::from_str::<T>(&x).ok())
+            {
+                remaining.retain(|x| !matches_key(&item, x, slug_namespace, parse_id));
+                found.push(item);
+            }
+        }
+
+        // A key is only missing if neither the item with its id nor the one with its slug exist
+        if !remaining.is_empty() {
+            let missing_ids = self
+                .multi_get::<String>(
+                    &missing_namespace(namespace),
+                    remaining
+                        .iter()
+                        .map(|x| parse_id(x).map(|x| x.to_string()).unwrap_or_default()),
+                )
+                .await?;
+            let missing_slugs = match slug_namespace {
+                Some(slug_namespace) => {
+                    self.multi_get::<String>(
+                        &missing_namespace(slug_namespace),
+                        remaining.iter().map(|x| T::slug_key(x)),
+                    )
+                    .await?
+                }
+                None => vec![Some(String::new()); remaining.len()],
+            };
+
+            remaining = remaining
+                .into_iter()
+                .zip(missing_ids.into_iter().zip(missing_slugs))
+                .filter(|(key, (missing_id, missing_slug))| {
+                    let id_missing = parse_id(key).is_none() || missing_id.is_some();
+                    !(id_missing && missing_slug.is_some())
+                })
+                .map(|(key, _)| key)
+                .collect();
+        }
+
+        Ok((found, remaining))
+    }
+
+    // Forgets that the item with the id and slug wasn't found, once it's been created
+    pub async fn delete_missing(
+        &mut self,
+        namespace: &str,
+        slug_namespace: Option<&str>,
+        id: i64,
+        slug: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        let missing_slug_namespace = slug_namespace.map(missing_namespace);
+        self.delete_many([
+            (&*missing_namespace(namespace), Some(id.to_string())),
+            (
+                missing_slug_namespace.as_deref().unwrap_or_default(),
+                slug.filter(|_| slug_namespace.is_some())
+                    .map(|x| x.to_lowercase()),
+            ),
+        ])
+        .await?;
+        Ok(())
+    }
+
     pub async fn set(
         &mut self,
         namespace: &str,
@@ -72,6 +367,26 @@ impl RedisConnection {
         Ok(())
     }
 
+    // Sets the key without an expiry, for state which has to outlive the cache
+    pub async fn set_persistent(
+        &mut self,
+        namespace: &str,
+        id: &str,
+        data: &str,
+    ) -> Result<(), DatabaseError> {
+        let mut cmd = cmd("SET");
+        redis_args(
+            &mut cmd,
+            vec![
+                format!("{}_{}:{}", self.meta_namespace, namespace, id),
+                data.to_string(),
+            ]
+            .as_slice(),
+        );
+        redis_execute(&mut cmd, &mut self.connection).await?;
+        Ok(())
+    }
+
     pub async fn set_serialized_to_json<Id, D>(
         &mut self,
         namespace: &str,
@@ -140,6 +455,262 @@ impl RedisConnection {
         Ok(redis_execute(&mut cmd, &mut self.connection).await?)
     }
 
+    // Sets the keys with their data and expiries in a single round trip
+    pub async fn set_many(
+        &mut self,
+        entries: impl IntoIterator<Item = (&str, String, String, Option<i64>)>,
+    ) -> Result<(), DatabaseError> {
+        let mut pipe = redis::pipe();
+        let mut any = false;
+        for (namespace, id, data, expiry) in entries {
+            pipe.cmd("SET")
+                .arg(format!("{}_{}:{}", self.meta_namespace, namespace, id))
+                .arg(data)
+                .arg("EX")
+                .arg(expiry.unwrap_or(DEFAULT_EXPIRY))
+                .ignore();
+            any = true;
+        }
+
+        if any {
+            pipe.query_async::<_, ()>(&mut self.connection).await?;
+        }
+        Ok(())
+    }
+
+    // Sets the key only if it doesn't exist yet, returning whether it was set
+    pub async fn set_if_absent(
+        &mut self,
+        namespace: &str,
+        id: &str,
+        data: &str,
+        expiry: Option<i64>,
+    ) -> Result<bool, DatabaseError> {
+        let mut cmd = cmd("SET");
+        redis_args(
+            &mut cmd,
+            vec![
+                format!("{}_{}:{}", self.meta_namespace, namespace, id),
+                data.to_string(),
+                "NX".to_string(),
+                "EX".to_string(),
+                expiry.unwrap_or(DEFAULT_EXPIRY).to_string(),
+            ]
+            .as_slice(),
+        );
+        let res: Option<String> = redis_execute(&mut cmd, &mut self.connection).await?;
+        Ok(res.is_some())
+    }
+
+    // Sets each of the keys which don't exist yet in a single round trip, returning whether each
+    // of them was set
+    pub async fn set_many_if_absent(
+        &mut self,
+        namespace: &str,
+        ids: impl IntoIterator<Item = String>,
+        data: &str,
+        expiry: Option<i64>,
+    ) -> Result<Vec<bool>, DatabaseError> {
+        let mut pipe = redis::pipe();
+        let mut count = 0;
+        for id in ids {
+            pipe.cmd("SET")
+                .arg(format!("{}_{}:{}", self.meta_namespace, namespace, id))
+                .arg(data)
+                .arg("NX")
+                .arg("EX")
+                .arg(expiry.unwrap_or(DEFAULT_EXPIRY));
+            count += 1;
+        }
+
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let res: Vec<Option<String>> = pipe.query_async(&mut self.connection).await?;
+        Ok(res.into_iter().map(|x| x.is_some()).collect())
+    }
+
+    // Pushes to the front of a list, keeping at most `max_len` of the newest entries
+    pub async fn push_capped(
+        &mut self,
+        namespace: &str,
+        id: &str,
+        data: &str,
+        max_len: usize,
+        expiry: Option<i64>,
+    ) -> Result<(), DatabaseError> {
+        let key = format!("{}_{}:{}", self.meta_namespace, namespace, id);
+        redis::pipe()
+            .atomic()
+            .cmd("LPUSH")
+            .arg(&key)
+            .arg(data)
+            .ignore()
+            .cmd("LTRIM")
+            .arg(&key)
+            .arg(0)
+            .arg(max_len as i64 - 1)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(expiry.unwrap_or(DEFAULT_EXPIRY))
+            .ignore()
+            .query_async::<_, ()>(&mut self.connection)
+            .await?;
+        Ok(())
+    }
+
+    // Records a hit in a sliding window of `window` milliseconds ending at `now`, returning how
+    // many hits the window holds and when the oldest of them was made
+    pub async fn add_window_hit(
+        &mut self,
+        namespace: &str,
+        id: &str,
+        hit: &str,
+        now: i64,
+        window: i64,
+    ) -> Result<(usize, i64), DatabaseError> {
+        let key = format!("{}_{}:{}", self.meta_namespace, namespace, id);
+        let (count, oldest): (usize, Vec<(String, f64)>) = redis::pipe()
+            .atomic()
+            .cmd("ZREMRANGEBYSCORE")
+            .arg(&key)
+            .arg("-inf")
+            .arg(now - window)
+            .ignore()
+            .cmd("ZADD")
+            .arg(&key)
+            .arg(now)
+            .arg(hit)
+            .ignore()
+            .cmd("ZCARD")
+            .arg(&key)
+            .cmd("ZRANGE")
+            .arg(&key)
+            .arg(0)
+            .arg(0)
+            .arg("WITHSCORES")
+            .cmd("PEXPIRE")
+            .arg(&key)
+            .arg(window)
+            .ignore()
+            .query_async(&mut self.connection)
+            .await?;
+        Ok((count, oldest.first().map(|x| x.1 as i64).unwrap_or(now)))
+    }
+
+    // Increments a member's score in a sorted set, refreshing the set's expiry
+    pub async fn increment_member(
+        &mut self,
+        namespace: &str,
+        id: &str,
+        member: &str,
+        expiry: Option<i64>,
+    ) -> Result<(), DatabaseError> {
+        let key = format!("{}_{}:{}", self.meta_namespace, namespace, id);
+        redis::pipe()
+            .atomic()
+            .cmd("ZINCRBY")
+            .arg(&key)
+            .arg(1)
+            .arg(member)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(expiry.unwrap_or(DEFAULT_EXPIRY))
+            .ignore()
+            .query_async::<_, ()>(&mut self.connection)
+            .await?;
+        Ok(())
+    }
+
+    // Gets the `count` highest scoring members of a sorted set with their scores, highest first
+    pub async fn get_top_members(
+        &mut self,
+        namespace: &str,
+        id: &str,
+        count: usize,
+    ) -> Result<Vec<(String, u64)>, DatabaseError> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut cmd = cmd("ZREVRANGE");
+        redis_args(
+            &mut cmd,
+            vec![
+                format!("{}_{}:{}", self.meta_namespace, namespace, id),
+                "0".to_string(),
+                (count - 1).to_string(),
+                "WITHSCORES".to_string(),
+            ]
+            .as_slice(),
+        );
+        let res: Vec<(String, f64)> = redis_execute(&mut cmd, &mut self.connection).await?;
+        Ok(res
+            .into_iter()
+            .map(|(x, score)| (x, score as u64))
+            .collect())
+    }
+
+    pub async fn remove_window_hit(
+        &mut self,
+        namespace: &str,
+        id: &str,
+        hit: &str,
+    ) -> Result<(), DatabaseError> {
+        let mut cmd = cmd("ZREM");
+        redis_args(
+            &mut cmd,
+            vec![
+                format!("{}_{}:{}", self.meta_namespace, namespace, id),
+                hit.to_string(),
+            ]
+            .as_slice(),
+        );
+        redis_execute::<()>(&mut cmd, &mut self.connection).await?;
+        Ok(())
+    }
+
+    pub async fn get_list(
+        &mut self,
+        namespace: &str,
+        id: &str,
+    ) -> Result<Vec<String>, DatabaseError> {
+        let mut cmd = cmd("LRANGE");
+        redis_args(
+            &mut cmd,
+            vec![
+                format!("{}_{}:{}", self.meta_namespace, namespace, id),
+                "0".to_string(),
+                "-1".to_string(),
+            ]
+            .as_slice(),
+        );
+        let res = redis_execute(&mut cmd, &mut self.connection).await?;
+        Ok(res)
+    }
+
+    // Publishes to a pub/sub channel, returning how many subscribers received the message
+    pub async fn publish(
+        &mut self,
+        namespace: &str,
+        id: &str,
+        data: &str,
+    ) -> Result<usize, DatabaseError> {
+        let mut cmd = cmd("PUBLISH");
+        redis_args(
+            &mut cmd,
+            vec![
+                format!("{}_{}:{}", self.meta_namespace, namespace, id),
+                data.to_string(),
+            ]
+            .as_slice(),
+        );
+        let res = redis_execute(&mut cmd, &mut self.connection).await?;
+        Ok(res)
+    }
+
     pub async fn delete<T1>(&mut self, namespace: &str, id: T1) -> Result<(), DatabaseError>
     where
         T1: Display,
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 #[serde(rename_all = "camelCase")]
 pub struct AuthorizationPermissions {
     bucket_id: Option<String>,
-    bucket_name: Option<String>,
+    pub bucket_name: Option<String>,
     capabilities: Vec<String>,
     name_prefix: Option<String>,
 }
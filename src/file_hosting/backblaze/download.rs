@@ -0,0 +1,36 @@
+use super::authorization::AuthorizationData;
+use crate::file_hosting::FileHostingError;
+use bytes::Bytes;
+
+pub async fn download_file_by_name(
+    authorization_data: &AuthorizationData,
+    file_name: &str,
+) -> Result<Bytes, FileHostingError> {
+    let bucket_name = authorization_data
+        .allowed
+        .bucket_name
+        .as_deref()
+        .ok_or_else(|| {
+            FileHostingError::BackblazeError(serde_json::json!(
+                "Files can only be downloaded with a key restricted to a bucket"
+            ))
+        })?;
+
+    let response = reqwest::Client::new()
+        .get(&format!(
+            "{}/file/{}/{}",
+            authorization_data.download_url, bucket_name, file_name
+        ))
+        .header(
+            reqwest::header::AUTHORIZATION,
+            &authorization_data.authorization_token,
+        )
+        .send()
+        .await?;
+
+    if response.status().is_success() {
+        Ok(response.bytes().await?)
+    } else {
+        Err(FileHostingError::BackblazeError(response.json().await?))
+    }
+}
@@ -55,4 +55,10 @@ impl FileHost for MockHost {
             file_name: file_name.to_string(),
         })
     }
+
+    async fn download_file(&self, file_name: &str) -> Result<Bytes, FileHostingError> {
+        let path = std::path::Path::new(&dotenvy::var("MOCK_FILE_PATH").unwrap())
+            .join(file_name.replace("../", ""));
+        Ok(std::fs::read(path)?.into())
+    }
 }
@@ -56,4 +56,6 @@ pub trait FileHost {
         file_id: &str,
         file_name: &str,
     ) -> Result<DeleteFileData, FileHostingError>;
+
+    async fn download_file(&self, file_name: &str) -> Result<Bytes, FileHostingError>;
 }
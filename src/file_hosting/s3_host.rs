@@ -90,4 +90,16 @@ impl FileHost for S3Host {
             file_name: file_name.to_string(),
         })
     }
+
+    async fn download_file(&self, file_name: &str) -> Result<Bytes, FileHostingError> {
+        let response = self
+            .bucket
+            .get_object(format!("/{file_name}"))
+            .await
+            .map_err(|_| {
+                FileHostingError::S3Error("Error while downloading file from S3".to_string())
+            })?;
+
+        Ok(response.bytes().clone())
+    }
 }
@@ -0,0 +1,66 @@
+use super::{FileScanner, FileScanningError, ScanVerdict};
+use actix_rt::net::TcpStream;
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// The largest chunk sent to clamd at once. Files are streamed in chunks no larger than this.
+const CHUNK_SIZE: usize = 1 << 20;
+/// How long a scan may take, including sending the file
+const SCAN_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Scans files with a clamd daemon, using its `INSTREAM` command over TCP
+pub struct ClamAvScanner {
+    address: String,
+}
+
+impl ClamAvScanner {
+    pub fn new(address: &str) -> Self {
+        ClamAvScanner {
+            address: address.to_string(),
+        }
+    }
+
+    async fn scan_stream(&self, file_bytes: &[u8]) -> Result<String, FileScanningError> {
+        let mut stream = TcpStream::connect(&self.address).await?;
+
+        stream.write_all(b"zINSTREAM\0").await?;
+        for chunk in file_bytes.chunks(CHUNK_SIZE) {
+            stream
+                .write_all(&(chunk.len() as u32).to_be_bytes())
+                .await?;
+            stream.write_all(chunk).await?;
+        }
+        // A chunk of length zero ends the stream
+        stream.write_all(&[0; 4]).await?;
+        stream.flush().await?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+
+        Ok(String::from_utf8_lossy(&response)
+            .trim_end_matches('\0')
+            .trim()
+            .to_string())
+    }
+}
+
+#[async_trait]
+impl FileScanner for ClamAvScanner {
+    async fn scan(&self, file_bytes: Bytes) -> Result<ScanVerdict, FileScanningError> {
+        let response = actix_rt::time::timeout(SCAN_TIMEOUT, self.scan_stream(&file_bytes))
+            .await
+            .map_err(|_| FileScanningError::Timeout)??;
+
+        // Responses look like `stream: OK` or `stream: Eicar-Signature FOUND`
+        let result = response.strip_prefix("stream:").unwrap_or(&response).trim();
+        if result == "OK" {
+            Ok(ScanVerdict::Clean)
+        } else if let Some(signature) = result.strip_suffix("FOUND") {
+            Ok(ScanVerdict::Flagged(signature.trim().to_string()))
+        } else {
+            Err(FileScanningError::Scanner(response))
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use super::{FileScanner, FileScanningError, ScanVerdict};
+use async_trait::async_trait;
+use bytes::Bytes;
+
+/// The standard antivirus test file, which real scanners flag without it being harmful
+const EICAR_TEST_SIGNATURE: &[u8] =
+    br"X5O!P%@AP[4\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*";
+
+/// Passes every file except those containing the EICAR test signature, for local development
+/// and tests
+#[derive(Default)]
+pub struct MockScanner(());
+
+impl MockScanner {
+    pub fn new() -> Self {
+        MockScanner(())
+    }
+}
+
+#[async_trait]
+impl FileScanner for MockScanner {
+    async fn scan(&self, file_bytes: Bytes) -> Result<ScanVerdict, FileScanningError> {
+        if file_bytes
+            .windows(EICAR_TEST_SIGNATURE.len())
+            .any(|x| x == EICAR_TEST_SIGNATURE)
+        {
+            return Ok(ScanVerdict::Flagged("Eicar-Signature".to_string()));
+        }
+
+        Ok(ScanVerdict::Clean)
+    }
+}
@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use thiserror::Error;
+
+mod clamav;
+mod mock;
+
+pub use clamav::ClamAvScanner;
+pub use mock::MockScanner;
+
+#[derive(Error, Debug)]
+pub enum FileScanningError {
+    #[error("Error while communicating with the scanner: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The scanner took too long to respond")]
+    Timeout,
+    #[error("The scanner could not scan the file: {0}")]
+    Scanner(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+    /// The file matched the named signature
+    Flagged(String),
+}
+
+/// Checks uploaded files for malware
+#[async_trait]
+pub trait FileScanner {
+    async fn scan(&self, file_bytes: Bytes) -> Result<ScanVerdict, FileScanningError>;
+}
@@ -5,6 +5,7 @@ use database::redis::RedisPool;
 use log::{info, warn};
 use queue::{
     analytics::AnalyticsQueue, payouts::PayoutsQueue, session::AuthQueue, socket::ActiveSockets,
+    webhooks::WebhookQueue,
 };
 use scheduler::Scheduler;
 use sqlx::Postgres;
@@ -15,15 +16,25 @@ use clickhouse_crate::Client;
 use util::cors::default_cors;
 
 use crate::{
+    queue::email::send_queued_emails,
     queue::payouts::process_payout,
-    search::indexing::index_projects,
+    routes::v3::images::publish_private_images,
+    routes::v3::organizations::enforce_organization_2fa,
+    routes::v3::projects::{list_scheduled_projects, purge_deleted_projects},
+    routes::v3::user_exports::purge_expired_exports,
+    routes::v3::version_creation::{publish_scheduled_versions, purge_expired_uploads},
+    search::indexing::{index_organizations, index_projects, index_users, prune_documents},
     util::env::{parse_strings_from_var, parse_var},
+    util::maintenance::MaintenanceState,
 };
 
 pub mod auth;
 pub mod clickhouse;
 pub mod database;
 pub mod file_hosting;
+pub mod file_scanning;
+pub mod mailer;
+pub mod metrics;
 pub mod models;
 pub mod queue;
 pub mod ratelimit;
@@ -44,6 +55,8 @@ pub struct LabrinthConfig {
     pub redis_pool: RedisPool,
     pub clickhouse: Client,
     pub file_host: Arc<dyn file_hosting::FileHost + Send + Sync>,
+    pub scanner: Arc<dyn file_scanning::FileScanner + Send + Sync>,
+    pub mailer: Arc<dyn mailer::Mailer + Send + Sync>,
     pub maxmind: Arc<queue::maxmind::MaxMindIndexer>,
     pub scheduler: Arc<Scheduler>,
     pub ip_salt: Pepper,
@@ -51,15 +64,20 @@ pub struct LabrinthConfig {
     pub session_queue: web::Data<AuthQueue>,
     pub payouts_queue: web::Data<PayoutsQueue>,
     pub analytics_queue: Arc<AnalyticsQueue>,
+    pub webhook_queue: web::Data<WebhookQueue>,
     pub active_sockets: web::Data<RwLock<ActiveSockets>>,
+    pub rate_limits: ratelimit::RateLimits,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn app_setup(
     pool: sqlx::Pool<Postgres>,
     redis_pool: RedisPool,
     search_config: search::SearchConfig,
     clickhouse: &mut Client,
     file_host: Arc<dyn file_hosting::FileHost + Send + Sync>,
+    scanner: Arc<dyn file_scanning::FileScanner + Send + Sync>,
+    mailer: Arc<dyn mailer::Mailer + Send + Sync>,
     maxmind: Arc<queue::maxmind::MaxMindIndexer>,
 ) -> LabrinthConfig {
     info!(
@@ -67,6 +85,9 @@ pub fn app_setup(
         dotenvy::var("BIND_ADDR").unwrap()
     );
 
+    // The file validators are registered up front rather than on the first upload
+    lazy_static::initialize(&validate::FILE_VALIDATORS);
+
     let mut scheduler = scheduler::Scheduler::new();
 
     // The interval in seconds at which the local database is indexed
@@ -82,12 +103,32 @@ pub fn app_setup(
         let redis_pool_ref = redis_pool_ref.clone();
         let search_config_ref = search_config_ref.clone();
         async move {
+            // Indexing is paused while the database is being migrated
+            if MaintenanceState::get(&redis_pool_ref).await.read_only {
+                info!("Skipping indexing the local database during maintenance");
+                return;
+            }
+
             info!("Indexing local database");
-            let result = index_projects(pool_ref, redis_pool_ref.clone(), &search_config_ref).await;
+            let result =
+                index_projects(pool_ref.clone(), redis_pool_ref.clone(), &search_config_ref).await;
             if let Err(e) = result {
                 warn!("Local project indexing failed: {:?}", e);
             }
+            if let Err(e) = index_users(&pool_ref, &search_config_ref).await {
+                warn!("Local user indexing failed: {:?}", e);
+            }
+            if let Err(e) = index_organizations(&pool_ref, &search_config_ref).await {
+                warn!("Local organization indexing failed: {:?}", e);
+            }
             info!("Done indexing local database");
+
+            // Versions and projects can be removed outside the API, leaving their documents
+            // behind
+            let result = prune_documents(&pool_ref, &redis_pool_ref, &search_config_ref).await;
+            if let Err(e) = result {
+                warn!("Pruning search documents failed: {:?}", e);
+            }
         }
     });
 
@@ -104,6 +145,7 @@ pub fn app_setup(
                 UPDATE mods
                 SET status = requested_status
                 WHERE status = $1 AND approved < CURRENT_DATE AND requested_status IS NOT NULL
+                    AND go_live_at IS NULL
                 ",
                 crate::models::projects::ProjectStatus::Scheduled.as_str(),
             )
@@ -201,6 +243,205 @@ pub fn app_setup(
         });
     }
 
+    // Removes deleted projects once they can no longer be restored
+    let pool_ref = pool.clone();
+    let redis_pool_ref = redis_pool.clone();
+    scheduler.run(std::time::Duration::from_secs(60 * 60), move || {
+        let pool_ref = pool_ref.clone();
+        let redis_pool_ref = redis_pool_ref.clone();
+
+        async move {
+            info!("Purging deleted projects");
+            let result = purge_deleted_projects(&pool_ref, &redis_pool_ref).await;
+            if let Err(e) = result {
+                warn!("Purging deleted projects failed: {:?}", e);
+            }
+            info!("Done purging deleted projects");
+        }
+    });
+
+    // Takes away the permissions of organization members who haven't enabled 2FA when their
+    // organization requires it
+    let pool_ref = pool.clone();
+    let redis_pool_ref = redis_pool.clone();
+    scheduler.run(std::time::Duration::from_secs(60 * 60), move || {
+        let pool_ref = pool_ref.clone();
+        let redis_pool_ref = redis_pool_ref.clone();
+
+        async move {
+            info!("Enforcing organization 2FA requirements");
+            let result = enforce_organization_2fa(&pool_ref, &redis_pool_ref).await;
+            if let Err(e) = result {
+                warn!("Enforcing organization 2FA requirements failed: {:?}", e);
+            }
+            info!("Done enforcing organization 2FA requirements");
+        }
+    });
+
+    // Removes chunked uploads which were never completed
+    {
+        let pool_ref = pool.clone();
+        let file_host_ref = file_host.clone();
+        scheduler.run(std::time::Duration::from_secs(60 * 60), move || {
+            let pool_ref = pool_ref.clone();
+            let file_host_ref = file_host_ref.clone();
+
+            async move {
+                info!("Removing expired pending uploads");
+                let result = purge_expired_uploads(&pool_ref, &*file_host_ref).await;
+                if let Err(e) = result {
+                    warn!("Removing expired pending uploads failed: {:?}", e);
+                }
+                info!("Done removing expired pending uploads");
+            }
+        });
+    }
+
+    // Removes the archives of data exports once their download links expire
+    {
+        let pool_ref = pool.clone();
+        let file_host_ref = file_host.clone();
+        scheduler.run(std::time::Duration::from_secs(60 * 60), move || {
+            let pool_ref = pool_ref.clone();
+            let file_host_ref = file_host_ref.clone();
+
+            async move {
+                info!("Removing expired data exports");
+                let result = purge_expired_exports(&pool_ref, &*file_host_ref).await;
+                if let Err(e) = result {
+                    warn!("Removing expired data exports failed: {:?}", e);
+                }
+                info!("Done removing expired data exports");
+            }
+        });
+    }
+
+    // Moves the images of projects which were made public out of the private prefix
+    {
+        let pool_ref = pool.clone();
+        let redis_ref = redis_pool.clone();
+        let search_config_ref = search_config.clone();
+        let file_host_ref = file_host.clone();
+        scheduler.run(std::time::Duration::from_secs(5 * 60), move || {
+            let pool_ref = pool_ref.clone();
+            let redis_ref = redis_ref.clone();
+            let search_config_ref = search_config_ref.clone();
+            let file_host_ref = file_host_ref.clone();
+
+            async move {
+                info!("Publishing the private images of public projects");
+                let result = publish_private_images(
+                    &pool_ref,
+                    &redis_ref,
+                    &search_config_ref,
+                    &*file_host_ref,
+                )
+                .await;
+                if let Err(e) = result {
+                    warn!("Publishing private images failed: {:?}", e);
+                }
+                info!("Done publishing private images");
+            }
+        });
+    }
+
+    let webhook_queue = web::Data::new(WebhookQueue::new());
+    {
+        let pool_ref = pool.clone();
+        let redis_ref = redis_pool.clone();
+        let webhook_queue_ref = webhook_queue.clone();
+        scheduler.run(std::time::Duration::from_secs(15), move || {
+            let pool_ref = pool_ref.clone();
+            let redis_ref = redis_ref.clone();
+            let webhook_queue_ref = webhook_queue_ref.clone();
+
+            async move {
+                info!("Delivering project webhooks");
+                let result = webhook_queue_ref.index(&pool_ref, &redis_ref).await;
+                if let Err(e) = result {
+                    warn!("Delivering project webhooks failed: {:?}", e);
+                }
+                info!("Done delivering project webhooks");
+            }
+        });
+    }
+
+    // Sends the emails queued for critical notifications
+    {
+        let pool_ref = pool.clone();
+        let mailer_ref = mailer.clone();
+        scheduler.run(std::time::Duration::from_secs(30), move || {
+            let pool_ref = pool_ref.clone();
+            let mailer_ref = mailer_ref.clone();
+
+            async move {
+                info!("Sending notification emails");
+                let result = send_queued_emails(&pool_ref, &*mailer_ref).await;
+                if let Err(e) = result {
+                    warn!("Sending notification emails failed: {:?}", e);
+                }
+                info!("Done sending notification emails");
+            }
+        });
+    }
+
+    // Lists approved projects once the go-live time their team scheduled has passed
+    {
+        let pool_ref = pool.clone();
+        let redis_ref = redis_pool.clone();
+        let search_config_ref = search_config.clone();
+        scheduler.run(std::time::Duration::from_secs(60), move || {
+            let pool_ref = pool_ref.clone();
+            let redis_ref = redis_ref.clone();
+            let search_config_ref = search_config_ref.clone();
+
+            async move {
+                info!("Listing scheduled projects");
+                let result = list_scheduled_projects(
+                    chrono::Utc::now(),
+                    &pool_ref,
+                    &redis_ref,
+                    &search_config_ref,
+                )
+                .await;
+                if let Err(e) = result {
+                    warn!("Listing scheduled projects failed: {:?}", e);
+                }
+                info!("Done listing scheduled projects");
+            }
+        });
+    }
+
+    // Publishes scheduled versions once their publish time has passed
+    {
+        let pool_ref = pool.clone();
+        let redis_ref = redis_pool.clone();
+        let search_config_ref = search_config.clone();
+        let webhook_queue_ref = webhook_queue.clone();
+        scheduler.run(std::time::Duration::from_secs(60), move || {
+            let pool_ref = pool_ref.clone();
+            let redis_ref = redis_ref.clone();
+            let search_config_ref = search_config_ref.clone();
+            let webhook_queue_ref = webhook_queue_ref.clone();
+
+            async move {
+                info!("Publishing scheduled versions");
+                let result = publish_scheduled_versions(
+                    chrono::Utc::now(),
+                    &pool_ref,
+                    &redis_ref,
+                    &search_config_ref,
+                    &webhook_queue_ref,
+                )
+                .await;
+                if let Err(e) = result {
+                    warn!("Publishing scheduled versions failed: {:?}", e);
+                }
+                info!("Done publishing scheduled versions");
+            }
+        });
+    }
+
     {
         let pool_ref = pool.clone();
         let redis_ref = redis_pool.clone();
@@ -233,6 +474,8 @@ pub fn app_setup(
         redis_pool,
         clickhouse: clickhouse.clone(),
         file_host,
+        scanner,
+        mailer,
         maxmind,
         scheduler: Arc::new(scheduler),
         ip_salt,
@@ -240,7 +483,9 @@ pub fn app_setup(
         session_queue,
         payouts_queue,
         analytics_queue,
+        webhook_queue,
         active_sockets,
+        rate_limits: ratelimit::RateLimits::from_env(),
     }
 }
 
@@ -264,17 +509,21 @@ pub fn app_config(cfg: &mut web::ServiceConfig, labrinth_config: LabrinthConfig)
     .app_data(web::Data::new(labrinth_config.redis_pool.clone()))
     .app_data(web::Data::new(labrinth_config.pool.clone()))
     .app_data(web::Data::new(labrinth_config.file_host.clone()))
+    .app_data(web::Data::new(labrinth_config.scanner.clone()))
     .app_data(web::Data::new(labrinth_config.search_config.clone()))
     .app_data(labrinth_config.session_queue.clone())
     .app_data(labrinth_config.payouts_queue.clone())
+    .app_data(labrinth_config.webhook_queue.clone())
     .app_data(web::Data::new(labrinth_config.ip_salt.clone()))
     .app_data(web::Data::new(labrinth_config.analytics_queue.clone()))
     .app_data(web::Data::new(labrinth_config.clickhouse.clone()))
     .app_data(web::Data::new(labrinth_config.maxmind.clone()))
     .app_data(labrinth_config.active_sockets.clone())
+    .app_data(web::Data::new(labrinth_config.rate_limits.clone()))
     .configure(routes::v2::config)
     .configure(routes::v3::config)
     .configure(routes::internal::config)
+    .configure(metrics::config)
     .configure(routes::root_config)
     .default_service(web::get().wrap(default_cors()).to(routes::not_found));
 }
@@ -305,6 +554,8 @@ pub fn check_env_vars() -> bool {
     failed |= check_var::<String>("REDIS_URL");
     failed |= check_var::<String>("BIND_ADDR");
     failed |= check_var::<String>("SELF_ADDR");
+    failed |= check_var::<String>("IMAGE_SIGNING_SECRET");
+    failed |= check_var::<String>("DATABASE_ENCRYPTION_SECRET");
 
     failed |= check_var::<String>("STORAGE_BACKEND");
 
@@ -335,6 +586,22 @@ pub fn check_env_vars() -> bool {
         }
     }
 
+    let scanner_backend = dotenvy::var("SCANNER_BACKEND").ok();
+    match scanner_backend.as_deref() {
+        Some("clamav") => {
+            failed |= check_var::<String>("CLAMAV_ADDRESS");
+        }
+        Some("local") => {}
+        Some(backend) => {
+            warn!("Variable `SCANNER_BACKEND` contains an invalid value: {}. Expected \"clamav\" or \"local\".", backend);
+            failed |= true;
+        }
+        _ => {
+            warn!("Variable `SCANNER_BACKEND` is not set!");
+            failed |= true;
+        }
+    }
+
     failed |= check_var::<usize>("LOCAL_INDEX_INTERVAL");
     failed |= check_var::<usize>("VERSION_INDEX_INTERVAL");
 
@@ -375,6 +642,23 @@ pub fn check_env_vars() -> bool {
     failed |= check_var::<String>("SMTP_PASSWORD");
     failed |= check_var::<String>("SMTP_HOST");
 
+    let mail_backend = dotenvy::var("MAIL_BACKEND").ok();
+    match mail_backend.as_deref() {
+        Some("smtp") => {
+            failed |= check_var::<u16>("SMTP_PORT");
+            failed |= check_var::<String>("SMTP_FROM");
+        }
+        Some("local") => {}
+        Some(backend) => {
+            warn!("Variable `MAIL_BACKEND` contains an invalid value: {}. Expected \"smtp\" or \"local\".", backend);
+            failed |= true;
+        }
+        _ => {
+            warn!("Variable `MAIL_BACKEND` is not set!");
+            failed |= true;
+        }
+    }
+
     failed |= check_var::<String>("SITE_VERIFY_EMAIL_PATH");
     failed |= check_var::<String>("SITE_RESET_PASSWORD_PATH");
 
@@ -0,0 +1,30 @@
+use super::{Email, MailError, Mailer};
+use async_trait::async_trait;
+use log::info;
+use std::sync::Mutex;
+
+/// Keeps sent emails in memory instead of delivering them, for local development and tests
+#[derive(Default)]
+pub struct MockMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+impl MockMailer {
+    pub fn new() -> Self {
+        MockMailer::default()
+    }
+
+    pub fn sent(&self) -> Vec<Email> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Mailer for MockMailer {
+    async fn send(&self, email: Email) -> Result<(), MailError> {
+        info!("Sending email \"{}\" to {}", email.subject, email.to);
+        self.sent.lock().unwrap().push(email);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+use async_trait::async_trait;
+
+mod mock;
+mod smtp;
+
+pub use crate::auth::email::MailError;
+pub use mock::MockMailer;
+pub use smtp::SmtpMailer;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    /// The rendered HTML body
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer {
+    async fn send(&self, email: Email) -> Result<(), MailError>;
+}
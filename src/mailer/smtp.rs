@@ -0,0 +1,50 @@
+use super::{Email, MailError, Mailer};
+use async_trait::async_trait;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+
+pub struct SmtpMailer {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(
+        host: &str,
+        port: u16,
+        username: &str,
+        password: &str,
+        from: &str,
+    ) -> Result<Self, MailError> {
+        let credentials = Credentials::new(username.to_string(), password.to_string());
+        let transport = SmtpTransport::relay(host)?
+            .port(port)
+            .credentials(credentials)
+            .build();
+
+        Ok(SmtpMailer {
+            transport,
+            from: Mailbox::new(Some("Modrinth".to_string()), from.parse()?),
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: Email) -> Result<(), MailError> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(email.to.parse()?)
+            .subject(email.subject)
+            .header(ContentType::TEXT_HTML)
+            .body(email.body)?;
+
+        // The transport is blocking, so it is run off the async executor
+        let transport = self.transport.clone();
+        actix_web::web::block(move || transport.send(&message)).await??;
+
+        Ok(())
+    }
+}
@@ -1,14 +1,10 @@
 use actix_web::{App, HttpServer};
-use actix_web_prom::PrometheusMetricsBuilder;
 use env_logger::Env;
 use labrinth::database::redis::RedisPool;
 use labrinth::file_hosting::S3Host;
-use labrinth::ratelimit::errors::ARError;
-use labrinth::ratelimit::memory::{MemoryStore, MemoryStoreActor};
-use labrinth::ratelimit::middleware::RateLimiter;
 use labrinth::search;
 use labrinth::util::env::parse_var;
-use labrinth::{check_env_vars, clickhouse, database, file_hosting, queue};
+use labrinth::{check_env_vars, clickhouse, database, file_hosting, file_scanning, mailer, queue};
 use log::{error, info};
 use std::sync::Arc;
 
@@ -81,18 +77,39 @@ async fn main() -> std::io::Result<()> {
         _ => panic!("Invalid storage backend specified. Aborting startup!"),
     };
 
+    let scanner_backend = dotenvy::var("SCANNER_BACKEND").unwrap_or_else(|_| "local".to_string());
+
+    let scanner: Arc<dyn file_scanning::FileScanner + Send + Sync> = match scanner_backend.as_str()
+    {
+        "clamav" => Arc::new(file_scanning::ClamAvScanner::new(
+            &dotenvy::var("CLAMAV_ADDRESS").unwrap(),
+        )),
+        "local" => Arc::new(file_scanning::MockScanner::new()),
+        _ => panic!("Invalid scanner backend specified. Aborting startup!"),
+    };
+
+    let mail_backend = dotenvy::var("MAIL_BACKEND").unwrap_or_else(|_| "local".to_string());
+
+    let mailer: Arc<dyn mailer::Mailer + Send + Sync> = match mail_backend.as_str() {
+        "smtp" => Arc::new(
+            mailer::SmtpMailer::new(
+                &dotenvy::var("SMTP_HOST").unwrap(),
+                parse_var("SMTP_PORT").unwrap(),
+                &dotenvy::var("SMTP_USERNAME").unwrap(),
+                &dotenvy::var("SMTP_PASSWORD").unwrap(),
+                &dotenvy::var("SMTP_FROM").unwrap(),
+            )
+            .unwrap(),
+        ),
+        "local" => Arc::new(mailer::MockMailer::new()),
+        _ => panic!("Invalid mail backend specified. Aborting startup!"),
+    };
+
     info!("Initializing clickhouse connection");
     let mut clickhouse = clickhouse::init_client().await.unwrap();
 
     let maxmind_reader = Arc::new(queue::maxmind::MaxMindIndexer::new().await.unwrap());
 
-    let store = MemoryStore::new();
-
-    let prometheus = PrometheusMetricsBuilder::new("labrinth")
-        .endpoint("/metrics")
-        .build()
-        .expect("Failed to create prometheus metrics middleware");
-
     let search_config = search::SearchConfig::new(None);
     info!("Starting Actix HTTP server!");
 
@@ -102,35 +119,14 @@ async fn main() -> std::io::Result<()> {
         search_config.clone(),
         &mut clickhouse,
         file_host.clone(),
+        scanner,
+        mailer,
         maxmind_reader.clone(),
     );
 
     // Init App
     HttpServer::new(move || {
         App::new()
-            .wrap(prometheus.clone())
-            .wrap(actix_web::middleware::Compress::default())
-            .wrap(
-                RateLimiter::new(MemoryStoreActor::from(store.clone()).start())
-                    .with_identifier(|req| {
-                        let connection_info = req.connection_info();
-                        let ip =
-                            String::from(if parse_var("CLOUDFLARE_INTEGRATION").unwrap_or(false) {
-                                if let Some(header) = req.headers().get("CF-Connecting-IP") {
-                                    header.to_str().map_err(|_| ARError::Identification)?
-                                } else {
-                                    connection_info.peer_addr().ok_or(ARError::Identification)?
-                                }
-                            } else {
-                                connection_info.peer_addr().ok_or(ARError::Identification)?
-                            });
-
-                        Ok(ip)
-                    })
-                    .with_interval(std::time::Duration::from_secs(60))
-                    .with_max_requests(300)
-                    .with_ignore_key(dotenvy::var("RATE_LIMIT_IGNORE_KEY").ok()),
-            )
             .wrap(sentry_actix::Sentry::new())
             .configure(|cfg| labrinth::app_config(cfg, labrinth_config.clone()))
     })
@@ -0,0 +1,303 @@
+use crate::database::redis::RedisPool;
+use crate::routes::ApiError;
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header, Method, StatusCode},
+    web, Error as AWError, HttpRequest, HttpResponse,
+};
+use futures::future::{ok, LocalBoxFuture, Ready};
+use lazy_static::lazy_static;
+use log::warn;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::PgPool;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+const NAMESPACE: &str = "labrinth";
+
+/// Labelled on requests which didn't match a route, so scanners probing random paths can't
+/// create new series
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+lazy_static! {
+    pub static ref METRICS: Metrics = Metrics::new();
+}
+
+/// The metrics of this process, scraped from `/metrics` in the Prometheus text format.
+/// Every label takes a bounded set of values: routes are labelled with their pattern rather
+/// than their path, and statuses with their class.
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    database_connections: IntGaugeVec,
+    redis_connections: IntGaugeVec,
+    search_index_runs: IntCounterVec,
+    search_index_duration: HistogramVec,
+    search_indexed_documents: IntCounterVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some(NAMESPACE.to_string()), None)
+            .expect("Failed to create the metrics registry");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Requests handled, by route"),
+            &["route", "method", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "How long requests took to respond to, by route",
+            ),
+            &["route", "method"],
+        )
+        .unwrap();
+        let database_connections = IntGaugeVec::new(
+            Opts::new(
+                "database_connections",
+                "Connections in the Postgres pool, by whether they're in use",
+            ),
+            &["state"],
+        )
+        .unwrap();
+        let redis_connections = IntGaugeVec::new(
+            Opts::new(
+                "redis_connections",
+                "Connections in the Redis pool, by whether they're in use",
+            ),
+            &["state"],
+        )
+        .unwrap();
+        let search_index_runs = IntCounterVec::new(
+            Opts::new(
+                "search_index_runs_total",
+                "Full search reindexes, by whether they succeeded",
+            ),
+            &["result"],
+        )
+        .unwrap();
+        let search_index_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "search_index_duration_seconds",
+                "How long full search reindexes took",
+            )
+            .buckets(vec![1.0, 5.0, 15.0, 60.0, 300.0, 900.0, 1800.0, 3600.0]),
+            &["result"],
+        )
+        .unwrap();
+        let search_indexed_documents = IntCounterVec::new(
+            Opts::new(
+                "search_indexed_documents_total",
+                "Documents added to search indexes, by index",
+            ),
+            &["index"],
+        )
+        .unwrap();
+
+        for collector in [
+            Box::new(http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_request_duration.clone()),
+            Box::new(database_connections.clone()),
+            Box::new(redis_connections.clone()),
+            Box::new(search_index_runs.clone()),
+            Box::new(search_index_duration.clone()),
+            Box::new(search_indexed_documents.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("Failed to register a metric");
+        }
+
+        Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            database_connections,
+            redis_connections,
+            search_index_runs,
+            search_index_duration,
+            search_indexed_documents,
+        }
+    }
+
+    fn observe_request(&self, route: &str, method: &Method, status: StatusCode, elapsed: Duration) {
+        let method = method_label(method);
+        self.http_requests
+            .with_label_values(&[route, method, status_class(status)])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[route, method])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Records a full search reindex, which `index_projects` runs
+    pub fn observe_search_index_run(&self, succeeded: bool, elapsed: Duration) {
+        let result = if succeeded { "success" } else { "error" };
+        self.search_index_runs.with_label_values(&[result]).inc();
+        self.search_index_duration
+            .with_label_values(&[result])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_indexed_documents(&self, index: &str, count: usize) {
+        self.search_indexed_documents
+            .with_label_values(&[index])
+            .inc_by(count as u64);
+    }
+
+    // The pools are read when scraped rather than tracked as connections are taken
+    fn set_pool_gauges(&self, pool: &PgPool, redis: &RedisPool) {
+        let idle = pool.num_idle() as i64;
+        self.database_connections
+            .with_label_values(&["idle"])
+            .set(idle);
+        self.database_connections
+            .with_label_values(&["in_use"])
+            .set((pool.size() as i64 - idle).max(0));
+
+        let status = redis.pool.status();
+        self.redis_connections
+            .with_label_values(&["idle"])
+            .set(status.available as i64);
+        self.redis_connections
+            .with_label_values(&["in_use"])
+            .set(status.size.saturating_sub(status.available) as i64);
+        self.redis_connections
+            .with_label_values(&["waiting"])
+            .set(status.waiting as i64);
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        if let Err(err) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            warn!("Failed to encode metrics: {}", err);
+        }
+
+        buffer
+    }
+}
+
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::PATCH => "PATCH",
+        Method::DELETE => "DELETE",
+        Method::HEAD => "HEAD",
+        Method::OPTIONS => "OPTIONS",
+        _ => "OTHER",
+    }
+}
+
+fn status_class(status: StatusCode) -> &'static str {
+    match status.as_u16() {
+        100..=199 => "1xx",
+        200..=299 => "2xx",
+        300..=399 => "3xx",
+        400..=499 => "4xx",
+        _ => "5xx",
+    }
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/metrics", web::get().to(metrics_get));
+}
+
+/// Serves the metrics to callers with the `METRICS_TOKEN` bearer token. The route is hidden
+/// unless a token is set.
+pub async fn metrics_get(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let token = dotenvy::var("METRICS_TOKEN")
+        .ok()
+        .filter(|x| !x.is_empty())
+        .ok_or(ApiError::NotFound)?;
+
+    let authorized = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.strip_prefix("Bearer "))
+        .map_or(false, |x| x.as_bytes() == token.as_bytes());
+    if !authorized {
+        return Err(ApiError::CustomAuthentication(
+            "You need the metrics token to read metrics".to_string(),
+        ));
+    }
+
+    METRICS.set_pool_gauges(&pool, &redis);
+
+    Ok(HttpResponse::Ok()
+        .content_type(prometheus::TEXT_FORMAT)
+        .body(METRICS.encode()))
+}
+
+/// Records the count, duration and status of requests by the pattern of the route they
+/// matched
+pub struct RequestMetrics;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = AWError> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = AWError;
+    type Transform = RequestMetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestMetricsMiddleware {
+            service: Rc::new(service),
+        })
+    }
+}
+
+pub struct RequestMetricsMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestMetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = AWError> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = AWError;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let srv = self.service.clone();
+
+        Box::pin(async move {
+            let start = Instant::now();
+            let method = req.method().clone();
+            let route = req
+                .match_pattern()
+                .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
+            let res = srv.call(req).await;
+
+            let status = match &res {
+                Ok(res) => res.status(),
+                Err(err) => err.as_response_error().status_code(),
+            };
+            METRICS.observe_request(&route, &method, status, start.elapsed());
+
+            res
+        })
+    }
+}
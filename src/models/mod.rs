@@ -3,18 +3,29 @@ pub mod v2;
 pub mod v3;
 
 pub use v3::analytics;
+pub use v3::audit_log;
 pub use v3::collections;
+pub use v3::connections;
+pub use v3::exports;
 pub use v3::ids;
 pub use v3::images;
+pub use v3::moderation;
 pub use v3::notifications;
 pub use v3::oauth_clients;
 pub use v3::organizations;
 pub use v3::pack;
 pub use v3::pats;
 pub use v3::payouts;
+pub use v3::project_blocks;
+pub use v3::project_history;
+pub use v3::project_transfers;
 pub use v3::projects;
 pub use v3::reports;
 pub use v3::sessions;
 pub use v3::teams;
 pub use v3::threads;
+pub use v3::uploads;
+pub use v3::user_blocks;
 pub use v3::users;
+pub use v3::webhooks;
+pub use v3::wiki;
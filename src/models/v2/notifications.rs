@@ -3,11 +3,12 @@ use serde::{Deserialize, Serialize};
 
 use crate::models::{
     ids::{
-        NotificationId, OrganizationId, ProjectId, ReportId, TeamId, ThreadId, ThreadMessageId,
-        UserId, VersionId,
+        CollectionId, NotificationId, OrganizationId, ProjectId, ReportId, TeamId, ThreadId,
+        ThreadMessageId, UserExportId, UserId, VersionId,
     },
     notifications::{Notification, NotificationAction, NotificationBody},
     projects::ProjectStatus,
+    reports::ReportStatus,
 };
 
 #[derive(Serialize, Deserialize)]
@@ -65,6 +66,65 @@ pub enum LegacyNotificationBody {
         project_id: Option<ProjectId>,
         report_id: Option<ReportId>,
     },
+    VersionPublishDelayed {
+        project_id: ProjectId,
+        version_id: VersionId,
+    },
+    ReportResolved {
+        report_id: ReportId,
+        status: ReportStatus,
+    },
+    Mention {
+        thread_id: ThreadId,
+        message_id: ThreadMessageId,
+        mentioned_by: UserId,
+
+        project_id: Option<ProjectId>,
+        report_id: Option<ReportId>,
+    },
+    DataExportReady {
+        export_id: UserExportId,
+        url: String,
+        expires: DateTime<Utc>,
+    },
+    CollectionUpdate {
+        collection_id: CollectionId,
+        project_ids: Vec<ProjectId>,
+    },
+    OrganizationMemberJoined {
+        organization_id: OrganizationId,
+        user_id: UserId,
+        role: String,
+    },
+    OrganizationTwoFactorRequired {
+        organization_id: OrganizationId,
+        user_id: UserId,
+        restricted_after: DateTime<Utc>,
+    },
+    VersionFilesFlagged {
+        project_id: ProjectId,
+        version_id: VersionId,
+        file_names: Vec<String>,
+    },
+    OrganizationAnnouncement {
+        organization_id: OrganizationId,
+        body: String,
+    },
+    VersionReuploadFlagged {
+        project_id: ProjectId,
+        version_id: VersionId,
+        original_project_id: ProjectId,
+        report_id: ReportId,
+    },
+    ProjectTransferRequested {
+        project_id: ProjectId,
+        initiated_by: UserId,
+        organization_id: Option<OrganizationId>,
+    },
+    ProjectTransferResolved {
+        project_id: ProjectId,
+        accepted: bool,
+    },
     LegacyMarkdown {
         notification_type: Option<String>,
         title: String,
@@ -83,6 +143,34 @@ impl LegacyNotification {
             NotificationBody::OrganizationInvite { .. } => Some("organization_invite".to_string()),
             NotificationBody::StatusChange { .. } => Some("status_change".to_string()),
             NotificationBody::ModeratorMessage { .. } => Some("moderator_message".to_string()),
+            NotificationBody::VersionPublishDelayed { .. } => {
+                Some("version_publish_delayed".to_string())
+            }
+            NotificationBody::ReportResolved { .. } => Some("report_resolved".to_string()),
+            NotificationBody::Mention { .. } => Some("mention".to_string()),
+            NotificationBody::DataExportReady { .. } => Some("data_export_ready".to_string()),
+            NotificationBody::CollectionUpdate { .. } => Some("collection_update".to_string()),
+            NotificationBody::OrganizationMemberJoined { .. } => {
+                Some("organization_member_joined".to_string())
+            }
+            NotificationBody::OrganizationTwoFactorRequired { .. } => {
+                Some("organization_two_factor_required".to_string())
+            }
+            NotificationBody::VersionFilesFlagged { .. } => {
+                Some("version_files_flagged".to_string())
+            }
+            NotificationBody::OrganizationAnnouncement { .. } => {
+                Some("organization_announcement".to_string())
+            }
+            NotificationBody::VersionReuploadFlagged { .. } => {
+                Some("version_reupload_flagged".to_string())
+            }
+            NotificationBody::ProjectTransferRequested { .. } => {
+                Some("project_transfer_requested".to_string())
+            }
+            NotificationBody::ProjectTransferResolved { .. } => {
+                Some("project_transfer_resolved".to_string())
+            }
             NotificationBody::LegacyMarkdown {
                 notification_type, ..
             } => notification_type.clone(),
@@ -139,6 +227,106 @@ impl LegacyNotification {
                 project_id,
                 report_id,
             },
+            NotificationBody::VersionPublishDelayed {
+                project_id,
+                version_id,
+            } => LegacyNotificationBody::VersionPublishDelayed {
+                project_id,
+                version_id,
+            },
+            NotificationBody::ReportResolved { report_id, status } => {
+                LegacyNotificationBody::ReportResolved { report_id, status }
+            }
+            NotificationBody::Mention {
+                thread_id,
+                message_id,
+                mentioned_by,
+                project_id,
+                report_id,
+            } => LegacyNotificationBody::Mention {
+                thread_id,
+                message_id,
+                mentioned_by,
+                project_id,
+                report_id,
+            },
+            NotificationBody::DataExportReady {
+                export_id,
+                url,
+                expires,
+            } => LegacyNotificationBody::DataExportReady {
+                export_id,
+                url,
+                expires,
+            },
+            NotificationBody::CollectionUpdate {
+                collection_id,
+                project_ids,
+            } => LegacyNotificationBody::CollectionUpdate {
+                collection_id,
+                project_ids,
+            },
+            NotificationBody::OrganizationMemberJoined {
+                organization_id,
+                user_id,
+                role,
+            } => LegacyNotificationBody::OrganizationMemberJoined {
+                organization_id,
+                user_id,
+                role,
+            },
+            NotificationBody::OrganizationTwoFactorRequired {
+                organization_id,
+                user_id,
+                restricted_after,
+            } => LegacyNotificationBody::OrganizationTwoFactorRequired {
+                organization_id,
+                user_id,
+                restricted_after,
+            },
+            NotificationBody::VersionFilesFlagged {
+                project_id,
+                version_id,
+                file_names,
+            } => LegacyNotificationBody::VersionFilesFlagged {
+                project_id,
+                version_id,
+                file_names,
+            },
+            NotificationBody::OrganizationAnnouncement {
+                organization_id,
+                body,
+            } => LegacyNotificationBody::OrganizationAnnouncement {
+                organization_id,
+                body,
+            },
+            NotificationBody::VersionReuploadFlagged {
+                project_id,
+                version_id,
+                original_project_id,
+                report_id,
+            } => LegacyNotificationBody::VersionReuploadFlagged {
+                project_id,
+                version_id,
+                original_project_id,
+                report_id,
+            },
+            NotificationBody::ProjectTransferRequested {
+                project_id,
+                initiated_by,
+                organization_id,
+            } => LegacyNotificationBody::ProjectTransferRequested {
+                project_id,
+                initiated_by,
+                organization_id,
+            },
+            NotificationBody::ProjectTransferResolved {
+                project_id,
+                accepted,
+            } => LegacyNotificationBody::ProjectTransferResolved {
+                project_id,
+                accepted,
+            },
             NotificationBody::LegacyMarkdown {
                 notification_type,
                 name,
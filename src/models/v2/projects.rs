@@ -167,9 +167,9 @@ impl LegacyProject {
         let donation_urls = data
             .link_urls
             .iter()
-            .filter(|(_, l)| l.donation)
-            .map(|(_, l)| DonationLink::try_from(l.clone()).ok())
-            .collect::<Option<Vec<_>>>();
+            .filter_map(|(name, l)| DonationLink::from_link(name, l))
+            .collect::<Vec<_>>();
+        let donation_urls = Some(donation_urls);
 
         Self {
             id: data.id,
@@ -394,16 +394,29 @@ pub struct DonationLink {
     pub url: String,
 }
 
-impl TryFrom<Link> for DonationLink {
-    type Error = String;
-    fn try_from(link: Link) -> Result<Self, String> {
-        if !link.donation {
-            return Err("Not a donation".to_string());
+impl DonationLink {
+    /// The donation link for a v3 link, if it's a donation link. Links named `other` were on
+    /// v2's "Other" donation platform, which isn't a known platform anymore.
+    pub fn from_link(name: &str, link: &Link) -> Option<Self> {
+        if !link.donation && name != "other" {
+            return None;
         }
-        Ok(Self {
-            platform: capitalize_first(&link.platform),
-            url: link.url,
-            id: link.platform,
+
+        // Buy Me a Coffee was `bmac` in v2
+        let id = if name == "buymeacoffee" { "bmac" } else { name };
+        Some(Self {
+            platform: capitalize_first(id),
+            url: link.url.clone(),
+            id: id.to_string(),
         })
     }
+
+    /// The name of the v3 link the donation link is stored as
+    pub fn link_name(&self) -> String {
+        if self.id == "bmac" {
+            "buymeacoffee".to_string()
+        } else {
+            self.id.clone()
+        }
+    }
 }
@@ -30,7 +30,7 @@ impl From<ItemType> for LegacyItemType {
             ItemType::Project => LegacyItemType::Project,
             ItemType::Version => LegacyItemType::Version,
             ItemType::User => LegacyItemType::User,
-            ItemType::Unknown => LegacyItemType::Unknown,
+            ItemType::File | ItemType::Unknown => LegacyItemType::Unknown,
         }
     }
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
@@ -93,28 +95,15 @@ impl LegacyResultSearchProject {
             og_project_type.clone()
         };
 
-        let loader_fields = result_search_project.loader_fields.clone();
-        let get_one_bool_loader_field = |key: &str| {
-            loader_fields
-                .get(key)
-                .cloned()
-                .unwrap_or_default()
-                .first()
-                .and_then(|s| s.as_bool())
-        };
-
-        let singleplayer = get_one_bool_loader_field("singleplayer");
-        let client_only = get_one_bool_loader_field("client_only").unwrap_or(false);
-        let server_only = get_one_bool_loader_field("server_only").unwrap_or(false);
-        let client_and_server = get_one_bool_loader_field("client_and_server");
-
-        let (client_side, server_side) = v2_reroute::convert_side_types_v2_bools(
-            singleplayer,
-            client_only,
-            server_only,
-            client_and_server,
-            Some(&*og_project_type),
-        );
+        let side_types: HashMap<_, _> = v2_reroute::SIDE_TYPE_FIELDS
+            .iter()
+            .filter_map(|field| {
+                let value = result_search_project.loader_fields.get(*field)?.first()?;
+                Some((field.to_string(), value.clone()))
+            })
+            .collect();
+        let (client_side, server_side) =
+            v2_reroute::convert_side_types_v2(&side_types, Some(&*og_project_type));
         let client_side = client_side.to_string();
         let server_side = server_side.to_string();
 
@@ -1,5 +1,6 @@
-use crate::models::ids::{ImageId, ProjectId, ReportId, ThreadId, ThreadMessageId};
+use crate::models::ids::{ImageId, OrganizationId, ProjectId, ReportId, ThreadId, ThreadMessageId};
 use crate::models::projects::ProjectStatus;
+use crate::models::reports::ReportStatus;
 use crate::models::users::{User, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -40,10 +41,18 @@ pub enum LegacyMessageBody {
     },
     ThreadClosure,
     ThreadReopen,
+    ReportStatusChange {
+        new_status: ReportStatus,
+        old_status: ReportStatus,
+    },
     Deleted {
         #[serde(default)]
         private: bool,
     },
+    OrganizationAnnouncement {
+        organization_id: OrganizationId,
+        body: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Eq, PartialEq, Copy, Clone)]
@@ -93,9 +102,23 @@ impl From<crate::models::v3::threads::MessageBody> for LegacyMessageBody {
             crate::models::v3::threads::MessageBody::ThreadReopen => {
                 LegacyMessageBody::ThreadReopen
             }
+            crate::models::v3::threads::MessageBody::ReportStatusChange {
+                new_status,
+                old_status,
+            } => LegacyMessageBody::ReportStatusChange {
+                new_status,
+                old_status,
+            },
             crate::models::v3::threads::MessageBody::Deleted { private } => {
                 LegacyMessageBody::Deleted { private }
             }
+            crate::models::v3::threads::MessageBody::OrganizationAnnouncement {
+                organization_id,
+                body,
+            } => LegacyMessageBody::OrganizationAnnouncement {
+                organization_id,
+                body,
+            },
         }
     }
 }
@@ -0,0 +1,131 @@
+use super::ids::Base62Id;
+use crate::models::ids::{OrganizationId, ProjectId};
+use crate::models::users::UserId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(from = "Base62Id")]
+#[serde(into = "Base62Id")]
+pub struct AuditLogEntryId(pub u64);
+
+/// A destructive or permission-changing action recorded in the audit log
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    TeamMemberEdit,
+    ProjectStatusChange,
+    ProjectDelete,
+    VersionDelete,
+    ProjectTransfer,
+    ImageModeration,
+    ProjectBlock,
+    #[serde(rename = "oauth_client_edit")]
+    OAuthClientEdit,
+    FileTakedown,
+    Unknown,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::TeamMemberEdit => "team_member_edit",
+            AuditAction::ProjectStatusChange => "project_status_change",
+            AuditAction::ProjectDelete => "project_delete",
+            AuditAction::VersionDelete => "version_delete",
+            AuditAction::ProjectTransfer => "project_transfer",
+            AuditAction::ImageModeration => "image_moderation",
+            AuditAction::ProjectBlock => "project_block",
+            AuditAction::OAuthClientEdit => "oauth_client_edit",
+            AuditAction::FileTakedown => "file_takedown",
+            AuditAction::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_string(string: &str) -> AuditAction {
+        match string {
+            "team_member_edit" => AuditAction::TeamMemberEdit,
+            "project_status_change" => AuditAction::ProjectStatusChange,
+            "project_delete" => AuditAction::ProjectDelete,
+            "version_delete" => AuditAction::VersionDelete,
+            "project_transfer" => AuditAction::ProjectTransfer,
+            "image_moderation" => AuditAction::ImageModeration,
+            "project_block" => AuditAction::ProjectBlock,
+            "oauth_client_edit" => AuditAction::OAuthClientEdit,
+            "file_takedown" => AuditAction::FileTakedown,
+            _ => AuditAction::Unknown,
+        }
+    }
+}
+
+/// The kind of item an audit log entry's action was taken on
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditTargetType {
+    Project,
+    Version,
+    User,
+    Image,
+    #[serde(rename = "oauth_client")]
+    OAuthClient,
+    Unknown,
+}
+
+impl AuditTargetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditTargetType::Project => "project",
+            AuditTargetType::Version => "version",
+            AuditTargetType::User => "user",
+            AuditTargetType::Image => "image",
+            AuditTargetType::OAuthClient => "oauth_client",
+            AuditTargetType::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_string(string: &str) -> AuditTargetType {
+        match string {
+            "project" => AuditTargetType::Project,
+            "version" => AuditTargetType::Version,
+            "user" => AuditTargetType::User,
+            "image" => AuditTargetType::Image,
+            "oauth_client" => AuditTargetType::OAuthClient,
+            _ => AuditTargetType::Unknown,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditLogEntry {
+    pub id: AuditLogEntryId,
+    /// The user who took the action, which is kept after the user is deleted
+    pub actor_id: UserId,
+    pub action: AuditAction,
+    pub target_type: AuditTargetType,
+    /// The base62 id of the item the action was taken on
+    pub target_id: String,
+    pub project_id: Option<ProjectId>,
+    pub organization_id: Option<OrganizationId>,
+    /// What the action changed, such as the old and new values of edited fields
+    pub summary: serde_json::Value,
+    /// The IP the action was taken from, which is only shown to moderators
+    pub ip: Option<String>,
+    pub created: DateTime<Utc>,
+}
+
+impl From<crate::database::models::audit_log_item::AuditLogEntry> for AuditLogEntry {
+    fn from(data: crate::database::models::audit_log_item::AuditLogEntry) -> Self {
+        Self {
+            id: data.id.into(),
+            actor_id: data.actor_id.into(),
+            action: data.action,
+            target_type: data.target_type,
+            target_id: crate::models::ids::base62_impl::to_base62(data.target_id as u64),
+            project_id: data.project_id.map(|x| x.into()),
+            organization_id: data.organization_id.map(|x| x.into()),
+            summary: data.summary,
+            ip: data.ip,
+            created: data.created,
+        }
+    }
+}
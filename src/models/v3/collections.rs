@@ -29,8 +29,10 @@ pub struct Collection {
     /// Color of the collection.
     pub color: Option<u32>,
 
-    /// The status of the collectin (eg: whether collection is public or not)
+    /// The moderation status of the collection
     pub status: CollectionStatus,
+    /// Who can see the collection
+    pub visibility: CollectionVisibility,
 
     /// The date at which the collection was first published.
     pub created: DateTime<Utc>,
@@ -40,6 +42,9 @@ pub struct Collection {
 
     /// A list of ProjectIds that are in this collection.
     pub projects: Vec<ProjectId>,
+
+    /// The number of users following this collection.
+    pub followers: u32,
 }
 
 impl From<database::models::Collection> for Collection {
@@ -55,20 +60,20 @@ impl From<database::models::Collection> for Collection {
             icon_url: c.icon_url,
             color: c.color,
             status: c.status,
+            visibility: c.visibility,
+            followers: c.follows as u32,
         }
     }
 }
 
-/// A status decides the visibility of a collection in search, URLs, and the whole site itself.
-/// Listed - collection is displayed on search, and accessible by URL (for if/when search is implemented for collections)
-/// Unlisted - collection is not displayed on search, but accessible by URL
+/// The moderation status of a collection. Who can see a listed collection is decided by its
+/// visibility.
+/// Listed - collection is shown according to its visibility
 /// Rejected - collection is disabled
 #[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum CollectionStatus {
     Listed,
-    Unlisted,
-    Private,
     Rejected,
     Unknown,
 }
@@ -83,8 +88,6 @@ impl CollectionStatus {
     pub fn from_string(string: &str) -> CollectionStatus {
         match string {
             "listed" => CollectionStatus::Listed,
-            "unlisted" => CollectionStatus::Unlisted,
-            "private" => CollectionStatus::Private,
             "rejected" => CollectionStatus::Rejected,
             _ => CollectionStatus::Unknown,
         }
@@ -92,8 +95,6 @@ impl CollectionStatus {
     pub fn as_str(&self) -> &'static str {
         match self {
             CollectionStatus::Listed => "listed",
-            CollectionStatus::Unlisted => "unlisted",
-            CollectionStatus::Private => "private",
             CollectionStatus::Rejected => "rejected",
             CollectionStatus::Unknown => "unknown",
         }
@@ -103,9 +104,7 @@ impl CollectionStatus {
     pub fn is_hidden(&self) -> bool {
         match self {
             CollectionStatus::Rejected => true,
-            CollectionStatus::Private => true,
             CollectionStatus::Listed => false,
-            CollectionStatus::Unlisted => false,
             CollectionStatus::Unknown => false,
         }
     }
@@ -113,8 +112,6 @@ impl CollectionStatus {
     pub fn is_approved(&self) -> bool {
         match self {
             CollectionStatus::Listed => true,
-            CollectionStatus::Private => true,
-            CollectionStatus::Unlisted => true,
             CollectionStatus::Rejected => false,
             CollectionStatus::Unknown => false,
         }
@@ -123,10 +120,69 @@ impl CollectionStatus {
     pub fn can_be_requested(&self) -> bool {
         match self {
             CollectionStatus::Listed => true,
-            CollectionStatus::Private => true,
-            CollectionStatus::Unlisted => true,
             CollectionStatus::Rejected => false,
             CollectionStatus::Unknown => false,
         }
     }
 }
+
+/// Who can see a collection.
+/// Public - collection is accessible by URL, and listed on its owner's profile
+/// Unlisted - collection is accessible by URL, but not listed anywhere
+/// Private - collection is only accessible by its owner, and those given a share token
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CollectionVisibility {
+    #[default]
+    Public,
+    Unlisted,
+    Private,
+}
+
+impl std::fmt::Display for CollectionVisibility {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}", self.as_str())
+    }
+}
+
+impl CollectionVisibility {
+    pub fn from_string(string: &str) -> CollectionVisibility {
+        match string {
+            "unlisted" => CollectionVisibility::Unlisted,
+            "private" => CollectionVisibility::Private,
+            _ => CollectionVisibility::Public,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CollectionVisibility::Public => "public",
+            CollectionVisibility::Unlisted => "unlisted",
+            CollectionVisibility::Private => "private",
+        }
+    }
+
+    // Whether the collection is shown on its owner's profile
+    pub fn is_listed(&self) -> bool {
+        matches!(self, CollectionVisibility::Public)
+    }
+}
+
+/// A token which lets anyone holding it view a private collection, by passing it in the
+/// `share` query parameter
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CollectionShareToken {
+    pub token: String,
+    pub created: DateTime<Utc>,
+}
+
+impl From<database::models::collection_share_token_item::CollectionShareToken>
+    for CollectionShareToken
+{
+    fn from(data: database::models::collection_share_token_item::CollectionShareToken) -> Self {
+        Self {
+            token: data.token,
+            created: data.created,
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use super::ids::Base62Id;
+use crate::auth::AuthProvider;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(from = "Base62Id")]
+#[serde(into = "Base62Id")]
+pub struct UserConnectionId(pub u64);
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionProvider {
+    GitHub,
+    Discord,
+    GitLab,
+    Mastodon,
+    Website,
+}
+
+impl ConnectionProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionProvider::GitHub => "github",
+            ConnectionProvider::Discord => "discord",
+            ConnectionProvider::GitLab => "gitlab",
+            ConnectionProvider::Mastodon => "mastodon",
+            ConnectionProvider::Website => "website",
+        }
+    }
+
+    pub fn from_string(string: &str) -> ConnectionProvider {
+        match string {
+            "github" => ConnectionProvider::GitHub,
+            "discord" => ConnectionProvider::Discord,
+            "gitlab" => ConnectionProvider::GitLab,
+            "mastodon" => ConnectionProvider::Mastodon,
+            _ => ConnectionProvider::Website,
+        }
+    }
+
+    /// The authentication provider accounts are verified with. Providers without one are
+    /// verified with a rel="me" link back to the user's profile instead.
+    pub fn auth_provider(&self) -> Option<AuthProvider> {
+        match self {
+            ConnectionProvider::GitHub => Some(AuthProvider::GitHub),
+            ConnectionProvider::Discord => Some(AuthProvider::Discord),
+            ConnectionProvider::GitLab => Some(AuthProvider::GitLab),
+            ConnectionProvider::Mastodon | ConnectionProvider::Website => None,
+        }
+    }
+
+    pub fn from_auth_provider(provider: AuthProvider) -> Option<ConnectionProvider> {
+        match provider {
+            AuthProvider::GitHub => Some(ConnectionProvider::GitHub),
+            AuthProvider::Discord => Some(ConnectionProvider::Discord),
+            AuthProvider::GitLab => Some(ConnectionProvider::GitLab),
+            _ => None,
+        }
+    }
+}
+
+/// An account on another site linked to a user's profile. Only verified connections are shown
+/// to other users.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UserConnection {
+    pub id: UserConnectionId,
+    pub provider: ConnectionProvider,
+    pub username: String,
+    /// A link to the account, if the provider has public profile pages
+    pub url: Option<String>,
+    pub verified: bool,
+    pub created: DateTime<Utc>,
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+impl From<crate::database::models::user_connection_item::UserConnection> for UserConnection {
+    fn from(data: crate::database::models::user_connection_item::UserConnection) -> Self {
+        let url = match data.provider {
+            ConnectionProvider::GitHub => Some(format!("https://github.com/{}", data.username)),
+            ConnectionProvider::GitLab => Some(format!("https://gitlab.com/{}", data.username)),
+            ConnectionProvider::Discord => None,
+            ConnectionProvider::Mastodon | ConnectionProvider::Website => {
+                Some(data.external_id.clone())
+            }
+        };
+
+        Self {
+            id: data.id.into(),
+            provider: data.provider,
+            username: data.username,
+            url,
+            verified: data.verified,
+            created: data.created,
+            verified_at: data.verified_at,
+        }
+    }
+}
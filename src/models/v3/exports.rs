@@ -0,0 +1,65 @@
+use super::ids::Base62Id;
+use crate::models::users::UserId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(from = "Base62Id")]
+#[serde(into = "Base62Id")]
+pub struct UserExportId(pub u64);
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UserExportStatus {
+    Queued,
+    Processing,
+    Complete,
+    Failed,
+    /// The archive was removed from the CDN once its download link stopped working
+    Expired,
+}
+
+impl UserExportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserExportStatus::Queued => "queued",
+            UserExportStatus::Processing => "processing",
+            UserExportStatus::Complete => "complete",
+            UserExportStatus::Failed => "failed",
+            UserExportStatus::Expired => "expired",
+        }
+    }
+
+    pub fn from_string(string: &str) -> UserExportStatus {
+        match string {
+            "queued" => UserExportStatus::Queued,
+            "processing" => UserExportStatus::Processing,
+            "complete" => UserExportStatus::Complete,
+            "expired" => UserExportStatus::Expired,
+            _ => UserExportStatus::Failed,
+        }
+    }
+}
+
+/// An export of all of a user's personal data. The download link is sent to the user in a
+/// notification once the archive is ready.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UserExport {
+    pub id: UserExportId,
+    pub user_id: UserId,
+    pub status: UserExportStatus,
+    pub created: DateTime<Utc>,
+    pub completed: Option<DateTime<Utc>>,
+}
+
+impl From<crate::database::models::user_export_item::UserExport> for UserExport {
+    fn from(data: crate::database::models::user_export_item::UserExport) -> Self {
+        Self {
+            id: data.id.into(),
+            user_id: data.user_id.into(),
+            status: data.status,
+            created: data.created,
+            completed: data.completed,
+        }
+    }
+}
@@ -1,11 +1,16 @@
 use thiserror::Error;
 
+pub use super::audit_log::AuditLogEntryId;
 pub use super::collections::CollectionId;
+pub use super::connections::UserConnectionId;
+pub use super::exports::UserExportId;
 pub use super::images::ImageId;
 pub use super::notifications::NotificationId;
 pub use super::oauth_clients::OAuthClientAuthorizationId;
 pub use super::oauth_clients::{OAuthClientId, OAuthRedirectUriId};
-pub use super::organizations::OrganizationId;
+pub use super::organizations::{
+    OrganizationId, OrganizationInviteLinkId, OrganizationRoleTemplateId,
+};
 pub use super::pats::PatId;
 pub use super::payouts::PayoutId;
 pub use super::projects::{ProjectId, VersionId};
@@ -14,7 +19,9 @@ pub use super::sessions::SessionId;
 pub use super::teams::TeamId;
 pub use super::threads::ThreadId;
 pub use super::threads::ThreadMessageId;
+pub use super::uploads::PendingUploadId;
 pub use super::users::UserId;
+pub use super::webhooks::WebhookId;
 
 /// Generates a random 64 bit integer that is exactly `n` characters
 /// long when encoded as base62.
@@ -129,6 +136,13 @@ base62_id_impl!(OAuthClientId, OAuthClientId);
 base62_id_impl!(OAuthRedirectUriId, OAuthRedirectUriId);
 base62_id_impl!(OAuthClientAuthorizationId, OAuthClientAuthorizationId);
 base62_id_impl!(PayoutId, PayoutId);
+base62_id_impl!(WebhookId, WebhookId);
+base62_id_impl!(PendingUploadId, PendingUploadId);
+base62_id_impl!(AuditLogEntryId, AuditLogEntryId);
+base62_id_impl!(UserExportId, UserExportId);
+base62_id_impl!(OrganizationInviteLinkId, OrganizationInviteLinkId);
+base62_id_impl!(OrganizationRoleTemplateId, OrganizationRoleTemplateId);
+base62_id_impl!(UserConnectionId, UserConnectionId);
 
 pub mod base62_impl {
     use serde::de::{self, Deserializer, Visitor};
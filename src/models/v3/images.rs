@@ -2,13 +2,16 @@ use super::{
     ids::{Base62Id, ProjectId, ThreadMessageId, VersionId},
     pats::Scopes,
     reports::ReportId,
-    users::UserId,
+    users::{User, UserId},
 };
 use crate::database::models::image_item::Image as DBImage;
-use chrono::{DateTime, Utc};
+use crate::database::models::project_item::Project as DBProject;
+use crate::util::env::parse_var;
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 #[serde(from = "Base62Id")]
 #[serde(into = "Base62Id")]
 pub struct ImageId(pub u64);
@@ -17,9 +20,12 @@ pub struct ImageId(pub u64);
 pub struct Image {
     pub id: ImageId,
     pub url: String,
+    /// URLs of the resized variants of this image, by width
+    pub variants: HashMap<String, String>,
     pub size: u64,
     pub created: DateTime<Utc>,
     pub owner_id: UserId,
+    pub moderation_status: ImageModerationStatus,
 
     // context it is associated with
     #[serde(flatten)]
@@ -48,14 +54,76 @@ impl From<DBImage> for Image {
         Image {
             id: x.id.into(),
             url: x.url,
+            variants: x.variants,
             size: x.size,
             created: x.created,
             owner_id: x.owner_id.into(),
+            moderation_status: x.moderation_status,
             context,
         }
     }
 }
 
+const DEFAULT_PENDING_IMAGE_ACCOUNT_AGE_DAYS: i64 = 7;
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageModerationStatus {
+    #[default]
+    Approved,
+    /// Only shown to the team of the image's project and moderators until a moderator approves it
+    Pending,
+    /// Hidden from everyone, with its files replaced by a placeholder
+    Quarantined,
+}
+
+impl ImageModerationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageModerationStatus::Approved => "approved",
+            ImageModerationStatus::Pending => "pending",
+            ImageModerationStatus::Quarantined => "quarantined",
+        }
+    }
+
+    pub fn from_string(string: &str) -> ImageModerationStatus {
+        match string {
+            "approved" => ImageModerationStatus::Approved,
+            "quarantined" => ImageModerationStatus::Quarantined,
+            _ => ImageModerationStatus::Pending,
+        }
+    }
+
+    /// The status of an image the user uploads to the project. Images from accounts younger than
+    /// `PENDING_IMAGE_ACCOUNT_AGE_DAYS` or for projects which weren't approved yet have to be
+    /// reviewed first.
+    pub fn for_upload(user: &User, project: &DBProject) -> ImageModerationStatus {
+        if user.role.is_mod() {
+            return ImageModerationStatus::Approved;
+        }
+
+        let account_age_days = parse_var("PENDING_IMAGE_ACCOUNT_AGE_DAYS")
+            .unwrap_or(DEFAULT_PENDING_IMAGE_ACCOUNT_AGE_DAYS);
+        if Utc::now() - user.created < Duration::days(account_age_days)
+            || project.approved.is_none()
+        {
+            ImageModerationStatus::Pending
+        } else {
+            ImageModerationStatus::Approved
+        }
+    }
+
+    /// Whether images with this status are shown to the user. `elevated` users, the project's team
+    /// and moderators, can see pending images.
+    pub fn is_visible(&self, elevated: bool) -> bool {
+        match self {
+            ImageModerationStatus::Approved => true,
+            ImageModerationStatus::Pending => elevated,
+            ImageModerationStatus::Quarantined => false,
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 #[serde(tag = "context")]
 #[serde(rename_all = "snake_case")]
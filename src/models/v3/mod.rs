@@ -1,16 +1,27 @@
 pub mod analytics;
+pub mod audit_log;
 pub mod collections;
+pub mod connections;
+pub mod exports;
 pub mod ids;
 pub mod images;
+pub mod moderation;
 pub mod notifications;
 pub mod oauth_clients;
 pub mod organizations;
 pub mod pack;
 pub mod pats;
 pub mod payouts;
+pub mod project_blocks;
+pub mod project_history;
+pub mod project_transfers;
 pub mod projects;
 pub mod reports;
 pub mod sessions;
 pub mod teams;
 pub mod threads;
+pub mod uploads;
+pub mod user_blocks;
 pub mod users;
+pub mod webhooks;
+pub mod wiki;
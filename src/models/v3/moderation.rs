@@ -0,0 +1,95 @@
+use super::ids::{ProjectId, UserId};
+use super::projects::ProjectStatus;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The things moderators check when reviewing a project
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecklistItem {
+    LicensePresent,
+    DescriptionAdequate,
+    NoReuploadedContent,
+    FilesValid,
+}
+
+impl ChecklistItem {
+    pub fn as_friendly_str(&self) -> &'static str {
+        match self {
+            ChecklistItem::LicensePresent => "License",
+            ChecklistItem::DescriptionAdequate => "Description",
+            ChecklistItem::NoReuploadedContent => "Reuploaded content",
+            ChecklistItem::FilesValid => "Files",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecklistResult {
+    Pass,
+    Fail,
+    NotApplicable,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ChecklistEntry {
+    pub result: ChecklistResult,
+    pub note: Option<String>,
+}
+
+/// A moderator's checklist for one moderation pass over a project. Items which are left out
+/// haven't been checked.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ModerationReview {
+    pub project_id: ProjectId,
+    pub moderator_id: UserId,
+    pub items: BTreeMap<ChecklistItem, ChecklistEntry>,
+    pub created: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+    /// When the project was approved or rejected, ending the review
+    pub decided: Option<DateTime<Utc>>,
+    /// The status the project was given when the review ended
+    pub decision: Option<ProjectStatus>,
+}
+
+impl ModerationReview {
+    /// The message telling the project's team what failed the review, if anything did
+    pub fn rejection_message(&self) -> Option<String> {
+        let failed = self
+            .items
+            .iter()
+            .filter(|(_, entry)| entry.result == ChecklistResult::Fail)
+            .map(|(item, entry)| match entry.note.as_deref().map(str::trim) {
+                Some(note) if !note.is_empty() => {
+                    format!("- **{}**: {}", item.as_friendly_str(), note)
+                }
+                _ => format!("- **{}**", item.as_friendly_str()),
+            })
+            .collect::<Vec<_>>();
+
+        if failed.is_empty() {
+            return None;
+        }
+
+        Some(format!(
+            "This project was rejected because of the following issues:\n\n{}",
+            failed.join("\n")
+        ))
+    }
+}
+
+impl From<crate::database::models::moderation_review_item::ModerationReview> for ModerationReview {
+    fn from(data: crate::database::models::moderation_review_item::ModerationReview) -> Self {
+        Self {
+            project_id: data.project_id.into(),
+            moderator_id: data.moderator_id.into(),
+            items: data.items,
+            created: data.created,
+            updated: data.updated,
+            decided: data.decided,
+            decision: data.decision,
+        }
+    }
+}
@@ -3,8 +3,11 @@ use super::ids::OrganizationId;
 use super::users::UserId;
 use crate::database::models::notification_item::Notification as DBNotification;
 use crate::database::models::notification_item::NotificationAction as DBNotificationAction;
-use crate::models::ids::{ProjectId, ReportId, TeamId, ThreadId, ThreadMessageId, VersionId};
+use crate::models::ids::{
+    CollectionId, ProjectId, ReportId, TeamId, ThreadId, ThreadMessageId, UserExportId, VersionId,
+};
 use crate::models::projects::ProjectStatus;
+use crate::models::reports::ReportStatus;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -58,6 +61,74 @@ pub enum NotificationBody {
         project_id: Option<ProjectId>,
         report_id: Option<ReportId>,
     },
+    VersionPublishDelayed {
+        project_id: ProjectId,
+        version_id: VersionId,
+    },
+    ReportResolved {
+        report_id: ReportId,
+        status: ReportStatus,
+    },
+    Mention {
+        thread_id: ThreadId,
+        message_id: ThreadMessageId,
+        mentioned_by: UserId,
+
+        project_id: Option<ProjectId>,
+        report_id: Option<ReportId>,
+    },
+    DataExportReady {
+        export_id: UserExportId,
+        url: String,
+        expires: DateTime<Utc>,
+    },
+    CollectionUpdate {
+        collection_id: CollectionId,
+        project_ids: Vec<ProjectId>,
+    },
+    OrganizationMemberJoined {
+        organization_id: OrganizationId,
+        user_id: UserId,
+        role: String,
+    },
+    /// Sent to a member of an organization which requires 2FA who hasn't enabled it, and to the
+    /// organization's admins
+    OrganizationTwoFactorRequired {
+        organization_id: OrganizationId,
+        user_id: UserId,
+        restricted_after: DateTime<Utc>,
+    },
+    /// Sent to moderators when the virus scanner flags files of a version, which withholds it
+    VersionFilesFlagged {
+        project_id: ProjectId,
+        version_id: VersionId,
+        file_names: Vec<String>,
+    },
+    /// Sent to the members of an organization and its projects when it posts an announcement
+    OrganizationAnnouncement {
+        organization_id: OrganizationId,
+        body: String,
+    },
+    /// Sent to moderators when a version has files which were already uploaded to another
+    /// project, along with the report opened for it
+    VersionReuploadFlagged {
+        project_id: ProjectId,
+        version_id: VersionId,
+        original_project_id: ProjectId,
+        report_id: ReportId,
+    },
+    /// Sent to the recipient of a project's ownership transfer, who has to accept it. For
+    /// transfers to an organization, this is sent to the organization's owner.
+    ProjectTransferRequested {
+        project_id: ProjectId,
+        initiated_by: UserId,
+        organization_id: Option<OrganizationId>,
+    },
+    /// Sent to the user who started a project's ownership transfer once it's answered
+    ProjectTransferResolved {
+        project_id: ProjectId,
+        accepted: bool,
+    },
     LegacyMarkdown {
         notification_type: Option<String>,
         name: String,
@@ -68,6 +139,165 @@ pub enum NotificationBody {
     Unknown,
 }
 
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationType {
+    ProjectUpdate,
+    TeamInvite,
+    OrganizationInvite,
+    StatusChange,
+    ModeratorMessage,
+    VersionPublishDelayed,
+    ReportResolved,
+    Mention,
+    DataExportReady,
+    CollectionUpdate,
+    OrganizationMemberJoined,
+    OrganizationTwoFactorRequired,
+    VersionFilesFlagged,
+    OrganizationAnnouncement,
+    VersionReuploadFlagged,
+    ProjectTransferRequested,
+    ProjectTransferResolved,
+}
+
+impl NotificationType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationType::ProjectUpdate => "project_update",
+            NotificationType::TeamInvite => "team_invite",
+            NotificationType::OrganizationInvite => "organization_invite",
+            NotificationType::StatusChange => "status_change",
+            NotificationType::ModeratorMessage => "moderator_message",
+            NotificationType::VersionPublishDelayed => "version_publish_delayed",
+            NotificationType::ReportResolved => "report_resolved",
+            NotificationType::Mention => "mention",
+            NotificationType::DataExportReady => "data_export_ready",
+            NotificationType::CollectionUpdate => "collection_update",
+            NotificationType::OrganizationMemberJoined => "organization_member_joined",
+            NotificationType::OrganizationTwoFactorRequired => "organization_two_factor_required",
+            NotificationType::VersionFilesFlagged => "version_files_flagged",
+            NotificationType::OrganizationAnnouncement => "organization_announcement",
+            NotificationType::VersionReuploadFlagged => "version_reupload_flagged",
+            NotificationType::ProjectTransferRequested => "project_transfer_requested",
+            NotificationType::ProjectTransferResolved => "project_transfer_resolved",
+        }
+    }
+
+    /// Critical notifications can also be delivered by email, which is on unless the user
+    /// turns it off
+    pub fn sends_email(&self) -> bool {
+        matches!(
+            self,
+            NotificationType::TeamInvite
+                | NotificationType::StatusChange
+                | NotificationType::ProjectTransferRequested
+                | NotificationType::ReportResolved
+        )
+    }
+
+    pub fn iterator() -> impl Iterator<Item = NotificationType> {
+        [
+            NotificationType::ProjectUpdate,
+            NotificationType::TeamInvite,
+            NotificationType::OrganizationInvite,
+            NotificationType::StatusChange,
+            NotificationType::ModeratorMessage,
+            NotificationType::VersionPublishDelayed,
+            NotificationType::ReportResolved,
+            NotificationType::Mention,
+            NotificationType::DataExportReady,
+            NotificationType::CollectionUpdate,
+            NotificationType::OrganizationMemberJoined,
+            NotificationType::OrganizationTwoFactorRequired,
+            NotificationType::VersionFilesFlagged,
+            NotificationType::OrganizationAnnouncement,
+            NotificationType::VersionReuploadFlagged,
+            NotificationType::ProjectTransferRequested,
+            NotificationType::ProjectTransferResolved,
+        ]
+        .iter()
+        .copied()
+    }
+}
+
+impl NotificationBody {
+    /// The type used to look up a user's preferences for this notification.
+    /// Legacy and unknown notifications have no type, and are always delivered.
+    pub fn notification_type(&self) -> Option<NotificationType> {
+        match self {
+            NotificationBody::ProjectUpdate { .. } => Some(NotificationType::ProjectUpdate),
+            NotificationBody::TeamInvite { .. } => Some(NotificationType::TeamInvite),
+            NotificationBody::OrganizationInvite { .. } => {
+                Some(NotificationType::OrganizationInvite)
+            }
+            NotificationBody::StatusChange { .. } => Some(NotificationType::StatusChange),
+            NotificationBody::ModeratorMessage { .. } => Some(NotificationType::ModeratorMessage),
+            NotificationBody::VersionPublishDelayed { .. } => {
+                Some(NotificationType::VersionPublishDelayed)
+            }
+            NotificationBody::ReportResolved { .. } => Some(NotificationType::ReportResolved),
+            NotificationBody::Mention { .. } => Some(NotificationType::Mention),
+            NotificationBody::DataExportReady { .. } => Some(NotificationType::DataExportReady),
+            NotificationBody::CollectionUpdate { .. } => Some(NotificationType::CollectionUpdate),
+            NotificationBody::OrganizationMemberJoined { .. } => {
+                Some(NotificationType::OrganizationMemberJoined)
+            }
+            NotificationBody::OrganizationTwoFactorRequired { .. } => {
+                Some(NotificationType::OrganizationTwoFactorRequired)
+            }
+            NotificationBody::VersionFilesFlagged { .. } => {
+                Some(NotificationType::VersionFilesFlagged)
+            }
+            NotificationBody::OrganizationAnnouncement { .. } => {
+                Some(NotificationType::OrganizationAnnouncement)
+            }
+            NotificationBody::VersionReuploadFlagged { .. } => {
+                Some(NotificationType::VersionReuploadFlagged)
+            }
+            NotificationBody::ProjectTransferRequested { .. } => {
+                Some(NotificationType::ProjectTransferRequested)
+            }
+            NotificationBody::ProjectTransferResolved { .. } => {
+                Some(NotificationType::ProjectTransferResolved)
+            }
+            NotificationBody::LegacyMarkdown { .. } | NotificationBody::Unknown => None,
+        }
+    }
+
+    /// The user whose action sent this notification, if it was sent by a user. Users who blocked
+    /// them don't receive it.
+    pub fn originator(&self) -> Option<UserId> {
+        match self {
+            NotificationBody::TeamInvite { invited_by, .. }
+            | NotificationBody::OrganizationInvite { invited_by, .. } => Some(*invited_by),
+            NotificationBody::Mention { mentioned_by, .. } => Some(*mentioned_by),
+            NotificationBody::OrganizationMemberJoined { user_id, .. } => Some(*user_id),
+            NotificationBody::ProjectTransferRequested { initiated_by, .. } => Some(*initiated_by),
+            _ => None,
+        }
+    }
+}
+
+/// Whether notifications of a type are delivered to the user
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NotificationPreference {
+    #[serde(rename = "type")]
+    pub notification_type: NotificationType,
+    pub in_app: bool,
+    /// Always false for types which can't be delivered by email
+    pub send_email: bool,
+}
+
+/// A change to a user's delivery settings. Omitted channels are left as they are.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EditNotificationPreference {
+    #[serde(rename = "type")]
+    pub notification_type: NotificationType,
+    pub in_app: Option<bool>,
+    pub send_email: Option<bool>,
+}
+
 impl From<DBNotification> for Notification {
     fn from(notif: DBNotification) -> Self {
         let (name, text, link, actions) = {
@@ -166,6 +396,188 @@ impl From<DBNotification> for Notification {
                     },
                     vec![],
                 ),
+                NotificationBody::VersionPublishDelayed {
+                    project_id,
+                    version_id,
+                } => (
+                    "A scheduled version could not be published".to_string(),
+                    format!(
+                        "The version {} will be published once the project {} is approved",
+                        version_id, project_id
+                    ),
+                    format!("/project/{}/version/{}", project_id, version_id),
+                    vec![],
+                ),
+                NotificationBody::ReportResolved { report_id, status } => (
+                    "Your report has been closed".to_string(),
+                    format!(
+                        "A moderator has marked your report as {}",
+                        status.as_str().replace('_', " ")
+                    ),
+                    format!("/dashboard/report/{}", report_id),
+                    vec![],
+                ),
+                NotificationBody::Mention {
+                    project_id,
+                    report_id,
+                    ..
+                } => (
+                    "You were mentioned in a thread".to_string(),
+                    "Click on the link to read the message.".to_string(),
+                    if let Some(project_id) = project_id {
+                        format!("/project/{}/moderation", project_id)
+                    } else if let Some(report_id) = report_id {
+                        format!("/dashboard/report/{}", report_id)
+                    } else {
+                        "#".to_string()
+                    },
+                    vec![],
+                ),
+                NotificationBody::DataExportReady { url, expires, .. } => (
+                    "Your data export is ready".to_string(),
+                    format!(
+                        "The archive of your personal data can be downloaded until {}",
+                        expires.format("%Y-%m-%d %H:%M UTC")
+                    ),
+                    url.clone(),
+                    vec![],
+                ),
+                NotificationBody::CollectionUpdate {
+                    collection_id,
+                    project_ids,
+                } => (
+                    "A collection you follow has been updated!".to_string(),
+                    format!(
+                        "{} project(s) have been added to the collection {}",
+                        project_ids.len(),
+                        collection_id
+                    ),
+                    format!("/collection/{}", collection_id),
+                    vec![],
+                ),
+                NotificationBody::OrganizationMemberJoined {
+                    organization_id,
+                    user_id,
+                    role,
+                } => (
+                    "A new member has joined your organization".to_string(),
+                    format!(
+                        "The user {} has joined the organization as {} through an invite link",
+                        user_id, role
+                    ),
+                    format!("/organization/{}", organization_id),
+                    vec![],
+                ),
+                NotificationBody::OrganizationTwoFactorRequired {
+                    organization_id,
+                    user_id,
+                    restricted_after,
+                } => (
+                    "Your organization requires two-factor authentication".to_string(),
+                    if UserId::from(notif.user_id) == *user_id {
+                        format!(
+                            "Enable two-factor authentication on your account by {}, or you will lose your permissions in the organization until you do",
+                            restricted_after.format("%Y-%m-%d %H:%M UTC")
+                        )
+                    } else {
+                        format!(
+                            "The user {} hasn't enabled two-factor authentication, and will lose their permissions in the organization after {}",
+                            user_id,
+                            restricted_after.format("%Y-%m-%d %H:%M UTC")
+                        )
+                    },
+                    format!("/organization/{}", organization_id),
+                    vec![],
+                ),
+                NotificationBody::VersionFilesFlagged {
+                    project_id,
+                    version_id,
+                    file_names,
+                } => (
+                    "A version was withheld by the virus scanner".to_string(),
+                    format!(
+                        "The scanner flagged {} of the version {}, which is hidden until it's reviewed",
+                        file_names.join(", "),
+                        version_id
+                    ),
+                    format!("/project/{}/version/{}", project_id, version_id),
+                    vec![],
+                ),
+                NotificationBody::OrganizationAnnouncement {
+                    organization_id,
+                    body,
+                } => (
+                    "Your organization posted an announcement".to_string(),
+                    body.clone(),
+                    format!("/organization/{}", organization_id),
+                    vec![],
+                ),
+                NotificationBody::VersionReuploadFlagged {
+                    project_id,
+                    version_id,
+                    original_project_id,
+                    report_id,
+                } => (
+                    "A version was flagged as a reupload".to_string(),
+                    format!(
+                        "The version {} of the project {} has files which were already uploaded to the project {}",
+                        version_id, project_id, original_project_id
+                    ),
+                    format!("/dashboard/report/{}", report_id),
+                    vec![],
+                ),
+                NotificationBody::ProjectTransferRequested {
+                    project_id,
+                    initiated_by,
+                    organization_id,
+                } => (
+                    "A project is being transferred to you".to_string(),
+                    if let Some(organization_id) = organization_id {
+                        format!(
+                            "The user {} wants to transfer the project {} to the organization {}",
+                            initiated_by, project_id, organization_id
+                        )
+                    } else {
+                        format!(
+                            "The user {} wants to transfer the ownership of the project {} to you",
+                            initiated_by, project_id
+                        )
+                    },
+                    format!("/project/{}", project_id),
+                    vec![
+                        NotificationAction {
+                            name: "Accept".to_string(),
+                            action_route: (
+                                "POST".to_string(),
+                                format!("project/{project_id}/transfer/accept"),
+                            ),
+                        },
+                        NotificationAction {
+                            name: "Decline".to_string(),
+                            action_route: (
+                                "POST".to_string(),
+                                format!("project/{project_id}/transfer/decline"),
+                            ),
+                        },
+                    ],
+                ),
+                NotificationBody::ProjectTransferResolved {
+                    project_id,
+                    accepted,
+                } => (
+                    if *accepted {
+                        "Your project transfer was accepted".to_string()
+                    } else {
+                        "Your project transfer was declined".to_string()
+                    },
+                    format!(
+                        "The transfer of the project {} was {}",
+                        project_id,
+                        if *accepted { "accepted" } else { "declined" }
+                    ),
+                    format!("/project/{}", project_id),
+                    vec![],
+                ),
                 NotificationBody::LegacyMarkdown {
                     name,
                     text,
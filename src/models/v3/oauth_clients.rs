@@ -1,5 +1,5 @@
 use super::{
-    ids::{Base62Id, UserId},
+    ids::{Base62Id, OrganizationId, UserId},
     pats::Scopes,
 };
 use chrono::{DateTime, Utc};
@@ -55,6 +55,10 @@ pub struct OAuthClient {
     // The user that created (and thus controls) this client
     pub created_by: UserId,
 
+    // The organization that owns this client, if any.
+    // Organization-owned clients are controlled by the organization's members instead of the creator
+    pub organization_id: Option<OrganizationId>,
+
     // When this client was created
     pub created: DateTime<Utc>,
 
@@ -81,6 +85,11 @@ pub struct GetOAuthClientsRequest {
     pub ids: Vec<String>,
 }
 
+#[derive(Deserialize, Serialize)]
+pub struct GetUserOAuthClientsQueryParam {
+    pub organization_id: Option<OrganizationId>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct DeleteOAuthClientQueryParam {
     pub client_id: OAuthClientId,
@@ -95,6 +104,7 @@ impl From<DBOAuthClient> for OAuthClient {
             max_scopes: value.max_scopes,
             redirect_uris: value.redirect_uris.into_iter().map(|r| r.into()).collect(),
             created_by: value.created_by.into(),
+            organization_id: value.organization_id.map(|x| x.into()),
             created: value.created,
             url: value.url,
             description: value.description,
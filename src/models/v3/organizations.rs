@@ -1,8 +1,12 @@
 use super::{
     ids::{Base62Id, TeamId},
-    teams::TeamMember,
+    teams::{OrganizationPermissions, ProjectPermissions, TeamMember},
+    users::UserId,
 };
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// The ID of a team
 #[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -10,6 +14,18 @@ use serde::{Deserialize, Serialize};
 #[serde(into = "Base62Id")]
 pub struct OrganizationId(pub u64);
 
+/// The ID of an organization invite link
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(from = "Base62Id")]
+#[serde(into = "Base62Id")]
+pub struct OrganizationInviteLinkId(pub u64);
+
+/// The ID of an organization role template
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(from = "Base62Id")]
+#[serde(into = "Base62Id")]
+pub struct OrganizationRoleTemplateId(pub u64);
+
 /// An organization of users who control a project
 #[derive(Serialize, Deserialize)]
 pub struct Organization {
@@ -29,8 +45,39 @@ pub struct Organization {
     /// The color of the organization (picked from the icon)
     pub color: Option<u32>,
 
+    /// Whether members need two-factor authentication. Members without it lose their
+    /// permissions after a grace period.
+    pub require_2fa: bool,
+
     /// A list of the members of the organization
     pub members: Vec<TeamMember>,
+
+    /// Aggregated stats of the organization's projects, only returned when fetching a single
+    /// organization
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_summary: Option<OrganizationProjectSummary>,
+
+    /// How the payouts of projects added to the organization are split between its members, only
+    /// returned to members who can edit them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_payout_splits: Option<Vec<OrganizationPayoutSplit>>,
+}
+
+/// Aggregated stats of the projects owned by an organization
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct OrganizationProjectSummary {
+    /// The number of projects counted
+    pub projects: u32,
+    /// The number of projects of each project type. Projects with several types count towards each.
+    pub project_types: HashMap<String, u32>,
+    /// The number of projects supporting each loader
+    pub loaders: HashMap<String, u32>,
+    /// The total downloads of the projects
+    pub downloads: u64,
+    /// The total followers of the projects
+    pub followers: u64,
+    /// When any of the projects was last updated
+    pub updated: Option<DateTime<Utc>>,
 }
 
 impl Organization {
@@ -47,6 +94,105 @@ impl Organization {
             members: team_members,
             icon_url: data.icon_url,
             color: data.color,
+            require_2fa: data.require_2fa,
+            project_summary: None,
+            default_payout_splits: None,
+        }
+    }
+}
+
+/// A member's default share of the payouts of an organization's projects
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OrganizationPayoutSplit {
+    pub user_id: UserId,
+    /// The member's share, out of 100
+    pub weight: Decimal,
+}
+
+impl From<crate::database::models::organization_payout_split_item::OrganizationPayoutSplit>
+    for OrganizationPayoutSplit
+{
+    fn from(
+        data: crate::database::models::organization_payout_split_item::OrganizationPayoutSplit,
+    ) -> Self {
+        Self {
+            user_id: data.user_id.into(),
+            weight: data.weight,
+        }
+    }
+}
+
+/// A link users can join an organization with, as members with the link's role and permissions
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OrganizationInviteLink {
+    pub id: OrganizationInviteLinkId,
+    pub organization_id: OrganizationId,
+    /// The identifier to join the organization with
+    pub identifier: String,
+    pub role: String,
+    pub organization_permissions: OrganizationPermissions,
+    pub created_by: UserId,
+    pub created: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+    /// The number of times the link can be used, unlimited if not set
+    pub max_uses: Option<u32>,
+    pub uses: u32,
+}
+
+impl From<crate::database::models::organization_invite_link_item::OrganizationInviteLink>
+    for OrganizationInviteLink
+{
+    fn from(
+        data: crate::database::models::organization_invite_link_item::OrganizationInviteLink,
+    ) -> Self {
+        Self {
+            id: data.id.into(),
+            organization_id: data.organization_id.into(),
+            identifier: data.identifier,
+            role: data.role,
+            organization_permissions: data.organization_permissions,
+            created_by: data.created_by.into(),
+            created: data.created,
+            expires: data.expires,
+            max_uses: data.max_uses.map(|x| x as u32),
+            uses: data.uses as u32,
+        }
+    }
+}
+
+/// The most role templates an organization can have
+pub const MAX_ROLE_TEMPLATES: i64 = 20;
+
+/// A named preset of permissions, which members of the organization and of its projects can be
+/// given when they're invited or edited
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OrganizationRoleTemplate {
+    pub id: OrganizationRoleTemplateId,
+    pub organization_id: OrganizationId,
+    pub name: String,
+    pub description: String,
+    /// The permissions given to members of project teams, and the default project permissions
+    /// given to members of the organization
+    pub permissions: ProjectPermissions,
+    /// The permissions given to members of the organization. Unused for project team members.
+    pub organization_permissions: OrganizationPermissions,
+    pub created: DateTime<Utc>,
+}
+
+impl From<crate::database::models::organization_role_template_item::OrganizationRoleTemplate>
+    for OrganizationRoleTemplate
+{
+    fn from(
+        data: crate::database::models::organization_role_template_item::OrganizationRoleTemplate,
+    ) -> Self {
+        Self {
+            id: data.id.into(),
+            organization_id: data.organization_id.into(),
+            name: data.name,
+            description: data.description,
+            permissions: data.permissions,
+            organization_permissions: data.organization_permissions,
+            created: data.created,
         }
     }
 }
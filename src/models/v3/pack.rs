@@ -1,3 +1,4 @@
+use crate::models::ids::VersionId;
 use crate::{models::v2::projects::LegacySideType, util::env::parse_strings_from_var};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
@@ -106,4 +107,43 @@ impl PackDependency {
             PackDependency::QuiltLoader => "quilt-loader",
         }
     }
+
+    /// The loader the dependency is on, if it's on a loader rather than the game
+    pub fn loader(&self) -> Option<&'static str> {
+        match self {
+            PackDependency::Forge => Some("forge"),
+            PackDependency::Neoforge => Some("neoforge"),
+            PackDependency::FabricLoader => Some("fabric"),
+            PackDependency::QuiltLoader => Some("quilt"),
+            PackDependency::Minecraft => None,
+        }
+    }
+}
+
+/// A file in a pack hosted on Modrinth whose version doesn't support the pack's loaders or game
+/// version
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct IncompatiblePackFile {
+    /// The path of the file in the pack
+    pub path: String,
+    pub version_id: VersionId,
+    pub expected_loaders: Vec<String>,
+    pub actual_loaders: Vec<String>,
+    pub expected_game_versions: Vec<String>,
+    pub actual_game_versions: Vec<String>,
+}
+
+impl std::fmt::Display for IncompatiblePackFile {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "{} (version {}) supports loaders [{}] and game versions [{}], but the pack uses [{}] and [{}]",
+            self.path,
+            self.version_id,
+            self.actual_loaders.join(", "),
+            self.actual_game_versions.join(", "),
+            self.expected_loaders.join(", "),
+            self.expected_game_versions.join(", "),
+        )
+    }
 }
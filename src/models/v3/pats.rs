@@ -1,6 +1,6 @@
 use super::ids::Base62Id;
 use crate::bitflags_serde_impl;
-use crate::models::ids::UserId;
+use crate::models::ids::{ProjectId, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -155,6 +155,8 @@ pub struct PersonalAccessToken {
     pub created: DateTime<Utc>,
     pub expires: DateTime<Utc>,
     pub last_used: Option<DateTime<Utc>>,
+    /// The projects the token may act on. Unrestricted tokens can act on every project.
+    pub project_ids: Option<Vec<ProjectId>>,
 }
 
 impl PersonalAccessToken {
@@ -175,10 +177,23 @@ impl PersonalAccessToken {
             created: data.created,
             expires: data.expires,
             last_used: data.last_used,
+            project_ids: data
+                .project_ids
+                .map(|x| x.into_iter().map(|x| x.into()).collect()),
         }
     }
 }
 
+/// A single recorded use of a personal access token
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PatUsage {
+    pub used: DateTime<Utc>,
+    /// The route pattern which was requested, ie `/v3/project/{id}`
+    pub route: Option<String>,
+    /// The requesting IP, with the host part truncated
+    pub ip: Option<String>,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -3,6 +3,9 @@ use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+/// The currency all payouts are made in
+pub const PAYOUT_CURRENCY: &str = "USD";
+
 #[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 #[serde(from = "Base62Id")]
 #[serde(into = "Base62Id")]
@@ -74,6 +77,62 @@ impl PayoutMethodType {
             _ => PayoutMethodType::Unknown,
         }
     }
+
+    /// The fields of the user's payout method which must be set to withdraw using this type
+    pub fn required_fields(&self) -> Vec<String> {
+        match self {
+            PayoutMethodType::Venmo => vec!["handle".to_string()],
+            PayoutMethodType::PayPal => vec!["email".to_string()],
+            PayoutMethodType::Tremendous | PayoutMethodType::Unknown => vec![],
+        }
+    }
+}
+
+/// The payout method a user has configured to receive their payouts with
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UserPayoutMethod {
+    Paypal {
+        email: String,
+    },
+    Venmo {
+        handle: String,
+    },
+    BankTransfer {
+        account_holder: String,
+        iban: String,
+        bic: Option<String>,
+    },
+}
+
+impl UserPayoutMethod {
+    /// Hides everything but the last four characters of the sensitive fields
+    pub fn masked(&self) -> Self {
+        fn mask(value: &str) -> String {
+            let len = value.chars().count();
+            value
+                .chars()
+                .enumerate()
+                .map(|(i, c)| if i + 4 < len { '*' } else { c })
+                .collect()
+        }
+
+        match self {
+            UserPayoutMethod::Paypal { email } => UserPayoutMethod::Paypal { email: mask(email) },
+            UserPayoutMethod::Venmo { handle } => UserPayoutMethod::Venmo {
+                handle: mask(handle),
+            },
+            UserPayoutMethod::BankTransfer {
+                account_holder,
+                iban,
+                bic,
+            } => UserPayoutMethod::BankTransfer {
+                account_holder: account_holder.clone(),
+                iban: mask(iban),
+                bic: bic.clone(),
+            },
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug)]
@@ -117,6 +176,72 @@ impl PayoutStatus {
     }
 }
 
+/// Whether a payout reached the user, is still on its way or didn't go through
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutOutcome {
+    Paid,
+    Pending,
+    /// The payout failed or was cancelled, and its amount was returned to the user's balance
+    Failed,
+}
+
+impl PayoutOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PayoutOutcome::Paid => "paid",
+            PayoutOutcome::Pending => "pending",
+            PayoutOutcome::Failed => "failed",
+        }
+    }
+}
+
+impl From<PayoutStatus> for PayoutOutcome {
+    fn from(status: PayoutStatus) -> Self {
+        match status {
+            PayoutStatus::Success => PayoutOutcome::Paid,
+            PayoutStatus::InTransit | PayoutStatus::Cancelling | PayoutStatus::Unknown => {
+                PayoutOutcome::Pending
+            }
+            PayoutStatus::Cancelled | PayoutStatus::Failed => PayoutOutcome::Failed,
+        }
+    }
+}
+
+/// A payout in a user's payout history. Amounts are exact decimal strings.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PayoutRecord {
+    pub id: PayoutId,
+    pub status: PayoutStatus,
+    pub outcome: PayoutOutcome,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub amount: Decimal,
+    #[serde(with = "rust_decimal::serde::str_option")]
+    pub fee: Option<Decimal>,
+    pub currency: String,
+    pub method: Option<PayoutMethodType>,
+    /// When the payout was requested
+    pub initiated: DateTime<Utc>,
+    /// When the payout was paid, failed or was cancelled
+    pub completed: Option<DateTime<Utc>>,
+}
+
+impl From<crate::database::models::payout_item::Payout> for PayoutRecord {
+    fn from(data: crate::database::models::payout_item::Payout) -> Self {
+        Self {
+            id: data.id.into(),
+            status: data.status,
+            outcome: data.status.into(),
+            amount: data.amount.normalize(),
+            fee: data.fee.map(|x| x.normalize()),
+            currency: PAYOUT_CURRENCY.to_string(),
+            method: data.method,
+            initiated: data.created,
+            completed: data.completed,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct PayoutMethod {
     pub id: String,
@@ -127,6 +252,8 @@ pub struct PayoutMethod {
     pub image_url: Option<String>,
     pub interval: PayoutInterval,
     pub fee: PayoutMethodFee,
+    #[serde(default)]
+    pub required_fields: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
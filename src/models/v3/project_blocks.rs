@@ -0,0 +1,25 @@
+use super::users::UserId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The most users a project can block
+pub const MAX_PROJECT_BLOCKS: i64 = 500;
+
+/// A user blocked from posting in a project's threads and from joining its team
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProjectBlock {
+    pub user_id: UserId,
+    /// The team member who blocked the user, unless their account was deleted since
+    pub blocked_by: Option<UserId>,
+    pub created: DateTime<Utc>,
+}
+
+impl From<crate::database::models::project_block_item::ProjectBlock> for ProjectBlock {
+    fn from(data: crate::database::models::project_block_item::ProjectBlock) -> Self {
+        Self {
+            user_id: data.user_id.into(),
+            blocked_by: data.blocked_by.map(|x| x.into()),
+            created: data.created,
+        }
+    }
+}
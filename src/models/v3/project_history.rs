@@ -0,0 +1,113 @@
+use super::ids::{ProjectId, VersionId};
+use super::users::UserId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use spdx::{Expression, LicenseItem, LicenseReq};
+
+/// A group of the fields moderators check when approving a project, whose changes are recorded
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryFieldGroup {
+    License,
+    /// The loader fields which say which environments a version runs in
+    Environment,
+}
+
+impl HistoryFieldGroup {
+    pub fn fields(&self) -> &'static [&'static str] {
+        match self {
+            HistoryFieldGroup::License => &["license_id", "license_url"],
+            HistoryFieldGroup::Environment => &[
+                "singleplayer",
+                "client_and_server",
+                "client_only",
+                "server_only",
+            ],
+        }
+    }
+
+    pub fn from_string(string: &str) -> Option<HistoryFieldGroup> {
+        match string {
+            "license" => Some(HistoryFieldGroup::License),
+            "environment" => Some(HistoryFieldGroup::Environment),
+            _ => None,
+        }
+    }
+
+    pub fn list() -> &'static [HistoryFieldGroup] {
+        &[HistoryFieldGroup::License, HistoryFieldGroup::Environment]
+    }
+}
+
+/// A change to one of the fields moderators check when approving a project
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectFieldChange {
+    pub project_id: ProjectId,
+    /// The version whose loader field changed, for fields set on versions
+    pub version_id: Option<VersionId>,
+    pub field: String,
+    pub old_value: serde_json::Value,
+    pub new_value: serde_json::Value,
+    /// The user who made the change, unless their account was deleted
+    pub actor_id: Option<UserId>,
+    pub created: DateTime<Utc>,
+}
+
+impl From<crate::database::models::project_field_history_item::ProjectFieldChange>
+    for ProjectFieldChange
+{
+    fn from(data: crate::database::models::project_field_history_item::ProjectFieldChange) -> Self {
+        Self {
+            project_id: data.project_id.into(),
+            version_id: data.version_id.map(|x| x.into()),
+            field: data.field,
+            old_value: data.old_value,
+            new_value: data.new_value,
+            actor_id: data.actor_id.map(|x| x.into()),
+            created: data.created,
+        }
+    }
+}
+
+/// How restrictive a license is: 0 for permissive licenses, 1 for copyleft ones and 2 for
+/// licenses which aren't free, including custom ones such as All Rights Reserved. Of the
+/// licenses a project is offered under, the least restrictive is the one which counts.
+pub fn license_restrictiveness(license: &str) -> u8 {
+    fn restrictiveness(req: &LicenseReq) -> u8 {
+        match &req.license {
+            LicenseItem::Spdx { id, .. } if !id.is_osi_approved() && !id.is_fsf_free_libre() => 2,
+            LicenseItem::Spdx { id, .. } if id.is_copyleft() => 1,
+            LicenseItem::Spdx { .. } => 0,
+            LicenseItem::Other { .. } => 2,
+        }
+    }
+
+    let Ok(expression) = Expression::parse(license) else {
+        return 2;
+    };
+    (0..2)
+        .find(|max| expression.evaluate(|req| restrictiveness(req) <= *max))
+        .unwrap_or(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn licenses_are_ranked_by_restrictiveness() {
+        assert_eq!(license_restrictiveness("MIT"), 0);
+        assert_eq!(license_restrictiveness("GPL-3.0-only"), 1);
+        assert_eq!(license_restrictiveness("CC-BY-NC-4.0"), 2);
+        assert_eq!(
+            license_restrictiveness(crate::models::projects::DEFAULT_LICENSE_ID),
+            2
+        );
+    }
+
+    #[test]
+    fn the_least_restrictive_choice_counts() {
+        assert_eq!(license_restrictiveness("MIT OR GPL-3.0-only"), 0);
+        assert_eq!(license_restrictiveness("MIT AND GPL-3.0-only"), 1);
+    }
+}
@@ -0,0 +1,76 @@
+use super::ids::{OrganizationId, ProjectId};
+use super::teams::ProjectPermissions;
+use super::users::UserId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How long the recipient of a transfer has to accept it, in days
+pub const TRANSFER_EXPIRY_DAYS: i64 = 7;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectTransferStatus {
+    Pending,
+    Accepted,
+    Declined,
+    Cancelled,
+    /// The recipient didn't answer in time, which is set once a new transfer is started
+    Expired,
+    Unknown,
+}
+
+impl ProjectTransferStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProjectTransferStatus::Pending => "pending",
+            ProjectTransferStatus::Accepted => "accepted",
+            ProjectTransferStatus::Declined => "declined",
+            ProjectTransferStatus::Cancelled => "cancelled",
+            ProjectTransferStatus::Expired => "expired",
+            ProjectTransferStatus::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_string(string: &str) -> ProjectTransferStatus {
+        match string {
+            "pending" => ProjectTransferStatus::Pending,
+            "accepted" => ProjectTransferStatus::Accepted,
+            "declined" => ProjectTransferStatus::Declined,
+            "cancelled" => ProjectTransferStatus::Cancelled,
+            "expired" => ProjectTransferStatus::Expired,
+            _ => ProjectTransferStatus::Unknown,
+        }
+    }
+}
+
+/// A request to hand the ownership of a project to a user or an organization
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProjectTransfer {
+    pub project_id: ProjectId,
+    /// The owner who started the transfer
+    pub initiated_by: UserId,
+    /// The user receiving the project, unset for transfers to an organization
+    pub user_id: Option<UserId>,
+    /// The organization receiving the project, unset for transfers to a user
+    pub organization_id: Option<OrganizationId>,
+    /// The permissions the current owner keeps as a regular member once the transfer is accepted
+    pub previous_owner_permissions: ProjectPermissions,
+    pub status: ProjectTransferStatus,
+    pub created: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+}
+
+impl From<crate::database::models::project_transfer_item::ProjectTransfer> for ProjectTransfer {
+    fn from(data: crate::database::models::project_transfer_item::ProjectTransfer) -> Self {
+        Self {
+            project_id: data.project_id.into(),
+            initiated_by: data.initiated_by.into(),
+            user_id: data.target_user_id.map(|x| x.into()),
+            organization_id: data.target_organization_id.map(|x| x.into()),
+            previous_owner_permissions: data.previous_owner_permissions,
+            status: data.status,
+            created: data.created,
+            expires: data.expires,
+        }
+    }
+}
@@ -1,14 +1,15 @@
 use std::collections::{HashMap, HashSet};
 
 use super::ids::base62_impl::parse_base62;
-use super::ids::{Base62Id, OrganizationId};
+use super::ids::{Base62Id, ImageId, OrganizationId};
 use super::teams::TeamId;
 use super::users::UserId;
 use crate::database::models::loader_fields::VersionField;
-use crate::database::models::project_item::{LinkUrl, QueryProject};
-use crate::database::models::version_item::QueryVersion;
+use crate::database::models::project_item::{LinkUrl, ProjectTranslation, QueryProject};
+use crate::database::models::version_item::{QueryDependency, QueryVersion};
 use crate::models::threads::ThreadId;
 use crate::search::ResultSearchProject;
+use crate::validate::registry::ValidationIssue;
 use chrono::{DateTime, Utc};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -47,6 +48,12 @@ pub struct Project {
     pub summary: String,
     /// A long form description of the project.
     pub description: String,
+    /// The raw README URL the description is imported from, if any
+    #[serde(default)]
+    pub description_source_url: Option<String>,
+    /// The date at which the description was last imported from its source
+    #[serde(default)]
+    pub description_fetched: Option<DateTime<Utc>>,
 
     /// The date at which the project was first published.
     pub published: DateTime<Utc>,
@@ -59,6 +66,9 @@ pub struct Project {
     pub approved: Option<DateTime<Utc>>,
     /// The date at which the project entered the moderation queue
     pub queued: Option<DateTime<Utc>>,
+    /// When the project will be listed once approved, if its team scheduled it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub go_live_at: Option<DateTime<Utc>>,
 
     /// The status of the project
     pub status: ProjectStatus,
@@ -82,6 +92,9 @@ pub struct Project {
 
     /// A list of the categories that the project is in.
     pub additional_categories: Vec<String>,
+    /// The categories shown on the project. These are its categories, unless up to 3 of its
+    /// categories were picked to be shown in their place.
+    pub display_categories: Vec<String>,
     /// A list of loaders this project supports
     pub loaders: Vec<String>,
 
@@ -89,6 +102,9 @@ pub struct Project {
     pub versions: Vec<VersionId>,
     /// The URL of the icon of the project
     pub icon_url: Option<String>,
+    /// URLs of the resized variants of the project's icon, by width
+    #[serde(default)]
+    pub icon_variants: HashMap<String, String>,
 
     /// A collection of links to the project's various pages.
     pub link_urls: HashMap<String, Link>,
@@ -105,6 +121,22 @@ pub struct Project {
     /// The monetization status of this project
     pub monetization_status: MonetizationStatus,
 
+    /// Whether the project is flagged as containing mature content
+    #[serde(default)]
+    pub mature: bool,
+
+    /// Whether any of the project's files were taken down by a moderator. Only shown to the
+    /// project's team members and moderators.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_withheld_files: Option<bool>,
+
+    /// The language the project is shown in, if it was translated to one the client asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// The untranslated fields, if the project is shown in another language
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<ProjectFallback>,
+
     /// Aggregated loader-fields across its myriad of versions
     #[serde(flatten)]
     pub fields: HashMap<String, Vec<serde_json::Value>>,
@@ -166,10 +198,13 @@ impl From<QueryProject> for Project {
             name: m.name,
             summary: m.summary,
             description: m.description,
+            description_source_url: m.description_source_url,
+            description_fetched: m.description_fetched,
             published: m.published,
             updated: m.updated,
             approved: m.approved,
             queued: m.queued,
+            go_live_at: m.go_live_at,
             status: m.status,
             requested_status: m.requested_status,
             moderator_message: if let Some(message) = m.moderation_message {
@@ -203,15 +238,21 @@ impl From<QueryProject> for Project {
             },
             downloads: m.downloads as u32,
             followers: m.follows as u32,
+            display_categories: if data.display_categories.is_empty() {
+                data.categories.clone()
+            } else {
+                data.display_categories
+            },
             categories: data.categories,
             additional_categories: data.additional_categories,
             loaders: m.loaders,
             versions: data.versions.into_iter().map(|v| v.into()).collect(),
             icon_url: m.icon_url,
+            icon_variants: m.icon_variants,
             link_urls: data
                 .urls
                 .into_iter()
-                .map(|d| (d.platform_name.clone(), Link::from(d)))
+                .map(|d| (d.name.clone(), Link::from(d)))
                 .collect(),
             gallery: data
                 .gallery_items
@@ -223,17 +264,52 @@ impl From<QueryProject> for Project {
                     description: x.description,
                     created: x.created,
                     ordering: x.ordering,
+                    variants: x.variants,
+                    image_id: x.image_id.map(|x| x.into()),
                 })
                 .collect(),
             color: m.color,
             thread_id: data.thread_id.into(),
             monetization_status: m.monetization_status,
+            mature: m.mature,
+            has_withheld_files: None,
+            language: None,
+            fallback: None,
             fields,
         }
     }
 }
 
+/// The fields of a project in its original language
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectFallback {
+    pub name: String,
+    pub summary: String,
+    pub description: String,
+}
+
 impl Project {
+    /// Shows the project in the translation's language. Fields which weren't translated are
+    /// left as they are, and the original ones are kept in `fallback`.
+    pub fn localize(&mut self, translation: ProjectTranslation) {
+        self.fallback = Some(ProjectFallback {
+            name: self.name.clone(),
+            summary: self.summary.clone(),
+            description: self.description.clone(),
+        });
+        self.language = Some(translation.language);
+
+        if let Some(name) = translation.name {
+            self.name = name;
+        }
+        if let Some(summary) = translation.summary {
+            self.summary = summary;
+        }
+        if let Some(description) = translation.description {
+            self.description = description;
+        }
+    }
+
     // Matches the from QueryProject, but with a ResultSearchProject
     pub fn from_search(m: ResultSearchProject) -> Option<Self> {
         let project_id = ProjectId(parse_base62(&m.project_id).ok()?);
@@ -282,12 +358,17 @@ impl Project {
             loaders.retain(|l| !mrpack_loaders.contains(l));
         }
 
-        // Categories
-        let mut categories = m.display_categories.clone();
-        categories.retain(|c| !loaders.contains(c));
+        // Categories. Documents without the primary categories were indexed before categories
+        // could be picked to be displayed, so their primary categories are the displayed ones.
+        let mut display_categories = m.display_categories.clone();
+        display_categories.retain(|c| !loaders.contains(c));
         if let Some(ref mrpack_loaders) = mrpack_loaders_strings {
-            categories.retain(|l| !mrpack_loaders.contains(l));
+            display_categories.retain(|l| !mrpack_loaders.contains(l));
         }
+        let categories = m
+            .primary_categories
+            .clone()
+            .unwrap_or_else(|| display_categories.clone());
 
         // Additional categories
         let mut additional_categories = m.categories.clone();
@@ -305,12 +386,6 @@ impl Project {
             .map(MonetizationStatus::from_string)
             .unwrap_or(MonetizationStatus::Monetized);
 
-        let link_urls = m
-            .links
-            .into_iter()
-            .map(|d| (d.platform_name.clone(), Link::from(d)))
-            .collect();
-
         let gallery = m
             .gallery_items
             .into_iter()
@@ -321,6 +396,8 @@ impl Project {
                 description: x.description,
                 created: x.created,
                 ordering: x.ordering,
+                variants: x.variants,
+                image_id: x.image_id.map(|x| x.into()),
             })
             .collect();
 
@@ -334,10 +411,13 @@ impl Project {
             name: m.name,
             summary: m.summary,
             description: "".to_string(), // Body is potentially huge, do not store in search
+            description_source_url: None,
+            description_fetched: None,
             published,
             updated,
             approved,
             queued,
+            go_live_at: None,
             status,
             requested_status,
             moderator_message: None, // Deprecated
@@ -366,14 +446,20 @@ impl Project {
             followers: m.follows as u32,
             categories,
             additional_categories,
+            display_categories,
             loaders,
             versions,
             icon_url,
-            link_urls,
+            icon_variants: HashMap::new(),
+            link_urls: m.links,
             gallery,
             color: m.color,
             thread_id,
             monetization_status,
+            mature: m.mature,
+            has_withheld_files: None,
+            language: None,
+            fallback: None,
             fields: m
                 .loader_fields
                 .into_iter()
@@ -390,6 +476,13 @@ pub struct GalleryItem {
     pub description: Option<String>,
     pub created: DateTime<Utc>,
     pub ordering: i64,
+    /// URLs of the resized variants of the image, by width
+    #[serde(default)]
+    pub variants: HashMap<String, String>,
+    /// The id of the uploaded image, by which the item can be edited. Unset for items uploaded
+    /// before gallery images were tracked
+    #[serde(default)]
+    pub image_id: Option<ImageId>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -409,6 +502,7 @@ pub struct License {
 
 #[derive(Serialize, Deserialize, Validate, Clone, Eq, PartialEq)]
 pub struct Link {
+    /// The platform the link is on, one of the `LinkPlatform`s
     pub platform: String,
     pub donation: bool,
     #[validate(
@@ -427,6 +521,106 @@ impl From<LinkUrl> for Link {
     }
 }
 
+/// The platforms project links can be on. A link named after a platform has to be on that
+/// platform's site, and links with any other name are generic links, which only need to be https.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkPlatform {
+    Github,
+    Issues,
+    Wiki,
+    Discord,
+    Patreon,
+    #[serde(rename = "ko-fi")]
+    KoFi,
+    Paypal,
+    BuyMeACoffee,
+    Generic,
+}
+
+impl LinkPlatform {
+    pub fn from_string(string: &str) -> Option<LinkPlatform> {
+        match string {
+            "github" => Some(LinkPlatform::Github),
+            "issues" => Some(LinkPlatform::Issues),
+            "wiki" => Some(LinkPlatform::Wiki),
+            "discord" => Some(LinkPlatform::Discord),
+            "patreon" => Some(LinkPlatform::Patreon),
+            "ko-fi" => Some(LinkPlatform::KoFi),
+            "paypal" => Some(LinkPlatform::Paypal),
+            "buymeacoffee" => Some(LinkPlatform::BuyMeACoffee),
+            "generic" => Some(LinkPlatform::Generic),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkPlatform::Github => "github",
+            LinkPlatform::Issues => "issues",
+            LinkPlatform::Wiki => "wiki",
+            LinkPlatform::Discord => "discord",
+            LinkPlatform::Patreon => "patreon",
+            LinkPlatform::KoFi => "ko-fi",
+            LinkPlatform::Paypal => "paypal",
+            LinkPlatform::BuyMeACoffee => "buymeacoffee",
+            LinkPlatform::Generic => "generic",
+        }
+    }
+
+    /// The sites links on the platform can be on, including their subdomains. Links on platforms
+    /// without any can be on any site.
+    pub fn hosts(&self) -> &'static [&'static str] {
+        match self {
+            LinkPlatform::Github => &["github.com"],
+            LinkPlatform::Discord => &["discord.gg", "discord.com"],
+            LinkPlatform::Patreon => &["patreon.com"],
+            LinkPlatform::KoFi => &["ko-fi.com"],
+            LinkPlatform::Paypal => &["paypal.com", "paypal.me"],
+            LinkPlatform::BuyMeACoffee => &["buymeacoffee.com"],
+            LinkPlatform::Issues | LinkPlatform::Wiki | LinkPlatform::Generic => &[],
+        }
+    }
+
+    /// Checks a project link, and returns the platform it's on. The error names the link and
+    /// explains what's wrong with it.
+    pub fn of_link(name: &str, url: &str) -> Result<LinkPlatform, String> {
+        let platform = LinkPlatform::from_string(name).unwrap_or(LinkPlatform::Generic);
+
+        if platform == LinkPlatform::Generic
+            && (name.is_empty()
+                || name.len() > 32
+                || !name.bytes().all(|x| {
+                    x.is_ascii_lowercase() || x.is_ascii_digit() || x == b'-' || x == b'_'
+                }))
+        {
+            return Err(format!(
+                "The link name {name} must be 1 to 32 lowercase letters, numbers, dashes or underscores"
+            ));
+        }
+
+        let host = url::Url::parse(url)
+            .ok()
+            .filter(|x| x.scheme() == "https")
+            .and_then(|x| x.host_str().map(|x| x.to_lowercase()))
+            .ok_or_else(|| format!("The {name} link {url} must be an https URL"))?;
+
+        let hosts = platform.hosts();
+        if !hosts.is_empty()
+            && !hosts
+                .iter()
+                .any(|x| host == *x || host.ends_with(&format!(".{x}")))
+        {
+            return Err(format!(
+                "The {name} link {url} must be on {}",
+                hosts.join(" or ")
+            ));
+        }
+
+        Ok(platform)
+    }
+}
+
 /// A status decides the visibility of a project in search, URLs, and the whole site itself.
 /// Approved - Project is displayed on search, and accessible by URL
 /// Rejected - Project is not displayed on search, and not accessible by URL (Temporary state, project can reapply)
@@ -448,6 +642,7 @@ pub enum ProjectStatus {
     Withheld,
     Scheduled,
     Private,
+    Deleted,
     Unknown,
 }
 
@@ -467,7 +662,9 @@ impl ProjectStatus {
             "unlisted" => ProjectStatus::Unlisted,
             "archived" => ProjectStatus::Archived,
             "withheld" => ProjectStatus::Withheld,
+            "scheduled" => ProjectStatus::Scheduled,
             "private" => ProjectStatus::Private,
+            "deleted" => ProjectStatus::Deleted,
             _ => ProjectStatus::Unknown,
         }
     }
@@ -483,6 +680,7 @@ impl ProjectStatus {
             ProjectStatus::Withheld => "withheld",
             ProjectStatus::Scheduled => "scheduled",
             ProjectStatus::Private => "private",
+            ProjectStatus::Deleted => "deleted",
         }
     }
     pub fn as_friendly_str(&self) -> &'static str {
@@ -497,6 +695,7 @@ impl ProjectStatus {
             ProjectStatus::Withheld => "Withheld",
             ProjectStatus::Scheduled => "Scheduled",
             ProjectStatus::Private => "Private",
+            ProjectStatus::Deleted => "Deleted",
         }
     }
 
@@ -511,6 +710,7 @@ impl ProjectStatus {
             ProjectStatus::Withheld,
             ProjectStatus::Scheduled,
             ProjectStatus::Private,
+            ProjectStatus::Deleted,
             ProjectStatus::Unknown,
         ]
         .iter()
@@ -526,6 +726,7 @@ impl ProjectStatus {
             ProjectStatus::Unknown => true,
             ProjectStatus::Scheduled => true,
             ProjectStatus::Private => true,
+            ProjectStatus::Deleted => true,
 
             ProjectStatus::Approved => false,
             ProjectStatus::Unlisted => false,
@@ -539,6 +740,11 @@ impl ProjectStatus {
         matches!(self, ProjectStatus::Approved | ProjectStatus::Archived)
     }
 
+    // Project's versions, gallery, and thread can't be changed
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, ProjectStatus::Archived)
+    }
+
     // Project is "Approved" by moderators
     pub fn is_approved(&self) -> bool {
         matches!(
@@ -564,6 +770,7 @@ impl ProjectStatus {
             ProjectStatus::Unknown => false,
             ProjectStatus::Withheld => false,
             ProjectStatus::Scheduled => false,
+            ProjectStatus::Deleted => false,
         }
     }
 }
@@ -633,11 +840,29 @@ pub struct Version {
     pub status: VersionStatus,
     /// The requested status of the version (used for scheduling)
     pub requested_status: Option<VersionStatus>,
+    /// When the version will be automatically published, if it is scheduled
+    pub publish_at: Option<DateTime<Utc>>,
+    /// Whether any of the files were already uploaded to a project the uploader isn't a member
+    /// of. Only shown to the project's team members and moderators.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flagged_reupload: Option<bool>,
+    /// Whether any of the files were taken down by a moderator. Only shown to the project's team
+    /// members and moderators, as withheld files are left out of the files otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_withheld_files: Option<bool>,
 
     /// A list of files available for download for this version.
     pub files: Vec<VersionFile>,
     /// A list of projects that this version depends on.
     pub dependencies: Vec<Dependency>,
+    /// Dependencies the version's mod metadata declares which couldn't be matched to a project.
+    /// Only returned when the version is created.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependency_suggestions: Vec<DependencySuggestion>,
+    /// Problems the file validators found with the version's files which didn't stop them being
+    /// uploaded. Only returned when the version is created.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub validation_warnings: Vec<ValidationIssue>,
 
     /// The loaders that this version works on
     pub loaders: Vec<Loader>,
@@ -662,6 +887,15 @@ where
 
 impl From<QueryVersion> for Version {
     fn from(data: QueryVersion) -> Version {
+        Version::from_query(data, false)
+    }
+}
+
+impl Version {
+    /// Builds the version, only including the files' scan statuses, withheld files and whether it's
+    /// flagged as a reupload if `show_scans` is set. They should only be shown to the project's team
+    /// members and moderators.
+    pub fn from_query(data: QueryVersion, show_scans: bool) -> Version {
         let v = data.inner;
         Version {
             id: v.id.into(),
@@ -675,19 +909,19 @@ impl From<QueryVersion> for Version {
             changelog: v.changelog,
             date_published: v.date_published,
             downloads: v.downloads as u32,
-            version_type: match v.version_type.as_str() {
-                "release" => VersionType::Release,
-                "beta" => VersionType::Beta,
-                "alpha" => VersionType::Alpha,
-                _ => VersionType::Release,
-            },
+            version_type: VersionType::from_string(&v.version_type),
             ordering: v.ordering,
 
             status: v.status,
             requested_status: v.requested_status,
+            publish_at: v.publish_at,
+            flagged_reupload: show_scans.then_some(v.flagged_reupload),
+            has_withheld_files: show_scans
+                .then(|| data.files.iter().any(|f| f.withheld_at.is_some())),
             files: data
                 .files
                 .into_iter()
+                .filter(|f| show_scans || f.withheld_at.is_none())
                 .map(|f| VersionFile {
                     url: f.url,
                     filename: f.filename,
@@ -695,18 +929,18 @@ impl From<QueryVersion> for Version {
                     primary: f.primary,
                     size: f.size,
                     file_type: f.file_type,
+                    scan_status: show_scans.then_some(f.scan_status),
+                    scanned_at: if show_scans { f.scanned_at } else { None },
+                    withheld: show_scans.then_some(f.withheld_at.is_some()),
                 })
                 .collect(),
             dependencies: data
                 .dependencies
                 .into_iter()
-                .map(|d| Dependency {
-                    version_id: d.version_id.map(|i| VersionId(i.0 as u64)),
-                    project_id: d.project_id.map(|i| ProjectId(i.0 as u64)),
-                    file_name: d.file_name,
-                    dependency_type: DependencyType::from_string(d.dependency_type.as_str()),
-                })
+                .map(Dependency::from)
                 .collect(),
+            dependency_suggestions: Vec::new(),
+            validation_warnings: Vec::new(),
             loaders: data.loaders.into_iter().map(Loader).collect(),
             // Only add the internal component of the field for display
             // "ie": "game_versions",["1.2.3"] instead of "game_versions",ArrayEnum(...)
@@ -725,6 +959,8 @@ impl From<QueryVersion> for Version {
 /// Draft - Version is not displayed on project, and not accessible by URL
 /// Unlisted - Version is not displayed on project, and accessible by URL
 /// Scheduled - Version is scheduled to be released in the future
+/// Withheld - Version has a file which was flagged by the virus scanner, and is hidden until a
+/// moderator reviews it
 #[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum VersionStatus {
@@ -733,6 +969,7 @@ pub enum VersionStatus {
     Draft,
     Unlisted,
     Scheduled,
+    Withheld,
     Unknown,
 }
 
@@ -749,6 +986,7 @@ impl VersionStatus {
             "draft" => VersionStatus::Draft,
             "unlisted" => VersionStatus::Unlisted,
             "scheduled" => VersionStatus::Scheduled,
+            "withheld" => VersionStatus::Withheld,
             _ => VersionStatus::Unknown,
         }
     }
@@ -760,6 +998,7 @@ impl VersionStatus {
             VersionStatus::Unlisted => "unlisted",
             VersionStatus::Unknown => "unknown",
             VersionStatus::Scheduled => "scheduled",
+            VersionStatus::Withheld => "withheld",
         }
     }
 
@@ -770,6 +1009,7 @@ impl VersionStatus {
             VersionStatus::Draft,
             VersionStatus::Unlisted,
             VersionStatus::Scheduled,
+            VersionStatus::Withheld,
             VersionStatus::Unknown,
         ]
         .iter()
@@ -785,6 +1025,7 @@ impl VersionStatus {
 
             VersionStatus::Draft => true,
             VersionStatus::Scheduled => true,
+            VersionStatus::Withheld => true,
             VersionStatus::Unknown => true,
         }
     }
@@ -802,6 +1043,7 @@ impl VersionStatus {
             VersionStatus::Draft => true,
             VersionStatus::Unlisted => true,
             VersionStatus::Scheduled => false,
+            VersionStatus::Withheld => false,
 
             VersionStatus::Unknown => false,
         }
@@ -824,6 +1066,16 @@ pub struct VersionFile {
     pub size: u32,
     /// The type of the file
     pub file_type: Option<FileType>,
+    /// The result of the file's latest virus scan. Only shown to the project's team members and
+    /// moderators.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_status: Option<FileScanStatus>,
+    /// When the file was last scanned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scanned_at: Option<DateTime<Utc>>,
+    /// Whether the file was taken down by a moderator, in which case it can't be downloaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub withheld: Option<bool>,
 }
 
 /// A dendency which describes what versions are required, break support, or are optional to the
@@ -838,6 +1090,33 @@ pub struct Dependency {
     pub file_name: Option<String>,
     /// The type of the dependency
     pub dependency_type: DependencyType,
+    /// Whether the dependency was filled in from the version's mod metadata rather than given by
+    /// the uploader
+    #[serde(default)]
+    pub inferred: bool,
+}
+
+impl From<QueryDependency> for Dependency {
+    fn from(data: QueryDependency) -> Self {
+        Self {
+            version_id: data.version_id.map(|i| VersionId(i.0 as u64)),
+            project_id: data.project_id.map(|i| ProjectId(i.0 as u64)),
+            file_name: data.file_name,
+            dependency_type: DependencyType::from_string(data.dependency_type.as_str()),
+            inferred: data.inferred,
+        }
+    }
+}
+
+/// A dependency declared in a version's mod metadata on a mod id which doesn't belong to any known
+/// project, so couldn't be filled in
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DependencySuggestion {
+    /// The id of the mod in its loader metadata
+    pub mod_id: String,
+    /// The versions of the mod which work, in the loader's own syntax
+    pub version_range: Option<String>,
+    pub dependency_type: DependencyType,
 }
 
 #[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug)]
@@ -855,6 +1134,14 @@ impl std::fmt::Display for VersionType {
 }
 
 impl VersionType {
+    pub fn from_string(string: &str) -> VersionType {
+        match string {
+            "beta" => VersionType::Beta,
+            "alpha" => VersionType::Alpha,
+            _ => VersionType::Release,
+        }
+    }
+
     // These are constant, so this can remove unneccessary allocations (`to_string`)
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -936,6 +1223,42 @@ impl FileType {
     }
 }
 
+/// The result of the latest virus scan of a version file
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileScanStatus {
+    Pending,
+    Clean,
+    Flagged,
+    Error,
+}
+
+impl std::fmt::Display for FileScanStatus {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str(self.as_str())
+    }
+}
+
+impl FileScanStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileScanStatus::Pending => "pending",
+            FileScanStatus::Clean => "clean",
+            FileScanStatus::Flagged => "flagged",
+            FileScanStatus::Error => "error",
+        }
+    }
+
+    pub fn from_string(string: &str) -> FileScanStatus {
+        match string {
+            "clean" => FileScanStatus::Clean,
+            "flagged" => FileScanStatus::Flagged,
+            "error" => FileScanStatus::Error,
+            _ => FileScanStatus::Pending,
+        }
+    }
+}
+
 /// A project loader
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 #[serde(transparent)]
@@ -952,6 +1275,22 @@ pub struct SearchRequest {
 
     pub new_filters: Option<String>,
 
+    /// Includes mature projects in the results, if the user has opted in to seeing them
+    #[serde(default)]
+    pub include_mature: bool,
+
+    /// Comma separated top-level fields of each hit to return, see `util::fields`
+    pub fields: Option<String>,
+
+    /// ISO-8601 durations, such as `P7D`, limiting the results to projects updated or created
+    /// within that long ago
+    pub updated_within: Option<String>,
+    pub created_within: Option<String>,
+
+    /// Whether each hit is a project (`project`, the default), or one of the matching versions of
+    /// a project (`version`)
+    pub group: Option<String>,
+
     // TODO: Deprecated values below. WILL BE REMOVED V3!
     pub facets: Option<String>,
     pub filters: Option<String>,
@@ -15,18 +15,69 @@ pub struct Report {
     pub report_type: String,
     pub item_id: String,
     pub item_type: ItemType,
+    /// Another project the report is about, such as the project a file was reuploaded from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub related_project_id: Option<ProjectId>,
     pub reporter: UserId,
     pub body: String,
     pub created: DateTime<Utc>,
     pub closed: bool,
+    pub status: ReportStatus,
+    pub assigned_moderator: Option<UserId>,
     pub thread_id: ThreadId,
 }
 
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    Open,
+    UnderReview,
+    Resolved,
+    Rejected,
+}
+
+impl std::fmt::Display for ReportStatus {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}", self.as_str())
+    }
+}
+
+impl ReportStatus {
+    pub fn from_string(string: &str) -> ReportStatus {
+        match string {
+            "under_review" => ReportStatus::UnderReview,
+            "resolved" => ReportStatus::Resolved,
+            "rejected" => ReportStatus::Rejected,
+            _ => ReportStatus::Open,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportStatus::Open => "open",
+            ReportStatus::UnderReview => "under_review",
+            ReportStatus::Resolved => "resolved",
+            ReportStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        matches!(self, ReportStatus::Resolved | ReportStatus::Rejected)
+    }
+
+    /// Closed reports can only be moved back to an open status, and only when explicitly reopened
+    pub fn can_transition_to(&self, new_status: ReportStatus, reopen: bool) -> bool {
+        !self.is_closed() || (reopen && !new_status.is_closed())
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub enum ItemType {
     Project,
     Version,
+    /// A file of a version, reported by its hash
+    File,
     User,
     Unknown,
 }
@@ -36,6 +87,7 @@ impl ItemType {
         match self {
             ItemType::Project => "project",
             ItemType::Version => "version",
+            ItemType::File => "file",
             ItemType::User => "user",
             ItemType::Unknown => "unknown",
         }
@@ -47,7 +99,10 @@ impl From<DBReport> for Report {
         let mut item_id = "".to_string();
         let mut item_type = ItemType::Unknown;
 
-        if let Some(project_id) = x.project_id {
+        if let Some(file_hash) = x.file_hash {
+            item_id = file_hash;
+            item_type = ItemType::File;
+        } else if let Some(project_id) = x.project_id {
             item_id = ProjectId::from(project_id).to_string();
             item_type = ItemType::Project;
         } else if let Some(version_id) = x.version_id {
@@ -63,10 +118,13 @@ impl From<DBReport> for Report {
             report_type: x.report_type,
             item_id,
             item_type,
+            related_project_id: x.related_project_id.map(|x| x.into()),
             reporter: x.reporter.into(),
             body: x.body,
             created: x.created,
             closed: x.closed,
+            status: x.status,
+            assigned_moderator: x.assigned_moderator.map(|x| x.into()),
             thread_id: x.thread_id.into(),
         }
     }
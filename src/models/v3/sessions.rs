@@ -58,3 +58,22 @@ impl Session {
         }
     }
 }
+
+/// A session as listed to its user. Only the approximate location of the session is shown,
+/// rather than its IP address.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ActiveSession {
+    pub id: SessionId,
+
+    pub created: DateTime<Utc>,
+    pub last_login: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+
+    pub os: Option<String>,
+    pub platform: Option<String>,
+    pub user_agent: String,
+
+    pub country: Option<String>,
+
+    pub current: bool,
+}
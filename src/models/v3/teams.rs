@@ -1,6 +1,7 @@
-use super::ids::Base62Id;
+use super::ids::{Base62Id, OrganizationRoleTemplateId};
 use crate::bitflags_serde_impl;
-use crate::models::users::User;
+use crate::models::users::{User, UserId};
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +13,8 @@ pub struct TeamId(pub u64);
 
 pub const OWNER_ROLE: &str = "Owner";
 pub const DEFAULT_ROLE: &str = "Member";
+/// The most members a team can have, counting the users who haven't accepted their invites yet
+pub const MAX_TEAM_MEMBERS: i64 = 256;
 
 /// A team of users who control a project
 #[derive(Serialize, Deserialize)]
@@ -35,6 +38,9 @@ bitflags::bitflags! {
         const DELETE_PROJECT = 1 << 7;
         const VIEW_ANALYTICS = 1 << 8;
         const VIEW_PAYOUTS = 1 << 9;
+        const MANAGE_WEBHOOKS = 1 << 10;
+        const VIEW_AUDIT_LOG = 1 << 11;
+        const EDIT_WIKI = 1 << 12;
     }
 }
 
@@ -56,6 +62,14 @@ impl ProjectPermissions {
             return Some(ProjectPermissions::all());
         }
 
+        // Members of an organization which requires 2FA can't do anything until they enable it,
+        // whatever their permissions in the project are
+        if let Some(member) = organization_team_member {
+            if member.accepted && member.two_factor_restricted {
+                return Some(ProjectPermissions::empty());
+            }
+        }
+
         if let Some(member) = project_team_member {
             if member.accepted {
                 return Some(member.permissions);
@@ -91,6 +105,10 @@ bitflags::bitflags! {
         const REMOVE_PROJECT = 1 << 5;
         const DELETE_ORGANIZATION = 1 << 6;
         const EDIT_MEMBER_DEFAULT_PERMISSIONS = 1 << 7; // Separate from EDIT_MEMBER
+        const MANAGE_OAUTH_CLIENTS = 1 << 8;
+        const VIEW_AUDIT_LOG = 1 << 9;
+        const SEND_ANNOUNCEMENTS = 1 << 10;
+        const EDIT_PAYOUTS = 1 << 11;
         const NONE = 0b0;
     }
 }
@@ -114,6 +132,10 @@ impl OrganizationPermissions {
 
         if let Some(member) = team_member {
             if member.accepted {
+                // The organization requires 2FA, which the member hasn't enabled
+                if member.two_factor_restricted {
+                    return Some(OrganizationPermissions::NONE);
+                }
                 return member.organization_permissions;
             }
         }
@@ -156,6 +178,20 @@ pub struct TeamMember {
     pub payouts_split: Option<Decimal>,
     /// Ordering of the member in the list
     pub ordering: i64,
+
+    /// When the user was invited or added to the team
+    pub created: DateTime<Utc>,
+    /// When the user accepted their invite. None for pending members
+    pub accepted_at: Option<DateTime<Utc>>,
+    /// The user who sent a pending member's invite.
+    /// Only shown to those who can manage the team's invites
+    pub invited_by: Option<UserId>,
+    /// Whether the member lost their permissions because they haven't enabled two-factor
+    /// authentication, which the organization requires
+    pub two_factor_restricted: bool,
+    /// The organization role template the member's permissions were last set from.
+    /// Hidden along with the permissions.
+    pub role_template_id: Option<OrganizationRoleTemplateId>,
 }
 
 impl TeamMember {
@@ -198,6 +234,19 @@ impl TeamMember {
                 Some(data.payouts_split)
             },
             ordering: data.ordering,
+            created: data.created,
+            accepted_at: data.accepted_at,
+            invited_by: if data.accepted || override_permissions {
+                None
+            } else {
+                data.invited_by.map(|x| x.into())
+            },
+            two_factor_restricted: data.two_factor_restricted,
+            role_template_id: if override_permissions {
+                None
+            } else {
+                data.role_template_id.map(|x| x.into())
+            },
         }
     }
 }
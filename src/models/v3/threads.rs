@@ -1,6 +1,8 @@
 use super::ids::{Base62Id, ImageId};
-use crate::models::ids::{ProjectId, ReportId};
+use crate::models::ids::{OrganizationId, ProjectId, ReportId};
+use crate::models::images::{Image, ImageContext, ImageModerationStatus};
 use crate::models::projects::ProjectStatus;
+use crate::models::reports::ReportStatus;
 use crate::models::users::{User, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -32,6 +34,18 @@ pub struct ThreadMessage {
     pub author_id: Option<UserId>,
     pub body: MessageBody,
     pub created: DateTime<Utc>,
+    #[serde(default)]
+    pub attachments: Vec<ThreadMessageAttachment>,
+}
+
+/// The maximum number of images which can be attached to a thread message
+pub const MAX_MESSAGE_ATTACHMENTS: usize = 5;
+
+/// An image attached to a thread message
+#[derive(Serialize, Deserialize)]
+pub struct ThreadMessageAttachment {
+    pub id: ImageId,
+    pub url: String,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -51,10 +65,19 @@ pub enum MessageBody {
     },
     ThreadClosure,
     ThreadReopen,
+    ReportStatusChange {
+        new_status: ReportStatus,
+        old_status: ReportStatus,
+    },
     Deleted {
         #[serde(default)]
         private: bool,
     },
+    /// A message an organization sent to the threads of all of its projects at once
+    OrganizationAnnouncement {
+        organization_id: OrganizationId,
+        body: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Eq, PartialEq, Copy, Clone)]
@@ -92,7 +115,14 @@ impl ThreadType {
 }
 
 impl Thread {
-    pub fn from(data: crate::database::models::Thread, users: Vec<User>, user: &User) -> Self {
+    /// The thread as the user sees it. Images attached to the messages are shown with them,
+    /// except on deleted messages, and only the uploader and moderators see pending images.
+    pub fn from(
+        data: crate::database::models::Thread,
+        users: Vec<User>,
+        images: &[Image],
+        user: &User,
+    ) -> Self {
         let thread_type = data.type_;
 
         Thread {
@@ -124,6 +154,30 @@ impl Thread {
                     } else {
                         x.author_id.map(|x| x.into())
                     },
+                    attachments: if let MessageBody::Text { .. } = x.body {
+                        images
+                            .iter()
+                            .filter(|image| {
+                                image.context
+                                    == ImageContext::ThreadMessage {
+                                        thread_message_id: Some(x.id.into()),
+                                    }
+                                    && match image.moderation_status {
+                                        ImageModerationStatus::Approved => true,
+                                        ImageModerationStatus::Pending => {
+                                            image.owner_id == user.id || user.role.is_mod()
+                                        }
+                                        ImageModerationStatus::Quarantined => false,
+                                    }
+                            })
+                            .map(|image| ThreadMessageAttachment {
+                                id: image.id,
+                                url: image.url.clone(),
+                            })
+                            .collect()
+                    } else {
+                        Vec::new()
+                    },
                     body: x.body,
                     created: x.created,
                 })
@@ -135,3 +189,53 @@ impl Thread {
         }
     }
 }
+
+/// An update pushed to the clients subscribed to a thread
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ThreadUpdate {
+    Message { message: ThreadMessage },
+    MessageDeleted { id: ThreadMessageId },
+}
+
+impl ThreadUpdate {
+    /// The update as the user sees it, if they can see it at all. As in threads, private
+    /// messages are only shown to moderators, who are anonymous to everyone else.
+    pub fn from(
+        data: crate::database::models::thread_item::ThreadUpdate,
+        user: &User,
+    ) -> Option<Self> {
+        use crate::database::models::thread_item::ThreadUpdate as DBThreadUpdate;
+
+        match data {
+            DBThreadUpdate::Message {
+                message,
+                author_is_mod,
+            } => {
+                let private = match message.body {
+                    MessageBody::Text { private, .. } | MessageBody::Deleted { private } => private,
+                    _ => false,
+                };
+                if private && !user.role.is_mod() {
+                    return None;
+                }
+
+                Some(ThreadUpdate::Message {
+                    message: ThreadMessage {
+                        id: message.id.into(),
+                        author_id: if author_is_mod && !user.role.is_mod() {
+                            None
+                        } else {
+                            message.author_id.map(|x| x.into())
+                        },
+                        body: message.body,
+                        created: message.created,
+                        attachments: Vec::new(),
+                    },
+                })
+            }
+            DBThreadUpdate::MessageDeleted { id, private } => (!private || user.role.is_mod())
+                .then(|| ThreadUpdate::MessageDeleted { id: id.into() }),
+        }
+    }
+}
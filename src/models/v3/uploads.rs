@@ -0,0 +1,26 @@
+use super::ids::Base62Id;
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(from = "Base62Id")]
+#[serde(into = "Base62Id")]
+pub struct PendingUploadId(pub u64);
+
+/// A file being uploaded in several chunks, which can be resumed if the connection drops.
+/// Chunks are sent in order of their index, and every chunk except the last is `chunk_size` bytes.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PendingUpload {
+    pub id: PendingUploadId,
+    pub chunk_size: u32,
+    pub chunk_count: u32,
+}
+
+impl From<crate::database::models::pending_upload_item::PendingUpload> for PendingUpload {
+    fn from(data: crate::database::models::pending_upload_item::PendingUpload) -> Self {
+        Self {
+            id: data.id.into(),
+            chunk_size: data.chunk_size as u32,
+            chunk_count: data.chunk_count() as u32,
+        }
+    }
+}
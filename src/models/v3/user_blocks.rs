@@ -0,0 +1,22 @@
+use super::users::UserId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The most users a user can block
+pub const MAX_USER_BLOCKS: i64 = 1000;
+
+/// A user blocked by the current user
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UserBlock {
+    pub user_id: UserId,
+    pub created: DateTime<Utc>,
+}
+
+impl From<crate::database::models::user_block_item::UserBlock> for UserBlock {
+    fn from(data: crate::database::models::user_block_item::UserBlock) -> Self {
+        Self {
+            user_id: data.blocked_user_id.into(),
+            created: data.created,
+        }
+    }
+}
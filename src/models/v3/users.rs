@@ -1,4 +1,6 @@
+use super::connections::UserConnection;
 use super::ids::Base62Id;
+use super::payouts::UserPayoutMethod;
 use crate::{auth::AuthProvider, bitflags_serde_impl};
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
@@ -10,6 +12,8 @@ use serde::{Deserialize, Serialize};
 pub struct UserId(pub u64);
 
 pub const DELETED_USER: UserId = UserId(127155982985829);
+/// The user which opens reports automatically, such as for reuploaded files
+pub const AUTOMOD_USER: UserId = UserId(127155982985830);
 
 bitflags::bitflags! {
     #[derive(Copy, Clone, Debug)]
@@ -53,6 +57,11 @@ pub struct User {
     pub has_password: Option<bool>,
     pub has_totp: Option<bool>,
     pub payout_data: Option<UserPayoutData>,
+    /// Whether mature projects are hidden from this user's searches
+    pub hide_mature_content: Option<bool>,
+    /// Accounts on other sites linked to the user. Unverified connections are only shown to
+    /// the user themselves.
+    pub connections: Vec<UserConnection>,
 
     // DEPRECATED. Always returns None
     pub github_id: Option<u64>,
@@ -63,6 +72,8 @@ pub struct UserPayoutData {
     pub paypal_address: Option<String>,
     pub paypal_country: Option<String>,
     pub venmo_handle: Option<String>,
+    /// The configured payout method, with its sensitive fields masked
+    pub method: Option<UserPayoutMethod>,
     #[serde(with = "rust_decimal::serde::float")]
     pub balance: Decimal,
 }
@@ -85,6 +96,13 @@ impl From<DBUser> for User {
             auth_providers: None,
             has_password: None,
             has_totp: None,
+            hide_mature_content: None,
+            connections: data
+                .connections
+                .into_iter()
+                .filter(|x| x.verified)
+                .map(UserConnection::from)
+                .collect(),
             github_id: None,
         }
     }
@@ -0,0 +1,35 @@
+use super::ids::Base62Id;
+use crate::models::projects::ProjectId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(from = "Base62Id")]
+#[serde(into = "Base62Id")]
+pub struct WebhookId(pub u64);
+
+/// A URL which receives a signed POST request whenever a version of the project is published.
+/// The secret used for signing deliveries is never returned.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProjectWebhook {
+    pub id: WebhookId,
+    pub project_id: ProjectId,
+    pub url: String,
+    pub created: DateTime<Utc>,
+
+    pub last_attempt: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl From<crate::database::models::webhook_item::ProjectWebhook> for ProjectWebhook {
+    fn from(data: crate::database::models::webhook_item::ProjectWebhook) -> Self {
+        Self {
+            id: data.id.into(),
+            project_id: data.project_id.into(),
+            url: data.url,
+            created: data.created,
+            last_attempt: data.last_attempt,
+            last_error: data.last_error,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use super::ids::{ProjectId, UserId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A page of a project's documentation, beyond its description
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WikiPage {
+    pub project_id: ProjectId,
+    /// The page's id in its URL, which is unique within the project
+    pub slug: String,
+    pub title: String,
+    /// The markdown body of the page
+    pub body: String,
+    /// The position of the page among the project's pages
+    pub ordering: i32,
+    /// The user who last created or edited the page
+    pub updated_by: UserId,
+    pub created: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+}
+
+impl From<crate::database::models::wiki_page_item::WikiPage> for WikiPage {
+    fn from(data: crate::database::models::wiki_page_item::WikiPage) -> Self {
+        Self {
+            project_id: data.project_id.into(),
+            slug: data.slug,
+            title: data.title,
+            body: data.body,
+            ordering: data.ordering,
+            updated_by: data.updated_by.into(),
+            created: data.created,
+            updated: data.updated,
+        }
+    }
+}
@@ -1,16 +1,77 @@
-use crate::database::models::DatabaseError;
+use crate::database::models::daily_download_item::DailyDownload;
+use crate::database::models::{DatabaseError, ProjectId, VersionId};
 use crate::database::redis::RedisPool;
 use crate::models::analytics::{Download, PageView, Playtime};
 use crate::routes::ApiError;
+use chrono::Utc;
 use dashmap::{DashMap, DashSet};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
 use redis::cmd;
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 
 const DOWNLOADS_NAMESPACE: &str = "downloads";
+const DOWNLOAD_CONTEXTS_NAMESPACE: &str = "download_contexts";
+
+/// How long after fetching a pack's files its dependencies are counted as pack-driven, in seconds
+pub const DOWNLOAD_CONTEXT_EXPIRY: i64 = 30 * 60; // 30 minutes
+
+/// The state behind a download context token, issued when a launcher fetches the files of a
+/// version to install it
+#[derive(Serialize, Deserialize)]
+pub struct DownloadContext {
+    pub version_id: VersionId,
+    /// Every project in the version's dependency closure
+    pub project_ids: Vec<ProjectId>,
+}
+
+impl DownloadContext {
+    pub async fn insert(&self, redis: &RedisPool) -> Result<String, DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        let token = ChaCha20Rng::from_entropy()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect::<String>();
+
+        redis
+            .set_serialized_to_json(
+                DOWNLOAD_CONTEXTS_NAMESPACE,
+                &token,
+                self,
+                Some(DOWNLOAD_CONTEXT_EXPIRY),
+            )
+            .await?;
+        Ok(token)
+    }
+
+    /// Whether a download of the project made with the token was driven by installing the pack.
+    /// Unknown or expired tokens, and projects outside of the pack's dependencies, are organic.
+    pub async fn is_pack_driven(
+        token: &str,
+        project_id: ProjectId,
+        redis: &RedisPool,
+    ) -> Result<bool, DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        let context: Option<DownloadContext> = redis
+            .get_deserialized_from_json(DOWNLOAD_CONTEXTS_NAMESPACE, token)
+            .await?;
+
+        Ok(context
+            .map(|x| x.project_ids.contains(&project_id))
+            .unwrap_or(false))
+    }
+}
 
 pub struct AnalyticsQueue {
     views_queue: DashSet<PageView>,
-    downloads_queue: DashMap<String, Download>,
+    // Each download is kept with whether it was driven by installing a pack
+    downloads_queue: DashMap<String, (Download, bool)>,
     playtime_queue: DashSet<Playtime>,
 }
 
@@ -34,7 +95,7 @@ impl AnalyticsQueue {
         self.views_queue.insert(page_view);
     }
 
-    pub fn add_download(&self, download: Download) {
+    pub fn add_download(&self, download: Download, pack_driven: bool) {
         let ip_stripped = if let Some(ip) = download.ip.to_ipv4_mapped() {
             let octets = ip.octets();
             u64::from_be_bytes([0, 0, 0, 0, octets[0], octets[1], octets[2], octets[3]])
@@ -42,8 +103,10 @@ impl AnalyticsQueue {
             let octets = download.ip.octets();
             u64::from_be_bytes([0, 0, 0, 0, octets[0], octets[1], octets[2], octets[3]])
         };
-        self.downloads_queue
-            .insert(format!("{}-{}", ip_stripped, download.project_id), download);
+        self.downloads_queue.insert(
+            format!("{}-{}", ip_stripped, download.project_id),
+            (download, pack_driven),
+        );
     }
 
     pub fn add_playtime(&self, playtime: Playtime) {
@@ -134,17 +197,28 @@ impl AnalyticsQueue {
 
             let version_ids = raw_downloads
                 .iter()
-                .map(|x| x.version_id as i64)
+                .map(|x| x.0.version_id as i64)
                 .collect::<Vec<_>>();
             let project_ids = raw_downloads
                 .iter()
-                .map(|x| x.project_id as i64)
+                .map(|x| x.0.project_id as i64)
+                .collect::<Vec<_>>();
+            let today = Utc::now().date_naive();
+            let daily_downloads = raw_downloads
+                .iter()
+                .map(|x| DailyDownload {
+                    project_id: ProjectId(x.0.project_id as i64),
+                    version_id: VersionId(x.0.version_id as i64),
+                    date: today,
+                    downloads: 1,
+                    pack_downloads: x.1 as i32,
+                })
                 .collect::<Vec<_>>();
 
             let mut transaction = pool.begin().await?;
             let mut downloads = client.insert("downloads")?;
 
-            for (_, download) in raw_downloads {
+            for (_, (download, _)) in raw_downloads {
                 downloads.write(&download).await?;
             }
 
@@ -166,6 +240,8 @@ impl AnalyticsQueue {
             .execute(&mut *transaction)
             .await?;
 
+            DailyDownload::increment_many(&daily_downloads, &mut transaction).await?;
+
             transaction.commit().await?;
             downloads.end().await?;
         }
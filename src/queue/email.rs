@@ -0,0 +1,122 @@
+use crate::auth::email::render_email;
+use crate::database::models::notification_email_item::NotificationEmail;
+use crate::database::models::DatabaseError;
+use crate::mailer::{Email, Mailer};
+use crate::models::ids::NotificationId;
+use crate::models::notifications::NotificationBody;
+use log::warn;
+use sqlx::PgPool;
+
+// One initial attempt, followed by up to four retries on later runs
+const MAX_EMAIL_ATTEMPTS: i32 = 5;
+const EMAIL_BATCH_SIZE: i64 = 100;
+const MAX_ERROR_LENGTH: usize = 2048;
+// Emails whose sender stopped while sending them are sent again after this long
+const EMAIL_CLAIM_TIMEOUT_MINUTES: i32 = 15;
+
+/// Sends the emails queued for notifications. Failed emails stay queued and are retried on the
+/// following runs until they run out of attempts.
+///
+/// Emails are claimed before they're sent, and each is removed as soon as it's sent, so that
+/// no lock is held while talking to the mail server and sent emails aren't sent again if a
+/// later one fails.
+pub async fn send_queued_emails(
+    pool: &PgPool,
+    mailer: &(dyn Mailer + Send + Sync),
+) -> Result<(), DatabaseError> {
+    let emails = NotificationEmail::claim_pending(
+        MAX_EMAIL_ATTEMPTS,
+        EMAIL_BATCH_SIZE,
+        EMAIL_CLAIM_TIMEOUT_MINUTES,
+        pool,
+    )
+    .await?;
+
+    for email in emails {
+        let (subject, body) = match render_notification_email(&email.body) {
+            Some(rendered) => rendered,
+            None => {
+                NotificationEmail::remove(email.id, pool).await?;
+                continue;
+            }
+        };
+
+        let result = mailer
+            .send(Email {
+                to: email.email,
+                subject,
+                body,
+            })
+            .await;
+
+        match result {
+            Ok(()) => NotificationEmail::remove(email.id, pool).await?,
+            Err(err) => {
+                warn!(
+                    "Sending email for notification {} failed (attempt {} of {}): {}",
+                    NotificationId::from(email.notification_id),
+                    email.attempts + 1,
+                    MAX_EMAIL_ATTEMPTS,
+                    err
+                );
+
+                let error = err
+                    .to_string()
+                    .chars()
+                    .take(MAX_ERROR_LENGTH)
+                    .collect::<String>();
+                NotificationEmail::record_failure(email.id, &error, pool).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders the subject and HTML body of the email for a notification, if its type has one
+pub fn render_notification_email(body: &NotificationBody) -> Option<(String, String)> {
+    let site_url = dotenvy::var("SITE_URL").unwrap_or_default();
+
+    let (title, description, line_two, button) = match body {
+        NotificationBody::TeamInvite {
+            project_id, role, ..
+        } => (
+            "You have been invited to join a team",
+            format!("You have been invited to join the team of a project on Modrinth as {role}."),
+            "You can accept or decline the invite from your notifications on Modrinth.",
+            ("View project", format!("{site_url}/project/{project_id}")),
+        ),
+        NotificationBody::StatusChange {
+            project_id,
+            old_status,
+            new_status,
+        } => (
+            "Your project has been reviewed",
+            format!(
+                "A moderator has changed the status of your project from {} to {}.",
+                old_status.as_friendly_str(),
+                new_status.as_friendly_str()
+            ),
+            "Any messages from the moderators can be found in the project's moderation thread.",
+            ("View project", format!("{site_url}/project/{project_id}")),
+        ),
+        NotificationBody::ReportResolved { report_id, status } => (
+            "Your report has been closed",
+            format!(
+                "Thank you for your report. A moderator has reviewed it and marked it as {}.",
+                status.as_str()
+            ),
+            "Any response from the moderators can be found in the report's thread.",
+            (
+                "View report",
+                format!("{site_url}/dashboard/report/{report_id}"),
+            ),
+        ),
+        _ => return None,
+    };
+
+    Some((
+        title.to_string(),
+        render_email(title, &description, line_two, Some((button.0, &button.1))),
+    ))
+}
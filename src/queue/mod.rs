@@ -1,5 +1,7 @@
 pub mod analytics;
+pub mod email;
 pub mod maxmind;
 pub mod payouts;
 pub mod session;
 pub mod socket;
+pub mod webhooks;
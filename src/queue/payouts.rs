@@ -358,6 +358,7 @@ impl PayoutsQueue {
                 let method = PayoutMethod {
                     id: product.id,
                     type_: PayoutMethodType::Tremendous,
+                    required_fields: PayoutMethodType::Tremendous.required_fields(),
                     name: product.name.clone(),
                     supported_countries: product.countries.into_iter().map(|x| x.abbr).collect(),
                     image_url: product
@@ -434,6 +435,7 @@ impl PayoutsQueue {
                 let paypal_us = PayoutMethod {
                     id: "paypal_us".to_string(),
                     type_: PayoutMethodType::PayPal,
+                    required_fields: PayoutMethodType::PayPal.required_fields(),
                     name: "PayPal".to_string(),
                     supported_countries: vec!["US".to_string()],
                     image_url: None,
@@ -462,6 +464,7 @@ impl PayoutsQueue {
                 PayoutMethod {
                     id: "paypal_in".to_string(),
                     type_: PayoutMethodType::PayPal,
+                    required_fields: PayoutMethodType::PayPal.required_fields(),
                     name: "PayPal".to_string(),
                     supported_countries: rust_iso3166::ALL
                         .iter()
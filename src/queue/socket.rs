@@ -1,15 +1,19 @@
 //! "Database" for Hydra
+use crate::models::ids::UserId;
 use actix_ws::Session;
 use dashmap::DashMap;
 
 pub struct ActiveSockets {
     pub auth_sockets: DashMap<String, Session>,
+    /// How many thread sockets each user has open
+    pub thread_sockets: DashMap<UserId, usize>,
 }
 
 impl Default for ActiveSockets {
     fn default() -> Self {
         Self {
             auth_sockets: DashMap::new(),
+            thread_sockets: DashMap::new(),
         }
     }
 }
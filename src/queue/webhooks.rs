@@ -0,0 +1,351 @@
+use crate::database::models::webhook_item::{ProjectWebhook, WebhookDelivery};
+use crate::database::models::{DatabaseError, VersionId};
+use crate::database::redis::RedisPool;
+use crate::models::projects::Version;
+use chrono::Utc;
+use futures::StreamExt;
+use hmac::{Hmac, Mac, NewMac};
+use itertools::Itertools;
+use log::warn;
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use url::Url;
+
+pub const SIGNATURE_HEADER: &str = "X-Modrinth-Signature";
+
+// One initial attempt, followed by up to three retries
+const MAX_DELIVERY_ATTEMPTS: i32 = 4;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// How many due deliveries are claimed each time the queue is processed
+const DELIVERY_BATCH_SIZE: i64 = 100;
+/// How many deliveries are sent at the same time
+const DELIVERY_CONCURRENCY: usize = 10;
+/// How long claimed deliveries are left to the instance which claimed them. Batches take much
+/// less time than this, even if every delivery times out.
+const DELIVERY_LEASE_MINUTES: i64 = 5;
+
+pub struct WebhookQueue;
+
+impl Default for WebhookQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Outgoing project webhook deliveries are kept in the database until they succeed, and the due
+// ones are sent every 15 seconds
+impl WebhookQueue {
+    pub fn new() -> Self {
+        WebhookQueue
+    }
+
+    /// Queues deliveries of the versions to their projects' webhooks. This can be done in the
+    /// transaction creating them, as versions which aren't listed by the time they're delivered
+    /// are skipped.
+    pub async fn add_versions<'a, E>(&self, ids: &[VersionId], exec: E) -> Result<(), DatabaseError>
+    where
+        E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+    {
+        WebhookDelivery::insert_versions(ids, exec).await
+    }
+
+    pub async fn index(&self, pool: &PgPool, redis: &RedisPool) -> Result<(), DatabaseError> {
+        let lease_until = Utc::now() + chrono::Duration::minutes(DELIVERY_LEASE_MINUTES);
+        let deliveries = WebhookDelivery::claim_due(DELIVERY_BATCH_SIZE, lease_until, pool).await?;
+
+        if deliveries.is_empty() {
+            return Ok(());
+        }
+
+        let version_ids = deliveries
+            .iter()
+            .map(|x| x.version_id)
+            .unique()
+            .collect::<Vec<_>>();
+        let mut payloads = HashMap::new();
+        for version in crate::database::models::Version::get_many(&version_ids, pool, redis).await?
+        {
+            // The version may have been hidden again before it was delivered
+            if version.inner.status.is_listed() {
+                payloads.insert(
+                    version.inner.id,
+                    serde_json::to_vec(&Version::from(version))?,
+                );
+            }
+        }
+
+        futures::stream::iter(deliveries)
+            .map(|delivery| process_delivery(delivery, &payloads, pool))
+            .buffer_unordered(DELIVERY_CONCURRENCY)
+            .for_each(|result| async {
+                if let Err(err) = result {
+                    warn!("Processing a webhook delivery failed: {}", err);
+                }
+            })
+            .await;
+
+        Ok(())
+    }
+}
+
+// Sends a delivery, removing it once it succeeds or runs out of attempts. Failed attempts are
+// retried after 1, 2 and 4 minutes.
+async fn process_delivery(
+    delivery: WebhookDelivery,
+    payloads: &HashMap<VersionId, Vec<u8>>,
+    pool: &PgPool,
+) -> Result<(), DatabaseError> {
+    let (Some(payload), Some(webhook)) = (
+        payloads.get(&delivery.version_id),
+        ProjectWebhook::get(delivery.webhook_id, pool).await?,
+    ) else {
+        return WebhookDelivery::remove(delivery.id, pool).await;
+    };
+
+    let result = deliver(&webhook, payload).await;
+    let attempts = delivery.attempts + 1;
+    match &result {
+        Err(err) if attempts < MAX_DELIVERY_ATTEMPTS => {
+            warn!("Delivering webhook {} failed: {}", webhook.id.0, err);
+            let next_attempt = Utc::now() + chrono::Duration::minutes(1 << (attempts - 1));
+            WebhookDelivery::retry_at(delivery.id, next_attempt, pool).await?;
+        }
+        Err(err) => {
+            warn!(
+                "Delivering webhook {} failed for good: {}",
+                webhook.id.0, err
+            );
+            WebhookDelivery::remove(delivery.id, pool).await?;
+        }
+        Ok(()) => WebhookDelivery::remove(delivery.id, pool).await?,
+    }
+
+    ProjectWebhook::update_attempt(webhook.id, result.err(), pool).await
+}
+
+/// Computes the hex-encoded HMAC-SHA256 of `payload` keyed with the webhook's secret.
+/// This is sent in the `X-Modrinth-Signature` header so receivers can verify deliveries.
+pub fn sign_payload(secret: &str, payload: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take a key of any size");
+    mac.update(payload);
+
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Checks that a webhook URL is https and only resolves to public addresses, so webhooks can't
+/// be used to make requests to the servers' own network. Returns the parsed URL and the
+/// addresses it resolved to.
+pub async fn resolve_webhook_url(url: &str) -> Result<(Url, Vec<SocketAddr>), String> {
+    let url = Url::parse(url).map_err(|err| format!("Invalid webhook URL: {err}"))?;
+    if url.scheme() != "https" {
+        return Err("Webhook URLs must use https".to_string());
+    }
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Err("Webhook URLs must have a host".to_string());
+    };
+
+    // IPv6 hosts are bracketed in URLs, but not when they're resolved
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|err| format!("Couldn't resolve {host}: {err}"))?
+        .collect::<Vec<_>>();
+
+    if addrs.is_empty() {
+        return Err(format!("Couldn't resolve {host}"));
+    }
+    if addrs.iter().any(|x| !is_public_address(x.ip())) {
+        return Err(format!("{host} resolves to a private address"));
+    }
+
+    Ok((url, addrs))
+}
+
+/// Whether the address is reachable from the internet, rather than being on a private network
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // 0.0.0.0/8, the shared address space 100.64.0.0/10 and the reserved 240.0.0.0/4
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // Unique local fc00::/7 and link-local fe80::/10
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80)
+}
+
+// The host may resolve differently by the time of delivery, so it's checked again, and the
+// request is sent to the checked addresses. Redirects aren't followed, as they could lead
+// anywhere.
+async fn deliver(webhook: &ProjectWebhook, payload: &[u8]) -> Result<(), String> {
+    let (url, addrs) = resolve_webhook_url(&webhook.url).await?;
+    let client = reqwest::Client::builder()
+        .resolve_to_addrs(url.host_str().unwrap_or_default(), &addrs)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|err| err.to_string())?;
+
+    send(&client, url, &webhook.secret, payload).await
+}
+
+async fn send(
+    client: &reqwest::Client,
+    url: Url,
+    secret: &str,
+    payload: &[u8],
+) -> Result<(), String> {
+    let response = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, sign_payload(secret, payload))
+        .body(payload.to_vec())
+        .timeout(DELIVERY_TIMEOUT)
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Received status code {}",
+            response.status().as_u16()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // Accepts a single request and answers it with the status, returning the request as it was
+    // received
+    async fn receive_one(listener: TcpListener, status: &str) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        loop {
+            let read = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..read]);
+
+            let text = String::from_utf8_lossy(&request);
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .filter_map(|x| x.split_once(':'))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                    .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    break;
+                }
+            }
+            if read == 0 {
+                break;
+            }
+        }
+
+        stream
+            .write_all(format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\n\r\n").as_bytes())
+            .await
+            .unwrap();
+        String::from_utf8(request).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn deliveries_are_signed() {
+        let secret = "a-sufficiently-long-secret";
+        let payload = br#"{"id":"AABBCCDD"}"#;
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/hook", listener.local_addr().unwrap())).unwrap();
+        let receiver = actix_rt::spawn(receive_one(listener, "204 No Content"));
+
+        send(&client, url, secret, payload).await.unwrap();
+
+        let request = receiver.await.unwrap();
+        assert!(request.starts_with("POST /hook "));
+        assert!(request.to_lowercase().contains(&format!(
+            "{}: {}",
+            SIGNATURE_HEADER.to_lowercase(),
+            sign_payload(secret, payload)
+        )));
+        assert!(request.ends_with(std::str::from_utf8(payload).unwrap()));
+
+        // Receivers answering with an error fail the delivery
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/hook", listener.local_addr().unwrap())).unwrap();
+        let receiver = actix_rt::spawn(receive_one(listener, "500 Internal Server Error"));
+
+        assert_eq!(
+            send(&client, url, secret, payload).await,
+            Err("Received status code 500".to_string())
+        );
+        receiver.await.unwrap();
+    }
+
+    #[test]
+    fn sign_payload_matches_known_digest() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            sign_payload("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn private_addresses_are_not_public() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_address(ip.parse().unwrap()), "{ip}");
+        }
+
+        for ip in ["93.184.216.34", "1.1.1.1", "2606:4700:4700::1111"] {
+            assert!(is_public_address(ip.parse().unwrap()), "{ip}");
+        }
+    }
+}
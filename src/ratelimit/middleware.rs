@@ -1,260 +1,221 @@
+use crate::auth::validate::get_request_client;
+use crate::database::redis::RedisPool;
 use crate::ratelimit::errors::ARError;
-use crate::ratelimit::{ActorMessage, ActorResponse};
-use actix::dev::*;
+use crate::ratelimit::{RateLimits, RouteClass};
+use crate::util::env::parse_var;
+use crate::util::guards::ADMIN_KEY_HEADER;
 use actix_web::{
-    dev::{Service, ServiceRequest, ServiceResponse, Transform},
-    error::Error as AWError,
-    http::header::{HeaderName, HeaderValue},
-};
-use futures::future::{ok, Ready};
-use log::*;
-use std::{
-    cell::RefCell,
-    future::Future,
-    ops::Fn,
-    pin::Pin,
-    rc::Rc,
-    task::{Context, Poll},
-    time::Duration,
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderMap, HeaderName, HeaderValue},
+    web, Error as AWError, ResponseError,
 };
+use chrono::Utc;
+use futures::future::{ok, LocalBoxFuture, Ready};
+use log::{info, warn};
+use sqlx::PgPool;
+use std::rc::Rc;
 
-type RateLimiterIdentifier = Rc<Box<dyn Fn(&ServiceRequest) -> Result<String, ARError> + 'static>>;
+const RATE_LIMIT_NAMESPACE: &str = "rate_limits";
 
-pub struct RateLimiter<T>
-where
-    T: Handler<ActorMessage> + Send + Sync + 'static,
-    T::Context: ToEnvelope<T, ActorMessage>,
-{
-    interval: Duration,
-    max_requests: usize,
-    store: Addr<T>,
-    identifier: RateLimiterIdentifier,
-    ignore_key: Option<String>,
-}
+/// Limits how often each client can call each class of route with a sliding window kept in Redis.
+/// Authenticated clients are told apart by their user, so all of a user's sessions, PATs and OAuth
+/// tokens share one budget. Anonymous clients are told apart by their IP.
+/// The budgets are read from the `RateLimits` in the app data, and nothing is limited without it.
+pub struct RateLimiter;
 
-impl<T> RateLimiter<T>
+impl<S, B> Transform<S, ServiceRequest> for RateLimiter
 where
-    T: Handler<ActorMessage> + Send + Sync + 'static,
-    <T as Actor>::Context: ToEnvelope<T, ActorMessage>,
-{
-    /// Creates a new instance of `RateLimiter` with the provided address of `StoreActor`.
-    pub fn new(store: Addr<T>) -> Self {
-        let identifier = |req: &ServiceRequest| {
-            let connection_info = req.connection_info();
-            let ip = connection_info.peer_addr().ok_or(ARError::Identification)?;
-            Ok(String::from(ip))
-        };
-        RateLimiter {
-            interval: Duration::from_secs(0),
-            max_requests: 0,
-            store,
-            identifier: Rc::new(Box::new(identifier)),
-            ignore_key: None,
-        }
-    }
-
-    /// Specify the interval. The counter for a client is reset after this interval
-    pub fn with_interval(mut self, interval: Duration) -> Self {
-        self.interval = interval;
-        self
-    }
-
-    /// Specify the maximum number of requests allowed in the given interval.
-    pub fn with_max_requests(mut self, max_requests: usize) -> Self {
-        self.max_requests = max_requests;
-        self
-    }
-
-    /// Sets key which can be used to bypass rate-limiter
-    pub fn with_ignore_key(mut self, ignore_key: Option<String>) -> Self {
-        self.ignore_key = ignore_key;
-        self
-    }
-
-    /// Function to get the identifier for the client request
-    pub fn with_identifier<F: Fn(&ServiceRequest) -> Result<String, ARError> + 'static>(
-        mut self,
-        identifier: F,
-    ) -> Self {
-        self.identifier = Rc::new(Box::new(identifier));
-        self
-    }
-}
-
-impl<T, S, B> Transform<S, ServiceRequest> for RateLimiter<T>
-where
-    T: Handler<ActorMessage> + Send + Sync + 'static,
-    T::Context: ToEnvelope<T, ActorMessage>,
     S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = AWError> + 'static,
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
-    type Error = S::Error;
-    type Transform = RateLimitMiddleware<S, T>;
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = AWError;
+    type Transform = RateLimitMiddleware<S>;
     type InitError = ();
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
         ok(RateLimitMiddleware {
-            service: Rc::new(RefCell::new(service)),
-            store: self.store.clone(),
-            max_requests: self.max_requests,
-            interval: self.interval.as_secs(),
-            identifier: self.identifier.clone(),
-            ignore_key: self.ignore_key.clone(),
+            service: Rc::new(service),
         })
     }
 }
 
-/// Service factory for RateLimiter
-pub struct RateLimitMiddleware<S, T>
-where
-    S: 'static,
-    T: Handler<ActorMessage> + 'static,
-{
-    service: Rc<RefCell<S>>,
-    store: Addr<T>,
-    // Exists here for the sole purpose of knowing the max_requests and interval from RateLimiter
-    max_requests: usize,
-    interval: u64,
-    identifier: RateLimiterIdentifier,
-    ignore_key: Option<String>,
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
 }
 
-impl<T, S, B> Service<ServiceRequest> for RateLimitMiddleware<S, T>
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
 where
-    T: Handler<ActorMessage> + 'static,
     S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = AWError> + 'static,
     S::Future: 'static,
     B: 'static,
-    T::Context: ToEnvelope<T, ActorMessage>,
 {
-    type Response = ServiceResponse<B>;
-    type Error = S::Error;
-    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = AWError;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
 
-    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        self.service.borrow_mut().poll_ready(cx)
-    }
+    forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let store = self.store.clone();
         let srv = self.service.clone();
-        let max_requests = self.max_requests;
-        let interval = Duration::from_secs(self.interval);
-        let identifier = self.identifier.clone();
-        let ignore_key = self.ignore_key.clone();
-        Box::pin(async move {
-            let identifier: String = (identifier)(&req)?;
 
-            if let Some(ignore_key) = ignore_key {
-                if let Some(key) = req.headers().get("x-ratelimit-key") {
-                    if key.to_str().ok().unwrap_or_default() == &*ignore_key {
-                        let fut = srv.call(req);
-                        let res = fut.await?;
-                        return Ok(res);
-                    }
+        Box::pin(async move {
+            let window = match check_rate_limit(&req).await {
+                Ok(window) => window,
+                Err(err) => {
+                    let response = err.error_response();
+                    return Ok(req.into_response(response).map_into_right_body());
                 }
-            }
+            };
 
-            let remaining: ActorResponse = store
-                .send(ActorMessage::Get(String::from(&identifier)))
-                .await
-                .map_err(|_| ARError::Identification)?;
-            match remaining {
-                ActorResponse::Get(opt) => {
-                    let opt = opt.await?;
-                    if let Some(c) = opt {
-                        // Existing entry in store
-                        let expiry = store
-                            .send(ActorMessage::Expire(String::from(&identifier)))
-                            .await
-                            .map_err(|_| ARError::ReadWrite("Setting timeout".to_string()))?;
-                        let reset: Duration = match expiry {
-                            ActorResponse::Expire(dur) => dur.await?,
-                            _ => unreachable!(),
-                        };
-                        if c == 0 {
-                            info!("Limit exceeded for client: {}", &identifier);
-                            Err(ARError::Limited {
-                                max_requests,
-                                remaining: c,
-                                reset: reset.as_secs(),
-                            }
-                            .into())
-                        } else {
-                            // Decrement value
-                            let res: ActorResponse = store
-                                .send(ActorMessage::Update {
-                                    key: identifier,
-                                    value: 1,
-                                })
-                                .await
-                                .map_err(|_| {
-                                    ARError::ReadWrite("Decrementing ratelimit".to_string())
-                                })?;
-                            let updated_value: usize = match res {
-                                ActorResponse::Update(c) => c.await?,
-                                _ => unreachable!(),
-                            };
-                            // Execute the request
-                            let fut = srv.call(req);
-                            let mut res = fut.await?;
-                            let headers = res.headers_mut();
-                            // Safe unwraps, since usize is always convertible to string
-                            headers.insert(
-                                HeaderName::from_static("x-ratelimit-limit"),
-                                HeaderValue::from_str(max_requests.to_string().as_str())?,
-                            );
-                            headers.insert(
-                                HeaderName::from_static("x-ratelimit-remaining"),
-                                HeaderValue::from_str(updated_value.to_string().as_str())?,
-                            );
-                            headers.insert(
-                                HeaderName::from_static("x-ratelimit-reset"),
-                                HeaderValue::from_str(reset.as_secs().to_string().as_str())?,
-                            );
-                            Ok(res)
-                        }
-                    } else {
-                        // New client, create entry in store
-                        let current_value = max_requests - 1;
-                        let res = store
-                            .send(ActorMessage::Set {
-                                key: String::from(&identifier),
-                                value: current_value,
-                                expiry: interval,
-                            })
-                            .await
-                            .map_err(|_| ARError::ReadWrite("Creating store entry".to_string()))?;
-                        match res {
-                            ActorResponse::Set(c) => c.await?,
-                            _ => unreachable!(),
-                        }
-                        let fut = srv.call(req);
-                        let mut res = fut.await?;
-                        let headers = res.headers_mut();
-                        // Safe unwraps, since usize is always convertible to string
-                        headers.insert(
-                            HeaderName::from_static("x-ratelimit-limit"),
-                            HeaderValue::from_str(max_requests.to_string().as_str()).unwrap(),
-                        );
-                        headers.insert(
-                            HeaderName::from_static("x-ratelimit-remaining"),
-                            HeaderValue::from_str(current_value.to_string().as_str()).unwrap(),
-                        );
-                        headers.insert(
-                            HeaderName::from_static("x-ratelimit-reset"),
-                            HeaderValue::from_str(interval.as_secs().to_string().as_str()).unwrap(),
-                        );
-                        Ok(res)
-                    }
-                }
-                _ => {
-                    unreachable!();
-                }
+            let mut res = srv.call(req).await?;
+            if let Some(window) = window {
+                window.insert_headers(res.headers_mut());
             }
+            Ok(res.map_into_left_body())
         })
     }
 }
+
+/// The state of a client's window after a request was let through
+struct WindowState {
+    max_requests: usize,
+    remaining: usize,
+    reset: u64,
+}
+
+impl WindowState {
+    fn insert_headers(&self, headers: &mut HeaderMap) {
+        for (name, value) in [
+            ("x-ratelimit-limit", self.max_requests as u64),
+            ("x-ratelimit-remaining", self.remaining as u64),
+            ("x-ratelimit-reset", self.reset),
+        ] {
+            headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+        }
+    }
+}
+
+// Records the request in the client's window, erroring if the window is already full.
+// Requests which aren't limited return `None`.
+async fn check_rate_limit(req: &ServiceRequest) -> Result<Option<WindowState>, ARError> {
+    let (Some(limits), Some(pool), Some(redis)) = (
+        req.app_data::<web::Data<RateLimits>>(),
+        req.app_data::<web::Data<PgPool>>(),
+        req.app_data::<web::Data<RedisPool>>(),
+    ) else {
+        return Ok(None);
+    };
+
+    if is_exempt(req, limits) {
+        return Ok(None);
+    }
+
+    let class = RouteClass::of(req.path(), req.method());
+    if limits.budget(class, false).is_none() {
+        return Ok(None);
+    }
+
+    // Requests whose token can't be looked up are limited as anonymous ones, and the route
+    // rejects the token itself
+    let (client, elevated) = match get_request_client(req.request(), &**pool, redis).await {
+        Ok(Some(client)) => (format!("user:{}", client.user_id.0), client.role.is_mod()),
+        _ => (format!("ip:{}", client_ip(req)?), false),
+    };
+
+    let Some(max_requests) = limits.budget(class, elevated) else {
+        return Ok(None);
+    };
+
+    let id = format!("{}:{}", class.as_str(), client);
+    let window = limits.window.as_millis() as i64;
+    let now = Utc::now().timestamp_millis();
+    let hit = format!("{}-{}", now, rand::random::<u32>());
+
+    // If Redis can't be reached, requests are let through rather than all failing
+    let (count, oldest) = match record_hit(redis, &id, &hit, now, window).await {
+        Ok(x) => x,
+        Err(err) => {
+            warn!("Failed to check the rate limit of {}: {}", id, err);
+            return Ok(None);
+        }
+    };
+    let reset = ((oldest + window - now).max(0) as u64 + 999) / 1000;
+
+    if count > max_requests {
+        // Rejected requests don't count towards the limit
+        if let Err(err) = remove_hit(redis, &id, &hit).await {
+            warn!("Failed to remove a rejected request of {}: {}", id, err);
+        }
+
+        info!("Rate limit exceeded for client: {}", id);
+        return Err(ARError::Limited {
+            max_requests,
+            remaining: 0,
+            reset,
+        });
+    }
+
+    Ok(Some(WindowState {
+        max_requests,
+        remaining: max_requests - count,
+        reset,
+    }))
+}
+
+// Internal callers, like the CDN counting downloads with the admin key, aren't limited
+fn is_exempt(req: &ServiceRequest, limits: &RateLimits) -> bool {
+    let header_matches = |header: &str, key: Option<String>| {
+        key.map_or(false, |key| {
+            req.headers()
+                .get(header)
+                .map_or(false, |x| x.as_bytes() == key.as_bytes())
+        })
+    };
+
+    header_matches("x-ratelimit-key", limits.ignore_key.clone())
+        || header_matches(ADMIN_KEY_HEADER, dotenvy::var("LABRINTH_ADMIN_KEY").ok())
+}
+
+fn client_ip(req: &ServiceRequest) -> Result<String, ARError> {
+    let connection_info = req.connection_info();
+
+    if parse_var("CLOUDFLARE_INTEGRATION").unwrap_or(false) {
+        if let Some(header) = req.headers().get("CF-Connecting-IP") {
+            return header
+                .to_str()
+                .map(String::from)
+                .map_err(|_| ARError::Identification);
+        }
+    }
+
+    connection_info
+        .peer_addr()
+        .map(String::from)
+        .ok_or(ARError::Identification)
+}
+
+async fn record_hit(
+    redis: &RedisPool,
+    id: &str,
+    hit: &str,
+    now: i64,
+    window: i64,
+) -> Result<(usize, i64), crate::database::models::DatabaseError> {
+    let mut redis = redis.connect().await?;
+    redis
+        .add_window_hit(RATE_LIMIT_NAMESPACE, id, hit, now, window)
+        .await
+}
+
+async fn remove_hit(
+    redis: &RedisPool,
+    id: &str,
+    hit: &str,
+) -> Result<(), crate::database::models::DatabaseError> {
+    let mut redis = redis.connect().await?;
+    redis.remove_window_hit(RATE_LIMIT_NAMESPACE, id, hit).await
+}
@@ -1,64 +1,99 @@
-use std::future::Future;
-use std::marker::Send;
-use std::pin::Pin;
 use std::time::Duration;
 
-use crate::ratelimit::errors::ARError;
-use actix::dev::*;
+use actix_web::http::Method;
+
+use crate::util::env::parse_var;
 
 pub mod errors;
-pub mod memory;
-/// The code for this module was directly taken from https://github.com/TerminalWitchcraft/actix-ratelimit
-/// with some modifications including upgrading it to Actix 4!
 pub mod middleware;
 
-/// Represents message that can be handled by a `StoreActor`
-pub enum ActorMessage {
-    /// Get the remaining count based on the provided identifier
-    Get(String),
-    /// Set the count of the client identified by `key` to `value` valid for `expiry`
-    Set {
-        key: String,
-        value: usize,
-        expiry: Duration,
-    },
-    /// Change the value of count for the client identified by `key` by `value`
-    Update { key: String, value: usize },
-    /// Get the expiration time for the client.
-    Expire(String),
-    /// Remove the client from the store
-    Remove(String),
+/// The classes of routes which each have their own rate limit budget
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RouteClass {
+    Read,
+    Search,
+    Write,
+    Auth,
 }
 
-impl Message for ActorMessage {
-    type Result = ActorResponse;
-}
+impl RouteClass {
+    /// Classifies a request by its path, such as `/v3/search`, and its method
+    pub fn of(path: &str, method: &Method) -> RouteClass {
+        let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
 
-/// Wrapper type for `Pin<Box<dyn Future>>` type
-pub type Output<T> = Pin<Box<dyn Future<Output = Result<T, ARError>> + Send>>;
+        match segments.as_slice() {
+            [_, "auth", ..] => RouteClass::Auth,
+            [_, "search"] => RouteClass::Search,
+            _ if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) => {
+                RouteClass::Read
+            }
+            _ => RouteClass::Write,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteClass::Read => "read",
+            RouteClass::Search => "search",
+            RouteClass::Write => "write",
+            RouteClass::Auth => "auth",
+        }
+    }
+}
 
-/// Represents data returned in response to `Messages` by a `StoreActor`
-pub enum ActorResponse {
-    /// Returned in response to [Messages::Get](enum.Messages.html)
-    Get(Output<Option<usize>>),
-    /// Returned in response to [Messages::Set](enum.Messages.html)
-    Set(Output<()>),
-    /// Returned in response to [Messages::Update](enum.Messages.html)
-    Update(Output<usize>),
-    /// Returned in response to [Messages::Expire](enum.Messages.html)
-    Expire(Output<Duration>),
-    /// Returned in response to [Messages::Remove](enum.Messages.html)
-    Remove(Output<usize>),
+/// How many requests each client can make to each class of route within the window.
+/// Classes without a budget aren't limited.
+#[derive(Clone, Debug)]
+pub struct RateLimits {
+    pub window: Duration,
+    pub read: Option<usize>,
+    pub search: Option<usize>,
+    pub write: Option<usize>,
+    pub auth: Option<usize>,
+    /// How many times larger the budgets of moderators and admins are
+    pub elevated_multiplier: usize,
+    /// Requests carrying this key in the `x-ratelimit-key` header aren't limited
+    pub ignore_key: Option<String>,
 }
 
-impl<A, M> MessageResponse<A, M> for ActorResponse
-where
-    A: Actor,
-    M: actix::Message<Result = ActorResponse>,
-{
-    fn handle(self, _: &mut A::Context, tx: Option<OneshotSender<Self>>) {
-        if let Some(tx) = tx {
-            let _ = tx.send(self);
+impl RateLimits {
+    pub fn from_env() -> Self {
+        RateLimits {
+            window: Duration::from_secs(60),
+            read: Some(parse_var("RATE_LIMIT_READ").unwrap_or(600)),
+            search: Some(parse_var("RATE_LIMIT_SEARCH").unwrap_or(300)),
+            write: Some(parse_var("RATE_LIMIT_WRITE").unwrap_or(60)),
+            auth: Some(parse_var("RATE_LIMIT_AUTH").unwrap_or(10)),
+            elevated_multiplier: parse_var("RATE_LIMIT_ELEVATED_MULTIPLIER").unwrap_or(10),
+            ignore_key: dotenvy::var("RATE_LIMIT_IGNORE_KEY").ok(),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        RateLimits {
+            window: Duration::from_secs(60),
+            read: None,
+            search: None,
+            write: None,
+            auth: None,
+            elevated_multiplier: 1,
+            ignore_key: None,
         }
     }
+
+    /// The number of requests a client can make to a class of route within the window
+    pub fn budget(&self, class: RouteClass, elevated: bool) -> Option<usize> {
+        let budget = match class {
+            RouteClass::Read => self.read,
+            RouteClass::Search => self.search,
+            RouteClass::Write => self.write,
+            RouteClass::Auth => self.auth,
+        }?;
+
+        Some(if elevated {
+            budget.saturating_mul(self.elevated_multiplier)
+        } else {
+            budget
+        })
+    }
 }
@@ -1,9 +1,12 @@
 use crate::auth::validate::get_user_record_from_bearer_token;
+use crate::database::cache_warming::CacheWarmer;
+use crate::database::models::daily_download_item::DailyDownload;
+use crate::database::models::{ProjectId as DBProjectId, VersionId as DBVersionId};
 use crate::database::redis::RedisPool;
 use crate::models::analytics::Download;
 use crate::models::ids::ProjectId;
 use crate::models::pats::Scopes;
-use crate::queue::analytics::AnalyticsQueue;
+use crate::queue::analytics::{AnalyticsQueue, DownloadContext};
 use crate::queue::maxmind::MaxMindIndexer;
 use crate::queue::session::AuthQueue;
 use crate::routes::ApiError;
@@ -11,6 +14,7 @@ use crate::search::SearchConfig;
 use crate::util::date::get_current_tenths_of_ms;
 use crate::util::guards::admin_key_guard;
 use actix_web::{patch, post, web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use sqlx::PgPool;
 use std::collections::HashMap;
@@ -21,7 +25,9 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("admin")
             .service(count_download)
-            .service(force_reindex),
+            .service(force_reindex)
+            .service(warm_cache)
+            .service(backfill_daily_downloads),
     );
 }
 
@@ -64,13 +70,20 @@ pub async fn count_download(
         .ok()
         .map(|x| x as i64);
 
+    // The query can carry a download context, so files are matched without it
+    let file_url = download_body
+        .url
+        .split_once('?')
+        .map(|x| x.0)
+        .unwrap_or(&download_body.url);
+
     let (version_id, project_id) = if let Some(version) = sqlx::query!(
         "
             SELECT v.id id, v.mod_id mod_id FROM files f
             INNER JOIN versions v ON v.id = f.version_id
             WHERE f.url = $1
             ",
-        download_body.url,
+        file_url,
     )
     .fetch_optional(pool.as_ref())
     .await?
@@ -98,38 +111,50 @@ pub async fn count_download(
     let url = url::Url::parse(&download_body.url)
         .map_err(|_| ApiError::InvalidInput("invalid download URL specified!".to_string()))?;
 
+    let pack_driven =
+        if let Some((_, token)) = url.query_pairs().find(|(key, _)| key == "download_context") {
+            DownloadContext::is_pack_driven(&token, DBProjectId(project_id), &redis).await?
+        } else {
+            false
+        };
+
     let ip = crate::routes::analytics::convert_to_ip_v6(&download_body.ip)
         .unwrap_or_else(|_| Ipv4Addr::new(127, 0, 0, 1).to_ipv6_mapped());
 
-    analytics_queue.add_download(Download {
-        recorded: get_current_tenths_of_ms(),
-        domain: url.host_str().unwrap_or_default().to_string(),
-        site_path: url.path().to_string(),
-        user_id: user
-            .and_then(|(scopes, x)| {
-                if scopes.contains(Scopes::PERFORM_ANALYTICS) {
-                    Some(x.id.0 as u64)
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(0),
-        project_id: project_id as u64,
-        version_id: version_id as u64,
-        ip,
-        country: maxmind.query(ip).await.unwrap_or_default(),
-        user_agent: download_body
-            .headers
-            .get("user-agent")
-            .cloned()
-            .unwrap_or_default(),
-        headers: download_body
-            .headers
-            .clone()
-            .into_iter()
-            .filter(|x| !crate::routes::analytics::FILTERED_HEADERS.contains(&&*x.0.to_lowercase()))
-            .collect(),
-    });
+    analytics_queue.add_download(
+        Download {
+            recorded: get_current_tenths_of_ms(),
+            domain: url.host_str().unwrap_or_default().to_string(),
+            site_path: url.path().to_string(),
+            user_id: user
+                .and_then(|(scopes, x)| {
+                    if scopes.contains(Scopes::PERFORM_ANALYTICS) {
+                        Some(x.id.0 as u64)
+                    } else {
+                        None
+                    }
+                })
+                .unwrap_or(0),
+            project_id: project_id as u64,
+            version_id: version_id as u64,
+            ip,
+            country: maxmind.query(ip).await.unwrap_or_default(),
+            user_agent: download_body
+                .headers
+                .get("user-agent")
+                .cloned()
+                .unwrap_or_default(),
+            headers: download_body
+                .headers
+                .clone()
+                .into_iter()
+                .filter(|x| {
+                    !crate::routes::analytics::FILTERED_HEADERS.contains(&&*x.0.to_lowercase())
+                })
+                .collect(),
+        },
+        pack_driven,
+    );
 
     Ok(HttpResponse::NoContent().body(""))
 }
@@ -140,8 +165,77 @@ pub async fn force_reindex(
     redis: web::Data<RedisPool>,
     config: web::Data<SearchConfig>,
 ) -> Result<HttpResponse, ApiError> {
-    use crate::search::indexing::index_projects;
+    use crate::search::indexing::{
+        index_organizations, index_projects, index_users, prune_documents,
+    };
     let redis = redis.get_ref();
     index_projects(pool.as_ref().clone(), redis.clone(), &config).await?;
+    index_users(&pool, &config).await?;
+    index_organizations(&pool, &config).await?;
+    prune_documents(&pool, redis, &config).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Deserialize)]
+pub struct WarmCacheQuery {
+    /// How many of the most downloaded projects to warm
+    pub projects: Option<i64>,
+    pub concurrency: Option<usize>,
+}
+
+// Warms the caches of the most downloaded projects, such as after Redis was flushed
+#[post("/_warm-cache", guard = "admin_key_guard")]
+pub async fn warm_cache(
+    web::Query(query): web::Query<WarmCacheQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let default = CacheWarmer::from_env();
+    let warmer = CacheWarmer {
+        projects: query.projects.unwrap_or(default.projects),
+        concurrency: query.concurrency.unwrap_or(default.concurrency),
+    };
+
+    let warmed = warmer.warm(&pool, &redis).await?;
+    Ok(HttpResponse::Ok().json(warmed))
+}
+
+#[derive(Deserialize)]
+pub struct BackfillDownloads {
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+}
+
+// Rebuilds the daily downloads between the dates from the raw downloads in clickhouse
+#[post("/_backfill-daily-downloads", guard = "admin_key_guard")]
+pub async fn backfill_daily_downloads(
+    pool: web::Data<PgPool>,
+    clickhouse: web::Data<clickhouse::Client>,
+    range: web::Json<BackfillDownloads>,
+) -> Result<HttpResponse, ApiError> {
+    let downloads = crate::clickhouse::fetch_daily_downloads(
+        range.start_date,
+        range.end_date,
+        clickhouse.into_inner(),
+    )
+    .await?
+    .into_iter()
+    .filter_map(|x| {
+        Some(DailyDownload {
+            project_id: DBProjectId(x.project_id as i64),
+            version_id: DBVersionId(x.version_id as i64),
+            date: DateTime::from_timestamp(x.time as i64, 0)?.date_naive(),
+            downloads: x.total as i32,
+            pack_downloads: 0,
+        })
+    })
+    .collect::<Vec<_>>();
+
+    let mut transaction = pool.begin().await?;
+    for chunk in downloads.chunks(10000) {
+        DailyDownload::upsert_many(chunk, &mut transaction).await?;
+    }
+    transaction.commit().await?;
+
     Ok(HttpResponse::NoContent().finish())
 }
@@ -2,8 +2,12 @@ use crate::auth::email::send_email;
 use crate::auth::validate::get_user_record_from_bearer_token;
 use crate::auth::{get_user_from_headers, AuthProvider, AuthenticationError};
 use crate::database::models::flow_item::Flow;
+use crate::database::models::generate_user_connection_id;
+use crate::database::models::team_item::TeamMember;
+use crate::database::models::user_connection_item::UserConnection;
 use crate::database::redis::RedisPool;
 use crate::file_hosting::FileHost;
+use crate::models::connections::ConnectionProvider;
 use crate::models::ids::base62_impl::{parse_base62, to_base62};
 use crate::models::ids::random_base62_rng;
 use crate::models::pats::Scopes;
@@ -217,8 +221,10 @@ impl TempUser {
                     None
                 },
                 venmo_handle: None,
+                payout_method_masked: None,
+                hide_mature_content: true,
                 totp_secret: None,
-                username,
+                username: username.clone(),
                 name: self.name,
                 email: self.email,
                 email_verified: true,
@@ -228,9 +234,12 @@ impl TempUser {
                 role: Role::Developer.to_string(),
                 badges: Badges::default(),
                 balance: Decimal::ZERO,
+                connections: Vec::new(),
             }
             .insert(transaction)
             .await?;
+            crate::database::models::User::clear_caches(&[(user_id, Some(username))], redis)
+                .await?;
 
             Ok(user_id)
         } else {
@@ -1029,6 +1038,19 @@ pub struct Authorization {
     pub state: String,
 }
 
+/// Checks that users can be sent back to the URL once they finish authenticating
+pub fn check_callback_url(url: &str) -> Result<(), AuthenticationError> {
+    let url = url::Url::parse(url).map_err(|_| AuthenticationError::Url)?;
+
+    let allowed_callback_urls = parse_strings_from_var("ALLOWED_CALLBACK_URLS").unwrap_or_default();
+    let domain = url.host_str().ok_or(AuthenticationError::Url)?;
+    if !allowed_callback_urls.iter().any(|x| domain.ends_with(x)) && domain != "modrinth.com" {
+        return Err(AuthenticationError::Url);
+    }
+
+    Ok(())
+}
+
 // Init link takes us to GitHub API and calls back to callback endpoint with a code and state
 // http://localhost:8000/auth/init?url=https://modrinth.com
 #[get("init")]
@@ -1039,13 +1061,7 @@ pub async fn init(
     redis: Data<RedisPool>,
     session_queue: Data<AuthQueue>,
 ) -> Result<HttpResponse, AuthenticationError> {
-    let url = url::Url::parse(&info.url).map_err(|_| AuthenticationError::Url)?;
-
-    let allowed_callback_urls = parse_strings_from_var("ALLOWED_CALLBACK_URLS").unwrap_or_default();
-    let domain = url.host_str().ok_or(AuthenticationError::Url)?;
-    if !allowed_callback_urls.iter().any(|x| domain.ends_with(x)) && domain != "modrinth.com" {
-        return Err(AuthenticationError::Url);
-    }
+    check_callback_url(&info.url)?;
 
     let user_id = if let Some(token) = info.token {
         let (_, user) = get_user_record_from_bearer_token(
@@ -1315,6 +1331,47 @@ pub async fn auth_callback(
                     }.render());
                 }
             }
+        } else if let Some(Flow::LinkConnection {
+            user_id,
+            url,
+            provider,
+        }) = flow
+        {
+            Flow::remove(&state, &redis).await?;
+
+            let connection_provider = ConnectionProvider::from_auth_provider(provider)
+                .ok_or_else(|| AuthenticationError::InvalidAuthMethod)?;
+
+            let token = provider.get_token(query).await?;
+            let oauth_user = provider.get_user(&token).await?;
+
+            // An account can only be linked to one user at a time
+            if UserConnection::get_verified_user(connection_provider, &oauth_user.id, &**client)
+                .await?
+                .is_some_and(|x| x != user_id)
+            {
+                return Err(AuthenticationError::DuplicateConnection);
+            }
+
+            let mut transaction = client.begin().await?;
+            UserConnection {
+                id: generate_user_connection_id(&mut transaction).await?,
+                user_id,
+                provider: connection_provider,
+                external_id: oauth_user.id,
+                username: oauth_user.username,
+                verified: true,
+                created: Utc::now(),
+                verified_at: Some(Utc::now()),
+            }
+            .insert(&mut transaction)
+            .await?;
+            transaction.commit().await?;
+            crate::database::models::User::clear_caches(&[(user_id, None)], &redis).await?;
+
+            Ok(HttpResponse::TemporaryRedirect()
+                .append_header(("Location", &*url))
+                .json(serde_json::json!({ "url": url })))
         } else {
             Err::<HttpResponse, AuthenticationError>(AuthenticationError::InvalidCredentials)
         }
@@ -1523,9 +1580,11 @@ pub async fn create_account_with_password(
         paypal_country: None,
         paypal_email: None,
         venmo_handle: None,
+        payout_method_masked: None,
+        hide_mature_content: true,
         totp_secret: None,
         username: new_account.username.clone(),
-        name: Some(new_account.username),
+        name: Some(new_account.username.clone()),
         email: Some(new_account.email.clone()),
         email_verified: false,
         avatar_url: None,
@@ -1534,9 +1593,12 @@ pub async fn create_account_with_password(
         role: Role::Developer.to_string(),
         badges: Badges::default(),
         balance: Decimal::ZERO,
+        connections: Vec::new(),
     }
     .insert(&mut transaction)
     .await?;
+    crate::database::models::User::clear_caches(&[(user_id, Some(new_account.username))], &redis)
+        .await?;
 
     let session = issue_session(req, user_id, &mut transaction, &redis).await?;
     let res = crate::models::sessions::Session::from(session, true, None);
@@ -1864,8 +1926,14 @@ pub async fn finish_2fa_flow(
             )?;
         }
 
+        let restricted_teams =
+            TeamMember::clear_two_factor_restrictions(user_id, &mut transaction).await?;
+
         transaction.commit().await?;
         crate::database::models::User::clear_caches(&[(user.id.into(), None)], &redis).await?;
+        for team_id in restricted_teams {
+            TeamMember::clear_cache(team_id, &redis).await?;
+        }
 
         Ok(HttpResponse::Ok().json(serde_json::json!({
             "backup_codes": codes,
@@ -5,12 +5,20 @@ pub mod session;
 
 use super::v3::oauth_clients;
 pub use super::ApiError;
+use crate::metrics::RequestMetrics;
+use crate::ratelimit::middleware::RateLimiter;
+use crate::util::compress::Compression;
 use crate::util::cors::default_cors;
+use crate::util::maintenance::ReadOnlyMode;
 
 pub fn config(cfg: &mut actix_web::web::ServiceConfig) {
     cfg.service(
         actix_web::web::scope("_internal")
+            .wrap(Compression::from_env())
+            .wrap(RateLimiter)
+            .wrap(ReadOnlyMode)
             .wrap(default_cors())
+            .wrap(RequestMetrics)
             .configure(admin::config)
             // TODO: write tests that catch these
             .configure(oauth_clients::config)
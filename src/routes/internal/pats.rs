@@ -8,11 +8,13 @@ use crate::database::redis::RedisPool;
 use actix_web::web::{self, Data};
 use actix_web::{delete, get, patch, post, HttpRequest, HttpResponse};
 use chrono::{DateTime, Utc};
+use itertools::Itertools;
 use rand::distributions::Alphanumeric;
 use rand::Rng;
 use rand_chacha::rand_core::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 
+use crate::models::ids::ProjectId;
 use crate::models::pats::{PersonalAccessToken, Scopes};
 use crate::queue::session::AuthQueue;
 use crate::util::validate::validation_errors_to_string;
@@ -61,12 +63,66 @@ pub async fn get_pats(
     ))
 }
 
+#[get("pat/{id}/usage")]
+pub async fn get_pat_usage(
+    req: HttpRequest,
+    id: web::Path<(String,)>,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    session_queue: Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PAT_READ]),
+    )
+    .await?
+    .1;
+
+    let id = id.into_inner().0;
+    let pat = database::models::pat_item::PersonalAccessToken::get(&id, &**pool, &redis)
+        .await?
+        .filter(|x| x.user_id == user.id.into())
+        .ok_or(ApiError::NotFound)?;
+
+    let usage = database::models::pat_item::PersonalAccessToken::get_usage(pat.id, &redis).await?;
+
+    Ok(HttpResponse::Ok().json(usage))
+}
+
 #[derive(Deserialize, Validate)]
 pub struct NewPersonalAccessToken {
     pub scopes: Scopes,
     #[validate(length(min = 3, max = 255))]
     pub name: String,
     pub expires: DateTime<Utc>,
+    /// The projects the token is restricted to. Left out, the token can act on every project.
+    #[serde(default)]
+    pub project_ids: Option<Vec<ProjectId>>,
+}
+
+// Checks that every project a token is being restricted to exists
+async fn validate_pat_projects(
+    project_ids: &[ProjectId],
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<Vec<database::models::ProjectId>, ApiError> {
+    let project_ids = project_ids
+        .iter()
+        .map(|x| database::models::ProjectId::from(*x))
+        .unique()
+        .collect::<Vec<_>>();
+
+    let projects = database::models::Project::get_many_ids(&project_ids, pool, redis).await?;
+    if projects.len() != project_ids.len() {
+        return Err(ApiError::InvalidInput(
+            "Invalid project id specified!".to_string(),
+        ));
+    }
+
+    Ok(project_ids)
 }
 
 #[post("pat")]
@@ -102,6 +158,11 @@ pub async fn create_pat(
     .await?
     .1;
 
+    let project_ids = match &info.project_ids {
+        Some(project_ids) => Some(validate_pat_projects(project_ids, &pool, &redis).await?),
+        None => None,
+    };
+
     let mut transaction = pool.begin().await?;
 
     let id = generate_pat_id(&mut transaction).await?;
@@ -123,6 +184,7 @@ pub async fn create_pat(
         created: Utc::now(),
         expires: info.expires,
         last_used: None,
+        project_ids: project_ids.clone(),
     }
     .insert(&mut transaction)
     .await?;
@@ -143,6 +205,7 @@ pub async fn create_pat(
         created: Utc::now(),
         expires: info.expires,
         last_used: None,
+        project_ids: project_ids.map(|x| x.into_iter().map(|x| x.into()).collect()),
     }))
 }
 
@@ -152,6 +215,8 @@ pub struct ModifyPersonalAccessToken {
     #[validate(length(min = 3, max = 255))]
     pub name: Option<String>,
     pub expires: Option<DateTime<Utc>>,
+    /// Restricts the token to these projects. Tokens can only ever be narrowed to fewer projects.
+    pub project_ids: Option<Vec<ProjectId>>,
 }
 
 #[patch("pat/{id}")]
@@ -186,6 +251,11 @@ pub async fn edit_pat(
                         "Invalid scopes requested!".to_string(),
                     ));
                 }
+                if !pat.scopes.contains(*scopes) {
+                    return Err(ApiError::InvalidInput(
+                        "The scopes of a PAT can only be narrowed!".to_string(),
+                    ));
+                }
 
                 sqlx::query!(
                     "
@@ -212,6 +282,36 @@ pub async fn edit_pat(
                 .execute(&mut *transaction)
                 .await?;
             }
+            if let Some(project_ids) = &info.project_ids {
+                let project_ids = match &pat.project_ids {
+                    Some(current) => {
+                        let project_ids = project_ids
+                            .iter()
+                            .map(|x| database::models::ProjectId::from(*x))
+                            .unique()
+                            .collect::<Vec<_>>();
+                        if project_ids.iter().any(|x| !current.contains(x)) {
+                            return Err(ApiError::InvalidInput(
+                                "The projects of a PAT can only be narrowed!".to_string(),
+                            ));
+                        }
+                        project_ids
+                    }
+                    None => validate_pat_projects(project_ids, &pool, &redis).await?,
+                };
+
+                sqlx::query!(
+                    "
+                    UPDATE pats
+                    SET project_ids = $1
+                    WHERE id = $2
+                    ",
+                    &project_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+                    pat.id.0
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
             if let Some(expires) = &info.expires {
                 if expires < &Utc::now() {
                     return Err(ApiError::InvalidInput(
@@ -171,36 +171,7 @@ pub async fn delete(
     redis: Data<RedisPool>,
     session_queue: Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
-    let current_user = get_user_from_headers(
-        &req,
-        &**pool,
-        &redis,
-        &session_queue,
-        Some(&[Scopes::SESSION_DELETE]),
-    )
-    .await?
-    .1;
-
-    let session = DBSession::get(info.into_inner().0, &**pool, &redis).await?;
-
-    if let Some(session) = session {
-        if session.user_id == current_user.id.into() {
-            let mut transaction = pool.begin().await?;
-            DBSession::remove(session.id, &mut transaction).await?;
-            transaction.commit().await?;
-            DBSession::clear_cache(
-                vec![(
-                    Some(session.id),
-                    Some(session.session),
-                    Some(session.user_id),
-                )],
-                &redis,
-            )
-            .await?;
-        }
-    }
-
-    Ok(HttpResponse::NoContent().body(""))
+    crate::routes::v3::sessions::session_delete(req, info, pool, redis, session_queue).await
 }
 
 #[post("refresh")]
@@ -231,7 +231,9 @@ fn find_file<'a>(
     version: &'a QueryVersion,
     file: &str,
 ) -> Option<&'a QueryFile> {
-    if let Some(selected_file) = version.files.iter().find(|x| x.filename == file) {
+    // Files taken down by moderators aren't served
+    let files = version.files.iter().filter(|x| x.withheld_at.is_none());
+    if let Some(selected_file) = files.clone().find(|x| x.filename == file) {
         return Some(selected_file);
     }
 
@@ -248,11 +250,10 @@ fn find_file<'a>(
 
     for fileext in fileexts {
         if file == format!("{}-{}.{}", &project_id, &vcoords, fileext) {
-            return version
-                .files
-                .iter()
+            return files
+                .clone()
                 .find(|x| x.primary)
-                .or_else(|| version.files.iter().last());
+                .or_else(|| files.clone().last());
         }
     }
     None
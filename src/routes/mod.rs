@@ -125,6 +125,14 @@ pub enum ApiError {
     Reroute(#[from] reqwest::Error),
     #[error("Resource not found")]
     NotFound,
+    #[error("{0}")]
+    Gone(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    UnavailableForLegalReasons(String),
 }
 
 impl actix_web::ResponseError for ApiError {
@@ -153,6 +161,10 @@ impl actix_web::ResponseError for ApiError {
             ApiError::Mail(..) => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::Reroute(..) => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::Gone(..) => StatusCode::GONE,
+            ApiError::Conflict(..) => StatusCode::CONFLICT,
+            ApiError::Forbidden(..) => StatusCode::FORBIDDEN,
+            ApiError::UnavailableForLegalReasons(..) => StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
         }
     }
 
@@ -182,6 +194,10 @@ impl actix_web::ResponseError for ApiError {
                 ApiError::Clickhouse(..) => "clickhouse_error",
                 ApiError::Reroute(..) => "reroute_error",
                 ApiError::NotFound => "not_found",
+                ApiError::Gone(..) => "gone",
+                ApiError::Conflict(..) => "conflict",
+                ApiError::Forbidden(..) => "forbidden",
+                ApiError::UnavailableForLegalReasons(..) => "unavailable_for_legal_reasons",
             },
             description: &self.to_string(),
         })
@@ -13,12 +13,20 @@ pub mod version_file;
 mod versions;
 
 pub use super::ApiError;
+use crate::metrics::RequestMetrics;
+use crate::ratelimit::middleware::RateLimiter;
+use crate::util::compress::Compression;
 use crate::util::cors::default_cors;
+use crate::util::maintenance::ReadOnlyMode;
 
 pub fn config(cfg: &mut actix_web::web::ServiceConfig) {
     cfg.service(
         actix_web::web::scope("v2")
+            .wrap(Compression::from_env())
+            .wrap(RateLimiter)
+            .wrap(ReadOnlyMode)
             .wrap(default_cors())
+            .wrap(RequestMetrics)
             .configure(super::internal::admin::config)
             // Todo: separate these- they need to also follow v2-v3 conversion
             .configure(super::internal::session::config)
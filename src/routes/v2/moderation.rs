@@ -1,5 +1,4 @@
 use super::ApiError;
-use crate::models::projects::Project;
 use crate::models::v2::projects::LegacyProject;
 use crate::queue::session::AuthQueue;
 use crate::routes::v3;
@@ -34,16 +33,24 @@ pub async fn get_projects(
         req,
         pool.clone(),
         redis.clone(),
-        web::Query(v3::moderation::ResultCount { count: count.count }),
+        web::Query(v3::moderation::ModerationQueueQuery {
+            sort: Default::default(),
+            project_type: None,
+            count: count.count,
+            offset: 0,
+            // V2 doesn't support exports, so this is JSON even if CSV is accepted
+            format: Some("json".to_string()),
+        }),
         session_queue,
     )
     .await
     .or_else(v2_reroute::flatten_404_error)?;
 
     // Convert to V2 projects
-    match v2_reroute::extract_ok_json::<Vec<Project>>(response).await {
-        Ok(project) => {
-            let legacy_projects = LegacyProject::from_many(project, &**pool, &redis).await?;
+    match v2_reroute::extract_ok_json::<v3::moderation::ModerationQueue>(response).await {
+        Ok(queue) => {
+            let projects = queue.projects.into_iter().map(|x| x.project).collect();
+            let legacy_projects = LegacyProject::from_many(projects, &**pool, &redis).await?;
             Ok(HttpResponse::Ok().json(legacy_projects))
         }
         Err(response) => Ok(response),
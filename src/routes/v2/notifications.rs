@@ -136,7 +136,11 @@ pub async fn notifications_delete(
     // Returns NoContent, so no need to convert
     v3::notifications::notifications_delete(
         req,
-        web::Query(v3::notifications::NotificationIds { ids: ids.ids }),
+        web::Query(v3::notifications::NotificationsDeleteQuery {
+            ids: Some(ids.ids),
+            notification_type: None,
+            before: None,
+        }),
         pool,
         redis,
         session_queue,
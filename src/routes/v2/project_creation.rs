@@ -1,6 +1,7 @@
 use crate::database::models::version_item;
 use crate::database::redis::RedisPool;
 use crate::file_hosting::FileHost;
+use crate::file_scanning::FileScanner;
 use crate::models;
 use crate::models::ids::ImageId;
 use crate::models::projects::{Loader, Project, ProjectStatus};
@@ -140,6 +141,7 @@ pub async fn project_create(
     client: Data<PgPool>,
     redis: Data<RedisPool>,
     file_host: Data<Arc<dyn FileHost + Send + Sync>>,
+    scanner: Data<Arc<dyn FileScanner + Send + Sync>>,
     session_queue: Data<AuthQueue>,
 ) -> Result<HttpResponse, CreateError> {
     // Convert V2 multipart payload to V3 multipart payload
@@ -188,6 +190,9 @@ pub async fn project_create(
                         file_types: v.file_types,
                         uploaded_images: v.uploaded_images,
                         ordering: v.ordering,
+                        publish_at: None,
+                        infer_dependencies: v.infer_dependencies,
+                        allow_incompatible: v.allow_incompatible,
                         fields,
                     }
                 })
@@ -208,7 +213,7 @@ pub async fn project_create(
             }
             if let Some(donation_urls) = legacy_create.donation_urls {
                 for donation_url in donation_urls {
-                    link_urls.insert(donation_url.platform, donation_url.url);
+                    link_urls.insert(donation_url.link_name(), donation_url.url);
                 }
             }
 
@@ -217,6 +222,7 @@ pub async fn project_create(
                 slug: legacy_create.slug,
                 summary: legacy_create.description, // Description becomes summary
                 description: legacy_create.body,    // Body becomes description
+                description_source_url: None,
                 initial_versions,
                 categories: legacy_create.categories,
                 additional_categories: legacy_create.additional_categories,
@@ -240,6 +246,7 @@ pub async fn project_create(
         client.clone(),
         redis.clone(),
         file_host,
+        scanner,
         session_queue,
     )
     .await?;
@@ -2,17 +2,18 @@ use crate::database::models::categories::LinkPlatform;
 use crate::database::models::{project_item, version_item};
 use crate::database::redis::RedisPool;
 use crate::file_hosting::FileHost;
-use crate::models::projects::{
-    Link, MonetizationStatus, Project, ProjectStatus, SearchRequest, Version,
-};
+use crate::models::projects::{MonetizationStatus, Project, ProjectStatus, SearchRequest, Version};
 use crate::models::v2::projects::{DonationLink, LegacyProject, LegacySideType, LegacyVersion};
 use crate::models::v2::search::LegacySearchResults;
 use crate::queue::session::AuthQueue;
+use crate::queue::webhooks::WebhookQueue;
 use crate::routes::v3::projects::ProjectIds;
 use crate::routes::{v2_reroute, v3, ApiError};
-use crate::search::{search_for_project, SearchConfig, SearchError};
+use crate::search::analytics::spawn_record_search;
+use crate::search::filters::validate_search_filters;
+use crate::search::{parse_facets, search_for_project, SearchConfig, SearchError};
+use crate::util::fields::FieldsQuery;
 use actix_web::{delete, get, patch, post, web, HttpRequest, HttpResponse};
-use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use std::collections::HashMap;
@@ -50,33 +51,19 @@ pub fn config(cfg: &mut web::ServiceConfig) {
 
 #[get("search")]
 pub async fn project_search(
+    req: HttpRequest,
     web::Query(info): web::Query<SearchRequest>,
     config: web::Data<SearchConfig>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, SearchError> {
     // TODO: make this nicer
     // Search now uses loader_fields instead of explicit 'client_side' and 'server_side' fields
     // While the backend for this has changed, it doesnt affect much
     // in the API calls except that 'versions:x' is now 'game_versions:x'
     let facets: Option<Vec<Vec<Vec<String>>>> = if let Some(facets) = info.facets {
-        let facets = serde_json::from_str::<Vec<Vec<serde_json::Value>>>(&facets)?;
-        // Search can now *optionally* have a third inner array: So Vec(AND)<Vec(OR)<Vec(AND)< _ >>>
-        // For every inner facet, we will check if it can be deserialized into a Vec<&str>, and do so.
-        // If not, we will assume it is a single facet and wrap it in a Vec.
-        let facets: Vec<Vec<Vec<String>>> = facets
-            .into_iter()
-            .map(|facets| {
-                facets
-                    .into_iter()
-                    .map(|facet| {
-                        if facet.is_array() {
-                            serde_json::from_value::<Vec<String>>(facet).unwrap_or_default()
-                        } else {
-                            vec![serde_json::from_value::<String>(facet).unwrap_or_default()]
-                        }
-                    })
-                    .collect_vec()
-            })
-            .collect_vec();
+        let facets = parse_facets(&facets)?;
 
         // These loaders speciically used to be combined with 'mod' to be a plugin, but now
         // they are their own loader type. We will convert 'mod' to 'mod' OR 'plugin'
@@ -123,7 +110,15 @@ pub async fn project_search(
         ..info
     };
 
-    let results = search_for_project(&info, &config).await?;
+    validate_search_filters(&info, &pool, &redis).await?;
+    let show_mature =
+        v3::projects::search_shows_mature(&req, &info, &pool, &redis, &session_queue).await;
+    let results = search_for_project(&info, &config, show_mature, None).await?;
+    spawn_record_search(
+        info.query.as_deref(),
+        results.total_hits,
+        redis.get_ref().clone(),
+    );
 
     let results = LegacySearchResults::from(results);
 
@@ -171,6 +166,7 @@ pub async fn projects_get(
     let response = v3::projects::projects_get(
         req,
         web::Query(ids),
+        web::Query(FieldsQuery::default()),
         pool.clone(),
         redis.clone(),
         session_queue,
@@ -199,9 +195,18 @@ pub async fn project_get(
 ) -> Result<HttpResponse, ApiError> {
     // Convert V2 data to V3 data
     // Call V3 project creation
-    let response = v3::projects::project_get(req, info, pool.clone(), redis.clone(), session_queue)
-        .await
-        .or_else(v2_reroute::flatten_404_error)?;
+    let response = v3::projects::project_get(
+        req,
+        info,
+        web::Query(FieldsQuery::default()),
+        web::Query(v3::projects::SlugRedirectQuery::default()),
+        web::Query(v3::projects::LanguageQuery::default()),
+        pool.clone(),
+        redis.clone(),
+        session_queue,
+    )
+    .await
+    .or_else(v2_reroute::flatten_404_error)?;
 
     // Convert response to V2 format
     match v2_reroute::extract_ok_json::<Project>(response).await {
@@ -370,6 +375,7 @@ pub struct EditProject {
 }
 
 #[patch("{id}")]
+#[allow(clippy::too_many_arguments)]
 pub async fn project_edit(
     req: HttpRequest,
     info: web::Path<(String,)>,
@@ -378,6 +384,7 @@ pub async fn project_edit(
     new_project: web::Json<EditProject>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
+    webhook_queue: web::Data<WebhookQueue>,
 ) -> Result<HttpResponse, ApiError> {
     let v2_new_project = new_project.into_inner();
     let client_side = v2_new_project.client_side;
@@ -435,25 +442,20 @@ pub async fn project_edit(
             .map(|x| {
                 x.urls
                     .into_iter()
-                    .filter_map(|l| {
-                        if l.donation {
-                            Some(Link::from(l)) // TODO: tests
-                        } else {
-                            None
-                        }
-                    })
+                    .filter(|l| l.donation || l.name == "other")
+                    .map(|l| l.name)
                     .collect::<Vec<_>>()
             })
             .unwrap_or_default();
 
         // Set existing donation links to None
         for old_link in donation_links {
-            new_links.insert(old_link.platform, None);
+            new_links.insert(old_link, None);
         }
 
         // Add new donation links
         for donation_url in donation_urls {
-            new_links.insert(donation_url.id, Some(donation_url.url));
+            new_links.insert(donation_url.link_name(), Some(donation_url.url));
         }
     }
 
@@ -463,6 +465,7 @@ pub async fn project_edit(
         description: v2_new_project.body,    // Body becomes description
         categories: v2_new_project.categories,
         additional_categories: v2_new_project.additional_categories,
+        display_categories: None,
         license_url: v2_new_project.license_url,
         link_urls: Some(new_links),
         license_id: v2_new_project.license_id,
@@ -472,6 +475,9 @@ pub async fn project_edit(
         moderation_message: v2_new_project.moderation_message,
         moderation_message_body: v2_new_project.moderation_message_body,
         monetization_status: v2_new_project.monetization_status,
+        mature: None,
+        description_source_url: None,
+        go_live_at: None,
     };
 
     // This returns 204 or failure so we don't need to do anything with it
@@ -480,7 +486,7 @@ pub async fn project_edit(
         req.clone(),
         info,
         pool.clone(),
-        config,
+        config.clone(),
         web::Json(new_project),
         redis.clone(),
         session_queue.clone(),
@@ -498,10 +504,17 @@ pub async fn project_edit(
         for version in versions {
             let version = Version::from(version);
             let mut fields = version.fields;
+            let (_, project_type) = LegacyProject::get_project_type(&version.project_types);
             let (current_client_side, current_server_side) =
-                v2_reroute::convert_side_types_v2(&fields, None);
+                v2_reroute::convert_side_types_v2(&fields, Some(&project_type));
             let client_side = client_side.unwrap_or(current_client_side);
             let server_side = server_side.unwrap_or(current_server_side);
+
+            // Leave the fields as they are if the side types don't change, as v3 can store
+            // combinations v2 can't represent
+            if (client_side, server_side) == (current_client_side, current_server_side) {
+                continue;
+            }
             fields.extend(v2_reroute::convert_side_types_v3(client_side, server_side));
 
             response = v3::versions::version_edit_helper(
@@ -509,11 +522,13 @@ pub async fn project_edit(
                 (version.id,),
                 pool.clone(),
                 redis.clone(),
+                config.clone(),
                 v3::versions::EditVersion {
                     fields,
                     ..Default::default()
                 },
                 session_queue.clone(),
+                webhook_queue.clone(),
             )
             .await?;
         }
@@ -606,23 +621,25 @@ pub async fn projects_edit(
                 link_urls.insert(link.name, None);
             }
         }
+        // v2's "Other" donation links are stored as generic links
+        link_urls.insert("other".to_string(), None);
         // add
         for donation_url in donation_url {
-            link_urls.insert(donation_url.id, Some(donation_url.url));
+            link_urls.insert(donation_url.link_name(), Some(donation_url.url));
         }
     }
 
     // For every delete, we will set the link to None
     if let Some(donation_url) = bulk_edit_project.remove_donation_urls {
         for donation_url in donation_url {
-            link_urls.insert(donation_url.id, None);
+            link_urls.insert(donation_url.link_name(), None);
         }
     }
 
     // For every add, we will set the link to the new url
     if let Some(donation_url) = bulk_edit_project.add_donation_urls {
         for donation_url in donation_url {
-            link_urls.insert(donation_url.id, Some(donation_url.url));
+            link_urls.insert(donation_url.link_name(), Some(donation_url.url));
         }
     }
 
@@ -692,6 +709,7 @@ pub async fn project_icon_edit(
     info: web::Path<(String,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
     payload: web::Payload,
     session_queue: web::Data<AuthQueue>,
@@ -703,6 +721,7 @@ pub async fn project_icon_edit(
         info,
         pool,
         redis,
+        search_config,
         file_host,
         payload,
         session_queue,
@@ -717,13 +736,22 @@ pub async fn delete_project_icon(
     info: web::Path<(String,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
     // Returns NoContent, so no need to convert
-    v3::projects::delete_project_icon(req, info, pool, redis, file_host, session_queue)
-        .await
-        .or_else(v2_reroute::flatten_404_error)
+    v3::projects::delete_project_icon(
+        req,
+        info,
+        pool,
+        redis,
+        search_config,
+        file_host,
+        session_queue,
+    )
+    .await
+    .or_else(v2_reroute::flatten_404_error)
 }
 
 #[derive(Serialize, Deserialize, Validate)]
@@ -745,6 +773,7 @@ pub async fn add_gallery_item(
     info: web::Path<(String,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
     payload: web::Payload,
     session_queue: web::Data<AuthQueue>,
@@ -762,6 +791,7 @@ pub async fn add_gallery_item(
         info,
         pool,
         redis,
+        search_config,
         file_host,
         payload,
         session_queue,
@@ -799,6 +829,7 @@ pub async fn edit_gallery_item(
     info: web::Path<(String,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
     // Returns NoContent, so no need to convert
@@ -814,6 +845,7 @@ pub async fn edit_gallery_item(
         info,
         pool,
         redis,
+        search_config,
         session_queue,
     )
     .await
@@ -826,12 +858,14 @@ pub struct GalleryDeleteQuery {
 }
 
 #[delete("{id}/gallery")]
+#[allow(clippy::too_many_arguments)]
 pub async fn delete_gallery_item(
     req: HttpRequest,
     web::Query(item): web::Query<GalleryDeleteQuery>,
     info: web::Path<(String,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
@@ -842,6 +876,7 @@ pub async fn delete_gallery_item(
         info,
         pool,
         redis,
+        search_config,
         file_host,
         session_queue,
     )
@@ -113,7 +113,14 @@ pub async fn reports_get(
 ) -> Result<HttpResponse, ApiError> {
     let response = v3::reports::reports_get(
         req,
-        web::Query(v3::reports::ReportIds { ids: ids.ids }),
+        web::Query(v3::reports::ReportsQuery {
+            ids: Some(ids.ids),
+            status: None,
+            assigned: None,
+            count: None,
+            offset: None,
+            format: None,
+        }),
         pool,
         redis,
         session_queue,
@@ -180,6 +187,7 @@ pub async fn report_edit(
         web::Json(v3::reports::EditReport {
             body: edit_report.body,
             closed: edit_report.closed,
+            ..Default::default()
         }),
     )
     .await
@@ -246,7 +246,7 @@ pub async fn donation_platform_list(
     Ok(
         match v2_reroute::extract_ok_json::<Vec<LinkPlatformQueryData>>(response).await {
             Ok(platforms) => {
-                let platforms = platforms
+                let mut platforms = platforms
                     .into_iter()
                     .filter_map(|p| {
                         if p.donation {
@@ -255,20 +255,28 @@ pub async fn donation_platform_list(
                                 // We capitalize to recreate the old behavior, with some special handling.
                                 // This may result in different behaviour for platforms added after the v3 migration.
                                 name: match p.name.as_str() {
-                                    "bmac" => "Buy Me A Coffee".to_string(),
+                                    "buymeacoffee" => "Buy Me A Coffee".to_string(),
                                     "github" => "GitHub Sponsors".to_string(),
                                     "ko-fi" => "Ko-fi".to_string(),
                                     "paypal" => "PayPal".to_string(),
                                     // Otherwise, capitalize it
                                     _ => capitalize_first(&p.name),
                                 },
-                                short: p.name,
+                                short: match p.name.as_str() {
+                                    "buymeacoffee" => "bmac".to_string(),
+                                    _ => p.name,
+                                },
                             })
                         } else {
                             None
                         }
                     })
                     .collect::<Vec<_>>();
+                // "Other" donation links are generic links in v3, so it isn't a platform anymore
+                platforms.push(DonationPlatformQueryData {
+                    short: "other".to_string(),
+                    name: "Other".to_string(),
+                });
                 HttpResponse::Ok().json(platforms)
             }
             Err(response) => response,
@@ -176,6 +176,7 @@ pub async fn add_team_member(
             organization_permissions: new_member.organization_permissions,
             payouts_split: new_member.payouts_split,
             ordering: new_member.ordering,
+            role_template: None,
         }),
         redis,
         session_queue,
@@ -213,6 +214,7 @@ pub async fn edit_team_member(
             role: edit_member.role.clone(),
             payouts_split: edit_member.payouts_split,
             ordering: edit_member.ordering,
+            role_template: None,
         }),
         redis,
         session_queue,
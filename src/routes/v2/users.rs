@@ -8,6 +8,7 @@ use crate::models::v2::projects::LegacyProject;
 use crate::models::v2::user::LegacyUser;
 use crate::queue::session::AuthQueue;
 use crate::routes::{v2_reroute, v3, ApiError};
+use crate::search::SearchConfig;
 use actix_web::{delete, get, patch, web, HttpRequest, HttpResponse};
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -155,6 +156,7 @@ pub async fn user_edit(
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
+    search_config: web::Data<SearchConfig>,
 ) -> Result<HttpResponse, ApiError> {
     let new_user = new_user.into_inner();
     // Returns NoContent, so we don't need to convert to V2
@@ -168,10 +170,13 @@ pub async fn user_edit(
             role: new_user.role,
             badges: new_user.badges,
             venmo_handle: None,
+            hide_mature_content: None,
+            banned: None,
         }),
         pool,
         redis,
         session_queue,
+        search_config,
     )
     .await
     .or_else(v2_reroute::flatten_404_error)
@@ -193,6 +198,7 @@ pub async fn user_icon_edit(
     file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
     payload: web::Payload,
     session_queue: web::Data<AuthQueue>,
+    search_config: web::Data<SearchConfig>,
 ) -> Result<HttpResponse, ApiError> {
     // Returns NoContent, so we don't need to convert to V2
     v3::users::user_icon_edit(
@@ -204,6 +210,7 @@ pub async fn user_icon_edit(
         file_host,
         payload,
         session_queue,
+        search_config,
     )
     .await
     .or_else(v2_reroute::flatten_404_error)
@@ -216,9 +223,10 @@ pub async fn user_delete(
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
+    search_config: web::Data<SearchConfig>,
 ) -> Result<HttpResponse, ApiError> {
     // Returns NoContent, so we don't need to convert to V2
-    v3::users::user_delete(req, info, pool, redis, session_queue)
+    v3::users::user_delete(req, info, pool, redis, session_queue, search_config)
         .await
         .or_else(v2_reroute::flatten_404_error)
 }
@@ -235,10 +243,11 @@ pub async fn user_follows(
         .await
         .or_else(v2_reroute::flatten_404_error)?;
 
-    // Convert to V2 projects
-    match v2_reroute::extract_ok_json::<Vec<Project>>(response).await {
-        Ok(project) => {
-            let legacy_projects = LegacyProject::from_many(project, &**pool, &redis).await?;
+    // Convert to V2 projects. Followed collections aren't listed in V2
+    match v2_reroute::extract_ok_json::<v3::users::UserFollows>(response).await {
+        Ok(follows) => {
+            let legacy_projects =
+                LegacyProject::from_many(follows.projects, &**pool, &redis).await?;
             Ok(HttpResponse::Ok().json(legacy_projects))
         }
         Err(response) => Ok(response),
@@ -2,12 +2,14 @@ use crate::database::models::loader_fields::VersionField;
 use crate::database::models::{project_item, version_item};
 use crate::database::redis::RedisPool;
 use crate::file_hosting::FileHost;
+use crate::file_scanning::FileScanner;
 use crate::models::ids::ImageId;
 use crate::models::projects::{
     Dependency, FileType, Loader, ProjectId, Version, VersionId, VersionStatus, VersionType,
 };
 use crate::models::v2::projects::LegacyVersion;
 use crate::queue::session::AuthQueue;
+use crate::queue::webhooks::WebhookQueue;
 use crate::routes::v3::project_creation::CreateError;
 use crate::routes::{v2_reroute, v3};
 use actix_multipart::Multipart;
@@ -68,6 +70,11 @@ pub struct InitialVersionData {
 
     // The ordering relative to other versions
     pub ordering: Option<i32>,
+    // Whether to fill in dependencies from the primary file's mod metadata
+    #[serde(default = "v3::version_creation::default_infer_dependencies")]
+    pub infer_dependencies: bool,
+    #[serde(default)]
+    pub allow_incompatible: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -77,6 +84,7 @@ struct InitialFileData {
 }
 
 // under `/api/v1/version`
+#[allow(clippy::too_many_arguments)]
 #[post("version")]
 pub async fn version_create(
     req: HttpRequest,
@@ -84,7 +92,9 @@ pub async fn version_create(
     client: Data<PgPool>,
     redis: Data<RedisPool>,
     file_host: Data<Arc<dyn FileHost + Send + Sync>>,
+    scanner: Data<Arc<dyn FileScanner + Send + Sync>>,
     session_queue: Data<AuthQueue>,
+    webhook_queue: Data<WebhookQueue>,
 ) -> Result<HttpResponse, CreateError> {
     let payload = v2_reroute::alter_actix_multipart(
         payload,
@@ -203,6 +213,9 @@ pub async fn version_create(
                     file_types: legacy_create.file_types,
                     uploaded_images: legacy_create.uploaded_images,
                     ordering: legacy_create.ordering,
+                    publish_at: None,
+                    infer_dependencies: legacy_create.infer_dependencies,
+                    allow_incompatible: legacy_create.allow_incompatible,
                     fields,
                 })
             }
@@ -217,7 +230,9 @@ pub async fn version_create(
         client.clone(),
         redis.clone(),
         file_host,
+        scanner,
         session_queue,
+        webhook_queue,
     )
     .await?;
 
@@ -258,6 +273,7 @@ async fn get_example_version_fields(
 }
 
 // under /api/v1/version/{version_id}
+#[allow(clippy::too_many_arguments)]
 #[post("{version_id}/file")]
 pub async fn upload_file_to_version(
     req: HttpRequest,
@@ -266,6 +282,7 @@ pub async fn upload_file_to_version(
     client: Data<PgPool>,
     redis: Data<RedisPool>,
     file_host: Data<Arc<dyn FileHost + Send + Sync>>,
+    scanner: Data<Arc<dyn FileScanner + Send + Sync>>,
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, CreateError> {
     // Returns NoContent, so no need to convert to V2
@@ -276,6 +293,7 @@ pub async fn upload_file_to_version(
         client.clone(),
         redis.clone(),
         file_host,
+        scanner,
         session_queue,
     )
     .await?;
@@ -7,6 +7,7 @@ use crate::models::ids::VersionId;
 use crate::models::projects::{Dependency, FileType, Version, VersionStatus, VersionType};
 use crate::models::v2::projects::LegacyVersion;
 use crate::queue::session::AuthQueue;
+use crate::queue::webhooks::WebhookQueue;
 use crate::routes::{v2_reroute, v3};
 use crate::search::SearchConfig;
 use actix_web::{delete, get, patch, web, HttpRequest, HttpResponse};
@@ -92,6 +93,8 @@ pub async fn version_list(
         version_type: filters.version_type,
         limit: filters.limit,
         offset: filters.offset,
+        count: None,
+        before_version: None,
     };
 
     let response =
@@ -226,13 +229,16 @@ pub struct EditVersionFileType {
 }
 
 #[patch("{id}")]
+#[allow(clippy::too_many_arguments)]
 pub async fn version_edit(
     req: HttpRequest,
     info: web::Path<(VersionId,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     new_version: web::Json<EditVersion>,
     session_queue: web::Data<AuthQueue>,
+    webhook_queue: web::Data<WebhookQueue>,
 ) -> Result<HttpResponse, ApiError> {
     let new_version = new_version.into_inner();
 
@@ -292,6 +298,7 @@ pub async fn version_edit(
                 .collect::<Vec<_>>()
         }),
         ordering: new_version.ordering,
+        publish_at: None,
         fields,
     };
 
@@ -300,8 +307,10 @@ pub async fn version_edit(
         info,
         pool,
         redis,
+        search_config,
         web::Json(serde_json::to_value(new_version)?),
         session_queue,
+        webhook_queue,
     )
     .await
     .or_else(v2_reroute::flatten_404_error)?;
@@ -126,29 +126,70 @@ where
     Ok(new_multipart)
 }
 
+/// The v3 loader fields that v2 "client_side" and "server_side" values are stored in
+pub const SIDE_TYPE_FIELDS: [&str; 4] = [
+    "singleplayer",
+    "client_and_server",
+    "client_only",
+    "server_only",
+];
+
+// How each "client_side" and "server_side" pair is stored in the v3 side type fields, in the
+// order of SIDE_TYPE_FIELDS. Every pair has its own combination, so that converting a pair to v3
+// and back doesn't change it. Pairs stored before this table existed always had 'singleplayer'
+// and 'client_and_server' set to the same value, so those combinations still read the same.
+const SIDE_TYPES: [(LegacySideType, LegacySideType, [bool; 4]); 9] = {
+    use LegacySideType::{Optional, Required, Unsupported};
+    [
+        (Required, Required, [true, true, false, false]),
+        (Required, Optional, [false, true, true, false]),
+        (Required, Unsupported, [true, true, true, false]),
+        (Optional, Required, [false, true, false, true]),
+        (Optional, Optional, [true, true, true, true]),
+        (Optional, Unsupported, [true, false, true, false]),
+        (Unsupported, Required, [true, true, false, true]),
+        (Unsupported, Optional, [true, false, false, true]),
+        (Unsupported, Unsupported, [false, false, false, false]),
+    ]
+};
+
+// The side types of each project type, for versions whose loaders don't have the side type fields
+// (such as the plugin, datapack and shader loaders). Mods and modpacks always have them.
+const PROJECT_TYPE_SIDE_TYPES: [(&str, LegacySideType, LegacySideType); 6] = {
+    use LegacySideType::{Optional, Required, Unknown, Unsupported};
+    [
+        ("mod", Unknown, Unknown),
+        ("modpack", Unknown, Unknown),
+        ("plugin", Unsupported, Required),
+        ("datapack", Optional, Required),
+        ("shader", Required, Unsupported),
+        ("resourcepack", Required, Unsupported),
+    ]
+};
+
 // Converts a "client_side" and "server_side" pair into the new v3 corresponding fields
+// Unknown sides are stored as unsupported
 pub fn convert_side_types_v3(
     client_side: LegacySideType,
     server_side: LegacySideType,
 ) -> HashMap<String, Value> {
-    use LegacySideType::{Optional, Required};
-
-    let singleplayer = client_side == Required
-        || client_side == Optional
-        || server_side == Required
-        || server_side == Optional;
-    let client_and_server = singleplayer;
-    let client_only =
-        (client_side == Required || client_side == Optional) && server_side != Required;
-    let server_only =
-        (server_side == Required || server_side == Optional) && client_side != Required;
-
-    let mut fields = HashMap::new();
-    fields.insert("singleplayer".to_string(), json!(singleplayer));
-    fields.insert("client_and_server".to_string(), json!(client_and_server));
-    fields.insert("client_only".to_string(), json!(client_only));
-    fields.insert("server_only".to_string(), json!(server_only));
-    fields
+    let known = |side| match side {
+        LegacySideType::Unknown => LegacySideType::Unsupported,
+        side => side,
+    };
+    let (client_side, server_side) = (known(client_side), known(server_side));
+
+    let values = SIDE_TYPES
+        .iter()
+        .find(|(client, server, _)| *client == client_side && *server == server_side)
+        .map(|(_, _, values)| *values)
+        .unwrap_or_default();
+
+    SIDE_TYPE_FIELDS
+        .iter()
+        .zip(values)
+        .map(|(field, value)| (field.to_string(), json!(value)))
+        .collect()
 }
 
 // Converts plugin loaders from v2 to v3
@@ -171,76 +212,35 @@ pub fn convert_plugin_loaders_v3(facets: Vec<Vec<Vec<String>>>) -> Vec<Vec<Vec<S
         .collect::<Vec<_>>()
 }
 
-// Convert search facets from V3 back to v2
-// this is not lossless. (See tests)
+// Converts the v3 side type fields of a version back to a "client_side" and "server_side" pair
+// If the version doesn't have any of the fields, the side types of the project type are used.
 pub fn convert_side_types_v2(
     side_types: &HashMap<String, Value>,
     project_type: Option<&str>,
-) -> (LegacySideType, LegacySideType) {
-    let client_and_server = side_types
-        .get("client_and_server")
-        .and_then(|x| x.as_bool())
-        .unwrap_or(false);
-    let singleplayer = side_types
-        .get("singleplayer")
-        .and_then(|x| x.as_bool())
-        .unwrap_or(client_and_server);
-    let client_only = side_types
-        .get("client_only")
-        .and_then(|x| x.as_bool())
-        .unwrap_or(false);
-    let server_only = side_types
-        .get("server_only")
-        .and_then(|x| x.as_bool())
-        .unwrap_or(false);
-
-    convert_side_types_v2_bools(
-        Some(singleplayer),
-        client_only,
-        server_only,
-        Some(client_and_server),
-        project_type,
-    )
-}
-
-// Client side, server side
-pub fn convert_side_types_v2_bools(
-    singleplayer: Option<bool>,
-    client_only: bool,
-    server_only: bool,
-    client_and_server: Option<bool>,
-    project_type: Option<&str>,
 ) -> (LegacySideType, LegacySideType) {
     use LegacySideType::{Optional, Required, Unknown, Unsupported};
 
-    match project_type {
-        Some("plugin") => (Unsupported, Required),
-        Some("datapack") => (Optional, Required),
-        Some("shader") => (Required, Unsupported),
-        Some("resourcepack") => (Required, Unsupported),
-        _ => {
-            let singleplayer = singleplayer.or(client_and_server).unwrap_or(false);
-
-            match (singleplayer, client_only, server_only) {
-                // Only singleplayer
-                (true, false, false) => (Required, Required),
-
-                // Client only and not server only
-                (false, true, false) => (Required, Unsupported),
-                (true, true, false) => (Required, Unsupported),
-
-                // Server only and not client only
-                (false, false, true) => (Unsupported, Required),
-                (true, false, true) => (Unsupported, Required),
+    let values = SIDE_TYPE_FIELDS.map(|field| side_types.get(field).and_then(|x| x.as_bool()));
+    if values.iter().all(Option::is_none) {
+        return PROJECT_TYPE_SIDE_TYPES
+            .iter()
+            .find(|(name, _, _)| Some(*name) == project_type)
+            .map(|(_, client_side, server_side)| (*client_side, *server_side))
+            .unwrap_or((Unknown, Unknown));
+    }
 
-                // Both server only and client only
-                (true, true, true) => (Optional, Optional),
-                (false, true, true) => (Optional, Optional),
+    let values = values.map(|x| x.unwrap_or(false));
+    if let Some((client_side, server_side, _)) = SIDE_TYPES.iter().find(|(_, _, x)| *x == values) {
+        return (*client_side, *server_side);
+    }
 
-                // Bad type
-                (false, false, false) => (Unknown, Unknown),
-            }
-        }
+    // Combinations v2 never stores, which can still be set through v3
+    let [_, _, client_only, server_only] = values;
+    match (client_only, server_only) {
+        (true, true) => (Optional, Optional),
+        (true, false) => (Required, Unsupported),
+        (false, true) => (Unsupported, Required),
+        (false, false) => (Required, Required),
     }
 }
 
@@ -255,29 +255,52 @@ pub fn capitalize_first(input: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::v2::projects::LegacySideType::{Optional, Required, Unsupported};
+    use crate::models::v2::projects::LegacySideType::{Optional, Required, Unknown, Unsupported};
 
     #[test]
     fn convert_types() {
-        // Converting types from V2 to V3 and back should be idempotent- for certain pairs
-        let lossy_pairs = [
-            (Optional, Unsupported),
-            (Unsupported, Optional),
-            (Required, Optional),
-            (Optional, Required),
-            (Unsupported, Unsupported),
-        ];
-
+        // Converting types from V2 to V3 and back should be idempotent
         for client_side in [Required, Optional, Unsupported] {
             for server_side in [Required, Optional, Unsupported] {
-                if lossy_pairs.contains(&(client_side, server_side)) {
-                    continue;
-                }
                 let side_types = convert_side_types_v3(client_side, server_side);
-                let (client_side2, server_side2) = convert_side_types_v2(&side_types, None);
-                assert_eq!(client_side, client_side2);
-                assert_eq!(server_side, server_side2);
+                for project_type in [None, Some("mod"), Some("datapack"), Some("plugin")] {
+                    let (client_side2, server_side2) =
+                        convert_side_types_v2(&side_types, project_type);
+                    assert_eq!(client_side, client_side2);
+                    assert_eq!(server_side, server_side2);
+                }
             }
         }
     }
+
+    #[test]
+    fn side_types_are_distinct() {
+        for (i, (_, _, values)) in SIDE_TYPES.iter().enumerate() {
+            assert!(SIDE_TYPES[i + 1..].iter().all(|(_, _, x)| x != values));
+        }
+    }
+
+    #[test]
+    fn missing_side_types_use_project_type() {
+        let no_fields = HashMap::new();
+        assert_eq!(
+            convert_side_types_v2(&no_fields, Some("datapack")),
+            (Optional, Required)
+        );
+        assert_eq!(
+            convert_side_types_v2(&no_fields, Some("shader")),
+            (Required, Unsupported)
+        );
+        assert_eq!(
+            convert_side_types_v2(&no_fields, Some("mod")),
+            (Unknown, Unknown)
+        );
+
+        // A datapack that also has side type fields uses them
+        let side_types = convert_side_types_v3(Optional, Optional);
+        assert_eq!(
+            convert_side_types_v2(&side_types, Some("datapack")),
+            (Optional, Optional)
+        );
+    }
 }
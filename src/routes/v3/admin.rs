@@ -0,0 +1,751 @@
+use super::tags::CategoryData;
+use super::ApiError;
+use crate::auth::get_user_from_headers;
+use crate::database::models::categories::{Category, ProjectType};
+use crate::database::models::loader_fields::{
+    Loader, LoaderField, LoaderFieldEnum, LoaderFieldEnumValue, LoaderFieldType,
+};
+use crate::database::models::{LoaderFieldEnumId, LoaderId};
+use crate::database::redis::RedisPool;
+use crate::models::pats::Scopes;
+use crate::queue::session::AuthQueue;
+use crate::search::analytics::{self, QueryCount};
+use crate::search::indexing::DEFAULT_ATTRIBUTES_FOR_FACETING;
+use crate::util::jobs::{Job, JobStatus, JobType};
+use crate::util::maintenance::MaintenanceState;
+use crate::util::validate::validation_errors_to_string;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use validator::Validate;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("admin")
+            .route("search/analytics", web::get().to(search_analytics_get))
+            .route("maintenance", web::put().to(maintenance_edit))
+            .route("jobs", web::get().to(jobs_list))
+            .route("jobs/{id}", web::get().to(job_get))
+            .route("loader-fields", web::get().to(loader_fields_list))
+            .route("loader-fields", web::post().to(loader_field_create))
+            .route("loader-fields/{field}", web::patch().to(loader_field_edit))
+            .route(
+                "loader-fields/{field}",
+                web::delete().to(loader_field_delete),
+            )
+            .route(
+                "loader-fields/{field}/values",
+                web::post().to(loader_field_value_create),
+            )
+            .route(
+                "loader-fields/{field}/values/{value}",
+                web::delete().to(loader_field_value_delete),
+            )
+            .route("categories", web::post().to(category_create))
+            .route(
+                "categories/{project_type}/{name}",
+                web::delete().to(category_delete),
+            ),
+    );
+}
+
+#[derive(Deserialize)]
+pub struct SearchAnalyticsQuery {
+    /// The day to get analytics for, defaulting to today (UTC)
+    pub date: Option<NaiveDate>,
+    /// How many of the top queries to return
+    pub count: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SearchAnalytics {
+    pub date: NaiveDate,
+    pub top_queries: Vec<QueryCount>,
+    pub top_zero_result_queries: Vec<QueryCount>,
+}
+
+pub async fn search_analytics_get(
+    req: HttpRequest,
+    web::Query(query): web::Query<SearchAnalyticsQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ANALYTICS]),
+    )
+    .await?
+    .1;
+
+    if !user.role.is_admin() {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to view search analytics.".to_string(),
+        ));
+    }
+
+    let date = query.date.unwrap_or_else(|| Utc::now().date_naive());
+    let (top_queries, top_zero_result_queries) =
+        analytics::get_top_queries(date, query.count.unwrap_or(50).min(1000), &redis).await?;
+
+    Ok(HttpResponse::Ok().json(SearchAnalytics {
+        date,
+        top_queries,
+        top_zero_result_queries,
+    }))
+}
+
+#[derive(Deserialize, Validate)]
+pub struct EditMaintenance {
+    /// Whether requests which make changes are rejected
+    pub read_only: bool,
+    #[validate(length(min = 1, max = 2048))]
+    pub message: Option<String>,
+    /// How many seconds clients should wait before retrying
+    pub retry_after: Option<u64>,
+}
+
+/// Makes the API read-only, or writable again, on every worker
+pub async fn maintenance_edit(
+    req: HttpRequest,
+    edit: web::Json<EditMaintenance>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(&req, &**pool, &redis, &session_queue, None)
+        .await?
+        .1;
+
+    if !user.role.is_admin() {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to change the maintenance mode.".to_string(),
+        ));
+    }
+
+    edit.validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let edit = edit.into_inner();
+    let state = MaintenanceState {
+        read_only: edit.read_only,
+        message: edit.message,
+        retry_after: edit.retry_after,
+    };
+    state.set(&redis).await?;
+
+    Ok(HttpResponse::Ok().json(state))
+}
+
+#[derive(Deserialize)]
+pub struct JobsQuery {
+    #[serde(rename = "type")]
+    pub job_type: Option<JobType>,
+    pub status: Option<JobStatus>,
+}
+
+/// Lists the running and recent background jobs, newest first
+pub async fn jobs_list(
+    req: HttpRequest,
+    web::Query(query): web::Query<JobsQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_can_view_jobs(&req, &pool, &redis, &session_queue).await?;
+
+    let jobs = Job::list(&redis)
+        .await?
+        .into_iter()
+        .filter(|x| query.job_type.is_none() || query.job_type == Some(x.job_type))
+        .filter(|x| query.status.is_none() || query.status == Some(x.status))
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(jobs))
+}
+
+pub async fn job_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_can_view_jobs(&req, &pool, &redis, &session_queue).await?;
+
+    let job = Job::get(&info.into_inner().0, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(HttpResponse::Ok().json(job))
+}
+
+async fn check_can_view_jobs(
+    req: &HttpRequest,
+    pool: &PgPool,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> Result<(), ApiError> {
+    let user = get_user_from_headers(req, pool, redis, session_queue, None)
+        .await?
+        .1;
+
+    if !user.role.is_admin() {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to view background jobs.".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn check_can_edit_loader_fields(
+    req: &HttpRequest,
+    pool: &PgPool,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> Result<(), ApiError> {
+    let user = get_user_from_headers(req, pool, redis, session_queue, None)
+        .await?
+        .1;
+
+    if !user.role.is_admin() {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to manage loader fields.".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// A loader field, with the loaders it can be set on and the values it can take if it's an enum
+#[derive(Serialize, Deserialize)]
+pub struct AdminLoaderField {
+    pub field: String,
+    pub field_type: String,
+    pub optional: bool,
+    pub min_val: Option<i32>,
+    pub max_val: Option<i32>,
+    pub loaders: Vec<String>,
+    pub values: Option<Vec<LoaderFieldEnumValue>>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct NewLoaderField {
+    #[validate(length(min = 1, max = 64))]
+    pub field: String,
+    pub field_type: String,
+    /// The enum an enum field takes its values from, created if it doesn't exist yet.
+    /// Defaults to the name of the field.
+    #[validate(length(min = 1, max = 64))]
+    pub enum_name: Option<String>,
+    #[serde(default = "default_optional")]
+    pub optional: bool,
+    pub min_val: Option<i32>,
+    pub max_val: Option<i32>,
+    #[serde(default)]
+    pub loaders: Vec<String>,
+}
+
+fn default_optional() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+pub struct EditLoaderField {
+    pub optional: Option<bool>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub min_val: Option<Option<i32>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub max_val: Option<Option<i32>>,
+    pub loaders: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct NewLoaderFieldValue {
+    #[validate(length(min = 1, max = 64))]
+    pub value: String,
+    pub ordering: Option<i32>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+// Gets the ids of the loaders with the names
+fn get_loader_ids(loaders: &[Loader], names: &[String]) -> Result<Vec<LoaderId>, ApiError> {
+    names
+        .iter()
+        .map(|name| {
+            loaders
+                .iter()
+                .find(|x| &x.loader == name)
+                .map(|x| x.id)
+                .ok_or_else(|| ApiError::InvalidInput(format!("'{name}' is not a loader.")))
+        })
+        .collect()
+}
+
+async fn get_loader_field(
+    field: &str,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<LoaderField, ApiError> {
+    LoaderField::get_fields_all(pool, redis)
+        .await?
+        .into_iter()
+        .find(|x| x.field == field)
+        .ok_or(ApiError::NotFound)
+}
+
+// Loader fields are cached per loader, so every loader's are cleared when one changes
+async fn clear_loader_fields_cache(pool: &PgPool, redis: &RedisPool) -> Result<(), ApiError> {
+    let loader_ids = Loader::list(pool, redis)
+        .await?
+        .into_iter()
+        .map(|x| x.id)
+        .collect::<Vec<_>>();
+    LoaderField::clear_cache(&loader_ids, redis).await?;
+
+    Ok(())
+}
+
+pub async fn loader_fields_list(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_can_edit_loader_fields(&req, &pool, &redis, &session_queue).await?;
+
+    let loaders = Loader::list(&**pool, &redis).await?;
+    let loader_fields = LoaderField::get_fields_per_loader(
+        &loaders.iter().map(|x| x.id).collect::<Vec<_>>(),
+        &**pool,
+        &redis,
+    )
+    .await?;
+    let fields = LoaderField::get_fields_all(&**pool, &redis).await?;
+    let mut values =
+        LoaderFieldEnumValue::list_many_loader_fields(&fields, &**pool, &redis).await?;
+
+    let mut field_loaders: HashMap<_, Vec<String>> = HashMap::new();
+    for loader in &loaders {
+        for field in loader_fields.get(&loader.id).into_iter().flatten() {
+            field_loaders
+                .entry(field.id)
+                .or_default()
+                .push(loader.loader.clone());
+        }
+    }
+
+    let mut results = fields
+        .into_iter()
+        .map(|x| AdminLoaderField {
+            field_type: x.field_type.to_str().to_string(),
+            optional: x.optional,
+            min_val: x.min_val,
+            max_val: x.max_val,
+            loaders: field_loaders.remove(&x.id).unwrap_or_default(),
+            values: values.remove(&x.id),
+            field: x.field,
+        })
+        .collect::<Vec<_>>();
+    results.sort_by(|a, b| a.field.cmp(&b.field));
+
+    Ok(HttpResponse::Ok().json(results))
+}
+
+/// Creates a loader field. Its values are indexed as filterable search attributes the next
+/// time projects are indexed.
+pub async fn loader_field_create(
+    req: HttpRequest,
+    new_field: web::Json<NewLoaderField>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_can_edit_loader_fields(&req, &pool, &redis, &session_queue).await?;
+
+    new_field
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+    let new_field = new_field.into_inner();
+
+    if LoaderField::get_fields_all(&**pool, &redis)
+        .await?
+        .iter()
+        .any(|x| x.field == new_field.field)
+    {
+        return Err(ApiError::InvalidInput(format!(
+            "The loader field '{}' already exists.",
+            new_field.field
+        )));
+    }
+    // The field is a key of versions and of search documents
+    if !new_field
+        .field
+        .chars()
+        .all(|x| x.is_ascii_lowercase() || x.is_ascii_digit() || x == '_')
+        || DEFAULT_ATTRIBUTES_FOR_FACETING.contains(&new_field.field.as_str())
+    {
+        return Err(ApiError::InvalidInput(format!(
+            "'{}' can't be used as the name of a loader field.",
+            new_field.field
+        )));
+    }
+
+    let loaders = Loader::list(&**pool, &redis).await?;
+    let loader_ids = get_loader_ids(&loaders, &new_field.loaders)?;
+
+    let mut transaction = pool.begin().await?;
+
+    let enum_name = new_field.enum_name.as_ref().unwrap_or(&new_field.field);
+    let enum_id = if new_field.field_type.ends_with("enum") {
+        let enum_id = match LoaderFieldEnum::get(enum_name, &mut *transaction, &redis).await? {
+            Some(loader_field_enum) => loader_field_enum.id,
+            None => LoaderFieldEnum::insert(enum_name, &mut transaction).await?,
+        };
+        Some(enum_id.0)
+    } else {
+        None
+    };
+    let field_type = LoaderFieldType::build(&new_field.field_type, enum_id).ok_or_else(|| {
+        ApiError::InvalidInput(format!(
+            "'{}' is not a type of loader field.",
+            new_field.field_type
+        ))
+    })?;
+
+    let id = LoaderField::insert(
+        &new_field.field,
+        &field_type,
+        new_field.optional,
+        new_field.min_val,
+        new_field.max_val,
+        &mut transaction,
+    )
+    .await?;
+    LoaderField::set_loaders(id, &loader_ids, &mut transaction).await?;
+
+    transaction.commit().await?;
+
+    LoaderFieldEnum::clear_cache(enum_name, &redis).await?;
+    clear_loader_fields_cache(&pool, &redis).await?;
+
+    Ok(HttpResponse::Ok().json(AdminLoaderField {
+        field: new_field.field,
+        field_type: field_type.to_str().to_string(),
+        optional: new_field.optional,
+        min_val: new_field.min_val,
+        max_val: new_field.max_val,
+        loaders: new_field.loaders,
+        values: enum_id.map(|_| Vec::new()),
+    }))
+}
+
+pub async fn loader_field_edit(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    edit_field: web::Json<EditLoaderField>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_can_edit_loader_fields(&req, &pool, &redis, &session_queue).await?;
+
+    let mut field = get_loader_field(&info.into_inner().0, &pool, &redis).await?;
+    let edit_field = edit_field.into_inner();
+
+    if let Some(optional) = edit_field.optional {
+        field.optional = optional;
+    }
+    if let Some(min_val) = edit_field.min_val {
+        field.min_val = min_val;
+    }
+    if let Some(max_val) = edit_field.max_val {
+        field.max_val = max_val;
+    }
+
+    let mut transaction = pool.begin().await?;
+    field.update(&mut transaction).await?;
+    if let Some(loaders) = &edit_field.loaders {
+        let loader_ids = get_loader_ids(&Loader::list(&**pool, &redis).await?, loaders)?;
+        LoaderField::set_loaders(field.id, &loader_ids, &mut transaction).await?;
+    }
+    transaction.commit().await?;
+
+    clear_loader_fields_cache(&pool, &redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+/// Deletes a loader field, as long as no version has a value for it
+pub async fn loader_field_delete(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_can_edit_loader_fields(&req, &pool, &redis, &session_queue).await?;
+
+    let field = get_loader_field(&info.into_inner().0, &pool, &redis).await?;
+    if LoaderField::is_used(field.id, &**pool).await? {
+        return Err(ApiError::InvalidInput(format!(
+            "The loader field '{}' is still set on versions.",
+            field.field
+        )));
+    }
+
+    let mut transaction = pool.begin().await?;
+    LoaderField::remove(field.id, &mut transaction).await?;
+    transaction.commit().await?;
+
+    clear_loader_fields_cache(&pool, &redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+// Gets an enum loader field's enum and the values it has
+async fn get_loader_field_enum_values(
+    field: &str,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(LoaderFieldEnumId, Vec<LoaderFieldEnumValue>), ApiError> {
+    let field = get_loader_field(field, pool, redis).await?;
+    let enum_id = match field.field_type {
+        LoaderFieldType::Enum(enum_id) | LoaderFieldType::ArrayEnum(enum_id) => enum_id,
+        _ => {
+            return Err(ApiError::InvalidInput(format!(
+                "'{}' is not an enumerable field, but an '{}' field.",
+                field.field,
+                field.field_type.to_str()
+            )))
+        }
+    };
+
+    Ok((
+        enum_id,
+        LoaderFieldEnumValue::list(enum_id, pool, redis).await?,
+    ))
+}
+
+/// Adds a value to an enum loader field. Game versions, and other fields whose values are
+/// ordered, have to be given the ordering of the new value.
+pub async fn loader_field_value_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    new_value: web::Json<NewLoaderFieldValue>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_can_edit_loader_fields(&req, &pool, &redis, &session_queue).await?;
+
+    new_value
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let field = info.into_inner().0;
+    let (enum_id, values) = get_loader_field_enum_values(&field, &pool, &redis).await?;
+
+    if values.iter().any(|x| x.value == new_value.value) {
+        return Err(ApiError::InvalidInput(format!(
+            "'{}' is already a value of '{field}'.",
+            new_value.value
+        )));
+    }
+    let ordered = field == "game_versions" || values.iter().any(|x| x.ordering.is_some());
+    if ordered && new_value.ordering.is_none() {
+        return Err(ApiError::InvalidInput(format!(
+            "The values of '{field}' are ordered, so the new value needs an ordering."
+        )));
+    }
+
+    let mut transaction = pool.begin().await?;
+    let value = LoaderFieldEnumValue::insert(
+        enum_id,
+        &new_value.value,
+        new_value.ordering,
+        new_value.metadata.as_ref(),
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    LoaderFieldEnumValue::clear_cache(enum_id, &redis).await?;
+
+    Ok(HttpResponse::Ok().json(value))
+}
+
+/// Deletes a value of an enum loader field, as long as no version has it set
+pub async fn loader_field_value_delete(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_can_edit_loader_fields(&req, &pool, &redis, &session_queue).await?;
+
+    let (field, value) = info.into_inner();
+    let (_, values) = get_loader_field_enum_values(&field, &pool, &redis).await?;
+    let value = values
+        .into_iter()
+        .find(|x| x.value == value)
+        .ok_or(ApiError::NotFound)?;
+
+    if LoaderFieldEnumValue::is_used(value.id, &**pool).await? {
+        return Err(ApiError::InvalidInput(format!(
+            "'{}' is still set on versions.",
+            value.value
+        )));
+    }
+
+    let mut transaction = pool.begin().await?;
+    LoaderFieldEnumValue::remove(value.id, &mut transaction).await?;
+    transaction.commit().await?;
+
+    LoaderFieldEnumValue::clear_cache(value.enum_id, &redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+async fn check_can_edit_categories(
+    req: &HttpRequest,
+    pool: &PgPool,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> Result<(), ApiError> {
+    let user = get_user_from_headers(req, pool, redis, session_queue, None)
+        .await?
+        .1;
+
+    if !user.role.is_admin() {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to manage categories.".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize, Validate)]
+pub struct NewCategory {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    /// The project type projects have to be of to be in the category
+    pub project_type: String,
+    #[validate(length(min = 1, max = 20000))]
+    pub icon: String,
+    #[serde(default = "default_header")]
+    #[validate(length(min = 1, max = 256))]
+    pub header: String,
+}
+
+fn default_header() -> String {
+    "Categories".to_string()
+}
+
+/// Registers a category for a project type, so projects of that type can be put in it. A
+/// category can be registered for any number of project types.
+pub async fn category_create(
+    req: HttpRequest,
+    new_category: web::Json<NewCategory>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_can_edit_categories(&req, &pool, &redis, &session_queue).await?;
+
+    new_category
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+    let new_category = new_category.into_inner();
+
+    // Categories are search facets, as `categories:{name}`
+    if !new_category
+        .name
+        .chars()
+        .all(|x| x.is_ascii_lowercase() || x.is_ascii_digit() || x == '-' || x == '_')
+    {
+        return Err(ApiError::InvalidInput(format!(
+            "'{}' can't be used as the name of a category.",
+            new_category.name
+        )));
+    }
+
+    let project_type = ProjectType::get_id(&new_category.project_type, &**pool)
+        .await?
+        .ok_or_else(|| {
+            ApiError::InvalidInput(format!(
+                "'{}' is not a project type.",
+                new_category.project_type
+            ))
+        })?;
+    if Category::get_id_project(&new_category.name, project_type, &**pool)
+        .await?
+        .is_some()
+    {
+        return Err(ApiError::InvalidInput(format!(
+            "The category '{}' is already registered for {} projects.",
+            new_category.name, new_category.project_type
+        )));
+    }
+
+    let mut transaction = pool.begin().await?;
+    Category::insert(
+        &new_category.name,
+        project_type,
+        &new_category.icon,
+        &new_category.header,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Category::clear_cache(&redis).await?;
+
+    Ok(HttpResponse::Ok().json(CategoryData {
+        icon: new_category.icon,
+        name: new_category.name,
+        project_type: new_category.project_type,
+        header: new_category.header,
+    }))
+}
+
+/// Unregisters a category from a project type, as long as no project is in it
+pub async fn category_delete(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_can_edit_categories(&req, &pool, &redis, &session_queue).await?;
+
+    let (project_type, name) = info.into_inner();
+    let project_type = ProjectType::get_id(&project_type, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let id = Category::get_id_project(&name, project_type, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if Category::is_used(id, &**pool).await? {
+        return Err(ApiError::InvalidInput(format!(
+            "Projects are still in the category '{name}'."
+        )));
+    }
+
+    let mut transaction = pool.begin().await?;
+    Category::remove(id, &mut transaction).await?;
+    transaction.commit().await?;
+
+    Category::clear_cache(&redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
@@ -0,0 +1,172 @@
+use crate::auth::get_user_from_headers;
+use crate::database::models::audit_log_item::AuditLogEntry;
+use crate::database::models::{self as db_models, TeamMember};
+use crate::database::redis::RedisPool;
+use crate::models::audit_log::{self, AuditAction};
+use crate::models::pats::Scopes;
+use crate::models::teams::{OrganizationPermissions, ProjectPermissions};
+use crate::queue::session::AuthQueue;
+use crate::routes::ApiError;
+use crate::util::env::parse_var;
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+/// The IP an audited action was taken from
+pub fn request_ip(req: &HttpRequest) -> Option<String> {
+    if parse_var("CLOUDFLARE_INTEGRATION").unwrap_or(false) {
+        if let Some(header) = req.headers().get("CF-Connecting-IP") {
+            return header.to_str().ok().map(String::from);
+        }
+    }
+
+    req.connection_info().peer_addr().map(String::from)
+}
+
+/// Records a field in an entry's summary if the edit changes it
+pub fn record_change<T: Serialize + PartialEq>(
+    summary: &mut serde_json::Map<String, serde_json::Value>,
+    field: &str,
+    old: T,
+    new: Option<T>,
+) {
+    if let Some(new) = new {
+        if old != new {
+            summary.insert(
+                field.to_string(),
+                serde_json::json!({ "old": old, "new": new }),
+            );
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AuditLogQuery {
+    pub action: Option<AuditAction>,
+    pub count: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+pub async fn project_audit_log_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    web::Query(query): web::Query<AuditLogQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?
+    .1;
+    let string = info.into_inner().0;
+
+    let project_item = db_models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if !user.role.is_mod() {
+        let (team_member, organization_team_member) =
+            TeamMember::get_for_project_permissions(&project_item.inner, user.id.into(), &**pool)
+                .await?;
+
+        // Hide the project
+        if team_member.is_none() && organization_team_member.is_none() {
+            return Err(ApiError::NotFound);
+        }
+
+        let permissions = ProjectPermissions::get_permissions_by_role(
+            &user.role,
+            &team_member,
+            &organization_team_member,
+        )
+        .unwrap_or_default();
+
+        if !permissions.contains(ProjectPermissions::VIEW_AUDIT_LOG) {
+            return Err(ApiError::CustomAuthentication(
+                "You don't have permission to view this project's audit log.".to_string(),
+            ));
+        }
+    }
+
+    let entries = AuditLogEntry::list(
+        Some(project_item.inner.id),
+        None,
+        query.action,
+        query.count.unwrap_or(100).clamp(0, 1000),
+        query.offset.unwrap_or(0).max(0),
+        &**pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(to_response(entries, user.role.is_mod())))
+}
+
+pub async fn organization_audit_log_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    web::Query(query): web::Query<AuditLogQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_READ]),
+    )
+    .await?
+    .1;
+    let string = info.into_inner().0;
+
+    let organization = db_models::Organization::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if !user.role.is_mod() {
+        let team_member =
+            TeamMember::get_from_user_id(organization.team_id, user.id.into(), &**pool).await?;
+        let permissions =
+            OrganizationPermissions::get_permissions_by_role(&user.role, &team_member)
+                .unwrap_or_default();
+
+        if !permissions.contains(OrganizationPermissions::VIEW_AUDIT_LOG) {
+            return Err(ApiError::CustomAuthentication(
+                "You don't have permission to view this organization's audit log.".to_string(),
+            ));
+        }
+    }
+
+    let entries = AuditLogEntry::list(
+        None,
+        Some(organization.id),
+        query.action,
+        query.count.unwrap_or(100).clamp(0, 1000),
+        query.offset.unwrap_or(0).max(0),
+        &**pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(to_response(entries, user.role.is_mod())))
+}
+
+// IPs are only shown to moderators
+fn to_response(entries: Vec<AuditLogEntry>, show_ips: bool) -> Vec<audit_log::AuditLogEntry> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            let mut entry = audit_log::AuditLogEntry::from(entry);
+            if !show_ips {
+                entry.ip = None;
+            }
+            entry
+        })
+        .collect()
+}
@@ -1,11 +1,15 @@
-use crate::auth::checks::is_visible_collection;
+use crate::auth::checks::{is_visible_collection, is_visible_project};
 use crate::auth::{filter_visible_collections, get_user_from_headers};
+use crate::database::models::collection_share_token_item::CollectionShareToken;
+use crate::database::models::notification_item::NotificationBuilder;
+use crate::database::models::user_block_item::UserBlock;
 use crate::database::models::{collection_item, generate_collection_id, project_item};
 use crate::database::redis::RedisPool;
 use crate::file_hosting::FileHost;
-use crate::models::collections::{Collection, CollectionStatus};
+use crate::models::collections::{Collection, CollectionStatus, CollectionVisibility};
 use crate::models::ids::base62_impl::parse_base62;
 use crate::models::ids::{CollectionId, ProjectId};
+use crate::models::notifications::NotificationBody;
 use crate::models::pats::Scopes;
 use crate::queue::session::AuthQueue;
 use crate::routes::v3::project_creation::CreateError;
@@ -32,7 +36,24 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("{id}", web::delete().to(collection_delete))
             .route("{id}", web::patch().to(collection_edit))
             .route("{id}/icon", web::patch().to(collection_icon_edit))
-            .route("{id}/icon", web::delete().to(delete_collection_icon)),
+            .route("{id}/icon", web::delete().to(delete_collection_icon))
+            .route("{id}/projects", web::post().to(collection_projects_add))
+            .route(
+                "{id}/projects",
+                web::delete().to(collection_projects_remove),
+            )
+            .route(
+                "{id}/projects/order",
+                web::patch().to(collection_projects_reorder),
+            )
+            .route("{id}/follow", web::post().to(collection_follow))
+            .route("{id}/follow", web::delete().to(collection_unfollow))
+            .route("{id}/share", web::get().to(collection_share_tokens_get))
+            .route("{id}/share", web::post().to(collection_share_token_create))
+            .route(
+                "{id}/share/{token}",
+                web::delete().to(collection_share_token_delete),
+            ),
     );
 }
 
@@ -51,6 +72,9 @@ pub struct CollectionCreateData {
     #[serde(default = "Vec::new")]
     /// A list of initial projects to use with the created collection
     pub projects: Vec<String>,
+    #[serde(default)]
+    /// Who can see the collection
+    pub visibility: CollectionVisibility,
 }
 
 pub async fn collection_create(
@@ -97,6 +121,7 @@ pub async fn collection_create(
         name: collection_create_data.name,
         description: collection_create_data.description,
         status: CollectionStatus::Listed,
+        visibility: collection_create_data.visibility,
         projects: initial_project_ids
             .iter()
             .copied()
@@ -118,7 +143,9 @@ pub async fn collection_create(
         icon_url: None,
         color: None,
         status: collection_builder.status,
+        visibility: collection_builder.visibility,
         projects: initial_project_ids,
+        followers: 0,
     };
     transaction.commit().await?;
 
@@ -160,9 +187,16 @@ pub async fn collections_get(
     Ok(HttpResponse::Ok().json(collections))
 }
 
+#[derive(Deserialize)]
+pub struct CollectionShareQuery {
+    /// A share token, which grants access to the collection if it's private
+    pub share: Option<String>,
+}
+
 pub async fn collection_get(
     req: HttpRequest,
     info: web::Path<(String,)>,
+    web::Query(query): web::Query<CollectionShareQuery>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
@@ -186,6 +220,15 @@ pub async fn collection_get(
         if is_visible_collection(&data, &user_option).await? {
             return Ok(HttpResponse::Ok().json(Collection::from(data)));
         }
+
+        // Share tokens only give access to private collections, not rejected ones
+        if let Some(token) = &query.share {
+            if !data.status.is_hidden()
+                && CollectionShareToken::is_valid(data.id, token, &**pool).await?
+            {
+                return Ok(HttpResponse::Ok().json(Collection::from(data)));
+            }
+        }
     }
     Err(ApiError::NotFound)
 }
@@ -205,6 +248,7 @@ pub struct EditCollection {
     )]
     pub description: Option<Option<String>>,
     pub status: Option<CollectionStatus>,
+    pub visibility: Option<CollectionVisibility>,
     #[validate(length(max = 64))]
     pub new_projects: Option<Vec<String>>,
 }
@@ -294,6 +338,20 @@ pub async fn collection_edit(
             .await?;
         }
 
+        if let Some(visibility) = &new_collection.visibility {
+            sqlx::query!(
+                "
+                UPDATE collections
+                SET visibility = $1
+                WHERE (id = $2)
+                ",
+                visibility.as_str(),
+                id as database::models::ids::CollectionId,
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
         if let Some(new_project_ids) = &new_collection.new_projects {
             // Delete all existing projects
             sqlx::query!(
@@ -306,10 +364,6 @@ pub async fn collection_edit(
             .execute(&mut *transaction)
             .await?;
 
-            let collection_item_ids = new_project_ids
-                .iter()
-                .map(|_| collection_item.id.0)
-                .collect_vec();
             let mut validated_project_ids = Vec::new();
             for project_id in new_project_ids {
                 let project = database::models::Project::get(project_id, &**pool, &redis)
@@ -319,19 +373,27 @@ pub async fn collection_edit(
                             "The specified project {project_id} does not exist!"
                         ))
                     })?;
-                validated_project_ids.push(project.inner.id.0);
+                validated_project_ids.push(project.inner.id);
             }
             // Insert- don't throw an error if it already exists
-            sqlx::query!(
-                "
-                        INSERT INTO collections_mods (collection_id, mod_id)
-                        SELECT * FROM UNNEST ($1::int8[], $2::int8[])
-                        ON CONFLICT DO NOTHING
-                        ",
-                &collection_item_ids[..],
-                &validated_project_ids[..],
+            database::models::Collection::add_projects(
+                collection_item.id,
+                &validated_project_ids,
+                &mut transaction,
+            )
+            .await?;
+
+            notify_collection_followers(
+                &collection_item,
+                new_collection.status.unwrap_or(collection_item.status),
+                new_collection
+                    .visibility
+                    .unwrap_or(collection_item.visibility),
+                &validated_project_ids,
+                user.id.into(),
+                &mut transaction,
+                &redis,
             )
-            .execute(&mut *transaction)
             .await?;
 
             sqlx::query!(
@@ -355,6 +417,275 @@ pub async fn collection_edit(
     }
 }
 
+#[derive(Deserialize, Validate)]
+pub struct CollectionProjects {
+    #[validate(length(min = 1, max = 256))]
+    /// The ids or slugs of the projects
+    pub projects: Vec<String>,
+}
+
+/// Gets the collection and checks that the user is allowed to modify it
+async fn get_modifiable_collection(
+    req: &HttpRequest,
+    id: &str,
+    pool: &PgPool,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> Result<(database::models::Collection, models::users::User), ApiError> {
+    let user = get_user_from_headers(
+        req,
+        pool,
+        redis,
+        session_queue,
+        Some(&[Scopes::COLLECTION_WRITE]),
+    )
+    .await?
+    .1;
+
+    let id = database::models::CollectionId(parse_base62(id)? as i64);
+    let collection_item = database::models::Collection::get(id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if !can_modify_collection(&collection_item, &user) {
+        return Err(ApiError::CustomAuthentication(
+            "You do not have permission to edit this collection!".to_string(),
+        ));
+    }
+
+    Ok((collection_item, user))
+}
+
+async fn touch_collection(
+    id: database::models::CollectionId,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), ApiError> {
+    sqlx::query!(
+        "
+        UPDATE collections
+        SET updated = NOW()
+        WHERE id = $1
+        ",
+        id as database::models::ids::CollectionId,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+}
+
+/// Sends the followers of a collection a single notification listing the projects an edit
+/// added to it. Followers aren't notified of changes to hidden or private collections, and the
+/// user making the edit is never notified.
+async fn notify_collection_followers(
+    collection: &database::models::Collection,
+    status: CollectionStatus,
+    visibility: CollectionVisibility,
+    project_ids: &[database::models::ProjectId],
+    editor_id: database::models::UserId,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<(), ApiError> {
+    let added_ids = project_ids
+        .iter()
+        .filter(|x| !collection.projects.contains(x))
+        .map(|x| ProjectId::from(*x))
+        .collect::<Vec<_>>();
+
+    if added_ids.is_empty() || status.is_hidden() || visibility == CollectionVisibility::Private {
+        return Ok(());
+    }
+
+    let mut followers =
+        database::models::Collection::get_followers(collection.id, &mut **transaction).await?;
+    followers.retain(|x| *x != editor_id);
+
+    if !followers.is_empty() {
+        NotificationBuilder {
+            body: NotificationBody::CollectionUpdate {
+                collection_id: collection.id.into(),
+                project_ids: added_ids,
+            },
+        }
+        .insert_many(followers, transaction, redis)
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn collection_projects_add(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    body: web::Json<CollectionProjects>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    body.validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let (collection_item, user) =
+        get_modifiable_collection(&req, &info.into_inner().0, &pool, &redis, &session_queue)
+            .await?;
+    let user_id = user.id.into();
+    let user_option = Some(user);
+
+    let mut project_ids = Vec::new();
+    let mut invalid_ids = Vec::new();
+    for project_id in body.projects.iter().unique() {
+        let project = database::models::Project::get(project_id, &**pool, &redis).await?;
+
+        match project {
+            Some(project) if is_visible_project(&project.inner, &user_option, &pool).await? => {
+                if !project_ids.contains(&project.inner.id) {
+                    project_ids.push(project.inner.id);
+                }
+            }
+            _ => invalid_ids.push(project_id.clone()),
+        }
+    }
+
+    if !invalid_ids.is_empty() {
+        return Err(ApiError::InvalidInput(format!(
+            "The following projects do not exist: {}",
+            invalid_ids.join(", ")
+        )));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    database::models::Collection::add_projects(collection_item.id, &project_ids, &mut transaction)
+        .await?;
+    touch_collection(collection_item.id, &mut transaction).await?;
+    notify_collection_followers(
+        &collection_item,
+        collection_item.status,
+        collection_item.visibility,
+        &project_ids,
+        user_id,
+        &mut transaction,
+        &redis,
+    )
+    .await?;
+
+    transaction.commit().await?;
+    database::models::Collection::clear_cache(collection_item.id, &redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn collection_projects_remove(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    body: web::Json<CollectionProjects>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    body.validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let (collection_item, _) =
+        get_modifiable_collection(&req, &info.into_inner().0, &pool, &redis, &session_queue)
+            .await?;
+
+    let project_ids = database::models::Project::get_many(&body.projects, &**pool, &redis)
+        .await?
+        .into_iter()
+        .map(|x| x.inner.id)
+        .collect::<Vec<_>>();
+
+    let mut transaction = pool.begin().await?;
+
+    database::models::Collection::remove_projects(
+        collection_item.id,
+        &project_ids,
+        &mut transaction,
+    )
+    .await?;
+    touch_collection(collection_item.id, &mut transaction).await?;
+
+    transaction.commit().await?;
+    database::models::Collection::clear_cache(collection_item.id, &redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn collection_projects_reorder(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    body: web::Json<CollectionProjects>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (collection_item, _) =
+        get_modifiable_collection(&req, &info.into_inner().0, &pool, &redis, &session_queue)
+            .await?;
+
+    let mut project_ids = Vec::new();
+    for project_id in &body.projects {
+        let project = database::models::Project::get(project_id, &**pool, &redis)
+            .await?
+            .ok_or_else(|| {
+                ApiError::InvalidInput(format!(
+                    "The specified project {project_id} does not exist!"
+                ))
+            })?;
+        project_ids.push(project.inner.id);
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    // The new order must be a permutation of the projects currently in the collection, which are
+    // read from the database rather than the cache so a concurrent edit can't slip past the check
+    let current_ids =
+        database::models::Collection::lock_projects(collection_item.id, &mut transaction).await?;
+    if let Some(duplicate) = body
+        .projects
+        .iter()
+        .zip(&project_ids)
+        .find(|(_, id)| project_ids.iter().filter(|x| x == id).count() > 1)
+    {
+        return Err(ApiError::InvalidInput(format!(
+            "The project {} is listed more than once!",
+            duplicate.0
+        )));
+    }
+    let foreign = body
+        .projects
+        .iter()
+        .zip(&project_ids)
+        .filter(|(_, id)| !current_ids.contains(id))
+        .map(|(x, _)| x.as_str())
+        .collect::<Vec<_>>();
+    if !foreign.is_empty() {
+        return Err(ApiError::InvalidInput(format!(
+            "The following projects are not in this collection: {}",
+            foreign.join(", ")
+        )));
+    }
+    if project_ids.len() != current_ids.len() {
+        return Err(ApiError::InvalidInput(
+            "The new order must contain every project in the collection!".to_string(),
+        ));
+    }
+
+    database::models::Collection::reorder_projects(
+        collection_item.id,
+        &project_ids,
+        &mut transaction,
+    )
+    .await?;
+    touch_collection(collection_item.id, &mut transaction).await?;
+
+    transaction.commit().await?;
+    database::models::Collection::clear_cache(collection_item.id, &redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Extension {
     pub ext: String,
@@ -544,6 +875,155 @@ pub async fn collection_delete(
     }
 }
 
+pub async fn collection_follow(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_WRITE]),
+    )
+    .await?
+    .1;
+
+    let id = database::models::CollectionId(parse_base62(&info.into_inner().0)? as i64);
+    let collection = database::models::Collection::get(id, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let user_id: database::models::UserId = user.id.into();
+
+    if !is_visible_collection(&collection, &Some(user)).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    // Users blocked by the collection's owner aren't told why they can't follow it
+    if UserBlock::is_blocked(collection.user_id, user_id, &**pool).await? {
+        return Err(ApiError::Forbidden(
+            "You can't follow this collection.".to_string(),
+        ));
+    }
+
+    if database::models::Collection::is_following(collection.id, user_id, &**pool).await? {
+        return Err(ApiError::InvalidInput(
+            "You are already following this collection!".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+    database::models::Collection::follow(collection.id, user_id, &mut transaction).await?;
+    transaction.commit().await?;
+    database::models::Collection::clear_cache(collection.id, &redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn collection_unfollow(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_WRITE]),
+    )
+    .await?
+    .1;
+
+    let id = database::models::CollectionId(parse_base62(&info.into_inner().0)? as i64);
+    let collection = database::models::Collection::get(id, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let user_id: database::models::UserId = user.id.into();
+
+    if !database::models::Collection::is_following(collection.id, user_id, &**pool).await? {
+        return Err(ApiError::InvalidInput(
+            "You are not following this collection!".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+    database::models::Collection::unfollow(collection.id, user_id, &mut transaction).await?;
+    transaction.commit().await?;
+    database::models::Collection::clear_cache(collection.id, &redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn collection_share_tokens_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (collection, _) =
+        get_modifiable_collection(&req, &info.into_inner().0, &pool, &redis, &session_queue)
+            .await?;
+
+    let tokens = CollectionShareToken::get_for_collection(collection.id, &**pool)
+        .await?
+        .into_iter()
+        .map(models::collections::CollectionShareToken::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(tokens))
+}
+
+pub async fn collection_share_token_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (collection, _) =
+        get_modifiable_collection(&req, &info.into_inner().0, &pool, &redis, &session_queue)
+            .await?;
+
+    let token = CollectionShareToken::generate(collection.id);
+
+    let mut transaction = pool.begin().await?;
+    token.insert(&mut transaction).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(models::collections::CollectionShareToken::from(token)))
+}
+
+pub async fn collection_share_token_delete(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, token) = info.into_inner();
+    let (collection, _) =
+        get_modifiable_collection(&req, &id, &pool, &redis, &session_queue).await?;
+
+    let mut transaction = pool.begin().await?;
+    let removed = CollectionShareToken::remove(collection.id, &token, &mut transaction).await?;
+    transaction.commit().await?;
+
+    if removed {
+        Ok(HttpResponse::NoContent().body(""))
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
 fn can_modify_collection(
     collection: &database::models::Collection,
     user: &models::users::User,
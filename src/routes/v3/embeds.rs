@@ -0,0 +1,244 @@
+use crate::database::models::categories::ProjectType;
+use crate::database::models::project_item::QueryProject;
+use crate::database::models::{Organization, Project, TeamMember, User};
+use crate::database::redis::RedisPool;
+use crate::models::projects::ProjectId;
+use crate::routes::ApiError;
+use crate::util::sanitize::escape_attribute;
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("oembed", web::get().to(oembed_get));
+}
+
+/// How many characters of the summary are shown in an embed, including the ellipsis
+const EMBED_SUMMARY_LENGTH: usize = 200;
+/// The size of the rich oEmbed card, unless the consumer asks for a smaller one
+const OEMBED_WIDTH: u32 = 400;
+const OEMBED_HEIGHT: u32 = 150;
+
+#[derive(Serialize, Deserialize)]
+pub struct EmbedAuthor {
+    pub name: String,
+    pub url: String,
+}
+
+/// What's shown when a project is linked somewhere which unfurls links
+#[derive(Serialize, Deserialize)]
+pub struct ProjectEmbed {
+    pub id: ProjectId,
+    pub url: String,
+    pub title: String,
+    pub summary: String,
+    pub icon_url: Option<String>,
+    /// The featured gallery image
+    pub image_url: Option<String>,
+    /// The owning user or organization
+    pub author: Option<EmbedAuthor>,
+    pub downloads: u32,
+    pub follows: u32,
+    pub categories: Vec<String>,
+    /// The dominant color of the icon
+    pub color: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct OEmbedQuery {
+    pub url: String,
+    pub maxwidth: Option<u32>,
+    pub maxheight: Option<u32>,
+    pub format: Option<String>,
+}
+
+/// A response of the rich type from the oEmbed spec (https://oembed.com)
+#[derive(Serialize, Deserialize)]
+pub struct OEmbed {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub version: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_url: Option<String>,
+    pub provider_name: String,
+    pub provider_url: String,
+    pub html: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Gets the project, which is only embeddable while it's listed
+async fn get_listed_project(
+    id: &str,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<QueryProject, ApiError> {
+    Project::get(id, pool, redis)
+        .await?
+        .filter(|x| x.inner.status.is_searchable())
+        .ok_or(ApiError::NotFound)
+}
+
+async fn build_embed(
+    project: QueryProject,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<ProjectEmbed, ApiError> {
+    let site_url = dotenvy::var("SITE_URL").unwrap_or_default();
+
+    let author = if let Some(organization_id) = project.inner.organization_id {
+        Organization::get_id(organization_id, pool, redis)
+            .await?
+            .map(|x| EmbedAuthor {
+                url: format!("{}/organization/{}", site_url, x.slug),
+                name: x.name,
+            })
+    } else {
+        let owner = TeamMember::get_from_team_full(project.inner.team_id, pool, redis)
+            .await?
+            .into_iter()
+            .find(|x| x.is_owner && x.accepted);
+
+        match owner {
+            Some(owner) => User::get_id(owner.user_id, pool, redis)
+                .await?
+                .map(|x| EmbedAuthor {
+                    url: format!("{}/user/{}", site_url, x.username),
+                    name: x.username,
+                }),
+            None => None,
+        }
+    };
+
+    let id: ProjectId = project.inner.id.into();
+    let project_type = project
+        .project_types
+        .first()
+        .map(|x| &**x)
+        .unwrap_or("project");
+
+    let summary = if project.inner.summary.chars().count() > EMBED_SUMMARY_LENGTH {
+        let truncated = project
+            .inner
+            .summary
+            .chars()
+            .take(EMBED_SUMMARY_LENGTH - 1)
+            .collect::<String>();
+        format!("{}…", truncated.trim_end())
+    } else {
+        project.inner.summary
+    };
+
+    Ok(ProjectEmbed {
+        id,
+        url: format!(
+            "{}/{}/{}",
+            site_url,
+            project_type,
+            project.inner.slug.unwrap_or_else(|| id.to_string())
+        ),
+        title: project.inner.name,
+        summary,
+        icon_url: project.inner.icon_url,
+        image_url: project
+            .gallery_items
+            .into_iter()
+            .find(|x| x.featured)
+            .map(|x| x.image_url),
+        author,
+        downloads: project.inner.downloads as u32,
+        follows: project.inner.follows as u32,
+        categories: project.categories,
+        color: project.inner.color,
+    })
+}
+
+pub async fn project_embed_get(
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let project = get_listed_project(&info.into_inner().0, &pool, &redis).await?;
+
+    Ok(HttpResponse::Ok().json(build_embed(project, &pool, &redis).await?))
+}
+
+/// An oEmbed provider for project pages, so link unfurlers can use the API directly
+pub async fn oembed_get(
+    web::Query(query): web::Query<OEmbedQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    // The spec has providers answer 501 for formats they don't support
+    if query.format.as_deref().is_some_and(|x| x != "json") {
+        return Ok(HttpResponse::NotImplemented().finish());
+    }
+
+    let site_url = dotenvy::var("SITE_URL").unwrap_or_default();
+    let site_host = url::Url::parse(&site_url)
+        .ok()
+        .and_then(|x| x.host_str().map(|x| x.to_string()));
+    let url = url::Url::parse(&query.url)
+        .map_err(|_| ApiError::InvalidInput("The URL is invalid!".to_string()))?;
+    if url.host_str().is_none() || url.host_str().map(|x| x.to_string()) != site_host {
+        return Err(ApiError::NotFound);
+    }
+
+    // Project pages are at `/{project type}/{id or slug}`, which may be followed by a tab
+    let mut segments = url.path_segments().into_iter().flatten();
+    let (Some(project_type), Some(id)) = (segments.next(), segments.next()) else {
+        return Err(ApiError::NotFound);
+    };
+    if project_type != "project"
+        && !ProjectType::list(&**pool, &redis)
+            .await?
+            .iter()
+            .any(|x| x == project_type)
+    {
+        return Err(ApiError::NotFound);
+    }
+
+    let project = get_listed_project(id, &pool, &redis).await?;
+    let embed = build_embed(project, &pool, &redis).await?;
+
+    let width = query.maxwidth.map_or(OEMBED_WIDTH, |x| x.min(OEMBED_WIDTH));
+    let height = query
+        .maxheight
+        .map_or(OEMBED_HEIGHT, |x| x.min(OEMBED_HEIGHT));
+
+    let icon = embed
+        .icon_url
+        .as_ref()
+        .map(|x| {
+            format!(
+                "<img src=\"{}\" alt=\"\" width=\"64\" height=\"64\">",
+                escape_attribute(x)
+            )
+        })
+        .unwrap_or_default();
+    let html = format!(
+        "<blockquote class=\"project-embed\" style=\"max-width:{}px;max-height:{}px\">{}<a href=\"{}\">{}</a><p>{}</p></blockquote>",
+        width,
+        height,
+        icon,
+        escape_attribute(&embed.url),
+        escape_attribute(&embed.title),
+        escape_attribute(&embed.summary),
+    );
+
+    Ok(HttpResponse::Ok().json(OEmbed {
+        type_: "rich".to_string(),
+        version: "1.0".to_string(),
+        title: embed.title,
+        author_name: embed.author.as_ref().map(|x| x.name.clone()),
+        author_url: embed.author.map(|x| x.url),
+        provider_name: "Modrinth".to_string(),
+        provider_url: site_url,
+        html,
+        width,
+        height,
+    }))
+}
@@ -0,0 +1,105 @@
+use crate::database::redis::RedisPool;
+use crate::search::SearchConfig;
+use actix_web::{web, HttpResponse};
+use futures::Future;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::time::Duration;
+
+// How long each dependency has to respond before it's considered down
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("live", web::get().to(health_live));
+    cfg.route("ready", web::get().to(health_ready));
+}
+
+#[derive(Serialize)]
+pub struct DependencyStatus {
+    pub up: bool,
+    /// Whether the API is reported as not ready while this dependency is down
+    pub critical: bool,
+}
+
+#[derive(Serialize)]
+pub struct ReadinessStatus {
+    pub ready: bool,
+    pub dependencies: HashMap<&'static str, DependencyStatus>,
+}
+
+/// Responds as long as the process is running, without checking any dependencies
+pub async fn health_live() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({ "live": true }))
+}
+
+/// Checks that Postgres, Redis and Meilisearch can be reached. Responds with 503 if any
+/// critical dependency is down. The checks only read, and don't go through any caches.
+pub async fn health_ready(
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    config: web::Data<SearchConfig>,
+) -> HttpResponse {
+    let (postgres, redis, meilisearch) = futures::join!(
+        check(async {
+            sqlx::query!("SELECT 1 AS one")
+                .fetch_one(&**pool)
+                .await
+                .is_ok()
+        }),
+        check(async {
+            let Ok(mut redis) = redis.connect().await else {
+                return false;
+            };
+
+            redis::cmd("PING")
+                .query_async::<_, String>(&mut redis.connection)
+                .await
+                .is_ok()
+        }),
+        check(config.make_client().is_healthy()),
+    );
+
+    let dependencies = HashMap::from([
+        (
+            "postgres",
+            DependencyStatus {
+                up: postgres,
+                critical: true,
+            },
+        ),
+        (
+            "redis",
+            DependencyStatus {
+                up: redis,
+                critical: true,
+            },
+        ),
+        (
+            "meilisearch",
+            DependencyStatus {
+                up: meilisearch,
+                critical: config.required,
+            },
+        ),
+    ]);
+
+    let ready = dependencies.values().all(|x| x.up || !x.critical);
+    let status = ReadinessStatus {
+        ready,
+        dependencies,
+    };
+
+    if ready {
+        HttpResponse::Ok().json(status)
+    } else {
+        HttpResponse::ServiceUnavailable().json(status)
+    }
+}
+
+// Runs a check, which fails if it doesn't finish in time
+async fn check(check: impl Future<Output = bool>) -> bool {
+    tokio::time::timeout(CHECK_TIMEOUT, check)
+        .await
+        .unwrap_or(false)
+}
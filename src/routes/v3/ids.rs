@@ -0,0 +1,235 @@
+use super::threads::is_authorized_thread;
+use crate::auth::checks::{is_visible_collection, is_visible_project, is_visible_version};
+use crate::auth::get_user_from_headers;
+use crate::database;
+use crate::database::models::ids as db_ids;
+use crate::database::redis::RedisPool;
+use crate::models::ids::base62_impl::{parse_base62, to_base62};
+use crate::models::pats::Scopes;
+use crate::models::projects::ProjectId;
+use crate::models::users::User;
+use crate::queue::session::AuthQueue;
+use crate::routes::ApiError;
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("id/{id}", web::get().to(id_resolve));
+}
+
+/// The kinds of items a bare ID can refer to, in the order they're checked
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdType {
+    Project,
+    Version,
+    User,
+    Organization,
+    Collection,
+    Report,
+    Thread,
+}
+
+impl IdType {
+    pub fn from_string(string: &str) -> Option<IdType> {
+        match string {
+            "project" => Some(IdType::Project),
+            "version" => Some(IdType::Version),
+            "user" => Some(IdType::User),
+            "organization" => Some(IdType::Organization),
+            "collection" => Some(IdType::Collection),
+            "report" => Some(IdType::Report),
+            "thread" => Some(IdType::Thread),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResolvedId {
+    #[serde(rename = "type")]
+    pub type_: IdType,
+    pub id: String,
+    /// A few fields identifying the item, like its name
+    pub summary: serde_json::Value,
+}
+
+pub async fn id_resolve(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_base62(&info.into_inner().0).map_err(|_| ApiError::NotFound)? as i64;
+
+    // Each kind of item has its own read scope, which is checked once its kind is known
+    let auth = get_user_from_headers(&req, &**pool, &redis, &session_queue, None)
+        .await
+        .ok();
+    let user_with_scope = |scope: Scopes| {
+        auth.as_ref()
+            .filter(|(scopes, _)| scopes.contains(scope))
+            .map(|(_, user)| user.clone())
+    };
+
+    let id_types = sqlx::query!(
+        "
+        SELECT id_type FROM (
+            SELECT 'project' id_type, 1 priority FROM mods WHERE id = $1
+            UNION ALL
+            SELECT 'version', 2 FROM versions WHERE id = $1
+            UNION ALL
+            SELECT 'user', 3 FROM users WHERE id = $1
+            UNION ALL
+            SELECT 'organization', 4 FROM organizations WHERE id = $1
+            UNION ALL
+            SELECT 'collection', 5 FROM collections WHERE id = $1
+            UNION ALL
+            SELECT 'report', 6 FROM reports WHERE id = $1
+            UNION ALL
+            SELECT 'thread', 7 FROM threads WHERE id = $1
+        ) ids
+        ORDER BY priority
+        ",
+        id,
+    )
+    .fetch_all(&**pool)
+    .await?
+    .into_iter()
+    .filter_map(|x| x.id_type.as_deref().and_then(IdType::from_string))
+    .collect::<Vec<_>>();
+
+    // Items the user can't see are skipped, so hidden items 404 rather than leaking their kind
+    for id_type in id_types {
+        let scope = match id_type {
+            IdType::Project => Scopes::PROJECT_READ,
+            IdType::Version => Scopes::VERSION_READ,
+            IdType::User => Scopes::USER_READ,
+            IdType::Organization => Scopes::ORGANIZATION_READ,
+            IdType::Collection => Scopes::COLLECTION_READ,
+            IdType::Report => Scopes::REPORT_READ,
+            IdType::Thread => Scopes::THREAD_READ,
+        };
+
+        if let Some(summary) =
+            visible_summary(id_type, id, &user_with_scope(scope), &pool, &redis).await?
+        {
+            return Ok(HttpResponse::Ok().json(ResolvedId {
+                type_: id_type,
+                id: to_base62(id as u64),
+                summary,
+            }));
+        }
+    }
+
+    Err(ApiError::NotFound)
+}
+
+// Applies the same visibility rules as the item's own GET route
+async fn visible_summary(
+    id_type: IdType,
+    id: i64,
+    user_option: &Option<User>,
+    pool: &web::Data<PgPool>,
+    redis: &RedisPool,
+) -> Result<Option<serde_json::Value>, ApiError> {
+    let summary = match id_type {
+        IdType::Project => {
+            let Some(project) =
+                database::models::Project::get_id(db_ids::ProjectId(id), &***pool, redis).await?
+            else {
+                return Ok(None);
+            };
+            if !is_visible_project(&project.inner, user_option, pool).await? {
+                return Ok(None);
+            }
+
+            json!({
+                "slug": project.inner.slug,
+                "name": project.inner.name,
+            })
+        }
+        IdType::Version => {
+            let Some(version) =
+                database::models::Version::get(db_ids::VersionId(id), &***pool, redis).await?
+            else {
+                return Ok(None);
+            };
+            if !is_visible_version(&version.inner, user_option, pool, redis).await? {
+                return Ok(None);
+            }
+
+            json!({
+                "project_id": ProjectId::from(version.inner.project_id),
+                "name": version.inner.name,
+                "version_number": version.inner.version_number,
+            })
+        }
+        IdType::User => {
+            let Some(user) =
+                database::models::User::get_id(db_ids::UserId(id), &***pool, redis).await?
+            else {
+                return Ok(None);
+            };
+
+            json!({ "username": user.username })
+        }
+        IdType::Organization => {
+            let Some(organization) =
+                database::models::Organization::get_id(db_ids::OrganizationId(id), &***pool, redis)
+                    .await?
+            else {
+                return Ok(None);
+            };
+
+            json!({
+                "slug": organization.slug,
+                "name": organization.name,
+            })
+        }
+        IdType::Collection => {
+            let Some(collection) =
+                database::models::Collection::get(db_ids::CollectionId(id), &***pool, redis)
+                    .await?
+            else {
+                return Ok(None);
+            };
+            if !is_visible_collection(&collection, user_option).await? {
+                return Ok(None);
+            }
+
+            json!({ "name": collection.name })
+        }
+        IdType::Report => {
+            let (Some(report), Some(user)) = (
+                database::models::report_item::Report::get(db_ids::ReportId(id), &***pool).await?,
+                user_option,
+            ) else {
+                return Ok(None);
+            };
+            if !user.role.is_mod() && report.reporter != user.id.into() {
+                return Ok(None);
+            }
+
+            json!({ "report_type": report.report_type })
+        }
+        IdType::Thread => {
+            let (Some(thread), Some(user)) = (
+                database::models::Thread::get(db_ids::ThreadId(id), &***pool).await?,
+                user_option,
+            ) else {
+                return Ok(None);
+            };
+            if !is_authorized_thread(&thread, user, pool).await? {
+                return Ok(None);
+            }
+
+            json!({ "thread_type": thread.type_ })
+        }
+    };
+
+    Ok(Some(summary))
+}
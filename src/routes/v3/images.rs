@@ -1,18 +1,30 @@
 use std::sync::Arc;
 
 use crate::auth::checks::{is_team_member_project, is_team_member_version};
-use crate::auth::get_user_from_headers;
+use crate::auth::validate::{check_pat_not_project_restricted, check_pat_project_restriction};
+use crate::auth::{check_is_moderator_from_headers, get_user_from_headers};
 use crate::database;
+use crate::database::models::audit_log_item::AuditLogBuilder;
 use crate::database::models::{project_item, report_item, thread_item, version_item};
 use crate::database::redis::RedisPool;
 use crate::file_hosting::FileHost;
+use crate::models::audit_log::{AuditAction, AuditTargetType};
 use crate::models::ids::{ThreadMessageId, VersionId};
-use crate::models::images::{Image, ImageContext};
+use crate::models::images::{Image, ImageContext, ImageId, ImageModerationStatus};
+use crate::models::pats::Scopes;
+use crate::models::projects::ProjectStatus;
 use crate::models::reports::ReportId;
 use crate::queue::session::AuthQueue;
+use crate::routes::v3::audit_log::request_ip;
 use crate::routes::ApiError;
+use crate::search::indexing::spawn_reindex_projects;
+use crate::search::SearchConfig;
+use crate::util::img;
 use crate::util::routes::read_from_payload;
+use crate::util::signed_urls::{private_path, verify_signature, PRIVATE_PREFIX};
 use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use log::warn;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 
@@ -20,6 +32,11 @@ use super::threads::is_authorized_thread;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.route("image", web::post().to(images_add));
+    cfg.route(
+        "image/{id}/moderation",
+        web::patch().to(image_moderation_edit),
+    );
+    cfg.route("image/signed/{path:.*}", web::get().to(signed_image_get));
 }
 
 #[derive(Serialize, Deserialize)]
@@ -63,6 +80,7 @@ pub async fn images_add(
                 if let Some(id) = data.project_id {
                     let project = project_item::Project::get(&id, &**pool, &redis).await?;
                     if let Some(project) = project {
+                        check_pat_project_restriction(&req, project.inner.id)?;
                         if is_team_member_project(&project.inner, &Some(user.clone()), &pool)
                             .await?
                         {
@@ -84,6 +102,7 @@ pub async fn images_add(
                 if let Some(id) = data.version_id {
                     let version = version_item::Version::get(id.into(), &**pool, &redis).await?;
                     if let Some(version) = version {
+                        check_pat_project_restriction(&req, version.inner.project_id)?;
                         if is_team_member_version(
                             &version.inner,
                             &Some(user.clone()),
@@ -164,27 +183,50 @@ pub async fn images_add(
             }
         }
 
+        // Project restricted PATs can only upload images for their projects
+        if !matches!(
+            context,
+            ImageContext::Project {
+                project_id: Some(_)
+            } | ImageContext::Version {
+                version_id: Some(_)
+            }
+        ) {
+            check_pat_not_project_restricted(&req)?;
+        }
+
         // Upload the image to the file host
         let bytes =
             read_from_payload(&mut payload, 1_048_576, "Icons must be smaller than 1MiB").await?;
 
+        crate::util::img::check_image_size(&bytes)?;
+
         let hash = sha1::Sha1::from(&bytes).hexdigest();
+        let bytes = bytes.freeze();
         let upload_data = file_host
             .upload_file(
                 content_type,
                 &format!("data/cached_images/{}.{}", hash, data.ext),
-                bytes.freeze(),
+                bytes.clone(),
             )
             .await?;
+        let variants = crate::util::img::upload_image_variants(
+            bytes,
+            &format!("data/cached_images/{}", hash),
+            &***file_host,
+        )
+        .await;
 
         let mut transaction = pool.begin().await?;
 
         let db_image: database::models::Image = database::models::Image {
             id: database::models::generate_image_id(&mut transaction).await?,
             url: format!("{}/{}", cdn_url, upload_data.file_name),
+            variants: crate::util::img::get_variant_urls(&variants, &cdn_url),
             size: upload_data.content_length as u64,
             created: chrono::Utc::now(),
             owner_id: database::models::UserId::from(user.id),
+            moderation_status: ImageModerationStatus::Approved,
             context: context.context_as_str().to_string(),
             project_id: if let ImageContext::Project {
                 project_id: Some(id),
@@ -226,9 +268,11 @@ pub async fn images_add(
         let image = Image {
             id: db_image.id.into(),
             url: db_image.url,
+            variants: db_image.variants,
             size: db_image.size,
             created: db_image.created,
             owner_id: db_image.owner_id.into(),
+            moderation_status: db_image.moderation_status,
             context,
         };
 
@@ -241,3 +285,278 @@ pub async fn images_add(
         ))
     }
 }
+
+#[derive(Deserialize)]
+pub struct EditImageModeration {
+    /// `approved` to show the image, or `quarantined` to hide it and replace its files with a
+    /// placeholder
+    pub status: ImageModerationStatus,
+}
+
+pub async fn image_moderation_edit(
+    req: HttpRequest,
+    info: web::Path<(ImageId,)>,
+    edit: web::Json<EditImageModeration>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
+    file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = check_is_moderator_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?;
+
+    let image = database::models::Image::get(info.into_inner().0.into(), &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if edit.status == ImageModerationStatus::Pending {
+        return Err(ApiError::InvalidInput(
+            "Images can only be approved or quarantined.".to_string(),
+        ));
+    }
+    if image.moderation_status == ImageModerationStatus::Quarantined {
+        return Err(ApiError::InvalidInput(
+            "Quarantined images can't be restored, as their files were replaced.".to_string(),
+        ));
+    }
+    if image.moderation_status == edit.status {
+        return Ok(HttpResponse::NoContent().body(""));
+    }
+
+    if edit.status == ImageModerationStatus::Quarantined {
+        let cdn_url = dotenvy::var("CDN_URL")?;
+        img::replace_with_placeholder(&image.url, &image.variants, &cdn_url, &***file_host).await?;
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    database::models::Image::set_moderation_status(image.id, edit.status, &mut transaction).await?;
+
+    AuditLogBuilder {
+        actor_id: user.id.into(),
+        action: AuditAction::ImageModeration,
+        target_type: AuditTargetType::Image,
+        target_id: image.id.0,
+        project_id: image.project_id,
+        organization_id: None,
+        summary: serde_json::json!({
+            "old_status": image.moderation_status,
+            "new_status": edit.status,
+        }),
+        ip: request_ip(&req),
+    }
+    .insert(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    database::models::Image::clear_cache(image.id, &redis).await?;
+    // The project's gallery and icon show the image depending on its status
+    if let Some(project_id) = image.project_id {
+        if let Some(project) = project_item::Project::get_id(project_id, &**pool, &redis).await? {
+            project_item::Project::clear_cache(project.inner.id, project.inner.slug, None, &redis)
+                .await?;
+
+            spawn_reindex_projects(
+                vec![project.inner.id],
+                pool.get_ref().clone(),
+                redis.get_ref().clone(),
+                search_config.get_ref().clone(),
+            );
+        }
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SignedImageQuery {
+    pub expires: i64,
+    pub signature: String,
+}
+
+/// Serves a private image to the holder of a signed URL to it. Like the download links of data
+/// exports, the signature is checked instead of the user's authorization.
+pub async fn signed_image_get(
+    info: web::Path<(String,)>,
+    web::Query(query): web::Query<SignedImageQuery>,
+    file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
+) -> Result<HttpResponse, ApiError> {
+    let path = info.into_inner().0;
+
+    if !verify_signature(&path, query.expires, &query.signature, Utc::now())? {
+        return Err(ApiError::NotFound);
+    }
+
+    let content_type = path
+        .rsplit('.')
+        .next()
+        .and_then(crate::util::ext::get_image_content_type)
+        .ok_or(ApiError::NotFound)?;
+    let bytes = file_host
+        .download_file(&path)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .append_header(("Cache-Control", "private"))
+        .body(bytes))
+}
+
+/// Moves the private images of projects which have been made public to the public prefix, and
+/// points their URLs at the public copies
+pub async fn publish_private_images(
+    pool: &PgPool,
+    redis: &RedisPool,
+    search_config: &SearchConfig,
+    file_host: &(dyn FileHost + Send + Sync),
+) -> Result<(), ApiError> {
+    let cdn_url = dotenvy::var("CDN_URL")?;
+    let private_url = format!("{cdn_url}/{PRIVATE_PREFIX}");
+
+    let project_ids = sqlx::query!(
+        "
+        SELECT m.id FROM mods m
+        WHERE NOT (m.status = ANY($1)) AND (
+            starts_with(m.icon_url, $2)
+            OR EXISTS (
+                SELECT 1 FROM mods_gallery mg
+                WHERE mg.mod_id = m.id AND starts_with(mg.image_url, $2)
+            )
+        )
+        ",
+        &*ProjectStatus::iterator()
+            .filter(|x| x.is_hidden())
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>(),
+        private_url,
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| database::models::ProjectId(x.id))
+    .collect::<Vec<_>>();
+
+    let mut published = Vec::new();
+    for project_id in project_ids {
+        match publish_project_images(project_id, &cdn_url, pool, redis, file_host).await {
+            Ok(()) => published.push(project_id),
+            Err(e) => warn!(
+                "Publishing the images of project {} failed: {:?}",
+                project_id.0, e
+            ),
+        }
+    }
+
+    if !published.is_empty() {
+        spawn_reindex_projects(
+            published,
+            pool.clone(),
+            redis.clone(),
+            search_config.clone(),
+        );
+    }
+
+    Ok(())
+}
+
+async fn publish_project_images(
+    project_id: database::models::ProjectId,
+    cdn_url: &str,
+    pool: &PgPool,
+    redis: &RedisPool,
+    file_host: &(dyn FileHost + Send + Sync),
+) -> Result<(), ApiError> {
+    let Some(project) = project_item::Project::get_id(project_id, pool, redis).await? else {
+        return Ok(());
+    };
+
+    let urls = project
+        .inner
+        .icon_url
+        .iter()
+        .chain(project.inner.icon_variants.values())
+        .chain(
+            project
+                .gallery_items
+                .iter()
+                .flat_map(|x| std::iter::once(&x.image_url).chain(x.variants.values())),
+        );
+    let mut private_paths = Vec::new();
+    for path in urls.filter_map(|x| private_path(x, cdn_url)) {
+        let content_type = path
+            .rsplit('.')
+            .next()
+            .and_then(crate::util::ext::get_image_content_type)
+            .unwrap_or("application/octet-stream");
+        let bytes = file_host.download_file(path).await?;
+        file_host
+            .upload_file(content_type, &path[PRIVATE_PREFIX.len()..], bytes)
+            .await?;
+        private_paths.push(path.to_string());
+    }
+
+    let private_url = format!("{cdn_url}/{PRIVATE_PREFIX}");
+    let public_url = format!("{cdn_url}/");
+    let mut transaction = pool.begin().await?;
+    sqlx::query!(
+        "
+        UPDATE mods
+        SET icon_url = REPLACE(icon_url, $2, $3),
+            icon_variants = REPLACE(icon_variants::text, $2, $3)::jsonb
+        WHERE id = $1
+        ",
+        project_id as database::models::ProjectId,
+        private_url,
+        public_url,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+        "
+        UPDATE mods_gallery
+        SET image_url = REPLACE(image_url, $2, $3),
+            variants = REPLACE(variants::text, $2, $3)::jsonb
+        WHERE mod_id = $1
+        ",
+        project_id as database::models::ProjectId,
+        private_url,
+        public_url,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    let image_ids = sqlx::query!(
+        "
+        UPDATE uploaded_images
+        SET url = REPLACE(url, $2, $3),
+            variants = REPLACE(variants::text, $2, $3)::jsonb
+        WHERE mod_id = $1 AND starts_with(url, $2)
+        RETURNING id
+        ",
+        project_id as database::models::ProjectId,
+        private_url,
+        public_url,
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+
+    for image in image_ids {
+        database::models::Image::clear_cache(database::models::ImageId(image.id), redis).await?;
+    }
+    project_item::Project::clear_cache(project_id, project.inner.slug, None, redis).await?;
+
+    for path in private_paths {
+        file_host.delete_file_version("", &path).await?;
+    }
+
+    Ok(())
+}
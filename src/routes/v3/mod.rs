@@ -1,35 +1,74 @@
 pub use super::ApiError;
+use crate::metrics::RequestMetrics;
+use crate::ratelimit::middleware::RateLimiter;
+use crate::util::compress::Compression;
 use crate::util::cors::default_cors;
+use crate::util::maintenance::ReadOnlyMode;
 use actix_web::{web, HttpResponse};
 use serde_json::json;
 
+pub mod admin;
 pub mod analytics_get;
+pub mod audit_log;
 pub mod collections;
+pub mod embeds;
+pub mod health;
+pub mod ids;
 pub mod images;
 pub mod moderation;
 pub mod notifications;
+pub mod organization_announcements;
+pub mod organization_invite_links;
+pub mod organization_payout_splits;
+pub mod organization_role_templates;
 pub mod organizations;
 pub mod payouts;
+pub mod project_blocks;
 pub mod project_creation;
+pub mod project_history;
+pub mod project_transfers;
+pub mod project_translations;
+pub mod project_wiki;
 pub mod projects;
+pub mod recommendations;
 pub mod reports;
+pub mod sessions;
 pub mod statistics;
 pub mod tags;
 pub mod teams;
+pub mod thread_socket;
 pub mod threads;
+pub mod user_blocks;
+pub mod user_connections;
+pub mod user_exports;
+pub mod user_payouts;
 pub mod users;
 pub mod version_creation;
 pub mod version_file;
 pub mod versions;
+pub mod webhooks;
 
 pub mod oauth_clients;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
+    // Registered before the rest of the API, so that health checks aren't rate limited
+    cfg.service(
+        web::scope("v3/health")
+            .wrap(default_cors())
+            .configure(health::config),
+    );
     cfg.service(
         web::scope("v3")
+            .wrap(Compression::from_env())
+            .wrap(RateLimiter)
+            .wrap(ReadOnlyMode)
             .wrap(default_cors())
+            .wrap(RequestMetrics)
+            .configure(admin::config)
             .configure(analytics_get::config)
             .configure(collections::config)
+            .configure(embeds::config)
+            .configure(ids::config)
             .configure(images::config)
             .configure(moderation::config)
             .configure(notifications::config)
@@ -37,14 +76,18 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .configure(project_creation::config)
             .configure(projects::config)
             .configure(reports::config)
+            .configure(sessions::config)
             .configure(statistics::config)
             .configure(tags::config)
             .configure(teams::config)
+            .configure(thread_socket::config)
             .configure(threads::config)
             .configure(users::config)
             .configure(version_file::config)
             .configure(payouts::config)
-            .configure(versions::config),
+            .configure(versions::config)
+            // The rest of the token routes are internal, but their usage is part of the API
+            .service(crate::routes::internal::pats::get_pat_usage),
     );
 }
 
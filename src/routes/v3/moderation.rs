@@ -1,32 +1,114 @@
+use std::collections::{BTreeMap, HashMap};
+
 use super::ApiError;
+use crate::auth::get_user_from_headers;
 use crate::database;
+use crate::database::models::moderation_review_item;
+use crate::database::models::project_item::{ModerationQueueExportRow, ModerationQueueSort};
 use crate::database::redis::RedisPool;
-use crate::models::projects::ProjectStatus;
+use crate::models::ids::ProjectId;
+use crate::models::images::Image;
+use crate::models::moderation::{ChecklistEntry, ChecklistItem, ModerationReview};
+use crate::models::projects::Project;
 use crate::queue::session::AuthQueue;
+use crate::util::csv;
+use crate::util::validate::validation_errors_to_string;
 use crate::{auth::check_is_moderator_from_headers, models::pats::Scopes};
 use actix_web::{web, HttpRequest, HttpResponse};
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use validator::Validate;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.route("moderation/projects", web::get().to(get_projects));
+    cfg.route("moderation/images", web::get().to(get_images));
 }
 
-#[derive(Deserialize)]
-pub struct ResultCount {
+#[derive(Deserialize, Validate)]
+pub struct ModerationQueueQuery {
+    #[serde(default)]
+    pub sort: ModerationQueueSort,
+    pub project_type: Option<String>,
     #[serde(default = "default_count")]
+    #[validate(range(min = 1, max = 1000))]
     pub count: i16,
+    #[serde(default)]
+    #[validate(range(min = 0))]
+    pub offset: i64,
+    /// `csv` to export the whole queue as CSV, ignoring `count` and `offset`
+    pub format: Option<String>,
 }
 
 fn default_count() -> i16 {
     100
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct ModerationQueue {
+    /// The total number of projects in the queue matching the filters
+    pub total: i64,
+    pub projects: Vec<ModerationQueueProject>,
+}
+
+/// The columns of moderation queue exports, in order:
+/// - `id`: the project's id
+/// - `slug`: the project's slug, if it has one
+/// - `name`: the project's name
+/// - `queued`: when the project entered the queue, in RFC 3339
+/// - `queue_age`: how long the project has been in the queue, in seconds
+/// - `downloads`: the project's downloads
+/// - `owner`: the username of the project's owner, unless it's in an organization
+/// - `organization`: the name of the project's organization, if it has one
+/// - `open_reports`: the number of open reports against members of the project's team
+/// - `flagged_reupload`: whether any of the project's versions are flagged as reuploads
+const QUEUE_EXPORT_COLUMNS: &[&str] = &[
+    "id",
+    "slug",
+    "name",
+    "queued",
+    "queue_age",
+    "downloads",
+    "owner",
+    "organization",
+    "open_reports",
+    "flagged_reupload",
+];
+
+fn queue_export_record(row: ModerationQueueExportRow) -> Vec<String> {
+    vec![
+        ProjectId::from(row.id).to_string(),
+        row.slug.unwrap_or_default(),
+        row.name,
+        row.queued.to_rfc3339(),
+        (Utc::now() - row.queued).num_seconds().to_string(),
+        row.downloads.to_string(),
+        row.owner.unwrap_or_default(),
+        row.organization.unwrap_or_default(),
+        row.open_reports.to_string(),
+        row.flagged_reupload.to_string(),
+    ]
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModerationQueueProject {
+    pub project: Project,
+    /// When the project entered the queue
+    pub queued: DateTime<Utc>,
+    /// How long the project has been in the queue, in seconds
+    pub queue_age: i64,
+    /// The number of open reports against members of the project's team
+    pub open_reports: i64,
+    /// Whether any of the project's versions have files which were already uploaded to a project
+    /// the uploader isn't a member of
+    pub flagged_reupload: bool,
+}
+
 pub async fn get_projects(
     req: HttpRequest,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
-    count: web::Query<ResultCount>,
+    query: web::Query<ModerationQueueQuery>,
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
     check_is_moderator_from_headers(
@@ -38,28 +120,250 @@ pub async fn get_projects(
     )
     .await?;
 
-    use futures::stream::TryStreamExt;
-
-    let project_ids = sqlx::query!(
-        "
-        SELECT id FROM mods
-        WHERE status = $1
-        ORDER BY queued ASC
-        LIMIT $2;
-        ",
-        ProjectStatus::Processing.as_str(),
-        count.count as i64
+    query
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    if csv::wants_csv(&req, query.format.as_deref()) {
+        let mut transaction = pool.begin().await?;
+        database::Project::declare_moderation_queue_export(
+            csv::EXPORT_CURSOR,
+            query.project_type.as_deref().filter(|x| !x.is_empty()),
+            query.sort,
+            &mut transaction,
+        )
+        .await?;
+
+        return Ok(csv::cursor_response(
+            "moderation-queue.csv",
+            QUEUE_EXPORT_COLUMNS,
+            transaction,
+            queue_export_record,
+        ));
+    }
+
+    let (total, entries) = database::Project::get_moderation_queue(
+        query.project_type.as_deref().filter(|x| !x.is_empty()),
+        query.sort,
+        query.count as i64,
+        query.offset,
+        &**pool,
     )
-    .fetch_many(&**pool)
-    .try_filter_map(|e| async { Ok(e.right().map(|m| database::models::ProjectId(m.id))) })
-    .try_collect::<Vec<database::models::ProjectId>>()
     .await?;
 
-    let projects: Vec<_> = database::Project::get_many_ids(&project_ids, &**pool, &redis)
+    let mut projects: HashMap<_, _> = database::Project::get_many_ids(
+        &entries.iter().map(|x| x.id).collect::<Vec<_>>(),
+        &**pool,
+        &redis,
+    )
+    .await?
+    .into_iter()
+    .map(|x| (x.inner.id, x))
+    .collect();
+
+    let now = Utc::now();
+    let projects = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let project = projects.remove(&entry.id)?;
+
+            Some(ModerationQueueProject {
+                project: project.into(),
+                queued: entry.queued,
+                queue_age: (now - entry.queued).num_seconds(),
+                open_reports: entry.open_reports,
+                flagged_reupload: entry.flagged_reupload,
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ModerationQueue { total, projects }))
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ImageQueueQuery {
+    #[serde(default = "default_count")]
+    #[validate(range(min = 1, max = 1000))]
+    pub count: i16,
+    #[serde(default)]
+    #[validate(range(min = 0))]
+    pub offset: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ImageQueue {
+    /// The total number of images waiting for approval
+    pub total: i64,
+    pub images: Vec<ModerationQueueImage>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModerationQueueImage {
+    pub image: Image,
+    /// The project the image was uploaded to, if it was uploaded to one and it wasn't deleted
+    pub project: Option<Project>,
+}
+
+/// Lists the images waiting for a moderator's approval, oldest first
+pub async fn get_images(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    query: web::Query<ImageQueueQuery>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_is_moderator_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?;
+
+    query
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let (total, image_ids) =
+        database::models::Image::get_pending(query.count as i64, query.offset, &**pool).await?;
+
+    let mut images: HashMap<_, _> = database::models::Image::get_many(&image_ids, &**pool, &redis)
         .await?
         .into_iter()
-        .map(crate::models::projects::Project::from)
+        .map(|x| (x.id, x))
         .collect();
+    let projects: HashMap<_, _> = database::Project::get_many_ids(
+        &images
+            .values()
+            .filter_map(|x| x.project_id)
+            .collect::<Vec<_>>(),
+        &**pool,
+        &redis,
+    )
+    .await?
+    .into_iter()
+    .map(|x| (x.inner.id, x))
+    .collect();
+
+    let images = image_ids
+        .into_iter()
+        .filter_map(|id| {
+            let image = images.remove(&id)?;
+            let project = image
+                .project_id
+                .and_then(|x| projects.get(&x).cloned())
+                .map(Project::from);
+
+            Some(ModerationQueueImage {
+                image: image.into(),
+                project,
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ImageQueue { total, images }))
+}
+
+#[derive(Deserialize)]
+pub struct EditModerationReview {
+    /// The checked items. Items which are left out are removed from the review.
+    pub items: BTreeMap<ChecklistItem, ChecklistEntry>,
+}
+
+/// Saves the checklist of the project's moderation pass in progress, starting one if needed
+pub async fn review_edit(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    edit: web::Json<EditModerationReview>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = check_is_moderator_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?;
+
+    let project = database::Project::get(&info.into_inner().0, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let edit = edit.into_inner();
+    if edit
+        .items
+        .values()
+        .filter_map(|x| x.note.as_ref())
+        .any(|x| x.len() > 2048)
+    {
+        return Err(ApiError::InvalidInput(
+            "Review notes can be at most 2048 characters long!".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+    moderation_review_item::ModerationReview::upsert(
+        project.inner.id,
+        user.id.into(),
+        &edit.items,
+        &mut transaction,
+    )
+    .await?;
+    let review =
+        moderation_review_item::ModerationReview::get_latest(project.inner.id, &mut *transaction)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ModerationReview::from(review)))
+}
+
+/// Gets the project's latest review. Moderators can always see it, and the project's team
+/// can once the project has been approved or rejected.
+pub async fn review_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?
+    .1;
+
+    let project = database::Project::get(&info.into_inner().0, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let review = moderation_review_item::ModerationReview::get_latest(project.inner.id, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if !user.role.is_mod() {
+        let (team_member, organization_team_member) =
+            database::models::TeamMember::get_for_project_permissions(
+                &project.inner,
+                user.id.into(),
+                &**pool,
+            )
+            .await?;
+
+        // Reviews in progress are hidden from the team
+        if (team_member.is_none() && organization_team_member.is_none()) || review.decided.is_none()
+        {
+            return Err(ApiError::NotFound);
+        }
+    }
 
-    Ok(HttpResponse::Ok().json(projects))
+    Ok(HttpResponse::Ok().json(ModerationReview::from(review)))
 }
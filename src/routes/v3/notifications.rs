@@ -2,11 +2,13 @@ use crate::auth::get_user_from_headers;
 use crate::database;
 use crate::database::redis::RedisPool;
 use crate::models::ids::NotificationId;
-use crate::models::notifications::Notification;
+use crate::models::notifications::{Notification, NotificationType};
 use crate::models::pats::Scopes;
+use crate::models::users::User;
 use crate::queue::session::AuthQueue;
 use crate::routes::ApiError;
 use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 
@@ -14,6 +16,10 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.route("notifications", web::get().to(notifications_get));
     cfg.route("notifications", web::patch().to(notifications_read));
     cfg.route("notifications", web::delete().to(notifications_delete));
+    cfg.route(
+        "notifications/read-all",
+        web::patch().to(notifications_read_all),
+    );
 
     cfg.service(
         web::scope("notification")
@@ -28,6 +34,27 @@ pub struct NotificationIds {
     pub ids: String,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct NotificationFilters {
+    #[serde(rename = "type")]
+    pub notification_type: Option<NotificationType>,
+    pub before: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NotificationsDeleteQuery {
+    /// The notifications to delete. If not set, all of the user's notifications matching the filters are deleted
+    pub ids: Option<String>,
+    #[serde(rename = "type")]
+    pub notification_type: Option<NotificationType>,
+    pub before: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NotificationsAffected {
+    pub count: u64,
+}
+
 pub async fn notifications_get(
     req: HttpRequest,
     web::Query(ids): web::Query<NotificationIds>,
@@ -242,7 +269,7 @@ pub async fn notifications_read(
 
 pub async fn notifications_delete(
     req: HttpRequest,
-    web::Query(ids): web::Query<NotificationIds>,
+    web::Query(query): web::Query<NotificationsDeleteQuery>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
@@ -257,7 +284,15 @@ pub async fn notifications_delete(
     .await?
     .1;
 
-    let notification_ids = serde_json::from_str::<Vec<NotificationId>>(&ids.ids)?
+    let Some(ids) = query.ids else {
+        let filters = NotificationFilters {
+            notification_type: query.notification_type,
+            before: query.before,
+        };
+        return notifications_delete_all(user, filters, pool, redis).await;
+    };
+
+    let notification_ids = serde_json::from_str::<Vec<NotificationId>>(&ids)?
         .into_iter()
         .map(|x| x.into())
         .collect::<Vec<_>>();
@@ -287,3 +322,68 @@ pub async fn notifications_delete(
 
     Ok(HttpResponse::NoContent().body(""))
 }
+
+async fn notifications_delete_all(
+    user: User,
+    filters: NotificationFilters,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+) -> Result<HttpResponse, ApiError> {
+    let mut transaction = pool.begin().await?;
+
+    let count = database::models::notification_item::Notification::remove_all_user(
+        user.id.into(),
+        filters.notification_type,
+        filters.before,
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+    // Cleared after committing, so a request in between can't cache the old notifications again
+    database::models::notification_item::Notification::clear_user_notifications_cache(
+        &[user.id.into()],
+        &redis,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(NotificationsAffected { count }))
+}
+
+pub async fn notifications_read_all(
+    req: HttpRequest,
+    web::Query(filters): web::Query<NotificationFilters>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::NOTIFICATION_WRITE]),
+    )
+    .await?
+    .1;
+
+    let mut transaction = pool.begin().await?;
+
+    let count = database::models::notification_item::Notification::read_all_user(
+        user.id.into(),
+        filters.notification_type,
+        filters.before,
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+    // Cleared after committing, so a request in between can't cache the old notifications again
+    database::models::notification_item::Notification::clear_user_notifications_cache(
+        &[user.id.into()],
+        &redis,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(NotificationsAffected { count }))
+}
@@ -13,26 +13,34 @@ use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use validator::Validate;
 
+use super::audit_log::{record_change, request_ip};
 use super::ApiError;
 use crate::{
     auth::checks::ValidateAllAuthorized,
     file_hosting::FileHost,
-    models::{ids::base62_impl::parse_base62, oauth_clients::DeleteOAuthClientQueryParam},
+    models::{
+        ids::base62_impl::parse_base62,
+        oauth_clients::{DeleteOAuthClientQueryParam, GetUserOAuthClientsQueryParam},
+        teams::OrganizationPermissions,
+    },
     util::routes::read_from_payload,
 };
 use crate::{
     auth::{checks::ValidateAuthorized, get_user_from_headers},
     database::{
         models::{
+            audit_log_item::AuditLogBuilder,
             generate_oauth_client_id, generate_oauth_redirect_id,
             oauth_client_authorization_item::OAuthClientAuthorization,
             oauth_client_item::{OAuthClient, OAuthRedirectUri},
-            DatabaseError, OAuthClientId, User,
+            DatabaseError, OAuthClientId, Organization, OrganizationId, TeamMember, User,
         },
         redis::RedisPool,
     },
     models::{
         self,
+        audit_log::{AuditAction, AuditTargetType},
+        ids::OrganizationId as ApiOrganizationId,
         oauth_clients::{GetOAuthClientsRequest, OAuthClientCreationResult},
         pats::Scopes,
     },
@@ -63,6 +71,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
 pub async fn get_user_clients(
     req: HttpRequest,
     info: web::Path<String>,
+    web::Query(query): web::Query<GetUserOAuthClientsQueryParam>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
@@ -80,10 +89,37 @@ pub async fn get_user_clients(
     let target_user = User::get(&info.into_inner(), &**pool, &redis).await?;
 
     if let Some(target_user) = target_user {
-        let clients = OAuthClient::get_all_user_clients(target_user.id, &**pool).await?;
-        clients
-            .iter()
-            .validate_all_authorized(Some(&current_user))?;
+        // If an organization is specified, the clients owned by that organization are listed instead
+        let clients = if let Some(organization_id) = query.organization_id {
+            let organization_id = organization_id.into();
+            if !can_manage_organization_clients(organization_id, &current_user, &pool).await? {
+                return Err(ApiError::CustomAuthentication(
+                    "You don't have permission to view this organization's OAuth applications!"
+                        .to_string(),
+                ));
+            }
+
+            // Only the organizations the target user is a member of are listed under them
+            let is_member = TeamMember::get_from_user_id_organization(
+                organization_id,
+                target_user.id,
+                false,
+                &**pool,
+            )
+            .await?
+            .is_some();
+            if !is_member {
+                return Err(ApiError::NotFound);
+            }
+
+            OAuthClient::get_all_organization_clients(organization_id, &**pool).await?
+        } else {
+            let clients = OAuthClient::get_all_user_clients(target_user.id, &**pool).await?;
+            clients
+                .iter()
+                .validate_all_authorized(Some(&current_user))?;
+            clients
+        };
 
         let response = clients
             .into_iter()
@@ -158,6 +194,9 @@ pub struct NewOAuthApp {
 
     #[validate(length(max = 255))]
     pub description: Option<String>,
+
+    // If set, the client is owned by this organization rather than the creating user
+    pub organization_id: Option<ApiOrganizationId>,
 }
 
 #[post("app")]
@@ -182,6 +221,25 @@ pub async fn oauth_client_create<'a>(
         .validate()
         .map_err(|e| CreateError::ValidationError(validation_errors_to_string(e, None)))?;
 
+    let organization_id: Option<OrganizationId> = new_oauth_app.organization_id.map(|x| x.into());
+    if let Some(organization_id) = organization_id {
+        if Organization::get_id(organization_id, &**pool, &redis)
+            .await?
+            .is_none()
+        {
+            return Err(CreateError::InvalidInput(
+                "The specified organization does not exist!".to_string(),
+            ));
+        }
+
+        if !can_manage_organization_clients(organization_id, &current_user, &pool).await? {
+            return Err(CreateError::CustomAuthenticationError(
+                "You don't have permission to create OAuth applications for this organization!"
+                    .to_string(),
+            ));
+        }
+    }
+
     let mut transaction = pool.begin().await?;
 
     let client_id = generate_oauth_client_id(&mut transaction).await?;
@@ -200,6 +258,7 @@ pub async fn oauth_client_create<'a>(
         redirect_uris,
         created: Utc::now(),
         created_by: current_user.id.into(),
+        organization_id,
         url: new_oauth_app.url.clone(),
         description: new_oauth_app.description.clone(),
         secret_hash: client_secret_hash,
@@ -236,7 +295,7 @@ pub async fn oauth_client_delete<'a>(
 
     let client = OAuthClient::get(client_id.into_inner().into(), &**pool).await?;
     if let Some(client) = client {
-        client.validate_authorized(Some(&current_user))?;
+        validate_client_management(&client, &current_user, &pool).await?;
         OAuthClient::remove(client.id, &**pool).await?;
 
         Ok(HttpResponse::NoContent().body(""))
@@ -305,7 +364,7 @@ pub async fn oauth_client_edit(
     }
 
     if let Some(existing_client) = OAuthClient::get(client_id.into_inner().into(), &**pool).await? {
-        existing_client.validate_authorized(Some(&current_user))?;
+        validate_client_management(&existing_client, &current_user, &pool).await?;
 
         let mut updated_client = existing_client.clone();
         let OAuthClientEdit {
@@ -316,6 +375,39 @@ pub async fn oauth_client_edit(
             url,
             description,
         } = client_updates.into_inner();
+
+        let mut summary = serde_json::Map::new();
+        record_change(&mut summary, "name", &existing_client.name, name.as_ref());
+        record_change(
+            &mut summary,
+            "icon_url",
+            &existing_client.icon_url,
+            icon_url.as_ref(),
+        );
+        record_change(
+            &mut summary,
+            "max_scopes",
+            existing_client.max_scopes,
+            max_scopes,
+        );
+        record_change(
+            &mut summary,
+            "redirect_uris",
+            existing_client
+                .redirect_uris
+                .iter()
+                .map(|x| x.uri.clone())
+                .collect::<Vec<_>>(),
+            redirect_uris.clone(),
+        );
+        record_change(&mut summary, "url", &existing_client.url, url.as_ref());
+        record_change(
+            &mut summary,
+            "description",
+            &existing_client.description,
+            description.as_ref(),
+        );
+
         if let Some(name) = name {
             updated_client.name = name;
         }
@@ -345,6 +437,19 @@ pub async fn oauth_client_edit(
             edit_redirects(redirects, &existing_client, &mut transaction).await?;
         }
 
+        AuditLogBuilder {
+            actor_id: current_user.id.into(),
+            action: AuditAction::OAuthClientEdit,
+            target_type: AuditTargetType::OAuthClient,
+            target_id: existing_client.id.0,
+            project_id: None,
+            organization_id: existing_client.organization_id,
+            summary: summary.into(),
+            ip: request_ip(&req),
+        }
+        .insert(&mut transaction)
+        .await?;
+
         transaction.commit().await?;
 
         Ok(HttpResponse::Ok().body(""))
@@ -388,7 +493,7 @@ pub async fn oauth_client_icon_edit(
                 ApiError::InvalidInput("The specified client does not exist!".to_string())
             })?;
 
-        client.validate_authorized(Some(&user))?;
+        validate_client_management(&client, &user, &pool).await?;
 
         if let Some(ref icon) = client.icon_url {
             let name = icon.split(&format!("{cdn_url}/")).nth(1);
@@ -454,7 +559,7 @@ pub async fn oauth_client_icon_delete(
         .ok_or_else(|| {
             ApiError::InvalidInput("The specified client does not exist!".to_string())
         })?;
-    client.validate_authorized(Some(&user))?;
+    validate_client_management(&client, &user, &pool).await?;
 
     if let Some(ref icon) = client.icon_url {
         let name = icon.split(&format!("{cdn_url}/")).nth(1);
@@ -600,9 +705,44 @@ pub async fn get_clients_inner(
 
     let ids: Vec<OAuthClientId> = ids.iter().map(|i| (*i).into()).collect();
     let clients = OAuthClient::get_many(&ids, &**pool).await?;
-    clients
-        .iter()
-        .validate_all_authorized(Some(&current_user))?;
+    for client in &clients {
+        validate_client_management(client, &current_user, &pool).await?;
+    }
 
     Ok(clients.into_iter().map(|c| c.into()).collect_vec())
 }
+
+// Organization-owned clients are controlled by members of the organization holding MANAGE_OAUTH_CLIENTS,
+// while clients owned by a user are controlled by their creator
+async fn validate_client_management(
+    client: &OAuthClient,
+    user: &models::users::User,
+    pool: &PgPool,
+) -> Result<(), ApiError> {
+    if let Some(organization_id) = client.organization_id {
+        if can_manage_organization_clients(organization_id, user, pool).await? {
+            Ok(())
+        } else {
+            Err(ApiError::CustomAuthentication(
+                "You don't have sufficient permissions to interact with this OAuth application"
+                    .to_string(),
+            ))
+        }
+    } else {
+        client.validate_authorized(Some(user))
+    }
+}
+
+async fn can_manage_organization_clients(
+    organization_id: OrganizationId,
+    user: &models::users::User,
+    pool: &PgPool,
+) -> Result<bool, DatabaseError> {
+    let team_member =
+        TeamMember::get_from_user_id_organization(organization_id, user.id.into(), false, pool)
+            .await?;
+    let permissions = OrganizationPermissions::get_permissions_by_role(&user.role, &team_member)
+        .unwrap_or_default();
+
+    Ok(permissions.contains(OrganizationPermissions::MANAGE_OAUTH_CLIENTS))
+}
@@ -0,0 +1,276 @@
+use super::ApiError;
+use crate::auth::get_user_from_headers;
+use crate::database::models::notification_item::NotificationBuilder;
+use crate::database::models::thread_item::{ThreadMessageBuilder, ThreadUpdate};
+use crate::database::models::{Organization, TeamId, TeamMember, ThreadId, ThreadMessage, UserId};
+use crate::database::redis::RedisPool;
+use crate::models::notifications::NotificationBody;
+use crate::models::organizations::OrganizationId;
+use crate::models::pats::Scopes;
+use crate::models::teams::OrganizationPermissions;
+use crate::models::threads::MessageBody;
+use crate::models::users::User;
+use crate::queue::session::AuthQueue;
+use crate::util::jobs::{Job, JobHandle, JobType};
+use crate::util::sanitize;
+use crate::util::validate::validation_errors_to_string;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use validator::Validate;
+
+const ANNOUNCEMENTS_NAMESPACE: &str = "organization_announcements";
+/// How long an organization has to wait between announcements, in seconds
+const ANNOUNCEMENT_INTERVAL: i64 = 60 * 60;
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct NewAnnouncement {
+    #[validate(length(min = 1, max = 65536))]
+    pub body: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AnnouncementResponse {
+    /// How many project threads the announcement is posted to
+    pub thread_count: usize,
+    /// The background job posting the announcement, which can be polled for its progress
+    pub job_id: String,
+}
+
+// Gets the organization and checks that the user can send announcements for it
+async fn get_announcing_organization(
+    organization_id: &str,
+    user: &User,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<Organization, ApiError> {
+    let organization = Organization::get(organization_id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let team_member =
+        TeamMember::get_from_user_id(organization.team_id, user.id.into(), pool).await?;
+    let permissions = OrganizationPermissions::get_permissions_by_role(&user.role, &team_member)
+        .unwrap_or_default();
+
+    if !permissions.contains(OrganizationPermissions::SEND_ANNOUNCEMENTS) {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to send announcements for this organization!".to_string(),
+        ));
+    }
+
+    Ok(organization)
+}
+
+/// Posts a message to the threads of all of the organization's projects, and notifies the
+/// members of the organization and its projects. The messages are posted in the background.
+pub async fn announcement_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    new_announcement: web::Json<NewAnnouncement>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_WRITE, Scopes::THREAD_WRITE]),
+    )
+    .await?
+    .1;
+
+    new_announcement
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let organization =
+        get_announcing_organization(&info.into_inner().0, &user, &pool, &redis).await?;
+
+    let body = sanitize::sanitize_body(&new_announcement.body, sanitize::is_strict(&req))
+        .map_err(|err| ApiError::InvalidInput(err.to_string()))?;
+
+    let threads = sqlx::query!(
+        "
+        SELECT t.id thread_id, m.team_id
+        FROM threads t
+        INNER JOIN mods m ON m.id = t.mod_id
+        WHERE m.organization_id = $1
+        ",
+        organization.id as crate::database::models::ids::OrganizationId,
+    )
+    .fetch_all(&**pool)
+    .await?
+    .into_iter()
+    .map(|x| (ThreadId(x.thread_id), TeamId(x.team_id)))
+    .collect::<Vec<_>>();
+
+    let mut redis_connection = redis.connect().await?;
+    let allowed = redis_connection
+        .set_if_absent(
+            ANNOUNCEMENTS_NAMESPACE,
+            &organization.id.0.to_string(),
+            &Utc::now().to_rfc3339(),
+            Some(ANNOUNCEMENT_INTERVAL),
+        )
+        .await?;
+    if !allowed {
+        return Err(ApiError::InvalidInput(
+            "An organization can only send one announcement per hour!".to_string(),
+        ));
+    }
+
+    let organization_id: OrganizationId = organization.id.into();
+    let job = JobHandle::start(
+        JobType::OrganizationAnnouncement,
+        Some(organization_id.to_string()),
+        &redis,
+    )
+    .await;
+    let response = AnnouncementResponse {
+        thread_count: threads.len(),
+        job_id: job.id().to_string(),
+    };
+
+    let pool = pool.into_inner();
+    let redis = redis.into_inner();
+    actix_rt::spawn(async move {
+        let mut job = job;
+        let result = post_announcement(
+            organization_id,
+            organization.team_id,
+            &user,
+            body,
+            threads,
+            &pool,
+            &redis,
+            &mut job,
+        )
+        .await;
+        if let Err(err) = &result {
+            warn!(
+                "Failed to post announcement of organization {}: {}",
+                organization_id, err
+            );
+        }
+        job.finish(&result).await;
+    });
+
+    Ok(HttpResponse::Accepted().json(response))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn post_announcement(
+    organization_id: OrganizationId,
+    organization_team_id: TeamId,
+    author: &User,
+    body: String,
+    threads: Vec<(ThreadId, TeamId)>,
+    pool: &PgPool,
+    redis: &RedisPool,
+    job: &mut JobHandle,
+) -> Result<(), ApiError> {
+    job.progress(0, threads.len() as u64, "Posting announcement")
+        .await;
+
+    let mut transaction = pool.begin().await?;
+
+    let mut posted = Vec::with_capacity(threads.len());
+    for (thread_id, _) in &threads {
+        let message_id = ThreadMessageBuilder {
+            author_id: Some(author.id.into()),
+            body: MessageBody::OrganizationAnnouncement {
+                organization_id,
+                body: body.clone(),
+            },
+            thread_id: *thread_id,
+        }
+        .insert(&mut transaction)
+        .await?;
+        posted.push((*thread_id, message_id));
+
+        job.advance(1, "Posting announcement").await;
+    }
+
+    // Members of several of the projects are only notified once
+    let team_ids = threads
+        .iter()
+        .map(|(_, team_id)| team_id.0)
+        .chain(std::iter::once(organization_team_id.0))
+        .collect::<Vec<_>>();
+    let members = sqlx::query!(
+        "
+        SELECT DISTINCT user_id
+        FROM team_members
+        WHERE team_id = ANY($1) AND accepted = TRUE AND user_id != $2
+        ",
+        &team_ids,
+        UserId::from(author.id) as UserId,
+    )
+    .fetch_all(&mut *transaction)
+    .await?
+    .into_iter()
+    .map(|x| UserId(x.user_id))
+    .collect::<Vec<_>>();
+
+    NotificationBuilder {
+        body: NotificationBody::OrganizationAnnouncement {
+            organization_id,
+            body,
+        },
+    }
+    .insert_many(members, &mut transaction, redis)
+    .await?;
+
+    transaction.commit().await?;
+
+    let message_ids = posted.iter().map(|(_, x)| *x).collect::<Vec<_>>();
+    for message in ThreadMessage::get_many(&message_ids, pool).await? {
+        let thread_id = message.thread_id;
+        ThreadUpdate::Message {
+            message,
+            author_is_mod: author.role.is_mod(),
+        }
+        .publish(thread_id, redis)
+        .await;
+    }
+
+    Ok(())
+}
+
+/// Gets the progress of one of the organization's announcements
+pub async fn announcement_get(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_READ]),
+    )
+    .await?
+    .1;
+    let (organization_id, job_id) = info.into_inner();
+
+    let organization = get_announcing_organization(&organization_id, &user, &pool, &redis).await?;
+    let organization_id = OrganizationId::from(organization.id).to_string();
+
+    let job = Job::get(&job_id, &redis)
+        .await?
+        .filter(|x| {
+            x.job_type == JobType::OrganizationAnnouncement
+                && x.target_id.as_ref() == Some(&organization_id)
+        })
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(HttpResponse::Ok().json(job))
+}
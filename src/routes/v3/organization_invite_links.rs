@@ -0,0 +1,306 @@
+use super::ApiError;
+use crate::auth::get_user_from_headers;
+use crate::database::models::notification_item::NotificationBuilder;
+use crate::database::models::organization_invite_link_item::OrganizationInviteLink;
+use crate::database::models::{
+    generate_organization_invite_link_id, generate_team_member_id, Organization, TeamMember, User,
+};
+use crate::database::redis::RedisPool;
+use crate::models::ids::OrganizationInviteLinkId;
+use crate::models::notifications::NotificationBody;
+use crate::models::organizations;
+use crate::models::pats::Scopes;
+use crate::models::teams::{OrganizationPermissions, ProjectPermissions};
+use crate::models::users::User as ApiUser;
+use crate::queue::session::AuthQueue;
+use crate::util::validate::validation_errors_to_string;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use validator::Validate;
+
+const DEFAULT_LINK_EXPIRY_DAYS: i64 = 7;
+const MAX_LINK_EXPIRY_DAYS: i64 = 90;
+
+fn default_role() -> String {
+    "Member".to_string()
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct NewOrganizationInviteLink {
+    /// The role of the members joining with the link
+    #[validate(length(min = 1, max = 255))]
+    #[serde(default = "default_role")]
+    pub role: String,
+    #[serde(default)]
+    pub organization_permissions: OrganizationPermissions,
+    /// When the link stops working. Defaults to a week from now.
+    pub expires: Option<DateTime<Utc>>,
+    /// How many users can join with the link. Unlimited if not set.
+    #[validate(range(min = 1))]
+    pub max_uses: Option<u32>,
+}
+
+// Gets the organization and checks that the user can manage its invites
+async fn get_managed_organization(
+    organization_id: &str,
+    user: &ApiUser,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(Organization, OrganizationPermissions), ApiError> {
+    let organization = Organization::get(organization_id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let team_member =
+        TeamMember::get_from_user_id(organization.team_id, user.id.into(), pool).await?;
+    let permissions = OrganizationPermissions::get_permissions_by_role(&user.role, &team_member)
+        .unwrap_or_default();
+
+    if !permissions.contains(OrganizationPermissions::MANAGE_INVITES) {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to manage invites to this organization!".to_string(),
+        ));
+    }
+
+    Ok((organization, permissions))
+}
+
+pub async fn invite_link_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    new_link: web::Json<NewOrganizationInviteLink>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_WRITE]),
+    )
+    .await?
+    .1;
+
+    new_link
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let (organization, permissions) =
+        get_managed_organization(&info.into_inner().0, &user, &pool, &redis).await?;
+
+    if !permissions.contains(new_link.organization_permissions) {
+        return Err(ApiError::InvalidInput(
+            "The link grants organization permissions that you don't have".to_string(),
+        ));
+    }
+
+    let now = Utc::now();
+    let expires = new_link
+        .expires
+        .unwrap_or_else(|| now + Duration::days(DEFAULT_LINK_EXPIRY_DAYS));
+    if expires <= now || expires > now + Duration::days(MAX_LINK_EXPIRY_DAYS) {
+        return Err(ApiError::InvalidInput(format!(
+            "Invite links must expire within {MAX_LINK_EXPIRY_DAYS} days!"
+        )));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    let link = OrganizationInviteLink {
+        id: generate_organization_invite_link_id(&mut transaction).await?,
+        organization_id: organization.id,
+        identifier: OrganizationInviteLink::generate_identifier(),
+        role: new_link.role.clone(),
+        organization_permissions: new_link.organization_permissions,
+        created_by: user.id.into(),
+        created: now,
+        expires,
+        max_uses: new_link.max_uses.map(|x| x.min(i32::MAX as u32) as i32),
+        uses: 0,
+    };
+    link.insert(&mut transaction).await?;
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(organizations::OrganizationInviteLink::from(link)))
+}
+
+pub async fn invite_links_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_READ]),
+    )
+    .await?
+    .1;
+
+    let (organization, _) =
+        get_managed_organization(&info.into_inner().0, &user, &pool, &redis).await?;
+
+    let links = OrganizationInviteLink::get_organization(organization.id, &**pool)
+        .await?
+        .into_iter()
+        .map(organizations::OrganizationInviteLink::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(links))
+}
+
+pub async fn invite_link_delete(
+    req: HttpRequest,
+    info: web::Path<(String, OrganizationInviteLinkId)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_WRITE]),
+    )
+    .await?
+    .1;
+    let (organization_id, link_id) = info.into_inner();
+
+    let (organization, _) =
+        get_managed_organization(&organization_id, &user, &pool, &redis).await?;
+
+    let mut transaction = pool.begin().await?;
+    let removed =
+        OrganizationInviteLink::remove(organization.id, link_id.into(), &mut transaction).await?;
+    transaction.commit().await?;
+
+    if removed {
+        Ok(HttpResponse::NoContent().body(""))
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
+/// Joins an organization through an invite link. The user becomes an accepted member right
+/// away, and the members who can manage invites are notified.
+pub async fn organization_join(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_WRITE]),
+    )
+    .await?
+    .1;
+    let (organization_id, identifier) = info.into_inner();
+
+    let organization = Organization::get(&organization_id, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let link = OrganizationInviteLink::get(organization.id, &identifier, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if link.is_exhausted() {
+        return Err(ApiError::Gone(
+            "This invite link has expired or has been used up".to_string(),
+        ));
+    }
+
+    if let Some(member) =
+        TeamMember::get_from_user_id_pending(organization.team_id, user.id.into(), &**pool).await?
+    {
+        return Err(ApiError::InvalidInput(if member.accepted {
+            "You are already a member of this organization".to_string()
+        } else {
+            "You have already been invited to this organization. Accept the invite instead."
+                .to_string()
+        }));
+    }
+
+    if organization.require_2fa && !user.has_totp.unwrap_or(false) {
+        return Err(ApiError::InvalidInput(
+            "This organization requires two-factor authentication. Enable it on your account to join the organization."
+                .to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    // Counted in the same transaction, so concurrent joins can't go over the link's uses
+    if !OrganizationInviteLink::use_link(link.id, &mut transaction).await? {
+        return Err(ApiError::Gone(
+            "This invite link has expired or has been used up".to_string(),
+        ));
+    }
+
+    let now = Utc::now();
+    TeamMember {
+        id: generate_team_member_id(&mut transaction).await?,
+        team_id: organization.team_id,
+        user_id: user.id.into(),
+        role: link.role.clone(),
+        is_owner: false,
+        permissions: ProjectPermissions::empty(),
+        organization_permissions: Some(link.organization_permissions),
+        accepted: true,
+        payouts_split: Decimal::ZERO,
+        ordering: 0,
+        created: now,
+        accepted_at: Some(now),
+        invited_by: Some(link.created_by),
+        two_factor_restricted: false,
+        role_template_id: None,
+    }
+    .insert(&mut transaction)
+    .await?;
+
+    let recipients = TeamMember::get_from_team_full(organization.team_id, &**pool, &redis)
+        .await?
+        .into_iter()
+        .filter(|member| {
+            member.accepted
+                && (member.is_owner
+                    || member
+                        .organization_permissions
+                        .unwrap_or_default()
+                        .contains(OrganizationPermissions::MANAGE_INVITES))
+        })
+        .map(|member| member.user_id)
+        .collect::<Vec<_>>();
+
+    NotificationBuilder {
+        body: NotificationBody::OrganizationMemberJoined {
+            organization_id: organization.id.into(),
+            user_id: user.id,
+            role: link.role,
+        },
+    }
+    .insert_many(recipients, &mut transaction, &redis)
+    .await?;
+
+    transaction.commit().await?;
+
+    User::clear_project_cache(&[user.id.into()], &redis).await?;
+    TeamMember::clear_cache(organization.team_id, &redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
@@ -0,0 +1,282 @@
+use super::ApiError;
+use crate::auth::get_user_from_headers;
+use crate::database::models::organization_payout_split_item::OrganizationPayoutSplit;
+use crate::database::models::{Organization, TeamId, TeamMember, UserId};
+use crate::database::redis::RedisPool;
+use crate::models::ids::ProjectId;
+use crate::models::organizations::{self, OrganizationId};
+use crate::models::pats::Scopes;
+use crate::models::teams::OrganizationPermissions;
+use crate::models::users::User;
+use crate::queue::session::AuthQueue;
+use crate::util::jobs::{Job, JobHandle, JobType};
+use actix_web::{web, HttpRequest, HttpResponse};
+use itertools::Itertools;
+use log::warn;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+#[derive(Serialize, Deserialize)]
+pub struct EditPayoutSplitsQuery {
+    /// Whether to split the payouts of the organization's existing projects by the new defaults
+    #[serde(default)]
+    pub apply_to_existing: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ApplyPayoutSplitsResponse {
+    /// The background job applying the splits, which can be polled for its progress
+    pub job_id: String,
+}
+
+/// The members of a project who aren't members of its organization, whose splits were zeroed
+#[derive(Serialize, Deserialize)]
+pub struct PayoutSplitConflict {
+    pub project_id: ProjectId,
+    pub user_ids: Vec<crate::models::ids::UserId>,
+}
+
+// Gets the organization and checks that the user can edit its payout splits
+async fn get_payouts_organization(
+    organization_id: &str,
+    user: &User,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<Organization, ApiError> {
+    let organization = Organization::get(organization_id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let team_member =
+        TeamMember::get_from_user_id(organization.team_id, user.id.into(), pool).await?;
+    let permissions = OrganizationPermissions::get_permissions_by_role(&user.role, &team_member)
+        .unwrap_or_default();
+
+    if !permissions.contains(OrganizationPermissions::EDIT_PAYOUTS) {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to edit the payouts of this organization!".to_string(),
+        ));
+    }
+
+    Ok(organization)
+}
+
+pub async fn payout_splits_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_READ]),
+    )
+    .await?
+    .1;
+
+    let organization = get_payouts_organization(&info.into_inner().0, &user, &pool, &redis).await?;
+
+    let splits = OrganizationPayoutSplit::get_organization(organization.id, &**pool)
+        .await?
+        .into_iter()
+        .map(organizations::OrganizationPayoutSplit::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(splits))
+}
+
+/// Sets how the payouts of projects added to the organization are split between its members.
+/// An empty list stops splitting them. With `apply_to_existing`, the organization's existing
+/// projects are split by the new defaults in a background job.
+pub async fn payout_splits_edit(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    query: web::Query<EditPayoutSplitsQuery>,
+    splits: web::Json<Vec<organizations::OrganizationPayoutSplit>>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_WRITE]),
+    )
+    .await?
+    .1;
+
+    let organization = get_payouts_organization(&info.into_inner().0, &user, &pool, &redis).await?;
+
+    let splits = splits.into_inner();
+    if splits.iter().map(|x| x.user_id).unique().count() != splits.len() {
+        return Err(ApiError::InvalidInput(
+            "Each member can only have one payout split".to_string(),
+        ));
+    }
+    if splits.iter().any(|x| x.weight < Decimal::ZERO) {
+        return Err(ApiError::InvalidInput(
+            "Payout splits can't be negative".to_string(),
+        ));
+    }
+    if !splits.is_empty()
+        && splits.iter().map(|x| x.weight).sum::<Decimal>() != Decimal::ONE_HUNDRED
+    {
+        return Err(ApiError::InvalidInput(
+            "Payout splits have to add up to 100".to_string(),
+        ));
+    }
+
+    let members = TeamMember::get_from_team_full(organization.team_id, &**pool, &redis).await?;
+    if let Some(split) = splits.iter().find(|split| {
+        !members
+            .iter()
+            .any(|x| x.accepted && x.user_id == split.user_id.into())
+    }) {
+        return Err(ApiError::InvalidInput(format!(
+            "User {} is not a member of this organization",
+            split.user_id
+        )));
+    }
+
+    let mut transaction = pool.begin().await?;
+    OrganizationPayoutSplit::set_organization(
+        organization.id,
+        &splits
+            .into_iter()
+            .map(|x| OrganizationPayoutSplit {
+                user_id: x.user_id.into(),
+                weight: x.weight,
+            })
+            .collect::<Vec<_>>(),
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    if !query.apply_to_existing {
+        return Ok(HttpResponse::NoContent().body(""));
+    }
+
+    let projects = sqlx::query!(
+        "
+        SELECT id, team_id
+        FROM mods
+        WHERE organization_id = $1
+        ",
+        organization.id as crate::database::models::ids::OrganizationId,
+    )
+    .fetch_all(&**pool)
+    .await?
+    .into_iter()
+    .map(|x| {
+        (
+            ProjectId::from(crate::database::models::ProjectId(x.id)),
+            TeamId(x.team_id),
+        )
+    })
+    .collect::<Vec<_>>();
+
+    let organization_id: OrganizationId = organization.id.into();
+    let job = JobHandle::start(
+        JobType::OrganizationPayoutSplits,
+        Some(organization_id.to_string()),
+        &redis,
+    )
+    .await;
+    let response = ApplyPayoutSplitsResponse {
+        job_id: job.id().to_string(),
+    };
+
+    let pool = pool.into_inner();
+    let redis = redis.into_inner();
+    actix_rt::spawn(async move {
+        let mut job = job;
+        let result = apply_to_projects(organization.id, projects, &pool, &redis, &mut job).await;
+        if let Err(err) = &result {
+            warn!(
+                "Failed to apply the payout splits of organization {}: {}",
+                organization_id, err
+            );
+        }
+        job.finish(&result).await;
+    });
+
+    Ok(HttpResponse::Accepted().json(response))
+}
+
+// Splits the payouts of each of the projects by the organization's defaults, reporting the
+// members who aren't in the organization as the job's result
+async fn apply_to_projects(
+    organization_id: crate::database::models::OrganizationId,
+    projects: Vec<(ProjectId, TeamId)>,
+    pool: &PgPool,
+    redis: &RedisPool,
+    job: &mut JobHandle,
+) -> Result<(), ApiError> {
+    job.progress(0, projects.len() as u64, "Applying payout splits")
+        .await;
+
+    let mut conflicts = Vec::new();
+    for (project_id, team_id) in projects {
+        let mut transaction = pool.begin().await?;
+        let user_ids =
+            OrganizationPayoutSplit::apply_to_team(organization_id, team_id, &mut transaction)
+                .await?;
+        transaction.commit().await?;
+
+        TeamMember::clear_cache(team_id, redis).await?;
+        if !user_ids.is_empty() {
+            conflicts.push(PayoutSplitConflict {
+                project_id,
+                user_ids: user_ids.into_iter().map(|x: UserId| x.into()).collect(),
+            });
+        }
+
+        job.advance(1, "Applying payout splits").await;
+    }
+
+    job.set_result(&conflicts).await;
+
+    Ok(())
+}
+
+/// Gets the progress of applying the organization's payout splits to its projects, along with
+/// the conflicts it found once it's finished
+pub async fn payout_splits_job_get(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_READ]),
+    )
+    .await?
+    .1;
+    let (organization_id, job_id) = info.into_inner();
+
+    let organization = get_payouts_organization(&organization_id, &user, &pool, &redis).await?;
+    let organization_id = OrganizationId::from(organization.id).to_string();
+
+    let job = Job::get(&job_id, &redis)
+        .await?
+        .filter(|x| {
+            x.job_type == JobType::OrganizationPayoutSplits
+                && x.target_id.as_ref() == Some(&organization_id)
+        })
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(HttpResponse::Ok().json(job))
+}
@@ -0,0 +1,327 @@
+use super::ApiError;
+use crate::auth::get_user_from_headers;
+use crate::database::models::organization_role_template_item::OrganizationRoleTemplate;
+use crate::database::models::{
+    generate_organization_role_template_id, Organization, OrganizationId, TeamMember,
+};
+use crate::database::redis::RedisPool;
+use crate::models::ids::OrganizationRoleTemplateId;
+use crate::models::organizations::{self, MAX_ROLE_TEMPLATES};
+use crate::models::pats::Scopes;
+use crate::models::teams::{OrganizationPermissions, ProjectPermissions};
+use crate::models::users::User as ApiUser;
+use crate::queue::session::AuthQueue;
+use crate::util::validate::validation_errors_to_string;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use validator::Validate;
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct NewRoleTemplate {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    #[validate(length(max = 255))]
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub permissions: ProjectPermissions,
+    #[serde(default)]
+    pub organization_permissions: OrganizationPermissions,
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct EditRoleTemplate {
+    #[validate(length(min = 1, max = 64))]
+    pub name: Option<String>,
+    #[validate(length(max = 255))]
+    pub description: Option<String>,
+    pub permissions: Option<ProjectPermissions>,
+    pub organization_permissions: Option<OrganizationPermissions>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EditRoleTemplateQuery {
+    /// Whether to give the template's new permissions to the members who were given it
+    #[serde(default)]
+    pub propagate: bool,
+}
+
+// Gets the organization and the user's permissions in it. Only members can see its templates.
+async fn get_member_organization(
+    organization_id: &str,
+    user: &ApiUser,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(Organization, OrganizationPermissions), ApiError> {
+    let organization = Organization::get(organization_id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let team_member =
+        TeamMember::get_from_user_id(organization.team_id, user.id.into(), pool).await?;
+    if team_member.is_none() && !user.role.is_mod() {
+        return Err(ApiError::NotFound);
+    }
+
+    let permissions = OrganizationPermissions::get_permissions_by_role(&user.role, &team_member)
+        .unwrap_or_default();
+
+    Ok((organization, permissions))
+}
+
+// Checks that the user can manage the organization's templates, and can give out the
+// permissions of the template
+fn check_template_permissions(
+    permissions: OrganizationPermissions,
+    project_permissions: ProjectPermissions,
+    organization_permissions: OrganizationPermissions,
+) -> Result<(), ApiError> {
+    if !permissions.contains(OrganizationPermissions::EDIT_MEMBER) {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to manage the role templates of this organization!"
+                .to_string(),
+        ));
+    }
+    if !permissions.contains(organization_permissions) {
+        return Err(ApiError::InvalidInput(
+            "The template has organization permissions that you don't have".to_string(),
+        ));
+    }
+    if !project_permissions.is_empty()
+        && !permissions.contains(OrganizationPermissions::EDIT_MEMBER_DEFAULT_PERMISSIONS)
+    {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to give members default project permissions".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Gets a role template to give a member of a team. The template has to belong to the
+/// organization of the team, or to the organization which owns the team's project.
+pub async fn get_team_role_template(
+    template_id: OrganizationRoleTemplateId,
+    organization_id: Option<OrganizationId>,
+    pool: &PgPool,
+) -> Result<OrganizationRoleTemplate, ApiError> {
+    let template = if let Some(organization_id) = organization_id {
+        OrganizationRoleTemplate::get(organization_id, template_id.into(), pool).await?
+    } else {
+        None
+    };
+
+    template.ok_or_else(|| {
+        ApiError::InvalidInput(
+            "The role template doesn't exist in the organization of this team".to_string(),
+        )
+    })
+}
+
+pub async fn role_templates_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_READ]),
+    )
+    .await?
+    .1;
+
+    let (organization, _) =
+        get_member_organization(&info.into_inner().0, &user, &pool, &redis).await?;
+
+    let templates = OrganizationRoleTemplate::get_organization(organization.id, &**pool)
+        .await?
+        .into_iter()
+        .map(organizations::OrganizationRoleTemplate::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(templates))
+}
+
+pub async fn role_template_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    new_template: web::Json<NewRoleTemplate>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_WRITE]),
+    )
+    .await?
+    .1;
+
+    new_template
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let (organization, permissions) =
+        get_member_organization(&info.into_inner().0, &user, &pool, &redis).await?;
+    check_template_permissions(
+        permissions,
+        new_template.permissions,
+        new_template.organization_permissions,
+    )?;
+
+    let existing = OrganizationRoleTemplate::get_organization(organization.id, &**pool).await?;
+    if existing.iter().any(|x| x.name == new_template.name) {
+        return Err(ApiError::InvalidInput(
+            "The organization already has a role template with this name".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    let template = OrganizationRoleTemplate {
+        id: generate_organization_role_template_id(&mut transaction).await?,
+        organization_id: organization.id,
+        name: new_template.name.clone(),
+        description: new_template.description.clone(),
+        permissions: new_template.permissions,
+        organization_permissions: new_template.organization_permissions,
+        created: Utc::now(),
+    };
+    if !template
+        .insert(MAX_ROLE_TEMPLATES, &mut transaction)
+        .await?
+    {
+        return Err(ApiError::InvalidInput(format!(
+            "An organization can have at most {MAX_ROLE_TEMPLATES} role templates"
+        )));
+    }
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(organizations::OrganizationRoleTemplate::from(template)))
+}
+
+/// Edits a role template. With `propagate`, the members who were given the template and haven't
+/// had their permissions edited since are given its new permissions.
+pub async fn role_template_edit(
+    req: HttpRequest,
+    info: web::Path<(String, OrganizationRoleTemplateId)>,
+    query: web::Query<EditRoleTemplateQuery>,
+    edit_template: web::Json<EditRoleTemplate>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_WRITE]),
+    )
+    .await?
+    .1;
+    let (organization_id, template_id) = info.into_inner();
+
+    edit_template
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let (organization, permissions) =
+        get_member_organization(&organization_id, &user, &pool, &redis).await?;
+    let mut template = OrganizationRoleTemplate::get(organization.id, template_id.into(), &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if let Some(name) = &edit_template.name {
+        let existing = OrganizationRoleTemplate::get_organization(organization.id, &**pool).await?;
+        if existing
+            .iter()
+            .any(|x| x.id != template.id && &x.name == name)
+        {
+            return Err(ApiError::InvalidInput(
+                "The organization already has a role template with this name".to_string(),
+            ));
+        }
+        template.name = name.clone();
+    }
+    if let Some(description) = &edit_template.description {
+        template.description = description.clone();
+    }
+    if let Some(permissions) = edit_template.permissions {
+        template.permissions = permissions;
+    }
+    if let Some(organization_permissions) = edit_template.organization_permissions {
+        template.organization_permissions = organization_permissions;
+    }
+    check_template_permissions(
+        permissions,
+        template.permissions,
+        template.organization_permissions,
+    )?;
+
+    let mut transaction = pool.begin().await?;
+    template.update(&mut transaction).await?;
+    let team_ids = if query.propagate {
+        OrganizationRoleTemplate::apply_to_members(template.id, &mut transaction).await?
+    } else {
+        Vec::new()
+    };
+    transaction.commit().await?;
+
+    for team_id in team_ids.into_iter().unique() {
+        TeamMember::clear_cache(team_id, &redis).await?;
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn role_template_delete(
+    req: HttpRequest,
+    info: web::Path<(String, OrganizationRoleTemplateId)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_WRITE]),
+    )
+    .await?
+    .1;
+    let (organization_id, template_id) = info.into_inner();
+
+    let (organization, permissions) =
+        get_member_organization(&organization_id, &user, &pool, &redis).await?;
+    check_template_permissions(
+        permissions,
+        ProjectPermissions::empty(),
+        OrganizationPermissions::empty(),
+    )?;
+
+    let mut transaction = pool.begin().await?;
+    let removed =
+        OrganizationRoleTemplate::remove(organization.id, template_id.into(), &mut transaction)
+            .await?;
+    transaction.commit().await?;
+
+    if removed {
+        Ok(HttpResponse::NoContent().body(""))
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
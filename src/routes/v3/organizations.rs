@@ -1,23 +1,33 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use super::users::EditLimits;
 use super::ApiError;
+use crate::auth::validate::check_pat_project_restriction;
 use crate::auth::{filter_visible_projects, get_user_from_headers};
+use crate::database::models::notification_item::NotificationBuilder;
+use crate::database::models::organization_payout_split_item::OrganizationPayoutSplit;
+use crate::database::models::reserved_names::get_name_holder;
 use crate::database::models::team_item::TeamMember;
 use crate::database::models::{generate_organization_id, team_item, Organization};
 use crate::database::redis::RedisPool;
 use crate::file_hosting::FileHost;
 use crate::models::ids::base62_impl::parse_base62;
 use crate::models::ids::UserId;
+use crate::models::notifications::NotificationBody;
 use crate::models::organizations::OrganizationId;
 use crate::models::pats::Scopes;
 use crate::models::teams::{OrganizationPermissions, ProjectPermissions};
 use crate::queue::session::AuthQueue;
 use crate::routes::v3::project_creation::CreateError;
+use crate::search::indexing::reindex_organizations;
+use crate::search::SearchConfig;
+use crate::util::env::parse_var;
 use crate::util::routes::read_from_payload;
 use crate::util::validate::validation_errors_to_string;
 use crate::{database, models};
 use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
 use futures::TryStreamExt;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -33,6 +43,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("{id}", web::get().to(organization_get))
             .route("{id}", web::patch().to(organizations_edit))
             .route("{id}", web::delete().to(organization_delete))
+            .route("{id}/limits", web::patch().to(organization_limits_edit))
             .route("{id}/projects", web::post().to(organization_projects_add))
             .route(
                 "{id}/projects/{project_id}",
@@ -43,10 +54,78 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route(
                 "{id}/members",
                 web::get().to(super::teams::team_members_get_organization),
+            )
+            .route(
+                "{id}/audit-log",
+                web::get().to(super::audit_log::organization_audit_log_get),
+            )
+            .route(
+                "{id}/announcement",
+                web::post().to(super::organization_announcements::announcement_create),
+            )
+            .route(
+                "{id}/announcement/{job_id}",
+                web::get().to(super::organization_announcements::announcement_get),
+            )
+            .route(
+                "{id}/payout-splits",
+                web::get().to(super::organization_payout_splits::payout_splits_get),
+            )
+            .route(
+                "{id}/payout-splits",
+                web::patch().to(super::organization_payout_splits::payout_splits_edit),
+            )
+            .route(
+                "{id}/payout-splits/{job_id}",
+                web::get().to(super::organization_payout_splits::payout_splits_job_get),
+            )
+            .route(
+                "{id}/invite-link",
+                web::post().to(super::organization_invite_links::invite_link_create),
+            )
+            .route(
+                "{id}/invite-links",
+                web::get().to(super::organization_invite_links::invite_links_get),
+            )
+            .route(
+                "{id}/invite-link/{link_id}",
+                web::delete().to(super::organization_invite_links::invite_link_delete),
+            )
+            .route(
+                "{id}/roles",
+                web::get().to(super::organization_role_templates::role_templates_get),
+            )
+            .route(
+                "{id}/roles",
+                web::post().to(super::organization_role_templates::role_template_create),
+            )
+            .route(
+                "{id}/roles/{role_id}",
+                web::patch().to(super::organization_role_templates::role_template_edit),
+            )
+            .route(
+                "{id}/roles/{role_id}",
+                web::delete().to(super::organization_role_templates::role_template_delete),
+            )
+            .route(
+                "{id}/join/{identifier}",
+                web::post().to(super::organization_invite_links::organization_join),
             ),
     );
 }
 
+// Brings the organization's search document up to date, removing it if it was deleted. A
+// failure only leaves search out of date until the next full reindex.
+async fn reindex_organization(
+    id: database::models::OrganizationId,
+    pool: &PgPool,
+    config: &SearchConfig,
+) {
+    if let Err(e) = reindex_organizations(&[id], pool, config).await {
+        log::warn!("Failed to reindex organization {:?}: {}", id, e);
+    }
+}
+
 pub async fn organization_projects_get(
     req: HttpRequest,
     info: web::Path<(String,)>,
@@ -109,6 +188,7 @@ pub async fn organization_create(
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
+    search_config: web::Data<SearchConfig>,
 ) -> Result<HttpResponse, CreateError> {
     let current_user = get_user_from_headers(
         &req,
@@ -126,17 +206,25 @@ pub async fn organization_create(
 
     let mut transaction = pool.begin().await?;
 
-    // Try title
+    let slug = new_organization.slug.to_lowercase();
+
+    // The slug can't be another organization's id
     let name_organization_id_option: Option<OrganizationId> =
-        serde_json::from_str(&format!("\"{}\"", new_organization.slug)).ok();
-    let mut organization_strings = vec![];
+        serde_json::from_str(&format!("\"{}\"", slug)).ok();
     if let Some(name_organization_id) = name_organization_id_option {
-        organization_strings.push(name_organization_id.to_string());
+        let results = Organization::get_many(
+            &[name_organization_id.to_string()],
+            &mut *transaction,
+            &redis,
+        )
+        .await?;
+        if !results.is_empty() {
+            return Err(CreateError::SlugCollision);
+        }
     }
-    organization_strings.push(new_organization.slug.clone());
-    let results = Organization::get_many(&organization_strings, &mut *transaction, &redis).await?;
-    if !results.is_empty() {
-        return Err(CreateError::SlugCollision);
+
+    if let Some(holder) = get_name_holder(&slug, None, None, &mut *transaction).await? {
+        return Err(CreateError::Conflict(holder.conflict_message(&slug)));
     }
 
     let organization_id = generate_organization_id(&mut transaction).await?;
@@ -159,15 +247,23 @@ pub async fn organization_create(
     // Create organization
     let organization = Organization {
         id: organization_id,
-        slug: new_organization.slug.clone(),
+        slug,
         name: new_organization.name.clone(),
         description: new_organization.description.clone(),
         team_id,
         icon_url: None,
         color: None,
+        require_2fa: false,
     };
     organization.clone().insert(&mut transaction).await?;
     transaction.commit().await?;
+    database::models::Organization::clear_cache(
+        organization_id,
+        Some(organization.slug.clone()),
+        &redis,
+    )
+    .await?;
+    reindex_organization(organization_id, &pool, &search_config).await;
 
     // Only member is the owner, the logged in one
     let member_data = TeamMember::get_from_team_full(team_id, &**pool, &redis)
@@ -229,6 +325,15 @@ pub async fn organization_get(
                     .find(|x| x.user_id == user.id.into() && x.accepted)
             })
             .is_some();
+        let can_edit_payouts = current_user.as_ref().map_or(false, |user| {
+            let team_member = members_data
+                .iter()
+                .find(|x| x.user_id == user.id.into() && x.accepted)
+                .cloned();
+            OrganizationPermissions::get_permissions_by_role(&user.role, &team_member)
+                .unwrap_or_default()
+                .contains(OrganizationPermissions::EDIT_PAYOUTS)
+        });
         let team_members: Vec<_> = members_data
             .into_iter()
             .filter(|x| {
@@ -245,7 +350,29 @@ pub async fn organization_get(
             })
             .collect();
 
-        let organization = models::organizations::Organization::from(data, team_members);
+        // Projects which aren't listed are only counted for members of the organization
+        let summaries = Organization::get_project_summaries(data.id, &**pool, &redis).await?;
+        let project_summary = if logged_in {
+            summaries.all
+        } else {
+            summaries.listed
+        };
+
+        let default_payout_splits = if can_edit_payouts {
+            Some(
+                OrganizationPayoutSplit::get_organization(data.id, &**pool)
+                    .await?
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
+            )
+        } else {
+            None
+        };
+
+        let mut organization = models::organizations::Organization::from(data, team_members);
+        organization.project_summary = Some(project_summary);
+        organization.default_payout_splits = default_payout_splits;
         return Ok(HttpResponse::Ok().json(organization));
     }
     Err(ApiError::NotFound)
@@ -342,6 +469,7 @@ pub struct OrganizationEdit {
     pub slug: Option<String>,
     #[validate(length(min = 3, max = 64))]
     pub name: Option<String>,
+    pub require_2fa: Option<bool>,
 }
 
 pub async fn organizations_edit(
@@ -351,6 +479,7 @@ pub async fn organizations_edit(
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
+    search_config: web::Data<SearchConfig>,
 ) -> Result<HttpResponse, ApiError> {
     let user = get_user_from_headers(
         &req,
@@ -449,23 +578,21 @@ pub async fn organizations_edit(
                     }
                 }
 
-                // Make sure the new name is different from the old one
-                // We are able to unwrap here because the name is always set
-                if !slug.eq(&organization_item.slug.clone()) {
-                    let results = sqlx::query!(
-                        "
-                        SELECT EXISTS(SELECT 1 FROM organizations WHERE LOWER(slug) = LOWER($1))
-                        ",
-                        slug
+                // Make sure the new slug is different from the old one
+                let slug = slug.to_lowercase();
+                if slug != organization_item.slug.to_lowercase() {
+                    if let Some(holder) =
+                        get_name_holder(&slug, None, Some(id), &mut *transaction).await?
+                    {
+                        return Err(ApiError::Conflict(holder.conflict_message(&slug)));
+                    }
+
+                    Organization::insert_slug_history(
+                        id,
+                        &organization_item.slug,
+                        &mut transaction,
                     )
-                    .fetch_one(&mut *transaction)
                     .await?;
-
-                    if results.exists.unwrap_or(true) {
-                        return Err(ApiError::InvalidInput(
-                            "slug collides with other organization's id!".to_string(),
-                        ));
-                    }
                 }
 
                 sqlx::query!(
@@ -474,11 +601,52 @@ pub async fn organizations_edit(
                     SET slug = $1
                     WHERE (id = $2)
                     ",
-                    Some(slug),
+                    slug,
+                    id as database::models::ids::OrganizationId,
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+
+            if let Some(require_2fa) = new_organization.require_2fa {
+                if !perms.contains(OrganizationPermissions::EDIT_DETAILS) {
+                    return Err(ApiError::CustomAuthentication(
+                        "You do not have the permissions to change whether this organization requires two-factor authentication!"
+                            .to_string(),
+                    ));
+                }
+                if require_2fa && !user.has_totp.unwrap_or(false) {
+                    return Err(ApiError::InvalidInput(
+                        "You need to enable two-factor authentication on your account before requiring it for the organization!"
+                            .to_string(),
+                    ));
+                }
+
+                sqlx::query!(
+                    "
+                    UPDATE organizations
+                    SET require_2fa = $1
+                    WHERE (id = $2)
+                    ",
+                    require_2fa,
                     id as database::models::ids::OrganizationId,
                 )
                 .execute(&mut *transaction)
                 .await?;
+
+                // Members who were told to enable 2FA, or lost their permissions, no longer need it
+                if !require_2fa {
+                    sqlx::query!(
+                        "
+                        UPDATE team_members
+                        SET two_factor_notified = NULL, two_factor_restricted = FALSE
+                        WHERE team_id = $1
+                        ",
+                        organization_item.team_id as database::models::ids::TeamId,
+                    )
+                    .execute(&mut *transaction)
+                    .await?;
+                }
             }
 
             transaction.commit().await?;
@@ -488,6 +656,19 @@ pub async fn organizations_edit(
                 &redis,
             )
             .await?;
+            // The new slug may have been looked up before the organization had it
+            if new_organization.slug.is_some() {
+                database::models::Organization::clear_cache(
+                    organization_item.id,
+                    new_organization.slug.clone(),
+                    &redis,
+                )
+                .await?;
+            }
+            if new_organization.require_2fa == Some(false) {
+                TeamMember::clear_cache(organization_item.team_id, &redis).await?;
+            }
+            reindex_organization(organization_item.id, &pool, &search_config).await;
 
             Ok(HttpResponse::NoContent().body(""))
         } else {
@@ -500,12 +681,56 @@ pub async fn organizations_edit(
     }
 }
 
+/// Overrides how many projects an organization can have. Only admins can change limits.
+pub async fn organization_limits_edit(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    new_limits: web::Json<EditLimits>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ORGANIZATION_WRITE]),
+    )
+    .await?
+    .1;
+
+    if !user.role.is_admin() {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to edit the limits of this organization!".to_string(),
+        ));
+    }
+
+    let organization = Organization::get(&info.into_inner().0, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let mut transaction = pool.begin().await?;
+    Organization::set_project_limit(
+        organization.id,
+        new_limits
+            .project_limit
+            .map(|x| x.min(i32::MAX as u32) as i32),
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
 pub async fn organization_delete(
     req: HttpRequest,
     info: web::Path<(String,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
+    search_config: web::Data<SearchConfig>,
 ) -> Result<HttpResponse, ApiError> {
     let user = get_user_from_headers(
         &req,
@@ -593,6 +818,11 @@ pub async fn organization_delete(
             accepted: true,
             payouts_split: Decimal::ZERO,
             ordering: 0,
+            created: Utc::now(),
+            accepted_at: Some(Utc::now()),
+            invited_by: None,
+            two_factor_restricted: false,
+            role_template_id: None,
         };
         member.insert(&mut transaction).await?;
     }
@@ -610,6 +840,7 @@ pub async fn organization_delete(
     }
 
     if result.is_some() {
+        reindex_organization(organization.id, &pool, &search_config).await;
         Ok(HttpResponse::NoContent().body(""))
     } else {
         Err(ApiError::NotFound)
@@ -650,6 +881,7 @@ pub async fn organization_projects_add(
         .ok_or_else(|| {
             ApiError::InvalidInput("The specified project does not exist!".to_string())
         })?;
+    check_pat_project_restriction(&req, project_item.inner.id)?;
     if project_item.inner.organization_id.is_some() {
         return Err(ApiError::InvalidInput(
             "The specified project is already owned by an organization!".to_string(),
@@ -689,45 +921,12 @@ pub async fn organization_projects_add(
     .unwrap_or_default();
     if permissions.contains(OrganizationPermissions::ADD_PROJECT) {
         let mut transaction = pool.begin().await?;
-        sqlx::query!(
-            "
-            UPDATE mods
-            SET organization_id = $1
-            WHERE (id = $2)
-            ",
-            organization.id as database::models::OrganizationId,
-            project_item.inner.id as database::models::ids::ProjectId
-        )
-        .execute(&mut *transaction)
-        .await?;
-
-        // The former owner is no longer an owner (as it is now 'owned' by the organization, 'given' to them)
-        // The former owner is still a member of the project, but not an owner
-        // When later removed from the organization, the project will  be owned by whoever is specified as the new owner there
-
-        let organization_owner_user_id = sqlx::query!(
-            "
-            SELECT u.id 
-            FROM team_members
-            INNER JOIN users u ON u.id = team_members.user_id
-            WHERE team_id = $1 AND is_owner = TRUE
-            ",
-            organization.team_id as database::models::ids::TeamId
-        )
-        .fetch_one(&mut *transaction)
-        .await?;
-        let organization_owner_user_id =
-            database::models::ids::UserId(organization_owner_user_id.id);
-
-        sqlx::query!(
-            "
-            DELETE FROM team_members
-            WHERE team_id = $1 AND (is_owner = TRUE OR user_id = $2)
-            ",
-            project_item.inner.team_id as database::models::ids::TeamId,
-            organization_owner_user_id as database::models::ids::UserId,
+        move_project_to_organization(
+            project_item.inner.id,
+            project_item.inner.team_id,
+            &organization,
+            &mut transaction,
         )
-        .execute(&mut *transaction)
         .await?;
 
         transaction.commit().await?;
@@ -741,6 +940,12 @@ pub async fn organization_projects_add(
             &redis,
         )
         .await?;
+        database::models::Organization::clear_cache(
+            organization.id,
+            Some(organization.slug),
+            &redis,
+        )
+        .await?;
     } else {
         return Err(ApiError::CustomAuthentication(
             "You do not have permission to add projects to this organization!".to_string(),
@@ -749,6 +954,61 @@ pub async fn organization_projects_add(
     Ok(HttpResponse::Ok().finish())
 }
 
+/// Moves a project into an organization, which owns it from then on. The project's owner is
+/// removed from its team along with the organization's owner, who has every permission on the
+/// project through the organization. The project's payouts are then split by the organization's
+/// defaults, if it has any.
+pub async fn move_project_to_organization(
+    project_id: database::models::ids::ProjectId,
+    project_team_id: database::models::ids::TeamId,
+    organization: &database::models::Organization,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), ApiError> {
+    sqlx::query!(
+        "
+        UPDATE mods
+        SET organization_id = $1
+        WHERE (id = $2)
+        ",
+        organization.id as database::models::OrganizationId,
+        project_id as database::models::ids::ProjectId
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    // The former owner is no longer an owner (as it is now 'owned' by the organization, 'given' to them)
+    // The former owner is still a member of the project, but not an owner
+    // When later removed from the organization, the project will  be owned by whoever is specified as the new owner there
+
+    let organization_owner_user_id = sqlx::query!(
+        "
+        SELECT u.id 
+        FROM team_members
+        INNER JOIN users u ON u.id = team_members.user_id
+        WHERE team_id = $1 AND is_owner = TRUE
+        ",
+        organization.team_id as database::models::ids::TeamId
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+    let organization_owner_user_id = database::models::ids::UserId(organization_owner_user_id.id);
+
+    sqlx::query!(
+        "
+        DELETE FROM team_members
+        WHERE team_id = $1 AND (is_owner = TRUE OR user_id = $2)
+        ",
+        project_team_id as database::models::ids::TeamId,
+        organization_owner_user_id as database::models::ids::UserId,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    OrganizationPayoutSplit::apply_to_team(organization.id, project_team_id, transaction).await?;
+
+    Ok(())
+}
+
 #[derive(Deserialize)]
 pub struct OrganizationProjectRemoval {
     // A new owner must be supplied for the project.
@@ -756,9 +1016,18 @@ pub struct OrganizationProjectRemoval {
     pub new_owner: UserId,
 }
 
+#[derive(Deserialize)]
+pub struct OrganizationProjectRemovalQuery {
+    // Copies the organization's members onto the project team, keeping the permissions they had
+    #[serde(default)]
+    pub transfer_members: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn organization_projects_remove(
     req: HttpRequest,
     info: web::Path<(String, String)>,
+    web::Query(query): web::Query<OrganizationProjectRemovalQuery>,
     pool: web::Data<PgPool>,
     data: web::Json<OrganizationProjectRemoval>,
     redis: web::Data<RedisPool>,
@@ -857,6 +1126,11 @@ pub async fn organization_projects_remove(
                     accepted: true,
                     payouts_split: Decimal::ZERO,
                     ordering: 0,
+                    created: Utc::now(),
+                    accepted_at: Some(Utc::now()),
+                    invited_by: None,
+                    two_factor_restricted: false,
+                    role_template_id: None,
                 };
                 member.insert(&mut transaction).await?;
                 member
@@ -881,6 +1155,16 @@ pub async fn organization_projects_remove(
         .execute(&mut *transaction)
         .await?;
 
+        let mut transferred_users = Vec::new();
+        if query.transfer_members {
+            transferred_users = TeamMember::copy_organization_members(
+                organization.team_id,
+                project_item.inner.team_id,
+                &mut transaction,
+            )
+            .await?;
+        }
+
         sqlx::query!(
             "
             UPDATE mods
@@ -891,9 +1175,12 @@ pub async fn organization_projects_remove(
         )
         .execute(&mut *transaction)
         .await?;
+        // The organization's templates can no longer be applied to the project's members
+        TeamMember::clear_role_templates(project_item.inner.team_id, &mut transaction).await?;
 
         transaction.commit().await?;
-        database::models::User::clear_project_cache(&[current_user.id.into()], &redis).await?;
+        transferred_users.push(current_user.id.into());
+        database::models::User::clear_project_cache(&transferred_users, &redis).await?;
         database::models::TeamMember::clear_cache(project_item.inner.team_id, &redis).await?;
         database::models::Project::clear_cache(
             project_item.inner.id,
@@ -902,6 +1189,12 @@ pub async fn organization_projects_remove(
             &redis,
         )
         .await?;
+        database::models::Organization::clear_cache(
+            organization.id,
+            Some(organization.slug),
+            &redis,
+        )
+        .await?;
     } else {
         return Err(ApiError::CustomAuthentication(
             "You do not have permission to add projects to this organization!".to_string(),
@@ -925,6 +1218,7 @@ pub async fn organization_icon_edit(
     file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
     mut payload: web::Payload,
     session_queue: web::Data<AuthQueue>,
+    search_config: web::Data<SearchConfig>,
 ) -> Result<HttpResponse, ApiError> {
     if let Some(content_type) = crate::util::ext::get_image_content_type(&ext.ext) {
         let cdn_url = dotenvy::var("CDN_URL")?;
@@ -1010,6 +1304,7 @@ pub async fn organization_icon_edit(
             &redis,
         )
         .await?;
+        reindex_organization(organization_item.id, &pool, &search_config).await;
 
         Ok(HttpResponse::NoContent().body(""))
     } else {
@@ -1027,6 +1322,7 @@ pub async fn delete_organization_icon(
     redis: web::Data<RedisPool>,
     file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
     session_queue: web::Data<AuthQueue>,
+    search_config: web::Data<SearchConfig>,
 ) -> Result<HttpResponse, ApiError> {
     let user = get_user_from_headers(
         &req,
@@ -1095,6 +1391,101 @@ pub async fn delete_organization_icon(
         &redis,
     )
     .await?;
+    reindex_organization(organization_item.id, &pool, &search_config).await;
 
     Ok(HttpResponse::NoContent().body(""))
 }
+
+/// How long members of an organization which requires 2FA have to enable it after they're
+/// notified, before they lose their permissions
+pub fn organization_2fa_grace_period() -> Duration {
+    Duration::days(parse_var("ORGANIZATION_2FA_GRACE_DAYS").unwrap_or(14))
+}
+
+/// Notifies the members of organizations which require 2FA who haven't enabled it, along with
+/// the organizations' admins. Members who still haven't enabled it after the grace period lose
+/// their permissions, which they get back once they enable it.
+pub async fn enforce_organization_2fa(pool: &PgPool, redis: &RedisPool) -> Result<(), ApiError> {
+    let now = Utc::now();
+    let grace_period = organization_2fa_grace_period();
+
+    let mut transaction = pool.begin().await?;
+
+    let notified_members = sqlx::query!(
+        "
+        UPDATE team_members tm
+        SET two_factor_notified = $1
+        FROM organizations o, users u
+        WHERE o.team_id = tm.team_id AND u.id = tm.user_id AND o.require_2fa
+            AND tm.accepted AND tm.two_factor_notified IS NULL AND u.totp_secret IS NULL
+        RETURNING tm.user_id, tm.team_id, o.id organization_id
+        ",
+        now,
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+
+    for member in notified_members {
+        let user_id = database::models::ids::UserId(member.user_id);
+        let admins = TeamMember::get_from_team_full(
+            database::models::ids::TeamId(member.team_id),
+            pool,
+            redis,
+        )
+        .await?
+        .into_iter()
+        .filter(|admin| {
+            admin.accepted
+                && !admin.two_factor_restricted
+                && admin.user_id != user_id
+                && (admin.is_owner
+                    || admin
+                        .organization_permissions
+                        .unwrap_or_default()
+                        .contains(OrganizationPermissions::EDIT_MEMBER))
+        })
+        .map(|admin| admin.user_id);
+
+        NotificationBuilder {
+            body: NotificationBody::OrganizationTwoFactorRequired {
+                organization_id: database::models::ids::OrganizationId(member.organization_id)
+                    .into(),
+                user_id: user_id.into(),
+                restricted_after: now + grace_period,
+            },
+        }
+        .insert_many(
+            std::iter::once(user_id).chain(admins).collect(),
+            &mut transaction,
+            redis,
+        )
+        .await?;
+    }
+
+    let mut restricted_teams = sqlx::query!(
+        "
+        UPDATE team_members tm
+        SET two_factor_restricted = TRUE
+        FROM users u
+        WHERE u.id = tm.user_id AND tm.two_factor_notified <= $1
+            AND NOT tm.two_factor_restricted AND u.totp_secret IS NULL
+        RETURNING tm.team_id
+        ",
+        now - grace_period,
+    )
+    .fetch_all(&mut *transaction)
+    .await?
+    .into_iter()
+    .map(|x| x.team_id)
+    .collect::<Vec<_>>();
+
+    transaction.commit().await?;
+
+    restricted_teams.sort_unstable();
+    restricted_teams.dedup();
+    for team_id in restricted_teams {
+        TeamMember::clear_cache(database::models::ids::TeamId(team_id), redis).await?;
+    }
+
+    Ok(())
+}
@@ -147,7 +147,7 @@ pub async fn paypal_webhook(
                 sqlx::query!(
                     "
                     UPDATE payouts
-                    SET status = $1
+                    SET status = $1, completed = NOW()
                     WHERE platform_id = $2
                     ",
                     if &*webhook.event_type == "PAYMENT.PAYOUTS-ITEM.CANCELED" {
@@ -175,7 +175,7 @@ pub async fn paypal_webhook(
             sqlx::query!(
                 "
                 UPDATE payouts
-                SET status = $1
+                SET status = $1, completed = NOW()
                 WHERE platform_id = $2
                 ",
                 PayoutStatus::Success.as_str(),
@@ -268,7 +268,7 @@ pub async fn tremendous_webhook(
                 sqlx::query!(
                     "
                     UPDATE payouts
-                    SET status = $1
+                    SET status = $1, completed = NOW()
                     WHERE platform_id = $2
                     ",
                     if &*webhook.event == "REWARDS.CANCELED" {
@@ -296,7 +296,7 @@ pub async fn tremendous_webhook(
             sqlx::query!(
                 "
                 UPDATE payouts
-                SET status = $1
+                SET status = $1, completed = NOW()
                 WHERE platform_id = $2
                 ",
                 PayoutStatus::Success.as_str(),
@@ -465,6 +465,7 @@ pub async fn create_payout(
                 method: Some(body.method),
                 method_address: Some(display_address),
                 platform_id: None,
+                completed: None,
             };
 
             let res: PayoutsResponse = payouts_queue.make_paypal_request(
@@ -536,6 +537,7 @@ pub async fn create_payout(
                         method: Some(PayoutMethodType::Tremendous),
                         method_address: Some(email.clone()),
                         platform_id: None,
+                        completed: None,
                     };
 
                     #[derive(Deserialize)]
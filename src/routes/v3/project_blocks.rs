@@ -0,0 +1,222 @@
+use crate::auth::get_user_from_headers;
+use crate::database::models as db_models;
+use crate::database::models::audit_log_item::AuditLogBuilder;
+use crate::database::models::project_block_item::ProjectBlock as DBProjectBlock;
+use crate::database::redis::RedisPool;
+use crate::models::audit_log::{AuditAction, AuditTargetType};
+use crate::models::pats::Scopes;
+use crate::models::project_blocks::{ProjectBlock, MAX_PROJECT_BLOCKS};
+use crate::models::teams::ProjectPermissions;
+use crate::models::users::{User, UserId};
+use crate::queue::session::AuthQueue;
+use crate::routes::v3::audit_log::request_ip;
+use crate::routes::ApiError;
+use actix_web::{web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+
+/// Whether the user is blocked from interacting with the project. Blocks don't apply to moderators.
+pub async fn is_blocked_from_project(
+    project_id: db_models::ProjectId,
+    user: &User,
+    pool: &PgPool,
+) -> Result<bool, ApiError> {
+    if user.role.is_mod() {
+        return Ok(false);
+    }
+
+    Ok(DBProjectBlock::is_blocked(project_id, user.id.into(), pool).await?)
+}
+
+// Gets the project if the user can manage its blocks, which is up to whoever can invite users
+// to its team
+async fn get_managed_project(
+    project_id: &str,
+    user: &User,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<db_models::project_item::QueryProject, ApiError> {
+    let project = db_models::Project::get(project_id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if user.role.is_mod() {
+        return Ok(project);
+    }
+
+    let (team_member, organization_team_member) =
+        db_models::TeamMember::get_for_project_permissions(&project.inner, user.id.into(), pool)
+            .await?;
+
+    // Hide the project
+    if team_member.is_none() && organization_team_member.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    let permissions = ProjectPermissions::get_permissions_by_role(
+        &user.role,
+        &team_member,
+        &organization_team_member,
+    )
+    .unwrap_or_default();
+
+    if !permissions.contains(ProjectPermissions::MANAGE_INVITES) {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to manage the users blocked from this project.".to_string(),
+        ));
+    }
+
+    Ok(project)
+}
+
+async fn record_block(
+    req: &HttpRequest,
+    user: &User,
+    project_id: db_models::ProjectId,
+    blocked_user_id: db_models::UserId,
+    blocked: bool,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), ApiError> {
+    AuditLogBuilder {
+        actor_id: user.id.into(),
+        action: AuditAction::ProjectBlock,
+        target_type: AuditTargetType::User,
+        target_id: blocked_user_id.0,
+        project_id: Some(project_id),
+        organization_id: None,
+        summary: serde_json::json!({ "blocked": blocked }),
+        ip: request_ip(req),
+    }
+    .insert(transaction)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn project_blocks_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?
+    .1;
+
+    let project = get_managed_project(&info.into_inner().0, &user, &pool, &redis).await?;
+
+    let blocks = DBProjectBlock::get_for_project(project.inner.id, &**pool)
+        .await?
+        .into_iter()
+        .map(ProjectBlock::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(blocks))
+}
+
+pub async fn project_block_add(
+    req: HttpRequest,
+    info: web::Path<(String, UserId)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+    let (project_id, blocked_user_id) = info.into_inner();
+
+    let project = get_managed_project(&project_id, &user, &pool, &redis).await?;
+
+    if blocked_user_id == user.id {
+        return Err(ApiError::InvalidInput(
+            "You can't block yourself".to_string(),
+        ));
+    }
+    db_models::User::get_id(blocked_user_id.into(), &**pool, &redis)
+        .await?
+        .ok_or_else(|| ApiError::InvalidInput("The specified user does not exist!".to_string()))?;
+
+    let mut transaction = pool.begin().await?;
+    let already_blocked =
+        DBProjectBlock::is_blocked(project.inner.id, blocked_user_id.into(), &mut *transaction)
+            .await?;
+    if !already_blocked {
+        if !DBProjectBlock::insert(
+            project.inner.id,
+            blocked_user_id.into(),
+            user.id.into(),
+            MAX_PROJECT_BLOCKS,
+            &mut transaction,
+        )
+        .await?
+        {
+            return Err(ApiError::InvalidInput(format!(
+                "A project can block at most {MAX_PROJECT_BLOCKS} users"
+            )));
+        }
+
+        record_block(
+            &req,
+            &user,
+            project.inner.id,
+            blocked_user_id.into(),
+            true,
+            &mut transaction,
+        )
+        .await?;
+    }
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn project_block_remove(
+    req: HttpRequest,
+    info: web::Path<(String, UserId)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+    let (project_id, blocked_user_id) = info.into_inner();
+
+    let project = get_managed_project(&project_id, &user, &pool, &redis).await?;
+
+    let mut transaction = pool.begin().await?;
+    if !DBProjectBlock::remove(project.inner.id, blocked_user_id.into(), &mut transaction).await? {
+        return Err(ApiError::NotFound);
+    }
+    record_block(
+        &req,
+        &user,
+        project.inner.id,
+        blocked_user_id.into(),
+        false,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
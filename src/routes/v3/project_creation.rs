@@ -1,23 +1,35 @@
-use super::version_creation::{try_create_version_fields, InitialVersionData};
+use super::projects::project_category_ids;
+use super::version_creation::{
+    infer_dependencies, spawn_scan_version, try_create_version_fields, InitialVersionData,
+};
 use crate::auth::{get_user_from_headers, AuthenticationError};
 use crate::database::models::loader_fields::{Loader, LoaderField, LoaderFieldEnumValue};
+use crate::database::models::mod_metadata_item::ModMetadataIds;
+use crate::database::models::organization_payout_split_item::OrganizationPayoutSplit;
 use crate::database::models::thread_item::ThreadBuilder;
 use crate::database::models::{self, image_item, User};
 use crate::database::redis::RedisPool;
 use crate::file_hosting::{FileHost, FileHostingError};
+use crate::file_scanning::FileScanner;
 use crate::models::error::ApiError;
 use crate::models::ids::{ImageId, OrganizationId};
-use crate::models::images::{Image, ImageContext};
+use crate::models::images::{Image, ImageContext, ImageModerationStatus};
+use crate::models::pack::IncompatiblePackFile;
 use crate::models::pats::Scopes;
 use crate::models::projects::{
-    License, Link, MonetizationStatus, ProjectId, ProjectStatus, VersionId, VersionStatus,
+    License, Link, LinkPlatform, MonetizationStatus, ProjectId, ProjectStatus, VersionId,
+    VersionStatus,
 };
 use crate::models::teams::ProjectPermissions;
 use crate::models::threads::ThreadType;
 use crate::models::users::UserId;
 use crate::queue::session::AuthQueue;
 use crate::search::indexing::IndexingError;
+use crate::util::env::parse_var;
+use crate::util::readme;
 use crate::util::routes::read_from_field;
+use crate::util::sanitize;
+use crate::util::signed_urls::image_path;
 use crate::util::validate::validation_errors_to_string;
 use actix_multipart::{Field, Multipart};
 use actix_web::http::StatusCode;
@@ -75,6 +87,10 @@ pub enum CreateError {
     InvalidFileType(String),
     #[error("Slug is already taken!")]
     SlugCollision,
+    #[error("Slug was recently used by another project!")]
+    SlugReserved,
+    #[error("{0}")]
+    Conflict(String),
     #[error("Authentication Error: {0}")]
     Unauthorized(#[from] AuthenticationError),
     #[error("Authentication Error: {0}")]
@@ -83,6 +99,18 @@ pub enum CreateError {
     ImageError(#[from] ImageError),
     #[error("Reroute Error: {0}")]
     RerouteError(#[from] reqwest::Error),
+    #[error("Project limit reached: {count} of at most {limit} projects have been created")]
+    ProjectLimitReached { limit: i64, count: i64 },
+    #[error(
+        "The pack has files which aren't compatible with it: {}",
+        .0.iter().map(|x| x.to_string()).join("; ")
+    )]
+    IncompatiblePackFiles(Vec<IncompatiblePackFile>),
+    #[error("Version {index}: {error}")]
+    BulkVersionError {
+        index: usize,
+        error: Box<CreateError>,
+    },
 }
 
 impl actix_web::ResponseError for CreateError {
@@ -105,40 +133,81 @@ impl actix_web::ResponseError for CreateError {
             CreateError::Unauthorized(..) => StatusCode::UNAUTHORIZED,
             CreateError::CustomAuthenticationError(..) => StatusCode::UNAUTHORIZED,
             CreateError::SlugCollision => StatusCode::BAD_REQUEST,
+            CreateError::SlugReserved => StatusCode::CONFLICT,
+            CreateError::Conflict(..) => StatusCode::CONFLICT,
             CreateError::ValidationError(..) => StatusCode::BAD_REQUEST,
             CreateError::FileValidationError(..) => StatusCode::BAD_REQUEST,
             CreateError::ImageError(..) => StatusCode::BAD_REQUEST,
             CreateError::RerouteError(..) => StatusCode::INTERNAL_SERVER_ERROR,
+            CreateError::ProjectLimitReached { .. } => StatusCode::BAD_REQUEST,
+            CreateError::IncompatiblePackFiles(..) => StatusCode::BAD_REQUEST,
+            CreateError::BulkVersionError { error, .. } => error.status_code(),
         }
     }
 
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code()).json(ApiError {
-            error: match self {
-                CreateError::EnvError(..) => "environment_error",
-                CreateError::SqlxDatabaseError(..) => "database_error",
-                CreateError::DatabaseError(..) => "database_error",
-                CreateError::IndexingError(..) => "indexing_error",
-                CreateError::FileHostingError(..) => "file_hosting_error",
-                CreateError::SerDeError(..) => "invalid_input",
-                CreateError::MultipartError(..) => "invalid_input",
-                CreateError::MissingValueError(..) => "invalid_input",
-                CreateError::InvalidIconFormat(..) => "invalid_input",
-                CreateError::InvalidInput(..) => "invalid_input",
-                CreateError::InvalidGameVersion(..) => "invalid_input",
-                CreateError::InvalidLoader(..) => "invalid_input",
-                CreateError::InvalidCategory(..) => "invalid_input",
-                CreateError::InvalidFileType(..) => "invalid_input",
-                CreateError::Unauthorized(..) => "unauthorized",
-                CreateError::CustomAuthenticationError(..) => "unauthorized",
-                CreateError::SlugCollision => "invalid_input",
-                CreateError::ValidationError(..) => "invalid_input",
-                CreateError::FileValidationError(..) => "invalid_input",
-                CreateError::ImageError(..) => "invalid_image",
-                CreateError::RerouteError(..) => "reroute_error",
-            },
+        let error = ApiError {
+            error: self.error_name(),
             description: &self.to_string(),
-        })
+        };
+
+        if let Some(files) = self.incompatible_pack_files() {
+            return HttpResponse::build(self.status_code()).json(IncompatiblePackFilesError {
+                error,
+                incompatible_files: files,
+            });
+        }
+
+        HttpResponse::build(self.status_code()).json(error)
+    }
+}
+
+/// The error of a pack with incompatible files, which lists each of them
+#[derive(Serialize)]
+struct IncompatiblePackFilesError<'a> {
+    #[serde(flatten)]
+    error: ApiError<'a>,
+    incompatible_files: &'a [IncompatiblePackFile],
+}
+
+impl CreateError {
+    fn error_name(&self) -> &'static str {
+        match self {
+            CreateError::EnvError(..) => "environment_error",
+            CreateError::SqlxDatabaseError(..) => "database_error",
+            CreateError::DatabaseError(..) => "database_error",
+            CreateError::IndexingError(..) => "indexing_error",
+            CreateError::FileHostingError(..) => "file_hosting_error",
+            CreateError::SerDeError(..) => "invalid_input",
+            CreateError::MultipartError(..) => "invalid_input",
+            CreateError::MissingValueError(..) => "invalid_input",
+            CreateError::InvalidIconFormat(..) => "invalid_input",
+            CreateError::InvalidInput(..) => "invalid_input",
+            CreateError::InvalidGameVersion(..) => "invalid_input",
+            CreateError::InvalidLoader(..) => "invalid_input",
+            CreateError::InvalidCategory(..) => "invalid_input",
+            CreateError::InvalidFileType(..) => "invalid_input",
+            CreateError::Unauthorized(..) => "unauthorized",
+            CreateError::CustomAuthenticationError(..) => "unauthorized",
+            CreateError::SlugCollision => "invalid_input",
+            CreateError::SlugReserved => "slug_reserved",
+            CreateError::Conflict(..) => "conflict",
+            CreateError::ValidationError(..) => "invalid_input",
+            CreateError::FileValidationError(..) => "invalid_input",
+            CreateError::ImageError(..) => "invalid_image",
+            CreateError::RerouteError(..) => "reroute_error",
+            CreateError::ProjectLimitReached { .. } => "project_limit_reached",
+            CreateError::IncompatiblePackFiles(..) => "incompatible_pack_files",
+            CreateError::BulkVersionError { error, .. } => error.error_name(),
+        }
+    }
+
+    fn incompatible_pack_files(&self) -> Option<&[IncompatiblePackFile]> {
+        match self {
+            CreateError::IncompatiblePackFiles(files) => Some(files),
+            CreateError::BulkVersionError { error, .. } => error.incompatible_pack_files(),
+            _ => None,
+        }
     }
 }
 
@@ -174,6 +243,13 @@ pub struct ProjectCreateData {
     #[serde(alias = "mod_body")]
     /// A long description of the project, in markdown.
     pub description: String,
+    #[validate(
+        custom(function = "crate::util::readme::validate_readme_url"),
+        length(max = 2048)
+    )]
+    #[serde(default)]
+    /// A raw README URL to import the description from instead
+    pub description_source_url: Option<String>,
 
     #[validate(length(max = 32))]
     #[validate]
@@ -217,6 +293,15 @@ pub struct ProjectCreateData {
     pub organization_id: Option<OrganizationId>,
 }
 
+#[derive(Serialize)]
+pub struct ProjectCreateResponse {
+    #[serde(flatten)]
+    pub project: crate::models::projects::Project,
+    /// A problem which didn't stop the project from being created, like its README not importing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Validate, Clone)]
 pub struct NewGalleryItem {
     /// The name of the multipart item where the gallery media is located
@@ -255,10 +340,12 @@ pub async fn project_create(
     client: Data<PgPool>,
     redis: Data<RedisPool>,
     file_host: Data<Arc<dyn FileHost + Send + Sync>>,
+    scanner: Data<Arc<dyn FileScanner + Send + Sync>>,
     session_queue: Data<AuthQueue>,
 ) -> Result<HttpResponse, CreateError> {
     let mut transaction = client.begin().await?;
     let mut uploaded_files = Vec::new();
+    let mut created_versions = Vec::new();
 
     let result = project_create_inner(
         req,
@@ -266,6 +353,7 @@ pub async fn project_create(
         &mut transaction,
         &***file_host,
         &mut uploaded_files,
+        &mut created_versions,
         &client,
         &redis,
         &session_queue,
@@ -282,6 +370,16 @@ pub async fn project_create(
         }
     } else {
         transaction.commit().await?;
+
+        for version_id in created_versions {
+            spawn_scan_version(
+                version_id,
+                client.get_ref().clone(),
+                redis.get_ref().clone(),
+                file_host.get_ref().clone(),
+                scanner.get_ref().clone(),
+            );
+        }
     }
 
     result
@@ -316,6 +414,54 @@ Get logged in user
     - Add project data to indexing queue
 */
 
+/// The most projects a user can own outside of organizations, unless an admin set an override
+pub fn default_user_project_limit() -> i64 {
+    parse_var("USER_PROJECT_LIMIT").unwrap_or(100)
+}
+
+/// The most projects an organization can have, unless an admin set an override
+pub fn default_organization_project_limit() -> i64 {
+    parse_var("ORGANIZATION_PROJECT_LIMIT").unwrap_or(500)
+}
+
+// Checks that the user, or the organization the project is created in, has room for another
+// project. Moderators aren't limited.
+async fn check_project_limit(
+    user: &crate::models::users::User,
+    organization_id: Option<OrganizationId>,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), CreateError> {
+    if user.role.is_mod() {
+        return Ok(());
+    }
+
+    let (limit, count) = if let Some(organization_id) = organization_id {
+        let organization_id = organization_id.into();
+        let limit = models::Organization::get_project_limit(organization_id, &mut **transaction)
+            .await?
+            .map(i64::from)
+            .unwrap_or_else(default_organization_project_limit);
+        let count =
+            models::Project::count_organization_projects(organization_id, &mut **transaction)
+                .await?;
+        (limit, count)
+    } else {
+        let user_id = user.id.into();
+        let limit = User::get_project_limit(user_id, &mut **transaction)
+            .await?
+            .map(i64::from)
+            .unwrap_or_else(default_user_project_limit);
+        let count = models::Project::count_user_projects(user_id, &mut **transaction).await?;
+        (limit, count)
+    };
+
+    if count >= limit {
+        return Err(CreateError::ProjectLimitReached { limit, count });
+    }
+
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn project_create_inner(
     req: HttpRequest,
@@ -323,6 +469,7 @@ async fn project_create_inner(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     file_host: &dyn FileHost,
     uploaded_files: &mut Vec<UploadedFile>,
+    created_versions: &mut Vec<models::VersionId>,
     pool: &PgPool,
     redis: &RedisPool,
     session_queue: &AuthQueue,
@@ -340,6 +487,7 @@ async fn project_create_inner(
     )
     .await?
     .1;
+    let strict = sanitize::is_strict(&req);
 
     let project_id: ProjectId = models::generate_project_id(transaction).await?.into();
     let all_loaders = models::loader_fields::Loader::list(&mut **transaction, redis).await?;
@@ -419,6 +567,12 @@ async fn project_create_inner(
             }
         }
 
+        if models::Project::is_slug_reserved(&create_data.slug, None, &mut **transaction).await? {
+            return Err(CreateError::SlugReserved);
+        }
+
+        check_project_limit(&current_user, create_data.organization_id, transaction).await?;
+
         // Create VersionBuilders for the versions specified in `initial_versions`
         versions = Vec::with_capacity(create_data.initial_versions.len());
         for (i, data) in create_data.initial_versions.iter().enumerate() {
@@ -437,6 +591,7 @@ async fn project_create_inner(
                     project_id,
                     current_user.id,
                     &all_loaders,
+                    strict,
                     transaction,
                     redis,
                 )
@@ -500,6 +655,7 @@ async fn project_create_inner(
                         "Gallery image exceeds the maximum of 5MiB.",
                     )
                     .await?;
+                    crate::util::img::check_image_size(&data)?;
                     let hash = sha1::Sha1::from(&data).hexdigest();
                     let (_, file_extension) =
                         super::version_creation::get_name_ext(&content_disposition)?;
@@ -507,14 +663,23 @@ async fn project_create_inner(
                         .ok_or_else(|| {
                             CreateError::InvalidIconFormat(file_extension.to_string())
                         })?;
-                    let url = format!("data/{project_id}/images/{hash}.{file_extension}");
+                    // New projects are hidden, so their images start out private
+                    let path = image_path(format!("data/{project_id}/images/{hash}"), true);
+                    let url = format!("{path}.{file_extension}");
+                    let data = data.freeze();
                     let upload_data = file_host
-                        .upload_file(content_type, &url, data.freeze())
+                        .upload_file(content_type, &url, data.clone())
                         .await?;
                     uploaded_files.push(UploadedFile {
                         file_id: upload_data.file_id,
                         file_name: upload_data.file_name,
                     });
+                    let variants =
+                        crate::util::img::upload_image_variants(data, &path, file_host).await;
+                    uploaded_files.extend(variants.values().map(|x| UploadedFile {
+                        file_id: x.file_id.clone(),
+                        file_name: x.file_name.clone(),
+                    }));
                     gallery_urls.push(crate::models::projects::GalleryItem {
                         url: format!("{cdn_url}/{url}"),
                         featured: item.featured,
@@ -522,6 +687,8 @@ async fn project_create_inner(
                         description: item.description.clone(),
                         created: Utc::now(),
                         ordering: item.ordering,
+                        variants: crate::util::img::get_variant_urls(&variants, &cdn_url),
+                        image_id: None,
                     });
                     return Ok(());
                 }
@@ -555,6 +722,7 @@ async fn project_create_inner(
                 version_data.primary_file.is_some(),
                 version_data.primary_file.as_deref() == Some(name),
                 None,
+                version_data.allow_incompatible,
                 transaction,
                 redis,
             )
@@ -587,30 +755,48 @@ async fn project_create_inner(
             }
         }
 
-        // Convert the list of category names to actual categories
-        let mut categories = Vec::with_capacity(project_create_data.categories.len());
-        for category in &project_create_data.categories {
-            let ids = models::categories::Category::get_ids(category, &mut **transaction).await?;
-            if ids.is_empty() {
-                return Err(CreateError::InvalidCategory(category.clone()));
+        // The response doesn't include the versions, so the dependencies which couldn't be
+        // inferred aren't suggested
+        for (version_data, builder) in project_create_data
+            .initial_versions
+            .iter()
+            .zip(versions.iter_mut())
+        {
+            if version_data.infer_dependencies {
+                infer_dependencies(builder, &mut *transaction).await?;
             }
-
-            // TODO: We should filter out categories that don't match the project type of any of the versions
-            // ie: if mod and modpack both share a name this should only have modpack if it only has a modpack as a version
-            categories.extend(ids.values());
         }
 
-        let mut additional_categories =
-            Vec::with_capacity(project_create_data.additional_categories.len());
-        for category in &project_create_data.additional_categories {
-            let ids = models::categories::Category::get_ids(category, &mut **transaction).await?;
-            if ids.is_empty() {
-                return Err(CreateError::InvalidCategory(category.clone()));
-            }
-            // TODO: We should filter out categories that don't match the project type of any of the versions
-            // ie: if mod and modpack both share a name this should only have modpack if it only has a modpack as a version
-            additional_categories.extend(ids.values());
-        }
+        // Convert the list of category names to the categories of the project types of the
+        // initial versions
+        let project_types = all_loaders
+            .iter()
+            .filter(|loader| versions.iter().any(|v| v.loaders.contains(&loader.id)))
+            .flat_map(|loader| loader.supported_project_types.clone())
+            .unique()
+            .collect::<Vec<_>>();
+        let category_ids = models::categories::Category::get_ids_project_types(
+            &[
+                &project_create_data.categories[..],
+                &project_create_data.additional_categories[..],
+            ]
+            .concat(),
+            &project_types,
+            &mut **transaction,
+        )
+        .await?;
+        let categories = project_category_ids(
+            &project_create_data.categories,
+            &category_ids,
+            &project_types,
+        )
+        .map_err(CreateError::InvalidCategory)?;
+        let additional_categories = project_category_ids(
+            &project_create_data.additional_categories,
+            &category_ids,
+            &project_types,
+        )
+        .map_err(CreateError::InvalidCategory)?;
 
         let mut members = vec![];
 
@@ -630,6 +816,10 @@ async fn project_create_inner(
         let team = models::team_item::TeamBuilder { members };
 
         let team_id = team.insert(&mut *transaction).await?;
+        if let Some(organization_id) = project_create_data.organization_id {
+            OrganizationPayoutSplit::apply_to_team(organization_id.into(), team_id, transaction)
+                .await?;
+        }
 
         let status;
         if project_create_data.is_draft.unwrap_or(false) {
@@ -652,41 +842,53 @@ async fn project_create_inner(
 
         let link_platforms =
             models::categories::LinkPlatform::list(&mut **transaction, redis).await?;
-        for (platform, url) in &project_create_data.link_urls {
-            let platform_id =
-                models::categories::LinkPlatform::get_id(platform, &mut **transaction)
-                    .await?
-                    .ok_or_else(|| {
-                        CreateError::InvalidInput(format!(
-                            "Link platform {} does not exist.",
-                            platform.clone()
-                        ))
-                    })?;
+        for (name, url) in &project_create_data.link_urls {
+            let platform = LinkPlatform::of_link(name, url).map_err(CreateError::InvalidInput)?;
             let link_platform = link_platforms
                 .iter()
-                .find(|x| x.id == platform_id)
+                .find(|x| x.name == platform.as_str())
                 .ok_or_else(|| {
                     CreateError::InvalidInput(format!(
                         "Link platform {} does not exist.",
-                        platform.clone()
+                        platform.as_str()
                     ))
                 })?;
             link_urls.push(models::project_item::LinkUrl {
-                platform_id,
+                name: name.clone(),
+                platform_id: link_platform.id,
                 platform_name: link_platform.name.clone(),
                 url: url.clone(),
                 donation: link_platform.donation,
             })
         }
 
+        // Failing to import the README shouldn't stop the project from being created, so the
+        // description is left empty and the problem is returned as a warning instead
+        let mut warning = None;
+        let (description, description_fetched) = match &project_create_data.description_source_url {
+            Some(url) => match readme::fetch_readme(url).await {
+                Ok(markdown) => (markdown, Some(Utc::now())),
+                Err(err) => {
+                    warning = Some(format!("The description could not be imported: {err}"));
+                    (String::new(), None)
+                }
+            },
+            None => (
+                sanitize::sanitize_body(&project_create_data.description, strict)
+                    .map_err(|err| CreateError::InvalidInput(err.to_string()))?,
+                None,
+            ),
+        };
+
         let project_builder_actual = models::project_item::ProjectBuilder {
             project_id: project_id.into(),
             team_id,
             organization_id: project_create_data.organization_id.map(|x| x.into()),
             name: project_create_data.name,
             summary: project_create_data.summary,
-            description: project_create_data.description,
+            description,
             icon_url: icon_data.clone().map(|x| x.0),
+            icon_variants: icon_data.clone().map(|x| x.1).unwrap_or_default(),
 
             license_url: project_create_data.license_url,
             categories,
@@ -706,17 +908,52 @@ async fn project_create_inner(
                     description: x.description.clone(),
                     created: x.created,
                     ordering: x.ordering,
+                    variants: x.variants.clone(),
+                    image_id: None,
+                    moderation_status: ImageModerationStatus::Approved,
                 })
                 .collect(),
-            color: icon_data.and_then(|x| x.1),
+            color: icon_data.and_then(|x| x.2),
             monetization_status: MonetizationStatus::Monetized,
+            description_source_url: project_create_data.description_source_url,
+            description_fetched,
         };
         let project_builder = project_builder_actual.clone();
 
         let now = Utc::now();
 
         let id = project_builder_actual.insert(&mut *transaction).await?;
+        for version in &project_builder.initial_versions {
+            if let Some(metadata) = version.primary_mod_metadata() {
+                ModMetadataIds::register(
+                    project_builder.project_id,
+                    &metadata.mod_ids,
+                    &mut *transaction,
+                )
+                .await?;
+            }
+
+            super::version_creation::flag_reupload(
+                version.version_id,
+                project_builder.project_id,
+                &super::version_creation::sha1_hashes(&version.files),
+                current_user.id.into(),
+                &mut *transaction,
+                redis,
+            )
+            .await?;
+        }
+        created_versions.extend(
+            project_builder
+                .initial_versions
+                .iter()
+                .map(|v| v.version_id),
+        );
         User::clear_project_cache(&[current_user.id.into()], redis).await?;
+        models::Project::clear_cache(id, project_builder.slug.clone(), None, redis).await?;
+        if let Some(organization_id) = project_builder.organization_id {
+            models::Organization::clear_cache(organization_id, None, redis).await?;
+        }
 
         for image_id in project_create_data.uploaded_images {
             if let Some(db_image) =
@@ -792,10 +1029,13 @@ async fn project_create_inner(
             name: project_builder.name.clone(),
             summary: project_builder.summary.clone(),
             description: project_builder.description.clone(),
+            description_source_url: project_builder.description_source_url.clone(),
+            description_fetched: project_builder.description_fetched,
             published: now,
             updated: now,
             approved: None,
             queued: None,
+            go_live_at: None,
             status,
             requested_status: project_builder.requested_status,
             moderator_message: None,
@@ -806,6 +1046,7 @@ async fn project_create_inner(
             },
             downloads: 0,
             followers: 0,
+            display_categories: project_create_data.categories.clone(),
             categories: project_create_data.categories,
             additional_categories: project_create_data.additional_categories,
             loaders: vec![],
@@ -815,20 +1056,28 @@ async fn project_create_inner(
                 .map(|v| v.version_id.into())
                 .collect::<Vec<_>>(),
             icon_url: project_builder.icon_url.clone(),
+            icon_variants: project_builder.icon_variants.clone(),
             link_urls: project_builder
                 .link_urls
                 .clone()
                 .into_iter()
-                .map(|x| (x.platform_name.clone(), Link::from(x)))
+                .map(|x| (x.name.clone(), Link::from(x)))
                 .collect(),
             gallery: gallery_urls,
             color: project_builder.color,
             thread_id: thread_id.into(),
             monetization_status: MonetizationStatus::Monetized,
+            mature: false,
+            has_withheld_files: Some(false),
+            language: None,
+            fallback: None,
             fields: HashMap::new(), // Fields instantiate to empty
         };
 
-        Ok(HttpResponse::Ok().json(response))
+        Ok(HttpResponse::Ok().json(ProjectCreateResponse {
+            project: response,
+            warning,
+        }))
     }
 }
 
@@ -837,6 +1086,7 @@ async fn create_initial_version(
     project_id: ProjectId,
     author: UserId,
     all_loaders: &[models::loader_fields::Loader],
+    strict: bool,
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     redis: &RedisPool,
 ) -> Result<models::version_item::VersionBuilder, CreateError> {
@@ -885,6 +1135,7 @@ async fn create_initial_version(
             project_id: d.project_id.map(|x| x.into()),
             dependency_type: d.dependency_type.to_string(),
             file_name: None,
+            inferred: false,
         })
         .collect::<Vec<_>>();
 
@@ -894,7 +1145,11 @@ async fn create_initial_version(
         author_id: author.into(),
         name: version_data.version_title.clone(),
         version_number: version_data.version_number.clone(),
-        changelog: version_data.version_body.clone().unwrap_or_default(),
+        changelog: sanitize::sanitize_body(
+            version_data.version_body.as_deref().unwrap_or_default(),
+            strict,
+        )
+        .map_err(|err| CreateError::InvalidInput(err.to_string()))?,
         files: Vec::new(),
         dependencies,
         loaders,
@@ -904,6 +1159,7 @@ async fn create_initial_version(
         version_type: version_data.release_channel.to_string(),
         requested_status: None,
         ordering: version_data.ordering,
+        publish_at: None,
     };
 
     Ok(version)
@@ -916,18 +1172,21 @@ async fn process_icon_upload(
     file_host: &dyn FileHost,
     mut field: Field,
     cdn_url: &str,
-) -> Result<(String, Option<u32>), CreateError> {
+) -> Result<(String, HashMap<String, String>, Option<u32>), CreateError> {
     if let Some(content_type) = crate::util::ext::get_image_content_type(file_extension) {
         let data = read_from_field(&mut field, 262144, "Icons must be smaller than 256KiB").await?;
 
         let color = crate::util::img::get_color_from_img(&data)?;
 
         let hash = sha1::Sha1::from(&data).hexdigest();
+        // New projects are hidden, so their icons start out private
+        let path = image_path(format!("data/{id}/{hash}"), true);
+        let data = data.freeze();
         let upload_data = file_host
             .upload_file(
                 content_type,
-                &format!("data/{id}/{hash}.{file_extension}"),
-                data.freeze(),
+                &format!("{path}.{file_extension}"),
+                data.clone(),
             )
             .await?;
 
@@ -936,7 +1195,17 @@ async fn process_icon_upload(
             file_name: upload_data.file_name.clone(),
         });
 
-        Ok((format!("{}/{}", cdn_url, upload_data.file_name), color))
+        let variants = crate::util::img::upload_image_variants(data, &path, file_host).await;
+        uploaded_files.extend(variants.values().map(|x| UploadedFile {
+            file_id: x.file_id.clone(),
+            file_name: x.file_name.clone(),
+        }));
+
+        Ok((
+            format!("{}/{}", cdn_url, upload_data.file_name),
+            crate::util::img::get_variant_urls(&variants, cdn_url),
+            color,
+        ))
     } else {
         Err(CreateError::InvalidIconFormat(file_extension.to_string()))
     }
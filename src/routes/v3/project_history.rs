@@ -0,0 +1,155 @@
+use crate::auth::checks::is_visible_project;
+use crate::auth::get_user_from_headers;
+use crate::database::models::project_field_history_item;
+use crate::database::models::project_item::QueryProject;
+use crate::database::models::thread_item::ThreadMessageBuilder;
+use crate::database::models::{self as db_models, ids as db_ids, TeamMember};
+use crate::database::redis::RedisPool;
+use crate::models::pats::Scopes;
+use crate::models::project_history::{
+    license_restrictiveness, HistoryFieldGroup, ProjectFieldChange,
+};
+use crate::models::threads::MessageBody;
+use crate::models::users::AUTOMOD_USER;
+use crate::queue::session::AuthQueue;
+use crate::routes::ApiError;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use serde::Deserialize;
+use sqlx::PgPool;
+
+#[derive(Deserialize)]
+pub struct HistoryQuery {
+    /// The comma separated field groups to list the changes of, which defaults to all of them
+    pub fields: Option<String>,
+}
+
+/// Records a change to a field moderators check when approving a project, if it changed
+pub async fn record_field_change(
+    project_id: db_ids::ProjectId,
+    version_id: Option<db_ids::VersionId>,
+    field: &str,
+    old_value: serde_json::Value,
+    new_value: serde_json::Value,
+    actor_id: db_ids::UserId,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), ApiError> {
+    if old_value == new_value {
+        return Ok(());
+    }
+
+    project_field_history_item::ProjectFieldChange {
+        project_id,
+        version_id,
+        field: field.to_string(),
+        old_value,
+        new_value,
+        actor_id: Some(actor_id),
+        created: Utc::now(),
+    }
+    .insert(transaction)
+    .await?;
+
+    Ok(())
+}
+
+/// Leaves a note for the moderators in the project's thread when an approved project moves to a
+/// more restrictive license
+pub async fn note_license_change(
+    project: &QueryProject,
+    old_license: &str,
+    new_license: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), ApiError> {
+    if project.inner.approved.is_none()
+        || license_restrictiveness(new_license) <= license_restrictiveness(old_license)
+    {
+        return Ok(());
+    }
+
+    ThreadMessageBuilder {
+        author_id: Some(AUTOMOD_USER.into()),
+        body: MessageBody::Text {
+            body: format!(
+                "The license of this project was changed from {old_license} to the more restrictive {new_license} after it was approved."
+            ),
+            private: true,
+            replying_to: None,
+            associated_images: Vec::new(),
+        },
+        thread_id: project.thread_id,
+    }
+    .insert(transaction)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn project_history_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    web::Query(query): web::Query<HistoryQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?
+    .1;
+
+    let project = db_models::Project::get(&info.into_inner().0, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if !user.role.is_mod() {
+        let (team_member, organization_team_member) =
+            TeamMember::get_for_project_permissions(&project.inner, user.id.into(), &**pool)
+                .await?;
+
+        if team_member.is_none() && organization_team_member.is_none() {
+            // Hide the project from users who can't see it at all
+            if !is_visible_project(&project.inner, &Some(user), &pool).await? {
+                return Err(ApiError::NotFound);
+            }
+
+            return Err(ApiError::CustomAuthentication(
+                "You don't have permission to view this project's history.".to_string(),
+            ));
+        }
+    }
+
+    let groups = match &query.fields {
+        Some(fields) => fields
+            .split(',')
+            .map(|x| {
+                HistoryFieldGroup::from_string(x.trim()).ok_or_else(|| {
+                    ApiError::InvalidInput(format!("Unknown history field group: {x}"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => HistoryFieldGroup::list().to_vec(),
+    };
+    let fields = groups
+        .iter()
+        .flat_map(|x| x.fields())
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+
+    let changes = project_field_history_item::ProjectFieldChange::get_for_project(
+        project.inner.id,
+        &fields,
+        &**pool,
+    )
+    .await?
+    .into_iter()
+    .map(ProjectFieldChange::from)
+    .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(changes))
+}
@@ -0,0 +1,498 @@
+use crate::auth::get_user_from_headers;
+use crate::database::models::audit_log_item::AuditLogBuilder;
+use crate::database::models::notification_item::NotificationBuilder;
+use crate::database::models::project_transfer_item::ProjectTransfer;
+use crate::database::models::{self as db_models, TeamMember};
+use crate::database::redis::RedisPool;
+use crate::models::audit_log::{AuditAction, AuditTargetType};
+use crate::models::ids::{OrganizationId, UserId};
+use crate::models::notifications::NotificationBody;
+use crate::models::pats::Scopes;
+use crate::models::project_transfers::{self, ProjectTransferStatus, TRANSFER_EXPIRY_DAYS};
+use crate::models::teams::{OrganizationPermissions, ProjectPermissions, DEFAULT_ROLE, OWNER_ROLE};
+use crate::models::users::User;
+use crate::queue::session::AuthQueue;
+use crate::routes::v3::audit_log::request_ip;
+use crate::routes::v3::organizations::move_project_to_organization;
+use crate::routes::ApiError;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+#[derive(Serialize, Deserialize)]
+pub struct NewProjectTransfer {
+    /// The user to transfer the project to, if it isn't transferred to an organization
+    pub user_id: Option<UserId>,
+    /// The organization to transfer the project to, if it isn't transferred to a user
+    pub organization_id: Option<OrganizationId>,
+    /// The permissions the current owner keeps as a regular member once the transfer is accepted
+    #[serde(default)]
+    pub previous_owner_permissions: ProjectPermissions,
+}
+
+// Whether the user can accept or decline the transfer. Transfers to an organization can be
+// answered by any member who could add the project to it themselves.
+async fn is_recipient(
+    user: &User,
+    transfer: &ProjectTransfer,
+    pool: &PgPool,
+) -> Result<bool, ApiError> {
+    if let Some(organization_id) = transfer.target_organization_id {
+        let organization_team_member =
+            TeamMember::get_from_user_id_organization(organization_id, user.id.into(), false, pool)
+                .await?;
+        let permissions =
+            OrganizationPermissions::get_permissions_by_role(&user.role, &organization_team_member)
+                .unwrap_or_default();
+
+        Ok(permissions.contains(OrganizationPermissions::ADD_PROJECT))
+    } else {
+        Ok(transfer.target_user_id == Some(db_models::UserId::from(user.id)))
+    }
+}
+
+async fn record_transfer(
+    req: &HttpRequest,
+    user: &User,
+    transfer: &ProjectTransfer,
+    status: ProjectTransferStatus,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), ApiError> {
+    AuditLogBuilder {
+        actor_id: user.id.into(),
+        action: AuditAction::ProjectTransfer,
+        target_type: AuditTargetType::Project,
+        target_id: transfer.project_id.0,
+        project_id: Some(transfer.project_id),
+        organization_id: transfer.target_organization_id,
+        summary: serde_json::json!({
+            "status": status,
+            "initiated_by": UserId::from(transfer.initiated_by),
+            "user_id": transfer.target_user_id.map(UserId::from),
+            "organization_id": transfer.target_organization_id.map(OrganizationId::from),
+        }),
+        ip: request_ip(req),
+    }
+    .insert(transaction)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn project_transfer_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    new_transfer: web::Json<NewProjectTransfer>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+    let string = info.into_inner().0;
+
+    let project = db_models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    // Projects owned by an organization are moved out of it with a new owner instead
+    if project.inner.organization_id.is_some() {
+        return Err(ApiError::InvalidInput(
+            "Projects owned by an organization can't be transferred".to_string(),
+        ));
+    }
+
+    let team_member =
+        TeamMember::get_from_user_id_project(project.inner.id, user.id.into(), false, &**pool)
+            .await?;
+    if !team_member.map(|x| x.is_owner).unwrap_or(false) {
+        return Err(ApiError::CustomAuthentication(
+            "Only the owner of a project can transfer it".to_string(),
+        ));
+    }
+
+    let (recipient, organization) = match (new_transfer.user_id, new_transfer.organization_id) {
+        (Some(user_id), None) => {
+            if user_id == user.id {
+                return Err(ApiError::InvalidInput(
+                    "You already own this project".to_string(),
+                ));
+            }
+            db_models::User::get_id(user_id.into(), &**pool, &redis)
+                .await?
+                .ok_or_else(|| {
+                    ApiError::InvalidInput("The specified user does not exist!".to_string())
+                })?;
+
+            (Some(user_id.into()), None)
+        }
+        (None, Some(organization_id)) => {
+            let organization =
+                db_models::Organization::get_id(organization_id.into(), &**pool, &redis)
+                    .await?
+                    .ok_or_else(|| {
+                        ApiError::InvalidInput(
+                            "The specified organization does not exist!".to_string(),
+                        )
+                    })?;
+
+            // The organization's owner is asked to accept transfers to it
+            let owner = TeamMember::get_from_team_full(organization.team_id, &**pool, &redis)
+                .await?
+                .into_iter()
+                .find(|x| x.is_owner)
+                .map(|x| x.user_id);
+
+            (owner, Some(organization))
+        }
+        _ => {
+            return Err(ApiError::InvalidInput(
+                "A project must be transferred to either a user or an organization".to_string(),
+            ))
+        }
+    };
+
+    if ProjectTransfer::get_pending(project.inner.id, &**pool)
+        .await?
+        .is_some()
+    {
+        return Err(ApiError::InvalidInput(
+            "This project already has a pending transfer, which has to be cancelled first"
+                .to_string(),
+        ));
+    }
+
+    let now = Utc::now();
+    let transfer = ProjectTransfer {
+        project_id: project.inner.id,
+        initiated_by: user.id.into(),
+        target_user_id: new_transfer.user_id.map(|x| x.into()),
+        target_organization_id: organization.as_ref().map(|x| x.id),
+        previous_owner_permissions: new_transfer.previous_owner_permissions,
+        status: ProjectTransferStatus::Pending,
+        created: now,
+        expires: now + Duration::days(TRANSFER_EXPIRY_DAYS),
+    };
+
+    let mut transaction = pool.begin().await?;
+    ProjectTransfer::expire_stale(project.inner.id, &mut transaction).await?;
+    transfer.insert(&mut transaction).await?;
+
+    if let Some(recipient) = recipient {
+        NotificationBuilder {
+            body: NotificationBody::ProjectTransferRequested {
+                project_id: project.inner.id.into(),
+                initiated_by: user.id,
+                organization_id: transfer.target_organization_id.map(|x| x.into()),
+            },
+        }
+        .insert(recipient, &mut transaction, &redis)
+        .await?;
+    }
+    record_transfer(
+        &req,
+        &user,
+        &transfer,
+        ProjectTransferStatus::Pending,
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(project_transfers::ProjectTransfer::from(transfer)))
+}
+
+/// Gets the project's pending transfer, which only its owner and recipient can see
+pub async fn project_transfer_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?
+    .1;
+    let string = info.into_inner().0;
+
+    let project = db_models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let transfer = ProjectTransfer::get_pending(project.inner.id, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let is_owner =
+        TeamMember::get_from_user_id_project(project.inner.id, user.id.into(), false, &**pool)
+            .await?
+            .map(|x| x.is_owner)
+            .unwrap_or(false);
+    if !user.role.is_mod()
+        && !is_owner
+        && transfer.initiated_by != db_models::UserId::from(user.id)
+        && !is_recipient(&user, &transfer, &**pool).await?
+    {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(HttpResponse::Ok().json(project_transfers::ProjectTransfer::from(transfer)))
+}
+
+/// Cancels the project's pending transfer, which only the user who started it can do
+pub async fn project_transfer_cancel(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+    let string = info.into_inner().0;
+
+    let project = db_models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let transfer = ProjectTransfer::get_pending(project.inner.id, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if transfer.initiated_by != db_models::UserId::from(user.id) {
+        return Err(ApiError::CustomAuthentication(
+            "Only the user who started a transfer can cancel it".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+    if ProjectTransfer::resolve(
+        project.inner.id,
+        ProjectTransferStatus::Cancelled,
+        &mut transaction,
+    )
+    .await?
+    {
+        record_transfer(
+            &req,
+            &user,
+            &transfer,
+            ProjectTransferStatus::Cancelled,
+            &mut transaction,
+        )
+        .await?;
+    }
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn project_transfer_accept(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    answer_transfer(req, info, pool, redis, session_queue, true).await
+}
+
+pub async fn project_transfer_decline(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    answer_transfer(req, info, pool, redis, session_queue, false).await
+}
+
+async fn answer_transfer(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+    accept: bool,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+    let string = info.into_inner().0;
+
+    let project = db_models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    // Expired transfers can't be answered anymore
+    let transfer = ProjectTransfer::get_pending(project.inner.id, &**pool)
+        .await?
+        .ok_or_else(|| {
+            ApiError::InvalidInput("This project has no pending transfer".to_string())
+        })?;
+
+    if !is_recipient(&user, &transfer, &**pool).await? {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to answer this transfer".to_string(),
+        ));
+    }
+
+    let status = if accept {
+        ProjectTransferStatus::Accepted
+    } else {
+        ProjectTransferStatus::Declined
+    };
+
+    let mut transaction = pool.begin().await?;
+    if !ProjectTransfer::resolve(project.inner.id, status, &mut transaction).await? {
+        return Err(ApiError::InvalidInput(
+            "This project has no pending transfer".to_string(),
+        ));
+    }
+
+    let team_id = project.inner.team_id;
+    let mut previous_owner = None;
+    let mut organization = None;
+    if accept {
+        // The project may have changed hands since the transfer was started
+        if project.inner.organization_id.is_some() {
+            return Err(ApiError::InvalidInput(
+                "This project has been moved into an organization since the transfer was started"
+                    .to_string(),
+            ));
+        }
+        let owner = TeamMember::get_from_team_full(team_id, &**pool, &redis)
+            .await?
+            .into_iter()
+            .find(|x| x.is_owner)
+            .ok_or_else(|| ApiError::InvalidInput("This project has no owner".to_string()))?;
+        if transfer.target_user_id == Some(owner.user_id) {
+            return Err(ApiError::InvalidInput(
+                "You already own this project".to_string(),
+            ));
+        }
+
+        TeamMember::edit_team_member(
+            team_id,
+            owner.user_id,
+            Some(transfer.previous_owner_permissions),
+            None,
+            Some(DEFAULT_ROLE.to_string()),
+            None,
+            None,
+            None,
+            Some(false),
+            &mut transaction,
+        )
+        .await?;
+
+        if let Some(organization_id) = transfer.target_organization_id {
+            let organization_item =
+                db_models::Organization::get_id(organization_id, &**pool, &redis)
+                    .await?
+                    .ok_or_else(|| {
+                        ApiError::InvalidInput(
+                            "The specified organization does not exist!".to_string(),
+                        )
+                    })?;
+            move_project_to_organization(
+                project.inner.id,
+                team_id,
+                &organization_item,
+                &mut transaction,
+            )
+            .await?;
+            organization = Some(organization_item);
+        } else if TeamMember::get_from_user_id_pending(team_id, user.id.into(), &**pool)
+            .await?
+            .is_some()
+        {
+            TeamMember::edit_team_member(
+                team_id,
+                user.id.into(),
+                Some(ProjectPermissions::all()),
+                None,
+                Some(OWNER_ROLE.to_string()),
+                Some(true),
+                None,
+                None,
+                Some(true),
+                &mut transaction,
+            )
+            .await?;
+        } else {
+            let now = Utc::now();
+            TeamMember {
+                id: db_models::generate_team_member_id(&mut transaction).await?,
+                team_id,
+                user_id: user.id.into(),
+                role: OWNER_ROLE.to_string(),
+                is_owner: true,
+                permissions: ProjectPermissions::all(),
+                organization_permissions: None,
+                accepted: true,
+                payouts_split: Decimal::ZERO,
+                ordering: 0,
+                created: now,
+                accepted_at: Some(now),
+                invited_by: Some(transfer.initiated_by),
+                two_factor_restricted: false,
+                role_template_id: None,
+            }
+            .insert(&mut transaction)
+            .await?;
+        }
+
+        previous_owner = Some(owner.user_id);
+    }
+
+    record_transfer(&req, &user, &transfer, status, &mut transaction).await?;
+    NotificationBuilder {
+        body: NotificationBody::ProjectTransferResolved {
+            project_id: project.inner.id.into(),
+            accepted: accept,
+        },
+    }
+    .insert(transfer.initiated_by, &mut transaction, &redis)
+    .await?;
+
+    transaction.commit().await?;
+
+    if let Some(previous_owner) = previous_owner {
+        TeamMember::clear_cache(team_id, &redis).await?;
+        db_models::User::clear_project_cache(&[previous_owner, user.id.into()], &redis).await?;
+        db_models::Project::clear_cache(project.inner.id, project.inner.slug, None, &redis).await?;
+    }
+    if let Some(organization) = organization {
+        db_models::Organization::clear_cache(organization.id, Some(organization.slug), &redis)
+            .await?;
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
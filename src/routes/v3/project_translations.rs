@@ -0,0 +1,195 @@
+use crate::auth::get_user_from_headers;
+use crate::database::models as db_models;
+use crate::database::models::project_item::ProjectTranslation;
+use crate::database::redis::RedisPool;
+use crate::models::pats::Scopes;
+use crate::models::teams::ProjectPermissions;
+use crate::models::users::User;
+use crate::queue::session::AuthQueue;
+use crate::routes::v3::projects::check_not_archived;
+use crate::routes::ApiError;
+use crate::search::indexing::spawn_reindex_projects;
+use crate::search::SearchConfig;
+use crate::util::language::validate_language;
+use crate::util::sanitize;
+use crate::util::validate::validation_errors_to_string;
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use validator::Validate;
+
+/// A project's fields in another language. Fields which are left out are shown in the
+/// original language.
+#[derive(Serialize, Deserialize, Validate)]
+pub struct EditProjectTranslation {
+    #[validate(
+        length(min = 3, max = 64),
+        custom(function = "crate::util::validate::validate_name")
+    )]
+    pub name: Option<String>,
+    #[validate(length(min = 3, max = 256))]
+    pub summary: Option<String>,
+    #[validate(length(max = 65536))]
+    pub description: Option<String>,
+}
+
+pub async fn project_translation_edit(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    edit: web::Json<EditProjectTranslation>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+
+    edit.validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let (string, language) = info.into_inner();
+    let language = parse_language(&language)?;
+
+    let project = db_models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    check_not_archived(&project.inner)?;
+    check_translation_permissions(&project.inner, &user, &pool).await?;
+
+    let edit = edit.into_inner();
+    if edit.name.is_none() && edit.summary.is_none() && edit.description.is_none() {
+        return Err(ApiError::InvalidInput(
+            "A translation needs at least one of a name, summary or description!".to_string(),
+        ));
+    }
+
+    let description = edit
+        .description
+        .map(|x| sanitize::sanitize_body(&x, sanitize::is_strict(&req)))
+        .transpose()
+        .map_err(|err| ApiError::InvalidInput(err.to_string()))?;
+
+    let mut transaction = pool.begin().await?;
+    ProjectTranslation {
+        language,
+        name: edit.name,
+        summary: edit.summary,
+        description,
+    }
+    .upsert(project.inner.id, &mut transaction)
+    .await?;
+    transaction.commit().await?;
+
+    db_models::Project::clear_cache(project.inner.id, project.inner.slug, None, &redis).await?;
+
+    spawn_reindex_projects(
+        vec![project.inner.id],
+        pool.get_ref().clone(),
+        redis.get_ref().clone(),
+        search_config.get_ref().clone(),
+    );
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn project_translation_delete(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+
+    let (string, language) = info.into_inner();
+    let language = parse_language(&language)?;
+
+    let project = db_models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    check_not_archived(&project.inner)?;
+    check_translation_permissions(&project.inner, &user, &pool).await?;
+
+    let mut transaction = pool.begin().await?;
+    let removed = ProjectTranslation::remove(project.inner.id, &language, &mut transaction).await?;
+    transaction.commit().await?;
+
+    if !removed {
+        return Err(ApiError::NotFound);
+    }
+
+    db_models::Project::clear_cache(project.inner.id, project.inner.slug, None, &redis).await?;
+
+    // Reindexing replaces the project's documents, which drops the translated fields
+    spawn_reindex_projects(
+        vec![project.inner.id],
+        pool.get_ref().clone(),
+        redis.get_ref().clone(),
+        search_config.get_ref().clone(),
+    );
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+fn parse_language(language: &str) -> Result<String, ApiError> {
+    let language = language.to_lowercase();
+    validate_language(&language).map_err(|_| {
+        ApiError::InvalidInput(format!(
+            "{language} isn't a BCP-47 primary language subtag, such as `de`!"
+        ))
+    })?;
+
+    Ok(language)
+}
+
+async fn check_translation_permissions(
+    project: &db_models::project_item::Project,
+    user: &User,
+    pool: &PgPool,
+) -> Result<(), ApiError> {
+    if user.role.is_mod() {
+        return Ok(());
+    }
+
+    let (team_member, organization_team_member) =
+        db_models::TeamMember::get_for_project_permissions(project, user.id.into(), pool).await?;
+
+    // Hide the project
+    if team_member.is_none() && organization_team_member.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    let permissions = ProjectPermissions::get_permissions_by_role(
+        &user.role,
+        &team_member,
+        &organization_team_member,
+    )
+    .unwrap_or_default();
+
+    if !permissions.contains(ProjectPermissions::EDIT_DETAILS) {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to edit this project's translations.".to_string(),
+        ));
+    }
+
+    Ok(())
+}
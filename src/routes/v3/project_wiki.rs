@@ -0,0 +1,363 @@
+use crate::auth::checks::is_visible_project;
+use crate::auth::get_user_from_headers;
+use crate::database::models as db_models;
+use crate::database::models::wiki_page_item;
+use crate::database::redis::RedisPool;
+use crate::models::pats::Scopes;
+use crate::models::teams::ProjectPermissions;
+use crate::models::users::User;
+use crate::models::wiki::WikiPage;
+use crate::queue::session::AuthQueue;
+use crate::routes::v3::projects::check_not_archived;
+use crate::routes::ApiError;
+use crate::util::sanitize;
+use crate::util::validate::validation_errors_to_string;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use validator::Validate;
+
+/// The most wiki pages a project can have
+const MAX_WIKI_PAGES: usize = 50;
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct NewWikiPage {
+    #[validate(
+        length(min = 1, max = 64),
+        regex = "crate::util::validate::RE_URL_SAFE"
+    )]
+    pub slug: String,
+    #[validate(length(min = 1, max = 256))]
+    pub title: String,
+    #[validate(length(max = 65536))]
+    pub body: String,
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct EditWikiPage {
+    #[validate(
+        length(min = 1, max = 64),
+        regex = "crate::util::validate::RE_URL_SAFE"
+    )]
+    pub slug: Option<String>,
+    #[validate(length(min = 1, max = 256))]
+    pub title: Option<String>,
+    #[validate(length(max = 65536))]
+    pub body: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WikiOrder {
+    /// The slugs of every wiki page of the project, in their new order
+    pub slugs: Vec<String>,
+}
+
+// Gets the project if the user can see it, as its wiki is only as visible as it is
+async fn get_visible_project(
+    req: &HttpRequest,
+    project_id: &str,
+    pool: &web::Data<PgPool>,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> Result<db_models::project_item::QueryProject, ApiError> {
+    let user_option = get_user_from_headers(
+        req,
+        &**pool,
+        redis,
+        session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    let project = db_models::Project::get(project_id, &**pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    if !is_visible_project(&project.inner, &user_option, pool).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(project)
+}
+
+// Gets the project if the user can edit its wiki
+async fn get_editable_project(
+    project_id: &str,
+    user: &User,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<db_models::project_item::QueryProject, ApiError> {
+    let project = db_models::Project::get(project_id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    check_not_archived(&project.inner)?;
+
+    if user.role.is_mod() {
+        return Ok(project);
+    }
+
+    let (team_member, organization_team_member) =
+        db_models::TeamMember::get_for_project_permissions(&project.inner, user.id.into(), pool)
+            .await?;
+
+    // Hide the project
+    if team_member.is_none() && organization_team_member.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    let permissions = ProjectPermissions::get_permissions_by_role(
+        &user.role,
+        &team_member,
+        &organization_team_member,
+    )
+    .unwrap_or_default();
+
+    if !permissions.contains(ProjectPermissions::EDIT_WIKI) {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to edit this project's wiki.".to_string(),
+        ));
+    }
+
+    Ok(project)
+}
+
+pub async fn wiki_pages_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let project =
+        get_visible_project(&req, &info.into_inner().0, &pool, &redis, &session_queue).await?;
+
+    let pages = wiki_page_item::WikiPage::get_for_project(project.inner.id, &**pool)
+        .await?
+        .into_iter()
+        .map(WikiPage::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(pages))
+}
+
+pub async fn wiki_page_get(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let (project_id, slug) = info.into_inner();
+    let project = get_visible_project(&req, &project_id, &pool, &redis, &session_queue).await?;
+
+    let page = wiki_page_item::WikiPage::get(project.inner.id, &slug.to_lowercase(), &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(HttpResponse::Ok().json(WikiPage::from(page)))
+}
+
+pub async fn wiki_page_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    new_page: web::Json<NewWikiPage>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+
+    new_page
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let project = get_editable_project(&info.into_inner().0, &user, &pool, &redis).await?;
+
+    let new_page = new_page.into_inner();
+    let slug = new_page.slug.to_lowercase();
+    let body = sanitize::sanitize_body(&new_page.body, sanitize::is_strict(&req))
+        .map_err(|err| ApiError::InvalidInput(err.to_string()))?;
+
+    let mut transaction = pool.begin().await?;
+
+    let slugs = wiki_page_item::WikiPage::get_slugs(project.inner.id, &mut *transaction).await?;
+    if slugs.len() >= MAX_WIKI_PAGES {
+        return Err(ApiError::InvalidInput(format!(
+            "A project can have at most {MAX_WIKI_PAGES} wiki pages!"
+        )));
+    }
+    if slugs.contains(&slug) {
+        return Err(ApiError::Conflict(format!(
+            "The project already has a wiki page with the slug {slug}!"
+        )));
+    }
+
+    let now = Utc::now();
+    wiki_page_item::WikiPage {
+        project_id: project.inner.id,
+        slug: slug.clone(),
+        title: new_page.title,
+        body,
+        ordering: 0,
+        updated_by: user.id.into(),
+        created: now,
+        updated: now,
+    }
+    .insert(&mut transaction)
+    .await?;
+
+    let page = wiki_page_item::WikiPage::get(project.inner.id, &slug, &mut *transaction)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(WikiPage::from(page)))
+}
+
+pub async fn wiki_page_edit(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    edit: web::Json<EditWikiPage>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+
+    edit.validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let (project_id, slug) = info.into_inner();
+    let slug = slug.to_lowercase();
+    let project = get_editable_project(&project_id, &user, &pool, &redis).await?;
+
+    let mut transaction = pool.begin().await?;
+
+    let mut page = wiki_page_item::WikiPage::get(project.inner.id, &slug, &mut *transaction)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let edit = edit.into_inner();
+    if let Some(new_slug) = edit.slug.map(|x| x.to_lowercase()) {
+        if new_slug != slug
+            && wiki_page_item::WikiPage::get(project.inner.id, &new_slug, &mut *transaction)
+                .await?
+                .is_some()
+        {
+            return Err(ApiError::Conflict(format!(
+                "The project already has a wiki page with the slug {new_slug}!"
+            )));
+        }
+        page.slug = new_slug;
+    }
+    if let Some(title) = edit.title {
+        page.title = title;
+    }
+    if let Some(body) = edit.body {
+        page.body = sanitize::sanitize_body(&body, sanitize::is_strict(&req))
+            .map_err(|err| ApiError::InvalidInput(err.to_string()))?;
+    }
+    page.updated_by = user.id.into();
+
+    page.update(&slug, &mut transaction).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn wiki_page_delete(
+    req: HttpRequest,
+    info: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+
+    let (project_id, slug) = info.into_inner();
+    let project = get_editable_project(&project_id, &user, &pool, &redis).await?;
+
+    let mut transaction = pool.begin().await?;
+    let removed =
+        wiki_page_item::WikiPage::remove(project.inner.id, &slug.to_lowercase(), &mut transaction)
+            .await?;
+    transaction.commit().await?;
+
+    if !removed {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn wiki_order_edit(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    order: web::Json<WikiOrder>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+
+    let project = get_editable_project(&info.into_inner().0, &user, &pool, &redis).await?;
+
+    let slugs = order
+        .into_inner()
+        .slugs
+        .into_iter()
+        .map(|x| x.to_lowercase())
+        .collect::<Vec<_>>();
+
+    let mut transaction = pool.begin().await?;
+
+    let current_slugs =
+        wiki_page_item::WikiPage::get_slugs(project.inner.id, &mut *transaction).await?;
+    if current_slugs.iter().sorted().collect_vec() != slugs.iter().sorted().collect_vec() {
+        return Err(ApiError::InvalidInput(
+            "The new order must contain every wiki page of the project exactly once.".to_string(),
+        ));
+    }
+
+    wiki_page_item::WikiPage::reorder(project.inner.id, &slugs, &mut transaction).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
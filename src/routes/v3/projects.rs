@@ -1,34 +1,55 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::auth::checks::is_visible_project;
-use crate::auth::{filter_visible_projects, get_user_from_headers};
+use crate::auth::checks::{
+    filter_enlisted_projects_ids, filter_visible_project_ids, is_visible_project,
+};
+use crate::auth::get_user_from_headers;
+use crate::auth::validate::{check_pat_project_restriction, check_pat_project_restrictions};
+use crate::database::models::audit_log_item::AuditLogBuilder;
 use crate::database::models::notification_item::NotificationBuilder;
 use crate::database::models::project_item::{GalleryItem, ModCategory};
 use crate::database::models::thread_item::ThreadMessageBuilder;
+use crate::database::models::user_block_item::UserBlock;
 use crate::database::models::{ids as db_ids, image_item, TeamMember};
 use crate::database::redis::RedisPool;
 use crate::database::{self, models as db_models};
 use crate::file_hosting::FileHost;
 use crate::models;
+use crate::models::audit_log::{AuditAction, AuditTargetType};
 use crate::models::ids::base62_impl::parse_base62;
-use crate::models::images::ImageContext;
+use crate::models::ids::ImageId;
+use crate::models::images::{ImageContext, ImageModerationStatus};
+use crate::models::moderation::ModerationReview;
 use crate::models::notifications::NotificationBody;
 use crate::models::pats::Scopes;
 use crate::models::projects::{
-    MonetizationStatus, Project, ProjectId, ProjectStatus, SearchRequest,
+    LinkPlatform, MonetizationStatus, Project, ProjectId, ProjectStatus, SearchRequest,
 };
 use crate::models::teams::ProjectPermissions;
 use crate::models::threads::MessageBody;
 use crate::queue::session::AuthQueue;
+use crate::routes::v3::audit_log::request_ip;
+use crate::routes::v3::project_history::{note_license_change, record_field_change};
 use crate::routes::ApiError;
-use crate::search::indexing::remove_documents;
-use crate::search::{search_for_project, SearchConfig, SearchError};
-use crate::util::img;
+use crate::search::analytics::spawn_record_search;
+use crate::search::filters::validate_search_filters;
+use crate::search::indexing::{reindex_projects, remove_documents, spawn_reindex_projects};
+use crate::search::{
+    search_all, search_for_project, ResultSearchProject, SearchConfig, SearchError, SearchGroup,
+    SearchGrouping, SearchOrganization, SearchUser,
+};
+use crate::util::fields::FieldsQuery;
+use crate::util::language::preferred_languages;
 use crate::util::routes::read_from_payload;
+use crate::util::signed_urls::{
+    image_path, private_path, sign_project_images, signed_url_expiry, unsign_url,
+};
 use crate::util::validate::validation_errors_to_string;
+use crate::util::{etag, img, readme, sanitize};
+use actix_web::http::header::{self, EntityTag, HeaderName, HeaderValue};
 use actix_web::{web, HttpRequest, HttpResponse};
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
 use futures::TryStreamExt;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -36,8 +57,12 @@ use serde_json::json;
 use sqlx::PgPool;
 use validator::Validate;
 
+/// How many days a deleted project can be restored for before it's removed for good
+pub const PROJECT_RESTORE_WINDOW_DAYS: i64 = 30;
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.route("search", web::get().to(project_search));
+    cfg.route("search/all", web::get().to(search_all_get));
     cfg.route("projects", web::get().to(projects_get));
     cfg.route("projects", web::patch().to(projects_edit));
     cfg.route("projects_random", web::get().to(random_projects_get));
@@ -46,16 +71,130 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         web::scope("project")
             .route("{id}", web::get().to(project_get))
             .route("{id}/check", web::get().to(project_get_check))
+            .route(
+                "{id}/embed",
+                web::get().to(super::embeds::project_embed_get),
+            )
             .route("{id}", web::delete().to(project_delete))
+            .route("{id}/restore", web::post().to(project_restore))
             .route("{id}", web::patch().to(project_edit))
             .route("{id}/icon", web::patch().to(project_icon_edit))
             .route("{id}/icon", web::delete().to(delete_project_icon))
+            .route(
+                "{id}/description/refresh",
+                web::post().to(project_description_refresh),
+            )
             .route("{id}/gallery", web::post().to(add_gallery_item))
             .route("{id}/gallery", web::patch().to(edit_gallery_item))
             .route("{id}/gallery", web::delete().to(delete_gallery_item))
+            .route("{id}/gallery/order", web::patch().to(edit_gallery_order))
+            .route(
+                "{id}/gallery/{image_id}",
+                web::patch().to(edit_gallery_item_by_id),
+            )
+            .route(
+                "{id}/translations/{lang}",
+                web::put().to(super::project_translations::project_translation_edit),
+            )
+            .route(
+                "{id}/translations/{lang}",
+                web::delete().to(super::project_translations::project_translation_delete),
+            )
+            .route(
+                "{id}/wiki",
+                web::get().to(super::project_wiki::wiki_pages_get),
+            )
+            .route(
+                "{id}/wiki",
+                web::post().to(super::project_wiki::wiki_page_create),
+            )
+            .route(
+                "{id}/wiki",
+                web::patch().to(super::project_wiki::wiki_order_edit),
+            )
+            .route(
+                "{id}/wiki/{slug}",
+                web::get().to(super::project_wiki::wiki_page_get),
+            )
+            .route(
+                "{id}/wiki/{slug}",
+                web::patch().to(super::project_wiki::wiki_page_edit),
+            )
+            .route(
+                "{id}/wiki/{slug}",
+                web::delete().to(super::project_wiki::wiki_page_delete),
+            )
+            .route(
+                "{id}/moderation/review",
+                web::get().to(super::moderation::review_get),
+            )
+            .route(
+                "{id}/moderation/review",
+                web::put().to(super::moderation::review_edit),
+            )
             .route("{id}/follow", web::post().to(project_follow))
             .route("{id}/follow", web::delete().to(project_unfollow))
             .route("{id}/organization", web::get().to(project_get_organization))
+            .route(
+                "{id}/versions/bulk",
+                web::post().to(super::version_creation::version_create_bulk),
+            )
+            .route(
+                "{id}/webhooks",
+                web::get().to(super::webhooks::webhooks_get),
+            )
+            .route(
+                "{id}/audit-log",
+                web::get().to(super::audit_log::project_audit_log_get),
+            )
+            .route(
+                "{id}/history",
+                web::get().to(super::project_history::project_history_get),
+            )
+            .route(
+                "{id}/transfer",
+                web::get().to(super::project_transfers::project_transfer_get),
+            )
+            .route(
+                "{id}/transfer",
+                web::post().to(super::project_transfers::project_transfer_create),
+            )
+            .route(
+                "{id}/transfer",
+                web::delete().to(super::project_transfers::project_transfer_cancel),
+            )
+            .route(
+                "{id}/transfer/accept",
+                web::post().to(super::project_transfers::project_transfer_accept),
+            )
+            .route(
+                "{id}/transfer/decline",
+                web::post().to(super::project_transfers::project_transfer_decline),
+            )
+            .route(
+                "{id}/blocks",
+                web::get().to(super::project_blocks::project_blocks_get),
+            )
+            .route(
+                "{id}/blocks/{user_id}",
+                web::put().to(super::project_blocks::project_block_add),
+            )
+            .route(
+                "{id}/blocks/{user_id}",
+                web::delete().to(super::project_blocks::project_block_remove),
+            )
+            .route(
+                "{id}/webhooks",
+                web::post().to(super::webhooks::webhook_create),
+            )
+            .route(
+                "{id}/webhooks/{webhook_id}",
+                web::delete().to(super::webhooks::webhook_delete),
+            )
+            .route(
+                "{id}/statistics/downloads",
+                web::get().to(super::statistics::project_download_statistics),
+            )
             .service(
                 web::scope("{project_id}")
                     .route(
@@ -67,7 +206,15 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                         "version/{slug}",
                         web::get().to(super::versions::version_project_get),
                     )
-                    .route("dependencies", web::get().to(dependency_list)),
+                    .route(
+                        "version-matrix",
+                        web::get().to(super::versions::version_matrix),
+                    )
+                    .route("dependencies", web::get().to(dependency_list))
+                    .route(
+                        "changelog",
+                        web::get().to(super::versions::version_changelog),
+                    ),
             ),
     );
 }
@@ -119,10 +266,12 @@ pub struct ProjectIds {
 pub async fn projects_get(
     req: HttpRequest,
     web::Query(ids): web::Query<ProjectIds>,
+    web::Query(fields): web::Query<FieldsQuery>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
+    let fields = fields.parse().map_err(ApiError::InvalidInput)?;
     let ids = serde_json::from_str::<Vec<&str>>(&ids.ids)?;
     let projects_data = db_models::Project::get_many(&ids, &**pool, &redis).await?;
 
@@ -136,19 +285,130 @@ pub async fn projects_get(
     .await
     .map(|x| x.1)
     .ok();
+    check_pat_project_restrictions(&req, projects_data.iter().map(|x| x.inner.id))?;
+
+    let visible_ids = filter_visible_project_ids(
+        projects_data.iter().map(|x| &x.inner).collect(),
+        &user_option,
+        &pool,
+    )
+    .await?;
+    let projects = projects_data
+        .into_iter()
+        .filter(|x| visible_ids.contains(&x.inner.id))
+        .collect::<Vec<_>>();
+
+    let elevated_ids = filter_elevated_project_ids(&projects, &user_option, &pool).await?;
+    let cdn_url = dotenvy::var("CDN_URL")?;
+    let expires = signed_url_expiry(Utc::now());
+    let any_private_images = projects.iter().any(|x| has_private_images(x, &cdn_url));
+    let mut etag = etag::response_etag(
+        projects
+            .iter()
+            .map(|x| (&*x.etag, elevated_ids.contains(&x.inner.id))),
+    );
+    if any_private_images {
+        etag = etag::vary(etag, &expires.to_string());
+    }
+    let etag = vary_etag_by_fields(etag, &fields);
+    if let Some(response) = etag::not_modified(&req, &etag) {
+        return Ok(response);
+    }
+
+    let projects = projects
+        .into_iter()
+        .map(|mut x| {
+            let elevated = elevated_ids.contains(&x.inner.id);
+            x.hide_unmoderated_images(elevated);
+            let has_withheld_files = x.has_withheld_files;
+            let mut project = Project::from(x);
+            project.has_withheld_files = elevated.then_some(has_withheld_files);
+            if any_private_images {
+                sign_project_images(&mut project, expires)?;
+            }
+            Ok(project)
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+    with_etag_and_fields(etag, &projects, fields)
+}
+
+// Whether the project has images which haven't been moved out of the private prefix yet
+fn has_private_images(project: &db_models::project_item::QueryProject, cdn_url: &str) -> bool {
+    project
+        .inner
+        .icon_url
+        .iter()
+        .chain(project.gallery_items.iter().map(|x| &x.image_url))
+        .any(|x| private_path(x, cdn_url).is_some())
+}
+
+fn vary_etag_by_fields(etag: EntityTag, fields: &Option<Vec<String>>) -> EntityTag {
+    match fields {
+        Some(fields) => etag::vary(etag, &fields.join(",")),
+        None => etag,
+    }
+}
+
+// Projections are applied to the built models, so cached projects are never pruned
+fn with_etag_and_fields<T: Serialize>(
+    etag: EntityTag,
+    body: &T,
+    fields: Option<Vec<String>>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(match fields {
+        Some(fields) => etag::with_etag(etag, &crate::util::fields::prune(body, &fields)?),
+        None => etag::with_etag(etag, body),
+    })
+}
+
+// Projects whose team the user is on, or every project for moderators. These users can be shown
+// data that others can't, so their responses get a different ETag.
+async fn filter_elevated_project_ids(
+    projects: &[db_models::project_item::QueryProject],
+    user_option: &Option<models::users::User>,
+    pool: &web::Data<PgPool>,
+) -> Result<Vec<db_ids::ProjectId>, ApiError> {
+    if user_option
+        .as_ref()
+        .map(|x| x.role.is_mod())
+        .unwrap_or(false)
+    {
+        return Ok(projects.iter().map(|x| x.inner.id).collect());
+    }
+
+    filter_enlisted_projects_ids(
+        projects.iter().map(|x| &x.inner).collect(),
+        user_option,
+        pool,
+    )
+    .await
+}
 
-    let projects = filter_visible_projects(projects_data, &user_option, &pool).await?;
+#[derive(Deserialize, Default)]
+pub struct SlugRedirectQuery {
+    /// Whether to redirect requests for a project's old slug to its current one
+    #[serde(default)]
+    pub redirect: bool,
+}
 
-    Ok(HttpResponse::Ok().json(projects))
+#[derive(Serialize, Deserialize, Default)]
+pub struct LanguageQuery {
+    /// The language to show the project in, overriding the `Accept-Language` header
+    pub lang: Option<String>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn project_get(
     req: HttpRequest,
     info: web::Path<(String,)>,
+    web::Query(fields): web::Query<FieldsQuery>,
+    web::Query(slug_redirect): web::Query<SlugRedirectQuery>,
+    web::Query(language): web::Query<LanguageQuery>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
+    let fields = fields.parse().map_err(ApiError::InvalidInput)?;
     let string = info.into_inner().0;
 
     let project_data = db_models::Project::get(&string, &**pool, &redis).await?;
@@ -163,9 +423,83 @@ pub async fn project_get(
     .map(|x| x.1)
     .ok();
 
-    if let Some(data) = project_data {
+    if let Some(mut data) = project_data {
         if is_visible_project(&data.inner, &user_option, &pool).await? {
-            return Ok(HttpResponse::Ok().json(Project::from(data)));
+            let elevated =
+                !filter_elevated_project_ids(std::slice::from_ref(&data), &user_option, &pool)
+                    .await?
+                    .is_empty();
+
+            // The project was found through one of its old slugs
+            let moved_to = data.inner.slug.clone().filter(|slug| {
+                !slug.eq_ignore_ascii_case(&string)
+                    && parse_base62(&string).ok() != Some(data.inner.id.0 as u64)
+            });
+
+            if let Some(slug) = &moved_to {
+                if slug_redirect.redirect {
+                    let path = req.path();
+                    let mut location =
+                        format!("{}{}", &path[..path.rfind('/').unwrap_or(0) + 1], slug);
+                    if !req.query_string().is_empty() {
+                        location = format!("{}?{}", location, req.query_string());
+                    }
+
+                    return Ok(HttpResponse::PermanentRedirect()
+                        .append_header(("Location", location))
+                        .finish());
+                }
+            }
+
+            // The project is shown in the first language the client asked for that it's
+            // translated to
+            let translation = preferred_languages(&req, language.lang.as_deref())
+                .into_iter()
+                .find_map(|language| {
+                    data.translations
+                        .iter()
+                        .find(|x| x.language == language)
+                        .cloned()
+                });
+
+            let cdn_url = dotenvy::var("CDN_URL")?;
+            let expires = signed_url_expiry(Utc::now());
+            let private_images = has_private_images(&data, &cdn_url);
+
+            let mut etag = etag::response_etag([(&*data.etag, elevated)]);
+            if let Some(translation) = &translation {
+                etag = etag::vary(etag, &translation.language);
+            }
+            // Signed URLs change once they expire
+            if private_images {
+                etag = etag::vary(etag, &expires.to_string());
+            }
+            let etag = vary_etag_by_fields(etag, &fields);
+            if let Some(response) = etag::not_modified(&req, &etag) {
+                return Ok(response);
+            }
+
+            data.hide_unmoderated_images(elevated);
+            let has_withheld_files = data.has_withheld_files;
+            let mut project = Project::from(data);
+            project.has_withheld_files = elevated.then_some(has_withheld_files);
+            if private_images {
+                sign_project_images(&mut project, expires)?;
+            }
+            if let Some(translation) = translation {
+                project.localize(translation);
+            }
+
+            let mut response = with_etag_and_fields(etag, &project, fields)?;
+            response
+                .headers_mut()
+                .append(header::VARY, HeaderValue::from_static("accept-language"));
+            if let Some(Ok(slug)) = moved_to.map(|x| HeaderValue::from_str(&x)) {
+                response
+                    .headers_mut()
+                    .insert(HeaderName::from_static("x-slug-moved-to"), slug);
+            }
+            return Ok(response);
         }
     }
     Err(ApiError::NotFound)
@@ -182,10 +516,24 @@ pub struct EditProject {
     pub summary: Option<String>,
     #[validate(length(max = 65536))]
     pub description: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    #[validate(
+        custom(function = "crate::util::readme::validate_readme_url"),
+        length(max = 2048)
+    )]
+    pub description_source_url: Option<Option<String>>,
     #[validate(length(max = 3))]
     pub categories: Option<Vec<String>>,
     #[validate(length(max = 256))]
     pub additional_categories: Option<Vec<String>>,
+    /// Up to 3 of the project's categories to show on it in place of all of them. Empty to show
+    /// all of them again.
+    #[validate(length(max = 3))]
+    pub display_categories: Option<Vec<String>>,
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -227,6 +575,51 @@ pub struct EditProject {
     #[validate(length(max = 65536))]
     pub moderation_message_body: Option<Option<String>>,
     pub monetization_status: Option<MonetizationStatus>,
+    pub mature: Option<bool>,
+    /// When the project is listed once a moderator approves it, instead of right away. Can only
+    /// be set before the project is approved.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    #[validate(custom(function = "crate::util::validate::validate_publish_at"))]
+    pub go_live_at: Option<Option<DateTime<Utc>>>,
+}
+
+/// The response to an approval which scheduled the project to go live later
+#[derive(Serialize, Deserialize)]
+pub struct ScheduledGoLive {
+    pub status: ProjectStatus,
+    pub go_live_at: DateTime<Utc>,
+}
+
+/// Archived projects are read-only until they are unarchived, though they stay visible
+pub fn check_not_archived(project: &db_models::project_item::Project) -> Result<(), ApiError> {
+    if project.status.is_read_only() {
+        return Err(ApiError::InvalidInput(
+            "This project is archived and can't be changed until it is unarchived!".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Checks a link, and finds the platform it's stored under
+fn link_platform_id(
+    link_platforms: &[db_models::categories::LinkPlatform],
+    name: &str,
+    url: &str,
+) -> Result<db_ids::LinkPlatformId, ApiError> {
+    let platform = LinkPlatform::of_link(name, url).map_err(ApiError::InvalidInput)?;
+
+    link_platforms
+        .iter()
+        .find(|x| x.name == platform.as_str())
+        .map(|x| x.id)
+        .ok_or_else(|| {
+            ApiError::InvalidInput(format!("Platform {} does not exist.", platform.as_str()))
+        })
 }
 
 pub async fn project_edit(
@@ -316,6 +709,37 @@ pub async fn project_edit(
                 .await?;
             }
 
+            if let Some(go_live_at) = &new_project.go_live_at {
+                if !perms.contains(ProjectPermissions::EDIT_DETAILS) {
+                    return Err(ApiError::CustomAuthentication(
+                        "You do not have the permissions to schedule when this project goes live!"
+                            .to_string(),
+                    ));
+                }
+
+                if project_item.inner.status.is_approved()
+                    || project_item.inner.status == ProjectStatus::Scheduled
+                {
+                    return Err(ApiError::InvalidInput(
+                        "Only projects which haven't been approved yet can be scheduled to go live!"
+                            .to_string(),
+                    ));
+                }
+
+                sqlx::query!(
+                    "
+                    UPDATE mods
+                    SET go_live_at = $1
+                    WHERE (id = $2)
+                    ",
+                    *go_live_at,
+                    id as db_ids::ProjectId,
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+
+            let mut scheduled_go_live = None;
             if let Some(status) = &new_project.status {
                 if !perms.contains(ProjectPermissions::EDIT_DETAILS) {
                     return Err(ApiError::CustomAuthentication(
@@ -324,6 +748,12 @@ pub async fn project_edit(
                     ));
                 }
 
+                if status == &ProjectStatus::Deleted {
+                    return Err(ApiError::InvalidInput(
+                        "Projects can only be deleted through the delete route!".to_string(),
+                    ));
+                }
+
                 if !(user.role.is_mod()
                     || !project_item.inner.status.is_approved()
                         && status == &ProjectStatus::Processing
@@ -334,6 +764,23 @@ pub async fn project_edit(
                     ));
                 }
 
+                // Approving a project whose team scheduled it to go live leaves it scheduled
+                // until then, when it's listed
+                let decision = *status;
+                scheduled_go_live = new_project
+                    .go_live_at
+                    .unwrap_or(project_item.inner.go_live_at)
+                    .filter(|x| {
+                        *x > Utc::now()
+                            && decision == ProjectStatus::Approved
+                            && !project_item.inner.status.is_approved()
+                    });
+                let status = if scheduled_go_live.is_some() {
+                    &ProjectStatus::Scheduled
+                } else {
+                    status
+                };
+
                 if status == &ProjectStatus::Processing {
                     if project_item.versions.is_empty() {
                         return Err(ApiError::InvalidInput(String::from(
@@ -461,6 +908,55 @@ pub async fn project_edit(
                 .insert(&mut transaction)
                 .await?;
 
+                // A moderator deciding on the project ends its review, and rejections tell the
+                // team which checks failed
+                if user.role.is_mod() && status != &ProjectStatus::Processing {
+                    let review = db_models::moderation_review_item::ModerationReview::decide(
+                        id,
+                        decision,
+                        &mut transaction,
+                    )
+                    .await?;
+
+                    if let Some(message) = review
+                        .filter(|_| status == &ProjectStatus::Rejected)
+                        .and_then(|x| ModerationReview::from(x).rejection_message())
+                    {
+                        ThreadMessageBuilder {
+                            author_id: Some(user.id.into()),
+                            body: MessageBody::Text {
+                                body: message,
+                                private: false,
+                                replying_to: None,
+                                associated_images: Vec::new(),
+                            },
+                            thread_id: project_item.thread_id,
+                        }
+                        .insert(&mut transaction)
+                        .await?;
+                    }
+                }
+
+                if status != &project_item.inner.status {
+                    AuditLogBuilder {
+                        actor_id: user.id.into(),
+                        action: AuditAction::ProjectStatusChange,
+                        target_type: AuditTargetType::Project,
+                        target_id: id.0,
+                        project_id: Some(id),
+                        organization_id: project_item.inner.organization_id,
+                        summary: json!({
+                            "status": {
+                                "old": project_item.inner.status,
+                                "new": status,
+                            }
+                        }),
+                        ip: request_ip(&req),
+                    }
+                    .insert(&mut transaction)
+                    .await?;
+                }
+
                 sqlx::query!(
                     "
                     UPDATE mods
@@ -545,6 +1041,7 @@ pub async fn project_edit(
             if let Some(categories) = &new_project.categories {
                 edit_project_categories(
                     categories,
+                    &project_item.project_types,
                     &perms,
                     id as db_ids::ProjectId,
                     false,
@@ -556,6 +1053,7 @@ pub async fn project_edit(
             if let Some(categories) = &new_project.additional_categories {
                 edit_project_categories(
                     categories,
+                    &project_item.project_types,
                     &perms,
                     id as db_ids::ProjectId,
                     true,
@@ -564,6 +1062,48 @@ pub async fn project_edit(
                 .await?;
             }
 
+            // Edited categories are inserted again, so which are displayed is set again too
+            if new_project.display_categories.is_some()
+                || new_project.categories.is_some()
+                || new_project.additional_categories.is_some()
+            {
+                if let Some(display_categories) = &new_project.display_categories {
+                    if !perms.contains(ProjectPermissions::EDIT_DETAILS) {
+                        return Err(ApiError::CustomAuthentication(
+                            "You do not have the permissions to edit the displayed categories of this project!"
+                                .to_string(),
+                        ));
+                    }
+
+                    let categories = new_project
+                        .categories
+                        .as_ref()
+                        .unwrap_or(&project_item.categories);
+                    let additional_categories = new_project
+                        .additional_categories
+                        .as_ref()
+                        .unwrap_or(&project_item.additional_categories);
+                    if let Some(category) = display_categories
+                        .iter()
+                        .find(|x| !categories.contains(*x) && !additional_categories.contains(*x))
+                    {
+                        return Err(ApiError::InvalidInput(format!(
+                            "{category} isn't one of the categories of this project"
+                        )));
+                    }
+                }
+
+                ModCategory::set_display(
+                    id,
+                    new_project
+                        .display_categories
+                        .as_ref()
+                        .unwrap_or(&project_item.display_categories),
+                    &mut transaction,
+                )
+                .await?;
+            }
+
             if let Some(license_url) = &new_project.license_url {
                 if !perms.contains(ProjectPermissions::EDIT_DETAILS) {
                     return Err(ApiError::CustomAuthentication(
@@ -583,6 +1123,17 @@ pub async fn project_edit(
                 )
                 .execute(&mut *transaction)
                 .await?;
+
+                record_field_change(
+                    id,
+                    None,
+                    "license_url",
+                    json!(project_item.inner.license_url),
+                    json!(license_url),
+                    user.id.into(),
+                    &mut transaction,
+                )
+                .await?;
             }
 
             if let Some(slug) = &new_project.slug {
@@ -628,6 +1179,19 @@ pub async fn project_edit(
                             "Slug collides with other project's id!".to_string(),
                         ));
                     }
+
+                    if db_models::Project::is_slug_reserved(slug, Some(id), &mut *transaction)
+                        .await?
+                    {
+                        return Err(ApiError::InvalidInput(
+                            "Slug was recently used by another project!".to_string(),
+                        ));
+                    }
+
+                    if let Some(old_slug) = &project_item.inner.slug {
+                        db_models::Project::insert_slug_history(id, old_slug, &mut transaction)
+                            .await?;
+                    }
                 }
 
                 sqlx::query!(
@@ -672,6 +1236,24 @@ pub async fn project_edit(
                 )
                 .execute(&mut *transaction)
                 .await?;
+
+                record_field_change(
+                    id,
+                    None,
+                    "license_id",
+                    json!(project_item.inner.license),
+                    json!(license),
+                    user.id.into(),
+                    &mut transaction,
+                )
+                .await?;
+                note_license_change(
+                    &project_item,
+                    &project_item.inner.license,
+                    &license,
+                    &mut transaction,
+                )
+                .await?;
             }
             if let Some(links) = &new_project.link_urls {
                 if !links.is_empty() {
@@ -690,9 +1272,7 @@ pub async fn project_edit(
                     sqlx::query!(
                         "
                         DELETE FROM mods_links
-                        WHERE joining_mod_id = $1 AND joining_platform_id IN (
-                            SELECT id FROM link_platforms WHERE name = ANY($2)
-                        )
+                        WHERE joining_mod_id = $1 AND name = ANY($2)
                         ",
                         id as db_ids::ProjectId,
                         &ids_to_delete
@@ -700,25 +1280,19 @@ pub async fn project_edit(
                     .execute(&mut *transaction)
                     .await?;
 
-                    for (platform, url) in links {
+                    let link_platforms =
+                        db_models::categories::LinkPlatform::list(&mut *transaction, &redis)
+                            .await?;
+                    for (name, url) in links {
                         if let Some(url) = url {
-                            let platform_id = db_models::categories::LinkPlatform::get_id(
-                                platform,
-                                &mut *transaction,
-                            )
-                            .await?
-                            .ok_or_else(|| {
-                                ApiError::InvalidInput(format!(
-                                    "Platform {} does not exist.",
-                                    platform.clone()
-                                ))
-                            })?;
+                            let platform_id = link_platform_id(&link_platforms, name, url)?;
                             sqlx::query!(
                                 "
-                                INSERT INTO mods_links (joining_mod_id, joining_platform_id, url)
-                                VALUES ($1, $2, $3)
+                                INSERT INTO mods_links (joining_mod_id, name, joining_platform_id, url)
+                                VALUES ($1, $2, $3, $4)
                                 ",
                                 id as db_ids::ProjectId,
+                                name,
                                 platform_id as db_ids::LinkPlatformId,
                                 url
                             )
@@ -783,6 +1357,9 @@ pub async fn project_edit(
                     ));
                 }
 
+                let description = sanitize::sanitize_body(description, sanitize::is_strict(&req))
+                    .map_err(|err| ApiError::InvalidInput(err.to_string()))?;
+
                 sqlx::query!(
                     "
                     UPDATE mods
@@ -796,6 +1373,46 @@ pub async fn project_edit(
                 .await?;
             }
 
+            if let Some(description_source_url) = &new_project.description_source_url {
+                if !perms.contains(ProjectPermissions::EDIT_BODY) {
+                    return Err(ApiError::CustomAuthentication(
+                        "You do not have the permissions to edit the description (body) of this project!"
+                            .to_string(),
+                    ));
+                }
+
+                if new_project.description.is_some() && description_source_url.is_some() {
+                    return Err(ApiError::InvalidInput(
+                        "A description can't be submitted along with a README to import it from!"
+                            .to_string(),
+                    ));
+                }
+
+                // Removing the source keeps the imported description
+                let description = match description_source_url {
+                    Some(url) => Some(
+                        readme::fetch_readme(url)
+                            .await
+                            .map_err(|err| ApiError::InvalidInput(err.to_string()))?,
+                    ),
+                    None => None,
+                };
+
+                sqlx::query!(
+                    "
+                    UPDATE mods
+                    SET description = COALESCE($1, description), description_source_url = $2, description_fetched = $3
+                    WHERE (id = $4)
+                    ",
+                    description,
+                    description_source_url.as_deref(),
+                    description.as_ref().map(|_| Utc::now()),
+                    id as db_ids::ProjectId,
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+
             if let Some(monetization_status) = &new_project.monetization_status {
                 if !perms.contains(ProjectPermissions::EDIT_DETAILS) {
                     return Err(ApiError::CustomAuthentication(
@@ -828,11 +1445,35 @@ pub async fn project_edit(
                 .await?;
             }
 
+            if let Some(mature) = new_project.mature {
+                if !perms.contains(ProjectPermissions::EDIT_DETAILS) {
+                    return Err(ApiError::CustomAuthentication(
+                        "You do not have the permissions to edit the mature flag of this project!"
+                            .to_string(),
+                    ));
+                }
+
+                sqlx::query!(
+                    "
+                    UPDATE mods
+                    SET mature = $1
+                    WHERE (id = $2)
+                    ",
+                    mature,
+                    id as db_ids::ProjectId,
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+
             // check new description and body for links to associated images
             // if they no longer exist in the description or body, delete them
+            // The gallery images and icon are uploaded images of the project as well
             let checkable_strings: Vec<&str> = vec![&new_project.description, &new_project.summary]
                 .into_iter()
                 .filter_map(|x| x.as_ref().map(|y| y.as_str()))
+                .chain(project_item.gallery_items.iter().map(|x| &*x.image_url))
+                .chain(project_item.inner.icon_url.as_deref())
                 .collect();
 
             let context = ImageContext::Project {
@@ -849,6 +1490,36 @@ pub async fn project_edit(
                 &redis,
             )
             .await?;
+            // The new slug may have been looked up before the project had it
+            if new_project.slug.is_some() {
+                db_models::Project::clear_cache(
+                    project_item.inner.id,
+                    new_project.slug.clone(),
+                    None,
+                    &redis,
+                )
+                .await?;
+            }
+            // The organization's project summary depends on the statuses of its projects
+            if let Some(organization_id) = project_item.inner.organization_id {
+                if new_project.status.is_some() {
+                    db_models::Organization::clear_cache(organization_id, None, &redis).await?;
+                }
+            }
+
+            spawn_reindex_projects(
+                vec![project_item.inner.id],
+                pool.get_ref().clone(),
+                redis.get_ref().clone(),
+                search_config.get_ref().clone(),
+            );
+
+            if let Some(go_live_at) = scheduled_go_live {
+                return Ok(HttpResponse::Ok().json(ScheduledGoLive {
+                    status: ProjectStatus::Scheduled,
+                    go_live_at,
+                }));
+            }
 
             Ok(HttpResponse::NoContent().body(""))
         } else {
@@ -861,8 +1532,42 @@ pub async fn project_edit(
     }
 }
 
+/// Gets the ids of the categories to give a project of the project types, given the ids of the
+/// categories registered for them. Fails naming the categories which aren't registered for any
+/// of the types. Projects without versions don't have a type yet, so their categories only have
+/// to exist.
+pub fn project_category_ids(
+    categories: &[String],
+    category_ids: &HashMap<String, Vec<db_ids::CategoryId>>,
+    project_types: &[String],
+) -> Result<Vec<db_ids::CategoryId>, String> {
+    let invalid = categories
+        .iter()
+        .filter(|x| !category_ids.contains_key(*x))
+        .map(|x| {
+            if project_types.is_empty() {
+                format!("{x} doesn't exist")
+            } else {
+                format!(
+                    "{x} isn't a category of {} projects",
+                    project_types.join("/")
+                )
+            }
+        })
+        .collect::<Vec<_>>();
+    if !invalid.is_empty() {
+        return Err(invalid.join(", "));
+    }
+
+    Ok(categories
+        .iter()
+        .flat_map(|x| category_ids.get(x).into_iter().flatten().copied())
+        .collect())
+}
+
 pub async fn edit_project_categories(
-    categories: &Vec<String>,
+    categories: &[String],
+    project_types: &[String],
     perms: &ProjectPermissions,
     project_id: db_ids::ProjectId,
     additional: bool,
@@ -875,50 +1580,191 @@ pub async fn edit_project_categories(
         )));
     }
 
-    let mut mod_categories = Vec::new();
-    for category in categories {
-        let category_ids =
-            db_models::categories::Category::get_ids(category, &mut **transaction).await?;
-        // TODO: We should filter out categories that don't match the project type of any of the versions
-        // ie: if mod and modpack both share a name this should only have modpack if it only has a modpack as a version
-
-        let mcategories = category_ids
-            .values()
-            .map(|x| ModCategory::new(project_id, *x, additional))
-            .collect::<Vec<_>>();
-        mod_categories.extend(mcategories);
-    }
+    let category_ids = db_models::categories::Category::get_ids_project_types(
+        categories,
+        project_types,
+        &mut **transaction,
+    )
+    .await?;
+    let mod_categories = project_category_ids(categories, &category_ids, project_types)
+        .map_err(|err| ApiError::InvalidInput(format!("Invalid category: {err}")))?
+        .into_iter()
+        .map(|x| ModCategory::new(project_id, x, additional))
+        .collect::<Vec<_>>();
     ModCategory::insert_many(mod_categories, &mut *transaction).await?;
 
     Ok(())
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct ReturnSearchResults {
-    pub hits: Vec<Project>,
+pub struct ReturnSearchResults<T = Project> {
+    pub hits: Vec<T>,
     pub page: usize,
     pub hits_per_page: usize,
     pub total_hits: usize,
+    /// The cutoffs computed from `updated_within` and `created_within`, if they were given
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub updated_after: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub created_after: Option<DateTime<Utc>>,
 }
 
-pub async fn project_search(
+/// The version a hit is for, given with each hit of searches grouped by version
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SearchHitVersion {
+    pub id: models::ids::VersionId,
+    pub game_versions: Vec<String>,
+    pub loaders: Vec<String>,
+}
+
+impl SearchHitVersion {
+    fn from_search(hit: &ResultSearchProject) -> Option<Self> {
+        Some(SearchHitVersion {
+            id: models::ids::VersionId(parse_base62(&hit.version_id).ok()?),
+            game_versions: hit
+                .loader_fields
+                .get("game_versions")
+                .into_iter()
+                .flatten()
+                .filter_map(|x| x.as_str().map(String::from))
+                .collect(),
+            loaders: hit.version_loaders.clone(),
+        })
+    }
+}
+
+/// Mature projects are only included in search results if they were requested, and the
+/// authenticated user has turned off hiding mature content
+pub async fn search_shows_mature(
+    req: &HttpRequest,
+    info: &SearchRequest,
+    pool: &PgPool,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> bool {
+    info.include_mature
+        && get_user_from_headers(
+            req,
+            pool,
+            redis,
+            session_queue,
+            Some(&[Scopes::PROJECT_READ]),
+        )
+        .await
+        .map(|x| x.1.hide_mature_content == Some(false))
+        .unwrap_or(false)
+}
+
+pub async fn project_search(
+    req: HttpRequest,
     web::Query(info): web::Query<SearchRequest>,
     config: web::Data<SearchConfig>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, SearchError> {
-    let results = search_for_project(&info, &config).await?;
+    let fields = crate::util::fields::parse_fields(info.fields.as_deref())
+        .map_err(SearchError::InvalidInput)?;
+    let grouping = SearchGrouping::parse(info.group.as_deref())?;
+    validate_search_filters(&info, &pool, &redis).await?;
+    let show_mature = search_shows_mature(&req, &info, &pool, &redis, &session_queue).await;
+    let results = search_for_project(&info, &config, show_mature, fields.as_deref()).await?;
+    spawn_record_search(
+        info.query.as_deref(),
+        results.total_hits,
+        redis.get_ref().clone(),
+    );
 
-    let results = ReturnSearchResults {
-        hits: results
-            .hits
-            .into_iter()
-            .filter_map(Project::from_search)
-            .collect::<Vec<_>>(),
+    let hits = results
+        .hits
+        .into_iter()
+        .filter_map(|hit| {
+            let version = match grouping {
+                SearchGrouping::Project => None,
+                SearchGrouping::Version => Some(SearchHitVersion::from_search(&hit)?),
+            };
+            Some((Project::from_search(hit)?, version))
+        })
+        .collect::<Vec<_>>();
+    if fields.is_none() && grouping == SearchGrouping::Project {
+        return Ok(HttpResponse::Ok().json(ReturnSearchResults {
+            hits: hits.into_iter().map(|(project, _)| project).collect(),
+            page: results.page,
+            hits_per_page: results.hits_per_page,
+            total_hits: results.total_hits,
+            updated_after: results.updated_after,
+            created_after: results.created_after,
+        }));
+    }
+
+    // Hits of searches grouped by version are given the version they're for
+    let hits = hits
+        .iter()
+        .map(|(project, version)| {
+            let mut hit = match &fields {
+                Some(fields) => crate::util::fields::prune(project, fields)?,
+                None => serde_json::to_value(project)?,
+            };
+            if let (Some(version), Some(hit)) = (version, hit.as_object_mut()) {
+                hit.insert("version".to_string(), serde_json::to_value(version)?);
+            }
+            Ok(hit)
+        })
+        .collect::<Result<Vec<_>, serde_json::Error>>()?;
+    Ok(HttpResponse::Ok().json(ReturnSearchResults {
+        hits,
         page: results.page,
         hits_per_page: results.hits_per_page,
         total_hits: results.total_hits,
-    };
+        updated_after: results.updated_after,
+        created_after: results.created_after,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct AllSearchRequest {
+    pub query: Option<String>,
+    /// How many hits to return for each kind of result
+    pub limit: Option<usize>,
+}
 
-    Ok(HttpResponse::Ok().json(results))
+const DEFAULT_ALL_SEARCH_LIMIT: usize = 5;
+const MAX_ALL_SEARCH_LIMIT: usize = 100;
+
+#[derive(Serialize, Deserialize)]
+pub struct ReturnAllSearchResults {
+    pub projects: SearchGroup<Project>,
+    pub users: SearchGroup<SearchUser>,
+    pub organizations: SearchGroup<SearchOrganization>,
+}
+
+/// Searches projects, users and organizations at once, grouping the hits by kind
+pub async fn search_all_get(
+    web::Query(info): web::Query<AllSearchRequest>,
+    config: web::Data<SearchConfig>,
+) -> Result<HttpResponse, SearchError> {
+    let limit = info.limit.unwrap_or(DEFAULT_ALL_SEARCH_LIMIT);
+    if !(1..=MAX_ALL_SEARCH_LIMIT).contains(&limit) {
+        return Err(SearchError::InvalidInput(format!(
+            "The limit must be between 1 and {MAX_ALL_SEARCH_LIMIT}"
+        )));
+    }
+
+    let results = search_all(info.query.as_deref().unwrap_or_default(), limit, &config).await?;
+
+    Ok(HttpResponse::Ok().json(ReturnAllSearchResults {
+        projects: SearchGroup {
+            hits: results
+                .projects
+                .hits
+                .into_iter()
+                .filter_map(Project::from_search)
+                .collect(),
+            total_hits: results.projects.total_hits,
+        },
+        users: results.users,
+        organizations: results.organizations,
+    }))
 }
 
 //checks the validity of a project id or slug
@@ -1075,6 +1921,10 @@ pub async fn projects_edit(
         .map(|x| x.into())
         .collect();
 
+    for project_id in &project_ids {
+        check_pat_project_restriction(&req, *project_id)?;
+    }
+
     let projects_data = db_models::Project::get_many_ids(&project_ids, &**pool, &redis).await?;
 
     if let Some(id) = project_ids
@@ -1163,6 +2013,7 @@ pub async fn projects_edit(
         bulk_edit_project_categories(
             &categories,
             &project.categories,
+            &project.project_types,
             project.inner.id as db_ids::ProjectId,
             CategoryChanges::new(
                 &bulk_edit_project.categories,
@@ -1178,6 +2029,7 @@ pub async fn projects_edit(
         bulk_edit_project_categories(
             &categories,
             &project.additional_categories,
+            &project.project_types,
             project.inner.id as db_ids::ProjectId,
             CategoryChanges::new(
                 &bulk_edit_project.additional_categories,
@@ -1189,6 +2041,15 @@ pub async fn projects_edit(
             &mut transaction,
         )
         .await?;
+        // The edited categories were inserted again, without being displayed
+        if !project.display_categories.is_empty() {
+            ModCategory::set_display(
+                project.inner.id,
+                &project.display_categories,
+                &mut transaction,
+            )
+            .await?;
+        }
 
         if let Some(links) = &bulk_edit_project.link_urls {
             let ids_to_delete = links
@@ -1199,9 +2060,7 @@ pub async fn projects_edit(
             sqlx::query!(
                 "
                 DELETE FROM mods_links
-                WHERE joining_mod_id = $1 AND joining_platform_id IN (
-                    SELECT id FROM link_platforms WHERE name = ANY($2)
-                )
+                WHERE joining_mod_id = $1 AND name = ANY($2)
                 ",
                 project.inner.id as db_ids::ProjectId,
                 &ids_to_delete
@@ -1209,24 +2068,16 @@ pub async fn projects_edit(
             .execute(&mut *transaction)
             .await?;
 
-            for (platform, url) in links {
+            for (name, url) in links {
                 if let Some(url) = url {
-                    let platform_id = link_platforms
-                        .iter()
-                        .find(|x| &x.name == platform)
-                        .ok_or_else(|| {
-                            ApiError::InvalidInput(format!(
-                                "Platform {} does not exist.",
-                                platform.clone()
-                            ))
-                        })?
-                        .id;
+                    let platform_id = link_platform_id(&link_platforms, name, url)?;
                     sqlx::query!(
                         "
-                        INSERT INTO mods_links (joining_mod_id, joining_platform_id, url)
-                        VALUES ($1, $2, $3)
+                        INSERT INTO mods_links (joining_mod_id, name, joining_platform_id, url)
+                        VALUES ($1, $2, $3, $4)
                         ",
                         project.inner.id as db_ids::ProjectId,
+                        name,
                         platform_id as db_ids::LinkPlatformId,
                         url
                     )
@@ -1247,6 +2098,7 @@ pub async fn projects_edit(
 pub async fn bulk_edit_project_categories(
     all_db_categories: &[db_models::categories::Category],
     project_categories: &Vec<String>,
+    project_types: &[String],
     project_id: db_ids::ProjectId,
     bulk_changes: CategoryChanges<'_>,
     max_num_categories: usize,
@@ -1289,17 +2141,21 @@ pub async fn bulk_edit_project_categories(
         .execute(&mut **transaction)
         .await?;
 
-        let mut mod_categories = Vec::new();
-        for category in set_categories {
-            let category_id = all_db_categories
-                .iter()
-                .find(|x| x.category == category)
-                .ok_or_else(|| {
-                    ApiError::InvalidInput(format!("Category {} does not exist.", category.clone()))
-                })?
-                .id;
-            mod_categories.push(ModCategory::new(project_id, category_id, is_additional));
+        let mut category_ids: HashMap<String, Vec<db_ids::CategoryId>> = HashMap::new();
+        for category in all_db_categories
+            .iter()
+            .filter(|x| project_types.is_empty() || project_types.contains(&x.project_type))
+        {
+            category_ids
+                .entry(category.category.clone())
+                .or_default()
+                .push(category.id);
         }
+        let mod_categories = project_category_ids(&set_categories, &category_ids, project_types)
+            .map_err(|err| ApiError::InvalidInput(format!("Invalid category: {err}")))?
+            .into_iter()
+            .map(|x| ModCategory::new(project_id, x, is_additional))
+            .collect::<Vec<_>>();
         ModCategory::insert_many(mod_categories, &mut *transaction).await?;
     }
 
@@ -1311,6 +2167,37 @@ pub struct Extension {
     pub ext: String,
 }
 
+/// Records an image uploaded as the project's icon or to its gallery, so that moderators can
+/// review it before it's shown publicly
+async fn insert_project_image(
+    url: String,
+    variants: HashMap<String, String>,
+    size: u64,
+    user: &models::users::User,
+    project: &db_models::Project,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<image_item::Image, ApiError> {
+    let image = image_item::Image {
+        id: db_models::generate_image_id(transaction).await?,
+        url,
+        variants,
+        size,
+        created: Utc::now(),
+        owner_id: user.id.into(),
+        moderation_status: ImageModerationStatus::for_upload(user, project),
+        context: ImageContext::Project { project_id: None }
+            .context_as_str()
+            .to_string(),
+        project_id: Some(project.id),
+        version_id: None,
+        thread_message_id: None,
+        report_id: None,
+    };
+    image.insert(transaction).await?;
+
+    Ok(image)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn project_icon_edit(
     web::Query(ext): web::Query<Extension>,
@@ -1318,6 +2205,7 @@ pub async fn project_icon_edit(
     info: web::Path<(String,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
     mut payload: web::Payload,
     session_queue: web::Data<AuthQueue>,
@@ -1371,13 +2259,15 @@ pub async fn project_icon_edit(
             }
         }
 
-        if let Some(icon) = project_item.inner.icon_url {
+        if let Some(icon) = &project_item.inner.icon_url {
             let name = icon.split(&format!("{cdn_url}/")).nth(1);
 
             if let Some(icon_path) = name {
                 file_host.delete_file_version("", icon_path).await?;
             }
         }
+        img::delete_image_variants(&project_item.inner.icon_variants, &cdn_url, &***file_host)
+            .await?;
 
         let bytes =
             read_from_payload(&mut payload, 262144, "Icons must be smaller than 256KiB").await?;
@@ -1386,25 +2276,47 @@ pub async fn project_icon_edit(
 
         let hash = sha1::Sha1::from(&bytes).hexdigest();
         let project_id: ProjectId = project_item.inner.id.into();
+        // The icons of hidden projects are kept private until the project is made public
+        let path = image_path(
+            format!("data/{}/{}", project_id, hash),
+            project_item.inner.status.is_hidden(),
+        );
+        let bytes = bytes.freeze();
         let upload_data = file_host
             .upload_file(
                 content_type,
-                &format!("data/{}/{}.{}", project_id, hash, ext.ext),
-                bytes.freeze(),
+                &format!("{}.{}", path, ext.ext),
+                bytes.clone(),
             )
             .await?;
+        let variants = img::upload_image_variants(bytes, &path, &***file_host).await;
 
         let mut transaction = pool.begin().await?;
 
+        if let Some(image_id) = project_item.inner.icon_image_id {
+            image_item::Image::remove(image_id, &mut transaction, &redis).await?;
+        }
+        let image = insert_project_image(
+            format!("{}/{}", cdn_url, upload_data.file_name),
+            img::get_variant_urls(&variants, &cdn_url),
+            upload_data.content_length as u64,
+            &user,
+            &project_item.inner,
+            &mut transaction,
+        )
+        .await?;
+
         sqlx::query!(
             "
             UPDATE mods
-            SET icon_url = $1, color = $2
-            WHERE (id = $3)
+            SET icon_url = $1, color = $2, icon_variants = $3, icon_image_id = $5
+            WHERE (id = $4)
             ",
-            format!("{}/{}", cdn_url, upload_data.file_name),
+            image.url,
             color.map(|x| x as i32),
+            serde_json::json!(image.variants),
             project_item.inner.id as db_ids::ProjectId,
+            image.id as db_ids::ImageId,
         )
         .execute(&mut *transaction)
         .await?;
@@ -1418,6 +2330,13 @@ pub async fn project_icon_edit(
         )
         .await?;
 
+        spawn_reindex_projects(
+            vec![project_item.inner.id],
+            pool.get_ref().clone(),
+            redis.get_ref().clone(),
+            search_config.get_ref().clone(),
+        );
+
         Ok(HttpResponse::NoContent().body(""))
     } else {
         Err(ApiError::InvalidInput(format!(
@@ -1432,6 +2351,7 @@ pub async fn delete_project_icon(
     info: web::Path<(String,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
@@ -1489,13 +2409,19 @@ pub async fn delete_project_icon(
             file_host.delete_file_version("", icon_path).await?;
         }
     }
+    img::delete_image_variants(&project_item.inner.icon_variants, &cdn_url, &***file_host).await?;
 
     let mut transaction = pool.begin().await?;
 
+    // Removing the image unsets the project's icon image as well
+    if let Some(image_id) = project_item.inner.icon_image_id {
+        image_item::Image::remove(image_id, &mut transaction, &redis).await?;
+    }
+
     sqlx::query!(
         "
         UPDATE mods
-        SET icon_url = NULL, color = NULL
+        SET icon_url = NULL, color = NULL, icon_variants = '{}'::jsonb
         WHERE (id = $1)
         ",
         project_item.inner.id as db_ids::ProjectId,
@@ -1503,6 +2429,101 @@ pub async fn delete_project_icon(
     .execute(&mut *transaction)
     .await?;
 
+    transaction.commit().await?;
+    db_models::Project::clear_cache(project_item.inner.id, project_item.inner.slug, None, &redis)
+        .await?;
+
+    spawn_reindex_projects(
+        vec![project_item.inner.id],
+        pool.get_ref().clone(),
+        redis.get_ref().clone(),
+        search_config.get_ref().clone(),
+    );
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn project_description_refresh(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+    let string = info.into_inner().0;
+
+    let project_item = db_models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or_else(|| {
+            ApiError::InvalidInput("The specified project does not exist!".to_string())
+        })?;
+
+    if !user.role.is_mod() {
+        let (team_member, organization_team_member) =
+            db_models::TeamMember::get_for_project_permissions(
+                &project_item.inner,
+                user.id.into(),
+                &**pool,
+            )
+            .await?;
+
+        // Hide the project
+        if team_member.is_none() && organization_team_member.is_none() {
+            return Err(ApiError::CustomAuthentication(
+                "The specified project does not exist!".to_string(),
+            ));
+        }
+        let permissions = ProjectPermissions::get_permissions_by_role(
+            &user.role,
+            &team_member,
+            &organization_team_member,
+        )
+        .unwrap_or_default();
+
+        if !permissions.contains(ProjectPermissions::EDIT_BODY) {
+            return Err(ApiError::CustomAuthentication(
+                "You don't have permission to edit this project's description.".to_string(),
+            ));
+        }
+    }
+    check_not_archived(&project_item.inner)?;
+
+    let url = project_item
+        .inner
+        .description_source_url
+        .as_deref()
+        .ok_or_else(|| {
+            ApiError::InvalidInput(
+                "This project's description isn't imported from a README!".to_string(),
+            )
+        })?;
+    let description = readme::fetch_readme(url)
+        .await
+        .map_err(|err| ApiError::InvalidInput(err.to_string()))?;
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "
+        UPDATE mods
+        SET description = $1, description_fetched = NOW()
+        WHERE (id = $2)
+        ",
+        description,
+        project_item.inner.id as db_ids::ProjectId,
+    )
+    .execute(&mut *transaction)
+    .await?;
+
     transaction.commit().await?;
     db_models::Project::clear_cache(project_item.inner.id, project_item.inner.slug, None, &redis)
         .await?;
@@ -1528,6 +2549,7 @@ pub async fn add_gallery_item(
     info: web::Path<(String,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
     mut payload: web::Payload,
     session_queue: web::Data<AuthQueue>,
@@ -1554,6 +2576,8 @@ pub async fn add_gallery_item(
                 ApiError::InvalidInput("The specified project does not exist!".to_string())
             })?;
 
+        check_not_archived(&project_item.inner)?;
+
         if project_item.gallery_items.len() > 64 {
             return Err(ApiError::CustomAuthentication(
                 "You have reached the maximum of gallery images to upload.".to_string(),
@@ -1596,10 +2620,15 @@ pub async fn add_gallery_item(
             "Gallery image exceeds the maximum of 5MiB.",
         )
         .await?;
+        img::check_image_size(&bytes)?;
         let hash = sha1::Sha1::from(&bytes).hexdigest();
 
         let id: ProjectId = project_item.inner.id.into();
-        let url = format!("data/{}/images/{}.{}", id, hash, &*ext.ext);
+        let path = image_path(
+            format!("data/{}/images/{}", id, hash),
+            project_item.inner.status.is_hidden(),
+        );
+        let url = format!("{}.{}", path, &*ext.ext);
 
         let file_url = format!("{cdn_url}/{url}");
         if project_item
@@ -1612,9 +2641,11 @@ pub async fn add_gallery_item(
             ));
         }
 
-        file_host
-            .upload_file(content_type, &url, bytes.freeze())
+        let bytes = bytes.freeze();
+        let upload_data = file_host
+            .upload_file(content_type, &url, bytes.clone())
             .await?;
+        let variants = img::upload_image_variants(bytes, &path, &***file_host).await;
 
         let mut transaction = pool.begin().await?;
 
@@ -1632,13 +2663,26 @@ pub async fn add_gallery_item(
             .await?;
         }
 
+        let image = insert_project_image(
+            file_url,
+            img::get_variant_urls(&variants, &cdn_url),
+            upload_data.content_length as u64,
+            &user,
+            &project_item.inner,
+            &mut transaction,
+        )
+        .await?;
+
         let gallery_item = vec![db_models::project_item::GalleryItem {
-            image_url: file_url,
+            image_url: image.url,
             featured: item.featured,
             name: item.name,
             description: item.description,
             created: Utc::now(),
             ordering: item.ordering.unwrap_or(0),
+            variants: image.variants,
+            image_id: Some(image.id),
+            moderation_status: image.moderation_status,
         }];
         GalleryItem::insert_many(gallery_item, project_item.inner.id, &mut transaction).await?;
 
@@ -1651,6 +2695,13 @@ pub async fn add_gallery_item(
         )
         .await?;
 
+        spawn_reindex_projects(
+            vec![project_item.inner.id],
+            pool.get_ref().clone(),
+            redis.get_ref().clone(),
+            search_config.get_ref().clone(),
+        );
+
         Ok(HttpResponse::NoContent().body(""))
     } else {
         Err(ApiError::InvalidInput(format!(
@@ -1688,6 +2739,94 @@ pub async fn edit_gallery_item(
     info: web::Path<(String,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    item.validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+    // Private images are given to clients by their signed URLs
+    let url = unsign_url(&item.url)?;
+
+    edit_gallery(
+        req,
+        info.into_inner().0,
+        GalleryItemRef::Url(url),
+        GalleryItemEdit {
+            featured: item.featured,
+            name: item.name,
+            description: item.description,
+            ordering: item.ordering,
+        },
+        pool,
+        redis,
+        search_config,
+        session_queue,
+    )
+    .await
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct GalleryItemEdit {
+    pub featured: Option<bool>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<Option<String>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    #[validate(length(min = 1, max = 2048))]
+    pub description: Option<Option<String>>,
+    pub ordering: Option<i64>,
+}
+
+/// Edits a gallery item by the id of its uploaded image, as returned in the project's gallery
+pub async fn edit_gallery_item_by_id(
+    req: HttpRequest,
+    info: web::Path<(String, ImageId)>,
+    web::Json(edit): web::Json<GalleryItemEdit>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    edit.validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+    let (string, image_id) = info.into_inner();
+
+    edit_gallery(
+        req,
+        string,
+        GalleryItemRef::ImageId(image_id.into()),
+        edit,
+        pool,
+        redis,
+        search_config,
+        session_queue,
+    )
+    .await
+}
+
+// The gallery item being edited, by its URL or the id of its uploaded image
+enum GalleryItemRef {
+    Url(String),
+    ImageId(db_ids::ImageId),
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn edit_gallery(
+    req: HttpRequest,
+    string: String,
+    item_ref: GalleryItemRef,
+    item: GalleryItemEdit,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
     let user = get_user_from_headers(
@@ -1699,10 +2838,6 @@ pub async fn edit_gallery_item(
     )
     .await?
     .1;
-    let string = info.into_inner().0;
-
-    item.validate()
-        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
 
     let project_item = db_models::Project::get(&string, &**pool, &redis)
         .await?
@@ -1710,6 +2845,8 @@ pub async fn edit_gallery_item(
             ApiError::InvalidInput("The specified project does not exist!".to_string())
         })?;
 
+    check_not_archived(&project_item.inner)?;
+
     if !user.role.is_mod() {
         let (team_member, organization_team_member) =
             db_models::TeamMember::get_for_project_permissions(
@@ -1740,24 +2877,41 @@ pub async fn edit_gallery_item(
     }
     let mut transaction = pool.begin().await?;
 
-    let id = sqlx::query!(
-        "
-        SELECT id FROM mods_gallery
-        WHERE image_url = $1
-        ",
-        item.url
-    )
-    .fetch_optional(&mut *transaction)
-    .await?
-    .ok_or_else(|| {
-        ApiError::InvalidInput(format!(
-            "Gallery item at URL {} is not part of the project's gallery.",
-            item.url
-        ))
-    })?
-    .id;
-
-    let mut transaction = pool.begin().await?;
+    let id = match item_ref {
+        GalleryItemRef::Url(url) => {
+            sqlx::query!(
+                "
+                SELECT id FROM mods_gallery
+                WHERE image_url = $1 AND mod_id = $2
+                ",
+                url,
+                project_item.inner.id as db_ids::ProjectId,
+            )
+            .fetch_optional(&mut *transaction)
+            .await?
+            .ok_or_else(|| {
+                ApiError::InvalidInput(format!(
+                    "Gallery item at URL {} is not part of the project's gallery.",
+                    url
+                ))
+            })?
+            .id
+        }
+        GalleryItemRef::ImageId(image_id) => {
+            sqlx::query!(
+                "
+                SELECT id FROM mods_gallery
+                WHERE image_id = $1 AND mod_id = $2
+                ",
+                image_id as db_ids::ImageId,
+                project_item.inner.id as db_ids::ProjectId,
+            )
+            .fetch_optional(&mut *transaction)
+            .await?
+            .ok_or(ApiError::NotFound)?
+            .id
+        }
+    };
 
     if let Some(featured) = item.featured {
         if featured {
@@ -1831,6 +2985,111 @@ pub async fn edit_gallery_item(
     db_models::Project::clear_cache(project_item.inner.id, project_item.inner.slug, None, &redis)
         .await?;
 
+    spawn_reindex_projects(
+        vec![project_item.inner.id],
+        pool.get_ref().clone(),
+        redis.get_ref().clone(),
+        search_config.get_ref().clone(),
+    );
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GalleryOrder {
+    /// The urls of every gallery item of the project, in their new order
+    pub urls: Vec<String>,
+}
+
+pub async fn edit_gallery_order(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    web::Json(mut order): web::Json<GalleryOrder>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+    let string = info.into_inner().0;
+    order.urls = order
+        .urls
+        .iter()
+        .map(|x| unsign_url(x))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let project_item = db_models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or_else(|| {
+            ApiError::InvalidInput("The specified project does not exist!".to_string())
+        })?;
+
+    check_not_archived(&project_item.inner)?;
+
+    if !user.role.is_mod() {
+        let (team_member, organization_team_member) =
+            db_models::TeamMember::get_for_project_permissions(
+                &project_item.inner,
+                user.id.into(),
+                &**pool,
+            )
+            .await?;
+
+        // Hide the project
+        if team_member.is_none() && organization_team_member.is_none() {
+            return Err(ApiError::CustomAuthentication(
+                "The specified project does not exist!".to_string(),
+            ));
+        }
+        let permissions = ProjectPermissions::get_permissions_by_role(
+            &user.role,
+            &team_member,
+            &organization_team_member,
+        )
+        .unwrap_or_default();
+
+        if !permissions.contains(ProjectPermissions::EDIT_DETAILS) {
+            return Err(ApiError::CustomAuthentication(
+                "You don't have permission to edit this project's gallery.".to_string(),
+            ));
+        }
+    }
+
+    let current_urls = project_item
+        .gallery_items
+        .iter()
+        .map(|x| &x.image_url)
+        .sorted()
+        .collect_vec();
+    if current_urls != order.urls.iter().sorted().collect_vec() {
+        return Err(ApiError::InvalidInput(
+            "The new order must contain every gallery item of the project exactly once."
+                .to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+    GalleryItem::reorder(project_item.inner.id, &order.urls, &mut transaction).await?;
+    transaction.commit().await?;
+
+    db_models::Project::clear_cache(project_item.inner.id, project_item.inner.slug, None, &redis)
+        .await?;
+
+    spawn_reindex_projects(
+        vec![project_item.inner.id],
+        pool.get_ref().clone(),
+        redis.get_ref().clone(),
+        search_config.get_ref().clone(),
+    );
+
     Ok(HttpResponse::NoContent().body(""))
 }
 
@@ -1839,12 +3098,14 @@ pub struct GalleryDeleteQuery {
     pub url: String,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn delete_gallery_item(
     req: HttpRequest,
-    web::Query(item): web::Query<GalleryDeleteQuery>,
+    web::Query(mut item): web::Query<GalleryDeleteQuery>,
     info: web::Path<(String,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
@@ -1858,6 +3119,7 @@ pub async fn delete_gallery_item(
     .await?
     .1;
     let string = info.into_inner().0;
+    item.url = unsign_url(&item.url)?;
 
     let project_item = db_models::Project::get(&string, &**pool, &redis)
         .await?
@@ -1865,6 +3127,8 @@ pub async fn delete_gallery_item(
             ApiError::InvalidInput("The specified project does not exist!".to_string())
         })?;
 
+    check_not_archived(&project_item.inner)?;
+
     if !user.role.is_mod() {
         let (team_member, organization_team_member) =
             db_models::TeamMember::get_for_project_permissions(
@@ -1896,9 +3160,9 @@ pub async fn delete_gallery_item(
     }
     let mut transaction = pool.begin().await?;
 
-    let id = sqlx::query!(
+    let gallery_row = sqlx::query!(
         "
-        SELECT id FROM mods_gallery
+        SELECT id, image_id FROM mods_gallery
         WHERE image_url = $1
         ",
         item.url
@@ -1910,8 +3174,7 @@ pub async fn delete_gallery_item(
             "Gallery item at URL {} is not part of the project's gallery.",
             item.url
         ))
-    })?
-    .id;
+    })?;
 
     let cdn_url = dotenvy::var("CDN_URL")?;
     let name = item.url.split(&format!("{cdn_url}/")).nth(1);
@@ -1919,6 +3182,13 @@ pub async fn delete_gallery_item(
     if let Some(icon_path) = name {
         file_host.delete_file_version("", icon_path).await?;
     }
+    if let Some(gallery_item) = project_item
+        .gallery_items
+        .iter()
+        .find(|x| x.image_url == item.url)
+    {
+        img::delete_image_variants(&gallery_item.variants, &cdn_url, &***file_host).await?;
+    }
 
     let mut transaction = pool.begin().await?;
 
@@ -1927,16 +3197,27 @@ pub async fn delete_gallery_item(
         DELETE FROM mods_gallery
         WHERE id = $1
         ",
-        id
+        gallery_row.id
     )
     .execute(&mut *transaction)
     .await?;
 
+    if let Some(image_id) = gallery_row.image_id {
+        image_item::Image::remove(db_ids::ImageId(image_id), &mut transaction, &redis).await?;
+    }
+
     transaction.commit().await?;
 
     db_models::Project::clear_cache(project_item.inner.id, project_item.inner.slug, None, &redis)
         .await?;
 
+    spawn_reindex_projects(
+        vec![project_item.inner.id],
+        pool.get_ref().clone(),
+        redis.get_ref().clone(),
+        search_config.get_ref().clone(),
+    );
+
     Ok(HttpResponse::NoContent().body(""))
 }
 
@@ -1995,29 +3276,36 @@ pub async fn project_delete(
         }
     }
 
+    // The project is only hidden for now, and is removed for good by `purge_deleted_projects`
+    // once it can no longer be restored
     let mut transaction = pool.begin().await?;
-    let context = ImageContext::Project {
-        project_id: Some(project.inner.id.into()),
-    };
-    let uploaded_images = db_models::Image::get_many_contexted(context, &mut transaction).await?;
-    for image in uploaded_images {
-        image_item::Image::remove(image.id, &mut transaction, &redis).await?;
-    }
-
-    sqlx::query!(
-        "
-        DELETE FROM collections_mods
-        WHERE mod_id = $1
-        ",
-        project.inner.id as db_ids::ProjectId,
-    )
-    .execute(&mut *transaction)
-    .await?;
+    let result =
+        db_models::Project::soft_delete(project.inner.id, &mut transaction, &redis).await?;
 
-    let result = db_models::Project::remove(project.inner.id, &mut transaction, &redis).await?;
+    if result.is_some() {
+        AuditLogBuilder {
+            actor_id: user.id.into(),
+            action: AuditAction::ProjectDelete,
+            target_type: AuditTargetType::Project,
+            target_id: project.inner.id.0,
+            project_id: Some(project.inner.id),
+            organization_id: project.inner.organization_id,
+            summary: json!({
+                "name": project.inner.name,
+                "slug": project.inner.slug,
+            }),
+            ip: request_ip(&req),
+        }
+        .insert(&mut transaction)
+        .await?;
+    }
 
     transaction.commit().await?;
 
+    if let Some(organization_id) = project.inner.organization_id {
+        db_models::Organization::clear_cache(organization_id, None, &redis).await?;
+    }
+
     remove_documents(
         &project
             .versions
@@ -2035,6 +3323,235 @@ pub async fn project_delete(
     }
 }
 
+pub async fn project_restore(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_DELETE]),
+    )
+    .await?
+    .1;
+    let string = info.into_inner().0;
+
+    let project = db_models::Project::get_deleted(&string, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if !user.role.is_admin() {
+        // Projects in an organization are owned by the organization's owner
+        let team_id = if let Some(organization_id) = project.organization_id {
+            db_models::Organization::get_id(organization_id, &**pool, &redis)
+                .await?
+                .map(|x| x.team_id)
+        } else {
+            Some(project.team_id)
+        };
+        let is_owner = if let Some(team_id) = team_id {
+            TeamMember::get_from_user_id(team_id, user.id.into(), &**pool)
+                .await?
+                .map(|x| x.is_owner)
+                .unwrap_or(false)
+        } else {
+            false
+        };
+
+        // Deleted projects are hidden from everyone else
+        if !is_owner {
+            return Err(ApiError::NotFound);
+        }
+    }
+
+    if project.deleted_at < Utc::now() - Duration::days(PROJECT_RESTORE_WINDOW_DAYS) {
+        return Err(ApiError::InvalidInput(
+            "This project was deleted too long ago to be restored!".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+    db_models::Project::restore(&project, &mut transaction, &redis).await?;
+    transaction.commit().await?;
+
+    if let Some(organization_id) = project.organization_id {
+        db_models::Organization::clear_cache(organization_id, None, &redis).await?;
+    }
+
+    spawn_reindex_projects(
+        vec![project.id],
+        pool.get_ref().clone(),
+        redis.get_ref().clone(),
+        search_config.get_ref().clone(),
+    );
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+/// Removes every deleted project which can no longer be restored, as deleting a project did
+/// before deletions could be undone. Their search documents were removed when they were deleted.
+pub async fn purge_deleted_projects(pool: &PgPool, redis: &RedisPool) -> Result<(), ApiError> {
+    let project_ids = db_models::Project::get_deleted_before(
+        Utc::now() - Duration::days(PROJECT_RESTORE_WINDOW_DAYS),
+        pool,
+    )
+    .await?;
+
+    for project_id in project_ids {
+        let mut transaction = pool.begin().await?;
+
+        let context = ImageContext::Project {
+            project_id: Some(project_id.into()),
+        };
+        let uploaded_images =
+            db_models::Image::get_many_contexted(context, &mut transaction).await?;
+        for image in uploaded_images {
+            image_item::Image::remove(image.id, &mut transaction, redis).await?;
+        }
+
+        sqlx::query!(
+            "
+            DELETE FROM collections_mods
+            WHERE mod_id = $1
+            ",
+            project_id as db_ids::ProjectId,
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        db_models::Project::remove(project_id, &mut transaction, redis).await?;
+
+        transaction.commit().await?;
+    }
+
+    Ok(())
+}
+
+/// Lists every scheduled project whose go-live time has passed, as of `now`. Its team is
+/// notified and it's indexed for search only then, rather than when it was approved.
+pub async fn list_scheduled_projects(
+    now: DateTime<Utc>,
+    pool: &PgPool,
+    redis: &RedisPool,
+    search_config: &SearchConfig,
+) -> Result<(), ApiError> {
+    let due_projects = sqlx::query!(
+        "
+        SELECT m.id, m.team_id, m.slug, m.webhook_sent, t.id thread_id
+        FROM mods m
+        INNER JOIN threads t ON t.mod_id = m.id
+        WHERE m.status = $1 AND m.go_live_at <= $2
+        ",
+        ProjectStatus::Scheduled.as_str(),
+        now,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if due_projects.is_empty() {
+        return Ok(());
+    }
+
+    for project in &due_projects {
+        let mut transaction = pool.begin().await?;
+
+        sqlx::query!(
+            "
+            UPDATE mods
+            SET status = $1, go_live_at = NULL, approved = COALESCE(approved, NOW())
+            WHERE id = $2
+            ",
+            ProjectStatus::Approved.as_str(),
+            project.id,
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        let members = sqlx::query!(
+            "
+            SELECT user_id FROM team_members
+            WHERE team_id = $1 AND accepted
+            ",
+            project.team_id,
+        )
+        .fetch_all(&mut *transaction)
+        .await?
+        .into_iter()
+        .map(|x| db_ids::UserId(x.user_id))
+        .collect::<Vec<_>>();
+
+        NotificationBuilder {
+            body: NotificationBody::StatusChange {
+                project_id: db_ids::ProjectId(project.id).into(),
+                old_status: ProjectStatus::Scheduled,
+                new_status: ProjectStatus::Approved,
+            },
+        }
+        .insert_many(members, &mut transaction, redis)
+        .await?;
+
+        ThreadMessageBuilder {
+            author_id: None,
+            body: MessageBody::StatusChange {
+                new_status: ProjectStatus::Approved,
+                old_status: ProjectStatus::Scheduled,
+            },
+            thread_id: db_ids::ThreadId(project.thread_id),
+        }
+        .insert(&mut transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        if !project.webhook_sent {
+            if let Ok(webhook_url) = dotenvy::var("PUBLIC_DISCORD_WEBHOOK") {
+                crate::util::webhook::send_discord_webhook(
+                    db_ids::ProjectId(project.id).into(),
+                    pool,
+                    redis,
+                    webhook_url,
+                    None,
+                )
+                .await
+                .ok();
+
+                sqlx::query!(
+                    "
+                    UPDATE mods
+                    SET webhook_sent = TRUE
+                    WHERE id = $1
+                    ",
+                    project.id,
+                )
+                .execute(pool)
+                .await?;
+            }
+        }
+
+        db_models::Project::clear_cache(
+            db_ids::ProjectId(project.id),
+            project.slug.clone(),
+            None,
+            redis,
+        )
+        .await?;
+    }
+
+    let project_ids = due_projects
+        .iter()
+        .map(|x| db_ids::ProjectId(x.id))
+        .collect::<Vec<_>>();
+    reindex_projects(&project_ids, pool, redis, search_config).await?;
+
+    Ok(())
+}
+
 pub async fn project_follow(
     req: HttpRequest,
     info: web::Path<(String,)>,
@@ -2066,6 +3583,13 @@ pub async fn project_follow(
         return Err(ApiError::NotFound);
     }
 
+    // Users blocked by the project's owner aren't told why they can't follow it
+    if UserBlock::is_blocked_by_project_owner(project_id, user_id, &**pool).await? {
+        return Err(ApiError::Forbidden(
+            "You can't follow this project.".to_string(),
+        ));
+    }
+
     let following = sqlx::query!(
         "
         SELECT EXISTS(SELECT 1 FROM mod_follows mf WHERE mf.follower_id = $1 AND mf.mod_id = $2)
@@ -0,0 +1,161 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use itertools::Itertools;
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use super::ApiError;
+use crate::auth::get_user_from_headers;
+use crate::database::models::User;
+use crate::database::redis::RedisPool;
+use crate::models::ids::ProjectId;
+use crate::models::pats::Scopes;
+use crate::models::projects::{Project, SearchRequest};
+use crate::queue::session::AuthQueue;
+use crate::search::{search_for_project, SearchConfig, SearchError};
+
+const RECOMMENDATIONS_NAMESPACE: &str = "user_recommendations";
+const RECOMMENDATIONS_EXPIRY: i64 = 60 * 60; // 1 hour
+
+// How many of the categories and loaders a user follows the most recommendations are based on
+const RECOMMENDATION_TAGS: usize = 5;
+
+#[derive(Deserialize)]
+pub struct RecommendationsQuery {
+    #[serde(default = "default_count")]
+    pub count: usize,
+}
+
+fn default_count() -> usize {
+    20
+}
+
+/// Recommends projects in the categories of the projects the user follows, and for the loaders
+/// they use, most downloaded first. Anonymous users, and users who don't follow anything with
+/// categories, are recommended the most downloaded projects.
+pub async fn user_recommendations(
+    req: HttpRequest,
+    web::Query(query): web::Query<RecommendationsQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    config: web::Data<SearchConfig>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    if !(1..=100).contains(&query.count) {
+        return Err(ApiError::InvalidInput(
+            "Count must be between 1 and 100!".to_string(),
+        ));
+    }
+
+    let user_option = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    let cache_key = format!(
+        "{}_{}",
+        user_option
+            .as_ref()
+            .map(|x| x.id.to_string())
+            .unwrap_or_else(|| "anonymous".to_string()),
+        query.count
+    );
+
+    let mut conn = redis.connect().await?;
+    if let Some(projects) = conn
+        .get_deserialized_from_json::<Vec<Project>>(RECOMMENDATIONS_NAMESPACE, &cache_key)
+        .await?
+    {
+        return Ok(HttpResponse::Ok().json(projects));
+    }
+
+    let (followed, (categories, loaders)) = match &user_option {
+        Some(user) => (
+            User::get_followed_projects(user.id.into(), &**pool)
+                .await?
+                .into_iter()
+                .map(ProjectId::from)
+                .collect_vec(),
+            User::get_followed_tags(user.id.into(), RECOMMENDATION_TAGS, &**pool).await?,
+        ),
+        None => (Vec::new(), (Vec::new(), Vec::new())),
+    };
+
+    // Search uses loaders as categories, so both are filtered on the same attribute. Without
+    // any categories to go by, the most downloaded projects are recommended.
+    let filter = if categories.is_empty() {
+        String::new()
+    } else {
+        [&categories, &loaders]
+            .into_iter()
+            .filter(|tags| !tags.is_empty())
+            .map(|tags| {
+                format!(
+                    "({})",
+                    tags.iter()
+                        .map(|tag| format!("categories = \"{}\"", tag.replace('"', "")))
+                        .join(" OR ")
+                )
+            })
+            .join(" AND ")
+    };
+    let projects = search_recommendations(filter, &followed, query.count, &config).await?;
+
+    conn.set_serialized_to_json(
+        RECOMMENDATIONS_NAMESPACE,
+        &cache_key,
+        &projects,
+        Some(RECOMMENDATIONS_EXPIRY),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(projects))
+}
+
+// Searches the most downloaded listed projects matching the filter, leaving out the excluded ones
+async fn search_recommendations(
+    filter: String,
+    excluded: &[ProjectId],
+    count: usize,
+    config: &SearchConfig,
+) -> Result<Vec<Project>, ApiError> {
+    let filter = excluded
+        .iter()
+        .map(|id| format!("project_id != \"{id}\""))
+        .chain((!filter.is_empty()).then_some(filter))
+        .join(" AND ");
+
+    let request = SearchRequest {
+        query: None,
+        offset: None,
+        index: Some("relevance".to_string()),
+        limit: Some(count.to_string()),
+        new_filters: (!filter.is_empty()).then_some(filter),
+        include_mature: false,
+        fields: None,
+        updated_within: None,
+        created_within: None,
+        group: None,
+        facets: None,
+        filters: None,
+        version: None,
+    };
+
+    let results = search_for_project(&request, config, false, None)
+        .await
+        .map_err(|err| match err {
+            SearchError::MeiliSearch(err) => ApiError::Search(err),
+            err => ApiError::InvalidInput(err.to_string()),
+        })?;
+
+    Ok(results
+        .hits
+        .into_iter()
+        .filter_map(Project::from_search)
+        .collect())
+}
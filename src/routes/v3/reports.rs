@@ -1,19 +1,25 @@
 use crate::auth::{check_is_moderator_from_headers, get_user_from_headers};
 use crate::database;
 use crate::database::models::image_item;
+use crate::database::models::notification_item::NotificationBuilder;
 use crate::database::models::thread_item::{ThreadBuilder, ThreadMessageBuilder};
 use crate::database::redis::RedisPool;
 use crate::models::ids::ImageId;
 use crate::models::ids::{base62_impl::parse_base62, ProjectId, UserId, VersionId};
 use crate::models::images::{Image, ImageContext};
+use crate::models::notifications::NotificationBody;
 use crate::models::pats::Scopes;
-use crate::models::reports::{ItemType, Report};
+use crate::models::reports::{ItemType, Report, ReportStatus};
 use crate::models::threads::{MessageBody, ThreadType};
+use crate::models::users::Role;
 use crate::queue::session::AuthQueue;
+use crate::routes::v3::version_file::default_algorithm_from_hashes;
 use crate::routes::ApiError;
+use crate::util::csv;
 use crate::util::img;
+use crate::util::validate::validation_errors_to_string;
 use actix_web::{web, HttpRequest, HttpResponse};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use futures::StreamExt;
 use serde::Deserialize;
 use sqlx::PgPool;
@@ -83,10 +89,12 @@ pub async fn report_create(
         project_id: None,
         version_id: None,
         user_id: None,
+        related_project_id: None,
         body: new_report.body.clone(),
         reporter: current_user.id.into(),
         created: Utc::now(),
         closed: false,
+        file_hash: None,
     };
 
     match new_report.item_type {
@@ -128,6 +136,24 @@ pub async fn report_create(
 
             report.version_id = Some(version_id.into())
         }
+        ItemType::File => {
+            let hash = new_report.item_id.to_lowercase();
+            let file = database::models::Version::get_file_from_hash(
+                default_algorithm_from_hashes(&[hash.clone()]),
+                hash.clone(),
+                None,
+                &**pool,
+                &redis,
+            )
+            .await?
+            .ok_or_else(|| {
+                ApiError::InvalidInput(format!("File could not be found: {}", new_report.item_id))
+            })?;
+
+            // File reports are also attached to the version, so they show up alongside it
+            report.version_id = Some(file.version_id);
+            report.file_hash = Some(hash);
+        }
         ItemType::User => {
             let user_id = UserId(parse_base62(new_report.item_id.as_str())?);
 
@@ -208,10 +234,13 @@ pub async fn report_create(
         report_type: new_report.report_type.clone(),
         item_id: new_report.item_id.clone(),
         item_type: new_report.item_type.clone(),
+        related_project_id: None,
         reporter: current_user.id,
         body: new_report.body.clone(),
         created: Utc::now(),
         closed: false,
+        status: ReportStatus::Open,
+        assigned_moderator: None,
         thread_id: thread_id.into(),
     }))
 }
@@ -300,19 +329,94 @@ pub async fn reports(
 }
 
 #[derive(Deserialize)]
-pub struct ReportIds {
-    pub ids: String,
+pub struct ReportsQuery {
+    /// The reports to get. If not set, reports are listed using the other filters
+    pub ids: Option<String>,
+    pub status: Option<ReportStatus>,
+    /// Either `me` or a user id, to only list reports assigned to that moderator
+    pub assigned: Option<String>,
+    pub count: Option<i64>,
+    pub offset: Option<i64>,
+    /// `csv` to export every report matching the filters as CSV. Only moderators can export.
+    pub format: Option<String>,
+}
+
+/// The columns of report exports, in order:
+/// - `id`: the report's id
+/// - `type`: the report type, such as `spam`
+/// - `item_type`: `project`, `version`, `file` or `user`
+/// - `item_id`: the id of the reported item, or the hash of a reported file
+/// - `created`: when the report was made, in RFC 3339
+/// - `reporter`: the username of the reporter
+/// - `status`: the report's status
+/// - `assigned_moderator`: the username of the assigned moderator, if there is one
+/// - `body`: the report's body
+const REPORT_EXPORT_COLUMNS: &[&str] = &[
+    "id",
+    "type",
+    "item_type",
+    "item_id",
+    "created",
+    "reporter",
+    "status",
+    "assigned_moderator",
+    "body",
+];
+
+#[derive(sqlx::FromRow)]
+struct ReportExportRow {
+    id: i64,
+    report_type: String,
+    mod_id: Option<i64>,
+    version_id: Option<i64>,
+    user_id: Option<i64>,
+    file_hash: Option<String>,
+    created: DateTime<Utc>,
+    reporter: String,
+    status: String,
+    assigned_moderator: Option<String>,
+    body: String,
+}
+
+fn report_export_record(row: ReportExportRow) -> Vec<String> {
+    let (item_type, item_id) = if let Some(file_hash) = row.file_hash {
+        (ItemType::File, file_hash)
+    } else if let Some(project_id) = row.mod_id {
+        (ItemType::Project, ProjectId(project_id as u64).to_string())
+    } else if let Some(version_id) = row.version_id {
+        (ItemType::Version, VersionId(version_id as u64).to_string())
+    } else if let Some(user_id) = row.user_id {
+        (ItemType::User, UserId(user_id as u64).to_string())
+    } else {
+        (ItemType::Unknown, String::new())
+    };
+
+    vec![
+        crate::models::ids::ReportId(row.id as u64).to_string(),
+        row.report_type,
+        item_type.as_str().to_string(),
+        item_id,
+        row.created.to_rfc3339(),
+        row.reporter,
+        row.status,
+        row.assigned_moderator.unwrap_or_default(),
+        row.body,
+    ]
 }
 
 pub async fn reports_get(
     req: HttpRequest,
-    web::Query(ids): web::Query<ReportIds>,
+    web::Query(query): web::Query<ReportsQuery>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
 ) -> Result<HttpResponse, ApiError> {
+    let Some(ids) = query.ids.as_ref() else {
+        return reports_list(req, query, pool, redis, session_queue).await;
+    };
+
     let report_ids: Vec<crate::database::models::ids::ReportId> =
-        serde_json::from_str::<Vec<crate::models::ids::ReportId>>(&ids.ids)?
+        serde_json::from_str::<Vec<crate::models::ids::ReportId>>(ids)?
             .into_iter()
             .map(|x| x.into())
             .collect();
@@ -339,6 +443,108 @@ pub async fn reports_get(
     Ok(HttpResponse::Ok().json(all_reports))
 }
 
+/// Lists reports oldest first, for working through the moderation queue.
+/// Users who aren't moderators only see their own reports.
+async fn reports_list(
+    req: HttpRequest,
+    query: ReportsQuery,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::REPORT_READ]),
+    )
+    .await?
+    .1;
+
+    let assigned_moderator = match query.assigned.as_deref() {
+        Some("me") => Some(user.id.into()),
+        Some(assigned) => Some(
+            database::models::User::get(assigned, &**pool, &redis)
+                .await?
+                .ok_or_else(|| {
+                    ApiError::InvalidInput("The assigned user does not exist!".to_string())
+                })?
+                .id,
+        ),
+        None => None,
+    };
+
+    if csv::wants_csv(&req, query.format.as_deref()) {
+        if !user.role.is_mod() {
+            return Err(ApiError::CustomAuthentication(
+                "You don't have permission to export reports!".to_string(),
+            ));
+        }
+
+        let mut transaction = pool.begin().await?;
+        sqlx::query(&format!(
+            "
+            DECLARE {} NO SCROLL CURSOR FOR
+            SELECT r.id, rt.name report_type, r.mod_id, r.version_id, r.user_id, r.file_hash, r.created,
+                u.username reporter, r.status, am.username assigned_moderator, r.body
+            FROM reports r
+            INNER JOIN report_types rt ON rt.id = r.report_type_id
+            INNER JOIN users u ON u.id = r.reporter
+            LEFT JOIN users am ON am.id = r.assigned_moderator
+            WHERE ($1::varchar IS NULL OR r.status = $1)
+            AND ($2::bigint IS NULL OR r.assigned_moderator = $2)
+            ORDER BY r.created ASC, r.id ASC
+            ",
+            csv::EXPORT_CURSOR
+        ))
+        .bind(query.status.map(|x| x.as_str()))
+        .bind(assigned_moderator.map(|x: database::models::UserId| x.0))
+        .execute(&mut *transaction)
+        .await?;
+
+        return Ok(csv::cursor_response(
+            "reports.csv",
+            REPORT_EXPORT_COLUMNS,
+            transaction,
+            report_export_record,
+        ));
+    }
+
+    let reporter = if user.role.is_mod() {
+        None
+    } else {
+        Some(database::models::UserId::from(user.id))
+    };
+
+    let report_ids = sqlx::query!(
+        "
+        SELECT id FROM reports
+        WHERE ($1::varchar IS NULL OR status = $1)
+        AND ($2::bigint IS NULL OR assigned_moderator = $2)
+        AND ($3::bigint IS NULL OR reporter = $3)
+        ORDER BY created ASC
+        LIMIT $4 OFFSET $5
+        ",
+        query.status.map(|x| x.as_str()),
+        assigned_moderator.map(|x: database::models::UserId| x.0),
+        reporter.map(|x| x.0),
+        query.count.unwrap_or(100).clamp(0, 1000),
+        query.offset.unwrap_or(0).max(0),
+    )
+    .fetch_all(&**pool)
+    .await?
+    .into_iter()
+    .map(|x| crate::database::models::ids::ReportId(x.id))
+    .collect::<Vec<_>>();
+
+    let mut reports =
+        crate::database::models::report_item::Report::get_many(&report_ids, &**pool).await?;
+    reports.sort_by(|a, b| a.created.cmp(&b.created));
+
+    Ok(HttpResponse::Ok().json(reports.into_iter().map(Report::from).collect::<Vec<_>>()))
+}
+
 pub async fn report_get(
     req: HttpRequest,
     pool: web::Data<PgPool>,
@@ -371,11 +577,24 @@ pub async fn report_get(
     }
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Validate, Default)]
 pub struct EditReport {
     #[validate(length(max = 65536))]
     pub body: Option<String>,
     pub closed: Option<bool>,
+    pub status: Option<ReportStatus>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub assigned_moderator: Option<Option<UserId>>,
+    /// Posted to the report's thread when the report is closed
+    #[validate(length(min = 1, max = 65536))]
+    pub resolution: Option<String>,
+    /// Required to move a closed report back to an open status
+    #[serde(default)]
+    pub reopen: bool,
 }
 
 pub async fn report_edit(
@@ -397,6 +616,10 @@ pub async fn report_edit(
     .1;
     let id = info.into_inner().0.into();
 
+    edit_report
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
     let report = crate::database::models::report_item::Report::get(id, &**pool).await?;
 
     if let Some(report) = report {
@@ -420,19 +643,41 @@ pub async fn report_edit(
             .await?;
         }
 
-        if let Some(edit_closed) = edit_report.closed {
-            if !user.role.is_mod() {
-                return Err(ApiError::InvalidInput(
-                    "You cannot reopen a report!".to_string(),
-                ));
+        // `closed` is kept for older clients: closing resolves the report, and un-closing reopens it
+        let new_status = edit_report.status.or(edit_report.closed.map(|closed| {
+            if !closed {
+                ReportStatus::Open
+            } else if report.status.is_closed() {
+                report.status
+            } else {
+                ReportStatus::Resolved
+            }
+        }));
+        let reopen = edit_report.reopen || edit_report.closed == Some(false);
+
+        if (new_status.is_some()
+            || edit_report.assigned_moderator.is_some()
+            || edit_report.resolution.is_some())
+            && !user.role.is_mod()
+        {
+            return Err(ApiError::CustomAuthentication(
+                "You do not have permission to change the status of this report!".to_string(),
+            ));
+        }
+
+        if let Some(new_status) = new_status.filter(|x| *x != report.status) {
+            if !report.status.can_transition_to(new_status, reopen) {
+                return Err(ApiError::InvalidInput(format!(
+                    "A {} report cannot be changed to {} without reopening it!",
+                    report.status, new_status
+                )));
             }
 
             ThreadMessageBuilder {
                 author_id: Some(user.id.into()),
-                body: if !edit_closed && report.closed {
-                    MessageBody::ThreadReopen
-                } else {
-                    MessageBody::ThreadClosure
+                body: MessageBody::ReportStatusChange {
+                    new_status,
+                    old_status: report.status,
                 },
                 thread_id: report.thread_id,
             }
@@ -442,10 +687,11 @@ pub async fn report_edit(
             sqlx::query!(
                 "
                 UPDATE reports
-                SET closed = $1
-                WHERE (id = $2)
+                SET status = $1, closed = $2
+                WHERE (id = $3)
                 ",
-                edit_closed,
+                new_status.as_str(),
+                new_status.is_closed(),
                 id as crate::database::models::ids::ReportId,
             )
             .execute(&mut *transaction)
@@ -457,11 +703,71 @@ pub async fn report_edit(
                 SET show_in_mod_inbox = $1
                 WHERE id = $2
                 ",
-                !(edit_closed || report.closed),
+                !new_status.is_closed(),
                 report.thread_id.0,
             )
             .execute(&mut *transaction)
             .await?;
+
+            if new_status.is_closed() {
+                NotificationBuilder {
+                    body: NotificationBody::ReportResolved {
+                        report_id: id.into(),
+                        status: new_status,
+                    },
+                }
+                .insert(report.reporter, &mut transaction, &redis)
+                .await?;
+            }
+        }
+
+        if let Some(resolution) = &edit_report.resolution {
+            if !new_status.map(|x| x.is_closed()).unwrap_or(false) {
+                return Err(ApiError::InvalidInput(
+                    "A resolution can only be given when closing the report!".to_string(),
+                ));
+            }
+
+            ThreadMessageBuilder {
+                author_id: Some(user.id.into()),
+                body: MessageBody::Text {
+                    body: resolution.clone(),
+                    private: false,
+                    replying_to: None,
+                    associated_images: Vec::new(),
+                },
+                thread_id: report.thread_id,
+            }
+            .insert(&mut transaction)
+            .await?;
+        }
+
+        if let Some(assigned_moderator) = edit_report.assigned_moderator {
+            if let Some(assigned_moderator) = assigned_moderator {
+                let assigned_user =
+                    database::models::User::get_id(assigned_moderator.into(), &**pool, &redis)
+                        .await?;
+                if !assigned_user
+                    .map(|x| Role::from_string(&x.role).is_mod())
+                    .unwrap_or(false)
+                {
+                    return Err(ApiError::InvalidInput(
+                        "Reports can only be assigned to moderators!".to_string(),
+                    ));
+                }
+            }
+
+            sqlx::query!(
+                "
+                UPDATE reports
+                SET assigned_moderator = $1
+                WHERE (id = $2)
+                ",
+                assigned_moderator.map(|x| x.0 as i64),
+                id as crate::database::models::ids::ReportId,
+            )
+            .execute(&mut *transaction)
+            .await?;
         }
 
         // delete any images no longer in the body
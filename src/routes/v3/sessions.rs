@@ -0,0 +1,168 @@
+use std::sync::Arc;
+
+use crate::auth::get_user_from_headers;
+use crate::database::models::session_item::Session as DBSession;
+use crate::database::redis::RedisPool;
+use crate::models::pats::Scopes;
+use crate::models::sessions::ActiveSession;
+use crate::queue::maxmind::MaxMindIndexer;
+use crate::queue::session::AuthQueue;
+use crate::routes::analytics::convert_to_ip_v6;
+use crate::routes::ApiError;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use serde::Deserialize;
+use sqlx::PgPool;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("auth/sessions")
+            .route("", web::get().to(sessions_list))
+            .route("", web::delete().to(sessions_delete))
+            .route("{id}", web::delete().to(session_delete)),
+    );
+}
+
+fn current_session(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(AUTHORIZATION)
+        .and_then(|x| x.to_str().ok())
+}
+
+pub async fn sessions_list(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+    maxmind: web::Data<Arc<MaxMindIndexer>>,
+) -> Result<HttpResponse, ApiError> {
+    let current_user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::SESSION_READ]),
+    )
+    .await?
+    .1;
+
+    let session_ids = DBSession::get_user_sessions(current_user.id.into(), &**pool, &redis).await?;
+    let mut sessions = Vec::new();
+    // Tokens issued to OAuth applications aren't sessions the user logged in with, so they're
+    // left out
+    for session in DBSession::get_many_ids(&session_ids, &**pool, &redis)
+        .await?
+        .into_iter()
+        .filter(|x| x.expires > Utc::now() && x.session.starts_with("mra_"))
+    {
+        let country = match session.country.filter(|x| !x.is_empty()) {
+            Some(country) => Some(country),
+            None => match convert_to_ip_v6(&session.ip) {
+                Ok(ip) => maxmind.query(ip).await,
+                Err(_) => None,
+            },
+        };
+
+        sessions.push(ActiveSession {
+            id: session.id.into(),
+            current: Some(&*session.session) == current_session(&req),
+            created: session.created,
+            last_login: session.last_login,
+            expires: session.expires,
+            os: session.os,
+            platform: session.platform,
+            user_agent: session.user_agent,
+            country,
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+pub async fn session_delete(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let current_user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::SESSION_DELETE]),
+    )
+    .await?
+    .1;
+
+    let session = DBSession::get(info.into_inner().0, &**pool, &redis).await?;
+
+    if let Some(session) = session {
+        if session.user_id == current_user.id.into() {
+            let mut transaction = pool.begin().await?;
+            DBSession::remove(session.id, &mut transaction).await?;
+            transaction.commit().await?;
+            DBSession::clear_cache(
+                vec![(
+                    Some(session.id),
+                    Some(session.session),
+                    Some(session.user_id),
+                )],
+                &redis,
+            )
+            .await?;
+        }
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+#[derive(Deserialize)]
+pub struct SessionsDeleteQuery {
+    #[serde(default)]
+    pub except_current: bool,
+}
+
+pub async fn sessions_delete(
+    req: HttpRequest,
+    query: web::Query<SessionsDeleteQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let current_user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::SESSION_DELETE]),
+    )
+    .await?
+    .1;
+
+    let session_ids = DBSession::get_user_sessions(current_user.id.into(), &**pool, &redis).await?;
+    let sessions = DBSession::get_many_ids(&session_ids, &**pool, &redis)
+        .await?
+        .into_iter()
+        .filter(|x| !query.except_current || Some(&*x.session) != current_session(&req))
+        .collect::<Vec<_>>();
+
+    let mut transaction = pool.begin().await?;
+    for session in &sessions {
+        DBSession::remove(session.id, &mut transaction).await?;
+    }
+    transaction.commit().await?;
+
+    DBSession::clear_cache(
+        sessions
+            .into_iter()
+            .map(|x| (Some(x.id), Some(x.session), Some(x.user_id)))
+            .collect(),
+        &redis,
+    )
+    .await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
@@ -1,6 +1,21 @@
+use crate::auth::get_user_from_headers;
+use crate::database::models::daily_download_item::{DailyDownload, DownloadTotals};
+use crate::database::models::{self as db_models, TeamMember};
+use crate::database::redis::RedisPool;
+use crate::models::ids::VersionId;
+use crate::models::pats::Scopes;
+use crate::models::teams::ProjectPermissions;
+use crate::models::users::User;
+use crate::queue::session::AuthQueue;
 use crate::routes::ApiError;
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Datelike, Duration, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::collections::HashMap;
+
+// The longest range of download statistics which can be fetched at once, in days
+const MAX_STATISTICS_RANGE_DAYS: i64 = 2 * 365;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.route("statistics", web::get().to(get_stats));
@@ -90,3 +105,196 @@ pub async fn get_stats(pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError
 
     Ok(HttpResponse::Ok().json(v3_stats))
 }
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatisticsResolution {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl StatisticsResolution {
+    /// The first day of the period containing the date
+    fn period_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            StatisticsResolution::Day => date,
+            StatisticsResolution::Week => {
+                date - Duration::days(date.weekday().num_days_from_monday() as i64)
+            }
+            StatisticsResolution::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    fn next_period(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            StatisticsResolution::Day => date.checked_add_signed(Duration::days(1)),
+            StatisticsResolution::Week => date.checked_add_signed(Duration::weeks(1)),
+            StatisticsResolution::Month => date.checked_add_months(Months::new(1)),
+        }
+    }
+}
+
+/// The range of download statistics to fetch. Both dates are inclusive, and default to
+/// the 30 days up to today.
+#[derive(Serialize, Deserialize)]
+pub struct DownloadStatisticsQuery {
+    #[serde(default)]
+    pub resolution: StatisticsResolution,
+    pub start: Option<NaiveDate>,
+    pub end: Option<NaiveDate>,
+}
+
+impl DownloadStatisticsQuery {
+    fn range(&self) -> Result<(NaiveDate, NaiveDate), ApiError> {
+        let end = self.end.unwrap_or_else(|| Utc::now().date_naive());
+        let start = self.start.unwrap_or(end - Duration::days(30));
+
+        if start > end {
+            return Err(ApiError::InvalidInput(
+                "The start date must not be after the end date".to_string(),
+            ));
+        }
+
+        if end - start > Duration::days(MAX_STATISTICS_RANGE_DAYS) {
+            return Err(ApiError::InvalidInput(
+                "Download statistics can span at most 2 years".to_string(),
+            ));
+        }
+
+        Ok((start, end))
+    }
+}
+
+/// The downloads in the period starting on `date`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DownloadStatistic {
+    pub date: NaiveDate,
+    /// Every download, the sum of the organic and pack-driven ones
+    pub downloads: i64,
+    pub organic_downloads: i64,
+    /// The downloads made while installing a modpack which depends on the project
+    pub pack_downloads: i64,
+}
+
+/// Groups the daily downloads into periods, including periods without any downloads
+fn download_series(
+    daily_downloads: HashMap<NaiveDate, DownloadTotals>,
+    resolution: StatisticsResolution,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Vec<DownloadStatistic> {
+    let mut periods: HashMap<NaiveDate, DownloadTotals> = HashMap::new();
+    for (date, totals) in daily_downloads {
+        let entry = periods.entry(resolution.period_start(date)).or_default();
+        entry.downloads += totals.downloads;
+        entry.pack_downloads += totals.pack_downloads;
+    }
+
+    let mut series = Vec::new();
+    let mut date = Some(resolution.period_start(start));
+    while let Some(period) = date.filter(|x| *x <= end) {
+        let totals = periods.get(&period).copied().unwrap_or_default();
+        series.push(DownloadStatistic {
+            date: period,
+            downloads: totals.downloads,
+            organic_downloads: totals.downloads - totals.pack_downloads,
+            pack_downloads: totals.pack_downloads,
+        });
+        date = resolution.next_period(period);
+    }
+
+    series
+}
+
+async fn check_analytics_permissions(
+    project: &db_models::project_item::Project,
+    user: &User,
+    pool: &PgPool,
+) -> Result<(), ApiError> {
+    let (team_member, organization_team_member) =
+        TeamMember::get_for_project_permissions(project, user.id.into(), pool).await?;
+
+    // Hide the project
+    if !user.role.is_admin() && team_member.is_none() && organization_team_member.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    let permissions = ProjectPermissions::get_permissions_by_role(
+        &user.role,
+        &team_member,
+        &organization_team_member,
+    )
+    .unwrap_or_default();
+
+    if !permissions.contains(ProjectPermissions::VIEW_ANALYTICS) {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to view this project's analytics.".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn project_download_statistics(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    web::Query(query): web::Query<DownloadStatisticsQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ANALYTICS]),
+    )
+    .await?
+    .1;
+    let (start, end) = query.range()?;
+
+    let project = db_models::Project::get(&info.into_inner().0, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    check_analytics_permissions(&project.inner, &user, &pool).await?;
+
+    let downloads = DailyDownload::get_project_totals(project.inner.id, start, end, &**pool).await?;
+
+    Ok(HttpResponse::Ok().json(download_series(downloads, query.resolution, start, end)))
+}
+
+pub async fn version_download_statistics(
+    req: HttpRequest,
+    info: web::Path<(VersionId,)>,
+    web::Query(query): web::Query<DownloadStatisticsQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::ANALYTICS]),
+    )
+    .await?
+    .1;
+    let (start, end) = query.range()?;
+
+    let version = db_models::Version::get(info.into_inner().0.into(), &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let project = db_models::Project::get_id(version.inner.project_id, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    check_analytics_permissions(&project.inner, &user, &pool).await?;
+
+    let downloads = DailyDownload::get_version_totals(version.inner.id, start, end, &**pool).await?;
+
+    Ok(HttpResponse::Ok().json(download_series(downloads, query.resolution, start, end)))
+}
+
@@ -1,20 +1,30 @@
 use crate::auth::checks::is_visible_project;
 use crate::auth::get_user_from_headers;
+use crate::database::models::audit_log_item::AuditLogBuilder;
 use crate::database::models::notification_item::NotificationBuilder;
+use crate::database::models::project_block_item::ProjectBlock;
 use crate::database::models::team_item::TeamAssociationId;
+use crate::database::models::user_block_item::UserBlock;
 use crate::database::models::{Organization, Team, TeamMember, User};
 use crate::database::redis::RedisPool;
 use crate::database::Project;
+use crate::models::audit_log::{AuditAction, AuditTargetType};
+use crate::models::ids::OrganizationRoleTemplateId;
 use crate::models::notifications::NotificationBody;
 use crate::models::pats::Scopes;
-use crate::models::teams::{OrganizationPermissions, ProjectPermissions, TeamId};
-use crate::models::users::UserId;
+use crate::models::teams::{OrganizationPermissions, ProjectPermissions, TeamId, MAX_TEAM_MEMBERS};
+use crate::models::users::{Role, UserId};
 use crate::queue::session::AuthQueue;
+use crate::routes::v3::audit_log::{record_change, request_ip};
+use crate::routes::v3::organization_role_templates::get_team_role_template;
+use crate::routes::v3::project_blocks::is_blocked_from_project;
 use crate::routes::ApiError;
 use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::collections::HashMap;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.route("teams", web::get().to(teams_get));
@@ -28,6 +38,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                 web::delete().to(remove_team_member),
             )
             .route("{id}/members", web::post().to(add_team_member))
+            .route("{id}/members/bulk", web::post().to(add_team_members_bulk))
             .route("{id}/join", web::post().to(join_team))
             .route("{id}/owner", web::patch().to(transfer_ownership)),
     );
@@ -82,7 +93,7 @@ pub async fn team_members_get_project(
             false
         };
 
-        let team_members: Vec<_> = members_data
+        let mut team_members: Vec<_> = members_data
             .into_iter()
             .filter(|x| {
                 logged_in
@@ -97,6 +108,9 @@ pub async fn team_members_get_project(
                 })
             })
             .collect();
+        if !can_view_inviters(project.inner.team_id, &current_user, &pool).await? {
+            team_members.iter_mut().for_each(|x| x.invited_by = None);
+        }
 
         Ok(HttpResponse::Ok().json(team_members))
     } else {
@@ -139,6 +153,7 @@ pub async fn team_members_get_organization(
         let user_id = current_user.as_ref().map(|x| x.id.into());
 
         let logged_in = current_user
+            .as_ref()
             .and_then(|user| {
                 members_data
                     .iter()
@@ -146,7 +161,7 @@ pub async fn team_members_get_organization(
             })
             .is_some();
 
-        let team_members: Vec<_> = members_data
+        let mut team_members: Vec<_> = members_data
             .into_iter()
             .filter(|x| {
                 logged_in
@@ -161,6 +176,9 @@ pub async fn team_members_get_organization(
                 })
             })
             .collect();
+        if !can_view_inviters(organization.team_id, &current_user, &pool).await? {
+            team_members.iter_mut().for_each(|x| x.invited_by = None);
+        }
 
         Ok(HttpResponse::Ok().json(team_members))
     } else {
@@ -198,6 +216,7 @@ pub async fn team_members_get(
     let user_id = current_user.as_ref().map(|x| x.id.into());
 
     let logged_in = current_user
+        .as_ref()
         .and_then(|user| {
             members_data
                 .iter()
@@ -205,7 +224,7 @@ pub async fn team_members_get(
         })
         .is_some();
 
-    let team_members: Vec<_> = members_data
+    let mut team_members: Vec<_> = members_data
         .into_iter()
         .filter(|x| {
             logged_in
@@ -221,6 +240,9 @@ pub async fn team_members_get(
                 .map(|user| crate::models::teams::TeamMember::from(data, user.clone(), !logged_in))
         })
         .collect();
+    if !can_view_inviters(id.into(), &current_user, &pool).await? {
+        team_members.iter_mut().for_each(|x| x.invited_by = None);
+    }
 
     Ok(HttpResponse::Ok().json(team_members))
 }
@@ -267,7 +289,7 @@ pub async fn teams_get(
 
     let mut teams: Vec<Vec<crate::models::teams::TeamMember>> = vec![];
 
-    for (_, member_data) in &teams_groups {
+    for (team_id, member_data) in &teams_groups {
         let members = member_data.collect::<Vec<_>>();
 
         let logged_in = current_user
@@ -279,21 +301,72 @@ pub async fn teams_get(
             })
             .is_some();
 
-        let team_members = members
+        let mut team_members = members
             .into_iter()
             .filter(|x| logged_in || x.accepted)
             .flat_map(|data| {
                 users.iter().find(|x| x.id == data.user_id).map(|user| {
                     crate::models::teams::TeamMember::from(data, user.clone(), !logged_in)
                 })
-            });
+            })
+            .collect::<Vec<_>>();
+        if logged_in
+            && !can_view_inviters(
+                crate::database::models::TeamId(team_id),
+                &current_user,
+                &pool,
+            )
+            .await?
+        {
+            team_members.iter_mut().for_each(|x| x.invited_by = None);
+        }
 
-        teams.push(team_members.collect());
+        teams.push(team_members);
     }
 
     Ok(HttpResponse::Ok().json(teams))
 }
 
+// Who sent a pending member's invite is only shown to users who can manage the team's invites
+async fn can_view_inviters(
+    team_id: crate::database::models::TeamId,
+    user: &Option<crate::models::users::User>,
+    pool: &PgPool,
+) -> Result<bool, ApiError> {
+    let Some(user) = user else {
+        return Ok(false);
+    };
+
+    let member = TeamMember::get_from_user_id(team_id, user.id.into(), pool).await?;
+    let can_manage_invites = match Team::get_association(team_id, pool).await? {
+        Some(TeamAssociationId::Project(pid)) => {
+            let organization =
+                Organization::get_associated_organization_project_id(pid, pool).await?;
+            let organization_team_member = if let Some(organization) = &organization {
+                TeamMember::get_from_user_id(organization.team_id, user.id.into(), pool).await?
+            } else {
+                None
+            };
+
+            ProjectPermissions::get_permissions_by_role(
+                &user.role,
+                &member,
+                &organization_team_member,
+            )
+            .unwrap_or_default()
+            .contains(ProjectPermissions::MANAGE_INVITES)
+        }
+        Some(TeamAssociationId::Organization(_)) => {
+            OrganizationPermissions::get_permissions_by_role(&user.role, &member)
+                .unwrap_or_default()
+                .contains(OrganizationPermissions::MANAGE_INVITES)
+        }
+        None => false,
+    };
+
+    Ok(can_manage_invites)
+}
+
 pub async fn join_team(
     req: HttpRequest,
     info: web::Path<(TeamId,)>,
@@ -312,8 +385,13 @@ pub async fn join_team(
     .await?
     .1;
 
-    let member =
-        TeamMember::get_from_user_id_pending(team_id, current_user.id.into(), &**pool).await?;
+    // Invites to deleted projects' teams can't be accepted
+    let team_association = Team::get_association(team_id, &**pool).await?;
+    let member = if team_association.is_some() {
+        TeamMember::get_from_user_id_pending(team_id, current_user.id.into(), &**pool).await?
+    } else {
+        None
+    };
 
     if let Some(member) = member {
         if member.accepted {
@@ -321,6 +399,25 @@ pub async fn join_team(
                 "You are already a member of this team".to_string(),
             ));
         }
+        if let Some(TeamAssociationId::Project(project_id)) = team_association {
+            if is_blocked_from_project(project_id, &current_user, &pool).await? {
+                return Err(ApiError::Forbidden("You can't join this team.".to_string()));
+            }
+        }
+        if UserBlock::is_blocked_by_team_owner(team_id, current_user.id.into(), &**pool).await? {
+            return Err(ApiError::Forbidden("You can't join this team.".to_string()));
+        }
+        if let Some(TeamAssociationId::Organization(organization_id)) = team_association {
+            let organization = Organization::get_id(organization_id, &**pool, &redis).await?;
+            if organization.is_some_and(|x| x.require_2fa)
+                && !current_user.has_totp.unwrap_or(false)
+            {
+                return Err(ApiError::InvalidInput(
+                    "This organization requires two-factor authentication. Enable it on your account to join the organization."
+                        .to_string(),
+                ));
+            }
+        }
         let mut transaction = pool.begin().await?;
 
         // Edit Team Member to set Accepted to True
@@ -373,40 +470,113 @@ pub struct NewTeamMember {
     pub payouts_split: Decimal,
     #[serde(default = "default_ordering")]
     pub ordering: i64,
+    /// An organization role template to copy the member's permissions from. Can't be given
+    /// along with permissions.
+    #[serde(default)]
+    pub role_template: Option<OrganizationRoleTemplateId>,
 }
 
-pub async fn add_team_member(
-    req: HttpRequest,
-    info: web::Path<(TeamId,)>,
-    pool: web::Data<PgPool>,
-    new_member: web::Json<NewTeamMember>,
-    redis: web::Data<RedisPool>,
-    session_queue: web::Data<AuthQueue>,
-) -> Result<HttpResponse, ApiError> {
-    let team_id = info.into_inner().0.into();
+/// The most users which can be invited in one bulk invite
+pub const MAX_BULK_INVITES: usize = 50;
 
-    let mut transaction = pool.begin().await?;
+/// An invite in a bulk invite. The user is given by their id or username.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BulkNewTeamMember {
+    pub user: String,
+    #[serde(default = "default_role")]
+    pub role: String,
+    #[serde(default)]
+    pub permissions: ProjectPermissions,
+    #[serde(default)]
+    pub organization_permissions: Option<OrganizationPermissions>,
+    #[serde(default)]
+    #[serde(with = "rust_decimal::serde::float")]
+    pub payouts_split: Decimal,
+    #[serde(default = "default_ordering")]
+    pub ordering: i64,
+    #[serde(default)]
+    pub role_template: Option<OrganizationRoleTemplateId>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InviteErrorKind {
+    UserNotFound,
+    AlreadyMember,
+    Blocked,
+    TeamFull,
+    InvalidInput,
+}
+
+/// Why an invite was rejected
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InviteError {
+    pub error: InviteErrorKind,
+    pub description: String,
+}
+
+impl InviteError {
+    fn new(error: InviteErrorKind, description: &str) -> Self {
+        InviteError {
+            error,
+            description: description.to_string(),
+        }
+    }
+}
+
+impl From<InviteError> for ApiError {
+    fn from(err: InviteError) -> Self {
+        ApiError::InvalidInput(err.description)
+    }
+}
+
+/// The result of one of the invites of a bulk invite
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BulkInviteResult {
+    /// The user as they were given in the request
+    pub user: String,
+    pub success: bool,
+    pub error: Option<InviteError>,
+}
+
+// Gets the organization whose role templates can be given to members of the team
+async fn get_team_organization_id(
+    team_association: TeamAssociationId,
+    pool: &PgPool,
+) -> Result<Option<crate::database::models::OrganizationId>, ApiError> {
+    Ok(match team_association {
+        TeamAssociationId::Project(pid) => {
+            Organization::get_associated_organization_project_id(pid, pool)
+                .await?
+                .map(|x| x.id)
+        }
+        TeamAssociationId::Organization(oid) => Some(oid),
+    })
+}
+
+// The permissions of a user inviting others to a team, which bound the permissions they can give
+#[derive(Clone, Copy)]
+enum InviterPermissions {
+    Project(ProjectPermissions),
+    Organization(OrganizationPermissions),
+}
+
+// Checks that the user can invite users to the team
+async fn get_inviter_permissions(
+    current_user: &crate::models::users::User,
+    team_id: crate::database::models::TeamId,
+    team_association: TeamAssociationId,
+    pool: &PgPool,
+) -> Result<InviterPermissions, ApiError> {
+    let member = TeamMember::get_from_user_id(team_id, current_user.id.into(), pool).await?;
 
-    let current_user = get_user_from_headers(
-        &req,
-        &**pool,
-        &redis,
-        &session_queue,
-        Some(&[Scopes::PROJECT_WRITE]),
-    )
-    .await?
-    .1;
-    let team_association = Team::get_association(team_id, &**pool)
-        .await?
-        .ok_or_else(|| ApiError::InvalidInput("The team specified does not exist".to_string()))?;
-    let member = TeamMember::get_from_user_id(team_id, current_user.id.into(), &**pool).await?;
     match team_association {
         // If team is associated with a project, check if they have permissions to invite users to that project
         TeamAssociationId::Project(pid) => {
             let organization =
-                Organization::get_associated_organization_project_id(pid, &**pool).await?;
+                Organization::get_associated_organization_project_id(pid, pool).await?;
             let organization_team_member = if let Some(organization) = &organization {
-                TeamMember::get_from_user_id(organization.team_id, current_user.id.into(), &**pool)
+                TeamMember::get_from_user_id(organization.team_id, current_user.id.into(), pool)
                     .await?
             } else {
                 None
@@ -423,6 +593,55 @@ pub async fn add_team_member(
                     "You don't have permission to invite users to this team".to_string(),
                 ));
             }
+
+            Ok(InviterPermissions::Project(permissions))
+        }
+        // If team is associated with an organization, check if they have permissions to invite users to that organization
+        TeamAssociationId::Organization(_) => {
+            let organization_permissions =
+                OrganizationPermissions::get_permissions_by_role(&current_user.role, &member)
+                    .unwrap_or_default();
+            if !organization_permissions.contains(OrganizationPermissions::MANAGE_INVITES) {
+                return Err(ApiError::CustomAuthentication(
+                    "You don't have permission to invite users to this organization".to_string(),
+                ));
+            }
+
+            Ok(InviterPermissions::Organization(organization_permissions))
+        }
+    }
+}
+
+// Fills in the new member's permissions from their role template, and checks that the inviter
+// can give them. Returns the id of the template.
+async fn check_new_member(
+    new_member: &mut NewTeamMember,
+    inviter_permissions: InviterPermissions,
+    team_association: TeamAssociationId,
+    pool: &PgPool,
+) -> Result<Option<crate::database::models::OrganizationRoleTemplateId>, ApiError> {
+    // The template's permissions are checked below like any other permissions being given
+    let role_template_id = if let Some(template_id) = new_member.role_template {
+        // Permissions default to none, so only ones which were actually given are rejected
+        if !new_member.permissions.is_empty() || new_member.organization_permissions.is_some() {
+            return Err(ApiError::InvalidInput(
+                "Permissions can't be given along with a role template".to_string(),
+            ));
+        }
+
+        let organization_id = get_team_organization_id(team_association, pool).await?;
+        let template = get_team_role_template(template_id, organization_id, pool).await?;
+        new_member.permissions = template.permissions;
+        if let TeamAssociationId::Organization(_) = team_association {
+            new_member.organization_permissions = Some(template.organization_permissions);
+        }
+        Some(template.id)
+    } else {
+        None
+    };
+
+    match inviter_permissions {
+        InviterPermissions::Project(permissions) => {
             if !permissions.contains(new_member.permissions) {
                 return Err(ApiError::InvalidInput(
                     "The new member has permissions that you don't have".to_string(),
@@ -436,16 +655,7 @@ pub async fn add_team_member(
                 ));
             }
         }
-        // If team is associated with an organization, check if they have permissions to invite users to that organization
-        TeamAssociationId::Organization(_) => {
-            let organization_permissions =
-                OrganizationPermissions::get_permissions_by_role(&current_user.role, &member)
-                    .unwrap_or_default();
-            if !organization_permissions.contains(OrganizationPermissions::MANAGE_INVITES) {
-                return Err(ApiError::CustomAuthentication(
-                    "You don't have permission to invite users to this organization".to_string(),
-                ));
-            }
+        InviterPermissions::Organization(organization_permissions) => {
             if !organization_permissions
                 .contains(new_member.organization_permissions.unwrap_or_default())
             {
@@ -471,32 +681,75 @@ pub async fn add_team_member(
         ));
     }
 
+    Ok(role_template_id)
+}
+
+// Adds the new member to the team, once their permissions have been checked. The outer error is
+// an internal failure, and the inner one a reason the invite was rejected. Returns whether the
+// user was added without having to accept an invite.
+#[allow(clippy::too_many_arguments)]
+async fn insert_team_member(
+    new_member: &NewTeamMember,
+    role_template_id: Option<crate::database::models::OrganizationRoleTemplateId>,
+    current_user: &crate::models::users::User,
+    team_id: crate::database::models::TeamId,
+    team_association: TeamAssociationId,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<Result<bool, InviteError>, ApiError> {
     let request =
-        TeamMember::get_from_user_id_pending(team_id, new_member.user_id.into(), &**pool).await?;
+        TeamMember::get_from_user_id_pending(team_id, new_member.user_id.into(), pool).await?;
 
     if let Some(req) = request {
-        if req.accepted {
-            return Err(ApiError::InvalidInput(
-                "The user is already a member of that team".to_string(),
-            ));
-        } else {
-            return Err(ApiError::InvalidInput(
-                "There is already a pending member request for this user".to_string(),
-            ));
+        return Ok(Err(InviteError::new(
+            InviteErrorKind::AlreadyMember,
+            if req.accepted {
+                "The user is already a member of that team"
+            } else {
+                "There is already a pending member request for this user"
+            },
+        )));
+    }
+    let Some(new_user) = User::get_id(new_member.user_id.into(), pool, redis).await? else {
+        return Ok(Err(InviteError::new(
+            InviteErrorKind::UserNotFound,
+            "An invalid User ID specified",
+        )));
+    };
+
+    if TeamMember::count(team_id, &mut **transaction).await? >= MAX_TEAM_MEMBERS {
+        return Ok(Err(InviteError::new(
+            InviteErrorKind::TeamFull,
+            &format!("A team can have at most {MAX_TEAM_MEMBERS} members"),
+        )));
+    }
+
+    if let TeamAssociationId::Organization(organization_id) = team_association {
+        let organization = Organization::get_id(organization_id, pool, redis).await?;
+        if organization.is_some_and(|x| x.require_2fa) && new_user.totp_secret.is_none() {
+            return Ok(Err(InviteError::new(
+                InviteErrorKind::InvalidInput,
+                "This organization requires two-factor authentication, which the user hasn't enabled",
+            )));
         }
     }
-    let new_user =
-        crate::database::models::User::get_id(new_member.user_id.into(), &**pool, &redis)
-            .await?
-            .ok_or_else(|| ApiError::InvalidInput("An invalid User ID specified".to_string()))?;
 
     let mut force_accepted = false;
     if let TeamAssociationId::Project(pid) = team_association {
+        if !Role::from_string(&new_user.role).is_mod()
+            && ProjectBlock::is_blocked(pid, new_user.id, pool).await?
+        {
+            return Ok(Err(InviteError::new(
+                InviteErrorKind::Blocked,
+                "This user is blocked from this project and can't be invited",
+            )));
+        }
+
         // We cannot add the owner to a project team in their own org
-        let organization =
-            Organization::get_associated_organization_project_id(pid, &**pool).await?;
+        let organization = Organization::get_associated_organization_project_id(pid, pool).await?;
         let new_user_organization_team_member = if let Some(organization) = &organization {
-            TeamMember::get_from_user_id(organization.team_id, new_user.id, &**pool).await?
+            TeamMember::get_from_user_id(organization.team_id, new_user.id, pool).await?
         } else {
             None
         };
@@ -505,9 +758,10 @@ pub async fn add_team_member(
             .map(|tm| tm.is_owner)
             .unwrap_or(false)
         {
-            return Err(ApiError::InvalidInput(
-                "You cannot add the owner of an organization to a project team owned by that organization".to_string(),
-            ));
+            return Ok(Err(InviteError::new(
+                InviteErrorKind::InvalidInput,
+                "You cannot add the owner of an organization to a project team owned by that organization",
+            )));
         }
 
         // In the case of adding a user that is in an org, to a project that is owned by that same org,
@@ -519,7 +773,7 @@ pub async fn add_team_member(
         }
     }
 
-    let new_id = crate::database::models::ids::generate_team_member_id(&mut transaction).await?;
+    let new_id = crate::database::models::ids::generate_team_member_id(transaction).await?;
     TeamMember {
         id: new_id,
         team_id,
@@ -531,44 +785,236 @@ pub async fn add_team_member(
         accepted: force_accepted,
         payouts_split: new_member.payouts_split,
         ordering: new_member.ordering,
+        created: Utc::now(),
+        accepted_at: force_accepted.then(Utc::now),
+        invited_by: Some(current_user.id.into()),
+        two_factor_restricted: false,
+        role_template_id,
     }
-    .insert(&mut transaction)
+    .insert(transaction)
     .await?;
 
+    Ok(Ok(force_accepted))
+}
+
+// The notification sent to users invited to the team with the role
+fn invite_notification(
+    team_id: crate::database::models::TeamId,
+    team_association: TeamAssociationId,
+    invited_by: UserId,
+    role: String,
+) -> NotificationBody {
+    match team_association {
+        TeamAssociationId::Project(pid) => NotificationBody::TeamInvite {
+            project_id: pid.into(),
+            team_id: team_id.into(),
+            invited_by,
+            role,
+        },
+        TeamAssociationId::Organization(oid) => NotificationBody::OrganizationInvite {
+            organization_id: oid.into(),
+            team_id: team_id.into(),
+            invited_by,
+            role,
+        },
+    }
+}
+
+pub async fn add_team_member(
+    req: HttpRequest,
+    info: web::Path<(TeamId,)>,
+    pool: web::Data<PgPool>,
+    new_member: web::Json<NewTeamMember>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let team_id = info.into_inner().0.into();
+
+    let mut transaction = pool.begin().await?;
+
+    let current_user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+    let team_association = Team::get_association(team_id, &**pool)
+        .await?
+        .ok_or_else(|| ApiError::InvalidInput("The team specified does not exist".to_string()))?;
+    let inviter_permissions =
+        get_inviter_permissions(&current_user, team_id, team_association, &pool).await?;
+
+    let mut new_member = new_member.into_inner();
+    let role_template_id = check_new_member(
+        &mut new_member,
+        inviter_permissions,
+        team_association,
+        &pool,
+    )
+    .await?;
+    let force_accepted = insert_team_member(
+        &new_member,
+        role_template_id,
+        &current_user,
+        team_id,
+        team_association,
+        &mut transaction,
+        &pool,
+        &redis,
+    )
+    .await??;
+
     // If the user has an opportunity to accept the invite, send a notification
     if !force_accepted {
-        match team_association {
-            TeamAssociationId::Project(pid) => {
-                NotificationBuilder {
-                    body: NotificationBody::TeamInvite {
-                        project_id: pid.into(),
-                        team_id: team_id.into(),
-                        invited_by: current_user.id,
-                        role: new_member.role.clone(),
-                    },
-                }
-                .insert(new_member.user_id.into(), &mut transaction, &redis)
-                .await?;
+        NotificationBuilder {
+            body: invite_notification(
+                team_id,
+                team_association,
+                current_user.id,
+                new_member.role.clone(),
+            ),
+        }
+        .insert(new_member.user_id.into(), &mut transaction, &redis)
+        .await?;
+    }
+
+    transaction.commit().await?;
+    TeamMember::clear_cache(team_id, &redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+/// Invites several users to a team at once. Each invite is checked and added on its own, so some
+/// can succeed when others are rejected, and the result of each is listed in a 207 response.
+pub async fn add_team_members_bulk(
+    req: HttpRequest,
+    info: web::Path<(TeamId,)>,
+    pool: web::Data<PgPool>,
+    new_members: web::Json<Vec<BulkNewTeamMember>>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let team_id = info.into_inner().0.into();
+    let new_members = new_members.into_inner();
+
+    if new_members.len() > MAX_BULK_INVITES {
+        return Err(ApiError::InvalidInput(format!(
+            "At most {MAX_BULK_INVITES} users can be invited at once"
+        )));
+    }
+
+    let current_user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+    let team_association = Team::get_association(team_id, &**pool)
+        .await?
+        .ok_or_else(|| ApiError::InvalidInput("The team specified does not exist".to_string()))?;
+    let inviter_permissions =
+        get_inviter_permissions(&current_user, team_id, team_association, &pool).await?;
+
+    let mut results = Vec::with_capacity(new_members.len());
+    // The users to notify, by the role they were invited with
+    let mut invited: HashMap<String, Vec<crate::database::models::UserId>> = HashMap::new();
+    for bulk_member in new_members {
+        let Some(user) = User::get(&bulk_member.user, &**pool, &redis).await? else {
+            results.push(BulkInviteResult {
+                user: bulk_member.user,
+                success: false,
+                error: Some(InviteError::new(
+                    InviteErrorKind::UserNotFound,
+                    "The user doesn't exist",
+                )),
+            });
+            continue;
+        };
+
+        let mut new_member = NewTeamMember {
+            user_id: user.id.into(),
+            role: bulk_member.role,
+            permissions: bulk_member.permissions,
+            organization_permissions: bulk_member.organization_permissions,
+            payouts_split: bulk_member.payouts_split,
+            ordering: bulk_member.ordering,
+            role_template: bulk_member.role_template,
+        };
+        let role_template_id = match check_new_member(
+            &mut new_member,
+            inviter_permissions,
+            team_association,
+            &pool,
+        )
+        .await
+        {
+            Ok(role_template_id) => role_template_id,
+            Err(ApiError::InvalidInput(description))
+            | Err(ApiError::CustomAuthentication(description)) => {
+                results.push(BulkInviteResult {
+                    user: bulk_member.user,
+                    success: false,
+                    error: Some(InviteError {
+                        error: InviteErrorKind::InvalidInput,
+                        description,
+                    }),
+                });
+                continue;
             }
-            TeamAssociationId::Organization(oid) => {
-                NotificationBuilder {
-                    body: NotificationBody::OrganizationInvite {
-                        organization_id: oid.into(),
-                        team_id: team_id.into(),
-                        invited_by: current_user.id,
-                        role: new_member.role.clone(),
-                    },
+            Err(err) => return Err(err),
+        };
+
+        let mut transaction = pool.begin().await?;
+        let result = insert_team_member(
+            &new_member,
+            role_template_id,
+            &current_user,
+            team_id,
+            team_association,
+            &mut transaction,
+            &pool,
+            &redis,
+        )
+        .await?;
+        transaction.commit().await?;
+
+        match result {
+            Ok(force_accepted) => {
+                if !force_accepted {
+                    invited.entry(new_member.role).or_default().push(user.id);
                 }
-                .insert(new_member.user_id.into(), &mut transaction, &redis)
-                .await?;
+                results.push(BulkInviteResult {
+                    user: bulk_member.user,
+                    success: true,
+                    error: None,
+                });
             }
+            Err(err) => results.push(BulkInviteResult {
+                user: bulk_member.user,
+                success: false,
+                error: Some(err),
+            }),
         }
     }
 
+    let mut transaction = pool.begin().await?;
+    for (role, users) in invited {
+        NotificationBuilder {
+            body: invite_notification(team_id, team_association, current_user.id, role),
+        }
+        .insert_many(users, &mut transaction, &redis)
+        .await?;
+    }
     transaction.commit().await?;
     TeamMember::clear_cache(team_id, &redis).await?;
 
-    Ok(HttpResponse::NoContent().body(""))
+    Ok(HttpResponse::MultiStatus().json(results))
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -578,6 +1024,10 @@ pub struct EditTeamMember {
     pub role: Option<String>,
     pub payouts_split: Option<Decimal>,
     pub ordering: Option<i64>,
+    /// An organization role template to copy the member's permissions from. Can't be given
+    /// along with permissions.
+    #[serde(default)]
+    pub role_template: Option<OrganizationRoleTemplateId>,
 }
 
 pub async fn edit_team_member(
@@ -614,6 +1064,25 @@ pub async fn edit_team_member(
             )
         })?;
 
+    let mut edit_member = edit_member.into_inner();
+    let role_template_id = if let Some(template_id) = edit_member.role_template {
+        if edit_member.permissions.is_some() || edit_member.organization_permissions.is_some() {
+            return Err(ApiError::InvalidInput(
+                "Permissions can't be given along with a role template".to_string(),
+            ));
+        }
+
+        let organization_id = get_team_organization_id(team_association, &pool).await?;
+        let template = get_team_role_template(template_id, organization_id, &pool).await?;
+        edit_member.permissions = Some(template.permissions);
+        if let TeamAssociationId::Organization(_) = team_association {
+            edit_member.organization_permissions = Some(template.organization_permissions);
+        }
+        Some(template.id)
+    } else {
+        None
+    };
+
     let mut transaction = pool.begin().await?;
 
     if edit_member_db.is_owner
@@ -624,7 +1093,8 @@ pub async fn edit_team_member(
         ));
     }
 
-    match team_association {
+    // The entry in the audit log is listed under the project and its organization, if any
+    let (audit_project_id, audit_organization_id) = match team_association {
         TeamAssociationId::Project(project_id) => {
             let organization =
                 Organization::get_associated_organization_project_id(project_id, &**pool).await?;
@@ -660,8 +1130,10 @@ pub async fn edit_team_member(
                         .to_string(),
                 ));
             }
+
+            (Some(project_id), organization.map(|x| x.id))
         }
-        TeamAssociationId::Organization(_) => {
+        TeamAssociationId::Organization(organization_id) => {
             let organization_permissions =
                 OrganizationPermissions::get_permissions_by_role(&current_user.role, &member)
                     .unwrap_or_default();
@@ -690,8 +1162,10 @@ pub async fn edit_team_member(
                         .to_string(),
                 ));
             }
+
+            (None, Some(organization_id))
         }
-    }
+    };
 
     if let Some(payouts_split) = edit_member.payouts_split {
         if payouts_split < Decimal::ZERO || payouts_split > Decimal::from(5000) {
@@ -715,6 +1189,65 @@ pub async fn edit_team_member(
     )
     .await?;
 
+    // Members whose permissions are edited by hand no longer follow their template
+    let permissions_edited =
+        edit_member.permissions.is_some() || edit_member.organization_permissions.is_some();
+    if permissions_edited {
+        TeamMember::set_role_template(id, user_id, role_template_id, &mut transaction).await?;
+    }
+
+    let mut summary = serde_json::Map::new();
+    record_change(
+        &mut summary,
+        "permissions",
+        edit_member_db.permissions,
+        edit_member.permissions,
+    );
+    record_change(
+        &mut summary,
+        "organization_permissions",
+        edit_member_db.organization_permissions,
+        edit_member.organization_permissions.map(Some),
+    );
+    record_change(
+        &mut summary,
+        "role",
+        edit_member_db.role.clone(),
+        edit_member.role.clone(),
+    );
+    record_change(
+        &mut summary,
+        "payouts_split",
+        edit_member_db.payouts_split,
+        edit_member.payouts_split,
+    );
+    record_change(
+        &mut summary,
+        "ordering",
+        edit_member_db.ordering,
+        edit_member.ordering,
+    );
+    record_change(
+        &mut summary,
+        "role_template",
+        edit_member_db
+            .role_template_id
+            .map(OrganizationRoleTemplateId::from),
+        permissions_edited.then(|| role_template_id.map(OrganizationRoleTemplateId::from)),
+    );
+    AuditLogBuilder {
+        actor_id: current_user.id.into(),
+        action: AuditAction::TeamMemberEdit,
+        target_type: AuditTargetType::User,
+        target_id: user_id.0,
+        project_id: audit_project_id,
+        organization_id: audit_organization_id,
+        summary: summary.into(),
+        ip: request_ip(&req),
+    }
+    .insert(&mut transaction)
+    .await?;
+
     transaction.commit().await?;
     TeamMember::clear_cache(id, &redis).await?;
 
@@ -0,0 +1,214 @@
+use std::time::{Duration, Instant};
+
+use crate::auth::get_user_from_token;
+use crate::database;
+use crate::database::models::thread_item::THREAD_UPDATES_NAMESPACE;
+use crate::database::redis::RedisPool;
+use crate::models::pats::Scopes;
+use crate::models::threads::{ThreadId, ThreadUpdate};
+use crate::models::users::User;
+use crate::queue::session::AuthQueue;
+use crate::queue::socket::ActiveSockets;
+use crate::routes::v3::threads::is_authorized_thread;
+use crate::routes::ApiError;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_ws::{Message, MessageStream, Session};
+use futures::StreamExt;
+use log::warn;
+use serde::Deserialize;
+use sqlx::PgPool;
+use tokio::sync::RwLock;
+use tokio_stream::wrappers::IntervalStream;
+
+/// How many thread sockets a user may have open at once
+const MAX_THREAD_SOCKETS_PER_USER: usize = 5;
+/// How often the socket is pinged, and the user's access to the thread checked again
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+/// How long the client may go without sending anything before the socket is closed
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(45);
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("ws/thread/{id}", web::get().to(thread_socket));
+}
+
+#[derive(Deserialize)]
+pub struct ThreadSocketQuery {
+    /// Browsers can't set headers when opening a WebSocket, so the token can be given here
+    pub token: Option<String>,
+}
+
+enum SocketEvent {
+    Update(String),
+    Client(Result<Message, actix_ws::ProtocolError>),
+    Heartbeat,
+}
+
+pub async fn thread_socket(
+    req: HttpRequest,
+    info: web::Path<(ThreadId,)>,
+    web::Query(query): web::Query<ThreadSocketQuery>,
+    body: web::Payload,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+    active_sockets: web::Data<RwLock<ActiveSockets>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let thread_id: database::models::ThreadId = info.into_inner().0.into();
+
+    let user = get_user_from_token(
+        &req,
+        query.token.as_deref(),
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::THREAD_READ]),
+    )
+    .await
+    .map_err(ApiError::from)?
+    .1;
+
+    let thread = database::models::Thread::get(thread_id, &**pool)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NotFound)?;
+    if !is_authorized_thread(&thread, &user, &pool).await? {
+        return Err(ApiError::NotFound.into());
+    }
+
+    {
+        let sockets = active_sockets.read().await;
+        let mut count = sockets.thread_sockets.entry(user.id).or_insert(0);
+        if *count >= MAX_THREAD_SOCKETS_PER_USER {
+            return Err(ApiError::InvalidInput(format!(
+                "You may only have {} thread sockets open at once",
+                MAX_THREAD_SOCKETS_PER_USER
+            ))
+            .into());
+        }
+        *count += 1;
+    }
+
+    let subscribed = async {
+        let pubsub = redis
+            .subscribe(THREAD_UPDATES_NAMESPACE, &thread_id.0.to_string())
+            .await
+            .map_err(ApiError::from)?;
+        let (res, session, msg_stream) = actix_ws::handle(&req, body)?;
+        Ok::<_, actix_web::Error>((pubsub, res, session, msg_stream))
+    }
+    .await;
+
+    let (pubsub, res, session, msg_stream) = match subscribed {
+        Ok(x) => x,
+        Err(err) => {
+            release_socket(&active_sockets, &user).await;
+            return Err(err);
+        }
+    };
+
+    let updates = pubsub
+        .into_on_message()
+        .filter_map(|msg| async move { msg.get_payload::<String>().ok() })
+        .map(SocketEvent::Update);
+
+    actix_web::rt::spawn(async move {
+        run_socket(session, msg_stream, updates, thread_id, &user, &pool).await;
+
+        release_socket(&active_sockets, &user).await;
+    });
+
+    Ok(res)
+}
+
+/// Forwards the thread's updates to the socket until either side closes it, the client stops
+/// responding, or the user can no longer view the thread
+async fn run_socket(
+    mut session: Session,
+    msg_stream: MessageStream,
+    updates: impl futures::Stream<Item = SocketEvent>,
+    thread_id: database::models::ThreadId,
+    user: &User,
+    pool: &PgPool,
+) {
+    let heartbeats = IntervalStream::new(actix_rt::time::interval(HEARTBEAT_INTERVAL))
+        .map(|_| SocketEvent::Heartbeat);
+    let mut events = Box::pin(futures::stream::select(
+        updates,
+        futures::stream::select(msg_stream.map(SocketEvent::Client), heartbeats),
+    ));
+
+    let mut last_seen = Instant::now();
+
+    while let Some(event) = events.next().await {
+        match event {
+            SocketEvent::Update(payload) => {
+                let Ok(update) = serde_json::from_str(&payload) else {
+                    continue;
+                };
+                let Some(update) = ThreadUpdate::from(update, user) else {
+                    continue;
+                };
+                let Ok(text) = serde_json::to_string(&update) else {
+                    continue;
+                };
+
+                if session.text(text).await.is_err() {
+                    return;
+                }
+            }
+            SocketEvent::Client(Ok(msg)) => {
+                last_seen = Instant::now();
+
+                match msg {
+                    Message::Ping(bytes) => {
+                        if session.pong(&bytes).await.is_err() {
+                            return;
+                        }
+                    }
+                    Message::Close(reason) => {
+                        let _ = session.close(reason).await;
+                        return;
+                    }
+                    _ => {}
+                }
+            }
+            SocketEvent::Client(Err(_)) => break,
+            SocketEvent::Heartbeat => {
+                if last_seen.elapsed() > CLIENT_TIMEOUT {
+                    break;
+                }
+
+                match can_view_thread(thread_id, user, pool).await {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(err) => warn!("Failed to check access to thread socket: {}", err),
+                }
+
+                if session.ping(b"").await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    let _ = session.close(None).await;
+}
+
+async fn can_view_thread(
+    thread_id: database::models::ThreadId,
+    user: &User,
+    pool: &PgPool,
+) -> Result<bool, ApiError> {
+    match database::models::Thread::get(thread_id, pool).await? {
+        Some(thread) => is_authorized_thread(&thread, user, pool).await,
+        None => Ok(false),
+    }
+}
+
+async fn release_socket(active_sockets: &RwLock<ActiveSockets>, user: &User) {
+    let sockets = active_sockets.read().await;
+    sockets.thread_sockets.remove_if_mut(&user.id, |_, count| {
+        *count = count.saturating_sub(1);
+        *count == 0
+    });
+}
@@ -1,10 +1,11 @@
 use std::sync::Arc;
 
+use crate::auth::validate::{check_pat_not_project_restricted, check_pat_project_restriction};
 use crate::auth::{check_is_moderator_from_headers, get_user_from_headers};
 use crate::database;
 use crate::database::models::image_item;
 use crate::database::models::notification_item::NotificationBuilder;
-use crate::database::models::thread_item::ThreadMessageBuilder;
+use crate::database::models::thread_item::{ThreadMessageBuilder, ThreadUpdate};
 use crate::database::redis::RedisPool;
 use crate::file_hosting::FileHost;
 use crate::models::ids::ThreadMessageId;
@@ -12,12 +13,18 @@ use crate::models::images::{Image, ImageContext};
 use crate::models::notifications::NotificationBody;
 use crate::models::pats::Scopes;
 use crate::models::projects::ProjectStatus;
-use crate::models::threads::{MessageBody, Thread, ThreadId, ThreadType};
+use crate::models::threads::{MessageBody, Thread, ThreadId, ThreadType, MAX_MESSAGE_ATTACHMENTS};
 use crate::models::users::User;
 use crate::queue::session::AuthQueue;
+use crate::routes::v3::project_blocks::is_blocked_from_project;
+use crate::routes::v3::projects::check_not_archived;
+use crate::routes::v3::user_blocks::is_blocked_by_project_owner;
 use crate::routes::ApiError;
+use crate::util::mentions::parse_mentions;
+use crate::util::sanitize;
 use actix_web::{web, HttpRequest, HttpResponse};
 use futures::TryStreamExt;
+use itertools::Itertools;
 use serde::Deserialize;
 use sqlx::PgPool;
 
@@ -80,6 +87,41 @@ pub async fn is_authorized_thread(
     })
 }
 
+/// Resolves the users mentioned in a message, keeping only those who can see it. Private
+/// messages can only be seen by moderators.
+async fn get_mentioned_users(
+    thread: &database::models::Thread,
+    body: &str,
+    private: bool,
+    author: &User,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<Vec<database::models::UserId>, ApiError> {
+    let usernames = parse_mentions(body);
+    if usernames.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let users = database::models::User::get_many(&usernames, pool, redis).await?;
+
+    let mut mentioned_users = Vec::new();
+    for user in users {
+        // Users are also fetched by id, so only keep the ones whose username was mentioned
+        if !usernames.contains(&user.username.to_lowercase()) || user.id == author.id.into() {
+            continue;
+        }
+
+        let user = User::from(user);
+        if (private && !user.role.is_mod()) || !is_authorized_thread(thread, &user, pool).await? {
+            continue;
+        }
+
+        mentioned_users.push(user.id.into());
+    }
+
+    Ok(mentioned_users)
+}
+
 pub async fn filter_authorized_threads(
     threads: Vec<database::models::Thread>,
     user: &User,
@@ -194,6 +236,16 @@ pub async fn filter_authorized_threads(
         .map(From::from)
         .collect();
 
+    let message_ids = return_threads
+        .iter()
+        .flat_map(|x| x.messages.iter().map(|x| x.id))
+        .collect::<Vec<_>>();
+    let images: Vec<Image> = image_item::Image::get_many_thread_messages(&message_ids, &***pool)
+        .await?
+        .into_iter()
+        .map(From::from)
+        .collect();
+
     let mut final_threads = Vec::new();
 
     for thread in return_threads {
@@ -214,6 +266,7 @@ pub async fn filter_authorized_threads(
                 .filter(|x| authors.contains(&x.id.into()))
                 .cloned()
                 .collect(),
+            &images,
             user,
         ));
     }
@@ -221,6 +274,19 @@ pub async fn filter_authorized_threads(
     Ok(final_threads)
 }
 
+/// Project restricted PATs can only use threads of the projects they are restricted to
+fn check_pat_thread_restriction(
+    req: &HttpRequest,
+    thread: &database::models::Thread,
+) -> Result<(), ApiError> {
+    match thread.project_id {
+        Some(project_id) => check_pat_project_restriction(req, project_id)?,
+        None => check_pat_not_project_restricted(req)?,
+    }
+
+    Ok(())
+}
+
 pub async fn thread_get(
     req: HttpRequest,
     info: web::Path<(ThreadId,)>,
@@ -243,6 +309,7 @@ pub async fn thread_get(
     .1;
 
     if let Some(mut data) = thread_data {
+        check_pat_thread_restriction(&req, &data)?;
         if is_authorized_thread(&data, &user, &pool).await? {
             let authors = &mut data.members;
 
@@ -260,8 +327,20 @@ pub async fn thread_get(
                 .map(From::from)
                 .collect();
 
-            return Ok(HttpResponse::Ok().json(Thread::from(data, users, &user)));
+            let message_ids = data.messages.iter().map(|x| x.id).collect::<Vec<_>>();
+            let images: Vec<Image> =
+                image_item::Image::get_many_thread_messages(&message_ids, &**pool)
+                    .await?
+                    .into_iter()
+                    .map(From::from)
+                    .collect();
+
+            return Ok(HttpResponse::Ok().json(Thread::from(data, users, &images, &user)));
         }
+
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to view this thread!".to_string(),
+        ));
     }
     Err(ApiError::NotFound)
 }
@@ -325,13 +404,14 @@ pub async fn thread_send_message(
     .1;
 
     let string: database::models::ThreadId = info.into_inner().0.into();
+    let mut new_message = new_message.into_inner();
 
     if let MessageBody::Text {
         body,
         replying_to,
         private,
-        ..
-    } = &new_message.body
+        associated_images,
+    } = &mut new_message.body
     {
         if body.len() > 65536 {
             return Err(ApiError::InvalidInput(
@@ -339,6 +419,20 @@ pub async fn thread_send_message(
             ));
         }
 
+        if associated_images.len() > MAX_MESSAGE_ATTACHMENTS {
+            return Err(ApiError::InvalidInput(format!(
+                "A message can have at most {MAX_MESSAGE_ATTACHMENTS} images!"
+            )));
+        }
+        if associated_images.iter().map(|x| x.0).unique().count() != associated_images.len() {
+            return Err(ApiError::InvalidInput(
+                "An image can only be attached once!".to_string(),
+            ));
+        }
+
+        *body = sanitize::sanitize_body(body, sanitize::is_strict(&req))
+            .map_err(|err| ApiError::InvalidInput(err.to_string()))?;
+
         if *private && !user.role.is_mod() {
             return Err(ApiError::InvalidInput(
                 "You are not allowed to send private messages!".to_string(),
@@ -370,10 +464,28 @@ pub async fn thread_send_message(
     let result = database::models::Thread::get(string, &**pool).await?;
 
     if let Some(thread) = result {
+        check_pat_thread_restriction(&req, &thread)?;
         if !is_authorized_thread(&thread, &user, &pool).await? {
             return Err(ApiError::NotFound);
         }
 
+        if let Some(project_id) = thread.project_id.filter(|_| !user.role.is_mod()) {
+            if let Some(project) =
+                database::models::Project::get_id(project_id, &**pool, &redis).await?
+            {
+                check_not_archived(&project.inner)?;
+            }
+
+            // Blocked users aren't told why they can't post
+            if is_blocked_from_project(project_id, &user, &pool).await?
+                || is_blocked_by_project_owner(project_id, &user, &pool).await?
+            {
+                return Err(ApiError::Forbidden(
+                    "You can't post in this thread.".to_string(),
+                ));
+            }
+        }
+
         let mut transaction = pool.begin().await?;
 
         let id = ThreadMessageBuilder {
@@ -455,6 +567,25 @@ pub async fn thread_send_message(
         .execute(&mut *transaction)
         .await?;
 
+        if let MessageBody::Text { body, private, .. } = &new_message.body {
+            let mentioned_users =
+                get_mentioned_users(&thread, body, *private, &user, &pool, &redis).await?;
+
+            if !mentioned_users.is_empty() {
+                NotificationBuilder {
+                    body: NotificationBody::Mention {
+                        thread_id: thread.id.into(),
+                        message_id: id.into(),
+                        mentioned_by: user.id,
+                        project_id: thread.project_id.map(|x| x.into()),
+                        report_id: thread.report_id.map(|x| x.into()),
+                    },
+                }
+                .insert_many(mentioned_users, &mut transaction, &redis)
+                .await?;
+            }
+        }
+
         if let MessageBody::Text {
             associated_images, ..
         } = &new_message.body
@@ -472,6 +603,12 @@ pub async fn thread_send_message(
                             image_id
                         )));
                     }
+                    if image.owner_id != user.id {
+                        return Err(ApiError::InvalidInput(format!(
+                            "Image {} was uploaded by another user",
+                            image_id
+                        )));
+                    }
 
                     sqlx::query!(
                         "
@@ -479,7 +616,7 @@ pub async fn thread_send_message(
                         SET thread_message_id = $1
                         WHERE id = $2
                         ",
-                        thread.id.0,
+                        id.0,
                         image_id.0 as i64
                     )
                     .execute(&mut *transaction)
@@ -497,6 +634,15 @@ pub async fn thread_send_message(
 
         transaction.commit().await?;
 
+        if let Some(message) = database::models::ThreadMessage::get(id, &**pool).await? {
+            ThreadUpdate::Message {
+                message,
+                author_is_mod: user.role.is_mod(),
+            }
+            .publish(thread.id, &redis)
+            .await;
+        }
+
         Ok(HttpResponse::NoContent().body(""))
     } else {
         Err(ApiError::NotFound)
@@ -549,6 +695,7 @@ pub async fn thread_read(
         Some(&[Scopes::THREAD_READ]),
     )
     .await?;
+    check_pat_not_project_restricted(&req)?;
 
     let id = info.into_inner().0;
     let mut transaction = pool.begin().await?;
@@ -622,6 +769,13 @@ pub async fn message_delete(
         database::models::ThreadMessage::remove_full(thread.id, private, &mut transaction).await?;
         transaction.commit().await?;
 
+        ThreadUpdate::MessageDeleted {
+            id: thread.id,
+            private,
+        }
+        .publish(thread.thread_id, &redis)
+        .await;
+
         Ok(HttpResponse::NoContent().body(""))
     } else {
         Err(ApiError::NotFound)
@@ -0,0 +1,125 @@
+use crate::auth::get_user_from_headers;
+use crate::database::models as db_models;
+use crate::database::models::user_block_item::UserBlock as DBUserBlock;
+use crate::database::redis::RedisPool;
+use crate::models::pats::Scopes;
+use crate::models::user_blocks::{UserBlock, MAX_USER_BLOCKS};
+use crate::models::users::{User, UserId};
+use crate::queue::session::AuthQueue;
+use crate::routes::ApiError;
+use actix_web::{web, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+
+/// Whether an owner of the project blocked the user from posting in its threads. Moderators
+/// can always post, so blocking one doesn't stop them moderating the project.
+pub async fn is_blocked_by_project_owner(
+    project_id: db_models::ProjectId,
+    user: &User,
+    pool: &PgPool,
+) -> Result<bool, ApiError> {
+    if user.role.is_mod() {
+        return Ok(false);
+    }
+
+    Ok(DBUserBlock::is_blocked_by_project_owner(project_id, user.id.into(), pool).await?)
+}
+
+pub async fn user_blocks_get(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_READ]),
+    )
+    .await?
+    .1;
+
+    let blocks = DBUserBlock::get_for_user(user.id.into(), &**pool)
+        .await?
+        .into_iter()
+        .map(UserBlock::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(blocks))
+}
+
+pub async fn user_block_add(
+    req: HttpRequest,
+    info: web::Path<(UserId,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_WRITE]),
+    )
+    .await?
+    .1;
+    let blocked_user_id = info.into_inner().0;
+
+    if blocked_user_id == user.id {
+        return Err(ApiError::InvalidInput(
+            "You can't block yourself".to_string(),
+        ));
+    }
+    db_models::User::get_id(blocked_user_id.into(), &**pool, &redis)
+        .await?
+        .ok_or_else(|| ApiError::InvalidInput("The specified user does not exist!".to_string()))?;
+
+    let mut transaction = pool.begin().await?;
+    if !DBUserBlock::insert(
+        user.id.into(),
+        blocked_user_id.into(),
+        MAX_USER_BLOCKS,
+        &mut transaction,
+    )
+    .await?
+    {
+        return Err(ApiError::InvalidInput(format!(
+            "You can block at most {MAX_USER_BLOCKS} users"
+        )));
+    }
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn user_block_remove(
+    req: HttpRequest,
+    info: web::Path<(UserId,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_WRITE]),
+    )
+    .await?
+    .1;
+    let blocked_user_id = info.into_inner().0;
+
+    let mut transaction = pool.begin().await?;
+    let removed =
+        DBUserBlock::remove(user.id.into(), blocked_user_id.into(), &mut transaction).await?;
+    transaction.commit().await?;
+
+    if removed {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
@@ -0,0 +1,297 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use validator::Validate;
+
+use super::ApiError;
+use crate::auth::get_user_from_headers;
+use crate::database::models::flow_item::Flow;
+use crate::database::models::user_connection_item::UserConnection as DBUserConnection;
+use crate::database::models::{generate_user_connection_id, User};
+use crate::database::redis::RedisPool;
+use crate::models::connections::{ConnectionProvider, UserConnection};
+use crate::models::ids::{UserConnectionId, UserId};
+use crate::models::pats::Scopes;
+use crate::queue::session::AuthQueue;
+use crate::routes::internal::flows::check_callback_url;
+use crate::util::rel_me;
+use crate::util::validate::validation_errors_to_string;
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct NewUserConnection {
+    pub provider: ConnectionProvider,
+    /// For providers verified with a rel="me" link, the page linking back to the profile
+    #[validate(
+        custom(function = "crate::util::rel_me::validate_page_url"),
+        length(max = 2048)
+    )]
+    pub url: Option<String>,
+    /// For providers verified with OAuth, where the user is sent once the account is linked
+    pub redirect_url: Option<String>,
+}
+
+// The username shown for a page. Mastodon profile URLs are shown as handles.
+fn page_username(provider: ConnectionProvider, url: &url::Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    let path = url.path().trim_end_matches('/');
+
+    match path.strip_prefix("/@") {
+        Some(name) if provider == ConnectionProvider::Mastodon && !name.contains('/') => {
+            format!("@{name}@{host}")
+        }
+        _ => format!("{host}{path}"),
+    }
+}
+
+pub async fn user_connections_list(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let current_user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    let user = User::get(&info.into_inner().0, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let can_view_private = current_user
+        .map(|x| x.role.is_mod() || x.id == user.id.into())
+        .unwrap_or(false);
+
+    let connections = user
+        .connections
+        .into_iter()
+        .filter(|x| can_view_private || x.verified)
+        .map(UserConnection::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(connections))
+}
+
+/// Starts linking an account. Accounts on providers with OAuth are linked once the user
+/// authorizes Modrinth at the returned URL. Other accounts are added unverified, and are
+/// verified once their page links back to the user's profile.
+pub async fn user_connection_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    new_connection: web::Json<NewUserConnection>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let current_user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_WRITE]),
+    )
+    .await?
+    .1;
+
+    new_connection
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let user = User::get(&info.into_inner().0, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if current_user.id != user.id.into() {
+        return Err(ApiError::CustomAuthentication(
+            "You do not have permission to link accounts to this user!".to_string(),
+        ));
+    }
+
+    let new_connection = new_connection.into_inner();
+    if let Some(provider) = new_connection.provider.auth_provider() {
+        let redirect_url = new_connection.redirect_url.ok_or_else(|| {
+            ApiError::InvalidInput("A redirect URL is required to link this provider!".to_string())
+        })?;
+        check_callback_url(&redirect_url)?;
+
+        let state = Flow::LinkConnection {
+            user_id: user.id,
+            url: redirect_url,
+            provider,
+        }
+        .insert(Duration::minutes(30), &redis)
+        .await?;
+
+        let url = provider.get_redirect_url(state)?;
+        return Ok(HttpResponse::Ok().json(serde_json::json!({ "url": url })));
+    }
+
+    let page_url = new_connection.url.ok_or_else(|| {
+        ApiError::InvalidInput("The URL of the page to link is required!".to_string())
+    })?;
+    let parsed_url = url::Url::parse(&page_url)
+        .map_err(|_| ApiError::InvalidInput("The URL of the page is invalid!".to_string()))?;
+
+    if DBUserConnection::get_verified_user(new_connection.provider, &page_url, &**pool)
+        .await?
+        .is_some_and(|x| x != user.id)
+    {
+        return Err(ApiError::Conflict(
+            "This account is already linked to another Modrinth user!".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+    let mut connection = DBUserConnection {
+        id: generate_user_connection_id(&mut transaction).await?,
+        user_id: user.id,
+        provider: new_connection.provider,
+        external_id: page_url,
+        username: page_username(new_connection.provider, &parsed_url),
+        verified: false,
+        created: Utc::now(),
+        verified_at: None,
+    };
+    connection.id = connection.insert(&mut transaction).await?;
+    transaction.commit().await?;
+
+    User::clear_caches(&[(user.id, None)], &redis).await?;
+
+    let connection = DBUserConnection::get(connection.id, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok().json(UserConnection::from(connection)))
+}
+
+/// Verifies an account by checking that its page has a rel="me" link to the user's profile
+pub async fn user_connection_verify(
+    req: HttpRequest,
+    info: web::Path<(String, UserConnectionId)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let current_user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_WRITE]),
+    )
+    .await?
+    .1;
+
+    let (user_id, connection_id) = info.into_inner();
+    let user = User::get(&user_id, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if current_user.id != user.id.into() {
+        return Err(ApiError::CustomAuthentication(
+            "You do not have permission to verify this user's accounts!".to_string(),
+        ));
+    }
+
+    let connection = DBUserConnection::get(connection_id.into(), &**pool)
+        .await?
+        .filter(|x| x.user_id == user.id)
+        .ok_or(ApiError::NotFound)?;
+
+    if connection.provider.auth_provider().is_some() {
+        return Err(ApiError::InvalidInput(
+            "This account is verified when it is linked!".to_string(),
+        ));
+    }
+
+    if !connection.verified {
+        if DBUserConnection::get_verified_user(
+            connection.provider,
+            &connection.external_id,
+            &**pool,
+        )
+        .await?
+        .is_some()
+        {
+            return Err(ApiError::Conflict(
+                "This account is already linked to another Modrinth user!".to_string(),
+            ));
+        }
+
+        let site_url = dotenvy::var("SITE_URL")?;
+        let profile_urls = [
+            format!("{site_url}/user/{}", user.username),
+            format!("{site_url}/user/{}", UserId::from(user.id)),
+        ];
+
+        let links = rel_me::fetch_rel_me_links(&connection.external_id)
+            .await
+            .map_err(|err| ApiError::InvalidInput(err.to_string()))?;
+        if !links.iter().any(|x| rel_me::link_matches(x, &profile_urls)) {
+            return Err(ApiError::InvalidInput(format!(
+                "The page has no rel=\"me\" link to {}!",
+                profile_urls[0]
+            )));
+        }
+
+        let mut transaction = pool.begin().await?;
+        DBUserConnection::verify(connection.id, &mut transaction).await?;
+        transaction.commit().await?;
+
+        User::clear_caches(&[(user.id, None)], &redis).await?;
+    }
+
+    let connection = DBUserConnection::get(connection.id, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok().json(UserConnection::from(connection)))
+}
+
+pub async fn user_connection_delete(
+    req: HttpRequest,
+    info: web::Path<(String, UserConnectionId)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let current_user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_WRITE]),
+    )
+    .await?
+    .1;
+
+    let (user_id, connection_id) = info.into_inner();
+    let user = User::get(&user_id, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if !current_user.role.is_mod() && current_user.id != user.id.into() {
+        return Err(ApiError::CustomAuthentication(
+            "You do not have permission to remove this user's accounts!".to_string(),
+        ));
+    }
+
+    let connection = DBUserConnection::get(connection_id.into(), &**pool)
+        .await?
+        .filter(|x| x.user_id == user.id)
+        .ok_or(ApiError::NotFound)?;
+
+    let mut transaction = pool.begin().await?;
+    DBUserConnection::remove(connection.id, &mut transaction).await?;
+    transaction.commit().await?;
+
+    User::clear_caches(&[(user.id, None)], &redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
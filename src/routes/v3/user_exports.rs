@@ -0,0 +1,465 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use log::warn;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use super::ApiError;
+use crate::auth::{filter_visible_projects, get_user_from_headers};
+use crate::database;
+use crate::database::models::flow_item::Flow;
+use crate::database::models::notification_item::NotificationBuilder;
+use crate::database::models::oauth_client_authorization_item::OAuthClientAuthorization as DBOAuthClientAuthorization;
+use crate::database::models::pat_item::PersonalAccessToken as DBPersonalAccessToken;
+use crate::database::models::user_export_item::UserExport as DBUserExport;
+use crate::database::models::{ids as db_ids, User};
+use crate::database::redis::RedisPool;
+use crate::file_hosting::{FileHost, FileHostingError};
+use crate::models::collections::Collection;
+use crate::models::exports::{UserExport, UserExportStatus};
+use crate::models::ids::UserId;
+use crate::models::notifications::{Notification, NotificationBody};
+use crate::models::oauth_clients::OAuthClientAuthorization;
+use crate::models::pats::{PersonalAccessToken, Scopes};
+use crate::models::projects::{Project, Version};
+use crate::models::reports::Report;
+use crate::models::threads::ThreadMessage;
+use crate::queue::session::AuthQueue;
+use crate::util::signed_urls::PRIVATE_PREFIX;
+
+/// How long the download link of an export works for. The archive is removed from the file
+/// host once it stops working.
+pub const EXPORT_EXPIRY_HOURS: i64 = 24;
+
+pub async fn user_export_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_READ, Scopes::USER_READ_EMAIL]),
+    )
+    .await?
+    .1;
+
+    let id = User::get(&info.into_inner().0, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?
+        .id;
+
+    if !user.role.is_admin() && user.id != id.into() {
+        return Err(ApiError::CustomAuthentication(
+            "You do not have permission to export the data of this user!".to_string(),
+        ));
+    }
+
+    let mut transaction = pool.begin().await?;
+    DBUserExport::lock_user(id, &mut transaction).await?;
+    if let Some(latest) = DBUserExport::get_latest_not_failed(id, &mut *transaction).await? {
+        if latest.created > Utc::now() - Duration::days(1) {
+            return Err(ApiError::InvalidInput(
+                "A data export can only be requested once per day!".to_string(),
+            ));
+        }
+    }
+
+    let export_id = db_ids::generate_user_export_id(&mut transaction).await?;
+    DBUserExport::insert(export_id, id, &mut transaction).await?;
+    transaction.commit().await?;
+
+    let export = DBUserExport::get(export_id, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let pool = pool.into_inner();
+    let redis = redis.into_inner();
+    let file_host = file_host.get_ref().clone();
+    actix_rt::spawn(async move {
+        run_export(export_id, id, &pool, &redis, &*file_host).await;
+    });
+
+    Ok(HttpResponse::Accepted().json(UserExport::from(export)))
+}
+
+pub async fn user_export_status(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_READ]),
+    )
+    .await?
+    .1;
+
+    let id = User::get(&info.into_inner().0, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?
+        .id;
+
+    if !user.role.is_admin() && user.id != id.into() {
+        return Err(ApiError::CustomAuthentication(
+            "You do not have permission to see the data exports of this user!".to_string(),
+        ));
+    }
+
+    let export = DBUserExport::get_latest(id, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(HttpResponse::Ok().json(UserExport::from(export)))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportDownloadQuery {
+    pub token: String,
+}
+
+/// Serves the archive of an export, which is kept under the private prefix so it can't be
+/// fetched from the CDN. The link is sent to the user in a notification, so the token in it is
+/// checked instead of the user's authorization.
+pub async fn user_export_download(
+    info: web::Path<(String,)>,
+    web::Query(query): web::Query<ExportDownloadQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
+) -> Result<HttpResponse, ApiError> {
+    let id = User::get(&info.into_inner().0, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?
+        .id;
+
+    let Some(Flow::DataExport { user_id, export_id }) = Flow::get(&query.token, &redis).await?
+    else {
+        return Err(ApiError::NotFound);
+    };
+
+    if user_id != id {
+        return Err(ApiError::NotFound);
+    }
+
+    let export = DBUserExport::get(export_id, &**pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let file_name = match (export.status, export.file_name) {
+        (UserExportStatus::Complete, Some(file_name)) => file_name,
+        _ => return Err(ApiError::NotFound),
+    };
+
+    let bytes = file_host
+        .download_file(&file_name)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"export-{}.zip\"", UserId::from(id)),
+        ))
+        .insert_header((header::CACHE_CONTROL, "private, no-store"))
+        .body(bytes))
+}
+
+async fn run_export(
+    export_id: db_ids::UserExportId,
+    user_id: db_ids::UserId,
+    pool: &PgPool,
+    redis: &RedisPool,
+    file_host: &(dyn FileHost + Send + Sync),
+) {
+    let result = async {
+        DBUserExport::set_status(export_id, UserExportStatus::Processing, None, pool).await?;
+
+        let archive = build_archive(user_id, pool, redis).await?;
+
+        let file_name = format!(
+            "{PRIVATE_PREFIX}data/exports/{}.zip",
+            ChaCha20Rng::from_entropy()
+                .sample_iter(&Alphanumeric)
+                .take(32)
+                .map(char::from)
+                .collect::<String>()
+        );
+        file_host
+            .upload_file("application/zip", &file_name, archive.into())
+            .await?;
+
+        DBUserExport::set_status(
+            export_id,
+            UserExportStatus::Complete,
+            Some(&file_name),
+            pool,
+        )
+        .await?;
+
+        let expires = Utc::now() + Duration::hours(EXPORT_EXPIRY_HOURS);
+        let token = Flow::DataExport { user_id, export_id }
+            .insert(Duration::hours(EXPORT_EXPIRY_HOURS), redis)
+            .await?;
+        let url = format!(
+            "{}/v3/user/{}/export/download?token={}",
+            dotenvy::var("SELF_ADDR")?,
+            UserId::from(user_id),
+            token
+        );
+
+        let mut transaction = pool.begin().await?;
+        NotificationBuilder {
+            body: NotificationBody::DataExportReady {
+                export_id: export_id.into(),
+                url,
+                expires,
+            },
+        }
+        .insert(user_id, &mut transaction, redis)
+        .await?;
+        transaction.commit().await?;
+
+        Ok::<(), ApiError>(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        warn!("Exporting the data of user {} failed: {:?}", user_id.0, e);
+
+        if let Err(e) =
+            DBUserExport::set_status(export_id, UserExportStatus::Failed, None, pool).await
+        {
+            warn!("Marking a data export as failed failed: {:?}", e);
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+enum ExportRecord {
+    Profile(crate::models::users::User),
+    Project(Project),
+    Version(Version),
+    Collection(Collection),
+    Follow(Project),
+    Notification(Notification),
+    ThreadMessage(ThreadMessage),
+    Report(Report),
+    PersonalAccessToken(PersonalAccessToken),
+    #[serde(rename = "oauth_authorization")]
+    OAuthAuthorization(OAuthClientAuthorization),
+}
+
+/// Collects all of a user's personal data into a zip archive holding a single JSON lines file,
+/// one record per line. Secrets such as access tokens and password hashes are left out.
+pub async fn build_archive(
+    user_id: db_ids::UserId,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<Vec<u8>, ApiError> {
+    let mut records = Vec::new();
+
+    let db_user = User::get_id(user_id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let viewer = Some(crate::models::users::User::from(db_user.clone()));
+    let mut profile = crate::models::users::User::from(db_user.clone());
+    profile.email = db_user.email;
+    profile.email_verified = Some(db_user.email_verified);
+    profile.has_password = Some(db_user.password.is_some());
+    profile.has_totp = Some(db_user.totp_secret.is_some());
+    profile.hide_mature_content = Some(db_user.hide_mature_content);
+    records.push(ExportRecord::Profile(profile));
+
+    let project_ids = sqlx::query!(
+        "
+        SELECT m.id FROM mods m
+        INNER JOIN team_members tm ON tm.team_id = m.team_id
+        WHERE tm.user_id = $1 AND tm.is_owner = TRUE AND tm.accepted = TRUE
+        ",
+        user_id as db_ids::UserId,
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| db_ids::ProjectId(x.id))
+    .collect::<Vec<_>>();
+    records.extend(
+        database::models::Project::get_many_ids(&project_ids, pool, redis)
+            .await?
+            .into_iter()
+            .map(|x| ExportRecord::Project(x.into())),
+    );
+
+    let version_ids = sqlx::query!(
+        "
+        SELECT id FROM versions
+        WHERE author_id = $1
+        ",
+        user_id as db_ids::UserId,
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| db_ids::VersionId(x.id))
+    .collect::<Vec<_>>();
+    records.extend(
+        database::models::Version::get_many(&version_ids, pool, redis)
+            .await?
+            .into_iter()
+            .map(|x| ExportRecord::Version(x.into())),
+    );
+
+    let collection_ids = User::get_collections(user_id, pool).await?;
+    records.extend(
+        database::models::Collection::get_many(&collection_ids, pool, redis)
+            .await?
+            .into_iter()
+            .map(|x| ExportRecord::Collection(x.into())),
+    );
+
+    let followed_ids = sqlx::query!(
+        "
+        SELECT mod_id FROM mod_follows
+        WHERE follower_id = $1
+        ",
+        user_id as db_ids::UserId,
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| db_ids::ProjectId(x.mod_id))
+    .collect::<Vec<_>>();
+    // Followed projects may have been hidden since they were followed
+    let followed_projects =
+        database::models::Project::get_many_ids(&followed_ids, pool, redis).await?;
+    records.extend(
+        filter_visible_projects(followed_projects, &viewer, &web::Data::new(pool.clone()))
+            .await?
+            .into_iter()
+            .map(ExportRecord::Follow),
+    );
+
+    records.extend(
+        database::models::notification_item::Notification::get_many_user(user_id, pool, redis)
+            .await?
+            .into_iter()
+            .map(|x| ExportRecord::Notification(x.into())),
+    );
+
+    let message_ids = sqlx::query!(
+        "
+        SELECT id FROM threads_messages
+        WHERE author_id = $1
+        ",
+        user_id as db_ids::UserId,
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| db_ids::ThreadMessageId(x.id))
+    .collect::<Vec<_>>();
+    records.extend(
+        database::models::ThreadMessage::get_many(&message_ids, pool)
+            .await?
+            .into_iter()
+            .map(|x| {
+                ExportRecord::ThreadMessage(ThreadMessage {
+                    id: x.id.into(),
+                    author_id: x.author_id.map(|x| x.into()),
+                    body: x.body,
+                    created: x.created,
+                    attachments: Vec::new(),
+                })
+            }),
+    );
+
+    let report_ids = sqlx::query!(
+        "
+        SELECT id FROM reports
+        WHERE reporter = $1
+        ",
+        user_id as db_ids::UserId,
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| db_ids::ReportId(x.id))
+    .collect::<Vec<_>>();
+    records.extend(
+        database::models::report_item::Report::get_many(&report_ids, pool)
+            .await?
+            .into_iter()
+            .map(|x| ExportRecord::Report(x.into())),
+    );
+
+    let pat_ids = DBPersonalAccessToken::get_user_pats(user_id, pool, redis).await?;
+    records.extend(
+        DBPersonalAccessToken::get_many_ids(&pat_ids, pool, redis)
+            .await?
+            .into_iter()
+            .map(|x| ExportRecord::PersonalAccessToken(PersonalAccessToken::from(x, false))),
+    );
+
+    records.extend(
+        DBOAuthClientAuthorization::get_all_for_user(user_id, pool)
+            .await?
+            .into_iter()
+            .map(|x| ExportRecord::OAuthAuthorization(x.into())),
+    );
+
+    let mut lines = Vec::new();
+    for record in records {
+        serde_json::to_writer(&mut lines, &record)?;
+        lines.push(b'\n');
+    }
+
+    Ok(zip_archive(&lines).map_err(|e| FileHostingError::FileSystemError(e.into()))?)
+}
+
+fn zip_archive(lines: &[u8]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file("data.jsonl", zip::write::FileOptions::default())?;
+    zip.write_all(lines)?;
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Removes the archives of exports whose download links have expired
+pub async fn purge_expired_exports(
+    pool: &PgPool,
+    file_host: &(dyn FileHost + Send + Sync),
+) -> Result<(), ApiError> {
+    let exports =
+        DBUserExport::get_completed_before(Utc::now() - Duration::hours(EXPORT_EXPIRY_HOURS), pool)
+            .await?;
+
+    for export in exports {
+        if let Some(file_name) = &export.file_name {
+            file_host.delete_file_version("", file_name).await?;
+        }
+
+        DBUserExport::set_status(export.id, UserExportStatus::Expired, None, pool).await?;
+    }
+
+    Ok(())
+}
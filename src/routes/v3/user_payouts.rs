@@ -0,0 +1,206 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use validator::Validate;
+
+use super::ApiError;
+use crate::auth::get_user_from_headers;
+use crate::database::models::payout_item::{Payout as DBPayout, PayoutStatementRow};
+use crate::database::models::{ids as db_ids, User};
+use crate::database::redis::RedisPool;
+use crate::models::ids::PayoutId;
+use crate::models::pats::Scopes;
+use crate::models::payouts::{PayoutOutcome, PayoutRecord, PayoutStatus, PAYOUT_CURRENCY};
+use crate::queue::session::AuthQueue;
+use crate::util::csv;
+use crate::util::validate::validation_errors_to_string;
+
+#[derive(Deserialize, Validate)]
+pub struct PayoutHistoryQuery {
+    /// Only payouts created at or after this time
+    pub start: Option<DateTime<Utc>>,
+    /// Only payouts created before this time
+    pub end: Option<DateTime<Utc>>,
+    #[serde(default = "default_count")]
+    #[validate(range(min = 1, max = 1000))]
+    pub count: i16,
+    #[serde(default)]
+    #[validate(range(min = 0))]
+    pub offset: i64,
+}
+
+fn default_count() -> i16 {
+    100
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PayoutHistory {
+    /// The total number of the user's payouts in the range
+    pub total: i64,
+    pub payouts: Vec<PayoutRecord>,
+}
+
+#[derive(Deserialize)]
+pub struct PayoutStatementQuery {
+    pub year: i32,
+}
+
+/// The columns of payout statements, in order:
+/// - `kind`: `payout`, or `month_total` for the row totalling a month, which follows its payouts
+/// - `month`: the month the payout was requested in, as `YYYY-MM` in UTC
+/// - `id`: the payout's id, empty for totals
+/// - `initiated`: when the payout was requested, in RFC 3339
+/// - `completed`: when the payout was paid, failed or was cancelled, in RFC 3339
+/// - `status`: the payout's status, empty for totals
+/// - `outcome`: `paid`, `pending` or `failed`, empty for totals
+/// - `method`: how the payout was sent
+/// - `amount`: the amount sent, or for totals the amount of the month's paid payouts
+/// - `fee`: the fee taken, or for totals the fees of the month's paid payouts
+/// - `currency`: the currency of the amount and fee
+const STATEMENT_COLUMNS: &[&str] = &[
+    "kind",
+    "month",
+    "id",
+    "initiated",
+    "completed",
+    "status",
+    "outcome",
+    "method",
+    "amount",
+    "fee",
+    "currency",
+];
+
+fn statement_record(row: PayoutStatementRow) -> Vec<String> {
+    let status = row.status.as_deref().map(PayoutStatus::from_string);
+
+    vec![
+        row.kind,
+        row.month,
+        row.id
+            .map(|x| PayoutId::from(x).to_string())
+            .unwrap_or_default(),
+        row.created.map(|x| x.to_rfc3339()).unwrap_or_default(),
+        row.completed.map(|x| x.to_rfc3339()).unwrap_or_default(),
+        status.map(|x| x.as_str().to_string()).unwrap_or_default(),
+        status
+            .map(|x| PayoutOutcome::from(x).as_str().to_string())
+            .unwrap_or_default(),
+        row.method.unwrap_or_default(),
+        row.amount.normalize().to_string(),
+        row.fee
+            .map(|x| x.normalize().to_string())
+            .unwrap_or_default(),
+        PAYOUT_CURRENCY.to_string(),
+    ]
+}
+
+/// Gets the user whose payouts are requested, who has to be the requesting user unless they're
+/// an admin
+async fn get_payout_user(
+    req: &HttpRequest,
+    id: &str,
+    pool: &PgPool,
+    redis: &RedisPool,
+    session_queue: &AuthQueue,
+) -> Result<db_ids::UserId, ApiError> {
+    let user = get_user_from_headers(
+        req,
+        pool,
+        redis,
+        session_queue,
+        Some(&[Scopes::PAYOUTS_READ]),
+    )
+    .await?
+    .1;
+
+    let id = User::get(id, pool, redis)
+        .await?
+        .ok_or(ApiError::NotFound)?
+        .id;
+
+    if !user.role.is_admin() && user.id != id.into() {
+        return Err(ApiError::CustomAuthentication(
+            "You do not have permission to see the payouts of this user!".to_string(),
+        ));
+    }
+
+    Ok(id)
+}
+
+pub async fn user_payout_history(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    query: web::Query<PayoutHistoryQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let id = get_payout_user(&req, &info.into_inner().0, &pool, &redis, &session_queue).await?;
+
+    query
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    if let (Some(start), Some(end)) = (query.start, query.end) {
+        if start > end {
+            return Err(ApiError::InvalidInput(
+                "The start of the range must be before its end!".to_string(),
+            ));
+        }
+    }
+
+    let (total, payout_ids) = DBPayout::get_user_page(
+        id,
+        query.start,
+        query.end,
+        query.count as i64,
+        query.offset,
+        &**pool,
+    )
+    .await?;
+
+    let mut payouts = DBPayout::get_many(&payout_ids, &**pool).await?;
+    payouts.sort_by_key(|x| payout_ids.iter().position(|id| *id == x.id));
+
+    Ok(HttpResponse::Ok().json(PayoutHistory {
+        total,
+        payouts: payouts.into_iter().map(PayoutRecord::from).collect(),
+    }))
+}
+
+/// Streams a CSV statement of the user's payouts in the year, with the totals of each month
+pub async fn user_payout_statement(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    query: web::Query<PayoutStatementQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let id = get_payout_user(&req, &info.into_inner().0, &pool, &redis, &session_queue).await?;
+
+    let year_start = |year: i32| {
+        NaiveDate::from_ymd_opt(year, 1, 1)
+            .and_then(|x| x.and_hms_opt(0, 0, 0))
+            .map(|x| Utc.from_utc_datetime(&x))
+    };
+    let (Some(start), Some(end)) = (
+        year_start(query.year),
+        query.year.checked_add(1).and_then(year_start),
+    ) else {
+        return Err(ApiError::InvalidInput("Invalid year!".to_string()));
+    };
+
+    let mut transaction = pool.begin().await?;
+    DBPayout::declare_statement_export(csv::EXPORT_CURSOR, id, start, end, &mut transaction)
+        .await?;
+
+    Ok(csv::cursor_response(
+        &format!("payouts-{}.csv", query.year),
+        STATEMENT_COLUMNS,
+        transaction,
+        statement_record,
+    ))
+}
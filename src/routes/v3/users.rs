@@ -1,6 +1,7 @@
 use std::{collections::HashMap, sync::Arc};
 
 use actix_web::{web, HttpRequest, HttpResponse};
+use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
@@ -8,22 +9,43 @@ use sqlx::PgPool;
 use validator::Validate;
 
 use crate::{
-    auth::{filter_visible_projects, get_user_from_headers},
-    database::{models::User, redis::RedisPool},
+    auth::{filter_visible_collections, filter_visible_projects, get_user_from_headers},
+    database::{
+        models::{
+            notification_preference_item::NotificationPreference as NotificationPreferenceItem,
+            reserved_names::get_name_holder, user_item::validate_payout_method, User,
+        },
+        redis::RedisPool,
+    },
     file_hosting::FileHost,
     models::{
         collections::{Collection, CollectionStatus},
         ids::UserId,
-        notifications::Notification,
+        notifications::{
+            EditNotificationPreference, Notification, NotificationPreference, NotificationType,
+        },
         pats::Scopes,
+        payouts::UserPayoutMethod,
         projects::Project,
         users::{Badges, Role},
     },
     queue::session::AuthQueue,
+    search::{indexing::reindex_users, SearchConfig},
     util::{routes::read_from_payload, validate::validation_errors_to_string},
 };
 
-use super::{oauth_clients::get_user_clients, ApiError};
+use super::{
+    oauth_clients::get_user_clients,
+    recommendations::user_recommendations,
+    user_blocks::{user_block_add, user_block_remove, user_blocks_get},
+    user_connections::{
+        user_connection_create, user_connection_delete, user_connection_verify,
+        user_connections_list,
+    },
+    user_exports::{user_export_create, user_export_download, user_export_status},
+    user_payouts::{user_payout_history, user_payout_statement},
+    ApiError,
+};
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.route("user", web::get().to(user_auth_get));
@@ -31,6 +53,10 @@ pub fn config(cfg: &mut web::ServiceConfig) {
 
     cfg.service(
         web::scope("user")
+            .route("recommendations", web::get().to(user_recommendations))
+            .route("blocks", web::get().to(user_blocks_get))
+            .route("blocks/{user_id}", web::put().to(user_block_add))
+            .route("blocks/{user_id}", web::delete().to(user_block_remove))
             .route("{user_id}/projects", web::get().to(projects_list))
             .route("{id}", web::get().to(user_get))
             .route("{user_id}/collections", web::get().to(collections_list))
@@ -38,9 +64,40 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("{id}", web::patch().to(user_edit))
             .route("{id}/icon", web::patch().to(user_icon_edit))
             .route("{id}", web::delete().to(user_delete))
+            .route("{id}/limits", web::patch().to(user_limits_edit))
             .route("{id}/follows", web::get().to(user_follows))
             .route("{id}/notifications", web::get().to(user_notifications))
-            .route("{id}/oauth_apps", web::get().to(get_user_clients)),
+            .route(
+                "{id}/notification-preferences",
+                web::get().to(user_notification_preferences),
+            )
+            .route(
+                "{id}/notification-preferences",
+                web::patch().to(user_notification_preferences_edit),
+            )
+            .route(
+                "{id}/payout-method",
+                web::patch().to(user_payout_method_edit),
+            )
+            .route("{id}/payouts", web::get().to(user_payout_history))
+            .route(
+                "{id}/payouts/statement",
+                web::get().to(user_payout_statement),
+            )
+            .route("{id}/oauth_apps", web::get().to(get_user_clients))
+            .route("{id}/export", web::post().to(user_export_create))
+            .route("{id}/export/status", web::get().to(user_export_status))
+            .route("{id}/export/download", web::get().to(user_export_download))
+            .route("{id}/connections", web::get().to(user_connections_list))
+            .route("{id}/connections", web::post().to(user_connection_create))
+            .route(
+                "{id}/connections/{connection_id}/verify",
+                web::post().to(user_connection_verify),
+            )
+            .route(
+                "{id}/connections/{connection_id}",
+                web::delete().to(user_connection_delete),
+            ),
     );
 }
 
@@ -169,7 +226,10 @@ pub async fn collections_list(
             crate::database::models::Collection::get_many(&project_data, &**pool, &redis)
                 .await?
                 .into_iter()
-                .filter(|x| can_view_private || matches!(x.status, CollectionStatus::Listed))
+                .filter(|x| {
+                    can_view_private
+                        || matches!(x.status, CollectionStatus::Listed) && x.visibility.is_listed()
+                })
                 .map(Collection::from)
                 .collect();
 
@@ -287,6 +347,9 @@ pub struct EditUser {
     pub badges: Option<Badges>,
     #[validate(length(max = 160))]
     pub venmo_handle: Option<String>,
+    pub hide_mature_content: Option<bool>,
+    /// Banned users are left out of search. Only moderators can ban users.
+    pub banned: Option<bool>,
 }
 
 pub async fn user_edit(
@@ -296,6 +359,7 @@ pub async fn user_edit(
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
+    search_config: web::Data<SearchConfig>,
 ) -> Result<HttpResponse, ApiError> {
     let (scopes, user) = get_user_from_headers(
         &req,
@@ -320,29 +384,24 @@ pub async fn user_edit(
             let mut transaction = pool.begin().await?;
 
             if let Some(username) = &new_user.username {
-                let existing_user_id_option = User::get(username, &**pool, &redis).await?;
-
-                if existing_user_id_option
-                    .map(|x| UserId::from(x.id))
-                    .map(|id| id == user.id)
-                    .unwrap_or(true)
+                // Usernames share a namespace with organization slugs
+                if let Some(holder) =
+                    get_name_holder(username, Some(id), None, &mut *transaction).await?
                 {
-                    sqlx::query!(
-                        "
-                        UPDATE users
-                        SET username = $1
-                        WHERE (id = $2)
-                        ",
-                        username,
-                        id as crate::database::models::ids::UserId,
-                    )
-                    .execute(&mut *transaction)
-                    .await?;
-                } else {
-                    return Err(ApiError::InvalidInput(format!(
-                        "Username {username} is taken!"
-                    )));
+                    return Err(ApiError::Conflict(holder.conflict_message(username)));
                 }
+
+                sqlx::query!(
+                    "
+                    UPDATE users
+                    SET username = $1
+                    WHERE (id = $2)
+                    ",
+                    username,
+                    id as crate::database::models::ids::UserId,
+                )
+                .execute(&mut *transaction)
+                .await?;
             }
 
             if let Some(name) = &new_user.name {
@@ -373,6 +432,20 @@ pub async fn user_edit(
                 .await?;
             }
 
+            if let Some(hide_mature_content) = new_user.hide_mature_content {
+                sqlx::query!(
+                    "
+                    UPDATE users
+                    SET hide_mature_content = $1
+                    WHERE (id = $2)
+                    ",
+                    hide_mature_content,
+                    id as crate::database::models::ids::UserId,
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+
             if let Some(role) = &new_user.role {
                 if !user.role.is_admin() {
                     return Err(ApiError::CustomAuthentication(
@@ -417,6 +490,26 @@ pub async fn user_edit(
                 .await?;
             }
 
+            if let Some(banned) = new_user.banned {
+                if !user.role.is_mod() {
+                    return Err(ApiError::CustomAuthentication(
+                        "You do not have the permissions to ban this user!".to_string(),
+                    ));
+                }
+
+                sqlx::query!(
+                    "
+                    UPDATE users
+                    SET banned = $1
+                    WHERE (id = $2)
+                    ",
+                    banned,
+                    id as crate::database::models::ids::UserId,
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+
             if let Some(venmo_handle) = &new_user.venmo_handle {
                 if !scopes.contains(Scopes::PAYOUTS_WRITE) {
                     return Err(ApiError::CustomAuthentication(
@@ -439,7 +532,19 @@ pub async fn user_edit(
             }
 
             transaction.commit().await?;
-            User::clear_caches(&[(id, Some(actual_user.username))], &redis).await?;
+            User::clear_caches(
+                &[
+                    (id, Some(actual_user.username)),
+                    (id, new_user.username.clone()),
+                ],
+                &redis,
+            )
+            .await?;
+
+            if new_user.username.is_some() || new_user.bio.is_some() || new_user.banned.is_some() {
+                reindex_user(id, &pool, &search_config).await;
+            }
+
             Ok(HttpResponse::NoContent().body(""))
         } else {
             Err(ApiError::CustomAuthentication(
@@ -451,6 +556,18 @@ pub async fn user_edit(
     }
 }
 
+// Brings the user's search document up to date, removing it if they were banned or deleted.
+// A failure only leaves search out of date until the next full reindex.
+async fn reindex_user(
+    id: crate::database::models::ids::UserId,
+    pool: &PgPool,
+    config: &SearchConfig,
+) {
+    if let Err(e) = reindex_users(&[id], pool, config).await {
+        log::warn!("Failed to reindex user {:?}: {}", id, e);
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Extension {
     pub ext: String,
@@ -466,6 +583,7 @@ pub async fn user_icon_edit(
     file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
     mut payload: web::Payload,
     session_queue: web::Data<AuthQueue>,
+    search_config: web::Data<SearchConfig>,
 ) -> Result<HttpResponse, ApiError> {
     if let Some(content_type) = crate::util::ext::get_image_content_type(&ext.ext) {
         let cdn_url = dotenvy::var("CDN_URL")?;
@@ -522,6 +640,7 @@ pub async fn user_icon_edit(
             .execute(&**pool)
             .await?;
             User::clear_caches(&[(actual_user.id, None)], &redis).await?;
+            reindex_user(actual_user.id, &pool, &search_config).await;
 
             Ok(HttpResponse::NoContent().body(""))
         } else {
@@ -535,12 +654,62 @@ pub async fn user_icon_edit(
     }
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct EditLimits {
+    /// The most projects that can be created, or null to use the default limit again
+    pub project_limit: Option<u32>,
+}
+
+/// Overrides how many projects a user can own. Only admins can change limits.
+pub async fn user_limits_edit(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    new_limits: web::Json<EditLimits>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::USER_WRITE]),
+    )
+    .await?
+    .1;
+
+    if !user.role.is_admin() {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to edit the limits of this user!".to_string(),
+        ));
+    }
+
+    let target = User::get(&info.into_inner().0, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let mut transaction = pool.begin().await?;
+    User::set_project_limit(
+        target.id,
+        new_limits
+            .project_limit
+            .map(|x| x.min(i32::MAX as u32) as i32),
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
 pub async fn user_delete(
     req: HttpRequest,
     info: web::Path<(String,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
     session_queue: web::Data<AuthQueue>,
+    search_config: web::Data<SearchConfig>,
 ) -> Result<HttpResponse, ApiError> {
     let user = get_user_from_headers(
         &req,
@@ -567,6 +736,7 @@ pub async fn user_delete(
         transaction.commit().await?;
 
         if result.is_some() {
+            reindex_user(id, &pool, &search_config).await;
             Ok(HttpResponse::NoContent().body(""))
         } else {
             Err(ApiError::NotFound)
@@ -576,6 +746,12 @@ pub async fn user_delete(
     }
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct UserFollows {
+    pub projects: Vec<Project>,
+    pub collections: Vec<Collection>,
+}
+
 pub async fn user_follows(
     req: HttpRequest,
     info: web::Path<(String,)>,
@@ -618,14 +794,22 @@ pub async fn user_follows(
         .try_collect::<Vec<crate::database::models::ProjectId>>()
         .await?;
 
-        let projects: Vec<_> =
-            crate::database::Project::get_many_ids(&project_ids, &**pool, &redis)
-                .await?
-                .into_iter()
-                .map(Project::from)
-                .collect();
-
-        Ok(HttpResponse::Ok().json(projects))
+        let projects =
+            crate::database::Project::get_many_ids(&project_ids, &**pool, &redis).await?;
+        // Projects hidden after being followed are left out
+        let projects = filter_visible_projects(projects, &Some(user.clone()), &pool).await?;
+
+        let collection_ids =
+            crate::database::models::Collection::get_user_follows(id, &**pool).await?;
+        let collections =
+            crate::database::models::Collection::get_many(&collection_ids, &**pool, &redis).await?;
+        // Collections made private after being followed are left out
+        let collections = filter_visible_collections(collections, &Some(user)).await?;
+
+        Ok(HttpResponse::Ok().json(UserFollows {
+            projects,
+            collections,
+        }))
     } else {
         Err(ApiError::NotFound)
     }
@@ -671,3 +855,168 @@ pub async fn user_notifications(
         Err(ApiError::NotFound)
     }
 }
+
+pub async fn user_notification_preferences(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::NOTIFICATION_READ]),
+    )
+    .await?
+    .1;
+    let id_option = User::get(&info.into_inner().0, &**pool, &redis).await?;
+
+    if let Some(id) = id_option.map(|x| x.id) {
+        if !user.role.is_admin() && user.id != id.into() {
+            return Err(ApiError::CustomAuthentication(
+                "You do not have permission to see the notification preferences of this user!"
+                    .to_string(),
+            ));
+        }
+
+        let stored_preferences = NotificationPreferenceItem::get_user(id, &**pool).await?;
+
+        // Types without a stored preference are enabled
+        let preferences = NotificationType::iterator()
+            .map(|notification_type| {
+                let stored = stored_preferences
+                    .iter()
+                    .find(|x| x.notification_type == notification_type.as_str());
+
+                NotificationPreference {
+                    notification_type,
+                    in_app: stored.map(|x| x.in_app).unwrap_or(true),
+                    send_email: notification_type.sends_email()
+                        && stored.and_then(|x| x.send_email).unwrap_or(true),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Ok(HttpResponse::Ok().json(preferences))
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
+pub async fn user_notification_preferences_edit(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    new_preferences: web::Json<Vec<EditNotificationPreference>>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::NOTIFICATION_WRITE]),
+    )
+    .await?
+    .1;
+    let id_option = User::get(&info.into_inner().0, &**pool, &redis).await?;
+
+    if let Some(id) = id_option.map(|x| x.id) {
+        if !user.role.is_admin() && user.id != id.into() {
+            return Err(ApiError::CustomAuthentication(
+                "You do not have permission to edit the notification preferences of this user!"
+                    .to_string(),
+            ));
+        }
+
+        if let Some(preference) = new_preferences
+            .iter()
+            .find(|x| x.send_email == Some(true) && !x.notification_type.sends_email())
+        {
+            return Err(ApiError::InvalidInput(format!(
+                "Notifications of type {} can't be delivered by email!",
+                preference.notification_type.as_str()
+            )));
+        }
+
+        // Channels left out of the edit keep their stored setting
+        let stored_preferences = NotificationPreferenceItem::get_user(id, &**pool).await?;
+        let preferences = new_preferences
+            .into_inner()
+            .into_iter()
+            .map(|x| {
+                let stored = stored_preferences
+                    .iter()
+                    .find(|y| y.notification_type == x.notification_type.as_str());
+
+                NotificationPreferenceItem {
+                    user_id: id,
+                    notification_type: x.notification_type.as_str().to_string(),
+                    in_app: x.in_app.or(stored.map(|y| y.in_app)).unwrap_or(true),
+                    send_email: x.send_email.or(stored.and_then(|y| y.send_email)),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut transaction = pool.begin().await?;
+        NotificationPreferenceItem::upsert_many(&preferences, &mut transaction).await?;
+        transaction.commit().await?;
+
+        Ok(HttpResponse::NoContent().body(""))
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
+pub async fn user_payout_method_edit(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    new_method: web::Json<UserPayoutMethod>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PAYOUTS_WRITE]),
+    )
+    .await?
+    .1;
+    let actual_user = User::get(&info.into_inner().0, &**pool, &redis).await?;
+
+    if let Some(actual_user) = actual_user {
+        if !user.role.is_admin() && user.id != actual_user.id.into() {
+            return Err(ApiError::CustomAuthentication(
+                "You do not have permission to edit the payout method of this user!".to_string(),
+            ));
+        }
+
+        let mut method = new_method.into_inner();
+        validate_payout_method(&mut method).map_err(|errors| {
+            ApiError::Validation(
+                errors
+                    .into_iter()
+                    .map(|(field, error)| {
+                        format!("Field {field} failed validation with error: {error}")
+                    })
+                    .join(", "),
+            )
+        })?;
+
+        let mut transaction = pool.begin().await?;
+        User::set_payout_method(actual_user.id, &method, &mut transaction).await?;
+        transaction.commit().await?;
+
+        User::clear_caches(&[(actual_user.id, Some(actual_user.username))], &redis).await?;
+        Ok(HttpResponse::NoContent().body(""))
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
@@ -1,43 +1,73 @@
 use super::project_creation::{CreateError, UploadedFile};
 use crate::auth::get_user_from_headers;
+use crate::auth::validate::check_pat_project_restriction;
+use crate::database::models::legacy_loader_fields::MinecraftGameVersion;
 use crate::database::models::loader_fields::{LoaderField, LoaderFieldEnumValue, VersionField};
+use crate::database::models::mod_metadata_item::ModMetadataIds;
 use crate::database::models::notification_item::NotificationBuilder;
+use crate::database::models::pending_upload_item::{PendingUpload, PendingUploadChunk};
 use crate::database::models::version_item::{
     DependencyBuilder, VersionBuilder, VersionFileBuilder,
 };
 use crate::database::models::{self, image_item, Organization};
 use crate::database::redis::RedisPool;
 use crate::file_hosting::FileHost;
+use crate::file_scanning::{FileScanner, ScanVerdict};
+use crate::models::ids::base62_impl::parse_base62;
 use crate::models::images::{Image, ImageContext, ImageId};
 use crate::models::notifications::NotificationBody;
-use crate::models::pack::PackFileHash;
+use crate::models::pack::{IncompatiblePackFile, PackFileHash, PackFormat};
 use crate::models::pats::Scopes;
-use crate::models::projects::{skip_nulls, DependencyType};
+use crate::models::projects::{skip_nulls, DependencyType, ProjectStatus};
 use crate::models::projects::{
-    Dependency, FileType, Loader, ProjectId, Version, VersionFile, VersionId, VersionStatus,
-    VersionType,
+    Dependency, DependencySuggestion, FileScanStatus, FileType, Loader, ProjectId, Version,
+    VersionFile, VersionId, VersionStatus, VersionType,
 };
 use crate::models::teams::ProjectPermissions;
+use crate::models::threads::ThreadType;
+use crate::models::uploads::{self, PendingUploadId};
+use crate::models::users::User;
 use crate::queue::session::AuthQueue;
-use crate::util::routes::read_from_field;
+use crate::queue::webhooks::WebhookQueue;
+use crate::routes::ApiError;
+use crate::search::indexing::reindex_projects;
+use crate::search::SearchConfig;
+use crate::util::routes::{read_from_field, read_from_payload};
+use crate::util::sanitize;
+use crate::util::signed_urls::PRIVATE_PREFIX;
 use crate::util::validate::validation_errors_to_string;
-use crate::validate::{validate_file, ValidationResult};
+use crate::validate::metadata::read_mod_metadata;
+use crate::validate::registry::{IssueSeverity, ValidationContext, ValidationIssue};
+use crate::validate::{validate_file, ValidationError, ValidationResult, FILE_VALIDATORS};
 use actix_multipart::{Field, Multipart};
 use actix_web::web::Data;
 use actix_web::{web, HttpRequest, HttpResponse};
-use chrono::Utc;
+use bytes::BytesMut;
+use chrono::{DateTime, Utc};
 use futures::stream::StreamExt;
 use itertools::Itertools;
+use log::warn;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgPool;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use validator::Validate;
 
+// The largest file which can be uploaded to a version, in bytes
+pub const MAX_FILE_SIZE: usize = 500 * (1 << 20);
+
 fn default_requested_status() -> VersionStatus {
     VersionStatus::Listed
 }
 
+pub fn default_infer_dependencies() -> bool {
+    true
+}
+
 #[derive(Serialize, Deserialize, Validate, Clone)]
 pub struct InitialVersionData {
     #[serde(alias = "mod_id")]
@@ -79,6 +109,17 @@ pub struct InitialVersionData {
     pub uploaded_images: Vec<ImageId>,
     // The ordering relative to other versions
     pub ordering: Option<i32>,
+    // When to automatically publish the version, which is created as scheduled
+    #[validate(custom(function = "crate::util::validate::validate_publish_at"))]
+    #[serde(default)]
+    pub publish_at: Option<DateTime<Utc>>,
+    // Whether to fill in dependencies from the primary file's mod metadata
+    #[serde(default = "default_infer_dependencies")]
+    pub infer_dependencies: bool,
+    // Whether to accept modpacks with hosted files which don't support the pack's loaders or game
+    // version, keeping the mismatches as warnings on the version
+    #[serde(default)]
+    pub allow_incompatible: bool,
 
     // Flattened loader fields
     // All other fields are loader-specific VersionFields
@@ -92,16 +133,21 @@ pub struct InitialVersionData {
 struct InitialFileData {
     #[serde(default = "HashMap::new")]
     pub file_types: HashMap<String, Option<FileType>>,
+    #[serde(default)]
+    pub allow_incompatible: bool,
 }
 
 // under `/api/v1/version`
+#[allow(clippy::too_many_arguments)]
 pub async fn version_create(
     req: HttpRequest,
     mut payload: Multipart,
     client: Data<PgPool>,
     redis: Data<RedisPool>,
     file_host: Data<Arc<dyn FileHost + Send + Sync>>,
+    scanner: Data<Arc<dyn FileScanner + Send + Sync>>,
     session_queue: Data<AuthQueue>,
+    webhook_queue: Data<WebhookQueue>,
 ) -> Result<HttpResponse, CreateError> {
     let mut transaction = client.begin().await?;
     let mut uploaded_files = Vec::new();
@@ -118,20 +164,52 @@ pub async fn version_create(
     )
     .await;
 
-    if result.is_err() {
-        let undo_result =
-            super::project_creation::undo_uploads(&***file_host, &uploaded_files).await;
-        let rollback_result = transaction.rollback().await;
+    // Deliveries to the project's webhooks are queued with the version
+    let result = match result {
+        Ok(version) => webhook_queue
+            .add_versions(&[version.id.into()], &mut *transaction)
+            .await
+            .map(|()| version)
+            .map_err(CreateError::from),
+        Err(err) => Err(err),
+    };
 
-        undo_result?;
-        if let Err(e) = rollback_result {
-            return Err(e.into());
+    match result {
+        Ok(version) => {
+            transaction.commit().await?;
+            spawn_scan_version(
+                version.id.into(),
+                client.get_ref().clone(),
+                redis.get_ref().clone(),
+                file_host.get_ref().clone(),
+                scanner.get_ref().clone(),
+            );
+
+            if version.status.is_listed() {
+                spawn_notify_followers(
+                    version.project_id.into(),
+                    version.id.into(),
+                    version.author_id.into(),
+                    client.get_ref().clone(),
+                    redis.get_ref().clone(),
+                );
+            }
+
+            Ok(HttpResponse::Ok().json(version))
         }
-    } else {
-        transaction.commit().await?;
-    }
+        Err(err) => {
+            let undo_result =
+                super::project_creation::undo_uploads(&***file_host, &uploaded_files).await;
+            let rollback_result = transaction.rollback().await;
+
+            undo_result?;
+            if let Err(e) = rollback_result {
+                return Err(e.into());
+            }
 
-    result
+            Err(err)
+        }
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -144,7 +222,7 @@ async fn version_create_inner(
     uploaded_files: &mut Vec<UploadedFile>,
     pool: &PgPool,
     session_queue: &AuthQueue,
-) -> Result<HttpResponse, CreateError> {
+) -> Result<Version, CreateError> {
     let cdn_url = dotenvy::var("CDN_URL")?;
 
     let mut initial_version_data = None;
@@ -182,138 +260,12 @@ async fn version_create_inner(
                 }
 
                 let version_create_data: InitialVersionData = serde_json::from_slice(&data)?;
+                let (builder, loaders) =
+                    create_version_builder(&req, &version_create_data, &user, transaction, redis)
+                        .await?;
                 initial_version_data = Some(version_create_data);
-                let version_create_data = initial_version_data.as_ref().unwrap();
-                if version_create_data.project_id.is_none() {
-                    return Err(CreateError::MissingValueError(
-                        "Missing project id".to_string(),
-                    ));
-                }
-
-                version_create_data.validate().map_err(|err| {
-                    CreateError::ValidationError(validation_errors_to_string(err, None))
-                })?;
-
-                if !version_create_data.status.can_be_requested() {
-                    return Err(CreateError::InvalidInput(
-                        "Status specified cannot be requested".to_string(),
-                    ));
-                }
-
-                let project_id: models::ProjectId = version_create_data.project_id.unwrap().into();
-
-                // Ensure that the project this version is being added to exists
-                if models::Project::get_id(project_id, &mut **transaction, redis)
-                    .await?
-                    .is_none()
-                {
-                    return Err(CreateError::InvalidInput(
-                        "An invalid project id was supplied".to_string(),
-                    ));
-                }
-
-                // Check that the user creating this version is a team member
-                // of the project the version is being added to.
-                let team_member = models::TeamMember::get_from_user_id_project(
-                    project_id,
-                    user.id.into(),
-                    false,
-                    &mut **transaction,
-                )
-                .await?;
-
-                // Get organization attached, if exists, and the member project permissions
-                let organization = models::Organization::get_associated_organization_project_id(
-                    project_id,
-                    &mut **transaction,
-                )
-                .await?;
-
-                let organization_team_member = if let Some(organization) = &organization {
-                    models::TeamMember::get_from_user_id(
-                        organization.team_id,
-                        user.id.into(),
-                        &mut **transaction,
-                    )
-                    .await?
-                } else {
-                    None
-                };
-
-                let permissions = ProjectPermissions::get_permissions_by_role(
-                    &user.role,
-                    &team_member,
-                    &organization_team_member,
-                )
-                .unwrap_or_default();
-
-                if !permissions.contains(ProjectPermissions::UPLOAD_VERSION) {
-                    return Err(CreateError::CustomAuthenticationError(
-                        "You don't have permission to upload this version!".to_string(),
-                    ));
-                }
-
-                let version_id: VersionId = models::generate_version_id(transaction).await?.into();
-
-                let all_loaders =
-                    models::loader_fields::Loader::list(&mut **transaction, redis).await?;
-                let loaders = version_create_data
-                    .loaders
-                    .iter()
-                    .map(|x| {
-                        all_loaders
-                            .iter()
-                            .find(|y| y.loader == x.0)
-                            .cloned()
-                            .ok_or_else(|| CreateError::InvalidLoader(x.0.clone()))
-                    })
-                    .collect::<Result<Vec<_>, _>>()?;
-                selected_loaders = Some(loaders.clone());
-                let loader_ids: Vec<models::LoaderId> = loaders.iter().map(|y| y.id).collect_vec();
-
-                let loader_fields =
-                    LoaderField::get_fields(&loader_ids, &mut **transaction, redis).await?;
-                let mut loader_field_enum_values = LoaderFieldEnumValue::list_many_loader_fields(
-                    &loader_fields,
-                    &mut **transaction,
-                    redis,
-                )
-                .await?;
-                let version_fields = try_create_version_fields(
-                    version_id,
-                    &version_create_data.fields,
-                    &loader_fields,
-                    &mut loader_field_enum_values,
-                )?;
-
-                let dependencies = version_create_data
-                    .dependencies
-                    .iter()
-                    .map(|d| models::version_item::DependencyBuilder {
-                        version_id: d.version_id.map(|x| x.into()),
-                        project_id: d.project_id.map(|x| x.into()),
-                        dependency_type: d.dependency_type.to_string(),
-                        file_name: None,
-                    })
-                    .collect::<Vec<_>>();
-
-                version_builder = Some(VersionBuilder {
-                    version_id: version_id.into(),
-                    project_id,
-                    author_id: user.id.into(),
-                    name: version_create_data.version_title.clone(),
-                    version_number: version_create_data.version_number.clone(),
-                    changelog: version_create_data.version_body.clone().unwrap_or_default(),
-                    files: Vec::new(),
-                    dependencies,
-                    loaders: loader_ids,
-                    version_fields,
-                    version_type: version_create_data.release_channel.to_string(),
-                    featured: version_create_data.featured,
-                    status: version_create_data.status,
-                    requested_status: None,
-                    ordering: version_create_data.ordering,
-                });
+                version_builder = Some(builder);
+                selected_loaders = Some(loaders);
 
                 return Ok(());
             }
@@ -349,6 +301,7 @@ async fn version_create_inner(
                 version_data.primary_file.is_some(),
                 version_data.primary_file.as_deref() == Some(name),
                 version_data.file_types.get(name).copied().flatten(),
+                version_data.allow_incompatible,
                 transaction,
                 redis,
             )
@@ -372,244 +325,1208 @@ async fn version_create_inner(
     let builder = version_builder
         .ok_or_else(|| CreateError::InvalidInput("`data` field is required".to_string()))?;
 
-    if builder.files.is_empty() {
-        return Err(CreateError::InvalidInput(
-            "Versions must have at least one file uploaded to them".to_string(),
-        ));
-    }
-
-    use futures::stream::TryStreamExt;
-
-    let users = sqlx::query!(
-        "
-        SELECT follower_id FROM mod_follows
-        WHERE mod_id = $1
-        ",
-        builder.project_id as crate::database::models::ids::ProjectId
-    )
-    .fetch_many(&mut **transaction)
-    .try_filter_map(|e| async { Ok(e.right().map(|m| models::ids::UserId(m.follower_id))) })
-    .try_collect::<Vec<models::ids::UserId>>()
-    .await?;
-
-    let project_id: ProjectId = builder.project_id.into();
-    let version_id: VersionId = builder.version_id.into();
-
-    NotificationBuilder {
-        body: NotificationBody::ProjectUpdate {
-            project_id,
-            version_id,
-        },
-    }
-    .insert_many(users, &mut *transaction, redis)
-    .await?;
-
-    let loader_structs = selected_loaders.unwrap_or_default();
-    let (all_project_types, all_games): (Vec<String>, Vec<String>) =
-        loader_structs.iter().fold((vec![], vec![]), |mut acc, x| {
-            acc.0.extend_from_slice(&x.supported_project_types);
-            acc.1.extend(x.supported_games.clone());
-            acc
-        });
-
-    let response = Version {
-        id: builder.version_id.into(),
-        project_id: builder.project_id.into(),
-        author_id: user.id,
-        featured: builder.featured,
-        name: builder.name.clone(),
-        version_number: builder.version_number.clone(),
-        project_types: all_project_types,
-        games: all_games,
-        changelog: builder.changelog.clone(),
-        date_published: Utc::now(),
-        downloads: 0,
-        version_type: version_data.release_channel,
-        status: builder.status,
-        requested_status: builder.requested_status,
-        ordering: builder.ordering,
-        files: builder
-            .files
-            .iter()
-            .map(|file| VersionFile {
-                hashes: file
-                    .hashes
-                    .iter()
-                    .map(|hash| {
-                        (
-                            hash.algorithm.clone(),
-                            // This is a hack since the hashes are currently stored as ASCII
-                            // in the database, but represented here as a Vec<u8>.  At some
-                            // point we need to change the hash to be the real bytes  in the
-                            // database and add more processing here.
-                            String::from_utf8(hash.hash.clone()).unwrap(),
-                        )
-                    })
-                    .collect(),
-                url: file.url.clone(),
-                filename: file.filename.clone(),
-                primary: file.primary,
-                size: file.size,
-                file_type: file.file_type,
-            })
-            .collect::<Vec<_>>(),
-        dependencies: version_data.dependencies,
-        loaders: version_data.loaders,
-        fields: version_data.fields,
-    };
-
-    let project_id = builder.project_id;
-    builder.insert(transaction).await?;
-
-    for image_id in version_data.uploaded_images {
-        if let Some(db_image) =
-            image_item::Image::get(image_id.into(), &mut **transaction, redis).await?
-        {
-            let image: Image = db_image.into();
-            if !matches!(image.context, ImageContext::Report { .. })
-                || image.context.inner_id().is_some()
-            {
-                return Err(CreateError::InvalidInput(format!(
-                    "Image {} is not unused and in the 'version' context",
-                    image_id
-                )));
-            }
-
-            sqlx::query!(
-                "
-                UPDATE uploaded_images
-                SET version_id = $1
-                WHERE id = $2
-                ",
-                version_id.0 as i64,
-                image_id.0 as i64
-            )
-            .execute(&mut **transaction)
-            .await?;
+    let loaders = selected_loaders.unwrap_or_default();
 
-            image_item::Image::clear_cache(image.id.into(), redis).await?;
-        } else {
-            return Err(CreateError::InvalidInput(format!(
-                "Image {} does not exist",
-                image_id
-            )));
-        }
-    }
+    finish_version_creation(version_data, builder, loaders, &user, transaction, redis).await
+}
 
-    models::Project::clear_cache(project_id, None, Some(true), redis).await?;
+#[derive(Serialize, Deserialize, Validate)]
+pub struct BulkVersionData {
+    /// Fields shared by all of the versions, such as the version number or changelog
+    #[serde(default)]
+    pub defaults: serde_json::Map<String, serde_json::Value>,
+    /// The versions to create, each overriding the defaults where they set a field
+    #[validate(length(min = 1, max = 32))]
+    pub versions: Vec<serde_json::Map<String, serde_json::Value>>,
+}
 
-    Ok(HttpResponse::Ok().json(response))
+struct BulkVersion {
+    data: InitialVersionData,
+    builder: VersionBuilder,
+    loaders: Vec<models::loader_fields::Loader>,
 }
 
-pub async fn upload_file_to_version(
+/// Creates several versions of a project at once, such as the same release for multiple
+/// loaders. Either all of the versions are created, or none of them are.
+#[allow(clippy::too_many_arguments)]
+pub async fn version_create_bulk(
     req: HttpRequest,
-    url_data: web::Path<(VersionId,)>,
+    info: web::Path<(String,)>,
     mut payload: Multipart,
     client: Data<PgPool>,
     redis: Data<RedisPool>,
     file_host: Data<Arc<dyn FileHost + Send + Sync>>,
-    session_queue: web::Data<AuthQueue>,
+    scanner: Data<Arc<dyn FileScanner + Send + Sync>>,
+    session_queue: Data<AuthQueue>,
+    webhook_queue: Data<WebhookQueue>,
 ) -> Result<HttpResponse, CreateError> {
     let mut transaction = client.begin().await?;
     let mut uploaded_files = Vec::new();
 
-    let version_id = models::VersionId::from(url_data.into_inner().0);
-
-    let result = upload_file_to_version_inner(
+    let result = version_create_bulk_inner(
         req,
+        &info.into_inner().0,
         &mut payload,
-        client,
         &mut transaction,
-        redis,
+        &redis,
         &***file_host,
         &mut uploaded_files,
-        version_id,
+        &client,
         &session_queue,
     )
     .await;
 
-    if result.is_err() {
-        let undo_result =
-            super::project_creation::undo_uploads(&***file_host, &uploaded_files).await;
-        let rollback_result = transaction.rollback().await;
+    // Deliveries to the project's webhooks are queued with the versions
+    let result = match result {
+        Ok(versions) => webhook_queue
+            .add_versions(
+                &versions.iter().map(|x| x.id.into()).collect::<Vec<_>>(),
+                &mut *transaction,
+            )
+            .await
+            .map(|()| versions)
+            .map_err(CreateError::from),
+        Err(err) => Err(err),
+    };
 
-        undo_result?;
-        if let Err(e) = rollback_result {
-            return Err(e.into());
+    match result {
+        Ok(versions) => {
+            transaction.commit().await?;
+
+            for version in &versions {
+                spawn_scan_version(
+                    version.id.into(),
+                    client.get_ref().clone(),
+                    redis.get_ref().clone(),
+                    file_host.get_ref().clone(),
+                    scanner.get_ref().clone(),
+                );
+
+                if version.status.is_listed() {
+                    spawn_notify_followers(
+                        version.project_id.into(),
+                        version.id.into(),
+                        version.author_id.into(),
+                        client.get_ref().clone(),
+                        redis.get_ref().clone(),
+                    );
+                }
+            }
+
+            Ok(HttpResponse::Ok().json(versions))
         }
-    } else {
-        transaction.commit().await?;
-    }
+        Err(err) => {
+            let undo_result =
+                super::project_creation::undo_uploads(&***file_host, &uploaded_files).await;
+            let rollback_result = transaction.rollback().await;
+
+            undo_result?;
+            if let Err(e) = rollback_result {
+                return Err(e.into());
+            }
 
-    result
+            Err(err)
+        }
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
-async fn upload_file_to_version_inner(
+async fn version_create_bulk_inner(
     req: HttpRequest,
+    project_id: &str,
     payload: &mut Multipart,
-    client: Data<PgPool>,
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-    redis: Data<RedisPool>,
+    redis: &RedisPool,
     file_host: &dyn FileHost,
     uploaded_files: &mut Vec<UploadedFile>,
-    version_id: models::VersionId,
+    pool: &PgPool,
     session_queue: &AuthQueue,
-) -> Result<HttpResponse, CreateError> {
+) -> Result<Vec<Version>, CreateError> {
     let cdn_url = dotenvy::var("CDN_URL")?;
 
-    let mut initial_file_data: Option<InitialFileData> = None;
-    let mut file_builders: Vec<VersionFileBuilder> = Vec::new();
-
     let user = get_user_from_headers(
         &req,
-        &**client,
-        &redis,
+        pool,
+        redis,
         session_queue,
-        Some(&[Scopes::VERSION_WRITE]),
+        Some(&[Scopes::VERSION_CREATE]),
     )
     .await?
     .1;
 
-    let result = models::Version::get(version_id, &**client, &redis).await?;
+    let project_id: ProjectId = models::Project::get(project_id, &mut **transaction, redis)
+        .await?
+        .ok_or_else(|| CreateError::InvalidInput("An invalid project id was supplied".to_string()))?
+        .inner
+        .id
+        .into();
 
-    let version = match result {
-        Some(v) => v,
-        None => {
-            return Err(CreateError::InvalidInput(
-                "An invalid version id was supplied".to_string(),
-            ));
-        }
-    };
+    let mut versions: Option<Vec<BulkVersion>> = None;
+    // The index of the version each file part belongs to
+    let mut file_versions = HashMap::new();
 
-    let all_loaders = models::loader_fields::Loader::list(&mut **transaction, &redis).await?;
+    let mut error = None;
+    while let Some(item) = payload.next().await {
+        let mut field: Field = item?;
 
-    // TODO: this coded is reused a lot, it should be refactored into a function
-    let selected_loaders = version
-        .loaders
-        .iter()
-        .map(|x| {
-            all_loaders
-                .iter()
-                .find(|y| &y.loader == x)
-                .cloned()
-                .ok_or_else(|| CreateError::InvalidLoader(x.clone()))
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+        if error.is_some() {
+            continue;
+        }
 
-    if models::Project::get_id(version.inner.project_id, &mut **transaction, &redis)
-        .await?
-        .is_none()
-    {
-        return Err(CreateError::InvalidInput(
-            "An invalid project id was supplied".to_string(),
-        ));
-    }
+        let result = async {
+            let content_disposition = field.content_disposition().clone();
+            let name = content_disposition.get_name().ok_or_else(|| {
+                CreateError::MissingValueError("Missing content name".to_string())
+            })?;
 
-    if !user.role.is_admin() {
-        let team_member = models::TeamMember::get_from_user_id_project(
-            version.inner.project_id,
-            user.id.into(),
+            if name == "data" {
+                let mut data = Vec::new();
+                while let Some(chunk) = field.next().await {
+                    data.extend_from_slice(&chunk?);
+                }
+
+                let bulk_data: BulkVersionData = serde_json::from_slice(&data)?;
+                bulk_data.validate().map_err(|err| {
+                    CreateError::ValidationError(validation_errors_to_string(err, None))
+                })?;
+
+                let mut created = Vec::with_capacity(bulk_data.versions.len());
+                for (index, version) in bulk_data.versions.into_iter().enumerate() {
+                    let version = create_bulk_version(
+                        &req,
+                        &bulk_data.defaults,
+                        version,
+                        project_id,
+                        &user,
+                        transaction,
+                        redis,
+                    )
+                    .await
+                    .and_then(|version| {
+                        for file_part in &version.data.file_parts {
+                            if file_versions.insert(file_part.clone(), index).is_some() {
+                                return Err(CreateError::InvalidInput(format!(
+                                    "File part {file_part} belongs to more than one version"
+                                )));
+                            }
+                        }
+
+                        Ok(version)
+                    })
+                    .map_err(|error| CreateError::BulkVersionError {
+                        index,
+                        error: Box::new(error),
+                    })?;
+
+                    created.push(version);
+                }
+                versions = Some(created);
+
+                return Ok(());
+            }
+
+            let versions = versions.as_mut().ok_or_else(|| {
+                CreateError::InvalidInput(String::from("`data` field must come before file fields"))
+            })?;
+            let index = *file_versions.get(name).ok_or_else(|| {
+                CreateError::InvalidInput(format!(
+                    "File part {name} isn't one of the file parts of any version"
+                ))
+            })?;
+            let BulkVersion {
+                data,
+                builder,
+                loaders,
+            } = &mut versions[index];
+            let loaders = loaders
+                .iter()
+                .map(|x| Loader(x.loader.clone()))
+                .collect::<Vec<_>>();
+
+            upload_file(
+                &mut field,
+                file_host,
+                data.file_parts.len(),
+                uploaded_files,
+                &mut builder.files,
+                &mut builder.dependencies,
+                &cdn_url,
+                &content_disposition,
+                builder.project_id.into(),
+                builder.version_id.into(),
+                &builder.version_fields,
+                loaders,
+                data.primary_file.is_some(),
+                data.primary_file.as_deref() == Some(name),
+                data.file_types.get(name).copied().flatten(),
+                data.allow_incompatible,
+                transaction,
+                redis,
+            )
+            .await
+            .map_err(|error| CreateError::BulkVersionError {
+                index,
+                error: Box::new(error),
+            })
+        }
+        .await;
+
+        if result.is_err() {
+            error = result.err();
+        }
+    }
+
+    if let Some(error) = error {
+        return Err(error);
+    }
+
+    let versions = versions
+        .ok_or_else(|| CreateError::InvalidInput("`data` field is required".to_string()))?;
+
+    let mut created = Vec::with_capacity(versions.len());
+    for (index, version) in versions.into_iter().enumerate() {
+        let version = finish_version_creation(
+            version.data,
+            version.builder,
+            version.loaders,
+            &user,
+            transaction,
+            redis,
+        )
+        .await
+        .map_err(|error| CreateError::BulkVersionError {
+            index,
+            error: Box::new(error),
+        })?;
+
+        created.push(version);
+    }
+
+    Ok(created)
+}
+
+// Merges a version of a bulk request over the shared defaults, and builds it
+async fn create_bulk_version(
+    req: &HttpRequest,
+    defaults: &serde_json::Map<String, serde_json::Value>,
+    version: serde_json::Map<String, serde_json::Value>,
+    project_id: ProjectId,
+    user: &User,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<BulkVersion, CreateError> {
+    let mut fields = defaults.clone();
+    fields.extend(version);
+    fields.insert("project_id".to_string(), serde_json::json!(project_id));
+
+    let data: InitialVersionData = serde_json::from_value(serde_json::Value::Object(fields))?;
+    let (builder, loaders) = create_version_builder(req, &data, user, transaction, redis).await?;
+
+    Ok(BulkVersion {
+        data,
+        builder,
+        loaders,
+    })
+}
+
+// Validates the initial version data and the permissions of the user uploading it, and
+// builds the version that the uploaded files will be attached to
+async fn create_version_builder(
+    req: &HttpRequest,
+    version_create_data: &InitialVersionData,
+    user: &User,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<(VersionBuilder, Vec<models::loader_fields::Loader>), CreateError> {
+    if version_create_data.project_id.is_none() {
+        return Err(CreateError::MissingValueError(
+            "Missing project id".to_string(),
+        ));
+    }
+
+    version_create_data
+        .validate()
+        .map_err(|err| CreateError::ValidationError(validation_errors_to_string(err, None)))?;
+
+    let status = if version_create_data.publish_at.is_some() {
+        if !matches!(
+            version_create_data.status,
+            VersionStatus::Draft | VersionStatus::Scheduled
+        ) {
+            return Err(CreateError::InvalidInput(
+                "Only draft versions can be scheduled for publishing".to_string(),
+            ));
+        }
+
+        VersionStatus::Scheduled
+    } else if version_create_data.status.can_be_requested() {
+        version_create_data.status
+    } else {
+        return Err(CreateError::InvalidInput(
+            "Status specified cannot be requested".to_string(),
+        ));
+    };
+
+    let project_id: models::ProjectId = version_create_data.project_id.unwrap().into();
+    check_pat_project_restriction(req, project_id)?;
+
+    // Ensure that the project this version is being added to exists
+    let project = models::Project::get_id(project_id, &mut **transaction, redis)
+        .await?
+        .ok_or_else(|| {
+            CreateError::InvalidInput("An invalid project id was supplied".to_string())
+        })?;
+    if project.inner.status.is_read_only() {
+        return Err(CreateError::InvalidInput(
+            "This project is archived and can't be changed until it is unarchived!".to_string(),
+        ));
+    }
+
+    // Check that the user creating this version is a team member
+    // of the project the version is being added to.
+    let team_member = models::TeamMember::get_from_user_id_project(
+        project_id,
+        user.id.into(),
+        false,
+        &mut **transaction,
+    )
+    .await?;
+
+    // Get organization attached, if exists, and the member project permissions
+    let organization = models::Organization::get_associated_organization_project_id(
+        project_id,
+        &mut **transaction,
+    )
+    .await?;
+
+    let organization_team_member = if let Some(organization) = &organization {
+        models::TeamMember::get_from_user_id(
+            organization.team_id,
+            user.id.into(),
+            &mut **transaction,
+        )
+        .await?
+    } else {
+        None
+    };
+
+    let permissions = ProjectPermissions::get_permissions_by_role(
+        &user.role,
+        &team_member,
+        &organization_team_member,
+    )
+    .unwrap_or_default();
+
+    if !permissions.contains(ProjectPermissions::UPLOAD_VERSION) {
+        return Err(CreateError::CustomAuthenticationError(
+            "You don't have permission to upload this version!".to_string(),
+        ));
+    }
+
+    let version_id: VersionId = models::generate_version_id(transaction).await?.into();
+
+    let all_loaders = models::loader_fields::Loader::list(&mut **transaction, redis).await?;
+    let loaders = version_create_data
+        .loaders
+        .iter()
+        .map(|x| {
+            all_loaders
+                .iter()
+                .find(|y| y.loader == x.0)
+                .cloned()
+                .ok_or_else(|| CreateError::InvalidLoader(x.0.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let loader_ids: Vec<models::LoaderId> = loaders.iter().map(|y| y.id).collect_vec();
+
+    let loader_fields = LoaderField::get_fields(&loader_ids, &mut **transaction, redis).await?;
+    let mut loader_field_enum_values =
+        LoaderFieldEnumValue::list_many_loader_fields(&loader_fields, &mut **transaction, redis)
+            .await?;
+    let version_fields = try_create_version_fields(
+        version_id,
+        &version_create_data.fields,
+        &loader_fields,
+        &mut loader_field_enum_values,
+    )?;
+
+    let dependencies = version_create_data
+        .dependencies
+        .iter()
+        .map(|d| models::version_item::DependencyBuilder {
+            version_id: d.version_id.map(|x| x.into()),
+            project_id: d.project_id.map(|x| x.into()),
+            dependency_type: d.dependency_type.to_string(),
+            file_name: None,
+            inferred: false,
+        })
+        .collect::<Vec<_>>();
+
+    let builder = VersionBuilder {
+        version_id: version_id.into(),
+        project_id,
+        author_id: user.id.into(),
+        name: version_create_data.version_title.clone(),
+        version_number: version_create_data.version_number.clone(),
+        changelog: sanitize::sanitize_body(
+            version_create_data
+                .version_body
+                .as_deref()
+                .unwrap_or_default(),
+            sanitize::is_strict(req),
+        )
+        .map_err(|err| CreateError::InvalidInput(err.to_string()))?,
+        files: Vec::new(),
+        dependencies,
+        loaders: loader_ids,
+        version_fields,
+        version_type: version_create_data.release_channel.to_string(),
+        featured: version_create_data.featured,
+        status,
+        requested_status: None,
+        ordering: version_create_data.ordering,
+        publish_at: version_create_data.publish_at,
+    };
+
+    Ok((builder, loaders))
+}
+
+/// Notifies everyone following a project that one of its versions was published, except for
+/// the version's author. Followers are notified in the background, since popular projects can
+/// have far more followers than fit comfortably in the publishing request.
+pub fn spawn_notify_followers(
+    project_id: models::ProjectId,
+    version_id: models::VersionId,
+    author_id: models::UserId,
+    pool: PgPool,
+    redis: RedisPool,
+) {
+    actix_rt::spawn(async move {
+        if let Err(e) = notify_followers(project_id, version_id, author_id, &pool, &redis).await {
+            warn!(
+                "Notifying followers of version {} failed: {:?}",
+                VersionId::from(version_id),
+                e
+            );
+        }
+    });
+}
+
+async fn notify_followers(
+    project_id: models::ProjectId,
+    version_id: models::VersionId,
+    author_id: models::UserId,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), models::DatabaseError> {
+    let users = sqlx::query!(
+        "
+        SELECT follower_id FROM mod_follows
+        WHERE mod_id = $1 AND follower_id != $2
+        ",
+        project_id as models::ProjectId,
+        author_id as models::UserId,
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| models::UserId(x.follower_id))
+    .collect::<Vec<_>>();
+
+    NotificationBuilder {
+        body: NotificationBody::ProjectUpdate {
+            project_id: project_id.into(),
+            version_id: version_id.into(),
+        },
+    }
+    .insert_many_chunked(users, pool, redis)
+    .await
+}
+
+/// Scans the files of a version which are waiting to be scanned, in the background. Scans can
+/// take a while for large files, so they aren't part of the upload request.
+pub fn spawn_scan_version(
+    version_id: models::VersionId,
+    pool: PgPool,
+    redis: RedisPool,
+    file_host: Arc<dyn FileHost + Send + Sync>,
+    scanner: Arc<dyn FileScanner + Send + Sync>,
+) {
+    actix_rt::spawn(async move {
+        if let Err(e) = scan_version_files(version_id, &pool, &redis, &*file_host, &*scanner).await
+        {
+            warn!(
+                "Scanning the files of version {} failed: {:?}",
+                VersionId::from(version_id),
+                e
+            );
+        }
+    });
+}
+
+/// Scans the files of a version which are waiting to be scanned. If any of them are flagged,
+/// the version is withheld and moderators are notified. Withheld versions stay hidden until a
+/// moderator changes their status, even if a rescan finds them clean.
+pub async fn scan_version_files(
+    version_id: models::VersionId,
+    pool: &PgPool,
+    redis: &RedisPool,
+    file_host: &dyn FileHost,
+    scanner: &dyn FileScanner,
+) -> Result<(), ApiError> {
+    let Some(version) = models::Version::get(version_id, pool, redis).await? else {
+        return Ok(());
+    };
+    let cdn_url = dotenvy::var("CDN_URL")?;
+
+    let mut flagged_files = Vec::new();
+    for file in version
+        .files
+        .iter()
+        .filter(|x| x.scan_status == FileScanStatus::Pending)
+    {
+        let verdict = async {
+            // Files are uploaded to the file host under their URL's path
+            let file_path = file
+                .url
+                .strip_prefix(&format!("{cdn_url}/"))
+                .ok_or_else(|| format!("{} isn't hosted on the CDN", file.url))?;
+            let file_bytes = file_host
+                .download_file(&urlencoding::decode(file_path)?)
+                .await?;
+            Ok::<_, Box<dyn std::error::Error>>(scanner.scan(file_bytes).await?)
+        }
+        .await;
+
+        let status = match verdict {
+            Ok(ScanVerdict::Clean) => FileScanStatus::Clean,
+            Ok(ScanVerdict::Flagged(signature)) => {
+                warn!(
+                    "File {} of version {} was flagged as {}",
+                    file.filename,
+                    VersionId::from(version_id),
+                    signature
+                );
+                flagged_files.push(file.filename.clone());
+                FileScanStatus::Flagged
+            }
+            Err(e) => {
+                warn!(
+                    "Scanning file {} of version {} failed: {}",
+                    file.filename,
+                    VersionId::from(version_id),
+                    e
+                );
+                FileScanStatus::Error
+            }
+        };
+
+        models::Version::update_file_scan_status(file.id, status, pool).await?;
+    }
+
+    if !flagged_files.is_empty() && version.inner.status != VersionStatus::Withheld {
+        let mut transaction = pool.begin().await?;
+
+        sqlx::query!(
+            "
+            UPDATE versions
+            SET status = $1, publish_at = NULL
+            WHERE id = $2
+            ",
+            VersionStatus::Withheld.as_str(),
+            version_id as models::VersionId,
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        let moderators = models::User::get_moderator_ids(&mut *transaction).await?;
+        NotificationBuilder {
+            body: NotificationBody::VersionFilesFlagged {
+                project_id: version.inner.project_id.into(),
+                version_id: version_id.into(),
+                file_names: flagged_files,
+            },
+        }
+        .insert_many(moderators, &mut transaction, redis)
+        .await?;
+
+        transaction.commit().await?;
+    }
+
+    models::Version::clear_cache(&version, redis).await?;
+    models::Project::clear_cache(version.inner.project_id, None, Some(true), redis).await?;
+
+    Ok(())
+}
+
+/// Publishes every scheduled version whose publish time has passed, as of `now`. Versions of
+/// projects which aren't approved yet stay scheduled, and their project's team is notified once.
+pub async fn publish_scheduled_versions(
+    now: DateTime<Utc>,
+    pool: &PgPool,
+    redis: &RedisPool,
+    search_config: &SearchConfig,
+    webhook_queue: &WebhookQueue,
+) -> Result<(), ApiError> {
+    let due_versions = sqlx::query!(
+        "
+        SELECT v.id, v.mod_id, v.author_id, v.publish_delayed, m.status project_status, m.team_id
+        FROM versions v
+        INNER JOIN mods m ON m.id = v.mod_id
+        WHERE v.status = $1 AND v.publish_at <= $2
+        ",
+        VersionStatus::Scheduled.as_str(),
+        now,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let (publishable, delayed): (Vec<_>, Vec<_>) = due_versions
+        .into_iter()
+        .partition(|x| ProjectStatus::from_string(&x.project_status).is_approved());
+
+    for version in delayed.iter().filter(|x| !x.publish_delayed) {
+        let mut transaction = pool.begin().await?;
+
+        let members = sqlx::query!(
+            "
+            SELECT user_id FROM team_members
+            WHERE team_id = $1 AND accepted
+            ",
+            version.team_id,
+        )
+        .fetch_all(&mut *transaction)
+        .await?
+        .into_iter()
+        .map(|x| models::UserId(x.user_id))
+        .collect::<Vec<_>>();
+
+        NotificationBuilder {
+            body: NotificationBody::VersionPublishDelayed {
+                project_id: models::ProjectId(version.mod_id).into(),
+                version_id: models::VersionId(version.id).into(),
+            },
+        }
+        .insert_many(members, &mut transaction, redis)
+        .await?;
+
+        sqlx::query!(
+            "
+            UPDATE versions
+            SET publish_delayed = TRUE
+            WHERE id = $1
+            ",
+            version.id,
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+    }
+
+    if publishable.is_empty() {
+        return Ok(());
+    }
+
+    let version_ids = publishable
+        .iter()
+        .map(|x| models::VersionId(x.id))
+        .collect::<Vec<_>>();
+    sqlx::query!(
+        "
+        UPDATE versions
+        SET status = $1, publish_at = NULL, date_published = NOW()
+        WHERE id = ANY($2)
+        ",
+        VersionStatus::Listed.as_str(),
+        &version_ids.iter().map(|x| x.0).collect::<Vec<_>>(),
+    )
+    .execute(pool)
+    .await?;
+
+    for version in models::Version::get_many(&version_ids, pool, redis).await? {
+        models::Version::clear_cache(&version, redis).await?;
+    }
+
+    let project_ids = publishable
+        .iter()
+        .map(|x| models::ProjectId(x.mod_id))
+        .unique()
+        .collect::<Vec<_>>();
+    for project_id in &project_ids {
+        models::Project::clear_cache(*project_id, None, Some(true), redis).await?;
+    }
+
+    reindex_projects(&project_ids, pool, redis, search_config).await?;
+
+    webhook_queue.add_versions(&version_ids, pool).await?;
+    for version in publishable {
+        spawn_notify_followers(
+            models::ProjectId(version.mod_id),
+            models::VersionId(version.id),
+            models::UserId(version.author_id),
+            pool.clone(),
+            redis.clone(),
+        );
+    }
+
+    Ok(())
+}
+
+// Attaches the version to its project once all of its files have been uploaded
+async fn finish_version_creation(
+    version_data: InitialVersionData,
+    mut builder: VersionBuilder,
+    loader_structs: Vec<models::loader_fields::Loader>,
+    user: &User,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<Version, CreateError> {
+    if builder.files.is_empty() {
+        return Err(CreateError::InvalidInput(
+            "Versions must have at least one file uploaded to them".to_string(),
+        ));
+    }
+
+    let version_id: VersionId = builder.version_id.into();
+
+    // Only the dependencies filled in here count as inferred
+    let mut dependencies = version_data
+        .dependencies
+        .into_iter()
+        .map(|x| Dependency {
+            inferred: false,
+            ..x
+        })
+        .collect::<Vec<_>>();
+    let dependency_suggestions = if version_data.infer_dependencies {
+        let (inferred, suggestions) = infer_dependencies(&mut builder, transaction).await?;
+        dependencies.extend(inferred);
+        suggestions
+    } else {
+        Vec::new()
+    };
+    let mod_ids = builder
+        .primary_mod_metadata()
+        .map(|x| x.mod_ids.clone())
+        .unwrap_or_default();
+
+    let (all_project_types, all_games): (Vec<String>, Vec<String>) =
+        loader_structs.iter().fold((vec![], vec![]), |mut acc, x| {
+            acc.0.extend_from_slice(&x.supported_project_types);
+            acc.1.extend(x.supported_games.clone());
+            acc
+        });
+
+    let mut response = Version {
+        id: builder.version_id.into(),
+        project_id: builder.project_id.into(),
+        author_id: user.id,
+        featured: builder.featured,
+        name: builder.name.clone(),
+        version_number: builder.version_number.clone(),
+        project_types: all_project_types,
+        games: all_games,
+        changelog: builder.changelog.clone(),
+        date_published: Utc::now(),
+        downloads: 0,
+        version_type: version_data.release_channel,
+        status: builder.status,
+        requested_status: builder.requested_status,
+        ordering: builder.ordering,
+        publish_at: builder.publish_at,
+        flagged_reupload: None,
+        has_withheld_files: Some(false),
+        files: builder
+            .files
+            .iter()
+            .map(|file| VersionFile {
+                hashes: file
+                    .hashes
+                    .iter()
+                    .map(|hash| {
+                        (
+                            hash.algorithm.clone(),
+                            // This is a hack since the hashes are currently stored as ASCII
+                            // in the database, but represented here as a Vec<u8>.  At some
+                            // point we need to change the hash to be the real bytes  in the
+                            // database and add more processing here.
+                            String::from_utf8(hash.hash.clone()).unwrap(),
+                        )
+                    })
+                    .collect(),
+                url: file.url.clone(),
+                filename: file.filename.clone(),
+                primary: file.primary,
+                size: file.size,
+                file_type: file.file_type,
+                scan_status: Some(FileScanStatus::Pending),
+                scanned_at: None,
+                withheld: Some(false),
+            })
+            .collect::<Vec<_>>(),
+        dependencies,
+        dependency_suggestions,
+        validation_warnings: builder
+            .files
+            .iter()
+            .flat_map(|x| x.validation_warnings.clone())
+            .collect(),
+        loaders: version_data.loaders,
+        fields: version_data.fields,
+    };
+
+    let project_id = builder.project_id;
+    let file_hashes = sha1_hashes(&builder.files);
+    builder.insert(transaction).await?;
+    ModMetadataIds::register(project_id, &mod_ids, transaction).await?;
+
+    response.flagged_reupload = Some(
+        flag_reupload(
+            version_id.into(),
+            project_id,
+            &file_hashes,
+            user.id.into(),
+            transaction,
+            redis,
+        )
+        .await?,
+    );
+
+    for image_id in version_data.uploaded_images {
+        if let Some(db_image) =
+            image_item::Image::get(image_id.into(), &mut **transaction, redis).await?
+        {
+            let image: Image = db_image.into();
+            if !matches!(image.context, ImageContext::Report { .. })
+                || image.context.inner_id().is_some()
+            {
+                return Err(CreateError::InvalidInput(format!(
+                    "Image {} is not unused and in the 'version' context",
+                    image_id
+                )));
+            }
+
+            sqlx::query!(
+                "
+                UPDATE uploaded_images
+                SET version_id = $1
+                WHERE id = $2
+                ",
+                version_id.0 as i64,
+                image_id.0 as i64
+            )
+            .execute(&mut **transaction)
+            .await?;
+
+            image_item::Image::clear_cache(image.id.into(), redis).await?;
+        } else {
+            return Err(CreateError::InvalidInput(format!(
+                "Image {} does not exist",
+                image_id
+            )));
+        }
+    }
+
+    models::Project::clear_cache(project_id, None, Some(true), redis).await?;
+
+    Ok(response)
+}
+
+/// The SHA-1 hashes of the files, which are what reuploads are looked up by
+pub fn sha1_hashes(files: &[VersionFileBuilder]) -> Vec<Vec<u8>> {
+    files
+        .iter()
+        .flat_map(|x| x.hashes.iter())
+        .filter(|x| x.algorithm == "sha1")
+        .map(|x| x.hash.clone())
+        .collect()
+}
+
+/// Flags the version if any of the files were already uploaded to another project which the
+/// uploader isn't a member of, directly or through its organization, and opens a report about it
+/// for moderators. Returns whether the version is flagged. The upload isn't blocked, as the same
+/// file is sometimes legitimately uploaded to several projects.
+pub async fn flag_reupload(
+    version_id: models::VersionId,
+    project_id: models::ProjectId,
+    file_hashes: &[Vec<u8>],
+    uploader: models::UserId,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<bool, CreateError> {
+    // Only one of the files has to match
+    let original_project_id = sqlx::query!(
+        "
+        SELECT v.mod_id FROM hashes h
+        INNER JOIN files f ON f.id = h.file_id
+        INNER JOIN versions v ON v.id = f.version_id
+        INNER JOIN mods m ON m.id = v.mod_id
+        LEFT JOIN organizations o ON o.id = m.organization_id
+        WHERE h.algorithm = 'sha1' AND h.hash = ANY($1) AND v.mod_id != $2
+        AND NOT EXISTS (
+            SELECT 1 FROM team_members tm
+            WHERE (tm.team_id = m.team_id OR tm.team_id = o.team_id)
+            AND tm.user_id = $3 AND tm.accepted = TRUE
+        )
+        ORDER BY v.date_published ASC
+        LIMIT 1
+        ",
+        file_hashes,
+        project_id as models::ProjectId,
+        uploader as models::UserId,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .map(|x| models::ProjectId(x.mod_id));
+
+    let Some(original_project_id) = original_project_id else {
+        return Ok(false);
+    };
+
+    sqlx::query!(
+        "
+        UPDATE versions
+        SET flagged_reupload = TRUE
+        WHERE id = $1
+        ",
+        version_id as models::VersionId,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    let report_id = models::generate_report_id(transaction).await?;
+    let report_type_id = models::categories::ReportType::get_id("reupload", &mut **transaction)
+        .await?
+        .ok_or_else(|| {
+            CreateError::InvalidInput("The reupload report type is missing".to_string())
+        })?;
+    models::report_item::Report {
+        id: report_id,
+        report_type_id,
+        project_id: Some(project_id),
+        version_id: Some(version_id),
+        user_id: None,
+        related_project_id: Some(original_project_id),
+        body: format!(
+            "The version {} has files which were already uploaded to the project {}.",
+            VersionId::from(version_id),
+            ProjectId::from(original_project_id)
+        ),
+        reporter: crate::models::users::AUTOMOD_USER.into(),
+        created: Utc::now(),
+        closed: false,
+        file_hash: None,
+    }
+    .insert(transaction)
+    .await?;
+
+    models::thread_item::ThreadBuilder {
+        type_: ThreadType::Report,
+        members: vec![],
+        project_id: None,
+        report_id: Some(report_id),
+    }
+    .insert(transaction)
+    .await?;
+
+    let moderators = models::User::get_moderator_ids(&mut **transaction).await?;
+    NotificationBuilder {
+        body: NotificationBody::VersionReuploadFlagged {
+            project_id: project_id.into(),
+            version_id: version_id.into(),
+            original_project_id: original_project_id.into(),
+            report_id: report_id.into(),
+        },
+    }
+    .insert_many(moderators, transaction, redis)
+    .await?;
+
+    Ok(true)
+}
+
+/// Fills in the dependencies the version's primary file declares in its mod metadata on mod ids
+/// which belong to other projects, returning them. The dependencies on mod ids no project is
+/// known by are returned as suggestions instead, for the uploader to fill in themselves.
+pub async fn infer_dependencies(
+    builder: &mut VersionBuilder,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(Vec<Dependency>, Vec<DependencySuggestion>), CreateError> {
+    let Some(metadata) = builder.primary_mod_metadata().cloned() else {
+        return Ok((Vec::new(), Vec::new()));
+    };
+
+    let declared_ids = metadata
+        .dependencies
+        .iter()
+        .map(|x| x.mod_id.clone())
+        .collect::<Vec<_>>();
+    let resolved =
+        ModMetadataIds::resolve(&declared_ids, builder.project_id, &mut **transaction).await?;
+
+    // The uploader's own dependencies take precedence over what the metadata declares
+    let mut existing = HashSet::new();
+    for dependency in &builder.dependencies {
+        if let Some(project_id) = dependency.try_get_project_id(transaction).await? {
+            existing.insert(project_id);
+        }
+    }
+
+    let mut inferred = Vec::new();
+    let mut suggestions = Vec::new();
+    for dependency in metadata.dependencies {
+        match resolved.get(&dependency.mod_id) {
+            Some(project_id) => {
+                if !existing.insert(*project_id) {
+                    continue;
+                }
+
+                builder.dependencies.push(DependencyBuilder {
+                    project_id: Some(*project_id),
+                    version_id: None,
+                    file_name: None,
+                    dependency_type: dependency.dependency_type.to_string(),
+                    inferred: true,
+                });
+                inferred.push(Dependency {
+                    version_id: None,
+                    project_id: Some((*project_id).into()),
+                    file_name: None,
+                    dependency_type: dependency.dependency_type,
+                    inferred: true,
+                });
+            }
+            None => suggestions.push(DependencySuggestion {
+                mod_id: dependency.mod_id,
+                version_range: dependency.version_range,
+                dependency_type: dependency.dependency_type,
+            }),
+        }
+    }
+
+    Ok((inferred, suggestions))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_file_to_version(
+    req: HttpRequest,
+    url_data: web::Path<(VersionId,)>,
+    mut payload: Multipart,
+    client: Data<PgPool>,
+    redis: Data<RedisPool>,
+    file_host: Data<Arc<dyn FileHost + Send + Sync>>,
+    scanner: Data<Arc<dyn FileScanner + Send + Sync>>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, CreateError> {
+    let mut transaction = client.begin().await?;
+    let mut uploaded_files = Vec::new();
+
+    let version_id = models::VersionId::from(url_data.into_inner().0);
+
+    let result = upload_file_to_version_inner(
+        req,
+        &mut payload,
+        client,
+        &mut transaction,
+        redis.clone(),
+        &***file_host,
+        &mut uploaded_files,
+        version_id,
+        &session_queue,
+    )
+    .await;
+
+    if result.is_err() {
+        let undo_result =
+            super::project_creation::undo_uploads(&***file_host, &uploaded_files).await;
+        let rollback_result = transaction.rollback().await;
+
+        undo_result?;
+        if let Err(e) = rollback_result {
+            return Err(e.into());
+        }
+    } else {
+        transaction.commit().await?;
+        spawn_scan_version(
+            version_id,
+            client.get_ref().clone(),
+            redis.get_ref().clone(),
+            file_host.get_ref().clone(),
+            scanner.get_ref().clone(),
+        );
+    }
+
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn upload_file_to_version_inner(
+    req: HttpRequest,
+    payload: &mut Multipart,
+    client: Data<PgPool>,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: Data<RedisPool>,
+    file_host: &dyn FileHost,
+    uploaded_files: &mut Vec<UploadedFile>,
+    version_id: models::VersionId,
+    session_queue: &AuthQueue,
+) -> Result<HttpResponse, CreateError> {
+    let cdn_url = dotenvy::var("CDN_URL")?;
+
+    let mut initial_file_data: Option<InitialFileData> = None;
+    let mut file_builders: Vec<VersionFileBuilder> = Vec::new();
+
+    let user = get_user_from_headers(
+        &req,
+        &**client,
+        &redis,
+        session_queue,
+        Some(&[Scopes::VERSION_WRITE]),
+    )
+    .await?
+    .1;
+
+    let result = models::Version::get(version_id, &**client, &redis).await?;
+
+    let version = match result {
+        Some(v) => v,
+        None => {
+            return Err(CreateError::InvalidInput(
+                "An invalid version id was supplied".to_string(),
+            ));
+        }
+    };
+
+    let all_loaders = models::loader_fields::Loader::list(&mut **transaction, &redis).await?;
+
+    // TODO: this coded is reused a lot, it should be refactored into a function
+    let selected_loaders = version
+        .loaders
+        .iter()
+        .map(|x| {
+            all_loaders
+                .iter()
+                .find(|y| &y.loader == x)
+                .cloned()
+                .ok_or_else(|| CreateError::InvalidLoader(x.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let project = models::Project::get_id(version.inner.project_id, &mut **transaction, &redis)
+        .await?
+        .ok_or_else(|| {
+            CreateError::InvalidInput("An invalid project id was supplied".to_string())
+        })?;
+    if project.inner.status.is_read_only() {
+        return Err(CreateError::InvalidInput(
+            "This project is archived and can't be changed until it is unarchived!".to_string(),
+        ));
+    }
+
+    if !user.role.is_admin() {
+        let team_member = models::TeamMember::get_from_user_id_project(
+            version.inner.project_id,
+            user.id.into(),
             false,
             &mut **transaction,
         )
@@ -621,125 +1538,552 @@ async fn upload_file_to_version_inner(
         )
         .await?;
 
-        let organization_team_member = if let Some(organization) = &organization {
-            models::TeamMember::get_from_user_id(
-                organization.team_id,
-                user.id.into(),
-                &mut **transaction,
-            )
-            .await?
-        } else {
-            None
-        };
+        let organization_team_member = if let Some(organization) = &organization {
+            models::TeamMember::get_from_user_id(
+                organization.team_id,
+                user.id.into(),
+                &mut **transaction,
+            )
+            .await?
+        } else {
+            None
+        };
+
+        let permissions = ProjectPermissions::get_permissions_by_role(
+            &user.role,
+            &team_member,
+            &organization_team_member,
+        )
+        .unwrap_or_default();
+
+        if !permissions.contains(ProjectPermissions::UPLOAD_VERSION) {
+            return Err(CreateError::CustomAuthenticationError(
+                "You don't have permission to upload files to this version!".to_string(),
+            ));
+        }
+    }
+
+    let project_id = ProjectId(version.inner.project_id.0 as u64);
+    let mut error = None;
+    while let Some(item) = payload.next().await {
+        let mut field: Field = item?;
+
+        if error.is_some() {
+            continue;
+        }
+
+        let result = async {
+            let content_disposition = field.content_disposition().clone();
+            let name = content_disposition.get_name().ok_or_else(|| {
+                CreateError::MissingValueError("Missing content name".to_string())
+            })?;
+
+            if name == "data" {
+                let mut data = Vec::new();
+                while let Some(chunk) = field.next().await {
+                    data.extend_from_slice(&chunk?);
+                }
+                let file_data: InitialFileData = serde_json::from_slice(&data)?;
+
+                initial_file_data = Some(file_data);
+                return Ok(());
+            }
+
+            let file_data = initial_file_data.as_ref().ok_or_else(|| {
+                CreateError::InvalidInput(String::from("`data` field must come before file fields"))
+            })?;
+
+            let loaders = selected_loaders
+                .iter()
+                .map(|x| Loader(x.loader.clone()))
+                .collect::<Vec<_>>();
+
+            let mut dependencies = version
+                .dependencies
+                .iter()
+                .map(|x| DependencyBuilder {
+                    project_id: x.project_id,
+                    version_id: x.version_id,
+                    file_name: x.file_name.clone(),
+                    dependency_type: x.dependency_type.clone(),
+                    inferred: x.inferred,
+                })
+                .collect();
+
+            upload_file(
+                &mut field,
+                file_host,
+                0,
+                uploaded_files,
+                &mut file_builders,
+                &mut dependencies,
+                &cdn_url,
+                &content_disposition,
+                project_id,
+                version_id.into(),
+                &version.version_fields,
+                loaders,
+                true,
+                false,
+                file_data.file_types.get(name).copied().flatten(),
+                file_data.allow_incompatible,
+                transaction,
+                &redis,
+            )
+            .await?;
+
+            Ok(())
+        }
+        .await;
+
+        if result.is_err() {
+            error = result.err();
+        }
+    }
+
+    if let Some(error) = error {
+        return Err(error);
+    }
+
+    if file_builders.is_empty() {
+        return Err(CreateError::InvalidInput(
+            "At least one file must be specified".to_string(),
+        ));
+    }
+
+    // Versions which are already flagged already have a report open
+    if !version.inner.flagged_reupload {
+        flag_reupload(
+            version_id,
+            version.inner.project_id,
+            &sha1_hashes(&file_builders),
+            user.id.into(),
+            transaction,
+            &redis,
+        )
+        .await?;
+    }
+    for file in file_builders {
+        file.insert(version_id, &mut *transaction).await?;
+    }
+
+    // Clear version cache
+    models::Version::clear_cache(&version, &redis).await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+// The size of every chunk of a resumable upload, except for the final one
+pub const UPLOAD_CHUNK_SIZE: i32 = 5 * (1 << 20);
+// The number of resumable uploads a user can have in progress at once
+pub const MAX_PENDING_UPLOADS: i64 = 5;
+// The combined size of the resumable uploads a user can have in progress at once, in bytes
+pub const MAX_PENDING_UPLOAD_BYTES: i64 = 2 * MAX_FILE_SIZE as i64;
+
+#[derive(Serialize, Deserialize, Validate, Clone)]
+pub struct StartUpload {
+    #[validate(length(min = 1, max = 2048))]
+    pub file_name: String,
+    pub size: u64,
+    /// The SHA1 hash of the whole file, checked once every chunk has been uploaded
+    #[validate(length(equal = 40))]
+    pub sha1: String,
+}
+
+pub async fn upload_start(
+    req: HttpRequest,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    session_queue: Data<AuthQueue>,
+    start_upload: web::Json<StartUpload>,
+) -> Result<HttpResponse, CreateError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::VERSION_CREATE]),
+    )
+    .await?
+    .1;
+
+    start_upload
+        .validate()
+        .map_err(|err| CreateError::ValidationError(validation_errors_to_string(err, None)))?;
+
+    if start_upload.size == 0 || start_upload.size > MAX_FILE_SIZE as u64 {
+        return Err(CreateError::InvalidInput(
+            "Project files must be between 1 byte and 500MiB in size".to_string(),
+        ));
+    }
+
+    if start_upload.file_name.contains('/') {
+        return Err(CreateError::InvalidInput(
+            "File names must not contain slashes!".to_string(),
+        ));
+    }
+
+    let file_extension = get_file_extension(&start_upload.file_name)?;
+    if crate::util::ext::project_file_type(file_extension).is_none() {
+        return Err(CreateError::InvalidFileType(file_extension.to_string()));
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    let (pending_count, pending_bytes) =
+        PendingUpload::get_user_usage(user.id.into(), &mut *transaction).await?;
+    if pending_count >= MAX_PENDING_UPLOADS {
+        return Err(CreateError::InvalidInput(format!(
+            "You can only have {MAX_PENDING_UPLOADS} uploads in progress at once"
+        )));
+    }
+    if pending_bytes + start_upload.size as i64 > MAX_PENDING_UPLOAD_BYTES {
+        return Err(CreateError::InvalidInput(
+            "Your uploads in progress exceed the maximum of 1000MiB combined".to_string(),
+        ));
+    }
+
+    let upload = PendingUpload {
+        id: models::generate_pending_upload_id(&mut transaction).await?,
+        user_id: user.id.into(),
+        file_name: start_upload.file_name.clone(),
+        size: start_upload.size as i64,
+        sha1: start_upload.sha1.to_lowercase(),
+        chunk_size: UPLOAD_CHUNK_SIZE,
+        created: Utc::now(),
+    };
+    upload.insert(&mut transaction).await?;
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(uploads::PendingUpload::from(upload)))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UploadChunkQuery {
+    /// The SHA1 hash of this chunk's bytes
+    pub sha1: String,
+}
+
+pub async fn upload_chunk(
+    req: HttpRequest,
+    info: web::Path<(PendingUploadId, u32)>,
+    web::Query(query): web::Query<UploadChunkQuery>,
+    mut payload: web::Payload,
+    pool: Data<PgPool>,
+    redis: Data<RedisPool>,
+    file_host: Data<Arc<dyn FileHost + Send + Sync>>,
+    session_queue: Data<AuthQueue>,
+) -> Result<HttpResponse, CreateError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::VERSION_CREATE]),
+    )
+    .await?
+    .1;
+
+    let (upload_id, chunk_index) = info.into_inner();
+    let upload = get_pending_upload(upload_id, &user, &**pool).await?;
+
+    let chunk_index = chunk_index as i32;
+    if chunk_index >= upload.chunk_count() {
+        return Err(CreateError::InvalidInput(format!(
+            "Chunk index {chunk_index} is out of range for an upload of {} chunks",
+            upload.chunk_count()
+        )));
+    }
+
+    let expected_len = upload.expected_chunk_len(chunk_index);
+    let data = read_from_payload(
+        &mut payload,
+        expected_len + 1,
+        "Chunk exceeds the chunk size of this upload",
+    )
+    .await
+    .map_err(|_| {
+        CreateError::InvalidInput("Chunk exceeds the chunk size of this upload".to_string())
+    })?;
+
+    if data.len() != expected_len {
+        return Err(CreateError::InvalidInput(format!(
+            "Chunk {chunk_index} must be exactly {expected_len} bytes long"
+        )));
+    }
+
+    if sha1::Sha1::from(&data).hexdigest() != query.sha1.to_lowercase() {
+        return Err(CreateError::InvalidInput(format!(
+            "The hash of chunk {chunk_index} does not match the provided hash"
+        )));
+    }
+
+    // Chunks are staged under the private prefix, so they aren't served by the CDN. Every
+    // attempt gets its own object, so a retried chunk never overwrites one being assembled.
+    let staged_name = format!(
+        "{PRIVATE_PREFIX}uploads/{}/{chunk_index}-{}",
+        upload.id.0,
+        ChaCha20Rng::from_entropy()
+            .sample_iter(&Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect::<String>()
+    );
+    let staged = file_host
+        .upload_file("application/octet-stream", &staged_name, data.freeze())
+        .await?;
+    let chunk = PendingUploadChunk {
+        chunk_index,
+        file_id: staged.file_id,
+        file_name: staged.file_name,
+    };
+
+    let mut transaction = pool.begin().await?;
+    let replaced = PendingUpload::get_chunks(upload.id, &mut *transaction)
+        .await?
+        .into_iter()
+        .find(|x| x.chunk_index == chunk_index);
+    PendingUpload::upsert_chunk(upload.id, &chunk, &mut *transaction).await?;
+    transaction.commit().await?;
+
+    if let Some(replaced) = replaced {
+        if let Err(e) = file_host
+            .delete_file_version(&replaced.file_id, &replaced.file_name)
+            .await
+        {
+            warn!(
+                "Failed to remove replaced chunk {}: {e}",
+                replaced.file_name
+            );
+        }
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_complete(
+    req: HttpRequest,
+    info: web::Path<(PendingUploadId,)>,
+    version_data: web::Json<InitialVersionData>,
+    client: Data<PgPool>,
+    redis: Data<RedisPool>,
+    file_host: Data<Arc<dyn FileHost + Send + Sync>>,
+    scanner: Data<Arc<dyn FileScanner + Send + Sync>>,
+    session_queue: Data<AuthQueue>,
+    webhook_queue: Data<WebhookQueue>,
+) -> Result<HttpResponse, CreateError> {
+    let mut transaction = client.begin().await?;
+    let mut uploaded_files = Vec::new();
+
+    let result = upload_complete_inner(
+        req,
+        info.into_inner().0,
+        version_data.into_inner(),
+        &mut transaction,
+        &redis,
+        &***file_host,
+        &mut uploaded_files,
+        &client,
+        &session_queue,
+    )
+    .await;
+
+    // Deliveries to the project's webhooks are queued with the version
+    let result = match result {
+        Ok((version, chunks)) => webhook_queue
+            .add_versions(&[version.id.into()], &mut *transaction)
+            .await
+            .map(|()| (version, chunks))
+            .map_err(CreateError::from),
+        Err(err) => Err(err),
+    };
+
+    match result {
+        Ok((version, chunks)) => {
+            transaction.commit().await?;
+            remove_staged_chunks(&***file_host, &chunks).await;
+            spawn_scan_version(
+                version.id.into(),
+                client.get_ref().clone(),
+                redis.get_ref().clone(),
+                file_host.get_ref().clone(),
+                scanner.get_ref().clone(),
+            );
+
+            if version.status.is_listed() {
+                spawn_notify_followers(
+                    version.project_id.into(),
+                    version.id.into(),
+                    version.author_id.into(),
+                    client.get_ref().clone(),
+                    redis.get_ref().clone(),
+                );
+            }
 
-        let permissions = ProjectPermissions::get_permissions_by_role(
-            &user.role,
-            &team_member,
-            &organization_team_member,
-        )
-        .unwrap_or_default();
+            Ok(HttpResponse::Ok().json(version))
+        }
+        Err(err) => {
+            let undo_result =
+                super::project_creation::undo_uploads(&***file_host, &uploaded_files).await;
+            let rollback_result = transaction.rollback().await;
+
+            undo_result?;
+            if let Err(e) = rollback_result {
+                return Err(e.into());
+            }
 
-        if !permissions.contains(ProjectPermissions::UPLOAD_VERSION) {
-            return Err(CreateError::CustomAuthenticationError(
-                "You don't have permission to upload files to this version!".to_string(),
-            ));
+            Err(err)
         }
     }
+}
 
-    let project_id = ProjectId(version.inner.project_id.0 as u64);
-    let mut error = None;
-    while let Some(item) = payload.next().await {
-        let mut field: Field = item?;
+#[allow(clippy::too_many_arguments)]
+async fn upload_complete_inner(
+    req: HttpRequest,
+    upload_id: PendingUploadId,
+    version_data: InitialVersionData,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+    file_host: &dyn FileHost,
+    uploaded_files: &mut Vec<UploadedFile>,
+    pool: &PgPool,
+    session_queue: &AuthQueue,
+) -> Result<(Version, Vec<PendingUploadChunk>), CreateError> {
+    let cdn_url = dotenvy::var("CDN_URL")?;
 
-        if error.is_some() {
-            continue;
-        }
+    let user = get_user_from_headers(
+        &req,
+        pool,
+        redis,
+        session_queue,
+        Some(&[Scopes::VERSION_CREATE]),
+    )
+    .await?
+    .1;
 
-        let result = async {
-            let content_disposition = field.content_disposition().clone();
-            let name = content_disposition.get_name().ok_or_else(|| {
-                CreateError::MissingValueError("Missing content name".to_string())
-            })?;
+    let upload = get_pending_upload(upload_id, &user, &mut **transaction).await?;
 
-            if name == "data" {
-                let mut data = Vec::new();
-                while let Some(chunk) = field.next().await {
-                    data.extend_from_slice(&chunk?);
-                }
-                let file_data: InitialFileData = serde_json::from_slice(&data)?;
+    let chunks = PendingUpload::get_chunks(upload.id, &mut **transaction).await?;
+    if chunks.len() as i32 != upload.chunk_count() {
+        return Err(CreateError::InvalidInput(format!(
+            "Only {} of {} chunks have been uploaded",
+            chunks.len(),
+            upload.chunk_count()
+        )));
+    }
 
-                initial_file_data = Some(file_data);
-                return Ok(());
-            }
+    let mut data = BytesMut::with_capacity(upload.size as usize);
+    let mut hasher = sha1::Sha1::new();
+    for chunk in &chunks {
+        let bytes = file_host.download_file(&chunk.file_name).await?;
+        hasher.update(&bytes);
+        data.extend_from_slice(&bytes);
+    }
 
-            let file_data = initial_file_data.as_ref().ok_or_else(|| {
-                CreateError::InvalidInput(String::from("`data` field must come before file fields"))
-            })?;
+    if data.len() as i64 != upload.size || hasher.hexdigest() != upload.sha1 {
+        return Err(CreateError::InvalidInput(
+            "The hash of the uploaded file does not match the hash it was started with".to_string(),
+        ));
+    }
 
-            let loaders = selected_loaders
-                .iter()
-                .map(|x| Loader(x.loader.clone()))
-                .collect::<Vec<_>>();
+    let (mut builder, selected_loaders) =
+        create_version_builder(&req, &version_data, &user, transaction, redis).await?;
 
-            let mut dependencies = version
-                .dependencies
-                .iter()
-                .map(|x| DependencyBuilder {
-                    project_id: x.project_id,
-                    version_id: x.version_id,
-                    file_name: x.file_name.clone(),
-                    dependency_type: x.dependency_type.clone(),
-                })
-                .collect();
+    let loaders = selected_loaders
+        .iter()
+        .map(|x| Loader(x.loader.clone()))
+        .collect::<Vec<_>>();
+
+    upload_file_data(
+        data,
+        &upload.file_name,
+        file_host,
+        1,
+        uploaded_files,
+        &mut builder.files,
+        &mut builder.dependencies,
+        &cdn_url,
+        builder.project_id.into(),
+        builder.version_id.into(),
+        &builder.version_fields,
+        loaders,
+        false,
+        true,
+        version_data
+            .file_types
+            .get(&upload.file_name)
+            .copied()
+            .flatten(),
+        version_data.allow_incompatible,
+        transaction,
+        redis,
+    )
+    .await?;
 
-            upload_file(
-                &mut field,
-                file_host,
-                0,
-                uploaded_files,
-                &mut file_builders,
-                &mut dependencies,
-                &cdn_url,
-                &content_disposition,
-                project_id,
-                version_id.into(),
-                &version.version_fields,
-                loaders,
-                true,
-                false,
-                file_data.file_types.get(name).copied().flatten(),
-                transaction,
-                &redis,
-            )
-            .await?;
+    PendingUpload::remove(upload.id, transaction).await?;
 
-            Ok(())
-        }
-        .await;
+    let version = finish_version_creation(
+        version_data,
+        builder,
+        selected_loaders,
+        &user,
+        transaction,
+        redis,
+    )
+    .await?;
 
-        if result.is_err() {
-            error = result.err();
+    Ok((version, chunks))
+}
+
+// Staged chunks which can't be removed are left behind in the private prefix, where they
+// are never served
+async fn remove_staged_chunks(
+    file_host: &(dyn FileHost + Send + Sync),
+    chunks: &[PendingUploadChunk],
+) {
+    for chunk in chunks {
+        if let Err(e) = file_host
+            .delete_file_version(&chunk.file_id, &chunk.file_name)
+            .await
+        {
+            warn!("Failed to remove staged chunk {}: {e}", chunk.file_name);
         }
     }
+}
 
-    if let Some(error) = error {
-        return Err(error);
-    }
+/// Removes the uploads which were started more than 24 hours ago and never completed,
+/// along with their staged chunks
+pub async fn purge_expired_uploads(
+    pool: &PgPool,
+    file_host: &(dyn FileHost + Send + Sync),
+) -> Result<(), CreateError> {
+    for upload_id in PendingUpload::get_expired(pool).await? {
+        let chunks = PendingUpload::get_chunks(upload_id, pool).await?;
+        remove_staged_chunks(file_host, &chunks).await;
 
-    if file_builders.is_empty() {
-        return Err(CreateError::InvalidInput(
-            "At least one file must be specified".to_string(),
-        ));
-    } else {
-        for file in file_builders {
-            file.insert(version_id, &mut *transaction).await?;
-        }
+        let mut transaction = pool.begin().await?;
+        PendingUpload::remove(upload_id, &mut transaction).await?;
+        transaction.commit().await?;
     }
 
-    // Clear version cache
-    models::Version::clear_cache(&version, &redis).await?;
+    Ok(())
+}
 
-    Ok(HttpResponse::NoContent().body(""))
+// Uploads are only visible to the user who started them
+async fn get_pending_upload<'a, E>(
+    upload_id: PendingUploadId,
+    user: &User,
+    exec: E,
+) -> Result<PendingUpload, CreateError>
+where
+    E: sqlx::Executor<'a, Database = sqlx::Postgres>,
+{
+    PendingUpload::get(upload_id.into(), exec)
+        .await?
+        .filter(|x| x.user_id == user.id.into())
+        .ok_or_else(|| CreateError::InvalidInput("The specified upload does not exist".to_string()))
 }
 
 // This function is used for adding a file to a version, uploading the initial
@@ -761,10 +2105,64 @@ pub async fn upload_file(
     ignore_primary: bool,
     force_primary: bool,
     file_type: Option<FileType>,
+    allow_incompatible: bool,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<(), CreateError> {
+    let (file_name, _) = get_name_ext(content_disposition)?;
+
+    let data = read_from_field(
+        field, MAX_FILE_SIZE,
+        "Project file exceeds the maximum of 500MiB. Contact a moderator or admin to request permission to upload larger files."
+    ).await?;
+
+    upload_file_data(
+        data,
+        file_name,
+        file_host,
+        total_files_len,
+        uploaded_files,
+        version_files,
+        dependencies,
+        cdn_url,
+        project_id,
+        version_id,
+        version_fields,
+        loaders,
+        ignore_primary,
+        force_primary,
+        file_type,
+        allow_incompatible,
+        transaction,
+        redis,
+    )
+    .await
+}
+
+// Validates and uploads a file which has already been read into memory, either from a
+// multipart field or from the chunks of a resumable upload
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_file_data(
+    data: BytesMut,
+    file_name: &str,
+    file_host: &dyn FileHost,
+    total_files_len: usize,
+    uploaded_files: &mut Vec<UploadedFile>,
+    version_files: &mut Vec<VersionFileBuilder>,
+    dependencies: &mut Vec<DependencyBuilder>,
+    cdn_url: &str,
+    project_id: ProjectId,
+    version_id: VersionId,
+    version_fields: &[VersionField],
+    loaders: Vec<Loader>,
+    ignore_primary: bool,
+    force_primary: bool,
+    file_type: Option<FileType>,
+    allow_incompatible: bool,
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     redis: &RedisPool,
 ) -> Result<(), CreateError> {
-    let (file_name, file_extension) = get_name_ext(content_disposition)?;
+    let file_extension = get_file_extension(file_name)?;
 
     if file_name.contains('/') {
         return Err(CreateError::InvalidInput(
@@ -775,32 +2173,26 @@ pub async fn upload_file(
     let content_type = crate::util::ext::project_file_type(file_extension)
         .ok_or_else(|| CreateError::InvalidFileType(file_extension.to_string()))?;
 
-    let data = read_from_field(
-        field, 500 * (1 << 20),
-        "Project file exceeds the maximum of 500MiB. Contact a moderator or admin to request permission to upload larger files."
-    ).await?;
-
-    let hash = sha1::Sha1::from(&data).hexdigest();
-    let exists = sqlx::query!(
-        "
-        SELECT EXISTS(SELECT 1 FROM hashes h
-        INNER JOIN files f ON f.id = h.file_id
-        INNER JOIN versions v ON v.id = f.version_id
-        WHERE h.algorithm = $2 AND h.hash = $1 AND v.mod_id != $3)
-        ",
-        hash.as_bytes(),
-        "sha1",
-        project_id.0 as i64
+    let context = validation_context(
+        file_name,
+        file_extension,
+        &loaders,
+        file_type,
+        transaction,
+        redis,
     )
-    .fetch_one(&mut **transaction)
-    .await?
-    .exists
-    .unwrap_or(false);
-
-    if exists {
-        return Err(CreateError::InvalidInput(
-            "Duplicate files are not allowed to be uploaded to Modrinth!".to_string(),
-        ));
+    .await?;
+    let issues = {
+        let data: bytes::Bytes = data.clone().into();
+        web::block(move || FILE_VALIDATORS.validate(&data, &context))
+            .await
+            .map_err(ValidationError::from)?
+    };
+    let (fatal, mut validation_warnings): (Vec<_>, Vec<_>) = issues
+        .into_iter()
+        .partition(|x| x.severity == IssueSeverity::Fatal);
+    if let Some(issue) = fatal.into_iter().next() {
+        return Err(ValidationError::InvalidInput(issue.message.into()).into());
     }
 
     let validation_result = validate_file(
@@ -819,26 +2211,45 @@ pub async fn upload_file(
         ref files,
     } = validation_result
     {
-        if dependencies.is_empty() {
-            let hashes: Vec<Vec<u8>> = format
-                .files
-                .iter()
-                .filter_map(|x| x.hashes.get(&PackFileHash::Sha1))
-                .map(|x| x.as_bytes().to_vec())
-                .collect();
+        let hashes: Vec<Vec<u8>> = format
+            .files
+            .iter()
+            .filter_map(|x| x.hashes.get(&PackFileHash::Sha1))
+            .map(|x| x.as_bytes().to_vec())
+            .collect();
+
+        let res = sqlx::query!(
+            "
+                SELECT v.id version_id, v.mod_id project_id, h.hash hash FROM hashes h
+                INNER JOIN files f on h.file_id = f.id
+                INNER JOIN versions v on f.version_id = v.id
+                WHERE h.algorithm = 'sha1' AND h.hash = ANY($1)
+                ",
+            &*hashes
+        )
+        .fetch_all(&mut **transaction)
+        .await?;
 
-            let res = sqlx::query!(
-                "
-                    SELECT v.id version_id, v.mod_id project_id, h.hash hash FROM hashes h
-                    INNER JOIN files f on h.file_id = f.id
-                    INNER JOIN versions v on f.version_id = v.id
-                    WHERE h.algorithm = 'sha1' AND h.hash = ANY($1)
-                    ",
-                &*hashes
-            )
-            .fetch_all(&mut **transaction)
-            .await?;
+        let hashed_versions = res
+            .iter()
+            .map(|x| (x.hash.clone(), models::VersionId(x.version_id)))
+            .collect::<HashMap<_, _>>();
+        let incompatible =
+            incompatible_pack_files(format, &hashed_versions, cdn_url, transaction, redis).await?;
+        if !incompatible.is_empty() {
+            if !allow_incompatible {
+                return Err(CreateError::IncompatiblePackFiles(incompatible));
+            }
+
+            validation_warnings.extend(incompatible.into_iter().map(|x| ValidationIssue {
+                severity: IssueSeverity::Warning,
+                code: "incompatible_pack_file".to_string(),
+                message: x.to_string(),
+                file_name: file_name.to_string(),
+            }));
+        }
 
+        if dependencies.is_empty() {
             for file in &format.files {
                 if let Some(dep) = res.iter().find(|x| {
                     Some(&*x.hash) == file.hashes.get(&PackFileHash::Sha1).map(|x| x.as_bytes())
@@ -848,6 +2259,7 @@ pub async fn upload_file(
                         version_id: Some(models::VersionId(dep.version_id)),
                         file_name: None,
                         dependency_type: DependencyType::Embedded.to_string(),
+                        inferred: false,
                     });
                 } else if let Some(first_download) = file.downloads.first() {
                     dependencies.push(DependencyBuilder {
@@ -861,6 +2273,7 @@ pub async fn upload_file(
                                 .to_string(),
                         ),
                         dependency_type: DependencyType::Embedded.to_string(),
+                        inferred: false,
                     });
                 }
             }
@@ -872,6 +2285,7 @@ pub async fn upload_file(
                         version_id: None,
                         file_name: Some(file.to_string()),
                         dependency_type: DependencyType::Embedded.to_string(),
+                        inferred: false,
                     });
                 }
             }
@@ -879,6 +2293,15 @@ pub async fn upload_file(
     }
 
     let data = data.freeze();
+    let mod_metadata = if file_extension == "jar" {
+        let data = data.clone();
+        web::block(move || read_mod_metadata(data))
+            .await
+            .ok()
+            .flatten()
+    } else {
+        None
+    };
     let primary = (version_files.iter().all(|x| !x.primary) && !ignore_primary)
         || force_primary
         || total_files_len == 1;
@@ -933,25 +2356,190 @@ pub async fn upload_file(
         primary,
         size: upload_data.content_length,
         file_type,
+        mod_metadata,
+        validation_warnings,
     });
 
     Ok(())
 }
 
+/// The loaders whose versions are checked against the loaders of the packs they're in. Versions
+/// for other loaders, such as resource packs and shaders, are used whatever the pack's loaders.
+const PACK_MOD_LOADERS: &[&str] = &["forge", "neoforge", "fabric", "quilt"];
+
+// Finds the files of a pack hosted on Modrinth whose versions don't support the pack's loaders
+// or game version. Files are matched to their versions by their SHA1 hash, or by their download
+// URL if it's on the CDN. A file whose version doesn't exist is always an error.
+async fn incompatible_pack_files(
+    format: &PackFormat,
+    hashed_versions: &HashMap<Vec<u8>, models::VersionId>,
+    cdn_url: &str,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<Vec<IncompatiblePackFile>, CreateError> {
+    let referenced = format
+        .files
+        .iter()
+        .filter_map(|file| {
+            file.hashes
+                .get(&PackFileHash::Sha1)
+                .and_then(|x| hashed_versions.get(x.as_bytes()).copied())
+                .or_else(|| {
+                    file.downloads
+                        .iter()
+                        .find_map(|x| cdn_version_id(x, cdn_url))
+                })
+                .map(|version_id| (file, version_id))
+        })
+        .collect::<Vec<_>>();
+    if referenced.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut expected_loaders = Vec::new();
+    let mut expected_game_versions = Vec::new();
+    for (dependency, value) in &format.dependencies {
+        if let Some(loader) = dependency.loader() {
+            expected_loaders.push(loader.to_string());
+        } else {
+            expected_game_versions.push(value.clone());
+        }
+    }
+    expected_loaders.sort();
+    // Quilt loads Fabric mods
+    let accepted_loaders: Vec<String> = if expected_loaders.iter().any(|x| x == "quilt") {
+        expected_loaders
+            .iter()
+            .cloned()
+            .chain(std::iter::once("fabric".to_string()))
+            .collect()
+    } else {
+        expected_loaders.clone()
+    };
+
+    let version_ids = referenced.iter().map(|x| x.1).collect::<Vec<_>>();
+    let versions = models::Version::get_many(&version_ids, &mut **transaction, redis)
+        .await?
+        .into_iter()
+        .map(|x| (x.inner.id, x))
+        .collect::<HashMap<_, _>>();
+
+    let mut incompatible = Vec::new();
+    for (file, version_id) in referenced {
+        let version = versions.get(&version_id).ok_or_else(|| {
+            CreateError::InvalidInput(format!(
+                "Pack file {} is from version {}, which doesn't exist",
+                file.path,
+                VersionId::from(version_id)
+            ))
+        })?;
+
+        let actual_game_versions = version
+            .version_fields
+            .iter()
+            .find(|x| x.field_name == MinecraftGameVersion::FIELD_NAME)
+            .map(|x| x.value.as_strings())
+            .unwrap_or_default();
+        let game_versions_match = actual_game_versions.is_empty()
+            || expected_game_versions
+                .iter()
+                .all(|x| actual_game_versions.contains(x));
+
+        let loaders_match = expected_loaders.is_empty()
+            || !version
+                .loaders
+                .iter()
+                .any(|x| PACK_MOD_LOADERS.contains(&x.as_str()))
+            || version.loaders.iter().any(|x| accepted_loaders.contains(x));
+
+        if !game_versions_match || !loaders_match {
+            incompatible.push(IncompatiblePackFile {
+                path: file.path.clone(),
+                version_id: version_id.into(),
+                expected_loaders: expected_loaders.clone(),
+                actual_loaders: version.loaders.clone(),
+                expected_game_versions: expected_game_versions.clone(),
+                actual_game_versions,
+            });
+        }
+    }
+
+    Ok(incompatible)
+}
+
+// The version of a file on the CDN, from its URL, which is of the form
+// `{cdn_url}/data/{project_id}/versions/{version_id}/{file_name}`
+fn cdn_version_id(url: &str, cdn_url: &str) -> Option<models::VersionId> {
+    let mut segments = url
+        .strip_prefix(cdn_url)?
+        .strip_prefix("/data/")?
+        .split('/');
+    let (_, versions, version_id) = (segments.next()?, segments.next()?, segments.next()?);
+    if versions != "versions" {
+        return None;
+    }
+
+    parse_base62(version_id)
+        .ok()
+        .map(|x| models::VersionId(x as i64))
+}
+
+// Gets what the file validators need to know about the file. Resource packs are checked as
+// such whatever the version's loaders are.
+async fn validation_context(
+    file_name: &str,
+    file_extension: &str,
+    loaders: &[Loader],
+    file_type: Option<FileType>,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    redis: &RedisPool,
+) -> Result<ValidationContext, CreateError> {
+    let (loaders, project_types) = if matches!(
+        file_type,
+        Some(FileType::RequiredResourcePack | FileType::OptionalResourcePack)
+    ) {
+        (
+            vec!["minecraft".to_string()],
+            vec!["resourcepack".to_string()],
+        )
+    } else {
+        let loaders = loaders.iter().map(|x| x.0.clone()).collect::<Vec<_>>();
+        let project_types = models::loader_fields::Loader::list(&mut **transaction, redis)
+            .await?
+            .into_iter()
+            .filter(|x| loaders.contains(&x.loader))
+            .flat_map(|x| x.supported_project_types)
+            .unique()
+            .collect();
+        (loaders, project_types)
+    };
+
+    Ok(ValidationContext {
+        file_name: file_name.to_string(),
+        file_extension: file_extension.to_string(),
+        loaders,
+        project_types,
+    })
+}
+
 pub fn get_name_ext(
     content_disposition: &actix_web::http::header::ContentDisposition,
 ) -> Result<(&str, &str), CreateError> {
     let file_name = content_disposition
         .get_filename()
         .ok_or_else(|| CreateError::MissingValueError("Missing content file name".to_string()))?;
-    let file_extension = if let Some(last_period) = file_name.rfind('.') {
-        file_name.get((last_period + 1)..).unwrap_or("")
+    let file_extension = get_file_extension(file_name)?;
+    Ok((file_name, file_extension))
+}
+
+pub fn get_file_extension(file_name: &str) -> Result<&str, CreateError> {
+    if let Some(last_period) = file_name.rfind('.') {
+        Ok(file_name.get((last_period + 1)..).unwrap_or(""))
     } else {
-        return Err(CreateError::MissingValueError(
+        Err(CreateError::MissingValueError(
             "Missing content file extension".to_string(),
-        ));
-    };
-    Ok((file_name, file_extension))
+        ))
+    }
 }
 
 // Reused functionality between project_creation and version_creation
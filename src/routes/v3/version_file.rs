@@ -1,31 +1,56 @@
+use super::audit_log::request_ip;
 use super::ApiError;
-use crate::auth::checks::{filter_visible_versions, is_visible_version};
-use crate::auth::{filter_visible_projects, get_user_from_headers};
+use crate::auth::checks::{
+    filter_visible_version_ids, filter_visible_versions, is_visible_version,
+};
+use crate::auth::validate::{check_pat_project_restriction, check_pat_project_restrictions};
+use crate::auth::{
+    check_is_moderator_from_headers, filter_visible_projects, get_user_from_headers,
+};
+use crate::database::models::audit_log_item::AuditLogBuilder;
 use crate::database::redis::RedisPool;
+use crate::models::audit_log::{AuditAction, AuditTargetType};
 use crate::models::ids::VersionId;
 use crate::models::pats::Scopes;
-use crate::models::projects::VersionType;
+use crate::models::projects::{VersionStatus, VersionType};
 use crate::models::teams::ProjectPermissions;
 use crate::queue::session::AuthQueue;
+use crate::util::validate::validation_errors_to_string;
 use crate::{database, models};
 use actix_web::{web, HttpRequest, HttpResponse};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use validator::Validate;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("version_file")
+            .route(
+                "upload/start",
+                web::post().to(super::version_creation::upload_start),
+            )
+            .route(
+                "upload/{upload_id}/complete",
+                web::post().to(super::version_creation::upload_complete),
+            )
+            .route(
+                "upload/{upload_id}/{chunk_index}",
+                web::put().to(super::version_creation::upload_chunk),
+            )
             .route("{version_id}", web::get().to(get_version_from_hash))
             .route("{version_id}/update", web::post().to(get_update_from_hash))
             .route("project", web::post().to(get_projects_from_hashes))
             .route("{version_id}", web::delete().to(delete_file))
-            .route("{version_id}/download", web::get().to(download_version)),
+            .route("{version_id}/download", web::get().to(download_version))
+            .route("{version_id}/takedown", web::post().to(file_takedown))
+            .route("{version_id}/takedown", web::delete().to(file_reinstate)),
     );
     cfg.service(
         web::scope("version_files")
             .route("update", web::post().to(update_files))
+            .route("update", web::get().to(update_files_for_loader))
             .route("update_individual", web::post().to(update_individual_files))
             .route("", web::post().to(get_versions_from_hashes)),
     );
@@ -63,6 +88,7 @@ pub async fn get_version_from_hash(
     )
     .await?;
     if let Some(file) = file {
+        check_pat_project_restriction(&req, file.project_id)?;
         let version = database::models::Version::get(file.version_id, &**pool, &redis).await?;
         if let Some(version) = version {
             if !is_visible_version(&version.inner, &user_option, &pool, &redis).await? {
@@ -145,6 +171,7 @@ pub async fn get_update_from_hash(
     )
     .await?
     {
+        check_pat_project_restriction(&req, file.project_id)?;
         if let Some(project) =
             database::models::Project::get_id(file.project_id, &**pool, &redis).await?
         {
@@ -226,6 +253,7 @@ pub async fn get_versions_from_hashes(
         &redis,
     )
     .await?;
+    check_pat_project_restrictions(&req, files.iter().map(|x| x.project_id))?;
 
     let version_ids = files.iter().map(|x| x.version_id).collect::<Vec<_>>();
     let versions_data = filter_visible_versions(
@@ -278,6 +306,7 @@ pub async fn get_projects_from_hashes(
         &redis,
     )
     .await?;
+    check_pat_project_restrictions(&req, files.iter().map(|x| x.project_id))?;
 
     let project_ids = files.iter().map(|x| x.project_id).collect::<Vec<_>>();
 
@@ -338,6 +367,7 @@ pub async fn update_files(
         &redis,
     )
     .await?;
+    check_pat_project_restrictions(&req, files.iter().map(|x| x.project_id))?;
 
     let projects = database::models::Project::get_many_ids(
         &files.iter().map(|x| x.project_id).collect::<Vec<_>>(),
@@ -407,6 +437,120 @@ pub async fn update_files(
     Ok(HttpResponse::Ok().json(response))
 }
 
+#[derive(Deserialize, Validate)]
+pub struct LoaderUpdateData {
+    pub algorithm: Option<String>, // Defaults to calculation based on size of hash
+    #[validate(length(min = 1, max = 1000))]
+    pub hashes: Vec<String>,
+    pub loader: String,
+    #[validate(length(min = 1))]
+    pub game_versions: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LoaderUpdateResponse {
+    pub updates: HashMap<String, models::projects::Version>,
+    // Hashes which don't match any file the user can see
+    pub unknown: Vec<String>,
+}
+
+pub async fn update_files_for_loader(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    update_data: web::Json<LoaderUpdateData>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    update_data
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let user_option = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::VERSION_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    let algorithm = update_data
+        .algorithm
+        .clone()
+        .unwrap_or_else(|| default_algorithm_from_hashes(&update_data.hashes));
+    let files = database::models::Version::get_files_from_hash(
+        algorithm.clone(),
+        &update_data.hashes,
+        &**pool,
+        &redis,
+    )
+    .await?;
+    check_pat_project_restrictions(&req, files.iter().map(|x| x.project_id))?;
+
+    let candidates = database::models::Version::get_many_for_loader(
+        &files
+            .iter()
+            .map(|x| x.project_id)
+            .unique()
+            .collect::<Vec<_>>(),
+        &update_data.loader,
+        &update_data.game_versions,
+        &**pool,
+        &redis,
+    )
+    .await?;
+    let file_versions = database::models::Version::get_many(
+        &files.iter().map(|x| x.version_id).collect::<Vec<_>>(),
+        &**pool,
+        &redis,
+    )
+    .await?;
+
+    // Visibility of the candidates and of the hashed files' own versions is checked at once
+    let visible_ids = filter_visible_version_ids(
+        candidates
+            .iter()
+            .chain(file_versions.iter())
+            .map(|x| &x.inner)
+            .collect(),
+        &user_option,
+        &pool,
+        &redis,
+    )
+    .await?
+    .into_iter()
+    .collect::<HashSet<_>>();
+
+    let mut updates = HashMap::new();
+    let mut unknown = Vec::new();
+    for hash in &update_data.hashes {
+        let file = files.iter().find(|x| {
+            x.hashes.get(&algorithm) == Some(hash) && visible_ids.contains(&x.version_id)
+        });
+
+        if let Some(file) = file {
+            let newest = candidates
+                .iter()
+                .filter(|x| x.inner.project_id == file.project_id)
+                .filter(|x| visible_ids.contains(&x.inner.id))
+                .max();
+
+            if let Some(newest) = newest {
+                updates.insert(
+                    hash.clone(),
+                    models::projects::Version::from(newest.clone()),
+                );
+            }
+        } else {
+            unknown.push(hash.clone());
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(LoaderUpdateResponse { updates, unknown }))
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct FileUpdateData {
     pub hash: String,
@@ -459,6 +603,7 @@ pub async fn update_individual_files(
         &redis,
     )
     .await?;
+    check_pat_project_restrictions(&req, files.iter().map(|x| x.project_id))?;
 
     let projects = database::models::Project::get_many_ids(
         &files.iter().map(|x| x.project_id).collect::<Vec<_>>(),
@@ -563,6 +708,7 @@ pub async fn delete_file(
     .await?;
 
     if let Some(row) = file {
+        check_pat_project_restriction(&req, row.project_id)?;
         if !user.role.is_admin() {
             let team_member = database::models::TeamMember::get_from_user_id_version(
                 row.version_id,
@@ -688,9 +834,15 @@ pub async fn download_version(
     .await?;
 
     if let Some(file) = file {
+        check_pat_project_restriction(&req, file.project_id)?;
         let version = database::models::Version::get(file.version_id, &**pool, &redis).await?;
 
         if let Some(version) = version {
+            if file.withheld_at.is_some() {
+                return Err(ApiError::UnavailableForLegalReasons(
+                    "This file was taken down and can't be downloaded".to_string(),
+                ));
+            }
             if !is_visible_version(&version.inner, &user_option, &pool, &redis).await? {
                 return Err(ApiError::NotFound);
             }
@@ -705,3 +857,124 @@ pub async fn download_version(
         Err(ApiError::NotFound)
     }
 }
+
+pub async fn file_takedown(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    hash_query: web::Query<HashQuery>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    set_file_takedown(req, info, pool, redis, hash_query, session_queue, true).await
+}
+
+pub async fn file_reinstate(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    hash_query: web::Query<HashQuery>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    set_file_takedown(req, info, pool, redis, hash_query, session_queue, false).await
+}
+
+/// Takes down a file, or reinstates it. Withheld files can't be downloaded and are only listed to
+/// the project's team and moderators. Taking down the primary file of a version withholds the
+/// version as well, which stays withheld when the file is reinstated until a moderator reviews it.
+async fn set_file_takedown(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    hash_query: web::Query<HashQuery>,
+    session_queue: web::Data<AuthQueue>,
+    withheld: bool,
+) -> Result<HttpResponse, ApiError> {
+    let user = check_is_moderator_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::VERSION_WRITE]),
+    )
+    .await?;
+
+    let hash = info.into_inner().0.to_lowercase();
+    let algorithm = hash_query
+        .algorithm
+        .clone()
+        .unwrap_or_else(|| default_algorithm_from_hashes(&[hash.clone()]));
+    let file = database::models::Version::get_file_from_hash(
+        algorithm,
+        hash.clone(),
+        hash_query.version_id.map(|x| x.into()),
+        &**pool,
+        &redis,
+    )
+    .await?
+    .ok_or(ApiError::NotFound)?;
+    check_pat_project_restriction(&req, file.project_id)?;
+    let version = database::models::Version::get(file.version_id, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if file.withheld_at.is_some() == withheld {
+        return Ok(HttpResponse::NoContent().body(""));
+    }
+
+    let organization = database::models::Organization::get_associated_organization_project_id(
+        file.project_id,
+        &**pool,
+    )
+    .await?;
+
+    let mut transaction = pool.begin().await?;
+
+    database::models::Version::set_file_withheld(
+        file.id,
+        withheld.then_some(user.id.into()),
+        &mut *transaction,
+    )
+    .await?;
+
+    if withheld && file.primary && version.inner.status != VersionStatus::Withheld {
+        sqlx::query!(
+            "
+            UPDATE versions
+            SET status = $1, publish_at = NULL
+            WHERE id = $2
+            ",
+            VersionStatus::Withheld.as_str(),
+            version.inner.id as database::models::VersionId,
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    AuditLogBuilder {
+        actor_id: user.id.into(),
+        action: AuditAction::FileTakedown,
+        target_type: AuditTargetType::Version,
+        target_id: version.inner.id.0,
+        project_id: Some(version.inner.project_id),
+        organization_id: organization.map(|x| x.id),
+        summary: serde_json::json!({
+            "filename": file.filename,
+            "hash": hash,
+            "withheld": withheld,
+        }),
+        ip: request_ip(&req),
+    }
+    .insert(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    database::models::Version::clear_cache(&version, &redis).await?;
+    database::models::Project::clear_cache(version.inner.project_id, None, Some(true), &redis)
+        .await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
@@ -1,29 +1,50 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
+use super::audit_log::request_ip;
+use super::projects::check_not_archived;
+use super::version_creation::{spawn_notify_followers, spawn_scan_version};
 use super::ApiError;
-use crate::auth::checks::{filter_visible_versions, is_visible_project, is_visible_version};
-use crate::auth::get_user_from_headers;
+use crate::auth::checks::{
+    filter_enlisted_version_ids, filter_visible_version_ids, filter_visible_versions,
+    is_team_member_project, is_team_member_version, is_visible_project, is_visible_version,
+};
+use crate::auth::validate::check_pat_project_restrictions;
+use crate::auth::{
+    check_is_moderator_from_headers, filter_visible_projects, get_user_from_headers,
+};
 use crate::database;
+use crate::database::models::audit_log_item::AuditLogBuilder;
 use crate::database::models::loader_fields::{
     self, LoaderField, LoaderFieldEnumValue, VersionField,
 };
-use crate::database::models::version_item::{DependencyBuilder, LoaderVersion};
+use crate::database::models::version_item::{
+    find_dependency_cycles, DependencyBuilder, LoaderVersion, VersionMatrix, VersionPageFilters,
+};
 use crate::database::models::{image_item, Organization};
 use crate::database::redis::RedisPool;
+use crate::file_hosting::FileHost;
+use crate::file_scanning::FileScanner;
 use crate::models;
+use crate::models::audit_log::{AuditAction, AuditTargetType};
 use crate::models::ids::base62_impl::parse_base62;
 use crate::models::ids::VersionId;
 use crate::models::images::ImageContext;
 use crate::models::pats::Scopes;
+use crate::models::project_history::HistoryFieldGroup;
 use crate::models::projects::{skip_nulls, Loader};
-use crate::models::projects::{Dependency, FileType, VersionStatus, VersionType};
+use crate::models::projects::{Dependency, FileType, VersionFile, VersionStatus, VersionType};
 use crate::models::teams::ProjectPermissions;
+use crate::queue::analytics::DownloadContext;
 use crate::queue::session::AuthQueue;
-use crate::search::indexing::remove_documents;
+use crate::queue::webhooks::WebhookQueue;
+use crate::routes::v3::project_history::record_field_change;
+use crate::search::indexing::{remove_documents, spawn_reindex_projects};
 use crate::search::SearchConfig;
-use crate::util::img;
 use crate::util::validate::validation_errors_to_string;
+use crate::util::{etag, img, sanitize};
 use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
@@ -41,6 +62,14 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("{id}", web::get().to(version_get))
             .route("{id}", web::patch().to(version_edit))
             .route("{id}", web::delete().to(version_delete))
+            .route("{id}/dependencies", web::get().to(version_dependency_graph))
+            .route("{id}/files", web::get().to(version_files_get))
+            .route("{id}/files", web::patch().to(version_files_edit))
+            .route(
+                "{id}/statistics/downloads",
+                web::get().to(super::statistics::version_download_statistics),
+            )
+            .route("{id}/rescan", web::post().to(version_rescan))
             .route(
                 "{version_id}/file",
                 web::post().to(super::version_creation::upload_file_to_version),
@@ -130,10 +159,44 @@ pub async fn versions_get(
     .await
     .map(|x| x.1)
     .ok();
+    check_pat_project_restrictions(&req, versions_data.iter().map(|x| x.inner.project_id))?;
+
+    let visible_ids = filter_visible_version_ids(
+        versions_data.iter().map(|x| &x.inner).collect(),
+        &user_option,
+        &pool,
+        &redis,
+    )
+    .await?;
+    let versions = versions_data
+        .into_iter()
+        .filter(|x| visible_ids.contains(&x.inner.id))
+        .collect::<Vec<_>>();
 
-    let versions = filter_visible_versions(versions_data, &user_option, &pool, &redis).await?;
+    let elevated_ids = filter_enlisted_version_ids(
+        versions.iter().map(|x| &x.inner).collect(),
+        &user_option,
+        &pool,
+        &redis,
+    )
+    .await?;
+    let etag = etag::response_etag(
+        versions
+            .iter()
+            .map(|x| (&*x.etag, elevated_ids.contains(&x.inner.id))),
+    );
+    if let Some(response) = etag::not_modified(&req, &etag) {
+        return Ok(response);
+    }
 
-    Ok(HttpResponse::Ok().json(versions))
+    let versions = versions
+        .into_iter()
+        .map(|x| {
+            let elevated = elevated_ids.contains(&x.inner.id);
+            models::projects::Version::from_query(x, elevated)
+        })
+        .collect::<Vec<_>>();
+    Ok(etag::with_etag(etag, &versions))
 }
 
 pub async fn version_get(
@@ -169,13 +232,491 @@ pub async fn version_get_helper(
 
     if let Some(data) = version_data {
         if is_visible_version(&data.inner, &user_option, &pool, &redis).await? {
-            return Ok(HttpResponse::Ok().json(models::projects::Version::from(data)));
+            let elevated = is_team_member_version(&data.inner, &user_option, &pool, &redis).await?;
+            let etag = etag::response_etag([(&*data.etag, elevated)]);
+            if let Some(response) = etag::not_modified(&req, &etag) {
+                return Ok(response);
+            }
+
+            return Ok(etag::with_etag(
+                etag,
+                &models::projects::Version::from_query(data, elevated),
+            ));
         }
     }
 
     Err(ApiError::NotFound)
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct VersionFiles {
+    pub files: Vec<VersionFile>,
+    /// A token for launchers to pass along in the `download_context` query parameter when
+    /// downloading the version's dependencies while installing it, so those downloads are
+    /// counted as pack-driven. Only given for versions with dependencies.
+    pub download_context: Option<String>,
+}
+
+pub async fn version_files_get(
+    req: HttpRequest,
+    info: web::Path<(models::ids::VersionId,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let id = info.into_inner().0;
+    let user_option = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::VERSION_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    let version = database::models::Version::get(id.into(), &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    if !is_visible_version(&version.inner, &user_option, &pool, &redis).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    let graph = database::models::Version::get_dependency_graph(
+        version.inner.id,
+        default_dependency_depth(),
+        false,
+        &**pool,
+        &redis,
+    )
+    .await?;
+
+    // Every project the version depends on, directly or through other dependencies
+    let project_ids = graph
+        .versions
+        .iter()
+        .map(|x| x.inner.project_id)
+        .chain(graph.edges.iter().filter_map(|x| x.dependency.project_id))
+        .filter(|x| *x != version.inner.project_id)
+        .unique()
+        .collect::<Vec<_>>();
+
+    let download_context = if project_ids.is_empty() {
+        None
+    } else {
+        Some(
+            DownloadContext {
+                version_id: version.inner.id,
+                project_ids,
+            }
+            .insert(&redis)
+            .await?,
+        )
+    };
+
+    Ok(HttpResponse::Ok().json(VersionFiles {
+        files: models::projects::Version::from(version).files,
+        download_context,
+    }))
+}
+
+fn default_dependency_depth() -> usize {
+    10
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct DependencyGraphQuery {
+    /// How many levels of dependencies to walk
+    #[serde(default = "default_dependency_depth")]
+    #[validate(range(min = 1, max = 10))]
+    pub depth: usize,
+    #[serde(default)]
+    pub include_incompatible: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DependencyEdge {
+    /// The version which has the dependency
+    pub version_id: VersionId,
+    pub dependency: Dependency,
+    /// For dependencies on a project, the newest version of it which is compatible with the
+    /// root version
+    pub resolved_version_id: Option<VersionId>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DependencyGraph {
+    pub versions: Vec<models::projects::Version>,
+    pub projects: Vec<models::projects::Project>,
+    pub edges: Vec<DependencyEdge>,
+    /// The versions of each circular dependency found, in dependency order
+    pub cycles: Vec<Vec<VersionId>>,
+}
+
+pub async fn version_dependency_graph(
+    req: HttpRequest,
+    info: web::Path<(models::ids::VersionId,)>,
+    web::Query(query): web::Query<DependencyGraphQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    query
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let id = info.into_inner().0;
+    let user_option = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ, Scopes::VERSION_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    let version = database::models::Version::get(id.into(), &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    if !is_visible_version(&version.inner, &user_option, &pool, &redis).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    let graph = database::models::Version::get_dependency_graph(
+        version.inner.id,
+        query.depth,
+        query.include_incompatible,
+        &**pool,
+        &redis,
+    )
+    .await?;
+
+    let visible_version_ids = filter_visible_version_ids(
+        graph.versions.iter().map(|x| &x.inner).collect(),
+        &user_option,
+        &pool,
+        &redis,
+    )
+    .await?;
+
+    // Only keep the versions which can be reached from the root through visible versions
+    let mut kept_version_ids = HashSet::from([version.inner.id]);
+    let mut frontier = vec![version.inner.id];
+    while let Some(version_id) = frontier.pop() {
+        for edge in graph.edges.iter().filter(|x| x.version_id == version_id) {
+            if let Some(dependency_version_id) = edge.target_version_id() {
+                if visible_version_ids.contains(&dependency_version_id)
+                    && kept_version_ids.insert(dependency_version_id)
+                {
+                    frontier.push(dependency_version_id);
+                }
+            }
+        }
+    }
+
+    let versions = graph
+        .versions
+        .into_iter()
+        .filter(|x| kept_version_ids.contains(&x.inner.id))
+        .collect::<Vec<_>>();
+
+    let project_ids = versions
+        .iter()
+        .map(|x| x.inner.project_id)
+        .chain(graph.edges.iter().filter_map(|x| {
+            if kept_version_ids.contains(&x.version_id) {
+                x.dependency.project_id
+            } else {
+                None
+            }
+        }))
+        .unique()
+        .collect::<Vec<_>>();
+    let projects = filter_visible_projects(
+        database::models::Project::get_many_ids(&project_ids, &**pool, &redis).await?,
+        &user_option,
+        &pool,
+    )
+    .await?;
+    let visible_project_ids = projects
+        .iter()
+        .map(|x| database::models::ProjectId::from(x.id))
+        .collect::<HashSet<_>>();
+
+    let edges = graph
+        .edges
+        .into_iter()
+        .filter(|x| {
+            kept_version_ids.contains(&x.version_id)
+                && x.target_version_id()
+                    .map(|x| kept_version_ids.contains(&x))
+                    .unwrap_or(true)
+                && x.dependency
+                    .project_id
+                    .map(|x| visible_project_ids.contains(&x))
+                    .unwrap_or(true)
+        })
+        .collect::<Vec<_>>();
+
+    // Cycles are found after hiding versions, so none of them pass through a hidden version
+    let cycles = find_dependency_cycles(version.inner.id, &edges)
+        .into_iter()
+        .map(|x| x.into_iter().map(VersionId::from).collect())
+        .collect();
+
+    Ok(HttpResponse::Ok().json(DependencyGraph {
+        versions: versions
+            .into_iter()
+            .map(models::projects::Version::from)
+            .collect(),
+        projects,
+        edges: edges
+            .into_iter()
+            .map(|x| DependencyEdge {
+                version_id: x.version_id.into(),
+                dependency: Dependency::from(x.dependency),
+                resolved_version_id: x.resolved_version_id.map(VersionId::from),
+            })
+            .collect(),
+        cycles,
+    }))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VersionMatrixQuery {
+    /// Only count versions released on this channel
+    pub channel: Option<VersionType>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VersionMatrixCell {
+    pub version_id: VersionId,
+    pub version_type: VersionType,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProjectVersionMatrix {
+    /// Newest first
+    pub game_versions: Vec<String>,
+    pub loaders: Vec<String>,
+    /// A row for each game version with a cell for each loader, holding the newest version
+    /// supporting both, or null if there is none
+    pub cells: Vec<Vec<Option<VersionMatrixCell>>>,
+}
+
+/// Gets the newest version of the project for each game version and loader its versions support
+pub async fn version_matrix(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    web::Query(query): web::Query<VersionMatrixQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let string = info.into_inner().0;
+    let user_option = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ, Scopes::VERSION_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    let project = database::models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    if !is_visible_project(&project.inner, &user_option, &pool).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    let mut versions =
+        database::models::Version::get_many(&project.versions, &**pool, &redis).await?;
+    let visible_version_ids = filter_visible_version_ids(
+        versions.iter().map(|x| &x.inner).collect(),
+        &user_option,
+        &pool,
+        &redis,
+    )
+    .await?;
+    // Unlisted versions only count for team members and moderators
+    let enlisted_version_ids = filter_enlisted_version_ids(
+        versions.iter().map(|x| &x.inner).collect(),
+        &user_option,
+        &pool,
+        &redis,
+    )
+    .await?;
+    versions.retain(|x| {
+        visible_version_ids.contains(&x.inner.id)
+            && (x.inner.status.is_listed() || enlisted_version_ids.contains(&x.inner.id))
+    });
+
+    let version_types = query.channel.map(|x| vec![x.as_str().to_string()]);
+    let matrix = VersionMatrix::build(&versions, version_types.as_deref());
+
+    Ok(HttpResponse::Ok().json(ProjectVersionMatrix {
+        game_versions: matrix.game_versions,
+        loaders: matrix.loaders,
+        cells: matrix
+            .cells
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|cell| {
+                        cell.map(|x| VersionMatrixCell {
+                            version_id: x.version_id.into(),
+                            version_type: VersionType::from_string(&x.version_type),
+                        })
+                    })
+                    .collect()
+            })
+            .collect(),
+    }))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChangelogQuery {
+    pub from_version: VersionId,
+    pub to_version: VersionId,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChangelogFile {
+    pub filename: String,
+    pub hashes: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChangelogVersion {
+    pub id: VersionId,
+    pub version_number: String,
+    pub version_type: VersionType,
+    pub date_published: DateTime<Utc>,
+    pub changelog: String,
+    pub files: Vec<ChangelogFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Changelog {
+    /// The versions after `from_version` up to and including `to_version`, oldest first
+    pub versions: Vec<ChangelogVersion>,
+    /// The changelogs of every version, each under a header with its version number
+    pub combined_changelog: String,
+}
+
+pub async fn version_changelog(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    web::Query(query): web::Query<ChangelogQuery>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let string = info.into_inner().0;
+    let user_option = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ, Scopes::VERSION_READ]),
+    )
+    .await
+    .map(|x| x.1)
+    .ok();
+
+    let project = database::models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    if !is_visible_project(&project.inner, &user_option, &pool).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    let versions = database::models::Version::get_many(
+        &[query.from_version.into(), query.to_version.into()],
+        &**pool,
+        &redis,
+    )
+    .await?;
+    let visible_version_ids = filter_visible_version_ids(
+        versions.iter().map(|x| &x.inner).collect(),
+        &user_option,
+        &pool,
+        &redis,
+    )
+    .await?;
+    let find_version = |id: VersionId| {
+        versions
+            .iter()
+            .find(|x| {
+                x.inner.id == id.into()
+                    && x.inner.project_id == project.inner.id
+                    && visible_version_ids.contains(&x.inner.id)
+            })
+            .ok_or_else(|| {
+                ApiError::InvalidInput(format!("Version {id} is not a version of this project!"))
+            })
+    };
+    let from = find_version(query.from_version)?;
+    let to = find_version(query.to_version)?;
+
+    if from.inner.date_published >= to.inner.date_published {
+        return Err(ApiError::InvalidInput(
+            "`from_version` must be older than `to_version`!".to_string(),
+        ));
+    }
+
+    // Only team members and moderators see versions which aren't listed
+    let show_unlisted = user_option
+        .as_ref()
+        .map(|x| x.role.is_mod())
+        .unwrap_or(false)
+        || !filter_enlisted_version_ids(vec![&to.inner], &user_option, &pool, &redis)
+            .await?
+            .is_empty();
+
+    let versions = database::models::Version::get_changelog_range(
+        project.inner.id,
+        from.inner.id,
+        to.inner.id,
+        &**pool,
+    )
+    .await?
+    .into_iter()
+    .filter(|x| show_unlisted || x.status.is_listed())
+    .map(|x| ChangelogVersion {
+        id: x.id.into(),
+        version_number: x.version_number,
+        version_type: VersionType::from_string(&x.version_type),
+        date_published: x.date_published,
+        changelog: x.changelog,
+        files: x
+            .files
+            .into_iter()
+            .map(|file| ChangelogFile {
+                filename: file.filename,
+                hashes: file.hashes,
+            })
+            .collect(),
+    })
+    .collect::<Vec<_>>();
+
+    let combined_changelog = versions
+        .iter()
+        .map(|x| format!("## {}\n\n{}", x.version_number, x.changelog.trim()))
+        .join("\n\n");
+
+    Ok(HttpResponse::Ok().json(Changelog {
+        versions,
+        combined_changelog,
+    }))
+}
+
 #[derive(Serialize, Deserialize, Validate, Default, Debug)]
 pub struct EditVersion {
     #[validate(
@@ -204,6 +745,13 @@ pub struct EditVersion {
     pub file_types: Option<Vec<EditVersionFileType>>,
 
     pub ordering: Option<Option<i32>>, //TODO: How do you actually pass this in json?
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    #[validate(custom(function = "crate::util::validate::validate_publish_at"))]
+    pub publish_at: Option<Option<DateTime<Utc>>>,
 
     // Flattened loader fields
     // All other fields are loader-specific VersionFields
@@ -222,13 +770,16 @@ pub struct EditVersionFileType {
 
 // TODO: Avoid this 'helper' pattern here and similar fnunctoins- a macro might be the best bet here to ensure it's callable from both v2 and v3
 // (web::Path can't be recreated naturally)
+#[allow(clippy::too_many_arguments)]
 pub async fn version_edit(
     req: HttpRequest,
     info: web::Path<(VersionId,)>,
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     new_version: web::Json<serde_json::Value>,
     session_queue: web::Data<AuthQueue>,
+    webhook_queue: web::Data<WebhookQueue>,
 ) -> Result<HttpResponse, ApiError> {
     let new_version: EditVersion = serde_json::from_value(new_version.into_inner())?;
     version_edit_helper(
@@ -236,18 +787,23 @@ pub async fn version_edit(
         info.into_inner(),
         pool,
         redis,
+        search_config,
         new_version,
         session_queue,
+        webhook_queue,
     )
     .await
 }
+#[allow(clippy::too_many_arguments)]
 pub async fn version_edit_helper(
     req: HttpRequest,
     info: (VersionId,),
     pool: web::Data<PgPool>,
     redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
     new_version: EditVersion,
     session_queue: web::Data<AuthQueue>,
+    webhook_queue: web::Data<WebhookQueue>,
 ) -> Result<HttpResponse, ApiError> {
     let user = get_user_from_headers(
         &req,
@@ -307,6 +863,13 @@ pub async fn version_edit_helper(
                 ));
             }
 
+            if let Some(project) =
+                database::models::Project::get_id(version_item.inner.project_id, &**pool, &redis)
+                    .await?
+            {
+                check_not_archived(&project.inner)?;
+            }
+
             let mut transaction = pool.begin().await?;
 
             if let Some(name) = &new_version.name {
@@ -371,6 +934,7 @@ pub async fn version_edit_helper(
                         version_id: x.version_id.map(|x| x.into()),
                         file_name: x.file_name.clone(),
                         dependency_type: x.dependency_type.to_string(),
+                        inferred: false,
                     })
                     .collect::<Vec<database::models::version_item::DependencyBuilder>>();
 
@@ -440,6 +1004,28 @@ pub async fn version_edit_helper(
                         enum_variants,
                     )
                     .map_err(ApiError::InvalidInput)?;
+
+                    if HistoryFieldGroup::Environment
+                        .fields()
+                        .contains(&vf_name.as_str())
+                    {
+                        let old_value = version_item
+                            .version_fields
+                            .iter()
+                            .find(|x| x.field_name == vf_name)
+                            .map(|x| x.value.serialize_internal())
+                            .unwrap_or_default();
+                        record_field_change(
+                            version_item.inner.project_id,
+                            Some(id),
+                            &vf_name,
+                            old_value,
+                            vf.value.serialize_internal(),
+                            user.id.into(),
+                            &mut transaction,
+                        )
+                        .await?;
+                    }
                     version_fields.push(vf);
                 }
                 VersionField::insert_many(version_fields, &mut transaction).await?;
@@ -536,6 +1122,9 @@ pub async fn version_edit_helper(
             }
 
             if let Some(body) = &new_version.changelog {
+                let body = sanitize::sanitize_body(body, sanitize::is_strict(&req))
+                    .map_err(|err| ApiError::InvalidInput(err.to_string()))?;
+
                 sqlx::query!(
                     "
                     UPDATE versions
@@ -583,6 +1172,7 @@ pub async fn version_edit_helper(
                 .await?;
             }
 
+            let mut newly_listed = false;
             if let Some(status) = &new_version.status {
                 if !status.can_be_requested() {
                     return Err(ApiError::InvalidInput(
@@ -590,10 +1180,17 @@ pub async fn version_edit_helper(
                     ));
                 }
 
+                if version_item.inner.status == VersionStatus::Withheld && !user.role.is_mod() {
+                    return Err(ApiError::CustomAuthentication(
+                        "Only moderators can change the status of a withheld version".to_string(),
+                    ));
+                }
+
+                // Changing the status directly cancels any scheduled publishing
                 sqlx::query!(
                     "
                     UPDATE versions
-                    SET status = $1
+                    SET status = $1, publish_at = NULL
                     WHERE (id = $2)
                     ",
                     status.as_str(),
@@ -601,6 +1198,38 @@ pub async fn version_edit_helper(
                 )
                 .execute(&mut *transaction)
                 .await?;
+
+                newly_listed = status.is_listed() && !version_item.inner.status.is_listed();
+            }
+
+            if let Some(publish_at) = &new_version.publish_at {
+                let status = new_version.status.unwrap_or(version_item.inner.status);
+                if !matches!(status, VersionStatus::Draft | VersionStatus::Scheduled) {
+                    return Err(ApiError::InvalidInput(
+                        "Only draft or scheduled versions can be scheduled for publishing!"
+                            .to_string(),
+                    ));
+                }
+
+                // Removing the publish time turns a scheduled version back into a draft
+                let status = if publish_at.is_some() {
+                    VersionStatus::Scheduled
+                } else {
+                    VersionStatus::Draft
+                };
+
+                sqlx::query!(
+                    "
+                    UPDATE versions
+                    SET status = $1, publish_at = $2, publish_delayed = FALSE
+                    WHERE (id = $3)
+                    ",
+                    status.as_str(),
+                    *publish_at,
+                    id as database::models::ids::VersionId,
+                )
+                .execute(&mut *transaction)
+                .await?;
             }
 
             if let Some(file_types) = &new_version.file_types {
@@ -662,6 +1291,12 @@ pub async fn version_edit_helper(
 
             img::delete_unused_images(context, checkable_strings, &mut transaction, &redis).await?;
 
+            if newly_listed {
+                webhook_queue
+                    .add_versions(&[version_item.inner.id], &mut *transaction)
+                    .await?;
+            }
+
             transaction.commit().await?;
             database::models::Version::clear_cache(&version_item, &redis).await?;
             database::models::Project::clear_cache(
@@ -671,6 +1306,24 @@ pub async fn version_edit_helper(
                 &redis,
             )
             .await?;
+
+            spawn_reindex_projects(
+                vec![version_item.inner.project_id],
+                pool.get_ref().clone(),
+                redis.get_ref().clone(),
+                search_config.get_ref().clone(),
+            );
+
+            if newly_listed {
+                spawn_notify_followers(
+                    version_item.inner.project_id,
+                    version_item.inner.id,
+                    version_item.inner.author_id,
+                    pool.get_ref().clone(),
+                    redis.get_ref().clone(),
+                );
+            }
+
             Ok(HttpResponse::NoContent().body(""))
         } else {
             Err(ApiError::CustomAuthentication(
@@ -682,6 +1335,179 @@ pub async fn version_edit_helper(
     }
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct EditVersionFiles {
+    /// Every file of the version in the order they should be listed
+    pub files: Vec<EditVersionFilesEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EditVersionFilesEntry {
+    /// The SHA-1 or SHA-512 hash of the file
+    pub hash: String,
+    /// Whether this should be the primary file. If no file is marked, the current primary
+    /// file is kept.
+    #[serde(default)]
+    pub primary: bool,
+}
+
+pub async fn version_files_edit(
+    req: HttpRequest,
+    info: web::Path<(VersionId,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    search_config: web::Data<SearchConfig>,
+    edit_files: web::Json<EditVersionFiles>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::VERSION_WRITE]),
+    )
+    .await?
+    .1;
+
+    let version_item = database::models::Version::get(info.into_inner().0.into(), &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let team_member = database::models::TeamMember::get_from_user_id_project(
+        version_item.inner.project_id,
+        user.id.into(),
+        false,
+        &**pool,
+    )
+    .await?;
+
+    let organization = Organization::get_associated_organization_project_id(
+        version_item.inner.project_id,
+        &**pool,
+    )
+    .await?;
+
+    let organization_team_member = if let Some(organization) = &organization {
+        database::models::TeamMember::get_from_user_id(
+            organization.team_id,
+            user.id.into(),
+            &**pool,
+        )
+        .await?
+    } else {
+        None
+    };
+
+    let permissions = ProjectPermissions::get_permissions_by_role(
+        &user.role,
+        &team_member,
+        &organization_team_member,
+    )
+    .unwrap_or_default();
+
+    if !permissions.contains(ProjectPermissions::UPLOAD_VERSION) {
+        return Err(ApiError::CustomAuthentication(
+            "You do not have the permissions to edit this version!".to_string(),
+        ));
+    }
+
+    if let Some(project) =
+        database::models::Project::get_id(version_item.inner.project_id, &**pool, &redis).await?
+    {
+        check_not_archived(&project.inner)?;
+    }
+
+    let edit_files = edit_files.into_inner();
+
+    let mut file_ids = Vec::new();
+    let mut primary = None;
+    for entry in &edit_files.files {
+        let file = version_item
+            .files
+            .iter()
+            .find(|x| {
+                x.hashes.get("sha1") == Some(&entry.hash)
+                    || x.hashes.get("sha512") == Some(&entry.hash)
+            })
+            .ok_or_else(|| {
+                ApiError::InvalidInput(format!(
+                    "The file with hash {} does not belong to this version!",
+                    entry.hash
+                ))
+            })?;
+
+        if file_ids.contains(&file.id) {
+            return Err(ApiError::InvalidInput(format!(
+                "The file with hash {} is listed more than once!",
+                entry.hash
+            )));
+        }
+        file_ids.push(file.id);
+
+        if entry.primary {
+            if primary.is_some() {
+                return Err(ApiError::InvalidInput(
+                    "Only one file can be the primary file!".to_string(),
+                ));
+            }
+            primary = Some(file);
+        }
+    }
+
+    if file_ids.len() != version_item.files.len() {
+        return Err(ApiError::InvalidInput(
+            "Every file of the version must be listed!".to_string(),
+        ));
+    }
+
+    let primary = match primary {
+        Some(primary) => primary,
+        None => version_item
+            .files
+            .iter()
+            .find(|x| x.primary)
+            .ok_or_else(|| {
+                ApiError::InvalidInput("A primary file must be specified!".to_string())
+            })?,
+    };
+
+    crate::validate::validate_primary_file(
+        &primary.filename,
+        primary.file_type,
+        &version_item.loaders,
+    )
+    .map_err(|err| ApiError::InvalidInput(err.to_string()))?;
+
+    let mut transaction = pool.begin().await?;
+    database::models::Version::update_files(
+        version_item.inner.id,
+        &file_ids,
+        primary.id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    database::models::Version::clear_cache(&version_item, &redis).await?;
+    database::models::Project::clear_cache(version_item.inner.project_id, None, Some(true), &redis)
+        .await?;
+
+    spawn_reindex_projects(
+        vec![version_item.inner.project_id],
+        pool.get_ref().clone(),
+        redis.get_ref().clone(),
+        search_config.get_ref().clone(),
+    );
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+/// The number of versions per page of a project's versions, unless another count is asked for
+const DEFAULT_VERSION_PAGE_SIZE: u32 = 100;
+/// The most versions a page of a project's versions can hold
+const MAX_VERSION_PAGE_SIZE: u32 = 500;
+
 #[derive(Serialize, Deserialize)]
 pub struct VersionListFilters {
     pub loaders: Option<String>,
@@ -689,6 +1515,14 @@ pub struct VersionListFilters {
     pub version_type: Option<VersionType>,
     pub limit: Option<usize>,
     pub offset: Option<usize>,
+    /// The number of versions per page. Passing it or `before_version` pages through the
+    /// versions, newest first, with the total in the `x-total-count` header and the
+    /// `before_version` of the next page in the `x-next-cursor` header.
+    #[serde(default)]
+    pub count: Option<u32>,
+    /// The last version of the previous page
+    #[serde(default)]
+    pub before_version: Option<VersionId>,
     /*
         Loader fields to filter with:
         "game_versions": ["1.16.5", "1.17"]
@@ -733,6 +1567,20 @@ pub async fn version_list(
             .loaders
             .as_ref()
             .map(|x| serde_json::from_str::<Vec<String>>(x).unwrap_or_default());
+
+        if filters.count.is_some() || filters.before_version.is_some() {
+            return version_list_page(
+                project,
+                &filters,
+                loader_filters,
+                loader_field_filters,
+                &user_option,
+                &pool,
+                &redis,
+            )
+            .await;
+        }
+
         let mut versions = database::models::Version::get_many(&project.versions, &**pool, &redis)
             .await?
             .into_iter()
@@ -828,12 +1676,104 @@ pub async fn version_list(
 
         let response = filter_visible_versions(response, &user_option, &pool, &redis).await?;
 
-        Ok(HttpResponse::Ok().json(response))
+        Ok(HttpResponse::Ok()
+            .insert_header((
+                "warning",
+                format!(
+                    "299 - \"A future version will page versions, {DEFAULT_VERSION_PAGE_SIZE} at a \
+                     time by default. Pass count and before_version to page through them.\""
+                ),
+            ))
+            .json(response))
     } else {
         Err(ApiError::NotFound)
     }
 }
 
+/// Responds with a page of the project's versions, which is filtered and paged in the database
+/// rather than after loading every version. Versions are only auto-featured when listing every
+/// version.
+async fn version_list_page(
+    project: database::models::project_item::QueryProject,
+    filters: &VersionListFilters,
+    loaders: Option<Vec<String>>,
+    loader_fields: Option<HashMap<String, Vec<serde_json::Value>>>,
+    user_option: &Option<models::users::User>,
+    pool: &web::Data<PgPool>,
+    redis: &RedisPool,
+) -> Result<HttpResponse, ApiError> {
+    let count = filters.count.unwrap_or(DEFAULT_VERSION_PAGE_SIZE);
+    if !(1..=MAX_VERSION_PAGE_SIZE).contains(&count) {
+        return Err(ApiError::InvalidInput(format!(
+            "The count must be between 1 and {MAX_VERSION_PAGE_SIZE}."
+        )));
+    }
+
+    if let Some(before_version) = filters.before_version {
+        let before_version = database::models::Version::get(before_version.into(), &***pool, redis)
+            .await?
+            .filter(|x| x.inner.project_id == project.inner.id);
+        if before_version.is_none() {
+            return Err(ApiError::InvalidInput(
+                "The version to start after isn't one of the project's versions.".to_string(),
+            ));
+        }
+    }
+
+    // The project's team and moderators see its hidden versions as well
+    let include_hidden = user_option
+        .as_ref()
+        .map(|x| x.role.is_mod())
+        .unwrap_or(false)
+        || is_team_member_project(&project.inner, user_option, pool).await?;
+    let page_filters = VersionPageFilters {
+        statuses: VersionStatus::iterator()
+            .filter(|x| include_hidden || !x.is_hidden())
+            .map(|x| x.to_string())
+            .collect(),
+        loaders,
+        loader_fields: loader_fields
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|(field, values)| {
+                values.into_iter().filter_map(move |value| {
+                    let value = match value {
+                        serde_json::Value::String(x) => x,
+                        serde_json::Value::Bool(x) => (x as i32).to_string(),
+                        serde_json::Value::Number(x) => x.to_string(),
+                        _ => return None,
+                    };
+                    Some((field.clone(), value))
+                })
+            })
+            .collect(),
+        featured: filters.featured,
+        version_type: filters.version_type.map(|x| x.as_str().to_string()),
+    };
+
+    let page = database::models::Version::get_page(
+        project.inner.id,
+        &page_filters,
+        filters.before_version.map(|x| x.into()),
+        count as i64,
+        &***pool,
+    )
+    .await?;
+
+    let mut versions =
+        database::models::Version::get_many(&page.version_ids, &***pool, redis).await?;
+    versions.sort_by_key(|x| page.version_ids.iter().position(|id| *id == x.inner.id));
+    let versions = filter_visible_versions(versions, user_option, pool, redis).await?;
+
+    let mut response = HttpResponse::Ok();
+    response.insert_header(("x-total-count", page.total.to_string()));
+    if let Some(next_cursor) = page.next_cursor {
+        response.insert_header(("x-next-cursor", VersionId::from(next_cursor).to_string()));
+    }
+
+    Ok(response.json(versions))
+}
+
 pub async fn version_delete(
     req: HttpRequest,
     info: web::Path<(models::ids::VersionId,)>,
@@ -909,6 +1849,31 @@ pub async fn version_delete(
 
     let result =
         database::models::Version::remove_full(version.inner.id, &redis, &mut transaction).await?;
+
+    if result.is_some() {
+        let organization = Organization::get_associated_organization_project_id(
+            version.inner.project_id,
+            &mut *transaction,
+        )
+        .await?;
+
+        AuditLogBuilder {
+            actor_id: user.id.into(),
+            action: AuditAction::VersionDelete,
+            target_type: AuditTargetType::Version,
+            target_id: version.inner.id.0,
+            project_id: Some(version.inner.project_id),
+            organization_id: organization.map(|x| x.id),
+            summary: serde_json::json!({
+                "name": version.inner.name,
+                "version_number": version.inner.version_number,
+            }),
+            ip: request_ip(&req),
+        }
+        .insert(&mut transaction)
+        .await?;
+    }
+
     transaction.commit().await?;
     remove_documents(&[version.inner.id.into()], &search_config).await?;
     database::models::Project::clear_cache(version.inner.project_id, None, Some(true), &redis)
@@ -920,3 +1885,41 @@ pub async fn version_delete(
         Err(ApiError::NotFound)
     }
 }
+
+/// Scans all of the version's files for viruses again, such as after a false positive was fixed
+/// in the scanner. A withheld version stays withheld until a moderator changes its status.
+pub async fn version_rescan(
+    req: HttpRequest,
+    info: web::Path<(models::ids::VersionId,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    file_host: web::Data<Arc<dyn FileHost + Send + Sync>>,
+    scanner: web::Data<Arc<dyn FileScanner + Send + Sync>>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    check_is_moderator_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::VERSION_WRITE]),
+    )
+    .await?;
+
+    let version = database::models::Version::get(info.into_inner().0.into(), &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    database::models::Version::reset_file_scans(version.inner.id, &**pool).await?;
+    database::models::Version::clear_cache(&version, &redis).await?;
+
+    spawn_scan_version(
+        version.inner.id,
+        pool.get_ref().clone(),
+        redis.get_ref().clone(),
+        file_host.get_ref().clone(),
+        scanner.get_ref().clone(),
+    );
+
+    Ok(HttpResponse::NoContent().body(""))
+}
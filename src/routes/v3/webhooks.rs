@@ -0,0 +1,185 @@
+use crate::auth::get_user_from_headers;
+use crate::database::models::webhook_item::ProjectWebhook;
+use crate::database::models::{self as db_models, generate_webhook_id};
+use crate::database::redis::RedisPool;
+use crate::models::pats::Scopes;
+use crate::models::teams::ProjectPermissions;
+use crate::models::users::User;
+use crate::models::webhooks::{self, WebhookId};
+use crate::queue::session::AuthQueue;
+use crate::queue::webhooks::resolve_webhook_url;
+use crate::routes::ApiError;
+use crate::util::validate::validation_errors_to_string;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use validator::Validate;
+
+pub async fn webhooks_get(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_READ]),
+    )
+    .await?
+    .1;
+    let string = info.into_inner().0;
+
+    let project_item = db_models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    check_webhook_permissions(&project_item.inner, &user, &pool).await?;
+
+    let webhooks = ProjectWebhook::get_all_project(project_item.inner.id, &**pool)
+        .await?
+        .into_iter()
+        .map(webhooks::ProjectWebhook::from)
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(webhooks))
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+pub struct NewWebhook {
+    #[validate(
+        url,
+        length(
+            max = 2048,
+            message = "The webhook URL must be no more than 2048 characters"
+        )
+    )]
+    pub url: String,
+    #[validate(length(min = 16, max = 255))]
+    pub secret: String,
+}
+
+pub async fn webhook_create(
+    req: HttpRequest,
+    info: web::Path<(String,)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    new_webhook: web::Json<NewWebhook>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+
+    new_webhook
+        .validate()
+        .map_err(|err| ApiError::Validation(validation_errors_to_string(err, None)))?;
+
+    let string = info.into_inner().0;
+
+    let project_item = db_models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    check_webhook_permissions(&project_item.inner, &user, &pool).await?;
+
+    resolve_webhook_url(&new_webhook.url)
+        .await
+        .map_err(ApiError::InvalidInput)?;
+
+    let mut transaction = pool.begin().await?;
+
+    let webhook = ProjectWebhook {
+        id: generate_webhook_id(&mut transaction).await?,
+        project_id: project_item.inner.id,
+        url: new_webhook.url.clone(),
+        secret: new_webhook.secret.clone(),
+        created: Utc::now(),
+        last_attempt: None,
+        last_error: None,
+    };
+    webhook.insert(&mut transaction).await?;
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(webhooks::ProjectWebhook::from(webhook)))
+}
+
+pub async fn webhook_delete(
+    req: HttpRequest,
+    info: web::Path<(String, WebhookId)>,
+    pool: web::Data<PgPool>,
+    redis: web::Data<RedisPool>,
+    session_queue: web::Data<AuthQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let user = get_user_from_headers(
+        &req,
+        &**pool,
+        &redis,
+        &session_queue,
+        Some(&[Scopes::PROJECT_WRITE]),
+    )
+    .await?
+    .1;
+    let (string, webhook_id) = info.into_inner();
+
+    let project_item = db_models::Project::get(&string, &**pool, &redis)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    check_webhook_permissions(&project_item.inner, &user, &pool).await?;
+
+    let webhook = ProjectWebhook::get(webhook_id.into(), &**pool)
+        .await?
+        .filter(|x| x.project_id == project_item.inner.id)
+        .ok_or(ApiError::NotFound)?;
+
+    let mut transaction = pool.begin().await?;
+    ProjectWebhook::remove(webhook.id, &mut transaction).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+async fn check_webhook_permissions(
+    project: &db_models::project_item::Project,
+    user: &User,
+    pool: &PgPool,
+) -> Result<(), ApiError> {
+    if user.role.is_mod() {
+        return Ok(());
+    }
+
+    let (team_member, organization_team_member) =
+        db_models::TeamMember::get_for_project_permissions(project, user.id.into(), pool).await?;
+
+    // Hide the project
+    if team_member.is_none() && organization_team_member.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    let permissions = ProjectPermissions::get_permissions_by_role(
+        &user.role,
+        &team_member,
+        &organization_team_member,
+    )
+    .unwrap_or_default();
+
+    if !permissions.contains(ProjectPermissions::MANAGE_WEBHOOKS) {
+        return Err(ApiError::CustomAuthentication(
+            "You don't have permission to manage this project's webhooks.".to_string(),
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use chrono::{NaiveDate, Utc};
+use lazy_static::lazy_static;
+use log::warn;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::database::models::DatabaseError;
+use crate::database::redis::RedisPool;
+
+const SEARCH_QUERIES_NAMESPACE: &str = "search_queries";
+const ZERO_RESULT_SEARCH_QUERIES_NAMESPACE: &str = "search_queries_zero_results";
+
+/// How long each day's counters are kept for, in seconds
+const ANALYTICS_EXPIRY: i64 = 60 * 60 * 24 * 30; // 30 days
+/// The longest query that is recorded, in characters. Longer queries are cut off.
+const MAX_QUERY_LENGTH: usize = 100;
+
+lazy_static! {
+    static ref RE_EMAIL: Regex = Regex::new(r"[^\s@]+@[^\s@]+\.[^\s@]+").unwrap();
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct QueryCount {
+    pub query: String,
+    pub count: u64,
+}
+
+// Lowercases and trims the query, returning `None` if it's empty or might contain a secret
+fn normalize_query(query: &str) -> Option<String> {
+    let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    let query = query.to_lowercase();
+
+    if query.is_empty() || RE_EMAIL.is_match(&query) || looks_like_token(&query) {
+        return None;
+    }
+
+    Some(query.chars().take(MAX_QUERY_LENGTH).collect())
+}
+
+// Labrinth's own tokens, and long unbroken runs of letters and digits like random secrets.
+// Slugs are split up by dashes or underscores, so they aren't caught by this.
+fn looks_like_token(query: &str) -> bool {
+    query.split(' ').any(|word| {
+        ["mra_", "mrp_", "mro_"].iter().any(|x| word.starts_with(x))
+            || (word.len() >= 24
+                && word.chars().all(|x| x.is_ascii_alphanumeric())
+                && word.chars().any(|x| x.is_ascii_digit())
+                && word.chars().any(|x| x.is_ascii_alphabetic()))
+    })
+}
+
+/// Records a search query and whether it had any results in the background. Failures are only
+/// logged, so recording can never slow down or fail the search itself.
+pub fn spawn_record_search(query: Option<&str>, total_hits: usize, redis: RedisPool) {
+    let Some(query) = query.and_then(normalize_query) else {
+        return;
+    };
+
+    actix_rt::spawn(async move {
+        if let Err(e) = record_search(&query, total_hits, &redis).await {
+            warn!("Failed to record search query analytics: {}", e);
+        }
+    });
+}
+
+async fn record_search(
+    query: &str,
+    total_hits: usize,
+    redis: &RedisPool,
+) -> Result<(), DatabaseError> {
+    let date = Utc::now().date_naive().to_string();
+    let mut redis = redis.connect().await?;
+
+    redis
+        .increment_member(
+            SEARCH_QUERIES_NAMESPACE,
+            &date,
+            query,
+            Some(ANALYTICS_EXPIRY),
+        )
+        .await?;
+    if total_hits == 0 {
+        redis
+            .increment_member(
+                ZERO_RESULT_SEARCH_QUERIES_NAMESPACE,
+                &date,
+                query,
+                Some(ANALYTICS_EXPIRY),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Gets the most searched queries of a day and the most searched of those without results
+pub async fn get_top_queries(
+    date: NaiveDate,
+    count: usize,
+    redis: &RedisPool,
+) -> Result<(Vec<QueryCount>, Vec<QueryCount>), DatabaseError> {
+    let date = date.to_string();
+    let mut redis = redis.connect().await?;
+
+    let to_counts = |x: Vec<(String, u64)>| {
+        x.into_iter()
+            .map(|(query, count)| QueryCount { query, count })
+            .collect::<Vec<_>>()
+    };
+
+    let top_queries = redis
+        .get_top_members(SEARCH_QUERIES_NAMESPACE, &date, count)
+        .await?;
+    let top_zero_result_queries = redis
+        .get_top_members(ZERO_RESULT_SEARCH_QUERIES_NAMESPACE, &date, count)
+        .await?;
+
+    Ok((to_counts(top_queries), to_counts(top_zero_result_queries)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_query_lowercases_and_trims() {
+        assert_eq!(
+            normalize_query("  Fabric   API "),
+            Some("fabric api".to_string())
+        );
+        assert_eq!(normalize_query("   "), None);
+    }
+
+    #[test]
+    fn normalize_query_skips_secrets() {
+        assert_eq!(normalize_query("mrp_abcdef"), None);
+        assert_eq!(normalize_query("contact someone@example.com"), None);
+        assert_eq!(normalize_query("x7Gk2pQ9rLm4Tz8VwB3nYc6H"), None);
+        assert!(normalize_query("create-aeronautics-addon-pack-1-20").is_some());
+    }
+}
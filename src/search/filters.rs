@@ -0,0 +1,607 @@
+use super::{parse_facets, SearchError};
+use crate::database::models::loader_fields::{LoaderField, LoaderFieldType};
+use crate::database::redis::RedisPool;
+use crate::models::projects::SearchRequest;
+use crate::search::indexing::DEFAULT_ATTRIBUTES_FOR_FACETING;
+use chrono::{DateTime, NaiveDate};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use thiserror::Error;
+
+/// The kind of values a filterable attribute holds, which decides the values and operators
+/// it can be filtered with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeKind {
+    Text,
+    Number,
+    Boolean,
+    Date,
+}
+
+impl AttributeKind {
+    fn of_default(attribute: &str) -> AttributeKind {
+        match attribute {
+            "downloads" | "follows" | "created_timestamp" | "modified_timestamp" | "color" => {
+                AttributeKind::Number
+            }
+            "date_created" | "date_modified" => AttributeKind::Date,
+            "open_source" | "mature" | "archived" => AttributeKind::Boolean,
+            _ => AttributeKind::Text,
+        }
+    }
+
+    fn of_loader_field(field_type: &LoaderFieldType) -> AttributeKind {
+        match field_type {
+            LoaderFieldType::Integer | LoaderFieldType::ArrayInteger => AttributeKind::Number,
+            LoaderFieldType::Boolean | LoaderFieldType::ArrayBoolean => AttributeKind::Boolean,
+            _ => AttributeKind::Text,
+        }
+    }
+
+    fn accepts(&self, value: &str) -> bool {
+        match self {
+            AttributeKind::Text => true,
+            AttributeKind::Number => value.parse::<f64>().is_ok(),
+            AttributeKind::Boolean => value == "true" || value == "false",
+            AttributeKind::Date => {
+                DateTime::parse_from_rfc3339(value).is_ok()
+                    || NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+            }
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            AttributeKind::Text => "text",
+            AttributeKind::Number => "a number",
+            AttributeKind::Boolean => "`true` or `false`",
+            AttributeKind::Date => "an ISO 8601 date",
+        }
+    }
+}
+
+/// An invalid facet or filter clause
+#[derive(Error, Debug, Serialize, PartialEq)]
+#[error("Invalid search clause `{clause}`: {message}")]
+pub struct FilterError {
+    /// The facet, or the clause of the filter, which is invalid
+    pub clause: String,
+    pub message: String,
+    /// The nearest valid attribute, if the clause filters on an unknown one
+    pub suggestion: Option<String>,
+}
+
+/// The attributes search can be filtered on: the default ones, and the loader fields
+pub struct FilterSchema {
+    attributes: HashMap<String, AttributeKind>,
+}
+
+impl FilterSchema {
+    pub fn new(loader_fields: &[LoaderField]) -> Self {
+        let mut attributes = DEFAULT_ATTRIBUTES_FOR_FACETING
+            .iter()
+            .map(|x| (x.to_string(), AttributeKind::of_default(x)))
+            .collect::<HashMap<_, _>>();
+        attributes.extend(loader_fields.iter().map(|x| {
+            (
+                x.field.clone(),
+                AttributeKind::of_loader_field(&x.field_type),
+            )
+        }));
+
+        Self { attributes }
+    }
+
+    /// Checks the facets and filters of a search request. Only the filters built from
+    /// `new_filters` are checked if they're given, as the others are ignored then.
+    pub fn validate_request(&self, info: &SearchRequest) -> Result<(), SearchError> {
+        if let Some(new_filters) = &info.new_filters {
+            return Ok(self.validate_filter(new_filters)?);
+        }
+
+        if let Some(facets) = &info.facets {
+            for facet in parse_facets(facets)?.iter().flatten().flatten() {
+                self.validate_facet(facet)?;
+            }
+        }
+
+        for filter in [&info.filters, &info.version].into_iter().flatten() {
+            self.validate_filter(filter)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks a facet, such as `categories:fabric` or `downloads>1000`
+    pub fn validate_facet(&self, facet: &str) -> Result<(), FilterError> {
+        let error = |message: &str| FilterError {
+            clause: facet.to_string(),
+            message: message.to_string(),
+            suggestion: None,
+        };
+
+        // The earliest operator is the one used, preferring the longest at the same position
+        // so that `>=` isn't read as `>`
+        let (position, operator) = FACET_OPERATORS
+            .iter()
+            .filter_map(|op| facet.find(op).map(|position| (position, *op)))
+            .min_by_key(|(position, op)| (*position, std::cmp::Reverse(op.len())))
+            .ok_or_else(|| error("Facets must have the form `attribute:value`"))?;
+
+        let attribute = facet[..position].trim();
+        let value = facet[position + operator.len()..].trim();
+        if attribute.is_empty() || value.is_empty() {
+            return Err(error("Facets must have the form `attribute:value`"));
+        }
+
+        let operator = if operator == ":" { "=" } else { operator };
+        self.validate_condition(
+            &Condition {
+                attribute: attribute.to_string(),
+                operator,
+                values: vec![unquote(value).to_string()],
+            },
+            facet,
+        )
+    }
+
+    /// Checks a filter in Meilisearch's filter syntax, such as
+    /// `categories = fabric AND (downloads > 1000 OR follows 10 TO 100)`
+    pub fn validate_filter(&self, filter: &str) -> Result<(), FilterError> {
+        let syntax_error = |message: String| FilterError {
+            clause: filter.to_string(),
+            message,
+            suggestion: None,
+        };
+
+        let tokens = tokenize(filter).map_err(syntax_error)?;
+        if tokens.is_empty() {
+            return Ok(());
+        }
+
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+            conditions: Vec::new(),
+        };
+        parser.parse_or().map_err(syntax_error)?;
+        if let Some(token) = parser.peek() {
+            return Err(syntax_error(format!("Unexpected `{token}`")));
+        }
+
+        for (condition, clause) in parser.conditions {
+            let clause = clause.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+            self.validate_condition(&condition, &clause.join(" "))?;
+        }
+
+        Ok(())
+    }
+
+    fn validate_condition(&self, condition: &Condition, clause: &str) -> Result<(), FilterError> {
+        let error = |message: String| FilterError {
+            clause: clause.to_string(),
+            message,
+            suggestion: None,
+        };
+
+        let Some(kind) = self.attributes.get(&condition.attribute) else {
+            return Err(FilterError {
+                suggestion: self.suggest(&condition.attribute),
+                ..error(format!(
+                    "`{}` isn't an attribute search can be filtered on",
+                    condition.attribute
+                ))
+            });
+        };
+
+        if matches!(condition.operator, ">" | ">=" | "<" | "<=" | "TO")
+            && !matches!(kind, AttributeKind::Number | AttributeKind::Date)
+        {
+            return Err(error(format!(
+                "`{}` can't be compared with `{}`, as only numbers and dates can be",
+                condition.attribute, condition.operator
+            )));
+        }
+
+        if let Some(value) = condition.values.iter().find(|x| !kind.accepts(x)) {
+            return Err(error(format!(
+                "`{}` must be compared with {}, but got `{}`",
+                condition.attribute,
+                kind.describe(),
+                value
+            )));
+        }
+
+        Ok(())
+    }
+
+    // The known attribute with the closest name, if any is close enough to be a likely typo
+    fn suggest(&self, attribute: &str) -> Option<String> {
+        self.attributes
+            .keys()
+            .map(|x| (edit_distance(attribute, x), x))
+            .filter(|(distance, _)| *distance <= (attribute.len() / 3).max(2))
+            .min()
+            .map(|(_, x)| x.clone())
+    }
+}
+
+/// Checks the facets and filters of a search request against the attributes search can be
+/// filtered on, including the loader fields
+pub async fn validate_search_filters(
+    info: &SearchRequest,
+    pool: &PgPool,
+    redis: &RedisPool,
+) -> Result<(), SearchError> {
+    let loader_fields = LoaderField::get_fields_all(pool, redis).await?;
+    FilterSchema::new(&loader_fields).validate_request(info)
+}
+
+const FACET_OPERATORS: [&str; 7] = ["!=", ">=", "<=", ":", "=", ">", "<"];
+const FILTER_OPERATORS: [&str; 6] = ["!=", ">=", "<=", "=", ">", "<"];
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+
+    value
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    OpenBracket,
+    CloseBracket,
+    Comma,
+    Operator(&'static str),
+    Word(String),
+    Quoted(String),
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::OpenBracket => write!(f, "["),
+            Token::CloseBracket => write!(f, "]"),
+            Token::Comma => write!(f, ","),
+            Token::Operator(operator) => write!(f, "{operator}"),
+            Token::Word(word) => write!(f, "{word}"),
+            Token::Quoted(value) => write!(f, "\"{}\"", value.replace('"', "\\\"")),
+        }
+    }
+}
+
+fn tokenize(filter: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = filter;
+
+    while let Some(c) = rest.chars().next() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '[' => Token::OpenBracket,
+            ']' => Token::CloseBracket,
+            ',' => Token::Comma,
+            '"' | '\'' => {
+                let mut value = String::new();
+                let mut chars = rest.char_indices().skip(1);
+                let end = loop {
+                    match chars.next() {
+                        Some((_, '\\')) => {
+                            if let Some((_, escaped)) = chars.next() {
+                                value.push(escaped);
+                            }
+                        }
+                        Some((i, x)) if x == c => break i,
+                        Some((_, x)) => value.push(x),
+                        None => return Err(format!("Unterminated quote in `{rest}`")),
+                    }
+                };
+
+                rest = &rest[end + 1..];
+                tokens.push(Token::Quoted(value));
+                continue;
+            }
+            _ => {
+                if let Some(operator) = FILTER_OPERATORS.iter().find(|x| rest.starts_with(*x)) {
+                    rest = &rest[operator.len()..];
+                    tokens.push(Token::Operator(operator));
+                    continue;
+                }
+
+                let end = rest
+                    .find(|x: char| x.is_whitespace() || "()[],\"'=!<>".contains(x))
+                    .unwrap_or(rest.len());
+                if end == 0 {
+                    return Err(format!("Unexpected `{c}`"));
+                }
+
+                tokens.push(Token::Word(rest[..end].to_string()));
+                rest = &rest[end..];
+                continue;
+            }
+        };
+
+        rest = &rest[c.len_utf8()..];
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+struct Condition {
+    attribute: String,
+    operator: &'static str,
+    values: Vec<String>,
+}
+
+// Parses filters by Meilisearch's grammar, collecting each condition along with its tokens
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    conditions: Vec<(Condition, &'a [Token])>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let matches = self.peek().is_some_and(|x| x.is_keyword(keyword));
+        if matches {
+            self.position += 1;
+        }
+        matches
+    }
+
+    fn parse_or(&mut self) -> Result<(), String> {
+        self.parse_and()?;
+        while self.eat_keyword("OR") {
+            self.parse_and()?;
+        }
+        Ok(())
+    }
+
+    fn parse_and(&mut self) -> Result<(), String> {
+        self.parse_not()?;
+        while self.eat_keyword("AND") {
+            self.parse_not()?;
+        }
+        Ok(())
+    }
+
+    fn parse_not(&mut self) -> Result<(), String> {
+        if self.eat_keyword("NOT") {
+            self.parse_not()
+        } else if self.peek() == Some(&Token::Open) {
+            self.position += 1;
+            self.parse_or()?;
+            match self.next() {
+                Some(Token::Close) => Ok(()),
+                _ => Err("Missing `)`".to_string()),
+            }
+        } else {
+            self.parse_condition()
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Word(value) | Token::Quoted(value)) => Ok(value.clone()),
+            Some(token) => Err(format!("Expected a value, but got `{token}`")),
+            None => Err("Expected a value at the end of the filter".to_string()),
+        }
+    }
+
+    fn parse_condition(&mut self) -> Result<(), String> {
+        let start = self.position;
+        let attribute = match self.next() {
+            Some(Token::Word(attribute) | Token::Quoted(attribute)) => attribute.clone(),
+            Some(token) => return Err(format!("Expected an attribute, but got `{token}`")),
+            None => return Err("Expected a condition at the end of the filter".to_string()),
+        };
+
+        let (operator, values) = match self.next() {
+            Some(Token::Operator(operator)) => (*operator, vec![self.parse_value()?]),
+            Some(token) if token.is_keyword("EXISTS") => ("EXISTS", Vec::new()),
+            Some(token) if token.is_keyword("IS") => {
+                self.eat_keyword("NOT");
+                if !(self.eat_keyword("NULL") || self.eat_keyword("EMPTY")) {
+                    return Err(format!("Expected `NULL` or `EMPTY` after `{attribute} IS`"));
+                }
+                ("IS", Vec::new())
+            }
+            Some(token) if token.is_keyword("NOT") => {
+                if self.eat_keyword("EXISTS") {
+                    ("EXISTS", Vec::new())
+                } else if self.eat_keyword("IN") {
+                    ("IN", self.parse_list()?)
+                } else {
+                    return Err(format!("Expected `IN` or `EXISTS` after `{attribute} NOT`"));
+                }
+            }
+            Some(token) if token.is_keyword("IN") => ("IN", self.parse_list()?),
+            Some(Token::Word(from) | Token::Quoted(from)) => {
+                let from = from.clone();
+                if !self.eat_keyword("TO") {
+                    return Err(format!("Expected an operator after `{attribute}`"));
+                }
+                ("TO", vec![from, self.parse_value()?])
+            }
+            _ => return Err(format!("Expected an operator after `{attribute}`")),
+        };
+
+        self.conditions.push((
+            Condition {
+                attribute,
+                operator,
+                values,
+            },
+            &self.tokens[start..self.position],
+        ));
+
+        Ok(())
+    }
+
+    fn parse_list(&mut self) -> Result<Vec<String>, String> {
+        if self.next() != Some(&Token::OpenBracket) {
+            return Err("Expected `[` after `IN`".to_string());
+        }
+
+        let mut values = Vec::new();
+        loop {
+            values.push(self.parse_value()?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::CloseBracket) => return Ok(values),
+                _ => return Err("Missing `]`".to_string()),
+            }
+        }
+    }
+}
+
+// The Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, x) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, y) in b.iter().enumerate() {
+            current[j + 1] = (previous[j] + usize::from(x != *y))
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> FilterSchema {
+        FilterSchema::new(&[])
+    }
+
+    #[test]
+    fn accepts_existing_facets() {
+        for facet in [
+            "categories:fabric",
+            "project_types:mod",
+            "name:'Mysterious Project'",
+            "client_side:required",
+            "open_source:true",
+            "downloads>1000",
+            "follows >= 10",
+            "categories != forge",
+            "date_created>2023-01-01T00:00:00Z",
+        ] {
+            assert_eq!(schema().validate_facet(facet), Ok(()), "{facet}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_facets() {
+        let error = schema().validate_facet("categoreis:fabric").unwrap_err();
+        assert_eq!(error.clause, "categoreis:fabric");
+        assert_eq!(error.suggestion.as_deref(), Some("categories"));
+
+        let error = schema().validate_facet("downloads>lots").unwrap_err();
+        assert!(error.message.contains("a number"));
+        assert_eq!(error.suggestion, None);
+
+        assert!(schema().validate_facet("license>MIT").is_err());
+        assert!(schema().validate_facet("open_source:yes").is_err());
+        assert!(schema().validate_facet("date_created>yesterday").is_err());
+        assert!(schema().validate_facet("fabric").is_err());
+        assert!(schema().validate_facet(":fabric").is_err());
+    }
+
+    #[test]
+    fn accepts_filters() {
+        for filter in [
+            "",
+            "categories = fabric",
+            "categories = \"fabric\" AND (downloads > 1000 OR follows 10 TO 100)",
+            "NOT mature = true AND license IN [MIT, 'Apache-2.0']",
+            "color EXISTS AND color NOT EXISTS OR license IS NOT EMPTY",
+            "project_id != \"abc\" AND project_id NOT IN [a, b]",
+            "created_timestamp >= 1700000000 AND game_versions = 1.20.1",
+        ] {
+            assert_eq!(schema().validate_filter(filter), Ok(()), "{filter}");
+        }
+    }
+
+    #[test]
+    fn points_at_the_invalid_filter_clause() {
+        let error = schema()
+            .validate_filter("categories = fabric AND downlods > 10")
+            .unwrap_err();
+        assert_eq!(error.clause, "downlods > 10");
+        assert_eq!(error.suggestion.as_deref(), Some("downloads"));
+
+        let error = schema()
+            .validate_filter("categories = fabric OR (follows > many)")
+            .unwrap_err();
+        assert_eq!(error.clause, "follows > many");
+
+        let error = schema().validate_filter("follows true TO 10").unwrap_err();
+        assert_eq!(error.clause, "follows true TO 10");
+
+        // No attribute is close enough to suggest
+        let error = schema().validate_filter("zzzzzzzzzz = 1").unwrap_err();
+        assert_eq!(error.suggestion, None);
+    }
+
+    #[test]
+    fn rejects_malformed_filters() {
+        for filter in [
+            "categories =",
+            "categories fabric",
+            "(categories = fabric",
+            "categories = fabric)",
+            "categories = 'fabric",
+            "categories IN [fabric, forge",
+            "categories = fabric AND",
+            "categories ! fabric",
+        ] {
+            let error = schema().validate_filter(filter).unwrap_err();
+            assert_eq!(error.clause, filter);
+        }
+    }
+
+    #[test]
+    fn measures_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+}
@@ -3,23 +3,42 @@ use log::info;
 use std::collections::HashMap;
 
 use super::IndexingError;
-use crate::database::models::{project_item, version_item, ProjectId, VersionId};
+use crate::database::models::{
+    project_item, version_item, OrganizationId, ProjectId, UserId, VersionId,
+};
 use crate::database::redis::RedisPool;
 use crate::models;
+use crate::models::projects::Link;
 use crate::models::v2::projects::LegacyProject;
 use crate::routes::v2_reroute;
-use crate::search::UploadSearchProject;
+use crate::search::{SearchOrganization, SearchUser, UploadSearchProject};
 use sqlx::postgres::PgPool;
 
+/// Fetches the visible versions to be indexed, alongside their project and owner.
+/// If `project_ids` is set, only versions of those projects are returned.
 pub async fn get_all_ids(
     pool: PgPool,
+    project_ids: Option<&[ProjectId]>,
 ) -> Result<Vec<(VersionId, ProjectId, String)>, IndexingError> {
+    get_ids_page(&pool, project_ids, None, None).await
+}
+
+/// Fetches a page of the visible versions to be indexed, in the order of their ids. The page
+/// starts after the version `after`, and has at most `limit` versions.
+pub async fn get_ids_page(
+    pool: &PgPool,
+    project_ids: Option<&[ProjectId]>,
+    after: Option<VersionId>,
+    limit: Option<i64>,
+) -> Result<Vec<(VersionId, ProjectId, String)>, IndexingError> {
+    let project_ids = project_ids.map(|x| x.iter().map(|x| x.0).collect::<Vec<i64>>());
+
     // TODO: Currently org owner is set to be considered owner. It may be worth considering
     // adding a new facetable 'organization' field to the search index, and using that instead,
     // and making owner to be optional.
-    let all_visible_ids: Vec<(VersionId, ProjectId, String)> = sqlx::query!(
+    let visible_ids: Vec<(VersionId, ProjectId, String)> = sqlx::query!(
         "
-        SELECT v.id id, m.id mod_id, COALESCE(u.username, ou.username) owner_username
+        SELECT DISTINCT ON (v.id) v.id id, m.id mod_id, COALESCE(u.username, ou.username) owner_username
         FROM versions v
         INNER JOIN mods m ON v.mod_id = m.id AND m.status = ANY($2)
         LEFT JOIN team_members tm ON tm.team_id = m.team_id AND tm.is_owner = TRUE AND tm.accepted = TRUE
@@ -27,20 +46,21 @@ pub async fn get_all_ids(
         LEFT JOIN organizations o ON o.id = m.organization_id
         LEFT JOIN team_members otm ON otm.team_id = o.team_id AND otm.is_owner = TRUE AND otm.accepted = TRUE
         LEFT JOIN users ou ON otm.user_id = ou.id
-        WHERE v.status != ANY($1)
-        GROUP BY v.id, m.id, u.username, ou.username
-        ORDER BY m.id DESC;
+        WHERE v.status != ANY($1) AND ($3::bigint[] IS NULL OR m.id = ANY($3))
+            AND ($4::bigint IS NULL OR v.id > $4)
+        ORDER BY v.id
+        LIMIT $5;
         ",
         &*crate::models::projects::VersionStatus::iterator()
             .filter(|x| x.is_hidden())
             .map(|x| x.to_string())
             .collect::<Vec<String>>(),
-        &*crate::models::projects::ProjectStatus::iterator()
-            .filter(|x| x.is_searchable())
-            .map(|x| x.to_string())
-            .collect::<Vec<String>>(),
+        &*listed_project_statuses(),
+        project_ids.as_deref(),
+        after.map(|x| x.0),
+        limit,
     )
-    .fetch_many(&pool)
+    .fetch_many(pool)
     .try_filter_map(|e| async move {
         Ok(e.right().map(|m| {
             let project_id: ProjectId = ProjectId(m.mod_id);
@@ -52,7 +72,28 @@ pub async fn get_all_ids(
     .try_collect::<Vec<_>>()
     .await?;
 
-    Ok(all_visible_ids)
+    Ok(visible_ids)
+}
+
+/// Counts the visible versions to be indexed
+pub async fn count_all_ids(pool: &PgPool) -> Result<i64, IndexingError> {
+    let count = sqlx::query!(
+        "
+        SELECT COUNT(*) FROM versions v
+        INNER JOIN mods m ON v.mod_id = m.id AND m.status = ANY($2)
+        WHERE v.status != ANY($1)
+        ",
+        &*crate::models::projects::VersionStatus::iterator()
+            .filter(|x| x.is_hidden())
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>(),
+        &*listed_project_statuses(),
+    )
+    .fetch_one(pool)
+    .await?
+    .count;
+
+    Ok(count.unwrap_or(0))
 }
 
 pub async fn index_local(
@@ -69,7 +110,11 @@ pub async fn index_local(
     let projects: HashMap<_, _> = project_item::Project::get_many_ids(&project_ids, pool, redis)
         .await?
         .into_iter()
-        .map(|p| (p.inner.id, p))
+        .map(|mut p| {
+            // Search results are public, so they only show approved images
+            p.hide_unmoderated_images(false);
+            (p.inner.id, p)
+        })
         .collect();
 
     info!("Fetched local projects!");
@@ -118,7 +163,13 @@ pub async fn index_local(
         // Uses version loaders, not project loaders.
         categories.append(&mut v.loaders.clone());
 
-        let display_categories = categories.clone();
+        // The categories picked to be shown on the project are shown in place of the rest
+        let mut display_categories = if m.display_categories.is_empty() {
+            m.categories.clone()
+        } else {
+            m.display_categories.clone()
+        };
+        display_categories.append(&mut v.loaders.clone());
         categories.append(&mut additional_categories);
 
         let version_fields = v.version_fields.clone();
@@ -203,12 +254,23 @@ pub async fn index_local(
             .collect::<Vec<_>>();
         let featured_gallery = featured_gallery.first().cloned();
 
+        let mut translations = HashMap::new();
+        for translation in &m.translations {
+            if let Some(name) = &translation.name {
+                translations.insert(format!("name_{}", translation.language), name.clone());
+            }
+            if let Some(summary) = &translation.summary {
+                translations.insert(format!("summary_{}", translation.language), summary.clone());
+            }
+        }
+
         let usp = UploadSearchProject {
             version_id: version_id.to_string(),
             project_id: project_id.to_string(),
             name: m.inner.name.clone(),
             summary: m.inner.summary.clone(),
             categories,
+            primary_categories: m.categories.clone(),
             follows: m.inner.follows,
             downloads: m.inner.downloads,
             icon_url: m.inner.icon_url.clone(),
@@ -225,7 +287,10 @@ pub async fn index_local(
             display_categories,
             open_source,
             color: m.inner.color,
+            mature: m.inner.mature,
+            archived: m.inner.status == models::projects::ProjectStatus::Archived,
             loader_fields,
+            translations,
             license_url: m.inner.license_url.clone(),
             monetization_status: Some(m.inner.monetization_status),
             team_id: team_id.to_string(),
@@ -237,9 +302,14 @@ pub async fn index_local(
             status: m.inner.status,
             requested_status: m.inner.requested_status,
             games: m.games.clone(),
-            links: m.urls.clone(),
+            links: m
+                .urls
+                .iter()
+                .map(|x| (x.name.clone(), Link::from(x.clone())))
+                .collect(),
             gallery_items: m.gallery_items.clone(),
             loaders,
+            version_loaders: v.loaders.clone(),
         };
 
         uploads.push(usp);
@@ -247,3 +317,84 @@ pub async fn index_local(
 
     Ok(uploads)
 }
+
+fn listed_project_statuses() -> Vec<String> {
+    crate::models::projects::ProjectStatus::iterator()
+        .filter(|x| x.is_searchable())
+        .map(|x| x.to_string())
+        .collect()
+}
+
+/// Fetches the user documents to be indexed, leaving out banned users.
+/// If `user_ids` is set, only those users are returned.
+pub async fn get_users(
+    pool: &PgPool,
+    user_ids: Option<&[UserId]>,
+) -> Result<Vec<SearchUser>, IndexingError> {
+    let user_ids = user_ids.map(|x| x.iter().map(|x| x.0).collect::<Vec<i64>>());
+
+    let users = sqlx::query!(
+        "
+        SELECT u.id, u.username, u.avatar_url, u.bio,
+            COUNT(m.id) projects, COALESCE(SUM(m.follows), 0)::bigint follows
+        FROM users u
+        LEFT JOIN team_members tm ON tm.user_id = u.id AND tm.is_owner = TRUE AND tm.accepted = TRUE
+        LEFT JOIN mods m ON m.team_id = tm.team_id AND m.status = ANY($1)
+        WHERE NOT u.banned AND ($2::bigint[] IS NULL OR u.id = ANY($2))
+        GROUP BY u.id
+        ",
+        &listed_project_statuses(),
+        user_ids.as_deref(),
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| SearchUser {
+        user_id: models::ids::UserId::from(UserId(x.id)).to_string(),
+        username: x.username,
+        avatar_url: x.avatar_url,
+        bio: x.bio,
+        follows: x.follows.unwrap_or(0),
+        projects: x.projects.unwrap_or(0),
+    })
+    .collect();
+
+    Ok(users)
+}
+
+/// Fetches the organization documents to be indexed.
+/// If `organization_ids` is set, only those organizations are returned.
+pub async fn get_organizations(
+    pool: &PgPool,
+    organization_ids: Option<&[OrganizationId]>,
+) -> Result<Vec<SearchOrganization>, IndexingError> {
+    let organization_ids = organization_ids.map(|x| x.iter().map(|x| x.0).collect::<Vec<i64>>());
+
+    let organizations = sqlx::query!(
+        "
+        SELECT o.id, o.slug, o.name, o.icon_url, o.description,
+            COUNT(m.id) projects, COALESCE(SUM(m.follows), 0)::bigint follows
+        FROM organizations o
+        LEFT JOIN mods m ON m.organization_id = o.id AND m.status = ANY($1)
+        WHERE $2::bigint[] IS NULL OR o.id = ANY($2)
+        GROUP BY o.id
+        ",
+        &listed_project_statuses(),
+        organization_ids.as_deref(),
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| SearchOrganization {
+        organization_id: models::ids::OrganizationId::from(OrganizationId(x.id)).to_string(),
+        slug: x.slug,
+        name: x.name,
+        icon_url: x.icon_url,
+        description: x.description,
+        follows: x.follows.unwrap_or(0),
+        projects: x.projects.unwrap_or(0),
+    })
+    .collect();
+
+    Ok(organizations)
+}
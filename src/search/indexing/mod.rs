@@ -2,20 +2,27 @@
 pub mod local_import;
 
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use crate::database::cache_warming::CacheWarmer;
+use crate::database::models::{OrganizationId, ProjectId, UserId, VersionId};
 use crate::database::redis::RedisPool;
-use crate::models::ids::base62_impl::to_base62;
+use crate::metrics::METRICS;
+use crate::models::ids::base62_impl::{parse_base62, to_base62};
 use crate::search::{SearchConfig, UploadSearchProject};
+use crate::util::jobs::{JobHandle, JobType};
+use crate::util::language::validate_language;
 use local_import::index_local;
-use log::info;
+use log::{info, warn};
 use meilisearch_sdk::client::Client;
+use meilisearch_sdk::documents::DocumentsQuery;
 use meilisearch_sdk::indexes::Index;
 use meilisearch_sdk::settings::{PaginationSetting, Settings};
+use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgPool;
 use thiserror::Error;
 
-use self::local_import::get_all_ids;
+use self::local_import::{count_all_ids, get_all_ids, get_ids_page, get_organizations, get_users};
 
 #[derive(Error, Debug)]
 pub enum IndexingError {
@@ -39,6 +46,9 @@ pub enum IndexingError {
 const MEILISEARCH_CHUNK_SIZE: usize = 2500; // Should be less than FETCH_PROJECT_SIZE
 const FETCH_PROJECT_SIZE: usize = 5000;
 
+// How many documents are read from the index at once when looking for stale ones
+const PRUNE_PAGE_SIZE: usize = 1000;
+
 const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
 
 pub async fn remove_documents(
@@ -56,71 +66,471 @@ pub async fn remove_documents(
     Ok(())
 }
 
+/// Re-indexes only the given projects, so edits show up in search without waiting
+/// for the next full reindex. Versions of these projects which are no longer visible
+/// are removed from the index.
+pub async fn reindex_projects(
+    project_ids: &[ProjectId],
+    pool: &PgPool,
+    redis: &RedisPool,
+    config: &SearchConfig,
+) -> Result<(), IndexingError> {
+    if project_ids.is_empty() {
+        return Ok(());
+    }
+
+    let indices = get_indexes(config).await?;
+
+    let all_loader_fields =
+        crate::database::models::loader_fields::LoaderField::get_fields_all(pool, redis)
+            .await?
+            .into_iter()
+            .map(|x| x.field)
+            .collect::<Vec<_>>();
+
+    let visible_ids = get_all_ids(pool.clone(), Some(project_ids))
+        .await?
+        .into_iter()
+        .map(|(version_id, project_id, owner_username)| {
+            (version_id, (project_id, owner_username.to_lowercase()))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let hidden_ids = sqlx::query!(
+        "
+        SELECT id FROM versions
+        WHERE mod_id = ANY($1)
+        ",
+        &project_ids.iter().map(|x| x.0).collect::<Vec<i64>>(),
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|x| VersionId(x.id))
+    .filter(|x| !visible_ids.contains_key(x))
+    .map(|x| x.into())
+    .collect::<Vec<crate::models::ids::VersionId>>();
+
+    if !hidden_ids.is_empty() {
+        remove_documents(&hidden_ids, config).await?;
+    }
+
+    if !visible_ids.is_empty() {
+        let uploads = index_local(pool, redis, visible_ids).await?;
+        add_projects(&indices, uploads, all_loader_fields, config, None).await?;
+    }
+
+    Ok(())
+}
+
+/// Runs [`reindex_projects`] in the background, logging any failure.
+/// The next full reindex will pick up anything missed here.
+pub fn spawn_reindex_projects(
+    project_ids: Vec<ProjectId>,
+    pool: PgPool,
+    redis: RedisPool,
+    config: SearchConfig,
+) {
+    actix_rt::spawn(async move {
+        if let Err(e) = reindex_projects(&project_ids, &pool, &redis, &config).await {
+            warn!("Failed to reindex projects {:?}: {}", project_ids, e);
+        }
+    });
+}
+
+/// Indexes every project, reporting progress to the job registry, then warms the caches of
+/// the most downloaded projects
 pub async fn index_projects(
     pool: PgPool,
     redis: RedisPool,
     config: &SearchConfig,
+) -> Result<(), IndexingError> {
+    let start = std::time::Instant::now();
+    let mut job = JobHandle::start(JobType::SearchIndex, None, &redis).await;
+    let result = index_all_projects(pool.clone(), &redis, config, &mut job).await;
+    job.finish(&result).await;
+    METRICS.observe_search_index_run(result.is_ok(), start.elapsed());
+
+    // The caches are warmed once indexing is done, as it's run after deploys
+    if result.is_ok() {
+        if let Err(err) = CacheWarmer::from_env().warm(&pool, &redis).await {
+            warn!("Failed to warm caches after indexing: {}", err);
+        }
+    }
+
+    result
+}
+
+async fn index_all_projects(
+    pool: PgPool,
+    redis: &RedisPool,
+    config: &SearchConfig,
+    job: &mut JobHandle,
 ) -> Result<(), IndexingError> {
     info!("Indexing projects.");
 
     let indices = get_indexes(config).await?;
 
+    // Every loader field is added to the indexes' attributes with the first page, rather than
+    // as pages with values for them are found
     let all_loader_fields =
-        crate::database::models::loader_fields::LoaderField::get_fields_all(&pool, &redis)
+        crate::database::models::loader_fields::LoaderField::get_fields_all(&pool, redis)
             .await?
             .into_iter()
             .map(|x| x.field)
             .collect::<Vec<_>>();
 
-    let all_ids = get_all_ids(pool.clone()).await?;
-    let all_ids_len = all_ids.len();
-    info!("Got all ids, indexing {} projects", all_ids_len);
+    let all_ids_len = count_all_ids(&pool).await? as usize;
+    info!("Indexing {} projects", all_ids_len);
 
+    // Every version is added to each of the indexes
+    job.progress(0, (all_ids_len * indices.len()) as u64, "Fetching projects")
+        .await;
+
+    // Versions are fetched a page at a time, and each page is added to the indexes before the
+    // next is fetched, so only one page is held in memory
     let mut so_far = 0;
-    let as_chunks: Vec<_> = all_ids
-        .into_iter()
-        .chunks(FETCH_PROJECT_SIZE)
-        .into_iter()
-        .map(|x| x.collect::<Vec<_>>())
-        .collect();
+    let mut after = None;
+    loop {
+        let id_page = get_ids_page(&pool, None, after, Some(FETCH_PROJECT_SIZE as i64)).await?;
+        let Some((last_id, _, _)) = id_page.last() else {
+            break;
+        };
+        after = Some(*last_id);
+        let page_len = id_page.len();
 
-    for id_chunk in as_chunks {
         info!(
-            "Fetching chunk {}-{}/{}, size: {}",
+            "Fetched page {}-{}/{}, size: {}",
             so_far,
-            so_far + FETCH_PROJECT_SIZE,
+            so_far + page_len,
             all_ids_len,
-            id_chunk.len()
+            page_len
         );
-        so_far += FETCH_PROJECT_SIZE;
+        so_far += page_len;
 
-        let id_chunk = id_chunk
+        let id_page = id_page
             .into_iter()
             .map(|(version_id, project_id, owner_username)| {
                 (version_id, (project_id, owner_username.to_lowercase()))
             })
             .collect::<HashMap<_, _>>();
-        let uploads = index_local(&pool, &redis, id_chunk).await?;
-
-        info!("Got chunk, adding to docs_to_add");
-        add_projects(&indices, uploads, all_loader_fields.clone(), config).await?;
+        let uploads = index_local(&pool, redis, id_page).await?;
+
+        info!("Got page, adding to docs_to_add");
+        add_projects(
+            &indices,
+            uploads,
+            all_loader_fields.clone(),
+            config,
+            Some(&mut *job),
+        )
+        .await?;
+
+        if page_len < FETCH_PROJECT_SIZE {
+            break;
+        }
     }
 
     info!("Done adding projects.");
     Ok(())
 }
 
+/// Indexes every user who isn't banned, removing the users who are no longer indexed
+pub async fn index_users(pool: &PgPool, config: &SearchConfig) -> Result<(), IndexingError> {
+    info!("Indexing users.");
+
+    let index = get_user_index(config).await?;
+    let users = get_users(pool, None).await?;
+    let indexed_ids = users
+        .iter()
+        .map(|x| x.user_id.clone())
+        .collect::<HashSet<_>>();
+
+    replace_documents(config, &index, &users, "user_id", &indexed_ids).await?;
+
+    info!("Done indexing {} users.", users.len());
+    Ok(())
+}
+
+/// Indexes every organization, removing the organizations which no longer exist
+pub async fn index_organizations(
+    pool: &PgPool,
+    config: &SearchConfig,
+) -> Result<(), IndexingError> {
+    info!("Indexing organizations.");
+
+    let index = get_organization_index(config).await?;
+    let organizations = get_organizations(pool, None).await?;
+    let indexed_ids = organizations
+        .iter()
+        .map(|x| x.organization_id.clone())
+        .collect::<HashSet<_>>();
+
+    replace_documents(
+        config,
+        &index,
+        &organizations,
+        "organization_id",
+        &indexed_ids,
+    )
+    .await?;
+
+    info!("Done indexing {} organizations.", organizations.len());
+    Ok(())
+}
+
+/// Re-indexes only the given users, such as after they're renamed. Users who are banned or
+/// were deleted are removed from the index.
+pub async fn reindex_users(
+    user_ids: &[UserId],
+    pool: &PgPool,
+    config: &SearchConfig,
+) -> Result<(), IndexingError> {
+    if user_ids.is_empty() {
+        return Ok(());
+    }
+
+    let index = get_user_index(config).await?;
+    let users = get_users(pool, Some(user_ids)).await?;
+    let removed_ids = user_ids
+        .iter()
+        .map(|x| crate::models::ids::UserId::from(*x).to_string())
+        .filter(|id| !users.iter().any(|x| &x.user_id == id))
+        .collect::<Vec<_>>();
+
+    update_documents(config, &index, &users, "user_id", &removed_ids).await
+}
+
+/// Re-indexes only the given organizations, such as after they're edited. Organizations which
+/// were deleted are removed from the index.
+pub async fn reindex_organizations(
+    organization_ids: &[OrganizationId],
+    pool: &PgPool,
+    config: &SearchConfig,
+) -> Result<(), IndexingError> {
+    if organization_ids.is_empty() {
+        return Ok(());
+    }
+
+    let index = get_organization_index(config).await?;
+    let organizations = get_organizations(pool, Some(organization_ids)).await?;
+    let removed_ids = organization_ids
+        .iter()
+        .map(|x| crate::models::ids::OrganizationId::from(*x).to_string())
+        .filter(|id| !organizations.iter().any(|x| &x.organization_id == id))
+        .collect::<Vec<_>>();
+
+    update_documents(
+        config,
+        &index,
+        &organizations,
+        "organization_id",
+        &removed_ids,
+    )
+    .await
+}
+
+// Adds or replaces the documents, and removes the documents with the removed ids
+async fn update_documents<T: Serialize>(
+    config: &SearchConfig,
+    index: &Index,
+    documents: &[T],
+    primary_key: &str,
+    removed_ids: &[String],
+) -> Result<(), IndexingError> {
+    let client = config.make_client();
+
+    for chunk in documents.chunks(MEILISEARCH_CHUNK_SIZE) {
+        index
+            .add_or_replace(chunk, Some(primary_key))
+            .await?
+            .wait_for_completion(&client, None, Some(TIMEOUT))
+            .await?;
+    }
+
+    for chunk in removed_ids.chunks(MEILISEARCH_CHUNK_SIZE) {
+        index
+            .delete_documents(chunk)
+            .await?
+            .wait_for_completion(&client, None, Some(TIMEOUT))
+            .await?;
+    }
+
+    Ok(())
+}
+
+// Replaces every document of the index, removing those whose ids aren't in `indexed_ids`
+async fn replace_documents<T: Serialize>(
+    config: &SearchConfig,
+    index: &Index,
+    documents: &[T],
+    primary_key: &str,
+    indexed_ids: &HashSet<String>,
+) -> Result<(), IndexingError> {
+    // The documents are only removed once every page has been read, as removing them while
+    // paging would shift the pages
+    let mut removed_ids = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let page = DocumentsQuery::new(index)
+            .with_offset(offset)
+            .with_limit(PRUNE_PAGE_SIZE)
+            .with_fields([primary_key])
+            .execute::<HashMap<String, String>>()
+            .await?;
+
+        removed_ids.extend(
+            page.results
+                .iter()
+                .filter_map(|x| x.get(primary_key))
+                .filter(|x| !indexed_ids.contains(*x))
+                .cloned(),
+        );
+
+        offset += page.results.len();
+        if page.results.len() < PRUNE_PAGE_SIZE {
+            break;
+        }
+    }
+
+    update_documents(config, index, documents, primary_key, &removed_ids).await
+}
+
+#[derive(Deserialize)]
+struct IndexedVersion {
+    version_id: String,
+}
+
+/// Removes documents from the search indexes whose version was deleted, or whose project is
+/// no longer listed, without going through the API. Returns how many documents were removed.
+pub async fn prune_documents(
+    pool: &PgPool,
+    redis: &RedisPool,
+    config: &SearchConfig,
+) -> Result<usize, IndexingError> {
+    let mut job = JobHandle::start(JobType::SearchPrune, None, redis).await;
+    let result = prune_stale_documents(pool, config, &mut job).await;
+    job.finish(&result).await;
+
+    result
+}
+
+async fn prune_stale_documents(
+    pool: &PgPool,
+    config: &SearchConfig,
+    job: &mut JobHandle,
+) -> Result<usize, IndexingError> {
+    info!("Pruning stale search documents.");
+
+    // Every index holds the same documents, so only the first needs to be read
+    let indices = get_indexes(config).await?;
+    let index = &indices[0];
+
+    let listed_statuses = crate::models::projects::ProjectStatus::iterator()
+        .filter(|x| x.is_searchable())
+        .map(|x| x.to_string())
+        .collect::<Vec<String>>();
+    let hidden_version_statuses = crate::models::projects::VersionStatus::iterator()
+        .filter(|x| x.is_hidden())
+        .map(|x| x.to_string())
+        .collect::<Vec<String>>();
+
+    // Documents are only removed once every page has been read, as removing them while
+    // paging would shift the pages
+    let mut stale_ids = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let page = DocumentsQuery::new(index)
+            .with_offset(offset)
+            .with_limit(PRUNE_PAGE_SIZE)
+            .with_fields(["version_id"])
+            .execute::<IndexedVersion>()
+            .await?;
+
+        if offset == 0 {
+            job.progress(0, page.total as u64, "Checking documents")
+                .await;
+        }
+
+        let page_ids = page
+            .results
+            .iter()
+            .filter_map(|x| match parse_base62(&x.version_id) {
+                Ok(id) => Some(id as i64),
+                Err(_) => {
+                    warn!(
+                        "Search document has an invalid version ID: {}",
+                        x.version_id
+                    );
+                    None
+                }
+            })
+            .collect::<Vec<i64>>();
+
+        if !page_ids.is_empty() {
+            let listed_ids = sqlx::query!(
+                "
+                SELECT v.id FROM versions v
+                INNER JOIN mods m ON m.id = v.mod_id
+                WHERE v.id = ANY($1) AND m.status = ANY($2) AND NOT (v.status = ANY($3))
+                ",
+                &page_ids,
+                &listed_statuses,
+                &hidden_version_statuses,
+            )
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|x| x.id)
+            .collect::<std::collections::HashSet<i64>>();
+
+            stale_ids.extend(
+                page_ids
+                    .into_iter()
+                    .filter(|x| !listed_ids.contains(x))
+                    .map(|x| crate::models::ids::VersionId(x as u64)),
+            );
+        }
+
+        offset += page.results.len();
+        job.advance(page.results.len() as u64, "Checking documents")
+            .await;
+
+        if page.results.len() < PRUNE_PAGE_SIZE {
+            break;
+        }
+    }
+
+    info!(
+        "Checked {} search documents, pruning {} stale ones",
+        offset,
+        stale_ids.len()
+    );
+
+    for chunk in stale_ids.chunks(MEILISEARCH_CHUNK_SIZE) {
+        remove_documents(chunk, config).await?;
+    }
+
+    info!("Done pruning search documents.");
+    Ok(stale_ids.len())
+}
+
 pub async fn get_indexes(
     config: &SearchConfig,
 ) -> Result<Vec<Index>, meilisearch_sdk::errors::Error> {
     let client = config.make_client();
     let project_name = config.get_index_name("projects");
     let project_filtered_name = config.get_index_name("projects_filtered");
-    let projects_index = create_or_update_index(&client, &project_name, None).await?;
+    let project_versions_name = config.get_index_name("projects_versions");
+    let projects_index =
+        create_or_update_index(&client, &project_name, "version_id", default_settings()).await?;
     let projects_filtered_index = create_or_update_index(
         &client,
         &project_filtered_name,
-        Some(&[
+        "version_id",
+        default_settings().with_ranking_rules([
             "sort",
             "words",
             "typo",
@@ -130,14 +540,56 @@ pub async fn get_indexes(
         ]),
     )
     .await?;
+    // Searches grouped by version use this index, as Meilisearch can't turn off the distinct
+    // attribute for a single search
+    let mut versions_settings = default_settings();
+    versions_settings.distinct_attribute = None;
+    let projects_versions_index = create_or_update_index(
+        &client,
+        &project_versions_name,
+        "version_id",
+        versions_settings,
+    )
+    .await?;
+
+    Ok(vec![
+        projects_index,
+        projects_filtered_index,
+        projects_versions_index,
+    ])
+}
 
-    Ok(vec![projects_index, projects_filtered_index])
+pub async fn get_user_index(
+    config: &SearchConfig,
+) -> Result<Index, meilisearch_sdk::errors::Error> {
+    let client = config.make_client();
+    create_or_update_index(
+        &client,
+        &config.get_index_name("users"),
+        "user_id",
+        user_settings(),
+    )
+    .await
+}
+
+pub async fn get_organization_index(
+    config: &SearchConfig,
+) -> Result<Index, meilisearch_sdk::errors::Error> {
+    let client = config.make_client();
+    create_or_update_index(
+        &client,
+        &config.get_index_name("organizations"),
+        "organization_id",
+        organization_settings(),
+    )
+    .await
 }
 
 async fn create_or_update_index(
     client: &Client,
     name: &str,
-    custom_rules: Option<&'static [&'static str]>,
+    primary_key: &str,
+    mut settings: Settings,
 ) -> Result<Index, meilisearch_sdk::errors::Error> {
     info!("Updating/creating index.");
 
@@ -147,10 +599,39 @@ async fn create_or_update_index(
 
             let old_settings = index.get_settings().await?;
 
-            let mut settings = default_settings();
+            // Translated attributes are added as projects are indexed, so they're kept
+            if let (Some(searchable), Some(old_searchable)) = (
+                settings.searchable_attributes.as_mut(),
+                old_settings.searchable_attributes.as_ref(),
+            ) {
+                searchable.extend(
+                    old_searchable
+                        .iter()
+                        .filter(|x| is_translated_attribute(x))
+                        .cloned(),
+                );
+            }
 
-            if let Some(custom_rules) = custom_rules {
-                settings = settings.with_ranking_rules(custom_rules);
+            // Loader fields are added as projects are indexed, so they're kept as well
+            for (attributes, old_attributes) in [
+                (
+                    settings.filterable_attributes.as_mut(),
+                    old_settings.filterable_attributes.as_ref(),
+                ),
+                (
+                    settings.displayed_attributes.as_mut(),
+                    old_settings.displayed_attributes.as_ref(),
+                ),
+            ] {
+                if let (Some(attributes), Some(old_attributes)) = (attributes, old_attributes) {
+                    let kept = old_attributes
+                        .iter()
+                        .filter(|x| !attributes.contains(x))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    attributes.extend(kept);
+                    attributes.sort();
+                }
             }
 
             let old_settings = Settings {
@@ -243,7 +724,7 @@ async fn create_or_update_index(
             info!("Creating index.");
 
             // Only create index and set settings if the index doesn't already exist
-            let task = client.create_index(name, Some("version_id")).await?;
+            let task = client.create_index(name, Some(primary_key)).await?;
             let task = task
                 .wait_for_completion(client, None, Some(TIMEOUT))
                 .await?;
@@ -251,12 +732,6 @@ async fn create_or_update_index(
                 .try_make_index(client)
                 .map_err(|x| x.unwrap_failure())?;
 
-            let mut settings = default_settings();
-
-            if let Some(custom_rules) = custom_rules {
-                settings = settings.with_ranking_rules(custom_rules);
-            }
-
             index
                 .set_settings(&settings)
                 .await?
@@ -272,6 +747,7 @@ async fn add_to_index(
     client: &Client,
     index: &Index,
     mods: &[UploadSearchProject],
+    mut job: Option<&mut JobHandle>,
 ) -> Result<(), IndexingError> {
     for chunk in mods.chunks(MEILISEARCH_CHUNK_SIZE) {
         info!(
@@ -284,6 +760,15 @@ async fn add_to_index(
             .wait_for_completion(client, None, Some(std::time::Duration::from_secs(3600)))
             .await?;
         info!("Added chunk of {} projects to index", chunk.len());
+        METRICS.observe_indexed_documents(&index.uid, chunk.len());
+
+        if let Some(job) = job.as_deref_mut() {
+            job.advance(
+                chunk.len() as u64,
+                format!("Added {} projects to {}", chunk.len(), index.uid),
+            )
+            .await;
+        }
     }
 
     Ok(())
@@ -293,31 +778,62 @@ async fn update_and_add_to_index(
     client: &Client,
     index: &Index,
     projects: &[UploadSearchProject],
-    _additional_fields: &[String],
+    additional_fields: &[String],
+    job: Option<&mut JobHandle>,
 ) -> Result<(), IndexingError> {
-    // TODO: Uncomment this- hardcoding loader_fields is a band-aid fix, and will be fixed soon
-    // let mut new_filterable_attributes: Vec<String> = index.get_filterable_attributes().await?;
-    // let mut new_displayed_attributes = index.get_displayed_attributes().await?;
-
-    // new_filterable_attributes.extend(additional_fields.iter().map(|s: &String| s.to_string()));
-    // new_displayed_attributes.extend(additional_fields.iter().map(|s| s.to_string()));
-    // info!("add attributes.");
-    // let filterable_task = index
-    //     .set_filterable_attributes(new_filterable_attributes)
-    //     .await?;
-    // let displayable_task = index
-    //     .set_displayed_attributes(new_displayed_attributes)
-    //     .await?;
-    // filterable_task
-    //     .wait_for_completion(client, None, Some(TIMEOUT))
-    //     .await?;
-    // displayable_task
-    //     .wait_for_completion(client, None, Some(TIMEOUT))
-    //     .await?;
+    // Loader fields are only filterable once the index knows about them, so fields added since
+    // the last run are added on
+    let mut filterable_attributes = index.get_filterable_attributes().await?;
+    let mut displayed_attributes = index.get_displayed_attributes().await?;
+    let new_filterable_attributes = additional_fields
+        .iter()
+        .filter(|x| !filterable_attributes.contains(*x))
+        .cloned()
+        .collect::<Vec<_>>();
+    let new_displayed_attributes = additional_fields
+        .iter()
+        .filter(|x| !displayed_attributes.contains(*x))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !new_filterable_attributes.is_empty() || !new_displayed_attributes.is_empty() {
+        info!("Adding loader field attributes.");
+        filterable_attributes.extend(new_filterable_attributes);
+        displayed_attributes.extend(new_displayed_attributes);
+        index
+            .set_filterable_attributes(&filterable_attributes)
+            .await?
+            .wait_for_completion(client, None, Some(TIMEOUT))
+            .await?;
+        index
+            .set_displayed_attributes(&displayed_attributes)
+            .await?
+            .wait_for_completion(client, None, Some(TIMEOUT))
+            .await?;
+    }
+
+    // Translated names and summaries are only searchable once the index knows about them
+    let mut searchable_attributes = index.get_searchable_attributes().await?;
+    let new_translated_attributes = projects
+        .iter()
+        .flat_map(|x| x.translations.keys())
+        .unique()
+        .filter(|x| !searchable_attributes.contains(*x))
+        .cloned()
+        .sorted()
+        .collect::<Vec<_>>();
+    if !new_translated_attributes.is_empty() {
+        info!("Adding translated searchable attributes.");
+        searchable_attributes.extend(new_translated_attributes);
+        index
+            .set_searchable_attributes(&searchable_attributes)
+            .await?
+            .wait_for_completion(client, None, Some(TIMEOUT))
+            .await?;
+    }
 
     info!("Adding to index.");
 
-    add_to_index(client, index, projects).await?;
+    add_to_index(client, index, projects, job).await?;
 
     Ok(())
 }
@@ -327,10 +843,18 @@ pub async fn add_projects(
     projects: Vec<UploadSearchProject>,
     additional_fields: Vec<String>,
     config: &SearchConfig,
+    mut job: Option<&mut JobHandle>,
 ) -> Result<(), IndexingError> {
     let client = config.make_client();
     for index in indices {
-        update_and_add_to_index(&client, index, &projects, &additional_fields).await?;
+        update_and_add_to_index(
+            &client,
+            index,
+            &projects,
+            &additional_fields,
+            job.as_deref_mut(),
+        )
+        .await?;
     }
 
     Ok(())
@@ -374,6 +898,7 @@ const DEFAULT_DISPLAYED_ATTRIBUTES: &[&str] = &[
     "gallery",
     "featured_gallery",
     "color",
+    "mature",
     // Note: loader fields are not here, but are added on as they are needed (so they can be dynamically added depending on which exist).
     // TODO: remove these- as they should be automatically populated. This is a band-aid fix.
     "server_only",
@@ -400,11 +925,26 @@ const DEFAULT_DISPLAYED_ATTRIBUTES: &[&str] = &[
     "links",
     "gallery_items",
     "loaders", // search uses loaders as categories- this is purely for the Project model.
+    "version_loaders",
+    "primary_categories",
 ];
 
 const DEFAULT_SEARCHABLE_ATTRIBUTES: &[&str] = &["name", "summary", "author", "slug"];
 
-const DEFAULT_ATTRIBUTES_FOR_FACETING: &[&str] = &[
+/// Attributes which are also indexed in each language projects are translated to, as
+/// `{attribute}_{language}`
+const TRANSLATED_SEARCHABLE_ATTRIBUTES: &[&str] = &["name", "summary"];
+
+fn is_translated_attribute(attribute: &str) -> bool {
+    attribute
+        .split_once('_')
+        .is_some_and(|(attribute, language)| {
+            TRANSLATED_SEARCHABLE_ATTRIBUTES.contains(&attribute)
+                && validate_language(language).is_ok()
+        })
+}
+
+pub(crate) const DEFAULT_ATTRIBUTES_FOR_FACETING: &[&str] = &[
     "categories",
     "license",
     "project_types",
@@ -419,6 +959,8 @@ const DEFAULT_ATTRIBUTES_FOR_FACETING: &[&str] = &[
     "project_id",
     "open_source",
     "color",
+    "mature",
+    "archived",
     // Note: loader fields are not here, but are added on as they are needed (so they can be dynamically added depending on which exist).
     // TODO: remove these- as they should be automatically populated. This is a band-aid fix.
     "server_only",
@@ -434,3 +976,44 @@ const DEFAULT_ATTRIBUTES_FOR_FACETING: &[&str] = &[
 
 const DEFAULT_SORTABLE_ATTRIBUTES: &[&str] =
     &["downloads", "follows", "date_created", "date_modified"];
+
+fn user_settings() -> Settings {
+    let mut sorted_display = USER_DISPLAYED_ATTRIBUTES.to_vec();
+    sorted_display.sort();
+    Settings::new()
+        .with_displayed_attributes(sorted_display)
+        .with_searchable_attributes(["username", "bio"])
+        .with_pagination(PaginationSetting {
+            max_total_hits: 2147483647,
+        })
+}
+
+const USER_DISPLAYED_ATTRIBUTES: &[&str] = &[
+    "user_id",
+    "username",
+    "avatar_url",
+    "bio",
+    "follows",
+    "projects",
+];
+
+fn organization_settings() -> Settings {
+    let mut sorted_display = ORGANIZATION_DISPLAYED_ATTRIBUTES.to_vec();
+    sorted_display.sort();
+    Settings::new()
+        .with_displayed_attributes(sorted_display)
+        .with_searchable_attributes(["name", "slug", "description"])
+        .with_pagination(PaginationSetting {
+            max_total_hits: 2147483647,
+        })
+}
+
+const ORGANIZATION_DISPLAYED_ATTRIBUTES: &[&str] = &[
+    "organization_id",
+    "slug",
+    "name",
+    "icon_url",
+    "description",
+    "follows",
+    "projects",
+];
@@ -1,11 +1,16 @@
-use crate::database::models::project_item::{GalleryItem, LinkUrl};
+use crate::database::models::project_item::GalleryItem;
+use crate::database::models::DatabaseError;
 use crate::models::error::ApiError;
-use crate::models::projects::{MonetizationStatus, ProjectStatus, SearchRequest};
+use crate::models::projects::{Link, MonetizationStatus, ProjectStatus, SearchRequest};
+use crate::util::env::parse_var;
 use actix_web::http::StatusCode;
 use actix_web::HttpResponse;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use filters::FilterError;
 use itertools::Itertools;
 use meilisearch_sdk::client::Client;
+use meilisearch_sdk::search::{SearchQuery, Selectors};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::borrow::Cow;
@@ -13,6 +18,8 @@ use std::collections::HashMap;
 use std::fmt::Write;
 use thiserror::Error;
 
+pub mod analytics;
+pub mod filters;
 pub mod indexing;
 
 #[derive(Error, Debug)]
@@ -29,6 +36,12 @@ pub enum SearchError {
     Env(#[from] dotenvy::Error),
     #[error("Invalid index to sort by: {0}")]
     InvalidIndex(String),
+    #[error("Invalid Input: {0}")]
+    InvalidInput(String),
+    #[error("Database Error: {0}")]
+    Database(#[from] DatabaseError),
+    #[error("{0}")]
+    InvalidFilter(#[from] FilterError),
 }
 
 impl actix_web::ResponseError for SearchError {
@@ -40,10 +53,23 @@ impl actix_web::ResponseError for SearchError {
             SearchError::IntParsing(..) => StatusCode::BAD_REQUEST,
             SearchError::InvalidIndex(..) => StatusCode::BAD_REQUEST,
             SearchError::FormatError(..) => StatusCode::BAD_REQUEST,
+            SearchError::InvalidInput(..) => StatusCode::BAD_REQUEST,
+            SearchError::Database(..) => StatusCode::INTERNAL_SERVER_ERROR,
+            SearchError::InvalidFilter(..) => StatusCode::BAD_REQUEST,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
+        // Invalid filters point at the clause at fault, so that clients can show where it is
+        if let SearchError::InvalidFilter(error) = self {
+            return HttpResponse::build(self.status_code()).json(serde_json::json!({
+                "error": "invalid_filter",
+                "description": self.to_string(),
+                "clause": error.clause,
+                "suggestion": error.suggestion,
+            }));
+        }
+
         HttpResponse::build(self.status_code()).json(ApiError {
             error: match self {
                 SearchError::Env(..) => "environment_error",
@@ -52,6 +78,9 @@ impl actix_web::ResponseError for SearchError {
                 SearchError::IntParsing(..) => "invalid_input",
                 SearchError::InvalidIndex(..) => "invalid_input",
                 SearchError::FormatError(..) => "invalid_input",
+                SearchError::InvalidInput(..) => "invalid_input",
+                SearchError::Database(..) => "database_error",
+                SearchError::InvalidFilter(..) => "invalid_filter",
             },
             description: &self.to_string(),
         })
@@ -63,6 +92,9 @@ pub struct SearchConfig {
     pub address: String,
     pub key: String,
     pub meta_namespace: String,
+    /// Whether the API is reported as not ready while Meilisearch is down. Everything but
+    /// search can still be served without it.
+    pub required: bool,
 }
 
 impl SearchConfig {
@@ -71,11 +103,13 @@ impl SearchConfig {
     pub fn new(meta_namespace: Option<String>) -> Self {
         let address = dotenvy::var("MEILISEARCH_ADDR").expect("MEILISEARCH_ADDR not set");
         let key = dotenvy::var("MEILISEARCH_KEY").expect("MEILISEARCH_KEY not set");
+        let required = parse_var("MEILISEARCH_REQUIRED").unwrap_or(true);
 
         Self {
             address,
             key,
             meta_namespace: meta_namespace.unwrap_or_default(),
+            required,
         }
     }
 
@@ -101,6 +135,8 @@ pub struct UploadSearchProject {
     pub summary: String,
     pub categories: Vec<String>,
     pub display_categories: Vec<String>,
+    /// The project's own categories, without its additional categories or loaders
+    pub primary_categories: Vec<String>,
     pub follows: i32,
     pub downloads: i32,
     pub icon_url: Option<String>,
@@ -117,6 +153,9 @@ pub struct UploadSearchProject {
     pub modified_timestamp: i64,
     pub open_source: bool,
     pub color: Option<u32>,
+    pub mature: bool,
+    /// Whether the project is archived, so that searches can leave out archived projects
+    pub archived: bool,
 
     // Hidden fields to get the Project model out of the search results.
     pub license_url: Option<String>,
@@ -129,13 +168,48 @@ pub struct UploadSearchProject {
     pub status: ProjectStatus,
     pub requested_status: Option<ProjectStatus>,
     pub loaders: Vec<String>, // Search uses loaders as categories- this is purely for the Project model.
-    pub links: Vec<LinkUrl>,
+    /// The loaders of the document's version, where `loaders` has those of every version
+    pub version_loaders: Vec<String>,
+    /// The project's links by name, as in the project model
+    pub links: HashMap<String, Link>,
     pub gallery_items: Vec<GalleryItem>, // Gallery *only* urls are stored in gallery, but the gallery items are stored here- required for the Project model.
     pub games: Vec<String>,              // Todo: in future, could be a searchable field.
     pub organization_id: Option<String>, // Todo: in future, could be a searchable field.
 
     #[serde(flatten)]
     pub loader_fields: HashMap<String, Vec<serde_json::Value>>,
+    /// The translated names and summaries, such as `name_de`, so searches in other languages
+    /// match
+    #[serde(flatten)]
+    pub translations: HashMap<String, String>,
+}
+
+/// A user document in the users index, used both for uploading and for search results
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchUser {
+    pub user_id: String,
+    pub username: String,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    /// The follows of the listed projects the user owns
+    pub follows: i64,
+    /// How many listed projects the user owns
+    pub projects: i64,
+}
+
+/// An organization document in the organizations index, used both for uploading and for
+/// search results
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchOrganization {
+    pub organization_id: String,
+    pub slug: String,
+    pub name: String,
+    pub icon_url: Option<String>,
+    pub description: String,
+    /// The follows of the organization's listed projects
+    pub follows: i64,
+    /// How many listed projects the organization has
+    pub projects: i64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -144,9 +218,15 @@ pub struct SearchResults {
     pub page: usize,
     pub hits_per_page: usize,
     pub total_hits: usize,
+    /// The earliest modification and creation dates allowed by `updated_within` and
+    /// `created_within`
+    pub updated_after: Option<DateTime<Utc>>,
+    pub created_after: Option<DateTime<Utc>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+// Attributes left out of the search by a projection are defaulted
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct ResultSearchProject {
     pub version_id: String,
     pub project_id: String,
@@ -168,8 +248,12 @@ pub struct ResultSearchProject {
     pub gallery: Vec<String>,
     pub featured_gallery: Option<String>,
     pub color: Option<u32>,
+    #[serde(default)]
+    pub mature: bool,
 
     // Hidden fields to get the Project model out of the search results.
+    /// Missing from documents indexed before categories could be picked to be displayed
+    pub primary_categories: Option<Vec<String>>,
     pub license_url: Option<String>,
     pub monetization_status: Option<String>,
     pub team_id: String,
@@ -180,7 +264,8 @@ pub struct ResultSearchProject {
     pub status: String,
     pub requested_status: Option<String>,
     pub loaders: Vec<String>, // Search uses loaders as categories- this is purely for the Project model.
-    pub links: Vec<LinkUrl>,
+    pub version_loaders: Vec<String>,
+    pub links: HashMap<String, Link>,
     pub gallery_items: Vec<GalleryItem>, // Gallery *only* urls are stored in gallery, but the gallery items are stored here- required for the Project model.
     pub games: Vec<String>,              // Todo: in future, could be a searchable field.
     pub organization_id: Option<String>, // Todo: in future, could be a searchable field.
@@ -205,9 +290,119 @@ pub fn get_sort_index(
     })
 }
 
+/// Whether a search collapses the versions of each project into one hit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchGrouping {
+    Project,
+    /// Each matching version is a hit of its own, so filters on version fields count versions
+    Version,
+}
+
+impl SearchGrouping {
+    pub fn parse(group: Option<&str>) -> Result<Self, SearchError> {
+        match group.unwrap_or("project") {
+            "project" => Ok(SearchGrouping::Project),
+            "version" => Ok(SearchGrouping::Version),
+            group => Err(SearchError::InvalidInput(format!(
+                "Invalid search group: {group}, expected project or version"
+            ))),
+        }
+    }
+}
+
+// The attributes every projection retrieves, as `Project::from_search` can't build a project
+// without them
+const REQUIRED_SEARCH_ATTRIBUTES: [&str; 6] = [
+    "project_id",
+    "team_id",
+    "thread_id",
+    "date_created",
+    "date_published",
+    "date_modified",
+];
+
+// The attributes projections also retrieve when grouping by version, which the hits' versions
+// are built from
+const VERSION_HIT_ATTRIBUTES: [&str; 3] = ["version_id", "game_versions", "version_loaders"];
+
+/// The search attributes each field of the project model is built from. Other fields, such as
+/// loader fields, are stored under their own name.
+fn project_field_attributes(field: &str) -> Option<&'static [&'static str]> {
+    Some(match field {
+        "id" => &["project_id"],
+        "slug" => &["slug"],
+        "project_types" => &["project_types"],
+        "games" => &["games"],
+        "team_id" => &["team_id"],
+        "organization" => &["organization_id"],
+        "name" => &["name"],
+        "summary" => &["summary"],
+        "published" => &["date_published"],
+        "updated" => &["date_modified"],
+        "approved" => &["date_created", "date_published"],
+        "queued" => &["date_queued"],
+        "status" => &["status"],
+        "requested_status" => &["requested_status"],
+        "license" => &["license", "license_url"],
+        "downloads" => &["downloads"],
+        "followers" => &["follows"],
+        "categories" => &[
+            "primary_categories",
+            "display_categories",
+            "loaders",
+            "mrpack_loaders",
+        ],
+        "additional_categories" => &[
+            "categories",
+            "primary_categories",
+            "display_categories",
+            "loaders",
+            "mrpack_loaders",
+        ],
+        "display_categories" => &["display_categories", "loaders", "mrpack_loaders"],
+        "loaders" => &["loaders", "mrpack_loaders"],
+        "versions" => &["versions"],
+        "icon_url" => &["icon_url"],
+        "link_urls" => &["links"],
+        "gallery" => &["gallery_items"],
+        "color" => &["color"],
+        "thread_id" => &["thread_id"],
+        "monetization_status" => &["monetization_status"],
+        "mature" => &["mature"],
+        // Not stored in search
+        "description" | "moderator_message" | "icon_variants" => &[],
+        _ => return None,
+    })
+}
+
+/// Parses the facets of a search request. Search can *optionally* have a third inner array:
+/// So Vec(AND)<Vec(OR)<Vec(AND)< _ >>>. Inner facets which aren't arrays are wrapped in one.
+pub fn parse_facets(facets: &str) -> Result<Vec<Vec<Vec<String>>>, serde_json::Error> {
+    Ok(serde_json::from_str::<Vec<Vec<Value>>>(facets)?
+        .into_iter()
+        .map(|facets| {
+            facets
+                .into_iter()
+                .map(|facet| {
+                    if facet.is_array() {
+                        serde_json::from_value::<Vec<String>>(facet).unwrap_or_default()
+                    } else {
+                        vec![serde_json::from_value::<String>(facet).unwrap_or_default()]
+                    }
+                })
+                .collect_vec()
+        })
+        .collect_vec())
+}
+
+/// Searches for projects matching `info`. Unless `show_mature` is set, mature projects are
+/// filtered out of the results. If `fields` is given, only the attributes needed for those
+/// fields of the project model are retrieved.
 pub async fn search_for_project(
     info: &SearchRequest,
     config: &SearchConfig,
+    show_mature: bool,
+    fields: Option<&[String]>,
 ) -> Result<SearchResults, SearchError> {
     let client = Client::new(&*config.address, Some(&*config.key));
 
@@ -215,11 +410,37 @@ pub async fn search_for_project(
     let index = info.index.as_deref().unwrap_or("relevance");
     let limit = info.limit.as_deref().unwrap_or("10").parse()?;
 
+    let now = Utc::now();
+    let updated_after = relative_cutoff("updated_within", info.updated_within.as_deref(), now)?;
+    let created_after = relative_cutoff("created_within", info.created_within.as_deref(), now)?;
+
+    let grouping = SearchGrouping::parse(info.group.as_deref())?;
+
+    // The versions index doesn't collapse versions into their projects. It ranks by relevance
+    // before any sort, as the projects index does.
     let sort = get_sort_index(config, index)?;
-    let meilisearch_index = client.get_index(sort.0).await?;
+    let index_name = match grouping {
+        SearchGrouping::Project => sort.0,
+        SearchGrouping::Version => config.get_index_name("projects_versions"),
+    };
+    let meilisearch_index = client.get_index(index_name).await?;
 
     let mut filter_string = String::new();
 
+    let attributes = fields.map(|fields| {
+        let mut attributes: Vec<&str> = REQUIRED_SEARCH_ATTRIBUTES.to_vec();
+        for field in fields {
+            match project_field_attributes(field) {
+                Some(field_attributes) => attributes.extend(field_attributes),
+                None => attributes.push(field.as_str()),
+            }
+        }
+        if grouping == SearchGrouping::Version {
+            attributes.extend(VERSION_HIT_ATTRIBUTES);
+        }
+        attributes.into_iter().unique().collect_vec()
+    });
+
     // Convert offset and limit to page and hits_per_page
     let hits_per_page = limit;
     let page = offset / limit + 1;
@@ -232,14 +453,14 @@ pub async fn search_for_project(
             .with_query(info.query.as_deref().unwrap_or_default())
             .with_sort(&sort.1);
 
+        if let Some(attributes) = &attributes {
+            query.with_attributes_to_retrieve(Selectors::Some(attributes.as_slice()));
+        }
+
         if let Some(new_filters) = info.new_filters.as_deref() {
-            query.with_filter(new_filters);
+            filter_string.push_str(new_filters);
         } else {
-            let facets = if let Some(facets) = &info.facets {
-                Some(serde_json::from_str::<Vec<Vec<Value>>>(facets)?)
-            } else {
-                None
-            };
+            let facets = info.facets.as_deref().map(parse_facets).transpose()?;
 
             let filters: Cow<_> = match (info.filters.as_deref(), info.version.as_deref()) {
                 (Some(f), Some(v)) => format!("({f}) AND ({v})").into(),
@@ -249,26 +470,6 @@ pub async fn search_for_project(
             };
 
             if let Some(facets) = facets {
-                // Search can now *optionally* have a third inner array: So Vec(AND)<Vec(OR)<Vec(AND)< _ >>>
-                // For every inner facet, we will check if it can be deserialized into a Vec<&str>, and do so.
-                // If not, we will assume it is a single facet and wrap it in a Vec.
-                let facets: Vec<Vec<Vec<String>>> = facets
-                    .into_iter()
-                    .map(|facets| {
-                        facets
-                            .into_iter()
-                            .map(|facet| {
-                                if facet.is_array() {
-                                    serde_json::from_value::<Vec<String>>(facet).unwrap_or_default()
-                                } else {
-                                    vec![serde_json::from_value::<String>(facet)
-                                        .unwrap_or_default()]
-                                }
-                            })
-                            .collect_vec()
-                    })
-                    .collect_vec();
-
                 filter_string.push('(');
                 for (index, facet_outer_list) in facets.iter().enumerate() {
                     filter_string.push('(');
@@ -303,10 +504,24 @@ pub async fn search_for_project(
             } else {
                 filter_string.push_str(&filters);
             }
+        }
 
-            if !filter_string.is_empty() {
-                query.with_filter(&filter_string);
-            }
+        if let Some(updated_after) = updated_after {
+            let clause = format!("modified_timestamp >= {}", updated_after.timestamp());
+            filter_string = and_filter(filter_string, &clause);
+        }
+        if let Some(created_after) = created_after {
+            let clause = format!("created_timestamp >= {}", created_after.timestamp());
+            filter_string = and_filter(filter_string, &clause);
+        }
+
+        // Projects indexed before the mature flag existed lack the attribute, which `!=` matches
+        if !show_mature {
+            filter_string = and_filter(filter_string, "mature != true");
+        }
+
+        if !filter_string.is_empty() {
+            query.with_filter(&filter_string);
         }
 
         query.execute::<ResultSearchProject>().await?
@@ -317,5 +532,224 @@ pub async fn search_for_project(
         page: results.page.unwrap_or_default(),
         hits_per_page: results.hits_per_page.unwrap_or_default(),
         total_hits: results.total_hits.unwrap_or_default(),
+        updated_after,
+        created_after,
+    })
+}
+
+/// The hits of one of the indexes searched by [`search_all`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchGroup<T> {
+    pub hits: Vec<T>,
+    pub total_hits: usize,
+}
+
+pub struct AllSearchResults {
+    pub projects: SearchGroup<ResultSearchProject>,
+    pub users: SearchGroup<SearchUser>,
+    pub organizations: SearchGroup<SearchOrganization>,
+}
+
+/// Searches projects, users and organizations at once, returning up to `limit` hits of each.
+/// Mature projects are left out.
+pub async fn search_all(
+    query: &str,
+    limit: usize,
+    config: &SearchConfig,
+) -> Result<AllSearchResults, SearchError> {
+    let client = config.make_client();
+    let projects_index = client.index(config.get_index_name("projects"));
+    let users_index = client.index(config.get_index_name("users"));
+    let organizations_index = client.index(config.get_index_name("organizations"));
+
+    let mut projects_query = SearchQuery::new(&projects_index);
+    projects_query.with_filter("mature != true");
+    let mut users_query = SearchQuery::new(&users_index);
+    let mut organizations_query = SearchQuery::new(&organizations_index);
+
+    let mut multi_search = client.multi_search();
+    for search_query in [
+        &mut projects_query,
+        &mut users_query,
+        &mut organizations_query,
+    ] {
+        search_query
+            .with_query(query)
+            .with_page(1)
+            .with_hits_per_page(limit);
+        multi_search.with_search_query(search_query.build());
+    }
+
+    // The results are in the order of the queries
+    let mut results = multi_search.execute::<Value>().await?.results.into_iter();
+
+    Ok(AllSearchResults {
+        projects: search_group(results.next())?,
+        users: search_group(results.next())?,
+        organizations: search_group(results.next())?,
+    })
+}
+
+fn search_group<T: DeserializeOwned>(
+    results: Option<meilisearch_sdk::search::SearchResults<Value>>,
+) -> Result<SearchGroup<T>, SearchError> {
+    let Some(results) = results else {
+        return Ok(SearchGroup {
+            hits: Vec::new(),
+            total_hits: 0,
+        });
+    };
+
+    Ok(SearchGroup {
+        hits: results
+            .hits
+            .into_iter()
+            .map(|x| serde_json::from_value(x.result))
+            .collect::<Result<_, _>>()?,
+        total_hits: results.total_hits.unwrap_or_default(),
     })
 }
+
+/// Combines the user's filters with a clause the server adds, keeping their precedence intact
+fn and_filter(filter_string: String, clause: &str) -> String {
+    if filter_string.is_empty() {
+        clause.to_string()
+    } else {
+        format!("({filter_string}) AND {clause}")
+    }
+}
+
+/// Relative date filters can't reach further back than this
+const MAX_RELATIVE_FILTER_DAYS: i64 = 10 * 365;
+
+/// Gets the earliest date allowed by a relative date filter, such as `updated_within=P7D`
+fn relative_cutoff(
+    name: &str,
+    duration: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, SearchError> {
+    let Some(duration) = duration else {
+        return Ok(None);
+    };
+
+    let parsed = parse_duration(duration).ok_or_else(|| {
+        SearchError::InvalidInput(format!(
+            "{name} must be an ISO-8601 duration such as P7D or PT12H, got {duration}"
+        ))
+    })?;
+    if parsed > Duration::days(MAX_RELATIVE_FILTER_DAYS) {
+        return Err(SearchError::InvalidInput(format!(
+            "{name} can't be longer than 10 years"
+        )));
+    }
+
+    Ok(Some(now - parsed))
+}
+
+/// Parses an ISO-8601 duration such as `P1Y2M3DT4H5M6S`. Years and months are taken to be 365
+/// and 30 days long, which is close enough for filtering.
+fn parse_duration(value: &str) -> Option<Duration> {
+    const DATE_UNITS: [(char, i64); 4] = [
+        ('Y', 365 * 86400),
+        ('M', 30 * 86400),
+        ('W', 7 * 86400),
+        ('D', 86400),
+    ];
+    const TIME_UNITS: [(char, i64); 3] = [('H', 3600), ('M', 60), ('S', 1)];
+
+    let value = value.strip_prefix('P')?;
+    let (date, time) = match value.split_once('T') {
+        Some((_, "")) => return None,
+        Some((date, time)) => (date, time),
+        None => (value, ""),
+    };
+
+    let mut seconds: i64 = 0;
+    let mut any_components = false;
+    for (part, units) in [(date, &DATE_UNITS[..]), (time, &TIME_UNITS[..])] {
+        // Each unit may only be given once, in order from largest to smallest
+        let mut units = units.iter();
+        let mut number = String::new();
+        for c in part.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+
+            let (_, unit_seconds) = units.find(|(unit, _)| *unit == c)?;
+            let amount: i64 = number.parse().ok()?;
+            seconds = seconds.checked_add(amount.checked_mul(*unit_seconds)?)?;
+            number.clear();
+            any_components = true;
+        }
+
+        if !number.is_empty() {
+            return None;
+        }
+    }
+
+    // Kept in milliseconds, so that durations too long for chrono can't panic
+    let milliseconds = seconds.checked_mul(1000)?;
+    any_components.then(|| Duration::milliseconds(milliseconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        for (value, seconds) in [
+            ("P7D", 7 * 86400),
+            ("PT12H", 12 * 3600),
+            ("P1W", 7 * 86400),
+            ("P1Y2M", 425 * 86400),
+            ("P1DT1H30M15S", 86400 + 3600 + 30 * 60 + 15),
+            ("PT90M", 90 * 60),
+            ("P0D", 0),
+        ] {
+            assert_eq!(
+                parse_duration(value),
+                Some(Duration::seconds(seconds)),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for value in [
+            "",
+            "P",
+            "PT",
+            "7D",
+            "P7",
+            "PD",
+            "P1DT",
+            "P1H",
+            "PT1D",
+            "P1D1Y",
+            "P1D1D",
+            "P-1D",
+            "P1.5D",
+            "p7d",
+            "P99999999999999999999D",
+            "P999999999999D",
+        ] {
+            assert_eq!(parse_duration(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn limits_relative_cutoffs() {
+        let now = Utc::now();
+        assert_eq!(
+            relative_cutoff("updated_within", Some("P1D"), now).unwrap(),
+            Some(now - Duration::days(1))
+        );
+        assert_eq!(relative_cutoff("updated_within", None, now).unwrap(), None);
+        assert!(relative_cutoff("updated_within", Some("P3650D"), now).is_ok());
+        assert!(relative_cutoff("updated_within", Some("P11Y"), now).is_err());
+        assert!(relative_cutoff("created_within", Some("yesterday"), now).is_err());
+    }
+}
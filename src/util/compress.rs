@@ -0,0 +1,262 @@
+use crate::util::env::parse_var;
+use actix_web::{
+    body::{self, BodySize, EitherBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::ErrorInternalServerError,
+    http::header::{self, HeaderValue},
+    http::StatusCode,
+    web, Error as AWError, HttpResponse,
+};
+use futures::future::{ok, LocalBoxFuture, Ready};
+use std::io::Write;
+use std::rc::Rc;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+}
+
+/// Compresses responses with gzip or brotli, whichever the client prefers in its
+/// `Accept-Encoding`. Only compressible content types above `COMPRESSION_MIN_SIZE` bytes are
+/// compressed, and the level is read from `COMPRESSION_LEVEL`, defaulting to the fastest one.
+///
+/// This is only wrapped around the API scopes, so the maven, updates and asset routes which
+/// redirect to the CDN or serve files are passed through as-is.
+#[derive(Copy, Clone)]
+pub struct Compression {
+    level: u32,
+    min_size: usize,
+}
+
+impl Compression {
+    pub fn from_env() -> Self {
+        Self {
+            level: parse_var("COMPRESSION_LEVEL").unwrap_or(1),
+            min_size: parse_var("COMPRESSION_MIN_SIZE").unwrap_or(1024),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Compression
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = AWError> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = AWError;
+    type Transform = CompressionMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(CompressionMiddleware {
+            service: Rc::new(service),
+            config: *self,
+        })
+    }
+}
+
+pub struct CompressionMiddleware<S> {
+    service: Rc<S>,
+    config: Compression,
+}
+
+impl<S, B> Service<ServiceRequest> for CompressionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = AWError> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = AWError;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let encoding = req
+            .headers()
+            .get(header::ACCEPT_ENCODING)
+            .and_then(|x| x.to_str().ok())
+            .and_then(negotiate_encoding);
+        let config = self.config;
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+
+            if !is_compressible(res.response()) {
+                return Ok(res.map_into_left_body());
+            }
+
+            // The response depends on the `Accept-Encoding` even when it's sent uncompressed
+            res.headers_mut()
+                .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+
+            let Some(encoding) = encoding else {
+                return Ok(res.map_into_left_body());
+            };
+
+            match res.response().body().size() {
+                BodySize::Sized(size) if (size as usize) >= config.min_size => {}
+                BodySize::Stream => {}
+                _ => return Ok(res.map_into_left_body()),
+            }
+
+            let (req, res) = res.into_parts();
+            let (mut head, body) = res.into_parts();
+
+            let body = body::to_bytes(body).await.map_err(|e| {
+                let e: Box<dyn std::error::Error> = e.into();
+                ErrorInternalServerError(e.to_string())
+            })?;
+
+            // Streamed bodies only have their size known once they're read
+            if body.len() < config.min_size {
+                let res = head.set_body(body).map_into_boxed_body();
+                return Ok(ServiceResponse::new(req, res).map_into_right_body());
+            }
+
+            let compressed = web::block(move || compress(&body, encoding, config.level))
+                .await?
+                .map_err(ErrorInternalServerError)?;
+
+            head.headers_mut().remove(header::CONTENT_LENGTH);
+            head.headers_mut().insert(
+                header::CONTENT_ENCODING,
+                HeaderValue::from_static(encoding.as_str()),
+            );
+
+            let res = head.set_body(compressed).map_into_boxed_body();
+            Ok(ServiceResponse::new(req, res).map_into_right_body())
+        })
+    }
+}
+
+// Picks the supported encoding with the highest quality, preferring brotli on ties.
+// Encodings with a quality of zero are refused by the client.
+fn negotiate_encoding(accept_encoding: &str) -> Option<Encoding> {
+    let mut brotli = None;
+    let mut gzip = None;
+    let mut wildcard = None;
+
+    for item in accept_encoding.split(',') {
+        let mut parts = item.split(';').map(str::trim);
+        let name = parts.next().unwrap_or_default().to_lowercase();
+        let quality = parts
+            .find_map(|x| x.strip_prefix("q="))
+            .and_then(|x| x.parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        match &*name {
+            "br" => brotli = Some(quality),
+            "gzip" | "x-gzip" => gzip = Some(quality),
+            "*" => wildcard = Some(quality),
+            _ => {}
+        }
+    }
+
+    let brotli = brotli.or(wildcard).unwrap_or(0.0);
+    let gzip = gzip.or(wildcard).unwrap_or(0.0);
+
+    if brotli > 0.0 && brotli >= gzip {
+        Some(Encoding::Brotli)
+    } else if gzip > 0.0 {
+        Some(Encoding::Gzip)
+    } else {
+        None
+    }
+}
+
+fn is_compressible(res: &HttpResponse<impl MessageBody>) -> bool {
+    let status = res.status();
+    if status.is_informational()
+        || status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED
+        || res.headers().contains_key(header::CONTENT_ENCODING)
+        || is_no_transform(res)
+    {
+        return false;
+    }
+
+    let Some(content_type) = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+    else {
+        return false;
+    };
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+
+    essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || matches!(
+            &*essence,
+            "application/json" | "application/javascript" | "application/xml" | "image/svg+xml"
+        )
+}
+
+// Responses with `Cache-Control: no-transform` are sent as they are, which streamed exports use
+// so they aren't buffered to be compressed
+fn is_no_transform(res: &HttpResponse<impl MessageBody>) -> bool {
+    res.headers()
+        .get_all(header::CACHE_CONTROL)
+        .filter_map(|x| x.to_str().ok())
+        .flat_map(|x| x.split(','))
+        .any(|x| x.trim().eq_ignore_ascii_case("no-transform"))
+}
+
+fn compress(body: &[u8], encoding: Encoding, level: u32) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Brotli => {
+            let mut output = Vec::new();
+            {
+                let mut writer =
+                    brotli::CompressorWriter::new(&mut output, 4096, level.min(11), 22);
+                writer.write_all(body)?;
+            }
+            Ok(output)
+        }
+        Encoding::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::new(level.min(9)));
+            encoder.write_all(body)?;
+            encoder.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiates_preferred_encoding() {
+        assert_eq!(
+            negotiate_encoding("gzip, deflate, br"),
+            Some(Encoding::Brotli)
+        );
+        assert_eq!(negotiate_encoding("gzip"), Some(Encoding::Gzip));
+        assert_eq!(negotiate_encoding("br;q=0.5, gzip"), Some(Encoding::Gzip));
+        assert_eq!(negotiate_encoding("*"), Some(Encoding::Brotli));
+        assert_eq!(negotiate_encoding("gzip;q=0, deflate"), None);
+        assert_eq!(negotiate_encoding("identity"), None);
+    }
+}
@@ -5,6 +5,7 @@ pub fn default_cors() -> Cors {
         .allow_any_origin()
         .allow_any_header()
         .allow_any_method()
+        .expose_headers(vec![actix_web::http::header::ETAG])
         .max_age(3600)
         .send_wildcard()
 }
@@ -0,0 +1,128 @@
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::{HttpRequest, HttpResponse};
+use bytes::Bytes;
+use futures::stream::{self, StreamExt};
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, Postgres, Transaction};
+
+/// The name of the cursor exports are read from. It has to be declared in the transaction
+/// passed to `cursor_response`.
+pub const EXPORT_CURSOR: &str = "csv_export";
+
+// How many rows are fetched from the cursor at a time
+const FETCH_SIZE: usize = 500;
+
+/// Whether a request asked for CSV, either with `?format=csv` or an `Accept: text/csv` header.
+/// The `format` query parameter takes precedence over the header.
+pub fn wants_csv(req: &HttpRequest, format: Option<&str>) -> bool {
+    if let Some(format) = format {
+        return format.eq_ignore_ascii_case("csv");
+    }
+
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|x| x.to_str().ok())
+        .is_some_and(|accept| {
+            accept.split(',').any(|x| {
+                x.split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .eq_ignore_ascii_case("text/csv")
+            })
+        })
+}
+
+/// Appends a record to a CSV body. Fields containing commas, quotes or line breaks are quoted,
+/// and records end with CRLF as in RFC 4180.
+pub fn write_record<I, S>(buffer: &mut String, fields: I)
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            buffer.push(',');
+        }
+
+        let field = field.as_ref();
+        if field.contains([',', '"', '\r', '\n']) {
+            buffer.push('"');
+            buffer.push_str(&field.replace('"', "\"\""));
+            buffer.push('"');
+        } else {
+            buffer.push_str(field);
+        }
+    }
+    buffer.push_str("\r\n");
+}
+
+/// Streams the rows of `EXPORT_CURSOR` as a CSV attachment. The cursor is read in batches as
+/// the body is sent, so the rows are never all held in memory. The transaction is rolled back
+/// once the cursor is exhausted.
+pub fn cursor_response<R>(
+    filename: &str,
+    columns: &'static [&'static str],
+    transaction: Transaction<'static, Postgres>,
+    record: fn(R) -> Vec<String>,
+) -> HttpResponse
+where
+    R: for<'r> FromRow<'r, PgRow> + Send + Unpin + 'static,
+{
+    let mut header = String::new();
+    write_record(&mut header, columns);
+
+    let rows = stream::try_unfold(Some(transaction), move |transaction| async move {
+        let Some(mut transaction) = transaction else {
+            return Ok(None);
+        };
+
+        // Not cached, as the columns of the cursor differ between exports
+        let rows: Vec<R> = sqlx::query_as(&format!("FETCH {FETCH_SIZE} FROM {EXPORT_CURSOR}"))
+            .persistent(false)
+            .fetch_all(&mut *transaction)
+            .await?;
+        if rows.is_empty() {
+            transaction.rollback().await?;
+            return Ok(None);
+        }
+
+        let mut body = String::new();
+        for row in rows {
+            write_record(&mut body, record(row));
+        }
+
+        Ok::<_, sqlx::Error>(Some((Bytes::from(body), Some(transaction))))
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{filename}\""),
+        ))
+        // Keeps the compression middleware from buffering the whole export
+        .insert_header((
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("no-store, no-transform"),
+        ))
+        .streaming(stream::once(async { Ok::<_, sqlx::Error>(Bytes::from(header)) }).chain(rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_fields() {
+        let mut buffer = String::new();
+        write_record(
+            &mut buffer,
+            ["plain", "with,comma", "say \"hi\"", "two\nlines", ""],
+        );
+        assert_eq!(
+            buffer,
+            "plain,\"with,comma\",\"say \"\"hi\"\"\",\"two\nlines\",\r\n"
+        );
+    }
+}
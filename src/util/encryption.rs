@@ -0,0 +1,67 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Error, Debug)]
+pub enum EncryptionError {
+    #[error("Environment Error")]
+    Env(#[from] dotenvy::Error),
+    #[error("The data could not be encrypted or decrypted")]
+    Cipher,
+}
+
+// The key is derived from `DATABASE_ENCRYPTION_SECRET`, so the secret can be any string
+fn cipher() -> Result<Aes256Gcm, EncryptionError> {
+    let secret = dotenvy::var("DATABASE_ENCRYPTION_SECRET")?;
+    let key = Sha256::digest(secret.as_bytes());
+
+    Aes256Gcm::new_from_slice(&key).map_err(|_| EncryptionError::Cipher)
+}
+
+/// Encrypts data to be stored in the database. The random nonce is prepended to the result.
+pub fn encrypt(plaintext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher()?
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| EncryptionError::Cipher)?;
+
+    let mut data = nonce.to_vec();
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+/// Decrypts data encrypted with [`encrypt`]
+pub fn decrypt(data: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    if data.len() < NONCE_LENGTH {
+        return Err(EncryptionError::Cipher);
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+
+    cipher()?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| EncryptionError::Cipher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        std::env::set_var("DATABASE_ENCRYPTION_SECRET", "test");
+
+        let plaintext = b"GB82WEST12345698765432";
+        let data = encrypt(plaintext).unwrap();
+        assert_ne!(&data[NONCE_LENGTH..], plaintext);
+        assert_eq!(decrypt(&data).unwrap(), plaintext);
+
+        // The nonce is random, and tampering is detected
+        assert_ne!(encrypt(plaintext).unwrap(), data);
+        let mut tampered = data.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&tampered).is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use actix_web::http::header::{self, EntityTag, Header};
+use actix_web::{HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+// Computes the ETag of a cached item from when its row was last modified and its serialized
+// body. The body is serialized through a `Value` so that map keys are always in the same order.
+pub fn item_etag<T: Serialize>(modified: DateTime<Utc>, body: &T) -> String {
+    let mut hasher = sha1::Sha1::new();
+    hasher.update(modified.to_rfc3339().as_bytes());
+    hasher.update(
+        &serde_json::to_value(body)
+            .and_then(|x| serde_json::to_vec(&x))
+            .unwrap_or_default(),
+    );
+    hasher.digest().to_string()
+}
+
+// Combines the ETags of the items in a response. Team members and moderators can be shown data
+// that other users can't, so whether the requester has elevated access to each item is hashed too.
+pub fn response_etag<'a>(items: impl IntoIterator<Item = (&'a str, bool)>) -> EntityTag {
+    let mut hasher = sha1::Sha1::new();
+    for (etag, elevated) in items {
+        hasher.update(etag.as_bytes());
+        hasher.update(if elevated { b"1" } else { b"0" });
+    }
+    EntityTag::new_strong(hasher.digest().to_string())
+}
+
+/// Gives each representation of a response, such as a pruned one, its own ETag
+pub fn vary(etag: EntityTag, variant: &str) -> EntityTag {
+    let mut hasher = sha1::Sha1::new();
+    hasher.update(etag.tag().as_bytes());
+    hasher.update(variant.as_bytes());
+    EntityTag::new_strong(hasher.digest().to_string())
+}
+
+/// Returns a 304 if the request's `If-None-Match` header matches the ETag
+pub fn not_modified(req: &HttpRequest, etag: &EntityTag) -> Option<HttpResponse> {
+    let matches = match header::IfNoneMatch::parse(req) {
+        Ok(header::IfNoneMatch::Any) => true,
+        Ok(header::IfNoneMatch::Items(items)) => items.iter().any(|x| x.weak_eq(etag)),
+        Err(_) => false,
+    };
+
+    matches.then(|| {
+        HttpResponse::NotModified()
+            .insert_header(header::ETag(etag.clone()))
+            .finish()
+    })
+}
+
+/// Serializes the body into a 200 response carrying the ETag
+pub fn with_etag<T: Serialize>(etag: EntityTag, body: &T) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(header::ETag(etag))
+        .json(body)
+}
@@ -0,0 +1,147 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use thiserror::Error;
+use url::Url;
+
+use crate::queue::webhooks::is_public_address;
+
+use super::env::parse_var;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Error, Debug)]
+pub enum FetchError {
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error("{0}")]
+    Address(String),
+    #[error("Error while fetching the URL: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("The server responded with {0}")]
+    Status(reqwest::StatusCode),
+    #[error("The response is larger than {} KiB", .0 / 1024)]
+    TooLarge(usize),
+}
+
+// Tests serve pages from the loopback address, which is refused otherwise. Only the test setup
+// sets `ALLOW_LOOPBACK_FETCHES`.
+fn allow_loopback() -> bool {
+    parse_var("ALLOW_LOOPBACK_FETCHES").unwrap_or(false)
+}
+
+/// Checks that a user supplied URL can be fetched with [`fetch`]. It must be https, unless it
+/// points at the loopback address in tests.
+pub fn validate_fetch_url(value: &str) -> Result<Url, validator::ValidationError> {
+    let url = Url::parse(value)
+        .ok()
+        .ok_or_else(|| validator::ValidationError::new("invalid URL"))?;
+
+    let host = url
+        .host_str()
+        .ok_or_else(|| validator::ValidationError::new("URL must have a host"))?
+        .to_lowercase();
+    let is_loopback = host == "localhost"
+        || host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|x| x.is_loopback());
+    if url.scheme() != "https" && !(is_loopback && allow_loopback() && url.scheme() == "http") {
+        return Err(validator::ValidationError::new("URL must be https"));
+    }
+
+    Ok(url)
+}
+
+// Resolves the host of the URL, refusing it if any of its addresses isn't public
+async fn resolve(url: &Url) -> Result<Vec<SocketAddr>, FetchError> {
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Err(FetchError::Address("The URL must have a host".to_string()));
+    };
+
+    // IPv6 hosts are bracketed in URLs, but not when they're resolved
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|err| FetchError::Address(format!("Couldn't resolve {host}: {err}")))?
+        .collect::<Vec<_>>();
+
+    if addrs.is_empty() {
+        return Err(FetchError::Address(format!("Couldn't resolve {host}")));
+    }
+    let allow_loopback = allow_loopback();
+    if addrs
+        .iter()
+        .any(|x| !(is_public_address(x.ip()) || (allow_loopback && x.ip().is_loopback())))
+    {
+        return Err(FetchError::Address(format!(
+            "{host} resolves to a private address"
+        )));
+    }
+
+    Ok(addrs)
+}
+
+/// Downloads a user supplied URL, which should have been checked with [`validate_fetch_url`],
+/// refusing anything larger than `max_size` bytes. The request is only sent to public
+/// addresses, and redirects aren't followed, as they could lead anywhere.
+pub async fn fetch(url: &str, max_size: usize) -> Result<Vec<u8>, FetchError> {
+    let url = Url::parse(url)?;
+    let addrs = resolve(&url).await?;
+
+    // The checked addresses are used, so the host can't resolve elsewhere for the request
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .resolve_to_addrs(url.host_str().unwrap_or_default(), &addrs)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+
+    let mut response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(FetchError::Status(response.status()));
+    }
+    if response
+        .content_length()
+        .is_some_and(|x| x > max_size as u64)
+    {
+        return Err(FetchError::TooLarge(max_size));
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+        if body.len() > max_size {
+            return Err(FetchError::TooLarge(max_size));
+        }
+    }
+
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_https_urls_are_valid() {
+        assert!(validate_fetch_url("https://example.com/page").is_ok());
+        assert!(validate_fetch_url("http://example.com/page").is_err());
+        assert!(validate_fetch_url("ftp://example.com/page").is_err());
+        assert!(validate_fetch_url("not a url").is_err());
+    }
+
+    #[actix_rt::test]
+    async fn private_addresses_are_refused() {
+        for url in [
+            "https://10.0.0.1/page",
+            "https://169.254.169.254/latest/meta-data",
+            "https://[fd00::1]/page",
+        ] {
+            assert!(matches!(
+                fetch(url, 1024).await,
+                Err(FetchError::Address(_))
+            ));
+        }
+    }
+}
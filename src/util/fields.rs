@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The `fields` query parameter, a comma separated list of the top-level fields to return
+#[derive(Serialize, Deserialize, Default)]
+pub struct FieldsQuery {
+    pub fields: Option<String>,
+}
+
+impl FieldsQuery {
+    /// Parses the requested fields, always including `id`. Returns `None` if no projection was
+    /// requested, and an error if the parameter is given without any fields.
+    pub fn parse(&self) -> Result<Option<Vec<String>>, String> {
+        parse_fields(self.fields.as_deref())
+    }
+}
+
+pub fn parse_fields(fields: Option<&str>) -> Result<Option<Vec<String>>, String> {
+    let Some(fields) = fields else {
+        return Ok(None);
+    };
+
+    let mut parsed = fields
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    if parsed.is_empty() {
+        return Err("At least one field must be given in `fields`!".to_string());
+    }
+
+    if !parsed.iter().any(|x| x == "id") {
+        parsed.push("id".to_string());
+    }
+
+    Ok(Some(parsed))
+}
+
+/// Serializes the body, keeping only the given top-level fields. Arrays are pruned item by item.
+/// Fields which don't exist are ignored.
+pub fn prune<T: Serialize>(body: &T, fields: &[String]) -> Result<Value, serde_json::Error> {
+    Ok(prune_value(serde_json::to_value(body)?, fields))
+}
+
+fn prune_value(value: Value, fields: &[String]) -> Value {
+    match value {
+        Value::Object(mut map) => {
+            map.retain(|key, _| fields.contains(key));
+            Value::Object(map)
+        }
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(|x| prune_value(x, fields)).collect())
+        }
+        value => value,
+    }
+}
@@ -1,13 +1,52 @@
 use crate::database;
 use crate::database::models::image_item;
 use crate::database::redis::RedisPool;
+use crate::file_hosting::{FileHost, UploadFileData};
 use crate::models::images::ImageContext;
 use crate::routes::ApiError;
+use crate::util::env::parse_var;
+use bytes::Bytes;
 use color_thief::ColorFormat;
+use image::codecs::gif::GifDecoder;
+use image::error::{LimitError, LimitErrorKind};
 use image::imageops::FilterType;
-use image::{EncodableLayout, ImageError};
+use image::io::Reader as ImageReader;
+use image::{
+    AnimationDecoder, DynamicImage, EncodableLayout, ImageError, ImageFormat, ImageOutputFormat,
+};
+use log::warn;
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// The widths of the resized variants generated for uploaded images
+pub const IMAGE_VARIANT_WIDTHS: [u32; 3] = [64, 256, 1024];
+
+const DEFAULT_MAX_IMAGE_PIXELS: u64 = 40_000_000;
+
+/// Rejects images whose pixel count exceeds `IMAGE_MAX_PIXELS`, without decoding them.
+/// Images in a format that cannot be read are let through, as they are never decoded here.
+pub fn check_image_size(data: &[u8]) -> Result<(), ImageError> {
+    let max_pixels = parse_var("IMAGE_MAX_PIXELS").unwrap_or(DEFAULT_MAX_IMAGE_PIXELS);
+
+    let dimensions = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|x| x.into_dimensions().ok());
+
+    if let Some((width, height)) = dimensions {
+        if width as u64 * height as u64 > max_pixels {
+            return Err(ImageError::Limits(LimitError::from_kind(
+                LimitErrorKind::DimensionError,
+            )));
+        }
+    }
+
+    Ok(())
+}
 
 pub fn get_color_from_img(data: &[u8]) -> Result<Option<u32>, ImageError> {
+    check_image_size(data)?;
+
     let image = image::load_from_memory(data)?
         .resize(256, 256, FilterType::Nearest)
         .crop_imm(128, 128, 64, 64);
@@ -19,6 +58,142 @@ pub fn get_color_from_img(data: &[u8]) -> Result<Option<u32>, ImageError> {
     Ok(color)
 }
 
+/// Resizes an image to each of the variant widths smaller than itself, encoding them as WebP.
+/// Animated GIFs only get a single static thumbnail of their first frame.
+fn generate_image_variants(data: &[u8]) -> Result<Vec<(u32, Vec<u8>)>, ImageError> {
+    check_image_size(data)?;
+
+    let format = image::guess_format(data)?;
+    let image = image::load_from_memory_with_format(data, format)?;
+
+    let widths = if format == ImageFormat::Gif
+        && GifDecoder::new(Cursor::new(data))?
+            .into_frames()
+            .take(2)
+            .count()
+            > 1
+    {
+        vec![IMAGE_VARIANT_WIDTHS[0].min(image.width())]
+    } else {
+        IMAGE_VARIANT_WIDTHS
+            .iter()
+            .copied()
+            .filter(|x| *x < image.width())
+            .collect()
+    };
+
+    widths
+        .into_iter()
+        .map(|width| {
+            // The WebP encoder only takes 8-bit RGB(A) images
+            let mut bytes = Vec::new();
+            DynamicImage::ImageRgba8(
+                image
+                    .resize(width, u32::MAX, FilterType::Lanczos3)
+                    .to_rgba8(),
+            )
+            .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::WebP)?;
+
+            Ok((width, bytes))
+        })
+        .collect()
+}
+
+/// Generates and uploads the resized variants of an image to `{path}_{width}.webp`, where `path`
+/// is the key of the original without its extension. Returns the uploaded variants by width.
+///
+/// Failing to generate or upload a variant never fails the upload of the original, so this
+/// logs the error and returns whichever variants were uploaded.
+pub async fn upload_image_variants(
+    data: Bytes,
+    path: &str,
+    file_host: &dyn FileHost,
+) -> HashMap<String, UploadFileData> {
+    let variants = match actix_web::web::block(move || generate_image_variants(&data)).await {
+        Ok(Ok(variants)) => variants,
+        Ok(Err(err)) => {
+            warn!("Failed to generate variants for image {path}: {err}");
+            return HashMap::new();
+        }
+        Err(err) => {
+            warn!("Failed to generate variants for image {path}: {err}");
+            return HashMap::new();
+        }
+    };
+
+    let mut uploaded = HashMap::new();
+    for (width, bytes) in variants {
+        match file_host
+            .upload_file("image/webp", &format!("{path}_{width}.webp"), bytes.into())
+            .await
+        {
+            Ok(upload_data) => {
+                uploaded.insert(width.to_string(), upload_data);
+            }
+            Err(err) => warn!("Failed to upload {width}px variant of image {path}: {err}"),
+        }
+    }
+
+    uploaded
+}
+
+/// Maps uploaded image variants to their public URLs
+pub fn get_variant_urls(
+    variants: &HashMap<String, UploadFileData>,
+    cdn_url: &str,
+) -> HashMap<String, String> {
+    variants
+        .iter()
+        .map(|(width, upload_data)| {
+            (
+                width.clone(),
+                format!("{cdn_url}/{}", upload_data.file_name),
+            )
+        })
+        .collect()
+}
+
+/// Deletes the variants of an image from the file host, given their public URLs
+pub async fn delete_image_variants(
+    variants: &HashMap<String, String>,
+    cdn_url: &str,
+    file_host: &dyn FileHost,
+) -> Result<(), ApiError> {
+    for url in variants.values() {
+        if let Some(path) = url.split(&format!("{cdn_url}/")).nth(1) {
+            file_host.delete_file_version("", path).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Shown in place of quarantined images
+const QUARANTINED_IMAGE_PLACEHOLDER: &[u8] = include_bytes!("../../assets/images/quarantined.png");
+
+/// Replaces the files of a quarantined image and its variants on the file host with a
+/// placeholder, so the image isn't served anymore even where its URL was embedded
+pub async fn replace_with_placeholder(
+    url: &str,
+    variants: &HashMap<String, String>,
+    cdn_url: &str,
+    file_host: &dyn FileHost,
+) -> Result<(), ApiError> {
+    for url in std::iter::once(url).chain(variants.values().map(|x| &**x)) {
+        if let Some(path) = url.split(&format!("{cdn_url}/")).nth(1) {
+            file_host
+                .upload_file(
+                    "image/png",
+                    path,
+                    Bytes::from_static(QUARANTINED_IMAGE_PLACEHOLDER),
+                )
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
 // check changes to associated images
 // if they no longer exist in the String list, delete them
 // Eg: if description is modified and no longer contains a link to an iamge
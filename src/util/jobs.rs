@@ -0,0 +1,211 @@
+use crate::database::models::DatabaseError;
+use crate::database::redis::RedisPool;
+use crate::models::ids::base62_impl::to_base62;
+use crate::models::ids::random_base62;
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+const JOBS_NAMESPACE: &str = "jobs";
+const RECENT_JOBS_NAMESPACE: &str = "jobs_recent";
+const RECENT_JOBS_KEY: &str = "all";
+
+/// How many of the newest jobs are listed
+const MAX_RECENT_JOBS: usize = 200;
+/// How long jobs are kept after they were last updated, in seconds
+const JOB_EXPIRY: i64 = 60 * 60 * 24;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobType {
+    SearchIndex,
+    SearchPrune,
+    OrganizationAnnouncement,
+    CacheWarming,
+    OrganizationPayoutSplits,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JobProgress {
+    pub current: u64,
+    pub total: u64,
+    pub message: Option<String>,
+}
+
+/// A long-running background task, as kept in the job registry
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Job {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub job_type: JobType,
+    /// The ID of what the job works on, if it's about a single thing
+    pub target_id: Option<String>,
+    pub status: JobStatus,
+    pub progress: Option<JobProgress>,
+    /// Why the job failed
+    pub error: Option<String>,
+    /// What the job reports beyond its status, for jobs which do
+    #[serde(default)]
+    pub result: Option<serde_json::Value>,
+    pub started: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+    pub finished: Option<DateTime<Utc>>,
+}
+
+impl Job {
+    pub async fn get(id: &str, redis: &RedisPool) -> Result<Option<Job>, DatabaseError> {
+        let mut redis = redis.connect().await?;
+        redis.get_deserialized_from_json(JOBS_NAMESPACE, id).await
+    }
+
+    /// Gets the recent jobs, newest first. Jobs which have expired are left out.
+    pub async fn list(redis: &RedisPool) -> Result<Vec<Job>, DatabaseError> {
+        let mut redis = redis.connect().await?;
+
+        let ids = redis
+            .get_list(RECENT_JOBS_NAMESPACE, RECENT_JOBS_KEY)
+            .await?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(redis
+            .multi_get::<String>(JOBS_NAMESPACE, ids)
+            .await?
+            .into_iter()
+            .flatten()
+            .filter_map(|x| serde_json::from_str(&x).ok())
+            .collect())
+    }
+}
+
+/// Reports a job's progress to the registry. The registry is only for observing jobs, so
+/// failing to reach Redis is logged rather than stopping the job.
+pub struct JobHandle {
+    job: Job,
+    redis: RedisPool,
+}
+
+impl JobHandle {
+    /// Registers a new running job
+    pub async fn start(
+        job_type: JobType,
+        target_id: Option<String>,
+        redis: &RedisPool,
+    ) -> JobHandle {
+        let now = Utc::now();
+        let handle = JobHandle {
+            job: Job {
+                id: to_base62(random_base62(8)),
+                job_type,
+                target_id,
+                status: JobStatus::Running,
+                progress: None,
+                error: None,
+                result: None,
+                started: now,
+                updated: now,
+                finished: None,
+            },
+            redis: redis.clone(),
+        };
+
+        if let Err(err) = handle.register().await {
+            warn!("Failed to register job {}: {}", handle.job.id, err);
+        }
+
+        handle
+    }
+
+    pub fn id(&self) -> &str {
+        &self.job.id
+    }
+
+    /// Sets how far along the job is
+    pub async fn progress(&mut self, current: u64, total: u64, message: impl Into<String>) {
+        self.job.progress = Some(JobProgress {
+            current,
+            total,
+            message: Some(message.into()),
+        });
+        self.save().await;
+    }
+
+    /// Moves the job along by `amount`, keeping the total it was given
+    pub async fn advance(&mut self, amount: u64, message: impl Into<String>) {
+        let (current, total) = self
+            .job
+            .progress
+            .as_ref()
+            .map_or((0, 0), |x| (x.current, x.total));
+        self.progress(current + amount, total, message).await;
+    }
+
+    /// Sets what the job reports, which is kept with it once it's finished
+    pub async fn set_result<T: Serialize>(&mut self, result: &T) {
+        match serde_json::to_value(result) {
+            Ok(result) => {
+                self.job.result = Some(result);
+                self.save().await;
+            }
+            Err(err) => warn!(
+                "Failed to serialize the result of job {}: {}",
+                self.job.id, err
+            ),
+        }
+    }
+
+    /// Marks the job as succeeded or failed, depending on its result
+    pub async fn finish<T, E: Display>(mut self, result: &Result<T, E>) {
+        let now = Utc::now();
+        match result {
+            Ok(_) => self.job.status = JobStatus::Succeeded,
+            Err(err) => {
+                self.job.status = JobStatus::Failed;
+                self.job.error = Some(err.to_string());
+            }
+        }
+        self.job.finished = Some(now);
+        self.save().await;
+    }
+
+    async fn register(&self) -> Result<(), DatabaseError> {
+        let mut redis = self.redis.connect().await?;
+        redis
+            .set_serialized_to_json(JOBS_NAMESPACE, &self.job.id, &self.job, Some(JOB_EXPIRY))
+            .await?;
+        redis
+            .push_capped(
+                RECENT_JOBS_NAMESPACE,
+                RECENT_JOBS_KEY,
+                &self.job.id,
+                MAX_RECENT_JOBS,
+                Some(JOB_EXPIRY),
+            )
+            .await
+    }
+
+    async fn save(&mut self) {
+        self.job.updated = Utc::now();
+
+        let result = async {
+            let mut redis = self.redis.connect().await?;
+            redis
+                .set_serialized_to_json(JOBS_NAMESPACE, &self.job.id, &self.job, Some(JOB_EXPIRY))
+                .await
+        };
+
+        if let Err(err) = result.await {
+            warn!("Failed to update job {}: {}", self.job.id, err);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use actix_web::http::header::ACCEPT_LANGUAGE;
+use actix_web::HttpRequest;
+
+/// Checks that a language is a BCP-47 primary language subtag, such as `de`. Subtags are
+/// expected to be lowercase, so `en-US` should be given as `en`.
+pub fn validate_language(language: &str) -> Result<(), validator::ValidationError> {
+    if !(2..=3).contains(&language.len()) || !language.bytes().all(|x| x.is_ascii_lowercase()) {
+        return Err(validator::ValidationError::new(
+            "language must be a lowercase BCP-47 primary subtag",
+        ));
+    }
+
+    Ok(())
+}
+
+/// The languages the client asked for, most preferred first. The `lang` query parameter is
+/// used if it's set, and otherwise the `Accept-Language` header. Only primary subtags are
+/// returned, so `de-AT` is treated as `de`.
+pub fn preferred_languages(req: &HttpRequest, lang: Option<&str>) -> Vec<String> {
+    if let Some(lang) = lang {
+        return primary_subtag(lang).into_iter().collect();
+    }
+
+    let Some(header) = req
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|x| x.to_str().ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut languages = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let language = primary_subtag(parts.next()?)?;
+            let quality = parts
+                .find_map(|x| x.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |x| x.trim().parse::<f32>().ok())?;
+
+            (quality > 0.0).then_some((language, quality))
+        })
+        .collect::<Vec<_>>();
+
+    // The sort is stable, so languages with the same quality keep the client's order
+    languages.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut preferred: Vec<String> = Vec::new();
+    for (language, _) in languages {
+        if !preferred.contains(&language) {
+            preferred.push(language);
+        }
+    }
+    preferred
+}
+
+fn primary_subtag(tag: &str) -> Option<String> {
+    let language = tag.trim().split('-').next()?.to_lowercase();
+    validate_language(&language).ok()?;
+    Some(language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn orders_languages_by_quality() {
+        let req = TestRequest::default()
+            .insert_header((
+                ACCEPT_LANGUAGE,
+                "en;q=0.5, de-AT, de;q=0.9, *;q=0.1, fr;q=0",
+            ))
+            .to_http_request();
+
+        assert_eq!(preferred_languages(&req, None), ["de", "en"]);
+        assert_eq!(preferred_languages(&req, Some("pt-BR")), ["pt"]);
+        assert!(preferred_languages(&req, Some("german")).is_empty());
+    }
+}
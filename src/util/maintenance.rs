@@ -0,0 +1,173 @@
+use crate::database::models::DatabaseError;
+use crate::database::redis::RedisPool;
+use crate::models::error::ApiError;
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header, Method},
+    web, Error as AWError, HttpResponse,
+};
+use futures::future::{ok, LocalBoxFuture, Ready};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+
+const MAINTENANCE_NAMESPACE: &str = "maintenance";
+const MAINTENANCE_KEY: &str = "state";
+
+/// How many seconds clients are told to wait before retrying, unless the state sets it
+pub const DEFAULT_RETRY_AFTER: u64 = 300;
+
+/// Whether the API is read-only for maintenance. This is kept in Redis, so every worker
+/// sees the same state and it survives restarts.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MaintenanceState {
+    pub read_only: bool,
+    /// Shown to clients whose requests are rejected
+    pub message: Option<String>,
+    /// Sent in the `Retry-After` header of rejected requests, in seconds
+    pub retry_after: Option<u64>,
+}
+
+impl MaintenanceState {
+    /// Gets the current state. If Redis can't be reached, the API is left writable rather
+    /// than rejecting every request.
+    pub async fn get(redis: &RedisPool) -> MaintenanceState {
+        let state = async {
+            let mut redis = redis.connect().await?;
+            redis
+                .get_deserialized_from_json::<MaintenanceState>(
+                    MAINTENANCE_NAMESPACE,
+                    MAINTENANCE_KEY,
+                )
+                .await
+        };
+
+        match state.await {
+            Ok(state) => state.unwrap_or_default(),
+            Err(err) => {
+                warn!("Failed to get the maintenance state: {}", err);
+                MaintenanceState::default()
+            }
+        }
+    }
+
+    pub async fn set(&self, redis: &RedisPool) -> Result<(), DatabaseError> {
+        let mut redis = redis.connect().await?;
+        redis
+            .set_persistent(
+                MAINTENANCE_NAMESPACE,
+                MAINTENANCE_KEY,
+                &serde_json::to_string(self)?,
+            )
+            .await
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::ServiceUnavailable()
+            .insert_header((
+                header::RETRY_AFTER,
+                self.retry_after.unwrap_or(DEFAULT_RETRY_AFTER).to_string(),
+            ))
+            .json(ApiError {
+                error: "maintenance",
+                description: self.message.as_deref().unwrap_or(
+                    "Modrinth is undergoing maintenance and is read-only. Please try again later.",
+                ),
+            })
+    }
+}
+
+/// Rejects requests which make changes with a 503 while the API is read-only for maintenance.
+/// Read requests, lookups sent as POST requests, logging in and the maintenance toggle itself
+/// are let through.
+pub struct ReadOnlyMode;
+
+impl<S, B> Transform<S, ServiceRequest> for ReadOnlyMode
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = AWError> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = AWError;
+    type Transform = ReadOnlyModeMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(ReadOnlyModeMiddleware {
+            service: Rc::new(service),
+        })
+    }
+}
+
+pub struct ReadOnlyModeMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for ReadOnlyModeMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = AWError> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = AWError;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let srv = self.service.clone();
+
+        Box::pin(async move {
+            // Only writes need the state, so reads don't wait on Redis
+            if makes_changes(req.method()) && !is_allowed(req.method(), req.path()) {
+                if let Some(redis) = req.app_data::<web::Data<RedisPool>>() {
+                    let state = MaintenanceState::get(redis).await;
+                    if state.read_only {
+                        let response = state.error_response();
+                        return Ok(req.into_response(response).map_into_right_body());
+                    }
+                }
+            }
+
+            let res = srv.call(req).await?;
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+fn makes_changes(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::POST | Method::PATCH | Method::PUT | Method::DELETE
+    )
+}
+
+// Users can still log in and refresh their sessions, and admins can turn maintenance off. Other
+// auth routes, such as signing up or revoking sessions, are writes like any other.
+fn is_allowed(method: &Method, path: &str) -> bool {
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+
+    matches!(
+        segments.as_slice(),
+        ["_internal" | "v2", "auth", "login"]
+            | ["_internal" | "v2", "auth", "login", "2fa"]
+            | ["_internal" | "v2", "session", "refresh"]
+            | ["v3", "admin", "maintenance"]
+    ) || (*method == Method::POST && is_lookup(&segments))
+}
+
+// Lookups which take their input as a POST body, since lists of hashes are too long for a query
+// string. They don't write anything.
+fn is_lookup(segments: &[&str]) -> bool {
+    matches!(
+        segments,
+        ["v2" | "v3", "version_file", _, "update"]
+            | ["v2" | "v3", "version_file", "project"]
+            | ["v2" | "v3", "version_files"]
+            | ["v2" | "v3", "version_files", "update" | "update_individual"]
+    )
+}
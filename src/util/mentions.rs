@@ -0,0 +1,63 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// The most users a single message can mention. Any mentions past this are ignored.
+pub const MAX_MENTIONS: usize = 10;
+
+lazy_static! {
+    static ref RE_CODE: Regex = Regex::new(r"(?s)```.*?```|`[^`]*`").unwrap();
+    // A mention must start the text or follow something other than a word character, so
+    // emails aren't picked up
+    static ref RE_MENTION: Regex = Regex::new(r"(?:^|[^\w@])@([A-Za-z0-9_-]+)").unwrap();
+}
+
+/// Finds the usernames mentioned with `@username` in a message, lowercased and without
+/// duplicates, in the order they're first mentioned. Mentions inside code are skipped.
+pub fn parse_mentions(body: &str) -> Vec<String> {
+    let body = RE_CODE.replace_all(body, " ");
+
+    let mut usernames: Vec<String> = Vec::new();
+    for captures in RE_MENTION.captures_iter(&body) {
+        let username = captures[1].to_lowercase();
+        if !usernames.contains(&username) {
+            usernames.push(username);
+        }
+
+        if usernames.len() >= MAX_MENTIONS {
+            break;
+        }
+    }
+
+    usernames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_mentions_next_to_punctuation() {
+        assert_eq!(
+            parse_mentions("@Alice, thanks! (cc @bob_2) and @alice again. @carol?"),
+            vec!["alice", "bob_2", "carol"]
+        );
+        assert!(parse_mentions("email me at someone@example.com").is_empty());
+    }
+
+    #[test]
+    fn skips_mentions_in_code() {
+        assert_eq!(
+            parse_mentions("`@inline` ```\n@fenced\n``` @outside"),
+            vec!["outside"]
+        );
+    }
+
+    #[test]
+    fn caps_mentions() {
+        let body = (0..20)
+            .map(|x| format!("@user{x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(parse_mentions(&body).len(), MAX_MENTIONS);
+    }
+}
@@ -1,13 +1,27 @@
 pub mod actix;
 pub mod bitflag;
 pub mod captcha;
+pub mod compress;
 pub mod cors;
+pub mod csv;
 pub mod date;
+pub mod encryption;
 pub mod env;
+pub mod etag;
 pub mod ext;
+pub mod fetch;
+pub mod fields;
 pub mod guards;
 pub mod img;
+pub mod jobs;
+pub mod language;
+pub mod maintenance;
+pub mod mentions;
+pub mod readme;
 pub mod redis;
+pub mod rel_me;
 pub mod routes;
+pub mod sanitize;
+pub mod signed_urls;
 pub mod validate;
 pub mod webhook;
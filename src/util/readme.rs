@@ -0,0 +1,76 @@
+use thiserror::Error;
+
+use super::fetch::{fetch, validate_fetch_url, FetchError};
+use super::sanitize;
+
+/// The largest README that will be downloaded, in bytes
+pub const MAX_README_SIZE: usize = 256 * 1024;
+/// The longest description a project can have, matching the limit on manually submitted bodies
+pub const MAX_DESCRIPTION_LENGTH: usize = 65536;
+
+const DEFAULT_ALLOWED_HOSTS: &[&str] = &["raw.githubusercontent.com", "gitlab.com"];
+
+#[derive(Error, Debug)]
+pub enum ReadmeError {
+    #[error("The README could not be fetched: {0}")]
+    Fetch(#[from] FetchError),
+    #[error("The README is not valid UTF-8")]
+    InvalidEncoding,
+    #[error("The README is longer than the maximum description length of {MAX_DESCRIPTION_LENGTH} characters")]
+    TooLong,
+}
+
+// The hosts READMEs can be fetched from, which can be overridden with `README_ALLOWED_HOSTS`
+fn allowed_hosts() -> Vec<String> {
+    match dotenvy::var("README_ALLOWED_HOSTS") {
+        Ok(hosts) => hosts
+            .split(',')
+            .map(|x| x.trim().to_lowercase())
+            .filter(|x| !x.is_empty())
+            .collect(),
+        Err(_) => DEFAULT_ALLOWED_HOSTS
+            .iter()
+            .map(|x| x.to_string())
+            .collect(),
+    }
+}
+
+/// Checks that the URL points at a raw file on an allow-listed host
+pub fn validate_readme_url(value: &str) -> Result<(), validator::ValidationError> {
+    let url = validate_fetch_url(value)?;
+
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    if !allowed_hosts().contains(&host) {
+        return Err(validator::ValidationError::new(
+            "README host is not allowed",
+        ));
+    }
+
+    // GitLab serves both its web pages and raw files from the same host
+    if host == "gitlab.com" && !url.path().contains("/-/raw/") {
+        return Err(validator::ValidationError::new(
+            "GitLab URLs must point at a raw file",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Downloads the markdown at the URL, which should have been checked with `validate_readme_url`
+pub async fn fetch_readme(url: &str) -> Result<String, ReadmeError> {
+    let body = fetch(url, MAX_README_SIZE).await?;
+    let markdown = String::from_utf8(body).map_err(|_| ReadmeError::InvalidEncoding)?;
+    sanitize_readme(&markdown)
+}
+
+// Applies the same limits and sanitization as a manually submitted body. Null characters can't
+// be stored in Postgres, so they're dropped, and line endings are normalized.
+fn sanitize_readme(markdown: &str) -> Result<String, ReadmeError> {
+    let markdown = markdown.replace("\r\n", "\n").replace('\0', "");
+
+    if markdown.chars().count() > MAX_DESCRIPTION_LENGTH {
+        return Err(ReadmeError::TooLong);
+    }
+
+    Ok(sanitize::sanitize(&markdown).body)
+}
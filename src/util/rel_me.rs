@@ -0,0 +1,108 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::fetch::{fetch, validate_fetch_url, FetchError};
+
+/// The largest page that will be downloaded when looking for links, in bytes
+pub const MAX_PAGE_SIZE: usize = 1024 * 1024;
+
+lazy_static! {
+    static ref RE_LINK_TAG: Regex = Regex::new(r"(?is)<(?:a|link)\s[^>]*>").unwrap();
+    static ref RE_ATTRIBUTE: Regex =
+        Regex::new(r#"(?is)([a-z-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap();
+}
+
+/// Checks that the URL can be fetched to look for links
+pub fn validate_page_url(value: &str) -> Result<(), validator::ValidationError> {
+    validate_fetch_url(value).map(|_| ())
+}
+
+/// Downloads the page at the URL, which should have been checked with `validate_page_url`,
+/// and returns the targets of its `rel="me"` links
+pub async fn fetch_rel_me_links(url: &str) -> Result<Vec<String>, FetchError> {
+    let body = fetch(url, MAX_PAGE_SIZE).await?;
+
+    Ok(rel_me_links(&String::from_utf8_lossy(&body)))
+}
+
+/// Finds the targets of the `<a>` and `<link>` tags with `me` in their `rel` attribute
+pub fn rel_me_links(html: &str) -> Vec<String> {
+    RE_LINK_TAG
+        .find_iter(html)
+        .filter_map(|tag| {
+            let mut rel_me = false;
+            let mut href = None;
+
+            for attribute in RE_ATTRIBUTE.captures_iter(tag.as_str()) {
+                let value = attribute
+                    .get(2)
+                    .or_else(|| attribute.get(3))
+                    .or_else(|| attribute.get(4))
+                    .map_or("", |x| x.as_str());
+
+                match &*attribute[1].to_lowercase() {
+                    "rel" => {
+                        rel_me = value
+                            .split_ascii_whitespace()
+                            .any(|x| x.eq_ignore_ascii_case("me"))
+                    }
+                    "href" => href = Some(value.replace("&amp;", "&")),
+                    _ => {}
+                }
+            }
+
+            href.filter(|_| rel_me)
+        })
+        .collect()
+}
+
+/// Whether a link points at one of the given URLs, ignoring the scheme, letter case and a
+/// trailing slash
+pub fn link_matches(link: &str, targets: &[String]) -> bool {
+    fn normalize(url: &str) -> String {
+        let url = url.trim();
+        let url = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .unwrap_or(url);
+        url.trim_end_matches('/').to_lowercase()
+    }
+
+    let link = normalize(link);
+    targets.iter().any(|x| normalize(x) == link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_rel_me_links() {
+        let html = r#"
+            <link rel="me" href="https://modrinth.com/user/alpha">
+            <a href='https://example.com/one' rel='nofollow me'>One</a>
+            <A REL=me HREF=https://example.com/two>Two</A>
+            <a href="https://example.com/three">Not me</a>
+            <a rel="member" href="https://example.com/four">Not me either</a>
+        "#;
+
+        assert_eq!(
+            rel_me_links(html),
+            [
+                "https://modrinth.com/user/alpha",
+                "https://example.com/one",
+                "https://example.com/two",
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_links() {
+        let targets = vec!["https://modrinth.com/user/Alpha".to_string()];
+        assert!(link_matches("http://modrinth.com/user/alpha/", &targets));
+        assert!(!link_matches(
+            "https://modrinth.com/user/alphabet",
+            &targets
+        ));
+    }
+}
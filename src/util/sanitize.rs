@@ -0,0 +1,714 @@
+use std::ops::Range;
+
+use actix_web::{web, HttpRequest};
+use lazy_static::lazy_static;
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use regex::Regex;
+use serde::Deserialize;
+use thiserror::Error;
+
+/// The longest body that will be sanitized, matching the limit on descriptions, changelogs and
+/// thread messages
+pub const MAX_BODY_LENGTH: usize = 65536;
+
+const DEFAULT_EMBED_HOSTS: &[&str] = &[
+    "www.youtube.com",
+    "youtube.com",
+    "www.youtube-nocookie.com",
+    "player.vimeo.com",
+    "streamable.com",
+];
+// Embeds can play their media, but can't navigate the page or open forms
+const IFRAME_SANDBOX: &str = "allow-scripts allow-same-origin allow-popups allow-presentation";
+
+const ALLOWED_TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "center",
+    "code",
+    "dd",
+    "del",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "iframe",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "small",
+    "source",
+    "span",
+    "strike",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+    "video",
+];
+const VOID_TAGS: &[&str] = &["br", "hr", "img", "source"];
+// Elements which are removed along with everything inside them
+const DROPPED_ELEMENTS: &[&str] = &[
+    "applet",
+    "frameset",
+    "math",
+    "noembed",
+    "noframes",
+    "noscript",
+    "object",
+    "plaintext",
+    "script",
+    "select",
+    "style",
+    "svg",
+    "template",
+    "textarea",
+    "title",
+    "xmp",
+];
+const GLOBAL_ATTRIBUTES: &[&str] = &["align", "title"];
+const URL_ATTRIBUTES: &[&str] = &["href", "poster", "src"];
+
+lazy_static! {
+    static ref RE_COMMENT: Regex =
+        Regex::new(r"^(?:<!--[\s\S]*?-->|<\?[\s\S]*?\?>|<!\[CDATA\[[\s\S]*?\]\]>|<![A-Za-z][^>]*>)")
+            .unwrap();
+    static ref RE_CLOSING_TAG: Regex = Regex::new(r"^</([A-Za-z][A-Za-z0-9-]*)\s*>").unwrap();
+    static ref RE_OPENING_TAG: Regex = Regex::new(
+        r#"^<([A-Za-z][A-Za-z0-9-]*)((?:\s+[A-Za-z_:][A-Za-z0-9_.:-]*(?:\s*=\s*(?:[^\s"'=<>`]+|'[^']*'|"[^"]*"))?)*)\s*/?>"#
+    )
+    .unwrap();
+    static ref RE_ATTRIBUTE: Regex = Regex::new(
+        r#"([A-Za-z_:][A-Za-z0-9_.:-]*)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'=<>`]+)))?"#
+    )
+    .unwrap();
+    static ref RE_ENTITY: Regex =
+        Regex::new(r"&(?:#([0-9]{1,7});?|#[xX]([0-9a-fA-F]{1,6});?|([A-Za-z]+);)").unwrap();
+}
+
+#[derive(Error, Debug)]
+pub enum SanitizeError {
+    #[error("The body is longer than the maximum length of {MAX_BODY_LENGTH} characters")]
+    TooLong,
+    #[error("The body contains content which isn't allowed: {}", .0.join(", "))]
+    Disallowed(Vec<String>),
+}
+
+#[derive(Deserialize)]
+struct SanitizeQuery {
+    #[serde(default)]
+    strict: bool,
+}
+
+/// Whether the request asked for bodies with disallowed content to be rejected with
+/// `?strict=true`, instead of having that content removed
+pub fn is_strict(req: &HttpRequest) -> bool {
+    web::Query::<SanitizeQuery>::from_query(req.query_string()).map_or(false, |x| x.strict)
+}
+
+/// A sanitized body, along with a description of everything that was removed from it
+pub struct Sanitized {
+    pub body: String,
+    pub removed: Vec<String>,
+}
+
+/// Sanitizes a markdown body before it's stored. In strict mode, bodies which had anything
+/// removed are rejected instead.
+pub fn sanitize_body(body: &str, strict: bool) -> Result<String, SanitizeError> {
+    if body.chars().count() > MAX_BODY_LENGTH {
+        return Err(SanitizeError::TooLong);
+    }
+
+    let sanitized = sanitize(body);
+    if strict && !sanitized.removed.is_empty() {
+        return Err(SanitizeError::Disallowed(sanitized.removed));
+    }
+
+    Ok(sanitized.body)
+}
+
+/// Removes everything from the markdown which isn't on the allow-list: HTML tags and
+/// attributes, embeds from other hosts and links to scripts. The markdown itself, including
+/// code, is left as it is.
+pub fn sanitize(body: &str) -> Sanitized {
+    let mut removed = Vec::new();
+    let mut replacements: Vec<(Range<usize>, String)> = Vec::new();
+
+    // The raw HTML the markdown contains, with the lines of HTML blocks merged back together
+    let mut html_ranges: Vec<Range<usize>> = Vec::new();
+    // Links and images which point somewhere unsafe are replaced with their text
+    let mut unsafe_link: Option<(Range<usize>, String)> = None;
+
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+    for (event, range) in Parser::new_ext(body, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Link(_, dest, _)) | Event::Start(Tag::Image(_, dest, _))
+                if unsafe_link.is_none() && !is_safe_url(&dest, true) =>
+            {
+                push_unique(
+                    &mut removed,
+                    format!("unsafe link to `{}`", scheme_of(&dest)),
+                );
+                unsafe_link = Some((range, String::new()));
+            }
+            Event::End(Tag::Link(..)) | Event::End(Tag::Image(..))
+                if unsafe_link
+                    .as_ref()
+                    .map_or(false, |(link_range, _)| *link_range == range) =>
+            {
+                if let Some((range, text)) = unsafe_link.take() {
+                    replacements.push((range, escape_markdown(&text)));
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, link_text)) = &mut unsafe_link {
+                    link_text.push_str(&text);
+                }
+            }
+            Event::Html(_) => match html_ranges.last_mut() {
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => html_ranges.push(range),
+            },
+            _ => {}
+        }
+    }
+
+    for range in html_ranges {
+        let html = &body[range.clone()];
+        let sanitized = sanitize_html(html, &mut removed);
+        if sanitized != html {
+            replacements.push((range, sanitized));
+        }
+    }
+
+    // HTML inside a link which is replaced is replaced along with it
+    replacements.sort_by_key(|(range, _)| range.start);
+    let mut sanitized = String::with_capacity(body.len());
+    let mut position = 0;
+    for (range, replacement) in replacements {
+        if range.start < position {
+            continue;
+        }
+        sanitized.push_str(&body[position..range.start]);
+        sanitized.push_str(&replacement);
+        position = range.end;
+    }
+    sanitized.push_str(&body[position..]);
+
+    Sanitized {
+        body: sanitized,
+        removed,
+    }
+}
+
+// Sanitizes raw HTML tag by tag. Text between the tags is kept, and any `<` which doesn't
+// start a tag is escaped.
+fn sanitize_html(html: &str, removed: &mut Vec<String>) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut position = 0;
+
+    while let Some(offset) = html[position..].find('<') {
+        let start = position + offset;
+        output.push_str(&html[position..start]);
+        let rest = &html[start..];
+
+        if let Some(comment) = RE_COMMENT.find(rest) {
+            position = start + comment.end();
+        } else if let Some(captures) = RE_CLOSING_TAG.captures(rest) {
+            let name = captures[1].to_lowercase();
+            if is_allowed_tag(&name) && !VOID_TAGS.contains(&name.as_str()) && name != "iframe" {
+                output.push_str(&format!("</{name}>"));
+            } else if !DROPPED_ELEMENTS.contains(&name.as_str()) && name != "iframe" {
+                push_unique(removed, format!("<{name}> tag"));
+            }
+            position = start + captures[0].len();
+        } else if let Some(captures) = RE_OPENING_TAG.captures(rest) {
+            let name = captures[1].to_lowercase();
+            let mut end = start + captures[0].len();
+
+            // Nothing inside these elements is kept, including anything nested inside iframes
+            if DROPPED_ELEMENTS.contains(&name.as_str()) || name == "iframe" {
+                end = find_closing_tag(html, end, &name);
+            }
+
+            if DROPPED_ELEMENTS.contains(&name.as_str()) {
+                push_unique(removed, format!("<{name}> element"));
+            } else if name == "iframe" {
+                match sanitize_iframe(&captures[2], removed) {
+                    Some(iframe) => output.push_str(&iframe),
+                    None => push_unique(removed, "iframe which isn't an allowed embed".to_string()),
+                }
+            } else if is_allowed_tag(&name) {
+                output.push_str(&sanitize_tag(&name, &captures[2], removed));
+            } else {
+                push_unique(removed, format!("<{name}> tag"));
+            }
+            position = end;
+        } else {
+            output.push_str("&lt;");
+            position = start + 1;
+        }
+    }
+    output.push_str(&html[position..]);
+
+    output
+}
+
+fn is_allowed_tag(name: &str) -> bool {
+    ALLOWED_TAGS.contains(&name)
+}
+
+// Finds the end of the element's closing tag, or the end of the HTML if it isn't closed
+fn find_closing_tag(html: &str, from: usize, name: &str) -> usize {
+    let closing = format!("</{name}");
+    let lowercase = html[from..].to_ascii_lowercase();
+
+    let mut search = 0;
+    while let Some(offset) = lowercase[search..].find(&closing) {
+        let tag_start = search + offset;
+        let after_name = &lowercase[tag_start + closing.len()..];
+        let whitespace = after_name.len() - after_name.trim_start().len();
+        if after_name[whitespace..].starts_with('>') {
+            return from + tag_start + closing.len() + whitespace + 1;
+        }
+        search = tag_start + closing.len();
+    }
+
+    html.len()
+}
+
+fn allowed_attributes(tag: &str) -> &'static [&'static str] {
+    match tag {
+        "a" => &["href"],
+        "img" => &["src", "alt", "width", "height"],
+        "video" => &[
+            "src", "poster", "width", "height", "controls", "loop", "muted",
+        ],
+        "source" => &["src", "type"],
+        "td" | "th" => &["colspan", "rowspan"],
+        "ol" => &["start"],
+        "details" => &["open"],
+        _ => &[],
+    }
+}
+
+fn parse_attributes(attributes: &str) -> Vec<(String, Option<String>)> {
+    RE_ATTRIBUTE
+        .captures_iter(attributes)
+        .map(|captures| {
+            let value = captures
+                .get(2)
+                .or_else(|| captures.get(3))
+                .or_else(|| captures.get(4))
+                .map(|x| decode_entities(x.as_str()));
+            (captures[1].to_lowercase(), value)
+        })
+        .collect()
+}
+
+fn sanitize_tag(name: &str, attributes: &str, removed: &mut Vec<String>) -> String {
+    let mut output = format!("<{name}");
+
+    for (attribute, value) in parse_attributes(attributes) {
+        if !GLOBAL_ATTRIBUTES.contains(&attribute.as_str())
+            && !allowed_attributes(name).contains(&attribute.as_str())
+        {
+            push_unique(removed, format!("`{attribute}` attribute on <{name}>"));
+            continue;
+        }
+
+        if URL_ATTRIBUTES.contains(&attribute.as_str())
+            && !value
+                .as_deref()
+                .map_or(false, |x| is_safe_url(x, attribute == "href"))
+        {
+            push_unique(removed, format!("unsafe `{attribute}` URL on <{name}>"));
+            continue;
+        }
+
+        push_attribute(&mut output, &attribute, value.as_deref());
+    }
+
+    output.push('>');
+    output
+}
+
+// Iframes are only kept if they embed media from an allow-listed host, and are always sandboxed
+fn sanitize_iframe(attributes: &str, removed: &mut Vec<String>) -> Option<String> {
+    let attributes = parse_attributes(attributes);
+    let src = attributes
+        .iter()
+        .find(|(name, _)| name == "src")
+        .and_then(|(_, value)| value.as_deref())?;
+    if !is_allowed_embed(src) {
+        return None;
+    }
+
+    let mut output = "<iframe".to_string();
+    for (attribute, value) in &attributes {
+        match attribute.as_str() {
+            "src" | "width" | "height" | "title" | "allowfullscreen" | "frameborder" => {
+                push_attribute(&mut output, attribute, value.as_deref())
+            }
+            // The sandbox is always replaced with our own
+            "sandbox" => {}
+            _ => push_unique(removed, format!("`{attribute}` attribute on <iframe>")),
+        }
+    }
+    push_attribute(&mut output, "sandbox", Some(IFRAME_SANDBOX));
+    output.push_str("></iframe>");
+
+    Some(output)
+}
+
+fn push_attribute(output: &mut String, name: &str, value: Option<&str>) {
+    output.push(' ');
+    output.push_str(name);
+    if let Some(value) = value {
+        output.push_str("=\"");
+        output.push_str(&escape_attribute(value));
+        output.push('"');
+    }
+}
+
+// The hosts media can be embedded from, which can be overridden with `EMBED_ALLOWED_HOSTS`
+fn embed_hosts() -> Vec<String> {
+    match dotenvy::var("EMBED_ALLOWED_HOSTS") {
+        Ok(hosts) => hosts
+            .split(',')
+            .map(|x| x.trim().to_lowercase())
+            .filter(|x| !x.is_empty())
+            .collect(),
+        Err(_) => DEFAULT_EMBED_HOSTS.iter().map(|x| x.to_string()).collect(),
+    }
+}
+
+fn is_allowed_embed(src: &str) -> bool {
+    let Ok(url) = url::Url::parse(src.trim()) else {
+        return false;
+    };
+
+    url.scheme() == "https"
+        && url
+            .host_str()
+            .map_or(false, |host| embed_hosts().contains(&host.to_lowercase()))
+}
+
+// Browsers ignore whitespace and control characters in URLs, so they're ignored when finding
+// the scheme too
+fn scheme_of(url: &str) -> String {
+    let url = url
+        .chars()
+        .filter(|x| !x.is_ascii_whitespace() && !x.is_ascii_control())
+        .collect::<String>();
+
+    match url.find([':', '/', '?', '#']) {
+        Some(index) if url[index..].starts_with(':') => url[..index].to_lowercase(),
+        _ => String::new(),
+    }
+}
+
+/// Whether the URL is relative or uses a scheme which can't run scripts
+fn is_safe_url(url: &str, allow_mailto: bool) -> bool {
+    match scheme_of(url).as_str() {
+        "" | "http" | "https" => true,
+        "mailto" => allow_mailto,
+        _ => false,
+    }
+}
+
+fn decode_entities(value: &str) -> String {
+    RE_ENTITY
+        .replace_all(value, |captures: &regex::Captures| {
+            let decoded = if let Some(decimal) = captures.get(1) {
+                decimal
+                    .as_str()
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(char::from_u32)
+            } else if let Some(hex) = captures.get(2) {
+                u32::from_str_radix(hex.as_str(), 16)
+                    .ok()
+                    .and_then(char::from_u32)
+            } else {
+                match &captures[3] {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some('\u{a0}'),
+                    "colon" => Some(':'),
+                    "Tab" => Some('\t'),
+                    "NewLine" => Some('\n'),
+                    _ => None,
+                }
+            };
+
+            decoded.map_or_else(|| captures[0].to_string(), |x| x.to_string())
+        })
+        .into_owned()
+}
+
+/// Escapes text so it can be placed in HTML, including inside a quoted attribute
+pub fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// Escapes the text so it stays plain text when parsed as markdown
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        if char.is_ascii_punctuation() {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+    escaped
+}
+
+fn push_unique(removed: &mut Vec<String>, description: String) {
+    if !removed.contains(&description) {
+        removed.push(description);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean(body: &str) -> String {
+        sanitize(body).body
+    }
+
+    #[test]
+    fn leaves_markdown_alone() {
+        let body = "# Title\n\nSome **bold** text, a [link](https://example.com) and a < b.\n\n- item\n- `code`\n";
+        let sanitized = sanitize(body);
+        assert_eq!(sanitized.body, body);
+        assert!(sanitized.removed.is_empty());
+    }
+
+    #[test]
+    fn leaves_code_alone() {
+        let body = "`<script>alert(1)</script>`\n\n```html\n<img src=x onerror=alert(1)>\n```\n\n    List<String> list;\n";
+        assert_eq!(clean(body), body);
+    }
+
+    #[test]
+    fn keeps_allowed_html() {
+        let body = "<p align=\"center\">\n<img src=\"https://cdn.example.com/a.png\" alt=\"Banner\" width=\"300\">\n</p>\n\n<details><summary>More</summary>Hidden</details>\n";
+        let sanitized = sanitize(body);
+        assert_eq!(sanitized.body, body);
+        assert!(sanitized.removed.is_empty());
+    }
+
+    #[test]
+    fn removes_scripts_with_their_content() {
+        let sanitized = sanitize("<script>\nalert(1)\n</script>\n\nText");
+        assert_eq!(sanitized.body, "\n\nText");
+        assert_eq!(sanitized.removed, vec!["<script> element"]);
+
+        let body = clean("Hi <SCRIPT src=//evil.com/x.js></SCRIPT> there");
+        assert!(!body.to_lowercase().contains("script"));
+    }
+
+    #[test]
+    fn removes_event_handlers() {
+        let sanitized = sanitize("<img src=\"x.png\" onerror=\"alert(1)\">");
+        assert_eq!(sanitized.body, "<img src=\"x.png\">");
+        assert_eq!(sanitized.removed, vec!["`onerror` attribute on <img>"]);
+
+        assert_eq!(
+            clean("<div onmouseover=alert(1) style=\"x\">Hi</div>"),
+            "<div>Hi</div>"
+        );
+        assert_eq!(
+            clean("<p ONCLICK = 'alert(1)' title=\"ok\">x</p>"),
+            "<p title=\"ok\">x</p>"
+        );
+    }
+
+    #[test]
+    fn removes_script_urls_from_html() {
+        for href in [
+            "javascript:alert(1)",
+            "JaVaScRiPt:alert(1)",
+            " javascript:alert(1)",
+            "java\tscript:alert(1)",
+            "jav&#x61;script:alert(1)",
+            "javascript&colon;alert(1)",
+            "&#106;&#97;&#118;&#97;&#115;&#99;&#114;&#105;&#112;&#116;&#58;alert(1)",
+            "vbscript:msgbox(1)",
+            "data:text/html;base64,PHNjcmlwdD5hbGVydCgxKTwvc2NyaXB0Pg==",
+        ] {
+            let sanitized = sanitize(&format!("<a href=\"{href}\">Click</a>"));
+            assert_eq!(sanitized.body, "<a>Click</a>", "{href}");
+            assert_eq!(sanitized.removed, vec!["unsafe `href` URL on <a>"]);
+        }
+
+        assert_eq!(
+            clean("<a href=\"https://example.com/?a=1&b=2\">Ok</a>"),
+            "<a href=\"https://example.com/?a=1&amp;b=2\">Ok</a>"
+        );
+        assert_eq!(
+            clean("<a href=\"mailto:me@example.com\">Mail</a>"),
+            "<a href=\"mailto:me@example.com\">Mail</a>"
+        );
+        assert_eq!(clean("<img src=\"mailto:me@example.com\">"), "<img>");
+    }
+
+    #[test]
+    fn removes_script_urls_from_markdown() {
+        let sanitized = sanitize("[Click me](javascript:alert(1)) and [safe](https://example.com)");
+        assert_eq!(sanitized.body, "Click me and [safe](https://example.com)");
+        assert_eq!(sanitized.removed, vec!["unsafe link to `javascript`"]);
+
+        assert_eq!(clean("<javascript:alert(1)>"), "javascript\\:alert\\(1\\)");
+        assert_eq!(
+            clean("[x][ref]\n\n[ref]: jav&#x61;script:alert(1)\n"),
+            "x\n\n[ref]: jav&#x61;script:alert(1)\n"
+        );
+        assert_eq!(clean("![alt](data:image/svg+xml,<svg>)"), "alt");
+    }
+
+    #[test]
+    fn removes_svg_payloads() {
+        for body in [
+            "<svg onload=alert(1)>",
+            "<svg><script>alert(1)</script></svg>",
+            "<svg><a xlink:href=\"javascript:alert(1)\"><text>Click</text></a></svg>",
+            "<math><mtext><img src=x onerror=alert(1)></mtext></math>",
+        ] {
+            // Inline elements leave their text behind, which is harmless
+            let sanitized = sanitize(body).body.to_lowercase();
+            for payload in [
+                "<svg",
+                "<math",
+                "<script",
+                "onload",
+                "onerror",
+                "javascript",
+            ] {
+                assert!(!sanitized.contains(payload), "{body}");
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_allowed_embeds() {
+        let sanitized = sanitize(
+            "<iframe width=\"560\" height=\"315\" src=\"https://www.youtube.com/embed/abc\" sandbox=\"allow-top-navigation\" allowfullscreen></iframe>",
+        );
+        assert_eq!(
+            sanitized.body,
+            format!("<iframe width=\"560\" height=\"315\" src=\"https://www.youtube.com/embed/abc\" allowfullscreen sandbox=\"{IFRAME_SANDBOX}\"></iframe>")
+        );
+        assert!(sanitized.removed.is_empty());
+
+        // Sanitizing twice doesn't change anything
+        assert_eq!(clean(&sanitized.body), sanitized.body);
+
+        assert_eq!(
+            clean("<video controls src=\"https://cdn.example.com/a.mp4\" onplay=\"alert(1)\"></video>"),
+            "<video controls src=\"https://cdn.example.com/a.mp4\"></video>"
+        );
+    }
+
+    #[test]
+    fn removes_other_embeds() {
+        for body in [
+            "<iframe src=\"https://evil.com/\"></iframe>",
+            "<iframe src=\"http://www.youtube.com/embed/abc\"></iframe>",
+            "<iframe src=\"javascript:alert(1)\"></iframe>",
+            "<iframe srcdoc=\"<script>alert(1)</script>\"></iframe>",
+            "<iframe src=\"https://www.youtube.com.evil.com/\"></iframe>",
+        ] {
+            let sanitized = sanitize(body);
+            assert_eq!(sanitized.body, "", "{body}");
+            assert_eq!(
+                sanitized.removed,
+                vec!["iframe which isn't an allowed embed"]
+            );
+        }
+
+        assert_eq!(
+            clean("<object data=\"x.swf\"></object><embed src=\"x.swf\">"),
+            ""
+        );
+    }
+
+    #[test]
+    fn removes_nested_iframes() {
+        let body = clean(
+            "<iframe src=\"https://www.youtube.com/embed/abc\"><iframe src=\"https://evil.com\"></iframe><script>alert(1)</script></iframe>",
+        );
+        assert!(body.starts_with("<iframe src=\"https://www.youtube.com/embed/abc\""));
+        assert!(!body.contains("evil"));
+        assert!(!body.contains("alert"));
+        assert_eq!(body.matches("<iframe").count(), 1);
+    }
+
+    #[test]
+    fn escapes_broken_tags() {
+        let body = clean("<div>\n<img src=x onerror=alert(1)//\n</div>");
+        assert!(!body.contains("<img"));
+
+        let body = clean("<div>\n<!-- <img src=x onerror=alert(1)> -->\n</div>");
+        assert_eq!(body, "<div>\n\n</div>");
+    }
+
+    #[test]
+    fn strict_mode_rejects_removed_content() {
+        assert!(sanitize_body("Hello <b>world</b>", true).is_ok());
+
+        match sanitize_body("Hello <img src=x onerror=alert(1)>", true) {
+            Err(SanitizeError::Disallowed(removed)) => {
+                assert_eq!(removed, vec!["`onerror` attribute on <img>"])
+            }
+            _ => panic!("Expected the body to be rejected"),
+        }
+
+        assert_eq!(
+            sanitize_body("Hello <img src=x onerror=alert(1)>", false).unwrap(),
+            "Hello <img src=\"x\">"
+        );
+        assert!(matches!(
+            sanitize_body(&"a".repeat(MAX_BODY_LENGTH + 1), false),
+            Err(SanitizeError::TooLong)
+        ));
+    }
+}
@@ -0,0 +1,115 @@
+//! Images of hidden projects are uploaded under a private prefix, which the CDN doesn't serve.
+//! Users who can see the project are given short-lived signed URLs to them instead, which
+//! labrinth checks before serving the image from the file host.
+
+use crate::models::projects::Project;
+use crate::routes::ApiError;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+
+/// The prefix of the paths of private images on the file host
+pub const PRIVATE_PREFIX: &str = "private/";
+
+/// How long a signed URL works for at the least, in seconds
+pub const SIGNED_URL_EXPIRY_SECS: i64 = 15 * 60;
+
+/// The route signed URLs point to, followed by the path of the image
+const SIGNED_ROUTE: &str = "/v3/image/signed/";
+
+/// The path to upload an image to, under the private prefix if the image shouldn't be public
+pub fn image_path(path: String, private: bool) -> String {
+    if private {
+        format!("{PRIVATE_PREFIX}{path}")
+    } else {
+        path
+    }
+}
+
+/// The path of an image if it's private, given its URL on the CDN
+pub fn private_path<'a>(url: &'a str, cdn_url: &str) -> Option<&'a str> {
+    url.strip_prefix(cdn_url)
+        .and_then(|x| x.strip_prefix('/'))
+        .filter(|x| x.starts_with(PRIVATE_PREFIX))
+}
+
+/// The expiry of URLs signed at the time. URLs signed in the same window expire together, so
+/// they're the same in each response until then and the responses can still be cached.
+pub fn signed_url_expiry(now: DateTime<Utc>) -> i64 {
+    (now.timestamp() / SIGNED_URL_EXPIRY_SECS + 2) * SIGNED_URL_EXPIRY_SECS
+}
+
+fn mac(path: &str, expires: i64) -> Result<Hmac<Sha256>, ApiError> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(dotenvy::var("IMAGE_SIGNING_SECRET")?.as_bytes())
+        .expect("HMAC can take a key of any size");
+    mac.update(format!("{path}:{expires}").as_bytes());
+
+    Ok(mac)
+}
+
+/// Signs the URL of a private image, so that it can be fetched until it expires. The URLs of
+/// public images are returned as they are.
+pub fn sign_url(url: &str, expires: i64) -> Result<String, ApiError> {
+    let cdn_url = dotenvy::var("CDN_URL")?;
+    let Some(path) = private_path(url, &cdn_url) else {
+        return Ok(url.to_string());
+    };
+
+    let signature = hex::encode(mac(path, expires)?.finalize().into_bytes());
+
+    Ok(format!(
+        "{}{SIGNED_ROUTE}{path}?expires={expires}&signature={signature}",
+        dotenvy::var("SELF_ADDR")?
+    ))
+}
+
+/// Signs the URLs of a project's private images. Only done for users who can see the project.
+pub fn sign_project_images(project: &mut Project, expires: i64) -> Result<(), ApiError> {
+    let urls = project
+        .icon_url
+        .iter_mut()
+        .chain(project.icon_variants.values_mut())
+        .chain(
+            project
+                .gallery
+                .iter_mut()
+                .flat_map(|x| std::iter::once(&mut x.url).chain(x.variants.values_mut())),
+        );
+    for url in urls {
+        *url = sign_url(url, expires)?;
+    }
+
+    Ok(())
+}
+
+/// Gets the CDN URL of an image from its signed URL. Routes which identify images by their URL
+/// use this, as clients only ever see the signed URLs of private images.
+pub fn unsign_url(url: &str) -> Result<String, ApiError> {
+    let signed_prefix = format!("{}{SIGNED_ROUTE}", dotenvy::var("SELF_ADDR")?);
+
+    Ok(match url.strip_prefix(&signed_prefix) {
+        Some(path) => format!(
+            "{}/{}",
+            dotenvy::var("CDN_URL")?,
+            path.split('?').next().unwrap_or_default()
+        ),
+        None => url.to_string(),
+    })
+}
+
+/// Checks that the signature of a private image's path is valid and hasn't expired
+pub fn verify_signature(
+    path: &str,
+    expires: i64,
+    signature: &str,
+    now: DateTime<Utc>,
+) -> Result<bool, ApiError> {
+    if !path.starts_with(PRIVATE_PREFIX) || path.contains("..") || expires < now.timestamp() {
+        return Ok(false);
+    }
+    let Ok(signature) = hex::decode(signature) else {
+        return Ok(false);
+    };
+
+    Ok(mac(path, expires)?.verify(&signature).is_ok())
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Duration, Utc};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -5,6 +6,9 @@ use validator::{ValidationErrors, ValidationErrorsKind};
 
 use crate::models::pats::Scopes;
 
+// How far in advance a version can be scheduled for publishing, in days
+pub const MAX_PUBLISH_DELAY_DAYS: i64 = 30;
+
 lazy_static! {
     pub static ref RE_URL_SAFE: Regex = Regex::new(r#"^[a-zA-Z0-9!@$()`.+,_"-]*$"#).unwrap();
 }
@@ -133,6 +137,23 @@ pub fn validate_name(value: &str) -> Result<(), validator::ValidationError> {
     Ok(())
 }
 
+pub fn validate_publish_at(value: &DateTime<Utc>) -> Result<(), validator::ValidationError> {
+    let now = Utc::now();
+    if *value <= now {
+        return Err(validator::ValidationError::new(
+            "Publish time must be in the future",
+        ));
+    }
+
+    if *value > now + Duration::days(MAX_PUBLISH_DELAY_DAYS) {
+        return Err(validator::ValidationError::new(
+            "Publish time must be within 30 days",
+        ));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,8 +1,16 @@
+use crate::validate::metadata::read_archive_file;
+use crate::validate::registry::{open_archive, FileValidator, ValidationContext, ValidationIssue};
 use crate::validate::{SupportedGameVersions, ValidationError, ValidationResult};
 use chrono::{DateTime, NaiveDateTime, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
 use std::io::Cursor;
 use zip::ZipArchive;
 
+lazy_static! {
+    static ref FABRIC_MOD_ID_REGEX: Regex = Regex::new(r"^[a-z][a-z0-9-_]{1,63}$").unwrap();
+}
+
 pub struct FabricValidator;
 
 impl super::Validator for FabricValidator {
@@ -39,3 +47,134 @@ impl super::Validator for FabricValidator {
         Ok(ValidationResult::Pass)
     }
 }
+
+/// Checks that Fabric mods have a `fabric.mod.json` which the loader can read
+pub struct FabricModJsonValidator;
+
+impl FileValidator for FabricModJsonValidator {
+    fn loaders(&self) -> &[&str] {
+        &["fabric"]
+    }
+
+    fn project_types(&self) -> &[&str] {
+        &["mod"]
+    }
+
+    fn file_extensions(&self) -> &[&str] {
+        &["jar"]
+    }
+
+    fn validate(&self, data: &bytes::Bytes, context: &ValidationContext) -> Vec<ValidationIssue> {
+        let mut archive = match open_archive(data, context) {
+            Ok(archive) => archive,
+            Err(issue) => return vec![issue],
+        };
+
+        let Some(json) = read_archive_file(&mut archive, "fabric.mod.json") else {
+            return vec![context.warning(
+                "missing_fabric_mod_json",
+                "No fabric.mod.json present for Fabric file. Tip: Make sure fabric.mod.json is in the root directory of your mod!",
+            )];
+        };
+        let mod_json: serde_json::Value = match serde_json::from_str(&json) {
+            Ok(mod_json) => mod_json,
+            Err(err) => {
+                return vec![context.warning(
+                    "invalid_fabric_mod_json",
+                    format!("fabric.mod.json is not valid JSON: {err}"),
+                )]
+            }
+        };
+
+        let mut issues = Vec::new();
+        if let Some(schema_version) = mod_json.get("schemaVersion") {
+            if schema_version.as_u64() != Some(1) {
+                issues.push(context.warning(
+                    "unsupported_fabric_schema_version",
+                    "fabric.mod.json has a schemaVersion other than 1",
+                ));
+            }
+        }
+        match mod_json.get("id").and_then(|x| x.as_str()) {
+            Some(id) if FABRIC_MOD_ID_REGEX.is_match(id) => {}
+            Some(id) => issues.push(context.warning(
+                "invalid_mod_id",
+                format!("The mod id {id} in fabric.mod.json must be 2 to 64 lowercase letters, numbers, dashes or underscores, starting with a letter"),
+            )),
+            None => issues.push(context.warning(
+                "missing_mod_id",
+                "fabric.mod.json has no mod id",
+            )),
+        }
+        if mod_json.get("version").and_then(|x| x.as_str()).is_none() {
+            issues.push(context.warning("missing_mod_version", "fabric.mod.json has no version"));
+        }
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::registry::IssueSeverity;
+
+    fn context() -> ValidationContext {
+        ValidationContext {
+            file_name: "mod.jar".to_string(),
+            file_extension: "jar".to_string(),
+            loaders: vec!["fabric".to_string()],
+            project_types: vec!["mod".to_string()],
+        }
+    }
+
+    fn validate(data: &'static [u8]) -> Vec<(IssueSeverity, String)> {
+        FabricModJsonValidator
+            .validate(&bytes::Bytes::from_static(data), &context())
+            .into_iter()
+            .map(|x| (x.severity, x.code))
+            .collect()
+    }
+
+    #[test]
+    fn valid_fabric_mod_passes() {
+        assert!(validate(include_bytes!(
+            "../../tests/files/validation/fabric-mod.jar"
+        ))
+        .is_empty());
+    }
+
+    #[test]
+    fn missing_fabric_mod_json_is_a_warning() {
+        assert_eq!(
+            validate(include_bytes!(
+                "../../tests/files/validation/no-metadata.jar"
+            )),
+            vec![(
+                IssueSeverity::Warning,
+                "missing_fabric_mod_json".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn malformed_fabric_mod_json_is_a_warning() {
+        assert_eq!(
+            validate(include_bytes!(
+                "../../tests/files/validation/fabric-mod-malformed.jar"
+            )),
+            vec![
+                (IssueSeverity::Warning, "invalid_mod_id".to_string()),
+                (IssueSeverity::Warning, "missing_mod_version".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unreadable_jar_is_fatal() {
+        assert_eq!(
+            validate(b"not a jar"),
+            vec![(IssueSeverity::Fatal, "invalid_archive".to_string())]
+        );
+    }
+}
@@ -1,8 +1,16 @@
+use crate::validate::metadata::read_archive_file;
+use crate::validate::registry::{open_archive, FileValidator, ValidationContext, ValidationIssue};
 use crate::validate::{SupportedGameVersions, ValidationError, ValidationResult};
 use chrono::{DateTime, NaiveDateTime, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
 use std::io::Cursor;
 use zip::ZipArchive;
 
+lazy_static! {
+    static ref FORGE_MOD_ID_REGEX: Regex = Regex::new(r"^[a-z][a-z0-9_]{1,63}$").unwrap();
+}
+
 pub struct ForgeValidator;
 
 impl super::Validator for ForgeValidator {
@@ -84,3 +92,135 @@ impl super::Validator for LegacyForgeValidator {
         Ok(ValidationResult::Pass)
     }
 }
+
+/// Checks that Forge and NeoForge mods have a `mods.toml` which the loader can read. Mods for
+/// versions before 1.13 declare themselves in `mcmod.info` instead, which is left alone.
+pub struct ModsTomlValidator;
+
+impl FileValidator for ModsTomlValidator {
+    fn loaders(&self) -> &[&str] {
+        &["forge", "neoforge"]
+    }
+
+    fn project_types(&self) -> &[&str] {
+        &["mod"]
+    }
+
+    fn file_extensions(&self) -> &[&str] {
+        &["jar"]
+    }
+
+    fn validate(&self, data: &bytes::Bytes, context: &ValidationContext) -> Vec<ValidationIssue> {
+        let mut archive = match open_archive(data, context) {
+            Ok(archive) => archive,
+            Err(issue) => return vec![issue],
+        };
+
+        let Some(toml) = read_archive_file(&mut archive, "META-INF/neoforge.mods.toml")
+            .or_else(|| read_archive_file(&mut archive, "META-INF/mods.toml"))
+        else {
+            if archive.by_name("mcmod.info").is_ok() {
+                return Vec::new();
+            }
+
+            return vec![context.warning(
+                "missing_mods_toml",
+                "No META-INF/mods.toml present for Forge file.",
+            )];
+        };
+        let mods_toml: toml::Value = match toml::from_str(&toml) {
+            Ok(mods_toml) => mods_toml,
+            Err(err) => {
+                return vec![context.warning(
+                    "invalid_mods_toml",
+                    format!("mods.toml is not valid TOML: {err}"),
+                )]
+            }
+        };
+
+        let mut issues = Vec::new();
+        for key in ["modLoader", "loaderVersion"] {
+            if mods_toml.get(key).and_then(|x| x.as_str()).is_none() {
+                issues.push(
+                    context.warning("missing_mods_toml_field", format!("mods.toml has no {key}")),
+                );
+            }
+        }
+
+        let mods = mods_toml
+            .get("mods")
+            .and_then(|x| x.as_array())
+            .map(|x| &**x)
+            .unwrap_or_default();
+        if mods.is_empty() {
+            issues.push(context.warning("missing_mod_id", "mods.toml doesn't declare any mods"));
+        }
+        for declared in mods {
+            match declared.get("modId").and_then(|x| x.as_str()) {
+                Some(id) if FORGE_MOD_ID_REGEX.is_match(id) => {}
+                Some(id) => issues.push(context.warning(
+                    "invalid_mod_id",
+                    format!("The mod id {id} in mods.toml must be 2 to 64 lowercase letters, numbers or underscores, starting with a letter"),
+                )),
+                None => issues.push(context.warning(
+                    "missing_mod_id",
+                    "A mod in mods.toml has no modId",
+                )),
+            }
+        }
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::registry::IssueSeverity;
+
+    fn validate(data: &'static [u8]) -> Vec<(IssueSeverity, String)> {
+        let context = ValidationContext {
+            file_name: "mod.jar".to_string(),
+            file_extension: "jar".to_string(),
+            loaders: vec!["forge".to_string()],
+            project_types: vec!["mod".to_string()],
+        };
+
+        ModsTomlValidator
+            .validate(&bytes::Bytes::from_static(data), &context)
+            .into_iter()
+            .map(|x| (x.severity, x.code))
+            .collect()
+    }
+
+    #[test]
+    fn valid_forge_mod_passes() {
+        assert!(validate(include_bytes!("../../tests/files/validation/forge-mod.jar")).is_empty());
+    }
+
+    #[test]
+    fn missing_mods_toml_is_a_warning() {
+        assert_eq!(
+            validate(include_bytes!(
+                "../../tests/files/validation/no-metadata.jar"
+            )),
+            vec![(IssueSeverity::Warning, "missing_mods_toml".to_string())]
+        );
+    }
+
+    #[test]
+    fn malformed_mods_toml_is_a_warning() {
+        assert_eq!(
+            validate(include_bytes!(
+                "../../tests/files/validation/forge-mod-malformed.jar"
+            )),
+            vec![
+                (
+                    IssueSeverity::Warning,
+                    "missing_mods_toml_field".to_string()
+                ),
+                (IssueSeverity::Warning, "invalid_mod_id".to_string()),
+            ]
+        );
+    }
+}
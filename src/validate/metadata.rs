@@ -0,0 +1,206 @@
+use crate::models::projects::DependencyType;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+/// Mod ids which loaders and the game provide themselves, so are never projects
+const BUILTIN_MOD_IDS: &[&str] = &[
+    "minecraft",
+    "java",
+    "fabricloader",
+    "quilt_loader",
+    "forge",
+    "neoforge",
+    "javafml",
+];
+
+/// What a mod declares about itself and its dependencies in its loader metadata
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ModMetadata {
+    /// The ids the mod is known by
+    pub mod_ids: Vec<String>,
+    pub dependencies: Vec<DeclaredDependency>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeclaredDependency {
+    pub mod_id: String,
+    /// The versions of the dependency the mod works with, in the loader's own syntax
+    pub version_range: Option<String>,
+    pub dependency_type: DependencyType,
+}
+
+#[derive(Deserialize)]
+struct FabricModJson {
+    id: String,
+    #[serde(default)]
+    provides: Vec<String>,
+    #[serde(default)]
+    depends: HashMap<String, FabricVersionRange>,
+    #[serde(default)]
+    recommends: HashMap<String, FabricVersionRange>,
+    #[serde(default)]
+    suggests: HashMap<String, FabricVersionRange>,
+    #[serde(default)]
+    breaks: HashMap<String, FabricVersionRange>,
+    #[serde(default)]
+    conflicts: HashMap<String, FabricVersionRange>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FabricVersionRange {
+    Single(String),
+    // Any of the ranges can match
+    Any(Vec<String>),
+}
+
+impl FabricVersionRange {
+    fn into_range(self) -> Option<String> {
+        let range = match self {
+            FabricVersionRange::Single(range) => range,
+            FabricVersionRange::Any(ranges) => ranges.join(" || "),
+        };
+
+        (!range.is_empty() && range != "*").then_some(range)
+    }
+}
+
+#[derive(Deserialize)]
+struct ModsToml {
+    #[serde(default)]
+    mods: Vec<ModsTomlMod>,
+    #[serde(default)]
+    dependencies: HashMap<String, Vec<ModsTomlDependency>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsTomlMod {
+    mod_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsTomlDependency {
+    mod_id: String,
+    // Forge marks dependencies as mandatory, while NeoForge gives them a type
+    mandatory: Option<bool>,
+    #[serde(rename = "type")]
+    dependency_type: Option<String>,
+    version_range: Option<String>,
+}
+
+/// Reads the Fabric or Forge/NeoForge metadata of a mod jar. Returns `None` if the file isn't a
+/// jar, has no metadata, or its metadata can't be parsed, as the metadata is only used to help
+/// fill in the version.
+pub fn read_mod_metadata(data: bytes::Bytes) -> Option<ModMetadata> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).ok()?;
+
+    let metadata = if let Some(json) = read_archive_file(&mut archive, "fabric.mod.json") {
+        read_fabric_metadata(&json)
+    } else if let Some(toml) = read_archive_file(&mut archive, "META-INF/neoforge.mods.toml")
+        .or_else(|| read_archive_file(&mut archive, "META-INF/mods.toml"))
+    {
+        read_forge_metadata(&toml)
+    } else {
+        None
+    }?;
+
+    let mut dependencies = metadata
+        .dependencies
+        .into_iter()
+        .filter(|x| !BUILTIN_MOD_IDS.contains(&&*x.mod_id) && !metadata.mod_ids.contains(&x.mod_id))
+        .collect::<Vec<_>>();
+    // The dependencies are sorted by id, so a mod declared more than once keeps its first entry
+    dependencies.dedup_by(|a, b| a.mod_id == b.mod_id);
+
+    Some(ModMetadata {
+        mod_ids: metadata.mod_ids,
+        dependencies,
+    })
+}
+
+pub(super) fn read_archive_file(
+    archive: &mut ZipArchive<Cursor<bytes::Bytes>>,
+    name: &str,
+) -> Option<String> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).ok()?;
+    Some(contents)
+}
+
+fn read_fabric_metadata(json: &str) -> Option<ModMetadata> {
+    let mod_json: FabricModJson = serde_json::from_str(json).ok()?;
+
+    let mut dependencies = Vec::new();
+    for (declared, dependency_type) in [
+        (mod_json.depends, DependencyType::Required),
+        (mod_json.recommends, DependencyType::Optional),
+        (mod_json.suggests, DependencyType::Optional),
+        (mod_json.breaks, DependencyType::Incompatible),
+        (mod_json.conflicts, DependencyType::Incompatible),
+    ] {
+        dependencies.extend(
+            declared
+                .into_iter()
+                .map(|(mod_id, range)| DeclaredDependency {
+                    mod_id,
+                    version_range: range.into_range(),
+                    dependency_type,
+                }),
+        );
+    }
+    // The maps have no order, so keep the dependencies stable between reads
+    dependencies.sort_by(|a, b| a.mod_id.cmp(&b.mod_id));
+
+    let mut mod_ids = vec![mod_json.id];
+    mod_ids.extend(mod_json.provides);
+
+    Some(ModMetadata {
+        mod_ids,
+        dependencies,
+    })
+}
+
+fn read_forge_metadata(toml: &str) -> Option<ModMetadata> {
+    let mods_toml: ModsToml = toml::from_str(toml).ok()?;
+
+    let mod_ids = mods_toml
+        .mods
+        .into_iter()
+        .map(|x| x.mod_id)
+        .collect::<Vec<_>>();
+
+    let mut dependencies = mods_toml
+        .dependencies
+        .into_iter()
+        .filter(|(mod_id, _)| mod_ids.contains(mod_id))
+        .flat_map(|(_, dependencies)| dependencies)
+        .filter_map(|x| {
+            let dependency_type = match (x.dependency_type.as_deref(), x.mandatory) {
+                (Some("required"), _) => DependencyType::Required,
+                (Some("optional"), _) => DependencyType::Optional,
+                (Some("incompatible"), _) => DependencyType::Incompatible,
+                // Discouraged mods still work, so aren't worth listing
+                (Some(_), _) => return None,
+                (None, Some(false)) => DependencyType::Optional,
+                (None, _) => DependencyType::Required,
+            };
+
+            Some(DeclaredDependency {
+                mod_id: x.mod_id,
+                version_range: x.version_range.filter(|x| !x.is_empty()),
+                dependency_type,
+            })
+        })
+        .collect::<Vec<_>>();
+    dependencies.sort_by(|a, b| a.mod_id.cmp(&b.mod_id));
+
+    Some(ModMetadata {
+        mod_ids,
+        dependencies,
+    })
+}
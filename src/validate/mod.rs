@@ -5,15 +5,17 @@ use crate::database::redis::RedisPool;
 use crate::models::pack::PackFormat;
 use crate::models::projects::{FileType, Loader};
 use crate::validate::datapack::DataPackValidator;
-use crate::validate::fabric::FabricValidator;
-use crate::validate::forge::{ForgeValidator, LegacyForgeValidator};
+use crate::validate::fabric::{FabricModJsonValidator, FabricValidator};
+use crate::validate::forge::{ForgeValidator, LegacyForgeValidator, ModsTomlValidator};
 use crate::validate::liteloader::LiteLoaderValidator;
 use crate::validate::modpack::ModpackValidator;
 use crate::validate::plugin::*;
 use crate::validate::quilt::QuiltValidator;
-use crate::validate::resourcepack::{PackValidator, TexturePackValidator};
+use crate::validate::registry::{ValidatorRegistry, ZipIntegrityValidator};
+use crate::validate::resourcepack::{PackMcmetaValidator, PackValidator, TexturePackValidator};
 use crate::validate::shader::{CanvasShaderValidator, CoreShaderValidator, ShaderValidator};
 use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
 use std::io::Cursor;
 use thiserror::Error;
 use zip::ZipArchive;
@@ -22,9 +24,11 @@ mod datapack;
 mod fabric;
 mod forge;
 mod liteloader;
+pub mod metadata;
 mod modpack;
 pub mod plugin;
 mod quilt;
+pub mod registry;
 mod resourcepack;
 mod shader;
 
@@ -107,6 +111,18 @@ static VALIDATORS: &[&dyn Validator] = &[
     &DataPackValidator,
 ];
 
+lazy_static! {
+    /// The validators uploaded files are checked with, which are registered at startup
+    pub static ref FILE_VALIDATORS: ValidatorRegistry = ValidatorRegistry::new(
+        &[
+            &FabricModJsonValidator,
+            &ModsTomlValidator,
+            &PackMcmetaValidator,
+        ],
+        &ZipIntegrityValidator,
+    );
+}
+
 /// The return value is whether this file should be marked as primary or not, based on the analysis of the file
 #[allow(clippy::too_many_arguments)]
 pub async fn validate_file(
@@ -148,6 +164,55 @@ pub async fn validate_file(
     }
 }
 
+/// Checks that a file already uploaded to a version can be made its primary file. Resource
+/// packs, source and javadoc jars, and files the version's loaders can't load are rejected.
+pub fn validate_primary_file(
+    file_name: &str,
+    file_type: Option<FileType>,
+    loaders: &[String],
+) -> Result<(), ValidationError> {
+    if matches!(
+        file_type,
+        Some(FileType::RequiredResourcePack | FileType::OptionalResourcePack)
+    ) {
+        return Err(ValidationError::InvalidInput(
+            "A resource pack can't be the primary file".into(),
+        ));
+    }
+
+    if file_name.ends_with("-sources.jar") || file_name.ends_with("-javadoc.jar") {
+        return Err(ValidationError::InvalidInput(
+            format!("{file_name} is a sources or javadoc jar and can't be the primary file").into(),
+        ));
+    }
+
+    let file_extension = file_name
+        .rsplit_once('.')
+        .map(|x| x.1.to_lowercase())
+        .unwrap_or_default();
+
+    let mut visited = false;
+    for validator in VALIDATORS {
+        if loaders
+            .iter()
+            .any(|x| validator.get_supported_loaders().contains(&&**x))
+        {
+            if validator.get_file_extensions().contains(&&*file_extension) {
+                return Ok(());
+            }
+            visited = true;
+        }
+    }
+
+    if visited {
+        Err(ValidationError::InvalidInput(
+            format!("File extension {file_extension} is invalid for the primary file").into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 async fn validate_minecraft_file(
     data: bytes::Bytes,
     file_extension: String,
@@ -0,0 +1,312 @@
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Cursor;
+use zip::ZipArchive;
+
+/// The extensions of files which are zip archives, so can be checked by the file validators
+const ARCHIVE_EXTENSIONS: &[&str] = &["jar", "zip", "mrpack", "litemod"];
+/// The largest file any validator accepts unless it declares its own limit, which is the most
+/// that can be uploaded
+pub const DEFAULT_MAX_FILE_SIZE: usize = 500 * (1 << 20);
+/// The most an archive can hold once extracted, which keeps zip bombs from being read
+const MAX_EXTRACTED_SIZE: u64 = 4 * (1 << 30);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    /// The file is rejected
+    Fatal,
+    /// The file is still uploaded, and the uploader is told about the problem
+    Warning,
+}
+
+/// A problem a file validator found with an uploaded file
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub severity: IssueSeverity,
+    /// What kind of problem it is, such as `missing_fabric_mod_json`
+    pub code: String,
+    pub message: String,
+    /// The uploaded file the problem is with
+    pub file_name: String,
+}
+
+/// What's known about an uploaded file besides its contents
+#[derive(Clone, Debug)]
+pub struct ValidationContext {
+    pub file_name: String,
+    pub file_extension: String,
+    pub loaders: Vec<String>,
+    /// The project types the loaders support
+    pub project_types: Vec<String>,
+}
+
+impl ValidationContext {
+    pub fn fatal(&self, code: &str, message: impl Into<String>) -> ValidationIssue {
+        self.issue(IssueSeverity::Fatal, code, message)
+    }
+
+    pub fn warning(&self, code: &str, message: impl Into<String>) -> ValidationIssue {
+        self.issue(IssueSeverity::Warning, code, message)
+    }
+
+    fn issue(
+        &self,
+        severity: IssueSeverity,
+        code: &str,
+        message: impl Into<String>,
+    ) -> ValidationIssue {
+        ValidationIssue {
+            severity,
+            code: code.to_string(),
+            message: message.into(),
+            file_name: self.file_name.clone(),
+        }
+    }
+}
+
+/// Checks the files uploaded for one or more loaders and project types
+pub trait FileValidator: Sync + Send {
+    fn loaders(&self) -> &[&str];
+    fn project_types(&self) -> &[&str];
+    fn file_extensions(&self) -> &[&str];
+    /// The largest file the validator accepts, in bytes
+    fn max_size(&self) -> usize {
+        DEFAULT_MAX_FILE_SIZE
+    }
+    fn validate(&self, data: &Bytes, context: &ValidationContext) -> Vec<ValidationIssue>;
+}
+
+/// The file validators, keyed by the loaders and project types they check. Every archive is
+/// checked by the generic archive validator first, so the files of loaders without their own
+/// validators are still checked.
+pub struct ValidatorRegistry {
+    validators: Vec<&'static dyn FileValidator>,
+    // The indices of the validators for each loader and project type
+    keys: HashMap<(String, String), Vec<usize>>,
+    archive_validator: &'static dyn FileValidator,
+}
+
+impl ValidatorRegistry {
+    pub fn new(
+        validators: &[&'static dyn FileValidator],
+        archive_validator: &'static dyn FileValidator,
+    ) -> ValidatorRegistry {
+        let mut keys: HashMap<(String, String), Vec<usize>> = HashMap::new();
+        for (index, validator) in validators.iter().enumerate() {
+            for loader in validator.loaders() {
+                for project_type in validator.project_types() {
+                    keys.entry((loader.to_string(), project_type.to_string()))
+                        .or_default()
+                        .push(index);
+                }
+            }
+        }
+
+        ValidatorRegistry {
+            validators: validators.to_vec(),
+            keys,
+            archive_validator,
+        }
+    }
+
+    /// The validators which check the file, besides the archive validator
+    fn validators_for(&self, context: &ValidationContext) -> Vec<&'static dyn FileValidator> {
+        let mut indices = Vec::new();
+        for loader in &context.loaders {
+            for project_type in &context.project_types {
+                if let Some(found) = self.keys.get(&(loader.clone(), project_type.clone())) {
+                    indices.extend_from_slice(found);
+                }
+            }
+        }
+        indices.sort_unstable();
+        indices.dedup();
+
+        indices
+            .into_iter()
+            .map(|x| self.validators[x])
+            .filter(|x| x.file_extensions().contains(&&*context.file_extension))
+            .collect()
+    }
+
+    /// Runs the validators for the file, stopping at the first fatal issue
+    pub fn validate(&self, data: &Bytes, context: &ValidationContext) -> Vec<ValidationIssue> {
+        let validators = std::iter::once(self.archive_validator)
+            .filter(|x| x.file_extensions().contains(&&*context.file_extension))
+            .chain(self.validators_for(context));
+
+        let mut issues = Vec::new();
+        for validator in validators {
+            if data.len() > validator.max_size() {
+                issues.push(context.fatal(
+                    "file_too_large",
+                    format!(
+                        "{} is larger than the maximum of {}MiB",
+                        context.file_name,
+                        validator.max_size() >> 20
+                    ),
+                ));
+                break;
+            }
+
+            issues.extend(validator.validate(data, context));
+            if issues.iter().any(|x| x.severity == IssueSeverity::Fatal) {
+                break;
+            }
+        }
+
+        issues
+    }
+}
+
+/// Opens the file as a zip archive, which is a fatal issue if it isn't one
+pub(super) fn open_archive(
+    data: &Bytes,
+    context: &ValidationContext,
+) -> Result<ZipArchive<Cursor<Bytes>>, ValidationIssue> {
+    ZipArchive::new(Cursor::new(data.clone())).map_err(|err| {
+        context.fatal(
+            "invalid_archive",
+            format!("{} is not a valid archive: {err}", context.file_name),
+        )
+    })
+}
+
+/// Checks that any archive can be opened and that all of its entries can be read
+pub struct ZipIntegrityValidator;
+
+impl FileValidator for ZipIntegrityValidator {
+    fn loaders(&self) -> &[&str] {
+        &[]
+    }
+
+    fn project_types(&self) -> &[&str] {
+        &[]
+    }
+
+    fn file_extensions(&self) -> &[&str] {
+        ARCHIVE_EXTENSIONS
+    }
+
+    fn validate(&self, data: &Bytes, context: &ValidationContext) -> Vec<ValidationIssue> {
+        let mut archive = match open_archive(data, context) {
+            Ok(archive) => archive,
+            Err(issue) => return vec![issue],
+        };
+
+        let mut extracted_size = 0u64;
+        for index in 0..archive.len() {
+            let mut entry = match archive.by_index(index) {
+                Ok(entry) => entry,
+                Err(err) => {
+                    return vec![context.fatal(
+                        "corrupt_archive",
+                        format!("{} has an unreadable entry: {err}", context.file_name),
+                    )]
+                }
+            };
+
+            extracted_size += entry.size();
+            if extracted_size > MAX_EXTRACTED_SIZE {
+                return vec![context.fatal(
+                    "archive_too_large",
+                    format!("{} is too large once extracted", context.file_name),
+                )];
+            }
+
+            // Reading the whole entry checks it against its checksum
+            let name = entry.name().to_string();
+            if let Err(err) = std::io::copy(&mut entry, &mut std::io::sink()) {
+                return vec![context.fatal(
+                    "corrupt_archive",
+                    format!("{} is corrupt in {}: {err}", name, context.file_name),
+                )];
+            }
+        }
+
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::FILE_VALIDATORS;
+
+    fn context(loader: &str, project_type: &str) -> ValidationContext {
+        ValidationContext {
+            file_name: "mod.jar".to_string(),
+            file_extension: "jar".to_string(),
+            loaders: vec![loader.to_string()],
+            project_types: vec![project_type.to_string()],
+        }
+    }
+
+    fn codes(issues: Vec<ValidationIssue>) -> Vec<(IssueSeverity, String)> {
+        issues.into_iter().map(|x| (x.severity, x.code)).collect()
+    }
+
+    #[test]
+    fn intact_archive_passes() {
+        let data = Bytes::from_static(include_bytes!(
+            "../../tests/files/validation/fabric-mod.jar"
+        ));
+        assert!(ZipIntegrityValidator
+            .validate(&data, &context("fabric", "mod"))
+            .is_empty());
+    }
+
+    #[test]
+    fn corrupt_archive_is_fatal() {
+        let data = Bytes::from_static(include_bytes!("../../tests/files/validation/corrupt.jar"));
+        assert_eq!(
+            codes(ZipIntegrityValidator.validate(&data, &context("fabric", "mod"))),
+            vec![(IssueSeverity::Fatal, "corrupt_archive".to_string())]
+        );
+
+        let data = Bytes::from_static(b"not a jar");
+        assert_eq!(
+            codes(ZipIntegrityValidator.validate(&data, &context("fabric", "mod"))),
+            vec![(IssueSeverity::Fatal, "invalid_archive".to_string())]
+        );
+    }
+
+    #[test]
+    fn registry_runs_the_validators_of_the_loader() {
+        let data = Bytes::from_static(include_bytes!(
+            "../../tests/files/validation/no-metadata.jar"
+        ));
+        assert_eq!(
+            codes(FILE_VALIDATORS.validate(&data, &context("fabric", "mod"))),
+            vec![(
+                IssueSeverity::Warning,
+                "missing_fabric_mod_json".to_string()
+            )]
+        );
+        assert_eq!(
+            codes(FILE_VALIDATORS.validate(&data, &context("neoforge", "mod"))),
+            vec![(IssueSeverity::Warning, "missing_mods_toml".to_string())]
+        );
+
+        // Unknown loaders only have their archives checked
+        assert!(FILE_VALIDATORS
+            .validate(&data, &context("unknown", "mod"))
+            .is_empty());
+        let data = Bytes::from_static(include_bytes!("../../tests/files/validation/corrupt.jar"));
+        assert_eq!(
+            codes(FILE_VALIDATORS.validate(&data, &context("unknown", "mod"))),
+            vec![(IssueSeverity::Fatal, "corrupt_archive".to_string())]
+        );
+    }
+
+    #[test]
+    fn registry_stops_at_fatal_issues() {
+        let data = Bytes::from_static(include_bytes!("../../tests/files/validation/corrupt.jar"));
+        assert_eq!(
+            codes(FILE_VALIDATORS.validate(&data, &context("fabric", "mod"))),
+            vec![(IssueSeverity::Fatal, "corrupt_archive".to_string())]
+        );
+    }
+}
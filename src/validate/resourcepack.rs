@@ -1,3 +1,5 @@
+use crate::validate::metadata::read_archive_file;
+use crate::validate::registry::{open_archive, FileValidator, ValidationContext, ValidationIssue};
 use crate::validate::{SupportedGameVersions, ValidationError, ValidationResult};
 use chrono::{DateTime, NaiveDateTime, Utc};
 use std::io::Cursor;
@@ -82,3 +84,112 @@ impl super::Validator for TexturePackValidator {
         Ok(ValidationResult::Pass)
     }
 }
+
+/// Checks that resource packs have a `pack.mcmeta` which the game can read. Texture packs from
+/// before 1.6 have a `pack.txt` instead, which is left alone.
+pub struct PackMcmetaValidator;
+
+impl FileValidator for PackMcmetaValidator {
+    fn loaders(&self) -> &[&str] {
+        &["minecraft"]
+    }
+
+    fn project_types(&self) -> &[&str] {
+        &["resourcepack"]
+    }
+
+    fn file_extensions(&self) -> &[&str] {
+        &["zip"]
+    }
+
+    fn validate(&self, data: &bytes::Bytes, context: &ValidationContext) -> Vec<ValidationIssue> {
+        let mut archive = match open_archive(data, context) {
+            Ok(archive) => archive,
+            Err(issue) => return vec![issue],
+        };
+
+        let Some(json) = read_archive_file(&mut archive, "pack.mcmeta") else {
+            if archive.by_name("pack.txt").is_ok() {
+                return Vec::new();
+            }
+
+            return vec![context.warning(
+                "missing_pack_mcmeta",
+                "No pack.mcmeta present for pack file. Tip: Make sure pack.mcmeta is in the root directory of your pack!",
+            )];
+        };
+        let mcmeta: serde_json::Value = match serde_json::from_str(&json) {
+            Ok(mcmeta) => mcmeta,
+            Err(err) => {
+                return vec![context.warning(
+                    "invalid_pack_mcmeta",
+                    format!("pack.mcmeta is not valid JSON: {err}"),
+                )]
+            }
+        };
+
+        let Some(pack) = mcmeta.get("pack") else {
+            return vec![context.warning("invalid_pack_mcmeta", "pack.mcmeta has no pack section")];
+        };
+
+        let mut issues = Vec::new();
+        if pack.get("pack_format").and_then(|x| x.as_u64()).is_none() {
+            issues.push(context.warning("missing_pack_format", "pack.mcmeta has no pack_format"));
+        }
+        // The description can be plain text or a text component
+        if pack.get("description").map_or(true, |x| x.is_null()) {
+            issues.push(
+                context.warning("missing_pack_description", "pack.mcmeta has no description"),
+            );
+        }
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::registry::IssueSeverity;
+
+    fn validate(data: &'static [u8]) -> Vec<(IssueSeverity, String)> {
+        let context = ValidationContext {
+            file_name: "pack.zip".to_string(),
+            file_extension: "zip".to_string(),
+            loaders: vec!["minecraft".to_string()],
+            project_types: vec!["resourcepack".to_string()],
+        };
+
+        PackMcmetaValidator
+            .validate(&bytes::Bytes::from_static(data), &context)
+            .into_iter()
+            .map(|x| (x.severity, x.code))
+            .collect()
+    }
+
+    #[test]
+    fn valid_resource_pack_passes() {
+        assert!(validate(include_bytes!(
+            "../../tests/files/validation/resource-pack.zip"
+        ))
+        .is_empty());
+    }
+
+    #[test]
+    fn missing_pack_mcmeta_is_a_warning() {
+        assert_eq!(
+            validate(include_bytes!("../../tests/files/simple-zip.zip")),
+            vec![(IssueSeverity::Warning, "missing_pack_mcmeta".to_string())]
+        );
+    }
+
+    #[test]
+    fn malformed_pack_mcmeta_is_a_warning() {
+        assert_eq!(
+            validate(include_bytes!(
+                "../../tests/files/validation/resource-pack-malformed.zip"
+            )),
+            vec![(IssueSeverity::Warning, "missing_pack_format".to_string())]
+        );
+    }
+}
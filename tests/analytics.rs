@@ -1,4 +1,7 @@
-use chrono::{DateTime, Duration, Utc};
+use actix_http::StatusCode;
+use actix_web::test;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use common::api_common::{Api, ApiBuildable, ApiVersion};
 use common::permissions::PermissionsTest;
 use common::permissions::PermissionsTestContext;
 use common::{
@@ -7,10 +10,14 @@ use common::{
     environment::{with_test_environment, TestEnvironment},
 };
 use itertools::Itertools;
+use labrinth::database::models::daily_download_item::DailyDownload;
+use labrinth::database::models::{ProjectId, VersionId};
 use labrinth::models::ids::base62_impl::parse_base62;
 use labrinth::models::teams::ProjectPermissions;
 use labrinth::queue::payouts;
+use labrinth::routes::v3::statistics::DownloadStatistic;
 use rust_decimal::{prelude::ToPrimitive, Decimal};
+use serde_json::json;
 
 mod common;
 
@@ -221,3 +228,219 @@ pub async fn permissions_analytics_revenue() {
     })
     .await;
 }
+
+#[actix_rt::test]
+pub async fn download_statistics() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = test_env.dummy.project_alpha.project_id.clone();
+        let alpha_version_id = test_env.dummy.project_alpha.version_id.clone();
+
+        // Simulate a few downloads, two days apart
+        let today = Utc::now().date_naive();
+        let download = |date: NaiveDate, downloads: i32| DailyDownload {
+            project_id: ProjectId(parse_base62(&alpha_project_id).unwrap() as i64),
+            version_id: VersionId(parse_base62(&alpha_version_id).unwrap() as i64),
+            date,
+            downloads,
+            pack_downloads: 0,
+        };
+        let mut transaction = test_env.db.pool.begin().await.unwrap();
+        DailyDownload::increment_many(
+            &[
+                download(today, 2),
+                download(today, 1),
+                download(today - Duration::days(2), 2),
+            ],
+            &mut transaction,
+        )
+        .await
+        .unwrap();
+        transaction.commit().await.unwrap();
+
+        let query = format!("start={}&end={}", today - Duration::days(3), today);
+        let series = api
+            .get_project_download_statistics_deserialized(&alpha_project_id, &query, USER_USER_PAT)
+            .await;
+        assert_eq!(
+            series.iter().map(|x| x.date).collect_vec(),
+            (0..=3)
+                .rev()
+                .map(|x| today - Duration::days(x))
+                .collect_vec()
+        );
+        assert_eq!(
+            series.iter().map(|x| x.downloads).collect_vec(),
+            vec![0, 2, 0, 3]
+        );
+
+        let resp = api
+            .get_version_download_statistics(&alpha_version_id, &query, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let series: Vec<DownloadStatistic> = test::read_body_json(resp).await;
+        assert_eq!(series.iter().map(|x| x.downloads).sum::<i64>(), 5);
+
+        // At most 2 years can be fetched at once
+        let query = format!("start={}&end={}", today - Duration::days(731), today);
+        let resp = api
+            .get_project_download_statistics(&alpha_project_id, &query, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+pub async fn permissions_download_statistics() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_project_id = test_env.dummy.project_alpha.project_id.clone();
+        let alpha_version_id = test_env.dummy.project_alpha.version_id.clone();
+        let alpha_team_id = test_env.dummy.project_alpha.team_id.clone();
+
+        let api = &test_env.api;
+
+        let view_analytics = ProjectPermissions::VIEW_ANALYTICS;
+
+        let req_gen = |ctx: PermissionsTestContext| async move {
+            let project_id = ctx.project_id.unwrap();
+            api.get_project_download_statistics(&project_id, "", ctx.test_pat.as_deref())
+                .await
+        };
+        PermissionsTest::new(&test_env)
+            .simple_project_permissions_test(view_analytics, req_gen)
+            .await
+            .unwrap();
+
+        let req_gen = |ctx: PermissionsTestContext| {
+            let alpha_version_id = alpha_version_id.clone();
+            async move {
+                api.get_version_download_statistics(&alpha_version_id, "", ctx.test_pat.as_deref())
+                    .await
+            }
+        };
+        PermissionsTest::new(&test_env)
+            .with_existing_project(&alpha_project_id, &alpha_team_id)
+            .with_user(FRIEND_USER_ID, FRIEND_USER_PAT, true)
+            .simple_project_permissions_test(view_analytics, req_gen)
+            .await
+            .unwrap();
+    })
+    .await;
+}
+
+// The request the CDN sends for each download, with the context in the URL when one is given
+fn count_download(
+    project_id: &str,
+    version_id: &str,
+    download_context: Option<&str>,
+    ip: &str,
+) -> actix_http::Request {
+    let mut url = "https://cdn.modrinth.com/data/file.jar".to_string();
+    if let Some(download_context) = download_context {
+        url.push_str(&format!("?download_context={download_context}"));
+    }
+
+    test::TestRequest::patch()
+        .uri("/_internal/admin/_count-download")
+        .append_header((
+            "Modrinth-Admin",
+            dotenvy::var("LABRINTH_ADMIN_KEY").unwrap(),
+        ))
+        .set_json(json!({
+            "url": url,
+            "project_id": project_id,
+            "version_name": version_id,
+            "ip": ip,
+            "headers": {},
+        }))
+        .to_request()
+}
+
+#[actix_rt::test]
+pub async fn pack_driven_downloads() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        // The analytics queue is flushed by hand, so the app is built from a config kept here
+        let config = common::setup(&test_env.db, test_env.mailer.clone()).await;
+        let analytics_queue = config.analytics_queue.clone();
+        let clickhouse = config.clickhouse.clone();
+        let api = ApiV3::build(config).await;
+
+        let alpha_project_id = test_env.dummy.project_alpha.project_id.clone();
+        let alpha_version_id = test_env.dummy.project_alpha.version_id.clone();
+        let beta_project_id = test_env.dummy.project_beta.project_id.clone();
+        let beta_version_id = test_env.dummy.project_beta.version_id.clone();
+
+        // Beta's version is the pack, which depends on alpha
+        let resp = api
+            .edit_version(
+                &beta_version_id,
+                json!({
+                    "dependencies": [{
+                        "project_id": alpha_project_id,
+                        "dependency_type": "required",
+                    }]
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Only versions with dependencies are given a context
+        let files = api
+            .get_version_files_deserialized(&alpha_version_id, USER_USER_PAT)
+            .await;
+        assert!(files.download_context.is_none());
+
+        let files = api
+            .get_version_files_deserialized(&beta_version_id, USER_USER_PAT)
+            .await;
+        assert_eq!(files.files.len(), 1);
+        let token = files.download_context.unwrap();
+
+        for req in [
+            // The pack itself is always an organic download
+            count_download(&beta_project_id, &beta_version_id, Some(&token), "10.0.0.1"),
+            // Its dependencies are pack-driven when fetched with the context
+            count_download(
+                &alpha_project_id,
+                &alpha_version_id,
+                Some(&token),
+                "10.0.0.1",
+            ),
+            // and organic without it, or with an unknown one
+            count_download(&alpha_project_id, &alpha_version_id, None, "10.0.0.2"),
+            count_download(
+                &alpha_project_id,
+                &alpha_version_id,
+                Some("unknown"),
+                "10.0.0.3",
+            ),
+        ] {
+            let resp = api.call(req).await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+
+        analytics_queue
+            .index(clickhouse, &test_env.db.redis_pool, &test_env.db.pool)
+            .await
+            .unwrap();
+
+        let today = Utc::now().date_naive();
+        let query = format!("start={today}&end={today}");
+        let series = api
+            .get_project_download_statistics_deserialized(&alpha_project_id, &query, USER_USER_PAT)
+            .await;
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].downloads, 3);
+        assert_eq!(series[0].organic_downloads, 2);
+        assert_eq!(series[0].pack_downloads, 1);
+
+        let series = api
+            .get_project_download_statistics_deserialized(&beta_project_id, &query, USER_USER_PAT)
+            .await;
+        assert_eq!(series[0].downloads, 1);
+        assert_eq!(series[0].pack_downloads, 0);
+    })
+    .await;
+}
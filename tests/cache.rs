@@ -0,0 +1,222 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use actix_http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use common::api_common::{Api, ApiUser, AppendsOptionalPat};
+use common::api_v3::ApiV3;
+use common::database::{USER_USER_ID, USER_USER_PAT};
+use common::environment::{with_test_environment, TestEnvironment};
+use labrinth::database::cache_warming::WarmedCaches;
+use labrinth::database::models::project_item::PROJECTS_NAMESPACE;
+use labrinth::database::models::version_item::VERSIONS_NAMESPACE;
+use labrinth::database::models::DatabaseError;
+use labrinth::database::redis::{CachedEntry, RedisPool};
+use labrinth::models::ids::base62_impl::parse_base62;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+mod common;
+
+const ITEMS_NAMESPACE: &str = "test_items";
+const ITEMS_SLUGS_NAMESPACE: &str = "test_items_slugs";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+struct Item {
+    id: i64,
+    slug: String,
+}
+
+impl CachedEntry for Item {
+    fn cache_id(&self) -> i64 {
+        self.id
+    }
+
+    fn cache_slug(&self) -> Option<String> {
+        Some(self.slug.clone())
+    }
+}
+
+// Gets the items as if they were in the database, counting how often it's queried
+async fn get_items(
+    keys: &[&str],
+    database: &[Item],
+    queries: &AtomicUsize,
+    redis: &RedisPool,
+) -> Result<Vec<Item>, DatabaseError> {
+    let keys = keys.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    redis
+        .get_cached_keys(
+            ITEMS_NAMESPACE,
+            Some(ITEMS_SLUGS_NAMESPACE),
+            &keys,
+            |x| x.parse().ok(),
+            |remaining| async move {
+                queries.fetch_add(1, Ordering::SeqCst);
+                // Slow enough for the other callers to run into the lock
+                tokio::time::sleep(Duration::from_millis(200)).await;
+
+                Ok(database
+                    .iter()
+                    .filter(|x| {
+                        remaining
+                            .iter()
+                            .any(|key| key == &x.id.to_string() || key.to_lowercase() == x.slug)
+                    })
+                    .cloned()
+                    .collect())
+            },
+        )
+        .await
+}
+
+#[actix_rt::test]
+async fn concurrent_fetches_query_once() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let redis = &test_env.db.redis_pool;
+        let database = vec![Item {
+            id: 1,
+            slug: "first".to_string(),
+        }];
+        let queries = AtomicUsize::new(0);
+
+        let results = futures::future::join_all(
+            (0..5).map(|_| get_items(&["first"], &database, &queries, redis)),
+        )
+        .await;
+
+        assert_eq!(queries.load(Ordering::SeqCst), 1);
+        for result in results {
+            assert_eq!(result.unwrap(), database);
+        }
+
+        // The item is cached by its id as well
+        assert_eq!(
+            get_items(&["1"], &database, &queries, redis).await.unwrap(),
+            database
+        );
+        assert_eq!(queries.load(Ordering::SeqCst), 1);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn missing_organizations_are_found_once_created() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        // The slug is cached as missing when it's looked up before the organization exists
+        let resp = api.get_organization("latecomers", USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let resp = api
+            .create_organization("Latecomers", "latecomers", "Arrived late", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let organization = api
+            .get_organization_deserialized("latecomers", USER_USER_PAT)
+            .await;
+
+        // Renaming it to a slug which was looked up before finds it by that slug too
+        let resp = api.get_organization("stragglers", USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let resp = api
+            .edit_organization(
+                &organization.id.to_string(),
+                json!({ "slug": "stragglers" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let renamed = api
+            .get_organization_deserialized("stragglers", USER_USER_PAT)
+            .await;
+        assert_eq!(renamed.id, organization.id);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn missing_users_are_found_once_renamed() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        let resp = api.get_user("newcomer", USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let req = TestRequest::patch()
+            .uri(&format!("/v3/user/{USER_USER_ID}"))
+            .append_pat(USER_USER_PAT)
+            .set_json(json!({ "username": "newcomer" }))
+            .to_request();
+        let resp = api.call(req).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = api.get_user("newcomer", USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["id"], json!(USER_USER_ID));
+    })
+    .await;
+}
+
+async fn warm_cache(api: &ApiV3) -> WarmedCaches {
+    let req = TestRequest::post()
+        .uri("/_internal/admin/_warm-cache?projects=10")
+        .append_header((
+            "Modrinth-Admin",
+            dotenvy::var("LABRINTH_ADMIN_KEY").unwrap(),
+        ))
+        .to_request();
+    let resp = api.call(req).await;
+    assert_status!(&resp, StatusCode::OK);
+    test::read_body_json(resp).await
+}
+
+#[actix_rt::test]
+async fn cache_warming_fills_the_most_downloaded_projects() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = parse_base62(&test_env.dummy.project_alpha.project_id).unwrap();
+        let alpha_version_id = parse_base62(&test_env.dummy.project_alpha.version_id).unwrap();
+        let beta_project_id = parse_base62(&test_env.dummy.project_beta.project_id).unwrap();
+
+        let mut redis = test_env.db.redis_pool.connect().await.unwrap();
+        redis
+            .delete_many([
+                (PROJECTS_NAMESPACE, Some(alpha_project_id.to_string())),
+                (PROJECTS_NAMESPACE, Some(beta_project_id.to_string())),
+                (VERSIONS_NAMESPACE, Some(alpha_version_id.to_string())),
+            ])
+            .await
+            .unwrap();
+
+        let warmed = warm_cache(api).await;
+        assert_eq!(warmed.projects, 1);
+        assert_eq!(warmed.versions, 1);
+        assert!(redis
+            .get(PROJECTS_NAMESPACE, &alpha_project_id.to_string())
+            .await
+            .unwrap()
+            .is_some());
+        assert!(redis
+            .get(VERSIONS_NAMESPACE, &alpha_version_id.to_string())
+            .await
+            .unwrap()
+            .is_some());
+        // Only public projects are warmed
+        assert!(redis
+            .get(PROJECTS_NAMESPACE, &beta_project_id.to_string())
+            .await
+            .unwrap()
+            .is_none());
+
+        // Entries which are already cached are skipped
+        let warmed = warm_cache(api).await;
+        assert_eq!(warmed.projects, 0);
+        assert_eq!(warmed.versions, 0);
+        assert_eq!(warmed.skipped, 2);
+    })
+    .await;
+}
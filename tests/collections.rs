@@ -0,0 +1,431 @@
+use actix_http::StatusCode;
+use actix_web::test;
+use common::api_common::{Api, ApiProject, ApiTeams, AppendsOptionalPat};
+use common::api_v3::ApiV3;
+use common::database::{
+    ENEMY_USER_PAT, FRIEND_USER_ID, FRIEND_USER_PAT, USER_USER_ID, USER_USER_PAT,
+};
+use common::environment::{with_test_environment, TestEnvironment};
+use labrinth::models::collections::{Collection, CollectionVisibility};
+use labrinth::models::notifications::{Notification, NotificationBody};
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn collection_projects_add_remove_and_reorder() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+
+        let (gamma_project, _) = api
+            .add_public_project("gamma", None, None, USER_USER_PAT)
+            .await;
+        let gamma_project_id = gamma_project.id.to_string();
+
+        let resp = api
+            .create_collection(
+                "Test Collection",
+                "Test Description",
+                &[alpha_project_id],
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let collection: Collection = test::read_body_json(resp).await;
+        let collection_id = collection.id.to_string();
+
+        // Adding projects appends them, ignoring ones already in the collection
+        let resp = api
+            .add_collection_projects(
+                &collection_id,
+                &[&gamma_project_id, alpha_project_id, beta_project_id],
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let collection = api
+            .get_collection_deserialized(&collection_id, USER_USER_PAT)
+            .await;
+        let project_ids = collection
+            .projects
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            project_ids,
+            vec![
+                alpha_project_id.clone(),
+                gamma_project_id.clone(),
+                beta_project_id.clone()
+            ]
+        );
+
+        // The new order must contain every project exactly once
+        let resp = api
+            .reorder_collection_projects(
+                &collection_id,
+                &[beta_project_id, alpha_project_id],
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // Projects can't be listed twice, and ones outside the collection are rejected
+        let (delta_project, _) = api
+            .add_public_project("delta", None, None, USER_USER_PAT)
+            .await;
+        let delta_project_id = delta_project.id.to_string();
+        let invalid_orders: [[&str; 4]; 2] = [
+            [
+                alpha_project_id,
+                alpha_project_id,
+                beta_project_id,
+                &gamma_project_id,
+            ],
+            [
+                alpha_project_id,
+                beta_project_id,
+                &gamma_project_id,
+                &delta_project_id,
+            ],
+        ];
+        for invalid_order in invalid_orders {
+            let resp = api
+                .reorder_collection_projects(&collection_id, &invalid_order, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::BAD_REQUEST);
+        }
+
+        let new_order = [
+            beta_project_id.as_str(),
+            alpha_project_id.as_str(),
+            gamma_project_id.as_str(),
+        ];
+        let resp = api
+            .reorder_collection_projects(&collection_id, &new_order, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Fetch twice- the ordering should survive being cached
+        for _ in 0..2 {
+            let collection = api
+                .get_collection_deserialized(&collection_id, USER_USER_PAT)
+                .await;
+            let project_ids = collection
+                .projects
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(project_ids, new_order);
+        }
+
+        // Removing keeps the order of the remaining projects
+        let resp = api
+            .remove_collection_projects(&collection_id, &[alpha_project_id], USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let collection = api
+            .get_collection_deserialized(&collection_id, USER_USER_PAT)
+            .await;
+        let project_ids = collection
+            .projects
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(project_ids, vec![beta_project_id.clone(), gamma_project_id]);
+
+        // Only the owner can modify the collection
+        let resp = api
+            .add_collection_projects(&collection_id, &[alpha_project_id], FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn collection_projects_add_rejects_invisible_projects() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+
+        let resp = api
+            .create_collection("Test Collection", "Test Description", &[], FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let collection: Collection = test::read_body_json(resp).await;
+        let collection_id = collection.id.to_string();
+
+        // Beta is private, so the friend user can't add it. Nonexistent projects are rejected too.
+        let resp = api
+            .add_collection_projects(
+                &collection_id,
+                &[alpha_project_id, beta_project_id, "doesnotexist"],
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let description = body["description"].as_str().unwrap();
+        assert!(description.contains(beta_project_id.as_str()));
+        assert!(description.contains("doesnotexist"));
+        assert!(!description.contains(alpha_project_id.as_str()));
+
+        // Nothing was added
+        let collection = api
+            .get_collection_deserialized(&collection_id, FRIEND_USER_PAT)
+            .await;
+        assert!(collection.projects.is_empty());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn collection_followers_are_counted_and_notified() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+
+        let (gamma_project, _) = api
+            .add_public_project("gamma", None, None, USER_USER_PAT)
+            .await;
+        let gamma_project_id = gamma_project.id.to_string();
+
+        let resp = api
+            .create_collection(
+                "Test Collection",
+                "Test Description",
+                &[alpha_project_id],
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let collection: Collection = test::read_body_json(resp).await;
+        let collection_id = collection.id.to_string();
+        assert_eq!(collection.followers, 0);
+
+        let resp = api.follow_collection(&collection_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.follow_collection(&collection_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let collection = api
+            .get_collection_deserialized(&collection_id, USER_USER_PAT)
+            .await;
+        assert_eq!(collection.followers, 1);
+
+        // Followed collections are listed alongside followed projects
+        let resp = api
+            .call(
+                test::TestRequest::get()
+                    .uri(&format!("/v3/user/{FRIEND_USER_ID}/follows"))
+                    .append_pat(FRIEND_USER_PAT)
+                    .to_request(),
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let follows: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(follows["collections"][0]["id"], collection_id);
+
+        // Adding several projects at once sends a single notification
+        let resp = api
+            .add_collection_projects(
+                &collection_id,
+                &[beta_project_id, &gamma_project_id, alpha_project_id],
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let updates = get_collection_updates(api, FRIEND_USER_ID, FRIEND_USER_PAT).await;
+        assert_eq!(updates.len(), 1);
+        let NotificationBody::CollectionUpdate { project_ids, .. } = &updates[0].body else {
+            panic!("Expected a collection update");
+        };
+        let mut project_ids = project_ids
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        project_ids.sort();
+        let mut expected = vec![beta_project_id.clone(), gamma_project_id.clone()];
+        expected.sort();
+        assert_eq!(project_ids, expected);
+
+        // The owner isn't notified of their own edits
+        assert!(get_collection_updates(api, USER_USER_ID, USER_USER_PAT)
+            .await
+            .is_empty());
+
+        let resp = api
+            .unfollow_collection(&collection_id, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .unfollow_collection(&collection_id, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let collection = api
+            .get_collection_deserialized(&collection_id, USER_USER_PAT)
+            .await;
+        assert_eq!(collection.followers, 0);
+
+        let resp = api
+            .remove_collection_projects(&collection_id, &[beta_project_id], USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .add_collection_projects(&collection_id, &[beta_project_id], USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        assert_eq!(
+            get_collection_updates(api, FRIEND_USER_ID, FRIEND_USER_PAT)
+                .await
+                .len(),
+            1
+        );
+
+        // Private collections can't be followed by users who can't see them
+        let resp = api
+            .edit_collection(
+                &collection_id,
+                json!({ "visibility": "private" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.follow_collection(&collection_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn collection_visibility() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        let resp = api
+            .create_collection("Test Collection", "Test Description", &[], USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let collection: Collection = test::read_body_json(resp).await;
+        assert_eq!(collection.visibility, CollectionVisibility::Public);
+        let collection_id = collection.id.to_string();
+
+        let share_token = api
+            .create_collection_share_token_deserialized(&collection_id, USER_USER_PAT)
+            .await
+            .token;
+        // Only the owner can share the collection
+        let resp = api
+            .create_collection_share_token(&collection_id, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        // Returns whether the collection is accessible, and whether it's listed on the owner's profile
+        let collection_id = collection_id.as_str();
+        let check = |pat: Option<&'static str>, share_token: Option<String>| async move {
+            let resp = match &share_token {
+                Some(token) => api.get_shared_collection(&collection_id, token, pat).await,
+                None => api.get_collection(&collection_id, pat).await,
+            };
+            let accessible = match resp.status() {
+                StatusCode::OK => true,
+                StatusCode::NOT_FOUND => false,
+                status => panic!("Unexpected status {status}"),
+            };
+
+            let resp = api.get_user_collections(USER_USER_ID, pat).await;
+            assert_status!(&resp, StatusCode::OK);
+            let collections: Vec<Collection> = test::read_body_json(resp).await;
+            let listed = collections
+                .iter()
+                .any(|x| x.id.to_string() == collection_id);
+
+            (accessible, listed)
+        };
+
+        assert_eq!(check(USER_USER_PAT, None).await, (true, true));
+        assert_eq!(check(ENEMY_USER_PAT, None).await, (true, true));
+        assert_eq!(check(None, None).await, (true, true));
+
+        let resp = api
+            .edit_collection(
+                &collection_id,
+                json!({ "visibility": "unlisted" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        assert_eq!(check(USER_USER_PAT, None).await, (true, true));
+        assert_eq!(check(ENEMY_USER_PAT, None).await, (true, false));
+        assert_eq!(check(None, None).await, (true, false));
+
+        let resp = api
+            .edit_collection(
+                &collection_id,
+                json!({ "visibility": "private" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        assert_eq!(check(USER_USER_PAT, None).await, (true, true));
+        assert_eq!(check(ENEMY_USER_PAT, None).await, (false, false));
+        assert_eq!(check(None, None).await, (false, false));
+        assert_eq!(
+            check(None, Some("not-a-token".to_string())).await,
+            (false, false)
+        );
+
+        // A share token grants read access to the private collection
+        assert_eq!(check(None, Some(share_token.clone())).await, (true, false));
+        assert_eq!(
+            check(ENEMY_USER_PAT, Some(share_token.clone())).await,
+            (true, false)
+        );
+
+        // Until it's revoked
+        let resp = api
+            .delete_collection_share_token(&collection_id, &share_token, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .delete_collection_share_token(&collection_id, &share_token, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        assert_eq!(check(None, Some(share_token.clone())).await, (false, false));
+
+        let resp = api
+            .edit_collection(
+                &collection_id,
+                json!({ "visibility": "public" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        assert_eq!(check(ENEMY_USER_PAT, None).await, (true, true));
+        assert_eq!(check(None, None).await, (true, true));
+    })
+    .await;
+}
+
+async fn get_collection_updates(
+    api: &ApiV3,
+    user_id: &str,
+    pat: Option<&str>,
+) -> Vec<Notification> {
+    let resp = api.get_user_notifications(user_id, pat).await;
+    assert_status!(&resp, StatusCode::OK);
+    let notifications: Vec<Notification> = test::read_body_json(resp).await;
+    notifications
+        .into_iter()
+        .filter(|x| matches!(x.body, NotificationBody::CollectionUpdate { .. }))
+        .collect()
+}
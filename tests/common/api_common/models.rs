@@ -133,6 +133,7 @@ pub struct CommonNotificationAction {
 pub enum CommonItemType {
     Project,
     Version,
+    File,
     User,
     Unknown,
 }
@@ -142,6 +143,7 @@ impl CommonItemType {
         match self {
             CommonItemType::Project => "project",
             CommonItemType::Version => "version",
+            CommonItemType::File => "file",
             CommonItemType::User => "user",
             CommonItemType::Unknown => "unknown",
         }
@@ -4,7 +4,10 @@ use actix_web::{
     test::{self, TestRequest},
 };
 use bytes::Bytes;
-use labrinth::models::{collections::Collection, v3::projects::Project};
+use labrinth::models::{
+    collections::{Collection, CollectionShareToken},
+    v3::projects::Project,
+};
 use serde_json::json;
 
 use crate::{
@@ -93,6 +96,51 @@ impl ApiV3 {
         self.call(req).await
     }
 
+    pub async fn add_collection_projects(
+        &self,
+        id: &str,
+        projects: &[&str],
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/collection/{id}/projects"))
+            .append_pat(pat)
+            .set_json(json!({ "projects": projects }))
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn remove_collection_projects(
+        &self,
+        id: &str,
+        projects: &[&str],
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v3/collection/{id}/projects"))
+            .append_pat(pat)
+            .set_json(json!({ "projects": projects }))
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn reorder_collection_projects(
+        &self,
+        id: &str,
+        projects: &[&str],
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::patch()
+            .uri(&format!("/v3/collection/{id}/projects/order"))
+            .append_pat(pat)
+            .set_json(json!({ "projects": projects }))
+            .to_request();
+
+        self.call(req).await
+    }
+
     pub async fn edit_collection_icon(
         &self,
         id: &str,
@@ -122,6 +170,72 @@ impl ApiV3 {
         }
     }
 
+    pub async fn follow_collection(&self, id: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/collection/{id}/follow"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn unfollow_collection(&self, id: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v3/collection/{id}/follow"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_shared_collection(
+        &self,
+        id: &str,
+        share_token: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::get()
+            .uri(&format!("/v3/collection/{id}?share={share_token}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn create_collection_share_token(
+        &self,
+        id: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::post()
+            .uri(&format!("/v3/collection/{id}/share"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn create_collection_share_token_deserialized(
+        &self,
+        id: &str,
+        pat: Option<&str>,
+    ) -> CollectionShareToken {
+        let resp = self.create_collection_share_token(id, pat).await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    pub async fn delete_collection_share_token(
+        &self,
+        id: &str,
+        share_token: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::delete()
+            .uri(&format!("/v3/collection/{id}/share/{share_token}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
     pub async fn delete_collection(&self, id: &str, pat: Option<&str>) -> ServiceResponse {
         let req = test::TestRequest::delete()
             .uri(&format!("/v3/collection/{id}"))
@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 
 use super::{
-    api_common::{Api, ApiBuildable},
+    api_common::{Api, ApiBuildable, AppendsOptionalPat},
     environment::LocalService,
 };
 use actix_web::{dev::ServiceResponse, test, App};
@@ -52,3 +52,47 @@ impl Api for ApiV3 {
         self.call(req).await
     }
 }
+
+impl ApiV3 {
+    pub async fn resolve_id(&self, id: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/id/{id}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn edit_maintenance(
+        &self,
+        read_only: bool,
+        message: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::put()
+            .uri("/v3/admin/maintenance")
+            .append_pat(pat)
+            .set_json(serde_json::json!({
+                "read_only": read_only,
+                "message": message,
+                "retry_after": 120,
+            }))
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_jobs(&self, query: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/admin/jobs?{query}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_job(&self, id: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/admin/jobs/{id}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+}
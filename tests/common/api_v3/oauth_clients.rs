@@ -41,6 +41,46 @@ impl ApiV3 {
         self.call(req).await
     }
 
+    pub async fn add_organization_oauth_client(
+        &self,
+        name: String,
+        max_scopes: Scopes,
+        redirect_uris: Vec<String>,
+        organization_id: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let max_scopes = max_scopes.bits();
+        let req = TestRequest::post()
+            .uri("/_internal/oauth/app")
+            .append_pat(pat)
+            .set_json(json!({
+                "name": name,
+                "max_scopes": max_scopes,
+                "redirect_uris": redirect_uris,
+                "organization_id": organization_id
+            }))
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_organization_oauth_clients(
+        &self,
+        user_id: &str,
+        organization_id: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::get()
+            .uri(&format!(
+                "/v3/user/{}/oauth_apps?organization_id={}",
+                user_id, organization_id
+            ))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
     pub async fn get_user_oauth_clients(
         &self,
         user_id: &str,
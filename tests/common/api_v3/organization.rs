@@ -170,10 +170,28 @@ impl ApiV3 {
         project_id_or_slug: &str,
         new_owner_user_id: UserId,
         pat: Option<&str>,
+    ) -> ServiceResponse {
+        self.organization_remove_project_with_members(
+            id_or_title,
+            project_id_or_slug,
+            new_owner_user_id,
+            false,
+            pat,
+        )
+        .await
+    }
+
+    pub async fn organization_remove_project_with_members(
+        &self,
+        id_or_title: &str,
+        project_id_or_slug: &str,
+        new_owner_user_id: UserId,
+        transfer_members: bool,
+        pat: Option<&str>,
     ) -> ServiceResponse {
         let req = test::TestRequest::delete()
             .uri(&format!(
-                "/v3/organization/{id_or_title}/projects/{project_id_or_slug}"
+                "/v3/organization/{id_or_title}/projects/{project_id_or_slug}?transfer_members={transfer_members}"
             ))
             .set_json(json!({
                 "new_owner": new_owner_user_id,
@@ -183,4 +201,218 @@ impl ApiV3 {
 
         self.call(req).await
     }
+
+    pub async fn get_organization_audit_log(
+        &self,
+        id_or_title: &str,
+        action: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let query = action.map(|x| format!("?action={x}")).unwrap_or_default();
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/organization/{id_or_title}/audit-log{query}"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn create_organization_invite_link(
+        &self,
+        id_or_title: &str,
+        link: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/organization/{id_or_title}/invite-link"))
+            .append_pat(pat)
+            .set_json(link)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_organization_invite_links(
+        &self,
+        id_or_title: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/organization/{id_or_title}/invite-links"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn delete_organization_invite_link(
+        &self,
+        id_or_title: &str,
+        link_id: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::delete()
+            .uri(&format!(
+                "/v3/organization/{id_or_title}/invite-link/{link_id}"
+            ))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn join_organization(
+        &self,
+        id_or_title: &str,
+        identifier: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/organization/{id_or_title}/join/{identifier}"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn create_organization_role_template(
+        &self,
+        id_or_title: &str,
+        template: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/organization/{id_or_title}/roles"))
+            .append_pat(pat)
+            .set_json(template)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_organization_role_templates(
+        &self,
+        id_or_title: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/organization/{id_or_title}/roles"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn edit_organization_role_template(
+        &self,
+        id_or_title: &str,
+        role_id: &str,
+        patch: serde_json::Value,
+        propagate: bool,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::patch()
+            .uri(&format!(
+                "/v3/organization/{id_or_title}/roles/{role_id}?propagate={propagate}"
+            ))
+            .append_pat(pat)
+            .set_json(patch)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn delete_organization_role_template(
+        &self,
+        id_or_title: &str,
+        role_id: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v3/organization/{id_or_title}/roles/{role_id}"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn edit_organization_limits(
+        &self,
+        id_or_title: &str,
+        limits: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::patch()
+            .uri(&format!("/v3/organization/{id_or_title}/limits"))
+            .append_pat(pat)
+            .set_json(limits)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn send_organization_announcement(
+        &self,
+        id_or_title: &str,
+        body: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/organization/{id_or_title}/announcement"))
+            .append_pat(pat)
+            .set_json(json!({ "body": body }))
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_organization_announcement(
+        &self,
+        id_or_title: &str,
+        job_id: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/organization/{id_or_title}/announcement/{job_id}"
+            ))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn edit_organization_payout_splits(
+        &self,
+        id_or_title: &str,
+        splits: serde_json::Value,
+        apply_to_existing: bool,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::patch()
+            .uri(&format!(
+                "/v3/organization/{id_or_title}/payout-splits?apply_to_existing={apply_to_existing}"
+            ))
+            .append_pat(pat)
+            .set_json(splits)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_organization_payout_splits_job(
+        &self,
+        id_or_title: &str,
+        job_id: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/organization/{id_or_title}/payout-splits/{job_id}"
+            ))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
 }
@@ -9,8 +9,16 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use labrinth::{
-    models::{organizations::Organization, projects::Project},
-    routes::v3::projects::ReturnSearchResults,
+    models::{
+        audit_log::AuditLogEntry, organizations::Organization, projects::Project, reports::Report,
+        webhooks::ProjectWebhook,
+    },
+    routes::v3::{
+        admin::SearchAnalytics,
+        moderation::{ImageQueue, ModerationQueue},
+        projects::{ReturnAllSearchResults, ReturnSearchResults},
+        statistics::DownloadStatistic,
+    },
     util::actix::AppendsMultipart,
 };
 use rust_decimal::Decimal;
@@ -477,6 +485,118 @@ impl ApiProject for ApiV3 {
 }
 
 impl ApiV3 {
+    /// Opens a socket to the thread's updates. The token is passed in the query if given, and
+    /// the socket's frames are read from the response's body.
+    pub async fn open_thread_socket(
+        &self,
+        id: &str,
+        query_token: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let uri = match query_token {
+            Some(token) => format!("/v3/ws/thread/{id}?token={token}"),
+            None => format!("/v3/ws/thread/{id}"),
+        };
+        let req = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header(("Upgrade", "websocket"))
+            .insert_header(("Connection", "Upgrade"))
+            .insert_header(("Sec-WebSocket-Version", "13"))
+            .insert_header(("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn follow_project(&self, id_or_slug: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/project/{id_or_slug}/follow"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn edit_gallery_order(
+        &self,
+        id_or_slug: &str,
+        urls: &[&str],
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::patch()
+            .uri(&format!("/v3/project/{id_or_slug}/gallery/order"))
+            .append_pat(pat)
+            .set_json(json!({ "urls": urls }))
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn edit_gallery_item_by_id(
+        &self,
+        id_or_slug: &str,
+        image_id: &str,
+        patch: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::patch()
+            .uri(&format!("/v3/project/{id_or_slug}/gallery/{image_id}"))
+            .append_pat(pat)
+            .set_json(patch)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn upload_image(
+        &self,
+        context: &str,
+        image: ImageData,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!(
+                "/v3/image?ext={ext}&context={context}",
+                ext = image.extension
+            ))
+            .append_pat(pat)
+            .set_payload(Bytes::from(image.icon))
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn write_to_thread_with_images(
+        &self,
+        id: &str,
+        content: &str,
+        image_ids: &[&str],
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/thread/{id}"))
+            .append_pat(pat)
+            .set_json(json!({
+                "body": {
+                    "type": "text",
+                    "body": content,
+                    "associated_images": image_ids,
+                }
+            }))
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn list_reports(&self, query: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/reports?{query}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn list_reports_deserialized(&self, query: &str, pat: Option<&str>) -> Vec<Report> {
+        let resp = self.list_reports(query, pat).await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
     pub async fn get_project_deserialized(&self, id_or_slug: &str, pat: Option<&str>) -> Project {
         let resp = self.get_project(id_or_slug, pat).await;
         assert_status!(&resp, StatusCode::OK);
@@ -506,6 +626,233 @@ impl ApiV3 {
         test::read_body_json(resp).await
     }
 
+    pub async fn get_moderation_queue(&self, query: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/moderation/projects?{query}"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_moderation_queue_deserialized(
+        &self,
+        query: &str,
+        pat: Option<&str>,
+    ) -> ModerationQueue {
+        let resp = self.get_moderation_queue(query, pat).await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    pub async fn get_image_moderation_queue_deserialized(&self, pat: Option<&str>) -> ImageQueue {
+        let req = test::TestRequest::get()
+            .uri("/v3/moderation/images")
+            .append_pat(pat)
+            .to_request();
+        let resp = self.call(req).await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    pub async fn edit_image_moderation(
+        &self,
+        image_id: &str,
+        status: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::patch()
+            .uri(&format!("/v3/image/{image_id}/moderation"))
+            .append_pat(pat)
+            .set_json(json!({ "status": status }))
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn edit_moderation_review(
+        &self,
+        id_or_slug: &str,
+        patch: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::put()
+            .uri(&format!("/v3/project/{id_or_slug}/moderation/review"))
+            .append_pat(pat)
+            .set_json(patch)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_moderation_review(
+        &self,
+        id_or_slug: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/project/{id_or_slug}/moderation/review"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_project_history(
+        &self,
+        id_or_slug: &str,
+        fields: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let query = fields
+            .map(|x| format!("?fields={}", urlencoding::encode(x)))
+            .unwrap_or_default();
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/project/{id_or_slug}/history{query}"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn transfer_project(
+        &self,
+        id_or_slug: &str,
+        transfer: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/project/{id_or_slug}/transfer"))
+            .append_pat(pat)
+            .set_json(transfer)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_project_transfer(
+        &self,
+        id_or_slug: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/project/{id_or_slug}/transfer"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn cancel_project_transfer(
+        &self,
+        id_or_slug: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v3/project/{id_or_slug}/transfer"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn answer_project_transfer(
+        &self,
+        id_or_slug: &str,
+        accept: bool,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let answer = if accept { "accept" } else { "decline" };
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/project/{id_or_slug}/transfer/{answer}"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn add_project_webhook(
+        &self,
+        id_or_slug: &str,
+        url: &str,
+        secret: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/project/{id_or_slug}/webhooks"))
+            .set_json(json!({
+                "url": url,
+                "secret": secret,
+            }))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_project_webhooks(
+        &self,
+        id_or_slug: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/project/{id_or_slug}/webhooks"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_project_webhooks_deserialized(
+        &self,
+        id_or_slug: &str,
+        pat: Option<&str>,
+    ) -> Vec<ProjectWebhook> {
+        let resp = self.get_project_webhooks(id_or_slug, pat).await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    pub async fn get_project_audit_log(
+        &self,
+        id_or_slug: &str,
+        action: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let query = action.map(|x| format!("?action={x}")).unwrap_or_default();
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/project/{id_or_slug}/audit-log{query}"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn get_project_audit_log_deserialized(
+        &self,
+        id_or_slug: &str,
+        action: Option<&str>,
+        pat: Option<&str>,
+    ) -> Vec<AuditLogEntry> {
+        let resp = self.get_project_audit_log(id_or_slug, action, pat).await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    pub async fn remove_project_webhook(
+        &self,
+        id_or_slug: &str,
+        webhook_id: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v3/project/{id_or_slug}/webhooks/{webhook_id}"))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
     pub async fn search_deserialized(
         &self,
         query: Option<&str>,
@@ -533,13 +880,356 @@ impl ApiV3 {
         test::read_body_json(resp).await
     }
 
-    pub async fn get_analytics_revenue(
+    pub async fn search_filtered(
         &self,
-        id_or_slugs: Vec<&str>,
-        ids_are_version_ids: bool,
-        start_date: Option<DateTime<Utc>>,
-        end_date: Option<DateTime<Utc>>,
-        resolution_minutes: Option<u32>,
+        facets: Option<serde_json::Value>,
+        filters: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let mut query = Vec::new();
+        if let Some(facets) = facets {
+            query.push(format!(
+                "facets={}",
+                urlencoding::encode(&facets.to_string())
+            ));
+        }
+        if let Some(filters) = filters {
+            query.push(format!("filters={}", urlencoding::encode(filters)));
+        }
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/search?{}", query.join("&")))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn search_fields(
+        &self,
+        query: &str,
+        fields: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/search?query={}&fields={}",
+                urlencoding::encode(query),
+                urlencoding::encode(fields)
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn search_within(
+        &self,
+        query: &str,
+        facets: Option<serde_json::Value>,
+        updated_within: Option<&str>,
+        created_within: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let mut params = vec![format!("query={}", urlencoding::encode(query))];
+        if let Some(facets) = facets {
+            params.push(format!(
+                "facets={}",
+                urlencoding::encode(&facets.to_string())
+            ));
+        }
+        if let Some(updated_within) = updated_within {
+            params.push(format!(
+                "updated_within={}",
+                urlencoding::encode(updated_within)
+            ));
+        }
+        if let Some(created_within) = created_within {
+            params.push(format!(
+                "created_within={}",
+                urlencoding::encode(created_within)
+            ));
+        }
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/search?{}", params.join("&")))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn search_grouped(
+        &self,
+        new_filters: &str,
+        group: &str,
+        offset: usize,
+        limit: usize,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/search?new_filters={}&group={group}&offset={offset}&limit={limit}",
+                urlencoding::encode(new_filters)
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn search_all_deserialized(
+        &self,
+        query: &str,
+        pat: Option<&str>,
+    ) -> ReturnAllSearchResults {
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/search/all?query={}",
+                urlencoding::encode(query)
+            ))
+            .append_pat(pat)
+            .to_request();
+        let resp = self.call(req).await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    pub async fn get_project_fields(
+        &self,
+        id_or_slug: &str,
+        fields: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/project/{id_or_slug}?fields={}",
+                urlencoding::encode(fields)
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_projects_fields(
+        &self,
+        ids_or_slugs: &[&str],
+        fields: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let ids_or_slugs = serde_json::to_string(ids_or_slugs).unwrap();
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/projects?ids={}&fields={}",
+                urlencoding::encode(&ids_or_slugs),
+                urlencoding::encode(fields)
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_project_changelog(
+        &self,
+        id_or_slug: &str,
+        from_version: &str,
+        to_version: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/project/{id_or_slug}/changelog?from_version={from_version}&to_version={to_version}"
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn restore_project(&self, id_or_slug: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/project/{id_or_slug}/restore"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn refresh_project_description(
+        &self,
+        id_or_slug: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/project/{id_or_slug}/description/refresh"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_project_in_language(
+        &self,
+        id_or_slug: &str,
+        lang: Option<&str>,
+        accept_language: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let query = lang.map(|x| format!("?lang={x}")).unwrap_or_default();
+        let mut req = test::TestRequest::get()
+            .uri(&format!("/v3/project/{id_or_slug}{query}"))
+            .append_pat(pat);
+        if let Some(accept_language) = accept_language {
+            req = req.append_header(("Accept-Language", accept_language));
+        }
+        self.call(req.to_request()).await
+    }
+
+    pub async fn edit_project_translation(
+        &self,
+        id_or_slug: &str,
+        lang: &str,
+        translation: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::put()
+            .uri(&format!("/v3/project/{id_or_slug}/translations/{lang}"))
+            .append_pat(pat)
+            .set_json(translation)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn delete_project_translation(
+        &self,
+        id_or_slug: &str,
+        lang: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v3/project/{id_or_slug}/translations/{lang}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn create_wiki_page(
+        &self,
+        id_or_slug: &str,
+        page: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/project/{id_or_slug}/wiki"))
+            .append_pat(pat)
+            .set_json(page)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_wiki_pages(&self, id_or_slug: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/project/{id_or_slug}/wiki"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_wiki_page(
+        &self,
+        id_or_slug: &str,
+        slug: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/project/{id_or_slug}/wiki/{slug}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn edit_wiki_page(
+        &self,
+        id_or_slug: &str,
+        slug: &str,
+        edit: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::patch()
+            .uri(&format!("/v3/project/{id_or_slug}/wiki/{slug}"))
+            .append_pat(pat)
+            .set_json(edit)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn edit_wiki_order(
+        &self,
+        id_or_slug: &str,
+        slugs: &[&str],
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::patch()
+            .uri(&format!("/v3/project/{id_or_slug}/wiki"))
+            .append_pat(pat)
+            .set_json(json!({ "slugs": slugs }))
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn delete_wiki_page(
+        &self,
+        id_or_slug: &str,
+        slug: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v3/project/{id_or_slug}/wiki/{slug}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_search_analytics(
+        &self,
+        date: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let query = date.map(|x| format!("?date={x}")).unwrap_or_default();
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/admin/search/analytics{query}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_search_analytics_deserialized(
+        &self,
+        date: Option<&str>,
+        pat: Option<&str>,
+    ) -> SearchAnalytics {
+        let resp = self.get_search_analytics(date, pat).await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    pub async fn search_deserialized_include_mature(
+        &self,
+        query: &str,
+        pat: Option<&str>,
+    ) -> ReturnSearchResults {
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/search?query={}&include_mature=true",
+                urlencoding::encode(query)
+            ))
+            .append_pat(pat)
+            .to_request();
+        let resp = self.call(req).await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    pub async fn get_analytics_revenue(
+        &self,
+        id_or_slugs: Vec<&str>,
+        ids_are_version_ids: bool,
+        start_date: Option<DateTime<Utc>>,
+        end_date: Option<DateTime<Utc>>,
+        resolution_minutes: Option<u32>,
         pat: Option<&str>,
     ) -> ServiceResponse {
         let pv_string = if ids_are_version_ids {
@@ -599,4 +1289,95 @@ impl ApiV3 {
         assert_status!(&resp, StatusCode::OK);
         test::read_body_json(resp).await
     }
+
+    pub async fn get_project_download_statistics(
+        &self,
+        id_or_slug: &str,
+        query: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/project/{id_or_slug}/statistics/downloads?{query}"
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_project_download_statistics_deserialized(
+        &self,
+        id_or_slug: &str,
+        query: &str,
+        pat: Option<&str>,
+    ) -> Vec<DownloadStatistic> {
+        let resp = self
+            .get_project_download_statistics(id_or_slug, query, pat)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    pub async fn get_version_download_statistics(
+        &self,
+        version_id: &str,
+        query: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/version/{version_id}/statistics/downloads?{query}"
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_project_embed(&self, id_or_slug: &str) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/project/{id_or_slug}/embed"))
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_oembed(&self, url: &str) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/oembed?url={}", urlencoding::encode(url)))
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_project_blocks(&self, id_or_slug: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/project/{id_or_slug}/blocks"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn block_project_user(
+        &self,
+        id_or_slug: &str,
+        user_id: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::put()
+            .uri(&format!("/v3/project/{id_or_slug}/blocks/{user_id}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn unblock_project_user(
+        &self,
+        id_or_slug: &str,
+        user_id: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v3/project/{id_or_slug}/blocks/{user_id}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
 }
@@ -85,6 +85,80 @@ impl ApiV3 {
         test::read_body_json(resp).await
     }
 
+    pub async fn create_loader_field(
+        &self,
+        field: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::post()
+            .uri("/v3/admin/loader-fields")
+            .append_pat(pat)
+            .set_json(field)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn delete_loader_field(&self, field: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = TestRequest::delete()
+            .uri(&format!("/v3/admin/loader-fields/{field}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn add_loader_field_value(
+        &self,
+        field: &str,
+        value: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::post()
+            .uri(&format!("/v3/admin/loader-fields/{field}/values"))
+            .append_pat(pat)
+            .set_json(value)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn delete_loader_field_value(
+        &self,
+        field: &str,
+        value: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::delete()
+            .uri(&format!("/v3/admin/loader-fields/{field}/values/{value}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn create_category(
+        &self,
+        category: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::post()
+            .uri("/v3/admin/categories")
+            .append_pat(pat)
+            .set_json(category)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn delete_category(
+        &self,
+        project_type: &str,
+        name: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::delete()
+            .uri(&format!("/v3/admin/categories/{project_type}/{name}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
     // TODO: fold this into v3 API of other v3 testing PR
     async fn get_games(&self) -> ServiceResponse {
         let req = TestRequest::get()
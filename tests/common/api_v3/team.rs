@@ -47,6 +47,50 @@ impl ApiV3 {
         assert_status!(&resp, StatusCode::OK);
         test::read_body_json(resp).await
     }
+
+    pub async fn mark_all_notifications_read(
+        &self,
+        notification_type: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let query = notification_type
+            .map(|x| format!("?type={x}"))
+            .unwrap_or_default();
+        let req = test::TestRequest::patch()
+            .uri(&format!("/v3/notifications/read-all{query}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn add_users_to_team_bulk(
+        &self,
+        team_id: &str,
+        members: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/team/{team_id}/members/bulk"))
+            .append_pat(pat)
+            .set_json(members)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn delete_all_notifications(
+        &self,
+        notification_type: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let query = notification_type
+            .map(|x| format!("?type={x}"))
+            .unwrap_or_default();
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v3/notifications{query}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
 }
 
 #[async_trait(?Send)]
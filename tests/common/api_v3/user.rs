@@ -1,7 +1,12 @@
+use actix_http::StatusCode;
 use actix_web::{dev::ServiceResponse, test};
 use async_trait::async_trait;
+use labrinth::models::notifications::NotificationPreference;
 
-use crate::common::api_common::{Api, ApiUser, AppendsOptionalPat};
+use crate::{
+    assert_status,
+    common::api_common::{Api, ApiUser, AppendsOptionalPat},
+};
 
 use super::ApiV3;
 
@@ -46,3 +51,221 @@ impl ApiUser for ApiV3 {
         self.call(req).await
     }
 }
+
+impl ApiV3 {
+    pub async fn get_notification_preferences(
+        &self,
+        user_id_or_username: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/user/{}/notification-preferences",
+                user_id_or_username
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_notification_preferences_deserialized(
+        &self,
+        user_id_or_username: &str,
+        pat: Option<&str>,
+    ) -> Vec<NotificationPreference> {
+        let resp = self
+            .get_notification_preferences(user_id_or_username, pat)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    pub async fn edit_notification_preferences(
+        &self,
+        user_id_or_username: &str,
+        preferences: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::patch()
+            .uri(&format!(
+                "/v3/user/{}/notification-preferences",
+                user_id_or_username
+            ))
+            .append_pat(pat)
+            .set_json(preferences)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn edit_payout_method(
+        &self,
+        user_id_or_username: &str,
+        method: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::patch()
+            .uri(&format!("/v3/user/{}/payout-method", user_id_or_username))
+            .append_pat(pat)
+            .set_json(method)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn create_user_export(
+        &self,
+        user_id_or_username: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/user/{}/export", user_id_or_username))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_user_export_status(
+        &self,
+        user_id_or_username: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/user/{}/export/status", user_id_or_username))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_recommendations(&self, count: usize, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/user/recommendations?count={count}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_user_blocks(&self, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri("/v3/user/blocks")
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn block_user(&self, user_id: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::put()
+            .uri(&format!("/v3/user/blocks/{user_id}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn unblock_user(&self, user_id: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = test::TestRequest::delete()
+            .uri(&format!("/v3/user/blocks/{user_id}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn edit_user_limits(
+        &self,
+        user_id_or_username: &str,
+        limits: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::patch()
+            .uri(&format!("/v3/user/{}/limits", user_id_or_username))
+            .append_pat(pat)
+            .set_json(limits)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn create_user_connection(
+        &self,
+        user_id_or_username: &str,
+        connection: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/user/{}/connections", user_id_or_username))
+            .append_pat(pat)
+            .set_json(connection)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn verify_user_connection(
+        &self,
+        user_id_or_username: &str,
+        connection_id: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::post()
+            .uri(&format!(
+                "/v3/user/{}/connections/{}/verify",
+                user_id_or_username, connection_id
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn delete_user_connection(
+        &self,
+        user_id_or_username: &str,
+        connection_id: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::delete()
+            .uri(&format!(
+                "/v3/user/{}/connections/{}",
+                user_id_or_username, connection_id
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_user_payouts(
+        &self,
+        user_id_or_username: &str,
+        start: Option<&str>,
+        end: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let mut query_string = String::new();
+        if let Some(start) = start {
+            query_string.push_str(&format!("&start={}", urlencoding::encode(start)));
+        }
+        if let Some(end) = end {
+            query_string.push_str(&format!("&end={}", urlencoding::encode(end)));
+        }
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/user/{}/payouts?{}",
+                user_id_or_username,
+                query_string.trim_start_matches('&')
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_user_payout_statement(
+        &self,
+        user_id_or_username: &str,
+        year: i32,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/v3/user/{}/payouts/statement?year={}",
+                user_id_or_username, year
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+}
@@ -22,8 +22,11 @@ use labrinth::{
         projects::{ProjectId, VersionType},
         v3::projects::Version,
     },
-    routes::v3::version_file::FileUpdateData,
-    util::actix::AppendsMultipart,
+    routes::v3::{
+        version_file::{FileUpdateData, LoaderUpdateResponse},
+        versions::{DependencyGraph, ProjectVersionMatrix, VersionFiles},
+    },
+    util::actix::{AppendsMultipart, MultipartSegment, MultipartSegmentData},
 };
 use serde_json::json;
 
@@ -60,12 +63,178 @@ impl ApiV3 {
         test::read_body_json(version).await
     }
 
+    pub async fn update_files_for_loader(
+        &self,
+        hashes: &[String],
+        loader: &str,
+        game_versions: &[&str],
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::get()
+            .uri("/v3/version_files/update")
+            .append_pat(pat)
+            .set_json(json!({
+                "algorithm": "sha1",
+                "hashes": hashes,
+                "loader": loader,
+                "game_versions": game_versions,
+            }))
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn update_files_for_loader_deserialized(
+        &self,
+        hashes: &[String],
+        loader: &str,
+        game_versions: &[&str],
+        pat: Option<&str>,
+    ) -> LoaderUpdateResponse {
+        let resp = self
+            .update_files_for_loader(hashes, loader, game_versions, pat)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    pub async fn start_upload(
+        &self,
+        file_name: &str,
+        size: usize,
+        sha1: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::post()
+            .uri("/v3/version_file/upload/start")
+            .set_json(json!({
+                "file_name": file_name,
+                "size": size,
+                "sha1": sha1,
+            }))
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn upload_chunk(
+        &self,
+        upload_id: &str,
+        chunk_index: usize,
+        chunk: &[u8],
+        sha1: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::put()
+            .uri(&format!(
+                "/v3/version_file/upload/{upload_id}/{chunk_index}?sha1={sha1}"
+            ))
+            .set_payload(chunk.to_vec())
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn complete_upload(
+        &self,
+        upload_id: &str,
+        version_data: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::post()
+            .uri(&format!("/v3/version_file/upload/{upload_id}/complete"))
+            .set_json(version_data)
+            .append_pat(pat)
+            .to_request();
+
+        self.call(req).await
+    }
+
+    pub async fn add_versions_bulk(
+        &self,
+        project_id: &str,
+        data: serde_json::Value,
+        jars: &[TestFile],
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let mut segments = vec![MultipartSegment {
+            name: "data".to_string(),
+            filename: None,
+            content_type: Some("application/json".to_string()),
+            data: MultipartSegmentData::Text(serde_json::to_string(&data).unwrap()),
+        }];
+        segments.extend(jars.iter().map(|jar| MultipartSegment {
+            name: jar.filename(),
+            filename: Some(jar.filename()),
+            content_type: Some("application/java-archive".to_string()),
+            data: MultipartSegmentData::Binary(jar.bytes()),
+        }));
+
+        let req = TestRequest::post()
+            .uri(&format!("/v3/project/{project_id}/versions/bulk"))
+            .append_pat(pat)
+            .set_multipart(segments)
+            .to_request();
+        self.call(req).await
+    }
+
     pub async fn get_version_deserialized(&self, id: &str, pat: Option<&str>) -> Version {
         let resp = self.get_version(id, pat).await;
         assert_status!(&resp, StatusCode::OK);
         test::read_body_json(resp).await
     }
 
+    pub async fn rescan_version(&self, id: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = TestRequest::post()
+            .uri(&format!("/v3/version/{id}/rescan"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_version_dependencies(
+        &self,
+        id: &str,
+        query: &str,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::get()
+            .uri(&format!("/v3/version/{id}/dependencies?{query}"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_version_dependencies_deserialized(
+        &self,
+        id: &str,
+        query: &str,
+        pat: Option<&str>,
+    ) -> DependencyGraph {
+        let resp = self.get_version_dependencies(id, query, pat).await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    pub async fn get_version_files(&self, id: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = TestRequest::get()
+            .uri(&format!("/v3/version/{id}/files"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_version_files_deserialized(
+        &self,
+        id: &str,
+        pat: Option<&str>,
+    ) -> VersionFiles {
+        let resp = self.get_version_files(id, pat).await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
     pub async fn update_individual_files(
         &self,
         algorithm: &str,
@@ -93,6 +262,90 @@ impl ApiV3 {
         assert_status!(&resp, StatusCode::OK);
         test::read_body_json(resp).await
     }
+
+    pub async fn edit_version_files(
+        &self,
+        id: &str,
+        files: serde_json::Value,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let req = TestRequest::patch()
+            .uri(&format!("/v3/version/{id}/files"))
+            .append_pat(pat)
+            .set_json(json!({ "files": files }))
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn take_down_file(&self, hash: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = TestRequest::post()
+            .uri(&format!("/v3/version_file/{hash}/takedown"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn reinstate_file(&self, hash: &str, pat: Option<&str>) -> ServiceResponse {
+        let req = TestRequest::delete()
+            .uri(&format!("/v3/version_file/{hash}/takedown"))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_project_versions_page(
+        &self,
+        project_id_slug: &str,
+        count: u32,
+        before_version: Option<&str>,
+        featured: Option<bool>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let mut query_string = format!("count={count}");
+        if let Some(before_version) = before_version {
+            query_string.push_str(&format!("&before_version={before_version}"));
+        }
+        if let Some(featured) = featured {
+            query_string.push_str(&format!("&featured={featured}"));
+        }
+
+        let req = TestRequest::get()
+            .uri(&format!(
+                "/v3/project/{project_id_slug}/version?{query_string}"
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_project_version_matrix(
+        &self,
+        project_id_slug: &str,
+        channel: Option<&str>,
+        pat: Option<&str>,
+    ) -> ServiceResponse {
+        let query_string = channel.map(|x| format!("?channel={x}")).unwrap_or_default();
+        let req = TestRequest::get()
+            .uri(&format!(
+                "/v3/project/{project_id_slug}/version-matrix{query_string}"
+            ))
+            .append_pat(pat)
+            .to_request();
+        self.call(req).await
+    }
+
+    pub async fn get_project_version_matrix_deserialized(
+        &self,
+        project_id_slug: &str,
+        channel: Option<&str>,
+        pat: Option<&str>,
+    ) -> ProjectVersionMatrix {
+        let resp = self
+            .get_project_version_matrix(project_id_slug, channel, pat)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        test::read_body_json(resp).await
+    }
 }
 
 #[async_trait(?Send)]
@@ -59,11 +59,83 @@ pub enum TestFile {
 
 impl TestFile {
     pub fn build_random_jar() -> Self {
+        Self::build_random_jar_with_padding(0)
+    }
+
+    // Builds a random .jar with an additional stored file of `padding_len` random bytes,
+    // for tests which need large files
+    pub fn build_random_jar_with_padding(padding_len: usize) -> Self {
+        if padding_len == 0 {
+            return Self::build_random_jar_with_entry(None);
+        }
+
+        let padding = (0..padding_len)
+            .map(|_| rand::random::<u8>())
+            .collect::<Vec<_>>();
+        Self::build_random_jar_with_entry(Some(("padding.bin", &padding)))
+    }
+
+    // Builds a random .jar containing the EICAR antivirus test file, which the mock scanner flags
+    pub fn build_infected_jar() -> Self {
+        Self::build_random_jar_with_entry(Some((
+            "eicar.com",
+            br"X5O!P%@AP[4\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*",
+        )))
+    }
+
+    // Builds a random .jar whose fabric.mod.json declares the mod id and the dependencies,
+    // given as a map of mod ids to version ranges
+    pub fn build_random_jar_with_metadata(mod_id: &str, depends: serde_json::Value) -> Self {
+        Self::build_random_jar_from(Some(mod_id), depends, None)
+    }
+
+    // Builds a random .jar whose fabric.mod.json has an invalid mod id and no version, which
+    // the file validators warn about
+    pub fn build_malformed_jar() -> Self {
+        let filename = format!("malformed-mod-{}.jar", rand::random::<u64>());
+        let fabric_mod_json = serde_json::json!({
+            "schemaVersion": 1,
+            "id": "Malformed Mod",
+            "name": filename,
+        })
+        .to_string();
+
+        let mut cursor = Cursor::new(Vec::new());
+        {
+            let mut zip = ZipWriter::new(&mut cursor);
+            for (name, contents) in [
+                ("fabric.mod.json", fabric_mod_json.into_bytes()),
+                ("random.bin", rand::random::<u64>().to_le_bytes().to_vec()),
+            ] {
+                zip.start_file(
+                    name,
+                    FileOptions::default().compression_method(CompressionMethod::Stored),
+                )
+                .unwrap();
+                zip.write_all(&contents).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        let bytes = cursor.into_inner();
+
+        TestFile::BasicModRandom { filename, bytes }
+    }
+
+    fn build_random_jar_with_entry(entry: Option<(&str, &[u8])>) -> Self {
+        Self::build_random_jar_from(None, serde_json::json!({}), entry)
+    }
+
+    fn build_random_jar_from(
+        mod_id: Option<&str>,
+        mut depends: serde_json::Value,
+        entry: Option<(&str, &[u8])>,
+    ) -> Self {
         let filename = format!("random-mod-{}.jar", rand::random::<u64>());
+        depends["minecraft"] = serde_json::json!(">=1.20-");
 
         let fabric_mod_json = serde_json::json!({
             "schemaVersion": 1,
-            "id": filename,
+            "id": mod_id.unwrap_or(&filename),
             "version": "1.0.1",
 
             "name": filename,
@@ -86,9 +158,7 @@ impl TestFile {
                 "io.github.modrinth.Modrinth"
               ]
             },
-            "depends": {
-              "minecraft": ">=1.20-"
-            }
+            "depends": depends
           }
         )
         .to_string();
@@ -103,6 +173,14 @@ impl TestFile {
             )
             .unwrap();
             zip.write_all(fabric_mod_json.as_bytes()).unwrap();
+            if let Some((name, contents)) = entry {
+                zip.start_file(
+                    name,
+                    FileOptions::default().compression_method(CompressionMethod::Stored),
+                )
+                .unwrap();
+                zip.write_all(contents).unwrap();
+            }
             zip.finish().unwrap();
         }
         let bytes = cursor.into_inner();
@@ -111,6 +189,20 @@ impl TestFile {
     }
 
     pub fn build_random_mrpack() -> Self {
+        Self::build_random_mrpack_with_files(
+            json!([]),
+            json!({
+                "fabric-loader": "0.14.22",
+                "minecraft": "1.20.1"
+            }),
+        )
+    }
+
+    // Builds a random .mrpack whose index has the given files and dependencies
+    pub fn build_random_mrpack_with_files(
+        files: serde_json::Value,
+        dependencies: serde_json::Value,
+    ) -> Self {
         let filename = format!("random-modpack-{}.mrpack", rand::random::<u64>());
 
         let modrinth_index_json = serde_json::json!({
@@ -118,11 +210,8 @@ impl TestFile {
             "game": "minecraft",
             "versionId": "1.20.1-9.6",
             "name": filename,
-            "files": [],
-            "dependencies": {
-                "fabric-loader": "0.14.22",
-                "minecraft": "1.20.1"
-            }
+            "files": files,
+            "dependencies": dependencies
         }
         )
         .to_string();
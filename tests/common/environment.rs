@@ -11,6 +11,8 @@ use crate::{assert_status, common::setup};
 use actix_http::StatusCode;
 use actix_web::dev::ServiceResponse;
 use futures::Future;
+use labrinth::mailer::MockMailer;
+use std::sync::Arc;
 
 pub async fn with_test_environment<Fut, A>(
     max_connections: Option<u32>,
@@ -40,6 +42,7 @@ where
         api: GenericApi::V3(test_env_api_v3.api),
         setup_api: test_env_api_v3.setup_api,
         dummy: test_env_api_v3.dummy,
+        mailer: test_env_api_v3.mailer,
     };
     let db = test_env_api_v3.db.clone();
     f(test_env_api_v3).await;
@@ -52,6 +55,7 @@ where
         api: GenericApi::V2(test_env_api_v2.api),
         setup_api: test_env_api_v2.setup_api,
         dummy: test_env_api_v2.dummy,
+        mailer: test_env_api_v2.mailer,
     };
     let db = test_env_api_v2.db.clone();
     f(test_env_api_v2).await;
@@ -68,12 +72,14 @@ pub struct TestEnvironment<A> {
     pub api: A,
     pub setup_api: ApiV3, // Used for setting up tests only (ie: in ScopesTest)
     pub dummy: dummy_data::DummyData,
+    pub mailer: Arc<MockMailer>,
 }
 
 impl<A: ApiBuildable> TestEnvironment<A> {
     async fn build(max_connections: Option<u32>) -> Self {
         let db = TemporaryDatabase::create(max_connections).await;
-        let labrinth_config = setup(&db).await;
+        let mailer = Arc::new(MockMailer::new());
+        let labrinth_config = setup(&db, mailer.clone()).await;
         let api = A::build(labrinth_config.clone()).await;
         let setup_api = ApiV3::build(labrinth_config).await;
         let dummy = dummy_data::get_dummy_data(&setup_api).await;
@@ -82,10 +88,11 @@ impl<A: ApiBuildable> TestEnvironment<A> {
             api,
             setup_api,
             dummy,
+            mailer,
         }
     }
     pub async fn build_setup_api(db: &TemporaryDatabase) -> ApiV3 {
-        let labrinth_config = setup(db).await;
+        let labrinth_config = setup(db, Arc::new(MockMailer::new())).await;
         ApiV3::build(labrinth_config).await
     }
 }
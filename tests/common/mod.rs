@@ -1,5 +1,5 @@
 use labrinth::{check_env_vars, clickhouse};
-use labrinth::{file_hosting, queue, LabrinthConfig};
+use labrinth::{file_hosting, file_scanning, mailer, queue, LabrinthConfig};
 use std::sync::Arc;
 
 pub mod api_common;
@@ -16,10 +16,16 @@ pub mod search;
 
 // Testing equivalent to 'setup' function, producing a LabrinthConfig
 // If making a test, you should probably use environment::TestEnvironment::build() (which calls this)
-pub async fn setup(db: &database::TemporaryDatabase) -> LabrinthConfig {
+// Emails sent by the app are kept in `mailer`
+pub async fn setup(
+    db: &database::TemporaryDatabase,
+    mailer: Arc<mailer::MockMailer>,
+) -> LabrinthConfig {
     println!("Setting up labrinth config");
 
     dotenvy::dotenv().ok();
+    // Tests serve the pages and READMEs the app fetches from the loopback address
+    std::env::set_var("ALLOW_LOOPBACK_FETCHES", "true");
 
     if check_env_vars() {
         println!("Some environment variables are missing!");
@@ -30,18 +36,26 @@ pub async fn setup(db: &database::TemporaryDatabase) -> LabrinthConfig {
     let search_config = db.search_config.clone();
     let file_host: Arc<dyn file_hosting::FileHost + Send + Sync> =
         Arc::new(file_hosting::MockHost::new());
+    let scanner: Arc<dyn file_scanning::FileScanner + Send + Sync> =
+        Arc::new(file_scanning::MockScanner::new());
     let mut clickhouse = clickhouse::init_client().await.unwrap();
 
     let maxmind_reader = Arc::new(queue::maxmind::MaxMindIndexer::new().await.unwrap());
 
-    labrinth::app_setup(
+    let mut config = labrinth::app_setup(
         pool.clone(),
         redis_pool.clone(),
         search_config,
         &mut clickhouse,
         file_host.clone(),
+        scanner,
+        mailer,
         maxmind_reader,
-    )
+    );
+
+    // Tests make many requests quickly, so only the rate limiting tests set budgets
+    config.rate_limits = labrinth::ratelimit::RateLimits::unlimited();
+    config
 }
 
 pub fn get_json_val_str(val: impl serde::Serialize) -> String {
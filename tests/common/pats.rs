@@ -23,6 +23,7 @@ pub async fn create_test_pat(scopes: Scopes, user_id: i64, db: &TemporaryDatabas
         created: Utc::now(),
         expires: Utc::now() + chrono::Duration::days(1),
         last_used: None,
+        project_ids: None,
     };
     pat.insert(&mut transaction).await.unwrap();
     transaction.commit().await.unwrap();
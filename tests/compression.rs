@@ -0,0 +1,96 @@
+use actix_http::StatusCode;
+use actix_web::http::header::HeaderMap;
+use actix_web::test;
+use bytes::Bytes;
+use common::api_common::{Api, ApiProject, AppendsOptionalPat};
+use common::api_v3::ApiV3;
+use common::database::USER_USER_PAT;
+use common::environment::{with_test_environment, TestEnvironment};
+use serde_json::json;
+use std::io::Read;
+
+mod common;
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|x| x.to_str().ok())
+}
+
+async fn get_projects(
+    test_env: &TestEnvironment<ApiV3>,
+    uri: &str,
+    accept_encoding: Option<&str>,
+) -> (HeaderMap, Bytes) {
+    let mut req = test::TestRequest::get().uri(uri).append_pat(USER_USER_PAT);
+    if let Some(accept_encoding) = accept_encoding {
+        req = req.append_header(("Accept-Encoding", accept_encoding));
+    }
+    let resp = test_env.api.call(req.to_request()).await;
+    assert_status!(&resp, StatusCode::OK);
+
+    let headers = resp.headers().clone();
+    (headers, test::read_body(resp).await)
+}
+
+#[actix_rt::test]
+async fn large_json_responses_are_compressed() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+
+        // A long description makes sure the list is above the size threshold
+        let resp = test_env
+            .api
+            .edit_project(
+                alpha_project_id,
+                json!({ "description": "A long description. ".repeat(200) }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Only scalar fields are requested so the output is the same between requests
+        let ids = serde_json::to_string(&[alpha_project_id, beta_project_id]).unwrap();
+        let uri = format!(
+            "/v3/projects?ids={}&fields=slug,name,summary,description,status",
+            urlencoding::encode(&ids)
+        );
+
+        let (headers, uncompressed) = get_projects(&test_env, &uri, None).await;
+        assert!(header(&headers, "content-encoding").is_none());
+        assert!(uncompressed.len() > 4000);
+
+        let (headers, body) = get_projects(&test_env, &uri, Some("gzip")).await;
+        assert_eq!(header(&headers, "content-encoding"), Some("gzip"));
+        assert!(header(&headers, "vary")
+            .unwrap()
+            .contains("accept-encoding"));
+        assert!(body.len() < uncompressed.len());
+        let mut decompressed = Vec::new();
+        flate2::read::GzDecoder::new(&body[..])
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, uncompressed);
+
+        let (headers, body) = get_projects(&test_env, &uri, Some("gzip, deflate, br")).await;
+        assert_eq!(header(&headers, "content-encoding"), Some("br"));
+        let mut decompressed = Vec::new();
+        brotli::Decompressor::new(&body[..], 4096)
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, uncompressed);
+
+        // Small responses aren't worth compressing
+        let resp = test_env
+            .api
+            .call(
+                test::TestRequest::get()
+                    .uri(&format!("/v3/project/{alpha_project_id}/check"))
+                    .append_header(("Accept-Encoding", "gzip"))
+                    .to_request(),
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        assert!(header(resp.headers(), "content-encoding").is_none());
+    })
+    .await;
+}
@@ -0,0 +1,62 @@
+use actix_http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use common::api_common::{Api, ApiBuildable};
+use common::api_v3::ApiV3;
+use common::environment::{with_test_environment, TestEnvironment};
+
+mod common;
+
+async fn get_ready(api: &ApiV3) -> (StatusCode, serde_json::Value) {
+    let req = TestRequest::get().uri("/v3/health/ready").to_request();
+    let resp = api.call(req).await;
+    let status = resp.status();
+    (status, test::read_body_json(resp).await)
+}
+
+#[actix_rt::test]
+async fn health_endpoints_report_dependencies() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        let req = TestRequest::get().uri("/v3/health/live").to_request();
+        let resp = api.call(req).await;
+        assert_status!(&resp, StatusCode::OK);
+
+        let (status, body) = get_ready(api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["ready"], true);
+        for dependency in ["postgres", "redis", "meilisearch"] {
+            assert_eq!(body["dependencies"][dependency]["up"], true);
+        }
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn meilisearch_outage_is_only_critical_when_required() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        // Nothing listens on port 1, so Meilisearch can't be reached
+        let mut config = common::setup(&test_env.db, test_env.mailer.clone()).await;
+        config.search_config.address = "http://127.0.0.1:1".to_string();
+        config.search_config.required = true;
+        let api = ApiV3::build(config.clone()).await;
+
+        let (status, body) = get_ready(&api).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["ready"], false);
+        assert_eq!(body["dependencies"]["meilisearch"]["up"], false);
+        assert_eq!(body["dependencies"]["postgres"]["up"], true);
+        assert_eq!(body["dependencies"]["redis"]["up"], true);
+
+        // The API can still serve everything but search without it
+        config.search_config.required = false;
+        let api = ApiV3::build(config).await;
+
+        let (status, body) = get_ready(&api).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["ready"], true);
+        assert_eq!(body["dependencies"]["meilisearch"]["up"], false);
+        assert_eq!(body["dependencies"]["meilisearch"]["critical"], false);
+    })
+    .await;
+}
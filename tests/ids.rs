@@ -0,0 +1,55 @@
+use actix_http::StatusCode;
+use actix_web::test;
+use common::api_v3::ApiV3;
+use common::database::{ENEMY_USER_PAT, USER_USER_PAT};
+use common::environment::{with_test_environment, TestEnvironment};
+use labrinth::routes::v3::ids::{IdType, ResolvedId};
+
+mod common;
+
+#[actix_rt::test]
+async fn resolve_ids_to_their_type() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha = &test_env.dummy.project_alpha;
+
+        let resp = api.resolve_id(&alpha.project_id, None).await;
+        assert_status!(&resp, StatusCode::OK);
+        let resolved: ResolvedId = test::read_body_json(resp).await;
+        assert_eq!(resolved.type_, IdType::Project);
+        assert_eq!(resolved.id, alpha.project_id);
+        assert_eq!(resolved.summary["slug"], alpha.project_slug.as_str());
+
+        let resp = api.resolve_id(&alpha.version_id, None).await;
+        assert_status!(&resp, StatusCode::OK);
+        let resolved: ResolvedId = test::read_body_json(resp).await;
+        assert_eq!(resolved.type_, IdType::Version);
+        assert_eq!(resolved.id, alpha.version_id);
+        assert_eq!(resolved.summary["project_id"], alpha.project_id.as_str());
+
+        // An id that doesn't refer to anything
+        let resp = api.resolve_id("zzzzzzzz", None).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn resolving_hidden_ids_does_not_leak_their_type() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+
+        // The beta project is private, so only its team can tell what its id is
+        let resp = api.resolve_id(beta_project_id, None).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let resp = api.resolve_id(beta_project_id, ENEMY_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let resp = api.resolve_id(beta_project_id, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let resolved: ResolvedId = test::read_body_json(resp).await;
+        assert_eq!(resolved.type_, IdType::Project);
+    })
+    .await;
+}
@@ -461,3 +461,102 @@ async fn get_available_loader_fields() {
     })
     .await;
 }
+
+#[actix_rt::test]
+async fn loader_fields_are_managed_by_admins() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let DummyProjectAlpha {
+            project_id_parsed: alpha_project_id_parsed,
+            ..
+        } = &test_env.dummy.project_alpha;
+
+        let new_field = json!({
+            "field": "test_flavor",
+            "field_type": "enum",
+            "loaders": ["fabric"]
+        });
+        let resp = api
+            .create_loader_field(new_field.clone(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .create_loader_field(new_field.clone(), ADMIN_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let resp = api.create_loader_field(new_field, ADMIN_USER_PAT).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        for value in ["mint", "vanilla"] {
+            let resp = api
+                .add_loader_field_value("test_flavor", json!({ "value": value }), ADMIN_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+        }
+        let flavors = api
+            .get_loader_field_variants_deserialized("test_flavor")
+            .await;
+        assert_eq!(flavors.len(), 2);
+
+        // Game versions are ordered, so new ones need to say where they go
+        let resp = api
+            .add_loader_field_value("game_versions", json!({ "value": "1.21" }), ADMIN_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let resp = api
+            .add_loader_field_value(
+                "game_versions",
+                json!({ "value": "1.21", "ordering": 50 }),
+                ADMIN_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+
+        // The new value can be set on versions straight away
+        let v = api
+            .add_public_version_deserialized(
+                *alpha_project_id_parsed,
+                "1.0.0",
+                TestFile::build_random_jar(),
+                None,
+                Some(
+                    serde_json::from_value(json!([{
+                        "op": "add",
+                        "path": "/test_flavor",
+                        "value": "mint"
+                    }]))
+                    .unwrap(),
+                ),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_eq!(v.fields.get("test_flavor").unwrap(), &json!("mint"));
+        let v = api
+            .get_version_deserialized(&v.id.to_string(), USER_USER_PAT)
+            .await;
+        assert_eq!(v.fields.get("test_flavor").unwrap(), &json!("mint"));
+
+        // Neither the field nor the value can be deleted while a version has them set
+        let resp = api
+            .delete_loader_field_value("test_flavor", "mint", ADMIN_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let resp = api.delete_loader_field("test_flavor", ADMIN_USER_PAT).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let resp = api
+            .delete_loader_field_value("test_flavor", "vanilla", ADMIN_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Once reindexed, projects can be filtered by the field
+        let resp = api.reset_search_index().await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let hits = api
+            .search_deserialized(None, Some(json!([["test_flavor:mint"]])), USER_USER_PAT)
+            .await
+            .hits;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, *alpha_project_id_parsed);
+    })
+    .await;
+}
@@ -0,0 +1,76 @@
+use actix_http::StatusCode;
+use actix_web::test;
+use common::api_common::{Api, ApiBuildable, ApiProject, ApiVersion, AppendsOptionalPat};
+use common::api_v3::ApiV3;
+use common::database::{ADMIN_USER_PAT, USER_USER_PAT};
+use common::environment::{with_test_environment, TestEnvironment};
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn maintenance_makes_the_api_read_only() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let alpha_file_hash: &str = &test_env.dummy.project_alpha.file_hash;
+        let patch = || json!({ "description": "A new description" });
+
+        // Only admins can turn maintenance on
+        let resp = api.edit_maintenance(true, None, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        let resp = api
+            .edit_maintenance(true, Some("Migrating the database"), ADMIN_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+
+        // Writes are rejected with the message, while reads still work
+        let resp = api
+            .edit_project(alpha_project_id, patch(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            resp.headers()
+                .get("Retry-After")
+                .and_then(|x| x.to_str().ok()),
+            Some("120")
+        );
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "maintenance");
+        assert_eq!(body["description"], "Migrating the database");
+
+        let resp = api.get_project(alpha_project_id, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+
+        // Lookups sent as POST requests still work, but auth routes which write don't
+        let resp = api
+            .get_versions_from_hashes(&[alpha_file_hash], "sha1", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let req = test::TestRequest::delete()
+            .uri("/v3/auth/sessions")
+            .append_pat(USER_USER_PAT)
+            .to_request();
+        let resp = api.call(req).await;
+        assert_status!(&resp, StatusCode::SERVICE_UNAVAILABLE);
+
+        // The state is kept in Redis, so a restarted worker is still read-only
+        let config = common::setup(&test_env.db, test_env.mailer.clone()).await;
+        let restarted = ApiV3::build(config).await;
+        let resp = restarted
+            .edit_project(alpha_project_id, patch(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::SERVICE_UNAVAILABLE);
+
+        // Once maintenance is over, writes go through again
+        let resp = api.edit_maintenance(false, None, ADMIN_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+
+        let resp = api
+            .edit_project(alpha_project_id, patch(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+    })
+    .await;
+}
@@ -0,0 +1,129 @@
+use actix_http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use common::api_common::{Api, ApiProject};
+use common::api_v3::ApiV3;
+use common::database::USER_USER_PAT;
+use common::environment::{with_test_environment, TestEnvironment};
+
+mod common;
+
+async fn scrape(api: &ApiV3, token: Option<&str>) -> (StatusCode, String) {
+    let mut req = TestRequest::get().uri("/metrics");
+    if let Some(token) = token {
+        req = req.append_header(("Authorization", format!("Bearer {token}")));
+    }
+    let resp = api.call(req.to_request()).await;
+    let status = resp.status();
+    let body = test::read_body(resp).await;
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+// Gets the value of the series of the metric which has all of the labels
+fn metric_value(body: &str, name: &str, labels: &[&str]) -> Option<f64> {
+    body.lines()
+        .find(|line| {
+            line.starts_with(&format!("{name}{{")) && labels.iter().all(|x| line.contains(x))
+        })
+        .and_then(|line| line.rsplit(' ').next())
+        .and_then(|x| x.parse().ok())
+}
+
+#[actix_rt::test]
+async fn metrics_are_scraped_with_the_token() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let token = dotenvy::var("METRICS_TOKEN").unwrap();
+
+        let (status, _) = scrape(api, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = scrape(api, Some("not-the-token")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        for _ in 0..2 {
+            let resp = api.get_project(alpha_project_id, USER_USER_PAT).await;
+            assert_status!(&resp, StatusCode::OK);
+        }
+        let resp = api.get_project("not-a-project", USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let req = TestRequest::get().uri("/v3/not/a/route").to_request();
+        let resp = api.call(req).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let resp = api.reset_search_index().await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let (status, body) = scrape(api, Some(&token)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Requests are counted by the route they matched rather than their path
+        let route = r#"route="/v3/project/{id}""#;
+        assert!(
+            metric_value(
+                &body,
+                "labrinth_http_requests_total",
+                &[route, r#"method="GET""#, r#"status="2xx""#],
+            )
+            .unwrap()
+                >= 2.0
+        );
+        assert!(
+            metric_value(
+                &body,
+                "labrinth_http_requests_total",
+                &[route, r#"status="4xx""#],
+            )
+            .unwrap()
+                >= 1.0
+        );
+        assert!(
+            metric_value(
+                &body,
+                "labrinth_http_request_duration_seconds_count",
+                &[route, r#"method="GET""#],
+            )
+            .unwrap()
+                >= 3.0
+        );
+        assert!(
+            metric_value(
+                &body,
+                "labrinth_http_requests_total",
+                &[r#"route="unmatched""#, r#"status="4xx""#],
+            )
+            .unwrap()
+                >= 1.0
+        );
+        assert!(!body.contains(alpha_project_id.as_str()));
+        assert!(!body.contains("not-a-project"));
+
+        for state in ["idle", "in_use"] {
+            let label = format!(r#"state="{state}""#);
+            assert!(
+                metric_value(&body, "labrinth_database_connections", &[&label]).unwrap() >= 0.0
+            );
+            assert!(metric_value(&body, "labrinth_redis_connections", &[&label]).unwrap() >= 0.0);
+        }
+
+        assert!(
+            metric_value(
+                &body,
+                "labrinth_search_index_runs_total",
+                &[r#"result="success""#],
+            )
+            .unwrap()
+                >= 1.0
+        );
+        assert!(
+            metric_value(
+                &body,
+                "labrinth_search_index_duration_seconds_count",
+                &[r#"result="success""#],
+            )
+            .unwrap()
+                >= 1.0
+        );
+        assert!(body.contains("labrinth_search_indexed_documents_total{"));
+    })
+    .await;
+}
@@ -1,9 +1,19 @@
+use actix_http::StatusCode;
+use actix_web::test;
 use common::{
-    database::{FRIEND_USER_ID, FRIEND_USER_PAT, USER_USER_PAT},
-    environment::with_test_environment_all,
+    api_v3::ApiV3,
+    database::{
+        ENEMY_USER_ID, ENEMY_USER_PAT, FRIEND_USER_ID, FRIEND_USER_ID_PARSED, FRIEND_USER_PAT,
+        MOD_USER_ID, MOD_USER_PAT, USER_USER_ID, USER_USER_PAT,
+    },
+    dummy_data::TestFile,
+    environment::{with_test_environment, with_test_environment_all, TestEnvironment},
 };
+use labrinth::models::notifications::{Notification, NotificationBody, NotificationType};
+use labrinth::queue::email::send_queued_emails;
+use serde_json::json;
 
-use crate::common::api_common::ApiTeams;
+use crate::common::api_common::{ApiProject, ApiTeams};
 
 mod common;
 
@@ -70,3 +80,338 @@ pub async fn get_user_notifications_after_deleting_does_not_show_notification()
     })
     .await;
 }
+
+#[actix_rt::test]
+pub async fn disabled_notification_types_are_not_delivered() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_team_id = test_env.dummy.project_alpha.team_id.clone();
+        let zeta_team_id = test_env.dummy.organization_zeta.team_id.clone();
+        let api = &test_env.api;
+
+        // Other users can't see or change the preferences
+        let resp = api
+            .get_notification_preferences(FRIEND_USER_ID, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .edit_notification_preferences(
+                FRIEND_USER_ID,
+                json!([{ "type": "team_invite", "in_app": false }]),
+                ENEMY_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        let resp = api
+            .edit_notification_preferences(
+                FRIEND_USER_ID,
+                json!([{ "type": "team_invite", "in_app": false }]),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Every known type is returned, with unset ones enabled
+        let preferences = api
+            .get_notification_preferences_deserialized(FRIEND_USER_ID, FRIEND_USER_PAT)
+            .await;
+        assert_eq!(preferences.len(), NotificationType::iterator().count());
+        for preference in preferences {
+            assert_eq!(
+                preference.in_app,
+                preference.notification_type != NotificationType::TeamInvite
+            );
+        }
+
+        // The project team invite is suppressed
+        let resp = api
+            .add_user_to_team(&alpha_team_id, FRIEND_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let notifications = api
+            .get_user_notifications_deserialized_common(FRIEND_USER_ID, FRIEND_USER_PAT)
+            .await;
+        assert_eq!(0, notifications.len());
+
+        // Organization invites are still delivered
+        let resp = api
+            .add_user_to_team(&zeta_team_id, FRIEND_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let notifications = api
+            .get_user_notifications_deserialized_common(FRIEND_USER_ID, FRIEND_USER_PAT)
+            .await;
+        assert_eq!(1, notifications.len());
+    })
+    .await;
+}
+
+async fn invite_friend_to_all_teams(test_env: &TestEnvironment<ApiV3>) {
+    for team_id in [
+        &test_env.dummy.project_alpha.team_id,
+        &test_env.dummy.project_beta.team_id,
+        &test_env.dummy.organization_zeta.team_id,
+    ] {
+        let resp = test_env
+            .api
+            .add_user_to_team(team_id, FRIEND_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+    }
+}
+
+#[actix_rt::test]
+pub async fn mark_all_notifications_read_marks_every_notification() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        invite_friend_to_all_teams(&test_env).await;
+        let api = &test_env.api;
+
+        // Populate the notification cache before marking them as read
+        let notifications = api
+            .get_user_notifications_deserialized_common(FRIEND_USER_ID, FRIEND_USER_PAT)
+            .await;
+        assert_eq!(3, notifications.len());
+        assert!(notifications.iter().all(|x| !x.read));
+
+        let resp = api.mark_all_notifications_read(None, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["count"], 3);
+
+        let notifications = api
+            .get_user_notifications_deserialized_common(FRIEND_USER_ID, FRIEND_USER_PAT)
+            .await;
+        assert_eq!(3, notifications.len());
+        assert!(notifications.iter().all(|x| x.read));
+
+        // Already read notifications aren't counted again
+        let resp = api.mark_all_notifications_read(None, FRIEND_USER_PAT).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["count"], 0);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+pub async fn delete_all_notifications_clears_inbox() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        invite_friend_to_all_teams(&test_env).await;
+        let api = &test_env.api;
+
+        let notifications = api
+            .get_user_notifications_deserialized_common(FRIEND_USER_ID, FRIEND_USER_PAT)
+            .await;
+        assert_eq!(3, notifications.len());
+
+        // Only the organization invite is deleted when filtering by type
+        let resp = api
+            .delete_all_notifications(Some("organization_invite"), FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["count"], 1);
+
+        let resp = api.delete_all_notifications(None, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["count"], 2);
+
+        let notifications = api
+            .get_user_notifications_deserialized_common(FRIEND_USER_ID, FRIEND_USER_PAT)
+            .await;
+        assert!(notifications.is_empty());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+pub async fn followers_are_notified_of_new_versions() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_project_id = test_env.dummy.project_alpha.project_id.clone();
+        let alpha_project_id_parsed = test_env.dummy.project_alpha.project_id_parsed;
+        let api = &test_env.api;
+
+        for pat in [FRIEND_USER_PAT, ENEMY_USER_PAT, USER_USER_PAT] {
+            let resp = api.follow_project(&alpha_project_id, pat).await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+
+        let version = api
+            .add_public_version_deserialized(
+                alpha_project_id_parsed,
+                "1.2.3",
+                TestFile::build_random_jar(),
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+
+        // Followers are notified in the background
+        for (user_id, pat) in [
+            (FRIEND_USER_ID, FRIEND_USER_PAT),
+            (ENEMY_USER_ID, ENEMY_USER_PAT),
+        ] {
+            let mut notifications = Vec::new();
+            for _ in 0..20 {
+                notifications = api
+                    .get_user_notifications_deserialized_common(user_id, pat)
+                    .await;
+                if !notifications.is_empty() {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+            }
+            assert_eq!(1, notifications.len());
+            assert!(notifications[0]
+                .link
+                .ends_with(&format!("/version/{}", version.id)));
+        }
+
+        // The publisher is never notified of their own release
+        let notifications = api
+            .get_user_notifications_deserialized_common(USER_USER_ID, USER_USER_PAT)
+            .await;
+        assert_eq!(0, notifications.len());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+pub async fn critical_notifications_are_emailed_to_verified_users() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        // Only the friend has verified their email
+        sqlx::query("UPDATE users SET email_verified = TRUE WHERE id = $1")
+            .bind(FRIEND_USER_ID_PARSED)
+            .execute(&test_env.db.pool)
+            .await
+            .unwrap();
+
+        for user_id in [FRIEND_USER_ID, ENEMY_USER_ID] {
+            let resp = api
+                .add_user_to_team(
+                    &test_env.dummy.project_alpha.team_id,
+                    user_id,
+                    None,
+                    None,
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+
+        // Organization invites aren't critical, so they are only delivered in-app
+        let resp = api
+            .add_user_to_team(
+                &test_env.dummy.organization_zeta.team_id,
+                FRIEND_USER_ID,
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        send_queued_emails(&test_env.db.pool, &*test_env.mailer)
+            .await
+            .unwrap();
+
+        let sent = test_env.mailer.sent();
+        assert_eq!(1, sent.len());
+        assert_eq!(sent[0].to, "friend@modrinth.com");
+        assert_eq!(sent[0].subject, "You have been invited to join a team");
+
+        // Turning off email delivery keeps the in-app notification
+        let resp = api
+            .edit_notification_preferences(
+                FRIEND_USER_ID,
+                json!([{ "type": "team_invite", "send_email": false }]),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = api
+            .add_user_to_team(
+                &test_env.dummy.project_beta.team_id,
+                FRIEND_USER_ID,
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        send_queued_emails(&test_env.db.pool, &*test_env.mailer)
+            .await
+            .unwrap();
+        assert_eq!(1, test_env.mailer.sent().len());
+
+        let notifications = api
+            .get_user_notifications_deserialized_common(FRIEND_USER_ID, FRIEND_USER_PAT)
+            .await;
+        assert_eq!(3, notifications.len());
+
+        // Email can only be turned on for critical types
+        let resp = api
+            .edit_notification_preferences(
+                FRIEND_USER_ID,
+                json!([{ "type": "project_update", "send_email": true }]),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+pub async fn mentioned_thread_members_are_notified() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_team_id = test_env.dummy.project_alpha.team_id.clone();
+        let alpha_thread_id = test_env.dummy.project_alpha.thread_id.clone();
+        let api = &test_env.api;
+
+        // Friend is on alpha's team and can see its thread, but enemy isn't
+        let resp = api
+            .add_user_to_team(&alpha_team_id, FRIEND_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(&alpha_team_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = api
+            .write_to_thread(
+                &alpha_thread_id,
+                "text",
+                "Hey @Friend, @enemy and `@moderator`, could you take a look?",
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let get_mentions = |user_id: &'static str, pat: Option<&'static str>| async move {
+            let resp = api.get_user_notifications(user_id, pat).await;
+            assert_status!(&resp, StatusCode::OK);
+            let notifications: Vec<Notification> = test::read_body_json(resp).await;
+            notifications
+                .into_iter()
+                .filter(|x| matches!(x.body, NotificationBody::Mention { .. }))
+                .collect::<Vec<_>>()
+        };
+
+        let mentions = get_mentions(FRIEND_USER_ID, FRIEND_USER_PAT).await;
+        assert_eq!(mentions.len(), 1);
+        let NotificationBody::Mention { mentioned_by, .. } = mentions[0].body else {
+            panic!("Expected a mention");
+        };
+        assert_eq!(mentioned_by.to_string(), USER_USER_ID);
+
+        // Enemy can't see the thread, and mentions in code don't count
+        assert!(get_mentions(ENEMY_USER_ID, ENEMY_USER_PAT).await.is_empty());
+        assert!(get_mentions(MOD_USER_ID, MOD_USER_PAT).await.is_empty());
+    })
+    .await;
+}
@@ -2,15 +2,17 @@ use actix_http::StatusCode;
 use actix_web::test;
 use common::{
     api_v3::ApiV3,
-    database::{FRIEND_USER_ID, FRIEND_USER_PAT, USER_USER_ID, USER_USER_PAT},
+    database::{FRIEND_USER_ID, FRIEND_USER_PAT, MOD_USER_PAT, USER_USER_ID, USER_USER_PAT},
     dummy_data::DummyOAuthClientAlpha,
     environment::{with_test_environment, TestEnvironment},
     get_json_val_str,
+    permissions::{PermissionsTest, PermissionsTestContext},
 };
 use labrinth::{
     models::{
         oauth_clients::{OAuthClient, OAuthClientCreationResult},
         pats::Scopes,
+        teams::OrganizationPermissions,
     },
     routes::v3::oauth_clients::OAuthClientEdit,
 };
@@ -200,3 +202,103 @@ async fn can_list_user_oauth_authorizations() {
     })
     .await;
 }
+
+#[actix_rt::test]
+async fn organization_oauth_clients_are_listed_by_organization() {
+    with_test_environment(None, |env: TestEnvironment<ApiV3>| async move {
+        let zeta_organization_id = env.dummy.organization_zeta.organization_id.clone();
+
+        let resp = env
+            .api
+            .add_organization_oauth_client(
+                "test_client".to_string(),
+                Scopes::all() - Scopes::restricted(),
+                vec!["https://modrinth.com".to_string()],
+                &zeta_organization_id,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let creation_result: OAuthClientCreationResult = test::read_body_json(resp).await;
+        assert_eq!(
+            Some(zeta_organization_id.clone()),
+            creation_result.client.organization_id.map(get_json_val_str)
+        );
+
+        // Organization clients are not listed alongside the creator's personal clients
+        let clients = env
+            .api
+            .get_user_oauth_clients(USER_USER_ID, USER_USER_PAT)
+            .await;
+        assert_eq!(1, clients.len());
+        assert_eq!(None, clients[0].organization_id.map(get_json_val_str));
+
+        let resp = env
+            .api
+            .get_organization_oauth_clients(USER_USER_ID, &zeta_organization_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let clients: Vec<OAuthClient> = test::read_body_json(resp).await;
+        assert_eq!(1, clients.len());
+        assert_eq!(
+            get_json_val_str(creation_result.client.id),
+            get_json_val_str(clients[0].id)
+        );
+
+        // The organization's clients aren't listed under users who aren't members of it
+        let resp = env
+            .api
+            .get_organization_oauth_clients(FRIEND_USER_ID, &zeta_organization_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        // Non-members of the organization cannot manage its clients, moderators included
+        for pat in [FRIEND_USER_PAT, MOD_USER_PAT] {
+            let resp = env
+                .api
+                .delete_oauth_client(&get_json_val_str(clients[0].id), pat)
+                .await;
+            assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        }
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn permissions_manage_organization_oauth_clients() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let manage_oauth_clients = OrganizationPermissions::MANAGE_OAUTH_CLIENTS;
+
+        // Create a client owned by the organization
+        let req_gen = |ctx: PermissionsTestContext| async move {
+            api.add_organization_oauth_client(
+                "test_client".to_string(),
+                Scopes::all() - Scopes::restricted(),
+                vec!["https://modrinth.com".to_string()],
+                &ctx.organization_id.unwrap(),
+                ctx.test_pat.as_deref(),
+            )
+            .await
+        };
+        PermissionsTest::new(&test_env)
+            .simple_organization_permissions_test(manage_oauth_clients, req_gen)
+            .await
+            .unwrap();
+
+        // List the clients owned by the organization
+        let req_gen = |ctx: PermissionsTestContext| async move {
+            api.get_organization_oauth_clients(
+                &ctx.user_id,
+                &ctx.organization_id.unwrap(),
+                ctx.test_pat.as_deref(),
+            )
+            .await
+        };
+        PermissionsTest::new(&test_env)
+            .simple_organization_permissions_test(manage_oauth_clients, req_gen)
+            .await
+            .unwrap();
+    })
+    .await;
+}
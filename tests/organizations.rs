@@ -1,22 +1,31 @@
 use crate::common::{
-    api_common::{ApiProject, ApiTeams},
+    api_common::{Api, ApiProject, ApiTeams, ApiUser, AppendsOptionalPat},
     database::{
-        generate_random_name, ADMIN_USER_PAT, ENEMY_USER_ID_PARSED, ENEMY_USER_PAT,
+        generate_random_name, ADMIN_USER_PAT, ENEMY_USER_ID, ENEMY_USER_ID_PARSED, ENEMY_USER_PAT,
         FRIEND_USER_ID_PARSED, MOD_USER_ID, MOD_USER_PAT, USER_USER_ID, USER_USER_ID_PARSED,
     },
-    dummy_data::{DummyImage, DummyOrganizationZeta, DummyProjectAlpha, DummyProjectBeta},
+    dummy_data::{
+        DummyImage, DummyOrganizationZeta, DummyProjectAlpha, DummyProjectBeta, TestFile,
+    },
 };
 use actix_http::StatusCode;
+use actix_web::test;
+use chrono::{Duration, Utc};
 use common::{
     api_v3::ApiV3,
     database::{FRIEND_USER_ID, FRIEND_USER_PAT, USER_USER_PAT},
     environment::{with_test_environment, with_test_environment_all, TestEnvironment},
     permissions::{PermissionsTest, PermissionsTestContext},
 };
+use labrinth::database::models::{User, UserId as DBUserId};
 use labrinth::models::{
-    teams::{OrganizationPermissions, ProjectPermissions},
+    notifications::{Notification, NotificationBody},
+    organizations::{OrganizationInviteLink, OrganizationRoleTemplate},
+    teams::{OrganizationPermissions, ProjectPermissions, TeamMember},
     users::UserId,
 };
+use labrinth::routes::v3::organizations::enforce_organization_2fa;
+use rust_decimal::Decimal;
 use serde_json::json;
 
 mod common;
@@ -38,22 +47,27 @@ async fn create_organization() {
         }
 
         // Failed creations slug:
-        // - slug collision with zeta
         // - too short slug
         // - too long slug
         // - not url safe slug
-        for slug in [
-            zeta_organization_slug,
-            "a",
-            &"a".repeat(100),
-            "not url safe%&^!#$##!@#$%^&*()",
-        ] {
+        for slug in ["a", &"a".repeat(100), "not url safe%&^!#$##!@#$%^&*()"] {
             let resp = api
                 .create_organization("Theta Org", slug, "theta_description", USER_USER_PAT)
                 .await;
             assert_status!(&resp, StatusCode::BAD_REQUEST);
         }
 
+        // Slug collision with zeta
+        let resp = api
+            .create_organization(
+                "Theta Org",
+                zeta_organization_slug,
+                "theta_description",
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::CONFLICT);
+
         // Failed creations description:
         // - too short desc
         // - too long desc
@@ -153,16 +167,10 @@ async fn patch_organization() {
         }
 
         // Failed patch to zeta slug:
-        // - slug collision with theta
         // - too short slug
         // - too long slug
         // - not url safe slug
-        for title in [
-            "theta",
-            "a",
-            &"a".repeat(100),
-            "not url safe%&^!#$##!@#$%^&*()",
-        ] {
+        for title in ["a", &"a".repeat(100), "not url safe%&^!#$##!@#$%^&*()"] {
             let resp = api
                 .edit_organization(
                     zeta_organization_id,
@@ -176,6 +184,19 @@ async fn patch_organization() {
             assert_status!(&resp, StatusCode::BAD_REQUEST);
         }
 
+        // Slug collision with theta
+        let resp = api
+            .edit_organization(
+                zeta_organization_id,
+                json!({
+                    "slug": "theta",
+                    "description": "theta_description"
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::CONFLICT);
+
         // Failed patch to zeta description:
         // - too short description
         // - too long description
@@ -1085,3 +1106,959 @@ async fn permissions_organization_permissions_consistency_test() {
     })
     .await;
 }
+
+#[actix_rt::test]
+async fn remove_project_can_transfer_organization_members() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let DummyProjectAlpha {
+            project_id: alpha_project_id,
+            team_id: alpha_team_id,
+            ..
+        } = &test_env.dummy.project_alpha;
+        let DummyProjectBeta {
+            project_id: beta_project_id,
+            team_id: beta_team_id,
+            ..
+        } = &test_env.dummy.project_beta;
+        let DummyOrganizationZeta {
+            organization_id: zeta_organization_id,
+            team_id: zeta_team_id,
+            ..
+        } = &test_env.dummy.organization_zeta;
+        let api = &test_env.api;
+
+        // Friend joins the organization with limited project permissions
+        let friend_permissions =
+            ProjectPermissions::EDIT_DETAILS | ProjectPermissions::UPLOAD_VERSION;
+        let resp = api
+            .add_user_to_team(
+                zeta_team_id,
+                FRIEND_USER_ID,
+                Some(friend_permissions),
+                Some(OrganizationPermissions::empty()),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(zeta_team_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        for project_id in [alpha_project_id, beta_project_id] {
+            let resp = api
+                .organization_add_project(zeta_organization_id, project_id, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+        }
+
+        // Alpha is handed to friend, and the organization's members are carried over
+        let resp = api
+            .organization_remove_project_with_members(
+                zeta_organization_id,
+                alpha_project_id,
+                UserId(FRIEND_USER_ID_PARSED as u64),
+                true,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+
+        let members = api
+            .get_team_members_deserialized(alpha_team_id, USER_USER_PAT)
+            .await;
+        assert_eq!(members.len(), 2);
+        let owners = members.iter().filter(|m| m.is_owner).collect::<Vec<_>>();
+        assert_eq!(owners.len(), 1);
+        assert_eq!(owners[0].user.id.to_string(), FRIEND_USER_ID);
+        assert_eq!(owners[0].permissions.unwrap(), ProjectPermissions::all());
+
+        // The organization owner stays on as a regular member with their effective permissions
+        let user_member = members
+            .iter()
+            .find(|m| m.user.id.to_string() == USER_USER_ID)
+            .unwrap();
+        assert!(!user_member.is_owner);
+        assert!(user_member.accepted);
+        assert_eq!(user_member.permissions.unwrap(), ProjectPermissions::all());
+
+        let resp = api
+            .edit_project(
+                alpha_project_id,
+                json!({ "summary": "Edited after leaving the organization" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Without transferring, only the new owner is left on beta
+        let resp = api
+            .organization_remove_project(
+                zeta_organization_id,
+                beta_project_id,
+                UserId(USER_USER_ID_PARSED as u64),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+
+        let members = api
+            .get_team_members_deserialized(beta_team_id, USER_USER_PAT)
+            .await;
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].user.id.to_string(), USER_USER_ID);
+        assert!(members[0].is_owner);
+
+        let resp = api
+            .edit_project(
+                beta_project_id,
+                json!({ "summary": "Edited after leaving the organization" }),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert!(resp.status().is_client_error());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn organization_project_summary() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let DummyProjectAlpha {
+            project_id: alpha_project_id,
+            ..
+        } = &test_env.dummy.project_alpha;
+        let DummyProjectBeta {
+            project_id: beta_project_id,
+            ..
+        } = &test_env.dummy.project_beta;
+        let zeta_organization_id: &str = &test_env.dummy.organization_zeta.organization_id;
+        let api = &test_env.api;
+
+        // Fetch the summary first so that it's cached
+        let organization = api
+            .get_organization_deserialized(zeta_organization_id, ENEMY_USER_PAT)
+            .await;
+        assert_eq!(organization.project_summary.unwrap().projects, 0);
+
+        // Alpha is an approved mod, beta is a mod awaiting review, and the third project is an
+        // approved modpack
+        let (modpack, _) = api
+            .add_public_project(
+                "summary-modpack",
+                Some(TestFile::build_random_mrpack()),
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        for project_id in [alpha_project_id, beta_project_id, &modpack.id.to_string()] {
+            let resp = api
+                .organization_add_project(zeta_organization_id, project_id, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+        }
+
+        // Outsiders only see the listed projects
+        let summary = api
+            .get_organization_deserialized(zeta_organization_id, ENEMY_USER_PAT)
+            .await
+            .project_summary
+            .unwrap();
+        assert_eq!(summary.projects, 2);
+        assert_eq!(summary.project_types.get("mod"), Some(&1));
+        assert_eq!(summary.project_types.get("modpack"), Some(&1));
+        assert_eq!(summary.loaders.get("fabric"), Some(&1));
+        assert!(summary.updated.is_some());
+
+        let summary = api
+            .get_organization_deserialized(zeta_organization_id, USER_USER_PAT)
+            .await
+            .project_summary
+            .unwrap();
+        assert_eq!(summary.projects, 3);
+        assert_eq!(summary.project_types.get("mod"), Some(&2));
+        assert_eq!(summary.project_types.get("modpack"), Some(&1));
+        assert_eq!(summary.loaders.get("fabric"), Some(&2));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn organization_invite_links() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let zeta_organization_id: &str = &test_env.dummy.organization_zeta.organization_id;
+        let api = &test_env.api;
+
+        // Only members who can manage invites can create links
+        let resp = api
+            .create_organization_invite_link(zeta_organization_id, json!({}), FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        // Links have to expire in the future, and within the limit
+        for expires in [
+            Utc::now() - Duration::hours(1),
+            Utc::now() + Duration::days(365),
+        ] {
+            let resp = api
+                .create_organization_invite_link(
+                    zeta_organization_id,
+                    json!({ "expires": expires }),
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::BAD_REQUEST);
+        }
+
+        let resp = api
+            .create_organization_invite_link(
+                zeta_organization_id,
+                json!({
+                    "role": "Tester",
+                    "organization_permissions": OrganizationPermissions::EDIT_DETAILS.bits(),
+                    "max_uses": 1,
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let link: OrganizationInviteLink = test::read_body_json(resp).await;
+        assert_eq!(link.uses, 0);
+
+        // Existing members can't join again
+        let resp = api
+            .join_organization(zeta_organization_id, &link.identifier, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let resp = api
+            .join_organization(zeta_organization_id, "unknown", FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let resp = api
+            .join_organization(zeta_organization_id, &link.identifier, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let members = api
+            .get_organization_members_deserialized(zeta_organization_id, USER_USER_PAT)
+            .await;
+        let friend = members
+            .iter()
+            .find(|x| x.user.id.to_string() == FRIEND_USER_ID)
+            .unwrap();
+        assert!(friend.accepted);
+        assert_eq!(friend.role, "Tester");
+        assert_eq!(
+            friend.organization_permissions,
+            Some(OrganizationPermissions::EDIT_DETAILS)
+        );
+
+        // The owner is told about the new member
+        let resp = api
+            .get_user_notifications(USER_USER_ID, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let notifications: Vec<Notification> = test::read_body_json(resp).await;
+        assert!(notifications.iter().any(|x| matches!(
+            &x.body,
+            NotificationBody::OrganizationMemberJoined { user_id, .. }
+                if user_id.to_string() == FRIEND_USER_ID
+        )));
+
+        // The link has been used up
+        let resp = api
+            .join_organization(zeta_organization_id, &link.identifier, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::GONE);
+
+        // Expired links can't be used either
+        let resp = api
+            .create_organization_invite_link(zeta_organization_id, json!({}), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let expiring_link: OrganizationInviteLink = test::read_body_json(resp).await;
+        sqlx::query("UPDATE organization_invite_links SET expires = NOW() WHERE identifier = $1")
+            .bind(&expiring_link.identifier)
+            .execute(&test_env.db.pool)
+            .await
+            .unwrap();
+        let resp = api
+            .join_organization(
+                zeta_organization_id,
+                &expiring_link.identifier,
+                ENEMY_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::GONE);
+
+        // Managing links needs the invite permission, which the new member doesn't have
+        let resp = api
+            .get_organization_invite_links(zeta_organization_id, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .get_organization_invite_links(zeta_organization_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let links: Vec<OrganizationInviteLink> = test::read_body_json(resp).await;
+        assert_eq!(links.len(), 2);
+        assert!(links.iter().any(|x| x.id == link.id && x.uses == 1));
+
+        // Revoked links stop working
+        let resp = api
+            .create_organization_invite_link(zeta_organization_id, json!({}), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let revoked_link: OrganizationInviteLink = test::read_body_json(resp).await;
+        let resp = api
+            .delete_organization_invite_link(
+                zeta_organization_id,
+                &revoked_link.id.to_string(),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .join_organization(
+                zeta_organization_id,
+                &revoked_link.identifier,
+                ENEMY_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn organization_role_templates() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let zeta_organization_id: &str = &test_env.dummy.organization_zeta.organization_id;
+        let zeta_team_id: &str = &test_env.dummy.organization_zeta.team_id;
+        let api = &test_env.api;
+
+        // Only members can see or manage the templates
+        let resp = api
+            .get_organization_role_templates(zeta_organization_id, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let resp = api
+            .create_organization_role_template(
+                zeta_organization_id,
+                json!({
+                    "name": "Editor",
+                    "description": "Edits the projects",
+                    "permissions": ProjectPermissions::EDIT_DETAILS.bits(),
+                    "organization_permissions": OrganizationPermissions::EDIT_DETAILS.bits(),
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let template: OrganizationRoleTemplate = test::read_body_json(resp).await;
+
+        let resp = api
+            .create_organization_role_template(
+                zeta_organization_id,
+                json!({ "name": "Editor" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // A template can't be given along with permissions
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/team/{zeta_team_id}/members"))
+            .append_pat(USER_USER_PAT)
+            .set_json(json!({
+                "user_id": FRIEND_USER_ID,
+                "role_template": template.id,
+                "permissions": ProjectPermissions::UPLOAD_VERSION.bits(),
+            }))
+            .to_request();
+        let resp = api.call(req).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // Inviting with a template copies its permissions
+        let req = test::TestRequest::post()
+            .uri(&format!("/v3/team/{zeta_team_id}/members"))
+            .append_pat(USER_USER_PAT)
+            .set_json(json!({
+                "user_id": FRIEND_USER_ID,
+                "role_template": template.id,
+            }))
+            .to_request();
+        let resp = api.call(req).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(zeta_team_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let get_friend = || async {
+            api.get_organization_members_deserialized(zeta_organization_id, USER_USER_PAT)
+                .await
+                .into_iter()
+                .find(|x| x.user.id.to_string() == FRIEND_USER_ID)
+                .unwrap()
+        };
+        let friend = get_friend().await;
+        assert_eq!(friend.permissions, Some(ProjectPermissions::EDIT_DETAILS));
+        assert_eq!(
+            friend.organization_permissions,
+            Some(OrganizationPermissions::EDIT_DETAILS)
+        );
+        assert_eq!(friend.role_template_id, Some(template.id));
+
+        let resp = api
+            .get_organization_role_templates(zeta_organization_id, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let templates: Vec<OrganizationRoleTemplate> = test::read_body_json(resp).await;
+        assert_eq!(templates.len(), 1);
+
+        // Members without the permission to edit members can't manage templates
+        let resp = api
+            .delete_organization_role_template(
+                zeta_organization_id,
+                &template.id.to_string(),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        // Edits only reach the members given the template when propagated
+        let resp = api
+            .edit_organization_role_template(
+                zeta_organization_id,
+                &template.id.to_string(),
+                json!({ "permissions": ProjectPermissions::UPLOAD_VERSION.bits() }),
+                false,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let friend = get_friend().await;
+        assert_eq!(friend.permissions, Some(ProjectPermissions::EDIT_DETAILS));
+
+        let new_organization_permissions =
+            OrganizationPermissions::EDIT_DETAILS | OrganizationPermissions::MANAGE_INVITES;
+        let resp = api
+            .edit_organization_role_template(
+                zeta_organization_id,
+                &template.id.to_string(),
+                json!({ "organization_permissions": new_organization_permissions.bits() }),
+                true,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let friend = get_friend().await;
+        assert_eq!(friend.permissions, Some(ProjectPermissions::UPLOAD_VERSION));
+        assert_eq!(
+            friend.organization_permissions,
+            Some(new_organization_permissions)
+        );
+
+        // Members edited by hand no longer follow the template
+        let resp = api
+            .edit_team_member(
+                zeta_team_id,
+                FRIEND_USER_ID,
+                json!({ "organization_permissions": OrganizationPermissions::EDIT_DETAILS.bits() }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let friend = get_friend().await;
+        assert_eq!(friend.role_template_id, None);
+
+        let resp = api
+            .delete_organization_role_template(
+                zeta_organization_id,
+                &template.id.to_string(),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .get_organization_role_templates(zeta_organization_id, USER_USER_PAT)
+            .await;
+        let templates: Vec<OrganizationRoleTemplate> = test::read_body_json(resp).await;
+        assert!(templates.is_empty());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn organizations_can_require_2fa() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let zeta_organization_id = &test_env.dummy.organization_zeta.organization_id;
+        let zeta_team_id = &test_env.dummy.organization_zeta.team_id;
+
+        // The friend joins before 2FA is required
+        let resp = api
+            .add_user_to_team(
+                zeta_team_id,
+                FRIEND_USER_ID,
+                None,
+                Some(OrganizationPermissions::EDIT_DETAILS),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(zeta_team_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Only users with 2FA can require it
+        let resp = api
+            .edit_organization(
+                zeta_organization_id,
+                json!({ "require_2fa": true }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        sqlx::query("UPDATE users SET totp_secret = 'TOTPSECRET' WHERE id = $1")
+            .bind(USER_USER_ID_PARSED)
+            .execute(&test_env.db.pool)
+            .await
+            .unwrap();
+        User::clear_caches(
+            &[(DBUserId(USER_USER_ID_PARSED), None)],
+            &test_env.db.redis_pool,
+        )
+        .await
+        .unwrap();
+
+        let resp = api
+            .edit_organization(
+                zeta_organization_id,
+                json!({ "require_2fa": true }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let organization = api
+            .get_organization_deserialized(zeta_organization_id, USER_USER_PAT)
+            .await;
+        assert!(organization.require_2fa);
+
+        // Users without 2FA can't be added anymore
+        let resp = api
+            .add_user_to_team(zeta_team_id, ENEMY_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // The friend and the owner are told, and the friend keeps their permissions for now
+        enforce_organization_2fa(&test_env.db.pool, &test_env.db.redis_pool)
+            .await
+            .unwrap();
+        for (user_id, pat) in [
+            (FRIEND_USER_ID, FRIEND_USER_PAT),
+            (USER_USER_ID, USER_USER_PAT),
+        ] {
+            let resp = api.get_user_notifications(user_id, pat).await;
+            assert_status!(&resp, StatusCode::OK);
+            let notifications: Vec<Notification> = test::read_body_json(resp).await;
+            assert!(notifications.iter().any(|x| matches!(
+                &x.body,
+                NotificationBody::OrganizationTwoFactorRequired { user_id, .. }
+                    if user_id.to_string() == FRIEND_USER_ID
+            )));
+        }
+
+        let resp = api
+            .edit_organization(
+                zeta_organization_id,
+                json!({ "description": "Edited before the grace period ended" }),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Once the grace period is over, the friend loses their permissions
+        sqlx::query(
+            "UPDATE team_members SET two_factor_notified = NOW() - INTERVAL '15 days' WHERE user_id = $1",
+        )
+        .bind(FRIEND_USER_ID_PARSED)
+        .execute(&test_env.db.pool)
+        .await
+        .unwrap();
+        enforce_organization_2fa(&test_env.db.pool, &test_env.db.redis_pool)
+            .await
+            .unwrap();
+
+        let members = api
+            .get_organization_members_deserialized(zeta_organization_id, USER_USER_PAT)
+            .await;
+        let friend = members
+            .iter()
+            .find(|x| x.user.id.to_string() == FRIEND_USER_ID)
+            .unwrap();
+        assert!(friend.two_factor_restricted);
+
+        let resp = api
+            .edit_organization(
+                zeta_organization_id,
+                json!({ "description": "Edited after the grace period ended" }),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        // No longer requiring 2FA gives their permissions back
+        let resp = api
+            .edit_organization(
+                zeta_organization_id,
+                json!({ "require_2fa": false }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .edit_organization(
+                zeta_organization_id,
+                json!({ "description": "Edited without 2FA being required" }),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn organization_slugs_share_namespace_with_usernames() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        // Slugs can't be taken by users, whatever their case
+        for slug in ["user", "User"] {
+            let resp = api
+                .create_organization("Theta Org", slug, "theta_description", USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::CONFLICT);
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            assert!(body["description"].as_str().unwrap().contains("user"));
+        }
+
+        let resp = api
+            .create_organization("Theta Org", "Theta", "theta_description", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let theta: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(theta["slug"], "theta");
+
+        // Nor can users take the usernames of organizations
+        let resp = api
+            .edit_user(
+                FRIEND_USER_ID,
+                json!({ "username": "theta" }),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::CONFLICT);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["description"]
+            .as_str()
+            .unwrap()
+            .contains("organization"));
+
+        // Renamed organizations are still found by their old slug
+        let resp = api
+            .edit_organization("theta", json!({ "slug": "theta-renamed" }), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        for slug in ["theta", "theta-renamed"] {
+            let organization = api.get_organization_deserialized(slug, USER_USER_PAT).await;
+            assert_eq!(organization.id.to_string(), theta["id"].as_str().unwrap());
+            assert_eq!(organization.slug, "theta-renamed");
+        }
+
+        // The old slug stays reserved for the organization
+        let resp = api
+            .create_organization("Other Theta", "theta", "theta_description", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::CONFLICT);
+        let resp = api
+            .edit_user(
+                FRIEND_USER_ID,
+                json!({ "username": "theta" }),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::CONFLICT);
+
+        // Users can still keep their own username
+        let resp = api
+            .edit_user(
+                FRIEND_USER_ID,
+                json!({ "username": "Friend" }),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn organization_announcements_are_posted_to_all_project_threads() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let zeta_organization_id = &test_env.dummy.organization_zeta.organization_id;
+        let zeta_team_id = &test_env.dummy.organization_zeta.team_id;
+        let alpha = &test_env.dummy.project_alpha;
+        let beta = &test_env.dummy.project_beta;
+
+        for project_id in [&alpha.project_id, &beta.project_id] {
+            let resp = api
+                .organization_add_project(zeta_organization_id, project_id, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+        }
+
+        // The friend is a member of both the organization and one of its projects
+        let resp = api
+            .add_user_to_team(zeta_team_id, FRIEND_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(zeta_team_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .add_user_to_team(&beta.team_id, FRIEND_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let message = "We're migrating CI this weekend";
+        let resp = api
+            .send_organization_announcement(zeta_organization_id, message, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        let resp = api
+            .send_organization_announcement(zeta_organization_id, message, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::ACCEPTED);
+        let announcement: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(announcement["thread_count"], 2);
+        let job_id = announcement["job_id"].as_str().unwrap();
+
+        // The messages are posted in the background
+        let mut job = serde_json::Value::Null;
+        for _ in 0..50 {
+            let resp = api
+                .get_organization_announcement(zeta_organization_id, job_id, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+            job = test::read_body_json(resp).await;
+            if job["status"] != "running" {
+                break;
+            }
+            actix_rt::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(job["status"], "succeeded");
+        assert_eq!(job["progress"]["current"], 2);
+
+        for thread_id in [&alpha.thread_id, &beta.thread_id] {
+            let resp = api.get_thread(thread_id, USER_USER_PAT).await;
+            assert_status!(&resp, StatusCode::OK);
+            let thread: serde_json::Value = test::read_body_json(resp).await;
+            let announcements = thread["messages"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|x| x["body"]["type"] == "organization_announcement")
+                .collect::<Vec<_>>();
+            assert_eq!(announcements.len(), 1);
+            assert_eq!(announcements[0]["body"]["body"], message);
+        }
+
+        // The friend is only notified once, and the sender isn't notified
+        for (user_id, pat, count) in [
+            (FRIEND_USER_ID, FRIEND_USER_PAT, 1),
+            (USER_USER_ID, USER_USER_PAT, 0),
+        ] {
+            let resp = api.get_user_notifications(user_id, pat).await;
+            assert_status!(&resp, StatusCode::OK);
+            let notifications: Vec<Notification> = test::read_body_json(resp).await;
+            let announcements = notifications
+                .iter()
+                .filter(|x| matches!(x.body, NotificationBody::OrganizationAnnouncement { .. }))
+                .count();
+            assert_eq!(announcements, count);
+        }
+
+        // Only one announcement can be sent an hour
+        let resp = api
+            .send_organization_announcement(zeta_organization_id, "Another one", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn organization_payout_splits_are_given_to_project_teams() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let zeta_organization_id = &test_env.dummy.organization_zeta.organization_id;
+        let zeta_team_id = &test_env.dummy.organization_zeta.team_id;
+        let alpha = &test_env.dummy.project_alpha;
+        let beta = &test_env.dummy.project_beta;
+
+        // Beta is in the organization before it has default splits, and has a member who isn't
+        // in the organization
+        let resp = api
+            .organization_add_project(zeta_organization_id, &beta.project_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let resp = api
+            .add_user_to_team(&beta.team_id, ENEMY_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(&beta.team_id, ENEMY_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = api
+            .add_user_to_team(
+                zeta_team_id,
+                FRIEND_USER_ID,
+                None,
+                Some(OrganizationPermissions::empty()),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(zeta_team_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let splits = json!([
+            { "user_id": USER_USER_ID, "weight": 60 },
+            { "user_id": FRIEND_USER_ID, "weight": 40 },
+        ]);
+        let resp = api
+            .edit_organization_payout_splits(
+                zeta_organization_id,
+                splits.clone(),
+                false,
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        for invalid in [
+            json!([{ "user_id": USER_USER_ID, "weight": 60 }]),
+            json!([
+                { "user_id": USER_USER_ID, "weight": 60 },
+                { "user_id": ENEMY_USER_ID, "weight": 40 },
+            ]),
+        ] {
+            let resp = api
+                .edit_organization_payout_splits(
+                    zeta_organization_id,
+                    invalid,
+                    false,
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::BAD_REQUEST);
+        }
+
+        let resp = api
+            .edit_organization_payout_splits(zeta_organization_id, splits, false, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Only members who can edit the splits see them
+        let organization = api
+            .get_organization_deserialized(zeta_organization_id, USER_USER_PAT)
+            .await;
+        let defaults = organization.default_payout_splits.unwrap();
+        assert_eq!(defaults.len(), 2);
+        assert_eq!(defaults[0].user_id.to_string(), USER_USER_ID);
+        assert_eq!(defaults[0].weight, Decimal::from(60));
+        let organization = api
+            .get_organization_deserialized(zeta_organization_id, FRIEND_USER_PAT)
+            .await;
+        assert!(organization.default_payout_splits.is_none());
+
+        // Projects added to the organization get the defaults, and existing projects don't
+        let resp = api
+            .organization_add_project(zeta_organization_id, &alpha.project_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+
+        let split_of = |members: &[TeamMember], user_id: &str| {
+            members
+                .iter()
+                .find(|x| x.user.id.to_string() == user_id)
+                .and_then(|x| x.payouts_split)
+        };
+        let members = api
+            .get_team_members_deserialized(&alpha.team_id, USER_USER_PAT)
+            .await;
+        assert_eq!(members.len(), 2);
+        assert_eq!(split_of(&members, USER_USER_ID), Some(Decimal::from(60)));
+        assert_eq!(split_of(&members, FRIEND_USER_ID), Some(Decimal::from(40)));
+        assert!(members.iter().all(|x| x.accepted && !x.is_owner));
+
+        let members = api
+            .get_team_members_deserialized(&beta.team_id, ENEMY_USER_PAT)
+            .await;
+        assert_eq!(members.len(), 1);
+        assert_eq!(split_of(&members, ENEMY_USER_ID), Some(Decimal::ZERO));
+
+        // Applying them to existing projects zeroes and reports the members outside the
+        // organization
+        let resp = api
+            .edit_organization_payout_splits(
+                zeta_organization_id,
+                json!([
+                    { "user_id": USER_USER_ID, "weight": 50 },
+                    { "user_id": FRIEND_USER_ID, "weight": 50 },
+                ]),
+                true,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::ACCEPTED);
+        let response: serde_json::Value = test::read_body_json(resp).await;
+        let job_id = response["job_id"].as_str().unwrap();
+
+        let mut job = serde_json::Value::Null;
+        for _ in 0..50 {
+            let resp = api
+                .get_organization_payout_splits_job(zeta_organization_id, job_id, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+            job = test::read_body_json(resp).await;
+            if job["status"] != "running" {
+                break;
+            }
+            actix_rt::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(job["status"], "succeeded");
+        assert_eq!(job["progress"]["current"], 2);
+        assert_eq!(
+            job["result"],
+            json!([{ "project_id": beta.project_id, "user_ids": [ENEMY_USER_ID] }])
+        );
+
+        for team_id in [&alpha.team_id, &beta.team_id] {
+            let members = api
+                .get_team_members_deserialized(team_id, USER_USER_PAT)
+                .await;
+            assert_eq!(split_of(&members, USER_USER_ID), Some(Decimal::from(50)));
+            assert_eq!(split_of(&members, FRIEND_USER_ID), Some(Decimal::from(50)));
+        }
+        let members = api
+            .get_team_members_deserialized(&beta.team_id, USER_USER_PAT)
+            .await;
+        assert_eq!(members.len(), 3);
+        assert_eq!(split_of(&members, ENEMY_USER_ID), Some(Decimal::ZERO));
+    })
+    .await;
+}
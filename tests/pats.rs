@@ -1,20 +1,23 @@
 use actix_http::StatusCode;
 use actix_web::test;
 use chrono::{Duration, Utc};
+use common::api_v3::ApiV3;
+use common::dummy_data::TestFile;
+use common::environment::{with_test_environment, TestEnvironment};
 use common::{database::*, environment::with_test_environment_all};
 
 use labrinth::models::pats::Scopes;
 use serde_json::json;
 
-use crate::common::api_common::AppendsOptionalPat;
+use crate::common::api_common::{ApiProject, ApiTeams, ApiVersion, AppendsOptionalPat};
 
 mod common;
 
 // Full pat test:
 // - create a PAT and ensure it can be used for the scope
 // - ensure access token is not returned for any PAT in GET
-// - ensure PAT can be patched to change scopes
 // - ensure PAT can be patched to change expiry
+// - ensure PAT scopes can be narrowed, but never widened
 // - ensure expired PATs cannot be used
 // - ensure PATs can be deleted
 #[actix_rt::test]
@@ -79,24 +82,11 @@ pub async fn pat_full_test() {
 
         assert_eq!(mock_pat_test(access_token).await, 200);
 
-        // Change scopes and test again
+        // Set expiry to the near future, and test again
         let req = test::TestRequest::patch()
             .uri(&format!("/_internal/pat/{}", id))
             .append_pat(USER_USER_PAT)
             .set_json(json!({
-                "scopes": 0,
-            }))
-            .to_request();
-        let resp = test_env.call(req).await;
-        assert_status!(&resp, StatusCode::NO_CONTENT);
-        assert_eq!(mock_pat_test(access_token).await, 401); // No longer works
-
-        // Change scopes back, and set expiry to the past, and test again
-        let req = test::TestRequest::patch()
-            .uri(&format!("/_internal/pat/{}", id))
-            .append_pat(USER_USER_PAT)
-            .set_json(json!({
-                "scopes": Scopes::COLLECTION_CREATE,
                 "expires": Utc::now() + Duration::seconds(1), // expires in 1 second
             }))
             .to_request();
@@ -130,7 +120,19 @@ pub async fn pat_full_test() {
         let resp = test_env.call(req).await;
         assert_status!(&resp, StatusCode::BAD_REQUEST);
 
-        // Similar to above with PAT creation, patching to a bad scope should fail
+        // Narrow scopes and test again
+        let req = test::TestRequest::patch()
+            .uri(&format!("/_internal/pat/{}", id))
+            .append_pat(USER_USER_PAT)
+            .set_json(json!({
+                "scopes": 0,
+            }))
+            .to_request();
+        let resp = test_env.call(req).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        assert_eq!(mock_pat_test(access_token).await, 401); // No longer works
+
+        // Scopes can't be widened again, whether they are restricted or not
         for i in 0..64 {
             let scope = Scopes::from_bits_truncate(1 << i);
             if !Scopes::all().contains(scope) {
@@ -145,10 +147,7 @@ pub async fn pat_full_test() {
                 }))
                 .to_request();
             let resp = test_env.call(req).await;
-            assert_eq!(
-                resp.status().as_u16(),
-                if scope.is_restricted() { 400 } else { 204 }
-            );
+            assert_status!(&resp, StatusCode::BAD_REQUEST);
         }
 
         // Delete PAT
@@ -267,7 +266,7 @@ pub async fn bad_pats() {
         let resp = test_env.call(req).await;
         assert_status!(&resp, StatusCode::BAD_REQUEST);
 
-        // Similar to above with PAT creation, patching to a bad scope should fail
+        // Patching to anything but the current scope widens it, which should fail
         for i in 0..64 {
             let scope = Scopes::from_bits_truncate(1 << i);
             if !Scopes::all().contains(scope) {
@@ -284,9 +283,248 @@ pub async fn bad_pats() {
             let resp = test_env.call(req).await;
             assert_eq!(
                 resp.status().as_u16(),
-                if scope.is_restricted() { 400 } else { 204 }
+                if scope == Scopes::COLLECTION_CREATE {
+                    204
+                } else {
+                    400
+                }
             );
         }
     })
     .await;
 }
+
+#[actix_rt::test]
+pub async fn pat_usage_is_recorded() {
+    with_test_environment_all(None, |test_env| async move {
+        let req = test::TestRequest::post()
+            .uri("/_internal/pat")
+            .append_pat(USER_USER_PAT)
+            .set_json(json!({
+                "scopes": Scopes::PAT_READ,
+                "name": "test_pat_usage Test",
+                "expires": Utc::now() + Duration::days(1),
+            }))
+            .to_request();
+        let resp = test_env.call(req).await;
+        assert_status!(&resp, StatusCode::OK);
+        let success: serde_json::Value = test::read_body_json(resp).await;
+        let id = success["id"].as_str().unwrap();
+        let access_token = success["access_token"].as_str().unwrap();
+
+        // Nothing is recorded before the PAT is used
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/pat/{id}/usage"))
+            .append_pat(USER_USER_PAT)
+            .to_request();
+        let resp = test_env.call(req).await;
+        assert_status!(&resp, StatusCode::OK);
+        let usage: serde_json::Value = test::read_body_json(resp).await;
+        assert!(usage.as_array().unwrap().is_empty());
+
+        for _ in 0..3 {
+            let req = test::TestRequest::get()
+                .uri("/_internal/pat")
+                .append_header(("Authorization", access_token))
+                .to_request();
+            let resp = test_env.call(req).await;
+            assert_status!(&resp, StatusCode::OK);
+        }
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/pat/{id}/usage"))
+            .append_pat(USER_USER_PAT)
+            .to_request();
+        let resp = test_env.call(req).await;
+        assert_status!(&resp, StatusCode::OK);
+        let usage: serde_json::Value = test::read_body_json(resp).await;
+        let usage = usage.as_array().unwrap();
+        assert_eq!(usage.len(), 3);
+        for record in usage {
+            assert_eq!(record["route"].as_str(), Some("/_internal/pat"));
+            assert!(record["used"].as_str().is_some());
+        }
+
+        // Other users can't see the usage of the PAT
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/pat/{id}/usage"))
+            .append_pat(FRIEND_USER_PAT)
+            .to_request();
+        let resp = test_env.call(req).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+pub async fn project_restricted_pats() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let alpha_project_id_parsed = test_env.dummy.project_alpha.project_id_parsed;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+        let beta_project_id_parsed = test_env.dummy.project_beta.project_id_parsed;
+
+        let scopes = Scopes::VERSION_CREATE
+            | Scopes::PROJECT_READ
+            | Scopes::VERSION_READ
+            | Scopes::PROJECT_WRITE
+            | Scopes::NOTIFICATION_READ;
+        let req = test::TestRequest::post()
+            .uri("/_internal/pat")
+            .append_pat(USER_USER_PAT)
+            .set_json(json!({
+                "scopes": scopes,
+                "name": "test_pat_projects Test",
+                "expires": Utc::now() + Duration::days(1),
+                "project_ids": [alpha_project_id],
+            }))
+            .to_request();
+        let resp = test_env.call(req).await;
+        assert_status!(&resp, StatusCode::OK);
+        let success: serde_json::Value = test::read_body_json(resp).await;
+        let id = success["id"].as_str().unwrap().to_string();
+        let access_token = success["access_token"].as_str().unwrap().to_string();
+
+        // Versions can only be uploaded to the project the PAT is restricted to
+        let resp = api
+            .add_public_version(
+                alpha_project_id_parsed,
+                "1.2.3",
+                TestFile::build_random_jar(),
+                None,
+                None,
+                Some(&access_token),
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let resp = api
+            .add_public_version(
+                beta_project_id_parsed,
+                "1.2.3",
+                TestFile::build_random_jar(),
+                None,
+                None,
+                Some(&access_token),
+            )
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        // The same goes for routes with the project in their path
+        let resp = api
+            .edit_project(
+                beta_project_id,
+                json!({ "summary": "Restricted edit" }),
+                Some(&access_token),
+            )
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .edit_project(
+                alpha_project_id,
+                json!({ "summary": "Restricted edit" }),
+                Some(&access_token),
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // And for routes which take the project from a list of ids or a file hash
+        for (uri, status) in [
+            (
+                format!("/v3/projects?ids=[\"{alpha_project_id}\"]"),
+                StatusCode::OK,
+            ),
+            (
+                format!("/v3/projects?ids=[\"{alpha_project_id}\",\"{beta_project_id}\"]"),
+                StatusCode::UNAUTHORIZED,
+            ),
+            (
+                format!(
+                    "/v3/version_file/{}",
+                    test_env.dummy.project_alpha.file_hash
+                ),
+                StatusCode::OK,
+            ),
+            (
+                format!("/v3/version_file/{}", test_env.dummy.project_beta.file_hash),
+                StatusCode::UNAUTHORIZED,
+            ),
+        ] {
+            let req = test::TestRequest::get()
+                .uri(&uri)
+                .append_pat(Some(&access_token))
+                .to_request();
+            let resp = test_env.call(req).await;
+            assert_status!(&resp, status);
+        }
+
+        // Routes which can't be tied to a project are refused
+        let req = test::TestRequest::post()
+            .uri("/v3/collection")
+            .append_pat(Some(&access_token))
+            .set_json(json!({ "name": "Restricted collection", "projects": [] }))
+            .to_request();
+        let resp = test_env.call(req).await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        // Routes unrelated to projects aren't affected
+        let resp = api
+            .get_user_notifications(USER_USER_ID, Some(&access_token))
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+
+        // The restriction is shown when listing PATs
+        let req = test::TestRequest::get()
+            .uri("/_internal/pat")
+            .append_pat(USER_USER_PAT)
+            .to_request();
+        let resp = test_env.call(req).await;
+        assert_status!(&resp, StatusCode::OK);
+        let pats: serde_json::Value = test::read_body_json(resp).await;
+        let pat = pats
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|x| x["id"] == json!(id))
+            .unwrap();
+        assert_eq!(pat["project_ids"], json!([alpha_project_id]));
+
+        // Neither the projects nor the scopes of the PAT can be widened
+        for patch in [
+            json!({ "project_ids": [alpha_project_id, beta_project_id] }),
+            json!({ "scopes": scopes | Scopes::PROJECT_DELETE }),
+        ] {
+            let req = test::TestRequest::patch()
+                .uri(&format!("/_internal/pat/{id}"))
+                .append_pat(USER_USER_PAT)
+                .set_json(patch)
+                .to_request();
+            let resp = test_env.call(req).await;
+            assert_status!(&resp, StatusCode::BAD_REQUEST);
+        }
+
+        // But they can be narrowed
+        let req = test::TestRequest::patch()
+            .uri(&format!("/_internal/pat/{id}"))
+            .append_pat(USER_USER_PAT)
+            .set_json(json!({
+                "project_ids": [],
+                "scopes": Scopes::VERSION_CREATE,
+            }))
+            .to_request();
+        let resp = test_env.call(req).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .add_public_version(
+                alpha_project_id_parsed,
+                "1.2.4",
+                TestFile::build_random_jar(),
+                None,
+                None,
+                Some(&access_token),
+            )
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+    })
+    .await;
+}
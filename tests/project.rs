@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use actix_http::StatusCode;
 use actix_web::test;
+use chrono::{Duration, Utc};
+use common::api_v3::request_data::get_public_project_creation_data;
 use common::api_v3::ApiV3;
 use common::database::*;
 use common::dummy_data::DUMMY_CATEGORIES;
@@ -11,15 +13,27 @@ use common::permissions::{PermissionsTest, PermissionsTestContext};
 use common::search::setup_search_projects;
 use futures::StreamExt;
 use labrinth::database::models::project_item::{PROJECTS_NAMESPACE, PROJECTS_SLUGS_NAMESPACE};
+use labrinth::database::redis::missing_namespace;
+use labrinth::file_hosting::MockHost;
+use labrinth::models::audit_log::AuditLogEntry;
 use labrinth::models::ids::base62_impl::parse_base62;
-use labrinth::models::projects::{Project, ProjectId};
+use labrinth::models::notifications::{Notification, NotificationBody};
+use labrinth::models::project_history::ProjectFieldChange;
+use labrinth::models::project_transfers::{ProjectTransfer, ProjectTransferStatus};
+use labrinth::models::projects::{Project, ProjectId, ProjectStatus};
 use labrinth::models::teams::ProjectPermissions;
-use labrinth::util::actix::{MultipartSegment, MultipartSegmentData};
+use labrinth::models::threads::{MessageBody, Thread};
+use labrinth::models::users::{UserId, AUTOMOD_USER};
+use labrinth::routes::v3::images::publish_private_images;
+use labrinth::routes::v3::projects::{
+    list_scheduled_projects, purge_deleted_projects, ScheduledGoLive,
+};
+use labrinth::util::actix::{AppendsMultipart, MultipartSegment, MultipartSegmentData};
 use serde_json::json;
 
 use crate::common::api_common::models::{CommonItemType, CommonProject};
 use crate::common::api_common::request_data::ProjectCreationRequestData;
-use crate::common::api_common::{ApiProject, ApiTeams, ApiVersion};
+use crate::common::api_common::{Api, ApiProject, ApiTeams, ApiVersion, AppendsOptionalPat};
 use crate::common::dummy_data::{
     DummyImage, DummyOrganizationZeta, DummyProjectAlpha, DummyProjectBeta, TestFile,
 };
@@ -93,6 +107,45 @@ async fn test_get_project() {
     .await;
 }
 
+#[actix_rt::test]
+async fn missing_projects_are_found_once_created() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        // Looking up a project which doesn't exist caches its slug as missing
+        let resp = api.get_project("latecomer", USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let mut redis_pool = test_env.db.redis_pool.connect().await.unwrap();
+        let missing_slugs = missing_namespace(PROJECTS_SLUGS_NAMESPACE);
+        assert!(redis_pool
+            .get(&missing_slugs, "latecomer")
+            .await
+            .unwrap()
+            .is_some());
+
+        // Creating a project with the slug forgets that it was missing
+        let (project, _) = api
+            .add_public_project(
+                "latecomer",
+                Some(TestFile::build_random_jar()),
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert!(redis_pool
+            .get(&missing_slugs, "latecomer")
+            .await
+            .unwrap()
+            .is_none());
+
+        let resp = api.get_project("latecomer", USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["id"], json!(project.id));
+    })
+    .await;
+}
+
 #[actix_rt::test]
 async fn test_add_remove_project() {
     // Test setup and dummy data
@@ -127,6 +180,13 @@ async fn test_add_remove_project() {
             ..json_segment.clone()
         };
 
+        // Basic json, with a different file, and another different slug
+        json_data["slug"] = json!("reupload_demo");
+        let json_reupload_segment = MultipartSegment {
+            data: MultipartSegmentData::Text(serde_json::to_string(&json_data).unwrap()),
+            ..json_segment.clone()
+        };
+
         let basic_mod_file = TestFile::BasicMod;
         let basic_mod_different_file = TestFile::BasicModDifferent;
 
@@ -188,14 +248,14 @@ async fn test_add_remove_project() {
             .await;
         assert_eq!(version.id, uploaded_version_id);
 
-        // Reusing with a different slug and the same file should fail
-        // Even if that file is named differently
+        // Reusing the same file in another project of the same user is allowed, and isn't
+        // flagged as a reupload, even if that file is named differently
         let resp = api
             .create_project(
                 ProjectCreationRequestData {
                     slug: "demo".to_string(),
                     segment_data: vec![
-                        json_diff_slug_file_segment.clone(),
+                        json_reupload_segment.clone(),
                         file_diff_name_segment.clone(),
                     ],
                     jar: None, // File not needed at this point
@@ -203,7 +263,14 @@ async fn test_add_remove_project() {
                 USER_USER_PAT,
             )
             .await;
-        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        assert_status!(&resp, StatusCode::OK);
+        let project = api
+            .get_project_deserialized_common("reupload_demo", USER_USER_PAT)
+            .await;
+        let version = api
+            .get_version_deserialized(&project.versions[0].to_string(), USER_USER_PAT)
+            .await;
+        assert_eq!(version.flagged_reupload, Some(false));
 
         // Reusing with the same slug and a different file should fail
         let resp = api
@@ -407,9 +474,11 @@ pub async fn test_patch_project() {
             .await;
         assert_status!(&resp, StatusCode::NO_CONTENT);
 
-        // Old slug no longer works
-        let resp = api.get_project(alpha_project_slug, USER_USER_PAT).await;
-        assert_status!(&resp, StatusCode::NOT_FOUND);
+        // Old slug now resolves to the renamed project
+        let project = api
+            .get_project_deserialized(alpha_project_slug, USER_USER_PAT)
+            .await;
+        assert_eq!(project.slug.unwrap(), "newslug");
 
         // New slug does work
         let project = api.get_project_deserialized("newslug", USER_USER_PAT).await;
@@ -646,6 +715,14 @@ async fn delete_project_with_report() {
             None
         );
 
+        // Reports are kept until the project is removed for good
+        let resp = api.get_report(alpha_report_id, ENEMY_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        expire_deleted_project(&test_env, alpha_project_id).await;
+        purge_deleted_projects(&test_env.db.pool, &test_env.db.redis_pool)
+            .await
+            .unwrap();
+
         // Report for alpha no longer exists
         let resp = api
             .get_report(
@@ -667,6 +744,85 @@ async fn delete_project_with_report() {
     .await;
 }
 
+// Moves a deleted project's deletion back past the restore window
+async fn expire_deleted_project(test_env: &TestEnvironment<ApiV3>, project_id: &str) {
+    sqlx::query("UPDATE mods SET deleted_at = NOW() - INTERVAL '31 days' WHERE id = $1")
+        .bind(parse_base62(project_id).unwrap() as i64)
+        .execute(&test_env.db.pool)
+        .await
+        .unwrap();
+}
+
+#[actix_rt::test]
+async fn deleted_projects_can_be_restored() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id: &str = &test_env.dummy.project_alpha.project_id;
+        let alpha_project_id_parsed = test_env.dummy.project_alpha.project_id_parsed;
+        let alpha_version_id = &test_env.dummy.project_alpha.version_id;
+
+        // Projects are indexed in the background, so wait for them to show up or drop out
+        let in_search = |expected: bool| async move {
+            let mut found = !expected;
+            for _ in 0..20 {
+                let projects = api.search_deserialized(None, None, USER_USER_PAT).await;
+                found = projects
+                    .hits
+                    .iter()
+                    .any(|p| p.id == alpha_project_id_parsed);
+                if found == expected {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+            }
+            found
+        };
+        assert!(in_search(true).await);
+
+        let resp = api.remove_project(alpha_project_id, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // The project is gone for everyone, including its team
+        for pat in [USER_USER_PAT, ADMIN_USER_PAT] {
+            let resp = api.get_project(alpha_project_id, pat).await;
+            assert_status!(&resp, StatusCode::NOT_FOUND);
+        }
+        let resp = api.get_version(alpha_version_id, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        assert!(!in_search(false).await);
+
+        // Only the former owner can restore it
+        let resp = api.restore_project(alpha_project_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let resp = api.restore_project(alpha_project_id, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let project = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(project.status, ProjectStatus::Approved);
+        let resp = api.get_version(alpha_version_id, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        assert!(in_search(true).await);
+
+        // Once the restore window has passed, the project can't be restored and is removed for good
+        let resp = api.remove_project(alpha_project_id, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        expire_deleted_project(&test_env, alpha_project_id).await;
+        let resp = api.restore_project(alpha_project_id, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        purge_deleted_projects(&test_env.db.pool, &test_env.db.redis_pool)
+            .await
+            .unwrap();
+        let resp = api.restore_project(alpha_project_id, ADMIN_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let resp = api.get_version(alpha_version_id, ADMIN_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+    })
+    .await;
+}
+
 #[actix_rt::test]
 async fn permissions_patch_project_v3() {
     with_test_environment(Some(8), |test_env: TestEnvironment<ApiV3>| async move {
@@ -1155,6 +1311,124 @@ async fn permissions_delete_project() {
     .await;
 }
 
+#[actix_rt::test]
+async fn test_project_webhooks() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+
+        let resp = api
+            .add_project_webhook(
+                alpha_project_id,
+                "https://example.com/hooks/modrinth",
+                "a-sufficiently-long-secret",
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let webhook: serde_json::Value = test::read_body_json(resp).await;
+        assert!(webhook.get("secret").is_none());
+        let webhook_id = webhook["id"].as_str().unwrap();
+
+        // Secrets that are too short and invalid URLs are rejected
+        let resp = api
+            .add_project_webhook(
+                alpha_project_id,
+                "https://example.com/hooks/modrinth",
+                "short",
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let resp = api
+            .add_project_webhook(
+                alpha_project_id,
+                "not a url",
+                "a-sufficiently-long-secret",
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // Webhooks have to be https, and can't point at private addresses
+        for url in [
+            "http://example.com/hooks/modrinth",
+            "https://localhost/hooks/modrinth",
+            "https://127.0.0.1/hooks/modrinth",
+            "https://169.254.169.254/latest/meta-data",
+            "https://[::1]/hooks/modrinth",
+        ] {
+            let resp = api
+                .add_project_webhook(
+                    alpha_project_id,
+                    url,
+                    "a-sufficiently-long-secret",
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::BAD_REQUEST);
+        }
+
+        let webhooks = api
+            .get_project_webhooks_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(webhooks.len(), 1);
+        assert_eq!(webhooks[0].url, "https://example.com/hooks/modrinth");
+        assert!(webhooks[0].last_attempt.is_none());
+        assert!(webhooks[0].last_error.is_none());
+
+        // Webhooks can only be removed through the project they belong to
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+        let resp = api
+            .remove_project_webhook(beta_project_id, webhook_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let resp = api
+            .remove_project_webhook(alpha_project_id, webhook_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let webhooks = api
+            .get_project_webhooks_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        assert!(webhooks.is_empty());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn permissions_manage_webhooks() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let manage_webhooks = ProjectPermissions::MANAGE_WEBHOOKS;
+        let api = &test_env.api;
+
+        let req_gen = |ctx: PermissionsTestContext| async move {
+            api.add_project_webhook(
+                &ctx.project_id.unwrap(),
+                "https://example.com/hooks/modrinth",
+                "a-sufficiently-long-secret",
+                ctx.test_pat.as_deref(),
+            )
+            .await
+        };
+        PermissionsTest::new(&test_env)
+            .simple_project_permissions_test(manage_webhooks, req_gen)
+            .await
+            .unwrap();
+
+        let req_gen = |ctx: PermissionsTestContext| async move {
+            api.get_project_webhooks(&ctx.project_id.unwrap(), ctx.test_pat.as_deref())
+                .await
+        };
+        PermissionsTest::new(&test_env)
+            .simple_project_permissions_test(manage_webhooks, req_gen)
+            .await
+            .unwrap();
+    })
+    .await;
+}
+
 #[actix_rt::test]
 async fn project_permissions_consistency_test() {
     with_test_environment_all(Some(10), |test_env| async move {
@@ -1389,3 +1663,1873 @@ async fn projects_various_visibility() {
 // Permissions:
 // TODO: permissions VIEW_PAYOUTS currently is unused. Add tests when it is used.
 // TODO: permissions VIEW_ANALYTICS currently is unused. Add tests when it is used.
+
+#[actix_rt::test]
+async fn reorder_and_feature_gallery_items() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let api = &test_env.api;
+
+        for i in 0..3u8 {
+            // Each image needs distinct bytes, as duplicate images are rejected
+            let mut image = DummyImage::SmallIcon.get_icon_data();
+            image.icon.push(i);
+            let resp = api
+                .add_gallery_item(
+                    alpha_project_id,
+                    image,
+                    i == 0,
+                    None,
+                    None,
+                    None,
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+
+        let project = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(project.gallery.len(), 3);
+        let featured_url = project
+            .gallery
+            .iter()
+            .find(|x| x.featured)
+            .unwrap()
+            .url
+            .clone();
+        let mut urls = project
+            .gallery
+            .iter()
+            .map(|x| x.url.clone())
+            .collect::<Vec<_>>();
+        urls.reverse();
+        let urls = urls.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+
+        // The new order must include every image exactly once
+        let resp = api
+            .edit_gallery_order(alpha_project_id, &urls[..2], USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let resp = api
+            .edit_gallery_order(alpha_project_id, &urls, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        let resp = api
+            .edit_gallery_order(alpha_project_id, &urls, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Feature a different image and edit its metadata in place
+        let new_featured_url = urls
+            .iter()
+            .find(|x| **x != featured_url)
+            .unwrap()
+            .to_string();
+        let resp = api
+            .edit_gallery_item(
+                alpha_project_id,
+                &new_featured_url,
+                HashMap::from([
+                    ("featured".to_string(), "true".to_string()),
+                    ("name".to_string(), "New featured image".to_string()),
+                ]),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let project = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(
+            project
+                .gallery
+                .iter()
+                .map(|x| x.url.as_str())
+                .collect::<Vec<_>>(),
+            urls
+        );
+        let featured = project
+            .gallery
+            .iter()
+            .filter(|x| x.featured)
+            .collect::<Vec<_>>();
+        assert_eq!(featured.len(), 1);
+        assert_eq!(featured[0].url, new_featured_url);
+        assert_eq!(featured[0].name.as_deref(), Some("New featured image"));
+
+        // Items can also be edited by the id of their image
+        let image_id = featured[0].image_id.unwrap().to_string();
+        let resp = api
+            .edit_gallery_item_by_id(
+                alpha_project_id,
+                &image_id,
+                json!({ "description": "Edited by id", "name": null }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .edit_gallery_item_by_id(
+                alpha_project_id,
+                &image_id,
+                json!({ "description": "Edited by id" }),
+                ENEMY_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .edit_gallery_item_by_id(
+                alpha_project_id,
+                "zzzzzzzz",
+                json!({ "description": "Edited by id" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let project = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        let item = project
+            .gallery
+            .iter()
+            .find(|x| x.url == new_featured_url)
+            .unwrap();
+        assert!(item.featured);
+        assert_eq!(item.name, None);
+        assert_eq!(item.description.as_deref(), Some("Edited by id"));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn uploaded_images_get_resized_variants() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let api = &test_env.api;
+
+        let cdn_url = dotenvy::var("CDN_URL").unwrap();
+        let mock_file_path = dotenvy::var("MOCK_FILE_PATH").unwrap();
+        let stored_file = |url: &str| {
+            std::path::Path::new(&mock_file_path)
+                .join(url.strip_prefix(&format!("{cdn_url}/")).unwrap())
+        };
+
+        let resp = api
+            .edit_project_icon(
+                alpha_project_id,
+                Some(DummyImage::SmallIcon.get_icon_data()),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .add_gallery_item(
+                alpha_project_id,
+                DummyImage::SmallIcon.get_icon_data(),
+                true,
+                None,
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // The 200x200 image is only downscaled to the variant widths smaller than itself
+        let project = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        let icon_url = project.icon_url.unwrap();
+        assert_eq!(project.icon_variants.keys().collect::<Vec<_>>(), vec!["64"]);
+        assert!(project.icon_variants["64"].ends_with("_64.webp"));
+        assert!(stored_file(&icon_url).exists());
+        let variant = std::fs::read(stored_file(&project.icon_variants["64"])).unwrap();
+        assert_eq!(&variant[8..12], b"WEBP");
+
+        let gallery_item = &project.gallery[0];
+        assert_eq!(gallery_item.variants.keys().collect::<Vec<_>>(), vec!["64"]);
+        assert!(stored_file(&gallery_item.url).exists());
+        assert!(gallery_item.variants["64"].ends_with("_64.webp"));
+        assert!(stored_file(&gallery_item.variants["64"]).exists());
+
+        // Removing the icon removes its variants as well
+        let icon_variant = project.icon_variants["64"].clone();
+        let resp = api
+            .edit_project_icon(alpha_project_id, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        assert!(!stored_file(&icon_variant).exists());
+        let project = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        assert!(project.icon_url.is_none());
+        assert!(project.icon_variants.is_empty());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn images_from_new_accounts_wait_for_approval() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let api = &test_env.api;
+
+        // The dummy accounts were just created, so their gallery images are pending
+        let resp = api
+            .add_gallery_item(
+                alpha_project_id,
+                DummyImage::SmallIcon.get_icon_data(),
+                true,
+                None,
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let project = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        let gallery_url = project.gallery[0].url.clone();
+        for pat in [None, ENEMY_USER_PAT] {
+            let project = api.get_project_deserialized(alpha_project_id, pat).await;
+            assert!(project.gallery.is_empty());
+        }
+
+        let queue = api
+            .get_image_moderation_queue_deserialized(MOD_USER_PAT)
+            .await;
+        assert_eq!(queue.total, 1);
+        assert_eq!(queue.images[0].image.url, gallery_url);
+        assert_eq!(
+            queue.images[0].project.as_ref().unwrap().id.to_string(),
+            *alpha_project_id
+        );
+        let image_id = queue.images[0].image.id.to_string();
+
+        // Only moderators can approve images
+        let resp = api
+            .edit_image_moderation(&image_id, "approved", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .edit_image_moderation(&image_id, "approved", MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let project = api.get_project_deserialized(alpha_project_id, None).await;
+        assert_eq!(project.gallery[0].url, gallery_url);
+        let queue = api
+            .get_image_moderation_queue_deserialized(MOD_USER_PAT)
+            .await;
+        assert_eq!(queue.total, 0);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn quarantined_images_are_hidden_and_replaced() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let api = &test_env.api;
+
+        let cdn_url = dotenvy::var("CDN_URL").unwrap();
+        let mock_file_path = dotenvy::var("MOCK_FILE_PATH").unwrap();
+
+        let resp = api
+            .edit_project_icon(
+                alpha_project_id,
+                Some(DummyImage::SmallIcon.get_icon_data()),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let icon_url = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await
+            .icon_url
+            .unwrap();
+        assert!(api
+            .get_project_deserialized(alpha_project_id, None)
+            .await
+            .icon_url
+            .is_none());
+
+        let queue = api
+            .get_image_moderation_queue_deserialized(MOD_USER_PAT)
+            .await;
+        let image_id = queue.images[0].image.id.to_string();
+        let resp = api
+            .edit_image_moderation(&image_id, "quarantined", MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Quarantined images are hidden from the project's team as well
+        let project = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        assert!(project.icon_url.is_none());
+        let stored_file = std::path::Path::new(&mock_file_path)
+            .join(icon_url.strip_prefix(&format!("{cdn_url}/")).unwrap());
+        assert_eq!(
+            std::fs::read(stored_file).unwrap(),
+            include_bytes!("../assets/images/quarantined.png")
+        );
+
+        // The files were replaced, so the image can't be approved anymore
+        let resp = api
+            .edit_image_moderation(&image_id, "approved", MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn project_etag_conditional_requests() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let api = &test_env.api;
+
+        let get_project = |pat: Option<&'static str>, etag: Option<String>| {
+            let mut req = test::TestRequest::get()
+                .uri(&format!("/v3/project/{alpha_project_id}"))
+                .append_pat(pat);
+            if let Some(etag) = etag {
+                req = req.insert_header(("If-None-Match", etag));
+            }
+            test_env.call(req.to_request())
+        };
+        let etag_of = |resp: &actix_web::dev::ServiceResponse| {
+            resp.headers()
+                .get("ETag")
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+
+        let resp = get_project(USER_USER_PAT, None).await;
+        assert_status!(&resp, StatusCode::OK);
+        let etag = etag_of(&resp);
+
+        // Replaying the request with the ETag returns no body
+        let resp = get_project(USER_USER_PAT, Some(etag.clone())).await;
+        assert_status!(&resp, StatusCode::NOT_MODIFIED);
+        assert_eq!(etag_of(&resp), etag);
+        assert!(test::read_body(resp).await.is_empty());
+
+        // Users outside of the team get a different ETag
+        let resp = get_project(ENEMY_USER_PAT, Some(etag.clone())).await;
+        assert_status!(&resp, StatusCode::OK);
+        assert_ne!(etag_of(&resp), etag);
+
+        // Editing the project changes the ETag
+        let resp = api
+            .edit_project(
+                alpha_project_id,
+                json!({ "summary": "A new summary for the project" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = get_project(USER_USER_PAT, Some(etag.clone())).await;
+        assert_status!(&resp, StatusCode::OK);
+        assert_ne!(etag_of(&resp), etag);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn project_fields_projection() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+        let api = &test_env.api;
+
+        let keys_of = |value: &serde_json::Value| {
+            let mut keys = value
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+
+        // The id is always included, and unknown fields are ignored
+        let resp = api
+            .get_project_fields(alpha_project_id, "name,downloads,unknown", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(keys_of(&body), ["downloads", "id", "name"]);
+        assert_eq!(body["id"], json!(alpha_project_id));
+
+        let resp = api
+            .get_projects_fields(
+                &[alpha_project_id.as_str(), beta_project_id.as_str()],
+                "icon_url",
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let body: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        assert_eq!(body.len(), 2);
+        for project in &body {
+            assert_eq!(keys_of(project), ["icon_url", "id"]);
+        }
+
+        // Pruned responses don't share the full response's ETag
+        let full_etag = api
+            .get_project(alpha_project_id, USER_USER_PAT)
+            .await
+            .headers()
+            .get("ETag")
+            .cloned();
+        let resp = api
+            .get_project_fields(alpha_project_id, "name", USER_USER_PAT)
+            .await;
+        assert_ne!(resp.headers().get("ETag").cloned(), full_etag);
+
+        let resp = api
+            .get_project_fields(alpha_project_id, "", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn moderation_queue_ordering_and_pagination() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        // Beta is already awaiting review, submit a few more projects after it
+        let mut queued = vec![test_env.dummy.project_beta.project_id.clone()];
+        for slug in ["queued-one", "queued-two", "queued-three"] {
+            let creation_data =
+                get_public_project_creation_data(slug, Some(TestFile::build_random_jar()), None);
+            let resp = api.create_project(creation_data, USER_USER_PAT).await;
+            assert_status!(&resp, StatusCode::OK);
+            let project: Project = test::read_body_json(resp).await;
+            queued.push(project.id.to_string());
+        }
+
+        let queue_ids = |query: &'static str| async move {
+            let queue = api
+                .get_moderation_queue_deserialized(query, MOD_USER_PAT)
+                .await;
+            let ids = queue
+                .projects
+                .iter()
+                .map(|x| x.project.id.to_string())
+                .collect::<Vec<_>>();
+            (queue.total, ids)
+        };
+
+        assert_eq!(queue_ids("").await, (4, queued.clone()));
+        let mut newest = queued.clone();
+        newest.reverse();
+        assert_eq!(queue_ids("sort=newest").await, (4, newest));
+
+        // The total covers the whole queue rather than the page
+        assert_eq!(
+            queue_ids("count=2&offset=1").await,
+            (4, queued[1..3].to_vec())
+        );
+        assert_eq!(queue_ids("offset=10").await, (4, vec![]));
+        assert_eq!(queue_ids("project_type=modpack").await, (0, vec![]));
+
+        sqlx::query("UPDATE mods SET downloads = 100 WHERE slug = 'queued-two'")
+            .execute(&test_env.db.pool)
+            .await
+            .unwrap();
+        let (_, ids) = queue_ids("sort=downloads").await;
+        assert_eq!(ids, [2, 0, 1, 3].map(|i| queued[i].clone()).to_vec());
+
+        // Reports against the team's members are counted as a risk hint
+        let resp = api
+            .create_report(
+                "spam",
+                USER_USER_ID,
+                CommonItemType::User,
+                "This user keeps uploading spam!",
+                ENEMY_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let queue = api
+            .get_moderation_queue_deserialized("", MOD_USER_PAT)
+            .await;
+        assert!(queue.projects.iter().all(|x| x.open_reports == 1));
+
+        // Only moderators can see the queue
+        let resp = api.get_moderation_queue("", USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn archived_projects_are_read_only() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let alpha_project_id_parsed = test_env.dummy.project_alpha.project_id_parsed;
+
+        let resp = api
+            .edit_project(
+                alpha_project_id,
+                json!({ "status": "archived" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Archived projects stay visible
+        let project = api.get_project_deserialized(alpha_project_id, None).await;
+        assert_eq!(project.status, ProjectStatus::Archived);
+
+        let resp = api
+            .add_public_version(
+                alpha_project_id_parsed,
+                "1.2.3",
+                TestFile::build_random_jar(),
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let resp = api
+            .edit_project(
+                alpha_project_id,
+                json!({ "status": "approved" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = api
+            .add_public_version(
+                alpha_project_id_parsed,
+                "1.2.3",
+                TestFile::build_random_jar(),
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn project_description_imported_from_readme() {
+    // A local server stands in for the README host so no real network calls are made
+    let readme = actix_web::web::Data::new(std::sync::Mutex::new(
+        "# Alpha\r\n\r\nImported from a README".to_string(),
+    ));
+    let server_readme = readme.clone();
+    let server = actix_web::HttpServer::new(move || {
+        actix_web::App::new().app_data(server_readme.clone()).route(
+            "/README.md",
+            actix_web::web::get().to(
+                |readme: actix_web::web::Data<std::sync::Mutex<String>>| async move {
+                    readme.lock().unwrap().clone()
+                },
+            ),
+        )
+    })
+    .workers(1)
+    .bind(("127.0.0.1", 0))
+    .unwrap();
+    let readme_url = format!("http://127.0.0.1:{}/README.md", server.addrs()[0].port());
+    let server = server.run();
+    let server_handle = server.handle();
+    actix_rt::spawn(server);
+
+    // Nothing listens on a port that was just freed
+    let closed_port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let unreachable_url = format!("http://127.0.0.1:{closed_port}/README.md");
+
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let source = |url: &str| -> Option<json_patch::Patch> {
+            Some(
+                serde_json::from_value(json!([
+                    { "op": "add", "path": "/description_source_url", "value": url },
+                ]))
+                .unwrap(),
+            )
+        };
+
+        let creation_data = get_public_project_creation_data("imported", None, source(&readme_url));
+        let resp = api.create_project(creation_data, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let project: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(project["description"], "# Alpha\n\nImported from a README");
+        assert_eq!(project["description_source_url"], readme_url.as_str());
+        assert!(project["description_fetched"].is_string());
+        assert!(project.get("warning").is_none());
+        let project_id = project["id"].as_str().unwrap().to_string();
+
+        // Network failures don't stop the project from being created
+        let creation_data =
+            get_public_project_creation_data("unreachable", None, source(&unreachable_url));
+        let resp = api.create_project(creation_data, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let project: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(project["description"], "");
+        assert!(project["description_fetched"].is_null());
+        assert!(project["warning"].is_string());
+
+        // Only allow-listed hosts can be imported from
+        let creation_data = get_public_project_creation_data(
+            "not-allowed",
+            None,
+            source("https://example.com/README.md"),
+        );
+        let resp = api.create_project(creation_data, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        *readme.lock().unwrap() = "# Alpha\n\nUpdated".to_string();
+
+        let resp = api
+            .refresh_project_description(&project_id, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        let resp = api
+            .refresh_project_description(&project_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let project = api
+            .get_project_deserialized(&project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(project.description, "# Alpha\n\nUpdated");
+
+        // Removing the source keeps the description, but it can't be refreshed anymore
+        let resp = api
+            .edit_project(
+                &project_id,
+                json!({ "description_source_url": null }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let project = api
+            .get_project_deserialized(&project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(project.description, "# Alpha\n\nUpdated");
+        assert!(project.description_source_url.is_none());
+
+        let resp = api
+            .refresh_project_description(&project_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // Setting it through an edit imports the README right away
+        *readme.lock().unwrap() = "# Alpha\n\nEdited".to_string();
+        let resp = api
+            .edit_project(
+                &project_id,
+                json!({ "description_source_url": readme_url }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let project = api
+            .get_project_deserialized(&project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(project.description, "# Alpha\n\nEdited");
+        assert!(project.description_fetched.is_some());
+    })
+    .await;
+
+    server_handle.stop(true).await;
+}
+
+#[actix_rt::test]
+async fn old_project_slugs_redirect_and_stay_reserved() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let alpha_project_slug = &test_env.dummy.project_alpha.project_slug;
+
+        // Fill the cache with the old slug before renaming
+        let resp = api.get_project(alpha_project_slug, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+
+        let resp = api
+            .edit_project(
+                alpha_project_slug,
+                json!({ "slug": "renamed-alpha" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // The old slug still resolves, and says where the project went
+        let resp = api.get_project(alpha_project_slug, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        assert_eq!(
+            resp.headers().get("X-Slug-Moved-To").unwrap(),
+            "renamed-alpha"
+        );
+        let project: Project = test::read_body_json(resp).await;
+        assert_eq!(&project.id.to_string(), alpha_project_id);
+
+        // The current slug doesn't get the header
+        let resp = api.get_project("renamed-alpha", USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        assert!(resp.headers().get("X-Slug-Moved-To").is_none());
+
+        // Or redirects there when asked to
+        let req = test::TestRequest::get()
+            .uri(&format!("/v3/project/{alpha_project_slug}?redirect=true"))
+            .append_pat(USER_USER_PAT)
+            .to_request();
+        let resp = api.call(req).await;
+        assert_status!(&resp, StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            resp.headers().get("Location").unwrap(),
+            "/v3/project/renamed-alpha?redirect=true"
+        );
+
+        // Another project can't take the old slug while it's reserved
+        let creation_data = get_public_project_creation_data(
+            alpha_project_slug,
+            Some(TestFile::build_random_jar()),
+            None,
+        );
+        let resp = api.create_project(creation_data, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::CONFLICT);
+
+        // But the project that gave it up can have it back
+        let resp = api
+            .edit_project(
+                "renamed-alpha",
+                json!({ "slug": alpha_project_slug }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.get_project(alpha_project_slug, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        assert!(resp.headers().get("X-Slug-Moved-To").is_none());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn project_descriptions_are_sanitized() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let description = "# Trailer\n\n<iframe src=\"https://www.youtube.com/embed/abc\"></iframe>\n\n<img src=\"x.png\" onerror=\"alert(1)\">\n\n[Click](javascript:alert(1))";
+        let with_description = |description: &str| -> Option<json_patch::Patch> {
+            Some(
+                serde_json::from_value(json!([
+                    { "op": "add", "path": "/description", "value": description },
+                ]))
+                .unwrap(),
+            )
+        };
+
+        // Strict mode rejects the body, describing what would have been removed
+        let creation_data =
+            get_public_project_creation_data("sanitized", None, with_description(description));
+        let req = test::TestRequest::post()
+            .uri("/v3/project?strict=true")
+            .append_pat(USER_USER_PAT)
+            .set_multipart(creation_data.segment_data)
+            .to_request();
+        let resp = api.call(req).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let error: serde_json::Value = test::read_body_json(resp).await;
+        let error = error["description"].as_str().unwrap();
+        assert!(error.contains("`onerror` attribute on <img>"));
+        assert!(error.contains("unsafe link to `javascript`"));
+
+        // Otherwise the sanitized body is what's stored
+        let creation_data =
+            get_public_project_creation_data("sanitized", None, with_description(description));
+        let resp = api.create_project(creation_data, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+
+        let project = api
+            .get_project_deserialized("sanitized", USER_USER_PAT)
+            .await;
+        assert_eq!(
+            project.description,
+            "# Trailer\n\n<iframe src=\"https://www.youtube.com/embed/abc\" sandbox=\"allow-scripts allow-same-origin allow-popups allow-presentation\"></iframe>\n\n<img src=\"x.png\">\n\nClick"
+        );
+
+        // Edits are sanitized the same way
+        let resp = api
+            .edit_project(
+                "sanitized",
+                json!({ "description": "Fixed <script>alert(1)</script> crash" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let project = api
+            .get_project_deserialized("sanitized", USER_USER_PAT)
+            .await;
+        assert_eq!(project.description, "Fixed alert(1) crash");
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn project_limits_cap_creation() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let zeta_id = &test_env.dummy.organization_zeta.organization_id;
+
+        // Only admins can change limits
+        let resp = api
+            .edit_user_limits(
+                FRIEND_USER_ID,
+                json!({ "project_limit": 1 }),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .edit_user_limits(
+                FRIEND_USER_ID,
+                json!({ "project_limit": 1 }),
+                ADMIN_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let creation_data = get_public_project_creation_data("limited-one", None, None);
+        let resp = api.create_project(creation_data, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+
+        // The limit is reached
+        let creation_data = get_public_project_creation_data("limited-two", None, None);
+        let resp = api.create_project(creation_data, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let error: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(error["error"], "project_limit_reached");
+
+        // Raising the override lets the user create another one
+        let resp = api
+            .edit_user_limits(
+                FRIEND_USER_ID,
+                json!({ "project_limit": 2 }),
+                ADMIN_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let creation_data = get_public_project_creation_data("limited-two", None, None);
+        let resp = api.create_project(creation_data, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+
+        // Organizations are limited separately
+        let resp = api
+            .edit_organization_limits(zeta_id, json!({ "project_limit": 0 }), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .edit_organization_limits(zeta_id, json!({ "project_limit": 0 }), ADMIN_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let in_zeta = || -> Option<json_patch::Patch> {
+            Some(
+                serde_json::from_value(json!([
+                    { "op": "add", "path": "/organization_id", "value": zeta_id },
+                ]))
+                .unwrap(),
+            )
+        };
+        let creation_data = get_public_project_creation_data("limited-org", None, in_zeta());
+        let resp = api.create_project(creation_data, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // Moderators aren't limited
+        let resp = api
+            .edit_user_limits(MOD_USER_ID, json!({ "project_limit": 0 }), ADMIN_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let creation_data = get_public_project_creation_data("limited-mod", None, None);
+        let resp = api.create_project(creation_data, MOD_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn project_links_are_validated() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_slug = &test_env.dummy.project_alpha.project_slug;
+
+        // Links on a known platform have to be on its site
+        for (name, url) in [
+            ("patreon", "https://example.com/my_user"),
+            ("github", "https://notgithub.com/my_user"),
+            ("mastodon", "http://mastodon.social/@my_user"),
+            ("Not A Name", "https://example.com"),
+        ] {
+            let resp = api
+                .edit_project(
+                    alpha_project_slug,
+                    json!({
+                        "link_urls": {
+                            name: url,
+                        },
+                    }),
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::BAD_REQUEST);
+            let error: serde_json::Value = test::read_body_json(resp).await;
+            assert!(error["description"].as_str().unwrap().contains(name));
+        }
+
+        // Subdomains are on the platform's site, and links with other names are generic
+        let resp = api
+            .edit_project(
+                alpha_project_slug,
+                json!({
+                    "link_urls": {
+                        "patreon": "https://www.patreon.com/my_user",
+                        "mastodon": "https://mastodon.social/@my_user",
+                    },
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let project = api
+            .get_project_deserialized(alpha_project_slug, USER_USER_PAT)
+            .await;
+        assert_eq!(project.link_urls["patreon"].platform, "patreon");
+        assert!(project.link_urls["patreon"].donation);
+        assert_eq!(project.link_urls["mastodon"].platform, "generic");
+        assert_eq!(
+            project.link_urls["mastodon"].url,
+            "https://mastodon.social/@my_user"
+        );
+        assert!(!project.link_urls["mastodon"].donation);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn rejections_are_explained_by_the_moderation_review() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+
+        // Only moderators can review projects
+        let review = json!({
+            "items": {
+                "license_present": { "result": "pass" },
+                "description_adequate": {
+                    "result": "fail",
+                    "note": "The description doesn't say what the mod does."
+                },
+                "no_reuploaded_content": { "result": "not_applicable", "note": null },
+                "files_valid": {
+                    "result": "fail",
+                    "note": "The jar contains an obfuscated launcher."
+                }
+            }
+        });
+        let resp = api
+            .edit_moderation_review(beta_project_id, review.clone(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        let resp = api
+            .edit_moderation_review(beta_project_id, review, MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+
+        // The team can't see the review while it's in progress
+        let resp = api
+            .get_moderation_review(beta_project_id, MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let resp = api
+            .get_moderation_review(beta_project_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let resp = api
+            .edit_project(
+                beta_project_id,
+                json!({ "status": "rejected" }),
+                MOD_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // The rejection message lists both failed items
+        let project = api
+            .get_project_deserialized(beta_project_id, USER_USER_PAT)
+            .await;
+        let resp = api
+            .get_thread(&project.thread_id.to_string(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let thread: Thread = test::read_body_json(resp).await;
+        let message = thread
+            .messages
+            .iter()
+            .find_map(|x| match &x.body {
+                MessageBody::Text { body, .. } => Some(body.clone()),
+                _ => None,
+            })
+            .unwrap();
+        assert!(message.contains("The description doesn't say what the mod does."));
+        assert!(message.contains("The jar contains an obfuscated launcher."));
+        assert!(!message.contains("License"));
+
+        // Now the project has been decided on, the team can read the review
+        let resp = api
+            .get_moderation_review(beta_project_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let review: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(review["decision"], "rejected");
+        assert_eq!(review["items"]["files_valid"]["result"], "fail");
+
+        let resp = api
+            .get_moderation_review(beta_project_id, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn listed_projects_have_embeds() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_slug = &test_env.dummy.project_alpha.project_slug;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+        let beta_project_slug = &test_env.dummy.project_beta.project_slug;
+
+        let summary = "a".repeat(250);
+        let resp = api
+            .edit_project(
+                alpha_project_slug,
+                json!({ "summary": summary }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = api.get_project_embed(alpha_project_slug).await;
+        assert_status!(&resp, StatusCode::OK);
+        let embed: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(embed["title"], "Test Project alpha");
+        assert!(embed["url"]
+            .as_str()
+            .unwrap()
+            .ends_with(&format!("/{alpha_project_slug}")));
+        assert_eq!(embed["author"]["name"], "user");
+        assert!(embed["downloads"].is_u64());
+        assert!(embed["follows"].is_u64());
+        assert!(embed["categories"].is_array());
+        // The summary is cut short
+        let embed_summary = embed["summary"].as_str().unwrap();
+        assert_eq!(embed_summary.chars().count(), 200);
+        assert!(embed_summary.ends_with('…'));
+
+        // Projects which aren't listed can't be embedded
+        let resp = api.get_project_embed(beta_project_id).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let site_url = dotenvy::var("SITE_URL").unwrap();
+        let resp = api
+            .get_oembed(&format!("{site_url}/mod/{alpha_project_slug}/versions"))
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let oembed: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(oembed["type"], "rich");
+        assert_eq!(oembed["version"], "1.0");
+        assert_eq!(oembed["title"], "Test Project alpha");
+        assert_eq!(oembed["author_name"], "user");
+        assert!(oembed["author_url"].is_string());
+        assert!(oembed["provider_name"].is_string());
+        assert_eq!(oembed["provider_url"], site_url.as_str());
+        assert!(oembed["html"]
+            .as_str()
+            .unwrap()
+            .contains("Test Project alpha"));
+        assert!(oembed["width"].as_u64().unwrap() > 0);
+        assert!(oembed["height"].as_u64().unwrap() > 0);
+
+        let resp = api
+            .get_oembed(&format!("{site_url}/mod/{beta_project_slug}"))
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        // Only URLs of the site's project pages are embedded
+        let resp = api
+            .get_oembed(&format!("https://example.com/mod/{alpha_project_slug}"))
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let resp = api
+            .get_oembed(&format!("{site_url}/user/{alpha_project_slug}"))
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn projects_have_wiki_pages() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_slug = &test_env.dummy.project_alpha.project_slug;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+
+        for (slug, title) in [
+            ("Getting-Started", "Getting started"),
+            ("config", "Configuration"),
+            ("faq", "FAQ"),
+        ] {
+            let resp = api
+                .create_wiki_page(
+                    alpha_project_slug,
+                    json!({ "slug": slug, "title": title, "body": format!("# {title}") }),
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+            let page: serde_json::Value = test::read_body_json(resp).await;
+            assert_eq!(page["slug"], slug.to_lowercase());
+            assert_eq!(page["updated_by"], USER_USER_ID);
+        }
+
+        // Slugs are unique within the project
+        let resp = api
+            .create_wiki_page(
+                alpha_project_slug,
+                json!({ "slug": "FAQ", "title": "Another FAQ", "body": "" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::CONFLICT);
+
+        // Only the project's team can write to it
+        let resp = api
+            .create_wiki_page(
+                alpha_project_slug,
+                json!({ "slug": "spam", "title": "Spam", "body": "" }),
+                ENEMY_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        // Anyone can read the wiki of a listed project, in order
+        let resp = api.get_wiki_pages(alpha_project_slug, None).await;
+        assert_status!(&resp, StatusCode::OK);
+        let pages: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        let slugs = pages
+            .iter()
+            .map(|x| x["slug"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(slugs, vec!["getting-started", "config", "faq"]);
+
+        let resp = api
+            .edit_wiki_page(
+                alpha_project_slug,
+                "config",
+                json!({ "title": "Config", "slug": "configuration" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .get_wiki_page(alpha_project_slug, "configuration", None)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let page: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(page["title"], "Config");
+        assert_eq!(page["body"], "# Configuration");
+
+        // The new order has to have every page
+        let resp = api
+            .edit_wiki_order(alpha_project_slug, &["faq", "configuration"], USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let resp = api
+            .edit_wiki_order(
+                alpha_project_slug,
+                &["faq", "getting-started", "configuration"],
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.get_wiki_pages(alpha_project_slug, None).await;
+        let pages: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        let slugs = pages
+            .iter()
+            .map(|x| x["slug"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(slugs, vec!["faq", "getting-started", "configuration"]);
+
+        let resp = api
+            .delete_wiki_page(alpha_project_slug, "faq", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.get_wiki_page(alpha_project_slug, "faq", None).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        // Projects have a limit on their pages
+        for index in 2..50 {
+            let resp = api
+                .create_wiki_page(
+                    alpha_project_slug,
+                    json!({ "slug": format!("page-{index}"), "title": "Page", "body": "" }),
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+        }
+        let resp = api
+            .create_wiki_page(
+                alpha_project_slug,
+                json!({ "slug": "page-50", "title": "Page", "body": "" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // The wiki of a private project is as hidden as the project
+        let resp = api
+            .create_wiki_page(
+                beta_project_id,
+                json!({ "slug": "secret", "title": "Secret", "body": "" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let resp = api.get_wiki_pages(beta_project_id, None).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let resp = api.get_wiki_page(beta_project_id, "secret", None).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let resp = api
+            .get_wiki_page(beta_project_id, "secret", ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let resp = api
+            .get_wiki_page(beta_project_id, "secret", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn license_changes_are_recorded() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+
+        // The approved project moves to another permissive license, then to a restrictive one
+        for license in ["Apache-2.0", "arr"] {
+            let resp = api
+                .edit_project(
+                    alpha_project_id,
+                    json!({ "license_id": license }),
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+
+        let resp = api
+            .get_project_history(alpha_project_id, Some("license"), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let history: Vec<ProjectFieldChange> = test::read_body_json(resp).await;
+        assert_eq!(
+            history
+                .iter()
+                .map(|x| (x.field.as_str(), x.old_value.clone(), x.new_value.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("license_id", json!("MIT"), json!("Apache-2.0")),
+                (
+                    "license_id",
+                    json!("Apache-2.0"),
+                    json!("LicenseRef-All-Rights-Reserved")
+                ),
+            ]
+        );
+        assert!(history
+            .iter()
+            .all(|x| x.actor_id == Some(UserId(USER_USER_ID_PARSED as u64))));
+
+        // Moderators see it too, and nothing else changed
+        let resp = api
+            .get_project_history(alpha_project_id, Some("environment"), MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let history: Vec<ProjectFieldChange> = test::read_body_json(resp).await;
+        assert!(history.is_empty());
+
+        // Only the move to the more restrictive license is noted for the moderators
+        let project = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        let resp = api
+            .get_thread(&project.thread_id.to_string(), MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let thread: Thread = test::read_body_json(resp).await;
+        let notes = thread
+            .messages
+            .iter()
+            .filter(|x| x.author_id == Some(AUTOMOD_USER))
+            .filter_map(|x| match &x.body {
+                MessageBody::Text { body, private, .. } => Some((body.clone(), *private)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].0.contains("LicenseRef-All-Rights-Reserved"));
+        assert!(notes[0].1);
+
+        // Users outside of the team can't see the history
+        let resp = api
+            .get_project_history(alpha_project_id, None, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+    })
+    .await;
+}
+
+async fn get_transfer_notifications(
+    api: &ApiV3,
+    user_id: &str,
+    pat: Option<&str>,
+) -> Vec<NotificationBody> {
+    let resp = api.get_user_notifications(user_id, pat).await;
+    assert_status!(&resp, StatusCode::OK);
+    let notifications: Vec<Notification> = test::read_body_json(resp).await;
+    notifications
+        .into_iter()
+        .map(|x| x.body)
+        .filter(|x| {
+            matches!(
+                x,
+                NotificationBody::ProjectTransferRequested { .. }
+                    | NotificationBody::ProjectTransferResolved { .. }
+            )
+        })
+        .collect()
+}
+
+#[actix_rt::test]
+async fn project_transfer_is_accepted() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let alpha_team_id = &test_env.dummy.project_alpha.team_id;
+        let transfer = json!({
+            "user_id": FRIEND_USER_ID,
+            "previous_owner_permissions": ProjectPermissions::UPLOAD_VERSION.bits(),
+        });
+
+        // Only the owner can start a transfer
+        for pat in [FRIEND_USER_PAT, ENEMY_USER_PAT] {
+            let resp = api
+                .transfer_project(alpha_project_id, transfer.clone(), pat)
+                .await;
+            assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        }
+
+        let resp = api
+            .transfer_project(alpha_project_id, transfer.clone(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let pending: ProjectTransfer = test::read_body_json(resp).await;
+        assert_eq!(pending.status, ProjectTransferStatus::Pending);
+        assert_eq!(pending.user_id, Some(UserId(FRIEND_USER_ID_PARSED as u64)));
+        assert_eq!((pending.expires - pending.created).num_days(), 7);
+
+        // A second transfer can't be started while one is pending
+        let resp = api
+            .transfer_project(
+                alpha_project_id,
+                json!({ "user_id": ENEMY_USER_ID }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // The recipient is asked to accept it, and nobody else can
+        let notifications = get_transfer_notifications(api, FRIEND_USER_ID, FRIEND_USER_PAT).await;
+        assert!(matches!(
+            notifications[..],
+            [NotificationBody::ProjectTransferRequested { initiated_by, organization_id: None, .. }]
+                if initiated_by == UserId(USER_USER_ID_PARSED as u64)
+        ));
+        let resp = api
+            .answer_project_transfer(alpha_project_id, true, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .get_project_transfer(alpha_project_id, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let resp = api
+            .answer_project_transfer(alpha_project_id, true, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // The owner role moved, and the previous owner stays with the permissions they chose
+        let members = api
+            .get_team_members_deserialized(alpha_team_id, FRIEND_USER_PAT)
+            .await;
+        let friend = members
+            .iter()
+            .find(|x| x.user.id.0 == FRIEND_USER_ID_PARSED as u64)
+            .unwrap();
+        assert!(friend.is_owner && friend.accepted);
+        assert_eq!(friend.permissions, Some(ProjectPermissions::all()));
+        let user = members
+            .iter()
+            .find(|x| x.user.id.0 == USER_USER_ID_PARSED as u64)
+            .unwrap();
+        assert!(!user.is_owner);
+        assert_eq!(user.permissions, Some(ProjectPermissions::UPLOAD_VERSION));
+
+        let resp = api
+            .get_project_transfer(alpha_project_id, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let resp = api
+            .transfer_project(alpha_project_id, transfer, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        // The previous owner is told, and the transfer is in the audit log
+        let notifications = get_transfer_notifications(api, USER_USER_ID, USER_USER_PAT).await;
+        assert!(matches!(
+            notifications[..],
+            [NotificationBody::ProjectTransferResolved { accepted: true, .. }]
+        ));
+        let entries: Vec<AuditLogEntry> = api
+            .get_project_audit_log_deserialized(
+                alpha_project_id,
+                Some("project_transfer"),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_eq!(
+            entries
+                .iter()
+                .map(|x| x.summary["status"].clone())
+                .collect::<Vec<_>>(),
+            vec![json!("accepted"), json!("pending")]
+        );
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn project_transfer_is_declined_or_cancelled() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let alpha_team_id = &test_env.dummy.project_alpha.team_id;
+        let transfer = json!({ "user_id": FRIEND_USER_ID });
+
+        let resp = api
+            .transfer_project(alpha_project_id, transfer.clone(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let resp = api
+            .answer_project_transfer(alpha_project_id, false, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Declined transfers can't be accepted afterwards
+        let resp = api
+            .answer_project_transfer(alpha_project_id, true, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let notifications = get_transfer_notifications(api, USER_USER_ID, USER_USER_PAT).await;
+        assert!(matches!(
+            notifications[..],
+            [NotificationBody::ProjectTransferResolved {
+                accepted: false,
+                ..
+            }]
+        ));
+
+        // Only the user who started a transfer can cancel it
+        let resp = api
+            .transfer_project(alpha_project_id, transfer, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let resp = api
+            .cancel_project_transfer(alpha_project_id, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .cancel_project_transfer(alpha_project_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .answer_project_transfer(alpha_project_id, true, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // The team is left as it was
+        let members = api
+            .get_team_members_deserialized(alpha_team_id, USER_USER_PAT)
+            .await;
+        assert!(members
+            .iter()
+            .all(|x| x.is_owner == (x.user.id.0 == USER_USER_ID_PARSED as u64)));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn project_transfer_expires() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+
+        let resp = api
+            .transfer_project(
+                alpha_project_id,
+                json!({ "user_id": FRIEND_USER_ID }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+
+        sqlx::query("UPDATE project_transfers SET expires = NOW() - INTERVAL '1 day'")
+            .execute(&test_env.db.pool)
+            .await
+            .unwrap();
+
+        let resp = api
+            .answer_project_transfer(alpha_project_id, true, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let resp = api
+            .get_project_transfer(alpha_project_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        // Expired transfers don't block new ones
+        let resp = api
+            .transfer_project(
+                alpha_project_id,
+                json!({ "user_id": ENEMY_USER_ID }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let resp = api
+            .get_project_transfer(alpha_project_id, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let pending: ProjectTransfer = test::read_body_json(resp).await;
+        assert_eq!(pending.user_id, Some(UserId(ENEMY_USER_ID_PARSED as u64)));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn project_transfer_to_organization() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let zeta_organization_id = &test_env.dummy.organization_zeta.organization_id;
+
+        // A project must go to either a user or an organization
+        let resp = api
+            .transfer_project(
+                alpha_project_id,
+                json!({ "user_id": FRIEND_USER_ID, "organization_id": zeta_organization_id }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let resp = api
+            .transfer_project(
+                alpha_project_id,
+                json!({ "organization_id": zeta_organization_id }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+
+        // Only users who could add the project to the organization themselves can accept
+        let resp = api
+            .answer_project_transfer(alpha_project_id, true, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        let resp = api
+            .answer_project_transfer(alpha_project_id, true, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let project = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(
+            project.organization.map(|x| x.to_string()).as_ref(),
+            Some(zeta_organization_id)
+        );
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn approved_projects_go_live_when_scheduled() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+        let go_live_at = Utc::now() + Duration::days(1);
+
+        for bad_time in [
+            Utc::now() - Duration::days(1),
+            Utc::now() + Duration::days(31),
+        ] {
+            let resp = api
+                .edit_project(
+                    beta_project_id,
+                    json!({ "go_live_at": bad_time }),
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::BAD_REQUEST);
+        }
+
+        let resp = api
+            .edit_project(
+                beta_project_id,
+                json!({ "go_live_at": go_live_at }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Approving the project schedules it instead of listing it
+        let resp = api
+            .edit_project(
+                beta_project_id,
+                json!({ "status": "approved" }),
+                MOD_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let scheduled: ScheduledGoLive = test::read_body_json(resp).await;
+        assert_eq!(scheduled.status, ProjectStatus::Scheduled);
+        assert_eq!(scheduled.go_live_at.timestamp(), go_live_at.timestamp());
+
+        let project = api
+            .get_project_deserialized(beta_project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(project.status, ProjectStatus::Scheduled);
+
+        // Scheduled projects can't be rescheduled
+        let resp = api
+            .edit_project(
+                beta_project_id,
+                json!({ "go_live_at": go_live_at }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let is_searchable = || async {
+            api.reset_search_index().await;
+            api.search_deserialized(None, None, USER_USER_PAT)
+                .await
+                .hits
+                .iter()
+                .any(|x| x.id.to_string() == *beta_project_id)
+        };
+        assert!(!is_searchable().await);
+
+        // Nothing happens before the go-live time
+        list_scheduled_projects(
+            Utc::now(),
+            &test_env.db.pool,
+            &test_env.db.redis_pool,
+            &test_env.db.search_config,
+        )
+        .await
+        .unwrap();
+        let project = api
+            .get_project_deserialized(beta_project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(project.status, ProjectStatus::Scheduled);
+
+        sqlx::query("UPDATE mods SET go_live_at = NOW() - interval '1 second' WHERE id = $1")
+            .bind(parse_base62(beta_project_id).unwrap() as i64)
+            .execute(&test_env.db.pool)
+            .await
+            .unwrap();
+        list_scheduled_projects(
+            Utc::now(),
+            &test_env.db.pool,
+            &test_env.db.redis_pool,
+            &test_env.db.search_config,
+        )
+        .await
+        .unwrap();
+
+        let project = api
+            .get_project_deserialized(beta_project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(project.status, ProjectStatus::Approved);
+        assert_eq!(project.go_live_at, None);
+        assert!(is_searchable().await);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn hidden_project_images_are_served_through_signed_urls() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+
+        let self_addr = dotenvy::var("SELF_ADDR").unwrap();
+        let cdn_url = dotenvy::var("CDN_URL").unwrap();
+        let mock_file_path = dotenvy::var("MOCK_FILE_PATH").unwrap();
+        let icon = DummyImage::SmallIcon.get_icon_data();
+        let icon_bytes = icon.icon.clone();
+
+        let resp = api
+            .edit_project_icon(beta_project_id, Some(icon), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // The icon is only on the CDN under the private prefix
+        let project = api
+            .get_project_deserialized(beta_project_id, USER_USER_PAT)
+            .await;
+        let signed_url = project.icon_url.unwrap();
+        let signed_path = signed_url.strip_prefix(&self_addr).unwrap();
+        assert!(signed_path.starts_with("/v3/image/signed/private/"));
+        let private_path = signed_path
+            .strip_prefix("/v3/image/signed/")
+            .unwrap()
+            .split('?')
+            .next()
+            .unwrap()
+            .to_string();
+        assert!(std::path::Path::new(&mock_file_path)
+            .join(&private_path)
+            .exists());
+
+        let resp = api.get_project(beta_project_id, None).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        // The image can't be fetched without a valid signature
+        let unsigned = format!("/v3/image/signed/{private_path}");
+        let resp = api
+            .call(test::TestRequest::get().uri(&unsigned).to_request())
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let forged = format!("{unsigned}?expires=99999999999&signature=00");
+        let resp = api
+            .call(test::TestRequest::get().uri(&forged).to_request())
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let resp = api
+            .call(test::TestRequest::get().uri(signed_path).to_request())
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        assert_eq!(test::read_body(resp).await.to_vec(), icon_bytes);
+
+        // Project lists sign the URLs as well
+        let resp = api
+            .get_user_projects(USER_USER_ID, Some(USER_USER_PAT))
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let projects: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        let listed_url = projects
+            .iter()
+            .find(|x| x["id"] == beta_project_id.as_str())
+            .unwrap()["icon_url"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let listed_path = listed_url.strip_prefix(&self_addr).unwrap();
+        assert!(listed_path.starts_with("/v3/image/signed/private/"));
+        let resp = api
+            .call(test::TestRequest::get().uri(listed_path).to_request())
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+
+        // Once the project is public, its images are moved to the public prefix
+        let resp = api
+            .edit_project(
+                beta_project_id,
+                json!({ "status": "approved" }),
+                MOD_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        publish_private_images(
+            &test_env.db.pool,
+            &test_env.db.redis_pool,
+            &test_env.db.search_config,
+            &MockHost::new(),
+        )
+        .await
+        .unwrap();
+
+        let project = api
+            .get_project_deserialized(beta_project_id, USER_USER_PAT)
+            .await;
+        let icon_url = project.icon_url.unwrap();
+        let public_path = private_path.strip_prefix("private/").unwrap();
+        assert_eq!(icon_url, format!("{cdn_url}/{public_path}"));
+        assert!(project
+            .icon_variants
+            .values()
+            .all(|x| x.starts_with(&format!("{cdn_url}/data/"))));
+        assert!(std::path::Path::new(&mock_file_path)
+            .join(public_path)
+            .exists());
+        assert!(!std::path::Path::new(&mock_file_path)
+            .join(&private_path)
+            .exists());
+    })
+    .await;
+}
@@ -0,0 +1,138 @@
+use actix_http::StatusCode;
+use actix_web::dev::ServiceResponse;
+use common::api_common::{Api, ApiBuildable, ApiProject};
+use common::api_v3::ApiV3;
+use common::database::{MOD_USER_PAT, USER_USER_PAT};
+use common::environment::{with_test_environment, TestEnvironment};
+use labrinth::models::pats::Scopes;
+use labrinth::ratelimit::RateLimits;
+use serde_json::json;
+
+mod common;
+
+fn header<'a>(resp: &'a ServiceResponse, name: &str) -> Option<&'a str> {
+    resp.headers().get(name).and_then(|x| x.to_str().ok())
+}
+
+#[actix_rt::test]
+async fn write_requests_are_limited_per_user() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let mut config = common::setup(&test_env.db, test_env.mailer.clone()).await;
+        config.rate_limits = RateLimits {
+            write: Some(2),
+            elevated_multiplier: 10,
+            ignore_key: Some("feedbeef".to_string()),
+            ..RateLimits::unlimited()
+        };
+        let api = ApiV3::build(config).await;
+
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let patch = || json!({ "description": "A new description" });
+
+        // Each request in the window uses up the budget
+        let resp = api
+            .edit_project(alpha_project_id, patch(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        assert_eq!(header(&resp, "x-ratelimit-limit"), Some("2"));
+        assert_eq!(header(&resp, "x-ratelimit-remaining"), Some("1"));
+
+        let resp = api
+            .edit_project(alpha_project_id, patch(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        assert_eq!(header(&resp, "x-ratelimit-remaining"), Some("0"));
+
+        // Once it's exhausted, requests are rejected until the window resets
+        let resp = api
+            .edit_project(alpha_project_id, patch(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&resp, "x-ratelimit-remaining"), Some("0"));
+        let reset: u64 = header(&resp, "x-ratelimit-reset").unwrap().parse().unwrap();
+        assert!(reset > 0 && reset <= 60);
+
+        // Read requests have their own budget, which is unlimited here
+        let resp = api.get_project(alpha_project_id, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        assert!(header(&resp, "x-ratelimit-limit").is_none());
+
+        // Moderators have an elevated budget
+        let resp = api
+            .edit_project(alpha_project_id, patch(), MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        assert_eq!(header(&resp, "x-ratelimit-limit"), Some("20"));
+
+        // Requests carrying the ignore key aren't limited
+        let req = actix_web::test::TestRequest::patch()
+            .uri(&format!("/v3/project/{alpha_project_id}"))
+            .append_header(("Authorization", USER_USER_PAT.unwrap()))
+            .append_header(("x-ratelimit-key", "feedbeef"))
+            .set_json(patch())
+            .to_request();
+        let resp = api.call(req).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn pats_share_their_users_budget() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let mut config = common::setup(&test_env.db, test_env.mailer.clone()).await;
+        config.rate_limits = RateLimits {
+            read: Some(5),
+            write: Some(1),
+            ..RateLimits::unlimited()
+        };
+        let api = ApiV3::build(config).await;
+
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let patch = || json!({ "description": "A new description" });
+
+        // Creating the PAT uses up the user's write budget
+        let req = actix_web::test::TestRequest::post()
+            .uri("/_internal/pat")
+            .append_header(("Authorization", USER_USER_PAT.unwrap()))
+            .set_json(json!({
+                "scopes": Scopes::PROJECT_WRITE | Scopes::PAT_READ,
+                "name": "test_pat_rate_limit",
+                "expires": chrono::Utc::now() + chrono::Duration::days(1),
+            }))
+            .to_request();
+        let resp = api.call(req).await;
+        assert_status!(&resp, StatusCode::OK);
+        let pat: serde_json::Value = actix_web::test::read_body_json(resp).await;
+        let pat_id = pat["id"].as_str().unwrap().to_string();
+        let access_token = pat["access_token"].as_str().unwrap().to_string();
+
+        let resp = api
+            .edit_project(alpha_project_id, patch(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&resp, "x-ratelimit-limit"), Some("1"));
+        assert_eq!(header(&resp, "x-ratelimit-remaining"), Some("0"));
+        assert!(header(&resp, "x-ratelimit-reset").is_some());
+
+        // A new PAT doesn't come with a budget of its own
+        let resp = api
+            .edit_project(alpha_project_id, patch(), Some(access_token.as_str()))
+            .await;
+        assert_status!(&resp, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&resp, "x-ratelimit-limit"), Some("1"));
+        assert_eq!(header(&resp, "x-ratelimit-remaining"), Some("0"));
+
+        // Checking the limit doesn't record the use of the PAT, so its rejected edit isn't in
+        // its usage history
+        let req = actix_web::test::TestRequest::get()
+            .uri(&format!("/v3/pat/{pat_id}/usage"))
+            .append_header(("Authorization", USER_USER_PAT.unwrap()))
+            .to_request();
+        let resp = api.call(req).await;
+        assert_status!(&resp, StatusCode::OK);
+        let usage: serde_json::Value = actix_web::test::read_body_json(resp).await;
+        assert!(usage.as_array().unwrap().is_empty());
+    })
+    .await;
+}
@@ -0,0 +1,455 @@
+use actix_http::StatusCode;
+use actix_web::test;
+use common::api_common::models::CommonItemType;
+use common::api_common::{Api, ApiProject, ApiVersion, AppendsOptionalPat};
+use common::api_v3::ApiV3;
+use common::database::{ENEMY_USER_PAT, MOD_USER_ID, MOD_USER_PAT, USER_USER_ID, USER_USER_PAT};
+use common::dummy_data::{DummyImage, TestFile};
+use common::environment::{with_test_environment, TestEnvironment};
+use labrinth::models::projects::VersionStatus;
+use labrinth::models::reports::{Report, ReportStatus};
+use labrinth::models::threads::{MessageBody, Thread};
+use serde_json::json;
+
+mod common;
+
+async fn create_alpha_report(test_env: &TestEnvironment<ApiV3>) -> Report {
+    let resp = test_env
+        .api
+        .create_report(
+            "copyright",
+            &test_env.dummy.project_alpha.project_id,
+            CommonItemType::Project,
+            "Hey! This is my project, copied without permission!",
+            ENEMY_USER_PAT,
+        )
+        .await;
+    assert_status!(&resp, StatusCode::OK);
+    test::read_body_json(resp).await
+}
+
+async fn get_report_deserialized(api: &ApiV3, id: &str) -> Report {
+    let resp = api.get_report(id, MOD_USER_PAT).await;
+    assert_status!(&resp, StatusCode::OK);
+    test::read_body_json(resp).await
+}
+
+#[actix_rt::test]
+async fn report_lifecycle() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let report = create_alpha_report(&test_env).await;
+        let report_id = report.id.to_string();
+        assert_eq!(report.status, ReportStatus::Open);
+
+        let open_reports = api
+            .list_reports_deserialized("status=open", MOD_USER_PAT)
+            .await;
+        assert!(open_reports.iter().any(|x| x.id == report.id));
+
+        // Assign the report and start reviewing it
+        let resp = api
+            .edit_report(
+                &report_id,
+                json!({ "status": "under_review", "assigned_moderator": MOD_USER_ID }),
+                MOD_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let report = get_report_deserialized(api, &report_id).await;
+        assert_eq!(report.status, ReportStatus::UnderReview);
+        assert_eq!(report.assigned_moderator.unwrap().to_string(), MOD_USER_ID);
+        assert!(!report.closed);
+
+        let assigned_reports = api
+            .list_reports_deserialized("assigned=me&status=under_review", MOD_USER_PAT)
+            .await;
+        assert_eq!(assigned_reports.len(), 1);
+        assert_eq!(assigned_reports[0].id, report.id);
+        let open_reports = api
+            .list_reports_deserialized("status=open", MOD_USER_PAT)
+            .await;
+        assert!(open_reports.iter().all(|x| x.id != report.id));
+
+        // Reports can only be assigned to moderators
+        let resp = api
+            .edit_report(
+                &report_id,
+                json!({ "assigned_moderator": USER_USER_ID }),
+                MOD_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // Resolve it with a message to the reporter
+        let resp = api
+            .edit_report(
+                &report_id,
+                json!({ "status": "resolved", "resolution": "The project has been taken down." }),
+                MOD_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let report = get_report_deserialized(api, &report_id).await;
+        assert_eq!(report.status, ReportStatus::Resolved);
+        assert!(report.closed);
+
+        // A closed report can't be moved back without explicitly reopening it
+        let resp = api
+            .edit_report(&report_id, json!({ "status": "open" }), MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let resp = api
+            .edit_report(
+                &report_id,
+                json!({ "status": "open", "reopen": true }),
+                MOD_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let report = get_report_deserialized(api, &report_id).await;
+        assert_eq!(report.status, ReportStatus::Open);
+        assert!(!report.closed);
+
+        // Every transition was recorded in the thread
+        let resp = api
+            .get_thread(&report.thread_id.to_string(), MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let thread: Thread = test::read_body_json(resp).await;
+        let transitions = thread
+            .messages
+            .iter()
+            .filter_map(|x| match x.body {
+                MessageBody::ReportStatusChange {
+                    old_status,
+                    new_status,
+                } => Some((old_status, new_status)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(transitions.len(), 3);
+        for transition in [
+            (ReportStatus::Open, ReportStatus::UnderReview),
+            (ReportStatus::UnderReview, ReportStatus::Resolved),
+            (ReportStatus::Resolved, ReportStatus::Open),
+        ] {
+            assert!(transitions.contains(&transition));
+        }
+        assert!(thread.messages.iter().any(|x| matches!(
+            &x.body,
+            MessageBody::Text { body, .. } if body == "The project has been taken down."
+        )));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn report_status_cannot_be_changed_by_reporter() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let report = create_alpha_report(&test_env).await;
+        let report_id = report.id.to_string();
+
+        for patch in [
+            json!({ "status": "resolved" }),
+            json!({ "closed": true }),
+            json!({ "assigned_moderator": MOD_USER_ID }),
+        ] {
+            let resp = api.edit_report(&report_id, patch, ENEMY_USER_PAT).await;
+            assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        }
+
+        let report = get_report_deserialized(api, &report_id).await;
+        assert_eq!(report.status, ReportStatus::Open);
+        assert!(report.assigned_moderator.is_none());
+
+        // Reporters only see their own reports in the list
+        let reports = api.list_reports_deserialized("", ENEMY_USER_PAT).await;
+        assert_eq!(reports.len(), 1);
+        let reports = api
+            .list_reports_deserialized("status=open", MOD_USER_PAT)
+            .await;
+        assert!(reports.iter().any(|x| x.id == report.id));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn reports_can_be_exported_as_csv() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        let bodies = [
+            "Plain report",
+            "Copied, \"word for word\", from my project",
+            "First line\nSecond line, with a comma",
+        ];
+        let items = [
+            (
+                &test_env.dummy.project_alpha.project_id,
+                CommonItemType::Project,
+            ),
+            (
+                &test_env.dummy.project_beta.project_id,
+                CommonItemType::Project,
+            ),
+            (
+                &test_env.dummy.project_alpha.version_id,
+                CommonItemType::Version,
+            ),
+        ];
+        for (body, (item_id, item_type)) in bodies.iter().zip(items) {
+            let resp = api
+                .create_report("copyright", item_id, item_type, body, ENEMY_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+        }
+
+        // Only moderators can export
+        let resp = api.list_reports("format=csv", ENEMY_USER_PAT).await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/v3/reports")
+            .append_pat(MOD_USER_PAT)
+            .insert_header(("Accept", "text/csv"))
+            .to_request();
+        let resp = api.call(req).await;
+        assert_status!(&resp, StatusCode::OK);
+        assert!(resp
+            .headers()
+            .get("Content-Type")
+            .and_then(|x| x.to_str().ok())
+            .is_some_and(|x| x.starts_with("text/csv")));
+        let body = test::read_body(resp).await;
+
+        let mut reader = csv::Reader::from_reader(body.as_ref());
+        assert_eq!(
+            reader.headers().unwrap().iter().collect::<Vec<_>>(),
+            [
+                "id",
+                "type",
+                "item_type",
+                "item_id",
+                "created",
+                "reporter",
+                "status",
+                "assigned_moderator",
+                "body"
+            ]
+        );
+        let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), bodies.len());
+        for (record, body) in records.iter().zip(bodies) {
+            assert_eq!(&record[1], "copyright");
+            assert_eq!(&record[5], "enemy");
+            assert_eq!(&record[6], "open");
+            assert_eq!(&record[8], body);
+        }
+        assert_eq!(&records[2][2], "version");
+        assert_eq!(&records[2][3], test_env.dummy.project_alpha.version_id);
+
+        // The format parameter works as well, and the filters still apply
+        let resp = api
+            .list_reports("format=csv&status=resolved", MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let mut reader = csv::Reader::from_reader(body.as_ref());
+        assert_eq!(reader.records().count(), 0);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn reported_files_can_be_taken_down() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let alpha_version_id = &test_env.dummy.project_alpha.version_id;
+        let alpha_file_hash = &test_env.dummy.project_alpha.file_hash;
+
+        let resp = api
+            .upload_file_to_version(
+                alpha_version_id,
+                &TestFile::BasicModDifferent,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let version = api
+            .get_version_deserialized(alpha_version_id, USER_USER_PAT)
+            .await;
+        let extra_hash = version
+            .files
+            .iter()
+            .find(|x| x.filename == "basic-mod-different.jar")
+            .unwrap()
+            .hashes["sha1"]
+            .clone();
+
+        // Files are reported by their hash, and the report is attached to their version
+        let resp = api
+            .create_report(
+                "copyright",
+                &extra_hash,
+                CommonItemType::File,
+                "This file is mine!",
+                ENEMY_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let report: Report = test::read_body_json(resp).await;
+        let report = get_report_deserialized(api, &report.id.to_string()).await;
+        assert_eq!(report.item_type.as_str(), "file");
+        assert_eq!(report.item_id, extra_hash);
+
+        let resp = api
+            .create_report(
+                "copyright",
+                "0000000000000000000000000000000000000000",
+                CommonItemType::File,
+                "This file is mine too!",
+                ENEMY_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // Only moderators can take files down
+        let resp = api.take_down_file(&extra_hash, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api.take_down_file(&extra_hash, MOD_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = api
+            .download_version_redirect(&extra_hash, "sha1", ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS);
+
+        // The file is hidden from the public, but the team can see it was withheld
+        let version = api
+            .get_version_deserialized(alpha_version_id, ENEMY_USER_PAT)
+            .await;
+        assert_eq!(version.status, VersionStatus::Listed);
+        assert!(version.files.iter().all(|x| x.hashes["sha1"] != extra_hash));
+        assert_eq!(version.has_withheld_files, None);
+        let project = api
+            .get_project_deserialized(alpha_project_id, ENEMY_USER_PAT)
+            .await;
+        assert_eq!(project.has_withheld_files, None);
+
+        let version = api
+            .get_version_deserialized(alpha_version_id, USER_USER_PAT)
+            .await;
+        let file = version
+            .files
+            .iter()
+            .find(|x| x.hashes["sha1"] == extra_hash)
+            .unwrap();
+        assert_eq!(file.withheld, Some(true));
+        assert_eq!(version.has_withheld_files, Some(true));
+        let project = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(project.has_withheld_files, Some(true));
+
+        // Reinstating the file makes it available again
+        let resp = api.reinstate_file(&extra_hash, MOD_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .download_version_redirect(&extra_hash, "sha1", ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::TEMPORARY_REDIRECT);
+        let version = api
+            .get_version_deserialized(alpha_version_id, ENEMY_USER_PAT)
+            .await;
+        assert!(version.files.iter().any(|x| x.hashes["sha1"] == extra_hash));
+        let project = api
+            .get_project_deserialized(alpha_project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(project.has_withheld_files, Some(false));
+
+        // Taking down the primary file withholds the whole version
+        let resp = api.take_down_file(alpha_file_hash, MOD_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let version = api
+            .get_version_deserialized(alpha_version_id, USER_USER_PAT)
+            .await;
+        assert_eq!(version.status, VersionStatus::Withheld);
+        let resp = api.get_version(alpha_version_id, ENEMY_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let resp = api
+            .download_version_redirect(alpha_file_hash, "sha1", ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn report_thread_messages_can_attach_images() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let report = create_alpha_report(&test_env).await;
+        let thread_id = report.thread_id.to_string();
+
+        let mut image_ids = Vec::new();
+        for pat in [ENEMY_USER_PAT, MOD_USER_PAT] {
+            let resp = api
+                .upload_image("thread_message", DummyImage::SmallIcon.get_icon_data(), pat)
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+            let image: serde_json::Value = test::read_body_json(resp).await;
+            image_ids.push(image["id"].as_str().unwrap().to_string());
+        }
+
+        // Only the uploader's own, unattached images can be attached
+        let resp = api
+            .write_to_thread_with_images(&thread_id, "Evidence", &[&image_ids[1]], ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let resp = api
+            .write_to_thread_with_images(&thread_id, "Evidence", &[&image_ids[0]], ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .write_to_thread_with_images(&thread_id, "Again", &[&image_ids[0]], ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let mut message_id = None;
+        for pat in [ENEMY_USER_PAT, MOD_USER_PAT] {
+            let resp = api.get_thread(&thread_id, pat).await;
+            assert_status!(&resp, StatusCode::OK);
+            let thread: Thread = test::read_body_json(resp).await;
+            let message = thread
+                .messages
+                .iter()
+                .find(|x| !x.attachments.is_empty())
+                .unwrap();
+            assert_eq!(message.attachments.len(), 1);
+            assert_eq!(message.attachments[0].id.to_string(), image_ids[0]);
+            assert!(!message.attachments[0].url.is_empty());
+            message_id = Some(message.id.to_string());
+        }
+
+        let resp = api.get_thread(&thread_id, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        // Deleting the message hides its images
+        let resp = api
+            .delete_thread_message(&message_id.unwrap(), ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.get_thread(&thread_id, MOD_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let thread: Thread = test::read_body_json(resp).await;
+        assert!(thread.messages.iter().all(|x| x.attachments.is_empty()));
+    })
+    .await;
+}
@@ -1,12 +1,23 @@
+use actix_http::StatusCode;
+use common::api_common::{Api, ApiProject, ApiUser, ApiVersion};
 use common::api_v3::ApiV3;
 use common::database::*;
 
-use common::dummy_data::DUMMY_CATEGORIES;
+use common::dummy_data::{TestFile, DUMMY_CATEGORIES};
 
+use actix_web::test;
+use chrono::{Duration, Utc};
 use common::environment::with_test_environment;
 use common::environment::TestEnvironment;
 use common::search::setup_search_projects;
 use futures::stream::StreamExt;
+use labrinth::database::models::project_item::Project as DBProject;
+use labrinth::database::models::ProjectId as DBProjectId;
+use labrinth::models::ids::base62_impl::parse_base62;
+use labrinth::models::ids::ProjectId;
+use labrinth::models::projects::Project;
+use labrinth::routes::v3::projects::ReturnSearchResults;
+use labrinth::search::indexing::local_import::get_all_ids;
 use serde_json::json;
 
 mod common;
@@ -113,3 +124,961 @@ async fn search_projects() {
     })
     .await;
 }
+
+#[actix_rt::test]
+async fn search_reflects_project_edit_without_reindex() {
+    with_test_environment(Some(10), |test_env: TestEnvironment<ApiV3>| async move {
+        let id_conversion = setup_search_projects(&test_env).await;
+
+        let api = &test_env.api;
+        let test_name = test_env.db.database_name.clone();
+
+        // Project 0 is owned by the regular user
+        let project_id = id_conversion
+            .iter()
+            .find(|(_, id)| **id == 0)
+            .map(|(project_id, _)| *project_id)
+            .unwrap();
+        let new_name = format!("{test_name} renamed");
+
+        let resp = api
+            .edit_project(
+                &ProjectId(project_id).to_string(),
+                json!({ "name": new_name }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // The project is reindexed in the background, so give it a moment to show up.
+        // No full reindex is triggered here.
+        let mut found_name = None;
+        for _ in 0..20 {
+            let projects = api
+                .search_deserialized(Some(&format!("\"&{test_name}\"")), None, USER_USER_PAT)
+                .await;
+            found_name = projects
+                .hits
+                .into_iter()
+                .find(|p| p.id.0 == project_id)
+                .map(|p| p.name);
+            if found_name.as_deref() == Some(new_name.as_str()) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+        assert_eq!(found_name, Some(new_name));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn mature_projects_are_hidden_unless_opted_in() {
+    with_test_environment(Some(10), |test_env: TestEnvironment<ApiV3>| async move {
+        let id_conversion = setup_search_projects(&test_env).await;
+
+        let api = &test_env.api;
+        let query = format!("\"&{}\"", test_env.db.database_name);
+
+        // Project 0 is owned by the regular user
+        let project_id = id_conversion
+            .iter()
+            .find(|(_, id)| **id == 0)
+            .map(|(project_id, _)| ProjectId(*project_id))
+            .unwrap();
+
+        let resp = api
+            .edit_project(
+                &project_id.to_string(),
+                json!({ "mature": true }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // The project is reindexed in the background, so wait for it to drop out of anonymous searches
+        let mut found = true;
+        for _ in 0..20 {
+            let projects = api.search_deserialized_include_mature(&query, None).await;
+            found = projects.hits.iter().any(|p| p.id == project_id);
+            if !found {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+        assert!(!found);
+
+        // Users hide mature content by default, even when asking to include it
+        let projects = api
+            .search_deserialized_include_mature(&query, USER_USER_PAT)
+            .await;
+        assert!(!projects.hits.iter().any(|p| p.id == project_id));
+
+        let resp = api
+            .edit_user(
+                USER_USER_ID,
+                json!({ "hide_mature_content": false }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Once opted in, mature projects are only included when explicitly requested
+        let projects = api
+            .search_deserialized(Some(&query), None, USER_USER_PAT)
+            .await;
+        assert!(!projects.hits.iter().any(|p| p.id == project_id));
+        let projects = api
+            .search_deserialized_include_mature(&query, USER_USER_PAT)
+            .await;
+        let project = projects.hits.iter().find(|p| p.id == project_id).unwrap();
+        assert!(project.mature);
+
+        // Mature projects are still accessible directly
+        let resp = api.get_project(&project_id.to_string(), None).await;
+        assert_status!(&resp, StatusCode::OK);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn search_fields_projection() {
+    with_test_environment(Some(10), |test_env: TestEnvironment<ApiV3>| async move {
+        setup_search_projects(&test_env).await;
+
+        let api = &test_env.api;
+        let test_name = test_env.db.database_name.clone();
+
+        let resp = api
+            .search_fields(
+                &format!("\"&{test_name}\""),
+                "name,downloads",
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let hits = body["hits"].as_array().unwrap();
+        assert!(!hits.is_empty());
+        for hit in hits {
+            let mut keys = hit.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+            keys.sort();
+            assert_eq!(keys, ["downloads", "id", "name"]);
+        }
+
+        let resp = api
+            .search_fields(&format!("\"&{test_name}\""), " ", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn search_queries_are_recorded_in_analytics() {
+    with_test_environment(Some(10), |test_env: TestEnvironment<ApiV3>| async move {
+        setup_search_projects(&test_env).await;
+
+        let api = &test_env.api;
+        let test_name = test_env.db.database_name.clone();
+        let query = format!("\"&{test_name}\"");
+        let missing_query = format!("{test_name} nothing matches this");
+
+        for _ in 0..2 {
+            let projects = api
+                .search_deserialized(Some(&format!("  {}  ", query.to_uppercase())), None, None)
+                .await;
+            assert!(projects.total_hits > 0);
+        }
+        let projects = api
+            .search_deserialized(Some(&missing_query), None, None)
+            .await;
+        assert_eq!(projects.total_hits, 0);
+        // Queries that might contain personal details aren't recorded
+        api.search_deserialized(Some("someone@example.com"), None, None)
+            .await;
+
+        // Queries are recorded in the background, so wait for them to show up
+        let mut analytics = None;
+        for _ in 0..20 {
+            let result = api
+                .get_search_analytics_deserialized(None, ADMIN_USER_PAT)
+                .await;
+            if result.top_queries.len() == 2 && result.top_zero_result_queries.len() == 1 {
+                analytics = Some(result);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+        let analytics = analytics.unwrap();
+
+        assert_eq!(analytics.top_queries[0].query, query);
+        assert_eq!(analytics.top_queries[0].count, 2);
+        assert_eq!(analytics.top_queries[1].query, missing_query);
+        assert_eq!(analytics.top_queries[1].count, 1);
+        assert_eq!(analytics.top_zero_result_queries[0].query, missing_query);
+
+        // Other days have their own counters
+        let analytics = api
+            .get_search_analytics_deserialized(Some("2020-01-01"), ADMIN_USER_PAT)
+            .await;
+        assert!(analytics.top_queries.is_empty());
+
+        // Only admins can see what people search for
+        let resp = api.get_search_analytics(None, MOD_USER_PAT).await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn recommendations_follow_categories() {
+    with_test_environment(Some(10), |test_env: TestEnvironment<ApiV3>| async move {
+        let id_conversion = setup_search_projects(&test_env).await;
+
+        let api = &test_env.api;
+        let test_name = test_env.db.database_name.clone();
+
+        // Projects 1 and 2 share their categories
+        let mut followed = Vec::new();
+        for id in [1, 2] {
+            let slug = format!("{test_name}-searchable-project-{id}");
+            let resp = api.follow_project(&slug, FRIEND_USER_PAT).await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+            let project = api.get_project_deserialized(&slug, FRIEND_USER_PAT).await;
+            followed.push(project.id);
+        }
+
+        let resp = api.get_recommendations(20, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let recommendations: Vec<Project> = test::read_body_json(resp).await;
+
+        assert!(!recommendations.is_empty());
+        for project in &recommendations {
+            assert!(!followed.contains(&project.id));
+            assert!(id_conversion.contains_key(&project.id.0));
+            assert!(project
+                .categories
+                .iter()
+                .chain(&project.additional_categories)
+                .any(|x| DUMMY_CATEGORIES[0..2].contains(&x.as_str())));
+        }
+
+        // Anonymous users are recommended popular projects instead
+        let resp = api.get_recommendations(5, None).await;
+        assert_status!(&resp, StatusCode::OK);
+        let recommendations: Vec<Project> = test::read_body_json(resp).await;
+        assert!(!recommendations.is_empty());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn invalid_search_filters_are_rejected() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        // Pairs of:
+        // 1. facets and filters to search with
+        // 2. the clause the error points at, and the attribute it suggests
+        let cases = [
+            (
+                Some(json!([["categories:fabric"], ["categoreis:forge"]])),
+                None,
+                "categoreis:forge",
+                Some("categories"),
+            ),
+            (
+                Some(json!([["downloads>lots"]])),
+                None,
+                "downloads>lots",
+                None,
+            ),
+            (Some(json!([["license>MIT"]])), None, "license>MIT", None),
+            (
+                None,
+                Some("categories = fabric AND folows > 10"),
+                "folows > 10",
+                Some("follows"),
+            ),
+            (
+                None,
+                Some("date_created > yesterday"),
+                "date_created > yesterday",
+                None,
+            ),
+            (
+                None,
+                Some("(categories = fabric"),
+                "(categories = fabric",
+                None,
+            ),
+        ];
+
+        for (facets, filters, clause, suggestion) in cases {
+            let resp = api.search_filtered(facets, filters, USER_USER_PAT).await;
+            assert_status!(&resp, StatusCode::BAD_REQUEST);
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            assert_eq!(body["error"], "invalid_filter");
+            assert_eq!(body["clause"], clause);
+            assert_eq!(body["suggestion"], json!(suggestion));
+            assert!(body["description"].as_str().unwrap().contains(clause));
+        }
+
+        // Valid filters still go through
+        let resp = api
+            .search_filtered(
+                Some(json!([["categories:fabric"], ["downloads>=0"]])),
+                Some("follows >= 0 AND open_source = true"),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn translated_projects_are_shown_and_searched_in_their_language() {
+    with_test_environment(Some(10), |test_env: TestEnvironment<ApiV3>| async move {
+        let id_conversion = setup_search_projects(&test_env).await;
+
+        let api = &test_env.api;
+        let test_name = test_env.db.database_name.clone();
+
+        // Project 0 is owned by the regular user
+        let project_id = id_conversion
+            .iter()
+            .find(|(_, id)| **id == 0)
+            .map(|(project_id, _)| ProjectId(*project_id))
+            .unwrap();
+        let id = project_id.to_string();
+        let original: Project =
+            test::read_body_json(api.get_project(&id, USER_USER_PAT).await).await;
+
+        let german_name = format!("Verzauberte Werkzeuge {test_name}");
+        let translation =
+            || json!({ "name": german_name, "summary": "Eine deutsche Zusammenfassung" });
+
+        // Only members of the project can translate it, and only to valid languages
+        let resp = api
+            .edit_project_translation(&id, "de", translation(), ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let resp = api
+            .edit_project_translation(&id, "german", translation(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let resp = api
+            .edit_project_translation(&id, "DE", translation(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // The translated fields are shown, with the original ones as a fallback
+        let resp = api
+            .get_project_in_language(&id, Some("de"), None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let project: Project = test::read_body_json(resp).await;
+        assert_eq!(project.name, german_name);
+        assert_eq!(project.summary, "Eine deutsche Zusammenfassung");
+        assert_eq!(project.description, original.description);
+        assert_eq!(project.language.as_deref(), Some("de"));
+        assert_eq!(project.fallback.unwrap().name, original.name);
+
+        // The header is used without the parameter, falling back to the next language asked for
+        let resp = api
+            .get_project_in_language(&id, None, Some("fr-FR, de-AT;q=0.8"), USER_USER_PAT)
+            .await;
+        let project: Project = test::read_body_json(resp).await;
+        assert_eq!(project.name, german_name);
+
+        let resp = api
+            .get_project_in_language(&id, Some("fr"), None, USER_USER_PAT)
+            .await;
+        let project: Project = test::read_body_json(resp).await;
+        assert_eq!(project.name, original.name);
+        assert!(project.fallback.is_none());
+
+        // The project is reindexed in the background, so give it a moment to show up
+        let search_german_name = || async {
+            api.search_deserialized(Some("\"Verzauberte Werkzeuge\""), None, USER_USER_PAT)
+                .await
+                .hits
+                .into_iter()
+                .any(|p| p.id == project_id)
+        };
+        let mut found = false;
+        for _ in 0..20 {
+            found = search_german_name().await;
+            if found {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+        assert!(found);
+
+        let resp = api
+            .delete_project_translation(&id, "de", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .delete_project_translation(&id, "de", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let resp = api
+            .get_project_in_language(&id, Some("de"), None, USER_USER_PAT)
+            .await;
+        let project: Project = test::read_body_json(resp).await;
+        assert_eq!(project.name, original.name);
+        assert!(project.language.is_none());
+
+        for _ in 0..20 {
+            found = search_german_name().await;
+            if !found {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+        assert!(!found);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn search_reindex_reports_job_progress() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        // Only admins can see jobs
+        let resp = api.get_jobs("", USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        // Poll the jobs while the reindex runs, recording the statuses it goes through
+        let mut statuses: Vec<String> = Vec::new();
+        let poll = async {
+            loop {
+                let resp = api.get_jobs("type=search_index", ADMIN_USER_PAT).await;
+                assert_status!(&resp, StatusCode::OK);
+                let jobs: Vec<serde_json::Value> = test::read_body_json(resp).await;
+                if let Some(job) = jobs.first() {
+                    let status = job["status"].as_str().unwrap().to_string();
+                    if statuses.last() != Some(&status) {
+                        statuses.push(status.clone());
+                    }
+                    if status != "running" {
+                        return job["id"].as_str().unwrap().to_string();
+                    }
+                }
+                actix_rt::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        };
+        let (resp, job_id) = futures::join!(api.reset_search_index(), poll);
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // The job only stops running once it's done
+        let (finished, running) = statuses.split_last().unwrap();
+        assert_eq!(finished, "succeeded");
+        assert!(running.iter().all(|x| x == "running"));
+
+        let resp = api.get_job(&job_id, ADMIN_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let job: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(job["type"], "search_index");
+        assert!(job["finished"].is_string());
+        assert!(job["error"].is_null());
+        assert!(job["progress"]["total"].as_u64().unwrap() > 0);
+        assert!(job["progress"]["current"].as_u64().unwrap() > 0);
+
+        // Jobs can be filtered by their status
+        let resp = api.get_jobs("status=succeeded", ADMIN_USER_PAT).await;
+        let jobs: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        assert!(jobs.iter().any(|x| x["id"] == job_id.as_str()));
+        let resp = api.get_jobs("status=failed", ADMIN_USER_PAT).await;
+        let jobs: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        assert!(jobs.is_empty());
+
+        let resp = api.get_job("notajob", ADMIN_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn reindex_prunes_documents_of_removed_versions() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let search_config = &test_env.db.search_config;
+        let client = search_config.make_client();
+        let index = client.index(search_config.get_index_name("projects"));
+
+        let resp = api.reset_search_index().await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // A document left behind by a version removed outside the API
+        let fake_version_id = "zzzzzzzz";
+        index
+            .add_or_replace(
+                &[json!({ "version_id": fake_version_id })],
+                Some("version_id"),
+            )
+            .await
+            .unwrap()
+            .wait_for_completion(&client, None, None)
+            .await
+            .unwrap();
+        assert!(index
+            .get_document::<serde_json::Value>(fake_version_id)
+            .await
+            .is_ok());
+
+        let resp = api.reset_search_index().await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Removing documents is queued, so wait for it to be processed
+        let mut removed = false;
+        for _ in 0..50 {
+            if index
+                .get_document::<serde_json::Value>(fake_version_id)
+                .await
+                .is_err()
+            {
+                removed = true;
+                break;
+            }
+            actix_rt::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert!(removed);
+
+        // Documents of versions which still exist are kept
+        let alpha_version_id = &test_env.dummy.project_alpha.version_id;
+        assert!(index
+            .get_document::<serde_json::Value>(alpha_version_id)
+            .await
+            .is_ok());
+
+        let resp = api.get_jobs("type=search_prune", ADMIN_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let jobs: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        assert!(jobs.iter().all(|x| x["status"] == "succeeded"));
+        assert!(!jobs.is_empty());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn search_by_relative_dates() {
+    with_test_environment(Some(10), |test_env: TestEnvironment<ApiV3>| async move {
+        let id_conversion = setup_search_projects(&test_env).await;
+
+        let api = &test_env.api;
+        let query = format!("\"&{}\"", test_env.db.database_name);
+
+        // Everything but project 0 was last updated a few days ago
+        let recent_id = id_conversion
+            .iter()
+            .find(|(_, id)| **id == 0)
+            .map(|(project_id, _)| *project_id)
+            .unwrap();
+        sqlx::query("UPDATE mods SET updated = NOW() - INTERVAL '3 days' WHERE id != $1")
+            .bind(recent_id as i64)
+            .execute(&test_env.db.pool)
+            .await
+            .unwrap();
+        for project_id in id_conversion.keys() {
+            DBProject::clear_cache(
+                DBProjectId(*project_id as i64),
+                None,
+                None,
+                &test_env.db.redis_pool,
+            )
+            .await
+            .unwrap();
+        }
+        let resp = api.reset_search_index().await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let search = |facets, updated_within, created_within| {
+            let query = query.clone();
+            async move {
+                let resp = api
+                    .search_within(
+                        &query,
+                        facets,
+                        updated_within,
+                        created_within,
+                        USER_USER_PAT,
+                    )
+                    .await;
+                assert_status!(&resp, StatusCode::OK);
+                test::read_body_json::<ReturnSearchResults, _>(resp).await
+            }
+        };
+        let found_ids = |results: &ReturnSearchResults| {
+            let mut ids = results
+                .hits
+                .iter()
+                .map(|p| id_conversion[&p.id.0])
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+
+        let before = Utc::now();
+        let results = search(None, Some("P1D"), None).await;
+        assert_eq!(found_ids(&results), vec![0]);
+        // The cutoff is returned so that clients can show it
+        let updated_after = results.updated_after.unwrap();
+        assert!(updated_after >= before - Duration::days(1));
+        assert!(updated_after <= Utc::now() - Duration::days(1));
+        assert!(results.created_after.is_none());
+
+        let all_ids = found_ids(&search(None, None, None).await);
+        let results = search(None, Some("P7D"), None).await;
+        assert_eq!(found_ids(&results), all_ids);
+
+        // The cutoffs are combined with the other filters
+        let results = search(Some(json!([["categories:forge"]])), Some("P1D"), None).await;
+        assert!(results.hits.is_empty());
+        let results = search(Some(json!([["categories:forge"]])), Some("P7D"), None).await;
+        assert_eq!(found_ids(&results), vec![7]);
+
+        // Everything was created just now
+        let results = search(None, Some("PT12H"), Some("P1W")).await;
+        assert_eq!(found_ids(&results), vec![0]);
+        assert!(results.created_after.is_some());
+
+        for (updated_within, created_within) in [
+            (Some("P1"), None),
+            (Some("1 day"), None),
+            (None, Some("PT")),
+            // Durations longer than 10 years are rejected
+            (Some("P11Y"), None),
+        ] {
+            let resp = api
+                .search_within(&query, None, updated_within, created_within, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::BAD_REQUEST);
+        }
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn search_all_groups_users_and_leaves_out_banned_ones() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        let resp = api.reset_search_index().await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let results = api.search_all_deserialized("frien", USER_USER_PAT).await;
+        assert!(results
+            .users
+            .hits
+            .iter()
+            .any(|x| x.user_id == FRIEND_USER_ID));
+        assert!(results.users.total_hits >= 1);
+        let results = api.search_all_deserialized("enem", USER_USER_PAT).await;
+        assert!(results
+            .users
+            .hits
+            .iter()
+            .any(|x| x.user_id == ENEMY_USER_ID));
+
+        // Only moderators can ban users
+        let resp = api
+            .edit_user(ENEMY_USER_ID, json!({ "banned": true }), ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        // Banned users are removed from search right away, and left out of later reindexes
+        let resp = api
+            .edit_user(ENEMY_USER_ID, json!({ "banned": true }), MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let results = api.search_all_deserialized("enem", USER_USER_PAT).await;
+        assert!(!results
+            .users
+            .hits
+            .iter()
+            .any(|x| x.user_id == ENEMY_USER_ID));
+
+        let resp = api.reset_search_index().await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let results = api.search_all_deserialized("enem", USER_USER_PAT).await;
+        assert!(!results
+            .users
+            .hits
+            .iter()
+            .any(|x| x.user_id == ENEMY_USER_ID));
+        let results = api.search_all_deserialized("frien", USER_USER_PAT).await;
+        assert!(results
+            .users
+            .hits
+            .iter()
+            .any(|x| x.user_id == FRIEND_USER_ID));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn full_reindex_pages_through_every_version() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let pool = &test_env.db.pool;
+        let search_config = &test_env.db.search_config;
+        let alpha = &test_env.dummy.project_alpha;
+        let alpha_project_id = parse_base62(&alpha.project_id).unwrap() as i64;
+        let alpha_version_id = parse_base62(&alpha.version_id).unwrap() as i64;
+
+        // Enough versions to take a few pages, spread over copies of the alpha project
+        let projects = 1200_i64;
+        let versions_per_project = 5_i64;
+        let first_id = 1_000_000_000_i64;
+        sqlx::query(
+            "
+            INSERT INTO mods
+            SELECT (jsonb_populate_record(m, jsonb_build_object('id', $2 + n, 'slug', 'synthetic-' || n))).*
+            FROM mods m, generate_series(1, $3) n
+            WHERE m.id = $1
+            ",
+        )
+        .bind(alpha_project_id)
+        .bind(first_id)
+        .bind(projects)
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "
+            INSERT INTO threads
+            SELECT (jsonb_populate_record(t, jsonb_build_object('id', $2 + n, 'mod_id', $2 + n))).*
+            FROM threads t, generate_series(1, $3) n
+            WHERE t.mod_id = $1
+            ",
+        )
+        .bind(alpha_project_id)
+        .bind(first_id)
+        .bind(projects)
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "
+            INSERT INTO versions
+            SELECT (jsonb_populate_record(v, jsonb_build_object('id', $2 + n * $4 + k, 'mod_id', $2 + n))).*
+            FROM versions v, generate_series(1, $3) n, generate_series(1, $4) k
+            WHERE v.id = $1
+            ",
+        )
+        .bind(alpha_version_id)
+        .bind(first_id)
+        .bind(projects)
+        .bind(versions_per_project)
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let expected = get_all_ids(pool.clone(), None).await.unwrap().len();
+        assert!(expected > (projects * versions_per_project) as usize);
+
+        let resp = api.reset_search_index().await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let client = search_config.make_client();
+        for index in ["projects", "projects_filtered", "projects_versions"] {
+            let stats = client
+                .index(search_config.get_index_name(index))
+                .get_stats()
+                .await
+                .unwrap();
+            assert_eq!(stats.number_of_documents, expected);
+        }
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn search_grouped_by_version_returns_each_matching_version() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha = &test_env.dummy.project_alpha;
+
+        // Alpha's first version is for 1.20.1, and its second for 1.20.5
+        let version = api
+            .add_public_version_deserialized(
+                ProjectId(parse_base62(&alpha.project_id).unwrap()),
+                "1.0.1",
+                TestFile::build_random_jar(),
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        let version_id = version.id.to_string();
+        let resp = api
+            .edit_version(
+                &version_id,
+                json!({ "game_versions": ["1.20.5"] }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = api.reset_search_index().await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let search = |game_versions: &str, group: &'static str, offset, limit| {
+            let filters = format!(
+                "project_id = \"{}\" AND game_versions IN [{game_versions}]",
+                alpha.project_id
+            );
+            async move {
+                let resp = api
+                    .search_grouped(&filters, group, offset, limit, USER_USER_PAT)
+                    .await;
+                assert_status!(&resp, StatusCode::OK);
+                test::read_body_json::<serde_json::Value, _>(resp).await
+            }
+        };
+        let both = "\"1.20.1\", \"1.20.5\"";
+
+        let results = search(both, "project", 0, 10).await;
+        assert_eq!(results["total_hits"], 1);
+        let hits = results["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].get("version").is_none());
+
+        let results = search(both, "version", 0, 10).await;
+        assert_eq!(results["total_hits"], 2);
+        let hits = results["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|x| x["id"] == alpha.project_id.as_str()));
+        let mut game_versions = hits
+            .iter()
+            .map(|x| x["version"]["game_versions"].clone())
+            .collect::<Vec<_>>();
+        game_versions.sort_by_key(|x| x.to_string());
+        assert_eq!(game_versions, [json!(["1.20.1"]), json!(["1.20.5"])]);
+        assert!(hits
+            .iter()
+            .all(|x| !x["version"]["loaders"].as_array().unwrap().is_empty()));
+
+        // Pages are of versions rather than projects
+        let results = search(both, "version", 1, 1).await;
+        assert_eq!(results["total_hits"], 2);
+        assert_eq!(results["hits"].as_array().unwrap().len(), 1);
+
+        // Only the versions for the filtered game version match
+        let results = search("\"1.20.5\"", "version", 0, 10).await;
+        assert_eq!(results["total_hits"], 1);
+        assert_eq!(results["hits"][0]["version"]["id"], version_id.as_str());
+
+        let resp = api.search_grouped("", "file", 0, 10, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn display_categories_are_indexed() {
+    with_test_environment(Some(10), |test_env: TestEnvironment<ApiV3>| async move {
+        let id_conversion = setup_search_projects(&test_env).await;
+
+        let api = &test_env.api;
+        let test_name = test_env.db.database_name.clone();
+
+        // Project 0 is a mod owned by the regular user
+        let project_id = id_conversion
+            .iter()
+            .find(|(_, id)| **id == 0)
+            .map(|(project_id, _)| ProjectId(*project_id))
+            .unwrap()
+            .to_string();
+
+        // Categories of other project types can't be used
+        let resp = api
+            .create_category(
+                json!({ "name": "shaders", "project_type": "datapack", "icon": "<svg></svg>" }),
+                ADMIN_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let resp = api
+            .edit_project(
+                &project_id,
+                json!({ "categories": ["shaders"] }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["description"].as_str().unwrap().contains("shaders"));
+
+        // Display categories have to be categories of the project
+        let resp = api
+            .edit_project(
+                &project_id,
+                json!({ "display_categories": [DUMMY_CATEGORIES[0]] }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let resp = api
+            .edit_project(
+                &project_id,
+                json!({
+                    "categories": [DUMMY_CATEGORIES[0], DUMMY_CATEGORIES[2]],
+                    "display_categories": [DUMMY_CATEGORIES[2]],
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let project = api
+            .get_project_deserialized(&project_id, USER_USER_PAT)
+            .await;
+        assert_eq!(project.display_categories, vec![DUMMY_CATEGORIES[2]]);
+        assert!(project
+            .categories
+            .contains(&DUMMY_CATEGORIES[0].to_string()));
+
+        // The project is reindexed in the background
+        let mut hit = None;
+        for _ in 0..20 {
+            let projects = api
+                .search_deserialized(Some(&format!("\"&{test_name}\"")), None, USER_USER_PAT)
+                .await;
+            hit = projects
+                .hits
+                .into_iter()
+                .find(|p| p.id.to_string() == project_id);
+            if hit
+                .as_ref()
+                .is_some_and(|x| x.display_categories == vec![DUMMY_CATEGORIES[2]])
+            {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+        let hit = hit.unwrap();
+        assert_eq!(hit.display_categories, vec![DUMMY_CATEGORIES[2]]);
+        assert!(hit.categories.contains(&DUMMY_CATEGORIES[0].to_string()));
+
+        // Categories can't be removed while projects are in them
+        let resp = api
+            .delete_category("mod", DUMMY_CATEGORIES[0], ADMIN_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let resp = api
+            .delete_category("datapack", "shaders", ADMIN_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+    })
+    .await;
+}
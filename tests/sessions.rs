@@ -0,0 +1,132 @@
+use actix_http::StatusCode;
+use actix_web::{dev::ServiceResponse, test};
+use common::{
+    api_v3::ApiV3,
+    database::USER_USER_ID_PARSED,
+    environment::{with_test_environment, TestEnvironment},
+};
+use labrinth::database::models::session_item::{Session, SessionBuilder};
+use labrinth::database::models::UserId;
+
+mod common;
+
+async fn create_session(test_env: &TestEnvironment<ApiV3>, session: &str) {
+    let mut transaction = test_env.db.pool.begin().await.unwrap();
+    SessionBuilder {
+        session: session.to_string(),
+        user_id: UserId(USER_USER_ID_PARSED),
+        os: None,
+        platform: None,
+        city: None,
+        country: Some("US".to_string()),
+        ip: "127.0.0.1".to_string(),
+        user_agent: "labrinth-test".to_string(),
+    }
+    .insert(&mut transaction)
+    .await
+    .unwrap();
+    transaction.commit().await.unwrap();
+
+    Session::clear_cache(
+        vec![(None, None, Some(UserId(USER_USER_ID_PARSED)))],
+        &test_env.db.redis_pool,
+    )
+    .await
+    .unwrap();
+}
+
+fn session_request(req: test::TestRequest, session: &str) -> actix_http::Request {
+    req.append_header(("Authorization", session))
+        .append_header(("User-Agent", "labrinth-test"))
+        .peer_addr("127.0.0.1:8000".parse().unwrap())
+        .to_request()
+}
+
+async fn list_sessions(test_env: &TestEnvironment<ApiV3>, session: &str) -> ServiceResponse {
+    let req = session_request(test::TestRequest::get().uri("/v3/auth/sessions"), session);
+    test_env.call(req).await
+}
+
+#[actix_rt::test]
+pub async fn revoked_sessions_stop_authenticating() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        create_session(&test_env, "mra_sessiontestone").await;
+        create_session(&test_env, "mra_sessiontesttwo").await;
+
+        let resp = list_sessions(&test_env, "mra_sessiontestone").await;
+        assert_status!(&resp, StatusCode::OK);
+        let sessions: serde_json::Value = test::read_body_json(resp).await;
+        let sessions = sessions.as_array().unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(
+            sessions
+                .iter()
+                .filter(|x| x["current"].as_bool().unwrap())
+                .count(),
+            1
+        );
+        // Only the country of a session is shown, not its IP address
+        for session in sessions {
+            assert_eq!(session["country"], "US");
+            assert!(session.get("ip").is_none());
+            assert!(session.get("city").is_none());
+        }
+        let other_id = sessions
+            .iter()
+            .find(|x| !x["current"].as_bool().unwrap())
+            .unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        // Revoke the second session from the first one
+        let req = session_request(
+            test::TestRequest::delete().uri(&format!("/v3/auth/sessions/{other_id}")),
+            "mra_sessiontestone",
+        );
+        let resp = test_env.call(req).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = list_sessions(&test_env, "mra_sessiontesttwo").await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = list_sessions(&test_env, "mra_sessiontestone").await;
+        assert_status!(&resp, StatusCode::OK);
+        let sessions: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(sessions.as_array().unwrap().len(), 1);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+pub async fn revoking_other_sessions_keeps_current_session() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        create_session(&test_env, "mra_sessiontestone").await;
+        create_session(&test_env, "mra_sessiontesttwo").await;
+        create_session(&test_env, "mra_sessiontestthree").await;
+
+        let req = session_request(
+            test::TestRequest::delete().uri("/v3/auth/sessions?except_current=true"),
+            "mra_sessiontestone",
+        );
+        let resp = test_env.call(req).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        for session in ["mra_sessiontesttwo", "mra_sessiontestthree"] {
+            let resp = list_sessions(&test_env, session).await;
+            assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        }
+        let resp = list_sessions(&test_env, "mra_sessiontestone").await;
+        assert_status!(&resp, StatusCode::OK);
+
+        // Without the flag, the current session is revoked as well
+        let req = session_request(
+            test::TestRequest::delete().uri("/v3/auth/sessions"),
+            "mra_sessiontestone",
+        );
+        let resp = test_env.call(req).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = list_sessions(&test_env, "mra_sessiontestone").await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+    })
+    .await;
+}
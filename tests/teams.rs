@@ -1,10 +1,15 @@
 use crate::common::{api_common::ApiTeams, database::*};
 use actix_http::StatusCode;
+use actix_web::test;
 use common::{
     api_v3::ApiV3,
     environment::{with_test_environment, with_test_environment_all, TestEnvironment},
 };
+use labrinth::models::audit_log::{AuditAction, AuditTargetType};
+use labrinth::models::ids::UserId;
+use labrinth::models::notifications::{Notification, NotificationBody};
 use labrinth::models::teams::{OrganizationPermissions, ProjectPermissions};
+use labrinth::routes::v3::teams::{BulkInviteResult, InviteErrorKind};
 use rust_decimal::Decimal;
 use serde_json::json;
 
@@ -571,6 +576,150 @@ async fn transfer_ownership_v3() {
     .await;
 }
 
+#[actix_rt::test]
+async fn team_members_show_join_dates_and_inviters() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_team_id = &test_env.dummy.project_alpha.team_id;
+
+        let resp = api
+            .add_user_to_team(alpha_team_id, FRIEND_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // The owner can manage invites, so sees who invited the pending member
+        let members = api
+            .get_team_members_deserialized(alpha_team_id, USER_USER_PAT)
+            .await;
+        let friend_member = members
+            .iter()
+            .find(|x| x.user.id.0 == FRIEND_USER_ID_PARSED as u64)
+            .unwrap();
+        assert!(!friend_member.accepted);
+        assert!(friend_member.accepted_at.is_none());
+        assert_eq!(
+            friend_member.invited_by,
+            Some(UserId(USER_USER_ID_PARSED as u64))
+        );
+        let invited_at = friend_member.created;
+
+        // Members without the permission to manage invites don't
+        let resp = api
+            .add_user_to_team(
+                alpha_team_id,
+                ENEMY_USER_ID,
+                Some(ProjectPermissions::empty()),
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(alpha_team_id, ENEMY_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        for pat in [ENEMY_USER_PAT, FRIEND_USER_PAT] {
+            let members = api.get_team_members_deserialized(alpha_team_id, pat).await;
+            let friend_member = members
+                .iter()
+                .find(|x| x.user.id.0 == FRIEND_USER_ID_PARSED as u64)
+                .unwrap();
+            assert!(friend_member.invited_by.is_none());
+        }
+
+        let resp = api.join_team(alpha_team_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let members = api
+            .get_team_members_deserialized(alpha_team_id, USER_USER_PAT)
+            .await;
+        let friend_member = members
+            .iter()
+            .find(|x| x.user.id.0 == FRIEND_USER_ID_PARSED as u64)
+            .unwrap();
+        assert!(friend_member.accepted);
+        assert!(friend_member.accepted_at.unwrap() >= invited_at);
+        assert!(friend_member.invited_by.is_none());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn team_member_edits_are_audit_logged() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let alpha_team_id = &test_env.dummy.project_alpha.team_id;
+
+        let resp = api
+            .add_user_to_team(
+                alpha_team_id,
+                FRIEND_USER_ID,
+                Some(ProjectPermissions::UPLOAD_VERSION),
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(alpha_team_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let new_permissions = ProjectPermissions::UPLOAD_VERSION | ProjectPermissions::EDIT_BODY;
+        let resp = api
+            .edit_team_member(
+                alpha_team_id,
+                FRIEND_USER_ID,
+                json!({ "permissions": new_permissions.bits() }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let entries = api
+            .get_project_audit_log_deserialized(
+                alpha_project_id,
+                Some("team_member_edit"),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.actor_id.to_string(), USER_USER_ID);
+        assert_eq!(entry.action, AuditAction::TeamMemberEdit);
+        assert_eq!(entry.target_type, AuditTargetType::User);
+        assert_eq!(entry.target_id, FRIEND_USER_ID);
+        assert_eq!(
+            entry.summary["permissions"],
+            json!({
+                "old": ProjectPermissions::UPLOAD_VERSION.bits(),
+                "new": new_permissions.bits(),
+            })
+        );
+        // Only moderators can see where an action was taken from
+        assert!(entry.ip.is_none());
+
+        // Other actions are filtered out
+        let entries = api
+            .get_project_audit_log_deserialized(
+                alpha_project_id,
+                Some("project_delete"),
+                USER_USER_PAT,
+            )
+            .await;
+        assert!(entries.is_empty());
+
+        // Members need permission to view the log, and non-members can't see it exists
+        let resp = api
+            .get_project_audit_log(alpha_project_id, None, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .get_project_audit_log(alpha_project_id, None, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+    })
+    .await;
+}
+
 // This test is currently not working.
 // #[actix_rt::test]
 // pub async fn no_acceptance_permissions() {
@@ -619,3 +768,97 @@ async fn transfer_ownership_v3() {
 
 //     test_env.cleanup().await;
 // }
+
+// Gets the user's notifications of invites to the team
+async fn get_team_invites(
+    api: &ApiV3,
+    user_id: &str,
+    pat: Option<&str>,
+    team_id: &str,
+) -> Vec<Notification> {
+    let resp = api.get_user_notifications(user_id, pat).await;
+    assert_status!(&resp, StatusCode::OK);
+    let notifications: Vec<Notification> = test::read_body_json(resp).await;
+    notifications
+        .into_iter()
+        .filter(|x| {
+            matches!(&x.body, NotificationBody::TeamInvite { team_id: invited_to, .. }
+                if invited_to.to_string() == team_id)
+        })
+        .collect()
+}
+
+#[actix_rt::test]
+async fn bulk_invites_report_each_result() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_team_id = &test_env.dummy.project_alpha.team_id;
+
+        let resp = api
+            .add_users_to_team_bulk(
+                alpha_team_id,
+                json!([
+                    { "user": FRIEND_USER_ID, "role": "Tester" },
+                    { "user": USER_USER_ID },
+                    { "user": "not-a-user" },
+                ]),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::MULTI_STATUS);
+        let results: Vec<BulkInviteResult> = test::read_body_json(resp).await;
+        assert_eq!(results.len(), 3);
+
+        assert_eq!(results[0].user, FRIEND_USER_ID);
+        assert!(results[0].success);
+        assert!(results[0].error.is_none());
+        assert!(!results[1].success);
+        assert_eq!(
+            results[1].error.as_ref().unwrap().error,
+            InviteErrorKind::AlreadyMember
+        );
+        assert_eq!(results[2].user, "not-a-user");
+        assert!(!results[2].success);
+        assert_eq!(
+            results[2].error.as_ref().unwrap().error,
+            InviteErrorKind::UserNotFound
+        );
+
+        let members = api
+            .get_team_members_deserialized(alpha_team_id, USER_USER_PAT)
+            .await;
+        let friend_member = members
+            .iter()
+            .find(|x| x.user.id.0 == FRIEND_USER_ID_PARSED as u64)
+            .unwrap();
+        assert!(!friend_member.accepted);
+        assert_eq!(friend_member.role, "Tester");
+
+        // Only the valid invite notified its user
+        let invites = get_team_invites(api, FRIEND_USER_ID, FRIEND_USER_PAT, alpha_team_id).await;
+        assert_eq!(invites.len(), 1);
+        let invites = get_team_invites(api, USER_USER_ID, USER_USER_PAT, alpha_team_id).await;
+        assert!(invites.is_empty());
+
+        // The permission to manage invites is still needed
+        let resp = api
+            .add_users_to_team_bulk(
+                alpha_team_id,
+                json!([{ "user": ENEMY_USER_ID }]),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        let too_many = (0..51).map(|x| json!({ "user": format!("user-{x}") }));
+        let resp = api
+            .add_users_to_team_bulk(
+                alpha_team_id,
+                serde_json::Value::Array(too_many.collect()),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
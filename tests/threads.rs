@@ -0,0 +1,250 @@
+use std::pin::Pin;
+use std::time::Duration;
+
+use actix_http::body::{BoxBody, MessageBody};
+use actix_http::ws::{OpCode, Parser};
+use actix_http::StatusCode;
+use actix_web::test;
+use bytes::BytesMut;
+use common::{
+    api_v3::ApiV3,
+    database::{
+        ENEMY_USER_ID, ENEMY_USER_PAT, FRIEND_USER_ID, FRIEND_USER_PAT, MOD_USER_ID, MOD_USER_PAT,
+        USER_USER_ID, USER_USER_PAT,
+    },
+    environment::{with_test_environment, TestEnvironment},
+};
+use serde_json::Value;
+
+use crate::common::api_common::{ApiProject, ApiTeams};
+
+mod common;
+
+/// A client's end of a thread socket, reading the frames the server sends
+struct ThreadSocket {
+    body: BoxBody,
+    buf: BytesMut,
+}
+
+impl ThreadSocket {
+    fn new(resp: actix_web::dev::ServiceResponse) -> Self {
+        assert_status!(&resp, StatusCode::SWITCHING_PROTOCOLS);
+        Self {
+            body: resp.into_body(),
+            buf: BytesMut::new(),
+        }
+    }
+
+    /// Waits for the next update sent over the socket, panicking if none arrives in time
+    async fn next_update(&mut self) -> Value {
+        loop {
+            if let Some((_, opcode, payload)) = Parser::parse(&mut self.buf, false, 65536).unwrap()
+            {
+                if opcode == OpCode::Text {
+                    return serde_json::from_slice(&payload.unwrap()).unwrap();
+                }
+                continue;
+            }
+
+            let chunk = actix_rt::time::timeout(
+                Duration::from_secs(5),
+                std::future::poll_fn(|cx| Pin::new(&mut self.body).poll_next(cx)),
+            )
+            .await
+            .expect("No update was sent over the socket")
+            .expect("The socket was closed")
+            .unwrap();
+            self.buf.extend_from_slice(&chunk);
+        }
+    }
+}
+
+#[actix_rt::test]
+pub async fn thread_sockets_receive_message_updates() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_thread_id = test_env.dummy.project_alpha.thread_id.clone();
+        let api = &test_env.api;
+
+        // Only users who can view the thread may subscribe to it
+        let resp = api.open_thread_socket(&alpha_thread_id, None, None).await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .open_thread_socket(&alpha_thread_id, None, ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        // The token can be passed in the query, as browsers can't set headers on sockets
+        let mut user_socket = ThreadSocket::new(
+            api.open_thread_socket(&alpha_thread_id, USER_USER_PAT, None)
+                .await,
+        );
+        let mut mod_socket = ThreadSocket::new(
+            api.open_thread_socket(&alpha_thread_id, None, MOD_USER_PAT)
+                .await,
+        );
+
+        let resp = api
+            .write_to_thread(&alpha_thread_id, "text", "Hello sockets", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let update = user_socket.next_update().await;
+        assert_eq!(update["type"], "message");
+        assert_eq!(update["message"]["body"]["body"], "Hello sockets");
+        assert_eq!(update["message"]["author_id"], USER_USER_ID);
+        let message_id = update["message"]["id"].as_str().unwrap().to_string();
+        assert_eq!(mod_socket.next_update().await, update);
+
+        // Moderators are anonymous to other users
+        let resp = api
+            .write_to_thread(
+                &alpha_thread_id,
+                "text",
+                "Hello from a moderator",
+                MOD_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let update = user_socket.next_update().await;
+        assert_eq!(update["message"]["body"]["body"], "Hello from a moderator");
+        assert!(update["message"]["author_id"].is_null());
+        let update = mod_socket.next_update().await;
+        assert!(update["message"]["author_id"].is_string());
+
+        let resp = api.delete_thread_message(&message_id, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let update = user_socket.next_update().await;
+        assert_eq!(update["type"], "message_deleted");
+        assert_eq!(update["id"], message_id.as_str());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+pub async fn thread_sockets_are_limited_per_user() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_thread_id = test_env.dummy.project_alpha.thread_id.clone();
+        let api = &test_env.api;
+
+        let mut sockets = Vec::new();
+        for _ in 0..5 {
+            sockets.push(ThreadSocket::new(
+                api.open_thread_socket(&alpha_thread_id, None, USER_USER_PAT)
+                    .await,
+            ));
+        }
+
+        let resp = api
+            .open_thread_socket(&alpha_thread_id, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // Other users have their own limit
+        let resp = api
+            .open_thread_socket(&alpha_thread_id, None, MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::SWITCHING_PROTOCOLS);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+pub async fn blocked_users_cannot_post_in_project_threads() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let alpha_project_id = test_env.dummy.project_alpha.project_id.clone();
+        let alpha_team_id = test_env.dummy.project_alpha.team_id.clone();
+        let alpha_thread_id = test_env.dummy.project_alpha.thread_id.clone();
+        let api = &test_env.api;
+
+        let resp = api
+            .add_user_to_team(&alpha_team_id, FRIEND_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(&alpha_team_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Blocking takes the permission to manage invites
+        let resp = api
+            .block_project_user(&alpha_project_id, ENEMY_USER_ID, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        for _ in 0..2 {
+            let resp = api
+                .block_project_user(&alpha_project_id, ENEMY_USER_ID, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+
+        let resp = api
+            .get_project_blocks(&alpha_project_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let blocks: Vec<Value> = test::read_body_json(resp).await;
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0]["user_id"], ENEMY_USER_ID);
+        assert_eq!(blocks[0]["blocked_by"], USER_USER_ID);
+
+        // Blocked users can't be invited, nor accept an invite sent before they were blocked
+        let resp = api
+            .add_user_to_team(&alpha_team_id, ENEMY_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let resp = api
+            .unblock_project_user(&alpha_project_id, ENEMY_USER_ID, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .add_user_to_team(&alpha_team_id, ENEMY_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .block_project_user(&alpha_project_id, ENEMY_USER_ID, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(&alpha_team_id, ENEMY_USER_PAT).await;
+        assert_status!(&resp, StatusCode::FORBIDDEN);
+
+        let resp = api
+            .unblock_project_user(&alpha_project_id, ENEMY_USER_ID, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(&alpha_team_id, ENEMY_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Blocked members can't post, while the rest of the team and moderators can
+        for user_id in [ENEMY_USER_ID, MOD_USER_ID] {
+            let resp = api
+                .block_project_user(&alpha_project_id, user_id, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+        let resp = api
+            .write_to_thread(&alpha_thread_id, "text", "Go away", ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::FORBIDDEN);
+        let body: Value = test::read_body_json(resp).await;
+        assert!(!body["description"].as_str().unwrap().contains("block"));
+        for pat in [FRIEND_USER_PAT, MOD_USER_PAT] {
+            let resp = api
+                .write_to_thread(&alpha_thread_id, "text", "Hello", pat)
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+
+        // Unblocking lets them post again
+        let resp = api
+            .unblock_project_user(&alpha_project_id, ENEMY_USER_ID, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .write_to_thread(&alpha_thread_id, "text", "Sorry", ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = api
+            .unblock_project_user(&alpha_project_id, ENEMY_USER_ID, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+    })
+    .await;
+}
@@ -1,9 +1,27 @@
-use crate::common::api_common::{ApiProject, ApiTeams};
+use crate::common::api_common::{Api, ApiProject, ApiTeams, ApiUser};
+use actix_http::StatusCode;
+use actix_web::dev::ServiceResponse;
+use actix_web::test;
 use common::dummy_data::TestFile;
 use common::{
-    database::{FRIEND_USER_ID, FRIEND_USER_PAT, USER_USER_ID, USER_USER_PAT},
-    environment::with_test_environment_all,
+    api_v3::ApiV3,
+    database::{
+        ADMIN_USER_PAT, ENEMY_USER_ID, ENEMY_USER_PAT, FRIEND_USER_ID, FRIEND_USER_ID_PARSED,
+        FRIEND_USER_PAT, MOD_USER_ID, MOD_USER_PAT, USER_USER_ID, USER_USER_ID_PARSED,
+        USER_USER_PAT,
+    },
+    environment::{with_test_environment, with_test_environment_all, TestEnvironment},
 };
+use labrinth::file_hosting::MockHost;
+use labrinth::models::ids::base62_impl::parse_base62;
+use labrinth::models::notifications::{Notification, NotificationBody};
+use labrinth::models::payouts::{PayoutOutcome, UserPayoutMethod};
+use labrinth::routes::v3::user_exports::purge_expired_exports;
+use labrinth::routes::v3::user_payouts::PayoutHistory;
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::io::Read;
+use std::str::FromStr;
 
 mod common;
 
@@ -15,6 +33,8 @@ mod common;
 // patch user
 // patch user icon
 // user follows
+// user data export
+// user payouts
 
 #[actix_rt::test]
 pub async fn get_user_projects_after_creating_project_returns_new_project() {
@@ -103,3 +123,677 @@ pub async fn get_user_projects_after_leaving_team_shows_no_team_projects() {
     })
     .await;
 }
+
+#[actix_rt::test]
+pub async fn payout_methods_are_validated_and_masked() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        // Only the user themselves (or an admin) can set their payout method
+        let resp = api
+            .edit_payout_method(
+                USER_USER_ID,
+                json!({ "type": "paypal", "email": "user@modrinth.com" }),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        for invalid in [
+            json!({ "type": "paypal", "email": "not-an-email" }),
+            json!({ "type": "venmo", "handle": "a b" }),
+            json!({
+                "type": "bank_transfer",
+                "account_holder": "User",
+                "iban": "GB82WEST12345698765431",
+            }),
+            json!({
+                "type": "bank_transfer",
+                "account_holder": "User",
+                "iban": "GB82WEST12345698765432",
+                "bic": "NOTABIC",
+            }),
+        ] {
+            let resp = api
+                .edit_payout_method(USER_USER_ID, invalid, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::BAD_REQUEST);
+        }
+
+        // A PayPal email chosen here isn't verified, so payouts aren't sent to it
+        let resp = api
+            .edit_payout_method(
+                USER_USER_ID,
+                json!({ "type": "paypal", "email": "someone-else@modrinth.com" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.get_current_user(USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let user: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(user["payout_data"]["method"]["type"], "paypal");
+        assert!(user["payout_data"]["paypal_address"].is_null());
+
+        let resp = api
+            .edit_payout_method(
+                USER_USER_ID,
+                json!({ "type": "venmo", "handle": "@user-handle" }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = api
+            .edit_payout_method(
+                USER_USER_ID,
+                json!({
+                    "type": "bank_transfer",
+                    "account_holder": "User",
+                    "iban": "GB82 WEST 1234 5698 7654 32",
+                    "bic": "WESTGB2L",
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Sensitive details are masked when read back
+        let resp = api.get_current_user(USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let user: serde_json::Value = test::read_body_json(resp).await;
+        let method = &user["payout_data"]["method"];
+        assert_eq!(method["type"], "bank_transfer");
+        assert_eq!(method["account_holder"], "User");
+        let iban = method["iban"].as_str().unwrap();
+        assert!(iban.ends_with("5432"));
+        assert!(!iban.contains("WEST"));
+
+        // The full details are only stored encrypted, and are loaded for sending payouts
+        let stored: Vec<u8> = sqlx::query_scalar("SELECT payout_method FROM users WHERE id = $1")
+            .bind(USER_USER_ID_PARSED)
+            .fetch_one(&test_env.db.pool)
+            .await
+            .unwrap();
+        assert!(!String::from_utf8_lossy(&stored).contains("GB82"));
+        let method = labrinth::database::models::User::get_payout_method(
+            labrinth::database::models::UserId(USER_USER_ID_PARSED),
+            &test_env.db.pool,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            method,
+            Some(UserPayoutMethod::BankTransfer { iban, .. }) if iban == "GB82WEST12345698765432"
+        ));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+pub async fn payout_history_and_statement_cover_the_range() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        for (id, created, amount, fee, status) in [
+            (1001, "2023-12-31T12:00:00Z", "99.99", "1", "success"),
+            (1002, "2024-01-05T12:00:00Z", "10.50", "0.25", "success"),
+            (1003, "2024-01-20T12:00:00Z", "5.25", "0.10", "success"),
+            (1004, "2024-01-25T12:00:00Z", "7.00", "0.50", "failed"),
+            (1005, "2024-02-10T12:00:00Z", "20.00", "1.00", "in-transit"),
+            (1006, "2024-02-15T12:00:00Z", "3.333", "0.001", "success"),
+        ] {
+            sqlx::query(
+                "
+                INSERT INTO payouts (id, user_id, created, amount, fee, status, method)
+                VALUES ($1, $2, $3::timestamptz, $4::numeric, $5::numeric, $6, 'paypal')
+                ",
+            )
+            .bind(id as i64)
+            .bind(USER_USER_ID_PARSED)
+            .bind(created)
+            .bind(amount)
+            .bind(fee)
+            .bind(status)
+            .execute(&test_env.db.pool)
+            .await
+            .unwrap();
+        }
+
+        // Only the user and admins can see their payouts
+        let resp = api
+            .get_user_payouts(USER_USER_ID, None, None, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .get_user_payout_statement(USER_USER_ID, 2024, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api
+            .get_user_payouts(USER_USER_ID, None, None, ADMIN_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+
+        let resp = api
+            .get_user_payouts(
+                USER_USER_ID,
+                Some("2024-01-01T00:00:00Z"),
+                Some("2024-02-01T00:00:00Z"),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        // Amounts are exact decimal strings
+        assert_eq!(body["payouts"][0]["amount"], "7");
+        assert_eq!(body["payouts"][0]["fee"], "0.5");
+        let history: PayoutHistory = serde_json::from_value(body).unwrap();
+        assert_eq!(history.total, 3);
+        assert_eq!(
+            history
+                .payouts
+                .iter()
+                .map(|x| x.outcome)
+                .collect::<Vec<_>>(),
+            [
+                PayoutOutcome::Failed,
+                PayoutOutcome::Paid,
+                PayoutOutcome::Paid
+            ]
+        );
+        assert!(history.payouts.iter().all(|x| x.currency == "USD"));
+
+        let resp = api
+            .get_user_payout_statement(USER_USER_ID, 2024, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let mut reader = csv::Reader::from_reader(body.as_ref());
+        let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+        let kinds = records
+            .iter()
+            .map(|x| (x[0].to_string(), x[1].to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                ("payout", "2024-01"),
+                ("payout", "2024-01"),
+                ("payout", "2024-01"),
+                ("month_total", "2024-01"),
+                ("payout", "2024-02"),
+                ("payout", "2024-02"),
+                ("month_total", "2024-02"),
+            ]
+            .map(|(kind, month)| (kind.to_string(), month.to_string()))
+        );
+        assert_eq!(&records[2][6], "failed");
+        assert_eq!(&records[4][6], "pending");
+
+        // Only paid payouts count towards the totals
+        let total =
+            |record: &csv::StringRecord, column: usize| Decimal::from_str(&record[column]).unwrap();
+        assert_eq!(total(&records[3], 8), Decimal::from_str("15.75").unwrap());
+        assert_eq!(total(&records[3], 9), Decimal::from_str("0.35").unwrap());
+        assert_eq!(total(&records[6], 8), Decimal::from_str("3.333").unwrap());
+        assert_eq!(total(&records[6], 9), Decimal::from_str("0.001").unwrap());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+pub async fn user_data_export_produces_archive_with_expiring_link() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        // Users can only export their own data
+        let resp = api.create_user_export(USER_USER_ID, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        let resp = api.create_user_export(USER_USER_ID, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::ACCEPTED);
+
+        // Only one export can be requested a day
+        let resp = api.create_user_export(USER_USER_ID, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // The export runs in the background
+        let mut status = serde_json::Value::Null;
+        for _ in 0..40 {
+            let resp = api
+                .get_user_export_status(USER_USER_ID, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+            let export: serde_json::Value = test::read_body_json(resp).await;
+            status = export["status"].clone();
+            if status != "queued" && status != "processing" {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+        assert_eq!(status, "complete");
+
+        let resp = api
+            .get_user_notifications(USER_USER_ID, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let notifications: Vec<Notification> = test::read_body_json(resp).await;
+        let url = notifications
+            .into_iter()
+            .find_map(|x| match x.body {
+                NotificationBody::DataExportReady { url, .. } => Some(url),
+                _ => None,
+            })
+            .unwrap();
+        let download_uri = url
+            .strip_prefix(&dotenvy::var("SELF_ADDR").unwrap())
+            .unwrap()
+            .to_string();
+
+        // The link only works with its token
+        let resp = api
+            .call(
+                test::TestRequest::get()
+                    .uri(&format!(
+                        "/v3/user/{USER_USER_ID}/export/download?token=wrong"
+                    ))
+                    .to_request(),
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        // The archive is served by the API rather than the CDN
+        let resp = download_export(api, &download_uri).await;
+        assert_status!(&resp, StatusCode::OK);
+        assert_eq!(resp.headers()["Content-Type"], "application/zip");
+        let archive = test::read_body(resp).await;
+
+        let file_name: String = sqlx::query_scalar("SELECT file_name FROM user_exports")
+            .fetch_one(&test_env.db.pool)
+            .await
+            .unwrap();
+        assert!(file_name.starts_with("private/"));
+        let path = std::path::Path::new(&dotenvy::var("MOCK_FILE_PATH").unwrap()).join(file_name);
+
+        let records = read_export_archive(&archive);
+        let record_types = records
+            .iter()
+            .map(|x| x["type"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(record_types[0], "profile");
+        assert_eq!(records[0]["data"]["id"], USER_USER_ID);
+        assert_eq!(record_types.iter().filter(|x| **x == "project").count(), 2);
+        assert!(record_types.contains(&"version"));
+        assert!(record_types.contains(&"personal_access_token"));
+
+        // Token secrets are never exported
+        for record in records
+            .iter()
+            .filter(|x| x["type"] == "personal_access_token")
+        {
+            assert!(record["data"]["access_token"].is_null());
+        }
+
+        // Once the link expires, the archive is removed
+        sqlx::query("UPDATE user_exports SET completed = NOW() - INTERVAL '25 hours'")
+            .execute(&test_env.db.pool)
+            .await
+            .unwrap();
+        purge_expired_exports(&test_env.db.pool, &MockHost::new())
+            .await
+            .unwrap();
+        assert!(!path.exists());
+
+        let resp = download_export(api, &download_uri).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        let resp = api
+            .get_user_export_status(USER_USER_ID, USER_USER_PAT)
+            .await;
+        let export: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(export["status"], "expired");
+
+        // Failed exports don't count towards the daily limit
+        sqlx::query("UPDATE user_exports SET status = 'failed'")
+            .execute(&test_env.db.pool)
+            .await
+            .unwrap();
+        let resp = api.create_user_export(USER_USER_ID, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::ACCEPTED);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn connections_are_verified_with_rel_me_links() {
+    // A local server stands in for the linked pages so no real network calls are made
+    let site_url = dotenvy::var("SITE_URL").unwrap();
+    let linked_page =
+        format!(r#"<html><head><link rel="me" href="{site_url}/user/user"></head></html>"#);
+    let server = actix_web::HttpServer::new(move || {
+        let linked_page = linked_page.clone();
+        actix_web::App::new()
+            .route(
+                "/linked",
+                actix_web::web::get().to(move || {
+                    let linked_page = linked_page.clone();
+                    async move { actix_web::HttpResponse::Ok().body(linked_page) }
+                }),
+            )
+            .route(
+                "/unlinked",
+                actix_web::web::get().to(|| async {
+                    actix_web::HttpResponse::Ok().body(r#"<a href="https://example.com">Hi</a>"#)
+                }),
+            )
+    })
+    .workers(1)
+    .bind(("127.0.0.1", 0))
+    .unwrap();
+    let server_url = format!("http://127.0.0.1:{}", server.addrs()[0].port());
+    let server = server.run();
+    let server_handle = server.handle();
+    actix_rt::spawn(server);
+
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let linked_url = format!("{server_url}/linked");
+        let new_connection = |url: &str| json!({ "provider": "website", "url": url });
+
+        let resp = api
+            .create_user_connection(USER_USER_ID, new_connection(&linked_url), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let connection: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(connection["verified"], false);
+        let connection_id = connection["id"].as_str().unwrap().to_string();
+
+        // Unverified connections are only shown to the user themselves
+        let user: serde_json::Value =
+            test::read_body_json(api.get_user(USER_USER_ID, None).await).await;
+        assert_eq!(user["connections"], json!([]));
+        let user: serde_json::Value =
+            test::read_body_json(api.get_current_user(USER_USER_PAT).await).await;
+        assert_eq!(user["connections"][0]["id"], connection_id.as_str());
+
+        // Only the user can verify their connections
+        let resp = api
+            .verify_user_connection(USER_USER_ID, &connection_id, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        let resp = api
+            .verify_user_connection(USER_USER_ID, &connection_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let connection: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(connection["verified"], true);
+        assert!(connection["verified_at"].is_string());
+
+        let user: serde_json::Value =
+            test::read_body_json(api.get_user(USER_USER_ID, None).await).await;
+        assert_eq!(user["connections"][0]["id"], connection_id.as_str());
+        assert_eq!(user["connections"][0]["url"], linked_url.as_str());
+
+        // The page is already linked to another user
+        let resp = api
+            .create_user_connection(FRIEND_USER_ID, new_connection(&linked_url), FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::CONFLICT);
+
+        // Pages without a link back to the profile can't be verified
+        let resp = api
+            .create_user_connection(
+                FRIEND_USER_ID,
+                new_connection(&format!("{server_url}/unlinked")),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let unlinked: serde_json::Value = test::read_body_json(resp).await;
+        let resp = api
+            .verify_user_connection(
+                FRIEND_USER_ID,
+                unlinked["id"].as_str().unwrap(),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // Once removed, the page can be linked to someone else
+        let resp = api
+            .delete_user_connection(USER_USER_ID, &connection_id, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let user: serde_json::Value =
+            test::read_body_json(api.get_user(USER_USER_ID, None).await).await;
+        assert_eq!(user["connections"], json!([]));
+
+        let resp = api
+            .create_user_connection(FRIEND_USER_ID, new_connection(&linked_url), FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+    })
+    .await;
+
+    server_handle.stop(true).await;
+}
+
+#[actix_rt::test]
+async fn concurrent_export_requests_only_queue_one_export() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+
+        let (first, second) = futures::join!(
+            api.create_user_export(USER_USER_ID, USER_USER_PAT),
+            api.create_user_export(USER_USER_ID, USER_USER_PAT),
+        );
+        let mut statuses = [first.status(), second.status()];
+        statuses.sort();
+        assert_eq!(statuses, [StatusCode::ACCEPTED, StatusCode::BAD_REQUEST]);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn exports_leave_out_followed_projects_which_are_hidden() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let beta_project_id = &test_env.dummy.project_beta.project_id;
+
+        let resp = api.follow_project(alpha_project_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        // The private beta project can't be followed by the friend through the API
+        sqlx::query("INSERT INTO mod_follows (follower_id, mod_id) VALUES ($1, $2)")
+            .bind(FRIEND_USER_ID_PARSED)
+            .bind(parse_base62(beta_project_id).unwrap() as i64)
+            .execute(&test_env.db.pool)
+            .await
+            .unwrap();
+
+        let resp = api
+            .create_user_export(FRIEND_USER_ID, FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::ACCEPTED);
+        for _ in 0..40 {
+            let resp = api
+                .get_user_export_status(FRIEND_USER_ID, FRIEND_USER_PAT)
+                .await;
+            let export: serde_json::Value = test::read_body_json(resp).await;
+            if export["status"] == "complete" {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+
+        let resp = api
+            .get_user_notifications(FRIEND_USER_ID, FRIEND_USER_PAT)
+            .await;
+        let notifications: Vec<Notification> = test::read_body_json(resp).await;
+        let url = notifications
+            .into_iter()
+            .find_map(|x| match x.body {
+                NotificationBody::DataExportReady { url, .. } => Some(url),
+                _ => None,
+            })
+            .unwrap();
+        let resp = download_export(
+            api,
+            url.strip_prefix(&dotenvy::var("SELF_ADDR").unwrap())
+                .unwrap(),
+        )
+        .await;
+        assert_status!(&resp, StatusCode::OK);
+
+        let records = read_export_archive(&test::read_body(resp).await);
+        let follows = records
+            .iter()
+            .filter(|x| x["type"] == "follow")
+            .map(|x| x["data"]["id"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(follows, [alpha_project_id.as_str()]);
+    })
+    .await;
+}
+
+async fn download_export(api: &ApiV3, uri: &str) -> ServiceResponse {
+    api.call(test::TestRequest::get().uri(uri).to_request())
+        .await
+}
+
+fn read_export_archive(bytes: &[u8]) -> Vec<serde_json::Value> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+    let mut lines = String::new();
+    archive
+        .by_name("data.jsonl")
+        .unwrap()
+        .read_to_string(&mut lines)
+        .unwrap();
+
+    lines
+        .lines()
+        .map(|x| serde_json::from_str(x).unwrap())
+        .collect()
+}
+
+#[actix_rt::test]
+async fn blocked_users_cannot_interact_with_the_blocker() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let alpha_team_id = &test_env.dummy.project_alpha.team_id;
+        let alpha_thread_id = &test_env.dummy.project_alpha.thread_id;
+
+        // The enemy joins the alpha team before they're blocked
+        let resp = api
+            .add_user_to_team(alpha_team_id, ENEMY_USER_ID, None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.join_team(alpha_team_id, ENEMY_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        for _ in 0..2 {
+            let resp = api.block_user(ENEMY_USER_ID, USER_USER_PAT).await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+        let resp = api.block_user(MOD_USER_ID, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.block_user(USER_USER_ID, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let resp = api.get_user_blocks(USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let blocks: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        let mut blocked = blocks
+            .iter()
+            .map(|x| x["user_id"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        blocked.sort();
+        assert_eq!(blocked, vec![MOD_USER_ID, ENEMY_USER_ID]);
+
+        // Blocks are invisible to the blocked user
+        let resp = api.get_user_blocks(ENEMY_USER_PAT).await;
+        let blocks: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        assert!(blocks.is_empty());
+
+        // The blocked user can't follow the blocker's projects, and isn't told why
+        let resp = api.follow_project(alpha_project_id, ENEMY_USER_PAT).await;
+        assert_status!(&resp, StatusCode::FORBIDDEN);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(!body["description"].as_str().unwrap().contains("block"));
+        let resp = api.follow_project(alpha_project_id, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Nor post in their projects' threads, while blocked moderators still can
+        let resp = api
+            .write_to_thread(alpha_thread_id, "text", "Go away", ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::FORBIDDEN);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(!body["description"].as_str().unwrap().contains("block"));
+        let resp = api
+            .write_to_thread(alpha_thread_id, "text", "Hello", MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // The blocker doesn't get notifications the blocked user sends, while others do
+        let (enemy_project, _) = api
+            .add_public_project("enemy-project", None, None, ENEMY_USER_PAT)
+            .await;
+        let enemy_team_id = api
+            .get_project_deserialized(&enemy_project.id.to_string(), ENEMY_USER_PAT)
+            .await
+            .team_id
+            .to_string();
+        for user_id in [USER_USER_ID, FRIEND_USER_ID] {
+            let resp = api
+                .add_user_to_team(&enemy_team_id, user_id, None, None, ENEMY_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+        assert!(
+            get_team_invites(api, USER_USER_ID, USER_USER_PAT, &enemy_team_id)
+                .await
+                .is_empty()
+        );
+        assert_eq!(
+            get_team_invites(api, FRIEND_USER_ID, FRIEND_USER_PAT, &enemy_team_id)
+                .await
+                .len(),
+            1
+        );
+
+        // Unblocking lets them interact again
+        let resp = api.unblock_user(ENEMY_USER_ID, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api.unblock_user(ENEMY_USER_ID, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+        let resp = api.follow_project(alpha_project_id, ENEMY_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let resp = api
+            .write_to_thread(alpha_thread_id, "text", "Sorry", ENEMY_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+    })
+    .await;
+}
+
+// Gets the user's notifications of invites to the team
+async fn get_team_invites(
+    api: &ApiV3,
+    user_id: &str,
+    pat: Option<&str>,
+    team_id: &str,
+) -> Vec<Notification> {
+    let resp = api.get_user_notifications(user_id, pat).await;
+    assert_status!(&resp, StatusCode::OK);
+    let notifications: Vec<Notification> = test::read_body_json(resp).await;
+    notifications
+        .into_iter()
+        .filter(|x| {
+            matches!(&x.body, NotificationBody::TeamInvite { team_id: invited_to, .. }
+                if invited_to.to_string() == team_id)
+        })
+        .collect()
+}
@@ -19,7 +19,11 @@ use futures::StreamExt;
 use itertools::Itertools;
 use labrinth::{
     database::models::project_item::PROJECTS_SLUGS_NAMESPACE,
-    models::{ids::base62_impl::parse_base62, projects::ProjectId, teams::ProjectPermissions},
+    models::{
+        ids::base62_impl::parse_base62, projects::ProjectId, teams::ProjectPermissions,
+        v2::projects::LegacySideType,
+    },
+    routes::v2_reroute,
     util::actix::{AppendsMultipart, MultipartSegment, MultipartSegmentData},
 };
 use serde_json::json;
@@ -129,6 +133,13 @@ async fn test_add_remove_project() {
             ..json_segment.clone()
         };
 
+        // Basic json, with a different file, and another different slug
+        json_data["slug"] = json!("reupload_demo");
+        let json_reupload_segment = MultipartSegment {
+            data: MultipartSegmentData::Text(serde_json::to_string(&json_data).unwrap()),
+            ..json_segment.clone()
+        };
+
         let basic_mod_file = TestFile::BasicMod;
         let basic_mod_different_file = TestFile::BasicModDifferent;
 
@@ -183,19 +194,19 @@ async fn test_add_remove_project() {
             .await;
         assert_eq!(version.id, uploaded_version_id);
 
-        // Reusing with a different slug and the same file should fail
+        // Reusing the same file in another project of the same user is allowed
         // Even if that file is named differently
         let req = test::TestRequest::post()
             .uri("/v2/project")
             .append_pat(USER_USER_PAT)
             .set_multipart(vec![
-                json_diff_slug_file_segment.clone(), // Different slug, different file name
-                file_diff_name_segment.clone(),      // Different file name, same content
+                json_reupload_segment.clone(),  // Different slug, different file name
+                file_diff_name_segment.clone(), // Different file name, same content
             ])
             .to_request();
 
         let resp = test_env.call(req).await;
-        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        assert_status!(&resp, StatusCode::OK);
 
         // Reusing with the same slug and a different file should fail
         let req = test::TestRequest::post()
@@ -382,10 +393,7 @@ pub async fn test_patch_v2() {
             .get_project_deserialized(alpha_project_slug, USER_USER_PAT)
             .await;
 
-        // Note: the original V2 value of this was "optional",
-        // but Required/Optional is no longer a carried combination in v3, as the changes made were lossy.
-        // Now, the test Required/Unsupported combination is tested instead.
-        // Setting Required/Optional in v2 will not work, this is known and accepteed.
+        // Every combination is tested in side_types_round_trip_through_v3
         assert_eq!(project.client_side.as_str(), "unsupported");
         assert_eq!(project.server_side.as_str(), "required");
     })
@@ -630,3 +638,201 @@ pub async fn test_bulk_edit_links() {
     })
     .await;
 }
+
+#[actix_rt::test]
+async fn side_types_round_trip_through_v3() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV2>| async move {
+        let api = &test_env.api;
+        let api_v3 = &test_env.setup_api;
+
+        let sides = ["required", "optional", "unsupported"];
+        for (i, (client_side, server_side)) in
+            sides.into_iter().cartesian_product(sides).enumerate()
+        {
+            let slug = format!("side-types-{i}");
+            let modify_json = serde_json::from_value(json!([
+                { "op": "add", "path": "/client_side", "value": client_side },
+                { "op": "add", "path": "/server_side", "value": server_side },
+            ]))
+            .unwrap();
+            let (_, versions) = api
+                .add_public_project(
+                    &slug,
+                    Some(TestFile::build_random_jar()),
+                    Some(modify_json),
+                    USER_USER_PAT,
+                )
+                .await;
+            let version_id = versions[0].id.to_string();
+
+            // Every combination is kept through v3
+            let project = api.get_project_deserialized(&slug, USER_USER_PAT).await;
+            assert_eq!(project.client_side.as_str(), client_side);
+            assert_eq!(project.server_side.as_str(), server_side);
+
+            let expected_fields = v2_reroute::convert_side_types_v3(
+                LegacySideType::from_string(client_side),
+                LegacySideType::from_string(server_side),
+            );
+            let version = api_v3
+                .get_version_deserialized(&version_id, USER_USER_PAT)
+                .await;
+            for field in v2_reroute::SIDE_TYPE_FIELDS {
+                assert_eq!(version.fields.get(field), expected_fields.get(field));
+            }
+        }
+
+        // A combination v2 can't represent isn't overwritten by v2 edits that don't change sides
+        let version_id = test_env.dummy.project_alpha.version_id.clone();
+        let alpha_project_slug = &test_env.dummy.project_alpha.project_slug;
+        let resp = api_v3
+            .edit_version(
+                &version_id,
+                json!({
+                    "singleplayer": true,
+                    "client_and_server": false,
+                    "client_only": false,
+                    "server_only": false,
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let project = api
+            .get_project_deserialized(alpha_project_slug, USER_USER_PAT)
+            .await;
+        let resp = api
+            .edit_project(
+                alpha_project_slug,
+                json!({
+                    "client_side": project.client_side,
+                    "server_side": project.server_side,
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let version = api_v3
+            .get_version_deserialized(&version_id, USER_USER_PAT)
+            .await;
+        assert_eq!(version.fields.get("singleplayer"), Some(&json!(true)));
+        assert_eq!(version.fields.get("client_and_server"), Some(&json!(false)));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn donation_urls_round_trip_through_v3() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV2>| async move {
+        let api = &test_env.api;
+        let api_v3 = &test_env.setup_api;
+        let alpha_project_slug = &test_env.dummy.project_alpha.project_slug;
+
+        let resp = api
+            .edit_project(
+                alpha_project_slug,
+                json!({
+                    "donation_urls": [
+                        {
+                            "id": "bmac",
+                            "platform": "Buy Me a Coffee",
+                            "url": "https://www.buymeacoffee.com/my_user"
+                        },
+                        {
+                            "id": "ko-fi",
+                            "platform": "Ko-fi",
+                            "url": "https://ko-fi.com/my_user"
+                        },
+                        {
+                            "id": "other",
+                            "platform": "Other",
+                            "url": "https://example.com/donate"
+                        }
+                    ],
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let project = api
+            .get_project_deserialized(alpha_project_slug, USER_USER_PAT)
+            .await;
+        let donation_urls = project
+            .donation_urls
+            .unwrap()
+            .into_iter()
+            .sorted_by_key(|x| x.id.clone())
+            .map(|x| (x.id, x.url))
+            .collect_vec();
+        assert_eq!(
+            donation_urls,
+            [
+                (
+                    "bmac".to_string(),
+                    "https://www.buymeacoffee.com/my_user".to_string()
+                ),
+                ("ko-fi".to_string(), "https://ko-fi.com/my_user".to_string()),
+                (
+                    "other".to_string(),
+                    "https://example.com/donate".to_string()
+                ),
+            ]
+        );
+
+        // v3 has structured links for them, with "Other" links being generic
+        let project = api_v3
+            .get_project_deserialized(alpha_project_slug, USER_USER_PAT)
+            .await;
+        assert_eq!(project.link_urls["buymeacoffee"].platform, "buymeacoffee");
+        assert_eq!(project.link_urls["ko-fi"].platform, "ko-fi");
+        assert_eq!(project.link_urls["other"].platform, "generic");
+
+        // Setting the donation links replaces all of them
+        let resp = api
+            .edit_project(
+                alpha_project_slug,
+                json!({
+                    "donation_urls": [
+                        {
+                            "id": "ko-fi",
+                            "platform": "Ko-fi",
+                            "url": "https://ko-fi.com/someone_else"
+                        }
+                    ],
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let project = api
+            .get_project_deserialized(alpha_project_slug, USER_USER_PAT)
+            .await;
+        let donation_urls = project.donation_urls.unwrap();
+        assert_eq!(donation_urls.len(), 1);
+        assert_eq!(donation_urls[0].id, "ko-fi");
+        assert_eq!(donation_urls[0].url, "https://ko-fi.com/someone_else");
+
+        // Links on the wrong site for their platform are rejected
+        let resp = api
+            .edit_project(
+                alpha_project_slug,
+                json!({
+                    "donation_urls": [
+                        {
+                            "id": "patreon",
+                            "platform": "Patreon",
+                            "url": "https://example.com/my_user"
+                        }
+                    ],
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
@@ -1,22 +1,32 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::common::api_common::ApiVersion;
+use crate::common::api_common::{ApiProject, ApiTeams, ApiVersion};
 use crate::common::database::*;
 use crate::common::dummy_data::{DummyProjectAlpha, DummyProjectBeta, TestFile};
 use crate::common::get_json_val_str;
 use actix_http::StatusCode;
 use actix_web::test;
+use chrono::{DateTime, Duration, Utc};
+use common::api_v3::request_data::{
+    get_public_project_creation_data, get_public_version_creation_data_json,
+};
 use common::api_v3::ApiV3;
 use common::asserts::assert_common_version_ids;
 use common::database::USER_USER_PAT;
-use common::environment::{with_test_environment, with_test_environment_all};
+use common::environment::{with_test_environment, with_test_environment_all, TestEnvironment};
 use futures::StreamExt;
 use labrinth::database::models::version_item::VERSIONS_NAMESPACE;
 use labrinth::models::ids::base62_impl::parse_base62;
 use labrinth::models::projects::{
-    Dependency, DependencyType, VersionId, VersionStatus, VersionType,
+    Dependency, DependencySuggestion, DependencyType, FileScanStatus, Version, VersionId,
+    VersionStatus, VersionType,
 };
+use labrinth::models::reports::Report;
+use labrinth::queue::webhooks::WebhookQueue;
+use labrinth::routes::v3::version_creation::publish_scheduled_versions;
 use labrinth::routes::v3::version_file::FileUpdateData;
+use labrinth::routes::v3::versions::ProjectVersionMatrix;
+use labrinth::validate::registry::{IssueSeverity, ValidationIssue};
 use serde_json::json;
 
 // importing common module.
@@ -465,7 +475,8 @@ pub async fn test_patch_version() {
                 project_id: Some(*beta_project_id_parsed),
                 version_id: None,
                 file_name: Some("dummy_file_name".to_string()),
-                dependency_type: DependencyType::Required
+                dependency_type: DependencyType::Required,
+                inferred: false,
             }]
         );
         assert_eq!(version.loaders, vec!["forge".to_string()]);
@@ -680,3 +691,1549 @@ async fn version_ordering_when_specified_orders_specified_before_unspecified() {
     })
     .await;
 }
+
+#[actix_rt::test]
+async fn chunked_upload_creates_version() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = test_env.dummy.project_alpha.project_id_parsed;
+
+        // Large enough to be split into two chunks
+        let jar = TestFile::build_random_jar_with_padding(6 * (1 << 20));
+        let bytes = jar.bytes();
+        let sha1 = sha1::Sha1::from(&bytes).hexdigest();
+
+        let resp = api
+            .start_upload(&jar.filename(), bytes.len(), &sha1, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let upload: serde_json::Value = test::read_body_json(resp).await;
+        let upload_id = upload["id"].as_str().unwrap();
+        let chunk_size = upload["chunk_size"].as_u64().unwrap() as usize;
+
+        let chunks = bytes.chunks(chunk_size).collect::<Vec<_>>();
+        assert!(chunks.len() > 1);
+        assert_eq!(upload["chunk_count"].as_u64().unwrap(), chunks.len() as u64);
+
+        let mut version_data = get_public_version_creation_data_json("1.2.3-chunked", None, &jar);
+        version_data["project_id"] = json!(alpha_project_id);
+
+        // Chunks whose hash doesn't match are rejected
+        let resp = api
+            .upload_chunk(upload_id, 0, chunks[0], &"0".repeat(40), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        for (index, chunk) in chunks.iter().enumerate().skip(1) {
+            let chunk_sha1 = sha1::Sha1::from(chunk).hexdigest();
+            let resp = api
+                .upload_chunk(upload_id, index, chunk, &chunk_sha1, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+
+        // The upload can't be completed while a chunk is missing
+        let resp = api
+            .complete_upload(upload_id, version_data.clone(), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // Sending a chunk again replaces it, as a client resuming an upload would
+        let chunk_sha1 = sha1::Sha1::from(chunks[0]).hexdigest();
+        for _ in 0..2 {
+            let resp = api
+                .upload_chunk(upload_id, 0, chunks[0], &chunk_sha1, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+
+        // Uploads can only be used by the user who started them
+        let resp = api
+            .complete_upload(upload_id, version_data.clone(), FRIEND_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let resp = api
+            .complete_upload(upload_id, version_data, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let version: Version = test::read_body_json(resp).await;
+        assert_eq!(version.files.len(), 1);
+        assert_eq!(version.files[0].hashes["sha1"], sha1);
+
+        let version = api
+            .get_version_deserialized(&version.id.to_string(), USER_USER_PAT)
+            .await;
+        assert_eq!(version.files[0].hashes["sha1"], sha1);
+        assert_eq!(version.files[0].size, bytes.len() as u32);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn pending_uploads_are_capped_per_user() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let sha1 = "0".repeat(40);
+
+        for _ in 0..5 {
+            let resp = api
+                .start_upload("mod.jar", 1 << 20, &sha1, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+        }
+        let resp = api
+            .start_upload("mod.jar", 1 << 20, &sha1, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // The combined size of a user's uploads is capped as well
+        for _ in 0..2 {
+            let resp = api
+                .start_upload("mod.jar", 500 << 20, &sha1, FRIEND_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+        }
+        let resp = api.start_upload("mod.jar", 1, &sha1, FRIEND_USER_PAT).await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn bulk_updates_by_loader_and_game_version() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let DummyProjectAlpha {
+            project_id_parsed: alpha_project_id_parsed,
+            version_id: alpha_version_id,
+            file_hash: alpha_version_hash,
+            ..
+        } = &test_env.dummy.project_alpha;
+        let DummyProjectBeta {
+            version_id: beta_version_id,
+            file_hash: beta_version_hash,
+            ..
+        } = &test_env.dummy.project_beta;
+
+        let newer_version = api
+            .add_public_version_deserialized(
+                *alpha_project_id_parsed,
+                "2.0.0",
+                TestFile::build_random_jar(),
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        let resp = api
+            .edit_version(
+                &newer_version.id.to_string(),
+                json!({ "game_versions": ["1.20.5"] }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // A modpack's worth of hashes, nearly all of which are unknown
+        let mut hashes = vec![alpha_version_hash.clone(), beta_version_hash.clone()];
+        hashes.extend((0..498).map(|i| format!("{i:040x}")));
+
+        let response = api
+            .update_files_for_loader_deserialized(&hashes, "fabric", &["1.20.1"], USER_USER_PAT)
+            .await;
+        assert_eq!(response.updates.len(), 2);
+        assert_eq!(
+            &response.updates[alpha_version_hash].id.to_string(),
+            alpha_version_id
+        );
+        assert_eq!(
+            &response.updates[beta_version_hash].id.to_string(),
+            beta_version_id
+        );
+        assert_eq!(response.unknown.len(), 498);
+
+        // Only alpha has a version for the newer game version
+        let response = api
+            .update_files_for_loader_deserialized(
+                &hashes,
+                "fabric",
+                &["1.20.1", "1.20.5"],
+                USER_USER_PAT,
+            )
+            .await;
+        assert_eq!(response.updates[alpha_version_hash].id, newer_version.id);
+        assert_eq!(
+            &response.updates[beta_version_hash].id.to_string(),
+            beta_version_id
+        );
+
+        // No versions support the loader, but the hashes are still known
+        let response = api
+            .update_files_for_loader_deserialized(&hashes, "forge", &["1.20.1"], USER_USER_PAT)
+            .await;
+        assert!(response.updates.is_empty());
+        assert_eq!(response.unknown.len(), 498);
+
+        // The private beta project's file is unknown to other users
+        let response = api
+            .update_files_for_loader_deserialized(&hashes, "fabric", &["1.20.1"], ENEMY_USER_PAT)
+            .await;
+        assert_eq!(response.updates.len(), 1);
+        assert_eq!(response.unknown.len(), 499);
+        assert!(response.unknown.contains(beta_version_hash));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn version_dependency_graph_detects_cycles() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let DummyProjectAlpha {
+            project_id_parsed: alpha_project_id_parsed,
+            version_id: alpha_version_id,
+            ..
+        } = &test_env.dummy.project_alpha;
+        let DummyProjectBeta {
+            project_id_parsed: beta_project_id_parsed,
+            version_id: beta_version_id,
+            ..
+        } = &test_env.dummy.project_beta;
+
+        let mut chain = Vec::new();
+        for version_number in ["2.0.0", "3.0.0"] {
+            let version = api
+                .add_public_version_deserialized(
+                    *alpha_project_id_parsed,
+                    version_number,
+                    TestFile::build_random_jar(),
+                    None,
+                    None,
+                    USER_USER_PAT,
+                )
+                .await;
+            chain.push(version.id.to_string());
+        }
+
+        // alpha -> 2.0.0 -> 3.0.0 -> alpha, with the private beta project as an optional dependency
+        // of 2.0.0 and the beta version marked incompatible with 3.0.0
+        let dependencies = [
+            (
+                alpha_version_id.clone(),
+                json!([{ "version_id": chain[0], "dependency_type": "required" }]),
+            ),
+            (
+                chain[0].clone(),
+                json!([
+                    { "version_id": chain[1], "dependency_type": "required" },
+                    { "project_id": beta_project_id_parsed, "dependency_type": "optional" },
+                ]),
+            ),
+            (
+                chain[1].clone(),
+                json!([
+                    { "version_id": alpha_version_id, "dependency_type": "required" },
+                    { "version_id": beta_version_id, "dependency_type": "incompatible" },
+                ]),
+            ),
+        ];
+        for (version_id, dependencies) in dependencies {
+            let resp = api
+                .edit_version(
+                    &version_id,
+                    json!({ "dependencies": dependencies }),
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+
+        let graph = api
+            .get_version_dependencies_deserialized(alpha_version_id, "", USER_USER_PAT)
+            .await;
+        assert_eq!(graph.versions.len(), 3);
+        assert_eq!(graph.edges.len(), 4);
+        assert_eq!(graph.projects.len(), 2);
+        assert_eq!(
+            graph.cycles,
+            vec![vec![
+                parse_version_id(alpha_version_id),
+                parse_version_id(&chain[0]),
+                parse_version_id(&chain[1]),
+            ]]
+        );
+        let optional = graph
+            .edges
+            .iter()
+            .find(|x| x.dependency.project_id == Some(*beta_project_id_parsed))
+            .unwrap();
+        assert_eq!(
+            optional.dependency.dependency_type,
+            DependencyType::Optional
+        );
+
+        // Incompatible dependencies are only included when asked for
+        let graph = api
+            .get_version_dependencies_deserialized(
+                alpha_version_id,
+                "include_incompatible=true",
+                USER_USER_PAT,
+            )
+            .await;
+        assert_eq!(graph.versions.len(), 4);
+        assert_eq!(graph.edges.len(), 5);
+        assert_eq!(graph.cycles.len(), 1);
+
+        // The depth limits how far the graph is walked
+        let graph = api
+            .get_version_dependencies_deserialized(alpha_version_id, "depth=1", USER_USER_PAT)
+            .await;
+        assert_eq!(graph.versions.len(), 2);
+        assert_eq!(graph.edges.len(), 1);
+        assert!(graph.cycles.is_empty());
+
+        let resp = api
+            .get_version_dependencies(alpha_version_id, "depth=11", USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        // The private beta project is hidden from other users
+        let graph = api
+            .get_version_dependencies_deserialized(
+                alpha_version_id,
+                "include_incompatible=true",
+                ENEMY_USER_PAT,
+            )
+            .await;
+        assert_eq!(graph.versions.len(), 3);
+        assert_eq!(graph.edges.len(), 3);
+        assert_eq!(graph.projects.len(), 1);
+        assert_eq!(graph.cycles.len(), 1);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn version_dependency_graph_resolves_project_dependencies() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_version_id = &test_env.dummy.project_alpha.version_id;
+
+        let (first, first_versions) = api
+            .add_public_project(
+                "first-dependency",
+                Some(TestFile::build_random_jar()),
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        let (second, _) = api
+            .add_public_project(
+                "second-dependency",
+                Some(TestFile::build_random_jar()),
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+
+        // The newest version of the second project doesn't support the root's game version,
+        // so the one before it is resolved
+        let compatible = api
+            .add_public_version_deserialized(
+                second.id,
+                "2.0.0",
+                TestFile::build_random_jar(),
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        api.add_public_version_deserialized(
+            second.id,
+            "3.0.0",
+            TestFile::build_random_jar(),
+            None,
+            Some(
+                serde_json::from_value(json!([{
+                    "op": "add",
+                    "path": "/game_versions",
+                    "value": ["1.20.5"]
+                }]))
+                .unwrap(),
+            ),
+            USER_USER_PAT,
+        )
+        .await;
+
+        // alpha -> first project -> second project
+        let first_version_id = first_versions[0].id.to_string();
+        let dependencies = [
+            (alpha_version_id.clone(), first.id),
+            (first_version_id.clone(), second.id),
+        ];
+        for (version_id, project_id) in dependencies {
+            let resp = api
+                .edit_version(
+                    &version_id,
+                    json!({
+                        "dependencies": [{ "project_id": project_id, "dependency_type": "required" }]
+                    }),
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+
+        let graph = api
+            .get_version_dependencies_deserialized(alpha_version_id, "", USER_USER_PAT)
+            .await;
+        let version_ids = graph
+            .versions
+            .iter()
+            .map(|x| x.id)
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            version_ids,
+            HashSet::from([
+                parse_version_id(alpha_version_id),
+                parse_version_id(&first_version_id),
+                compatible.id,
+            ])
+        );
+        assert_eq!(graph.projects.len(), 3);
+
+        let resolved = |project_id| {
+            graph
+                .edges
+                .iter()
+                .find(|x| x.dependency.project_id == Some(project_id))
+                .unwrap()
+                .resolved_version_id
+        };
+        assert_eq!(
+            resolved(first.id),
+            Some(parse_version_id(&first_version_id))
+        );
+        assert_eq!(resolved(second.id), Some(compatible.id));
+    })
+    .await;
+}
+
+fn parse_version_id(id: &str) -> VersionId {
+    VersionId(parse_base62(id).unwrap())
+}
+
+#[actix_rt::test]
+async fn scheduled_versions_are_published() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let DummyProjectAlpha {
+            project_id_parsed: alpha_project_id_parsed,
+            version_id: alpha_version_id,
+            ..
+        } = &test_env.dummy.project_alpha;
+
+        let schedule = |publish_at: DateTime<Utc>| -> Option<json_patch::Patch> {
+            Some(
+                serde_json::from_value(json!([
+                    { "op": "add", "path": "/status", "value": "draft" },
+                    { "op": "add", "path": "/publish_at", "value": publish_at },
+                ]))
+                .unwrap(),
+            )
+        };
+
+        // Versions can only be scheduled up to 30 days ahead, and only as drafts
+        let resp = api
+            .add_public_version(
+                *alpha_project_id_parsed,
+                "2.0.0",
+                TestFile::build_random_jar(),
+                None,
+                schedule(Utc::now() + Duration::days(31)),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let resp = api
+            .edit_version(
+                alpha_version_id,
+                json!({ "publish_at": Utc::now() + Duration::days(1) }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+
+        let version = api
+            .add_public_version_deserialized(
+                *alpha_project_id_parsed,
+                "2.0.0",
+                TestFile::build_random_jar(),
+                None,
+                schedule(Utc::now() + Duration::seconds(5)),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_eq!(version.status, VersionStatus::Scheduled);
+        assert!(version.publish_at.is_some());
+
+        // A version of a project which hasn't been approved yet
+        let creation_data =
+            get_public_project_creation_data("scheduled", Some(TestFile::build_random_jar()), None);
+        let resp = api.create_project(creation_data, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let unapproved_project = api
+            .get_project_deserialized("scheduled", USER_USER_PAT)
+            .await;
+        let delayed_version = api
+            .add_public_version_deserialized(
+                unapproved_project.id,
+                "2.0.0",
+                TestFile::build_random_jar(),
+                None,
+                schedule(Utc::now() + Duration::seconds(5)),
+                USER_USER_PAT,
+            )
+            .await;
+
+        // Nothing is due yet
+        publish_scheduled_versions(
+            Utc::now(),
+            &test_env.db.pool,
+            &test_env.db.redis_pool,
+            &test_env.db.search_config,
+            &WebhookQueue::new(),
+        )
+        .await
+        .unwrap();
+        let version = api
+            .get_version_deserialized(&version.id.to_string(), USER_USER_PAT)
+            .await;
+        assert_eq!(version.status, VersionStatus::Scheduled);
+
+        publish_scheduled_versions(
+            Utc::now() + Duration::minutes(1),
+            &test_env.db.pool,
+            &test_env.db.redis_pool,
+            &test_env.db.search_config,
+            &WebhookQueue::new(),
+        )
+        .await
+        .unwrap();
+
+        let version = api
+            .get_version_deserialized(&version.id.to_string(), USER_USER_PAT)
+            .await;
+        assert_eq!(version.status, VersionStatus::Listed);
+        assert!(version.publish_at.is_none());
+
+        let delayed_version = api
+            .get_version_deserialized(&delayed_version.id.to_string(), USER_USER_PAT)
+            .await;
+        assert_eq!(delayed_version.status, VersionStatus::Scheduled);
+
+        // The team is told why the version wasn't published
+        let notifications = api
+            .get_user_notifications_deserialized_common(USER_USER_ID, USER_USER_PAT)
+            .await;
+        assert!(notifications
+            .iter()
+            .any(|x| x.link.ends_with(&delayed_version.id.to_string())));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn changelog_covers_versions_between_two_versions() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let alpha_project_id_parsed = test_env.dummy.project_alpha.project_id_parsed;
+
+        let mut versions = Vec::new();
+        for version_number in ["2.0.0", "2.1.0", "2.2.0"] {
+            let version = api
+                .add_public_version_deserialized(
+                    alpha_project_id_parsed,
+                    version_number,
+                    TestFile::build_random_jar(),
+                    None,
+                    None,
+                    USER_USER_PAT,
+                )
+                .await;
+            let resp = api
+                .edit_version(
+                    &version.id.to_string(),
+                    json!({ "changelog": format!("Changes in {version_number}") }),
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+            versions.push(version.id.to_string());
+        }
+
+        // The range excludes `from_version` and includes `to_version`
+        let resp = api
+            .get_project_changelog(alpha_project_id, &versions[0], &versions[2], None)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let changelog: serde_json::Value = test::read_body_json(resp).await;
+        let numbers = changelog["versions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["version_number"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, vec!["2.1.0", "2.2.0"]);
+        assert_eq!(
+            changelog["combined_changelog"],
+            "## 2.1.0\n\nChanges in 2.1.0\n\n## 2.2.0\n\nChanges in 2.2.0"
+        );
+        assert!(!changelog["versions"][0]["files"][0]["hashes"]["sha1"]
+            .as_str()
+            .unwrap()
+            .is_empty());
+
+        // An unlisted version is only shown to the team
+        let resp = api
+            .edit_version(&versions[1], json!({ "status": "unlisted" }), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let resp = api
+            .get_project_changelog(alpha_project_id, &versions[0], &versions[2], None)
+            .await;
+        let changelog: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(changelog["versions"].as_array().unwrap().len(), 1);
+        assert_eq!(changelog["versions"][0]["version_number"], "2.2.0");
+
+        let resp = api
+            .get_project_changelog(alpha_project_id, &versions[0], &versions[2], USER_USER_PAT)
+            .await;
+        let changelog: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(changelog["versions"].as_array().unwrap().len(), 2);
+
+        // The versions must be in order
+        let resp = api
+            .get_project_changelog(alpha_project_id, &versions[2], &versions[0], None)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn project_versions_can_be_paged() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let alpha_project_id_parsed = test_env.dummy.project_alpha.project_id_parsed;
+
+        let mut created = Vec::new();
+        for i in 0..12 {
+            let version = api
+                .add_public_version_deserialized(
+                    alpha_project_id_parsed,
+                    &format!("2.{i}.0"),
+                    TestFile::build_random_jar(),
+                    None,
+                    None,
+                    USER_USER_PAT,
+                )
+                .await;
+            created.push(version.id.to_string());
+        }
+
+        // Listing every version still works, with a warning that it will be paged
+        let resp = api
+            .get_project_versions(
+                alpha_project_id,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        assert!(resp.headers().contains_key("warning"));
+        let all_versions: Vec<Version> = test::read_body_json(resp).await;
+        assert_eq!(all_versions.len(), 13);
+
+        let mut paged = Vec::new();
+        let mut cursor = None;
+        loop {
+            let resp = api
+                .get_project_versions_page(
+                    alpha_project_id,
+                    5,
+                    cursor.as_deref(),
+                    None,
+                    USER_USER_PAT,
+                )
+                .await;
+            assert_status!(&resp, StatusCode::OK);
+            assert_eq!(resp.headers()["x-total-count"], "13");
+            cursor = resp
+                .headers()
+                .get("x-next-cursor")
+                .map(|x| x.to_str().unwrap().to_string());
+            let page: Vec<Version> = test::read_body_json(resp).await;
+            assert!(page.len() <= 5);
+            paged.extend(page.into_iter().map(|x| x.id));
+
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        // The pages hold every version once, newest first
+        assert_eq!(paged, all_versions.iter().map(|x| x.id).collect::<Vec<_>>());
+        for id in &created {
+            assert!(paged.iter().any(|x| &x.to_string() == id));
+        }
+
+        // Filters apply before paging
+        for id in &created[..2] {
+            let resp = api
+                .edit_version(id, json!({ "featured": false }), USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+        let resp = api
+            .get_project_versions_page(alpha_project_id, 5, None, Some(false), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        assert_eq!(resp.headers()["x-total-count"], "2");
+        assert!(!resp.headers().contains_key("x-next-cursor"));
+        let page: Vec<Version> = test::read_body_json(resp).await;
+        assert_eq!(
+            page.iter().map(|x| x.id.to_string()).collect::<Vec<_>>(),
+            vec![created[1].clone(), created[0].clone()]
+        );
+
+        // The cursor must be one of the project's versions
+        let beta_version_id = &test_env.dummy.project_beta.version_id;
+        let resp = api
+            .get_project_versions_page(
+                alpha_project_id,
+                5,
+                Some(beta_version_id),
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn bulk_version_creation_is_all_or_nothing() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+
+        let fabric_jar = TestFile::build_random_jar();
+        let forge_jar = TestFile::build_random_jar();
+
+        let mut defaults = get_public_version_creation_data_json("3.0.0", None, &fabric_jar);
+        defaults["version_body"] = json!("Shared changelog");
+        let mut data = json!({
+            "defaults": defaults,
+            "versions": [
+                {
+                    "file_parts": [fabric_jar.filename()],
+                    "loaders": ["fabric"],
+                },
+                {
+                    "file_parts": [forge_jar.filename()],
+                    "loaders": ["forge"],
+                },
+            ],
+        });
+
+        // If any of the versions is invalid, none of them are created
+        data["versions"][1]["loaders"] = json!(["not-a-loader"]);
+        let resp = api
+            .add_versions_bulk(
+                alpha_project_id,
+                data.clone(),
+                &[fabric_jar.clone(), forge_jar.clone()],
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let error: serde_json::Value = test::read_body_json(resp).await;
+        assert!(error["description"]
+            .as_str()
+            .unwrap()
+            .starts_with("Version 1:"));
+
+        let versions = api
+            .get_project_versions_deserialized_common(
+                alpha_project_id,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_eq!(versions.len(), 1);
+
+        // Otherwise, all of them are created, in order
+        data["versions"][1]["loaders"] = json!(["forge"]);
+        let resp = api
+            .add_versions_bulk(
+                alpha_project_id,
+                data,
+                &[fabric_jar, forge_jar],
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let created: Vec<Version> = test::read_body_json(resp).await;
+        assert_eq!(created.len(), 2);
+        assert_eq!(created[0].loaders[0].0, "fabric");
+        assert_eq!(created[1].loaders[0].0, "forge");
+
+        for version in &created {
+            let version = api
+                .get_version_deserialized(&version.id.to_string(), USER_USER_PAT)
+                .await;
+            assert_eq!(version.version_number, "3.0.0");
+            assert_eq!(version.changelog, "Shared changelog");
+            assert_eq!(version.files.len(), 1);
+        }
+
+        let versions = api
+            .get_project_versions_deserialized_common(
+                alpha_project_id,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_eq!(versions.len(), 3);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn version_files_can_be_reordered() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_version_id = &test_env.dummy.project_alpha.version_id;
+
+        let sources_jar = TestFile::BasicModRandom {
+            filename: "basic-mod-sources.jar".to_string(),
+            bytes: TestFile::build_random_jar().bytes(),
+        };
+        for file in [&TestFile::BasicModDifferent, &sources_jar] {
+            let resp = api
+                .upload_file_to_version(alpha_version_id, file, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+        }
+
+        let version = api
+            .get_version_deserialized(alpha_version_id, USER_USER_PAT)
+            .await;
+        let hash = |filename: &str| {
+            version
+                .files
+                .iter()
+                .find(|x| x.filename == filename)
+                .unwrap()
+                .hashes["sha1"]
+                .clone()
+        };
+        let alpha_hash = hash("dummy-project-alpha.jar");
+        let different_hash = hash("basic-mod-different.jar");
+        let sources_hash = hash("basic-mod-sources.jar");
+
+        // Make the second jar primary, and list it last
+        let resp = api
+            .edit_version_files(
+                alpha_version_id,
+                json!([
+                    { "hash": sources_hash },
+                    { "hash": alpha_hash },
+                    { "hash": different_hash, "primary": true },
+                ]),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let version = api
+            .get_version_deserialized(alpha_version_id, USER_USER_PAT)
+            .await;
+        let filenames = version
+            .files
+            .iter()
+            .map(|x| x.filename.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            filenames,
+            [
+                "basic-mod-sources.jar",
+                "dummy-project-alpha.jar",
+                "basic-mod-different.jar"
+            ]
+        );
+        let primary = version
+            .files
+            .iter()
+            .filter(|x| x.primary)
+            .collect::<Vec<_>>();
+        assert_eq!(primary.len(), 1);
+        assert_eq!(primary[0].filename, "basic-mod-different.jar");
+
+        // Without a primary file, the current one is kept
+        let resp = api
+            .edit_version_files(
+                alpha_version_id,
+                json!([
+                    { "hash": different_hash },
+                    { "hash": alpha_hash },
+                    { "hash": sources_hash },
+                ]),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+        let version = api
+            .get_version_deserialized(alpha_version_id, USER_USER_PAT)
+            .await;
+        assert_eq!(version.files[0].filename, "basic-mod-different.jar");
+        assert!(version.files[0].primary);
+
+        // A sources jar can't be primary, there can only be one primary file, and every file
+        // of the version must be listed once
+        for files in [
+            json!([
+                { "hash": different_hash },
+                { "hash": alpha_hash },
+                { "hash": sources_hash, "primary": true },
+            ]),
+            json!([
+                { "hash": different_hash, "primary": true },
+                { "hash": alpha_hash, "primary": true },
+                { "hash": sources_hash },
+            ]),
+            json!([
+                { "hash": different_hash },
+                { "hash": alpha_hash },
+            ]),
+            json!([
+                { "hash": different_hash },
+                { "hash": alpha_hash },
+                { "hash": alpha_hash },
+            ]),
+            json!([
+                { "hash": different_hash },
+                { "hash": alpha_hash },
+                { "hash": sources_hash },
+                { "hash": test_env.dummy.project_beta.file_hash },
+            ]),
+        ] {
+            let resp = api
+                .edit_version_files(alpha_version_id, files, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::BAD_REQUEST);
+        }
+
+        // Only team members with permission to upload versions can change the files
+        let resp = api
+            .edit_version_files(
+                alpha_version_id,
+                json!([
+                    { "hash": alpha_hash, "primary": true },
+                    { "hash": different_hash },
+                    { "hash": sources_hash },
+                ]),
+                ENEMY_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+    })
+    .await;
+}
+
+// Waits for the version's files to be scanned, returning the version as its owner sees it
+async fn wait_for_scans(api: &ApiV3, version_id: &str) -> Version {
+    for _ in 0..50 {
+        let version = api
+            .get_version_deserialized(version_id, USER_USER_PAT)
+            .await;
+        if version
+            .files
+            .iter()
+            .all(|x| x.scan_status != Some(FileScanStatus::Pending))
+        {
+            return version;
+        }
+        actix_rt::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("The version's files were never scanned");
+}
+
+#[actix_rt::test]
+async fn version_files_are_scanned() {
+    with_test_environment(None, |env: TestEnvironment<ApiV3>| async move {
+        let api = &env.api;
+        let alpha_project_id_parsed = env.dummy.project_alpha.project_id_parsed;
+
+        let version = api
+            .add_public_version_deserialized(
+                alpha_project_id_parsed,
+                "2.0.0",
+                TestFile::build_random_jar(),
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        let version_id = version.id.to_string();
+
+        let version = wait_for_scans(api, &version_id).await;
+        assert_eq!(version.status, VersionStatus::Listed);
+        assert_eq!(version.files[0].scan_status, Some(FileScanStatus::Clean));
+        assert!(version.files[0].scanned_at.is_some());
+
+        // Scan results are only shown to the project's team and moderators
+        let version = api
+            .get_version_deserialized(&version_id, MOD_USER_PAT)
+            .await;
+        assert_eq!(version.files[0].scan_status, Some(FileScanStatus::Clean));
+        let version = api
+            .get_version_deserialized(&version_id, ENEMY_USER_PAT)
+            .await;
+        assert_eq!(version.files[0].scan_status, None);
+        assert_eq!(version.files[0].scanned_at, None);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn flagged_versions_are_withheld() {
+    with_test_environment(None, |env: TestEnvironment<ApiV3>| async move {
+        let api = &env.api;
+        let alpha_project_id_parsed = env.dummy.project_alpha.project_id_parsed;
+
+        let version = api
+            .add_public_version_deserialized(
+                alpha_project_id_parsed,
+                "2.0.0",
+                TestFile::build_infected_jar(),
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        let version_id = version.id.to_string();
+
+        let version = wait_for_scans(api, &version_id).await;
+        assert_eq!(version.status, VersionStatus::Withheld);
+        assert_eq!(version.files[0].scan_status, Some(FileScanStatus::Flagged));
+
+        // Withheld versions are hidden from the public
+        let resp = api.get_version(&version_id, ENEMY_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NOT_FOUND);
+
+        // Moderators are told to look at the version
+        let resp = api.get_user_notifications(MOD_USER_ID, MOD_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let notifications: serde_json::Value = test::read_body_json(resp).await;
+        assert!(notifications.as_array().unwrap().iter().any(|x| {
+            x["body"]["type"] == "version_files_flagged"
+                && x["body"]["version_id"] == version_id.as_str()
+        }));
+
+        // Only moderators can release a withheld version
+        let resp = api
+            .edit_version(&version_id, json!({ "status": "listed" }), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+
+        // Only moderators can rescan versions
+        let resp = api.rescan_version(&version_id, USER_USER_PAT).await;
+        assert_status!(&resp, StatusCode::UNAUTHORIZED);
+        let resp = api.rescan_version(&version_id, MOD_USER_PAT).await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        // Rescanning doesn't release the version, even if nothing is found
+        let version = wait_for_scans(api, &version_id).await;
+        assert_eq!(version.status, VersionStatus::Withheld);
+        assert_eq!(version.files[0].scan_status, Some(FileScanStatus::Flagged));
+
+        let resp = api
+            .edit_version(&version_id, json!({ "status": "listed" }), MOD_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn dependencies_are_inferred_from_mod_metadata() {
+    with_test_environment(None, |env: TestEnvironment<ApiV3>| async move {
+        let api = &env.api;
+        let alpha_project_id_parsed = env.dummy.project_alpha.project_id_parsed;
+        let beta_project_id_parsed = env.dummy.project_beta.project_id_parsed;
+
+        // Uploading a mod records the mod id it declares against its project
+        api.add_public_version_deserialized(
+            alpha_project_id_parsed,
+            "2.0.0",
+            TestFile::build_random_jar_with_metadata("inferred-alpha", json!({})),
+            None,
+            None,
+            USER_USER_PAT,
+        )
+        .await;
+
+        let version = api
+            .add_public_version_deserialized(
+                beta_project_id_parsed,
+                "2.0.0",
+                TestFile::build_random_jar_with_metadata(
+                    "inferred-beta",
+                    json!({
+                        "inferred-alpha": ">=2.0.0",
+                        "fabricloader": ">=0.14.0",
+                        "unknown-mod": ["1.x", "2.x"]
+                    }),
+                ),
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+
+        // Known mod ids become dependencies, and the rest are suggested to the uploader
+        let inferred = vec![Dependency {
+            project_id: Some(alpha_project_id_parsed),
+            version_id: None,
+            file_name: None,
+            dependency_type: DependencyType::Required,
+            inferred: true,
+        }];
+        assert_eq!(version.dependencies, inferred);
+        assert_eq!(
+            version.dependency_suggestions,
+            vec![DependencySuggestion {
+                mod_id: "unknown-mod".to_string(),
+                version_range: Some("1.x || 2.x".to_string()),
+                dependency_type: DependencyType::Required,
+            }]
+        );
+
+        let version = api
+            .get_version_deserialized(&version.id.to_string(), USER_USER_PAT)
+            .await;
+        assert_eq!(version.dependencies, inferred);
+        assert!(version.dependency_suggestions.is_empty());
+
+        // Inference can be turned off
+        let version = api
+            .add_public_version_deserialized(
+                beta_project_id_parsed,
+                "2.0.1",
+                TestFile::build_random_jar_with_metadata(
+                    "inferred-beta",
+                    json!({ "inferred-alpha": "*", "unknown-mod": "*" }),
+                ),
+                None,
+                Some(
+                    serde_json::from_value(json!([{
+                        "op": "add",
+                        "path": "/infer_dependencies",
+                        "value": false
+                    }]))
+                    .unwrap(),
+                ),
+                USER_USER_PAT,
+            )
+            .await;
+        assert!(version.dependencies.is_empty());
+        assert!(version.dependency_suggestions.is_empty());
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn malformed_mod_metadata_is_warned_about() {
+    with_test_environment(None, |env: TestEnvironment<ApiV3>| async move {
+        let api = &env.api;
+        let alpha_project_id_parsed = env.dummy.project_alpha.project_id_parsed;
+
+        // The version is still created, and the problems are returned with it
+        let jar = TestFile::build_malformed_jar();
+        let file_name = jar.filename();
+        let resp = api
+            .add_public_version(
+                alpha_project_id_parsed,
+                "2.0.0",
+                jar,
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let version: Version = test::read_body_json(resp).await;
+        assert_eq!(
+            version.validation_warnings,
+            vec![
+                ValidationIssue {
+                    severity: IssueSeverity::Warning,
+                    code: "invalid_mod_id".to_string(),
+                    message: "The mod id Malformed Mod in fabric.mod.json must be 2 to 64 lowercase letters, numbers, dashes or underscores, starting with a letter".to_string(),
+                    file_name: file_name.clone(),
+                },
+                ValidationIssue {
+                    severity: IssueSeverity::Warning,
+                    code: "missing_mod_version".to_string(),
+                    message: "fabric.mod.json has no version".to_string(),
+                    file_name,
+                },
+            ]
+        );
+
+        // Well-formed mods have nothing to warn about
+        let resp = api
+            .add_public_version(
+                alpha_project_id_parsed,
+                "2.0.1",
+                TestFile::build_random_jar_with_metadata("well-formed", json!({})),
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let version: Version = test::read_body_json(resp).await;
+        assert!(version.validation_warnings.is_empty());
+
+        // Files which aren't archives at all are rejected
+        let resp = api
+            .add_public_version(
+                alpha_project_id_parsed,
+                "2.0.2",
+                TestFile::BasicModRandom {
+                    filename: "not-a-jar.jar".to_string(),
+                    bytes: b"not a jar".to_vec(),
+                },
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn reuploaded_files_are_flagged() {
+    with_test_environment(None, |env: TestEnvironment<ApiV3>| async move {
+        let api = &env.api;
+        let alpha_project_id = &env.dummy.project_alpha.project_id;
+        let beta_project_id_parsed = env.dummy.project_beta.project_id_parsed;
+
+        // Uploading the alpha project's file to a project of someone outside its team isn't
+        // blocked, but the version is flagged
+        let (project, versions) = api
+            .add_public_project(
+                "reupload",
+                Some(TestFile::DummyProjectAlpha),
+                None,
+                FRIEND_USER_PAT,
+            )
+            .await;
+        let version_id = versions[0].id.to_string();
+        let version = api
+            .get_version_deserialized(&version_id, FRIEND_USER_PAT)
+            .await;
+        assert_eq!(version.flagged_reupload, Some(true));
+
+        // A report about both projects is opened for moderators
+        let resp = api.get_user_reports(MOD_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let reports: Vec<Report> = test::read_body_json(resp).await;
+        let report = reports
+            .iter()
+            .find(|x| x.report_type == "reupload")
+            .unwrap();
+        assert_eq!(report.item_id, project.id.to_string());
+        assert_eq!(
+            report.related_project_id.map(|x| x.to_string()).as_ref(),
+            Some(alpha_project_id)
+        );
+
+        let resp = api.get_user_notifications(MOD_USER_ID, MOD_USER_PAT).await;
+        assert_status!(&resp, StatusCode::OK);
+        let notifications: serde_json::Value = test::read_body_json(resp).await;
+        assert!(notifications.as_array().unwrap().iter().any(|x| {
+            x["body"]["type"] == "version_reupload_flagged"
+                && x["body"]["version_id"] == version_id.as_str()
+        }));
+
+        // Reuploads by members of the original project's team aren't flagged
+        let version = api
+            .add_public_version_deserialized(
+                beta_project_id_parsed,
+                "2.0.0",
+                TestFile::DummyProjectAlpha,
+                None,
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+        assert_eq!(version.flagged_reupload, Some(false));
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn version_matrix_has_newest_version_per_game_version_and_loader() {
+    with_test_environment(None, |test_env: TestEnvironment<ApiV3>| async move {
+        let api = &test_env.api;
+        let alpha_project_id = &test_env.dummy.project_alpha.project_id;
+        let alpha_project_id_parsed = test_env.dummy.project_alpha.project_id_parsed;
+        let alpha_version_id = &test_env.dummy.project_alpha.version_id;
+
+        let resp = api
+            .edit_version(
+                alpha_version_id,
+                json!({
+                    "game_versions": ["1.20.1"],
+                    "loaders": ["fabric"],
+                    "version_type": "release",
+                }),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::NO_CONTENT);
+
+        let mut version_ids = vec![];
+        for (version_number, patch_value) in [
+            (
+                "1.1.0",
+                json!({
+                    "game_versions": ["1.20.1", "1.20.2"],
+                    "loaders": ["forge"],
+                    "version_type": "release",
+                }),
+            ),
+            (
+                "1.2.0",
+                json!({
+                    "game_versions": ["1.20.2", "1.20.3"],
+                    "loaders": ["fabric"],
+                    "version_type": "beta",
+                }),
+            ),
+            (
+                "1.3.0",
+                json!({
+                    "game_versions": ["1.20.3"],
+                    "loaders": ["forge"],
+                    "version_type": "release",
+                    "status": "unlisted",
+                }),
+            ),
+        ] {
+            let version = api
+                .add_public_version_deserialized(
+                    alpha_project_id_parsed,
+                    version_number,
+                    TestFile::build_random_jar(),
+                    None,
+                    None,
+                    USER_USER_PAT,
+                )
+                .await;
+            let resp = api
+                .edit_version(&version.id.to_string(), patch_value, USER_USER_PAT)
+                .await;
+            assert_status!(&resp, StatusCode::NO_CONTENT);
+            version_ids.push(version.id.to_string());
+        }
+
+        let cell = |version_id: &str, version_type: VersionType| {
+            Some((version_id.to_string(), version_type))
+        };
+        let cells = |matrix: ProjectVersionMatrix| {
+            matrix
+                .cells
+                .into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .map(|x| x.map(|x| (x.version_id.to_string(), x.version_type)))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // The unlisted version only shows to the team
+        let matrix = api
+            .get_project_version_matrix_deserialized(alpha_project_id, None, FRIEND_USER_PAT)
+            .await;
+        assert_eq!(matrix.game_versions, vec!["1.20.3", "1.20.2", "1.20.1"]);
+        assert_eq!(matrix.loaders, vec!["fabric", "forge"]);
+        assert_eq!(
+            cells(matrix),
+            vec![
+                vec![cell(&version_ids[1], VersionType::Beta), None],
+                vec![
+                    cell(&version_ids[1], VersionType::Beta),
+                    cell(&version_ids[0], VersionType::Release),
+                ],
+                vec![
+                    cell(alpha_version_id, VersionType::Release),
+                    cell(&version_ids[0], VersionType::Release),
+                ],
+            ]
+        );
+
+        let matrix = api
+            .get_project_version_matrix_deserialized(alpha_project_id, None, USER_USER_PAT)
+            .await;
+        assert_eq!(
+            cells(matrix)[0],
+            vec![
+                cell(&version_ids[1], VersionType::Beta),
+                cell(&version_ids[2], VersionType::Release),
+            ]
+        );
+
+        // Beta versions don't count when only releases are asked for
+        let matrix = api
+            .get_project_version_matrix_deserialized(
+                alpha_project_id,
+                Some("release"),
+                FRIEND_USER_PAT,
+            )
+            .await;
+        assert_eq!(matrix.game_versions, vec!["1.20.2", "1.20.1"]);
+        assert_eq!(matrix.loaders, vec!["fabric", "forge"]);
+        assert_eq!(
+            cells(matrix),
+            vec![
+                vec![None, cell(&version_ids[0], VersionType::Release)],
+                vec![
+                    cell(alpha_version_id, VersionType::Release),
+                    cell(&version_ids[0], VersionType::Release),
+                ],
+            ]
+        );
+
+        let resp = api
+            .get_project_version_matrix(alpha_project_id, Some("nightly"), USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+    })
+    .await;
+}
+
+#[actix_rt::test]
+async fn modpacks_with_incompatible_files_are_rejected() {
+    with_test_environment(None, |env: TestEnvironment<ApiV3>| async move {
+        let api = &env.api;
+        let alpha_project_id = &env.dummy.project_alpha.project_id;
+        let alpha_version_id = &env.dummy.project_alpha.version_id;
+
+        let (modpack, _) = api
+            .add_public_project(
+                "incompatible-pack",
+                Some(TestFile::build_random_mrpack()),
+                None,
+                USER_USER_PAT,
+            )
+            .await;
+
+        // The alpha version only supports 1.20.1
+        let alpha_version = api
+            .get_version_deserialized(alpha_version_id, USER_USER_PAT)
+            .await;
+        let alpha_file = &alpha_version.files[0];
+        let pack = TestFile::build_random_mrpack_with_files(
+            json!([{
+                "path": "mods/alpha.jar",
+                "hashes": {
+                    "sha1": alpha_file.hashes["sha1"],
+                    "sha512": alpha_file.hashes["sha512"],
+                },
+                "downloads": [format!(
+                    "https://cdn.modrinth.com/data/{alpha_project_id}/versions/{alpha_version_id}/alpha.jar"
+                )],
+                "fileSize": alpha_file.size,
+            }]),
+            json!({
+                "fabric-loader": "0.14.22",
+                "minecraft": "1.20.2"
+            }),
+        );
+
+        let resp = api
+            .add_public_version(modpack.id, "2.0.0", pack.clone(), None, None, USER_USER_PAT)
+            .await;
+        assert_status!(&resp, StatusCode::BAD_REQUEST);
+        let error: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(error["error"], "incompatible_pack_files");
+        assert_eq!(
+            error["incompatible_files"],
+            json!([{
+                "path": "mods/alpha.jar",
+                "version_id": alpha_version_id,
+                "expected_loaders": ["fabric"],
+                "actual_loaders": ["fabric"],
+                "expected_game_versions": ["1.20.2"],
+                "actual_game_versions": ["1.20.1"],
+            }])
+        );
+
+        // With the override, the mismatch is kept as a warning on the version
+        let pack_file_name = pack.filename();
+        let resp = api
+            .add_public_version(
+                modpack.id,
+                "2.0.0",
+                pack,
+                None,
+                Some(
+                    serde_json::from_value(json!([{
+                        "op": "add",
+                        "path": "/allow_incompatible",
+                        "value": true
+                    }]))
+                    .unwrap(),
+                ),
+                USER_USER_PAT,
+            )
+            .await;
+        assert_status!(&resp, StatusCode::OK);
+        let version: Version = test::read_body_json(resp).await;
+        assert_eq!(version.validation_warnings.len(), 1);
+        let warning = &version.validation_warnings[0];
+        assert_eq!(warning.severity, IssueSeverity::Warning);
+        assert_eq!(warning.code, "incompatible_pack_file");
+        assert_eq!(warning.file_name, pack_file_name);
+        assert!(warning.message.contains(alpha_version_id.as_str()));
+    })
+    .await;
+}